@@ -0,0 +1,161 @@
+//! cBPF 程序校验
+
+use uapi::filter::*;
+
+use crate::BpfError;
+
+/// 操作码是否合法（只接受 Linux 允许的完整操作码，不允许多余的位）
+fn code_allowed(code: u16) -> bool {
+    match bpf_class(code) {
+        BPF_LD => {
+            code == BPF_LD | BPF_W | BPF_ABS
+                || code == BPF_LD | BPF_H | BPF_ABS
+                || code == BPF_LD | BPF_B | BPF_ABS
+                || code == BPF_LD | BPF_W | BPF_IND
+                || code == BPF_LD | BPF_H | BPF_IND
+                || code == BPF_LD | BPF_B | BPF_IND
+                || code == BPF_LD | BPF_W | BPF_IMM
+                || code == BPF_LD | BPF_W | BPF_MEM
+                || code == BPF_LD | BPF_W | BPF_LEN
+        }
+        BPF_LDX => {
+            code == BPF_LDX | BPF_W | BPF_IMM
+                || code == BPF_LDX | BPF_W | BPF_MEM
+                || code == BPF_LDX | BPF_W | BPF_LEN
+                || code == BPF_LDX | BPF_B | BPF_MSH
+        }
+        BPF_ST => code == BPF_ST,
+        BPF_STX => code == BPF_STX,
+        BPF_ALU => match bpf_op(code) {
+            BPF_NEG => code == BPF_ALU | BPF_NEG,
+            BPF_ADD | BPF_SUB | BPF_MUL | BPF_DIV | BPF_OR | BPF_AND | BPF_LSH | BPF_RSH
+            | BPF_MOD | BPF_XOR => code & !(0xf0 | BPF_X) == BPF_ALU,
+            _ => false,
+        },
+        BPF_JMP => match bpf_op(code) {
+            BPF_JA => code == BPF_JMP | BPF_JA,
+            BPF_JEQ | BPF_JGT | BPF_JGE | BPF_JSET => code & !(0xf0 | BPF_X) == BPF_JMP,
+            _ => false,
+        },
+        BPF_RET => code == BPF_RET | BPF_K || code == BPF_RET | BPF_A,
+        BPF_MISC => code == BPF_MISC | BPF_TAX || code == BPF_MISC | BPF_TXA,
+        _ => false,
+    }
+}
+
+/// 校验一个 cBPF 程序
+///
+/// 除逐条检查操作码外，还保证：
+/// - 所有跳转目标都落在程序内（cBPF 只能向前跳转，因此程序必然终止）；
+/// - 不除以常量 0，常量移位量小于 32；
+/// - 暂存存储器的下标小于 BPF_MEMWORDS；
+/// - 最后一条指令是 BPF_RET。
+///
+/// 暂存存储器在执行前清零，因此不检查先写后读。
+pub fn check(prog: &[SockFilter]) -> Result<(), BpfError> {
+    if prog.is_empty() || prog.len() > BPF_MAXINSNS {
+        return Err(BpfError::InvalidLength);
+    }
+
+    for (pc, insn) in prog.iter().enumerate() {
+        let invalid = Err(BpfError::InvalidInstruction { pc });
+        let code = insn.code;
+        if !code_allowed(code) {
+            return invalid;
+        }
+        // 下一条指令之后还剩的指令数
+        let remaining = prog.len() - pc - 1;
+        let ok = match bpf_class(code) {
+            BPF_LD | BPF_LDX if bpf_mode(code) == BPF_MEM => (insn.k as usize) < BPF_MEMWORDS,
+            BPF_ST | BPF_STX => (insn.k as usize) < BPF_MEMWORDS,
+            BPF_ALU if bpf_src(code) == BPF_K => match bpf_op(code) {
+                BPF_DIV | BPF_MOD => insn.k != 0,
+                BPF_LSH | BPF_RSH => insn.k < 32,
+                _ => true,
+            },
+            BPF_JMP if bpf_op(code) == BPF_JA => (insn.k as usize) < remaining,
+            BPF_JMP => (insn.jt as usize) < remaining && (insn.jf as usize) < remaining,
+            _ => true,
+        };
+        if !ok {
+            return invalid;
+        }
+    }
+
+    if bpf_class(prog[prog.len() - 1].code) != BPF_RET {
+        return Err(BpfError::MissingReturn);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_accepts_minimal_program() {
+        assert_eq!(check(&[SockFilter::stmt(BPF_RET | BPF_K, 0)]), Ok(()));
+    }
+
+    #[test]
+    fn test_check_rejects_bad_length() {
+        assert_eq!(check(&[]), Err(BpfError::InvalidLength));
+        let prog = alloc::vec![SockFilter::stmt(BPF_RET | BPF_K, 0); BPF_MAXINSNS + 1];
+        assert_eq!(check(&prog), Err(BpfError::InvalidLength));
+    }
+
+    #[test]
+    fn test_check_requires_trailing_ret() {
+        let prog = [
+            SockFilter::stmt(BPF_RET | BPF_K, 0),
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_LEN, 0),
+        ];
+        assert_eq!(check(&prog), Err(BpfError::MissingReturn));
+    }
+
+    #[test]
+    fn test_check_rejects_unknown_opcode() {
+        let prog = [
+            SockFilter::stmt(BPF_LD | BPF_B | BPF_MSH, 0),
+            SockFilter::stmt(BPF_RET | BPF_K, 0),
+        ];
+        assert_eq!(check(&prog), Err(BpfError::InvalidInstruction { pc: 0 }));
+        let prog = [SockFilter::stmt(BPF_RET | BPF_X, 0)];
+        assert_eq!(check(&prog), Err(BpfError::InvalidInstruction { pc: 0 }));
+    }
+
+    #[test]
+    fn test_check_rejects_out_of_range_jump() {
+        let prog = [
+            SockFilter::jump(BPF_JMP | BPF_JEQ | BPF_K, 1, 0, 1),
+            SockFilter::stmt(BPF_RET | BPF_K, 0),
+        ];
+        assert_eq!(check(&prog), Err(BpfError::InvalidInstruction { pc: 0 }));
+        let prog = [
+            SockFilter::stmt(BPF_JMP | BPF_JA, 1),
+            SockFilter::stmt(BPF_RET | BPF_K, 0),
+        ];
+        assert_eq!(check(&prog), Err(BpfError::InvalidInstruction { pc: 0 }));
+    }
+
+    #[test]
+    fn test_check_rejects_constant_div_zero_and_wide_shift() {
+        for insn in [
+            SockFilter::stmt(BPF_ALU | BPF_DIV | BPF_K, 0),
+            SockFilter::stmt(BPF_ALU | BPF_MOD | BPF_K, 0),
+            SockFilter::stmt(BPF_ALU | BPF_LSH | BPF_K, 32),
+        ] {
+            let prog = [insn, SockFilter::stmt(BPF_RET | BPF_A, 0)];
+            assert_eq!(check(&prog), Err(BpfError::InvalidInstruction { pc: 0 }));
+        }
+    }
+
+    #[test]
+    fn test_check_rejects_scratch_out_of_range() {
+        let prog = [
+            SockFilter::stmt(BPF_ST, BPF_MEMWORDS as u32),
+            SockFilter::stmt(BPF_RET | BPF_K, 0),
+        ];
+        assert_eq!(check(&prog), Err(BpfError::InvalidInstruction { pc: 0 }));
+    }
+}
@@ -0,0 +1,226 @@
+//! cBPF 解释器
+
+use uapi::filter::*;
+
+use crate::BpfInput;
+
+/// 执行一个已通过 [`check`](crate::check) 的 cBPF 程序，返回 BPF_RET 给出的结果
+///
+/// 与 Linux 一致，运行时的越界载入与除以 0 使程序立即返回 0。
+/// 对未经校验的程序，非法指令与越界跳转同样返回 0。
+pub fn run(prog: &[SockFilter], input: &dyn BpfInput) -> u32 {
+    let mut a: u32 = 0;
+    let mut x: u32 = 0;
+    let mut mem = [0u32; BPF_MEMWORDS];
+    let mut pc = 0usize;
+
+    while let Some(insn) = prog.get(pc) {
+        pc += 1;
+        let k = insn.k;
+        let code = insn.code;
+        match bpf_class(code) {
+            BPF_LD => {
+                a = match bpf_mode(code) {
+                    BPF_IMM => k,
+                    BPF_LEN => input.size(),
+                    BPF_MEM => mem[k as usize % BPF_MEMWORDS],
+                    mode => {
+                        let size = match bpf_size(code) {
+                            BPF_W => 4,
+                            BPF_H => 2,
+                            _ => 1,
+                        };
+                        let offset = if mode == BPF_IND {
+                            match x.checked_add(k) {
+                                Some(off) => off,
+                                None => return 0,
+                            }
+                        } else {
+                            k
+                        };
+                        match input.load(offset, size) {
+                            Some(v) => v,
+                            None => return 0,
+                        }
+                    }
+                };
+            }
+            BPF_LDX => {
+                x = match bpf_mode(code) {
+                    BPF_IMM => k,
+                    BPF_LEN => input.size(),
+                    BPF_MEM => mem[k as usize % BPF_MEMWORDS],
+                    // BPF_MSH：取 IP 首部长度
+                    _ => match input.load(k, 1) {
+                        Some(v) => (v & 0xf) << 2,
+                        None => return 0,
+                    },
+                };
+            }
+            BPF_ST => mem[k as usize % BPF_MEMWORDS] = a,
+            BPF_STX => mem[k as usize % BPF_MEMWORDS] = x,
+            BPF_ALU => {
+                let src = if bpf_src(code) == BPF_X { x } else { k };
+                a = match bpf_op(code) {
+                    BPF_ADD => a.wrapping_add(src),
+                    BPF_SUB => a.wrapping_sub(src),
+                    BPF_MUL => a.wrapping_mul(src),
+                    BPF_DIV => match a.checked_div(src) {
+                        Some(v) => v,
+                        None => return 0,
+                    },
+                    BPF_MOD => match a.checked_rem(src) {
+                        Some(v) => v,
+                        None => return 0,
+                    },
+                    BPF_OR => a | src,
+                    BPF_AND => a & src,
+                    BPF_XOR => a ^ src,
+                    BPF_LSH => a.checked_shl(src).unwrap_or(0),
+                    BPF_RSH => a.checked_shr(src).unwrap_or(0),
+                    BPF_NEG => a.wrapping_neg(),
+                    _ => return 0,
+                };
+            }
+            BPF_JMP => {
+                if bpf_op(code) == BPF_JA {
+                    pc += k as usize;
+                    continue;
+                }
+                let src = if bpf_src(code) == BPF_X { x } else { k };
+                let cond = match bpf_op(code) {
+                    BPF_JEQ => a == src,
+                    BPF_JGT => a > src,
+                    BPF_JGE => a >= src,
+                    BPF_JSET => a & src != 0,
+                    _ => return 0,
+                };
+                pc += if cond { insn.jt } else { insn.jf } as usize;
+            }
+            BPF_RET => {
+                return if bpf_rval(code) == BPF_A { a } else { k };
+            }
+            BPF_MISC => {
+                if bpf_miscop(code) == BPF_TXA {
+                    a = x;
+                } else {
+                    x = a;
+                }
+            }
+            _ => return 0,
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Packet, check};
+
+    fn run_checked(prog: &[SockFilter], data: &[u8]) -> u32 {
+        assert_eq!(check(prog), Ok(()));
+        run(prog, &Packet(data))
+    }
+
+    #[test]
+    fn test_run_returns_constant_and_accumulator() {
+        assert_eq!(run_checked(&[SockFilter::stmt(BPF_RET | BPF_K, 7)], &[]), 7);
+        let prog = [
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_LEN, 0),
+            SockFilter::stmt(BPF_RET | BPF_A, 0),
+        ];
+        assert_eq!(run_checked(&prog, &[0; 42]), 42);
+    }
+
+    #[test]
+    fn test_run_loads_network_byte_order() {
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9a];
+        for (size, expected) in [(BPF_W, 0x3456_789a), (BPF_H, 0x3456), (BPF_B, 0x34)] {
+            let prog = [
+                SockFilter::stmt(BPF_LD | size | BPF_ABS, 1),
+                SockFilter::stmt(BPF_RET | BPF_A, 0),
+            ];
+            assert_eq!(run_checked(&prog, &data), expected);
+        }
+    }
+
+    #[test]
+    fn test_run_out_of_bounds_load_returns_zero() {
+        let prog = [
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_ABS, 2),
+            SockFilter::stmt(BPF_RET | BPF_K, 0xffff),
+        ];
+        assert_eq!(run_checked(&prog, &[0; 5]), 0);
+        assert_eq!(run_checked(&prog, &[0; 6]), 0xffff);
+    }
+
+    #[test]
+    fn test_run_indirect_load_and_msh() {
+        // X = 4 * (P[0] & 0xf)，再取 P[X + 1]
+        let prog = [
+            SockFilter::stmt(BPF_LDX | BPF_B | BPF_MSH, 0),
+            SockFilter::stmt(BPF_LD | BPF_B | BPF_IND, 1),
+            SockFilter::stmt(BPF_RET | BPF_A, 0),
+        ];
+        let mut data = [0u8; 16];
+        data[0] = 0x42;
+        data[9] = 0xab;
+        assert_eq!(run_checked(&prog, &data), 0xab);
+    }
+
+    #[test]
+    fn test_run_conditional_jumps() {
+        // A == 0x0800 ? 1 : (A & 0x10 ? 2 : 3)
+        let prog = [
+            SockFilter::stmt(BPF_LD | BPF_H | BPF_ABS, 0),
+            SockFilter::jump(BPF_JMP | BPF_JEQ | BPF_K, 0x0800, 0, 1),
+            SockFilter::stmt(BPF_RET | BPF_K, 1),
+            SockFilter::jump(BPF_JMP | BPF_JSET | BPF_K, 0x10, 0, 1),
+            SockFilter::stmt(BPF_RET | BPF_K, 2),
+            SockFilter::stmt(BPF_RET | BPF_K, 3),
+        ];
+        assert_eq!(run_checked(&prog, &[0x08, 0x00]), 1);
+        assert_eq!(run_checked(&prog, &[0x00, 0x10]), 2);
+        assert_eq!(run_checked(&prog, &[0x00, 0x01]), 3);
+    }
+
+    #[test]
+    fn test_run_alu_scratch_and_misc() {
+        // mem[3] = 6; X = 7; A = mem[3] * X - 2 = 40; A %= 7 -> 5
+        let prog = [
+            SockFilter::stmt(BPF_LD | BPF_IMM, 6),
+            SockFilter::stmt(BPF_ST, 3),
+            SockFilter::stmt(BPF_LDX | BPF_W | BPF_IMM, 7),
+            SockFilter::stmt(BPF_LD | BPF_MEM, 3),
+            SockFilter::stmt(BPF_ALU | BPF_MUL | BPF_X, 0),
+            SockFilter::stmt(BPF_ALU | BPF_SUB | BPF_K, 2),
+            SockFilter::stmt(BPF_ALU | BPF_MOD | BPF_X, 0),
+            SockFilter::stmt(BPF_MISC | BPF_TAX, 0),
+            SockFilter::stmt(BPF_LD | BPF_IMM, 0),
+            SockFilter::stmt(BPF_MISC | BPF_TXA, 0),
+            SockFilter::stmt(BPF_RET | BPF_A, 0),
+        ];
+        assert_eq!(run_checked(&prog, &[]), 5);
+    }
+
+    #[test]
+    fn test_run_division_by_zero_register_returns_zero() {
+        let prog = [
+            SockFilter::stmt(BPF_LD | BPF_IMM, 10),
+            SockFilter::stmt(BPF_ALU | BPF_DIV | BPF_X, 0),
+            SockFilter::stmt(BPF_RET | BPF_K, 1),
+        ];
+        assert_eq!(run_checked(&prog, &[]), 0);
+    }
+
+    #[test]
+    fn test_run_unconditional_jump() {
+        let prog = [
+            SockFilter::stmt(BPF_JMP | BPF_JA, 1),
+            SockFilter::stmt(BPF_RET | BPF_K, 1),
+            SockFilter::stmt(BPF_RET | BPF_K, 2),
+        ];
+        assert_eq!(run_checked(&prog, &[]), 2);
+    }
+}
@@ -0,0 +1,68 @@
+//! 经典 BPF（cBPF）
+//!
+//! 提供 cBPF 程序的校验器与解释器，由 seccomp 过滤器与 socket 过滤器（SO_ATTACH_FILTER）共用。
+//!
+//! - [`check`]：加载程序时调用，拒绝非法操作码、越界跳转、除以常量 0 等；
+//!   通过校验的程序只向前跳转，解释执行必然在指令条数步内结束。
+//! - [`run`]：对一份输入数据执行程序，返回 BPF_RET 给出的 32 位结果。
+//! - [`check_seccomp`]：seccomp 过滤器在通用校验之外的额外约束。
+//!
+//! 输入数据通过 [`BpfInput`] 抽象：socket 过滤器按网络字节序读取报文（[`Packet`]），
+//! seccomp 则按本机字节序读取 `struct seccomp_data`。
+
+#![no_std]
+
+extern crate alloc;
+
+mod check;
+mod interp;
+mod seccomp;
+
+pub use check::check;
+pub use interp::run;
+pub use seccomp::check_seccomp;
+
+/// cBPF 程序校验错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BpfError {
+    /// 程序为空或超过 BPF_MAXINSNS 条指令
+    InvalidLength,
+    /// 第 `pc` 条指令非法（未知操作码、越界跳转、除以 0、越界访问暂存存储器等）
+    InvalidInstruction {
+        /// 出错指令的下标
+        pc: usize,
+    },
+    /// 程序的最后一条指令不是 BPF_RET
+    MissingReturn,
+}
+
+impl BpfError {
+    /// 转换为系统调用错误码（负数），与 Linux 一致，所有校验错误均为 EINVAL
+    pub fn to_errno(&self) -> isize {
+        -(uapi::errno::EINVAL as isize)
+    }
+}
+
+/// 程序可以读取的输入数据
+pub trait BpfInput {
+    /// 输入长度（字节），即 BPF_LEN 载入的值
+    fn size(&self) -> u32;
+
+    /// 从 `offset` 处载入 `size`（1、2 或 4）字节，越界时返回 `None`
+    fn load(&self, offset: u32, size: u32) -> Option<u32>;
+}
+
+/// 按网络字节序读取的报文
+pub struct Packet<'a>(pub &'a [u8]);
+
+impl BpfInput for Packet<'_> {
+    fn size(&self) -> u32 {
+        self.0.len() as u32
+    }
+
+    fn load(&self, offset: u32, size: u32) -> Option<u32> {
+        let start = offset as usize;
+        let bytes = self.0.get(start..start.checked_add(size as usize)?)?;
+        Some(bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+    }
+}
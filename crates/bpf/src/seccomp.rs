@@ -0,0 +1,134 @@
+//! seccomp 过滤器的额外约束与输入
+
+use uapi::filter::*;
+use uapi::seccomp::SeccompData;
+
+use crate::{BpfError, BpfInput, check};
+
+/// `struct seccomp_data` 的大小（字节）
+const SECCOMP_DATA_SIZE: u32 = core::mem::size_of::<SeccompData>() as u32;
+
+/// 校验一个 seccomp 过滤器
+///
+/// 在 [`check`] 的基础上，与 Linux 的 seccomp_check_filter 一致地收紧指令集：
+/// 载入只能是 4 字节对齐、不越界的 BPF_W | BPF_ABS，以及 BPF_LEN / BPF_IMM / BPF_MEM；
+/// 不允许 BPF_IND 与 BPF_MSH 等面向报文的寻址方式。
+pub fn check_seccomp(prog: &[SockFilter]) -> Result<(), BpfError> {
+    check(prog)?;
+    for (pc, insn) in prog.iter().enumerate() {
+        let code = insn.code;
+        let ok = match bpf_class(code) {
+            BPF_LD => match bpf_mode(code) {
+                BPF_ABS => bpf_size(code) == BPF_W && insn.k & 3 == 0 && insn.k < SECCOMP_DATA_SIZE,
+                BPF_IMM | BPF_MEM | BPF_LEN => true,
+                _ => false,
+            },
+            BPF_LDX => matches!(bpf_mode(code), BPF_IMM | BPF_MEM | BPF_LEN),
+            _ => true,
+        };
+        if !ok {
+            return Err(BpfError::InvalidInstruction { pc });
+        }
+    }
+    Ok(())
+}
+
+/// 过滤器按本机字节序读取 `struct seccomp_data`
+impl BpfInput for SeccompData {
+    fn size(&self) -> u32 {
+        SECCOMP_DATA_SIZE
+    }
+
+    fn load(&self, offset: u32, size: u32) -> Option<u32> {
+        if size != 4 || offset & 3 != 0 {
+            return None;
+        }
+        let word = match offset {
+            0 => self.nr as u32,
+            4 => self.arch,
+            8..=63 => {
+                let field = match offset {
+                    8 | 12 => self.instruction_pointer,
+                    _ => self.args[(offset as usize - 16) / 8],
+                };
+                let bytes = field.to_ne_bytes();
+                let start = (offset % 8) as usize;
+                u32::from_ne_bytes(bytes[start..start + 4].try_into().ok()?)
+            }
+            _ => return None,
+        };
+        Some(word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run;
+
+    fn data() -> SeccompData {
+        SeccompData {
+            nr: 64,
+            arch: uapi::seccomp::AUDIT_ARCH_RISCV64,
+            instruction_pointer: 0x1234_5678_9abc_def0,
+            args: [1, 2, 3, 4, 5, 0xdead_beef_0000_0006],
+        }
+    }
+
+    #[test]
+    fn test_check_seccomp_restricts_loads() {
+        let ok = [
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_ABS, 60),
+            SockFilter::stmt(BPF_RET | BPF_A, 0),
+        ];
+        assert_eq!(check_seccomp(&ok), Ok(()));
+        for insn in [
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_ABS, 2),
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_ABS, 64),
+            SockFilter::stmt(BPF_LD | BPF_H | BPF_ABS, 0),
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_IND, 0),
+            SockFilter::stmt(BPF_LDX | BPF_B | BPF_MSH, 0),
+        ] {
+            let prog = [insn, SockFilter::stmt(BPF_RET | BPF_A, 0)];
+            assert_eq!(
+                check_seccomp(&prog),
+                Err(BpfError::InvalidInstruction { pc: 0 })
+            );
+        }
+    }
+
+    #[test]
+    fn test_seccomp_data_loads_native_words() {
+        let d = data();
+        assert_eq!(d.size(), 64);
+        assert_eq!(d.load(0, 4), Some(64));
+        assert_eq!(d.load(4, 4), Some(uapi::seccomp::AUDIT_ARCH_RISCV64));
+        let ip = d.instruction_pointer.to_ne_bytes();
+        assert_eq!(
+            d.load(8, 4),
+            Some(u32::from_ne_bytes(ip[..4].try_into().unwrap()))
+        );
+        assert_eq!(
+            d.load(16, 4).zip(d.load(20, 4)).map(|(a, b)| a | b),
+            Some(1)
+        );
+        assert_eq!(d.load(64, 4), None);
+        assert_eq!(d.load(2, 4), None);
+    }
+
+    #[test]
+    fn test_seccomp_filter_matches_syscall_number() {
+        // nr == 64 ? ERRNO(1) : ALLOW
+        let prog = [
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_ABS, 0),
+            SockFilter::jump(BPF_JMP | BPF_JEQ | BPF_K, 64, 0, 1),
+            SockFilter::stmt(BPF_RET | BPF_K, uapi::seccomp::SECCOMP_RET_ERRNO | 1),
+            SockFilter::stmt(BPF_RET | BPF_K, uapi::seccomp::SECCOMP_RET_ALLOW),
+        ];
+        assert_eq!(check_seccomp(&prog), Ok(()));
+        let mut d = data();
+        assert_eq!(run(&prog, &d), uapi::seccomp::SECCOMP_RET_ERRNO | 1);
+        d.nr = 63;
+        assert_eq!(run(&prog, &d), uapi::seccomp::SECCOMP_RET_ALLOW);
+    }
+}
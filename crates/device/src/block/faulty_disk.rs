@@ -0,0 +1,447 @@
+//! 可编程故障的模拟块设备
+//!
+//! [`FaultyDisk`] 包装任意 [`BlockDriver`]（通常是 [`RamDisk`](super::RamDisk)），
+//! 在其之上模拟真实磁盘的"不可靠"行为，用于确定性地对 ext4 日志与回写路径做崩溃测试：
+//!
+//! - **延迟**：每个请求推进一个虚拟时钟（[`FaultyDisk::elapsed`]），不真正睡眠；
+//! - **写缓存与重排**：写入先进入易失写缓存，缓存超过重排窗口时按伪随机顺序落盘，
+//!   `flush` 作为屏障按提交顺序落盘全部缓存；
+//! - **掉电**：[`FaultyDisk::power_cut`] 丢弃（或部分保留、撕裂）尚未落盘的写；
+//! - **坏块**：[`FaultyDisk::add_bad_block`] 令指定块的读 / 写返回失败。
+//!
+//! # 不变量
+//!
+//! - 读请求总能看到自己之前的写（先查写缓存，再查底层设备），与真实磁盘缓存一致；
+//! - 同一块在缓存中至多一项，重复写覆盖旧值；
+//! - 随机性只来自 `seed`，相同种子与请求序列得到相同的落盘顺序。
+
+use super::BlockDriver;
+use crate::driver::{DeviceType, Driver};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use sync::SpinLock;
+
+/// 坏块的故障类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadBlockKind {
+    /// 读失败
+    Read,
+    /// 写失败
+    Write,
+    /// 读写均失败
+    ReadWrite,
+}
+
+/// 掉电模拟方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerCut {
+    /// 丢弃全部未落盘的写
+    DropPending,
+    /// 随机保留一部分未落盘的写（模拟重排后部分写已到达介质）
+    PersistRandomSubset,
+    /// 随机保留一部分，并让其中最后一个写只落盘前 `bytes` 字节（撕裂写）
+    TornWrite {
+        /// 撕裂写实际落盘的字节数
+        bytes: usize,
+    },
+}
+
+/// 模拟块设备的统计信息
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultyDiskStats {
+    /// 读请求数
+    pub reads: u64,
+    /// 写请求数
+    pub writes: u64,
+    /// flush 次数
+    pub flushes: u64,
+    /// 因坏块失败的请求数
+    pub errors: u64,
+    /// 未按提交顺序落盘的写数
+    pub reordered: u64,
+    /// 因掉电丢失的写数
+    pub lost: u64,
+}
+
+struct PendingWrite {
+    block_id: usize,
+    data: Vec<u8>,
+}
+
+struct DiskState {
+    /// 未落盘的写，按提交顺序排列
+    pending: Vec<PendingWrite>,
+    /// 坏块表
+    bad_blocks: Vec<(usize, BadBlockKind)>,
+    /// 写缓存容量；0 表示直写
+    reorder_window: usize,
+    read_latency: u64,
+    write_latency: u64,
+    /// 虚拟时钟
+    clock: u64,
+    rng: u64,
+    stats: FaultyDiskStats,
+}
+
+impl DiskState {
+    fn next_random(&mut self) -> u64 {
+        // xorshift64
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        x
+    }
+
+    fn is_bad(&self, block_id: usize, write: bool) -> bool {
+        self.bad_blocks.iter().any(|&(id, kind)| {
+            id == block_id
+                && match kind {
+                    BadBlockKind::Read => !write,
+                    BadBlockKind::Write => write,
+                    BadBlockKind::ReadWrite => true,
+                }
+        })
+    }
+}
+
+/// 带延迟、写重排、掉电与坏块模拟的块设备
+pub struct FaultyDisk {
+    inner: Arc<dyn BlockDriver>,
+    state: SpinLock<DiskState>,
+    device_id: usize,
+}
+
+impl FaultyDisk {
+    /// 包装一个块设备，默认无延迟、直写、无坏块
+    pub fn new(inner: Arc<dyn BlockDriver>, device_id: usize) -> Arc<Self> {
+        Arc::new(Self {
+            inner,
+            state: SpinLock::new(DiskState {
+                pending: Vec::new(),
+                bad_blocks: Vec::new(),
+                reorder_window: 0,
+                read_latency: 0,
+                write_latency: 0,
+                clock: 0,
+                rng: 0x2545_F491_4F6C_DD1D,
+                stats: FaultyDiskStats::default(),
+            }),
+            device_id,
+        })
+    }
+
+    /// 设置每个读 / 写请求推进的虚拟时间
+    pub fn set_latency(&self, read: u64, write: u64) {
+        let mut state = self.state.lock();
+        state.read_latency = read;
+        state.write_latency = write;
+    }
+
+    /// 设置写缓存容量（重排窗口）
+    ///
+    /// 0 表示直写；缩小窗口时多出的缓存项会立即落盘。
+    pub fn set_reorder_window(&self, window: usize) {
+        let mut state = self.state.lock();
+        state.reorder_window = window;
+        while state.pending.len() > window {
+            self.evict_one(&mut state);
+        }
+    }
+
+    /// 设置伪随机种子（0 会被替换为固定的非零值）
+    pub fn set_seed(&self, seed: u64) {
+        self.state.lock().rng = if seed == 0 {
+            0x2545_F491_4F6C_DD1D
+        } else {
+            seed
+        };
+    }
+
+    /// 将块标记为坏块
+    pub fn add_bad_block(&self, block_id: usize, kind: BadBlockKind) {
+        self.state.lock().bad_blocks.push((block_id, kind));
+    }
+
+    /// 清除全部坏块
+    pub fn clear_bad_blocks(&self) {
+        self.state.lock().bad_blocks.clear();
+    }
+
+    /// 已经过的虚拟时间
+    pub fn elapsed(&self) -> u64 {
+        self.state.lock().clock
+    }
+
+    /// 写缓存中尚未落盘的写数量
+    pub fn pending_writes(&self) -> usize {
+        self.state.lock().pending.len()
+    }
+
+    /// 获取统计信息
+    pub fn stats(&self) -> FaultyDiskStats {
+        self.state.lock().stats
+    }
+
+    /// 获取设备 ID
+    pub fn device_id(&self) -> usize {
+        self.device_id
+    }
+
+    /// 模拟掉电
+    ///
+    /// 按 `mode` 处理写缓存后清空缓存，设备随后可继续使用（相当于重新上电）。
+    ///
+    /// # 返回值
+    /// 丢失（未完整落盘）的写数量
+    pub fn power_cut(&self, mode: PowerCut) -> usize {
+        let mut state = self.state.lock();
+        let pending = core::mem::take(&mut state.pending);
+        let mut lost = 0;
+        let mut last_kept: Option<PendingWrite> = None;
+
+        for write in pending {
+            let keep = match mode {
+                PowerCut::DropPending => false,
+                PowerCut::PersistRandomSubset | PowerCut::TornWrite { .. } => {
+                    state.next_random() & 1 == 1
+                }
+            };
+            if !keep {
+                lost += 1;
+                continue;
+            }
+            if let Some(prev) = last_kept.replace(write) {
+                self.inner.write_block(prev.block_id, &prev.data);
+            }
+        }
+
+        if let Some(last) = last_kept {
+            match mode {
+                PowerCut::TornWrite { bytes } if bytes < last.data.len() => {
+                    let mut torn = alloc::vec![0u8; last.data.len()];
+                    self.inner.read_block(last.block_id, &mut torn);
+                    torn[..bytes].copy_from_slice(&last.data[..bytes]);
+                    self.inner.write_block(last.block_id, &torn);
+                    lost += 1;
+                }
+                _ => {
+                    self.inner.write_block(last.block_id, &last.data);
+                }
+            }
+        }
+
+        state.stats.lost += lost as u64;
+        lost
+    }
+
+    /// 随机选一个缓存项落盘
+    fn evict_one(&self, state: &mut DiskState) {
+        let idx = (state.next_random() % state.pending.len() as u64) as usize;
+        if idx != 0 {
+            state.stats.reordered += 1;
+        }
+        let write = state.pending.remove(idx);
+        self.inner.write_block(write.block_id, &write.data);
+    }
+}
+
+impl Driver for FaultyDisk {
+    fn try_handle_interrupt(&self, _irq: Option<usize>) -> bool {
+        false
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::Block
+    }
+
+    fn get_id(&self) -> String {
+        alloc::format!("faultydisk_{}", self.device_id)
+    }
+
+    fn as_block(&self) -> Option<&dyn BlockDriver> {
+        Some(self)
+    }
+}
+
+impl BlockDriver for FaultyDisk {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) -> bool {
+        let mut state = self.state.lock();
+        state.stats.reads += 1;
+        state.clock += state.read_latency;
+        if state.is_bad(block_id, false) {
+            state.stats.errors += 1;
+            return false;
+        }
+        if let Some(write) = state.pending.iter().find(|w| w.block_id == block_id) {
+            if buf.len() != write.data.len() {
+                return false;
+            }
+            buf.copy_from_slice(&write.data);
+            return true;
+        }
+        self.inner.read_block(block_id, buf)
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) -> bool {
+        let mut state = self.state.lock();
+        state.stats.writes += 1;
+        state.clock += state.write_latency;
+        if state.is_bad(block_id, true) {
+            state.stats.errors += 1;
+            return false;
+        }
+        if state.reorder_window == 0 {
+            return self.inner.write_block(block_id, buf);
+        }
+        if buf.len() != self.inner.block_size() || block_id >= self.inner.total_blocks() {
+            return false;
+        }
+        if let Some(write) = state.pending.iter_mut().find(|w| w.block_id == block_id) {
+            write.data.copy_from_slice(buf);
+            return true;
+        }
+        state.pending.push(PendingWrite {
+            block_id,
+            data: buf.to_vec(),
+        });
+        if state.pending.len() > state.reorder_window {
+            self.evict_one(&mut state);
+        }
+        true
+    }
+
+    fn flush(&self) -> bool {
+        let mut state = self.state.lock();
+        state.stats.flushes += 1;
+        let pending = core::mem::take(&mut state.pending);
+        let mut ok = true;
+        for write in pending {
+            ok &= self.inner.write_block(write.block_id, &write.data);
+        }
+        ok && self.inner.flush()
+    }
+
+    fn block_size(&self) -> usize {
+        self.inner.block_size()
+    }
+
+    fn total_blocks(&self) -> usize {
+        self.inner.total_blocks()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::RamDisk;
+    use sync::mock::init_arch_ops;
+
+    fn setup(window: usize) -> (Arc<RamDisk>, Arc<FaultyDisk>) {
+        init_arch_ops();
+        let ram = RamDisk::new(16 * 512, 512, 0);
+        let disk = FaultyDisk::new(ram.clone(), 1);
+        disk.set_reorder_window(window);
+        (ram, disk)
+    }
+
+    fn on_media(ram: &RamDisk, block_id: usize) -> u8 {
+        let mut buf = [0u8; 512];
+        assert!(ram.read_block(block_id, &mut buf));
+        buf[0]
+    }
+
+    #[test]
+    fn test_latency_and_bad_blocks() {
+        let (_ram, disk) = setup(0);
+        disk.set_latency(3, 7);
+        disk.add_bad_block(2, BadBlockKind::Write);
+
+        let mut buf = [0u8; 512];
+        assert!(disk.read_block(0, &mut buf));
+        assert!(!disk.write_block(2, &buf));
+        assert!(disk.read_block(2, &mut buf));
+        assert_eq!(disk.elapsed(), 3 + 7 + 3);
+        assert_eq!(disk.stats().errors, 1);
+    }
+
+    #[test]
+    fn test_write_cache_read_your_writes_and_flush() {
+        let (ram, disk) = setup(4);
+        assert!(disk.write_block(1, &[0xAA; 512]));
+        assert_eq!(disk.pending_writes(), 1);
+        assert_eq!(on_media(&ram, 1), 0);
+
+        let mut buf = [0u8; 512];
+        assert!(disk.read_block(1, &mut buf));
+        assert_eq!(buf[0], 0xAA);
+
+        assert!(disk.flush());
+        assert_eq!(disk.pending_writes(), 0);
+        assert_eq!(on_media(&ram, 1), 0xAA);
+    }
+
+    #[test]
+    fn test_power_cut_drops_unflushed_writes() {
+        let (ram, disk) = setup(8);
+        assert!(disk.write_block(0, &[1; 512]));
+        assert!(disk.flush());
+        assert!(disk.write_block(1, &[2; 512]));
+        assert!(disk.write_block(2, &[3; 512]));
+
+        assert_eq!(disk.power_cut(PowerCut::DropPending), 2);
+        assert_eq!(on_media(&ram, 0), 1);
+        assert_eq!(on_media(&ram, 1), 0);
+        assert_eq!(on_media(&ram, 2), 0);
+        assert_eq!(disk.stats().lost, 2);
+    }
+
+    #[test]
+    fn test_torn_write_persists_prefix_only() {
+        let (ram, disk) = setup(8);
+        disk.set_seed(7);
+        for id in 0..8 {
+            assert!(disk.write_block(id, &[0xEE; 512]));
+        }
+        let lost = disk.power_cut(PowerCut::TornWrite { bytes: 100 });
+        assert!(lost >= 1);
+
+        let full = (0..8)
+            .filter(|&id| {
+                let mut buf = [0u8; 512];
+                ram.read_block(id, &mut buf);
+                buf.iter().all(|&b| b == 0xEE)
+            })
+            .count();
+        let torn = (0..8)
+            .filter(|&id| {
+                let mut buf = [0u8; 512];
+                ram.read_block(id, &mut buf);
+                buf[99] == 0xEE && buf[100] == 0
+            })
+            .count();
+        assert_eq!(torn, 1);
+        assert_eq!(full + lost, 8);
+    }
+
+    #[test]
+    fn test_reordering_is_deterministic() {
+        let order = |seed: u64| {
+            let (ram, disk) = setup(3);
+            disk.set_seed(seed);
+            let mut landed = Vec::new();
+            for id in 0..10 {
+                assert!(disk.write_block(id, &[id as u8 + 1; 512]));
+                for b in 0..10 {
+                    if on_media(&ram, b) != 0 && !landed.contains(&b) {
+                        landed.push(b);
+                    }
+                }
+            }
+            landed
+        };
+        assert_eq!(order(11), order(11));
+    }
+}
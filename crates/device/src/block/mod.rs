@@ -2,20 +2,22 @@
 //!
 //! 包含块设备相关的驱动接口和实现
 
+mod faulty_disk;
 mod ram_disk;
 
 use alloc::{sync::Arc, vec::Vec};
-use lazy_static::lazy_static;
-use sync::RwLock;
+use sync::{LazyLock, Rcu};
 
 use crate::driver::Driver;
 
+pub use faulty_disk::{BadBlockKind, FaultyDisk, FaultyDiskStats, PowerCut};
 pub use ram_disk::RamDisk;
 
-lazy_static! {
-    /// 全局块设备驱动列表
-    pub static ref BLK_DRIVERS: RwLock<Vec<Arc<dyn BlockDriver>>> = RwLock::new(Vec::new());
-}
+/// 全局块设备驱动列表
+///
+/// 由 RCU 保护：`read()` 不加锁，读取期间不能睡眠，需要做 I/O 时先克隆出驱动的 `Arc`。
+pub static BLK_DRIVERS: LazyLock<Rcu<Vec<Arc<dyn BlockDriver>>>> =
+    LazyLock::new(|| Rcu::new(Vec::new()));
 
 /// 块设备驱动程序接口
 pub trait BlockDriver: Driver {
@@ -1,4 +1,8 @@
 //! 内存模拟块设备
+//!
+//! 在 `cfg(test)` 或 `fault-injection` feature 下，读写会先询问
+//! `test_support::fault`（`FaultPoint::BlockRead` / `FaultPoint::BlockWrite`），
+//! 用于模拟磁盘 I/O 错误。
 
 use super::BlockDriver;
 use crate::driver::{DeviceType, Driver};
@@ -70,10 +74,28 @@ impl Driver for RamDisk {
     }
 }
 
+/// 故障注入点：测试配置了对应 `FaultPoint` 时模拟 I/O 失败。
+#[cfg(any(test, feature = "fault-injection"))]
+#[inline]
+fn inject_io_failure(write: bool) -> bool {
+    use test_support::fault::{FaultPoint, should_fail};
+    should_fail(if write {
+        FaultPoint::BlockWrite
+    } else {
+        FaultPoint::BlockRead
+    })
+}
+
+#[cfg(not(any(test, feature = "fault-injection")))]
+#[inline(always)]
+fn inject_io_failure(_write: bool) -> bool {
+    false
+}
+
 // 实现 BlockDriver trait
 impl BlockDriver for RamDisk {
     fn read_block(&self, block_id: usize, buf: &mut [u8]) -> bool {
-        if buf.len() != self.block_size {
+        if buf.len() != self.block_size || inject_io_failure(false) {
             return false;
         }
 
@@ -89,7 +111,7 @@ impl BlockDriver for RamDisk {
     }
 
     fn write_block(&self, block_id: usize, buf: &[u8]) -> bool {
-        if buf.len() != self.block_size {
+        if buf.len() != self.block_size || inject_io_failure(true) {
             return false;
         }
 
@@ -120,53 +142,11 @@ impl BlockDriver for RamDisk {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use core::sync::atomic::{AtomicUsize, Ordering};
-    use sync::ArchOps;
-
-    struct DummyArchOps;
-
-    impl ArchOps for DummyArchOps {
-        unsafe fn read_and_disable_interrupts(&self) -> usize {
-            0
-        }
-
-        unsafe fn restore_interrupts(&self, _flags: usize) {}
-
-        fn sstatus_sie(&self) -> usize {
-            0
-        }
-
-        fn cpu_id(&self) -> usize {
-            0
-        }
-
-        fn max_cpu_count(&self) -> usize {
-            1
-        }
-    }
-
-    static DUMMY_ARCH_OPS: DummyArchOps = DummyArchOps;
-    // 0 = uninit, 1 = initializing, 2 = ready
-    static SYNC_INIT: AtomicUsize = AtomicUsize::new(0);
-
-    fn init_sync_arch_ops() {
-        match SYNC_INIT.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire) {
-            Ok(_) => {
-                // Safety: tests use a single global dummy ArchOps.
-                unsafe { sync::register_arch_ops(&DUMMY_ARCH_OPS) };
-                SYNC_INIT.store(2, Ordering::Release);
-            }
-            Err(_) => {
-                while SYNC_INIT.load(Ordering::Acquire) != 2 {
-                    core::hint::spin_loop();
-                }
-            }
-        }
-    }
+    use sync::mock::init_arch_ops;
 
     #[test]
     fn test_ramdisk_read_write_roundtrip() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let rd = RamDisk::new(4096, 512, 1);
         assert_eq!(rd.block_size(), 512);
         assert_eq!(rd.total_blocks(), 8);
@@ -188,7 +168,7 @@ mod tests {
 
     #[test]
     fn test_ramdisk_bounds_and_wrong_buf_size() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let rd = RamDisk::new(1024, 512, 1);
         assert_eq!(rd.total_blocks(), 2);
 
@@ -204,4 +184,28 @@ mod tests {
         let ok_write = [0u8; 512];
         assert!(!rd.write_block(2, &ok_write)); // out of range
     }
+
+    #[test]
+    fn test_ramdisk_injected_io_errors() {
+        use test_support::fault::{FaultPoint, FaultPolicy, FaultScope};
+
+        init_arch_ops();
+        let scope = FaultScope::new();
+        let rd = RamDisk::new(2048, 512, 1);
+        let wbuf = [0x5Au8; 512];
+
+        scope.inject(FaultPoint::BlockWrite, FaultPolicy::Nth(2));
+        assert!(rd.write_block(0, &wbuf));
+        assert!(!rd.write_block(1, &wbuf));
+        assert!(rd.write_block(2, &wbuf));
+
+        // 失败的写不应修改数据
+        let mut rbuf = [0u8; 512];
+        assert!(rd.read_block(1, &mut rbuf));
+        assert_eq!(rbuf, [0u8; 512]);
+
+        scope.inject(FaultPoint::BlockRead, FaultPolicy::Always);
+        assert!(!rd.read_block(0, &mut rbuf));
+        assert_eq!(scope.stats(FaultPoint::BlockRead).injected, 1);
+    }
 }
@@ -0,0 +1,104 @@
+//! `console=` 启动参数解析
+//!
+//! 与 Linux 相同，`console=` 可以出现多次，每个都启用一个控制台，
+//! 最后一个同时作为 `/dev/console`。参数值中 `,` 之后是选项：`loglevel:<n>` 单独设置
+//! 该控制台的日志级别（0..=7），其余选项（如波特率）被忽略。
+
+use alloc::vec::Vec;
+
+/// `console=` 指定的控制台设备
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleTarget {
+    /// `ttyS<n>`：第 n 个串口
+    Serial(usize),
+    /// `tty<n>`：第 n 个虚拟终端（从 1 开始），`tty0` 表示当前前台虚拟终端
+    Vt(usize),
+}
+
+impl ConsoleTarget {
+    /// 解析单个 `console=` 参数值，无法识别时返回 `None`
+    pub fn parse(value: &str) -> Option<Self> {
+        let name = value.split(',').next()?;
+        if let Some(index) = name.strip_prefix("ttyS") {
+            return index.parse().ok().map(Self::Serial);
+        }
+        name.strip_prefix("tty")?.parse().ok().map(Self::Vt)
+    }
+}
+
+/// 一个 `console=` 参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleParam {
+    /// 控制台设备
+    pub target: ConsoleTarget,
+    /// `loglevel:<n>` 选项指定的日志级别
+    pub loglevel: Option<u8>,
+}
+
+impl ConsoleParam {
+    /// 解析单个 `console=` 参数值，无法识别设备时返回 `None`
+    pub fn parse(value: &str) -> Option<Self> {
+        let target = ConsoleTarget::parse(value)?;
+        let loglevel = value
+            .split(',')
+            .skip(1)
+            .filter_map(|opt| opt.strip_prefix("loglevel:")?.parse().ok())
+            .find(|&level| level <= 7);
+        Some(Self { target, loglevel })
+    }
+}
+
+/// 按出现顺序返回 `cmdline` 中所有可识别的 `console=` 参数
+pub fn console_params(cmdline: &str) -> Vec<ConsoleParam> {
+    cmdline
+        .split_whitespace()
+        .filter_map(|tok| tok.strip_prefix("console="))
+        .filter_map(ConsoleParam::parse)
+        .collect()
+}
+
+/// 按出现顺序返回 `cmdline` 中所有可识别的 `console=` 设备
+pub fn console_targets(cmdline: &str) -> Vec<ConsoleTarget> {
+    console_params(cmdline)
+        .into_iter()
+        .map(|param| param.target)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_targets() {
+        assert_eq!(
+            ConsoleTarget::parse("ttyS1,115200"),
+            Some(ConsoleTarget::Serial(1))
+        );
+        assert_eq!(ConsoleTarget::parse("tty0"), Some(ConsoleTarget::Vt(0)));
+        assert_eq!(ConsoleTarget::parse("ttyS"), None);
+        assert_eq!(ConsoleTarget::parse("hvc0"), None);
+        assert_eq!(
+            console_targets("quiet console=tty1 console=lp0 console=ttyS0,115200n8"),
+            [ConsoleTarget::Vt(1), ConsoleTarget::Serial(0)]
+        );
+        assert!(console_targets("root=/dev/vda").is_empty());
+    }
+
+    #[test]
+    fn test_console_loglevel_option() {
+        assert_eq!(
+            console_params("console=ttyS0,115200,loglevel:7 console=tty1,loglevel:9"),
+            [
+                ConsoleParam {
+                    target: ConsoleTarget::Serial(0),
+                    loglevel: Some(7),
+                },
+                ConsoleParam {
+                    target: ConsoleTarget::Vt(1),
+                    loglevel: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! 帧缓冲抽象
+//!
+//! 文本控制台只依赖本模块定义的 [`FrameBuffer`] trait，
+//! 具体的显示设备（如 virtio-gpu）在 OS 侧实现该 trait 后即可承载控制台。
+
+/// 像素颜色，格式为 `0x00RRGGBB`
+pub type Rgb = u32;
+
+/// 帧缓冲接口
+pub trait FrameBuffer: Send {
+    /// 可见区域宽度（像素）
+    fn width(&self) -> usize;
+
+    /// 可见区域高度（像素）
+    fn height(&self) -> usize;
+
+    /// 写入单个像素，越界坐标应被忽略
+    fn write_pixel(&mut self, x: usize, y: usize, color: Rgb);
+
+    /// 以纯色填充矩形区域
+    ///
+    /// 默认实现逐像素写入，设备可覆盖为更高效的实现。
+    fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: Rgb) {
+        let x_end = (x + w).min(self.width());
+        let y_end = (y + h).min(self.height());
+        for py in y..y_end {
+            for px in x..x_end {
+                self.write_pixel(px, py, color);
+            }
+        }
+    }
+
+    /// 将已修改的内容提交到显示设备
+    fn flush(&mut self) {}
+}
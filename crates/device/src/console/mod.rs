@@ -1,13 +1,27 @@
 //! 控制台驱动模块
 
-use alloc::{string::String, sync::Arc, vec::Vec};
+pub mod cmdline;
+pub mod font;
+pub mod framebuffer;
+pub mod mux;
+pub mod vt;
+
+pub use cmdline::{ConsoleParam, ConsoleTarget, console_params, console_targets};
+pub use framebuffer::{FrameBuffer, Rgb};
+pub use mux::{ConsoleMux, EARLY_LOG_SIZE, EarlyLog};
+pub use vt::{FrameConsole, Key, Modifiers};
+
+use alloc::{string::String, sync::Arc};
 use lazy_static::lazy_static;
 use sync::RwLock;
 
+/// 运行时控制台就绪之前的早期输出记录
+pub static EARLY_LOG: EarlyLog = EarlyLog::new();
+
 lazy_static! {
-    /// 全局控制台列表
-    pub static ref CONSOLES: RwLock<Vec<Arc<dyn Console>>> = RwLock::new(Vec::new());
-    /// 全局主控制台
+    /// 全局控制台列表（内核输出写到其中的每一个控制台）
+    pub static ref CONSOLES: ConsoleMux = ConsoleMux::new();
+    /// 全局主控制台（内核从其读取输入）
     pub static ref MAIN_CONSOLE: RwLock<Option<Arc<dyn Console>>> = RwLock::new(None);
 }
 
@@ -0,0 +1,319 @@
+//! 控制台复用与早期输出交接
+//!
+//! 内核输出同时写到所有已注册的控制台（如串口与帧缓冲），每个控制台可以单独设置日志级别。
+//!
+//! 运行时控制台就绪之前的输出（SBI / earlycon）记录在 [`EarlyLog`] 中；切换到运行时控制台时
+//! 调用 [`ConsoleMux::handoff`]，把记录的内容回放到早期输出无法到达的控制台（如帧缓冲）。
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use sync::{RwLock, SpinLock};
+
+use super::Console;
+
+/// 早期输出缓冲区大小（字节）
+pub const EARLY_LOG_SIZE: usize = 16 * 1024;
+
+/// 表示控制台跟随全局控制台日志级别
+const LOGLEVEL_DEFAULT: u8 = u8::MAX;
+
+struct EarlyLogInner {
+    buf: [u8; EARLY_LOG_SIZE],
+    /// 最旧字节的位置
+    start: usize,
+    len: usize,
+    closed: bool,
+}
+
+/// 早期输出记录
+///
+/// 固定大小的环形缓冲区，不依赖堆分配；写满后丢弃最旧的内容。
+pub struct EarlyLog {
+    inner: SpinLock<EarlyLogInner>,
+}
+
+impl Default for EarlyLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EarlyLog {
+    /// 创建空的早期输出记录
+    pub const fn new() -> Self {
+        Self {
+            inner: SpinLock::new(EarlyLogInner {
+                buf: [0; EARLY_LOG_SIZE],
+                start: 0,
+                len: 0,
+                closed: false,
+            }),
+        }
+    }
+
+    /// 记录一段早期输出，关闭后不再记录
+    ///
+    /// 锁被占用（例如在持有锁时 panic 后再次输出）时直接丢弃，保证不会死锁。
+    pub fn record(&self, s: &str) {
+        let Some(mut inner) = self.inner.try_lock() else {
+            return;
+        };
+        if inner.closed {
+            return;
+        }
+        for &byte in s.as_bytes() {
+            let end = (inner.start + inner.len) % EARLY_LOG_SIZE;
+            inner.buf[end] = byte;
+            if inner.len == EARLY_LOG_SIZE {
+                inner.start = (inner.start + 1) % EARLY_LOG_SIZE;
+            } else {
+                inner.len += 1;
+            }
+        }
+    }
+
+    /// 停止记录
+    pub fn close(&self) {
+        self.inner.lock().closed = true;
+    }
+
+    /// 按写入顺序取出已记录的内容（被覆盖处截断的字符以替换字符表示）
+    pub fn contents(&self) -> String {
+        let inner = self.inner.lock();
+        let mut bytes = Vec::with_capacity(inner.len);
+        let first = (EARLY_LOG_SIZE - inner.start).min(inner.len);
+        bytes.extend_from_slice(&inner.buf[inner.start..inner.start + first]);
+        bytes.extend_from_slice(&inner.buf[..inner.len - first]);
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+struct MuxEntry {
+    console: Arc<dyn Console>,
+    /// 单独设置的日志级别，[`LOGLEVEL_DEFAULT`] 表示跟随全局控制台级别
+    loglevel: AtomicU8,
+    /// 交接时是否回放早期输出
+    replay: bool,
+}
+
+/// 控制台复用器
+pub struct ConsoleMux {
+    consoles: RwLock<Vec<MuxEntry>>,
+    handed_off: AtomicBool,
+}
+
+impl Default for ConsoleMux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleMux {
+    /// 创建空的复用器
+    pub fn new() -> Self {
+        Self {
+            consoles: RwLock::new(Vec::new()),
+            handed_off: AtomicBool::new(false),
+        }
+    }
+
+    /// 注册控制台，返回其编号
+    ///
+    /// `loglevel` 为 `None` 时跟随全局控制台级别。`replay` 表示早期输出到达不了该控制台，
+    /// 交接时需要回放；交接之后注册的控制台立即回放。
+    pub fn register(
+        &self,
+        console: Arc<dyn Console>,
+        loglevel: Option<u8>,
+        replay: bool,
+        early: &EarlyLog,
+    ) -> usize {
+        if replay && self.handed_off.load(Ordering::Acquire) {
+            console.write_str(&early.contents());
+        }
+        let mut consoles = self.consoles.write();
+        consoles.push(MuxEntry {
+            console,
+            loglevel: AtomicU8::new(loglevel.unwrap_or(LOGLEVEL_DEFAULT)),
+            replay,
+        });
+        consoles.len() - 1
+    }
+
+    /// 从早期输出切换到已注册的控制台：回放早期输出并停止记录
+    pub fn handoff(&self, early: &EarlyLog) {
+        if self.handed_off.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        early.close();
+        let contents = early.contents();
+        for entry in self.consoles.read().iter().filter(|entry| entry.replay) {
+            entry.console.write_str(&contents);
+        }
+    }
+
+    /// 是否已完成交接
+    pub fn handed_off(&self) -> bool {
+        self.handed_off.load(Ordering::Acquire)
+    }
+
+    /// 已注册的控制台数量
+    pub fn len(&self) -> usize {
+        self.consoles.read().len()
+    }
+
+    /// 是否没有注册任何控制台
+    pub fn is_empty(&self) -> bool {
+        self.consoles.read().is_empty()
+    }
+
+    /// 第 `idx` 个控制台
+    pub fn get(&self, idx: usize) -> Option<Arc<dyn Console>> {
+        self.consoles
+            .read()
+            .get(idx)
+            .map(|entry| entry.console.clone())
+    }
+
+    /// 第 `idx` 个控制台单独设置的日志级别
+    pub fn loglevel(&self, idx: usize) -> Option<u8> {
+        let level = self
+            .consoles
+            .read()
+            .get(idx)?
+            .loglevel
+            .load(Ordering::Acquire);
+        (level != LOGLEVEL_DEFAULT).then_some(level)
+    }
+
+    /// 设置第 `idx` 个控制台的日志级别，`None` 恢复为跟随全局控制台级别
+    pub fn set_loglevel(&self, idx: usize, loglevel: Option<u8>) {
+        if let Some(entry) = self.consoles.read().get(idx) {
+            entry
+                .loglevel
+                .store(loglevel.unwrap_or(LOGLEVEL_DEFAULT), Ordering::Release);
+        }
+    }
+
+    /// 向所有控制台写入字符串
+    pub fn write_str(&self, s: &str) {
+        for entry in self.consoles.read().iter() {
+            entry.console.write_str(s);
+        }
+    }
+
+    /// 向日志级别允许的控制台写入一条日志
+    ///
+    /// `console` 表示 `level` 满足全局控制台级别，用于没有单独设置级别的控制台。
+    pub fn write_log(&self, level: u8, s: &str, console: bool) {
+        for entry in self.consoles.read().iter() {
+            let loglevel = entry.loglevel.load(Ordering::Acquire);
+            let enabled = if loglevel == LOGLEVEL_DEFAULT {
+                console
+            } else {
+                level <= loglevel
+            };
+            if enabled {
+                entry.console.write_str(s);
+            }
+        }
+    }
+
+    /// 是否有控制台单独要求输出 `level` 级别的日志
+    pub fn wants_level(&self, level: u8) -> bool {
+        self.consoles.read().iter().any(|entry| {
+            let loglevel = entry.loglevel.load(Ordering::Acquire);
+            loglevel != LOGLEVEL_DEFAULT && level <= loglevel
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sync::mock::init_arch_ops;
+
+    struct BufConsole(SpinLock<String>);
+
+    impl Console for BufConsole {
+        fn write_str(&self, s: &str) {
+            self.0.lock().push_str(s);
+        }
+        fn read_char(&self) -> char {
+            '\0'
+        }
+        fn read_line(&self, _buf: &mut String) {}
+        fn flush(&self) {}
+    }
+
+    fn buf_console() -> Arc<BufConsole> {
+        init_arch_ops();
+        Arc::new(BufConsole(SpinLock::new(String::new())))
+    }
+
+    #[test]
+    fn test_early_log_ring() {
+        init_arch_ops();
+        let log = EarlyLog::new();
+        log.record("boot\n");
+        assert_eq!(log.contents(), "boot\n");
+
+        let filler = "x".repeat(EARLY_LOG_SIZE - 1);
+        log.record(&filler);
+        log.record("yz");
+        // 写满后最旧的 "boot\n" 与一个 'x' 被覆盖
+        let expected = "x".repeat(EARLY_LOG_SIZE - 2) + "yz";
+        assert_eq!(log.contents(), expected);
+
+        log.close();
+        log.record("late");
+        assert_eq!(log.contents(), expected);
+    }
+
+    #[test]
+    fn test_handoff_replays_early_output() {
+        let early = EarlyLog::new();
+        let mux = ConsoleMux::new();
+        let serial = buf_console();
+        let fb = buf_console();
+        early.record("[Boot] hello\n");
+        mux.register(serial.clone(), None, false, &early);
+        mux.register(fb.clone(), None, true, &early);
+        early.record("[Boot] devices\n");
+
+        mux.handoff(&early);
+        early.record("dropped");
+        mux.write_str("runtime\n");
+        assert_eq!(*serial.0.lock(), "runtime\n");
+        assert_eq!(*fb.0.lock(), "[Boot] hello\n[Boot] devices\nruntime\n");
+
+        // 交接之后注册的控制台立即回放
+        let late = buf_console();
+        mux.register(late.clone(), None, true, &early);
+        assert_eq!(*late.0.lock(), "[Boot] hello\n[Boot] devices\n");
+    }
+
+    #[test]
+    fn test_per_console_loglevel() {
+        let early = EarlyLog::new();
+        let mux = ConsoleMux::new();
+        let quiet = buf_console();
+        let verbose = buf_console();
+        mux.register(quiet.clone(), Some(3), false, &early);
+        let idx = mux.register(verbose.clone(), None, false, &early);
+
+        // 6 = Info：只有默认级别满足时才写到跟随全局级别的控制台
+        assert!(!mux.wants_level(6));
+        mux.write_log(6, "info\n", true);
+        mux.write_log(3, "err\n", true);
+        assert_eq!(*quiet.0.lock(), "err\n");
+        assert_eq!(*verbose.0.lock(), "info\nerr\n");
+
+        mux.set_loglevel(idx, Some(7));
+        assert_eq!(mux.loglevel(idx), Some(7));
+        assert!(mux.wants_level(7));
+        mux.write_log(7, "debug\n", false);
+        assert_eq!(*quiet.0.lock(), "err\n");
+        assert_eq!(*verbose.0.lock(), "info\nerr\ndebug\n");
+    }
+}
@@ -0,0 +1,297 @@
+//! 帧缓冲虚拟终端
+//!
+//! [`FrameConsole`] 在一块 [`FrameBuffer`] 上承载多个虚拟终端（VT），
+//! 每个 VT 拥有独立的 [`Parser`]、[`Screen`] 与输入队列，同一时刻只有前台 VT 被绘制。
+//!
+//! - `Alt+F<n>`（或 `Ctrl+Alt+F<n>`）切换到第 n 个 VT
+//! - `Shift+PageUp` / `Shift+PageDown` 在前台 VT 的回滚缓冲区中翻页
+//!
+//! 作为 [`Console`] 使用时，输出写入 VT 0，输入从 VT 0 的输入队列读取。
+
+pub mod parser;
+pub mod screen;
+
+pub use parser::{Action, Csi, Parser};
+pub use screen::{Attr, Cell, CellFlags, Color, Screen};
+
+use alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec};
+use bitflags::bitflags;
+use sync::SpinLock;
+
+use super::{
+    Console,
+    font::{FONT_HEIGHT, FONT_WIDTH},
+    framebuffer::FrameBuffer,
+};
+
+/// 每个 VT 的回滚缓冲区行数
+pub const SCROLLBACK_LINES: usize = 200;
+
+bitflags! {
+    /// 按键修饰键
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Modifiers: u8 {
+        /// Shift
+        const SHIFT = 1 << 0;
+        /// Ctrl
+        const CTRL = 1 << 1;
+        /// Alt
+        const ALT = 1 << 2;
+    }
+}
+
+/// 控制台关心的按键
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// 功能键 F1..F12，取值从 1 开始
+    Function(u8),
+    /// PageUp
+    PageUp,
+    /// PageDown
+    PageDown,
+    /// 其他按键
+    Other,
+}
+
+/// 单个虚拟终端
+struct VirtualTerminal {
+    parser: Parser,
+    screen: Screen,
+    input: VecDeque<u8>,
+}
+
+impl VirtualTerminal {
+    fn write(&mut self, s: &str) {
+        for c in s.chars() {
+            if let Some(action) = self.parser.advance(c) {
+                self.screen.apply(action);
+            }
+        }
+        // DSR/DA 等应答作为该 VT 的输入回送
+        self.input.extend(self.screen.take_responses());
+    }
+}
+
+struct FrameConsoleInner {
+    fb: Box<dyn FrameBuffer>,
+    vts: Vec<VirtualTerminal>,
+    active: usize,
+}
+
+impl FrameConsoleInner {
+    fn render(&mut self, full: bool) {
+        let active = self.active;
+        self.vts[active].screen.render(self.fb.as_mut(), full);
+    }
+}
+
+/// 帧缓冲文本控制台
+pub struct FrameConsole {
+    inner: SpinLock<FrameConsoleInner>,
+}
+
+impl FrameConsole {
+    /// 在 `fb` 上创建 `count` 个虚拟终端，屏幕尺寸由帧缓冲分辨率与字体大小决定
+    pub fn new(fb: Box<dyn FrameBuffer>, count: usize) -> Self {
+        let cols = fb.width() / FONT_WIDTH;
+        let rows = fb.height() / FONT_HEIGHT;
+        let vts = (0..count.max(1))
+            .map(|_| VirtualTerminal {
+                parser: Parser::new(),
+                screen: Screen::new(cols, rows, SCROLLBACK_LINES),
+                input: VecDeque::new(),
+            })
+            .collect();
+        let mut inner = FrameConsoleInner { fb, vts, active: 0 };
+        inner.render(true);
+        Self {
+            inner: SpinLock::new(inner),
+        }
+    }
+
+    /// 虚拟终端数量
+    pub fn count(&self) -> usize {
+        self.inner.lock().vts.len()
+    }
+
+    /// 前台虚拟终端编号
+    pub fn active(&self) -> usize {
+        self.inner.lock().active
+    }
+
+    /// 切换前台虚拟终端，编号越界时返回 `false`
+    pub fn switch_to(&self, vt: usize) -> bool {
+        let mut inner = self.inner.lock();
+        if vt >= inner.vts.len() {
+            return false;
+        }
+        if vt != inner.active {
+            inner.active = vt;
+            inner.render(true);
+        }
+        true
+    }
+
+    /// 向第 `vt` 个虚拟终端写入字符串，若其在前台则立即重绘
+    pub fn write_vt(&self, vt: usize, s: &str) {
+        let mut inner = self.inner.lock();
+        let Some(term) = inner.vts.get_mut(vt) else {
+            return;
+        };
+        term.write(s);
+        if vt == inner.active {
+            inner.render(false);
+        }
+    }
+
+    /// 处理控制台快捷键，返回按键是否已被消费
+    pub fn handle_key(&self, key: Key, modifiers: Modifiers) -> bool {
+        match key {
+            Key::Function(n) if n >= 1 && modifiers.contains(Modifiers::ALT) => {
+                self.switch_to(n as usize - 1);
+                true
+            }
+            Key::PageUp | Key::PageDown if modifiers == Modifiers::SHIFT => {
+                let mut inner = self.inner.lock();
+                let active = inner.active;
+                let screen = &mut inner.vts[active].screen;
+                let page = (screen.rows() / 2).max(1) as isize;
+                screen.scroll_view(if key == Key::PageUp { page } else { -page });
+                inner.render(false);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 将键盘输入送入前台虚拟终端，同时退出回滚查看
+    pub fn push_input(&self, bytes: &[u8]) {
+        let mut inner = self.inner.lock();
+        let active = inner.active;
+        let term = &mut inner.vts[active];
+        term.input.extend(bytes);
+        if term.screen.view_offset() != 0 {
+            term.screen.reset_view();
+            inner.render(false);
+        }
+    }
+
+    /// 从第 `vt` 个虚拟终端的输入队列取出一个字节
+    pub fn read_byte(&self, vt: usize) -> Option<u8> {
+        self.inner.lock().vts.get_mut(vt)?.input.pop_front()
+    }
+}
+
+impl Console for FrameConsole {
+    fn write_str(&self, s: &str) {
+        // 内核输出只含 '\n'，按 ONLCR 补齐回车
+        if s.contains('\n') {
+            self.write_vt(0, &s.replace('\n', "\r\n"));
+        } else {
+            self.write_vt(0, s);
+        }
+    }
+
+    fn read_char(&self) -> char {
+        loop {
+            if let Some(byte) = self.read_byte(0) {
+                let mut buf = [0u8; 4];
+                self.write_vt(0, (byte as char).encode_utf8(&mut buf)); // 回显
+                return byte as char;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    fn read_line(&self, buf: &mut String) {
+        loop {
+            let c = self.read_char();
+            if c == '\n' || c == '\r' {
+                break;
+            }
+            buf.push(c);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.lock().fb.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::framebuffer::Rgb;
+    use alloc::{sync::Arc, vec};
+    use sync::mock::init_arch_ops;
+
+    /// 记录每个像素的帧缓冲，`Arc` 让测试在控制台持有后仍可检查内容
+    #[derive(Clone)]
+    struct SharedFb(Arc<SpinLock<Vec<Rgb>>>);
+
+    const W: usize = 4 * FONT_WIDTH;
+    const H: usize = 2 * FONT_HEIGHT;
+
+    impl FrameBuffer for SharedFb {
+        fn width(&self) -> usize {
+            W
+        }
+        fn height(&self) -> usize {
+            H
+        }
+        fn write_pixel(&mut self, x: usize, y: usize, color: Rgb) {
+            if x < W && y < H {
+                self.0.lock()[y * W + x] = color;
+            }
+        }
+    }
+
+    fn console(count: usize) -> (FrameConsole, SharedFb) {
+        init_arch_ops();
+        let fb = SharedFb(Arc::new(SpinLock::new(vec![0; W * H])));
+        (FrameConsole::new(Box::new(fb.clone()), count), fb)
+    }
+
+    #[test]
+    fn test_switch_with_key_chord() {
+        let (con, fb) = console(3);
+        assert_eq!(con.count(), 3);
+        // 第二行首个像素：避开 (0, 0) 处的光标
+        let pixel = || fb.0.lock()[FONT_HEIGHT * W];
+        con.write_vt(1, "\r\n\x1b[41m \x1b[m");
+        // VT 1 不在前台，不应绘制到帧缓冲
+        assert_eq!(pixel(), 0);
+
+        assert!(!con.handle_key(Key::Function(2), Modifiers::empty()));
+        assert!(con.handle_key(Key::Function(2), Modifiers::ALT));
+        assert_eq!(con.active(), 1);
+        assert_eq!(pixel(), 0xaa0000);
+
+        assert!(con.handle_key(Key::Function(1), Modifiers::CTRL | Modifiers::ALT));
+        assert_eq!(con.active(), 0);
+        assert!(!con.switch_to(3));
+    }
+
+    #[test]
+    fn test_console_io_per_vt() {
+        let (con, _fb) = console(2);
+        con.write_str("a\nb");
+        con.write_vt(1, "\x1b[6n");
+        assert_eq!(con.read_byte(0), None);
+        let reply: Vec<u8> = core::iter::from_fn(|| con.read_byte(1)).collect();
+        assert_eq!(reply, b"\x1b[1;1R");
+
+        con.push_input(b"x");
+        assert_eq!(con.read_char(), 'x');
+    }
+
+    #[test]
+    fn test_scrollback_paging() {
+        let (con, _fb) = console(1);
+        con.write_str("1\n2\n3\n4\n");
+        assert!(con.handle_key(Key::PageUp, Modifiers::SHIFT));
+        assert_eq!(con.inner.lock().vts[0].screen.view_offset(), 1);
+        con.push_input(b"q");
+        assert_eq!(con.inner.lock().vts[0].screen.view_offset(), 0);
+    }
+}
@@ -0,0 +1,369 @@
+//! ANSI/VT102 转义序列解析器
+//!
+//! 按 DEC ANSI 解析器状态机的简化版本，将字符流拆分为可打印字符、
+//! C0 控制字符、ESC 序列与 CSI 序列；OSC/DCS 等字符串序列被完整吞掉。
+
+/// CSI 序列最多保留的参数个数，多出的参数被丢弃
+pub const MAX_PARAMS: usize = 16;
+
+/// 解析得到的 CSI 序列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Csi {
+    params: [u16; MAX_PARAMS],
+    len: usize,
+    /// 私有标记（`?`、`>`、`<`、`=`）
+    pub private: Option<u8>,
+    /// 中间字节（`0x20..=0x2f`）
+    pub intermediate: Option<u8>,
+    /// 终止字节
+    pub final_byte: u8,
+}
+
+impl Csi {
+    /// 第 `idx` 个参数，缺省或为 0 时返回 `default`
+    pub fn param(&self, idx: usize, default: u16) -> u16 {
+        match self.params[..self.len].get(idx) {
+            Some(&v) if v != 0 => v,
+            _ => default,
+        }
+    }
+
+    /// 全部参数（缺省参数记为 0）
+    pub fn params(&self) -> &[u16] {
+        &self.params[..self.len]
+    }
+}
+
+/// 解析器输出的动作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// 可打印字符
+    Print(char),
+    /// C0 控制字符
+    Execute(u8),
+    /// ESC 序列，`intermediate` 为可选中间字节
+    Esc {
+        /// 中间字节
+        intermediate: Option<u8>,
+        /// 终止字节
+        final_byte: u8,
+    },
+    /// CSI 序列
+    Csi(Csi),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    CsiEntry,
+    CsiParam,
+    CsiIntermediate,
+    CsiIgnore,
+    /// OSC/DCS/SOS/PM/APC 字符串，直到 BEL 或 ST
+    String,
+    /// 字符串中遇到 ESC，等待 `\` 构成 ST
+    StringEscape,
+}
+
+/// 转义序列解析器
+#[derive(Debug, Clone)]
+pub struct Parser {
+    state: State,
+    params: [u16; MAX_PARAMS],
+    len: usize,
+    private: Option<u8>,
+    intermediate: Option<u8>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    /// 创建处于初始状态的解析器
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            params: [0; MAX_PARAMS],
+            len: 0,
+            private: None,
+            intermediate: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.params = [0; MAX_PARAMS];
+        self.len = 0;
+        self.private = None;
+        self.intermediate = None;
+    }
+
+    fn csi(&self, final_byte: u8) -> Csi {
+        Csi {
+            params: self.params,
+            len: self.len,
+            private: self.private,
+            intermediate: self.intermediate,
+            final_byte,
+        }
+    }
+
+    fn push_digit(&mut self, digit: u8) {
+        if self.len == 0 {
+            self.len = 1;
+        }
+        if let Some(p) = self.params.get_mut(self.len - 1) {
+            *p = p.saturating_mul(10).saturating_add(digit as u16);
+        }
+    }
+
+    fn next_param(&mut self) {
+        if self.len == 0 {
+            self.len = 1;
+        }
+        if self.len < MAX_PARAMS {
+            self.len += 1;
+        }
+    }
+
+    /// 输入一个字符，返回该字符完成的动作（若有）
+    pub fn advance(&mut self, c: char) -> Option<Action> {
+        let code = c as u32;
+
+        // 任意状态下均生效的控制字符
+        match code {
+            // CAN / SUB 中止当前序列
+            0x18 | 0x1a => {
+                self.state = State::Ground;
+                return Some(Action::Execute(code as u8));
+            }
+            0x1b => {
+                self.state = match self.state {
+                    State::String | State::StringEscape => State::StringEscape,
+                    _ => {
+                        self.clear();
+                        State::Escape
+                    }
+                };
+                return None;
+            }
+            _ => {}
+        }
+
+        match self.state {
+            State::String => {
+                if code == 0x07 {
+                    self.state = State::Ground;
+                }
+                return None;
+            }
+            State::StringEscape => {
+                // ESC \ (ST) 结束字符串，其他字符视为新 ESC 序列的开始
+                if c == '\\' {
+                    self.state = State::Ground;
+                    return None;
+                }
+                self.clear();
+                self.state = State::Escape;
+            }
+            _ => {}
+        }
+
+        // 序列内部的 C0 控制字符立即执行，不打断序列
+        if code < 0x20 {
+            return Some(Action::Execute(code as u8));
+        }
+        if code == 0x7f {
+            return None;
+        }
+
+        let byte = if code < 0x80 { code as u8 } else { 0 };
+        match self.state {
+            State::Ground => Some(Action::Print(c)),
+            State::Escape => match byte {
+                b'[' => {
+                    self.state = State::CsiEntry;
+                    None
+                }
+                b']' | b'P' | b'X' | b'^' | b'_' => {
+                    self.state = State::String;
+                    None
+                }
+                0x20..=0x2f => {
+                    self.intermediate = Some(byte);
+                    self.state = State::EscapeIntermediate;
+                    None
+                }
+                0x30..=0x7e => {
+                    self.state = State::Ground;
+                    Some(Action::Esc {
+                        intermediate: None,
+                        final_byte: byte,
+                    })
+                }
+                _ => {
+                    self.state = State::Ground;
+                    None
+                }
+            },
+            State::EscapeIntermediate => match byte {
+                0x20..=0x2f => None,
+                0x30..=0x7e => {
+                    self.state = State::Ground;
+                    Some(Action::Esc {
+                        intermediate: self.intermediate,
+                        final_byte: byte,
+                    })
+                }
+                _ => {
+                    self.state = State::Ground;
+                    None
+                }
+            },
+            State::CsiEntry | State::CsiParam => match byte {
+                b'0'..=b'9' => {
+                    self.state = State::CsiParam;
+                    self.push_digit(byte - b'0');
+                    None
+                }
+                b';' | b':' => {
+                    self.state = State::CsiParam;
+                    self.next_param();
+                    None
+                }
+                b'<'..=b'?' if self.state == State::CsiEntry => {
+                    self.private = Some(byte);
+                    self.state = State::CsiParam;
+                    None
+                }
+                b'<'..=b'?' => {
+                    self.state = State::CsiIgnore;
+                    None
+                }
+                0x20..=0x2f => {
+                    self.intermediate = Some(byte);
+                    self.state = State::CsiIntermediate;
+                    None
+                }
+                0x40..=0x7e => {
+                    self.state = State::Ground;
+                    Some(Action::Csi(self.csi(byte)))
+                }
+                _ => {
+                    self.state = State::CsiIgnore;
+                    None
+                }
+            },
+            State::CsiIntermediate => match byte {
+                0x20..=0x2f => None,
+                0x40..=0x7e => {
+                    self.state = State::Ground;
+                    Some(Action::Csi(self.csi(byte)))
+                }
+                _ => {
+                    self.state = State::CsiIgnore;
+                    None
+                }
+            },
+            State::CsiIgnore => {
+                if (0x40..=0x7e).contains(&byte) {
+                    self.state = State::Ground;
+                }
+                None
+            }
+            State::String | State::StringEscape => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn parse(s: &str) -> Vec<Action> {
+        let mut p = Parser::new();
+        s.chars().filter_map(|c| p.advance(c)).collect()
+    }
+
+    #[test]
+    fn test_print_and_execute() {
+        assert_eq!(
+            parse("a\r\n"),
+            [
+                Action::Print('a'),
+                Action::Execute(b'\r'),
+                Action::Execute(b'\n')
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csi_params() {
+        let actions = parse("\x1b[12;;5H");
+        let Action::Csi(csi) = &actions[0] else {
+            panic!("expected CSI, got {:?}", actions);
+        };
+        assert_eq!(csi.final_byte, b'H');
+        assert_eq!(csi.params(), &[12, 0, 5]);
+        assert_eq!(csi.param(1, 1), 1);
+        assert_eq!(csi.param(2, 1), 5);
+        assert_eq!(csi.param(3, 7), 7);
+
+        let actions = parse("\x1b[?25l");
+        let Action::Csi(csi) = &actions[0] else {
+            panic!("expected CSI, got {:?}", actions);
+        };
+        assert_eq!(csi.private, Some(b'?'));
+        assert_eq!(csi.final_byte, b'l');
+    }
+
+    #[test]
+    fn test_esc_and_strings() {
+        assert_eq!(
+            parse("\x1b(0\x1b7"),
+            [
+                Action::Esc {
+                    intermediate: Some(b'('),
+                    final_byte: b'0'
+                },
+                Action::Esc {
+                    intermediate: None,
+                    final_byte: b'7'
+                },
+            ]
+        );
+        // OSC 以 BEL 或 ST 结束，内容被丢弃
+        assert_eq!(parse("\x1b]0;title\x07x"), [Action::Print('x')]);
+        assert_eq!(parse("\x1b]0;title\x1b\\y"), [Action::Print('y')]);
+    }
+
+    #[test]
+    fn test_control_inside_sequence_and_cancel() {
+        assert_eq!(
+            parse("\x1b[1\n2A"),
+            [
+                Action::Execute(b'\n'),
+                Action::Csi(Csi {
+                    params: {
+                        let mut p = [0; MAX_PARAMS];
+                        p[0] = 12;
+                        p
+                    },
+                    len: 1,
+                    private: None,
+                    intermediate: None,
+                    final_byte: b'A',
+                })
+            ]
+        );
+        assert_eq!(
+            parse("\x1b[12\x18A"),
+            [Action::Execute(0x18), Action::Print('A')]
+        );
+    }
+}
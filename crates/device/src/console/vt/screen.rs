@@ -0,0 +1,1044 @@
+//! 虚拟终端屏幕模型
+//!
+//! [`Screen`] 维护字符网格、光标、滚动区域与回滚缓冲区，
+//! 按 VT102 语义执行 [`Action`]，并将脏行渲染到 [`FrameBuffer`]。
+
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use bitflags::bitflags;
+
+use super::parser::{Action, Csi};
+use crate::console::{
+    font::{FONT_HEIGHT, FONT_WIDTH, glyph},
+    framebuffer::{FrameBuffer, Rgb},
+};
+
+/// 16 色调色板（VGA 配色）
+const PALETTE: [Rgb; 16] = [
+    0x000000, 0xaa0000, 0x00aa00, 0xaa5500, 0x0000aa, 0xaa00aa, 0x00aaaa, 0xaaaaaa, 0x555555,
+    0xff5555, 0x55ff55, 0xffff55, 0x5555ff, 0xff55ff, 0x55ffff, 0xffffff,
+];
+
+/// 默认前景色在调色板中的下标
+const DEFAULT_FG: u8 = 7;
+/// 默认背景色在调色板中的下标
+const DEFAULT_BG: u8 = 0;
+/// 制表位间隔
+const TAB_WIDTH: usize = 8;
+
+/// 字符颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// 终端默认颜色
+    Default,
+    /// 256 色调色板下标
+    Indexed(u8),
+    /// 24 位真彩色
+    Rgb(Rgb),
+}
+
+impl Color {
+    fn to_rgb(self, default: u8, bold: bool) -> Rgb {
+        match self {
+            Color::Default => PALETTE[default as usize],
+            // 粗体使低 8 色变为高亮色
+            Color::Indexed(i) if i < 8 && bold => PALETTE[i as usize + 8],
+            Color::Indexed(i) if i < 16 => PALETTE[i as usize],
+            Color::Indexed(i) if i < 232 => {
+                let i = i - 16;
+                let level = |v: u8| if v == 0 { 0 } else { 55 + 40 * v as u32 };
+                (level(i / 36) << 16) | (level((i / 6) % 6) << 8) | level(i % 6)
+            }
+            Color::Indexed(i) => {
+                let v = 8 + 10 * (i - 232) as u32;
+                (v << 16) | (v << 8) | v
+            }
+            Color::Rgb(rgb) => rgb,
+        }
+    }
+}
+
+bitflags! {
+    /// 字符显示属性
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CellFlags: u8 {
+        /// 粗体
+        const BOLD = 1 << 0;
+        /// 暗淡
+        const DIM = 1 << 1;
+        /// 下划线
+        const UNDERLINE = 1 << 2;
+        /// 闪烁
+        const BLINK = 1 << 3;
+        /// 反显
+        const REVERSE = 1 << 4;
+        /// 隐藏
+        const HIDDEN = 1 << 5;
+    }
+}
+
+/// 图形再现属性（SGR）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attr {
+    /// 前景色
+    pub fg: Color,
+    /// 背景色
+    pub bg: Color,
+    /// 显示属性
+    pub flags: CellFlags,
+}
+
+impl Attr {
+    /// 默认属性
+    pub const DEFAULT: Attr = Attr {
+        fg: Color::Default,
+        bg: Color::Default,
+        flags: CellFlags::empty(),
+    };
+}
+
+/// 屏幕上的一个字符单元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    /// 字符
+    pub ch: char,
+    /// 属性
+    pub attr: Attr,
+}
+
+impl Cell {
+    /// 以 `attr` 的颜色擦除得到的空白单元
+    fn blank(attr: Attr) -> Self {
+        Cell {
+            ch: ' ',
+            attr: Attr {
+                flags: CellFlags::empty(),
+                ..attr
+            },
+        }
+    }
+}
+
+/// G0/G1 字符集
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Ascii,
+    /// DEC 特殊图形字符集（制表符），以 ASCII 近似显示
+    DecGraphics,
+}
+
+impl Charset {
+    fn map(self, c: char) -> char {
+        if self == Charset::Ascii {
+            return c;
+        }
+        match c {
+            'j' | 'k' | 'l' | 'm' | 'n' | 't' | 'u' | 'v' | 'w' => '+',
+            'o' | 'p' | 'q' | 'r' | 's' => '-',
+            'x' => '|',
+            '`' | '~' => '*',
+            'a' => '#',
+            'f' => '\'',
+            'g' => '#',
+            'y' => '<',
+            'z' => '>',
+            '{' => 'n',
+            '|' => '!',
+            '}' => 'L',
+            _ => c,
+        }
+    }
+}
+
+/// DECSC 保存的光标状态
+#[derive(Debug, Clone, Copy)]
+struct SavedCursor {
+    x: usize,
+    y: usize,
+    attr: Attr,
+    origin: bool,
+    autowrap: bool,
+    charsets: [Charset; 2],
+    gl: usize,
+}
+
+impl SavedCursor {
+    const fn initial() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            attr: Attr::DEFAULT,
+            origin: false,
+            autowrap: true,
+            charsets: [Charset::Ascii; 2],
+            gl: 0,
+        }
+    }
+}
+
+/// 虚拟终端屏幕
+pub struct Screen {
+    cols: usize,
+    rows: usize,
+    /// 当前屏幕内容，按行优先存储
+    grid: Vec<Cell>,
+    /// 滚出屏幕顶部的行，最旧的在前
+    scrollback: VecDeque<Vec<Cell>>,
+    scrollback_limit: usize,
+    /// 回滚查看的行数，0 表示显示当前屏幕
+    view: usize,
+
+    x: usize,
+    y: usize,
+    /// 在最后一列写入字符后延迟换行（DECAWM 语义）
+    wrap_pending: bool,
+    attr: Attr,
+    /// 滚动区域 `[top, bottom)`
+    top: usize,
+    bottom: usize,
+    tabs: Vec<bool>,
+
+    autowrap: bool,
+    origin: bool,
+    insert: bool,
+    newline_mode: bool,
+    cursor_visible: bool,
+    charsets: [Charset; 2],
+    gl: usize,
+    saved: SavedCursor,
+
+    dirty: Vec<bool>,
+    /// 上次渲染时绘制光标的位置
+    drawn_cursor: Option<(usize, usize)>,
+    /// 待回送给输入端的应答（DSR、DA 等）
+    responses: Vec<u8>,
+}
+
+impl Screen {
+    /// 创建 `cols` x `rows` 的屏幕，回滚缓冲区最多保留 `scrollback_lines` 行
+    pub fn new(cols: usize, rows: usize, scrollback_lines: usize) -> Self {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        Self {
+            cols,
+            rows,
+            grid: vec![Cell::blank(Attr::DEFAULT); cols * rows],
+            scrollback: VecDeque::new(),
+            scrollback_limit: scrollback_lines,
+            view: 0,
+            x: 0,
+            y: 0,
+            wrap_pending: false,
+            attr: Attr::DEFAULT,
+            top: 0,
+            bottom: rows,
+            tabs: (0..cols).map(|i| i % TAB_WIDTH == 0 && i != 0).collect(),
+            autowrap: true,
+            origin: false,
+            insert: false,
+            newline_mode: false,
+            cursor_visible: true,
+            charsets: [Charset::Ascii; 2],
+            gl: 0,
+            saved: SavedCursor::initial(),
+            dirty: vec![true; rows],
+            drawn_cursor: None,
+            responses: Vec::new(),
+        }
+    }
+
+    /// 列数
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// 行数
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// 光标位置 `(列, 行)`，从 0 开始
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    /// 光标是否可见（DECTCEM）
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// 当前屏幕上 `(x, y)` 处的单元
+    pub fn cell(&self, x: usize, y: usize) -> &Cell {
+        &self.grid[y * self.cols + x]
+    }
+
+    /// 回滚缓冲区中的行数
+    pub fn scrollback_len(&self) -> usize {
+        self.scrollback.len()
+    }
+
+    /// 当前回滚查看的行数
+    pub fn view_offset(&self) -> usize {
+        self.view
+    }
+
+    /// 取出待回送给输入端的应答字节
+    pub fn take_responses(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.responses)
+    }
+
+    /// 回滚查看：正数向历史方向滚动，负数向当前屏幕方向滚动
+    pub fn scroll_view(&mut self, delta: isize) {
+        let view = (self.view as isize + delta).clamp(0, self.scrollback.len() as isize) as usize;
+        if view != self.view {
+            self.view = view;
+            self.mark_all_dirty();
+        }
+    }
+
+    /// 退出回滚查看，回到当前屏幕
+    pub fn reset_view(&mut self) {
+        self.scroll_view(-(self.view as isize));
+    }
+
+    /// 标记整屏需要重绘（如切换虚拟终端后）
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.fill(true);
+    }
+
+    /// 执行解析器产生的动作
+    pub fn apply(&mut self, action: Action) {
+        match action {
+            Action::Print(c) => self.print(c),
+            Action::Execute(b) => self.execute(b),
+            Action::Esc {
+                intermediate,
+                final_byte,
+            } => self.esc_dispatch(intermediate, final_byte),
+            Action::Csi(csi) => self.csi_dispatch(&csi),
+        }
+    }
+
+    // ---------------------------------------------------------------
+    // 基本操作
+    // ---------------------------------------------------------------
+
+    fn row_mut(&mut self, y: usize) -> &mut [Cell] {
+        self.dirty[y] = true;
+        &mut self.grid[y * self.cols..(y + 1) * self.cols]
+    }
+
+    fn blank(&self) -> Cell {
+        Cell::blank(self.attr)
+    }
+
+    fn print(&mut self, c: char) {
+        let c = self.charsets[self.gl].map(c);
+        if self.wrap_pending {
+            self.x = 0;
+            self.linefeed();
+        }
+        if self.insert {
+            self.insert_chars(1);
+        }
+        let (x, attr) = (self.x, self.attr);
+        self.row_mut(self.y)[x] = Cell { ch: c, attr };
+        if self.x + 1 < self.cols {
+            self.x += 1;
+        } else if self.autowrap {
+            self.wrap_pending = true;
+        }
+    }
+
+    fn execute(&mut self, b: u8) {
+        match b {
+            // BS
+            0x08 => {
+                self.x = self.x.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            // HT
+            0x09 => self.tab_forward(1),
+            // LF / VT / FF
+            0x0a..=0x0c => {
+                if self.newline_mode {
+                    self.x = 0;
+                }
+                self.linefeed();
+            }
+            // CR
+            0x0d => {
+                self.x = 0;
+                self.wrap_pending = false;
+            }
+            // SO / SI
+            0x0e => self.gl = 1,
+            0x0f => self.gl = 0,
+            _ => {}
+        }
+    }
+
+    fn linefeed(&mut self) {
+        self.wrap_pending = false;
+        if self.y + 1 == self.bottom {
+            self.scroll_up(self.top, self.bottom, 1);
+        } else if self.y + 1 < self.rows {
+            self.y += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.y == self.top {
+            self.scroll_down(self.top, self.bottom, 1);
+        } else if self.y > 0 {
+            self.y -= 1;
+        }
+    }
+
+    /// 将 `[start, end)` 行上滚 `n` 行，底部补空行
+    ///
+    /// 整屏滚动时移出的行进入回滚缓冲区。
+    fn scroll_up(&mut self, start: usize, end: usize, n: usize) {
+        let n = n.min(end - start);
+        if n == 0 {
+            return;
+        }
+        if start == 0 && end == self.rows && self.scrollback_limit > 0 {
+            for y in 0..n {
+                if self.scrollback.len() == self.scrollback_limit {
+                    self.scrollback.pop_front();
+                } else if self.view > 0 {
+                    // 保持回滚查看的内容不随新输出移动
+                    self.view += 1;
+                }
+                let line = self.grid[y * self.cols..(y + 1) * self.cols].to_vec();
+                self.scrollback.push_back(line);
+            }
+        }
+        let cols = self.cols;
+        self.grid
+            .copy_within((start + n) * cols..end * cols, start * cols);
+        let blank = self.blank();
+        self.grid[(end - n) * cols..end * cols].fill(blank);
+        self.dirty[start..end].fill(true);
+    }
+
+    /// 将 `[start, end)` 行下滚 `n` 行，顶部补空行
+    fn scroll_down(&mut self, start: usize, end: usize, n: usize) {
+        let n = n.min(end - start);
+        if n == 0 {
+            return;
+        }
+        let cols = self.cols;
+        self.grid
+            .copy_within(start * cols..(end - n) * cols, (start + n) * cols);
+        let blank = self.blank();
+        self.grid[start * cols..(start + n) * cols].fill(blank);
+        self.dirty[start..end].fill(true);
+    }
+
+    fn insert_chars(&mut self, n: usize) {
+        let (x, blank) = (self.x, self.blank());
+        let row = self.row_mut(self.y);
+        let n = n.min(row.len() - x);
+        row.copy_within(x..row.len() - n, x + n);
+        row[x..x + n].fill(blank);
+    }
+
+    fn delete_chars(&mut self, n: usize) {
+        let (x, blank) = (self.x, self.blank());
+        let row = self.row_mut(self.y);
+        let n = n.min(row.len() - x);
+        row.copy_within(x + n.., x);
+        let len = row.len();
+        row[len - n..].fill(blank);
+    }
+
+    fn erase_chars(&mut self, n: usize) {
+        let (x, blank) = (self.x, self.blank());
+        let row = self.row_mut(self.y);
+        let end = (x + n).min(row.len());
+        row[x..end].fill(blank);
+    }
+
+    fn erase_in_display(&mut self, mode: u16) {
+        let blank = self.blank();
+        match mode {
+            0 => {
+                self.erase_in_line(0);
+                for y in self.y + 1..self.rows {
+                    self.row_mut(y).fill(blank);
+                }
+            }
+            1 => {
+                for y in 0..self.y {
+                    self.row_mut(y).fill(blank);
+                }
+                self.erase_in_line(1);
+            }
+            2 | 3 => {
+                for y in 0..self.rows {
+                    self.row_mut(y).fill(blank);
+                }
+                if mode == 3 {
+                    self.scrollback.clear();
+                    self.reset_view();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn erase_in_line(&mut self, mode: u16) {
+        let (x, blank) = (self.x, self.blank());
+        let row = self.row_mut(self.y);
+        match mode {
+            0 => row[x..].fill(blank),
+            1 => row[..=x].fill(blank),
+            2 => row.fill(blank),
+            _ => {}
+        }
+    }
+
+    fn tab_forward(&mut self, n: usize) {
+        for _ in 0..n {
+            self.x = (self.x + 1..self.cols)
+                .find(|&i| self.tabs[i])
+                .unwrap_or(self.cols - 1);
+        }
+        self.wrap_pending = false;
+    }
+
+    fn tab_backward(&mut self, n: usize) {
+        for _ in 0..n {
+            self.x = (0..self.x).rev().find(|&i| self.tabs[i]).unwrap_or(0);
+        }
+        self.wrap_pending = false;
+    }
+
+    /// 移动光标到 `(x, y)`，原点模式下 `y` 相对滚动区域
+    fn goto(&mut self, x: usize, y: usize) {
+        let (min_y, max_y) = if self.origin {
+            (self.top, self.bottom - 1)
+        } else {
+            (0, self.rows - 1)
+        };
+        self.x = x.min(self.cols - 1);
+        self.y = (y + min_y).min(max_y);
+        self.wrap_pending = false;
+    }
+
+    /// 光标所在行相对原点的偏移
+    fn origin_row(&self) -> usize {
+        if self.origin {
+            self.y.saturating_sub(self.top)
+        } else {
+            self.y
+        }
+    }
+
+    fn cursor_up(&mut self, n: usize) {
+        let min = if self.y >= self.top { self.top } else { 0 };
+        self.y = self.y.saturating_sub(n).max(min);
+        self.wrap_pending = false;
+    }
+
+    fn cursor_down(&mut self, n: usize) {
+        let max = if self.y < self.bottom {
+            self.bottom - 1
+        } else {
+            self.rows - 1
+        };
+        self.y = (self.y + n).min(max);
+        self.wrap_pending = false;
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved = SavedCursor {
+            x: self.x,
+            y: self.y,
+            attr: self.attr,
+            origin: self.origin,
+            autowrap: self.autowrap,
+            charsets: self.charsets,
+            gl: self.gl,
+        };
+    }
+
+    fn restore_cursor(&mut self) {
+        let s = self.saved;
+        self.x = s.x.min(self.cols - 1);
+        self.y = s.y.min(self.rows - 1);
+        self.attr = s.attr;
+        self.origin = s.origin;
+        self.autowrap = s.autowrap;
+        self.charsets = s.charsets;
+        self.gl = s.gl;
+        self.wrap_pending = false;
+    }
+
+    /// RIS：恢复初始状态并清空屏幕与回滚缓冲区
+    fn reset(&mut self) {
+        *self = Screen::new(self.cols, self.rows, self.scrollback_limit);
+    }
+
+    // ---------------------------------------------------------------
+    // 序列分发
+    // ---------------------------------------------------------------
+
+    fn esc_dispatch(&mut self, intermediate: Option<u8>, final_byte: u8) {
+        match (intermediate, final_byte) {
+            (None, b'7') => self.save_cursor(),
+            (None, b'8') => self.restore_cursor(),
+            (None, b'D') => self.linefeed(),
+            (None, b'E') => {
+                self.x = 0;
+                self.linefeed();
+            }
+            (None, b'H') => self.tabs[self.x] = true,
+            (None, b'M') => self.reverse_index(),
+            (None, b'Z') => self.responses.extend_from_slice(b"\x1b[?6c"),
+            (None, b'c') => self.reset(),
+            // DECALN：以 'E' 填满屏幕
+            (Some(b'#'), b'8') => {
+                for y in 0..self.rows {
+                    self.row_mut(y).fill(Cell {
+                        ch: 'E',
+                        attr: Attr::DEFAULT,
+                    });
+                }
+                self.top = 0;
+                self.bottom = self.rows;
+                self.goto(0, 0);
+            }
+            (Some(g @ (b'(' | b')')), set) => {
+                let idx = (g - b'(') as usize;
+                self.charsets[idx] = if set == b'0' {
+                    Charset::DecGraphics
+                } else {
+                    Charset::Ascii
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, csi: &Csi) {
+        let n = csi.param(0, 1) as usize;
+        if csi.intermediate.is_some() {
+            return;
+        }
+        match (csi.private, csi.final_byte) {
+            (None, b'@') => self.insert_chars(n),
+            (None, b'A') => self.cursor_up(n),
+            (None, b'B') | (None, b'e') => self.cursor_down(n),
+            (None, b'C') | (None, b'a') => {
+                self.x = (self.x + n).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            (None, b'D') => {
+                self.x = self.x.saturating_sub(n);
+                self.wrap_pending = false;
+            }
+            (None, b'E') => {
+                self.cursor_down(n);
+                self.x = 0;
+            }
+            (None, b'F') => {
+                self.cursor_up(n);
+                self.x = 0;
+            }
+            (None, b'G') | (None, b'`') => {
+                self.x = (n - 1).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            (None, b'H') | (None, b'f') => {
+                let row = csi.param(0, 1) as usize;
+                let col = csi.param(1, 1) as usize;
+                self.goto(col - 1, row - 1);
+            }
+            (None, b'I') => self.tab_forward(n),
+            (None, b'J') => self.erase_in_display(csi.param(0, 0)),
+            (None, b'K') => self.erase_in_line(csi.param(0, 0)),
+            (None, b'L') if (self.top..self.bottom).contains(&self.y) => {
+                self.scroll_down(self.y, self.bottom, n);
+                self.x = 0;
+                self.wrap_pending = false;
+            }
+            (None, b'M') if (self.top..self.bottom).contains(&self.y) => {
+                self.scroll_up_region_no_history(self.y, n);
+                self.x = 0;
+                self.wrap_pending = false;
+            }
+            (None, b'P') => self.delete_chars(n),
+            (None, b'S') => self.scroll_up(self.top, self.bottom, n),
+            (None, b'T') => self.scroll_down(self.top, self.bottom, n),
+            (None, b'X') => self.erase_chars(n),
+            (None, b'Z') => self.tab_backward(n),
+            (None, b'c') => self.responses.extend_from_slice(b"\x1b[?6c"),
+            (None, b'd') => {
+                let x = self.x;
+                self.goto(x, n - 1);
+            }
+            (None, b'g') => match csi.param(0, 0) {
+                0 => self.tabs[self.x] = false,
+                3 => self.tabs.fill(false),
+                _ => {}
+            },
+            (None, b'h') | (None, b'l') => {
+                let on = csi.final_byte == b'h';
+                for &mode in csi.params() {
+                    match mode {
+                        4 => self.insert = on,
+                        20 => self.newline_mode = on,
+                        _ => {}
+                    }
+                }
+            }
+            (Some(b'?'), b'h') | (Some(b'?'), b'l') => {
+                let on = csi.final_byte == b'h';
+                for &mode in csi.params() {
+                    match mode {
+                        6 => {
+                            self.origin = on;
+                            self.goto(0, 0);
+                        }
+                        7 => self.autowrap = on,
+                        25 => self.cursor_visible = on,
+                        _ => {}
+                    }
+                }
+            }
+            (None, b'm') => self.sgr(csi.params()),
+            (None, b'n') => match csi.param(0, 0) {
+                5 => self.responses.extend_from_slice(b"\x1b[0n"),
+                6 => {
+                    let reply = alloc::format!("\x1b[{};{}R", self.origin_row() + 1, self.x + 1);
+                    self.responses.extend_from_slice(reply.as_bytes());
+                }
+                _ => {}
+            },
+            (None, b'r') => {
+                let top = csi.param(0, 1) as usize - 1;
+                let bottom = (csi.param(1, self.rows as u16) as usize).min(self.rows);
+                if top + 1 < bottom {
+                    self.top = top;
+                    self.bottom = bottom;
+                    self.goto(0, 0);
+                }
+            }
+            (None, b's') => self.save_cursor(),
+            (None, b'u') => self.restore_cursor(),
+            _ => {}
+        }
+    }
+
+    /// DL：删除行时移出的行不进入回滚缓冲区
+    fn scroll_up_region_no_history(&mut self, start: usize, n: usize) {
+        let limit = core::mem::replace(&mut self.scrollback_limit, 0);
+        self.scroll_up(start, self.bottom, n);
+        self.scrollback_limit = limit;
+    }
+
+    fn sgr(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            self.attr = Attr::DEFAULT;
+            return;
+        }
+        let mut i = 0;
+        while i < params.len() {
+            let p = params[i];
+            match p {
+                0 => self.attr = Attr::DEFAULT,
+                1 => self.attr.flags |= CellFlags::BOLD,
+                2 => self.attr.flags |= CellFlags::DIM,
+                4 => self.attr.flags |= CellFlags::UNDERLINE,
+                5 => self.attr.flags |= CellFlags::BLINK,
+                7 => self.attr.flags |= CellFlags::REVERSE,
+                8 => self.attr.flags |= CellFlags::HIDDEN,
+                22 => self.attr.flags -= CellFlags::BOLD | CellFlags::DIM,
+                24 => self.attr.flags -= CellFlags::UNDERLINE,
+                25 => self.attr.flags -= CellFlags::BLINK,
+                27 => self.attr.flags -= CellFlags::REVERSE,
+                28 => self.attr.flags -= CellFlags::HIDDEN,
+                30..=37 => self.attr.fg = Color::Indexed((p - 30) as u8),
+                39 => self.attr.fg = Color::Default,
+                40..=47 => self.attr.bg = Color::Indexed((p - 40) as u8),
+                49 => self.attr.bg = Color::Default,
+                90..=97 => self.attr.fg = Color::Indexed((p - 90 + 8) as u8),
+                100..=107 => self.attr.bg = Color::Indexed((p - 100 + 8) as u8),
+                38 | 48 => {
+                    let color = match params.get(i + 1) {
+                        Some(5) => {
+                            let idx = params.get(i + 2).copied().unwrap_or(0);
+                            i += 2;
+                            Some(Color::Indexed(idx.min(255) as u8))
+                        }
+                        Some(2) => {
+                            let c = |k: usize| params.get(i + k).copied().unwrap_or(0).min(255);
+                            let rgb = ((c(2) as u32) << 16) | ((c(3) as u32) << 8) | c(4) as u32;
+                            i += 4;
+                            Some(Color::Rgb(rgb))
+                        }
+                        _ => None,
+                    };
+                    if let Some(color) = color {
+                        if p == 38 {
+                            self.attr.fg = color;
+                        } else {
+                            self.attr.bg = color;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    // ---------------------------------------------------------------
+    // 渲染
+    // ---------------------------------------------------------------
+
+    /// 屏幕第 `y` 行当前应显示的内容（考虑回滚查看）
+    fn visible_row(&self, y: usize) -> &[Cell] {
+        let sb = self.scrollback.len();
+        let line = sb - self.view + y;
+        if line < sb {
+            &self.scrollback[line]
+        } else {
+            let y = line - sb;
+            &self.grid[y * self.cols..(y + 1) * self.cols]
+        }
+    }
+
+    /// 将需要更新的行绘制到帧缓冲；`full` 为真时重绘整屏
+    pub fn render(&mut self, fb: &mut dyn FrameBuffer, full: bool) {
+        let cursor = (self.cursor_visible && self.view == 0).then_some((self.x, self.y));
+        if cursor != self.drawn_cursor {
+            if let Some((_, y)) = self.drawn_cursor {
+                self.dirty[y] = true;
+            }
+            if let Some((_, y)) = cursor {
+                self.dirty[y] = true;
+            }
+        }
+        let rows = self.rows.min(fb.height() / FONT_HEIGHT);
+        let cols = self.cols.min(fb.width() / FONT_WIDTH);
+        for y in 0..rows {
+            if !full && !self.dirty[y] {
+                continue;
+            }
+            for x in 0..cols {
+                let cell = self.visible_row(y)[x];
+                draw_cell(fb, x, y, &cell, cursor == Some((x, y)));
+            }
+        }
+        self.dirty.fill(false);
+        self.drawn_cursor = cursor;
+        fb.flush();
+    }
+}
+
+fn draw_cell(fb: &mut dyn FrameBuffer, x: usize, y: usize, cell: &Cell, cursor: bool) {
+    let flags = cell.attr.flags;
+    let mut fg = cell
+        .attr
+        .fg
+        .to_rgb(DEFAULT_FG, flags.contains(CellFlags::BOLD));
+    let mut bg = cell.attr.bg.to_rgb(DEFAULT_BG, false);
+    if flags.contains(CellFlags::REVERSE) != cursor {
+        core::mem::swap(&mut fg, &mut bg);
+    }
+    if flags.contains(CellFlags::HIDDEN) {
+        fg = bg;
+    }
+    let bitmap = glyph(cell.ch);
+    let (px, py) = (x * FONT_WIDTH, y * FONT_HEIGHT);
+    for (dy, &bits) in bitmap.iter().enumerate() {
+        let bits = if flags.contains(CellFlags::UNDERLINE) && dy == FONT_HEIGHT - 1 {
+            0xff
+        } else {
+            bits
+        };
+        for dx in 0..FONT_WIDTH {
+            let color = if bits & (0x80 >> dx) != 0 { fg } else { bg };
+            fb.write_pixel(px + dx, py + dy, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::vt::parser::Parser;
+    use alloc::string::String;
+
+    fn feed(screen: &mut Screen, s: &str) {
+        let mut parser = Parser::new();
+        for c in s.chars() {
+            if let Some(action) = parser.advance(c) {
+                screen.apply(action);
+            }
+        }
+    }
+
+    fn row(screen: &Screen, y: usize) -> String {
+        (0..screen.cols())
+            .map(|x| screen.cell(x, y).ch)
+            .collect::<String>()
+            .trim_end()
+            .into()
+    }
+
+    #[test]
+    fn test_print_wrap_and_scrollback() {
+        let mut s = Screen::new(4, 2, 8);
+        feed(&mut s, "abcd");
+        // 最后一列写入后延迟换行
+        assert_eq!(s.cursor(), (3, 0));
+        feed(&mut s, "ef\r\ngh");
+        assert_eq!(row(&s, 0), "ef");
+        assert_eq!(row(&s, 1), "gh");
+        assert_eq!(s.scrollback_len(), 1);
+
+        s.scroll_view(5);
+        assert_eq!(s.view_offset(), 1);
+        assert_eq!(s.visible_row(0)[0].ch, 'a');
+        s.reset_view();
+        assert_eq!(s.view_offset(), 0);
+    }
+
+    #[test]
+    fn test_cursor_movement_and_erase() {
+        let mut s = Screen::new(10, 5, 0);
+        feed(&mut s, "\x1b[3;4Hx\x1b[2Ay\x1b[10Cz");
+        assert_eq!(s.cell(3, 2).ch, 'x');
+        assert_eq!(s.cell(4, 0).ch, 'y');
+        assert_eq!(s.cell(9, 0).ch, 'z');
+
+        feed(&mut s, "\x1b[1;1H0123456789\x1b[1;5H\x1b[K");
+        assert_eq!(row(&s, 0), "0123");
+        feed(&mut s, "\x1b[1;2H\x1b[1K");
+        assert_eq!(row(&s, 0), "  23");
+        feed(&mut s, "\x1b[2J");
+        assert!((0..5).all(|y| row(&s, y).is_empty()));
+    }
+
+    #[test]
+    fn test_insert_delete() {
+        let mut s = Screen::new(6, 3, 0);
+        feed(&mut s, "abcdef\x1b[1;2H\x1b[2@");
+        assert_eq!(row(&s, 0), "a  bcd");
+        feed(&mut s, "\x1b[3P");
+        assert_eq!(row(&s, 0), "acd");
+        feed(&mut s, "\x1b[2X");
+        assert_eq!(row(&s, 0), "a");
+
+        feed(&mut s, "\x1b[1;1H\x1b[2;1H2\x1b[3;1H3\x1b[2;1H\x1b[L");
+        assert_eq!(row(&s, 1), "");
+        assert_eq!(row(&s, 2), "2");
+        feed(&mut s, "\x1b[M");
+        assert_eq!(row(&s, 1), "2");
+    }
+
+    #[test]
+    fn test_scroll_region() {
+        let mut s = Screen::new(3, 4, 8);
+        feed(&mut s, "1\r\n2\r\n3\r\n4\x1b[2;3r");
+        // DECSTBM 后光标回到原点
+        assert_eq!(s.cursor(), (0, 0));
+        feed(&mut s, "\x1b[3;1H\nX");
+        assert_eq!(row(&s, 0), "1");
+        assert_eq!(row(&s, 1), "3");
+        assert_eq!(row(&s, 2), "X");
+        assert_eq!(row(&s, 3), "4");
+        // 区域内滚动不进入回滚缓冲区
+        assert_eq!(s.scrollback_len(), 0);
+
+        feed(&mut s, "\x1b[2;1H\x1bM");
+        assert_eq!(row(&s, 1), "");
+        assert_eq!(row(&s, 2), "3");
+    }
+
+    #[test]
+    fn test_sgr_and_save_restore() {
+        let mut s = Screen::new(8, 2, 0);
+        feed(&mut s, "\x1b[1;31;44ma\x1b[38;5;200;48;2;1;2;3mb\x1b[0mc");
+        let a = s.cell(0, 0).attr;
+        assert_eq!(a.fg, Color::Indexed(1));
+        assert_eq!(a.bg, Color::Indexed(4));
+        assert!(a.flags.contains(CellFlags::BOLD));
+        let b = s.cell(1, 0).attr;
+        assert_eq!(b.fg, Color::Indexed(200));
+        assert_eq!(b.bg, Color::Rgb(0x010203));
+        assert_eq!(s.cell(2, 0).attr, Attr::DEFAULT);
+
+        feed(&mut s, "\x1b[2;3H\x1b[7m\x1b7\x1b[0m\x1b[1;1H\x1b8d");
+        assert_eq!(s.cell(2, 1).ch, 'd');
+        assert!(s.cell(2, 1).attr.flags.contains(CellFlags::REVERSE));
+    }
+
+    #[test]
+    fn test_tabs_modes_and_reports() {
+        let mut s = Screen::new(20, 3, 0);
+        feed(&mut s, "\tx\x1b[3g\x1b[1;4H\x1bH\x1b[1;1H\ty");
+        assert_eq!(s.cell(8, 0).ch, 'x');
+        assert_eq!(s.cell(3, 0).ch, 'y');
+
+        feed(&mut s, "\x1b[?25l\x1b[2;5H\x1b[6n\x1b[c");
+        assert!(!s.cursor_visible());
+        assert_eq!(s.take_responses(), b"\x1b[2;5R\x1b[?6c");
+
+        feed(&mut s, "\x1b[?7l\x1b[3;19Habc");
+        assert_eq!(s.cell(19, 2).ch, 'c');
+        assert_eq!(s.cursor(), (19, 2));
+
+        feed(&mut s, "\x1b(0lqk\x1b(B");
+        assert_eq!(s.cell(19, 2).ch, '+');
+    }
+
+    struct MemFb {
+        w: usize,
+        h: usize,
+        pixels: Vec<Rgb>,
+        flushes: usize,
+    }
+
+    impl FrameBuffer for MemFb {
+        fn width(&self) -> usize {
+            self.w
+        }
+        fn height(&self) -> usize {
+            self.h
+        }
+        fn write_pixel(&mut self, x: usize, y: usize, color: Rgb) {
+            if x < self.w && y < self.h {
+                self.pixels[y * self.w + x] = color;
+            }
+        }
+        fn flush(&mut self) {
+            self.flushes += 1;
+        }
+    }
+
+    #[test]
+    fn test_render_glyph_and_cursor() {
+        let (w, h) = (2 * FONT_WIDTH, FONT_HEIGHT);
+        let mut fb = MemFb {
+            w,
+            h,
+            pixels: vec![0x123456; w * h],
+            flushes: 0,
+        };
+        let mut s = Screen::new(2, 1, 0);
+        feed(&mut s, "\x1b[32m|");
+        s.render(&mut fb, false);
+        assert_eq!(fb.flushes, 1);
+        // '|' 的竖线位于第 4 列，光标单元以反显绘制
+        let green = PALETTE[2];
+        assert_eq!(fb.pixels[4 * w + 4], green);
+        assert_eq!(fb.pixels[4 * w], PALETTE[DEFAULT_BG as usize]);
+        assert_eq!(fb.pixels[FONT_WIDTH], PALETTE[DEFAULT_FG as usize]);
+    }
+}
@@ -8,6 +8,7 @@
 //! - [`SerialDriver`] trait - 串口驱动接口
 //! - [`RtcDriver`] trait - 实时时钟驱动接口
 //! - [`Console`] trait - 控制台接口
+//! - [`FrameConsole`] - 帧缓冲虚拟终端控制台
 //! - [`IrqManager`] - 中断管理器
 //!
 //! # 架构解耦
@@ -50,7 +51,7 @@ pub use driver::{DeviceType, Driver};
 pub use irq::{IRQ_MANAGER, IntcDriver, IrqManager};
 
 // Re-export block
-pub use block::{BLK_DRIVERS, BlockDriver, RamDisk};
+pub use block::{BLK_DRIVERS, BlockDriver, FaultyDisk, RamDisk};
 
 // Re-export net
 pub use net::{NETWORK_DEVICES, NetDevice, NetDeviceError, NullNetDevice};
@@ -62,7 +63,7 @@ pub use serial::{SERIAL_DRIVERS, SerialDriver};
 pub use rtc::{DateTime, RTC_DRIVERS, RtcDriver};
 
 // Re-export console
-pub use console::{CONSOLES, Console, MAIN_CONSOLE};
+pub use console::{CONSOLES, Console, FrameBuffer, FrameConsole, MAIN_CONSOLE};
 
 // Re-export 全局驱动列表
 pub use driver::{CMDLINE, DRIVERS, register_driver};
@@ -0,0 +1,34 @@
+//! 为 test-support 的 Mock 网卡实现 [`NetDevice`]
+//!
+//! 供下游 crate（如 `net`）在宿主机测试中通过 `mock` feature 使用。
+
+use test_support::mock::net::MockNetDevice;
+
+use super::net_device::{NetDevice, NetDeviceError};
+
+impl NetDevice for MockNetDevice {
+    fn send(&self, packet: &[u8]) -> Result<(), NetDeviceError> {
+        self.transmit(packet);
+        Ok(())
+    }
+
+    fn receive(&self, buf: &mut [u8]) -> Result<usize, NetDeviceError> {
+        self.receive_into(buf).ok_or(NetDeviceError::QueueEmpty)
+    }
+
+    fn device_id(&self) -> usize {
+        MockNetDevice::device_id(self)
+    }
+
+    fn mtu(&self) -> usize {
+        MockNetDevice::mtu(self)
+    }
+
+    fn name(&self) -> &str {
+        MockNetDevice::NAME
+    }
+
+    fn mac_address(&self) -> [u8; 6] {
+        MockNetDevice::mac_address(self)
+    }
+}
@@ -2,30 +2,32 @@
 //!
 //! 管理和初始化各种网络设备
 
+#[cfg(any(test, feature = "mock"))]
+mod mock_net;
 mod net_device;
 mod null_net;
 
 use alloc::{sync::Arc, vec::Vec};
 use lazy_static::lazy_static;
-use sync::SpinLock;
+use sync::Rcu;
 
 pub use net_device::{NetDevice, NetDeviceError};
 pub use null_net::NullNetDevice;
 
 lazy_static! {
     /// 网络设备管理器
-    /// 负责存储和管理系统中的所有网络设备
-    pub static ref NETWORK_DEVICES: SpinLock<Vec<Arc<dyn NetDevice>>> = SpinLock::new(Vec::new());
+    /// 负责存储和管理系统中的所有网络设备，由 RCU 保护
+    pub static ref NETWORK_DEVICES: Rcu<Vec<Arc<dyn NetDevice>>> = Rcu::new(Vec::new());
 }
 
 /// 添加网络设备到网络设备管理器
 pub fn add_network_device(device: Arc<dyn NetDevice>) {
-    NETWORK_DEVICES.lock().push(device);
+    NETWORK_DEVICES.write().push(device);
 }
 
 /// 获取所有网络设备
 pub fn get_net_devices() -> Vec<Arc<dyn NetDevice>> {
-    NETWORK_DEVICES.lock().clone()
+    NETWORK_DEVICES.read().clone()
 }
 
 /// 格式化MAC地址为可读字符串
@@ -57,4 +59,29 @@ mod tests {
         assert_eq!(dev.mtu(), 1500);
         assert_eq!(dev.mac_address(), [0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
     }
+
+    #[test]
+    fn test_mock_net_device_queues() {
+        use test_support::mock::net::{MOCK_NET_MAC, MockNetDevice};
+
+        let dev = MockNetDevice::new(3, MOCK_NET_MAC);
+        let net: Arc<dyn NetDevice> = dev.clone();
+        let mut buf = [0u8; 4];
+        assert!(matches!(
+            net.receive(&mut buf),
+            Err(NetDeviceError::QueueEmpty)
+        ));
+
+        dev.inject(alloc::vec![1, 2, 3]);
+        assert_eq!(net.receive(&mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], &[1, 2, 3]);
+
+        net.send(&[9, 9]).unwrap();
+        assert_eq!(dev.pending_rx(), 0);
+        dev.set_loopback(true);
+        net.send(&[7]).unwrap();
+        assert_eq!(dev.take_sent(), [alloc::vec![9, 9], alloc::vec![7]]);
+        assert_eq!(net.receive(&mut buf).unwrap(), 1);
+        assert_eq!(net.name(), MockNetDevice::NAME);
+    }
 }
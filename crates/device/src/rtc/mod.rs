@@ -2,15 +2,12 @@
 
 use alloc::{sync::Arc, vec::Vec};
 use chrono::{DateTime as ChronoDateTime, Datelike, FixedOffset, TimeZone, Timelike, Utc};
-use lazy_static::lazy_static;
 use sync::RwLock;
 
 use crate::driver::Driver;
 
-lazy_static! {
-    /// 全局 RTC 驱动列表
-    pub static ref RTC_DRIVERS: RwLock<Vec<Arc<dyn RtcDriver>>> = RwLock::new(Vec::new());
-}
+/// 全局 RTC 驱动列表
+pub static RTC_DRIVERS: RwLock<Vec<Arc<dyn RtcDriver>>> = RwLock::new(Vec::new());
 
 /// 简化的日期时间结构（用于 sysfs 显示）
 #[derive(Debug, Clone, Copy)]
@@ -68,6 +65,11 @@ pub trait RtcDriver: Driver {
     /// 读取自纪元以来的秒数
     fn read_epoch(&self) -> u64;
 
+    /// 设置自纪元以来的秒数，不支持写入的 RTC 返回 `false`
+    fn set_epoch(&self, _epoch: u64) -> bool {
+        false
+    }
+
     /// 读取日期时间（北京时间，默认实现）
     fn read_datetime(&self) -> DateTime {
         DateTime::from_epoch(self.read_epoch())
@@ -25,4 +25,11 @@ pub trait SerialDriver: Driver {
     fn try_read(&self) -> Option<u8> {
         Some(self.read())
     }
+
+    /// 接收是否由中断驱动
+    ///
+    /// 为真时驱动在接收中断中把数据推送给对应的终端，否则终端需要轮询 [`try_read`](Self::try_read)。
+    fn rx_irq_enabled(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,48 @@
+//! Devpts 文件系统实现
+
+use alloc::sync::Arc;
+
+use vfs::{FileSystem, FsError, Inode, StatFs};
+
+use super::inode::DevPtsInode;
+
+/// Devpts 文件系统
+pub struct DevPtsFs {
+    root_inode: Arc<DevPtsInode>,
+}
+
+impl DevPtsFs {
+    /// 创建新的 devpts 实例
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            root_inode: DevPtsInode::new_root(),
+        })
+    }
+}
+
+impl FileSystem for DevPtsFs {
+    fn fs_type(&self) -> &'static str {
+        "devpts"
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root_inode.clone()
+    }
+
+    fn sync(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    fn statfs(&self) -> Result<StatFs, FsError> {
+        Ok(StatFs {
+            block_size: 4096,
+            total_blocks: 0,
+            free_blocks: 0,
+            available_blocks: 0,
+            total_inodes: 0,
+            free_inodes: 0,
+            fsid: 0,
+            max_filename_len: 255,
+        })
+    }
+}
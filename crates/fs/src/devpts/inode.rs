@@ -0,0 +1,233 @@
+//! Devpts Inode 实现
+
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use uapi::time::TimeSpec;
+use vfs::tty::pty::{pty_indices, pty_slave};
+use vfs::{
+    DirEntry, FileMode, FsError, Inode, InodeMetadata, InodeType, chrdev_major, console_minor,
+    makedev,
+};
+
+use crate::ops::fs_ops;
+
+/// 根目录的 inode 号
+const ROOT_INO: usize = 1;
+/// ptmx 的 inode 号
+const PTMX_INO: usize = 2;
+
+/// 从设备 N 的 inode 号（与 Linux 相同，为 N + 3）
+fn slave_ino(index: u32) -> usize {
+    index as usize + 3
+}
+
+/// Devpts 节点类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DevPtsKind {
+    /// 根目录
+    Root,
+    /// 主设备复用器
+    Ptmx,
+    /// 编号为 N 的从设备
+    Slave(u32),
+}
+
+/// Devpts Inode
+pub struct DevPtsInode {
+    kind: DevPtsKind,
+    /// 节点创建时间
+    ctime: TimeSpec,
+}
+
+impl DevPtsInode {
+    /// 创建根目录 inode
+    pub fn new_root() -> Arc<Self> {
+        Self::new(DevPtsKind::Root)
+    }
+
+    fn new(kind: DevPtsKind) -> Arc<Self> {
+        Arc::new(Self {
+            kind,
+            ctime: fs_ops().timespec_now(),
+        })
+    }
+
+    fn ino(&self) -> usize {
+        match self.kind {
+            DevPtsKind::Root => ROOT_INO,
+            DevPtsKind::Ptmx => PTMX_INO,
+            DevPtsKind::Slave(index) => slave_ino(index),
+        }
+    }
+
+    /// 解析从设备名：只接受规范的十进制编号（拒绝 "01"、"+1" 等）
+    fn parse_index(name: &str) -> Option<u32> {
+        let index: u32 = name.parse().ok()?;
+        (index.to_string() == name).then_some(index)
+    }
+}
+
+impl Inode for DevPtsInode {
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        let (inode_type, mode, nlinks, rdev) = match self.kind {
+            DevPtsKind::Root => (
+                InodeType::Directory,
+                FileMode::S_IFDIR | FileMode::from_bits_truncate(0o755),
+                2,
+                0,
+            ),
+            DevPtsKind::Ptmx => (
+                InodeType::CharDevice,
+                FileMode::S_IFCHR | FileMode::from_bits_truncate(0o666),
+                1,
+                makedev(chrdev_major::CONSOLE, console_minor::PTMX),
+            ),
+            DevPtsKind::Slave(index) => (
+                InodeType::CharDevice,
+                FileMode::S_IFCHR | FileMode::from_bits_truncate(0o620),
+                1,
+                makedev(chrdev_major::PTY_SLAVE, index),
+            ),
+        };
+        Ok(InodeMetadata {
+            inode_no: self.ino(),
+            inode_type,
+            mode,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            atime: self.ctime,
+            mtime: self.ctime,
+            ctime: self.ctime,
+            nlinks,
+            blocks: 0,
+            rdev,
+        })
+    }
+
+    fn read_at(&self, _offset: usize, _buf: &mut [u8]) -> Result<usize, FsError> {
+        match self.kind {
+            DevPtsKind::Root => Err(FsError::IsDirectory),
+            _ => Err(FsError::NotSupported),
+        }
+    }
+
+    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize, FsError> {
+        match self.kind {
+            DevPtsKind::Root => Err(FsError::IsDirectory),
+            _ => Err(FsError::NotSupported),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        if self.kind != DevPtsKind::Root {
+            return Err(FsError::NotDirectory);
+        }
+        if name == "ptmx" {
+            return Ok(Self::new(DevPtsKind::Ptmx));
+        }
+        match Self::parse_index(name) {
+            Some(index) if pty_slave(index).is_some() => Ok(Self::new(DevPtsKind::Slave(index))),
+            _ => Err(FsError::NotFound),
+        }
+    }
+
+    fn readdir(&self) -> Result<Vec<DirEntry>, FsError> {
+        if self.kind != DevPtsKind::Root {
+            return Err(FsError::NotDirectory);
+        }
+        let mut entries = Vec::new();
+        for name in [".", ".."] {
+            entries.push(DirEntry {
+                name: name.to_string(),
+                inode_no: ROOT_INO,
+                inode_type: InodeType::Directory,
+            });
+        }
+        entries.push(DirEntry {
+            name: "ptmx".to_string(),
+            inode_no: PTMX_INO,
+            inode_type: InodeType::CharDevice,
+        });
+        for index in pty_indices() {
+            entries.push(DirEntry {
+                name: index.to_string(),
+                inode_no: slave_ino(index),
+                inode_type: InodeType::CharDevice,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn cacheable(&self) -> bool {
+        !matches!(self.kind, DevPtsKind::Slave(_))
+    }
+
+    fn create(&self, _name: &str, _mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn mkdir(&self, _name: &str, _mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn truncate(&self, _size: usize) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn sync(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    fn symlink(&self, _name: &str, _target: &str) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn link(&self, _name: &str, _target: &Arc<dyn Inode>) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn unlink(&self, _name: &str) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn rmdir(&self, _name: &str) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn rename(
+        &self,
+        _old_name: &str,
+        _new_parent: Arc<dyn Inode>,
+        _new_name: &str,
+    ) -> Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn set_times(&self, _atime: Option<TimeSpec>, _mtime: Option<TimeSpec>) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    fn readlink(&self) -> Result<String, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn mknod(&self, _name: &str, _mode: FileMode, _dev: u64) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn chmod(&self, _mode: FileMode) -> Result<(), FsError> {
+        Err(FsError::NotSupported)
+    }
+
+    fn chown(&self, _uid: u32, _gid: u32) -> Result<(), FsError> {
+        Err(FsError::NotSupported)
+    }
+}
@@ -0,0 +1,14 @@
+//! Devpts - 伪终端从设备文件系统
+//!
+//! 挂载在 `/dev/pts`，内容完全由当前已分配的 PTY 决定（见 [`vfs::tty::pty`]）：
+//!
+//! - `/dev/pts/ptmx`：PTY 主设备复用器（c 5,2），与 `/dev/ptmx` 等价
+//! - `/dev/pts/N`：编号为 N 的 PTY 从设备（c 136,N），主设备关闭后消失
+//!
+//! 目录不可写；从设备节点不进入 dentry 缓存，以便及时反映 PTY 的分配与释放。
+
+mod devpts;
+mod inode;
+
+pub use devpts::DevPtsFs;
+pub use inode::DevPtsInode;
@@ -3,14 +3,26 @@
 //! 将 ext4_rs 的 inode 操作包装为 VFS Inode trait
 
 use crate::ops::fs_ops;
+use alloc::format;
 use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use ext4_rs::InodeFileType;
+use mm::{PageCache, PageCacheRegistry, mm_config};
 use sync::SpinLock;
 use uapi::time::TimeSpec;
 
-use vfs::{Dentry, DirEntry, FileMode, FsError, Inode, InodeMetadata, InodeType};
+use super::xattr;
+
+use vfs::{Dentry, DirEntry, FileMode, FsError, Inode, InodeMetadata, InodeType, XattrFlags};
+
+/// 所有 Ext4 文件的页缓存，以（文件系统对象地址, inode 号）区分；
+/// 同一文件的多个 Ext4Inode 对象共享同一个缓存
+static PAGE_CACHES: PageCacheRegistry<(usize, u32)> = PageCacheRegistry::new();
+
+/// O_TMPFILE 临时目录项名字的序号
+static TMPFILE_SEQ: AtomicUsize = AtomicUsize::new(0);
 
 /// Ext4 Inode 包装
 pub struct Ext4Inode {
@@ -22,18 +34,56 @@ pub struct Ext4Inode {
 
     /// 关联的 Dentry（弱引用，避免循环引用）
     dentry: SpinLock<Weak<Dentry>>,
+
+    /// 文件数据的页缓存，读写和共享文件映射都经过它
+    cache: Arc<PageCache>,
+
+    /// 由 O_TMPFILE 创建且尚未链接到任何目录，释放时回收 inode
+    orphan: AtomicBool,
 }
 
 impl Ext4Inode {
     /// 创建新的 Ext4Inode
     pub fn new(fs: Arc<SpinLock<ext4_rs::Ext4>>, ino: u32) -> Self {
+        let cache = PAGE_CACHES.get(Self::cache_key(&fs, ino));
         Self {
             fs,
             ino,
             dentry: SpinLock::new(Weak::new()),
+            cache,
+            orphan: AtomicBool::new(false),
         }
     }
 
+    fn cache_key(fs: &Arc<SpinLock<ext4_rs::Ext4>>, ino: u32) -> (usize, u32) {
+        (Arc::as_ptr(fs) as usize, ino)
+    }
+
+    /// 文件的页缓存
+    pub fn page_cache(&self) -> Arc<PageCache> {
+        self.cache.clone()
+    }
+
+    /// 把页缓存中的脏页（来自共享文件映射）写回磁盘
+    pub fn writeback(&self) -> Result<(), FsError> {
+        let size = self.metadata()?.size;
+        let page_size = mm_config().page_size();
+        let fs = self.fs.lock();
+        self.cache
+            .writeback(|index, data| {
+                // 不写超出文件末尾的部分，写回不改变文件大小
+                let offset = index * page_size;
+                let len = size.saturating_sub(offset).min(data.len());
+                if len == 0 {
+                    return Ok(());
+                }
+                fs.write_at(self.ino, offset, &data[..len])
+                    .map(|_| ())
+                    .map_err(|_| FsError::IoError.to_errno())
+            })
+            .map_err(|_| FsError::IoError)
+    }
+
     /// 辅助方法：获取完整路径（从 Dentry 动态获取）
     fn get_full_path(&self) -> Result<String, FsError> {
         let dentry = self.dentry.lock().upgrade().ok_or(FsError::IoError)?;
@@ -70,6 +120,27 @@ impl Ext4Inode {
     }
 }
 
+impl Drop for Ext4Inode {
+    /// 回收从未被链接的 O_TMPFILE inode
+    ///
+    /// 未链接的 inode 只能经由创建它的这个对象访问，所以它的释放就是最后一个引用的释放。
+    fn drop(&mut self) {
+        if !self.orphan.load(Ordering::Acquire) {
+            return;
+        }
+        let fs = self.fs.lock();
+        // 先删除扩展属性，释放可能占用的属性块
+        for name in xattr::list(&fs, self.ino).unwrap_or_default() {
+            let _ = xattr::set(&fs, self.ino, &name, None, XattrFlags::empty());
+        }
+        let mut inode_ref = fs.get_inode_ref(self.ino);
+        let _ = fs.truncate_inode(&mut inode_ref, 0);
+        fs.ialloc_free_inode(self.ino, false);
+        drop(fs);
+        PAGE_CACHES.remove(&Self::cache_key(&self.fs, self.ino));
+    }
+}
+
 impl Inode for Ext4Inode {
     fn metadata(&self) -> Result<InodeMetadata, FsError> {
         let fs = self.fs.lock();
@@ -127,9 +198,26 @@ impl Inode for Ext4Inode {
             return Err(FsError::IsDirectory);
         }
 
-        let fs = self.fs.lock();
-        fs.read_at(self.ino, offset, buf)
-            .map_err(|_| FsError::IoError)
+        let end = metadata.size.min(offset.saturating_add(buf.len()));
+        let page_size = mm_config().page_size();
+        let mut pos = offset;
+        while pos < end {
+            let index = pos / page_size;
+            let page_off = pos % page_size;
+            let take = (end - pos).min(page_size - page_off);
+            let page = self
+                .cache
+                .get_or_fill(index, |page| {
+                    let fs = self.fs.lock();
+                    fs.read_at(self.ino, index * page_size, page)
+                        .map(|_| ())
+                        .map_err(|_| FsError::IoError.to_errno())
+                })
+                .map_err(|_| FsError::IoError)?;
+            page.read(page_off, &mut buf[pos - offset..pos - offset + take]);
+            pos += take;
+        }
+        Ok(end.saturating_sub(offset))
     }
 
     fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize, FsError> {
@@ -138,9 +226,14 @@ impl Inode for Ext4Inode {
             return Err(FsError::IsDirectory);
         }
 
-        let fs = self.fs.lock();
-        fs.write_at(self.ino, offset, buf)
-            .map_err(|_| FsError::IoError)
+        // 写穿到磁盘，再同步已缓存的页
+        let written = {
+            let fs = self.fs.lock();
+            fs.write_at(self.ino, offset, buf)
+                .map_err(|_| FsError::IoError)?
+        };
+        self.cache.update(offset, &buf[..written]);
+        Ok(written)
     }
 
     fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
@@ -183,6 +276,39 @@ impl Inode for Ext4Inode {
         )))
     }
 
+    fn tmpfile(&self, mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+        let metadata = self.metadata()?;
+        if metadata.inode_type != InodeType::Directory {
+            return Err(FsError::NotDirectory);
+        }
+
+        // ext4_rs 只能在目录中创建 inode：先以临时名字创建，再删除目录项并把链接数清零
+        let name = format!(".tmpfile.{}", TMPFILE_SEQ.fetch_add(1, Ordering::Relaxed));
+        if self.lookup(&name).is_ok() {
+            return Err(FsError::AlreadyExists);
+        }
+
+        let fs = self.fs.lock();
+        let ftype = InodeFileType::S_IFREG.bits() | (mode.bits() & 0o7777) as u16;
+        let child = fs
+            .create(self.ino, &name, ftype)
+            .map_err(|_| FsError::NoSpace)?;
+
+        let mut parent_ref = fs.get_inode_ref(self.ino);
+        fs.dir_remove_entry(&mut parent_ref, &name)
+            .map_err(|_| FsError::IoError)?;
+        fs.write_back_inode(&mut parent_ref);
+
+        let mut child_ref = fs.get_inode_ref(child.inode_num);
+        child_ref.inode.set_links_count(0);
+        fs.write_back_inode(&mut child_ref);
+        drop(fs);
+
+        let inode = Ext4Inode::new(self.fs.clone(), child.inode_num);
+        inode.orphan.store(true, Ordering::Release);
+        Ok(Arc::new(inode))
+    }
+
     fn mkdir(&self, name: &str, _mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
         let metadata = self.metadata()?;
         if metadata.inode_type != InodeType::Directory {
@@ -249,6 +375,15 @@ impl Inode for Ext4Inode {
         fs.link(&mut self_ref, &mut target_ref, name)
             .map_err(|_| FsError::NoSpace)?;
 
+        if ext4_inode.orphan.swap(false, Ordering::AcqRel) {
+            // O_TMPFILE inode 第一次获得名字，链接数从 0 开始
+            let mut target_ref = fs.get_inode_ref(ext4_inode.ino);
+            if target_ref.inode.links_count() == 0 {
+                target_ref.inode.set_links_count(1);
+                fs.write_back_inode(&mut target_ref);
+            }
+        }
+
         Ok(())
     }
 
@@ -279,6 +414,11 @@ impl Inode for Ext4Inode {
                 .map_err(|_| FsError::IoError)?;
 
             fs.write_back_inode(&mut parent_ref);
+
+            if child_metadata.nlinks <= 1 {
+                // inode 号可能被新文件复用，不能让它看到旧文件的缓存
+                PAGE_CACHES.remove(&Self::cache_key(&self.fs, child_ext4.ino));
+            }
         }
 
         Ok(())
@@ -491,6 +631,7 @@ impl Inode for Ext4Inode {
             let mut inode_ref = fs.get_inode_ref(self.ino);
             fs.truncate_inode(&mut inode_ref, size as u64)
                 .map_err(|_| FsError::IoError)?;
+            self.cache.truncate(size);
         } else {
             let extend_size = size - old_size;
             let zero_buf = alloc::vec![0u8; extend_size.min(4096)];
@@ -501,6 +642,7 @@ impl Inode for Ext4Inode {
                 let to_write = (extend_size - written).min(zero_buf.len());
                 fs.write_at(self.ino, old_size + written, &zero_buf[..to_write])
                     .map_err(|_| FsError::IoError)?;
+                self.cache.update(old_size + written, &zero_buf[..to_write]);
                 written += to_write;
             }
         }
@@ -509,7 +651,7 @@ impl Inode for Ext4Inode {
     }
 
     fn sync(&self) -> Result<(), FsError> {
-        Ok(())
+        self.writeback()
     }
 
     fn set_dentry(&self, dentry: Weak<Dentry>) {
@@ -520,6 +662,10 @@ impl Inode for Ext4Inode {
         self.dentry.lock().upgrade()
     }
 
+    fn cache_negative(&self) -> bool {
+        true
+    }
+
     fn as_any(&self) -> &dyn core::any::Any {
         self
     }
@@ -616,4 +762,24 @@ impl Inode for Ext4Inode {
     fn mknod(&self, _name: &str, _mode: FileMode, _dev: u64) -> Result<Arc<dyn Inode>, FsError> {
         Err(FsError::NotSupported)
     }
+
+    fn getxattr(&self, name: &str) -> Result<Vec<u8>, FsError> {
+        let fs = self.fs.lock();
+        xattr::get(&fs, self.ino, name)
+    }
+
+    fn setxattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> Result<(), FsError> {
+        let fs = self.fs.lock();
+        xattr::set(&fs, self.ino, name, Some(value), flags)
+    }
+
+    fn listxattr(&self) -> Result<Vec<String>, FsError> {
+        let fs = self.fs.lock();
+        xattr::list(&fs, self.ino)
+    }
+
+    fn removexattr(&self, name: &str) -> Result<(), FsError> {
+        let fs = self.fs.lock();
+        xattr::set(&fs, self.ino, name, None, XattrFlags::empty())
+    }
 }
@@ -24,12 +24,20 @@
 //! BlockDriver (VirtIO Block)
 //! ```
 //!
+//! ## 页缓存
+//!
+//! 普通文件的数据经 [`mm::PageCache`] 读写：读命中缓存时不访问块设备，写入穿透到磁盘后同步
+//! 已缓存的页；共享文件映射直接映射缓存页，`msync`/`munmap` 时由 [`Ext4Inode::writeback`] 写回。
+//!
 //! ## 支持的操作
 //!
 //! - **文件操作**：read、write、truncate、sync
 //! - **目录操作**：lookup、create、mkdir、readdir、rmdir
 //! - **链接操作**：symlink、link、unlink、readlink
+//! - **匿名文件**：tmpfile（O_TMPFILE），创建后删除目录项，未链接就释放时回收 inode
 //! - **元数据**：chmod、chown、set_times
+//! - **扩展属性**：get/set/list/removexattr，inode 内与属性块两种存储，POSIX ACL 按 ext4 磁盘格式
+//!   转换（见 `xattr` 模块）
 //! - **重命名**：rename（支持跨目录移动）
 //!
 //! # 配置要求
@@ -41,8 +49,10 @@
 //!
 //! - `mknod` 未实现（设备文件创建）
 //! - 非日志模式，崩溃可能导致不一致
+//! - 没有孤儿 inode 链表，崩溃时未回收的 O_TMPFILE inode 需要 e2fsck 清理
 pub mod adapters;
 pub mod inode;
+mod xattr;
 
 pub use adapters::BlockDeviceAdapter;
 pub use inode::Ext4Inode;
@@ -0,0 +1,720 @@
+//! Ext4 扩展属性
+//!
+//! 磁盘格式与 Linux 一致（fs/ext4/xattr.h），属性保存在两处：
+//!
+//! - **inode 内**：inode 记录 `128 + i_extra_isize` 之后到 inode 末尾的空间，以魔数开头，
+//!   后接表项；值从区域末尾向前存放，偏移相对第一个表项
+//! - **属性块**：`i_file_acl` 指向的整块，32 字节块头之后是按（名字索引, 名字长度, 名字）
+//!   排序的表项，值从块尾向前存放；块可以被多个 inode 共享（`h_refcount`），
+//!   修改共享块时先复制出新块
+//!
+//! 每次修改都把 inode 的全部属性重新编排：依次尽量放进 inode 内，放不下的进属性块。
+//! ext4_rs 不解析这两块区域，这里直接按偏移读写块设备，只借用 ext4_rs 分配和释放属性块；
+//! 启用 metadata_csum 时同步更新 inode 与属性块的校验和。
+//!
+//! POSIX ACL 以名字索引 2、3（名字为空）保存，磁盘上是 ext4 自己的紧凑格式（fs/ext4/acl.h），
+//! 读写时与 xattr 接口的格式相互转换。
+//!
+//! 不支持 ea_inode 特性（值保存在独立 inode 中的大属性），遇到时返回 `NotSupported`。
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ext4_rs::{BlockDevice, Ext4};
+use uapi::posix_acl::{
+    POSIX_ACL_XATTR_VERSION, XATTR_NAME_POSIX_ACL_ACCESS, XATTR_NAME_POSIX_ACL_DEFAULT,
+};
+use vfs::{AclEntry, AclTag, FsError, PosixAcl, XattrFlags, check_xattr_flags};
+
+use crate::ops::fs_ops;
+
+/// 属性区与属性块的魔数
+const XATTR_MAGIC: u32 = 0xEA02_0000;
+/// 属性块头大小
+const BLOCK_HEADER_SIZE: usize = 32;
+/// 表项中名字之前的固定部分
+const ENTRY_HEADER_SIZE: usize = 16;
+/// inode 固定部分的大小，扩展部分（`i_extra_isize`）从这里开始
+const GOOD_OLD_INODE_SIZE: usize = 128;
+
+/// 超级块特性位
+const COMPAT_EXT_ATTR: u32 = 0x0008;
+const INCOMPAT_64BIT: u32 = 0x0080;
+const INCOMPAT_CSUM_SEED: u32 = 0x2000;
+const RO_COMPAT_METADATA_CSUM: u32 = 0x0400;
+
+/// 名字索引与对应的前缀（fs/ext4/xattr.c 中的 `ext4_xattr_handler_map`）
+const NAME_INDEXES: [(u8, &str); 4] = [
+    (1, "user."),
+    (4, "trusted."),
+    (6, "security."),
+    (7, "system."),
+];
+
+/// POSIX ACL 的名字索引与完整名字，表项中的名字为空
+const ACL_INDEXES: [(u8, &str); 2] = [
+    (2, XATTR_NAME_POSIX_ACL_ACCESS),
+    (3, XATTR_NAME_POSIX_ACL_DEFAULT),
+];
+
+/// 磁盘上 ACL 的版本（EXT4_ACL_VERSION）
+const EXT4_ACL_VERSION: u32 = 0x0001;
+
+/// 一个属性
+#[derive(Clone)]
+struct Entry {
+    index: u8,
+    name: Vec<u8>,
+    value: Vec<u8>,
+}
+
+impl Entry {
+    /// 带前缀的完整名字，名字索引不经过 xattr 接口时返回 `None`
+    fn full_name(&self) -> Option<String> {
+        if let Some((_, name)) = ACL_INDEXES.iter().find(|(i, _)| *i == self.index) {
+            return Some(String::from(*name));
+        }
+        let (_, prefix) = NAME_INDEXES.iter().find(|(i, _)| *i == self.index)?;
+        let name = core::str::from_utf8(&self.name).ok()?;
+        Some(alloc::format!("{}{}", prefix, name))
+    }
+
+    /// 表项占用的空间（不含值）
+    fn entry_size(&self) -> usize {
+        pad(ENTRY_HEADER_SIZE + self.name.len())
+    }
+
+    /// 值占用的空间
+    fn value_size(&self) -> usize {
+        pad(self.value.len())
+    }
+
+    /// 表项哈希（`ext4_xattr_hash_entry`）
+    fn hash(&self) -> u32 {
+        let mut hash = 0u32;
+        for &c in &self.name {
+            hash = (hash << 5) ^ (hash >> 27) ^ c as u32;
+        }
+        for word in self.value.chunks(4) {
+            let mut bytes = [0u8; 4];
+            bytes[..word.len()].copy_from_slice(word);
+            hash = (hash << 16) ^ (hash >> 16) ^ u32::from_le_bytes(bytes);
+        }
+        hash
+    }
+}
+
+/// 把完整名字拆分为名字索引与去掉前缀的名字
+fn encode_name(name: &str) -> Option<(u8, &[u8])> {
+    if let Some(&(index, _)) = ACL_INDEXES.iter().find(|(_, n)| *n == name) {
+        return Some((index, &[]));
+    }
+    NAME_INDEXES.iter().find_map(|&(index, prefix)| {
+        name.strip_prefix(prefix)
+            .filter(|suffix| !suffix.is_empty() && suffix.len() <= u8::MAX as usize)
+            .map(|suffix| (index, suffix.as_bytes()))
+    })
+}
+
+fn pad(size: usize) -> usize {
+    (size + 3) & !3
+}
+
+fn get_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn get_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn put_u16(buf: &mut [u8], offset: usize, value: u16) {
+    buf[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut [u8], offset: usize, value: u32) {
+    buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// ext4 使用的 crc32c（不做首尾取反）
+fn crc32c(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// 从 `buf[start..]` 解析表项，值的偏移相对 `buf` 开头
+fn parse_entries(buf: &[u8], start: usize) -> Result<Vec<Entry>, FsError> {
+    let mut entries = Vec::new();
+    let mut offset = start;
+    while offset + 4 <= buf.len() && get_u32(buf, offset) != 0 {
+        if offset + ENTRY_HEADER_SIZE > buf.len() {
+            return Err(FsError::IoError);
+        }
+        let name_len = buf[offset] as usize;
+        let index = buf[offset + 1];
+        let value_offs = get_u16(buf, offset + 2) as usize;
+        let value_inum = get_u32(buf, offset + 4);
+        let value_size = get_u32(buf, offset + 8) as usize;
+        let name_end = offset + ENTRY_HEADER_SIZE + name_len;
+        if name_end > buf.len() {
+            return Err(FsError::IoError);
+        }
+        if value_inum != 0 {
+            return Err(FsError::NotSupported);
+        }
+        if value_size != 0 && value_offs + value_size > buf.len() {
+            return Err(FsError::IoError);
+        }
+
+        entries.push(Entry {
+            index,
+            name: buf[offset + ENTRY_HEADER_SIZE..name_end].to_vec(),
+            value: buf[value_offs..value_offs + value_size].to_vec(),
+        });
+        offset += pad(ENTRY_HEADER_SIZE + name_len);
+    }
+    Ok(entries)
+}
+
+/// `entries` 占用的空间（含结尾的 4 字节空表项）
+fn space_needed(entries: &[&Entry]) -> usize {
+    entries
+        .iter()
+        .map(|e| e.entry_size() + e.value_size())
+        .sum::<usize>()
+        + 4
+}
+
+/// 把表项写入 `buf[start..]`，值从 `buf` 末尾向前存放，偏移相对 `buf` 开头
+///
+/// 调用者保证空间足够。
+fn write_entries(buf: &mut [u8], start: usize, entries: &[&Entry]) {
+    buf[start..].fill(0);
+    let mut offset = start;
+    let mut value_end = buf.len();
+    for entry in entries {
+        let value_offs = if entry.value.is_empty() {
+            0
+        } else {
+            value_end -= entry.value_size();
+            buf[value_end..value_end + entry.value.len()].copy_from_slice(&entry.value);
+            value_end
+        };
+        buf[offset] = entry.name.len() as u8;
+        buf[offset + 1] = entry.index;
+        put_u16(buf, offset + 2, value_offs as u16);
+        put_u32(buf, offset + 4, 0);
+        put_u32(buf, offset + 8, entry.value.len() as u32);
+        put_u32(buf, offset + 12, entry.hash());
+        buf[offset + ENTRY_HEADER_SIZE..offset + ENTRY_HEADER_SIZE + entry.name.len()]
+            .copy_from_slice(&entry.name);
+        offset += entry.entry_size();
+    }
+}
+
+/// 从超级块读出的布局参数
+struct Geometry {
+    block_size: usize,
+    inodes_per_group: u32,
+    inode_size: usize,
+    desc_size: usize,
+    first_data_block: usize,
+    ext_attr: bool,
+    /// 启用 metadata_csum 时的校验和种子
+    csum_seed: Option<u32>,
+}
+
+impl Geometry {
+    fn read(dev: &dyn BlockDevice) -> Result<Self, FsError> {
+        let sb = dev.read_offset(1024);
+        if sb.len() < 1024 || get_u16(&sb, 0x38) != 0xEF53 {
+            return Err(FsError::IoError);
+        }
+        let feature_compat = get_u32(&sb, 0x5C);
+        let feature_incompat = get_u32(&sb, 0x60);
+        let feature_ro_compat = get_u32(&sb, 0x64);
+        let inode_size = if get_u32(&sb, 0x4C) == 0 {
+            GOOD_OLD_INODE_SIZE
+        } else {
+            get_u16(&sb, 0x58) as usize
+        };
+        let desc_size = if feature_incompat & INCOMPAT_64BIT != 0 {
+            get_u16(&sb, 0xFE) as usize
+        } else {
+            32
+        };
+        let csum_seed = (feature_ro_compat & RO_COMPAT_METADATA_CSUM != 0).then(|| {
+            if feature_incompat & INCOMPAT_CSUM_SEED != 0 {
+                get_u32(&sb, 0x270)
+            } else {
+                crc32c(!0, &sb[0x68..0x78])
+            }
+        });
+
+        Ok(Self {
+            block_size: 1024 << get_u32(&sb, 0x18),
+            inodes_per_group: get_u32(&sb, 0x28),
+            inode_size,
+            desc_size,
+            first_data_block: get_u32(&sb, 0x14) as usize,
+            ext_attr: feature_compat & COMPAT_EXT_ATTR != 0,
+            csum_seed,
+        })
+    }
+
+    /// inode 记录在设备上的字节偏移
+    fn inode_offset(&self, dev: &dyn BlockDevice, ino: u32) -> usize {
+        let group = ((ino - 1) / self.inodes_per_group) as usize;
+        let index = ((ino - 1) % self.inodes_per_group) as usize;
+        let desc_offset = (self.first_data_block + 1) * self.block_size + group * self.desc_size;
+        let desc = dev.read_offset(desc_offset);
+        let mut table = get_u32(&desc, 0x08) as usize;
+        if self.desc_size >= 64 {
+            table |= (get_u32(&desc, 0x28) as usize) << 32;
+        }
+        table * self.block_size + index * self.inode_size
+    }
+}
+
+/// 直接从设备读出的 inode 记录
+struct RawInode {
+    ino: u32,
+    offset: usize,
+    bytes: Vec<u8>,
+}
+
+impl RawInode {
+    fn read(dev: &dyn BlockDevice, geo: &Geometry, ino: u32) -> Self {
+        let offset = geo.inode_offset(dev, ino);
+        let mut bytes = dev.read_offset(offset);
+        bytes.truncate(geo.inode_size);
+        Self { ino, offset, bytes }
+    }
+
+    fn extra_isize(&self) -> usize {
+        if self.bytes.len() > GOOD_OLD_INODE_SIZE {
+            get_u16(&self.bytes, 0x80) as usize
+        } else {
+            0
+        }
+    }
+
+    /// inode 内属性区（魔数之后）的起止位置，没有空间时返回 `None`
+    fn inline_area(&self) -> Option<(usize, usize)> {
+        let start = GOOD_OLD_INODE_SIZE + self.extra_isize() + 4;
+        (self.bytes.len() > GOOD_OLD_INODE_SIZE && start + 4 <= self.bytes.len())
+            .then_some((start, self.bytes.len()))
+    }
+
+    /// `i_blocks`（以 512 字节扇区或文件系统块为单位）
+    fn blocks(&self) -> u64 {
+        get_u32(&self.bytes, 0x1C) as u64 | (get_u16(&self.bytes, 0x74) as u64) << 32
+    }
+
+    fn set_blocks(&mut self, blocks: u64) {
+        put_u32(&mut self.bytes, 0x1C, blocks as u32);
+        put_u16(&mut self.bytes, 0x74, (blocks >> 32) as u16);
+    }
+
+    /// 一个文件系统块折合的 `i_blocks` 计数
+    fn blocks_per_fs_block(&self, geo: &Geometry) -> u64 {
+        const HUGE_FILE_FL: u32 = 0x40000;
+        if get_u32(&self.bytes, 0x20) & HUGE_FILE_FL != 0 {
+            1
+        } else {
+            (geo.block_size / 512) as u64
+        }
+    }
+
+    fn file_acl(&self) -> u64 {
+        get_u32(&self.bytes, 0x68) as u64 | (get_u16(&self.bytes, 0x76) as u64) << 32
+    }
+
+    fn set_file_acl(&mut self, block: u64) {
+        put_u32(&mut self.bytes, 0x68, block as u32);
+        put_u16(&mut self.bytes, 0x76, (block >> 32) as u16);
+    }
+
+    fn inline_entries(&self) -> Result<Vec<Entry>, FsError> {
+        match self.inline_area() {
+            Some((start, end)) if get_u32(&self.bytes, start - 4) == XATTR_MAGIC => {
+                parse_entries(&self.bytes[start..end], 0)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn set_inline_entries(&mut self, entries: &[&Entry]) {
+        let Some((start, end)) = self.inline_area() else {
+            return;
+        };
+        if entries.is_empty() {
+            self.bytes[start - 4..end].fill(0);
+        } else {
+            put_u32(&mut self.bytes, start - 4, XATTR_MAGIC);
+            write_entries(&mut self.bytes[start..end], 0, entries);
+        }
+    }
+
+    fn touch_ctime(&mut self) {
+        let now = fs_ops().timespec_now();
+        put_u32(&mut self.bytes, 0x0C, now.tv_sec as u32);
+        if self.extra_isize() >= 8 {
+            put_u32(
+                &mut self.bytes,
+                0x84,
+                ((now.tv_nsec as u32) << 2) & 0xFFFFFFFC,
+            );
+        }
+    }
+
+    fn write(&mut self, dev: &dyn BlockDevice, geo: &Geometry) {
+        if let Some(seed) = geo.csum_seed {
+            let has_hi = self.extra_isize() >= 4;
+            put_u16(&mut self.bytes, 0x7C, 0);
+            if has_hi {
+                put_u16(&mut self.bytes, 0x82, 0);
+            }
+            let generation = get_u32(&self.bytes, 0x64);
+            let mut crc = crc32c(seed, &self.ino.to_le_bytes());
+            crc = crc32c(crc, &generation.to_le_bytes());
+            crc = crc32c(crc, &self.bytes);
+            put_u16(&mut self.bytes, 0x7C, crc as u16);
+            if has_hi {
+                put_u16(&mut self.bytes, 0x82, (crc >> 16) as u16);
+            }
+        }
+        dev.write_offset(self.offset, &self.bytes);
+    }
+}
+
+/// 读取属性块，校验块头
+fn read_block(dev: &dyn BlockDevice, geo: &Geometry, block: u64) -> Result<Vec<u8>, FsError> {
+    let buf = dev.read_offset(block as usize * geo.block_size);
+    if get_u32(&buf, 0) != XATTR_MAGIC || get_u32(&buf, 8) != 1 {
+        return Err(FsError::IoError);
+    }
+    Ok(buf)
+}
+
+/// 写回属性块，更新块哈希与校验和
+fn write_block(dev: &dyn BlockDevice, geo: &Geometry, block: u64, buf: &mut [u8]) {
+    if let Some(seed) = geo.csum_seed {
+        put_u32(buf, 0x10, 0);
+        let crc = crc32c(seed, &block.to_le_bytes());
+        put_u32(buf, 0x10, crc32c(crc, buf));
+    }
+    dev.write_offset(block as usize * geo.block_size, buf);
+}
+
+/// 名字索引是否为 POSIX ACL
+fn is_acl_index(index: u8) -> bool {
+    ACL_INDEXES.iter().any(|(i, _)| *i == index)
+}
+
+/// xattr 接口格式的 ACL 转为磁盘格式（`ext4_acl_to_disk`）
+///
+/// 属主、属组、MASK 与 OTHER 表项不带 ID，只占 4 字节。
+fn acl_to_disk(value: &[u8]) -> Result<Vec<u8>, FsError> {
+    let acl = PosixAcl::from_xattr(value)?.ok_or(FsError::InvalidArgument)?;
+    let mut disk = EXT4_ACL_VERSION.to_le_bytes().to_vec();
+    for entry in acl.entries() {
+        disk.extend_from_slice(&entry.tag.raw().to_le_bytes());
+        disk.extend_from_slice(&entry.perm.to_le_bytes());
+        if entry.tag.has_id() {
+            disk.extend_from_slice(&entry.id.to_le_bytes());
+        }
+    }
+    Ok(disk)
+}
+
+/// 磁盘格式的 ACL 转为 xattr 接口格式（`ext4_acl_from_disk`），格式损坏时返回 `IoError`
+fn acl_from_disk(disk: &[u8]) -> Result<Vec<u8>, FsError> {
+    if disk.len() < 4 || get_u32(disk, 0) != EXT4_ACL_VERSION {
+        return Err(FsError::IoError);
+    }
+    let mut entries = Vec::new();
+    let mut offset = 4;
+    while offset < disk.len() {
+        if offset + 4 > disk.len() {
+            return Err(FsError::IoError);
+        }
+        let tag = AclTag::from_raw(get_u16(disk, offset)).ok_or(FsError::IoError)?;
+        let perm = get_u16(disk, offset + 2);
+        let id = if tag.has_id() {
+            if offset + 8 > disk.len() {
+                return Err(FsError::IoError);
+            }
+            offset += 8;
+            get_u32(disk, offset - 4)
+        } else {
+            offset += 4;
+            0
+        };
+        entries.push(AclEntry::new(tag, perm, id));
+    }
+    if entries.is_empty() {
+        return Ok(POSIX_ACL_XATTR_VERSION.to_le_bytes().to_vec());
+    }
+    let acl = PosixAcl::from_entries(entries).map_err(|_| FsError::IoError)?;
+    Ok(acl.to_xattr())
+}
+
+/// inode 的全部属性（inode 内的在前）
+fn read_entries(
+    dev: &dyn BlockDevice,
+    geo: &Geometry,
+    inode: &RawInode,
+) -> Result<Vec<Entry>, FsError> {
+    let mut entries = inode.inline_entries()?;
+    let block = inode.file_acl();
+    if block != 0 {
+        let buf = read_block(dev, geo, block)?;
+        entries.extend(parse_entries(&buf, BLOCK_HEADER_SIZE)?);
+    }
+    Ok(entries)
+}
+
+/// 读取属性值
+pub(super) fn get(fs: &Ext4, ino: u32, name: &str) -> Result<Vec<u8>, FsError> {
+    let (index, suffix) = encode_name(name).ok_or(FsError::NoData)?;
+    let dev = fs.block_device.as_ref();
+    let geo = Geometry::read(dev)?;
+    let inode = RawInode::read(dev, &geo, ino);
+    let value = read_entries(dev, &geo, &inode)?
+        .into_iter()
+        .find(|e| e.index == index && e.name == suffix)
+        .map(|e| e.value)
+        .ok_or(FsError::NoData)?;
+    if is_acl_index(index) {
+        return acl_from_disk(&value);
+    }
+    Ok(value)
+}
+
+/// 列出全部属性名
+pub(super) fn list(fs: &Ext4, ino: u32) -> Result<Vec<String>, FsError> {
+    let dev = fs.block_device.as_ref();
+    let geo = Geometry::read(dev)?;
+    let inode = RawInode::read(dev, &geo, ino);
+    Ok(read_entries(dev, &geo, &inode)?
+        .iter()
+        .filter_map(Entry::full_name)
+        .collect())
+}
+
+/// 设置（`value` 为 `Some`）或删除（`value` 为 `None`）属性
+pub(super) fn set(
+    fs: &Ext4,
+    ino: u32,
+    name: &str,
+    value: Option<&[u8]>,
+    flags: XattrFlags,
+) -> Result<(), FsError> {
+    let (index, suffix) = encode_name(name).ok_or(FsError::NotSupported)?;
+    let value = match value {
+        Some(value) if is_acl_index(index) => Some(acl_to_disk(value)?),
+        value => value.map(<[u8]>::to_vec),
+    };
+    let dev = fs.block_device.as_ref();
+    let geo = Geometry::read(dev)?;
+    if !geo.ext_attr {
+        return Err(FsError::NotSupported);
+    }
+
+    let inode = RawInode::read(dev, &geo, ino);
+    let old_block = inode.file_acl();
+    let old_blocks = inode.blocks();
+    let mut entries = read_entries(dev, &geo, &inode)?;
+    let pos = entries
+        .iter()
+        .position(|e| e.index == index && e.name == suffix);
+    match (value, pos) {
+        (Some(value), Some(pos)) => {
+            check_xattr_flags(true, flags)?;
+            entries[pos].value = value;
+        }
+        (Some(value), None) => {
+            check_xattr_flags(false, flags)?;
+            entries.push(Entry {
+                index,
+                name: suffix.to_vec(),
+                value,
+            });
+        }
+        (None, Some(pos)) => {
+            entries.remove(pos);
+        }
+        (None, None) => return Err(FsError::NoData),
+    }
+    entries.sort_by(|a, b| (a.index, a.name.len(), &a.name).cmp(&(b.index, b.name.len(), &b.name)));
+
+    // 依次尽量放进 inode 内，放不下的进属性块
+    let inline_capacity = inode.inline_area().map_or(0, |(start, end)| end - start);
+    let mut inline = Vec::new();
+    let mut in_block = Vec::new();
+    let mut inline_used = 4;
+    for entry in &entries {
+        let size = entry.entry_size() + entry.value_size();
+        if inline_used + size <= inline_capacity {
+            inline_used += size;
+            inline.push(entry);
+        } else {
+            in_block.push(entry);
+        }
+    }
+    if !in_block.is_empty() && BLOCK_HEADER_SIZE + space_needed(&in_block) > geo.block_size {
+        return Err(FsError::NoSpace);
+    }
+
+    let old_refcount = if old_block != 0 {
+        get_u32(&read_block(dev, &geo, old_block)?, 4)
+    } else {
+        0
+    };
+
+    // 只被自己引用的旧块原地改写，否则分配新块
+    let new_block = if in_block.is_empty() {
+        0
+    } else if old_block != 0 && old_refcount == 1 {
+        old_block
+    } else {
+        let mut inode_ref = fs.get_inode_ref(ino);
+        let block = fs
+            .balloc_alloc_block(&mut inode_ref, None)
+            .map_err(|_| FsError::NoSpace)?;
+        fs.write_back_inode(&mut inode_ref);
+        block
+    };
+
+    if new_block != 0 {
+        let mut buf = vec![0u8; geo.block_size];
+        put_u32(&mut buf, 0, XATTR_MAGIC);
+        put_u32(&mut buf, 4, 1);
+        put_u32(&mut buf, 8, 1);
+        write_entries(&mut buf, BLOCK_HEADER_SIZE, &in_block);
+        let hash = in_block.iter().try_fold(0u32, |hash, e| {
+            let e_hash = e.hash();
+            (e_hash != 0).then_some((hash << 16) ^ (hash >> 16) ^ e_hash)
+        });
+        put_u32(&mut buf, 0x0C, hash.unwrap_or(0));
+        write_block(dev, &geo, new_block, &mut buf);
+    }
+
+    // ext4_rs 分配块时可能写回了 inode，重新读取后再修改；属性块计入 i_blocks，
+    // 这里以调用 ext4_rs 之前的值为准统一结算
+    let mut inode = RawInode::read(dev, &geo, ino);
+    let per_block = inode.blocks_per_fs_block(&geo);
+    let mut blocks = old_blocks;
+    if new_block != 0 && new_block != old_block {
+        blocks += per_block;
+    }
+    if old_block != 0 && old_block != new_block {
+        blocks = blocks.saturating_sub(per_block);
+    }
+    inode.set_blocks(blocks);
+    inode.set_inline_entries(&inline);
+    inode.set_file_acl(new_block);
+    inode.touch_ctime();
+    inode.write(dev, &geo);
+
+    // inode 不再指向旧块后释放自己持有的引用
+    if old_block != 0 && old_block != new_block {
+        if old_refcount > 1 {
+            let mut buf = read_block(dev, &geo, old_block)?;
+            put_u32(&mut buf, 4, old_refcount - 1);
+            write_block(dev, &geo, old_block, &mut buf);
+        } else {
+            let mut inode_ref = fs.get_inode_ref(ino);
+            fs.balloc_free_blocks(&mut inode_ref, old_block, 1);
+            fs.write_back_inode(&mut inode_ref);
+            // ext4_rs 释放块时可能改动了 i_blocks，恢复为上面结算的值
+            let mut inode = RawInode::read(dev, &geo, ino);
+            inode.set_blocks(blocks);
+            inode.write(dev, &geo);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(index: u8, name: &str, value: &[u8]) -> Entry {
+        Entry {
+            index,
+            name: name.as_bytes().to_vec(),
+            value: value.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_crc32c() {
+        // 标准 CRC-32C 是首尾取反后的结果
+        assert_eq!(!crc32c(!0, b"123456789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn test_entries_round_trip() {
+        let a = entry(1, "mime_type", b"text/plain");
+        let b = entry(4, "overlay.opaque", b"y");
+        let c = entry(6, "selinux", b"");
+        let entries = [&a, &b, &c];
+
+        let mut buf = vec![0u8; 256];
+        assert!(BLOCK_HEADER_SIZE + space_needed(&entries) <= buf.len());
+        write_entries(&mut buf, BLOCK_HEADER_SIZE, &entries);
+
+        let parsed = parse_entries(&buf, BLOCK_HEADER_SIZE).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].full_name().unwrap(), "user.mime_type");
+        assert_eq!(parsed[0].value, b"text/plain");
+        assert_eq!(parsed[1].full_name().unwrap(), "trusted.overlay.opaque");
+        assert_eq!(parsed[2].full_name().unwrap(), "security.selinux");
+        assert!(parsed[2].value.is_empty());
+    }
+
+    #[test]
+    fn test_acl_disk_format() {
+        let acl = PosixAcl::from_entries(vec![
+            AclEntry::new(AclTag::UserObj, 6, 0),
+            AclEntry::new(AclTag::User, 4, 1000),
+            AclEntry::new(AclTag::GroupObj, 4, 0),
+            AclEntry::new(AclTag::Mask, 4, 0),
+            AclEntry::new(AclTag::Other, 0, 0),
+        ])
+        .unwrap();
+        let disk = acl_to_disk(&acl.to_xattr()).unwrap();
+        // 头部 4 字节，指定用户 8 字节，其余各 4 字节
+        assert_eq!(disk.len(), 4 + 8 + 4 * 4);
+        assert_eq!(get_u32(&disk, 0), EXT4_ACL_VERSION);
+        assert_eq!(acl_from_disk(&disk).unwrap(), acl.to_xattr());
+        assert_eq!(acl_from_disk(&disk[..10]), Err(FsError::IoError));
+        assert_eq!(
+            entry(2, "", b"").full_name().unwrap(),
+            "system.posix_acl_access"
+        );
+    }
+
+    #[test]
+    fn test_encode_name() {
+        assert_eq!(encode_name("user.a"), Some((1, &b"a"[..])));
+        assert_eq!(encode_name("system.data"), Some((7, &b"data"[..])));
+        assert_eq!(encode_name("system.posix_acl_access"), Some((2, &b""[..])));
+        assert_eq!(encode_name("system.posix_acl_default"), Some((3, &b""[..])));
+        assert_eq!(encode_name("user."), None);
+        assert_eq!(encode_name("other.a"), None);
+    }
+}
@@ -7,7 +7,9 @@
 //! - **[tmpfs](tmpfs)**: 临时文件系统(纯内存)
 //! - **[procfs](proc)**: 进程信息伪文件系统
 //! - **[sysfs](sysfs)**: 系统设备伪文件系统
+//! - **[devpts](devpts)**: 伪终端从设备文件系统（`/dev/pts`）
 //! - **[ext4]**: Linux Ext4文件系统
+//! - **[overlay]**: 联合挂载文件系统（可写上层叠加只读下层）
 //!
 //! ## 与运行时解耦（FsOps）
 //!
@@ -23,16 +25,21 @@
 
 extern crate alloc;
 
+pub mod devpts;
 pub mod ext4;
 pub mod ops;
+pub mod overlay;
 pub mod proc;
 pub mod sysfs;
 pub mod tmpfs;
 
+pub use devpts::{DevPtsFs, DevPtsInode};
 pub use ext4::{BlockDeviceAdapter, Ext4FileSystem, Ext4Inode};
 pub use ops::{
-    FsOps, MemoryAreaInfo, MountInfo, TaskInfo, TaskState, VmStats, fs_ops, register_fs_ops,
+    FsOps, IdMapKind, MemoryAreaInfo, MountInfo, SmapsInfo, TaskInfo, TaskState, VmStats, fs_ops,
+    register_fs_ops,
 };
+pub use overlay::{OverlayFs, OverlayInode};
 pub use proc::{ContentGenerator, ProcFS, ProcInode, ProcInodeContent};
 pub use sysfs::{SysFS, find_block_device, find_net_device};
 pub use tmpfs::{TmpFs, TmpfsInode};
@@ -8,8 +8,9 @@
 //!
 //! - 配置：页大小、ext4 块大小、镜像大小、块设备扇区大小
 //! - 时间：`timespec_now()`
-//! - 任务/进程信息：供 procfs 生成 `/proc/[pid]/*`
+//! - 任务/进程信息：供 procfs 生成 `/proc/[pid]/*`，读写用户命名空间映射
 //! - 系统信息：供 procfs 生成 `/proc/meminfo`、`/proc/uptime`、`/proc/mounts` 等
+//! - 审计：供 procfs 生成 `/proc/audit`、读写 `/proc/audit_rules`
 //!
 //! ## 注册与生命周期
 //!
@@ -21,6 +22,7 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use uapi::time::TimeSpec;
+use vfs::FsError;
 
 /// FS 运行时操作
 ///
@@ -73,6 +75,17 @@ pub trait FsOps: Send + Sync {
 
     /// 获取挂载点列表
     fn list_mounts(&self) -> Vec<MountInfo>;
+
+    // ========== 审计（procfs 需要）==========
+
+    /// 获取审计记录（/proc/audit 格式）
+    fn audit_log(&self) -> Vec<u8>;
+
+    /// 获取审计规则（/proc/audit_rules 格式）
+    fn audit_rules(&self) -> Vec<u8>;
+
+    /// 执行审计规则命令
+    fn set_audit_rules(&self, cmds: &[u8]) -> Result<(), FsError>;
 }
 
 /// 挂载点信息（用于 /proc/mounts）
@@ -159,6 +172,9 @@ pub trait TaskInfo: Send + Sync {
     /// 获取内存区域信息（用于 `/proc/[pid]/maps`）
     fn memory_areas(&self) -> Vec<MemoryAreaInfo>;
 
+    /// 获取内存区域及其内存占用（用于 `/proc/[pid]/smaps`）
+    fn smaps(&self) -> Vec<SmapsInfo>;
+
     /// 获取用户态 CPU 时间（时钟滴答数）
     fn utime(&self) -> u64;
 
@@ -170,6 +186,30 @@ pub trait TaskInfo: Send + Sync {
 
     /// 获取启动时间（时钟滴答数）
     fn start_time(&self) -> u64;
+
+    /// 读取所属用户命名空间的 ID 映射（`/proc/[pid]/uid_map`、`gid_map`）
+    fn id_map(&self, kind: IdMapKind) -> Vec<u8>;
+
+    /// 由当前任务写入所属用户命名空间的 ID 映射
+    fn write_id_map(&self, kind: IdMapKind, data: &[u8]) -> Result<(), FsError>;
+
+    /// 读取 OOM badness 调整值（`/proc/[pid]/oom_score_adj`）
+    fn oom_score_adj(&self) -> i32;
+
+    /// 由当前任务设置 OOM badness 调整值
+    fn set_oom_score_adj(&self, adj: i32) -> Result<(), FsError>;
+
+    /// 获取 OOM 分数（`/proc/[pid]/oom_score`，0 到 1000）
+    fn oom_score(&self) -> usize;
+}
+
+/// 用户命名空间 ID 映射种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdMapKind {
+    /// `/proc/[pid]/uid_map`
+    Uid,
+    /// `/proc/[pid]/gid_map`
+    Gid,
 }
 
 /// 虚拟内存统计信息
@@ -231,6 +271,27 @@ pub struct MemoryAreaInfo {
     pub path: Option<String>,
 }
 
+/// 内存区域的内存占用（用于 procfs `/proc/[pid]/smaps`）
+#[derive(Clone)]
+pub struct SmapsInfo {
+    /// 区域信息，与 `/proc/[pid]/maps` 中的一行相同
+    pub area: MemoryAreaInfo,
+    /// 常驻内存（字节）
+    pub rss_bytes: usize,
+    /// 按映射数均摊后的常驻内存（字节）
+    pub pss_bytes: usize,
+    /// 共享的干净页（字节）
+    pub shared_clean_bytes: usize,
+    /// 共享的脏页（字节）
+    pub shared_dirty_bytes: usize,
+    /// 私有的干净页（字节）
+    pub private_clean_bytes: usize,
+    /// 私有的脏页（字节）
+    pub private_dirty_bytes: usize,
+    /// 已换出（字节）
+    pub swap_bytes: usize,
+}
+
 // ========== FsOps 注册 ==========
 
 static FS_OPS_DATA: AtomicUsize = AtomicUsize::new(0);
@@ -278,6 +339,7 @@ mod test_mock {
     use alloc::sync::Arc;
     use alloc::vec::Vec;
     use uapi::time::TimeSpec;
+    use vfs::FsError;
 
     impl FsOps for test_support::mock::fs::MockFsOps {
         fn page_size(&self) -> usize {
@@ -331,6 +393,18 @@ mod test_mock {
         fn list_mounts(&self) -> Vec<MountInfo> {
             Vec::new()
         }
+
+        fn audit_log(&self) -> Vec<u8> {
+            Vec::new()
+        }
+
+        fn audit_rules(&self) -> Vec<u8> {
+            Vec::new()
+        }
+
+        fn set_audit_rules(&self, _cmds: &[u8]) -> Result<(), FsError> {
+            Err(FsError::NotSupported)
+        }
     }
 
     #[test]
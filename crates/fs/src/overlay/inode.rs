@@ -0,0 +1,512 @@
+//! Overlayfs Inode 实现
+//!
+//! 每个 [`OverlayInode`] 记录同一路径在上下两层中的 inode。父目录通过弱引用缓存已查找的子项，
+//! 保证同一路径同时只有一个存活的 `OverlayInode`，复制上升后所有持有者都能看到上层 inode。
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use sync::SpinLock;
+use uapi::time::TimeSpec;
+use vfs::{DirEntry, FileMode, FsError, Inode, InodeMetadata, InodeType, XattrFlags};
+
+/// 不透明目录的扩展属性，值为 `y` 时上层目录不与下层同名目录合并
+const OPAQUE_XATTR: &str = "trusted.overlay.opaque";
+
+/// 上层不支持扩展属性时代替 [`OPAQUE_XATTR`] 的标记文件名
+const OPAQUE_MARKER: &str = ".wh..wh..opq";
+
+/// overlayfs 自己使用的扩展属性前缀，对外隐藏
+const PRIVATE_XATTR_PREFIX: &str = "trusted.overlay.";
+
+/// 复制上升时每次搬运的字节数
+const COPY_CHUNK: usize = 4096;
+
+/// Overlayfs Inode
+pub struct OverlayInode {
+    /// 上层 inode，复制上升之前为 `None`
+    upper: SpinLock<Option<Arc<dyn Inode>>>,
+
+    /// 下层 inode；上层是非目录或不透明目录时为 `None`
+    lower: Option<Arc<dyn Inode>>,
+
+    /// 父目录与在其中的名字，根目录为 `None`；重命名时更新
+    link: SpinLock<Option<(Arc<OverlayInode>, String)>>,
+
+    /// 已查找过的子项（弱引用，避免循环引用）
+    children: SpinLock<BTreeMap<String, Weak<OverlayInode>>>,
+
+    /// 指向自身的弱引用
+    this: Weak<OverlayInode>,
+}
+
+impl OverlayInode {
+    fn new(
+        upper: Option<Arc<dyn Inode>>,
+        lower: Option<Arc<dyn Inode>>,
+        link: Option<(Arc<OverlayInode>, String)>,
+    ) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            upper: SpinLock::new(upper),
+            lower,
+            link: SpinLock::new(link),
+            children: SpinLock::new(BTreeMap::new()),
+            this: this.clone(),
+        })
+    }
+
+    /// 创建根目录
+    pub fn new_root(lower: Arc<dyn Inode>, upper: Arc<dyn Inode>) -> Arc<Self> {
+        let lower = if is_opaque(&upper) { None } else { Some(lower) };
+        Self::new(Some(upper), lower, None)
+    }
+
+    fn this(&self) -> Result<Arc<OverlayInode>, FsError> {
+        self.this.upgrade().ok_or(FsError::IoError)
+    }
+
+    fn upper(&self) -> Option<Arc<dyn Inode>> {
+        self.upper.lock().clone()
+    }
+
+    /// 当前可见的 inode：复制上升后为上层，否则为下层
+    fn real(&self) -> Arc<dyn Inode> {
+        match self.upper() {
+            Some(upper) => upper,
+            None => self
+                .lower
+                .clone()
+                .expect("overlay: inode has neither upper nor lower"),
+        }
+    }
+
+    /// 确保上层存在对应的 inode 并返回它（复制上升）
+    ///
+    /// 先递归复制上升父目录，再按类型在上层重建本节点，复制文件数据与属性。
+    fn copy_up(&self) -> Result<Arc<dyn Inode>, FsError> {
+        let mut upper = self.upper.lock();
+        if let Some(upper) = upper.as_ref() {
+            return Ok(upper.clone());
+        }
+
+        let (parent, name) = self.link.lock().clone().ok_or(FsError::IoError)?;
+        let dir = parent.copy_up()?;
+        let lower = self.lower.as_ref().ok_or(FsError::IoError)?;
+        let meta = lower.metadata()?;
+        let copied = match meta.inode_type {
+            InodeType::Directory => dir.mkdir(&name, meta.mode)?,
+            InodeType::Symlink => dir.symlink(&name, &lower.readlink()?)?,
+            InodeType::File => {
+                let copied = dir.create(&name, meta.mode)?;
+                copy_data(lower.as_ref(), copied.as_ref(), meta.size)?;
+                copied
+            }
+            _ => dir.mknod(&name, meta.mode, meta.rdev)?,
+        };
+
+        // 上层文件系统不支持的属性忽略
+        let _ = copied.chown(meta.uid, meta.gid);
+        let _ = copied.set_times(Some(meta.atime), Some(meta.mtime));
+        for name in lower.listxattr().unwrap_or_default() {
+            if name.starts_with(PRIVATE_XATTR_PREFIX) {
+                continue;
+            }
+            if let Ok(value) = lower.getxattr(&name) {
+                let _ = copied.setxattr(&name, &value, XattrFlags::empty());
+            }
+        }
+
+        *upper = Some(copied.clone());
+        Ok(copied)
+    }
+
+    /// 记录子项；并发查找时以先记录的为准
+    fn remember(&self, name: &str, child: Arc<OverlayInode>) -> Arc<OverlayInode> {
+        let mut children = self.children.lock();
+        if let Some(existing) = children.get(name).and_then(Weak::upgrade) {
+            return existing;
+        }
+        children.retain(|_, c| c.strong_count() > 0);
+        children.insert(String::from(name), Arc::downgrade(&child));
+        child
+    }
+
+    /// 查找可见的子项
+    fn child(&self, name: &str) -> Result<Arc<OverlayInode>, FsError> {
+        if name == OPAQUE_MARKER {
+            return Err(FsError::NotFound);
+        }
+        if let Some(child) = self.children.lock().get(name).and_then(Weak::upgrade) {
+            return Ok(child);
+        }
+
+        let upper = match self.upper() {
+            Some(dir) => lookup_optional(dir.as_ref(), name)?,
+            None => None,
+        };
+        let upper_is_dir = match &upper {
+            Some(inode) if is_whiteout(inode) => return Err(FsError::NotFound),
+            Some(inode) => inode.metadata()?.inode_type == InodeType::Directory,
+            None => false,
+        };
+
+        // 上层的非目录和不透明目录遮住下层，上层目录只与下层目录合并
+        let merge = match &upper {
+            Some(inode) => upper_is_dir && !is_opaque(inode),
+            None => true,
+        };
+        let mut lower = match (&self.lower, merge) {
+            (Some(dir), true) => lookup_optional(dir.as_ref(), name)?,
+            _ => None,
+        };
+        if upper.is_some() {
+            let lower_is_dir = match &lower {
+                Some(inode) => inode.metadata()?.inode_type == InodeType::Directory,
+                None => false,
+            };
+            if !lower_is_dir {
+                lower = None;
+            }
+        }
+        if upper.is_none() && lower.is_none() {
+            return Err(FsError::NotFound);
+        }
+
+        let child = Self::new(upper, lower, Some((self.this()?, String::from(name))));
+        Ok(self.remember(name, child))
+    }
+
+    /// 下层目录中是否有 `name`，有则删除或移走上层项后需要白化文件遮住它
+    fn lower_has(&self, name: &str) -> Result<bool, FsError> {
+        match &self.lower {
+            Some(dir) => Ok(lookup_optional(dir.as_ref(), name)?.is_some()),
+            None => Ok(false),
+        }
+    }
+
+    /// 在上层创建子项
+    ///
+    /// 本目录先复制上升；`name` 处的白化文件先删除，创建失败时恢复。
+    /// `make` 的第二个参数表示 `name` 处原先是否有白化文件。
+    fn create_child<F>(&self, name: &str, make: F) -> Result<Arc<dyn Inode>, FsError>
+    where
+        F: FnOnce(&Arc<dyn Inode>, bool) -> Result<Arc<dyn Inode>, FsError>,
+    {
+        if name == OPAQUE_MARKER {
+            return Err(FsError::InvalidArgument);
+        }
+        match self.child(name) {
+            Ok(_) => return Err(FsError::AlreadyExists),
+            Err(FsError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+
+        let dir = self.copy_up()?;
+        let whiteout = match lookup_optional(dir.as_ref(), name)? {
+            Some(inode) if is_whiteout(&inode) => true,
+            Some(_) => return Err(FsError::AlreadyExists),
+            None => false,
+        };
+        if whiteout {
+            dir.unlink(name)?;
+        }
+
+        let upper = match make(&dir, whiteout) {
+            Ok(inode) => inode,
+            Err(e) => {
+                if whiteout {
+                    let _ = make_whiteout(dir.as_ref(), name);
+                }
+                return Err(e);
+            }
+        };
+        let child = Self::new(Some(upper), None, Some((self.this()?, String::from(name))));
+        Ok(self.remember(name, child))
+    }
+
+    /// 删除子项：删除上层的项，下层也有时留下白化文件
+    fn remove_child(&self, name: &str, is_dir: bool) -> Result<(), FsError> {
+        let child = self.child(name)?;
+        let child_is_dir = child.real().metadata()?.inode_type == InodeType::Directory;
+        match (is_dir, child_is_dir) {
+            (true, false) => return Err(FsError::NotDirectory),
+            (false, true) => return Err(FsError::IsDirectory),
+            _ => {}
+        }
+        if is_dir
+            && child
+                .readdir()?
+                .iter()
+                .any(|entry| entry.name != "." && entry.name != "..")
+        {
+            return Err(FsError::DirectoryNotEmpty);
+        }
+
+        let dir = self.copy_up()?;
+        if let Some(upper) = child.upper() {
+            if is_dir {
+                // 合并视图为空时，上层目录里只剩白化文件和不透明标记
+                for entry in upper.readdir()? {
+                    if entry.name != "." && entry.name != ".." {
+                        upper.unlink(&entry.name)?;
+                    }
+                }
+                dir.rmdir(name)?;
+            } else {
+                dir.unlink(name)?;
+            }
+        }
+        if self.lower_has(name)? {
+            make_whiteout(dir.as_ref(), name)?;
+        }
+
+        self.children.lock().remove(name);
+        Ok(())
+    }
+}
+
+impl Inode for OverlayInode {
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        let mut meta = self.real().metadata()?;
+        // 复制上升前后 inode 号保持不变
+        if let Some(lower) = &self.lower {
+            meta.inode_no = lower.metadata()?.inode_no;
+        }
+        Ok(meta)
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        self.real().read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize, FsError> {
+        self.copy_up()?.write_at(offset, buf)
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        Ok(self.child(name)?)
+    }
+
+    fn create(&self, name: &str, mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+        self.create_child(name, |dir, _| dir.create(name, mode))
+    }
+
+    fn mkdir(&self, name: &str, mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+        self.create_child(name, |dir, whiteout| {
+            let created = dir.mkdir(name, mode)?;
+            // 取代被删除的下层项，不能再与下层同名目录合并
+            if whiteout {
+                make_opaque(&created)?;
+            }
+            Ok(created)
+        })
+    }
+
+    fn symlink(&self, name: &str, target: &str) -> Result<Arc<dyn Inode>, FsError> {
+        self.create_child(name, |dir, _| dir.symlink(name, target))
+    }
+
+    fn link(&self, name: &str, target: &Arc<dyn Inode>) -> Result<(), FsError> {
+        let target = target
+            .downcast_ref::<OverlayInode>()
+            .ok_or(FsError::CrossDevice)?;
+        let upper = target.copy_up()?;
+        self.create_child(name, |dir, _| {
+            dir.link(name, &upper)?;
+            dir.lookup(name)
+        })?;
+        Ok(())
+    }
+
+    fn unlink(&self, name: &str) -> Result<(), FsError> {
+        self.remove_child(name, false)
+    }
+
+    fn rmdir(&self, name: &str) -> Result<(), FsError> {
+        self.remove_child(name, true)
+    }
+
+    fn rename(
+        &self,
+        old_name: &str,
+        new_parent: Arc<dyn Inode>,
+        new_name: &str,
+    ) -> Result<(), FsError> {
+        let new_parent = new_parent
+            .downcast_arc::<OverlayInode>()
+            .map_err(|_| FsError::CrossDevice)?;
+        let child = self.child(old_name)?;
+
+        // 没有目录重定向：含下层内容的目录不能改名，目录也不能移到下层有同名项的位置
+        let child_is_dir = child.real().metadata()?.inode_type == InodeType::Directory;
+        if child_is_dir && (child.lower.is_some() || new_parent.lower_has(new_name)?) {
+            return Err(FsError::CrossDevice);
+        }
+
+        child.copy_up()?;
+        let new_dir = new_parent.copy_up()?;
+        let old_dir = self.copy_up()?;
+        // 新名字处的上层项（包括白化文件）被覆盖
+        old_dir.rename(old_name, new_dir, new_name)?;
+        if self.lower_has(old_name)? {
+            make_whiteout(old_dir.as_ref(), old_name)?;
+        }
+
+        self.children.lock().remove(old_name);
+        *child.link.lock() = Some((new_parent.clone(), new_name.to_string()));
+        new_parent
+            .children
+            .lock()
+            .insert(new_name.to_string(), Arc::downgrade(&child));
+        Ok(())
+    }
+
+    fn readdir(&self) -> Result<Vec<DirEntry>, FsError> {
+        let mut entries = Vec::new();
+        let mut seen = BTreeSet::new();
+
+        if let Some(dir) = self.upper() {
+            for entry in dir.readdir()? {
+                if entry.name == OPAQUE_MARKER {
+                    continue;
+                }
+                let hidden = entry.inode_type == InodeType::CharDevice
+                    && lookup_optional(dir.as_ref(), &entry.name)?
+                        .is_some_and(|inode| is_whiteout(&inode));
+                seen.insert(entry.name.clone());
+                if !hidden {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        // "." 与 ".." 以及上层已有的名字不再重复
+        if let Some(dir) = &self.lower {
+            for entry in dir.readdir()? {
+                if !seen.contains(&entry.name) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn truncate(&self, size: usize) -> Result<(), FsError> {
+        self.copy_up()?.truncate(size)
+    }
+
+    fn sync(&self) -> Result<(), FsError> {
+        match self.upper() {
+            Some(upper) => upper.sync(),
+            None => Ok(()),
+        }
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    fn set_times(&self, atime: Option<TimeSpec>, mtime: Option<TimeSpec>) -> Result<(), FsError> {
+        self.copy_up()?.set_times(atime, mtime)
+    }
+
+    fn readlink(&self) -> Result<String, FsError> {
+        self.real().readlink()
+    }
+
+    fn mknod(&self, name: &str, mode: FileMode, dev: u64) -> Result<Arc<dyn Inode>, FsError> {
+        self.create_child(name, |dir, _| dir.mknod(name, mode, dev))
+    }
+
+    fn chown(&self, uid: u32, gid: u32) -> Result<(), FsError> {
+        self.copy_up()?.chown(uid, gid)
+    }
+
+    fn chmod(&self, mode: FileMode) -> Result<(), FsError> {
+        self.copy_up()?.chmod(mode)
+    }
+
+    fn getxattr(&self, name: &str) -> Result<Vec<u8>, FsError> {
+        if name.starts_with(PRIVATE_XATTR_PREFIX) {
+            return Err(FsError::NoData);
+        }
+        self.real().getxattr(name)
+    }
+
+    fn setxattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> Result<(), FsError> {
+        if name.starts_with(PRIVATE_XATTR_PREFIX) {
+            return Err(FsError::NotSupported);
+        }
+        self.copy_up()?.setxattr(name, value, flags)
+    }
+
+    fn listxattr(&self) -> Result<Vec<String>, FsError> {
+        let mut names = self.real().listxattr()?;
+        names.retain(|name| !name.starts_with(PRIVATE_XATTR_PREFIX));
+        Ok(names)
+    }
+
+    fn removexattr(&self, name: &str) -> Result<(), FsError> {
+        if name.starts_with(PRIVATE_XATTR_PREFIX) {
+            return Err(FsError::NotSupported);
+        }
+        // 属性不存在时不必复制上升
+        self.getxattr(name)?;
+        self.copy_up()?.removexattr(name)
+    }
+}
+
+/// 在目录中查找 `name`，不存在时返回 `None`
+fn lookup_optional(dir: &dyn Inode, name: &str) -> Result<Option<Arc<dyn Inode>>, FsError> {
+    match dir.lookup(name) {
+        Ok(inode) => Ok(Some(inode)),
+        Err(FsError::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// 白化文件是设备号为 0/0 的字符设备
+fn is_whiteout(inode: &Arc<dyn Inode>) -> bool {
+    inode
+        .metadata()
+        .is_ok_and(|meta| meta.inode_type == InodeType::CharDevice && meta.rdev == 0)
+}
+
+/// 上层目录带有不透明标记（扩展属性或标记文件）
+fn is_opaque(dir: &Arc<dyn Inode>) -> bool {
+    dir.getxattr(OPAQUE_XATTR).is_ok_and(|value| value == b"y") || dir.lookup(OPAQUE_MARKER).is_ok()
+}
+
+/// 把上层目录标记为不透明，上层不支持扩展属性时退回标记文件
+fn make_opaque(dir: &Arc<dyn Inode>) -> Result<(), FsError> {
+    match dir.setxattr(OPAQUE_XATTR, b"y", XattrFlags::empty()) {
+        Err(FsError::NotSupported) => dir.create(OPAQUE_MARKER, FileMode::empty()).map(|_| ()),
+        result => result,
+    }
+}
+
+/// 在上层目录 `dir` 中创建遮住下层 `name` 的白化文件
+fn make_whiteout(dir: &dyn Inode, name: &str) -> Result<(), FsError> {
+    dir.mknod(name, FileMode::S_IFCHR, 0)?;
+    Ok(())
+}
+
+/// 把 `src` 的前 `size` 字节复制到 `dst`
+fn copy_data(src: &dyn Inode, dst: &dyn Inode, size: usize) -> Result<(), FsError> {
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut offset = 0;
+    while offset < size {
+        let len = COPY_CHUNK.min(size - offset);
+        let n = src.read_at(offset, &mut buf[..len])?;
+        if n == 0 {
+            break;
+        }
+        dst.write_at(offset, &buf[..n])?;
+        offset += n;
+    }
+    Ok(())
+}
@@ -0,0 +1,21 @@
+//! Overlayfs - 联合挂载文件系统
+//!
+//! 把一个可写的上层目录（通常是 tmpfs）叠加在只读的下层目录（如 ext4 根文件系统镜像）之上，
+//! 对外呈现两者合并后的目录树，下层永远不会被修改：
+//!
+//! - 查找时上层优先；上层与下层的同名目录合并为一个目录
+//! - 写入下层文件（包括修改属性）前先把它连同所有祖先目录复制到上层（复制上升）
+//! - 删除只存在于下层的项时在上层留下白化文件（whiteout，设备号为 0/0 的字符设备），遮住下层
+//! - 在白化文件处新建的目录带有不透明标记（`trusted.overlay.opaque` 扩展属性；上层不支持
+//!   扩展属性时退回 `.wh..wh..opq` 文件，沿用 OCI 镜像层的约定），不再与下层同名目录合并
+//! - 扩展属性读自当前可见的一层，修改时先复制上升；`trusted.overlay.*` 对外隐藏
+//! - readdir 合并两层内容，过滤白化文件与不透明标记
+//!
+//! 与 Linux 的差异：只支持一个下层；含有下层内容的目录不能重命名（相当于未开启
+//! `redirect_dir`，返回 `EXDEV`）；复制上升时属性按尽力复制，上层不支持的属性被忽略。
+
+mod inode;
+mod overlay;
+
+pub use inode::OverlayInode;
+pub use overlay::OverlayFs;
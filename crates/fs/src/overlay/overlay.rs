@@ -0,0 +1,56 @@
+//! Overlayfs 文件系统实现
+
+use alloc::sync::Arc;
+
+use vfs::{FileSystem, FsError, Inode, InodeType, StatFs};
+
+use super::inode::OverlayInode;
+
+/// Overlayfs 文件系统
+pub struct OverlayFs {
+    /// 合并后的根目录
+    root: Arc<OverlayInode>,
+
+    /// 上层目录所在的文件系统，统计信息与同步都以上层为准
+    upper_fs: Arc<dyn FileSystem>,
+}
+
+impl OverlayFs {
+    /// 以 `lower` 目录为下层、`upper` 目录为上层创建 overlayfs
+    ///
+    /// `upper` 必须是 `upper_fs` 中的目录；`lower` 只会被读取。
+    pub fn new(
+        lower: Arc<dyn Inode>,
+        upper: Arc<dyn Inode>,
+        upper_fs: Arc<dyn FileSystem>,
+    ) -> Result<Arc<Self>, FsError> {
+        if lower.metadata()?.inode_type != InodeType::Directory
+            || upper.metadata()?.inode_type != InodeType::Directory
+        {
+            return Err(FsError::NotDirectory);
+        }
+
+        Ok(Arc::new(Self {
+            root: OverlayInode::new_root(lower, upper),
+            upper_fs,
+        }))
+    }
+}
+
+impl FileSystem for OverlayFs {
+    fn fs_type(&self) -> &'static str {
+        "overlay"
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sync(&self) -> Result<(), FsError> {
+        self.upper_fs.sync()
+    }
+
+    fn statfs(&self) -> Result<StatFs, FsError> {
+        self.upper_fs.statfs()
+    }
+}
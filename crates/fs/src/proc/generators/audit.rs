@@ -0,0 +1,30 @@
+//! /proc/audit 与 /proc/audit_rules 生成器
+
+use alloc::vec::Vec;
+
+use crate::ops::fs_ops;
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// `/proc/audit` 内容生成器：列出审计缓冲区中的记录。
+pub struct AuditGenerator;
+
+impl ContentGenerator for AuditGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        Ok(fs_ops().audit_log())
+    }
+}
+
+/// `/proc/audit_rules` 内容生成器：读出规则，写入 `add` / `del` / `clear` 命令。
+pub struct AuditRulesGenerator;
+
+impl ContentGenerator for AuditRulesGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        Ok(fs_ops().audit_rules())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        fs_ops().set_audit_rules(data)?;
+        Ok(data.len())
+    }
+}
@@ -0,0 +1,35 @@
+//! /proc/buddyinfo 生成器
+//!
+//! 与 Linux 格式相同：每个有内存的区域一行，给出伙伴系统各阶（从 0 阶开始）空闲块的数量，
+//! 用于观察碎片化程度。
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// `/proc/buddyinfo` 内容生成器。
+pub struct BuddyinfoGenerator;
+
+impl ContentGenerator for BuddyinfoGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let mut out = String::new();
+        for zone in mm::frame_allocator::get_zone_info() {
+            if zone.present == 0 {
+                continue;
+            }
+            out.push_str(&format!(
+                "Node {}, zone {:>8} ",
+                zone.node,
+                zone.zone.name()
+            ));
+            for count in zone.free_blocks {
+                out.push_str(&format!("{:>7}", count));
+            }
+            out.push('\n');
+        }
+        Ok(out.into_bytes())
+    }
+}
@@ -0,0 +1,44 @@
+//! /proc/sys/kernel/dynamic_debug 生成器
+//!
+//! 按目标（子系统）打开或关闭调试日志，语法仿照 Linux 的 dynamic_debug/control：
+//! - 读出每个已登记目标一行：打开时为 `<target> =p`，关闭时为 `<target> =_`；
+//! - 写入若干行 `<target> +p` 或 `<target> -p`，`*` 表示所有已登记的目标。
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// `/proc/sys/kernel/dynamic_debug` 内容生成器。
+pub struct DynamicDebugGenerator;
+
+impl ContentGenerator for DynamicDebugGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let mut out = Vec::new();
+        klog::for_each_target(|name, debug| {
+            let flag = if debug { "p" } else { "_" };
+            out.extend_from_slice(format!("{} ={}\n", name, flag).as_bytes());
+        });
+        Ok(out)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        let text = core::str::from_utf8(data).map_err(|_| FsError::InvalidArgument)?;
+        for line in text.lines() {
+            let mut words = line.split_ascii_whitespace();
+            let Some(target) = words.next() else {
+                continue;
+            };
+            let enable = match (words.next(), words.next()) {
+                (Some("+p"), None) => true,
+                (Some("-p"), None) => false,
+                _ => return Err(FsError::InvalidArgument),
+            };
+            if !klog::set_target_debug(target, enable) {
+                return Err(FsError::NoSpace);
+            }
+        }
+        Ok(data.len())
+    }
+}
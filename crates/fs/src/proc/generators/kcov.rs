@@ -0,0 +1,18 @@
+//! /proc/kcov 生成器
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::proc::inode::ContentGenerator;
+use vfs::FsError;
+
+/// `/proc/kcov` 内容生成器（覆盖率计数点报告）。
+pub struct KcovGenerator;
+
+impl ContentGenerator for KcovGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let mut content = String::new();
+        test_support::coverage::write_report(&mut content).map_err(|_| FsError::IoError)?;
+        Ok(content.into_bytes())
+    }
+}
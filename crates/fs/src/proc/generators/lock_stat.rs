@@ -0,0 +1,48 @@
+//! /proc/lock_stat 生成器
+//!
+//! 每个已使用的锁类一行：获取次数、需要等待的获取次数与单次获取的最长自旋周期数；
+//! 写入 `0` 清零所有统计，与 Linux 的 `/proc/lock_stat` 相同。
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// `/proc/lock_stat` 内容生成器。
+pub struct LockStatGenerator;
+
+impl ContentGenerator for LockStatGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(
+            format!(
+                "{:<24} {:>16} {:>16} {:>16}\n",
+                "class name", "acquisitions", "contentions", "max-spin-cycles"
+            )
+            .as_bytes(),
+        );
+        sync::for_each_lock_stat(|class, stat| {
+            out.extend_from_slice(
+                format!(
+                    "{:<24} {:>16} {:>16} {:>16}\n",
+                    class.name(),
+                    stat.acquisitions,
+                    stat.contentions,
+                    stat.max_spin_cycles
+                )
+                .as_bytes(),
+            );
+        });
+        Ok(out)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        let text = core::str::from_utf8(data).map_err(|_| FsError::InvalidArgument)?;
+        if text.trim() != "0" {
+            return Err(FsError::InvalidArgument);
+        }
+        sync::clear_lock_stats();
+        Ok(data.len())
+    }
+}
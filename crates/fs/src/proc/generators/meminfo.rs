@@ -1,4 +1,6 @@
 //! /proc/meminfo 生成器
+//!
+//! 数据取自 [`mm::vmstat::snapshot`]，`HugePages_*` 取自 [`mm::hugetlb::stats`]；本内核没有对应机制的项（如 `Buffers`、`SwapCached`）输出 0。
 
 use alloc::{format, vec::Vec};
 
@@ -11,14 +13,18 @@ pub struct MeminfoGenerator;
 
 impl ContentGenerator for MeminfoGenerator {
     fn generate(&self) -> Result<Vec<u8>, FsError> {
-        let ops = fs_ops();
-        let page_size = ops.page_size();
-        let total_frames = ops.get_total_frames();
-        let free_frames = ops.get_free_frames();
+        let page_size = fs_ops().page_size();
+        let stat = mm::vmstat::snapshot();
+        let kb = |pages: usize| pages * page_size / 1024;
 
-        let total_kb = (total_frames * page_size) / 1024;
-        let free_kb = (free_frames * page_size) / 1024;
-        let available_kb = free_kb;
+        // 未被映射的干净页缓存页可以直接丢弃
+        let reclaimable_cache = stat
+            .file_pages
+            .saturating_sub(stat.file_mapped)
+            .saturating_sub(stat.file_dirty);
+        let available = stat.free_pages + reclaimable_cache;
+        let slab_kb = stat.slab_bytes.div_ceil(1024);
+        let huge = mm::hugetlb::stats();
 
         let content = format!(
             "MemTotal:       {:>8} kB
@@ -27,23 +33,43 @@ MemAvailable:   {:>8} kB
 Buffers:        {:>8} kB
 Cached:         {:>8} kB
 SwapCached:     {:>8} kB
-Active:         {:>8} kB
-Inactive:       {:>8} kB
-Active(anon):   {:>8} kB
-Inactive(anon): {:>8} kB
-Active(file):   {:>8} kB
-Inactive(file): {:>8} kB
-Unevictable:    {:>8} kB
-Mlocked:        {:>8} kB
 SwapTotal:      {:>8} kB
 SwapFree:       {:>8} kB
 Dirty:          {:>8} kB
-Writeback:      {:>8} kB
 AnonPages:      {:>8} kB
 Mapped:         {:>8} kB
-Shmem:          {:>8} kB
+Slab:           {:>8} kB
+SReclaimable:   {:>8} kB
+SUnreclaim:     {:>8} kB
+VmallocUsed:    {:>8} kB
+HugePages_Total:   {:>5}
+HugePages_Free:    {:>5}
+HugePages_Rsvd:    {:>5}
+HugePages_Surp:    {:>5}
+Hugepagesize:   {:>8} kB
+Hugetlb:        {:>8} kB
 ",
-            total_kb, free_kb, available_kb, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+            kb(stat.total_pages),
+            kb(stat.free_pages),
+            kb(available),
+            0,
+            kb(stat.file_pages),
+            0,
+            kb(stat.swap_total),
+            kb(stat.swap_free),
+            kb(stat.file_dirty),
+            kb(stat.anon_pages),
+            kb(stat.file_mapped),
+            slab_kb,
+            0,
+            slab_kb,
+            kb(stat.vmalloc_pages),
+            huge.total,
+            huge.free,
+            0,
+            0,
+            huge.page_size / 1024,
+            huge.total * huge.page_size / 1024,
         );
 
         Ok(content.into_bytes())
@@ -1,13 +1,39 @@
+pub mod audit;
+pub mod buddyinfo;
 pub mod cpuinfo;
+pub mod dynamic_debug;
+#[cfg(feature = "coverage")]
+pub mod kcov;
+#[cfg(feature = "lock-stat")]
+pub mod lock_stat;
 pub mod meminfo;
 pub mod mounts;
 pub mod process;
 pub mod psmem;
+pub mod sysctl;
 pub mod uptime;
+pub mod vmstat;
+pub mod zoneinfo;
 
+pub use audit::{AuditGenerator, AuditRulesGenerator};
+pub use buddyinfo::BuddyinfoGenerator;
 pub use cpuinfo::CpuinfoGenerator;
+pub use dynamic_debug::DynamicDebugGenerator;
+#[cfg(feature = "coverage")]
+pub use kcov::KcovGenerator;
+#[cfg(feature = "lock-stat")]
+pub use lock_stat::LockStatGenerator;
 pub use meminfo::MeminfoGenerator;
 pub use mounts::MountsGenerator;
-pub use process::{CmdlineGenerator, MapsGenerator, StatGenerator, StatusGenerator};
+pub use process::{
+    CmdlineGenerator, IdMapGenerator, MapsGenerator, OomScoreAdjGenerator, OomScoreGenerator,
+    SmapsGenerator, StatGenerator, StatusGenerator,
+};
 pub use psmem::PsmemGenerator;
+pub use sysctl::{
+    CompactMemoryGenerator, DentryBudgetGenerator, DentryStateGenerator, NrHugepagesGenerator,
+    SysctlBoolGenerator,
+};
 pub use uptime::UptimeGenerator;
+pub use vmstat::VmstatGenerator;
+pub use zoneinfo::ZoneinfoGenerator;
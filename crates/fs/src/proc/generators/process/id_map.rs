@@ -0,0 +1,33 @@
+//! `/proc/[pid]/uid_map`、`/proc/[pid]/gid_map` 生成器
+
+use alloc::vec::Vec;
+
+use crate::ops::{IdMapKind, fs_ops};
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// 读写指定任务所属用户命名空间 ID 映射的生成器
+pub struct IdMapGenerator {
+    pid: u32,
+    kind: IdMapKind,
+}
+
+impl IdMapGenerator {
+    /// 创建生成器（绑定到指定 pid 与映射种类）。
+    pub fn new(pid: u32, kind: IdMapKind) -> Self {
+        Self { pid, kind }
+    }
+}
+
+impl ContentGenerator for IdMapGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let task = fs_ops().get_task(self.pid).ok_or(FsError::NotFound)?;
+        Ok(task.id_map(self.kind))
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        let task = fs_ops().get_task(self.pid).ok_or(FsError::NotFound)?;
+        task.write_id_map(self.kind, data)?;
+        Ok(data.len())
+    }
+}
@@ -2,7 +2,7 @@
 
 use alloc::{format, string::String, vec::Vec};
 
-use crate::ops::fs_ops;
+use crate::ops::{MemoryAreaInfo, fs_ops};
 use crate::proc::ContentGenerator;
 use vfs::FsError;
 
@@ -28,14 +28,19 @@ impl ContentGenerator for MapsGenerator {
         }
 
         let mut out = String::new();
-        for area in areas {
-            let path = area.path.as_deref().unwrap_or("");
-            out.push_str(&format!(
-                "{:016x}-{:016x} {} {:08x} {} {:>8} {}\n",
-                area.start, area.end, area.perm, area.offset, area.dev, area.inode, path
-            ));
+        for area in &areas {
+            out.push_str(&map_line(area));
         }
 
         Ok(out.into_bytes())
     }
 }
+
+/// 格式化 maps 中的一行（`/proc/[pid]/smaps` 中每个区域的首行相同）
+pub(super) fn map_line(area: &MemoryAreaInfo) -> String {
+    let path = area.path.as_deref().unwrap_or("");
+    format!(
+        "{:016x}-{:016x} {} {:08x} {} {:>8} {}\n",
+        area.start, area.end, area.perm, area.offset, area.dev, area.inode, path
+    )
+}
@@ -1,11 +1,17 @@
 //! `/proc/[pid]` 目录下的进程级文件生成器集合
 
 pub mod cmdline;
+pub mod id_map;
 pub mod maps;
+pub mod oom;
+pub mod smaps;
 pub mod stat;
 pub mod status;
 
 pub use cmdline::CmdlineGenerator;
+pub use id_map::IdMapGenerator;
 pub use maps::MapsGenerator;
+pub use oom::{OomScoreAdjGenerator, OomScoreGenerator};
+pub use smaps::SmapsGenerator;
 pub use stat::StatGenerator;
 pub use status::StatusGenerator;
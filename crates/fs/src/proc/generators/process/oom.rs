@@ -0,0 +1,79 @@
+//! `/proc/[pid]/oom_score`、`/proc/[pid]/oom_score_adj` 生成器
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::ops::fs_ops;
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// `oom_score_adj` 的取值范围
+const OOM_SCORE_ADJ_RANGE: core::ops::RangeInclusive<i32> = -1000..=1000;
+
+/// 只读的 OOM 分数生成器
+pub struct OomScoreGenerator {
+    pid: u32,
+}
+
+impl OomScoreGenerator {
+    /// 创建生成器（绑定到指定 pid）。
+    pub fn new(pid: u32) -> Self {
+        Self { pid }
+    }
+}
+
+impl ContentGenerator for OomScoreGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let task = fs_ops().get_task(self.pid).ok_or(FsError::NotFound)?;
+        Ok(format!("{}\n", task.oom_score()).into_bytes())
+    }
+}
+
+/// 读写 OOM badness 调整值的生成器
+pub struct OomScoreAdjGenerator {
+    pid: u32,
+}
+
+impl OomScoreAdjGenerator {
+    /// 创建生成器（绑定到指定 pid）。
+    pub fn new(pid: u32) -> Self {
+        Self { pid }
+    }
+}
+
+impl ContentGenerator for OomScoreAdjGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let task = fs_ops().get_task(self.pid).ok_or(FsError::NotFound)?;
+        Ok(format!("{}\n", task.oom_score_adj()).into_bytes())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        let adj = parse_oom_score_adj(data)?;
+        let task = fs_ops().get_task(self.pid).ok_or(FsError::NotFound)?;
+        task.set_oom_score_adj(adj)?;
+        Ok(data.len())
+    }
+}
+
+/// 解析写入的十进制调整值，超出 -1000..=1000 时返回 `InvalidArgument`
+fn parse_oom_score_adj(data: &[u8]) -> Result<i32, FsError> {
+    let text = core::str::from_utf8(data).map_err(|_| FsError::InvalidArgument)?;
+    let adj: i32 = text.trim().parse().map_err(|_| FsError::InvalidArgument)?;
+    if !OOM_SCORE_ADJ_RANGE.contains(&adj) {
+        return Err(FsError::InvalidArgument);
+    }
+    Ok(adj)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_oom_score_adj() {
+        assert_eq!(parse_oom_score_adj(b"-1000\n"), Ok(-1000));
+        assert_eq!(parse_oom_score_adj(b"  500"), Ok(500));
+        assert_eq!(parse_oom_score_adj(b"1001"), Err(FsError::InvalidArgument));
+        assert_eq!(parse_oom_score_adj(b"abc"), Err(FsError::InvalidArgument));
+    }
+}
@@ -0,0 +1,108 @@
+//! /proc/sys 可写开关生成器
+
+use alloc::{format, vec::Vec};
+
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// 布尔型 sysctl：读出 `0\n` / `1\n`，写入 `0` 或 `1`。
+pub struct SysctlBoolGenerator {
+    get: fn() -> bool,
+    set: fn(bool),
+}
+
+impl SysctlBoolGenerator {
+    /// 用一对读写函数创建 sysctl
+    pub const fn new(get: fn() -> bool, set: fn(bool)) -> Self {
+        Self { get, set }
+    }
+}
+
+impl ContentGenerator for SysctlBoolGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        Ok(if (self.get)() { b"1\n" } else { b"0\n" }.to_vec())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        let value = match data.trim_ascii() {
+            b"0" => false,
+            b"1" => true,
+            _ => return Err(FsError::InvalidArgument),
+        };
+        (self.set)(value);
+        Ok(data.len())
+    }
+}
+
+/// `/proc/sys/vm/compact_memory`：写入任意内容即规整所有内存区域，不可读。
+pub struct CompactMemoryGenerator;
+
+impl ContentGenerator for CompactMemoryGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        mm::compaction::compact_memory();
+        Ok(data.len())
+    }
+}
+
+/// `/proc/sys/vm/nr_hugepages`：读出大页池中的大页数，写入目标数调整池的大小。
+///
+/// 连续帧不足或大页正在使用时实际数量可能达不到目标，以读出的值为准。
+pub struct NrHugepagesGenerator;
+
+impl ContentGenerator for NrHugepagesGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        Ok(format!("{}\n", mm::hugetlb::nr_hugepages()).into_bytes())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        let target = core::str::from_utf8(data.trim_ascii())
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .ok_or(FsError::InvalidArgument)?;
+        mm::hugetlb::set_nr_hugepages(target);
+        Ok(data.len())
+    }
+}
+
+/// `/proc/sys/fs/dentry-state`：目录项缓存的统计，格式同 Linux
+/// （`nr_dentry nr_unused age_limit want_pages nr_negative dummy`）。
+///
+/// 回收按 LRU 进行而不按时间，`age_limit` 固定为 Linux 的默认值 45，`want_pages` 为 0。
+pub struct DentryStateGenerator;
+
+impl ContentGenerator for DentryStateGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let stats = vfs::DENTRY_CACHE.stats();
+        Ok(format!(
+            "{}\t{}\t45\t0\t{}\t0\n",
+            stats.nr_dentry, stats.nr_unused, stats.nr_negative
+        )
+        .into_bytes())
+    }
+
+    fn write(&self, _data: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::PermissionDenied)
+    }
+}
+
+/// `/proc/sys/fs/dentry-budget`：目录项缓存的内存预算（字节），写入后立即回收超出的部分。
+pub struct DentryBudgetGenerator;
+
+impl ContentGenerator for DentryBudgetGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        Ok(format!("{}\n", vfs::DENTRY_CACHE.budget()).into_bytes())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, FsError> {
+        let budget = core::str::from_utf8(data.trim_ascii())
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .ok_or(FsError::InvalidArgument)?;
+        vfs::DENTRY_CACHE.set_budget(budget);
+        Ok(data.len())
+    }
+}
@@ -0,0 +1,51 @@
+//! /proc/vmstat 生成器
+//!
+//! 与 Linux 格式相同：每行一个计数器名和值，页数计数以页为单位。
+//! 数据取自 [`mm::vmstat::snapshot`]。
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use mm::frame_allocator::ZoneType;
+use mm::vmstat::VmEvent;
+
+use crate::ops::fs_ops;
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// `/proc/vmstat` 内容生成器。
+pub struct VmstatGenerator;
+
+impl ContentGenerator for VmstatGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let page_size = fs_ops().page_size();
+        let stat = mm::vmstat::snapshot();
+        let event = |e: VmEvent| stat.events[e as usize];
+
+        let mut out = String::new();
+        let mut line = |name: &str, value: usize| out.push_str(&format!("{} {}\n", name, value));
+        line("nr_free_pages", stat.free_pages);
+        line("nr_anon_pages", stat.anon_pages);
+        line("nr_mapped", stat.file_mapped);
+        line("nr_file_pages", stat.file_pages);
+        line("nr_dirty", stat.file_dirty);
+        line("nr_slab_unreclaimable", stat.slab_bytes.div_ceil(page_size));
+        line("nr_swap_free", stat.swap_free);
+        for zone in ZoneType::ALL {
+            let name = format!("pgalloc_{}", zone.name().to_lowercase());
+            line(&name, stat.pgalloc[zone as usize]);
+        }
+        line("pgfree", event(VmEvent::PgFree));
+        line("pgfault", event(VmEvent::PgFault));
+        line("pgmajfault", event(VmEvent::PgMajFault));
+        line("pswpin", event(VmEvent::PswpIn));
+        line("pswpout", event(VmEvent::PswpOut));
+        line("pgmigrate_success", event(VmEvent::PgMigrateSuccess));
+        line("pgmigrate_fail", event(VmEvent::PgMigrateFail));
+        line("compact_stall", event(VmEvent::CompactStall));
+        line("compact_fail", event(VmEvent::CompactFail));
+        line("compact_success", event(VmEvent::CompactSuccess));
+        Ok(out.into_bytes())
+    }
+}
@@ -0,0 +1,73 @@
+//! /proc/zoneinfo 生成器
+//!
+//! 按 Linux 的格式列出每个内存区域的空闲帧数、水位线、帧数、
+//! 回退保留（`protection`）与分配统计。
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use mm::frame_allocator::{Watermark, ZoneInfo, ZoneType};
+
+use crate::proc::ContentGenerator;
+use vfs::FsError;
+
+/// `/proc/zoneinfo` 内容生成器。
+pub struct ZoneinfoGenerator;
+
+impl ContentGenerator for ZoneinfoGenerator {
+    fn generate(&self) -> Result<Vec<u8>, FsError> {
+        let mut out = String::new();
+        for zone in mm::frame_allocator::get_zone_info() {
+            format_zone(&mut out, &zone);
+        }
+        Ok(out.into_bytes())
+    }
+}
+
+/// 输出一个区域
+fn format_zone(out: &mut String, zone: &ZoneInfo) {
+    // protection 第 j 项：为第 j 个区域分配时回退到本区域需要额外保留的帧数
+    let protection: Vec<String> = ZoneType::ALL
+        .iter()
+        .map(|&z| {
+            if z > zone.zone {
+                zone.lowmem_reserve
+            } else {
+                0
+            }
+        })
+        .map(|p| format!("{}", p))
+        .collect();
+
+    out.push_str(&format!(
+        "Node {}, zone {:>8}
+  pages free     {}
+        min      {}
+        low      {}
+        high     {}
+        spanned  {}
+        present  {}
+        managed  {}
+        protection: ({})
+      nr_alloc {}
+      nr_alloc_fallback {}
+      nr_alloc_fail {}
+  start_pfn:           {}
+",
+        zone.node,
+        zone.zone.name(),
+        zone.free,
+        zone.watermark[Watermark::Min as usize],
+        zone.watermark[Watermark::Low as usize],
+        zone.watermark[Watermark::High as usize],
+        zone.present,
+        zone.present,
+        zone.present,
+        protection.join(", "),
+        zone.nr_alloc,
+        zone.nr_alloc_fallback,
+        zone.nr_alloc_fail,
+        zone.start_pfn,
+    ));
+}
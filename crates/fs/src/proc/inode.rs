@@ -36,6 +36,11 @@ fn proc_pid_child_inode_no(pid: u32, offset: usize) -> usize {
 pub trait ContentGenerator: Send + Sync {
     /// 生成文件内容（每次调用时重新生成）
     fn generate(&self) -> Result<Vec<u8>, FsError>;
+
+    /// 处理写入（默认只读）
+    fn write(&self, _data: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::PermissionDenied)
+    }
 }
 
 /// ProcFS 中的 inode 节点。
@@ -238,8 +243,10 @@ impl ProcInode {
 
     /// 为指定 PID 创建进程目录
     fn create_process_dir(&self, pid: u32) -> Option<Arc<ProcInode>> {
+        use crate::ops::IdMapKind;
         use crate::proc::generators::{
-            CmdlineGenerator, MapsGenerator, StatGenerator, StatusGenerator,
+            CmdlineGenerator, IdMapGenerator, MapsGenerator, OomScoreAdjGenerator,
+            OomScoreGenerator, SmapsGenerator, StatGenerator, StatusGenerator,
         };
 
         let task = fs_ops().get_task(pid)?;
@@ -294,6 +301,42 @@ impl ProcInode {
         );
         let _ = proc_dir.add_child("maps", maps);
 
+        // 创建 uid_map / gid_map 文件
+        let uid_map = Self::new_dynamic_file_with_inode_no(
+            Arc::new(IdMapGenerator::new(pid, IdMapKind::Uid)),
+            FileMode::from_bits_truncate(0o644),
+            Some(proc_pid_child_inode_no(pid, 6)),
+        );
+        let _ = proc_dir.add_child("uid_map", uid_map);
+        let gid_map = Self::new_dynamic_file_with_inode_no(
+            Arc::new(IdMapGenerator::new(pid, IdMapKind::Gid)),
+            FileMode::from_bits_truncate(0o644),
+            Some(proc_pid_child_inode_no(pid, 7)),
+        );
+        let _ = proc_dir.add_child("gid_map", gid_map);
+
+        // 创建 oom_score / oom_score_adj 文件
+        let oom_score = Self::new_dynamic_file_with_inode_no(
+            Arc::new(OomScoreGenerator::new(pid)),
+            FileMode::from_bits_truncate(0o444),
+            Some(proc_pid_child_inode_no(pid, 8)),
+        );
+        let _ = proc_dir.add_child("oom_score", oom_score);
+        let oom_score_adj = Self::new_dynamic_file_with_inode_no(
+            Arc::new(OomScoreAdjGenerator::new(pid)),
+            FileMode::from_bits_truncate(0o644),
+            Some(proc_pid_child_inode_no(pid, 9)),
+        );
+        let _ = proc_dir.add_child("oom_score_adj", oom_score_adj);
+
+        // 创建 smaps 文件
+        let smaps = Self::new_dynamic_file_with_inode_no(
+            Arc::new(SmapsGenerator::new(pid)),
+            FileMode::from_bits_truncate(0o444),
+            Some(proc_pid_child_inode_no(pid, 10)),
+        );
+        let _ = proc_dir.add_child("smaps", smaps);
+
         // 验证任务仍然存在
         let _ = task;
 
@@ -337,8 +380,11 @@ impl Inode for ProcInode {
         }
     }
 
-    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize, FsError> {
-        Err(FsError::PermissionDenied)
+    fn write_at(&self, _offset: usize, buf: &[u8]) -> Result<usize, FsError> {
+        match &self.content {
+            ProcInodeContent::Dynamic(generator) => generator.write(buf),
+            _ => Err(FsError::PermissionDenied),
+        }
     }
 
     fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
@@ -451,7 +497,12 @@ impl Inode for ProcInode {
     }
 
     fn truncate(&self, _size: usize) -> Result<(), FsError> {
-        Err(FsError::PermissionDenied)
+        // 可写的动态文件（/proc/sys）忽略截断，使 `echo 1 > ...` 能正常打开
+        let writable = self.metadata.lock().mode.bits() & 0o222 != 0;
+        match &self.content {
+            ProcInodeContent::Dynamic(_) if writable => Ok(()),
+            _ => Err(FsError::PermissionDenied),
+        }
     }
 
     fn sync(&self) -> Result<(), FsError> {
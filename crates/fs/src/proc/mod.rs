@@ -6,7 +6,7 @@
 //!
 //! procfs 中的很多文件内容由生成器在读取时动态生成：
 //!
-//! - 系统级：`/proc/meminfo`、`/proc/cpuinfo`、`/proc/uptime`、`/proc/mounts` 等
+//! - 系统级：`/proc/meminfo`、`/proc/vmstat`、`/proc/cpuinfo`、`/proc/uptime`、`/proc/mounts` 等
 //! - 进程级：`/proc/[pid]/stat`、`/proc/[pid]/status`、`/proc/[pid]/maps`、`/proc/[pid]/cmdline` 等
 //!
 //! 生成器通常通过 [`crate::ops::fs_ops`] 获取任务/内存/挂载信息，并序列化为 Linux 风格文本。
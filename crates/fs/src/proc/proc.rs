@@ -24,7 +24,10 @@ impl ProcFS {
     /// 初始化 proc 文件系统树结构
     pub fn init_tree(self: &Arc<Self>) -> Result<(), FsError> {
         use crate::proc::generators::{
-            CpuinfoGenerator, MeminfoGenerator, MountsGenerator, PsmemGenerator, UptimeGenerator,
+            AuditGenerator, AuditRulesGenerator, BuddyinfoGenerator, CompactMemoryGenerator,
+            CpuinfoGenerator, DentryBudgetGenerator, DentryStateGenerator, DynamicDebugGenerator,
+            MeminfoGenerator, MountsGenerator, NrHugepagesGenerator, PsmemGenerator,
+            SysctlBoolGenerator, UptimeGenerator, VmstatGenerator, ZoneinfoGenerator,
         };
 
         let root = &self.root_inode;
@@ -37,6 +40,30 @@ impl ProcFS {
         );
         root.add_child("meminfo", meminfo)?;
 
+        // 创建 /proc/vmstat
+        let vmstat = ProcInode::new_dynamic_file(
+            "vmstat",
+            Arc::new(VmstatGenerator),
+            FileMode::from_bits_truncate(0o444),
+        );
+        root.add_child("vmstat", vmstat)?;
+
+        // 创建 /proc/buddyinfo
+        let buddyinfo = ProcInode::new_dynamic_file(
+            "buddyinfo",
+            Arc::new(BuddyinfoGenerator),
+            FileMode::from_bits_truncate(0o444),
+        );
+        root.add_child("buddyinfo", buddyinfo)?;
+
+        // 创建 /proc/zoneinfo
+        let zoneinfo = ProcInode::new_dynamic_file(
+            "zoneinfo",
+            Arc::new(ZoneinfoGenerator),
+            FileMode::from_bits_truncate(0o444),
+        );
+        root.add_child("zoneinfo", zoneinfo)?;
+
         // 创建 /proc/uptime
         let uptime = ProcInode::new_dynamic_file(
             "uptime",
@@ -69,6 +96,129 @@ impl ProcFS {
         );
         root.add_child("psmem", psmem)?;
 
+        // 创建 /proc/audit - 审计记录（仅 root 可读）
+        let audit = ProcInode::new_dynamic_file(
+            "audit",
+            Arc::new(AuditGenerator),
+            FileMode::from_bits_truncate(0o400),
+        );
+        root.add_child("audit", audit)?;
+
+        // 创建 /proc/audit_rules - 审计规则
+        let audit_rules = ProcInode::new_dynamic_file(
+            "audit_rules",
+            Arc::new(AuditRulesGenerator),
+            FileMode::from_bits_truncate(0o600),
+        );
+        root.add_child("audit_rules", audit_rules)?;
+
+        // 创建 /proc/sys/vm/allow_wx - 允许用户态建立 W+X 映射
+        let sys = ProcInode::new_directory(FileMode::from_bits_truncate(
+            0o555 | FileMode::S_IFDIR.bits(),
+        ));
+        let vm = ProcInode::new_directory(FileMode::from_bits_truncate(
+            0o555 | FileMode::S_IFDIR.bits(),
+        ));
+        let allow_wx = ProcInode::new_dynamic_file(
+            "allow_wx",
+            Arc::new(SysctlBoolGenerator::new(
+                mm::wx::allow_wx,
+                mm::wx::set_allow_wx,
+            )),
+            FileMode::from_bits_truncate(0o644),
+        );
+        vm.add_child("allow_wx", allow_wx)?;
+
+        // 创建 /proc/sys/vm/compact_memory - 手动触发内存规整
+        let compact_memory = ProcInode::new_dynamic_file(
+            "compact_memory",
+            Arc::new(CompactMemoryGenerator),
+            FileMode::from_bits_truncate(0o200),
+        );
+        vm.add_child("compact_memory", compact_memory)?;
+
+        // 创建 /proc/sys/vm/nr_hugepages - 大页池的大小
+        let nr_hugepages = ProcInode::new_dynamic_file(
+            "nr_hugepages",
+            Arc::new(NrHugepagesGenerator),
+            FileMode::from_bits_truncate(0o644),
+        );
+        vm.add_child("nr_hugepages", nr_hugepages)?;
+        sys.add_child("vm", vm)?;
+
+        // 创建 /proc/sys/kernel/dynamic_debug - 按子系统打开调试日志
+        let kernel = ProcInode::new_directory(FileMode::from_bits_truncate(
+            0o555 | FileMode::S_IFDIR.bits(),
+        ));
+        let dynamic_debug = ProcInode::new_dynamic_file(
+            "dynamic_debug",
+            Arc::new(DynamicDebugGenerator),
+            FileMode::from_bits_truncate(0o644),
+        );
+        kernel.add_child("dynamic_debug", dynamic_debug)?;
+
+        // 创建 /proc/sys/kernel/printk_time 与 printk_color - 控制台日志格式
+        let printk_time = ProcInode::new_dynamic_file(
+            "printk_time",
+            Arc::new(SysctlBoolGenerator::new(
+                klog::is_console_time,
+                klog::set_console_time,
+            )),
+            FileMode::from_bits_truncate(0o644),
+        );
+        kernel.add_child("printk_time", printk_time)?;
+        let printk_color = ProcInode::new_dynamic_file(
+            "printk_color",
+            Arc::new(SysctlBoolGenerator::new(
+                klog::is_console_color,
+                klog::set_console_color,
+            )),
+            FileMode::from_bits_truncate(0o644),
+        );
+        kernel.add_child("printk_color", printk_color)?;
+        sys.add_child("kernel", kernel)?;
+
+        // 创建 /proc/sys/fs/dentry-state 与 dentry-budget - 目录项缓存统计与内存预算
+        let fs = ProcInode::new_directory(FileMode::from_bits_truncate(
+            0o555 | FileMode::S_IFDIR.bits(),
+        ));
+        let dentry_state = ProcInode::new_dynamic_file(
+            "dentry-state",
+            Arc::new(DentryStateGenerator),
+            FileMode::from_bits_truncate(0o444),
+        );
+        fs.add_child("dentry-state", dentry_state)?;
+        let dentry_budget = ProcInode::new_dynamic_file(
+            "dentry-budget",
+            Arc::new(DentryBudgetGenerator),
+            FileMode::from_bits_truncate(0o644),
+        );
+        fs.add_child("dentry-budget", dentry_budget)?;
+        sys.add_child("fs", fs)?;
+        root.add_child("sys", sys)?;
+
+        // 创建 /proc/kcov - 覆盖率计数点报告
+        #[cfg(feature = "coverage")]
+        {
+            let kcov = ProcInode::new_dynamic_file(
+                "kcov",
+                Arc::new(crate::proc::generators::KcovGenerator),
+                FileMode::from_bits_truncate(0o444),
+            );
+            root.add_child("kcov", kcov)?;
+        }
+
+        // 创建 /proc/lock_stat - 锁竞争统计
+        #[cfg(feature = "lock-stat")]
+        {
+            let lock_stat = ProcInode::new_dynamic_file(
+                "lock_stat",
+                Arc::new(crate::proc::generators::LockStatGenerator),
+                FileMode::from_bits_truncate(0o600),
+            );
+            root.add_child("lock_stat", lock_stat)?;
+        }
+
         // 创建 /proc/self - 动态符号链接，指向当前进程
         let self_link = ProcInode::new_dynamic_symlink("self", || {
             use alloc::string::ToString;
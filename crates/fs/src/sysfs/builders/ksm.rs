@@ -0,0 +1,93 @@
+//! KSM sysfs 树构建器
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+
+use vfs::{FileMode, FsError, Inode};
+
+use crate::sysfs::inode::{AttrShowFn, AttrStoreFn, SysfsAttr, SysfsInode};
+
+/// 构建 /sys/kernel/mm/ksm
+///
+/// `run`、`pages_to_scan`、`sleep_millisecs` 可写，其余为只读统计（见 [`mm::ksm::stats`]）。
+pub fn build_ksm(root: &Arc<SysfsInode>) -> Result<(), FsError> {
+    let kernel_inode = root.lookup("kernel")?;
+    let kernel_dir = kernel_inode
+        .downcast_ref::<SysfsInode>()
+        .ok_or(FsError::InvalidArgument)?;
+
+    let mm_dir = SysfsInode::new_directory(FileMode::from_bits_truncate(0o040000 | 0o555));
+    kernel_dir.add_child("mm", mm_dir.clone())?;
+    let ksm_dir = SysfsInode::new_directory(FileMode::from_bits_truncate(0o040000 | 0o555));
+    mm_dir.add_child("ksm", ksm_dir.clone())?;
+
+    let add = |name: &str, show: Arc<AttrShowFn>, store: Option<Arc<AttrStoreFn>>| {
+        let mode = if store.is_some() { 0o644 } else { 0o444 };
+        let attr = SysfsAttr {
+            name: name.to_string(),
+            mode: FileMode::from_bits_truncate(mode),
+            show,
+            store,
+        };
+        ksm_dir.add_child(name, SysfsInode::new_attribute(attr))
+    };
+
+    add(
+        "run",
+        Arc::new(|| Ok(format!("{}\n", mm::ksm::run() as u8))),
+        Some(Arc::new(|s| {
+            let run = match s.trim() {
+                "0" => false,
+                "1" => true,
+                _ => return Err(FsError::InvalidArgument),
+            };
+            mm::ksm::set_run(run);
+            Ok(())
+        })),
+    )?;
+    add(
+        "pages_to_scan",
+        Arc::new(|| Ok(format!("{}\n", mm::ksm::pages_to_scan()))),
+        Some(Arc::new(|s| {
+            let pages = parse_usize(s)?;
+            mm::ksm::set_pages_to_scan(pages);
+            Ok(())
+        })),
+    )?;
+    add(
+        "sleep_millisecs",
+        Arc::new(|| Ok(format!("{}\n", mm::ksm::sleep_millisecs()))),
+        Some(Arc::new(|s| {
+            let ms = parse_usize(s)?;
+            mm::ksm::set_sleep_millisecs(ms);
+            Ok(())
+        })),
+    )?;
+    add(
+        "pages_shared",
+        Arc::new(|| Ok(format!("{}\n", mm::ksm::stats().pages_shared))),
+        None,
+    )?;
+    add(
+        "pages_sharing",
+        Arc::new(|| Ok(format!("{}\n", mm::ksm::stats().pages_sharing))),
+        None,
+    )?;
+    add(
+        "pages_unshared",
+        Arc::new(|| Ok(format!("{}\n", mm::ksm::stats().pages_unshared))),
+        None,
+    )?;
+    add(
+        "full_scans",
+        Arc::new(|| Ok(format!("{}\n", mm::ksm::stats().full_scans))),
+        None,
+    )?;
+
+    Ok(())
+}
+
+fn parse_usize(s: &str) -> Result<usize, FsError> {
+    s.trim().parse().map_err(|_| FsError::InvalidArgument)
+}
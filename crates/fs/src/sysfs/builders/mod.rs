@@ -4,6 +4,8 @@ pub mod block;
 pub mod devices;
 pub mod input;
 pub mod kernel;
+pub mod ksm;
 pub mod net;
+pub mod pstore;
 pub mod rtc;
 pub mod tty;
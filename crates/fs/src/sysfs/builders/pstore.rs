@@ -0,0 +1,40 @@
+//! pstore sysfs 树构建器
+
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+
+use vfs::{FileMode, FsError, Inode};
+
+use crate::sysfs::inode::{SysfsAttr, SysfsInode};
+
+/// 构建 /sys/fs/pstore
+///
+/// 后端中保存有上一次启动的崩溃日志时导出 `dmesg-ramoops-0`，写入任意内容清除该记录。
+pub fn build_pstore(root: &Arc<SysfsInode>) -> Result<(), FsError> {
+    let fs_inode = root.lookup("fs")?;
+    let fs_dir = fs_inode
+        .downcast_ref::<SysfsInode>()
+        .ok_or(FsError::InvalidArgument)?;
+
+    let pstore_dir = SysfsInode::new_directory(FileMode::from_bits_truncate(0o040000 | 0o750));
+    fs_dir.add_child("pstore", pstore_dir.clone())?;
+
+    if klog::pstore::read_record().is_none() {
+        return Ok(());
+    }
+    let dmesg_attr = SysfsAttr {
+        name: "dmesg-ramoops-0".to_string(),
+        mode: FileMode::from_bits_truncate(0o600),
+        show: Arc::new(|| {
+            let record = klog::pstore::read_record().unwrap_or_default();
+            Ok(String::from_utf8_lossy(&record).into_owned())
+        }),
+        store: Some(Arc::new(|_| {
+            klog::pstore::erase();
+            Ok(())
+        })),
+    };
+    pstore_dir.add_child("dmesg-ramoops-0", SysfsInode::new_attribute(dmesg_attr))?;
+
+    Ok(())
+}
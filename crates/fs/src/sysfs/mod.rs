@@ -8,6 +8,7 @@
 //!
 //! - `/sys/class/*`：按类别组织（block/net/tty/input/rtc 等）
 //! - `/sys/devices/*`：设备树（platform/devices 等）
+//! - `/sys/fs/pstore`：上一次启动保存的崩溃日志（见 `klog::pstore`）
 //!
 //! ## 构建器与设备注册表
 //!
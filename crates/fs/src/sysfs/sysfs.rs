@@ -60,6 +60,10 @@ impl SysFS {
         let kernel_dir = SysfsInode::new_directory(FileMode::from_bits_truncate(0o040000 | 0o555));
         root.add_child("kernel", kernel_dir)?;
 
+        // /sys/fs/
+        let fs_dir = SysfsInode::new_directory(FileMode::from_bits_truncate(0o040000 | 0o555));
+        root.add_child("fs", fs_dir)?;
+
         // /sys/devices/
         let devices_dir = SysfsInode::new_directory(FileMode::from_bits_truncate(0o040000 | 0o555));
         root.add_child("devices", devices_dir)?;
@@ -87,6 +91,12 @@ impl SysFS {
         // 3. 构建内核信息树
         builders::kernel::build_kernel_info(&self.root_inode)?;
 
+        // 4. 导出上一次启动保存的崩溃日志
+        builders::pstore::build_pstore(&self.root_inode)?;
+
+        // 5. 导出 KSM 的参数与统计
+        builders::ksm::build_ksm(&self.root_inode)?;
+
         Ok(())
     }
 }
@@ -12,7 +12,7 @@ use mm::address::{ConvertablePaddr, PageNum, UsizeConvert};
 use mm::frame_allocator::{FrameTracker, alloc_frame};
 use sync::SpinLock;
 use uapi::time::TimeSpec;
-use vfs::{DirEntry, FileMode, FsError, Inode, InodeMetadata, InodeType};
+use vfs::{DirEntry, FileMode, FsError, Inode, InodeMetadata, InodeType, XattrFlags, XattrMap};
 
 /// Tmpfs Inode 实现
 pub struct TmpfsInode {
@@ -33,6 +33,9 @@ pub struct TmpfsInode {
 
     /// 指向自身的弱引用
     self_ref: SpinLock<Weak<TmpfsInode>>,
+
+    /// 扩展属性
+    xattrs: XattrMap,
 }
 
 /// Tmpfs 统计信息
@@ -97,6 +100,7 @@ impl TmpfsInode {
             children: SpinLock::new(BTreeMap::new()),
             stats,
             self_ref: SpinLock::new(Weak::new()),
+            xattrs: XattrMap::new(),
         })
     }
 
@@ -166,6 +170,14 @@ impl TmpfsInode {
     }
 }
 
+impl Drop for TmpfsInode {
+    /// 数据页在最后一个引用释放时才归还，删除后仍被打开的文件和 O_TMPFILE 文件继续占用容量
+    fn drop(&mut self) {
+        let allocated = self.data.lock().iter().filter(|f| f.is_some()).count();
+        self.dec_allocated_pages(allocated);
+    }
+}
+
 impl Inode for TmpfsInode {
     fn metadata(&self) -> Result<InodeMetadata, FsError> {
         Ok(self.metadata.lock().clone())
@@ -347,6 +359,30 @@ impl Inode for TmpfsInode {
         Ok(new_inode as Arc<dyn Inode>)
     }
 
+    fn tmpfile(&self, mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+        let meta = self.metadata.lock();
+        if meta.inode_type != InodeType::Directory {
+            return Err(FsError::NotDirectory);
+        }
+        drop(meta);
+
+        let inode_no = self.alloc_inode_no();
+        let parent_weak = self.self_ref.lock().clone();
+
+        let new_inode = TmpfsInode::new(
+            inode_no,
+            InodeType::File,
+            mode,
+            parent_weak,
+            self.stats.clone(),
+        );
+
+        new_inode.metadata.lock().nlinks = 0;
+        *new_inode.self_ref.lock() = Arc::downgrade(&new_inode);
+
+        Ok(new_inode as Arc<dyn Inode>)
+    }
+
     fn mkdir(&self, name: &str, mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
         let meta = self.metadata.lock();
         if meta.inode_type != InodeType::Directory {
@@ -396,21 +432,15 @@ impl Inode for TmpfsInode {
 
         let child = children.get(name).ok_or(FsError::NotFound)?;
 
-        let child_meta = child.metadata.lock();
+        let mut child_meta = child.metadata.lock();
         if child_meta.inode_type == InodeType::Directory {
             return Err(FsError::IsDirectory);
         }
+        child_meta.nlinks = child_meta.nlinks.saturating_sub(1);
+        child_meta.ctime = fs_ops().timespec_now();
         drop(child_meta);
 
-        let child_data = child.data.lock();
-        let allocated = child_data
-            .iter()
-            .filter(|f: &&Option<_>| f.is_some())
-            .count();
-        drop(child_data);
-
         children.remove(name);
-        self.dec_allocated_pages(allocated);
         self.update_mtime();
 
         Ok(())
@@ -532,6 +562,10 @@ impl Inode for TmpfsInode {
         Ok(())
     }
 
+    fn cache_negative(&self) -> bool {
+        true
+    }
+
     fn as_any(&self) -> &dyn core::any::Any {
         self
     }
@@ -573,8 +607,37 @@ impl Inode for TmpfsInode {
         Ok(symlink_inode as Arc<dyn Inode>)
     }
 
-    fn link(&self, _name: &str, _target: &Arc<dyn Inode>) -> Result<(), FsError> {
-        Err(FsError::NotSupported)
+    fn link(&self, name: &str, target: &Arc<dyn Inode>) -> Result<(), FsError> {
+        let meta = self.metadata.lock();
+        if meta.inode_type != InodeType::Directory {
+            return Err(FsError::NotDirectory);
+        }
+        drop(meta);
+
+        let target = target
+            .downcast_ref::<TmpfsInode>()
+            .filter(|t| Arc::ptr_eq(&t.stats, &self.stats))
+            .and_then(|t| t.self_ref.lock().upgrade())
+            .ok_or(FsError::CrossDevice)?;
+
+        let mut children = self.children.lock();
+        if children.contains_key(name) {
+            return Err(FsError::AlreadyExists);
+        }
+
+        let mut target_meta = target.metadata.lock();
+        if target_meta.inode_type == InodeType::Directory {
+            return Err(FsError::NotPermitted);
+        }
+        target_meta.nlinks += 1;
+        target_meta.ctime = fs_ops().timespec_now();
+        drop(target_meta);
+
+        children.insert(String::from(name), target);
+        drop(children);
+
+        self.update_mtime();
+        Ok(())
     }
 
     fn rename(
@@ -654,11 +717,43 @@ impl Inode for TmpfsInode {
         Ok(new_inode as Arc<dyn Inode>)
     }
 
-    fn chmod(&self, _mode: FileMode) -> Result<(), FsError> {
-        Err(FsError::NotSupported)
+    fn chmod(&self, mode: FileMode) -> Result<(), FsError> {
+        let mut meta = self.metadata.lock();
+        meta.mode = (meta.mode & FileMode::S_IFMT) | (mode & !FileMode::S_IFMT);
+        meta.ctime = fs_ops().timespec_now();
+        Ok(())
     }
 
-    fn chown(&self, _uid: u32, _gid: u32) -> Result<(), FsError> {
-        Err(FsError::NotSupported)
+    fn chown(&self, uid: u32, gid: u32) -> Result<(), FsError> {
+        // 与 chown(2) 一致，-1 表示不修改
+        let mut meta = self.metadata.lock();
+        if uid != u32::MAX {
+            meta.uid = uid;
+        }
+        if gid != u32::MAX {
+            meta.gid = gid;
+        }
+        meta.ctime = fs_ops().timespec_now();
+        Ok(())
+    }
+
+    fn getxattr(&self, name: &str) -> Result<Vec<u8>, FsError> {
+        self.xattrs.get(name)
+    }
+
+    fn setxattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> Result<(), FsError> {
+        self.xattrs.set(name, value, flags)?;
+        self.metadata.lock().ctime = fs_ops().timespec_now();
+        Ok(())
+    }
+
+    fn listxattr(&self) -> Result<Vec<String>, FsError> {
+        Ok(self.xattrs.list())
+    }
+
+    fn removexattr(&self, name: &str) -> Result<(), FsError> {
+        self.xattrs.remove(name)?;
+        self.metadata.lock().ctime = fs_ops().timespec_now();
+        Ok(())
     }
 }
@@ -7,7 +7,11 @@
 //! - 数据以页为单位存储在物理内存中，读写不涉及块设备 I/O
 //! - 支持最大容量限制（以 MB 传入，内部换算为页数；0 表示无限制）
 //! - 文件数据采用“稀疏页”存储：未分配页读取为 0，写入时按需分配
-//! - inode 号与已分配页数通过共享的 `TmpfsStats` 统一统计
+//! - inode 号与已分配页数通过共享的 `TmpfsStats` 统一统计；数据页在 inode 最后一个引用
+//!   释放时才归还，删除后仍被打开的文件与 O_TMPFILE 创建的匿名文件继续占用容量
+//! - 支持硬链接，链接数随 link/unlink 增减
+//! - 扩展属性（包括 POSIX ACL 使用的 `system.posix_acl_*`）保存在每个 inode 的
+//!   [`vfs::XattrMap`] 中，不计入容量
 
 mod inode;
 mod tmpfs;
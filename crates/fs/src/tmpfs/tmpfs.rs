@@ -111,53 +111,12 @@ impl FileSystem for TmpFs {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use core::sync::atomic::{AtomicUsize, Ordering};
-    use sync::ArchOps;
+    use sync::mock::init_arch_ops;
     use vfs::{FileMode, InodeType};
 
-    struct DummyArchOps;
-
-    impl ArchOps for DummyArchOps {
-        unsafe fn read_and_disable_interrupts(&self) -> usize {
-            0
-        }
-
-        unsafe fn restore_interrupts(&self, _flags: usize) {}
-
-        fn sstatus_sie(&self) -> usize {
-            0
-        }
-
-        fn cpu_id(&self) -> usize {
-            0
-        }
-
-        fn max_cpu_count(&self) -> usize {
-            1
-        }
-    }
-
-    static DUMMY_ARCH_OPS: DummyArchOps = DummyArchOps;
-    static SYNC_INIT: AtomicUsize = AtomicUsize::new(0);
-
-    fn init_sync_arch_ops() {
-        match SYNC_INIT.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire) {
-            Ok(_) => {
-                // Safety: tests use a single global dummy ArchOps.
-                unsafe { sync::register_arch_ops(&DUMMY_ARCH_OPS) };
-                SYNC_INIT.store(2, Ordering::Release);
-            }
-            Err(_) => {
-                while SYNC_INIT.load(Ordering::Acquire) != 2 {
-                    core::hint::spin_loop();
-                }
-            }
-        }
-    }
-
     #[test]
     fn test_tmpfs_root_is_directory() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let fs = TmpFs::new(0);
         let root = fs.root_inode();
         let meta = root.metadata().unwrap();
@@ -169,7 +128,7 @@ mod tests {
 
     #[test]
     fn test_tmpfs_create_lookup_readdir_no_data_pages() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let fs = TmpFs::new(0);
         let root = fs.root_inode();
 
@@ -0,0 +1,78 @@
+//! 内核命令行中的日志参数
+//!
+//! 与 Linux 相同，按出现顺序处理，后出现的覆盖先出现的：
+//! - `loglevel=N`：控制台只输出优先级高于 N 的日志（N 为 0..=8，对应 `KERN_*` 数值）；
+//! - `quiet`：控制台只输出 Error 及以上（相当于 `loglevel=4`）；
+//! - `debug`：控制台输出全部日志，并记录 Debug 级别；
+//! - `printk.time=0|1`：控制台是否使用 `[秒.微秒]` 时间前缀（也接受 `n`/`y`）；
+//! - `printk.color=0|1`：控制台是否输出 ANSI 颜色。
+
+use super::level::LogLevel;
+
+/// 从命令行解析出的日志设置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BootLogParams {
+    /// 控制台输出级别
+    pub console_level: Option<LogLevel>,
+    /// 全局（缓冲）级别
+    pub global_level: Option<LogLevel>,
+    /// 控制台是否使用 `[秒.微秒]` 时间前缀
+    pub console_time: Option<bool>,
+    /// 控制台是否输出 ANSI 颜色
+    pub console_color: Option<bool>,
+}
+
+/// 解析布尔型参数值
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "1" | "y" | "Y" => Some(true),
+        "0" | "n" | "N" => Some(false),
+        _ => None,
+    }
+}
+
+/// Linux 的 `loglevel=N` 表示输出级别数值小于 N 的日志，换算为本系统的阈值
+///
+/// Emergency 总是输出，因此 0 和 1 等价。
+fn console_level_from_loglevel(n: u8) -> LogLevel {
+    LogLevel::from_u8(n.saturating_sub(1).min(LogLevel::Debug as u8))
+}
+
+impl BootLogParams {
+    /// 解析命令行，无法识别的参数被忽略
+    pub fn parse(cmdline: &str) -> Self {
+        let mut params = Self::default();
+        for tok in cmdline.split_whitespace() {
+            match tok {
+                "quiet" => params.console_level = Some(LogLevel::Error),
+                "debug" => {
+                    params.console_level = Some(LogLevel::Debug);
+                    params.global_level = Some(LogLevel::Debug);
+                }
+                _ if tok.starts_with("printk.") => {
+                    let Some((key, value)) = tok.split_once('=') else {
+                        continue;
+                    };
+                    match key {
+                        "printk.time" => {
+                            params.console_time = parse_bool(value).or(params.console_time)
+                        }
+                        "printk.color" => {
+                            params.console_color = parse_bool(value).or(params.console_color)
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {
+                    let level = tok
+                        .strip_prefix("loglevel=")
+                        .and_then(|n| n.parse::<u8>().ok());
+                    if let Some(n) = level {
+                        params.console_level = Some(console_level_from_loglevel(n));
+                    }
+                }
+            }
+        }
+        params
+    }
+}
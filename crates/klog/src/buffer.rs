@@ -2,31 +2,54 @@
 //!
 //! 该模块实现了高性能、多生产者单消费者 (MPSC) 环形缓冲区，
 //! 使用原子操作进行同步。
+//!
+//! 提供两种实现，接口相同：
+//! - [`GlobalLogBuffer`]：所有 CPU 共享一个环形缓冲区（默认）；
+//! - [`PerCpuLogBuffer`]：每个 CPU 写入自己的环形缓冲区，只共享一个全局序号，
+//!   读者按全局序号合并各 CPU 的条目（`per-cpu-buffer` 特性）。
+//!
+//! [`LogBuffer`] 是编译时选定的实现，由 `LogCore` 使用。
 
+use core::fmt;
 use core::ops::{Deref, DerefMut};
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-use super::config::GLOBAL_LOG_BUFFER_SIZE;
+use super::config::{GLOBAL_LOG_BUFFER_SIZE, MAX_LOG_CPUS, PER_CPU_LOG_BUFFER_SIZE};
 use super::entry::LogEntry;
 
+/// `LogCore` 使用的缓冲区实现
+#[cfg(not(feature = "per-cpu-buffer"))]
+pub(crate) type LogBuffer = GlobalLogBuffer;
+
+/// `LogCore` 使用的缓冲区实现
+#[cfg(feature = "per-cpu-buffer")]
+pub(crate) type LogBuffer = PerCpuLogBuffer;
+
 /// 单个日志条目的大小（以字节为单位）
 const LOG_ENTRY_SIZE: usize = core::mem::size_of::<LogEntry>();
 
 /// 缓冲区中可存储的最大日志条目数
 pub(crate) const MAX_LOG_ENTRIES: usize = GLOBAL_LOG_BUFFER_SIZE / LOG_ENTRY_SIZE;
 
+/// 每 CPU 缓冲区中可存储的最大日志条目数
+pub(crate) const PER_CPU_LOG_ENTRIES: usize = PER_CPU_LOG_BUFFER_SIZE / LOG_ENTRY_SIZE;
+
+/// 无锁操作各步骤之间的调度点
+///
+/// 宿主机测试中交给受控交错调度器切换线程，其余情况下为空操作。
+#[inline(always)]
+fn interleave_point() {
+    #[cfg(test)]
+    test_support::interleave::yield_now();
+}
+
 /// 计算日志条目格式化后的精确字节长度
 ///
 /// 格式: "{color_code}{level} [{timestamp:12}] [CPU{cpu_id}/T{task_id:3}] {message}{reset}\n"
 ///
-/// **重要**: 此函数的计算逻辑必须与以下格式化函数保持一致：
-/// - `log_core::direct_print_entry` - 控制台输出格式
-/// - `log_core::format_log_entry` - syslog 系统调用格式
-///
-/// 如果修改了日志输出格式，需要同步更新三处：
-/// 1. `log_core::direct_print_entry` - 实际格式化输出
-/// 2. `log_core::format_log_entry` - 字符串格式化
-/// 3. `calculate_formatted_length` (此函数) - 字节长度计算
+/// **重要**: 此函数的计算逻辑必须与 `log_core::format_log_entry` 在
+/// `LogStyle::DEFAULT` 下的输出（即 syslog 读取格式）保持一致；控制台格式可在运行时
+/// 切换，不参与字节计数。
 ///
 /// # 组成部分计算
 /// - ANSI 颜色代码: entry.level().color_code().len() (开始)
@@ -158,6 +181,10 @@ struct ReaderData {
     read_seq: AtomicUsize,
     /// 由于缓冲区溢出而丢弃的日志计数
     dropped: AtomicUsize,
+    /// 清除点：非破坏性读取从这里开始（syslog CLEAR 只推进它，不丢弃条目）
+    clear_seq: AtomicUsize,
+    /// 最旧的未被覆盖的序号：只随覆盖写入推进，与读指针无关
+    oldest_seq: AtomicUsize,
 }
 
 impl GlobalLogBuffer {
@@ -174,6 +201,8 @@ impl GlobalLogBuffer {
                 inner: ReaderData {
                     read_seq: AtomicUsize::new(1),
                     dropped: AtomicUsize::new(0),
+                    clear_seq: AtomicUsize::new(1),
+                    oldest_seq: AtomicUsize::new(1),
                 },
             },
             buffer: [EMPTY; MAX_LOG_ENTRIES],
@@ -190,31 +219,72 @@ impl GlobalLogBuffer {
     /// 4. 将日志数据复制到槽位（*不包括* seq 字段）
     /// 5. 使用 **Release** 内存屏障原子地设置 seq 来发布条目
     /// 6. 增加未读字节计数
-    pub(crate) fn write(&self, entry: &LogEntry) {
+    ///
+    /// 返回分配给该条目的序列号。
+    pub(crate) fn write(&self, entry: &LogEntry) -> usize {
         // step1: 原子地获取一个唯一的序列号（票据）
         let seq = self.writer_data.write_seq.fetch_add(1, Ordering::Relaxed);
+        interleave_point();
 
         // step2: 从序列号计算目标槽位索引
         let slot = seq % MAX_LOG_ENTRIES;
         let slot_ptr = unsafe { self.buffer.as_ptr().add(slot) as *mut LogEntry };
 
         // step3: 检查并处理潜在的缓冲区满（覆盖）逻辑
+        if seq >= MAX_LOG_ENTRIES {
+            self.reader_data
+                .oldest_seq
+                .fetch_max(seq + 1 - MAX_LOG_ENTRIES, Ordering::AcqRel);
+        }
         self.handle_overwrite(seq);
+        interleave_point();
 
         // step4: 将所有日志数据（*不包括* seq 字段）复制到槽位
         unsafe {
             entry.copy_data_to(slot_ptr);
         }
+        interleave_point();
 
         // step5: 通过原子地设置其 seq 来发布条目（Release 屏障）
         unsafe {
             entry.publish(slot_ptr, seq);
         }
+        interleave_point();
 
         // step6: 增加未读字节计数
         let formatted_len = calculate_formatted_length(entry);
         self.unread_bytes
             .fetch_add(formatted_len, Ordering::Release);
+        seq
+    }
+
+    /// 在序号为 `seq` 的条目的消息末尾追加文本（`pr_cont!`）
+    ///
+    /// `cpu_id` 是写入该条目的 CPU，只有每 CPU 缓冲区用它定位条目。
+    /// 返回追加后的条目副本与追加前的消息长度；条目已被覆盖时返回 `None`。
+    /// 尚未被读取的条目同时增加未读字节计数（先于长度更新，避免读者扣减后计数下溢）。
+    ///
+    /// 检查序号与追加之间槽位被覆盖写入时，追加的内容可能落到新条目上；
+    /// 这要求缓冲区在这段极短的时间内整圈回绕，实际中可以忽略。
+    pub(crate) fn append(
+        &self,
+        _cpu_id: usize,
+        seq: usize,
+        args: fmt::Arguments,
+    ) -> Option<(LogEntry, usize)> {
+        let slot_ptr = unsafe { self.buffer.as_ptr().add(seq % MAX_LOG_ENTRIES) as *mut LogEntry };
+        if unsafe { (*slot_ptr).seq() } != seq {
+            return None;
+        }
+        let old_len = unsafe {
+            LogEntry::append_message(slot_ptr, args, |added| {
+                if self.reader_index() <= seq {
+                    self.unread_bytes.fetch_add(added, Ordering::Release);
+                }
+            })
+        };
+        let entry = unsafe { (*slot_ptr).clone() };
+        (entry.seq() == seq).then_some((entry, old_len))
     }
 
     /// 处理缓冲区溢出，必要时推进读取指针
@@ -274,6 +344,7 @@ impl GlobalLogBuffer {
         }
 
         let entry_data = unsafe { (*slot_ptr).clone() };
+        interleave_point();
 
         // 减少未读字节计数
         let formatted_len = calculate_formatted_length(&entry_data);
@@ -338,6 +409,8 @@ impl GlobalLogBuffer {
             }
         }
 
+        interleave_point();
+
         // 计算缓冲区索引
         let slot = index % MAX_LOG_ENTRIES;
         let slot_ptr = unsafe { self.buffer.as_ptr().add(slot) as *const LogEntry };
@@ -363,4 +436,408 @@ impl GlobalLogBuffer {
     pub(crate) fn writer_index(&self) -> usize {
         self.writer_data.write_seq.load(Ordering::Acquire)
     }
+
+    /// 消费 `peek(seq)` 得到的条目：读指针仍为 `seq` 时推进一条并扣减未读字节
+    ///
+    /// 读指针已被覆盖写入推走时返回 `false`，调用方应从新的读指针重试。
+    /// 与 [`read`](Self::read) 不同，只有确定要交给读者的条目才会被移除。
+    pub(crate) fn consume(&self, seq: usize, entry: &LogEntry) -> bool {
+        if self
+            .reader_data
+            .read_seq
+            .compare_exchange(seq, seq + 1, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return false;
+        }
+        interleave_point();
+        let formatted_len = calculate_formatted_length(entry);
+        self.unread_bytes
+            .fetch_sub(formatted_len, Ordering::Release);
+        true
+    }
+
+    /// 把清除点推进到 `seq`，不会回退
+    ///
+    /// 只修改读取侧的清除点，不与写者竞争槽位。
+    pub(crate) fn clear(&self, seq: usize) {
+        self.reader_data.clear_seq.fetch_max(seq, Ordering::AcqRel);
+    }
+
+    /// 非破坏性读取的起始索引：清除点与读指针中较新的一个
+    pub(crate) fn clear_index(&self) -> usize {
+        let clear = self.reader_data.clear_seq.load(Ordering::Acquire);
+        clear.max(self.reader_index())
+    }
+
+    /// 最旧的未被覆盖的序号，不受读指针影响（供独立读者使用）
+    pub(crate) fn oldest_index(&self) -> usize {
+        self.reader_data.oldest_seq.load(Ordering::Acquire)
+    }
+
+    /// 按序号取条目，不受读指针影响；已被覆盖或尚未发布时返回 `None`
+    pub(crate) fn get(&self, seq: usize) -> Option<LogEntry> {
+        let slot = &self.buffer[seq % MAX_LOG_ENTRIES];
+        if slot.seq() != seq {
+            return None;
+        }
+        let entry = slot.clone();
+        // 复制期间被覆盖时放弃
+        (slot.seq() == seq).then_some(entry)
+    }
+}
+
+/// 每 CPU 日志缓冲区
+///
+/// 写者只在全局序号上做一次 `fetch_add`，随后写入本 CPU 的环形缓冲区，
+/// 不同 CPU 之间不争用槽位，也没有覆盖时的 CAS 循环。
+///
+/// 读取侧仍以全局序号寻址，对外表现与 [`GlobalLogBuffer`] 一致：
+/// - `[reader_index, writer_index)` 内的序号要么可读，要么正在写入；
+/// - 某个 CPU 覆盖了序号为 `s` 的条目时，读指针被推到 `s + 1` 之后，
+///   其他 CPU 上更旧的条目一并视为丢弃，保证读者看到的序号连续、有序。
+#[repr(C)]
+pub(crate) struct PerCpuLogBuffer {
+    /// 全局序号（由所有 CPU 共享，只做 fetch_add）
+    writer_data: CachePadded64<WriterData>,
+    /// 读取侧数据（由消费者更新）
+    reader_data: CachePadded64<ReaderData>,
+    /// 各 CPU 的环形缓冲区
+    rings: [CachePadded64<CpuRing>; MAX_LOG_CPUS],
+}
+
+/// 单个 CPU 的环形缓冲区
+#[repr(C)]
+struct CpuRing {
+    /// 本 CPU 已预留的槽位数（中断嵌套时同一 CPU 上仍可能并发写入）
+    head: AtomicUsize,
+    /// 本 CPU 写入、尚未被读取的日志总字节数
+    unread_bytes: AtomicUsize,
+    /// 固定大小的日志条目数组
+    slots: [LogEntry; PER_CPU_LOG_ENTRIES],
+}
+
+impl CpuRing {
+    const fn new() -> Self {
+        Self {
+            head: AtomicUsize::new(0),
+            unread_bytes: AtomicUsize::new(0),
+            slots: [const { LogEntry::empty() }; PER_CPU_LOG_ENTRIES],
+        }
+    }
+
+    /// 序号为 `seq` 的已发布条目所在的槽位
+    fn slot(&self, seq: usize) -> Option<&LogEntry> {
+        self.slots.iter().find(|slot| slot.seq() == seq)
+    }
+
+    /// 在本 CPU 的缓冲区中查找序号为 `seq` 的已发布条目
+    fn find(&self, seq: usize) -> Option<LogEntry> {
+        let slot = self.slot(seq)?;
+        let entry = slot.clone();
+        // 复制期间被覆盖时放弃
+        (slot.seq() == seq).then_some(entry)
+    }
+}
+
+impl PerCpuLogBuffer {
+    /// 在编译时创建一个新的每 CPU 日志缓冲区
+    pub(crate) const fn new() -> Self {
+        Self {
+            writer_data: CachePadded64 {
+                inner: WriterData {
+                    write_seq: AtomicUsize::new(1),
+                },
+            },
+            reader_data: CachePadded64 {
+                inner: ReaderData {
+                    read_seq: AtomicUsize::new(1),
+                    dropped: AtomicUsize::new(0),
+                    clear_seq: AtomicUsize::new(1),
+                    oldest_seq: AtomicUsize::new(1),
+                },
+            },
+            rings: [const {
+                CachePadded64 {
+                    inner: CpuRing::new(),
+                }
+            }; MAX_LOG_CPUS],
+        }
+    }
+
+    fn ring(&self, cpu_id: usize) -> &CpuRing {
+        &self.rings[cpu_id % MAX_LOG_CPUS]
+    }
+
+    /// 将日志条目写入其 CPU 的缓冲区
+    ///
+    /// 1. 原子地获取全局序号（票据）
+    /// 2. 在本 CPU 的缓冲区中预留槽位
+    /// 3. 槽位中仍有旧条目时，把读指针推过它
+    /// 4. 作废槽位后复制数据，再以 **Release** 顺序发布序号
+    /// 5. 增加本 CPU 的未读字节计数
+    ///
+    /// 返回分配给该条目的全局序号。
+    pub(crate) fn write(&self, entry: &LogEntry) -> usize {
+        let seq = self.writer_data.write_seq.fetch_add(1, Ordering::Relaxed);
+
+        let ring = self.ring(entry.cpu_id());
+        let local = ring.head.fetch_add(1, Ordering::Relaxed);
+        let slot_ptr =
+            unsafe { ring.slots.as_ptr().add(local % PER_CPU_LOG_ENTRIES) as *mut LogEntry };
+
+        let old_seq = unsafe { (*slot_ptr).seq() };
+        if old_seq != 0 {
+            self.handle_overwrite(old_seq);
+        }
+
+        unsafe {
+            entry.publish(slot_ptr, 0);
+            entry.copy_data_to(slot_ptr);
+            entry.publish(slot_ptr, seq);
+        }
+
+        let formatted_len = calculate_formatted_length(entry);
+        ring.unread_bytes
+            .fetch_add(formatted_len, Ordering::Release);
+        seq
+    }
+
+    /// 在 CPU `cpu_id` 写入的、序号为 `seq` 的条目末尾追加文本，
+    /// 语义同 [`GlobalLogBuffer::append`]
+    pub(crate) fn append(
+        &self,
+        cpu_id: usize,
+        seq: usize,
+        args: fmt::Arguments,
+    ) -> Option<(LogEntry, usize)> {
+        let ring = self.ring(cpu_id);
+        let slot_ptr = core::ptr::from_ref(ring.slot(seq)?).cast_mut();
+        let old_len = unsafe {
+            LogEntry::append_message(slot_ptr, args, |added| {
+                if self.reader_index() <= seq {
+                    ring.unread_bytes.fetch_add(added, Ordering::Release);
+                }
+            })
+        };
+        let entry = unsafe { (*slot_ptr).clone() };
+        (entry.seq() == seq).then_some((entry, old_len))
+    }
+
+    /// 序号为 `old_seq` 的条目被覆盖：读指针至少推进到 `old_seq + 1`
+    fn handle_overwrite(&self, old_seq: usize) {
+        self.reader_data
+            .oldest_seq
+            .fetch_max(old_seq + 1, Ordering::AcqRel);
+        let prev = self
+            .reader_data
+            .read_seq
+            .fetch_max(old_seq + 1, Ordering::AcqRel);
+        if prev <= old_seq {
+            self.reader_data
+                .dropped
+                .fetch_add(old_seq + 1 - prev, Ordering::Relaxed);
+        }
+    }
+
+    /// 按全局顺序读取下一个日志条目
+    ///
+    /// 下一个序号的条目尚未发布时返回 `None`，不会越过它读取更新的条目。
+    pub(crate) fn read(&self) -> Option<LogEntry> {
+        loop {
+            let seq = self.reader_index();
+            let entry = self.peek(seq)?;
+            if self.consume(seq, &entry) {
+                return Some(entry);
+            }
+        }
+    }
+
+    /// 返回缓冲区中未读日志条目的数量
+    pub(crate) fn len(&self) -> usize {
+        let write = self.writer_data.write_seq.load(Ordering::Relaxed);
+        let read = self.reader_data.read_seq.load(Ordering::Relaxed);
+        write.saturating_sub(read)
+    }
+
+    /// 返回未读日志的总字节数（格式化后）
+    pub(crate) fn unread_bytes(&self) -> usize {
+        self.rings
+            .iter()
+            .map(|ring| ring.unread_bytes.load(Ordering::Acquire))
+            .sum()
+    }
+
+    /// 返回由于缓冲区溢出而丢弃的日志总数
+    pub(crate) fn dropped_count(&self) -> usize {
+        self.reader_data.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 非破坏性读取：按全局序号 peek 日志条目，不移动读指针
+    ///
+    /// 依次在各 CPU 的缓冲区中查找该序号；超出 `[reader_index, writer_index)`、
+    /// 已被覆盖或尚未发布时返回 `None`。
+    pub(crate) fn peek(&self, index: usize) -> Option<LogEntry> {
+        if index < self.reader_index() || index >= self.writer_index() {
+            return None;
+        }
+        self.rings.iter().find_map(|ring| ring.find(index))
+    }
+
+    /// 获取当前可读取的起始索引（读指针位置）
+    pub(crate) fn reader_index(&self) -> usize {
+        self.reader_data.read_seq.load(Ordering::Acquire)
+    }
+
+    /// 获取当前写入位置（下一个要写入的索引）
+    pub(crate) fn writer_index(&self) -> usize {
+        self.writer_data.write_seq.load(Ordering::Acquire)
+    }
+
+    /// 消费 `peek(seq)` 得到的条目，语义同 [`GlobalLogBuffer::consume`]
+    pub(crate) fn consume(&self, seq: usize, entry: &LogEntry) -> bool {
+        if self
+            .reader_data
+            .read_seq
+            .compare_exchange(seq, seq + 1, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return false;
+        }
+        let formatted_len = calculate_formatted_length(entry);
+        self.ring(entry.cpu_id())
+            .unread_bytes
+            .fetch_sub(formatted_len, Ordering::Release);
+        true
+    }
+
+    /// 把清除点推进到 `seq`，不会回退
+    pub(crate) fn clear(&self, seq: usize) {
+        self.reader_data.clear_seq.fetch_max(seq, Ordering::AcqRel);
+    }
+
+    /// 非破坏性读取的起始索引：清除点与读指针中较新的一个
+    pub(crate) fn clear_index(&self) -> usize {
+        let clear = self.reader_data.clear_seq.load(Ordering::Acquire);
+        clear.max(self.reader_index())
+    }
+    /// 最旧的未被覆盖的序号，语义同 [`GlobalLogBuffer::oldest_index`]
+    pub(crate) fn oldest_index(&self) -> usize {
+        self.reader_data.oldest_seq.load(Ordering::Acquire)
+    }
+
+    /// 按全局序号取条目，不受读指针影响，语义同 [`GlobalLogBuffer::get`]
+    pub(crate) fn get(&self, seq: usize) -> Option<LogEntry> {
+        self.rings.iter().find_map(|ring| ring.find(seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::format;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    use test_support::interleave::{self, Model};
+
+    use super::*;
+    use crate::level::LogLevel;
+
+    fn entry(cpu_id: usize, n: usize) -> LogEntry {
+        LogEntry::from_args(
+            LogLevel::Info,
+            cpu_id,
+            0,
+            n,
+            format_args!("cpu{} #{}", cpu_id, n),
+        )
+    }
+
+    /// 派生 `writers` 个写者，每个写者依次写两条日志
+    fn spawn_writers(
+        buffer: &Arc<GlobalLogBuffer>,
+        writers: usize,
+    ) -> Vec<interleave::JoinHandle<()>> {
+        (0..writers)
+            .map(|cpu| {
+                let buffer = buffer.clone();
+                interleave::spawn(move || {
+                    for n in 0..2 {
+                        buffer.write(&entry(cpu, n));
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// 两个写者与破坏性读者并发：每条日志恰好读出一次、序号连续，
+    /// 同一写者的日志保持先后顺序，结束时未读字节归零
+    #[test]
+    fn test_interleaved_writers_and_reader() {
+        let report = Model::exhaustive(2).check(|| {
+            let buffer = Arc::new(GlobalLogBuffer::new());
+            let writers = spawn_writers(&buffer, 2);
+
+            let mut seen = Vec::new();
+            while seen.len() < 4 {
+                match buffer.read() {
+                    Some(e) => seen.push(e),
+                    None => interleave::spin_loop(),
+                }
+            }
+            for w in writers {
+                w.join();
+            }
+
+            for (i, e) in seen.iter().enumerate() {
+                assert_eq!(e.seq(), i + 1);
+            }
+            for cpu in 0..2 {
+                let order: Vec<_> = seen
+                    .iter()
+                    .filter(|e| e.cpu_id() == cpu)
+                    .map(|e| e.message())
+                    .collect();
+                assert_eq!(order, [format!("cpu{} #0", cpu), format!("cpu{} #1", cpu)]);
+            }
+            assert!(buffer.read().is_none());
+            assert_eq!(buffer.len(), 0);
+            assert_eq!(buffer.unread_bytes(), 0);
+        });
+        assert!(report.complete);
+        assert!(report.executions > 1);
+    }
+
+    /// `LogCore` 的读取路径（peek + consume）与写者并发：
+    /// 只会看到已发布的完整条目，且同一条目不会被消费两次
+    #[test]
+    fn test_interleaved_peek_consume() {
+        let report = Model::exhaustive(2).check(|| {
+            let buffer = Arc::new(GlobalLogBuffer::new());
+            let writers = spawn_writers(&buffer, 2);
+
+            let mut seq = buffer.reader_index();
+            while seq < 5 {
+                match buffer.peek(seq) {
+                    Some(e) => {
+                        assert_eq!(e.seq(), seq);
+                        assert_eq!(e.message(), format!("cpu{} #{}", e.cpu_id(), e.timestamp()));
+                        assert!(buffer.consume(seq, &e));
+                        assert!(!buffer.consume(seq, &e));
+                        seq += 1;
+                    }
+                    None => interleave::spin_loop(),
+                }
+            }
+            for w in writers {
+                w.join();
+            }
+
+            assert_eq!(buffer.reader_index(), buffer.writer_index());
+            assert_eq!(buffer.unread_bytes(), 0);
+        });
+        assert!(report.complete);
+    }
 }
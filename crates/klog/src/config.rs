@@ -8,15 +8,50 @@
 ///
 /// 缓冲区实现为**固定大小的环形缓冲区**。当缓冲区满时，新日志将
 /// 覆盖最旧的条目。对于一个 16KB 的缓冲区和典型的条目大小，
-/// 大约可以存储 50-60 个日志条目。
+/// 大约可以存储 30 个日志条目（每个条目含消息与结构化字段，约 500 字节）。
 pub const GLOBAL_LOG_BUFFER_SIZE: usize = 16 * 1024; // 16KB
 
+/// 每 CPU 缓冲区模式下单个 CPU 的缓冲区大小（以字节为单位）
+///
+/// 仅在启用 `per-cpu-buffer` 特性时使用。各 CPU 写入自己的环形缓冲区，
+/// 总占用为该值乘以 [`MAX_LOG_CPUS`]。
+pub const PER_CPU_LOG_BUFFER_SIZE: usize = 4 * 1024; // 4KB
+
+/// 每 CPU 缓冲区模式下的缓冲区个数
+///
+/// CPU ID 超出此范围时按取模映射到已有的缓冲区。
+pub const MAX_LOG_CPUS: usize = 8;
+
+/// 实际使用的日志缓冲区总大小（以字节为单位）
+#[cfg(not(feature = "per-cpu-buffer"))]
+pub const LOG_BUFFER_SIZE: usize = GLOBAL_LOG_BUFFER_SIZE;
+
+/// 实际使用的日志缓冲区总大小（以字节为单位）
+#[cfg(feature = "per-cpu-buffer")]
+pub const LOG_BUFFER_SIZE: usize = PER_CPU_LOG_BUFFER_SIZE * MAX_LOG_CPUS;
+
 /// 单个日志消息的最大长度（以字节为单位）
 ///
 /// 超过此长度的消息将被**截断**。此限制可防止单个日志占用
 /// 过多的缓冲区空间。
 pub const MAX_LOG_MESSAGE_LENGTH: usize = 256;
 
+/// 除主输出端外可注册的附加输出端（如 netconsole）数上限
+pub const MAX_LOG_SINKS: usize = 4;
+
+/// 可按目标单独控制调试日志的目标（子系统）数上限
+pub const MAX_LOG_TARGETS: usize = 64;
+
+/// 单条日志可携带的结构化字段数上限
+///
+/// 超出的字段被丢弃。
+pub const MAX_LOG_FIELDS: usize = 4;
+
+/// 结构化字段中字符串值的最大长度（以字节为单位）
+///
+/// 超过此长度的值在字符边界处截断。
+pub const MAX_LOG_FIELD_STR_LEN: usize = 16;
+
 /// 默认全局日志级别
 ///
 /// 处于此级别或更高优先级的日志将被记录到缓冲区中。
@@ -28,3 +63,11 @@ pub const DEFAULT_LOG_LEVEL: super::level::LogLevel = super::level::LogLevel::In
 /// 处于此级别或更高优先级的日志将**立即打印到控制台**。
 /// 默认值为 `Warning`，意味着默认情况下只有警告和错误才会出现在控制台上。
 pub const DEFAULT_CONSOLE_LEVEL: super::level::LogLevel = super::level::LogLevel::Info;
+
+/// 限速日志宏的默认窗口长度（与 `LogContextProvider::timestamp` 同单位，内核中为纳秒）
+///
+/// 与 Linux 的 `DEFAULT_RATELIMIT_INTERVAL`（5 秒）一致。
+pub const DEFAULT_RATELIMIT_INTERVAL: usize = 5_000_000_000;
+
+/// 限速日志宏在每个窗口内允许输出的条数
+pub const DEFAULT_RATELIMIT_BURST: usize = 10;
@@ -2,8 +2,11 @@
 //!
 //! 该模块定义了表示单个日志消息及其元数据的 `LogEntry` 结构体，
 //! 并提供了用于创建和格式化日志条目的实用程序。
+//!
+//! 除格式化后的消息外，条目还可以携带少量结构化字段（[`LogField`]），
+//! 供 /dev/kmsg 等接口以机器可读的方式导出。
 
-use super::config::MAX_LOG_MESSAGE_LENGTH;
+use super::config::{MAX_LOG_FIELD_STR_LEN, MAX_LOG_FIELDS, MAX_LOG_MESSAGE_LENGTH};
 use super::level::LogLevel;
 use core::cmp::min;
 use core::fmt::{self, Write};
@@ -22,6 +25,8 @@ pub struct LogEntry {
     seq: AtomicUsize,
     /// 日志级别 (Emergency, Error, Info, 等)
     level: LogLevel,
+    /// 经延迟路径写入：控制台输出一律交给刷写方
+    deferred: bool,
     /// 生成此日志的 CPU ID
     cpu_id: usize,
     /// 消息的实际长度（以字节为单位）
@@ -32,6 +37,137 @@ pub struct LogEntry {
     timestamp: usize,
     /// 用于日志消息的固定大小缓冲区
     message: [u8; MAX_LOG_MESSAGE_LENGTH],
+    /// 有效的结构化字段数
+    field_count: usize,
+    /// 结构化字段
+    fields: [LogField; MAX_LOG_FIELDS],
+}
+
+/// 结构化字段的值：整数或短字符串
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogValue {
+    /// 无符号整数
+    U64(u64),
+    /// 有符号整数
+    I64(i64),
+    /// 短字符串（超过 [`MAX_LOG_FIELD_STR_LEN`] 的部分被截断）
+    Str {
+        /// 字符串字节
+        buf: [u8; MAX_LOG_FIELD_STR_LEN],
+        /// 有效长度
+        len: u8,
+    },
+}
+
+impl LogValue {
+    /// 从字符串创建值，必要时在字符边界处截断
+    pub const fn from_str(s: &str) -> Self {
+        let bytes = s.as_bytes();
+        let mut len = if bytes.len() < MAX_LOG_FIELD_STR_LEN {
+            bytes.len()
+        } else {
+            MAX_LOG_FIELD_STR_LEN
+        };
+        // 退到 UTF-8 字符边界
+        while len < bytes.len() && len > 0 && (bytes[len] & 0xc0) == 0x80 {
+            len -= 1;
+        }
+        let mut buf = [0u8; MAX_LOG_FIELD_STR_LEN];
+        let mut i = 0;
+        while i < len {
+            buf[i] = bytes[i];
+            i += 1;
+        }
+        Self::Str {
+            buf,
+            len: len as u8,
+        }
+    }
+
+    /// 字符串值的内容，数值返回 `None`
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            // Safety: from_str 只在字符边界处截断
+            Self::Str { buf, len } => {
+                Some(unsafe { core::str::from_utf8_unchecked(&buf[..*len as usize]) })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LogValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::U64(v) => write!(f, "{}", v),
+            Self::I64(v) => write!(f, "{}", v),
+            Self::Str { .. } => f.write_str(self.as_str().unwrap_or("")),
+        }
+    }
+}
+
+macro_rules! impl_log_value_from {
+    ($variant:ident, $target:ty: $($ty:ty),*) => {
+        $(
+            impl From<$ty> for LogValue {
+                fn from(v: $ty) -> Self {
+                    Self::$variant(v as $target)
+                }
+            }
+        )*
+    };
+}
+
+impl_log_value_from!(U64, u64: u8, u16, u32, u64, usize);
+impl_log_value_from!(I64, i64: i8, i16, i32, i64, isize);
+
+impl From<&str> for LogValue {
+    fn from(s: &str) -> Self {
+        Self::from_str(s)
+    }
+}
+
+impl From<bool> for LogValue {
+    fn from(v: bool) -> Self {
+        Self::U64(v as u64)
+    }
+}
+
+/// 日志条目携带的一个结构化字段
+///
+/// 键是静态字符串，按惯例使用大写字母、数字和下划线（如 `"DEVICE"`），
+/// 不应包含 `=`、空白或换行。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogField {
+    key: &'static str,
+    value: LogValue,
+}
+
+impl LogField {
+    /// 创建字段
+    pub fn new(key: &'static str, value: impl Into<LogValue>) -> Self {
+        Self {
+            key,
+            value: value.into(),
+        }
+    }
+
+    const fn empty() -> Self {
+        Self {
+            key: "",
+            value: LogValue::U64(0),
+        }
+    }
+
+    /// 字段键
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// 字段值
+    pub fn value(&self) -> &LogValue {
+        &self.value
+    }
 }
 
 impl LogEntry {
@@ -43,11 +179,14 @@ impl LogEntry {
         Self {
             seq: AtomicUsize::new(0),
             level: LogLevel::Debug,
+            deferred: false,
             cpu_id: 0,
             length: 0,
             task_id: 0,
             timestamp: 0,
             message: [0; MAX_LOG_MESSAGE_LENGTH],
+            field_count: 0,
+            fields: [LogField::empty(); MAX_LOG_FIELDS],
         }
     }
 
@@ -70,11 +209,14 @@ impl LogEntry {
         let mut entry = Self {
             seq: AtomicUsize::new(0),
             level,
+            deferred: false,
             cpu_id,
             length: 0,
             task_id,
             timestamp,
             message: [0; MAX_LOG_MESSAGE_LENGTH],
+            field_count: 0,
+            fields: [LogField::empty(); MAX_LOG_FIELDS],
         };
 
         // 将消息格式化到固定大小的缓冲区中
@@ -86,6 +228,18 @@ impl LogEntry {
         entry
     }
 
+    /// 附加结构化字段，超过 [`MAX_LOG_FIELDS`] 的部分被丢弃
+    pub(crate) fn set_fields(&mut self, fields: &[LogField]) {
+        let count = min(fields.len(), MAX_LOG_FIELDS);
+        self.fields[..count].copy_from_slice(&fields[..count]);
+        self.field_count = count;
+    }
+
+    /// 返回日志携带的结构化字段
+    pub fn fields(&self) -> &[LogField] {
+        &self.fields[..self.field_count]
+    }
+
     /// 将日志消息作为字符串切片返回
     pub fn message(&self) -> &str {
         // Safety: MessageWriter 确保了有效的 UTF-8
@@ -97,6 +251,16 @@ impl LogEntry {
         self.level
     }
 
+    /// 是否经延迟路径（[`log_impl_deferred`](crate::log_impl_deferred)）写入
+    pub fn is_deferred(&self) -> bool {
+        self.deferred
+    }
+
+    /// 标记条目经延迟路径写入（供内部使用）
+    pub(crate) fn set_deferred(&mut self, deferred: bool) {
+        self.deferred = deferred;
+    }
+
     /// 返回生成此日志的 CPU ID
     pub fn cpu_id(&self) -> usize {
         self.cpu_id
@@ -127,11 +291,14 @@ impl LogEntry {
         // 我们必须逐个字段复制，**除了** seq
         unsafe {
             (*dest).level = self.level;
+            (*dest).deferred = self.deferred;
             (*dest).cpu_id = self.cpu_id;
             (*dest).length = self.length;
             (*dest).task_id = self.task_id;
             (*dest).timestamp = self.timestamp;
             (*dest).message.copy_from_slice(&self.message);
+            (*dest).field_count = self.field_count;
+            (*dest).fields = self.fields;
         }
     }
 
@@ -151,6 +318,44 @@ impl LogEntry {
         }
     }
 
+    /// 返回条目的全局序列号
+    ///
+    /// 序列号由所有 CPU 共享、从 1 开始单调递增，写入缓冲区时分配，
+    /// 可用于判断两条日志的先后以及是否有日志丢失。尚未写入缓冲区的条目为 0。
+    pub fn seq(&self) -> usize {
+        self.seq.load(Ordering::Acquire)
+    }
+
+    /// 设置条目副本的序列号（供内部使用）
+    pub(crate) fn set_seq(&mut self, seq: usize) {
+        *self.seq.get_mut() = seq;
+    }
+
+    /// 在槽中已发布条目的消息末尾追加格式化文本（供内部使用）
+    ///
+    /// 先写入新字节，再以新增的字节数调用 `commit`，最后才更新长度。消息只增长不改写，
+    /// 并发复制该条目的读者看到的要么是追加前、要么是追加后的完整消息。
+    /// 超出消息缓冲区的部分被截断。返回追加前的长度。
+    ///
+    /// # 安全性
+    ///
+    /// `dest` 必须指向环形缓冲区中有效的 `LogEntry`，且同一条目不能被并发追加
+    pub(crate) unsafe fn append_message(
+        dest: *mut LogEntry,
+        args: fmt::Arguments,
+        commit: impl FnOnce(usize),
+    ) -> usize {
+        unsafe {
+            let old_len = (*dest).length;
+            let mut writer = MessageWriter::new(&mut (&mut (*dest).message)[old_len..]);
+            let _ = core::fmt::write(&mut writer, args);
+            let added = writer.len();
+            commit(added);
+            (*dest).length = old_len + added;
+            old_len
+        }
+    }
+
     /// 检查槽是否已准备好读取（供内部使用）
     ///
     /// 使用 **Acquire** 内存顺序与生产者在 `publish()` 中的 Release 存储配对，
@@ -170,11 +375,14 @@ impl Clone for LogEntry {
         Self {
             seq: AtomicUsize::new(self.seq.load(Ordering::Relaxed)),
             level: self.level,
+            deferred: self.deferred,
             cpu_id: self.cpu_id,
             length: self.length,
             task_id: self.task_id,
             timestamp: self.timestamp,
             message: self.message,
+            field_count: self.field_count,
+            fields: self.fields,
         }
     }
 }
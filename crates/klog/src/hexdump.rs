@@ -0,0 +1,105 @@
+//! 十六进制转储
+//!
+//! [`pr_hex_dump!`](crate::pr_hex_dump) 按 `hexdump -C` 的格式把一段字节逐行写入日志，
+//! 每行一条记录：
+//!
+//! ```text
+//! <前缀>00000000  7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  |.ELF............|
+//! ```
+//!
+//! 行在写入时才格式化，不需要堆分配。前缀过长、一行 16 字节放不进
+//! [`MAX_LOG_MESSAGE_LENGTH`] 时相应减少每行的字节数。
+
+use core::fmt;
+
+use super::config::MAX_LOG_MESSAGE_LENGTH;
+
+/// 每行最多显示的字节数
+pub const HEX_DUMP_ROW_SIZE: usize = 16;
+
+/// 每行除数据外的固定长度：偏移 8、偏移后与 ASCII 列前的空格 3、中间分组 1、两侧 `|` 2
+const ROW_OVERHEAD: usize = 8 + 3 + 1 + 2;
+
+/// 前缀长度为 `prefix_len` 时每行的字节数（至少 1）
+pub(crate) fn row_size(prefix_len: usize) -> usize {
+    // 每字节占 " xx" 与一个 ASCII 字符
+    let room = MAX_LOG_MESSAGE_LENGTH.saturating_sub(prefix_len + ROW_OVERHEAD) / 4;
+    room.clamp(1, HEX_DUMP_ROW_SIZE)
+}
+
+/// 转储中的一行
+pub(crate) struct HexDumpLine<'a> {
+    /// 本行第一个字节在整段数据中的偏移
+    pub(crate) offset: usize,
+    /// 本行的字节
+    pub(crate) bytes: &'a [u8],
+    /// 每行的字节数，最后一行不足时用空格补齐，使 ASCII 列对齐
+    pub(crate) row_size: usize,
+}
+
+impl fmt::Display for HexDumpLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x} ", self.offset)?;
+        let half = self.row_size.div_ceil(2);
+        for i in 0..self.row_size {
+            if i == half {
+                f.write_str(" ")?;
+            }
+            match self.bytes.get(i) {
+                Some(b) => write!(f, " {:02x}", b)?,
+                None => f.write_str("   ")?,
+            }
+        }
+        f.write_str("  |")?;
+        for &b in self.bytes {
+            let c = if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            };
+            write!(f, "{}", c)?;
+        }
+        f.write_str("|")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn test_hex_dump_line_format() {
+        let data = b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0";
+        let line = HexDumpLine {
+            offset: 0,
+            bytes: data,
+            row_size: HEX_DUMP_ROW_SIZE,
+        };
+        assert_eq!(
+            format!("{}", line),
+            "00000000  7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  |.ELF............|"
+        );
+
+        // 最后一行不足时补齐，ASCII 列与完整行对齐
+        let short = HexDumpLine {
+            offset: 0x10,
+            bytes: b"hi\n",
+            row_size: HEX_DUMP_ROW_SIZE,
+        };
+        let short = format!("{}", short);
+        assert_eq!(short.find('|'), format!("{}", line).find('|'));
+        assert!(short.starts_with("00000010  68 69 0a "));
+        assert!(short.ends_with("|hi.|"));
+    }
+
+    #[test]
+    fn test_hex_dump_row_size_fits_message() {
+        assert_eq!(row_size(0), HEX_DUMP_ROW_SIZE);
+        let prefix = 200;
+        let rows = row_size(prefix);
+        assert!(rows < HEX_DUMP_ROW_SIZE);
+        assert!(prefix + ROW_OVERHEAD + rows * 4 <= MAX_LOG_MESSAGE_LENGTH);
+        assert_eq!(row_size(MAX_LOG_MESSAGE_LENGTH), 1);
+    }
+}
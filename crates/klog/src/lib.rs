@@ -4,11 +4,16 @@
 //!
 //! # 组件
 //!
-//! - [`buffer`] - 用于日志存储的无锁环形缓冲区
+//! - [`boot_params`] - 内核命令行中的日志参数（`loglevel=`、`quiet`、`debug`）
+//! - [`buffer`] - 用于日志存储的无锁环形缓冲区（全局共享或每 CPU 一个）
 //! - [`config`] - 配置常量（缓冲区大小、消息长度限制）
 //! - [`log_core`] - 核心日志实现 (LogCore)
 //! - [`entry`] - 日志条目结构和序列化
+//! - [`hexdump`] - `pr_hex_dump!` 使用的 `hexdump -C` 格式行
 //! - [`level`] - 日志级别定义（从 Emergency 到 Debug）
+//! - [`reader`] - 拥有独立游标的日志读者与快照
+//! - [`target`] - 按目标（子系统）打开调试日志的注册表
+//! - [`pstore`] - panic 时把日志保存到跨重启保留的存储中
 //! - [`macros`] - 面向用户的日志宏 (`pr_info!`, `pr_err!`, 等)
 //!
 //! # 设计概览
@@ -18,6 +23,7 @@
 //! 日志系统采用两层方法：
 //!
 //! 1. **即时控制台输出**：达到控制台级别阈值（默认：Warning 及以上）的日志会**直接打印到控制台**，以实现紧急可见性。
+//!    开启延迟模式（[`set_console_deferred`]）后改由刷写线程调用 [`flush_console`] 输出，Emergency 仍同步输出。
 //! 2. **环形缓冲区存储**：所有达到全局级别阈值（默认：Info 及以上）的日志都会被写入**无锁环形缓冲区**，用于异步消费或事后分析。
 //!
 //! ## 性能特点
@@ -25,6 +31,7 @@
 //! - **无锁并发**：使用原子操作（fetch_add, CAS）而非互斥锁，支持多生产者日志记录而**不会阻塞**。
 //! - **早期过滤**：日志级别检查在宏展开时发生，避免对禁用级别的日志进行格式化字符串评估。
 //! - **固定大小分配**：**没有动态内存分配**；所有结构体使用编译时已知的大小，适用于裸机环境。
+//! - **每 CPU 缓冲区**（`per-cpu-buffer` 特性）：各 CPU 写入自己的环形缓冲区，写者之间只共享一个全局序号，读者仍按序号看到全局有序的日志流。
 //! - **缓存优化**：读写器数据结构经过缓存行填充（64 字节），以防止多核系统上的**伪共享**。
 //! - **尽可能零拷贝**：在可行的情况下，日志条目是**就地构造**的，以最大限度地减少内存操作。
 //!
@@ -33,7 +40,7 @@
 //! 日志系统通过 trait 抽象与架构特定组件解耦：
 //!
 //! - **LogContextProvider**：提供 CPU ID、任务 ID、时间戳
-//! - **LogOutput**：提供控制台输出能力
+//! - **LogOutput**：提供控制台输出能力；[`register_log_sink`] 可以再注册若干附加输出端
 //!
 //! 使用方需要在启动时注册这些 trait 的实现。
 
@@ -46,19 +53,29 @@ extern crate std;
 
 extern crate alloc;
 
+mod boot_params;
 mod buffer;
 mod config;
 mod entry;
+mod hexdump;
 mod level;
 mod log_core;
 pub mod macros;
+pub mod pstore;
+mod reader;
+mod target;
 
+pub use boot_params::BootLogParams;
 pub use config::{
-    DEFAULT_CONSOLE_LEVEL, DEFAULT_LOG_LEVEL, GLOBAL_LOG_BUFFER_SIZE, MAX_LOG_MESSAGE_LENGTH,
+    DEFAULT_CONSOLE_LEVEL, DEFAULT_LOG_LEVEL, DEFAULT_RATELIMIT_BURST, DEFAULT_RATELIMIT_INTERVAL,
+    GLOBAL_LOG_BUFFER_SIZE, LOG_BUFFER_SIZE, MAX_LOG_CPUS, MAX_LOG_FIELD_STR_LEN, MAX_LOG_FIELDS,
+    MAX_LOG_MESSAGE_LENGTH, MAX_LOG_SINKS, PER_CPU_LOG_BUFFER_SIZE,
 };
-pub use entry::LogEntry;
+pub use entry::{LogEntry, LogField, LogValue};
+pub use hexdump::HEX_DUMP_ROW_SIZE;
 pub use level::LogLevel;
-pub use log_core::{LogCore, format_log_entry};
+pub use log_core::{LogCore, LogStyle, RateLimitState, format_log_entry};
+pub use reader::LogReader;
 
 use core::sync::atomic::{AtomicPtr, Ordering};
 
@@ -84,6 +101,23 @@ pub trait LogContextProvider: Send + Sync {
 pub trait LogOutput: Send + Sync {
     /// 输出字符串到控制台
     fn write_str(&self, s: &str);
+
+    /// 是否需要接收不满足全局控制台级别的日志
+    ///
+    /// 某个控制台单独设置了更详细的日志级别时返回 `true`。
+    fn wants_level(&self, _level: LogLevel) -> bool {
+        false
+    }
+
+    /// 输出一条已格式化的日志
+    ///
+    /// `console` 表示该级别满足全局控制台级别。默认实现只输出满足全局级别的日志，
+    /// 按控制台分别设置级别的实现可以覆盖此方法自行过滤。
+    fn write_log(&self, _level: LogLevel, s: &str, console: bool) {
+        if console {
+            self.write_str(s);
+        }
+    }
 }
 
 // ========== 全局注册机制 ==========
@@ -116,10 +150,23 @@ impl LogOutputPtr {
             vtable: AtomicPtr::new(core::ptr::null_mut()),
         }
     }
+
+    fn get(&self) -> Option<&'static dyn LogOutput> {
+        let data = self.data.load(Ordering::Acquire);
+        let vtable = self.vtable.load(Ordering::Acquire);
+        if data.is_null() || vtable.is_null() {
+            return None;
+        }
+        // Safety: 指针由 register_log_output 或 register_log_sink 设置，保证有效
+        Some(unsafe {
+            core::mem::transmute::<(*mut (), *mut ()), &'static dyn LogOutput>((data, vtable))
+        })
+    }
 }
 
 static CONTEXT_PROVIDER: ContextProviderPtr = ContextProviderPtr::new();
 static LOG_OUTPUT: LogOutputPtr = LogOutputPtr::new();
+static LOG_SINKS: [LogOutputPtr; MAX_LOG_SINKS] = [const { LogOutputPtr::new() }; MAX_LOG_SINKS];
 
 /// 注册日志上下文提供者
 ///
@@ -149,6 +196,36 @@ pub unsafe fn register_log_output(output: &'static dyn LogOutput) {
     LOG_OUTPUT.vtable.store(vtable, Ordering::Release);
 }
 
+/// 注册附加输出端（如 netconsole），与主输出端收到相同的日志
+///
+/// 附加输出端已满时返回 `false`。
+///
+/// # Safety
+///
+/// - sink 必须具有 'static 生命周期
+/// - 同一个 sink 只能注册一次
+pub unsafe fn register_log_sink(sink: &'static dyn LogOutput) -> bool {
+    let ptr: *const dyn LogOutput = sink;
+    let (data, vtable) =
+        unsafe { core::mem::transmute::<*const dyn LogOutput, (*mut (), *mut ())>(ptr) };
+    for slot in &LOG_SINKS {
+        if slot
+            .data
+            .compare_exchange(
+                core::ptr::null_mut(),
+                data,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            slot.vtable.store(vtable, Ordering::Release);
+            return true;
+        }
+    }
+    false
+}
+
 /// 获取已注册的上下文提供者
 pub(crate) fn get_context_provider() -> Option<&'static dyn LogContextProvider> {
     let data = CONTEXT_PROVIDER.data.load(Ordering::Acquire);
@@ -164,15 +241,15 @@ pub(crate) fn get_context_provider() -> Option<&'static dyn LogContextProvider>
 
 /// 获取已注册的日志输出
 pub(crate) fn get_log_output() -> Option<&'static dyn LogOutput> {
-    let data = LOG_OUTPUT.data.load(Ordering::Acquire);
-    let vtable = LOG_OUTPUT.vtable.load(Ordering::Acquire);
-    if data.is_null() || vtable.is_null() {
-        return None;
-    }
-    // Safety: 指针由 register_log_output 设置，保证有效
-    Some(unsafe {
-        core::mem::transmute::<(*mut (), *mut ()), &'static dyn LogOutput>((data, vtable))
-    })
+    LOG_OUTPUT.get()
+}
+
+/// 依次访问主输出端与所有附加输出端
+pub(crate) fn for_each_log_output(f: impl FnMut(&'static dyn LogOutput)) {
+    get_log_output()
+        .into_iter()
+        .chain(LOG_SINKS.iter().filter_map(LogOutputPtr::get))
+        .for_each(f);
 }
 
 // ========== 全局单例 ==========
@@ -191,22 +268,127 @@ pub fn log_impl(level: LogLevel, args: core::fmt::Arguments) {
     GLOBAL_LOG._log(level, args);
 }
 
+/// 携带结构化字段的日志实现（由 `pr_fields!` 宏调用）
+#[doc(hidden)]
+pub fn log_fields_impl(level: LogLevel, fields: &[LogField], args: core::fmt::Arguments) {
+    GLOBAL_LOG._log_fields(level, fields, args);
+}
+
+/// 延迟日志实现（由 `printk_deferred!` 宏调用）
+///
+/// 只写环形缓冲区，从不直接调用控制台输出端，可以在持有运行队列锁的调度器路径
+/// 或陷入处理中使用而不会与串口锁死锁。控制台输出由之后的普通日志、
+/// [`flush_console`] 或刷写线程完成。
+pub fn log_impl_deferred(level: LogLevel, args: core::fmt::Arguments) {
+    GLOBAL_LOG._log_deferred(level, args);
+}
+
+/// 十六进制转储实现（由 `pr_hex_dump!` 宏调用）
+#[doc(hidden)]
+pub fn hex_dump_impl(level: LogLevel, prefix: &str, data: &[u8]) {
+    GLOBAL_LOG._hex_dump(level, prefix, data);
+}
+
+/// 续接日志实现（由 `pr_cont!` 宏调用）
+#[doc(hidden)]
+pub fn log_cont_impl(args: core::fmt::Arguments) {
+    GLOBAL_LOG._log_cont(args);
+}
+
+/// 记下被级别过滤的日志（由宏调用），使其后的 `pr_cont!` 一并丢弃
+#[doc(hidden)]
+pub fn log_filtered_impl() {
+    GLOBAL_LOG._log_filtered();
+}
+
+/// `warn_once!` 的实现：以 Warning 级别记录带调用点上下文的警告
+#[doc(hidden)]
+pub fn warn_impl(site: &str, module: &str, args: core::fmt::Arguments) {
+    GLOBAL_LOG._log(
+        LogLevel::Warning,
+        format_args!("WARNING: {} ({}): {}", site, module, args),
+    );
+}
+
+/// 限速日志实现（由 `pr_*_ratelimited!` 宏调用）
+#[doc(hidden)]
+pub fn log_ratelimited(
+    state: &RateLimitState,
+    level: LogLevel,
+    site: &str,
+    args: core::fmt::Arguments,
+) {
+    GLOBAL_LOG._log_ratelimited(state, level, site, args);
+}
+
 /// 检查日志级别是否启用（由宏调用）
 #[doc(hidden)]
 pub fn is_level_enabled(level: LogLevel) -> bool {
     level as u8 <= GLOBAL_LOG._get_global_level() as u8
 }
 
+/// 带目标的日志实现（由 `pr_*!(target: ..., ...)` 宏调用）
+#[doc(hidden)]
+pub fn log_target_impl(target: &'static str, level: LogLevel, args: core::fmt::Arguments) {
+    GLOBAL_LOG._log_target(target, level, args);
+}
+
+/// 检查带目标的日志级别是否启用（由宏调用）
+#[doc(hidden)]
+pub fn is_target_level_enabled(target: &'static str, level: LogLevel) -> bool {
+    GLOBAL_LOG._is_target_level_enabled(target, level)
+}
+
+/// 打开或关闭某个目标的调试日志，`*` 表示所有已登记的目标
+///
+/// 注册表已满、无法登记新目标时返回 `false`。
+pub fn set_target_debug(target: &str, enable: bool) -> bool {
+    GLOBAL_LOG._set_target_debug(target, enable)
+}
+
+/// 遍历已登记的目标及其调试开关
+pub fn for_each_target(f: impl FnMut(&str, bool)) {
+    GLOBAL_LOG._for_each_target(f)
+}
+
 /// 从缓冲区读取下一个日志条目
 pub fn read_log() -> Option<LogEntry> {
     GLOBAL_LOG._read_log()
 }
 
+/// 创建拥有独立游标的全局日志读者，从最旧的保留条目开始并跟随新日志
+///
+/// 多个读者（/dev/kmsg 的每次打开、崩溃转储、测试框架）可以同时使用，
+/// 互不"偷走"条目，也不影响 syslog 的破坏性读指针。
+pub fn log_reader() -> LogReader<'static> {
+    GLOBAL_LOG._reader()
+}
+
+/// 创建全局日志的快照读者：只遍历创建时已有的条目
+pub fn log_snapshot() -> LogReader<'static> {
+    GLOBAL_LOG._snapshot()
+}
+
 /// 非破坏性读取：按索引 peek 日志条目，不移动读指针
 pub fn peek_log(index: usize) -> Option<LogEntry> {
     GLOBAL_LOG._peek_log(index)
 }
 
+/// 破坏性批量读取：把完整的日志行复制到 `buf`，返回写入的字节数
+pub fn read_log_into(buf: &mut [u8]) -> usize {
+    GLOBAL_LOG._read_log_into(buf)
+}
+
+/// 非破坏性批量读取：复制清除点之后最新的日志行，可选地随后清除
+pub fn read_all_log_into(buf: &mut [u8], clear: bool) -> usize {
+    GLOBAL_LOG._read_all_log_into(buf, clear)
+}
+
+/// 清除日志（只推进非破坏性读取的起点）
+pub fn clear_log() {
+    GLOBAL_LOG._clear_log()
+}
+
 /// 获取当前可读取的起始索引
 pub fn log_reader_index() -> usize {
     GLOBAL_LOG._log_reader_index()
@@ -232,6 +414,59 @@ pub fn log_dropped_count() -> usize {
     GLOBAL_LOG._log_dropped_count()
 }
 
+/// 开启或关闭延迟控制台输出
+///
+/// 开启后控制台输出由刷写线程通过 [`flush_console`] 完成（Emergency 仍同步输出）；
+/// 关闭时先同步输出积压的日志。
+pub fn set_console_deferred(deferred: bool) {
+    GLOBAL_LOG._set_console_deferred(deferred)
+}
+
+/// 当前的控制台输出格式
+pub fn console_style() -> LogStyle {
+    GLOBAL_LOG._console_style()
+}
+
+/// 控制台输出是否带 ANSI 颜色
+pub fn is_console_color() -> bool {
+    GLOBAL_LOG._console_style().color
+}
+
+/// 开启或关闭控制台输出的 ANSI 颜色
+pub fn set_console_color(enable: bool) {
+    GLOBAL_LOG._set_console_color(enable)
+}
+
+/// 控制台输出是否使用 `[秒.微秒]` 时间前缀
+pub fn is_console_time() -> bool {
+    GLOBAL_LOG._console_style().time_prefix
+}
+
+/// 开启或关闭控制台输出的 `[秒.微秒]` 时间前缀
+pub fn set_console_time(enable: bool) {
+    GLOBAL_LOG._set_console_time(enable)
+}
+
+/// 是否处于延迟控制台输出模式
+pub fn is_console_deferred() -> bool {
+    GLOBAL_LOG._is_console_deferred()
+}
+
+/// 是否有尚未输出到控制台的日志
+pub fn console_pending() -> bool {
+    GLOBAL_LOG._console_pending()
+}
+
+/// 把积压的日志输出到控制台，返回输出的条数
+pub fn flush_console() -> usize {
+    GLOBAL_LOG._flush_console()
+}
+
+/// 按内核命令行调整日志级别（`loglevel=N`、`quiet`、`debug`），返回解析出的设置
+pub fn apply_boot_params(cmdline: &str) -> BootLogParams {
+    GLOBAL_LOG._apply_boot_params(cmdline)
+}
+
 /// 设置全局日志级别阈值
 pub fn set_global_level(level: LogLevel) {
     GLOBAL_LOG._set_global_level(level);
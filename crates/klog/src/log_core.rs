@@ -3,12 +3,16 @@
 //! 该模块将所有日志状态和逻辑封装到一个单独的 `LogCore` 结构体中，
 //! 可以在保持**无锁、零分配**设计的同时，独立实例化用于测试。
 
-use super::buffer::GlobalLogBuffer;
-use super::config::{DEFAULT_CONSOLE_LEVEL, DEFAULT_LOG_LEVEL};
-use super::entry::LogEntry;
+use super::boot_params::BootLogParams;
+use super::buffer::LogBuffer;
+use super::config::{DEFAULT_CONSOLE_LEVEL, DEFAULT_LOG_LEVEL, MAX_LOG_CPUS};
+use super::entry::{LogEntry, LogField};
+use super::hexdump::{self, HexDumpLine};
 use super::level::LogLevel;
+use super::reader::LogReader;
+use super::target::TargetRegistry;
 use core::fmt;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
 
 /// 核心日志系统
 ///
@@ -20,16 +24,44 @@ use core::sync::atomic::{AtomicU8, Ordering};
 /// 所有方法都使用原子操作进行同步，使得整个结构体在
 /// 线程之间安全共享，无需外部加锁。
 pub struct LogCore {
-    /// 用于日志存储的无锁环形缓冲区
-    buffer: GlobalLogBuffer,
+    /// 用于日志存储的无锁环形缓冲区（启用 `per-cpu-buffer` 时为每 CPU 缓冲区）
+    buffer: LogBuffer,
 
     /// 全局日志级别阈值（控制日志是否缓冲）
     global_level: AtomicU8,
 
     /// 控制台输出级别阈值（控制是否立即打印）
     console_level: AtomicU8,
+
+    /// 按目标的调试开关
+    targets: TargetRegistry,
+
+    /// 延迟控制台输出：为 `true` 时控制台输出交给刷写线程（Emergency 除外）
+    console_deferred: AtomicBool,
+
+    /// 下一条待由刷写方输出到控制台的条目序号
+    console_seq: AtomicUsize,
+
+    /// 有经延迟路径写入、尚未输出到控制台的条目（类似软中断的待处理标志）
+    flush_pending: AtomicBool,
+
+    /// 控制台输出是否带 ANSI 颜色
+    console_color: AtomicBool,
+
+    /// 控制台输出是否使用 Linux 风格的 `[秒.微秒]` 时间前缀
+    console_time: AtomicBool,
+
+    /// 各 CPU 上一条日志的序号，供 `pr_cont!` 续接；0 表示没有，
+    /// [`CONT_FILTERED`] 表示上一条被级别过滤
+    cont_seq: [AtomicUsize; MAX_LOG_CPUS],
 }
 
+/// `cont_seq` 中表示上一条日志被级别过滤的标记，其后的续接文本一并丢弃
+const CONT_FILTERED: usize = usize::MAX;
+
+/// 续接文本无处追加、单独成条时使用的级别（同 Linux 的 default_message_loglevel）
+const CONT_DEFAULT_LEVEL: LogLevel = LogLevel::Warning;
+
 impl LogCore {
     /// 使用默认日志级别创建新的 LogCore 实例
     ///
@@ -51,9 +83,16 @@ impl LogCore {
     /// ```
     pub const fn default() -> Self {
         Self {
-            buffer: GlobalLogBuffer::new(),
+            buffer: LogBuffer::new(),
             global_level: AtomicU8::new(DEFAULT_LOG_LEVEL as u8),
             console_level: AtomicU8::new(DEFAULT_CONSOLE_LEVEL as u8),
+            targets: TargetRegistry::new(),
+            console_deferred: AtomicBool::new(false),
+            console_seq: AtomicUsize::new(1),
+            flush_pending: AtomicBool::new(false),
+            console_color: AtomicBool::new(LogStyle::DEFAULT.color),
+            console_time: AtomicBool::new(LogStyle::DEFAULT.time_prefix),
+            cont_seq: [const { AtomicUsize::new(0) }; MAX_LOG_CPUS],
         }
     }
 
@@ -80,9 +119,16 @@ impl LogCore {
     /// ```
     pub fn new(global_level: LogLevel, console_level: LogLevel) -> Self {
         Self {
-            buffer: GlobalLogBuffer::new(),
+            buffer: LogBuffer::new(),
             global_level: AtomicU8::new(global_level as u8),
             console_level: AtomicU8::new(console_level as u8),
+            targets: TargetRegistry::new(),
+            console_deferred: AtomicBool::new(false),
+            console_seq: AtomicUsize::new(1),
+            flush_pending: AtomicBool::new(false),
+            console_color: AtomicBool::new(LogStyle::DEFAULT.color),
+            console_time: AtomicBool::new(LogStyle::DEFAULT.time_prefix),
+            cont_seq: [const { AtomicUsize::new(0) }; MAX_LOG_CPUS],
         }
     }
 
@@ -97,18 +143,77 @@ impl LogCore {
     /// 3. 收集上下文 (时间戳、CPU ID、任务 ID)
     /// 4. 创建日志条目 (栈分配)
     /// 5. 原子缓冲区写入 (无锁)
-    /// 6. 可选的控制台输出 (如果满足 console_level)
+    /// 6. 可选的控制台输出 (满足 console_level，或输出端为某个控制台请求了该级别)
     ///
     /// # 参数
     ///
     /// * `level` - 日志级别 (Emergency 到 Debug)
     /// * `args` - 来自 `format_args!` 的格式化参数
     pub fn _log(&self, level: LogLevel, args: fmt::Arguments) {
+        self._log_fields(level, &[], args);
+    }
+
+    /// 记录一条携带结构化字段的日志
+    ///
+    /// 流程与 [`_log`](Self::_log) 相同，`fields` 随条目一起存入缓冲区，
+    /// 超过 `MAX_LOG_FIELDS` 的字段被丢弃。控制台输出只包含格式化后的消息。
+    pub fn _log_fields(&self, level: LogLevel, fields: &[LogField], args: fmt::Arguments) {
         // 1. 早期过滤 (全局级别)
         if !self.is_level_enabled(level) {
+            self._log_filtered();
+            return;
+        }
+        self.record(level, fields, args, false);
+    }
+
+    /// 延迟路径：只写缓冲区，从不直接调用控制台输出端
+    ///
+    /// 供调度器、陷入处理等持有运行队列锁或可能与串口锁形成环路的上下文使用。
+    /// 需要输出到控制台的条目只置位待刷写标志，由之后的普通日志、
+    /// [`_flush_console`](Self::_flush_console) 或刷写线程输出；Emergency 级别同样延迟。
+    pub fn _log_deferred(&self, level: LogLevel, args: fmt::Arguments) {
+        if !self.is_level_enabled(level) {
+            self._log_filtered();
+            return;
+        }
+        self.record(level, &[], args, true);
+    }
+
+    /// 记录一条带目标（子系统）标记的日志
+    ///
+    /// 满足全局级别，或该目标的调试开关已打开时记录。
+    pub fn _log_target(&self, target: &'static str, level: LogLevel, args: fmt::Arguments) {
+        if !self._is_target_level_enabled(target, level) {
+            self._log_filtered();
             return;
         }
+        self.record(level, &[], args, false);
+    }
+
+    /// 检查带目标的日志是否启用
+    ///
+    /// 被全局级别过滤的目标在此自动登记，之后可以通过
+    /// [`_set_target_debug`](Self::_set_target_debug) 单独打开。
+    pub fn _is_target_level_enabled(&self, target: &'static str, level: LogLevel) -> bool {
+        self.is_level_enabled(level) || self.targets.debug_enabled(target)
+    }
+
+    /// 打开或关闭某个目标的调试日志，`*` 表示所有已登记的目标
+    ///
+    /// 注册表已满、无法登记新目标时返回 `false`。
+    pub fn _set_target_debug(&self, target: &str, enable: bool) -> bool {
+        self.targets.set_debug(target, enable)
+    }
 
+    /// 遍历已登记的目标及其调试开关
+    pub fn _for_each_target(&self, f: impl FnMut(&str, bool)) {
+        self.targets.for_each(f);
+    }
+
+    /// 不经级别过滤地创建条目、写入缓冲区并按需输出到控制台
+    ///
+    /// `deferred` 为 `true` 时不输出，只置位待刷写标志。
+    fn record(&self, level: LogLevel, fields: &[LogField], args: fmt::Arguments, deferred: bool) {
         // 2. 收集上下文（通过 trait）
         let (cpu_id, task_id, timestamp) = if let Some(provider) = crate::get_context_provider() {
             (provider.cpu_id(), provider.task_id(), provider.timestamp())
@@ -118,14 +223,223 @@ impl LogCore {
         };
 
         // 3. 创建日志条目
-        let entry = LogEntry::from_args(level, cpu_id, task_id, timestamp, args);
+        let mut entry = LogEntry::from_args(level, cpu_id, task_id, timestamp, args);
+        entry.set_fields(fields);
+        entry.set_deferred(deferred);
+
+        // 4. 写入缓冲区 (无锁)，记下序号供本 CPU 后续的 `pr_cont!` 续接
+        let seq = self.buffer.write(&entry);
+        entry.set_seq(seq);
+        self.cont_slot(cpu_id).store(seq, Ordering::Relaxed);
+
+        // 5. 可选的即时控制台输出（输出端可以为单独设置了级别的控制台接收更多日志）
+        //    延迟模式下交给刷写线程，Emergency 仍同步输出
+        //    延迟路径写入的条目只置位待刷写标志
+        let Some(console) = self.console_target(level) else {
+            return;
+        };
+        if deferred {
+            self.flush_pending.store(true, Ordering::Release);
+            return;
+        }
+        let console_deferred = self.console_deferred.load(Ordering::Acquire);
+        if !console_deferred && self.flush_pending.load(Ordering::Acquire) {
+            // 先输出更早的延迟条目，保持控制台上的顺序
+            self._flush_console();
+        }
+        if level == LogLevel::Emergency || !console_deferred {
+            self.direct_print_entry(&entry, console);
+        }
+    }
+
+    /// 以 `hexdump -C` 的格式逐行记录 `data`，每行一条日志，行首加 `prefix`
+    pub fn _hex_dump(&self, level: LogLevel, prefix: &str, data: &[u8]) {
+        let row_size = hexdump::row_size(prefix.len());
+        for (i, bytes) in data.chunks(row_size).enumerate() {
+            let line = HexDumpLine {
+                offset: i * row_size,
+                bytes,
+                row_size,
+            };
+            self._log(level, format_args!("{}{}", prefix, line));
+        }
+    }
+
+    /// 在本 CPU 的上一条日志末尾追加文本（`pr_cont!`）
+    ///
+    /// 上一条日志必须由同一任务写入且尚未被读走或覆盖，否则续接文本以
+    /// Warning 级别单独成条；上一条日志被级别过滤时续接文本一并丢弃。
+    /// 上一条日志已输出到控制台时只补打续接的部分（另起一行）。
+    pub fn _log_cont(&self, args: fmt::Arguments) {
+        let (cpu_id, task_id) = crate::get_context_provider()
+            .map_or((0, 0), |provider| (provider.cpu_id(), provider.task_id()));
+        let last = self.cont_slot(cpu_id).load(Ordering::Relaxed);
+        if last == CONT_FILTERED {
+            return;
+        }
+        let appended = self
+            .buffer
+            .peek(last)
+            .filter(|prev| prev.cpu_id() == cpu_id && prev.task_id() == task_id)
+            .and_then(|_| self.buffer.append(cpu_id, last, args));
+        let Some((entry, old_len)) = appended else {
+            self._log(CONT_DEFAULT_LEVEL, args);
+            return;
+        };
+
+        let level = entry.level();
+        let Some(console) = self.console_target(level) else {
+            return;
+        };
+        // 由刷写方输出、尚未刷写的条目稍后会整条输出
+        if self.flushed_by_flusher(&entry, self.console_deferred.load(Ordering::Acquire))
+            && self.console_seq.load(Ordering::Acquire) <= last
+        {
+            return;
+        }
+        let mut text = alloc::string::String::from(entry.message().get(old_len..).unwrap_or(""));
+        text.push('\n');
+        crate::for_each_log_output(|output| output.write_log(level, &text, console));
+    }
+
+    /// 记下本 CPU 的上一条日志被级别过滤（由宏在过滤时调用），其后的 `pr_cont!` 一并丢弃
+    pub fn _log_filtered(&self) {
+        let cpu_id = crate::get_context_provider().map_or(0, |provider| provider.cpu_id());
+        self.cont_slot(cpu_id)
+            .store(CONT_FILTERED, Ordering::Relaxed);
+    }
+
+    /// 开启或关闭延迟控制台输出
+    ///
+    /// 开启后 `_log` 不再同步写控制台（Emergency 除外），由刷写线程调用
+    /// [`_flush_console`](Self::_flush_console) 按序输出；开启前已写入的条目不会再输出。
+    /// 关闭时先同步输出所有积压的条目，用于 panic 等必须立即看到日志的场景。
+    pub fn _set_console_deferred(&self, deferred: bool) {
+        if deferred {
+            self._flush_console();
+            self.console_seq
+                .store(self.buffer.writer_index(), Ordering::Release);
+            self.console_deferred.store(true, Ordering::Release);
+        } else {
+            self._flush_console();
+            self.console_deferred.store(false, Ordering::Release);
+            self._flush_console();
+        }
+    }
+
+    /// 当前的控制台输出格式
+    pub fn _console_style(&self) -> LogStyle {
+        LogStyle {
+            color: self.console_color.load(Ordering::Relaxed),
+            time_prefix: self.console_time.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 开启或关闭控制台输出的 ANSI 颜色
+    pub fn _set_console_color(&self, enable: bool) {
+        self.console_color.store(enable, Ordering::Relaxed);
+    }
+
+    /// 开启或关闭控制台输出的 `[秒.微秒]` 时间前缀
+    pub fn _set_console_time(&self, enable: bool) {
+        self.console_time.store(enable, Ordering::Relaxed);
+    }
+
+    /// 是否处于延迟控制台输出模式
+    pub fn _is_console_deferred(&self) -> bool {
+        self.console_deferred.load(Ordering::Acquire)
+    }
+
+    /// 是否有尚未输出到控制台的条目
+    pub fn _console_pending(&self) -> bool {
+        if self.console_deferred.load(Ordering::Acquire) {
+            self.console_seq.load(Ordering::Acquire) < self.buffer.writer_index()
+        } else {
+            self.flush_pending.load(Ordering::Acquire)
+        }
+    }
 
-        // 4. 写入缓冲区 (无锁)
-        self.buffer.write(&entry);
+    /// 把积压的条目按序号输出到控制台，返回输出的条数
+    ///
+    /// 可以被多个调用者并发调用，每个条目只由一个调用者输出。遇到尚未发布的条目时停止，
+    /// 已被覆盖的条目跳过并输出一行提示。延迟模式下输出写入时没有同步输出的条目
+    /// （同步输出的 Emergency 除外）；非延迟模式下只输出经延迟路径写入的条目。
+    pub fn _flush_console(&self) -> usize {
+        let console_deferred = self.console_deferred.load(Ordering::Acquire);
+        if !console_deferred {
+            // 先清除标志，扫描期间新写入的延迟条目会重新置位
+            self.flush_pending.store(false, Ordering::Release);
+        }
+        let mut printed = 0;
+        loop {
+            let seq = self.console_seq.load(Ordering::Acquire);
+            if seq >= self.buffer.writer_index() {
+                break;
+            }
+            let oldest = self.buffer.reader_index();
+            if seq < oldest {
+                let claimed = self
+                    .console_seq
+                    .compare_exchange(seq, oldest, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok();
+                // 非延迟模式下被覆盖的大多是已同步输出的条目，不提示
+                if claimed && console_deferred {
+                    let note = alloc::format!(
+                        "[klog: {} messages lost before reaching the console]\n",
+                        oldest - seq
+                    );
+                    crate::for_each_log_output(|output| output.write_str(&note));
+                }
+                continue;
+            }
+            let Some(entry) = self.buffer.peek(seq) else {
+                // 写者尚未发布，之后可能还有延迟条目
+                if !console_deferred {
+                    self.flush_pending.store(true, Ordering::Release);
+                }
+                break;
+            };
+            if self
+                .console_seq
+                .compare_exchange(seq, seq + 1, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+            {
+                continue;
+            }
+            if !self.flushed_by_flusher(&entry, console_deferred) {
+                continue;
+            }
+            if let Some(console) = self.console_target(entry.level()) {
+                self.direct_print_entry(&entry, console);
+                printed += 1;
+            }
+        }
+        printed
+    }
 
-        // 5. 可选的即时控制台输出
-        if self.is_console_level(level) {
-            self.direct_print_entry(&entry);
+    /// 限速日志记录：`state` 所在调用点在一个窗口内超过限额的日志被丢弃
+    ///
+    /// 新窗口开始时若上一窗口有被丢弃的日志，先以同一级别记录一条
+    /// `"<site>: N messages suppressed"` 摘要。级别过滤由调用方（宏）完成。
+    pub fn _log_ratelimited(
+        &self,
+        state: &RateLimitState,
+        level: LogLevel,
+        site: &str,
+        args: fmt::Arguments,
+    ) {
+        let now = crate::get_context_provider().map_or(0, |provider| provider.timestamp());
+        let (allowed, suppressed) = state.check(now);
+        if suppressed > 0 {
+            self._log(
+                level,
+                format_args!("{}: {} messages suppressed", site, suppressed),
+            );
+        }
+        if allowed {
+            self._log(level, args);
+        } else {
+            self._log_filtered();
         }
     }
 
@@ -137,11 +451,103 @@ impl LogCore {
         self.buffer.read()
     }
 
+    /// 创建拥有独立游标的读者，从最旧的保留条目开始并跟随新日志
+    ///
+    /// 读者只做非破坏性读取，不影响读指针与其他读者。
+    pub fn _reader(&self) -> LogReader<'_> {
+        LogReader::new(self, usize::MAX)
+    }
+
+    /// 创建快照读者：只遍历创建时已分配序号的条目
+    ///
+    /// 遍历期间被覆盖的条目会被跳过（计入 [`LogReader::take_lost`]），
+    /// 创建之后写入的条目不会出现。
+    pub fn _snapshot(&self) -> LogReader<'_> {
+        LogReader::new(self, self.buffer.writer_index())
+    }
+
+    /// 供 [`LogReader`] 访问缓冲区
+    pub(crate) fn buffer(&self) -> &LogBuffer {
+        &self.buffer
+    }
+
     /// 非破坏性读取：按索引 peek 日志条目，不移动读指针
     pub fn _peek_log(&self, index: usize) -> Option<LogEntry> {
         self.buffer.peek(index)
     }
 
+    /// 破坏性批量读取：从读指针开始逐条格式化到 `buf`
+    ///
+    /// 只复制完整的条目，放不下的条目留在缓冲区供下次读取。返回写入的字节数。
+    pub fn _read_log_into(&self, buf: &mut [u8]) -> usize {
+        let mut written = 0;
+        loop {
+            let seq = self.buffer.reader_index();
+            let Some(entry) = self.buffer.peek(seq) else {
+                break;
+            };
+            let line = format_syslog_line(&entry);
+            let end = written + line.len();
+            if end > buf.len() {
+                break;
+            }
+            if !self.buffer.consume(seq, &entry) {
+                // 读指针被覆盖写入推走，从新位置重试
+                continue;
+            }
+            buf[written..end].copy_from_slice(line.as_bytes());
+            written = end;
+        }
+        written
+    }
+
+    /// 非破坏性批量读取：复制清除点之后仍在缓冲区中的日志
+    ///
+    /// `buf` 放不下时舍弃最旧的条目，保留最新的部分。`clear` 为 `true` 时
+    /// 随后把清除点推进到本次读取的末尾，之后写入的日志不受影响。
+    /// 返回写入的字节数。
+    pub fn _read_all_log_into(&self, buf: &mut [u8], clear: bool) -> usize {
+        let end = self.buffer.writer_index();
+        let mut start = self.buffer.clear_index();
+        let line_len = |seq| {
+            self.buffer
+                .peek(seq)
+                .map_or(0, |e| format_syslog_line(&e).len())
+        };
+
+        let mut total: usize = (start..end).map(line_len).sum();
+        while total > buf.len() && start < end {
+            total -= line_len(start).min(total);
+            start += 1;
+        }
+
+        let mut written = 0;
+        for seq in start..end {
+            // 期间被覆盖的条目直接跳过
+            let Some(entry) = self.buffer.peek(seq) else {
+                continue;
+            };
+            let line = format_syslog_line(&entry);
+            let line_end = written + line.len();
+            if line_end > buf.len() {
+                break;
+            }
+            buf[written..line_end].copy_from_slice(line.as_bytes());
+            written = line_end;
+        }
+        if clear {
+            self.buffer.clear(end);
+        }
+        written
+    }
+
+    /// 清除日志：之后的非破坏性读取只返回此后写入的条目
+    ///
+    /// 不移动读指针，也不触碰槽位，因此不会与并发写者竞争。
+    pub fn _clear_log(&self) {
+        self.buffer.clear(self.buffer.writer_index());
+    }
+
     /// 获取当前可读取的起始索引
     pub fn _log_reader_index(&self) -> usize {
         self.buffer.reader_index()
@@ -197,8 +603,42 @@ impl LogCore {
         LogLevel::from_u8(level)
     }
 
+    /// 按内核命令行调整日志级别，返回解析出的设置
+    ///
+    /// 支持 `loglevel=N`、`quiet` 与 `debug`（见 [`BootLogParams`]）。控制台级别比全局级别
+    /// 更详细时同时放宽全局级别，否则这些日志根本不会被记录。
+    pub fn _apply_boot_params(&self, cmdline: &str) -> BootLogParams {
+        let params = BootLogParams::parse(cmdline);
+        if let Some(level) = params.global_level {
+            self._set_global_level(level);
+        }
+        if let Some(enable) = params.console_color {
+            self._set_console_color(enable);
+        }
+        if let Some(enable) = params.console_time {
+            self._set_console_time(enable);
+        }
+        if let Some(level) = params.console_level {
+            self._set_console_level(level);
+            if level > self._get_global_level() {
+                self._set_global_level(level);
+            }
+        }
+        params
+    }
+
     // ========== 内部辅助函数 ==========
 
+    /// 条目的控制台输出是否由刷写方（而非写入时同步）完成
+    fn flushed_by_flusher(&self, entry: &LogEntry, console_deferred: bool) -> bool {
+        entry.is_deferred() || (console_deferred && entry.level() != LogLevel::Emergency)
+    }
+
+    /// CPU `cpu_id` 的续接状态
+    fn cont_slot(&self, cpu_id: usize) -> &AtomicUsize {
+        &self.cont_seq[cpu_id % MAX_LOG_CPUS]
+    }
+
     /// 检查日志级别是否启用 (全局过滤器)
     #[inline(always)]
     fn is_level_enabled(&self, level: LogLevel) -> bool {
@@ -211,85 +651,162 @@ impl LogCore {
         level as u8 <= self.console_level.load(Ordering::Acquire)
     }
 
-    /// 使用 ANSI 颜色直接将日志条目打印到控制台（无堆分配）
-    ///
-    /// 此方法在早期启动时即可使用，因为它仅使用栈和 core::fmt::Write，
-    /// 不依赖堆分配器。
-    ///
-    /// **重要**: 此函数的格式化逻辑必须与 `format_log_entry` 和 `buffer::calculate_formatted_length` 保持一致。
-    /// 如果修改了日志输出格式，需要同步更新三处：
-    /// - `direct_print_entry` (此函数) - 用于早期启动的控制台输出
-    /// - `format_log_entry` - 用于 syslog 系统调用
-    /// - `buffer::calculate_formatted_length` - 用于精确字节计数
-    fn direct_print_entry(&self, entry: &LogEntry) {
-        use alloc::format;
-
-        // 格式化日志条目
-        let formatted = format!(
-            "{}{} [{:12}] [CPU{}/T{:3}] {}{}\n",
-            entry.level().color_code(),
-            entry.level().as_str(),
-            entry.timestamp(),
-            entry.cpu_id(),
-            entry.task_id(),
-            entry.message(),
-            entry.level().reset_color_code()
-        );
-
-        // 通过 trait 输出
-        if let Some(output) = crate::get_log_output() {
-            output.write_str(&formatted);
+    /// 该级别的日志是否需要交给输出端
+    ///
+    /// 需要时返回是否满足全局控制台级别；不满足但输出端为某个控制台请求了该级别时为 `false`。
+    fn console_target(&self, level: LogLevel) -> Option<bool> {
+        let console = self.is_console_level(level);
+        let mut wanted = console;
+        if !wanted {
+            crate::for_each_log_output(|output| wanted |= output.wants_level(level));
         }
+        wanted.then_some(console)
+    }
+
+    /// 按当前控制台格式（[`_console_style`](Self::_console_style)）把日志条目打印到控制台
+    fn direct_print_entry(&self, entry: &LogEntry, console: bool) {
+        let mut formatted = format_log_entry(entry, self._console_style());
+        formatted.push('\n');
+
+        // 通过 trait 输出到主输出端与附加输出端
+        crate::for_each_log_output(|output| output.write_log(entry.level(), &formatted, console));
     }
 }
 
 // 标记为 Sync 允许在 static 中使用
 unsafe impl Sync for LogCore {}
 
-/// 格式化日志条目为字符串（带 ANSI 颜色和上下文信息）
-///
-/// 将 LogEntry 格式化为用户可读的字符串，用于 syslog 系统调用等场景。
-/// 包含 ANSI 颜色代码、时间戳、CPU ID、任务 ID 等上下文信息。
+/// 限速状态
 ///
-/// **注意**：此函数使用堆分配（`alloc::format!`），仅在堆分配器初始化后可用。
-/// 主要用于 syslog 系统调用等运行时场景。早期启动时的控制台输出使用
-/// `direct_print_entry` 方法，该方法不依赖堆分配。
+/// 每个 `pr_*_ratelimited!` 调用点持有一个静态实例：每 `interval` 内最多放行
+/// `burst` 条日志，其余计入丢弃数，在下一个窗口开始时汇总报告。
+/// 只使用原子操作，可在中断上下文中使用。
+pub struct RateLimitState {
+    /// 窗口长度（与时间戳同单位），为 0 时不限速
+    interval: usize,
+    /// 每个窗口允许的条数，为 0 时不限速
+    burst: usize,
+    /// 当前窗口的起始时间戳，`usize::MAX` 表示尚未开始
+    begin: AtomicUsize,
+    /// 当前窗口已放行的条数
+    printed: AtomicUsize,
+    /// 当前窗口已丢弃的条数
+    missed: AtomicUsize,
+}
+
+impl RateLimitState {
+    /// 创建限速状态
+    pub const fn new(interval: usize, burst: usize) -> Self {
+        Self {
+            interval,
+            burst,
+            begin: AtomicUsize::new(usize::MAX),
+            printed: AtomicUsize::new(0),
+            missed: AtomicUsize::new(0),
+        }
+    }
+
+    /// 在时间 `now` 检查是否放行一条日志
+    ///
+    /// 返回 `(是否放行, 上一窗口丢弃的条数)`；后者只在本次调用开启新窗口时非零。
+    fn check(&self, now: usize) -> (bool, usize) {
+        if self.interval == 0 || self.burst == 0 {
+            return (true, 0);
+        }
+
+        let mut suppressed = 0;
+        let begin = self.begin.load(Ordering::Acquire);
+        if (begin == usize::MAX || now.wrapping_sub(begin) >= self.interval)
+            && self
+                .begin
+                .compare_exchange(begin, now, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            suppressed = self.missed.swap(0, Ordering::AcqRel);
+            self.printed.store(0, Ordering::Release);
+        }
+
+        if self.printed.fetch_add(1, Ordering::AcqRel) < self.burst {
+            (true, suppressed)
+        } else {
+            self.missed.fetch_add(1, Ordering::Relaxed);
+            (false, suppressed)
+        }
+    }
+}
+
+/// 日志条目的文本格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogStyle {
+    /// 按级别加 ANSI 颜色（见 [`LogLevel::color_code`]）
+    pub color: bool,
+    /// 以 Linux 风格的 `[    5.140900]`（秒.微秒）代替原始纳秒时间戳
+    pub time_prefix: bool,
+}
+
+impl LogStyle {
+    /// 默认格式：带颜色、原始时间戳，也是 syslog 读取使用的格式
+    pub const DEFAULT: Self = Self {
+        color: true,
+        time_prefix: false,
+    };
+}
+
+/// 按给定格式把日志条目格式化为字符串（不含换行）
 ///
-/// **重要**：此函数的格式化逻辑必须与 `direct_print_entry` 和 `buffer::calculate_formatted_length` 保持一致。
-/// 如果修改了日志输出格式，需要同步更新三处：
-/// - `direct_print_entry` - 用于早期启动的控制台输出（无堆分配）
-/// - `format_log_entry` (此函数) - 用于 syslog 系统调用（使用堆分配）
-/// - `buffer::calculate_formatted_length` - 用于精确字节计数
+/// 控制台按运行时设置的格式输出；syslog 读取固定使用 [`LogStyle::DEFAULT`]，
+/// 其长度必须与 `buffer::calculate_formatted_length` 的计算保持一致。
 ///
 /// # 格式
 /// ```text
-/// <color_code>[LEVEL] [timestamp] [CPU<id>/T<tid>] message<reset>
+/// <color>[LEVEL] [timestamp] [CPU<id>/T<tid>] message<reset>
+/// <color>[    5.140900] [LEVEL] [CPU<id>/T<tid>] message<reset>
 /// ```
 ///
 /// # 示例
 /// ```text
 /// \x1b[37m[INFO] [      123456] [CPU0/T  1] Kernel initialized\x1b[0m
-/// \x1b[31m[ERR] [      789012] [CPU0/T  5] Failed to mount /dev/sda1\x1b[0m
+/// [    5.140900] [ERR] [CPU0/T  5] Failed to mount /dev/sda1
 /// ```
-///
-/// # 参数
-/// * `entry` - 要格式化的日志条目
-///
-/// # 返回值
-/// 格式化后的字符串（包含 ANSI 颜色代码和上下文信息）
-pub fn format_log_entry(entry: &LogEntry) -> alloc::string::String {
-    use alloc::format;
-
-    format!(
-        "{}{} [{:12}] [CPU{}/T{:3}] {}{}",
-        entry.level().color_code(),
-        entry.level().as_str(),
-        entry.timestamp(),
+pub fn format_log_entry(entry: &LogEntry, style: LogStyle) -> alloc::string::String {
+    use alloc::string::String;
+    use core::fmt::Write;
+
+    let level = entry.level();
+    let mut out = String::new();
+    if style.color {
+        out.push_str(level.color_code());
+    }
+    let _ = if style.time_prefix {
+        let ns = entry.timestamp();
+        write!(
+            out,
+            "[{:5}.{:06}] {}",
+            ns / 1_000_000_000,
+            ns % 1_000_000_000 / 1_000,
+            level.as_str()
+        )
+    } else {
+        write!(out, "{} [{:12}]", level.as_str(), entry.timestamp())
+    };
+    let _ = write!(
+        out,
+        " [CPU{}/T{:3}] {}",
         entry.cpu_id(),
         entry.task_id(),
-        entry.message(),
-        entry.level().reset_color_code()
-    )
+        entry.message()
+    );
+    if style.color {
+        out.push_str(level.reset_color_code());
+    }
+    out
+}
+
+/// 格式化 syslog 读取返回的一行（[`format_log_entry`] 加换行）
+fn format_syslog_line(entry: &LogEntry) -> alloc::string::String {
+    let mut line = format_log_entry(entry, LogStyle::DEFAULT);
+    line.push('\n');
+    line
 }
 
 #[cfg(test)]
@@ -730,7 +1247,7 @@ mod tests {
 
         let reported = logger._log_unread_bytes();
         let entry = logger._read_log().unwrap();
-        let formatted = crate::format_log_entry(&entry);
+        let formatted = crate::format_log_entry(&entry, LogStyle::DEFAULT);
         let actual = formatted.len();
 
         // Context fields (cpu/task/timestamp) may vary; just check a reasonable bound.
@@ -794,4 +1311,390 @@ mod tests {
         let _ = logger._read_log();
         assert_eq!(logger._log_unread_bytes(), 0);
     }
+
+    #[test]
+    fn test_read_log_into_keeps_partial_entry() {
+        let logger = LogCore::new(LogLevel::Debug, LogLevel::Emergency);
+        test_log!(logger, LogLevel::Info, "first");
+        test_log!(logger, LogLevel::Info, "second");
+
+        let first_len = format_syslog_line(&logger._peek_log(1).unwrap()).len();
+        let mut buf = [0u8; 512];
+        // 只放得下第一条：第二条留在缓冲区
+        let n = logger._read_log_into(&mut buf[..first_len + 1]);
+        assert_eq!(n, first_len);
+        assert!(core::str::from_utf8(&buf[..n]).unwrap().contains("first"));
+        assert!(buf[..n].ends_with(b"\n"));
+        assert_eq!(logger._log_len(), 1);
+
+        let n = logger._read_log_into(&mut buf);
+        assert!(core::str::from_utf8(&buf[..n]).unwrap().contains("second"));
+        assert_eq!(logger._log_len(), 0);
+        assert_eq!(logger._log_unread_bytes(), 0);
+        assert_eq!(logger._read_log_into(&mut buf), 0);
+    }
+
+    #[test]
+    fn test_read_all_log_into_keeps_newest() {
+        let logger = LogCore::new(LogLevel::Debug, LogLevel::Emergency);
+        test_log!(logger, LogLevel::Info, "old");
+        test_log!(logger, LogLevel::Info, "new");
+
+        let new_len = format_syslog_line(&logger._peek_log(2).unwrap()).len();
+        let mut buf = [0u8; 512];
+        let n = logger._read_all_log_into(&mut buf[..new_len], false);
+        let text = core::str::from_utf8(&buf[..n]).unwrap();
+        assert!(text.contains("new") && !text.contains("old"));
+        // 非破坏性：读指针不动
+        assert_eq!(logger._log_len(), 2);
+    }
+
+    #[test]
+    fn test_clear_log_hides_old_entries_only() {
+        let logger = LogCore::new(LogLevel::Debug, LogLevel::Emergency);
+        test_log!(logger, LogLevel::Info, "before");
+        let mut buf = [0u8; 512];
+        let n = logger._read_all_log_into(&mut buf, true);
+        assert!(core::str::from_utf8(&buf[..n]).unwrap().contains("before"));
+        assert_eq!(logger._read_all_log_into(&mut buf, false), 0);
+
+        test_log!(logger, LogLevel::Info, "after");
+        logger._clear_log();
+        assert_eq!(logger._read_all_log_into(&mut buf, false), 0);
+        test_log!(logger, LogLevel::Info, "latest");
+        let n = logger._read_all_log_into(&mut buf, false);
+        let text = core::str::from_utf8(&buf[..n]).unwrap();
+        assert!(text.contains("latest") && !text.contains("after"));
+
+        // 清除不影响破坏性读取
+        assert_eq!(logger._log_len(), 3);
+    }
+
+    fn cpu_entry(cpu_id: usize, msg: &str) -> LogEntry {
+        LogEntry::from_args(LogLevel::Info, cpu_id, 0, 0, format_args!("{}", msg))
+    }
+
+    #[test]
+    fn test_per_cpu_buffer_merges_in_global_order() {
+        use crate::buffer::PerCpuLogBuffer;
+
+        let buffer = PerCpuLogBuffer::new();
+        buffer.write(&cpu_entry(1, "a"));
+        buffer.write(&cpu_entry(0, "b"));
+        buffer.write(&cpu_entry(3, "c"));
+        buffer.write(&cpu_entry(1, "d"));
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.peek(3).unwrap().message(), "c");
+
+        let order: alloc::vec::Vec<_> = core::iter::from_fn(|| buffer.read())
+            .map(|e| alloc::string::String::from(e.message()))
+            .collect();
+        assert_eq!(order, ["a", "b", "c", "d"]);
+        assert_eq!(buffer.unread_bytes(), 0);
+    }
+
+    #[test]
+    fn test_per_cpu_buffer_overwrite_keeps_sequence_contiguous() {
+        use crate::buffer::{PER_CPU_LOG_ENTRIES, PerCpuLogBuffer};
+
+        let buffer = PerCpuLogBuffer::new();
+        // 序号 1 写在 CPU1 上，之后 CPU0 写满并覆盖自己的第一条（序号 2）
+        buffer.write(&cpu_entry(1, "cpu1"));
+        for _ in 0..=PER_CPU_LOG_ENTRIES {
+            buffer.write(&cpu_entry(0, "cpu0"));
+        }
+
+        // 读指针越过被覆盖的序号 2，CPU1 上更旧的序号 1 一并视为丢弃
+        assert_eq!(buffer.reader_index(), 3);
+        assert_eq!(buffer.dropped_count(), 2);
+        assert!(buffer.peek(1).is_none());
+        assert_eq!(buffer.len(), PER_CPU_LOG_ENTRIES);
+        for _ in 0..PER_CPU_LOG_ENTRIES {
+            assert_eq!(buffer.read().unwrap().message(), "cpu0");
+        }
+        assert!(buffer.read().is_none());
+    }
+
+    #[test]
+    fn test_per_cpu_buffer_append_targets_writer_cpu() {
+        use crate::buffer::PerCpuLogBuffer;
+
+        let buffer = PerCpuLogBuffer::new();
+        let a = buffer.write(&cpu_entry(1, "a"));
+        let b = buffer.write(&cpu_entry(0, "b"));
+        assert_eq!((a, b), (1, 2));
+
+        let (entry, old_len) = buffer.append(1, a, format_args!("+{}", 1)).unwrap();
+        assert_eq!((entry.message(), old_len), ("a+1", 1));
+        assert_eq!(buffer.peek(b).unwrap().message(), "b");
+        // 序号不在该 CPU 的缓冲区中
+        assert!(buffer.append(0, a, format_args!("x")).is_none());
+
+        let expected = calculate_len("a+1") + calculate_len("b");
+        assert_eq!(buffer.unread_bytes(), expected);
+    }
+
+    fn calculate_len(msg: &str) -> usize {
+        format_syslog_line(&cpu_entry(0, msg)).len()
+    }
+
+    #[test]
+    fn test_log_cont_appends_to_previous_record() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        test_log!(logger, LogLevel::Info, "regs:");
+        for i in 0..3 {
+            logger._log_cont(format_args!(" x{}={}", i, i * 2));
+        }
+        assert_eq!(logger._log_len(), 1);
+        let entry = logger._peek_log(1).unwrap();
+        assert_eq!(entry.message(), "regs: x0=0 x1=2 x2=4");
+        assert_eq!(entry.level(), LogLevel::Info);
+        assert_eq!(entry.seq(), 1);
+        assert_eq!(logger._log_unread_bytes(), format_syslog_line(&entry).len());
+
+        logger._read_log().unwrap();
+        assert_eq!(logger._log_unread_bytes(), 0);
+        // 上一条已被读走，续接文本单独成条
+        logger._log_cont(format_args!("tail"));
+        let entry = logger._read_log().unwrap();
+        assert_eq!(
+            (entry.message(), entry.level()),
+            ("tail", LogLevel::Warning)
+        );
+    }
+
+    #[test]
+    fn test_log_cont_follows_filtered_record() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        logger._log_cont(format_args!("orphan"));
+        assert_eq!(logger._read_log().unwrap().message(), "orphan");
+
+        test_log!(logger, LogLevel::Info, "shown");
+        test_log!(logger, LogLevel::Debug, "hidden");
+        logger._log_cont(format_args!(" more"));
+        assert_eq!(logger._log_len(), 1);
+        assert_eq!(logger._read_log().unwrap().message(), "shown");
+    }
+
+    #[test]
+    fn test_entry_seq_is_monotonic() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        for i in 0..4 {
+            test_log!(logger, LogLevel::Info, "m{}", i);
+        }
+        let seqs: alloc::vec::Vec<_> = core::iter::from_fn(|| logger._read_log())
+            .map(|entry| entry.seq())
+            .collect();
+        assert_eq!(seqs, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_ratelimit_state_burst_and_window() {
+        let state = RateLimitState::new(100, 2);
+        assert_eq!(state.check(10), (true, 0));
+        assert_eq!(state.check(20), (true, 0));
+        assert_eq!(state.check(30), (false, 0));
+        assert_eq!(state.check(109), (false, 0));
+        // 新窗口：放行并报告上一窗口丢弃的条数
+        assert_eq!(state.check(110), (true, 2));
+        assert_eq!(state.check(120), (true, 0));
+        assert_eq!(state.check(130), (false, 0));
+
+        let unlimited = RateLimitState::new(0, 2);
+        for _ in 0..10 {
+            assert_eq!(unlimited.check(0), (true, 0));
+        }
+    }
+
+    #[test]
+    fn test_log_ratelimited_drops_excess() {
+        let logger = LogCore::new(LogLevel::Debug, LogLevel::Emergency);
+        let state = RateLimitState::new(1000, 3);
+        for i in 0..10 {
+            logger._log_ratelimited(&state, LogLevel::Warning, "rx", format_args!("drop {}", i));
+        }
+        assert_eq!(logger._log_len(), 3);
+        assert_eq!(logger._read_log().unwrap().message(), "drop 0");
+    }
+
+    #[test]
+    fn test_log_fields_roundtrip() {
+        use crate::entry::LogValue;
+
+        let logger = LogCore::new(LogLevel::Debug, LogLevel::Emergency);
+        let fields = [
+            LogField::new("DEVICE", "virtio-net0"),
+            LogField::new("LEN", 1514usize),
+            LogField::new("DELTA", -3),
+            LogField::new("NAME", "a-very-long-interface-name"),
+            LogField::new("DROPPED", 1u8),
+        ];
+        logger._log_fields(LogLevel::Info, &fields, format_args!("rx {}", 1));
+
+        let entry = logger._read_log().unwrap();
+        assert_eq!(entry.message(), "rx 1");
+        // 超出上限的字段被丢弃
+        assert_eq!(entry.fields().len(), crate::config::MAX_LOG_FIELDS);
+        assert_eq!(entry.fields()[0].key(), "DEVICE");
+        assert_eq!(entry.fields()[0].value().as_str(), Some("virtio-net0"));
+        assert_eq!(*entry.fields()[1].value(), LogValue::U64(1514));
+        assert_eq!(*entry.fields()[2].value(), LogValue::I64(-3));
+        assert_eq!(entry.fields()[3].value().as_str(), Some("a-very-long-inte"));
+    }
+
+    #[test]
+    fn test_log_value_truncates_at_char_boundary() {
+        use crate::entry::LogValue;
+
+        // 16 字节处落在 "é" 的中间
+        let value = LogValue::from_str("aaaaaaaaaaaaaaaé");
+        assert_eq!(value.as_str(), Some("aaaaaaaaaaaaaaa"));
+        assert_eq!(alloc::format!("{}", LogValue::from(-7i32)), "-7");
+    }
+
+    #[test]
+    fn test_target_debug_toggle() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+
+        logger._log_target("vfs", LogLevel::Debug, format_args!("hidden"));
+        logger._log_target("net", LogLevel::Info, format_args!("info passes"));
+        assert_eq!(logger._log_len(), 1);
+
+        // 被过滤过的目标已自动登记，默认关闭
+        let mut targets = alloc::vec::Vec::new();
+        logger._for_each_target(|name, debug| {
+            targets.push((alloc::string::String::from(name), debug))
+        });
+        assert_eq!(targets, [(alloc::string::String::from("vfs"), false)]);
+
+        assert!(logger._set_target_debug("vfs", true));
+        logger._log_target("vfs", LogLevel::Debug, format_args!("shown"));
+        logger._log_target("mm", LogLevel::Debug, format_args!("still hidden"));
+        assert_eq!(logger._log_len(), 2);
+
+        assert!(logger._set_target_debug("*", false));
+        logger._log_target("vfs", LogLevel::Debug, format_args!("hidden again"));
+        assert_eq!(logger._log_len(), 2);
+    }
+
+    #[test]
+    fn test_deferred_console_flush_in_order() {
+        let logger = LogCore::new(LogLevel::Debug, LogLevel::Debug);
+        test_log!(logger, LogLevel::Info, "before");
+        logger._set_console_deferred(true);
+        // 开启前写入的条目不再积压
+        assert!(!logger._console_pending());
+
+        test_log!(logger, LogLevel::Info, "a");
+        test_log!(logger, LogLevel::Emergency, "sync");
+        test_log!(logger, LogLevel::Warning, "b");
+        assert!(logger._console_pending());
+        // Emergency 已同步输出，只刷写其余两条
+        assert_eq!(logger._flush_console(), 2);
+        assert!(!logger._console_pending());
+        assert_eq!(logger._flush_console(), 0);
+
+        test_log!(logger, LogLevel::Info, "c");
+        logger._set_console_deferred(false);
+        assert!(!logger._is_console_deferred());
+        assert!(!logger._console_pending());
+    }
+
+    #[test]
+    fn test_log_deferred_never_prints_synchronously() {
+        let logger = LogCore::new(LogLevel::Debug, LogLevel::Debug);
+        logger._log_deferred(LogLevel::Info, format_args!("a"));
+        logger._log_deferred(LogLevel::Emergency, format_args!("b"));
+        assert!(logger._console_pending());
+        assert_eq!(logger._flush_console(), 2);
+        assert!(!logger._console_pending());
+        assert_eq!(logger._flush_console(), 0);
+
+        // 延迟模式下刷写线程同样输出延迟路径写入的 Emergency
+        logger._set_console_deferred(true);
+        logger._log_deferred(LogLevel::Emergency, format_args!("c"));
+        test_log!(logger, LogLevel::Emergency, "sync");
+        assert_eq!(logger._flush_console(), 1);
+        logger._set_console_deferred(false);
+    }
+
+    #[test]
+    fn test_hex_dump_one_record_per_row() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        let data: alloc::vec::Vec<u8> = (0..40).collect();
+        logger._hex_dump(LogLevel::Info, "sb: ", &data);
+        logger._hex_dump(LogLevel::Debug, "hidden: ", &data);
+        logger._hex_dump(LogLevel::Info, "empty: ", &[]);
+
+        let lines: alloc::vec::Vec<_> = core::iter::from_fn(|| logger._read_log())
+            .map(|entry| alloc::string::String::from(entry.message()))
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("sb: 00000000  00 01 02"));
+        assert!(lines[1].starts_with("sb: 00000010  10 11 12"));
+        assert!(lines[2].starts_with("sb: 00000020  20 21 22 23 24 25 26 27 "));
+        assert!(lines[2].ends_with("| !\"#$%&'|"));
+
+        // 长前缀时每行变短，但不会被截断
+        let prefix = "p".repeat(200);
+        logger._hex_dump(LogLevel::Info, &prefix, &data);
+        let entry = logger._read_log().unwrap();
+        assert!(entry.message().ends_with('|'));
+        assert!(logger._log_len() > 1);
+    }
+
+    #[test]
+    fn test_apply_boot_params() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Info);
+        logger._apply_boot_params("root=/dev/vda quiet");
+        assert_eq!(logger._get_console_level(), LogLevel::Error);
+        assert_eq!(logger._get_global_level(), LogLevel::Info);
+
+        // 后出现的参数覆盖先出现的；loglevel=8 需要放宽全局级别
+        logger._apply_boot_params("quiet loglevel=8");
+        assert_eq!(logger._get_console_level(), LogLevel::Debug);
+        assert_eq!(logger._get_global_level(), LogLevel::Debug);
+
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Info);
+        let params = logger._apply_boot_params("loglevel=5 loglevel=x");
+        assert_eq!(params.console_level, Some(LogLevel::Warning));
+        assert_eq!(params.global_level, None);
+        assert_eq!(logger._get_global_level(), LogLevel::Info);
+
+        let params = BootLogParams::parse("debug loglevel=0");
+        assert_eq!(params.console_level, Some(LogLevel::Emergency));
+        assert_eq!(params.global_level, Some(LogLevel::Debug));
+        assert_eq!(
+            BootLogParams::parse("console=ttyS0"),
+            BootLogParams::default()
+        );
+    }
+
+    #[test]
+    fn test_format_log_entry_styles() {
+        let entry = LogEntry::from_args(
+            LogLevel::Error,
+            0,
+            5,
+            5_140_900_123,
+            format_args!("disk failed"),
+        );
+        assert_eq!(
+            format_log_entry(&entry, LogStyle::DEFAULT),
+            "\x1b[31m[ERR] [  5140900123] [CPU0/T  5] disk failed\x1b[0m"
+        );
+        let plain_time = LogStyle {
+            color: false,
+            time_prefix: true,
+        };
+        assert_eq!(
+            format_log_entry(&entry, plain_time),
+            "[    5.140900] [ERR] [CPU0/T  5] disk failed"
+        );
+
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Info);
+        assert_eq!(logger._console_style(), LogStyle::DEFAULT);
+        logger._apply_boot_params("printk.time=1 printk.color=n printk.time=x");
+        assert_eq!(logger._console_style(), plain_time);
+    }
 }
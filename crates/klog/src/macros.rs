@@ -27,6 +27,27 @@
 //! - `pr_info!` - 信息级别（信息性消息）
 //! - `pr_debug!` - 调试级别（调试消息）
 //!
+//! 每个宏都可以在最前面加上 `target: "子系统"`（如 `pr_debug!(target: "vfs", "...")`），
+//! 这样即使全局级别过滤掉了该级别，也可以通过 [`set_target_debug`](crate::set_target_debug)
+//! 单独打开该子系统的日志。
+//!
+//! `pr_hex_dump!` 按 `hexdump -C` 的格式逐行转储一段字节，用于调试驱动的描述符、磁盘结构等。
+//!
+//! `printk_deferred!` 只写环形缓冲区、不直接调用控制台，用于调度器等持锁路径。
+//!
+//! `pr_cont!` 把消息接在本 CPU 的上一条日志之后，用于分几次拼出的一行输出。
+//!
+//! `pr_fields!` 在消息之外附带结构化键值字段（见 [`LogField`](crate::LogField)），
+//! 通过 /dev/kmsg 以机器可读的形式导出。
+//!
+//! `pr_*_once!` 每个调用点只记录一次（如反复出现的不支持的 ioctl），
+//! `warn_once!` 在条件第一次成立时记录带调用点信息的警告。
+//!
+//! 每个宏都有对应的限速版本 `pr_*_ratelimited!`：同一调用点在
+//! [`DEFAULT_RATELIMIT_INTERVAL`](crate::DEFAULT_RATELIMIT_INTERVAL) 内最多输出
+//! [`DEFAULT_RATELIMIT_BURST`](crate::DEFAULT_RATELIMIT_BURST) 条，其余被丢弃，
+//! 下一个窗口开始时输出一条 "N messages suppressed" 摘要。适用于中断、收包等可能刷屏的路径。
+//!
 //! # 性能
 //!
 //! 所有宏都在**宏展开时检查全局日志级别**。如果某个日志级别被禁用，则永远不会评估格式字符串，这使得**禁用的日志开销基本上为零**。
@@ -40,10 +61,124 @@ macro_rules! __klog_impl_filtered {
     ($level:expr, $args:expr) => {
         if $crate::is_level_enabled($level) {
             $crate::log_impl($level, $args);
+        } else {
+            $crate::log_filtered_impl();
         }
     };
 }
 
+/// 带目标（子系统）标记的内部实现宏
+///
+/// 满足全局级别，或该目标的调试开关已打开时记录。
+#[macro_export]
+macro_rules! __klog_impl_target {
+    ($target:expr, $level:expr, $args:expr) => {
+        if $crate::is_target_level_enabled($target, $level) {
+            $crate::log_target_impl($target, $level, $args);
+        } else {
+            $crate::log_filtered_impl();
+        }
+    };
+}
+
+/// 带有级别过滤和按调用点限速的内部实现宏
+///
+/// 每次展开都会生成一个独立的静态 [`RateLimitState`](crate::RateLimitState)，
+/// 因此限速以调用点为单位。
+#[macro_export]
+macro_rules! __klog_impl_ratelimited {
+    ($level:expr, $args:expr) => {{
+        static STATE: $crate::RateLimitState = $crate::RateLimitState::new(
+            $crate::DEFAULT_RATELIMIT_INTERVAL,
+            $crate::DEFAULT_RATELIMIT_BURST,
+        );
+        if $crate::is_level_enabled($level) {
+            $crate::log_ratelimited(&STATE, $level, concat!(file!(), ":", line!()), $args);
+        } else {
+            $crate::log_filtered_impl();
+        }
+    }};
+}
+
+/// 以指定级别记录一条携带结构化字段的消息
+///
+/// 字段写作 `[键 => 值, ...]`，键为字符串字面量，值可以是整数、`bool` 或 `&str`。
+///
+/// # 示例
+///
+/// ```rust
+/// use klog::{LogLevel, pr_fields};
+///
+/// let len = 1514usize;
+/// pr_fields!(LogLevel::Info, ["DEVICE" => "eth0", "LEN" => len], "收到 {} 字节", len);
+/// ```
+#[macro_export]
+macro_rules! pr_fields {
+    ($level:expr, [$($key:literal => $value:expr),* $(,)?], $($arg:tt)*) => {
+        if $crate::is_level_enabled($level) {
+            $crate::log_fields_impl(
+                $level,
+                &[$($crate::LogField::new($key, $value)),*],
+                format_args!($($arg)*),
+            );
+        } else {
+            $crate::log_filtered_impl();
+        }
+    };
+}
+
+/// 带有级别过滤、每个调用点只记录一次的内部实现宏
+///
+/// 每次展开生成一个独立的静态标志，第一次通过级别过滤的调用记录日志并置位。
+#[macro_export]
+macro_rules! __klog_impl_once {
+    ($level:expr, $args:expr) => {{
+        static DONE: ::core::sync::atomic::AtomicBool =
+            ::core::sync::atomic::AtomicBool::new(false);
+        if $crate::is_level_enabled($level)
+            && !DONE.swap(true, ::core::sync::atomic::Ordering::Relaxed)
+        {
+            $crate::log_impl($level, $args);
+        } else {
+            $crate::log_filtered_impl();
+        }
+    }};
+}
+
+/// 条件成立时发出一次警告，带调用点上下文，返回条件的值
+///
+/// 与 Linux 的 `WARN_ONCE` 类似：每个调用点只在条件第一次成立时以 Warning 级别记录
+/// `WARNING: <文件>:<行> (<模块>): <消息>`，之后只返回条件，不再记录。
+///
+/// # 示例
+///
+/// ```rust
+/// use klog::warn_once;
+///
+/// let request = 0x5401u32;
+/// if warn_once!(request > 0x5400, "不支持的请求 {:#x}", request) {
+///     // 回退处理
+/// }
+/// ```
+#[macro_export]
+macro_rules! warn_once {
+    ($cond:expr $(,)?) => {
+        $crate::warn_once!($cond, "{}", stringify!($cond))
+    };
+    ($cond:expr, $($arg:tt)+) => {{
+        static WARNED: ::core::sync::atomic::AtomicBool = ::core::sync::atomic::AtomicBool::new(false);
+        let cond: bool = $cond;
+        if cond && !WARNED.swap(true, ::core::sync::atomic::Ordering::Relaxed) {
+            $crate::warn_impl(
+                concat!(file!(), ":", line!()),
+                module_path!(),
+                format_args!($($arg)+),
+            );
+        }
+        cond
+    }};
+}
+
 /// 以 **EMERGENCY (紧急)** 级别记录消息
 ///
 /// 紧急日志表示系统不可用。这些日志始终会打印到控制台（如果控制台输出可用）并存储在缓冲区中。
@@ -59,12 +194,19 @@ macro_rules! __klog_impl_filtered {
 /// ```
 #[macro_export]
 macro_rules! pr_emerg {
-($($arg:tt)*) => {
-$crate::__klog_impl_filtered!(
-$crate::LogLevel::Emergency,
-format_args!($($arg)*)
-)
-}
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Emergency,
+            format_args!($($arg)*)
+        )
+    };
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_filtered!(
+            $crate::LogLevel::Emergency,
+            format_args!($($arg)*)
+        )
+    }
 }
 
 /// 以 **ALERT (警报)** 级别记录消息
@@ -79,6 +221,13 @@ format_args!($($arg)*)
 /// ```
 #[macro_export]
 macro_rules! pr_alert {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Alert,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__klog_impl_filtered!(
             $crate::LogLevel::Alert,
@@ -99,6 +248,13 @@ macro_rules! pr_alert {
 /// ```
 #[macro_export]
 macro_rules! pr_crit {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Critical,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__klog_impl_filtered!(
             $crate::LogLevel::Critical,
@@ -123,6 +279,13 @@ macro_rules! pr_crit {
 /// ```
 #[macro_export]
 macro_rules! pr_err {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Error,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__klog_impl_filtered!(
             $crate::LogLevel::Error,
@@ -146,6 +309,13 @@ macro_rules! pr_err {
 /// ```
 #[macro_export]
 macro_rules! pr_warn {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Warning,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__klog_impl_filtered!(
             $crate::LogLevel::Warning,
@@ -168,6 +338,13 @@ macro_rules! pr_warn {
 /// ```
 #[macro_export]
 macro_rules! pr_notice {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Notice,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__klog_impl_filtered!(
             $crate::LogLevel::Notice,
@@ -191,6 +368,13 @@ macro_rules! pr_notice {
 /// ```
 #[macro_export]
 macro_rules! pr_info {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Info,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__klog_impl_filtered!(
             $crate::LogLevel::Info,
@@ -217,6 +401,13 @@ macro_rules! pr_info {
 /// ```
 #[macro_export]
 macro_rules! pr_debug {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__klog_impl_target!(
+            $target,
+            $crate::LogLevel::Debug,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__klog_impl_filtered!(
             $crate::LogLevel::Debug,
@@ -224,3 +415,264 @@ macro_rules! pr_debug {
         )
     }
 }
+
+/// 以指定级别记录消息，控制台输出延迟到安全的上下文（类似 Linux 的 `printk_deferred`）
+///
+/// 只写环形缓冲区，不直接调用控制台输出端，用于调度器内部、持有运行队列锁
+/// 或陷入处理等可能与控制台锁形成死锁的路径。
+///
+/// # 示例
+///
+/// ```rust
+/// use klog::{LogLevel, printk_deferred};
+///
+/// let tid = 3u32;
+/// printk_deferred!(LogLevel::Debug, "任务 {} 加入运行队列", tid);
+/// ```
+#[macro_export]
+macro_rules! printk_deferred {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::is_level_enabled($level) {
+            $crate::log_impl_deferred($level, format_args!($($arg)*));
+        } else {
+            $crate::log_filtered_impl();
+        }
+    };
+}
+
+/// 以指定级别按 `hexdump -C` 的格式转储一段字节，每 16 字节一行、每行一条日志
+///
+/// 每行以 `prefix` 开头，随后是偏移、十六进制与 ASCII 列。前缀过长时每行字节数相应减少，
+/// 保证单行不超过 [`MAX_LOG_MESSAGE_LENGTH`](crate::MAX_LOG_MESSAGE_LENGTH)。
+///
+/// # 示例
+///
+/// ```rust
+/// use klog::{LogLevel, pr_hex_dump};
+///
+/// let desc = [0x00u8, 0x10, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00];
+/// pr_hex_dump!(LogLevel::Debug, "virtq desc: ", &desc);
+/// ```
+#[macro_export]
+macro_rules! pr_hex_dump {
+    ($level:expr, $prefix:expr, $data:expr $(,)?) => {
+        if $crate::is_level_enabled($level) {
+            $crate::hex_dump_impl($level, $prefix, $data);
+        } else {
+            $crate::log_filtered_impl();
+        }
+    };
+}
+
+/// 把消息接在本 CPU 的上一条日志之后（**KERN_CONT**）
+///
+/// 用于分几次拼出一行的输出（如寄存器转储），续接的文本直接追加到上一条记录中，
+/// 不会与其他 CPU 的日志交错。上一条日志被级别过滤时续接文本一并丢弃；
+/// 没有可续接的记录（如中间发生了任务切换）时以 Warning 级别单独成条。
+///
+/// # 示例
+///
+/// ```rust
+/// use klog::{pr_cont, pr_info};
+///
+/// let regs = [0x10usize, 0x20, 0x30];
+/// pr_info!("regs:");
+/// for (i, reg) in regs.iter().enumerate() {
+///     pr_cont!(" x{}={:#x}", i, reg);
+/// }
+/// ```
+#[macro_export]
+macro_rules! pr_cont {
+    ($($arg:tt)*) => {
+        $crate::log_cont_impl(format_args!($($arg)*))
+    };
+}
+
+// ========== 限速版本 ==========
+
+/// 以 **EMERGENCY (紧急)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_emerg_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Emergency,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ALERT (警报)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_alert_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Alert,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **CRITICAL (关键)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_crit_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Critical,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ERROR (错误)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_err_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Error,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **WARNING (警告)** 级别记录消息，同一调用点按窗口限速
+///
+/// # 示例
+///
+/// ```rust
+/// use klog::pr_warn_ratelimited;
+///
+/// let queue = 0usize;
+/// pr_warn_ratelimited!("virtio-net: rx queue {} 已满，丢弃数据包", queue);
+/// ```
+#[macro_export]
+macro_rules! pr_warn_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Warning,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **NOTICE (通知)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_notice_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Notice,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **INFO (信息)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_info_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Info,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **DEBUG (调试)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_debug_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_ratelimited!(
+            $crate::LogLevel::Debug,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+// ========== 只记录一次的版本 ==========
+
+/// 以 **EMERGENCY (紧急)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_emerg_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Emergency,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ALERT (警报)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_alert_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Alert,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **CRITICAL (关键)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_crit_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Critical,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ERROR (错误)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_err_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Error,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **WARNING (警告)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_warn_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Warning,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **NOTICE (通知)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_notice_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Notice,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **INFO (信息)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_info_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Info,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **DEBUG (调试)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_debug_once {
+    ($($arg:tt)*) => {
+        $crate::__klog_impl_once!(
+            $crate::LogLevel::Debug,
+            format_args!($($arg)*)
+        )
+    }
+}
@@ -0,0 +1,343 @@
+//! pstore：把崩溃前的日志保存到跨重启保留的存储中
+//!
+//! 内核 panic 时调用 [`dump`]，把环形缓冲区中保留的日志（放不下时保留最新的部分）
+//! 连同 panic 原因写入已注册的后端；重启后用 [`read_record`] 读回上一次启动的崩溃日志，
+//! 由 os 层导出到 `/sys/fs/pstore`。
+//!
+//! 后端只需提供按偏移读写的字节存储：[`RamPstore`] 使用一块保留的物理内存，
+//! os 层也可以基于块设备实现 [`PstoreBackend`]。
+//!
+//! 存储布局为 [`PSTORE_HEADER_SIZE`] 字节的头部（魔数、正文长度、校验和）加正文。
+//! 正文是不带颜色、使用 `[秒.微秒]` 时间前缀的文本日志。先写正文后写头部，
+//! 写到一半时断电或再次崩溃留下的残缺记录不会通过校验。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+use super::log_core::{LogCore, LogStyle, format_log_entry};
+
+/// 头部魔数（"KPST"）
+const PSTORE_MAGIC: u32 = 0x5453_504b;
+
+/// 头部大小：魔数、正文长度、校验和与保留字段各 4 字节
+pub const PSTORE_HEADER_SIZE: usize = 16;
+
+/// 崩溃日志使用的格式
+const PSTORE_STYLE: LogStyle = LogStyle {
+    color: false,
+    time_prefix: true,
+};
+
+/// pstore 存储后端
+pub trait PstoreBackend: Send + Sync {
+    /// 存储容量（字节）
+    fn size(&self) -> usize;
+
+    /// 从 `offset` 处读满 `buf`，失败返回 `false`
+    fn read(&self, offset: usize, buf: &mut [u8]) -> bool;
+
+    /// 把 `data` 写到 `offset` 处，失败返回 `false`
+    fn write(&self, offset: usize, data: &[u8]) -> bool;
+}
+
+/// 基于一块保留内存的后端
+///
+/// 内存内容需要在重启后保持不变（热重启或 QEMU 的系统复位），且不能交给内存分配器。
+pub struct RamPstore {
+    base: usize,
+    size: usize,
+}
+
+impl RamPstore {
+    /// 使用 `[base, base + size)` 作为存储
+    ///
+    /// # Safety
+    ///
+    /// 该区域必须已映射、可读写，并且只由 pstore 使用。
+    pub unsafe fn new(base: *mut u8, size: usize) -> Self {
+        Self {
+            base: base as usize,
+            size,
+        }
+    }
+}
+
+impl PstoreBackend for RamPstore {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn read(&self, offset: usize, buf: &mut [u8]) -> bool {
+        if offset
+            .checked_add(buf.len())
+            .is_none_or(|end| end > self.size)
+        {
+            return false;
+        }
+        // Safety: 范围已检查，区域由 `new` 的调用者保证有效
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                (self.base + offset) as *const u8,
+                buf.as_mut_ptr(),
+                buf.len(),
+            );
+        }
+        true
+    }
+
+    fn write(&self, offset: usize, data: &[u8]) -> bool {
+        if offset
+            .checked_add(data.len())
+            .is_none_or(|end| end > self.size)
+        {
+            return false;
+        }
+        // Safety: 同上
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                (self.base + offset) as *mut u8,
+                data.len(),
+            );
+        }
+        true
+    }
+}
+
+/// 存储 PstoreBackend trait object 的胖指针
+struct BackendPtr {
+    data: AtomicPtr<()>,
+    vtable: AtomicPtr<()>,
+}
+
+static BACKEND: BackendPtr = BackendPtr {
+    data: AtomicPtr::new(core::ptr::null_mut()),
+    vtable: AtomicPtr::new(core::ptr::null_mut()),
+};
+
+/// 正在转储，防止转储过程中再次 panic 时重入
+static DUMPING: AtomicBool = AtomicBool::new(false);
+
+/// 注册 pstore 后端，后注册的替换先注册的
+///
+/// # Safety
+///
+/// 不能与 [`dump`] 并发调用。
+pub unsafe fn register_backend(backend: &'static dyn PstoreBackend) {
+    let ptr: *const dyn PstoreBackend = backend;
+    let (data, vtable) =
+        unsafe { core::mem::transmute::<*const dyn PstoreBackend, (*mut (), *mut ())>(ptr) };
+    BACKEND.data.store(data, Ordering::Release);
+    BACKEND.vtable.store(vtable, Ordering::Release);
+}
+
+/// 获取已注册的后端
+fn backend() -> Option<&'static dyn PstoreBackend> {
+    let data = BACKEND.data.load(Ordering::Acquire);
+    let vtable = BACKEND.vtable.load(Ordering::Acquire);
+    if data.is_null() || vtable.is_null() {
+        return None;
+    }
+    // Safety: 指针由 register_backend 设置，保证有效
+    Some(unsafe {
+        core::mem::transmute::<(*mut (), *mut ()), &'static dyn PstoreBackend>((data, vtable))
+    })
+}
+
+/// 是否已注册后端
+pub fn is_available() -> bool {
+    backend().is_some()
+}
+
+/// 把缓冲区中保留的日志与崩溃原因写入后端，返回写入的正文字节数
+///
+/// 只在 panic 路径调用，每次启动只转储一次；没有后端或写入失败时返回 0。
+pub fn dump(reason: fmt::Arguments) -> usize {
+    if DUMPING.swap(true, Ordering::AcqRel) {
+        return 0;
+    }
+    let Some(backend) = backend() else {
+        return 0;
+    };
+    let capacity = backend.size().saturating_sub(PSTORE_HEADER_SIZE);
+    let body = collect(&crate::GLOBAL_LOG, capacity, reason);
+    if write_record(backend, &body) {
+        body.len()
+    } else {
+        0
+    }
+}
+
+/// 读回后端中保存的崩溃日志；没有后端或没有有效记录时返回 `None`
+pub fn read_record() -> Option<Vec<u8>> {
+    read_from(backend()?)
+}
+
+/// 清除后端中保存的记录，没有后端时返回 `false`
+pub fn erase() -> bool {
+    backend().is_some_and(|backend| backend.write(0, &[0; PSTORE_HEADER_SIZE]))
+}
+
+/// 生成不超过 `capacity` 字节的正文：尽可能多的最新日志，最后是崩溃原因
+///
+/// 通过快照读取，不影响 syslog 的读指针，已被读走的日志同样会保存。
+fn collect(core: &LogCore, capacity: usize, reason: fmt::Arguments) -> Vec<u8> {
+    let mut tail = alloc::format!("{}\n", reason);
+    tail.truncate(floor_char_boundary(&tail, capacity));
+    let mut budget = capacity - tail.len();
+
+    let lines: Vec<String> = core
+        ._snapshot()
+        .map(|entry| {
+            let mut line = format_log_entry(&entry, PSTORE_STYLE);
+            line.push('\n');
+            line
+        })
+        .collect();
+    let mut start = lines.len();
+    while start > 0 && lines[start - 1].len() <= budget {
+        start -= 1;
+        budget -= lines[start].len();
+    }
+
+    let mut body = Vec::with_capacity(capacity - budget);
+    for line in &lines[start..] {
+        body.extend_from_slice(line.as_bytes());
+    }
+    body.extend_from_slice(tail.as_bytes());
+    body
+}
+
+/// 不超过 `index` 的最大字符边界
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// 写入一条记录：先写正文，再写头部
+fn write_record(backend: &dyn PstoreBackend, body: &[u8]) -> bool {
+    let mut header = [0u8; PSTORE_HEADER_SIZE];
+    header[0..4].copy_from_slice(&PSTORE_MAGIC.to_le_bytes());
+    header[4..8].copy_from_slice(&(body.len() as u32).to_le_bytes());
+    header[8..12].copy_from_slice(&checksum(body).to_le_bytes());
+    backend.write(PSTORE_HEADER_SIZE, body) && backend.write(0, &header)
+}
+
+/// 读出并校验一条记录
+fn read_from(backend: &dyn PstoreBackend) -> Option<Vec<u8>> {
+    let mut header = [0u8; PSTORE_HEADER_SIZE];
+    if !backend.read(0, &mut header) {
+        return None;
+    }
+    let word = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+    let len = word(4) as usize;
+    if word(0) != PSTORE_MAGIC || len > backend.size().saturating_sub(PSTORE_HEADER_SIZE) {
+        return None;
+    }
+    let mut body = alloc::vec![0u8; len];
+    if !backend.read(PSTORE_HEADER_SIZE, &mut body) || checksum(&body) != word(8) {
+        return None;
+    }
+    Some(body)
+}
+
+/// FNV-1a 校验和
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash, &b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::LogLevel;
+    use std::sync::Mutex;
+
+    /// 用内存数组模拟的后端
+    struct VecBackend(Mutex<Vec<u8>>);
+
+    impl VecBackend {
+        fn new(size: usize) -> Self {
+            Self(Mutex::new(alloc::vec![0xff; size]))
+        }
+    }
+
+    impl PstoreBackend for VecBackend {
+        fn size(&self) -> usize {
+            self.0.lock().unwrap().len()
+        }
+
+        fn read(&self, offset: usize, buf: &mut [u8]) -> bool {
+            let store = self.0.lock().unwrap();
+            match store.get(offset..offset + buf.len()) {
+                Some(src) => {
+                    buf.copy_from_slice(src);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn write(&self, offset: usize, data: &[u8]) -> bool {
+            let mut store = self.0.lock().unwrap();
+            match store.get_mut(offset..offset + data.len()) {
+                Some(dst) => {
+                    dst.copy_from_slice(data);
+                    true
+                }
+                None => false,
+            }
+        }
+    }
+
+    #[test]
+    fn test_pstore_round_trip() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        logger._log(LogLevel::Info, format_args!("first"));
+        logger._log(LogLevel::Error, format_args!("second"));
+        // 已被 syslog 读走的日志同样保存
+        logger._read_log().unwrap();
+
+        let backend = VecBackend::new(4096);
+        assert_eq!(read_from(&backend), None);
+
+        let body = collect(
+            &logger,
+            4096 - PSTORE_HEADER_SIZE,
+            format_args!("Panicked: oops"),
+        );
+        assert!(write_record(&backend, &body));
+        let text = String::from_utf8(read_from(&backend).unwrap()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("[INFO] [CPU0/T  0] first"));
+        assert!(lines[1].ends_with("[ERR] [CPU0/T  0] second"));
+        assert_eq!(lines[2], "Panicked: oops");
+
+        // 损坏的正文不能通过校验
+        backend.write(PSTORE_HEADER_SIZE, b"X");
+        assert_eq!(read_from(&backend), None);
+    }
+
+    #[test]
+    fn test_pstore_keeps_newest_entries() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        for i in 0..10 {
+            logger._log(LogLevel::Info, format_args!("message {}", i));
+        }
+        let one_line = format_log_entry(&logger._peek_log(10).unwrap(), PSTORE_STYLE).len() + 1;
+        let body = collect(&logger, 2 * one_line + 4, format_args!("end"));
+        let text = String::from_utf8(body).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("message 8"));
+        assert!(lines[1].ends_with("message 9"));
+        assert_eq!(lines[2], "end");
+    }
+}
@@ -0,0 +1,174 @@
+//! 独立的日志读者
+//!
+//! 环形缓冲区只有一个破坏性读指针（syslog 读取），多个消费者共用时会互相"偷走"条目。
+//! [`LogReader`] 持有自己的游标，只做非破坏性读取：各读者互不影响，也不移动全局读指针，
+//! 只有被覆盖写入的条目才会错过（计入 [`LogReader::take_lost`]）。
+//!
+//! - [`LogCore::_reader`] 创建跟随新日志的读者，读到末尾后返回 `None`，之后可以继续读；
+//! - [`LogCore::_snapshot`] 创建快照读者，只遍历创建时已分配序号的条目，适合一次性导出。
+
+use super::entry::LogEntry;
+use super::log_core::LogCore;
+
+/// 拥有独立游标的日志读者
+///
+/// 实现了 [`Iterator`]：跟随读者读到当前末尾时返回 `None`，有新日志后可以继续迭代。
+pub struct LogReader<'a> {
+    core: &'a LogCore,
+    /// 下一条要读的序号
+    seq: usize,
+    /// 快照的结束序号（不含），跟随读者为 `usize::MAX`
+    end: usize,
+    /// 因覆盖写入而跳过、尚未报告的条目数
+    lost: usize,
+}
+
+impl<'a> LogReader<'a> {
+    pub(crate) fn new(core: &'a LogCore, end: usize) -> Self {
+        Self {
+            core,
+            seq: core.buffer().oldest_index(),
+            end,
+            lost: 0,
+        }
+    }
+
+    /// 下一条要读的序号
+    pub fn seq(&self) -> usize {
+        self.seq
+    }
+
+    /// 游标移到最旧的保留条目
+    pub fn seek_oldest(&mut self) {
+        self.seq = self.core.buffer().oldest_index();
+        self.lost = 0;
+    }
+
+    /// 游标移到清除点（syslog CLEAR 之后的第一条）
+    pub fn seek_clear(&mut self) {
+        self.seq = self
+            .core
+            .buffer()
+            .clear_index()
+            .max(self.core.buffer().oldest_index());
+        self.lost = 0;
+    }
+
+    /// 游标移到末尾，只读之后写入的日志
+    pub fn seek_end(&mut self) {
+        self.seq = self.core.buffer().writer_index();
+        self.lost = 0;
+    }
+
+    /// 取出并清零上次调用以来因覆盖写入而跳过的条目数
+    pub fn take_lost(&mut self) -> usize {
+        core::mem::take(&mut self.lost)
+    }
+
+    /// 查看游标处的条目而不前进
+    ///
+    /// 游标处的条目已被覆盖时先跳到最旧的保留条目并累计跳过的条数。
+    /// 读到末尾、快照结束或条目尚未发布时返回 `None`。
+    pub fn peek(&mut self) -> Option<LogEntry> {
+        let buffer = self.core.buffer();
+        loop {
+            if self.seq >= self.end || self.seq >= buffer.writer_index() {
+                return None;
+            }
+            let oldest = buffer.oldest_index();
+            if self.seq < oldest {
+                self.lost += oldest - self.seq;
+                self.seq = oldest;
+                continue;
+            }
+            if let Some(entry) = buffer.get(self.seq) {
+                return Some(entry);
+            }
+            // 尚未发布，或在检查之后刚被覆盖
+            if self.seq >= buffer.oldest_index() {
+                return None;
+            }
+        }
+    }
+
+    /// 游标前进一条（通常在 [`peek`](Self::peek) 得到的条目处理完之后调用）
+    pub fn advance(&mut self) {
+        self.seq += 1;
+    }
+}
+
+impl Iterator for LogReader<'_> {
+    type Item = LogEntry;
+
+    fn next(&mut self) -> Option<LogEntry> {
+        let entry = self.peek()?;
+        self.advance();
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::LogLevel;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    fn messages(reader: &mut LogReader) -> Vec<String> {
+        reader.map(|entry| String::from(entry.message())).collect()
+    }
+
+    #[test]
+    fn test_readers_do_not_steal_entries() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        logger._log(LogLevel::Info, format_args!("a"));
+        logger._log(LogLevel::Info, format_args!("b"));
+
+        let mut first = logger._reader();
+        let mut second = logger._reader();
+        assert_eq!(first.next().unwrap().message(), "a");
+        // 破坏性读取不影响独立读者
+        assert_eq!(logger._read_log().unwrap().message(), "a");
+        assert_eq!(messages(&mut second), ["a", "b"]);
+        assert_eq!(messages(&mut first), ["b"]);
+
+        // 跟随读者读到末尾后还能继续读新日志
+        logger._log(LogLevel::Info, format_args!("c"));
+        assert_eq!(messages(&mut first), ["c"]);
+        assert_eq!(first.seq(), 4);
+        assert_eq!(logger._log_len(), 2);
+    }
+
+    #[test]
+    fn test_snapshot_excludes_later_entries() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        logger._log(LogLevel::Info, format_args!("old"));
+        let mut snapshot = logger._snapshot();
+        logger._log(LogLevel::Info, format_args!("new"));
+        assert_eq!(messages(&mut snapshot), ["old"]);
+
+        let mut reader = logger._reader();
+        reader.seek_end();
+        assert!(reader.next().is_none());
+        logger._log(LogLevel::Info, format_args!("after"));
+        assert_eq!(messages(&mut reader), ["after"]);
+    }
+
+    #[test]
+    fn test_reader_reports_overwritten_entries() {
+        let logger = LogCore::new(LogLevel::Info, LogLevel::Emergency);
+        let mut reader = logger._reader();
+        let mut total = 0;
+        while logger.buffer().oldest_index() == 1 {
+            logger._log(LogLevel::Info, format_args!("log {}", total));
+            total += 1;
+        }
+
+        let entry = reader.peek().unwrap();
+        let lost = reader.take_lost();
+        assert!(lost > 0);
+        assert_eq!(entry.seq(), 1 + lost);
+        assert_eq!(reader.by_ref().count() + lost, total);
+        assert_eq!(reader.take_lost(), 0);
+    }
+}
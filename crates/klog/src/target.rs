@@ -0,0 +1,128 @@
+//! 按目标（子系统）控制调试日志
+//!
+//! 日志宏可以带 `target: "vfs"` 标记所属子系统。全局级别过滤掉的带目标日志
+//! 会查询本模块的注册表：该目标的调试开关打开时仍然记录，从而可以只打开
+//! 某一个子系统的 Debug 日志，而不必全局打开。
+//!
+//! 注册表是固定大小的数组，只使用原子操作：
+//! - 带目标的日志第一次被全局级别过滤时自动登记该目标（开关默认关闭）；
+//! - 运行时通过 [`TargetRegistry::set_debug`] 打开或关闭开关，
+//!   尚未登记的目标会被登记（名字复制到堆上并常驻）。
+
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+use super::config::MAX_LOG_TARGETS;
+
+/// 注册表中的一个目标
+struct TargetSlot {
+    /// 名字的地址，空指针表示槽位空闲
+    name_ptr: AtomicPtr<u8>,
+    /// 名字长度，为 0 表示登记尚未完成
+    name_len: AtomicUsize,
+    /// 调试开关
+    debug: AtomicBool,
+}
+
+impl TargetSlot {
+    const fn new() -> Self {
+        Self {
+            name_ptr: AtomicPtr::new(core::ptr::null_mut()),
+            name_len: AtomicUsize::new(0),
+            debug: AtomicBool::new(false),
+        }
+    }
+
+    /// 已完成登记时返回目标名
+    fn name(&self) -> Option<&'static str> {
+        let ptr = self.name_ptr.load(Ordering::Acquire);
+        let len = self.name_len.load(Ordering::Acquire);
+        if ptr.is_null() || len == 0 {
+            return None;
+        }
+        // Safety: 名字来自 'static 字符串，登记后不再改变
+        Some(unsafe { core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr, len)) })
+    }
+}
+
+/// 目标注册表
+pub(crate) struct TargetRegistry {
+    slots: [TargetSlot; MAX_LOG_TARGETS],
+}
+
+impl TargetRegistry {
+    /// 在编译时创建一个空注册表
+    pub(crate) const fn new() -> Self {
+        Self {
+            slots: [const { TargetSlot::new() }; MAX_LOG_TARGETS],
+        }
+    }
+
+    fn find(&self, target: &str) -> Option<&TargetSlot> {
+        self.slots
+            .iter()
+            .find(|slot| slot.name().is_some_and(|name| name == target))
+    }
+
+    /// 登记目标，注册表已满时返回 `None`
+    fn register(&self, target: &'static str) -> Option<&TargetSlot> {
+        if target.is_empty() {
+            return None;
+        }
+        for slot in &self.slots {
+            if slot
+                .name_ptr
+                .compare_exchange(
+                    core::ptr::null_mut(),
+                    target.as_ptr() as *mut u8,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                slot.name_len.store(target.len(), Ordering::Release);
+                return Some(slot);
+            }
+        }
+        None
+    }
+
+    /// 目标的调试开关是否打开；未登记的目标在此登记
+    pub(crate) fn debug_enabled(&self, target: &'static str) -> bool {
+        self.find(target)
+            .or_else(|| self.register(target))
+            .is_some_and(|slot| slot.debug.load(Ordering::Relaxed))
+    }
+
+    /// 设置目标的调试开关
+    ///
+    /// `target` 为 `*` 时作用于所有已登记的目标。注册表已满、无法登记新目标时返回 `false`。
+    pub(crate) fn set_debug(&self, target: &str, enable: bool) -> bool {
+        if target == "*" {
+            for slot in self.slots.iter().filter(|slot| slot.name().is_some()) {
+                slot.debug.store(enable, Ordering::Relaxed);
+            }
+            return true;
+        }
+        let slot = match self.find(target) {
+            Some(slot) => Some(slot),
+            None => self.register(Box::leak(target.into())),
+        };
+        match slot {
+            Some(slot) => {
+                slot.debug.store(enable, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 按登记顺序遍历目标及其调试开关
+    pub(crate) fn for_each(&self, mut f: impl FnMut(&str, bool)) {
+        for slot in &self.slots {
+            if let Some(name) = slot.name() {
+                f(name, slot.debug.load(Ordering::Relaxed));
+            }
+        }
+    }
+}
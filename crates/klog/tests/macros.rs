@@ -2,7 +2,10 @@
 
 use std::sync::{Mutex, MutexGuard, Once, OnceLock};
 
-use klog::{LogContextProvider, LogLevel, LogOutput, pr_debug, pr_err, pr_info, pr_warn};
+use klog::{
+    LogContextProvider, LogLevel, LogOutput, LogValue, pr_debug, pr_err, pr_fields, pr_info,
+    pr_warn, pr_warn_once, printk_deferred, warn_once,
+};
 
 static INIT: Once = Once::new();
 
@@ -123,3 +126,121 @@ fn test_pr_debug_filtered_by_default_level() {
     assert_eq!(klog::log_len(), 0);
     assert_eq!(take_output(), "");
 }
+
+#[test]
+fn test_pr_fields_attaches_key_values() {
+    let _guard = setup();
+
+    let len = 64usize;
+    pr_fields!(LogLevel::Info, ["DEVICE" => "eth0", "LEN" => len], "rx {}", len);
+
+    let entry = klog::read_log().unwrap();
+    assert_eq!(entry.message(), "rx 64");
+    let fields = entry.fields();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[0].key(), "DEVICE");
+    assert_eq!(fields[0].value().as_str(), Some("eth0"));
+    assert_eq!(*fields[1].value(), LogValue::U64(64));
+
+    // Console output carries only the formatted message.
+    let out = take_output();
+    assert!(out.contains("rx 64") && !out.contains("DEVICE"));
+}
+
+#[test]
+fn test_pr_debug_target_enabled_at_runtime() {
+    let _guard = setup();
+
+    pr_debug!(target: "itest", "off");
+    assert_eq!(klog::log_len(), 0);
+
+    assert!(klog::set_target_debug("itest", true));
+    pr_debug!(target: "itest", "on {}", 1);
+    pr_debug!(target: "other", "still off");
+    assert_eq!(klog::log_len(), 1);
+    assert_eq!(klog::read_log().unwrap().message(), "on 1");
+
+    assert!(klog::set_target_debug("itest", false));
+}
+
+#[test]
+fn test_once_macros_log_only_first_time() {
+    let _guard = setup();
+
+    for i in 0..3 {
+        pr_warn_once!("unsupported request {}", i);
+    }
+    assert_eq!(klog::log_len(), 1);
+    assert_eq!(klog::read_log().unwrap().message(), "unsupported request 0");
+
+    let mut hits = 0;
+    for i in 0..4 {
+        if warn_once!(i % 2 == 1, "odd {}", i) {
+            hits += 1;
+        }
+    }
+    assert_eq!(hits, 2);
+    assert_eq!(klog::log_len(), 1);
+    let entry = klog::read_log().unwrap();
+    assert_eq!(entry.level(), LogLevel::Warning);
+    assert!(entry.message().starts_with("WARNING: tests/macros.rs:"));
+    assert!(entry.message().ends_with("(macros): odd 1"));
+}
+
+static SINK_BUF: OnceLock<Mutex<String>> = OnceLock::new();
+
+struct TestSink;
+
+impl LogOutput for TestSink {
+    fn write_str(&self, s: &str) {
+        let buf = SINK_BUF.get_or_init(|| Mutex::new(String::new()));
+        buf.lock().unwrap().push_str(s);
+    }
+}
+
+static TEST_SINK: TestSink = TestSink;
+
+#[test]
+fn test_log_sink_receives_console_output() {
+    let _guard = setup();
+    static SINK_INIT: Once = Once::new();
+    SINK_INIT.call_once(|| unsafe {
+        assert!(klog::register_log_sink(&TEST_SINK));
+    });
+    SINK_BUF
+        .get_or_init(|| Mutex::new(String::new()))
+        .lock()
+        .unwrap()
+        .clear();
+
+    klog::set_console_level(LogLevel::Warning);
+    pr_err!("to every sink");
+    pr_info!("buffer only");
+    klog::set_console_level(klog::DEFAULT_CONSOLE_LEVEL);
+
+    let sink = SINK_BUF.get().unwrap().lock().unwrap().clone();
+    assert!(take_output().contains("to every sink"));
+    assert!(sink.contains("to every sink"));
+    assert!(!sink.contains("buffer only"));
+}
+
+#[test]
+fn test_printk_deferred_waits_for_flush() {
+    let _guard = setup();
+
+    printk_deferred!(LogLevel::Error, "from the scheduler");
+    printk_deferred!(LogLevel::Debug, "filtered");
+    assert!(take_output().is_empty());
+    assert!(klog::console_pending());
+
+    // 下一条普通日志先输出积压的延迟条目
+    pr_err!("after");
+    let output = take_output();
+    let deferred = output.find("from the scheduler").unwrap();
+    assert!(deferred < output.find("after").unwrap());
+    assert!(!klog::console_pending());
+
+    let entry = klog::read_log().unwrap();
+    assert!(entry.is_deferred());
+    assert!(!klog::read_log().unwrap().is_deferred());
+}
@@ -1,5 +1,6 @@
 //! 架构相关内存管理操作 trait 定义和注册
 
+use crate::address::{Ppn, Vpn};
 use crate::page_table::PagingError;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
@@ -17,6 +18,15 @@ pub trait ArchMmOps: Send + Sync {
     /// 将物理地址转换为虚拟地址（直接映射区域）
     fn paddr_to_vaddr(&self, paddr: usize) -> usize;
 
+    /// 一致性 DMA 内存的内核虚拟地址
+    ///
+    /// 返回的地址访问 `paddr` 时不需要软件维护缓存：设备与缓存一致的架构返回线性映射地址，
+    /// 否则返回非缓存的别名。
+    fn dma_coherent_vaddr(&self, paddr: usize) -> usize;
+
+    /// 物理地址转换为设备使用的总线地址
+    fn phys_to_dma(&self, paddr: usize) -> usize;
+
     /// 获取 sigreturn trampoline 代码字节
     fn sigreturn_trampoline_bytes(&self) -> &'static [u8];
 
@@ -32,21 +42,27 @@ pub trait ArchMmOps: Send + Sync {
 
 /// TLB 批处理上下文 trait
 ///
-/// 用于批量处理 TLB 刷新操作，减少 IPI 开销
+/// 用于批量处理 TLB 刷新操作，减少 IPI 开销：批处理期间修改的页只在本 CPU 上立即刷新，
+/// 其它 CPU 在 [`flush`](Self::flush) 时一次性刷新。
 pub trait TlbBatchContextTrait {
+    /// 记录根页表为 `root` 的地址空间中 `vpn` 处的页需要在其它 CPU 上刷新
+    fn add_page(&mut self, root: Ppn, vpn: Vpn);
+
     /// 刷新所有待处理的 TLB 条目
     fn flush(&mut self);
 }
 
+/// 架构 TLB 批处理上下文的最大字节数
+const TLB_BATCH_STORAGE: usize = 256;
+
 /// TLB 批处理上下文包装器
 ///
 /// 包装架构特定的 TlbBatchContext 实现
 pub struct TlbBatchContextWrapper {
-    // 存储 fat pointer 的两部分
-    inner_data: usize,
+    // fat pointer 的 vtable 部分，data 部分总是指向 _storage（包装器移动后仍然有效）
     inner_vtable: usize,
-    // 存储实际的上下文数据（最多 64 字节应该足够）
-    _storage: [u8; 64],
+    // 存储实际的上下文数据（需要容纳待刷新的页地址），按 usize 对齐
+    _storage: [usize; TLB_BATCH_STORAGE / core::mem::size_of::<usize>()],
 }
 
 impl TlbBatchContextWrapper {
@@ -57,34 +73,43 @@ impl TlbBatchContextWrapper {
     pub unsafe fn new<T: TlbBatchContextTrait + 'static>(ctx: T) -> Self {
         unsafe {
             let mut wrapper = Self {
-                inner_data: 0,
                 inner_vtable: 0,
-                _storage: [0u8; 64],
+                _storage: [0; TLB_BATCH_STORAGE / core::mem::size_of::<usize>()],
             };
             // 将 ctx 复制到 _storage 中
-            assert!(core::mem::size_of::<T>() <= 64);
+            assert!(core::mem::size_of::<T>() <= TLB_BATCH_STORAGE);
+            assert!(core::mem::align_of::<T>() <= core::mem::align_of::<usize>());
             let storage_ptr = wrapper._storage.as_mut_ptr() as *mut T;
             core::ptr::write(storage_ptr, ctx);
-            // 设置 inner 指向 storage 中的数据
+            // 只记录 vtable，使用时再与 _storage 的当前地址组合
             let fat_ptr = storage_ptr as *mut dyn TlbBatchContextTrait;
-            let (data, vtable) =
+            let (_, vtable) =
                 core::mem::transmute::<*mut dyn TlbBatchContextTrait, (usize, usize)>(fat_ptr);
-            wrapper.inner_data = data;
             wrapper.inner_vtable = vtable;
             wrapper
         }
     }
 
+    /// 取得 _storage 中的上下文
+    fn inner(&mut self) -> &mut dyn TlbBatchContextTrait {
+        let data = self._storage.as_mut_ptr() as usize;
+        // SAFETY: _storage 中存放着 new 写入的上下文，vtable 与之对应
+        unsafe {
+            &mut *core::mem::transmute::<(usize, usize), *mut dyn TlbBatchContextTrait>((
+                data,
+                self.inner_vtable,
+            ))
+        }
+    }
+
+    /// 记录需要在其它 CPU 上刷新的页
+    pub fn add_page(&mut self, root: Ppn, vpn: Vpn) {
+        self.inner().add_page(root, vpn);
+    }
+
     /// 刷新所有待处理的 TLB 条目
     pub fn flush(&mut self) {
-        if self.inner_data != 0 {
-            unsafe {
-                let fat_ptr = core::mem::transmute::<(usize, usize), *mut dyn TlbBatchContextTrait>(
-                    (self.inner_data, self.inner_vtable),
-                );
-                (*fat_ptr).flush()
-            }
-        }
+        self.inner().flush();
     }
 
     /// 在批处理上下文中执行操作
@@ -141,10 +166,13 @@ mod test_mock {
     extern crate test_support;
 
     use super::{ArchMmOps, TlbBatchContextTrait, TlbBatchContextWrapper};
+    use crate::address::{Ppn, Vpn};
 
     struct MockTlbBatchContext;
 
     impl TlbBatchContextTrait for MockTlbBatchContext {
+        fn add_page(&mut self, _root: Ppn, _vpn: Vpn) {}
+
         fn flush(&mut self) {}
     }
 
@@ -157,6 +185,14 @@ mod test_mock {
             self.paddr_to_vaddr(paddr)
         }
 
+        fn dma_coherent_vaddr(&self, paddr: usize) -> usize {
+            self.paddr_to_vaddr(paddr)
+        }
+
+        fn phys_to_dma(&self, paddr: usize) -> usize {
+            paddr
+        }
+
         fn sigreturn_trampoline_bytes(&self) -> &'static [u8] {
             self.sigreturn_trampoline_bytes()
         }
@@ -170,8 +206,42 @@ mod test_mock {
         }
 
         fn create_tlb_batch_context(&self) -> TlbBatchContextWrapper {
-            // SAFETY: 测试使用的 context 为 'static 且大小 <= 64 字节。
+            // SAFETY: 测试使用的 context 为 'static 且不超过 TLB_BATCH_STORAGE 字节。
             unsafe { TlbBatchContextWrapper::new(MockTlbBatchContext) }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::UsizeConvert;
+    use alloc::boxed::Box;
+
+    static FLUSHED: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingContext {
+        pages: usize,
+    }
+
+    impl TlbBatchContextTrait for CountingContext {
+        fn add_page(&mut self, _root: Ppn, _vpn: Vpn) {
+            self.pages += 1;
+        }
+
+        fn flush(&mut self) {
+            FLUSHED.store(self.pages, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_tlb_batch_wrapper_survives_move() {
+        // 包装器创建后被移动到堆上，记录的页仍写入它自己的上下文
+        let wrapper = unsafe { TlbBatchContextWrapper::new(CountingContext { pages: 0 }) };
+        let mut moved = Box::new(wrapper);
+        moved.add_page(Ppn::from_usize(1), Vpn::from_usize(2));
+        moved.add_page(Ppn::from_usize(1), Vpn::from_usize(3));
+        moved.flush();
+        assert_eq!(FLUSHED.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,152 @@
+//! 内存规整（compaction）与页迁移
+//!
+//! 长时间运行后空闲帧分散在各处，伙伴系统拼不出高阶块，内核栈与大页等连续分配随之失败。
+//! 规整在每个内存区域内运行两个相向的扫描器：
+//!
+//! - **迁移扫描器**从区域低端向上，找只被一个地址空间映射的页（见 [`crate::rmap`]）；
+//! - **空闲扫描器**从区域高端向下，取出空闲帧作为迁移目标。
+//!
+//! 每迁移一页，低端就空出一帧并与伙伴合并；两个扫描器相遇时该区域规整结束。
+//!
+//! 迁移（[`MappingArea::migrate_page`](crate::memory_space::MappingArea::migrate_page)）
+//! 需要持有页所在地址空间的锁，由 os crate 注册的 [`MigrateOps`] 按反向映射记录的根页表
+//! 找到地址空间后完成。内核自己的页、页缓存页与被多个地址空间共享的页不迁移。
+//!
+//! 多于一帧的连续分配失败时触发规整（[`compact_for_order`]），也可以经
+//! `/proc/sys/vm/compact_memory` 手动规整所有区域（[`compact_memory`]）。
+//! 结果计入 [`vmstat`](crate::vmstat) 的 `compact_*` 与 `pgmigrate_*` 事件。
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::address::{Ppn, UsizeConvert, Vpn};
+use crate::frame_allocator::{self, FrameTracker};
+use crate::rmap;
+use crate::vmstat::{self, VmEvent};
+
+/// 由 os crate 实现的页迁移操作
+pub trait MigrateOps: Send + Sync {
+    /// 把根页表为 `owner` 的地址空间中 `vpn` 处的物理页 `old` 迁移到 `new`
+    ///
+    /// 规整可能发生在任意分配连续帧的位置，调用者可能已持有该地址空间的锁，
+    /// 实现只能尝试加锁。
+    ///
+    /// # 返回值
+    /// 迁移成功时返回 `true`；失败时 `new` 被释放
+    fn migrate_page(&self, owner: Ppn, vpn: Vpn, old: Ppn, new: FrameTracker) -> bool;
+}
+
+static MIGRATE_OPS_DATA: AtomicUsize = AtomicUsize::new(0);
+static MIGRATE_OPS_VTABLE: AtomicUsize = AtomicUsize::new(0);
+
+/// 注册页迁移操作实现
+///
+/// # Safety
+/// 必须在单线程环境下调用，且只能调用一次
+pub unsafe fn register_migrate_ops(ops: &'static dyn MigrateOps) {
+    let ptr = ops as *const dyn MigrateOps;
+    // SAFETY: 将 fat pointer 拆分为 data 和 vtable 两部分存储
+    let (data, vtable) =
+        unsafe { core::mem::transmute::<*const dyn MigrateOps, (usize, usize)>(ptr) };
+    MIGRATE_OPS_VTABLE.store(vtable, Ordering::Release);
+    MIGRATE_OPS_DATA.store(data, Ordering::Release);
+}
+
+fn migrate_ops() -> Option<&'static dyn MigrateOps> {
+    let data = MIGRATE_OPS_DATA.load(Ordering::Acquire);
+    let vtable = MIGRATE_OPS_VTABLE.load(Ordering::Acquire);
+    if data == 0 {
+        return None;
+    }
+    // SAFETY: 重组 fat pointer
+    Some(unsafe { &*core::mem::transmute::<(usize, usize), *const dyn MigrateOps>((data, vtable)) })
+}
+
+/// 防止规整过程中的分配再次进入规整
+static COMPACTING: AtomicBool = AtomicBool::new(false);
+
+/// 规整所有内存区域，返回迁移的页数
+///
+/// 没有注册 [`MigrateOps`] 或已在规整中时什么都不做。
+pub fn compact_memory() -> usize {
+    compact(None).map_or(0, |(migrated, _)| migrated)
+}
+
+/// 为 `order` 阶的连续分配规整内存
+///
+/// 逐区域规整，某个区域拼出不小于 `order` 阶的空闲块时停止。
+///
+/// # 返回值
+/// 拼出了所需的空闲块、调用者应当重试分配时返回 `true`
+pub fn compact_for_order(order: usize) -> bool {
+    kcov!();
+    vmstat::count_event(VmEvent::CompactStall);
+    let success = compact(Some(order)).is_some_and(|(_, success)| success);
+    vmstat::count_event(if success {
+        VmEvent::CompactSuccess
+    } else {
+        VmEvent::CompactFail
+    });
+    success
+}
+
+/// 依次规整各区域，给定 `order` 时有区域拼出该阶空闲块即停止
+///
+/// # 返回值
+/// 迁移的页数与是否拼出了 `order` 阶空闲块；没有注册迁移操作或已在规整中时返回 `None`
+fn compact(order: Option<usize>) -> Option<(usize, bool)> {
+    let ops = migrate_ops()?;
+    if COMPACTING.swap(true, Ordering::Acquire) {
+        return None;
+    }
+    let mut migrated = 0;
+    let mut success = false;
+    for span in frame_allocator::zone_spans() {
+        let (n, done) = compact_zone(ops, span.start(), span.end(), order);
+        migrated += n;
+        if done {
+            success = true;
+            break;
+        }
+    }
+    COMPACTING.store(false, Ordering::Release);
+    Some((migrated, success))
+}
+
+/// 规整 `[start, end)` 这一个区域
+///
+/// # 返回值
+/// 迁移的页数与区域中是否已有 `order` 阶空闲块（`order` 为 `None` 时总是 `false`）
+fn compact_zone(ops: &dyn MigrateOps, start: Ppn, end: Ppn, order: Option<usize>) -> (usize, bool) {
+    let done = || order.is_some_and(|order| frame_allocator::zone_has_free_block(start, order));
+    if done() {
+        return (0, true);
+    }
+    let mut migrated = 0;
+    let mut free_cursor = end;
+    let mut scan = start;
+    while scan < free_cursor {
+        let old = scan;
+        scan = Ppn::from_usize(scan.as_usize() + 1);
+        if !frame_allocator::frame_is_allocated(old) {
+            continue;
+        }
+        let mappings = rmap::page_mappings(old);
+        let [entry] = mappings.as_slice() else {
+            continue;
+        };
+        // 迁移目标总在被迁移页之上，扫描器相遇时没有可用的目标
+        let Some(new) = frame_allocator::take_free_frame_below(&mut free_cursor, scan) else {
+            break;
+        };
+        if ops.migrate_page(entry.owner, entry.vpn, old, new) {
+            migrated += 1;
+            vmstat::count_event(VmEvent::PgMigrateSuccess);
+            if done() {
+                return (migrated, true);
+            }
+        } else {
+            vmstat::count_event(VmEvent::PgMigrateFail);
+        }
+    }
+    (migrated, done())
+}
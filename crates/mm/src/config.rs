@@ -2,6 +2,17 @@
 
 use core::sync::atomic::{AtomicUsize, Ordering};
 
+/// 一段属于某个 NUMA 节点的物理内存 `[start, end)`（物理地址）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryNode {
+    /// 节点编号（设备树 `numa-node-id`）
+    pub node: usize,
+    /// 起始物理地址
+    pub start: usize,
+    /// 结束物理地址（不包含）
+    pub end: usize,
+}
+
 /// 内存管理配置常量
 ///
 /// 此 trait 提供内存管理所需的配置常量。
@@ -24,6 +35,18 @@ pub trait MmConfig: Send + Sync {
 
     /// 信号返回跳板地址
     fn user_sigreturn_trampoline(&self) -> usize;
+
+    /// 各 NUMA 节点的物理内存区间
+    ///
+    /// 默认返回空列表，表示只有一个覆盖全部物理内存的节点 0。
+    fn memory_nodes(&self) -> &[MemoryNode] {
+        &[]
+    }
+
+    /// 当前 CPU 所属的 NUMA 节点，作为默认分配策略的首选节点
+    fn current_node(&self) -> usize {
+        0
+    }
 }
 
 static CONFIG_DATA: AtomicUsize = AtomicUsize::new(0);
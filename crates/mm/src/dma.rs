@@ -0,0 +1,225 @@
+//! 设备一致性 DMA 内存
+//!
+//! 驱动通过 [`dma_alloc_coherent`] 得到一块 CPU 与设备都能访问的内存：
+//! [`DmaRegion`] 同时给出内核虚拟地址与设备使用的总线地址，释放时 drop 即可。
+//! 小块描述符等从 [`DmaPool`] 中分配，多个块共用一页。
+//!
+//! 虚拟地址的缓存属性由架构决定（见 [`ArchMmOps::dma_coherent_vaddr`]），
+//! 总线地址由 [`ArchMmOps::phys_to_dma`] 从物理地址转换，驱动不再自己做地址换算。
+//!
+//! [`ArchMmOps::dma_coherent_vaddr`]: crate::ArchMmOps::dma_coherent_vaddr
+//! [`ArchMmOps::phys_to_dma`]: crate::ArchMmOps::phys_to_dma
+
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+
+use sync::SpinLock;
+
+use crate::address::{PageNum, UsizeConvert};
+use crate::arch_ops::arch_ops;
+use crate::config::mm_config;
+use crate::frame_allocator::{FrameRangeTracker, ZoneType, alloc_contig_frames_zone};
+
+/// 设备能访问 32 位以内总线地址时使用的 DMA 掩码
+pub const DMA_BIT_MASK_32: u64 = u32::MAX as u64;
+
+/// 按 DMA 掩码选择分配帧的区域
+fn zone_for_mask(dma_mask: u64) -> ZoneType {
+    if dma_mask <= DMA_BIT_MASK_32 {
+        ZoneType::Dma32
+    } else {
+        ZoneType::Normal
+    }
+}
+
+/// 一块一致性 DMA 内存，drop 时归还物理帧
+#[derive(Debug)]
+pub struct DmaRegion {
+    frames: FrameRangeTracker,
+    vaddr: NonNull<u8>,
+    dma_addr: usize,
+    size: usize,
+}
+
+// SAFETY: DmaRegion 独占它的物理帧，虚拟地址只是这些帧的别名
+unsafe impl Send for DmaRegion {}
+// SAFETY: 同上，只读访问不改变任何状态
+unsafe impl Sync for DmaRegion {}
+
+impl DmaRegion {
+    /// CPU 访问使用的内核虚拟地址
+    pub fn vaddr(&self) -> NonNull<u8> {
+        self.vaddr
+    }
+
+    /// 设备访问使用的总线地址
+    pub fn dma_addr(&self) -> usize {
+        self.dma_addr
+    }
+
+    /// 区域大小（字节，按页向上取整）
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// 区域是否为空（总是 `false`，零长度的分配会失败）
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// 占用的物理帧
+    pub fn frames(&self) -> &FrameRangeTracker {
+        &self.frames
+    }
+}
+
+/// 分配 `size` 字节的一致性 DMA 内存
+///
+/// 内存物理连续、按页对齐并清零，总线地址不超过 `dma_mask`。
+/// `size` 为 0 或帧不足时返回 `None`。
+pub fn dma_alloc_coherent(size: usize, dma_mask: u64) -> Option<DmaRegion> {
+    kcov!();
+    if size == 0 {
+        return None;
+    }
+    let page_size = mm_config().page_size();
+    let pages = size.div_ceil(page_size);
+    let frames = alloc_contig_frames_zone(pages, zone_for_mask(dma_mask))?;
+    let paddr = frames.start_ppn().start_addr().as_usize();
+    let ops = arch_ops();
+    let vaddr = NonNull::new(ops.dma_coherent_vaddr(paddr) as *mut u8)?;
+    let dma_addr = ops.phys_to_dma(paddr);
+    debug_assert!((dma_addr + pages * page_size - 1) as u64 <= dma_mask);
+    // 帧在分配时已经通过线性映射清零；缓存属性不同的别名需要再清一次
+    // SAFETY: vaddr 指向刚分配的 pages 页
+    unsafe { core::ptr::write_bytes(vaddr.as_ptr(), 0, pages * page_size) };
+    Some(DmaRegion {
+        frames,
+        vaddr,
+        dma_addr,
+        size: pages * page_size,
+    })
+}
+
+/// 把线性映射中的内核地址转换为设备使用的总线地址（流式 DMA）
+///
+/// # Safety
+/// `vaddr` 必须位于线性映射中，且在设备访问期间保持有效
+pub unsafe fn virt_to_dma(vaddr: usize) -> usize {
+    let ops = arch_ops();
+    // SAFETY: 由调用者保证 vaddr 位于线性映射中
+    ops.phys_to_dma(unsafe { ops.vaddr_to_paddr(vaddr) })
+}
+
+/// [`DmaPool`] 中的一块
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaBlock {
+    /// CPU 访问使用的内核虚拟地址
+    pub vaddr: NonNull<u8>,
+    /// 设备访问使用的总线地址
+    pub dma_addr: usize,
+}
+
+// SAFETY: DmaBlock 只是地址对，访问由持有者负责同步
+unsafe impl Send for DmaBlock {}
+
+/// 固定大小的一致性 DMA 小块分配器
+///
+/// 每次从 [`dma_alloc_coherent`] 取一页切成若干块，块不跨页。
+/// 页在池销毁时才归还，块释放后留在池中重用。
+pub struct DmaPool {
+    name: &'static str,
+    /// 块大小，已按对齐向上取整
+    block_size: usize,
+    dma_mask: u64,
+    inner: SpinLock<DmaPoolInner>,
+}
+
+struct DmaPoolInner {
+    pages: Vec<DmaRegion>,
+    free: Vec<DmaBlock>,
+}
+
+impl DmaPool {
+    /// 创建块大小为 `size`、按 `align` 对齐的池
+    ///
+    /// # Panics
+    /// `align` 不是 2 的幂或块大于一页时 panic
+    pub fn new(name: &'static str, size: usize, align: usize, dma_mask: u64) -> Self {
+        assert!(align.is_power_of_two(), "DmaPool {}: bad align", name);
+        let block_size = size.max(1).next_multiple_of(align);
+        assert!(
+            block_size <= mm_config().page_size(),
+            "DmaPool {}: block larger than a page",
+            name
+        );
+        Self {
+            name,
+            block_size,
+            dma_mask,
+            inner: SpinLock::new(DmaPoolInner {
+                pages: Vec::new(),
+                free: Vec::new(),
+            }),
+        }
+    }
+
+    /// 池的名字
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// 块大小（字节）
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// 分配一块清零的内存，帧不足时返回 `None`
+    pub fn alloc(&self) -> Option<DmaBlock> {
+        let mut inner = self.inner.lock();
+        if inner.free.is_empty() {
+            let page = dma_alloc_coherent(mm_config().page_size(), self.dma_mask)?;
+            let count = page.len() / self.block_size;
+            // 倒序压入，使块按地址从低到高分出
+            for i in (0..count).rev() {
+                let offset = i * self.block_size;
+                inner.free.push(DmaBlock {
+                    // SAFETY: offset 在页内
+                    vaddr: unsafe { page.vaddr().add(offset) },
+                    dma_addr: page.dma_addr() + offset,
+                });
+            }
+            inner.pages.push(page);
+        }
+        let block = inner.free.pop()?;
+        // SAFETY: 块属于池中的页，大小为 block_size
+        unsafe { core::ptr::write_bytes(block.vaddr.as_ptr(), 0, self.block_size) };
+        Some(block)
+    }
+
+    /// 归还 [`DmaPool::alloc`] 分配的块
+    pub fn free(&self, block: DmaBlock) {
+        let mut inner = self.inner.lock();
+        debug_assert!(
+            inner.pages.iter().any(|page| {
+                (page.dma_addr()..page.dma_addr() + page.len()).contains(&block.dma_addr)
+            }),
+            "DmaPool {}: block {:#x} not from this pool", // 块不属于此池
+            self.name,
+            block.dma_addr
+        );
+        inner.free.push(block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_for_mask() {
+        assert_eq!(zone_for_mask(DMA_BIT_MASK_32), ZoneType::Dma32);
+        assert_eq!(zone_for_mask(0xff_ffff), ZoneType::Dma32);
+        assert_eq!(zone_for_mask(u64::MAX), ZoneType::Normal);
+    }
+}
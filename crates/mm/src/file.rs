@@ -2,6 +2,8 @@
 
 use alloc::sync::Arc;
 
+use crate::page_cache::PageCache;
+
 /// 可用于内存映射读写的 Inode 接口
 ///
 /// 此 trait 抽象了文件 I/O 所需的最小接口。
@@ -11,6 +13,16 @@ pub trait MmInode: Send + Sync {
 
     /// 将缓冲区数据写入指定偏移
     fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize, isize>;
+
+    /// 文件的页缓存，返回 `Some` 时共享文件映射直接映射缓存页
+    fn page_cache(&self) -> Option<Arc<PageCache>> {
+        None
+    }
+
+    /// 把页缓存中的脏页写回存储设备
+    fn writeback(&self) -> Result<(), isize> {
+        Ok(())
+    }
 }
 
 /// 可映射到内存的文件接口
@@ -2,20 +2,42 @@
 //!
 //! 本模块提供物理内存帧的分配和跟踪功能。
 //!
-//! ## 分配策略（位图）
+//! ## 分配策略（伙伴系统）
 //!
-//! 分配器使用位图（bitmap）跟踪每个物理帧的分配状态：
+//! 分配器以伙伴系统管理物理帧：空闲内存组织成 `2^k`（`k <= MAX_ORDER`）帧的块，
+//! 块的起始物理页号按块大小自然对齐，每一阶维护一条空闲块双向链表。
 //!
-//! - **bitmap**：每个 bit 表示一个物理帧（0=空闲，1=已分配）
-//! - **last_alloc_hint**：上次分配位置提示，利用局部性加速查找
+//! - **链表节点**：存放在空闲块首帧的内存中，不额外占用堆内存
+//! - **state**：每帧一个字节，记录"已分配"、"空闲块内部"或"空闲块首帧的阶数"，用于 O(1) 判断伙伴是否空闲
 //!
 //! 分配流程：
 //!
-//! 1. 单帧分配：从 last_alloc_hint 开始循环查找第一个空闲位
-//! 2. 连续帧分配：扫描位图查找连续空闲区域（利用 u64 边界优化）
-//! 3. 对齐分配：从对齐边界开始查找满足要求的连续空闲帧
+//! 1. 从不小于所需阶数的最小非空链表取一个块，逐级对半拆分，后一半挂回低一阶链表
+//! 2. 连续帧分配：取 `num` 向上取整到 2 的幂的块，末尾多余的帧立即释放
+//! 3. 对齐分配：块本身按阶数自然对齐，取 `max(num, align_pages)` 对应的阶即可
 //!
-//! 释放时直接清除对应 bit，O(1) 操作，无需维护回收栈。
+//! 释放时检查同阶伙伴（页号异或块大小）是否为空闲块首帧，是则摘下合并并继续向上一阶检查。
+//! 分配和释放都是 O(log n)。各阶空闲块数量由 [`get_free_blocks`] 给出。
+//!
+//! ## 内存区域（zone）
+//!
+//! 物理内存按地址划分为若干区域，每个区域有独立的伙伴分配器（见 [`ZoneType`]）：
+//!
+//! - **DMA32**：物理地址低于 4 GiB，供只能寻址 32 位的设备（如 legacy virtio）使用
+//! - **Normal**：其余内存
+//!
+//! 普通分配优先使用 Normal，不够时回退到 DMA32；DMA 分配（[`alloc_contig_frames_zone`]）只使用 DMA32。
+//! 每个区域有 min / low / high 三条水位线：先按 low 水位分配，失败后放宽到 min 水位，
+//! min 以下的帧留作保留。回退到更低区域时还要额外保留 `lowmem_reserve` 帧，
+//! 避免普通分配耗尽 DMA32。各区域的水位与统计由 [`get_zone_info`] 给出（即 `/proc/zoneinfo`、`/proc/buddyinfo`）。
+//!
+//! ## NUMA 节点
+//!
+//! 物理内存还可以按 NUMA 节点划分（由 [`MmConfig::memory_nodes`](crate::MmConfig::memory_nodes) 给出，
+//! 通常解析自设备树的 `numa-node-id`），每段节点内存各自划分区域、各有一套伙伴分配器。
+//! 默认策略先从当前 CPU 所在的节点（[`numa_node_id`]）分配，不够时按节点编号依次回退到其他节点；
+//! [`alloc_frame_on_node`] 可以指定首选节点并用 [`NodeMask`] 限制允许的节点。
+//! 未配置节点时整段物理内存属于节点 0。
 //!
 //! ## RAII：自动回收
 //!
@@ -24,10 +46,20 @@
 //!
 //! 这使得"分配后忘记释放"的错误更难发生，也便于在异常路径上保持资源正确回收。
 //!
+//! fork 时私有映射的物理帧不再复制，而是包装成 [`TrackedFrames::Shared`] 由父子地址空间共享，
+//! `Arc` 的强引用计数就是该页的引用计数（写时复制，见 [`MappingArea::clone_cow`](crate::memory_space::MappingArea::clone_cow)）。
+//!
+//! 内存不足时 [`alloc_frame`] 会先通过 [`swap::reclaim`](crate::swap::reclaim) 把匿名页换出到交换区，
+//! 再重试分配；换出的页由 [`TrackedFrames::Swapped`] 记录其交换槽位。换不出页时再经
+//! [`shrinker::shrink_slab`](crate::shrinker::shrink_slab) 让目录项等内核缓存释放一批对象。
+//!
 //! ## 对齐连续帧分配
 //!
-//! [`alloc_contig_frames_aligned`] 支持按"页数"对齐起始地址（例如按 2MB 对齐，
-//! 传入 `align_pages = 512`）。
+//! [`alloc_contig_frames_aligned`] 支持按"页数"对齐起始物理页号（例如按 2MB 对齐，
+//! 传入 `align_pages = 512`）。单次连续分配最多 `2^MAX_ORDER` 帧。
+//!
+//! 多于一帧的连续分配失败时，先经 [`compaction`](crate::compaction) 把用户页迁移到区域高端、
+//! 在低端拼出足够大的空闲块，再重试一次。
 //!
 //! # 模块组成
 //!
@@ -35,11 +67,24 @@
 //! - [`FrameRangeTracker`]：用于已分配帧范围的 **RAII** 封装器。
 //! - [`init_frame_allocator`]：初始化全局帧分配器。
 //! - [`alloc_frame`]：分配单个帧。
+//! - [`alloc_frame_on_node`]：在指定的 NUMA 节点上分配单个帧。
 //! - `alloc_frames`：分配多个（非连续）帧。
 //! - `alloc_contig_frames`：分配多个连续帧。
 //! - `alloc_contig_frames_aligned`：分配带对齐要求的多个连续帧。
+//! - [`alloc_contig_frames_zone`]：从指定内存区域分配多个连续帧（如设备 DMA）。
+//!
+//! ## 故障注入
+//!
+//! 在 `cfg(test)` 或启用 `fault-injection` feature 时，每次从伙伴系统取帧前会先询问
+//! `test_support::fault`（`FaultPoint::FrameAlloc`），以便测试覆盖分配失败路径。
+//! 注入的失败与真实的内存不足一样，会经过回收、规整与 OOM 处理。
 
 use crate::address::{ConvertablePaddr, Paddr, PageNum, Ppn, PpnRange, UsizeConvert};
+use crate::config::MemoryNode;
+use crate::page_cache::CachedPage;
+use crate::swap::SwapSlot;
+use crate::vmstat::VmEvent;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use lazy_static::lazy_static;
 use sync::SpinLock;
@@ -95,6 +140,14 @@ impl FrameRangeTracker {
         FrameRangeTracker { range }
     }
 
+    /// 接管已分配的连续帧 `range`，不清零（大页池缩小时用它把大页归还帧分配器）。
+    ///
+    /// # Safety
+    /// `range` 必须已经分配，且不由其它跟踪器管理
+    pub(crate) unsafe fn from_raw(range: PpnRange) -> Self {
+        FrameRangeTracker { range }
+    }
+
     /// 获取连续帧范围的起始物理页号 (Ppn)。
     pub fn start_ppn(&self) -> Ppn {
         self.range.start()
@@ -114,11 +167,22 @@ impl FrameRangeTracker {
     pub fn range(&self) -> &PpnRange {
         &self.range
     }
+
+    /// 拆分为逐帧管理的 [`FrameTracker`]，帧中的内容保持不变（用于拆分大页）。
+    pub fn into_frames(self) -> Vec<FrameTracker> {
+        let frames = self.range.iter().map(FrameTracker).collect();
+        // 所有权已转移给各 FrameTracker，不能再整体回收
+        core::mem::forget(self);
+        frames
+    }
 }
 
 impl Drop for FrameRangeTracker {
-    /// 自动回收连续物理页帧。
+    /// 自动回收连续物理页帧；大页池的大页放回池中。
     fn drop(&mut self) {
+        if crate::hugetlb::put_page(&self.range) {
+            return;
+        }
         dealloc_contig_frames(self);
     }
 }
@@ -133,9 +197,23 @@ impl Drop for FrameRangeTracker {
 pub enum TrackedFrames {
     /// 单个物理帧。
     Single(FrameTracker),
+    /// 多个地址空间写时复制共享的单个物理帧。
+    ///
+    /// 引用计数即共享该帧的映射数，最后一个引用释放时回收物理帧；
+    /// 只剩一个引用时写缺页可以直接取回独占，无需复制。
+    Shared(Arc<FrameTracker>),
+    /// 共享文件映射直接映射的页缓存页，数据与文件读写看到的是同一份（见 [`crate::page_cache`]）。
+    Cached(Arc<CachedPage>),
+    /// 已换出到交换区的单个页，不占用物理帧，页表中对应的是换出项（见 [`crate::swap`]）。
+    Swapped(SwapSlot),
+    /// 经 `madvise(MADV_FREE)` 标记、内容可以丢弃的单个物理帧。
+    ///
+    /// 页表项去掉写权限，再次写入时恢复为 [`TrackedFrames::Single`]；
+    /// 回收时直接释放，不写交换区。
+    LazyFree(FrameTracker),
     /// 多个不连续物理帧。
     Multiple(Vec<FrameTracker>),
-    /// 多个连续物理帧。
+    /// 多个连续物理帧，作为一个大页整体映射（以大页首页为键，见 [`crate::page_table::PageSize`]）。
     Contiguous(FrameRangeTracker),
 }
 
@@ -143,6 +221,20 @@ pub enum TrackedFrames {
 // 辅助函数
 // ============================================================================
 
+/// 故障注入点：测试配置了 `FaultPoint::FrameAlloc` 时模拟分配失败。
+#[cfg(any(test, feature = "fault-injection"))]
+#[inline]
+fn inject_alloc_failure() -> bool {
+    use test_support::fault::{FaultPoint, should_fail};
+    should_fail(FaultPoint::FrameAlloc)
+}
+
+#[cfg(not(any(test, feature = "fault-injection")))]
+#[inline(always)]
+fn inject_alloc_failure() -> bool {
+    false
+}
+
 /// 将指定的物理页帧清零。
 fn clear_frame(ppn: Ppn) {
     let page_size = crate::mm_config().page_size();
@@ -159,29 +251,53 @@ fn clear_frame(ppn: Ppn) {
 // ============================================================================
 
 lazy_static! {
-    /// 全局物理帧分配器，由自旋锁保护。
-    static ref FRAME_ALLOCATOR: SpinLock<FrameAllocator> = SpinLock::new(FrameAllocator::new());
+    /// 全局物理帧分配器（各 NUMA 节点的各内存区域），由自旋锁保护。
+    static ref FRAME_ALLOCATOR: SpinLock<Nodes> = SpinLock::new(Nodes::new());
+}
+
+/// 伙伴系统的最高阶：单次最多分配 `2^MAX_ORDER` 个连续帧（4 KiB 页时为 4 MiB）。
+pub const MAX_ORDER: usize = 10;
+
+/// 阶数的个数（`0..=MAX_ORDER`）。
+pub const NR_ORDERS: usize = MAX_ORDER + 1;
+
+/// 帧状态：已分配。
+const FRAME_ALLOCATED: u8 = 0xFE;
+/// 帧状态：空闲，位于某个更大的空闲块内部（不是块首）。
+///
+/// 其余取值 `k`（`0..=MAX_ORDER`）表示该帧是一个 `2^k` 帧空闲块的首帧。
+const FRAME_FREE_TAIL: u8 = 0xFF;
+
+/// 空闲链表的空指针。
+const NIL: usize = usize::MAX;
+
+/// 空闲块首帧中存放的双向链表节点（以帧下标相连）。
+#[repr(C)]
+struct FreeNode {
+    prev: usize,
+    next: usize,
 }
 
 /// 物理帧分配器。
-/// 采用位图策略跟踪每个物理帧的分配状态。
+/// 采用伙伴系统管理一个内存区域内的物理帧，每一阶维护一条空闲块链表。
 pub struct FrameAllocator {
     /// 物理帧的起始 Ppn。
     start: Ppn,
     /// 物理帧的结束 Ppn (不包含)。
     end: Ppn,
-    /// 位图数据（每个 bit 表示一个帧：0=空闲，1=已分配）。
-    /// 使用 Vec<u64> 存储，利用 64 位操作优化查找。
-    bitmap: Vec<u64>,
+    /// 每个帧的状态：[`FRAME_ALLOCATED`]、[`FRAME_FREE_TAIL`] 或空闲块首帧的阶数。
+    state: Vec<u8>,
+    /// 各阶空闲链表的表头（帧下标）。
+    free_heads: [usize; NR_ORDERS],
+    /// 各阶空闲块的数量。
+    free_blocks: [usize; NR_ORDERS],
     /// 总帧数。
     total_frames: usize,
     /// 已分配帧数（用于快速统计）。
     allocated_count: usize,
-    /// 上次分配的位置提示（用于加速单帧分配）。
-    last_alloc_hint: usize,
 }
 
-/// 位图帧分配器的实现
+/// 伙伴系统帧分配器的实现
 impl FrameAllocator {
     /// 创建一个新的帧分配器实例。
     pub fn new() -> Self {
@@ -189,95 +305,202 @@ impl FrameAllocator {
             // 使用 usize::MAX 作为初始值，表示未初始化状态
             start: Ppn::from_usize(usize::MAX),
             end: Ppn::from_usize(usize::MAX),
-            bitmap: Vec::new(), // 空 Vec，不分配内存
+            state: Vec::new(), // 空 Vec，不分配内存
+            free_heads: [NIL; NR_ORDERS],
+            free_blocks: [0; NR_ORDERS],
             total_frames: 0,
             allocated_count: 0,
-            last_alloc_hint: 0,
         }
     }
 
     /// 初始化帧分配器，设置可用的物理内存范围。
+    ///
+    /// 范围内的帧被切分成按物理页号自然对齐的最大块挂入空闲链表。
     pub fn init(&mut self, start: Ppn, end: Ppn) {
         self.start = start;
         self.end = end;
         self.total_frames = end.as_usize() - start.as_usize();
 
-        // 计算位图需要的 u64 数量
-        let bitmap_u64_count = (self.total_frames + 63) / 64;
-
-        // 分配位图（此时堆分配器已初始化）
-        self.bitmap = alloc::vec![0u64; bitmap_u64_count];
+        // 分配状态数组（此时堆分配器已初始化），先全部视为已分配再逐块释放
+        self.state = alloc::vec![FRAME_ALLOCATED; self.total_frames];
+        self.free_heads = [NIL; NR_ORDERS];
+        self.free_blocks = [0; NR_ORDERS];
+        self.allocated_count = self.total_frames;
 
-        self.allocated_count = 0;
-        self.last_alloc_hint = 0;
+        self.free_range(0, self.total_frames);
     }
 
-    /// 检查帧是否空闲
+    /// 帧下标 `idx` 处链表节点的指针
     #[inline]
-    fn is_free(&self, frame_idx: usize) -> bool {
-        let word_idx = frame_idx / 64;
-        let bit_idx = frame_idx % 64;
-        (self.bitmap[word_idx] & (1u64 << bit_idx)) == 0
+    fn node(&self, idx: usize) -> *mut FreeNode {
+        let mut va = (self.start + idx).start_addr().to_vaddr();
+        // Safety: 只计算指针不解引用；帧在分配器管理的范围内，直接映射区中有对应的虚拟地址
+        unsafe { va.as_mut_ptr::<FreeNode>() }
     }
 
-    /// 标记帧为已分配
-    #[inline]
-    fn mark_allocated(&mut self, frame_idx: usize) {
-        let word_idx = frame_idx / 64;
-        let bit_idx = frame_idx % 64;
-        self.bitmap[word_idx] |= 1u64 << bit_idx;
+    /// 把首帧为 `idx` 的 `order` 阶空闲块插入链表头
+    fn push_free(&mut self, idx: usize, order: usize) {
+        let head = self.free_heads[order];
+        // Safety: 空闲帧不被任何人使用，链表节点只由持锁的分配器读写
+        unsafe {
+            self.node(idx).write(FreeNode {
+                prev: NIL,
+                next: head,
+            });
+            if head != NIL {
+                (*self.node(head)).prev = idx;
+            }
+        }
+        self.free_heads[order] = idx;
+        self.free_blocks[order] += 1;
+        self.state[idx] = order as u8;
     }
 
-    /// 标记帧为空闲
-    #[inline]
-    fn mark_free(&mut self, frame_idx: usize) {
-        let word_idx = frame_idx / 64;
-        let bit_idx = frame_idx % 64;
-        self.bitmap[word_idx] &= !(1u64 << bit_idx);
+    /// 把首帧为 `idx` 的 `order` 阶空闲块从链表中摘下
+    fn remove_free(&mut self, idx: usize, order: usize) {
+        // Safety: 同 push_free
+        let FreeNode { prev, next } = unsafe { self.node(idx).read() };
+        unsafe {
+            if prev != NIL {
+                (*self.node(prev)).next = next;
+            } else {
+                self.free_heads[order] = next;
+            }
+            if next != NIL {
+                (*self.node(next)).prev = prev;
+            }
+        }
+        self.free_blocks[order] -= 1;
+        self.state[idx] = FRAME_FREE_TAIL;
     }
 
-    /// 分配一个物理帧。
-    /// 从 last_alloc_hint 开始循环查找第一个空闲位。
-    pub fn alloc_frame(&mut self) -> Option<FrameTracker> {
-        let bitmap_len = self.bitmap.len();
-        if bitmap_len == 0 {
-            return None;
+    /// 分配一个 `order` 阶块，返回首帧下标。
+    ///
+    /// 从不小于 `order` 的最小非空阶取块，逐级对半拆分，后一半挂回低一阶的链表。
+    fn alloc_block(&mut self, order: usize) -> Option<usize> {
+        let mut k = (order..NR_ORDERS).find(|&k| self.free_heads[k] != NIL)?;
+        let idx = self.free_heads[k];
+        self.remove_free(idx, k);
+        while k > order {
+            k -= 1;
+            self.push_free(idx + (1 << k), k);
         }
+        self.state[idx..idx + (1 << order)].fill(FRAME_ALLOCATED);
+        self.allocated_count += 1 << order;
+        Some(idx)
+    }
 
-        // 从上次分配位置开始查找（局部性优化）
-        let start_idx = self.last_alloc_hint;
-
-        // 循环查找：[hint, end) + [0, hint)
-        for offset in 0..bitmap_len {
-            let idx = (start_idx + offset) % bitmap_len;
-            let word = self.bitmap[idx];
+    /// 释放首帧为 `idx` 的 `order` 阶块，并与空闲的伙伴逐级合并。
+    fn free_block(&mut self, mut idx: usize, mut order: usize) {
+        for state in &mut self.state[idx..idx + (1 << order)] {
+            debug_assert!(
+                *state == FRAME_ALLOCATED,
+                "dealloc_frame: double free detected" // 检测到重复释放
+            );
+            *state = FRAME_FREE_TAIL;
+        }
+        self.allocated_count -= 1 << order;
 
-            // 快速跳过全满的 u64
-            if word == u64::MAX {
-                continue;
+        let base = self.start.as_usize();
+        while order < MAX_ORDER {
+            // 伙伴按物理页号计算，保证合并出的块物理地址自然对齐
+            let buddy_ppn = (base + idx) ^ (1 << order);
+            if buddy_ppn < base || buddy_ppn + (1 << order) > self.end.as_usize() {
+                break;
+            }
+            let buddy = buddy_ppn - base;
+            if self.state[buddy] != order as u8 {
+                break;
             }
+            self.remove_free(buddy, order);
+            idx = idx.min(buddy);
+            order += 1;
+        }
+        self.push_free(idx, order);
+        kcov!();
+    }
 
-            // 找到第一个空闲位（trailing_zeros 找最低位的 0）
-            let bit_pos = (!word).trailing_zeros() as usize;
-            if bit_pos < 64 {
-                let frame_idx = idx * 64 + bit_pos;
+    /// 释放从 `idx` 开始的 `len` 个帧，拆成按物理页号对齐的最大块逐块释放。
+    fn free_range(&mut self, mut idx: usize, mut len: usize) {
+        let base = self.start.as_usize();
+        while len > 0 {
+            let align = ((base + idx).trailing_zeros() as usize).min(MAX_ORDER);
+            let order = align.min(len.ilog2() as usize);
+            self.free_block(idx, order);
+            idx += 1 << order;
+            len -= 1 << order;
+        }
+    }
 
-                // 检查是否超出范围
-                if frame_idx >= self.total_frames {
-                    continue;
-                }
+    /// 分配 `num` 个连续帧，起始物理页号对齐到 `align_pages`，返回首帧下标。
+    ///
+    /// 取足够大的块后把末尾多余的帧立即还回去。
+    fn alloc_range(&mut self, num: usize, align_pages: usize) -> Option<usize> {
+        if num == 0 || num.max(align_pages) > 1 << MAX_ORDER {
+            return None;
+        }
+        let order = num.max(align_pages).next_power_of_two().trailing_zeros() as usize;
+        if order > MAX_ORDER {
+            return None;
+        }
+        let idx = self.alloc_block(order)?;
+        self.free_range(idx + num, (1 << order) - num);
+        Some(idx)
+    }
 
-                // 标记为已分配
-                self.mark_allocated(frame_idx);
-                self.allocated_count += 1;
-                self.last_alloc_hint = idx;
+    /// 帧下标 `idx` 是否已分配
+    fn is_allocated(&self, idx: usize) -> bool {
+        self.state[idx] == FRAME_ALLOCATED
+    }
 
-                let ppn = self.start + frame_idx;
-                return Some(FrameTracker::new(ppn));
+    /// 把空闲帧 `idx` 从所在的空闲块中单独取出并标记为已分配，帧已分配时返回 `false`。
+    ///
+    /// 包含它的空闲块逐级对半拆分，不含 `idx` 的一半挂回低一阶的链表。
+    fn take_free_frame(&mut self, idx: usize) -> bool {
+        if self.is_allocated(idx) {
+            return false;
+        }
+        // 包含 idx 的空闲块首帧按物理页号对齐，从低阶往上找第一个阶数吻合的首帧
+        let base = self.start.as_usize();
+        let Some((mut head, mut order)) = (0..NR_ORDERS).find_map(|k| {
+            let head_ppn = (base + idx) & !((1 << k) - 1);
+            let head = head_ppn.checked_sub(base)?;
+            (self.state[head] == k as u8).then_some((head, k))
+        }) else {
+            return false;
+        };
+        self.remove_free(head, order);
+        while order > 0 {
+            order -= 1;
+            let half = 1 << order;
+            if idx < head + half {
+                self.push_free(head + half, order);
+            } else {
+                self.push_free(head, order);
+                head += half;
             }
         }
+        self.state[idx] = FRAME_ALLOCATED;
+        self.allocated_count += 1;
+        kcov!();
+        true
+    }
 
-        None // 内存耗尽
+    /// 是否有不小于 `order` 阶的空闲块
+    fn has_free_block(&self, order: usize) -> bool {
+        self.free_blocks[order.min(MAX_ORDER)..]
+            .iter()
+            .any(|&n| n > 0)
+    }
+
+    /// 分配一个物理帧。
+    pub fn alloc_frame(&mut self) -> Option<FrameTracker> {
+        let Some(idx) = self.alloc_block(0) else {
+            kcov!();
+            return None; // 内存耗尽
+        };
+        kcov!();
+        Some(FrameTracker::new(self.start + idx))
     }
 
     /// 分配指定数量的物理帧（不保证连续）。
@@ -296,127 +519,26 @@ impl FrameAllocator {
     }
 
     /// 分配指定数量的**连续**物理帧。
+    ///
+    /// 最多 `2^MAX_ORDER` 帧，更大的请求返回 `None`。
     pub fn alloc_contig_frames(&mut self, num: usize) -> Option<FrameRangeTracker> {
-        if num == 0 || num > self.total_frames - self.allocated_count {
-            return None;
-        }
-
-        let mut consecutive = 0;
-        let mut start_frame = 0;
-
-        // 逐 u64 扫描查找连续空闲帧
-        for idx in 0..self.bitmap.len() {
-            let word = self.bitmap[idx];
-
-            // 快速跳过全满的 u64
-            if word == u64::MAX {
-                consecutive = 0;
-                continue;
-            }
-
-            // 如果整个 u64 都是空闲，快速累加
-            if word == 0 {
-                if consecutive == 0 {
-                    start_frame = idx * 64;
-                }
-                let frames_in_word = 64.min(self.total_frames - idx * 64);
-                consecutive += frames_in_word;
-
-                if consecutive >= num {
-                    // 找到足够的连续帧，标记为已分配
-                    for i in 0..num {
-                        self.mark_allocated(start_frame + i);
-                    }
-                    self.allocated_count += num;
-
-                    let start_ppn = self.start + start_frame;
-                    let range = PpnRange::from_start_len(start_ppn, num);
-                    return Some(FrameRangeTracker::new(range));
-                }
-                continue;
-            }
-
-            // 逐位检查
-            for bit in 0..64 {
-                let frame_idx = idx * 64 + bit;
-                if frame_idx >= self.total_frames {
-                    break;
-                }
-
-                if (word & (1u64 << bit)) == 0 {
-                    if consecutive == 0 {
-                        start_frame = frame_idx;
-                    }
-                    consecutive += 1;
-
-                    if consecutive == num {
-                        // 找到足够的连续帧，标记为已分配
-                        for i in 0..num {
-                            self.mark_allocated(start_frame + i);
-                        }
-                        self.allocated_count += num;
-
-                        let start_ppn = self.start + start_frame;
-                        let range = PpnRange::from_start_len(start_ppn, num);
-                        return Some(FrameRangeTracker::new(range));
-                    }
-                } else {
-                    consecutive = 0;
-                }
-            }
-        }
-
-        None
+        self.alloc_contig_frames_aligned(num, 1)
     }
 
-    /// 分配指定数量的**连续**物理帧，并确保起始地址对齐到 `align_pages` 页的边界。
+    /// 分配指定数量的**连续**物理帧，并确保起始物理页号对齐到 `align_pages` 页的边界。
     pub fn alloc_contig_frames_aligned(
         &mut self,
         num: usize,
         align_pages: usize,
     ) -> Option<FrameRangeTracker> {
-        if num == 0 {
-            return None;
-        }
-
         debug_assert!(
             align_pages.is_power_of_two(),
             "Alignment must be power of 2" // 对齐必须是 2 的幂
         );
 
-        // 从对齐边界开始查找
-        let mut frame_idx = 0;
-        while frame_idx < self.total_frames {
-            // 对齐到下一个边界
-            let aligned_idx = (frame_idx + align_pages - 1) & !(align_pages - 1);
-            if aligned_idx + num > self.total_frames {
-                break;
-            }
-
-            // 检查从 aligned_idx 开始的 num 个帧是否都空闲
-            let mut all_free = true;
-            for i in 0..num {
-                if !self.is_free(aligned_idx + i) {
-                    all_free = false;
-                    frame_idx = aligned_idx + i + 1;
-                    break;
-                }
-            }
-
-            if all_free {
-                // 分配这些帧
-                for i in 0..num {
-                    self.mark_allocated(aligned_idx + i);
-                }
-                self.allocated_count += num;
-
-                let start_ppn = self.start + aligned_idx;
-                let range = PpnRange::from_start_len(start_ppn, num);
-                return Some(FrameRangeTracker::new(range));
-            }
-        }
-
-        None
+        let idx = self.alloc_range(num, align_pages)?;
+        let range = PpnRange::from_start_len(self.start + idx, num);
+        Some(FrameRangeTracker::new(range))
     }
 
     /// 回收一个物理帧。
@@ -427,18 +549,8 @@ impl FrameAllocator {
             "dealloc_frame: frame out of range" // 回收帧超出范围
         );
 
-        let ppn = frame.ppn();
-        let frame_idx = ppn.as_usize() - self.start.as_usize();
-
-        // 检查帧是否已被分配
-        debug_assert!(
-            !self.is_free(frame_idx),
-            "dealloc_frame: double free detected" // 检测到重复释放
-        );
-
-        // 标记为空闲
-        self.mark_free(frame_idx);
-        self.allocated_count -= 1;
+        let frame_idx = frame.ppn().as_usize() - self.start.as_usize();
+        self.free_block(frame_idx, 0);
     }
 
     /// 回收一个连续的物理帧范围。
@@ -452,17 +564,7 @@ impl FrameAllocator {
         );
 
         let start_idx = start.as_usize() - self.start.as_usize();
-        let len = frame_range.len();
-
-        // 批量标记为空闲
-        for i in 0..len {
-            debug_assert!(
-                !self.is_free(start_idx + i),
-                "dealloc_contig_frames: double free detected" // 检测到重复释放
-            );
-            self.mark_free(start_idx + i);
-        }
-        self.allocated_count -= len;
+        self.free_range(start_idx, frame_range.len());
     }
 
     /// 获取总的物理帧数
@@ -480,6 +582,11 @@ impl FrameAllocator {
         self.total_frames - self.allocated_count
     }
 
+    /// 获取各阶空闲块的数量，第 `k` 项为 `2^k` 帧空闲块的个数
+    pub fn free_blocks(&self) -> [usize; NR_ORDERS] {
+        self.free_blocks
+    }
+
     /// 获取帧分配器的当前状态
     /// # 返回值
     /// - 总帧数
@@ -500,24 +607,576 @@ impl Default for FrameAllocator {
     }
 }
 
+// ============================================================================
+// 内存区域
+// ============================================================================
+
+/// 内存区域的个数。
+pub const NR_ZONES: usize = 2;
+
+/// DMA32 区域的上界（物理地址，不包含）。
+pub const ZONE_DMA32_LIMIT: usize = 1 << 32;
+
+/// 水位线 min 占区域帧数的比例（`1 / WATERMARK_MIN_RATIO`）。
+const WATERMARK_MIN_RATIO: usize = 256;
+
+/// 更高区域的请求回退到本区域时保留的帧数占该更高区域帧数的比例（同 Linux `lowmem_reserve_ratio`）。
+const LOWMEM_RESERVE_RATIO: usize = 256;
+
+/// 物理内存区域，按物理地址从低到高排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ZoneType {
+    /// 物理地址低于 [`ZONE_DMA32_LIMIT`] 的内存，供只能寻址 32 位的设备使用
+    Dma32 = 0,
+    /// 其余内存
+    Normal = 1,
+}
+
+impl ZoneType {
+    /// 所有区域，按物理地址从低到高排列
+    pub const ALL: [ZoneType; NR_ZONES] = [ZoneType::Dma32, ZoneType::Normal];
+
+    /// 区域名称（与 Linux `/proc/zoneinfo` 相同）
+    pub fn name(self) -> &'static str {
+        match self {
+            ZoneType::Dma32 => "DMA32",
+            ZoneType::Normal => "Normal",
+        }
+    }
+
+    /// 为本区域分配时依次尝试的区域：先本区域，再逐级回退到更低的区域
+    fn fallback(self) -> &'static [ZoneType] {
+        match self {
+            ZoneType::Dma32 => &[ZoneType::Dma32],
+            ZoneType::Normal => &[ZoneType::Normal, ZoneType::Dma32],
+        }
+    }
+}
+
+/// 区域水位线
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watermark {
+    /// 保留帧数，分配不会让空闲帧低于它
+    Min = 0,
+    /// 正常分配要求的空闲帧数
+    Low = 1,
+    /// 回收的目标空闲帧数
+    High = 2,
+}
+
+/// 单个内存区域：伙伴分配器、水位线与统计
+struct Zone {
+    buddy: FrameAllocator,
+    /// 按 [`Watermark`] 索引的水位线（帧数）
+    watermark: [usize; 3],
+    /// 更高区域的请求回退到本区域时额外保留的帧数
+    lowmem_reserve: usize,
+    /// 本区域满足的分配帧数
+    nr_alloc: usize,
+    /// 其中为更高区域的请求回退而来的帧数
+    nr_alloc_fallback: usize,
+    /// 以本区域为首选区域、最终失败的分配次数
+    nr_alloc_fail: usize,
+}
+
+impl Zone {
+    fn new() -> Self {
+        Zone {
+            buddy: FrameAllocator::new(),
+            watermark: [0; 3],
+            lowmem_reserve: 0,
+            nr_alloc: 0,
+            nr_alloc_fallback: 0,
+            nr_alloc_fail: 0,
+        }
+    }
+
+    /// 用 `[start, end)` 初始化本区域，并按区域大小计算水位线
+    fn init(&mut self, start: Ppn, end: Ppn) {
+        self.buddy.init(start, end);
+        let min = self.buddy.total_frames() / WATERMARK_MIN_RATIO;
+        self.watermark = [min, min + min / 4, min + min / 2];
+        self.lowmem_reserve = 0;
+        self.nr_alloc = 0;
+        self.nr_alloc_fallback = 0;
+        self.nr_alloc_fail = 0;
+    }
+
+    fn contains(&self, ppn: Ppn) -> bool {
+        ppn >= self.buddy.start && ppn < self.buddy.end
+    }
+
+    /// 分配 `num` 帧后空闲帧是否仍不低于 `mark` 水位（加上 `reserve`）
+    fn watermark_ok(&self, num: usize, mark: Watermark, reserve: usize) -> bool {
+        self.buddy.free_frames() >= num + self.watermark[mark as usize] + reserve
+    }
+}
+
+/// 内存区域的统计信息（用于 `/proc/zoneinfo`、`/proc/buddyinfo`）
+#[derive(Debug, Clone)]
+pub struct ZoneInfo {
+    /// 所属 NUMA 节点
+    pub node: usize,
+    /// 区域
+    pub zone: ZoneType,
+    /// 起始物理页号
+    pub start_pfn: usize,
+    /// 区域内的帧数
+    pub present: usize,
+    /// 空闲帧数
+    pub free: usize,
+    /// 按 [`Watermark`] 索引的水位线（帧数）
+    pub watermark: [usize; 3],
+    /// 更高区域的请求回退到本区域时额外保留的帧数
+    pub lowmem_reserve: usize,
+    /// 各阶空闲块的数量
+    pub free_blocks: [usize; NR_ORDERS],
+    /// 本区域满足的分配帧数
+    pub nr_alloc: usize,
+    /// 其中为更高区域的请求回退而来的帧数
+    pub nr_alloc_fallback: usize,
+    /// 以本区域为首选区域、最终失败的分配次数
+    pub nr_alloc_fail: usize,
+}
+
+/// 一段连续物理内存的所有内存区域，按 [`ZoneType`] 索引
+struct Zones {
+    /// 所属 NUMA 节点
+    node: usize,
+    zones: [Zone; NR_ZONES],
+}
+
+impl Zones {
+    fn new() -> Self {
+        Zones {
+            node: 0,
+            zones: [Zone::new(), Zone::new()],
+        }
+    }
+
+    /// 把 `[start, end)` 按 [`ZONE_DMA32_LIMIT`] 划分到各区域
+    fn init(&mut self, start: Ppn, end: Ppn) {
+        let limit = Ppn::from_addr_floor(Paddr::from_usize(ZONE_DMA32_LIMIT));
+        self.init_with_limit(start, end, limit);
+    }
+
+    /// 把 `[start, end)` 在 `limit` 处划分为 DMA32 与 Normal 区域
+    fn init_with_limit(&mut self, start: Ppn, end: Ppn, limit: Ppn) {
+        let split = limit.max(start).min(end);
+        self.zone_mut(ZoneType::Dma32).init(start, split);
+        self.zone_mut(ZoneType::Normal).init(split, end);
+        // 普通分配回退到 DMA32 时为 DMA 分配保留一部分帧
+        let normal = self.zone(ZoneType::Normal).buddy.total_frames();
+        self.zone_mut(ZoneType::Dma32).lowmem_reserve = normal / LOWMEM_RESERVE_RATIO;
+    }
+
+    fn zone(&self, zone: ZoneType) -> &Zone {
+        &self.zones[zone as usize]
+    }
+
+    fn zone_mut(&mut self, zone: ZoneType) -> &mut Zone {
+        &mut self.zones[zone as usize]
+    }
+
+    /// 起始物理页号
+    fn start(&self) -> Ppn {
+        self.zone(ZoneType::Dma32).buddy.start
+    }
+
+    fn contains(&self, ppn: Ppn) -> bool {
+        self.zones.iter().any(|z| z.contains(ppn))
+    }
+
+    /// 包含 `ppn` 的区域
+    fn zone_of(&mut self, ppn: Ppn) -> &mut Zone {
+        let zone = ZoneType::ALL
+            .into_iter()
+            .find(|&zone| self.zone(zone).contains(ppn))
+            .expect("frame does not belong to any zone"); // 帧不属于任何区域
+        self.zone_mut(zone)
+    }
+
+    /// 为 `zone` 分配 `num` 个连续帧，起始物理页号对齐到 `align_pages`。
+    ///
+    /// 先按 low 水位依次尝试 `zone` 及更低的区域，都失败后放宽到 min 水位。
+    /// 全局分配经 [`Nodes::alloc_range`] 跨节点进行，这里只用于单段内存的测试。
+    #[cfg(test)]
+    fn alloc_range(&mut self, num: usize, align_pages: usize, zone: ZoneType) -> Option<Ppn> {
+        for mark in [Watermark::Low, Watermark::Min] {
+            if let Some(ppn) = self.try_alloc_range(num, align_pages, zone, mark) {
+                return Some(ppn);
+            }
+        }
+        kcov!();
+        self.zone_mut(zone).nr_alloc_fail += 1;
+        None
+    }
+
+    /// 在 `mark` 水位下依次尝试 `zone` 及更低的区域，失败时不计入统计
+    fn try_alloc_range(
+        &mut self,
+        num: usize,
+        align_pages: usize,
+        zone: ZoneType,
+        mark: Watermark,
+    ) -> Option<Ppn> {
+        for &z in zone.fallback() {
+            let target = self.zone_mut(z);
+            let reserve = if z == zone { 0 } else { target.lowmem_reserve };
+            if !target.watermark_ok(num, mark, reserve) {
+                continue;
+            }
+            let Some(idx) = target.buddy.alloc_range(num, align_pages) else {
+                continue;
+            };
+            target.nr_alloc += num;
+            if z != zone {
+                target.nr_alloc_fallback += num;
+            }
+            return Some(target.buddy.start + idx);
+        }
+        None
+    }
+
+    fn dealloc_frame(&mut self, frame: &FrameTracker) {
+        self.zone_of(frame.ppn()).buddy.dealloc_frame(frame);
+    }
+
+    fn dealloc_contig_frames(&mut self, frame_range: &FrameRangeTracker) {
+        self.zone_of(frame_range.start_ppn())
+            .buddy
+            .dealloc_contig_frames(frame_range);
+    }
+
+    fn total_frames(&self) -> usize {
+        self.zones.iter().map(|z| z.buddy.total_frames()).sum()
+    }
+
+    fn allocated_frames(&self) -> usize {
+        self.zones.iter().map(|z| z.buddy.allocated_frames()).sum()
+    }
+
+    fn free_frames(&self) -> usize {
+        self.zones.iter().map(|z| z.buddy.free_frames()).sum()
+    }
+
+    fn free_blocks(&self) -> [usize; NR_ORDERS] {
+        let mut blocks = [0; NR_ORDERS];
+        for zone in &self.zones {
+            for (sum, count) in blocks.iter_mut().zip(zone.buddy.free_blocks()) {
+                *sum += count;
+            }
+        }
+        blocks
+    }
+
+    fn zone_info(&self, zone: ZoneType) -> ZoneInfo {
+        let z = self.zone(zone);
+        ZoneInfo {
+            node: self.node,
+            zone,
+            start_pfn: z.buddy.start.as_usize(),
+            present: z.buddy.total_frames(),
+            free: z.buddy.free_frames(),
+            watermark: z.watermark,
+            lowmem_reserve: z.lowmem_reserve,
+            free_blocks: z.buddy.free_blocks(),
+            nr_alloc: z.nr_alloc,
+            nr_alloc_fallback: z.nr_alloc_fallback,
+            nr_alloc_fail: z.nr_alloc_fail,
+        }
+    }
+}
+
+// ============================================================================
+// NUMA 节点
+// ============================================================================
+
+/// 支持的最大 NUMA 节点数（[`NodeMask`] 的位数）。
+pub const MAX_NUMNODES: usize = 64;
+
+/// NUMA 节点集合，第 `n` 位表示节点 `n`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeMask(pub u64);
+
+impl NodeMask {
+    /// 所有节点
+    pub const ALL: NodeMask = NodeMask(u64::MAX);
+    /// 空集合
+    pub const NONE: NodeMask = NodeMask(0);
+
+    /// 只含 `node` 的集合
+    pub const fn single(node: usize) -> Self {
+        assert!(node < MAX_NUMNODES);
+        NodeMask(1 << node)
+    }
+
+    /// 是否包含 `node`
+    pub const fn contains(self, node: usize) -> bool {
+        node < MAX_NUMNODES && self.0 & (1 << node) != 0
+    }
+
+    /// 加入 `node`
+    pub fn insert(&mut self, node: usize) {
+        *self = NodeMask(self.0 | Self::single(node).0);
+    }
+
+    /// 是否为空集合
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// 按编号从小到大遍历集合中的节点
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..MAX_NUMNODES).filter(move |&node| self.contains(node))
+    }
+}
+
+/// 所有 NUMA 节点的物理内存，每段连续内存一组区域，按（节点，起始地址）排序
+struct Nodes {
+    ranges: Vec<Zones>,
+}
+
+impl Nodes {
+    fn new() -> Self {
+        Nodes { ranges: Vec::new() }
+    }
+
+    /// 按各节点的物理内存区间初始化，空区间被忽略
+    fn init(&mut self, nodes: &[MemoryNode]) {
+        self.ranges.clear();
+        for n in nodes {
+            assert!(n.node < MAX_NUMNODES, "NUMA node id out of range"); // 节点编号超出范围
+            let start = Ppn::from_addr_ceil(Paddr::from_usize(n.start));
+            let end = Ppn::from_addr_floor(Paddr::from_usize(n.end));
+            if start >= end {
+                continue;
+            }
+            let mut zones = Zones::new();
+            zones.node = n.node;
+            zones.init(start, end);
+            self.ranges.push(zones);
+        }
+        self.ranges.sort_by_key(|r| (r.node, r.start()));
+    }
+
+    /// 有内存的节点
+    fn online(&self) -> NodeMask {
+        let mut mask = NodeMask::NONE;
+        for r in &self.ranges {
+            mask.insert(r.node);
+        }
+        mask
+    }
+
+    /// 包含 `ppn` 的内存段
+    fn range_of(&mut self, ppn: Ppn) -> &mut Zones {
+        self.ranges
+            .iter_mut()
+            .find(|r| r.contains(ppn))
+            .expect("frame does not belong to any node") // 帧不属于任何节点
+    }
+
+    /// 管理 `ppn` 的伙伴分配器
+    fn buddy_of(&mut self, ppn: Ppn) -> Option<&mut FrameAllocator> {
+        self.ranges
+            .iter_mut()
+            .flat_map(|r| r.zones.iter_mut())
+            .find(|z| z.contains(ppn))
+            .map(|z| &mut z.buddy)
+    }
+
+    /// 各内存区域的物理页号范围，空区域除外
+    fn zone_spans(&self) -> Vec<PpnRange> {
+        self.ranges
+            .iter()
+            .flat_map(|r| r.zones.iter())
+            .filter(|z| z.buddy.total_frames() != 0)
+            .map(|z| PpnRange::new(z.buddy.start, z.buddy.end))
+            .collect()
+    }
+
+    /// `ppn` 所属的节点
+    fn node_of(&self, ppn: Ppn) -> Option<usize> {
+        self.ranges.iter().find(|r| r.contains(ppn)).map(|r| r.node)
+    }
+
+    /// 为 `zone` 分配 `num` 个连续帧，首选节点 `node`，只使用 `mask` 中的节点。
+    ///
+    /// 每条水位线下先尝试首选节点的各段内存，再按节点编号依次尝试其他节点，
+    /// 保证在动用任何节点的保留帧之前先用完所有节点 low 水位以上的帧。
+    fn alloc_range(
+        &mut self,
+        num: usize,
+        align_pages: usize,
+        zone: ZoneType,
+        node: usize,
+        mask: NodeMask,
+    ) -> Option<Ppn> {
+        if inject_alloc_failure() {
+            kcov!();
+            return None;
+        }
+        for mark in [Watermark::Low, Watermark::Min] {
+            for local in [true, false] {
+                for r in self.ranges.iter_mut() {
+                    if (r.node == node) != local || !mask.contains(r.node) {
+                        continue;
+                    }
+                    if let Some(ppn) = r.try_alloc_range(num, align_pages, zone, mark) {
+                        return Some(ppn);
+                    }
+                }
+            }
+        }
+        kcov!();
+        // 失败计入首选节点（没有时计入第一个节点）的首选区域
+        let target = match self.ranges.iter().position(|r| r.node == node) {
+            Some(i) => Some(i),
+            None if self.ranges.is_empty() => None,
+            None => Some(0),
+        };
+        if let Some(i) = target {
+            self.ranges[i].zone_mut(zone).nr_alloc_fail += 1;
+        }
+        None
+    }
+
+    /// 在首选节点 `node` 上为 `zone` 分配一个物理帧。
+    fn alloc_frame(&mut self, zone: ZoneType, node: usize, mask: NodeMask) -> Option<FrameTracker> {
+        self.alloc_range(1, 1, zone, node, mask)
+            .map(FrameTracker::new)
+    }
+
+    /// 在首选节点 `node` 上为 `zone` 分配指定数量的物理帧（不保证连续）。
+    fn alloc_frames(
+        &mut self,
+        num: usize,
+        zone: ZoneType,
+        node: usize,
+    ) -> Option<Vec<FrameTracker>> {
+        let mut frames = Vec::with_capacity(num);
+        for _ in 0..num {
+            // 分配失败时已分配的帧随 frames 一起 drop 回收
+            frames.push(self.alloc_frame(zone, node, NodeMask::ALL)?);
+        }
+        Some(frames)
+    }
+
+    /// 在首选节点 `node` 上为 `zone` 分配指定数量的**连续**物理帧，起始物理页号对齐到 `align_pages` 页的边界。
+    fn alloc_contig_frames(
+        &mut self,
+        num: usize,
+        align_pages: usize,
+        zone: ZoneType,
+        node: usize,
+    ) -> Option<FrameRangeTracker> {
+        debug_assert!(
+            align_pages.is_power_of_two(),
+            "Alignment must be power of 2" // 对齐必须是 2 的幂
+        );
+        let start = self.alloc_range(num, align_pages, zone, node, NodeMask::ALL)?;
+        Some(FrameRangeTracker::new(PpnRange::from_start_len(start, num)))
+    }
+
+    fn dealloc_frame(&mut self, frame: &FrameTracker) {
+        self.range_of(frame.ppn()).dealloc_frame(frame);
+        crate::vmstat::count_event(VmEvent::PgFree);
+    }
+
+    fn dealloc_contig_frames(&mut self, frame_range: &FrameRangeTracker) {
+        self.range_of(frame_range.start_ppn())
+            .dealloc_contig_frames(frame_range);
+        crate::vmstat::count_events(VmEvent::PgFree, frame_range.len());
+    }
+
+    fn total_frames(&self) -> usize {
+        self.ranges.iter().map(Zones::total_frames).sum()
+    }
+
+    fn allocated_frames(&self) -> usize {
+        self.ranges.iter().map(Zones::allocated_frames).sum()
+    }
+
+    fn free_frames(&self) -> usize {
+        self.ranges.iter().map(Zones::free_frames).sum()
+    }
+
+    fn get_stats(&self) -> (usize, usize, usize) {
+        (
+            self.total_frames(),
+            self.allocated_frames(),
+            self.free_frames(),
+        )
+    }
+
+    fn free_blocks(&self) -> [usize; NR_ORDERS] {
+        let mut blocks = [0; NR_ORDERS];
+        for r in &self.ranges {
+            for (sum, count) in blocks.iter_mut().zip(r.free_blocks()) {
+                *sum += count;
+            }
+        }
+        blocks
+    }
+
+    fn zone_info(&self) -> Vec<ZoneInfo> {
+        self.ranges
+            .iter()
+            .flat_map(|r| ZoneType::ALL.into_iter().map(|zone| r.zone_info(zone)))
+            .collect()
+    }
+}
+
 // ============================================================================
 // 公共 API
 // ============================================================================
 
 /// 使用可用的物理内存范围初始化全局帧分配器。
 ///
+/// 范围按 [`MmConfig::memory_nodes`](crate::MmConfig::memory_nodes) 划分到各 NUMA 节点，
+/// 不在任何节点区间内的部分（内存空洞）不会被使用；未配置节点时整段范围属于节点 0。
+///
 /// # 参数
 ///
 /// * `start_addr` - 可用物理内存的起始地址
 /// * `end_addr` - 可用物理内存的结束地址
 pub fn init_frame_allocator(start_addr: usize, end_addr: usize) {
-    // 将起始地址向上取整到页号
-    let start_ppn = Ppn::from_addr_ceil(Paddr::from_usize(start_addr));
-    // 将结束地址向下取整到页号
-    let end_ppn = Ppn::from_addr_floor(Paddr::from_usize(end_addr));
+    let mut nodes: Vec<MemoryNode> = crate::mm_config()
+        .memory_nodes()
+        .iter()
+        .map(|n| MemoryNode {
+            node: n.node,
+            start: n.start.max(start_addr),
+            end: n.end.min(end_addr),
+        })
+        .filter(|n| n.start < n.end)
+        .collect();
+    if nodes.is_empty() {
+        nodes.push(MemoryNode {
+            node: 0,
+            start: start_addr,
+            end: end_addr,
+        });
+    }
 
     let mut allocator = FRAME_ALLOCATOR.lock();
-    allocator.init(start_ppn, end_ppn);
+    allocator.init(&nodes);
+}
+
+/// 当前 CPU 所在的 NUMA 节点，默认分配策略的首选节点
+pub fn numa_node_id() -> usize {
+    crate::mm_config().current_node()
+}
+
+/// 有物理内存的 NUMA 节点
+pub fn online_nodes() -> NodeMask {
+    FRAME_ALLOCATOR.lock().online()
+}
+
+/// 物理页号 `ppn` 所属的 NUMA 节点，不受帧分配器管理时返回 `None`
+pub fn ppn_to_node(ppn: Ppn) -> Option<usize> {
+    FRAME_ALLOCATOR.lock().node_of(ppn)
 }
 
 /// 分配一个物理帧。
@@ -525,8 +1184,38 @@ pub fn init_frame_allocator(start_addr: usize, end_addr: usize) {
 /// # 返回
 ///
 /// 如果分配成功，返回 `Some(FrameTracker)`；否则返回 `None`。
+///
+/// 没有空闲帧时先尝试把匿名页换出到交换区，换出成功后重试；无页可换时交给 OOM killer
+/// 杀死一个进程释放内存后再重试，仍然无法释放内存时才返回 `None`。
+///
+/// 优先从当前 CPU 所在的节点分配，不够时回退到其他节点。
 pub fn alloc_frame() -> Option<FrameTracker> {
-    FRAME_ALLOCATOR.lock().alloc_frame()
+    alloc_frame_on_node(numa_node_id(), NodeMask::ALL)
+}
+
+/// 在 NUMA 节点 `node` 上分配一个物理帧，不够时回退到 `mask` 中的其他节点。
+///
+/// `mask` 不包含 `node` 时只从 `mask` 中的节点分配。内存不足时的回收与 OOM 处理同 [`alloc_frame`]。
+///
+/// # 返回
+///
+/// 如果分配成功，返回 `Some(FrameTracker)`；否则返回 `None`。
+pub fn alloc_frame_on_node(node: usize, mask: NodeMask) -> Option<FrameTracker> {
+    loop {
+        if let Some(frame) = FRAME_ALLOCATOR
+            .lock()
+            .alloc_frame(ZoneType::Normal, node, mask)
+        {
+            return Some(frame);
+        }
+        // 换出的帧可能被其它 CPU 抢先分配，只要还能换出或收缩缓存就继续重试
+        if crate::swap::reclaim(1) == 0
+            && crate::shrinker::shrink_slab(crate::shrinker::SHRINK_BATCH) == 0
+            && !crate::oom::out_of_memory()
+        {
+            return None;
+        }
+    }
 }
 
 /// 分配多个物理帧（不保证连续）。
@@ -539,7 +1228,10 @@ pub fn alloc_frame() -> Option<FrameTracker> {
 ///
 /// 如果分配成功，返回 `Some(Vec<FrameTracker>)`；否则返回 `None`。
 pub fn alloc_frames(num: usize) -> Option<Vec<FrameTracker>> {
-    FRAME_ALLOCATOR.lock().alloc_frames(num)
+    let node = numa_node_id();
+    FRAME_ALLOCATOR
+        .lock()
+        .alloc_frames(num, ZoneType::Normal, node)
 }
 
 /// 分配指定数量的**连续**物理帧。
@@ -552,7 +1244,18 @@ pub fn alloc_frames(num: usize) -> Option<Vec<FrameTracker>> {
 ///
 /// 如果分配成功，返回 `Some(FrameRangeTracker)`；否则返回 `None`。
 pub fn alloc_contig_frames(num: usize) -> Option<FrameRangeTracker> {
-    FRAME_ALLOCATOR.lock().alloc_contig_frames(num)
+    alloc_contig_or_compact(num, 1, ZoneType::Normal)
+}
+
+/// 从指定区域（不够时回退到更低的区域）分配指定数量的**连续**物理帧。
+///
+/// 用于有寻址限制的设备：传入 [`ZoneType::Dma32`] 时保证整段物理地址低于 [`ZONE_DMA32_LIMIT`]。
+///
+/// # 返回
+///
+/// 如果分配成功，返回 `Some(FrameRangeTracker)`；否则返回 `None`。
+pub fn alloc_contig_frames_zone(num: usize, zone: ZoneType) -> Option<FrameRangeTracker> {
+    alloc_contig_or_compact(num, 1, zone)
 }
 
 /// 分配指定数量的**连续**物理帧，并确保起始地址对齐。
@@ -566,9 +1269,67 @@ pub fn alloc_contig_frames(num: usize) -> Option<FrameRangeTracker> {
 ///
 /// 如果分配成功，返回 `Some(FrameRangeTracker)`；否则返回 `None`。
 pub fn alloc_contig_frames_aligned(num: usize, align_pages: usize) -> Option<FrameRangeTracker> {
-    FRAME_ALLOCATOR
-        .lock()
-        .alloc_contig_frames_aligned(num, align_pages)
+    alloc_contig_or_compact(num, align_pages, ZoneType::Normal)
+}
+
+/// 在当前节点上为 `zone` 分配连续帧；多于一帧的分配失败时先规整内存再重试一次
+fn alloc_contig_or_compact(
+    num: usize,
+    align_pages: usize,
+    zone: ZoneType,
+) -> Option<FrameRangeTracker> {
+    let node = numa_node_id();
+    let alloc = || {
+        FRAME_ALLOCATOR
+            .lock()
+            .alloc_contig_frames(num, align_pages, zone, node)
+    };
+    if let Some(range) = alloc() {
+        return Some(range);
+    }
+    let order = num.max(align_pages).next_power_of_two().trailing_zeros() as usize;
+    if num <= 1 || order > MAX_ORDER || !crate::compaction::compact_for_order(order) {
+        return None;
+    }
+    alloc()
+}
+
+/// 各内存区域的物理页号范围（内存规整逐区域扫描）
+pub(crate) fn zone_spans() -> Vec<PpnRange> {
+    FRAME_ALLOCATOR.lock().zone_spans()
+}
+
+/// 物理页 `ppn` 是否已分配，不受帧分配器管理时返回 `false`
+pub(crate) fn frame_is_allocated(ppn: Ppn) -> bool {
+    let mut allocator = FRAME_ALLOCATOR.lock();
+    allocator
+        .buddy_of(ppn)
+        .is_some_and(|buddy| buddy.is_allocated(ppn.as_usize() - buddy.start.as_usize()))
+}
+
+/// 包含 `ppn` 的内存区域是否有不小于 `order` 阶的空闲块
+pub(crate) fn zone_has_free_block(ppn: Ppn, order: usize) -> bool {
+    let mut allocator = FRAME_ALLOCATOR.lock();
+    allocator
+        .buddy_of(ppn)
+        .is_some_and(|buddy| buddy.has_free_block(order))
+}
+
+/// 从 `[lower, cursor)` 中由高到低取出一个空闲帧（内存规整的迁移目标）
+///
+/// `cursor` 移到取出的帧处，下次从它下面继续找；`lower` 与 `cursor` 必须属于同一区域。
+pub(crate) fn take_free_frame_below(cursor: &mut Ppn, lower: Ppn) -> Option<FrameTracker> {
+    let mut allocator = FRAME_ALLOCATOR.lock();
+    let buddy = allocator.buddy_of(lower)?;
+    while *cursor > lower {
+        *cursor = Ppn::from_usize(cursor.as_usize() - 1);
+        if buddy.take_free_frame(cursor.as_usize() - buddy.start.as_usize()) {
+            let ppn = *cursor;
+            drop(allocator);
+            return Some(FrameTracker::new(ppn));
+        }
+    }
+    None
 }
 
 /// 回收一个物理帧。此函数由 FrameTracker 的 Drop 实现调用。
@@ -605,6 +1366,17 @@ pub fn get_free_frames() -> usize {
     FRAME_ALLOCATOR.lock().free_frames()
 }
 
+/// 获取所有区域合计的各阶空闲块数量，第 `k` 项为 `2^k` 帧空闲块的个数
+pub fn get_free_blocks() -> [usize; NR_ORDERS] {
+    FRAME_ALLOCATOR.lock().free_blocks()
+}
+
+/// 获取各内存区域的水位与统计信息，按节点排列，每段内存内按 [`ZoneType::ALL`] 的顺序排列
+/// （`/proc/zoneinfo`、`/proc/buddyinfo`）
+pub fn get_zone_info() -> Vec<ZoneInfo> {
+    FRAME_ALLOCATOR.lock().zone_info()
+}
+
 /// 获取帧分配器的当前状态
 ///
 /// # 返回值
@@ -614,3 +1386,235 @@ pub fn get_free_frames() -> usize {
 pub fn get_stats() -> (usize, usize, usize) {
     FRAME_ALLOCATOR.lock().get_stats()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use test_support::fault::{FaultPoint, FaultPolicy, FaultScope};
+
+    /// 泄漏一段宿主内存，返回其中起始物理页号对齐到 `align_pages` 的 `pages` 个帧
+    /// （Mock 下物理地址与虚拟地址恒等）
+    fn host_frames(pages: usize, align_pages: usize) -> (Ppn, Ppn) {
+        #[repr(C, align(4096))]
+        struct Page([u8; 4096]);
+        let total = pages + align_pages - 1;
+        let mut v = alloc::vec::Vec::with_capacity(total);
+        v.resize_with(total, || Page([0; 4096]));
+        let buf: &'static mut [Page] = Box::leak(v.into_boxed_slice());
+        let start = Ppn::from_addr_floor(Paddr::from_usize(buf.as_ptr() as usize));
+        let start = Ppn::from_usize(start.as_usize().next_multiple_of(align_pages));
+        (start, start + pages)
+    }
+
+    /// 在 64 个按 64 帧对齐的宿主帧上创建独立的分配器（不经过全局分配器和 FrameTracker）
+    fn buddy_64() -> FrameAllocator {
+        let (start, end) = host_frames(64, 64);
+        let mut allocator = FrameAllocator::new();
+        allocator.init(start, end);
+        allocator
+    }
+
+    /// 把 `[ppn, ppn + num)` 还给独立的分配器（这些帧不属于全局分配器，不能经 Drop 回收）
+    fn release(nodes: &mut Nodes, ppn: Ppn, num: usize) {
+        let range = FrameRangeTracker::new(PpnRange::from_start_len(ppn, num));
+        nodes.dealloc_contig_frames(&range);
+        core::mem::forget(range);
+    }
+
+    #[test]
+    fn test_injected_frame_alloc_failure() {
+        let scope = FaultScope::new();
+        let (start, end) = host_frames(8, 1);
+        let mut nodes = Nodes::new();
+        nodes.init(&[MemoryNode {
+            node: 0,
+            start: start.start_addr().as_usize(),
+            end: end.start_addr().as_usize(),
+        }]);
+        let alloc =
+            |nodes: &mut Nodes, num| nodes.alloc_range(num, 1, ZoneType::Normal, 0, NodeMask::ALL);
+
+        scope.inject(FaultPoint::FrameAlloc, FaultPolicy::Nth(2));
+        let first = alloc(&mut nodes, 1).unwrap();
+        assert!(alloc(&mut nodes, 1).is_none());
+        let pair = alloc(&mut nodes, 2).unwrap();
+        assert_eq!(scope.stats(FaultPoint::FrameAlloc).injected, 1);
+
+        scope.inject(FaultPoint::FrameAlloc, FaultPolicy::Always);
+        assert!(alloc(&mut nodes, 1).is_none());
+        assert!(alloc(&mut nodes, 2).is_none());
+
+        // 注入失败不应改变分配器的账目，也不计入分配失败统计
+        assert_eq!(nodes.allocated_frames(), 3);
+        assert!(nodes.zone_info().iter().all(|z| z.nr_alloc_fail == 0));
+        scope.clear(FaultPoint::FrameAlloc);
+        release(&mut nodes, first, 1);
+        release(&mut nodes, pair, 2);
+        assert_eq!(nodes.allocated_frames(), 0);
+        assert!(alloc(&mut nodes, 1).is_some());
+    }
+
+    #[test]
+    fn test_zone_fallback_and_watermarks() {
+        // 1024 帧，前 512 帧作为 DMA32：min = low = 2，high = 3，DMA32 为回退保留 2 帧
+        let (start, end) = host_frames(1024, 512);
+        let mut zones = Zones::new();
+        zones.init_with_limit(start, end, start + 512);
+        let dma = zones.zone_info(ZoneType::Dma32);
+        let normal = zones.zone_info(ZoneType::Normal);
+        assert_eq!((dma.present, normal.present), (512, 512));
+        assert_eq!(normal.start_pfn, start.as_usize() + 512);
+        assert_eq!(normal.watermark, [2, 2, 3]);
+        assert_eq!(dma.lowmem_reserve, 2);
+
+        // DMA32 分配只来自低地址区域
+        let ppn = zones.alloc_range(4, 1, ZoneType::Dma32).unwrap();
+        assert!(ppn + 4 <= start + 512);
+
+        // 普通分配先用 Normal 到水位线，再回退到 DMA32 并为其保留 lowmem_reserve
+        let mut count = 0;
+        while zones.alloc_range(1, 1, ZoneType::Normal).is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 510 + 504);
+        let dma = zones.zone_info(ZoneType::Dma32);
+        assert_eq!(dma.free, 4);
+        assert_eq!(dma.nr_alloc_fallback, 504);
+        assert_eq!(zones.zone_info(ZoneType::Normal).nr_alloc_fail, 1);
+
+        // 保留的部分仍可用于 DMA32 分配，直到 min 水位
+        assert!(zones.alloc_range(2, 1, ZoneType::Dma32).is_some());
+        assert!(zones.alloc_range(1, 1, ZoneType::Dma32).is_none());
+        assert_eq!(zones.free_frames(), 4);
+    }
+
+    #[test]
+    fn test_numa_node_preference_and_fallback() {
+        let (s0, e0) = host_frames(256, 1);
+        let (s1, e1) = host_frames(256, 1);
+        let node = |node, start: Ppn, end: Ppn| MemoryNode {
+            node,
+            start: start.start_addr().as_usize(),
+            end: end.start_addr().as_usize(),
+        };
+        let mut nodes = Nodes::new();
+        nodes.init(&[node(1, s1, e1), node(0, s0, e0)]);
+        assert_eq!(nodes.online(), NodeMask(0b11));
+        let total = nodes.free_frames();
+
+        // 优先从首选节点分配
+        let ppn = nodes
+            .alloc_range(1, 1, ZoneType::Normal, 1, NodeMask::ALL)
+            .unwrap();
+        assert_eq!(nodes.node_of(ppn), Some(1));
+
+        // 掩码只含节点 0 时不会动用节点 1
+        let mut count = 0;
+        while let Some(ppn) = nodes.alloc_range(1, 1, ZoneType::Normal, 1, NodeMask::single(0)) {
+            assert_eq!(nodes.node_of(ppn), Some(0));
+            count += 1;
+        }
+        assert!(count > 0);
+        assert_eq!(nodes.free_frames(), total - 1 - count);
+
+        // 节点 0 耗尽后回退到节点 1
+        let ppn = nodes
+            .alloc_range(1, 1, ZoneType::Normal, 0, NodeMask::ALL)
+            .unwrap();
+        assert_eq!(nodes.node_of(ppn), Some(1));
+
+        // 失败计入首选节点
+        let info = nodes.zone_info();
+        assert_eq!(
+            info.iter().map(|z| z.node).collect::<Vec<_>>(),
+            [0, 0, 1, 1]
+        );
+        let fails = |node| {
+            info.iter()
+                .filter(|z| z.node == node)
+                .map(|z| z.nr_alloc_fail)
+                .sum::<usize>()
+        };
+        assert_eq!((fails(0), fails(1)), (0, 1));
+    }
+
+    #[test]
+    fn test_buddy_split_and_coalesce() {
+        let mut allocator = buddy_64();
+        assert_eq!(allocator.free_blocks()[6], 1);
+
+        // 拆分 64 帧块得到一帧，每个较低阶各留下一个空闲块
+        let idx = allocator.alloc_block(0).unwrap();
+        assert_eq!(allocator.free_frames(), 63);
+        assert_eq!(allocator.free_blocks()[..6], [1; 6]);
+
+        // 释放后与伙伴逐级合并回一个 64 帧块
+        allocator.free_block(idx, 0);
+        assert_eq!(allocator.free_frames(), 64);
+        assert_eq!(allocator.free_blocks()[..6], [0; 6]);
+        assert_eq!(allocator.free_blocks()[6], 1);
+    }
+
+    #[test]
+    fn test_buddy_take_free_frame() {
+        let mut allocator = buddy_64();
+
+        // 从 64 帧块中间取出一帧，其余 63 帧拆成 0..=5 阶各一个空闲块
+        assert!(allocator.take_free_frame(37));
+        assert!(allocator.is_allocated(37));
+        assert!(!allocator.take_free_frame(37));
+        assert_eq!(allocator.free_frames(), 63);
+        assert_eq!(allocator.free_blocks()[..6], [1; 6]);
+        assert!(!allocator.has_free_block(6));
+        assert!(allocator.has_free_block(5));
+
+        // 再取一帧只拆包含它的那个块
+        assert!(allocator.take_free_frame(3));
+        assert_eq!(allocator.free_frames(), 62);
+
+        allocator.free_block(3, 0);
+        allocator.free_block(37, 0);
+        assert_eq!(allocator.free_frames(), 64);
+        assert_eq!(allocator.free_blocks()[6], 1);
+    }
+
+    #[test]
+    fn test_buddy_contig_alloc_trims_and_aligns() {
+        let mut allocator = buddy_64();
+        let base = allocator.start.as_usize();
+
+        // 3 帧请求取 4 帧块，多余的一帧立即还回
+        let three = allocator.alloc_range(3, 1).unwrap();
+        assert_eq!(allocator.allocated_frames(), 3);
+
+        let aligned = allocator.alloc_range(1, 16).unwrap();
+        assert_eq!((base + aligned) % 16, 0);
+        assert_eq!(allocator.allocated_frames(), 4);
+        assert!(allocator.alloc_range(65, 1).is_none());
+
+        allocator.free_range(three, 3);
+        allocator.free_range(aligned, 1);
+        assert_eq!(allocator.free_blocks()[6], 1);
+    }
+
+    #[test]
+    fn test_buddy_fragmentation() {
+        let mut allocator = buddy_64();
+        let frames: Vec<usize> = (0..64).map(|_| allocator.alloc_block(0).unwrap()).collect();
+        assert!(allocator.alloc_block(0).is_none());
+
+        // 每隔一帧释放：空闲帧都不相邻，无法满足两帧的连续请求
+        for &idx in frames.iter().step_by(2) {
+            allocator.free_block(idx, 0);
+        }
+        assert_eq!(allocator.free_blocks()[0], 32);
+        assert!(allocator.alloc_range(2, 1).is_none());
+
+        for &idx in frames.iter().skip(1).step_by(2) {
+            allocator.free_block(idx, 0);
+        }
+        assert_eq!(allocator.free_blocks()[0], 0);
+        assert_eq!(allocator.free_blocks()[6], 1);
+    }
+}
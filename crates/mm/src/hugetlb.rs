@@ -0,0 +1,187 @@
+//! 预留大页池（hugetlb）
+//!
+//! 启动时按命令行的 `hugepages=N` 从帧分配器取出 N 个 2M 对齐的连续帧作为大页池，
+//! 运行中可以经 `/proc/sys/vm/nr_hugepages` 调整（见 [`set_nr_hugepages`]）。
+//! `mmap(MAP_HUGETLB)` 的区域只从池中取页并以 2M 大页映射，池空时映射失败，不回退到 4K 页。
+//!
+//! 池中的页被释放（[`FrameRangeTracker`] 被丢弃）时回到池中而不归还帧分配器，
+//! 只有缩小池时才归还。池中的页不换出、不迁移，所在区域也不会拆分为 4K 页。
+//! 池的用量经 `/proc/meminfo` 的 `HugePages_*` 行导出（见 [`stats`]）。
+
+use alloc::collections::btree_set::BTreeSet;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use sync::SpinLock;
+
+use crate::address::{Ppn, PpnRange};
+use crate::frame_allocator::{FrameRangeTracker, alloc_contig_frames_aligned};
+use crate::mm_config;
+use crate::page_table::PageSize;
+
+/// 池中的大页数（含已映射的），为 0 时释放连续帧不必查询池
+static NR_PAGES: AtomicUsize = AtomicUsize::new(0);
+/// 防止并发调整池的大小
+static RESIZING: AtomicBool = AtomicBool::new(false);
+
+static POOL: SpinLock<HugePool> = SpinLock::new(HugePool::new());
+
+/// 一个大页包含的页数
+pub fn huge_page_pages() -> usize {
+    PageSize::Size2M.pages()
+}
+
+/// 池中的大页数
+pub fn nr_hugepages() -> usize {
+    NR_PAGES.load(Ordering::Relaxed)
+}
+
+/// 池中空闲的大页数
+pub fn free_hugepages() -> usize {
+    POOL.lock().free.len()
+}
+
+/// 把池调整为 `target` 个大页，返回调整后的大页数
+///
+/// 扩大时连续帧不足则停在能取得的数量；缩小时只释放空闲的大页，已映射的大页留在池中。
+/// 另一个调整正在进行时什么都不做。
+pub fn set_nr_hugepages(target: usize) -> usize {
+    if RESIZING.swap(true, Ordering::Acquire) {
+        return nr_hugepages();
+    }
+    let pages = huge_page_pages();
+    while nr_hugepages() < target {
+        let Some(range) = alloc_contig_frames_aligned(pages, pages) else {
+            break;
+        };
+        let head = range.start_ppn();
+        // 帧的所有权交给池，缩小池时再归还帧分配器
+        core::mem::forget(range);
+        POOL.lock().add(head);
+        NR_PAGES.fetch_add(1, Ordering::Relaxed);
+    }
+    while nr_hugepages() > target {
+        let Some(head) = POOL.lock().shrink() else {
+            break;
+        };
+        NR_PAGES.fetch_sub(1, Ordering::Relaxed);
+        // SAFETY: 该大页已移出池，此后只由这个跟踪器管理
+        drop(unsafe { FrameRangeTracker::from_raw(PpnRange::from_start_len(head, pages)) });
+    }
+    RESIZING.store(false, Ordering::Release);
+    nr_hugepages()
+}
+
+/// 从池中取出一个内容清零的大页，池空时返回 `None`
+pub fn alloc_huge_page() -> Option<FrameRangeTracker> {
+    let head = POOL.lock().take()?;
+    Some(FrameRangeTracker::new(PpnRange::from_start_len(
+        head,
+        huge_page_pages(),
+    )))
+}
+
+/// 被释放的连续帧 `range` 是池中的大页时放回池中
+///
+/// # 返回值
+/// 已放回池中、调用者不应再把它归还帧分配器时返回 `true`
+pub(crate) fn put_page(range: &PpnRange) -> bool {
+    if nr_hugepages() == 0 || range.len() != huge_page_pages() {
+        return false;
+    }
+    POOL.lock().put(range.start())
+}
+
+/// 大页池统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HugeTlbStats {
+    /// 池中的大页数
+    pub total: usize,
+    /// 其中空闲的大页数
+    pub free: usize,
+    /// 大页的字节数
+    pub page_size: usize,
+}
+
+/// 读取当前的大页池统计
+pub fn stats() -> HugeTlbStats {
+    let pool = POOL.lock();
+    HugeTlbStats {
+        total: pool.pages.len(),
+        free: pool.free.len(),
+        page_size: huge_page_pages() * mm_config().page_size(),
+    }
+}
+
+/// 大页池，大页以首页的物理页号表示
+struct HugePool {
+    /// 属于池的大页
+    pages: BTreeSet<Ppn>,
+    /// 其中空闲的大页
+    free: Vec<Ppn>,
+}
+
+impl HugePool {
+    const fn new() -> Self {
+        Self {
+            pages: BTreeSet::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// 把新取得的大页加入池中
+    fn add(&mut self, head: Ppn) {
+        self.pages.insert(head);
+        self.free.push(head);
+    }
+
+    /// 取出一个空闲大页
+    fn take(&mut self) -> Option<Ppn> {
+        self.free.pop()
+    }
+
+    /// 放回 `head` 处的大页，它不属于池时返回 `false`
+    fn put(&mut self, head: Ppn) -> bool {
+        if !self.pages.contains(&head) {
+            return false;
+        }
+        self.free.push(head);
+        true
+    }
+
+    /// 把一个空闲大页移出池
+    fn shrink(&mut self) -> Option<Ppn> {
+        let head = self.free.pop()?;
+        self.pages.remove(&head);
+        Some(head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::UsizeConvert;
+
+    #[test]
+    fn test_huge_pool_put_and_shrink() {
+        let mut pool = HugePool::new();
+        let (a, b) = (Ppn::from_usize(0x200), Ppn::from_usize(0x400));
+        pool.add(a);
+        pool.add(b);
+
+        let taken = pool.take().unwrap();
+        assert_eq!(pool.free.len(), 1);
+
+        // 只有池中的大页可以放回
+        assert!(!pool.put(Ppn::from_usize(0x600)));
+        assert!(pool.put(taken));
+        assert_eq!(pool.free.len(), 2);
+
+        // 缩小只移出空闲的大页，已取出的大页留在池中
+        let in_use = pool.take().unwrap();
+        assert!(pool.shrink().is_some());
+        assert!(pool.shrink().is_none());
+        assert_eq!(pool.pages.len(), 1);
+        assert!(pool.put(in_use));
+    }
+}
@@ -0,0 +1,280 @@
+//! 相同页合并（KSM，kernel samepage merging）
+//!
+//! 经 `madvise(MADV_MERGEABLE)` 标记的区域由 os crate 的 ksmd 线程周期性扫描，
+//! 内容相同的私有页合并为一个去掉写权限的共享帧（[`TrackedFrames::Shared`]），
+//! 之后任一方写入时按写时复制分开。
+//!
+//! 与 Linux 相同，合并用到两棵树（这里是按内容哈希索引的表）：
+//!
+//! - **稳定表**：已合并的只读帧。扫描到的页与其中某帧内容相同时直接映射到该帧；
+//! - **不稳定表**：本轮扫描中内容稳定的候选页。两轮扫描之间哈希不变的页才成为候选，
+//!   频繁写入的页不参与合并；每轮扫描结束时清空。
+//!
+//! 扫描一次只持有一个地址空间的锁：页与不稳定表中的候选页哈希相同时，
+//! 先把它本身提升为稳定表中的只读帧，候选页在它所属的地址空间下一次被扫描时合并进来。
+//! 哈希只用于查找，合并前总是逐字节比较内容。
+//!
+//! 合并由 [`MappingArea::ksm_scan_page`](crate::memory_space::MappingArea::ksm_scan_page) 完成，
+//! 统计与参数经 `/sys/kernel/mm/ksm` 导出（见 [`stats`]）。
+
+use alloc::collections::btree_map::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use sync::SpinLock;
+
+use crate::address::{PageNum, Ppn, UsizeConvert, Vpn};
+use crate::arch_ops::arch_ops;
+use crate::frame_allocator::FrameTracker;
+use crate::mm_config;
+
+/// 每批扫描页数的默认值
+const DEFAULT_PAGES_TO_SCAN: usize = 100;
+/// 两批扫描之间休眠毫秒数的默认值
+const DEFAULT_SLEEP_MILLISECS: usize = 20;
+
+/// 是否运行 ksmd（`/sys/kernel/mm/ksm/run`）
+static RUN: AtomicBool = AtomicBool::new(false);
+/// 每批扫描的页数（`/sys/kernel/mm/ksm/pages_to_scan`）
+static PAGES_TO_SCAN: AtomicUsize = AtomicUsize::new(DEFAULT_PAGES_TO_SCAN);
+/// 两批扫描之间休眠的毫秒数（`/sys/kernel/mm/ksm/sleep_millisecs`）
+static SLEEP_MILLISECS: AtomicUsize = AtomicUsize::new(DEFAULT_SLEEP_MILLISECS);
+/// 完整扫描所有可合并区域的轮数
+static FULL_SCANS: AtomicUsize = AtomicUsize::new(0);
+
+static KSM: SpinLock<KsmState> = SpinLock::new(KsmState::new());
+
+/// ksmd 是否在运行
+pub fn run() -> bool {
+    RUN.load(Ordering::Relaxed)
+}
+
+/// 启动或停止 ksmd
+pub fn set_run(run: bool) {
+    RUN.store(run, Ordering::Relaxed);
+}
+
+/// 每批扫描的页数
+pub fn pages_to_scan() -> usize {
+    PAGES_TO_SCAN.load(Ordering::Relaxed)
+}
+
+/// 设置每批扫描的页数
+pub fn set_pages_to_scan(pages: usize) {
+    PAGES_TO_SCAN.store(pages, Ordering::Relaxed);
+}
+
+/// 两批扫描之间休眠的毫秒数
+pub fn sleep_millisecs() -> usize {
+    SLEEP_MILLISECS.load(Ordering::Relaxed)
+}
+
+/// 设置两批扫描之间休眠的毫秒数
+pub fn set_sleep_millisecs(ms: usize) {
+    SLEEP_MILLISECS.store(ms, Ordering::Relaxed);
+}
+
+/// 一轮完整扫描结束：清空不稳定表，丢弃本轮没有扫描到的页的哈希与已释放的稳定帧
+pub fn end_full_scan() {
+    KSM.lock().end_pass();
+    FULL_SCANS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// `frame` 是否是 KSM 合并得到的帧
+pub fn is_ksm_frame(frame: &Arc<FrameTracker>) -> bool {
+    let ptr = Arc::as_ptr(frame);
+    KSM.lock()
+        .stable
+        .values()
+        .flatten()
+        .any(|node| node.as_ptr() == ptr)
+}
+
+/// KSM 统计（页数）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KsmStats {
+    /// 被多处映射的合并帧数
+    pub pages_shared: usize,
+    /// 映射到合并帧、因而省下的页数
+    pub pages_sharing: usize,
+    /// 不稳定表中等待合并的候选页数
+    pub pages_unshared: usize,
+    /// 完整扫描的轮数
+    pub full_scans: usize,
+}
+
+/// 读取当前的 KSM 统计
+///
+/// 提升后还没有其它页合并进来（只有一个映射）的帧不计入。
+pub fn stats() -> KsmStats {
+    let ksm = KSM.lock();
+    let mut stats = KsmStats {
+        pages_unshared: ksm.unstable.len(),
+        full_scans: FULL_SCANS.load(Ordering::Relaxed),
+        ..KsmStats::default()
+    };
+    for node in ksm.stable.values().flatten() {
+        let count = node.strong_count();
+        if count >= 2 {
+            stats.pages_shared += 1;
+            stats.pages_sharing += count - 1;
+        }
+    }
+    stats
+}
+
+/// 扫描一页的结论
+pub(crate) enum KsmMatch {
+    /// 不合并
+    None,
+    /// 与稳定表中的这个帧内容相同，应合并到它
+    Stable(Arc<FrameTracker>),
+    /// 与不稳定表中的候选页哈希相同，应把本页提升为稳定帧
+    Unstable,
+}
+
+/// 查找 `owner` 地址空间 `vpn` 处内容为 `data` 的页可以合并到哪里
+pub(crate) fn lookup(owner: Ppn, vpn: Vpn, data: &[u8]) -> KsmMatch {
+    let hash = checksum(data);
+    let mut ksm = KSM.lock();
+    let stable = ksm.stable.get(&hash).into_iter().flatten();
+    if let Some(frame) = stable
+        .filter_map(Weak::upgrade)
+        .find(|frame| frame_content(frame) == data)
+    {
+        return KsmMatch::Stable(frame);
+    }
+    if ksm.note_candidate(owner, vpn, hash) {
+        KsmMatch::Unstable
+    } else {
+        KsmMatch::None
+    }
+}
+
+/// 把内容为 `data` 的只读帧加入稳定表
+pub(crate) fn insert_stable(data: &[u8], frame: &Arc<FrameTracker>) {
+    KSM.lock()
+        .stable
+        .entry(checksum(data))
+        .or_default()
+        .push(Arc::downgrade(frame));
+}
+
+/// KSM 的全局状态
+struct KsmState {
+    /// 稳定表：内容哈希到已合并的只读帧
+    stable: BTreeMap<u64, Vec<Weak<FrameTracker>>>,
+    /// 不稳定表：内容哈希到本轮扫描中的候选页
+    unstable: BTreeMap<u64, (Ppn, Vpn)>,
+    /// 各页最近一次被扫描时的内容哈希与扫描轮次
+    checksums: BTreeMap<(Ppn, Vpn), (u64, usize)>,
+    /// 当前扫描轮次
+    pass: usize,
+}
+
+impl KsmState {
+    const fn new() -> Self {
+        Self {
+            stable: BTreeMap::new(),
+            unstable: BTreeMap::new(),
+            checksums: BTreeMap::new(),
+            pass: 0,
+        }
+    }
+
+    /// 记录 `owner` 地址空间 `vpn` 处的页本轮的哈希 `hash`
+    ///
+    /// # 返回值
+    /// 内容自上一轮未变、且不稳定表中已有另一个哈希相同的候选页（随即移出）时返回 `true`
+    fn note_candidate(&mut self, owner: Ppn, vpn: Vpn, hash: u64) -> bool {
+        let prev = self.checksums.insert((owner, vpn), (hash, self.pass));
+        if prev.is_none_or(|(prev, _)| prev != hash) {
+            return false;
+        }
+        match self.unstable.get(&hash) {
+            Some(&candidate) if candidate != (owner, vpn) => {
+                self.unstable.remove(&hash);
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.unstable.insert(hash, (owner, vpn));
+                false
+            }
+        }
+    }
+
+    fn end_pass(&mut self) {
+        self.unstable.clear();
+        let pass = self.pass;
+        self.checksums.retain(|_, &mut (_, seen)| seen == pass);
+        self.stable.retain(|_, nodes| {
+            nodes.retain(|node| node.strong_count() > 0);
+            !nodes.is_empty()
+        });
+        self.pass += 1;
+    }
+}
+
+/// 页内容的哈希（FNV-1a，按 8 字节一组计算）
+fn checksum(data: &[u8]) -> u64 {
+    const PRIME: u64 = 0x100_0000_01b3;
+    data.chunks(8).fold(0xcbf2_9ce4_8422_2325, |hash, chunk| {
+        let mut word = [0u8; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        (hash ^ u64::from_le_bytes(word)).wrapping_mul(PRIME)
+    })
+}
+
+/// 只读帧 `frame` 的内容
+fn frame_content(frame: &FrameTracker) -> &[u8] {
+    // SAFETY: 稳定表中的帧在所有映射中都没有写权限，持有引用期间内容不变
+    unsafe {
+        core::slice::from_raw_parts(
+            arch_ops().paddr_to_vaddr(frame.ppn().start_addr().as_usize()) as *const u8,
+            mm_config().page_size(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ksm_candidate_needs_stable_content() {
+        let mut ksm = KsmState::new();
+        let owner = Ppn::from_usize(0x100);
+        let (a, b) = (Vpn::from_usize(0x10), Vpn::from_usize(0x11));
+
+        // 第一轮只记录哈希
+        assert!(!ksm.note_candidate(owner, a, 1));
+        assert!(!ksm.note_candidate(owner, b, 1));
+        ksm.end_pass();
+
+        // 第二轮内容未变：a 成为候选，b 与之相同，应被提升
+        assert!(!ksm.note_candidate(owner, a, 1));
+        assert!(ksm.note_candidate(owner, b, 1));
+        assert!(ksm.unstable.is_empty());
+
+        // 内容改变的页重新开始计
+        assert!(!ksm.note_candidate(owner, a, 2));
+        assert!(!ksm.note_candidate(owner, a, 2));
+        assert!(!ksm.note_candidate(owner, a, 2));
+        ksm.end_pass();
+
+        // 上一轮没有扫描到的页的哈希被丢弃
+        ksm.end_pass();
+        assert!(ksm.checksums.is_empty());
+    }
+
+    #[test]
+    fn test_ksm_checksum() {
+        let zero = [0u8; 4096];
+        let mut one = [0u8; 4096];
+        assert_eq!(checksum(&zero), checksum(&[0u8; 4096]));
+        one[4095] = 1;
+        assert_ne!(checksum(&zero), checksum(&one));
+    }
+}
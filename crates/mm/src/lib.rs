@@ -5,7 +5,7 @@
 //! # 架构解耦
 //!
 //! 通过 trait 抽象与架构特定组件解耦：
-//! - [`ArchMmOps`]: 地址转换、TLB 操作
+//! - [`ArchMmOps`]: 地址转换、DMA 地址、TLB 操作
 //! - [`MmConfig`]: 内存布局常量
 //!
 //! 使用前必须调用 [`register_arch_ops`] 和 [`register_config`] 注册实现。
@@ -20,33 +20,66 @@
 //! 3. `frame_allocator::init_frame_allocator(start, end)`：初始化物理帧分配器
 //!
 //! 随后即可构建页表与地址空间（[`page_table`] / [`memory_space`]）。
+//! 需要交换区时再调用 [`swap::register_swap_ops`] 和 [`swap::swapon`]。
+//! 经 [`shrinker::register_shrinker`] 登记的内核缓存在换出之后仍然分配失败时被要求释放对象。
+//! 调用 [`oom::register_oom_ops`] 后，回收失败的帧分配会先杀死一个进程再重试。
+//! 调用 [`compaction::register_migrate_ops`] 后，连续帧分配失败时会先迁移用户页规整内存再重试。
+//! `mmap(MAP_HUGETLB)` 从 [`hugetlb`] 的预留大页池取 2M 页。
+//! 经 `madvise(MADV_MERGEABLE)` 标记的区域由 os crate 的扫描线程按 [`ksm`] 合并相同的页。
+//! 调用 [`vmalloc::register_vmalloc_ops`] 后可以用 [`vmalloc::vmalloc`] 分配物理上不连续的大块内核内存。
+//! 内存统计（`/proc/meminfo`、`/proc/vmstat`）由 [`vmstat`] 汇总。
 
 #![no_std]
 #![feature(allocator_api)]
 
 extern crate alloc;
 
+/// 覆盖率计数点：启用 `coverage` feature 时展开为 `test_support::cov_point!()`，否则为空
+macro_rules! kcov {
+    () => {
+        #[cfg(feature = "coverage")]
+        test_support::cov_point!();
+    };
+}
+
 mod arch_ops;
 mod config;
 mod file;
 
 pub mod address;
+pub mod compaction;
+pub mod dma;
 pub mod frame_allocator;
+pub mod hugetlb;
+pub mod ksm;
 pub mod memory_space;
+pub mod oom;
+pub mod page_cache;
 pub mod page_table;
+pub mod rmap;
+pub mod shrinker;
+pub mod swap;
+pub mod vmalloc;
+pub mod vmstat;
+pub mod wx;
 
 pub use arch_ops::{
     ArchMmOps, TlbBatchContextTrait, TlbBatchContextWrapper, arch_ops, register_arch_ops,
 };
-pub use config::{MmConfig, mm_config, register_config};
+pub use config::{MemoryNode, MmConfig, mm_config, register_config};
+pub use dma::{DmaBlock, DmaPool, DmaRegion, dma_alloc_coherent};
 pub use file::{MmFile, MmInode};
 
 // Re-export 常用类型
 pub use address::{AlignOps, Paddr, PageNum, Ppn, PpnRange, UsizeConvert, Vaddr, Vpn, VpnRange};
 pub use frame_allocator::{
-    FrameRangeTracker, FrameTracker, TrackedFrames, alloc_contig_frames, alloc_frame, alloc_frames,
+    FrameRangeTracker, FrameTracker, NodeMask, TrackedFrames, alloc_contig_frames, alloc_frame,
+    alloc_frame_on_node, alloc_frames, numa_node_id,
 };
 pub use memory_space::{AreaType, MapType, MappingArea, MemorySpace, MmapFile};
+pub use page_cache::{CachedPage, PageCache, PageCacheRegistry};
 pub use page_table::{
     PageSize, PageTableEntry, PageTableInner, PagingError, PagingResult, UniversalPTEFlag,
 };
+pub use swap::{ReclaimResult, SwapDevice, SwapError, SwapOps, SwapSlot};
+pub use vmalloc::{VmallocOps, vfree, vmalloc};
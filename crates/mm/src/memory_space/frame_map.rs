@@ -0,0 +1,158 @@
+//! 映射区域的跟踪帧表
+//!
+//! 在虚拟页到 [`TrackedFrames`] 的映射之上自动维护反向映射（见 [`crate::rmap`]）：
+//! 插入时登记帧中的每个物理页，移除、替换或整个表被丢弃时注销。
+//! 物理页的第一个映射建立与最后一个映射解除时更新已映射页的统计（见 [`crate::vmstat`]）。
+
+use alloc::collections::btree_map::{self, BTreeMap};
+use core::ops::RangeBounds;
+
+use crate::address::{Ppn, UsizeConvert, Vpn};
+use crate::frame_allocator::TrackedFrames;
+use crate::rmap::{rmap_add, rmap_remove};
+use crate::vmstat::{self, NrItem};
+
+/// 映射区域中虚拟页到跟踪帧的表
+#[derive(Debug, Default)]
+pub(super) struct FrameMap {
+    frames: BTreeMap<Vpn, TrackedFrames>,
+    /// 所属地址空间（根页表的物理页号），第一次插入时记录
+    owner: Option<Ppn>,
+}
+
+impl FrameMap {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在 `owner` 地址空间的 `vpn` 处记录 `tracked`，返回被替换的旧帧
+    pub(super) fn insert(
+        &mut self,
+        owner: Ppn,
+        vpn: Vpn,
+        tracked: TrackedFrames,
+    ) -> Option<TrackedFrames> {
+        debug_assert!(
+            self.owner.is_none_or(|o| o == owner),
+            "FrameMap: frames of one area must belong to one address space" // 同一区域的帧必须属于同一地址空间
+        );
+        self.owner = Some(owner);
+        let old = self.frames.insert(vpn, tracked);
+        if let Some(old) = &old {
+            unmap_pages(owner, vpn, old);
+        }
+        let tracked = &self.frames[&vpn];
+        let item = mapped_item(tracked);
+        for_each_ppn(vpn, tracked, |ppn, vpn| {
+            if rmap_add(ppn, owner, vpn) {
+                vmstat::inc_state(item);
+            }
+        });
+        old
+    }
+
+    /// 移除 `vpn` 处的帧
+    pub(super) fn remove(&mut self, vpn: &Vpn) -> Option<TrackedFrames> {
+        let old = self.frames.remove(vpn)?;
+        if let Some(owner) = self.owner {
+            unmap_pages(owner, *vpn, &old);
+        }
+        Some(old)
+    }
+
+    pub(super) fn get(&self, vpn: &Vpn) -> Option<&TrackedFrames> {
+        self.frames.get(vpn)
+    }
+
+    pub(super) fn contains_key(&self, vpn: &Vpn) -> bool {
+        self.frames.contains_key(vpn)
+    }
+
+    pub(super) fn keys(&self) -> btree_map::Keys<'_, Vpn, TrackedFrames> {
+        self.frames.keys()
+    }
+
+    pub(super) fn values(&self) -> btree_map::Values<'_, Vpn, TrackedFrames> {
+        self.frames.values()
+    }
+
+    /// 可变遍历；调用者只能在保持物理页不变的前提下改变帧的跟踪方式（如转为共享）
+    pub(super) fn iter_mut(&mut self) -> btree_map::IterMut<'_, Vpn, TrackedFrames> {
+        self.frames.iter_mut()
+    }
+
+    pub(super) fn range<R: RangeBounds<Vpn>>(
+        &self,
+        range: R,
+    ) -> btree_map::Range<'_, Vpn, TrackedFrames> {
+        self.frames.range(range)
+    }
+
+    /// 对表中每个物理页及其映射到的虚拟页调用 `f`
+    pub(super) fn for_each_page(&self, mut f: impl FnMut(Ppn, Vpn)) {
+        for (vpn, tracked) in &self.frames {
+            for_each_ppn(*vpn, tracked, &mut f);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a FrameMap {
+    type Item = (&'a Vpn, &'a TrackedFrames);
+    type IntoIter = btree_map::Iter<'a, Vpn, TrackedFrames>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
+
+impl Drop for FrameMap {
+    fn drop(&mut self) {
+        let Some(owner) = self.owner else {
+            return;
+        };
+        for (vpn, tracked) in &self.frames {
+            unmap_pages(owner, *vpn, tracked);
+        }
+    }
+}
+
+/// 映射 `tracked` 中的页计入的统计项：页缓存页计为文件页，其余计为匿名页
+fn mapped_item(tracked: &TrackedFrames) -> NrItem {
+    match tracked {
+        TrackedFrames::Cached(_) => NrItem::FileMapped,
+        _ => NrItem::AnonPages,
+    }
+}
+
+/// 注销 `owner` 地址空间在 `vpn` 处对 `tracked` 中各页的映射
+fn unmap_pages(owner: Ppn, vpn: Vpn, tracked: &TrackedFrames) {
+    let item = mapped_item(tracked);
+    for_each_ppn(vpn, tracked, |ppn, vpn| {
+        if rmap_remove(ppn, owner, vpn) {
+            vmstat::dec_state(item);
+        }
+    });
+}
+
+/// 对 `tracked` 中的每个物理页及其映射到的虚拟页调用 `f`（以 `vpn` 为键）
+///
+/// 换出的页不占用物理页；连续帧依次映射到从 `vpn` 开始的各页，
+/// 多个不连续帧都映射在 `vpn` 处。
+fn for_each_ppn(vpn: Vpn, tracked: &TrackedFrames, mut f: impl FnMut(Ppn, Vpn)) {
+    match tracked {
+        TrackedFrames::Single(frame) | TrackedFrames::LazyFree(frame) => f(frame.ppn(), vpn),
+        TrackedFrames::Shared(frame) => f(frame.ppn(), vpn),
+        TrackedFrames::Cached(page) => f(page.ppn(), vpn),
+        TrackedFrames::Swapped(_) => {}
+        TrackedFrames::Multiple(frames) => frames.iter().for_each(|frame| f(frame.ppn(), vpn)),
+        TrackedFrames::Contiguous(range) => {
+            let start = range.start_ppn().as_usize();
+            for i in 0..range.len() {
+                f(
+                    Ppn::from_usize(start + i),
+                    Vpn::from_usize(vpn.as_usize() + i),
+                );
+            }
+        }
+    }
+}
@@ -1,12 +1,19 @@
-use alloc::collections::btree_map::BTreeMap;
+use alloc::sync::Arc;
 use core::cmp::min;
 
 use crate::address::{Paddr, PageNum, Ppn, UsizeConvert, Vpn, VpnRange};
 use crate::arch_ops::{TlbBatchContextWrapper, arch_ops};
-use crate::frame_allocator::{TrackedFrames, alloc_frame};
+use crate::frame_allocator::{
+    FrameTracker, TrackedFrames, alloc_contig_frames_aligned, alloc_frame,
+};
+use crate::hugetlb;
+use crate::ksm;
 use crate::memory_space::MmapFile;
+use crate::memory_space::frame_map::FrameMap;
 use crate::mm_config;
 use crate::page_table::{self, PageSize, PageTableEntry, PageTableInner, UniversalPTEFlag};
+use crate::rmap;
+use crate::swap::{self, ReclaimResult};
 use uapi::mm::MapFlags;
 
 /// 映射策略类型
@@ -22,6 +29,11 @@ pub enum MapType {
     /// - mmap(PROT_NONE) 需要"成功占位"但不应该映射可访问页表项
     /// - mprotect(PROT_NONE) 会把原有页表映射解除并转为 Reserved
     Reserved,
+    /// 固定物理页映射（区域首页映射到给定的物理页，其余页依次递增）
+    ///
+    /// 物理页由内核持有（如 vDSO 代码页与数据页），区域不分配也不释放帧，
+    /// 多个地址空间可以共享同一组物理页。
+    Fixed(Ppn),
 }
 
 /// 内存区域的类型
@@ -55,6 +67,10 @@ pub enum AreaType {
     UserHeap,
     /// 用户 mmap 匿名映射区域
     UserMmap,
+    /// 用户 vDSO 数据页（内核维护的时间数据，只读）
+    UserVvar,
+    /// 用户 vDSO 代码页
+    UserVdso,
 }
 
 /// 内存空间中的一个内存映射区域
@@ -69,9 +85,35 @@ pub struct MappingArea {
     /// 此映射区域的权限（使用 UniversalPTEFlag 以提高性能）
     permission: UniversalPTEFlag,
     /// 用于帧映射区域的跟踪帧
-    frames: BTreeMap<Vpn, TrackedFrames>,
+    ///
+    /// 2M 大页的连续帧以 [`TrackedFrames::Contiguous`] 记录在大页首页下；
+    /// 大页被部分解除映射或修改权限时拆分为 4K 页，连续帧随之改为逐页记录。
+    frames: FrameMap,
     /// 文件映射信息（如果是文件映射）
     file: Option<MmapFile>,
+    /// 是否经 `madvise(MADV_MERGEABLE)` 标记，由 KSM 扫描合并相同的页（见 [`crate::ksm`]）
+    mergeable: bool,
+    /// 是否是 `mmap(MAP_HUGETLB)` 的区域：只从大页池取 2M 页，不拆分为 4K 页（见 [`crate::hugetlb`]）
+    hugetlb: bool,
+}
+
+/// 映射区域的内存占用（字节）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AreaUsage {
+    /// 常驻内存
+    pub rss: usize,
+    /// 按映射数均摊后的常驻内存（proportional set size）
+    pub pss: usize,
+    /// 被多处映射的干净页
+    pub shared_clean: usize,
+    /// 被多处映射的脏页
+    pub shared_dirty: usize,
+    /// 只被本区域映射的干净页
+    pub private_clean: usize,
+    /// 只被本区域映射的脏页
+    pub private_dirty: usize,
+    /// 已换出到交换区的页
+    pub swap: usize,
 }
 
 impl MappingArea {
@@ -107,7 +149,11 @@ impl MappingArea {
                 .frames
                 .values()
                 .map(|t| match t {
-                    TrackedFrames::Single(_) => 1,
+                    TrackedFrames::Single(_)
+                    | TrackedFrames::Shared(_)
+                    | TrackedFrames::Cached(_)
+                    | TrackedFrames::LazyFree(_) => 1,
+                    TrackedFrames::Swapped(_) => 0,
                     TrackedFrames::Multiple(v) => v.len(),
                     TrackedFrames::Contiguous(r) => r.len(),
                 })
@@ -116,15 +162,58 @@ impl MappingArea {
         }
     }
 
-    /// 获取虚拟页号（VPN）对应的物理页号（PPN）（如果已映射）
+    /// 统计区域的内存占用（用于 `/proc/[pid]/smaps`）
+    ///
+    /// 物理页的映射数取自反向映射（见 [`crate::rmap`]），被多处映射的页计为共享，
+    /// 并按映射数均摊到 PSS；页表项带有脏位的页计为脏页。
+    pub fn usage<PT: PageTableInner<E>, E: PageTableEntry>(&self, page_table: &PT) -> AreaUsage {
+        let mut usage = AreaUsage::default();
+        if self.map_type != MapType::Framed {
+            return usage;
+        }
+        let page_size = mm_config().page_size();
+        self.frames.for_each_page(|ppn, vpn| {
+            let mapcount = rmap::page_mapcount(ppn).max(1);
+            let dirty = page_table
+                .walk(vpn)
+                .is_ok_and(|(_, _, flags)| flags.contains(UniversalPTEFlag::DIRTY));
+            let counter = match (mapcount > 1, dirty) {
+                (true, false) => &mut usage.shared_clean,
+                (true, true) => &mut usage.shared_dirty,
+                (false, false) => &mut usage.private_clean,
+                (false, true) => &mut usage.private_dirty,
+            };
+            *counter += page_size;
+            usage.rss += page_size;
+            usage.pss += page_size / mapcount;
+        });
+        usage.swap = self
+            .frames
+            .values()
+            .filter(|t| matches!(t, TrackedFrames::Swapped(_)))
+            .count()
+            * page_size;
+        usage
+    }
+
+    /// 获取虚拟页号（VPN）对应的物理页号（PPN）（如果已映射且未被换出）
     pub fn get_ppn(&self, vpn: Vpn) -> Option<crate::address::Ppn> {
-        self.frames.get(&vpn).map(|tracked| match tracked {
-            TrackedFrames::Single(frame) => frame.ppn(),
-            TrackedFrames::Multiple(frames) => frames.first().map(|f| f.ppn()).unwrap(),
-            TrackedFrames::Contiguous(_) => {
-                panic!("当前实现不支持连续帧");
+        // 大页的连续帧以首页为键，其余页向前查找
+        let (head, tracked) = self.frames.range(..=vpn).next_back()?;
+        match tracked {
+            TrackedFrames::Contiguous(range) => {
+                let offset = vpn.as_usize() - head.as_usize();
+                (offset < range.len())
+                    .then(|| Ppn::from_usize(range.start_ppn().as_usize() + offset))
             }
-        })
+            _ if *head != vpn => None,
+            TrackedFrames::Single(frame) => Some(frame.ppn()),
+            TrackedFrames::Shared(frame) => Some(frame.ppn()),
+            TrackedFrames::Cached(page) => Some(page.ppn()),
+            TrackedFrames::LazyFree(frame) => Some(frame.ppn()),
+            TrackedFrames::Swapped(_) => None,
+            TrackedFrames::Multiple(frames) => frames.first().map(|f| f.ppn()),
+        }
     }
 
     /// 创建一个新的映射区域描述符。
@@ -140,11 +229,40 @@ impl MappingArea {
             area_type,
             map_type,
             permission,
-            frames: BTreeMap::new(),
+            frames: FrameMap::new(),
             file,
+            mergeable: false,
+            hugetlb: false,
         }
     }
 
+    /// 创建一个从大页池取页的用户匿名映射区域，`vpn_range` 的两端必须按 2M 对齐
+    pub fn new_hugetlb(vpn_range: VpnRange, permission: UniversalPTEFlag) -> Self {
+        let mut area = MappingArea::new(
+            vpn_range,
+            AreaType::UserMmap,
+            MapType::Framed,
+            permission,
+            None,
+        );
+        area.hugetlb = true;
+        area
+    }
+
+    /// 以本区域为模板创建覆盖 `vpn_range` 的新区域（拆分时使用），区域类型、KSM 与大页池标记不变
+    fn derive(
+        &self,
+        vpn_range: VpnRange,
+        map_type: MapType,
+        permission: UniversalPTEFlag,
+        file: Option<MmapFile>,
+    ) -> Self {
+        let mut area = MappingArea::new(vpn_range, self.area_type, map_type, permission, file);
+        area.mergeable = self.mergeable;
+        area.hugetlb = self.hugetlb;
+        area
+    }
+
     /// 映射单个虚拟页到物理页
     pub fn map_one<PT: PageTableInner<E>, E: PageTableEntry>(
         &mut self,
@@ -162,39 +280,210 @@ impl MappingArea {
         batch: Option<&mut TlbBatchContextWrapper>,
     ) -> Result<(), page_table::PagingError> {
         let ppn = match self.map_type {
-            MapType::Direct => {
-                let vaddr = vpn.start_addr();
-                let paddr = unsafe { arch_ops().vaddr_to_paddr(vaddr.as_usize()) };
-                Ppn::from_addr_floor(Paddr::from_usize(paddr))
-            }
+            MapType::Direct => Self::direct_ppn(vpn),
             MapType::Framed => {
                 let frame = alloc_frame().ok_or(page_table::PagingError::FrameAllocFailed)?;
                 let ppn = frame.ppn();
-                self.frames.insert(vpn, TrackedFrames::Single(frame));
+                self.frames
+                    .insert(page_table.root_ppn(), vpn, TrackedFrames::Single(frame));
                 ppn
             }
             MapType::Reserved => {
                 return Ok(());
             }
+            MapType::Fixed(base) => {
+                let offset = vpn.as_usize() - self.vpn_range.start().as_usize();
+                Ppn::from_usize(base.as_usize() + offset)
+            }
         };
 
         page_table.map_with_batch(vpn, ppn, PageSize::Size4K, self.permission.clone(), batch)?;
         Ok(())
     }
 
+    /// 直接映射区域中 `vpn` 对应的物理页号
+    fn direct_ppn(vpn: Vpn) -> Ppn {
+        let paddr = unsafe { arch_ops().vaddr_to_paddr(vpn.start_addr().as_usize()) };
+        Ppn::from_addr_floor(Paddr::from_usize(paddr))
+    }
+
+    /// 帧映射区域是否使用 2M 大页：足够大的用户匿名映射
+    fn wants_huge_frames(&self) -> bool {
+        self.map_type == MapType::Framed
+            && self.area_type == AreaType::UserMmap
+            && self.file.is_none()
+            && self.vpn_range.len() >= PageSize::Size2M.pages()
+    }
+
     /// 映射此映射区域中的所有页
+    ///
+    /// 直接映射区域中虚拟地址与物理地址同时按大页对齐的部分使用 1G/2M 大页（如内核的线性映射），
+    /// 大的用户匿名映射中按 2M 对齐的部分分配 2M 连续帧并用大页映射；其余部分使用 4K 页。
+    /// 大页池的区域全部从池中取 2M 页，池空时失败。
     pub fn map<PT: PageTableInner<E>, E: PageTableEntry>(
         &mut self,
         page_table: &mut PT,
     ) -> Result<(), page_table::PagingError> {
         TlbBatchContextWrapper::execute(|batch| {
-            for vpn in self.vpn_range {
-                self.map_one_with_batch(page_table, vpn, Some(batch))?;
+            let end = self.vpn_range.end();
+            let mut vpn = self.vpn_range.start();
+            while vpn < end {
+                let pages = if self.hugetlb {
+                    self.map_hugetlb_page(page_table, vpn, Some(batch))?
+                } else {
+                    match self.map_huge_with_batch(page_table, vpn, batch)? {
+                        0 => {
+                            self.map_one_with_batch(page_table, vpn, Some(batch))?;
+                            1
+                        }
+                        pages => pages,
+                    }
+                };
+                vpn = Vpn::from_usize(vpn.as_usize() + pages);
             }
             Ok(())
         })
     }
 
+    /// 尝试在 `vpn` 处建立一个大页映射
+    ///
+    /// # 返回值
+    /// 映射的页数；`vpn` 处不适合使用大页（未对齐、超出区域、连续帧不足或已有 4K 映射）时返回 0
+    fn map_huge_with_batch<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+        batch: &mut TlbBatchContextWrapper,
+    ) -> Result<usize, page_table::PagingError> {
+        let huge_frames = self.wants_huge_frames();
+        for size in [PageSize::Size1G, PageSize::Size2M] {
+            let pages = size.pages();
+            if vpn.as_usize() % pages != 0
+                || vpn.as_usize() + pages > self.vpn_range.end().as_usize()
+            {
+                continue;
+            }
+            let (ppn, frames) = match self.map_type {
+                MapType::Direct => {
+                    let ppn = Self::direct_ppn(vpn);
+                    if ppn.as_usize() % pages != 0 {
+                        continue;
+                    }
+                    (ppn, None)
+                }
+                MapType::Framed if huge_frames && size == PageSize::Size2M => {
+                    let Some(frames) = alloc_contig_frames_aligned(pages, pages) else {
+                        return Ok(0);
+                    };
+                    (frames.start_ppn(), Some(frames))
+                }
+                _ => continue,
+            };
+            match page_table.map_with_batch(vpn, ppn, size, self.permission.clone(), Some(batch)) {
+                Ok(()) => {
+                    kcov!();
+                    if let Some(frames) = frames {
+                        self.frames.insert(
+                            page_table.root_ppn(),
+                            vpn,
+                            TrackedFrames::Contiguous(frames),
+                        );
+                    }
+                    return Ok(pages);
+                }
+                // 该范围内已有下一级页表（之前的 4K 映射留下的），退回小页
+                Err(page_table::PagingError::AlreadyMapped)
+                | Err(page_table::PagingError::HugePageConflict) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(0)
+    }
+
+    /// 从大页池取一个大页映射到以 `vpn` 为首页的 2M 范围
+    ///
+    /// # 返回值
+    /// 映射的页数；`vpn` 未按 2M 对齐或范围超出区域时返回 [`InvalidAddress`](page_table::PagingError::InvalidAddress)，
+    /// 池空时返回 [`FrameAllocFailed`](page_table::PagingError::FrameAllocFailed)
+    fn map_hugetlb_page<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+        batch: Option<&mut TlbBatchContextWrapper>,
+    ) -> Result<usize, page_table::PagingError> {
+        let pages = PageSize::Size2M.pages();
+        if vpn.as_usize() % pages != 0 || vpn.as_usize() + pages > self.vpn_range.end().as_usize() {
+            return Err(page_table::PagingError::InvalidAddress);
+        }
+        let frames = hugetlb::alloc_huge_page().ok_or(page_table::PagingError::FrameAllocFailed)?;
+        page_table.map_with_batch(
+            vpn,
+            frames.start_ppn(),
+            PageSize::Size2M,
+            self.permission.clone(),
+            batch,
+        )?;
+        self.frames.insert(
+            page_table.root_ppn(),
+            vpn,
+            TrackedFrames::Contiguous(frames),
+        );
+        Ok(pages)
+    }
+
+    /// 把覆盖 `vpn` 的大页拆分为 4K 页，帧映射区域的连续帧随之改为逐页跟踪
+    ///
+    /// 大页池的页不能拆分，需要拆分时返回 [`InvalidAddress`](page_table::PagingError::InvalidAddress)。
+    fn split_huge<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> Result<(), page_table::PagingError> {
+        let mut split = false;
+        while let Ok((_, size, _)) = page_table.walk(vpn) {
+            if size == PageSize::Size4K {
+                break;
+            }
+            if self.hugetlb {
+                return Err(page_table::PagingError::InvalidAddress);
+            }
+            page_table.split_huge_page(vpn)?;
+            split = true;
+        }
+        if !split {
+            return Ok(());
+        }
+        kcov!();
+        let head = Vpn::from_usize(vpn.as_usize() & !(PageSize::Size2M.pages() - 1));
+        if let Some(TrackedFrames::Contiguous(_)) = self.frames.get(&head) {
+            let Some(TrackedFrames::Contiguous(range)) = self.frames.remove(&head) else {
+                unreachable!();
+            };
+            for (i, frame) in range.into_frames().into_iter().enumerate() {
+                self.frames.insert(
+                    page_table.root_ppn(),
+                    Vpn::from_usize(head.as_usize() + i),
+                    TrackedFrames::Single(frame),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 若 `vpn` 落在某个大页内部（不是其首页），拆分该大页，使 `vpn` 成为页边界
+    fn split_huge_at<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> Result<(), page_table::PagingError> {
+        match page_table.walk(vpn) {
+            Ok((_, size, _)) if vpn.as_usize() % size.pages() != 0 => {
+                self.split_huge(page_table, vpn)
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// 解除映射单个虚拟页
     pub fn unmap_one<PT: PageTableInner<E>, E: PageTableEntry>(
         &mut self,
@@ -214,6 +503,17 @@ impl MappingArea {
         if self.map_type == MapType::Reserved {
             return Ok(());
         }
+        self.split_huge(page_table, vpn)?;
+        if self.map_type == MapType::Framed && !self.frames.contains_key(&vpn) {
+            // 被 madvise 丢弃的页没有页表项
+            return Ok(());
+        }
+        if let Some(TrackedFrames::Swapped(_)) = self.frames.get(&vpn) {
+            // 换出项不在 TLB 中，清除后槽位随帧集合一起释放
+            page_table.clear_swap_entry(vpn)?;
+            self.frames.remove(&vpn);
+            return Ok(());
+        }
         page_table.unmap_with_batch(vpn, batch)?;
 
         if self.map_type == MapType::Framed {
@@ -227,14 +527,45 @@ impl MappingArea {
         &mut self,
         page_table: &mut PT,
     ) -> Result<(), page_table::PagingError> {
+        let range = self.vpn_range;
         TlbBatchContextWrapper::execute(|batch| {
-            for vpn in self.vpn_range {
-                self.unmap_one_with_batch(page_table, vpn, Some(batch))?;
-            }
-            Ok(())
+            self.unmap_range_with_batch(page_table, range.start(), range.end(), batch)
         })
     }
 
+    /// 解除 `[start, end)` 内的映射：完整落在范围内的大页整体解除，跨越边界的大页先拆分
+    fn unmap_range_with_batch<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        start: Vpn,
+        end: Vpn,
+        batch: &mut TlbBatchContextWrapper,
+    ) -> Result<(), page_table::PagingError> {
+        if self.map_type == MapType::Reserved {
+            return Ok(());
+        }
+        self.split_huge_at(page_table, start)?;
+        self.split_huge_at(page_table, end)?;
+        let mut vpn = start;
+        while vpn < end {
+            match page_table.walk(vpn) {
+                Ok((_, size, _)) if size != PageSize::Size4K => {
+                    // 边界已拆分，这里的大页以 vpn 为首页且完整落在范围内
+                    page_table.unmap_with_batch(vpn, Some(batch))?;
+                    if self.map_type == MapType::Framed {
+                        self.frames.remove(&vpn);
+                    }
+                    vpn = Vpn::from_usize(vpn.as_usize() + size.pages());
+                }
+                _ => {
+                    self.unmap_one_with_batch(page_table, vpn, Some(batch))?;
+                    vpn = Vpn::from_usize(vpn.as_usize() + 1);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// 复制数据到已映射的区域
     pub fn copy_data<PT: PageTableInner<E>, E: PageTableEntry>(
         &self,
@@ -286,7 +617,7 @@ impl MappingArea {
             area_type: self.area_type,
             map_type: self.map_type,
             permission: self.permission.clone(),
-            frames: BTreeMap::new(),
+            frames: FrameMap::new(),
             file: self.file.as_ref().map(|f| MmapFile {
                 file: f.file.clone(),
                 offset: f.offset,
@@ -294,6 +625,8 @@ impl MappingArea {
                 prot: f.prot,
                 flags: f.flags,
             }),
+            mergeable: self.mergeable,
+            hugetlb: self.hugetlb,
         }
     }
 
@@ -302,7 +635,13 @@ impl MappingArea {
         &self,
         page_table: &mut PT,
     ) -> Result<Self, page_table::PagingError> {
+        kcov!();
         let mut new_area = self.clone_metadata();
+        if let MapType::Fixed(_) = self.map_type {
+            // 固定映射的物理页由内核持有，新地址空间共享同一组物理页
+            new_area.map(page_table)?;
+            return Ok(new_area);
+        }
         if self.map_type != MapType::Framed {
             return Err(page_table::PagingError::UnsupportedMapType);
         }
@@ -312,12 +651,15 @@ impl MappingArea {
         TlbBatchContextWrapper::execute(|batch| {
             for (vpn, tracked_frames) in &self.frames {
                 match tracked_frames {
-                    TrackedFrames::Single(frame) => {
+                    TrackedFrames::Single(_)
+                    | TrackedFrames::Shared(_)
+                    | TrackedFrames::Cached(_)
+                    | TrackedFrames::LazyFree(_) => {
+                        let src_ppn = self.get_ppn(*vpn).unwrap();
                         let new_frame =
                             alloc_frame().ok_or(page_table::PagingError::FrameAllocFailed)?;
 
                         let new_ppn = new_frame.ppn();
-                        let src_ppn = frame.ppn();
 
                         unsafe {
                             let src_va = arch_ops().paddr_to_vaddr(src_ppn.start_addr().as_usize());
@@ -338,9 +680,27 @@ impl MappingArea {
                             Some(batch),
                         )?;
 
-                        new_area
-                            .frames
-                            .insert(*vpn, TrackedFrames::Single(new_frame));
+                        new_area.frames.insert(
+                            page_table.root_ppn(),
+                            *vpn,
+                            TrackedFrames::Single(new_frame),
+                        );
+                    }
+                    TrackedFrames::Swapped(slot) => {
+                        // 换出的页直接从交换区读入新地址空间的帧，本区域仍保持换出
+                        let new_frame = Self::read_swapped(slot)?;
+                        page_table.map_with_batch(
+                            *vpn,
+                            new_frame.ppn(),
+                            PageSize::Size4K,
+                            self.permission.clone(),
+                            Some(batch),
+                        )?;
+                        new_area.frames.insert(
+                            page_table.root_ppn(),
+                            *vpn,
+                            TrackedFrames::Single(new_frame),
+                        );
                     }
                     TrackedFrames::Multiple(frames) => {
                         let mut new_frames = alloc::vec::Vec::new();
@@ -375,12 +735,66 @@ impl MappingArea {
                             new_frames.push(new_frame);
                         }
 
-                        new_area
-                            .frames
-                            .insert(*vpn, TrackedFrames::Multiple(new_frames));
+                        new_area.frames.insert(
+                            page_table.root_ppn(),
+                            *vpn,
+                            TrackedFrames::Multiple(new_frames),
+                        );
                     }
-                    TrackedFrames::Contiguous(_) => {
-                        return Err(page_table::PagingError::HugePageSplitNotImplemented);
+                    TrackedFrames::Contiguous(range) => {
+                        let pages = range.len();
+                        let copy = |src: Ppn, dst: Ppn, len: usize| unsafe {
+                            core::ptr::copy_nonoverlapping(
+                                arch_ops().paddr_to_vaddr(src.start_addr().as_usize()) as *const u8,
+                                arch_ops().paddr_to_vaddr(dst.start_addr().as_usize()) as *mut u8,
+                                len,
+                            );
+                        };
+                        // 大页池的区域只能从池中取页，不逐页复制
+                        let new_range = if self.hugetlb {
+                            Some(
+                                hugetlb::alloc_huge_page()
+                                    .ok_or(page_table::PagingError::FrameAllocFailed)?,
+                            )
+                        } else {
+                            alloc_contig_frames_aligned(pages, pages)
+                        };
+                        if let Some(new_range) = new_range {
+                            copy(range.start_ppn(), new_range.start_ppn(), pages * page_size);
+                            page_table.map_with_batch(
+                                *vpn,
+                                new_range.start_ppn(),
+                                PageSize::Size2M,
+                                self.permission.clone(),
+                                Some(batch),
+                            )?;
+                            new_area.frames.insert(
+                                page_table.root_ppn(),
+                                *vpn,
+                                TrackedFrames::Contiguous(new_range),
+                            );
+                            continue;
+                        }
+                        // 没有足够的连续帧时，新区域中逐页复制
+                        for i in 0..pages {
+                            let new_frame =
+                                alloc_frame().ok_or(page_table::PagingError::FrameAllocFailed)?;
+                            let page_vpn = Vpn::from_usize(vpn.as_usize() + i);
+                            let src_ppn = Ppn::from_usize(range.start_ppn().as_usize() + i);
+                            copy(src_ppn, new_frame.ppn(), page_size);
+                            page_table.map_with_batch(
+                                page_vpn,
+                                new_frame.ppn(),
+                                PageSize::Size4K,
+                                self.permission.clone(),
+                                Some(batch),
+                            )?;
+                            new_area.frames.insert(
+                                page_table.root_ppn(),
+                                page_vpn,
+                                TrackedFrames::Single(new_frame),
+                            );
+                        }
                     }
                 }
             }
@@ -389,12 +803,524 @@ impl MappingArea {
         })
     }
 
+    /// 是否为私有映射（写入对其它地址空间不可见），共享文件映射之外的帧映射都是私有的
+    pub fn is_private(&self) -> bool {
+        self.file
+            .as_ref()
+            .is_none_or(|f| !f.flags.contains(MapFlags::SHARED))
+    }
+
+    /// 是否为匿名映射（没有后备文件）
+    pub fn is_anonymous(&self) -> bool {
+        self.file.is_none()
+    }
+
+    /// 是否由 KSM 扫描合并相同的页
+    pub fn is_mergeable(&self) -> bool {
+        self.mergeable
+    }
+
+    /// 设置是否由 KSM 扫描合并相同的页
+    pub fn set_mergeable(&mut self, mergeable: bool) {
+        self.mergeable = mergeable;
+    }
+
+    /// 是否是从大页池取页的区域
+    pub fn is_hugetlb(&self) -> bool {
+        self.hugetlb
+    }
+
+    /// 页表项应使用的标志：写时复制共享的帧和可丢弃的帧去掉写权限，等写缺页时再处理
+    fn pte_flags(&self, tracked: Option<&TrackedFrames>) -> UniversalPTEFlag {
+        match tracked {
+            Some(TrackedFrames::Shared(_) | TrackedFrames::LazyFree(_)) => {
+                self.permission.clone() - UniversalPTEFlag::WRITEABLE
+            }
+            _ => self.permission.clone(),
+        }
+    }
+
+    /// 以写时复制方式克隆映射区域（用于 fork）
+    ///
+    /// 私有帧映射的物理帧改为由父子双方共享（[`TrackedFrames::Shared`]），
+    /// 双方页表项都去掉写权限；之后任一方写入时由 [`handle_cow_fault`](Self::handle_cow_fault) 复制。
+    /// 映射页缓存的共享文件映射直接共享缓存页；其它共享映射和不能共享的帧集合（包括大页）退化为
+    /// [`clone_with_data`](Self::clone_with_data) 深拷贝。
+    pub fn clone_cow<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        parent_table: &mut PT,
+        child_table: &mut PT,
+    ) -> Result<Self, page_table::PagingError> {
+        kcov!();
+        let private = self.is_private();
+        if self.map_type != MapType::Framed
+            || !self.frames.values().all(|t| match t {
+                TrackedFrames::Single(_)
+                | TrackedFrames::Shared(_)
+                | TrackedFrames::Swapped(_)
+                | TrackedFrames::LazyFree(_) => private,
+                TrackedFrames::Cached(_) => true,
+                _ => false,
+            })
+        {
+            return self.clone_with_data(child_table);
+        }
+
+        let mut new_area = self.clone_metadata();
+        let writable = self.permission.contains(UniversalPTEFlag::WRITEABLE);
+        let cow_flags = self.permission.clone() - UniversalPTEFlag::WRITEABLE;
+
+        TlbBatchContextWrapper::execute(|batch| {
+            for (vpn, tracked) in self.frames.iter_mut() {
+                let cloned = match tracked {
+                    TrackedFrames::Shared(frame) => TrackedFrames::Shared(frame.clone()),
+                    TrackedFrames::Cached(page) => TrackedFrames::Cached(page.clone()),
+                    // 换出的页不共享槽位，子进程得到一份独占的副本
+                    TrackedFrames::Swapped(slot) => {
+                        TrackedFrames::Single(Self::read_swapped(slot)?)
+                    }
+                    // 可丢弃的帧共享后不再可丢弃
+                    TrackedFrames::Single(_) | TrackedFrames::LazyFree(_) => {
+                        // 先撤销父进程的写权限，再把帧转为共享
+                        if writable {
+                            parent_table.update_flags_with_batch(
+                                *vpn,
+                                cow_flags.clone(),
+                                Some(batch),
+                            )?;
+                        }
+                        let (TrackedFrames::Single(frame) | TrackedFrames::LazyFree(frame)) =
+                            core::mem::replace(
+                                tracked,
+                                TrackedFrames::Multiple(alloc::vec::Vec::new()),
+                            )
+                        else {
+                            unreachable!();
+                        };
+                        let frame = Arc::new(frame);
+                        *tracked = TrackedFrames::Shared(frame.clone());
+                        TrackedFrames::Shared(frame)
+                    }
+                    _ => unreachable!(),
+                };
+                let ppn = match &cloned {
+                    TrackedFrames::Single(frame) => frame.ppn(),
+                    TrackedFrames::Shared(frame) => frame.ppn(),
+                    TrackedFrames::Cached(page) => page.ppn(),
+                    _ => unreachable!(),
+                };
+                child_table.map_with_batch(
+                    *vpn,
+                    ppn,
+                    PageSize::Size4K,
+                    new_area.pte_flags(Some(&cloned)),
+                    Some(batch),
+                )?;
+                new_area.frames.insert(child_table.root_ppn(), *vpn, cloned);
+            }
+            Ok(())
+        })?;
+
+        Ok(new_area)
+    }
+
+    /// 让 `vpn` 处的写时复制共享帧变为本区域独占，并按区域权限重新映射
+    ///
+    /// 只剩本区域引用时直接取回该帧，否则分配新帧并复制内容。
+    /// 可丢弃的帧（[`TrackedFrames::LazyFree`]）被写入后不再可丢弃，同样恢复为普通的独占帧。
+    ///
+    /// # 返回值
+    /// 该页原本是共享帧或可丢弃的帧时返回 `true`
+    pub fn unshare_page<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> Result<bool, page_table::PagingError> {
+        if let Some(TrackedFrames::LazyFree(_)) = self.frames.get(&vpn) {
+            let Some(TrackedFrames::LazyFree(frame)) = self.frames.remove(&vpn) else {
+                unreachable!();
+            };
+            self.frames
+                .insert(page_table.root_ppn(), vpn, TrackedFrames::Single(frame));
+            TlbBatchContextWrapper::execute(|batch| {
+                page_table.update_flags_with_batch(vpn, self.permission.clone(), Some(batch))
+            })?;
+            return Ok(true);
+        }
+        if !matches!(self.frames.get(&vpn), Some(TrackedFrames::Shared(_))) {
+            return Ok(false);
+        }
+        let Some(TrackedFrames::Shared(shared)) = self.frames.remove(&vpn) else {
+            unreachable!();
+        };
+        kcov!();
+        let shared = match Arc::try_unwrap(shared) {
+            Ok(frame) => {
+                // 其它地址空间都已复制或退出，直接取回独占
+                self.frames
+                    .insert(page_table.root_ppn(), vpn, TrackedFrames::Single(frame));
+                TlbBatchContextWrapper::execute(|batch| {
+                    page_table.update_flags_with_batch(vpn, self.permission.clone(), Some(batch))
+                })?;
+                return Ok(true);
+            }
+            Err(shared) => shared,
+        };
+        let Some(new_frame) = alloc_frame() else {
+            self.frames
+                .insert(page_table.root_ppn(), vpn, TrackedFrames::Shared(shared));
+            return Err(page_table::PagingError::FrameAllocFailed);
+        };
+        let page_size = mm_config().page_size();
+        unsafe {
+            let src_va = arch_ops().paddr_to_vaddr(shared.ppn().start_addr().as_usize());
+            let dst_va = arch_ops().paddr_to_vaddr(new_frame.ppn().start_addr().as_usize());
+            core::ptr::copy_nonoverlapping(src_va as *const u8, dst_va as *mut u8, page_size);
+        }
+        // 其它 CPU 上可能还缓存着指向共享帧的 TLB 项，须随批处理一起刷新
+        TlbBatchContextWrapper::execute(|batch| {
+            page_table.unmap_with_batch(vpn, Some(batch))?;
+            page_table.map_with_batch(
+                vpn,
+                new_frame.ppn(),
+                PageSize::Size4K,
+                self.permission.clone(),
+                Some(batch),
+            )
+        })?;
+        self.frames
+            .insert(page_table.root_ppn(), vpn, TrackedFrames::Single(new_frame));
+        Ok(true)
+    }
+
+    /// 处理 `vpn` 处的写缺页
+    ///
+    /// # 返回值
+    /// 缺页由写时复制引起并已解决（或其它 CPU 已先一步解决）时返回 `true`；
+    /// 区域不可写等真正的访问违例返回 `false`，由调用者投递 SIGSEGV
+    pub fn handle_cow_fault<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> Result<bool, page_table::PagingError> {
+        if self.map_type != MapType::Framed
+            || !self.permission.contains(UniversalPTEFlag::WRITEABLE)
+        {
+            return Ok(false);
+        }
+        if self.unshare_page(page_table, vpn)? {
+            return Ok(true);
+        }
+        // 已是独占帧：页表项可写说明是陈旧的 TLB 项，刷新后重试即可
+        match page_table.walk(vpn) {
+            Ok((_, _, flags)) if flags.contains(UniversalPTEFlag::WRITEABLE) => {
+                PT::tlb_flush(vpn);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// 是否可以换出：用户地址空间中的私有帧映射，大页池的区域除外
+    pub fn is_swappable(&self) -> bool {
+        self.map_type == MapType::Framed
+            && self.is_private()
+            && !self.hugetlb
+            && !matches!(
+                self.area_type,
+                AreaType::KernelText
+                    | AreaType::KernelRodata
+                    | AreaType::KernelData
+                    | AreaType::KernelStack
+                    | AreaType::KernelBss
+                    | AreaType::KernelHeap
+                    | AreaType::KernelMmio
+            )
+    }
+
+    /// 尝试把 `vpn` 处的页换出到交换区
+    ///
+    /// 访问位已置位的页只清除访问位（第二次机会）；写时复制共享的帧换出后也无法释放，暂不换出。
+    /// 换出时先撤销映射再写交换区，写出期间其它线程的访问会进入缺页处理并等待地址空间锁。
+    pub fn swap_out_page<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> ReclaimResult {
+        if !self.is_swappable() || !self.vpn_range.contains(vpn) {
+            return ReclaimResult::Gone;
+        }
+        match self.frames.get(&vpn) {
+            Some(TrackedFrames::Single(_)) => {}
+            Some(TrackedFrames::LazyFree(_)) => return self.discard_lazy_free(page_table, vpn),
+            Some(TrackedFrames::Shared(_)) => return ReclaimResult::Busy,
+            _ => return ReclaimResult::Gone,
+        }
+        let Ok((ppn, _, flags)) = page_table.walk(vpn) else {
+            return ReclaimResult::Gone;
+        };
+        if flags.contains(UniversalPTEFlag::ACCESSED) {
+            let cleared = TlbBatchContextWrapper::execute(|batch| {
+                page_table.update_flags_with_batch(
+                    vpn,
+                    flags - UniversalPTEFlag::ACCESSED,
+                    Some(batch),
+                )
+            });
+            return match cleared {
+                Ok(()) => ReclaimResult::Referenced,
+                Err(_) => ReclaimResult::Gone,
+            };
+        }
+        kcov!();
+        if TlbBatchContextWrapper::execute(|batch| page_table.unmap_with_batch(vpn, Some(batch)))
+            .is_err()
+        {
+            return ReclaimResult::Gone;
+        }
+        let page_size = mm_config().page_size();
+        let data = unsafe {
+            core::slice::from_raw_parts(
+                arch_ops().paddr_to_vaddr(ppn.start_addr().as_usize()) as *const u8,
+                page_size,
+            )
+        };
+        let slot = match swap::swap_write(data) {
+            Ok(slot) => slot,
+            Err(_) => {
+                // 交换区已满或写失败：恢复原映射
+                return match page_table.map(vpn, ppn, PageSize::Size4K, self.permission.clone()) {
+                    Ok(()) => ReclaimResult::Busy,
+                    Err(_) => ReclaimResult::Gone,
+                };
+            }
+        };
+        if page_table.set_swap_entry(vpn, slot.index(), None).is_err() {
+            let _ = page_table.map(vpn, ppn, PageSize::Size4K, self.permission.clone());
+            return ReclaimResult::Busy;
+        }
+        // 替换掉的 FrameTracker 在此释放物理帧
+        self.frames
+            .insert(page_table.root_ppn(), vpn, TrackedFrames::Swapped(slot));
+        ReclaimResult::Reclaimed
+    }
+
+    /// 把 `vpn` 处的物理页 `old` 迁移到新帧 `new`（内存规整，见 [`crate::compaction`]）
+    ///
+    /// 先解除映射并刷新 TLB，复制页内容后让页表项指向新帧，权限与访问位不变，旧帧随即释放。
+    /// 与换出相同，只迁移可换出区域中独占的页：普通页、可丢弃页与只剩一个引用的写时复制页。
+    ///
+    /// # 返回值
+    /// 迁移成功时返回 `true`；`vpn` 处已不是 `old` 或该页不可迁移时返回 `false`，`new` 被释放
+    pub fn migrate_page<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+        old: Ppn,
+        new: FrameTracker,
+    ) -> bool {
+        if !self.is_swappable() || !self.vpn_range.contains(vpn) {
+            return false;
+        }
+        let new_ppn = new.ppn();
+        let tracked = match self.frames.get(&vpn) {
+            Some(TrackedFrames::Single(frame)) if frame.ppn() == old => TrackedFrames::Single(new),
+            Some(TrackedFrames::LazyFree(frame)) if frame.ppn() == old => {
+                TrackedFrames::LazyFree(new)
+            }
+            Some(TrackedFrames::Shared(frame))
+                if frame.ppn() == old && Arc::strong_count(frame) == 1 =>
+            {
+                TrackedFrames::Shared(Arc::new(new))
+            }
+            _ => return false,
+        };
+        let Ok((ppn, PageSize::Size4K, flags)) = page_table.walk(vpn) else {
+            return false;
+        };
+        if ppn != old {
+            return false;
+        }
+        kcov!();
+        // 先解除映射：复制期间其它 CPU 上的线程不能再写旧帧
+        if TlbBatchContextWrapper::execute(|batch| page_table.unmap_with_batch(vpn, Some(batch)))
+            .is_err()
+        {
+            return false;
+        }
+        let page_size = mm_config().page_size();
+        unsafe {
+            let src_va = arch_ops().paddr_to_vaddr(old.start_addr().as_usize());
+            let dst_va = arch_ops().paddr_to_vaddr(new_ppn.start_addr().as_usize());
+            core::ptr::copy_nonoverlapping(src_va as *const u8, dst_va as *mut u8, page_size);
+        }
+        if page_table
+            .map(vpn, new_ppn, PageSize::Size4K, flags)
+            .is_err()
+        {
+            let _ = page_table.map(vpn, old, PageSize::Size4K, flags);
+            return false;
+        }
+        // 替换掉的旧帧在此释放
+        self.frames.insert(page_table.root_ppn(), vpn, tracked);
+        true
+    }
+
+    /// KSM 扫描 `vpn` 处的页，与内容相同的页合并（见 [`crate::ksm`]）
+    ///
+    /// 只合并可合并区域中独占的普通页。合并前先去掉页表项的写权限再比较内容，
+    /// 比较期间其它线程的写入会进入写时复制缺页，等待地址空间锁。
+    ///
+    /// # 返回值
+    /// 该页合并到了稳定表中的帧，或被提升为稳定帧时返回 `true`
+    pub fn ksm_scan_page<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> bool {
+        if !self.mergeable || !self.is_swappable() || !self.vpn_range.contains(vpn) {
+            return false;
+        }
+        let ppn = match self.frames.get(&vpn) {
+            Some(TrackedFrames::Single(frame)) => frame.ppn(),
+            _ => return false,
+        };
+        let Ok((pte_ppn, PageSize::Size4K, flags)) = page_table.walk(vpn) else {
+            return false;
+        };
+        if pte_ppn != ppn {
+            return false;
+        }
+        let page_size = mm_config().page_size();
+        // SAFETY: 该帧由本区域独占，持有地址空间锁期间不会被释放
+        let data = unsafe {
+            core::slice::from_raw_parts(
+                arch_ops().paddr_to_vaddr(ppn.start_addr().as_usize()) as *const u8,
+                page_size,
+            )
+        };
+        let found = ksm::lookup(page_table.root_ppn(), vpn, data);
+        if matches!(found, ksm::KsmMatch::None) {
+            return false;
+        }
+        let readonly = flags - UniversalPTEFlag::WRITEABLE;
+        if TlbBatchContextWrapper::execute(|batch| {
+            page_table.update_flags_with_batch(vpn, readonly, Some(batch))
+        })
+        .is_err()
+        {
+            return false;
+        }
+        kcov!();
+        match found {
+            ksm::KsmMatch::Stable(stable) => {
+                // 撤销写权限之前内容可能已经改变
+                let same = unsafe {
+                    let stable_va = arch_ops().paddr_to_vaddr(stable.ppn().start_addr().as_usize());
+                    core::slice::from_raw_parts(stable_va as *const u8, page_size) == data
+                };
+                let remapped = same
+                    && TlbBatchContextWrapper::execute(|batch| {
+                        page_table.unmap_with_batch(vpn, Some(batch))?;
+                        page_table.map_with_batch(
+                            vpn,
+                            stable.ppn(),
+                            PageSize::Size4K,
+                            readonly,
+                            Some(batch),
+                        )
+                    })
+                    .is_ok();
+                if !remapped {
+                    let _ = page_table.unmap(vpn);
+                    let _ = page_table.map(vpn, ppn, PageSize::Size4K, flags);
+                    return false;
+                }
+                // 替换掉的独占帧在此释放
+                self.frames
+                    .insert(page_table.root_ppn(), vpn, TrackedFrames::Shared(stable));
+            }
+            ksm::KsmMatch::Unstable => {
+                // 写权限已撤销，内容不再变化，可以作为稳定帧
+                let Some(TrackedFrames::Single(frame)) = self.frames.remove(&vpn) else {
+                    unreachable!();
+                };
+                let frame = Arc::new(frame);
+                ksm::insert_stable(data, &frame);
+                self.frames
+                    .insert(page_table.root_ppn(), vpn, TrackedFrames::Shared(frame));
+            }
+            ksm::KsmMatch::None => unreachable!(),
+        }
+        true
+    }
+
+    /// 让 `[start, end)` 内经 KSM 合并的页重新变为独占（MADV_UNMERGEABLE）
+    ///
+    /// fork 得到的写时复制共享帧保持不变。
+    pub fn ksm_unmerge_range<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        start: Vpn,
+        end: Vpn,
+    ) -> Result<(), page_table::PagingError> {
+        for vpn in VpnRange::new(start, end) {
+            let merged = matches!(
+                self.frames.get(&vpn),
+                Some(TrackedFrames::Shared(frame)) if ksm::is_ksm_frame(frame)
+            );
+            if merged {
+                self.unshare_page(page_table, vpn)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 把 `vpn` 处换出的页读回新分配的物理帧，并按区域权限重新映射
+    ///
+    /// # 返回值
+    /// 该页原本在交换区中时返回 `true`
+    pub fn swap_in_page<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> Result<bool, page_table::PagingError> {
+        let Some(TrackedFrames::Swapped(slot)) = self.frames.get(&vpn) else {
+            return Ok(false);
+        };
+        kcov!();
+        debug_assert_eq!(page_table.swap_entry(vpn), Some(slot.index()));
+        let frame = Self::read_swapped(slot)?;
+        // 换出项不在 TLB 中，直接映射覆盖即可
+        page_table.map(vpn, frame.ppn(), PageSize::Size4K, self.permission.clone())?;
+        // 替换掉的 SwapSlot 在此归还槽位
+        self.frames
+            .insert(page_table.root_ppn(), vpn, TrackedFrames::Single(frame));
+        Ok(true)
+    }
+
+    /// 分配新帧并读入槽位中的数据
+    fn read_swapped(
+        slot: &swap::SwapSlot,
+    ) -> Result<crate::frame_allocator::FrameTracker, page_table::PagingError> {
+        let frame = alloc_frame().ok_or(page_table::PagingError::FrameAllocFailed)?;
+        let buf = unsafe {
+            core::slice::from_raw_parts_mut(
+                arch_ops().paddr_to_vaddr(frame.ppn().start_addr().as_usize()) as *mut u8,
+                mm_config().page_size(),
+            )
+        };
+        swap::swap_read(slot, buf).map_err(|_| page_table::PagingError::SwapFailed)?;
+        Ok(frame)
+    }
+
     /// 拆分区域为两部分
     pub fn split_at<PT: PageTableInner<E>, E: PageTableEntry>(
         mut self,
-        _page_table: &mut PT,
+        page_table: &mut PT,
         split_vpn: Vpn,
     ) -> Result<(Self, Self), page_table::PagingError> {
+        kcov!();
         if !self.vpn_range.contains(split_vpn) {
             return Err(page_table::PagingError::InvalidAddress);
         }
@@ -403,9 +1329,11 @@ impl MappingArea {
             return Err(page_table::PagingError::InvalidAddress);
         }
 
-        if self.map_type != MapType::Framed {
+        if !matches!(self.map_type, MapType::Framed | MapType::Reserved) {
             return Err(page_table::PagingError::UnsupportedMapType);
         }
+        // 跨越拆分点的大页先拆成小页
+        self.split_huge_at(page_table, split_vpn)?;
 
         let left_range = VpnRange::new(self.vpn_range.start(), split_vpn);
         let right_range = VpnRange::new(split_vpn, self.vpn_range.end());
@@ -429,17 +1357,15 @@ impl MappingArea {
             flags: f.flags,
         });
 
-        let mut left_area = MappingArea::new(
+        let mut left_area = self.derive(
             left_range,
-            self.area_type,
             self.map_type,
             self.permission.clone(),
             left_file,
         );
 
-        let mut right_area = MappingArea::new(
+        let mut right_area = self.derive(
             right_range,
-            self.area_type,
             self.map_type,
             self.permission.clone(),
             right_file,
@@ -449,9 +1375,13 @@ impl MappingArea {
         for vpn in vpns {
             if let Some(tracked_frames) = self.frames.remove(&vpn) {
                 if vpn < split_vpn {
-                    left_area.frames.insert(vpn, tracked_frames);
+                    left_area
+                        .frames
+                        .insert(page_table.root_ppn(), vpn, tracked_frames);
                 } else {
-                    right_area.frames.insert(vpn, tracked_frames);
+                    right_area
+                        .frames
+                        .insert(page_table.root_ppn(), vpn, tracked_frames);
                 }
             }
         }
@@ -467,6 +1397,7 @@ impl MappingArea {
         end_vpn: Vpn,
         new_perm: UniversalPTEFlag,
     ) -> Result<alloc::vec::Vec<Self>, page_table::PagingError> {
+        kcov!();
         let area_start = self.vpn_range.start();
         let area_end = self.vpn_range.end();
 
@@ -514,9 +1445,8 @@ impl MappingArea {
         });
 
         let mut left_area = if area_start < change_start {
-            Some(MappingArea::new(
+            Some(self.derive(
                 left_range,
-                self.area_type,
                 self.map_type,
                 self.permission.clone(),
                 left_file,
@@ -525,9 +1455,8 @@ impl MappingArea {
             None
         };
 
-        let mut middle_area = MappingArea::new(
+        let mut middle_area = self.derive(
             middle_range,
-            self.area_type,
             if wants_mapping {
                 MapType::Framed
             } else {
@@ -538,9 +1467,8 @@ impl MappingArea {
         );
 
         let mut right_area = if change_end < area_end {
-            Some(MappingArea::new(
+            Some(self.derive(
                 right_range,
-                self.area_type,
                 self.map_type,
                 self.permission.clone(),
                 right_file,
@@ -550,14 +1478,24 @@ impl MappingArea {
         };
 
         match self.map_type {
-            MapType::Direct => return Err(page_table::PagingError::UnsupportedMapType),
+            MapType::Direct | MapType::Fixed(_) => {
+                return Err(page_table::PagingError::UnsupportedMapType);
+            }
             MapType::Framed => {
+                // 跨越修改范围边界的大页先拆成小页，其余大页整体修改
+                self.split_huge_at(page_table, change_start)?;
+                self.split_huge_at(page_table, change_end)?;
                 if wants_mapping {
                     TlbBatchContextWrapper::execute(|batch| {
                         for vpn in VpnRange::new(change_start, change_end) {
+                            // 换出项没有权限位，换入时按新区域的权限映射；
+                            // 被 madvise 丢弃的页没有页表项，缺页时按新区域的权限映射
+                            if self.get_ppn(vpn).is_none() {
+                                continue;
+                            }
                             page_table.update_flags_with_batch(
                                 vpn,
-                                middle_area.permission.clone(),
+                                middle_area.pte_flags(self.frames.get(&vpn)),
                                 Some(batch),
                             )?;
                         }
@@ -566,15 +1504,14 @@ impl MappingArea {
 
                     for vpn in VpnRange::new(change_start, change_end) {
                         if let Some(tracked) = self.frames.remove(&vpn) {
-                            middle_area.frames.insert(vpn, tracked);
+                            middle_area
+                                .frames
+                                .insert(page_table.root_ppn(), vpn, tracked);
                         }
                     }
                 } else {
                     TlbBatchContextWrapper::execute(|batch| {
-                        for vpn in VpnRange::new(change_start, change_end) {
-                            self.unmap_one_with_batch(page_table, vpn, Some(batch))?;
-                        }
-                        Ok::<(), page_table::PagingError>(())
+                        self.unmap_range_with_batch(page_table, change_start, change_end, batch)
                     })?;
                 }
 
@@ -583,11 +1520,11 @@ impl MappingArea {
                     if let Some(tracked) = self.frames.remove(&vpn) {
                         if vpn < change_start {
                             if let Some(ref mut l) = left_area {
-                                l.frames.insert(vpn, tracked);
+                                l.frames.insert(page_table.root_ppn(), vpn, tracked);
                             }
                         } else if vpn >= change_end {
                             if let Some(ref mut r) = right_area {
-                                r.frames.insert(vpn, tracked);
+                                r.frames.insert(page_table.root_ppn(), vpn, tracked);
                             }
                         }
                     }
@@ -600,7 +1537,11 @@ impl MappingArea {
                             let frame =
                                 alloc_frame().ok_or(page_table::PagingError::FrameAllocFailed)?;
                             let ppn = frame.ppn();
-                            middle_area.frames.insert(vpn, TrackedFrames::Single(frame));
+                            middle_area.frames.insert(
+                                page_table.root_ppn(),
+                                vpn,
+                                TrackedFrames::Single(frame),
+                            );
                             page_table.map_with_batch(
                                 vpn,
                                 ppn,
@@ -633,6 +1574,7 @@ impl MappingArea {
         start_vpn: Vpn,
         end_vpn: Vpn,
     ) -> Result<Option<(Self, Option<Self>)>, page_table::PagingError> {
+        kcov!();
         let area_start = self.vpn_range.start();
         let area_end = self.vpn_range.end();
 
@@ -643,15 +1585,17 @@ impl MappingArea {
             return Ok(Some((self, None)));
         }
 
-        if self.map_type != MapType::Reserved {
-            TlbBatchContextWrapper::execute(|batch| {
-                for vpn in VpnRange::new(unmap_start, unmap_end) {
-                    self.unmap_one_with_batch(page_table, vpn, Some(batch))?;
-                }
-                Ok::<(), page_table::PagingError>(())
-            })?;
+        // 固定映射的物理页按区域起始页计算，只允许整体解除映射
+        if matches!(self.map_type, MapType::Fixed(_))
+            && (unmap_start != area_start || unmap_end != area_end)
+        {
+            return Err(page_table::PagingError::UnsupportedMapType);
         }
 
+        TlbBatchContextWrapper::execute(|batch| {
+            self.unmap_range_with_batch(page_table, unmap_start, unmap_end, batch)
+        })?;
+
         if unmap_start == area_start && unmap_end == area_end {
             return Ok(None);
         } else if unmap_start == area_start {
@@ -684,17 +1628,15 @@ impl MappingArea {
                 flags: f.flags,
             });
 
-            let mut left_area = MappingArea::new(
+            let mut left_area = self.derive(
                 left_range,
-                self.area_type,
                 self.map_type,
                 self.permission.clone(),
                 left_file,
             );
 
-            let mut right_area = MappingArea::new(
+            let mut right_area = self.derive(
                 right_range,
-                self.area_type,
                 self.map_type,
                 self.permission.clone(),
                 right_file,
@@ -704,9 +1646,13 @@ impl MappingArea {
             for vpn in vpns {
                 if let Some(tracked_frames) = self.frames.remove(&vpn) {
                     if vpn < unmap_start {
-                        left_area.frames.insert(vpn, tracked_frames);
+                        left_area
+                            .frames
+                            .insert(page_table.root_ppn(), vpn, tracked_frames);
                     } else if vpn >= unmap_end {
-                        right_area.frames.insert(vpn, tracked_frames);
+                        right_area
+                            .frames
+                            .insert(page_table.root_ppn(), vpn, tracked_frames);
                     }
                 }
             }
@@ -716,7 +1662,23 @@ impl MappingArea {
     }
 
     /// 从文件加载数据到已分配的物理页中
-    pub fn load_from_file(&mut self) -> Result<(), page_table::PagingError> {
+    ///
+    /// 文件提供页缓存时，共享映射改为直接映射缓存页，与 `read`/`write` 看到同一份数据。
+    pub fn load_from_file<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+    ) -> Result<(), page_table::PagingError> {
+        let range = self.vpn_range;
+        self.load_range_from_file(page_table, range)
+    }
+
+    /// 从文件加载 `range` 内的页，见 [`load_from_file`](Self::load_from_file)
+    fn load_range_from_file<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        range: VpnRange,
+    ) -> Result<(), page_table::PagingError> {
+        kcov!();
         if let Some(ref mmap_file) = self.file {
             let inode = mmap_file
                 .file
@@ -725,12 +1687,53 @@ impl MappingArea {
             let start_vpn = self.vpn_range.start();
             let page_size = mm_config().page_size();
 
-            for (vpn, tracked_frame) in &self.frames {
+            let cache = if mmap_file.flags.contains(MapFlags::SHARED) {
+                inode.page_cache()
+            } else {
+                None
+            };
+            if let Some(cache) = cache {
+                let permission = self.permission.clone();
+                let vpns: alloc::vec::Vec<Vpn> = self
+                    .frames
+                    .range(range.start()..range.end())
+                    .map(|(vpn, _)| *vpn)
+                    .collect();
+                return TlbBatchContextWrapper::execute(|batch| {
+                    for vpn in vpns {
+                        let file_offset =
+                            mmap_file.offset + (vpn.as_usize() - start_vpn.as_usize()) * page_size;
+                        // 经文件系统读入时缓存页已由其填充，这里的填充结果只在缓存被绕过时使用
+                        let page = cache
+                            .get_or_fill(file_offset / page_size, |buf| {
+                                inode.read_at(file_offset, buf).map(|_| ())
+                            })
+                            .map_err(|_| page_table::PagingError::InvalidAddress)?;
+                        page_table.unmap_with_batch(vpn, Some(batch))?;
+                        page_table.map_with_batch(
+                            vpn,
+                            page.ppn(),
+                            PageSize::Size4K,
+                            permission.clone(),
+                            Some(batch),
+                        )?;
+                        // 替换原先的匿名帧，反向映射随之改为缓存页
+                        self.frames
+                            .insert(page_table.root_ppn(), vpn, TrackedFrames::Cached(page));
+                    }
+                    Ok(())
+                });
+            }
+
+            for (vpn, tracked_frame) in self.frames.range(range.start()..range.end()) {
                 let page_offset = vpn.as_usize() - start_vpn.as_usize();
                 let file_offset = mmap_file.offset + page_offset * page_size;
 
                 let ppn = match tracked_frame {
-                    TrackedFrames::Single(frame) => frame.ppn(),
+                    TrackedFrames::Single(frame) | TrackedFrames::LazyFree(frame) => frame.ppn(),
+                    TrackedFrames::Shared(frame) => frame.ppn(),
+                    TrackedFrames::Cached(page) => page.ppn(),
+                    TrackedFrames::Swapped(_) => continue,
                     TrackedFrames::Multiple(frames) => frames.first().map(|f| f.ppn()).unwrap(),
                     TrackedFrames::Contiguous(_) => {
                         panic!("当前实现不支持连续帧");
@@ -773,6 +1776,7 @@ impl MappingArea {
         &self,
         page_table: &mut PT,
     ) -> Result<(), page_table::PagingError> {
+        kcov!();
         if let Some(ref mmap_file) = self.file {
             if !mmap_file.flags.contains(MapFlags::SHARED) {
                 return Ok(());
@@ -784,6 +1788,7 @@ impl MappingArea {
                 .map_err(|_| page_table::PagingError::InvalidAddress)?;
             let start_vpn = self.vpn_range.start();
             let page_size = mm_config().page_size();
+            let mut cached_dirty = false;
 
             TlbBatchContextWrapper::execute(|batch| {
                 for (vpn, tracked_frame) in &self.frames {
@@ -796,11 +1801,28 @@ impl MappingArea {
                         continue;
                     }
 
+                    if let TrackedFrames::Cached(page) = tracked_frame {
+                        // 缓存页就是文件数据本身，记为脏页后统一写回
+                        page.mark_dirty();
+                        cached_dirty = true;
+                        page_table.update_flags_with_batch(
+                            *vpn,
+                            flags & !UniversalPTEFlag::DIRTY,
+                            Some(batch),
+                        )?;
+                        continue;
+                    }
+
                     let page_offset = vpn.as_usize() - start_vpn.as_usize();
                     let file_offset = mmap_file.offset + page_offset * page_size;
 
                     let ppn = match tracked_frame {
-                        TrackedFrames::Single(frame) => frame.ppn(),
+                        TrackedFrames::Single(frame) | TrackedFrames::LazyFree(frame) => {
+                            frame.ppn()
+                        }
+                        TrackedFrames::Shared(frame) => frame.ppn(),
+                        TrackedFrames::Cached(page) => page.ppn(),
+                        TrackedFrames::Swapped(_) => continue,
                         TrackedFrames::Multiple(frames) => frames.first().map(|f| f.ppn()).unwrap(),
                         TrackedFrames::Contiguous(_) => {
                             panic!("当前实现不支持连续帧");
@@ -843,21 +1865,32 @@ impl MappingArea {
                     )?;
                 }
                 Ok(())
-            })
-        } else {
-            Ok(())
+            })?;
+
+            if cached_dirty {
+                inode
+                    .writeback()
+                    .map_err(|_| page_table::PagingError::InvalidAddress)?;
+            }
         }
+        Ok(())
     }
 }
 
 /// 动态扩展和收缩
 impl MappingArea {
     /// 通过在末尾添加页来扩展区域（仅限 4K 页）
+    ///
+    /// 文件映射的新增页接着原映射读入文件中对应位置的内容。
     pub fn extend<PT: PageTableInner<E>, E: PageTableEntry>(
         &mut self,
         page_table: &mut PT,
         count: usize,
     ) -> Result<Vpn, page_table::PagingError> {
+        kcov!();
+        if self.hugetlb {
+            return Err(page_table::PagingError::UnsupportedMapType);
+        }
         let old_end = self.vpn_range.end();
         let new_end = Vpn::from_usize(old_end.as_usize() + count);
 
@@ -868,15 +1901,77 @@ impl MappingArea {
 
         self.vpn_range = VpnRange::new(self.vpn_range.start(), new_end);
 
+        if let Some(file) = self.file.as_mut() {
+            file.len = self.vpn_range.len() * mm_config().page_size();
+            self.load_range_from_file(page_table, VpnRange::new(old_end, new_end))?;
+        }
+
         Ok(new_end)
     }
 
+    /// 把整个区域移动到从 `new_start` 开始的地址（mremap）
+    ///
+    /// 页表项随之移动，物理帧和其中的数据不复制。换出的页先换入；
+    /// 移动距离不是 2M 的整数倍时，大页先拆分为 4K 页。
+    /// 调用者保证新范围不与任何区域（包括本区域的原范围）重叠。
+    pub fn move_to<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        new_start: Vpn,
+    ) -> Result<(), page_table::PagingError> {
+        kcov!();
+        let old_start = self.vpn_range.start();
+        let new_range = VpnRange::new(
+            new_start,
+            Vpn::from_usize(new_start.as_usize() + self.vpn_range.len()),
+        );
+        match self.map_type {
+            MapType::Reserved => {}
+            MapType::Framed => {
+                let huge_pages = PageSize::Size2M.pages();
+                let keep_huge =
+                    new_start.as_usize() % huge_pages == old_start.as_usize() % huge_pages;
+                let vpns: alloc::vec::Vec<Vpn> = self.frames.keys().copied().collect();
+                for vpn in vpns {
+                    self.swap_in_page(page_table, vpn)?;
+                    if !keep_huge {
+                        self.split_huge(page_table, vpn)?;
+                    }
+                }
+
+                let shift = |vpn: Vpn| {
+                    Vpn::from_usize(vpn.as_usize() - old_start.as_usize() + new_start.as_usize())
+                };
+                let vpns: alloc::vec::Vec<Vpn> = self.frames.keys().copied().collect();
+                TlbBatchContextWrapper::execute(|batch| {
+                    for vpn in vpns {
+                        // 保留原页表项的标志位（写时复制共享的页仍为只读）
+                        let (ppn, size, flags) = page_table.walk(vpn)?;
+                        page_table.unmap_with_batch(vpn, Some(batch))?;
+                        page_table.map_with_batch(shift(vpn), ppn, size, flags, Some(batch))?;
+                        if let Some(tracked) = self.frames.remove(&vpn) {
+                            self.frames
+                                .insert(page_table.root_ppn(), shift(vpn), tracked);
+                        }
+                    }
+                    Ok::<(), page_table::PagingError>(())
+                })?;
+            }
+            MapType::Direct | MapType::Fixed(_) => {
+                return Err(page_table::PagingError::UnsupportedMapType);
+            }
+        }
+        self.vpn_range = new_range;
+        Ok(())
+    }
+
     /// 通过从末尾移除页来收缩区域（仅限 4K 页）
     pub fn shrink<PT: PageTableInner<E>, E: PageTableEntry>(
         &mut self,
         page_table: &mut PT,
         count: usize,
     ) -> Result<Vpn, page_table::PagingError> {
+        kcov!();
         if count > self.vpn_range.len() {
             return Err(page_table::PagingError::ShrinkBelowStart);
         }
@@ -894,3 +1989,194 @@ impl MappingArea {
         Ok(new_end)
     }
 }
+
+/// madvise 建议的处理
+impl MappingArea {
+    /// 丢弃 `[start, end)` 内的页（MADV_DONTNEED）
+    ///
+    /// 解除映射并释放物理帧或交换槽位，区域本身保留；之后访问时由
+    /// [`handle_demand_fault`](Self::handle_demand_fault) 重新建立映射：匿名页填零，
+    /// 文件映射重新读入文件内容。共享文件映射先把脏页写回文件。
+    pub fn discard_range<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        start: Vpn,
+        end: Vpn,
+    ) -> Result<(), page_table::PagingError> {
+        kcov!();
+        match self.map_type {
+            MapType::Framed => {}
+            MapType::Reserved => return Ok(()),
+            MapType::Direct | MapType::Fixed(_) => {
+                return Err(page_table::PagingError::UnsupportedMapType);
+            }
+        }
+        if !self.is_private() {
+            self.sync_file(page_table)?;
+        }
+        TlbBatchContextWrapper::execute(|batch| {
+            self.unmap_range_with_batch(page_table, start, end, batch)
+        })
+    }
+
+    /// 把 `[start, end)` 内的页标记为可丢弃（MADV_FREE），只适用于大页池以外的私有匿名映射
+    ///
+    /// 独占的帧改为 [`TrackedFrames::LazyFree`] 并去掉页表项的写权限，换出的页直接归还槽位；
+    /// 写时复制共享的帧保持不变，大页先拆分为 4K 页。
+    ///
+    /// # 返回值
+    /// 被标记的页，由调用者交给回收
+    pub fn lazy_free_range<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        start: Vpn,
+        end: Vpn,
+    ) -> Result<alloc::vec::Vec<Vpn>, page_table::PagingError> {
+        kcov!();
+        match self.map_type {
+            MapType::Framed if self.file.is_none() && !self.hugetlb => {}
+            MapType::Reserved => return Ok(alloc::vec::Vec::new()),
+            _ => return Err(page_table::PagingError::UnsupportedMapType),
+        }
+        let flags = self.permission.clone() - UniversalPTEFlag::WRITEABLE;
+        let mut marked = alloc::vec::Vec::new();
+        TlbBatchContextWrapper::execute(|batch| {
+            for vpn in VpnRange::new(start, end) {
+                self.split_huge(page_table, vpn)?;
+                match self.frames.get(&vpn) {
+                    Some(TrackedFrames::Single(_)) => {
+                        page_table.update_flags_with_batch(vpn, flags.clone(), Some(batch))?;
+                        let Some(TrackedFrames::Single(frame)) = self.frames.remove(&vpn) else {
+                            unreachable!();
+                        };
+                        self.frames.insert(
+                            page_table.root_ppn(),
+                            vpn,
+                            TrackedFrames::LazyFree(frame),
+                        );
+                        marked.push(vpn);
+                    }
+                    Some(TrackedFrames::Swapped(_)) => {
+                        // 换出的内容同样可以丢弃，清除换出项后槽位随之释放
+                        page_table.clear_swap_entry(vpn)?;
+                        self.frames.remove(&vpn);
+                    }
+                    _ => {}
+                }
+            }
+            Ok::<(), page_table::PagingError>(())
+        })?;
+        Ok(marked)
+    }
+
+    /// 回收 `vpn` 处可丢弃的帧：解除映射后直接释放，不写交换区
+    fn discard_lazy_free<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> ReclaimResult {
+        if TlbBatchContextWrapper::execute(|batch| page_table.unmap_with_batch(vpn, Some(batch)))
+            .is_err()
+        {
+            return ReclaimResult::Gone;
+        }
+        kcov!();
+        // 移除的 FrameTracker 在此释放物理帧，再次访问时填零
+        self.frames.remove(&vpn);
+        ReclaimResult::Reclaimed
+    }
+
+    /// 预先建立 `[start, end)` 内的映射（MADV_WILLNEED）
+    ///
+    /// 换出的页换入，被丢弃的页按 [`handle_demand_fault`](Self::handle_demand_fault) 重新填充。
+    pub fn populate_range<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        start: Vpn,
+        end: Vpn,
+    ) -> Result<(), page_table::PagingError> {
+        if self.map_type != MapType::Framed {
+            return Ok(());
+        }
+        kcov!();
+        for vpn in VpnRange::new(start, end) {
+            if !self.swap_in_page(page_table, vpn)? {
+                self.handle_demand_fault(page_table, vpn)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 处理访问 `vpn` 处没有页表项的页引起的缺页（被 madvise 丢弃或回收的页）
+    ///
+    /// 分配新帧按区域权限映射：匿名页填零，文件映射读入文件中对应位置的内容；
+    /// 大页池的区域从池中取一个大页映射 `vpn` 所在的 2M 范围。
+    ///
+    /// # 返回值
+    /// 缺页已解决时返回 `true`；`vpn` 已有映射、已换出或不属于帧映射时返回 `false`
+    pub fn handle_demand_fault<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+    ) -> Result<bool, page_table::PagingError> {
+        if self.map_type != MapType::Framed
+            || !self.vpn_range.contains(vpn)
+            || self.frames.contains_key(&vpn)
+            || self.get_ppn(vpn).is_some()
+        {
+            return Ok(false);
+        }
+        kcov!();
+        if self.hugetlb {
+            let head = Vpn::from_usize(vpn.as_usize() & !(PageSize::Size2M.pages() - 1));
+            self.map_hugetlb_page(page_table, head, None)?;
+            return Ok(true);
+        }
+        self.map_one(page_table, vpn)?;
+        if self.file.is_some() {
+            self.load_range_from_file(
+                page_table,
+                VpnRange::new(vpn, Vpn::from_usize(vpn.as_usize() + 1)),
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// 为没有页表项的 `vpn` 分配新帧，写入 `data` 后按区域权限映射（userfaultfd 的 UFFDIO_COPY）
+    ///
+    /// `data` 为 `None` 或不足一页时其余部分为零。内容在建立页表项之前写入，
+    /// 其它线程一旦能访问该页，看到的就是完整的内容。
+    ///
+    /// # 返回值
+    /// `vpn` 已有映射或已换出时返回 [`PagingError::AlreadyMapped`](page_table::PagingError::AlreadyMapped)
+    pub fn fill_missing_page<PT: PageTableInner<E>, E: PageTableEntry>(
+        &mut self,
+        page_table: &mut PT,
+        vpn: Vpn,
+        data: Option<&[u8]>,
+    ) -> Result<(), page_table::PagingError> {
+        if self.map_type != MapType::Framed || self.hugetlb || !self.vpn_range.contains(vpn) {
+            return Err(page_table::PagingError::UnsupportedMapType);
+        }
+        if self.frames.contains_key(&vpn) || self.get_ppn(vpn).is_some() {
+            return Err(page_table::PagingError::AlreadyMapped);
+        }
+        kcov!();
+        let frame = alloc_frame().ok_or(page_table::PagingError::FrameAllocFailed)?;
+        if let Some(data) = data {
+            let len = data.len().min(mm_config().page_size());
+            // SAFETY: 新分配的帧由本函数独占，线性映射地址可写
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    data.as_ptr(),
+                    arch_ops().paddr_to_vaddr(frame.ppn().start_addr().as_usize()) as *mut u8,
+                    len,
+                );
+            }
+        }
+        page_table.map(vpn, frame.ppn(), PageSize::Size4K, self.permission.clone())?;
+        self.frames
+            .insert(page_table.root_ppn(), vpn, TrackedFrames::Single(frame));
+        Ok(())
+    }
+}
@@ -5,9 +5,10 @@
 
 /// 映射区域（VMA/MappingArea）相关定义与操作。
 pub mod mapping_area;
+mod frame_map;
 mod mmap_file;
 mod space;
 
-pub use mapping_area::{AreaType, MapType, MappingArea};
+pub use mapping_area::{AreaType, AreaUsage, MapType, MappingArea};
 pub use mmap_file::MmapFile;
 pub use space::*;
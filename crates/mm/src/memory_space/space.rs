@@ -11,6 +11,7 @@
 //! - [`MapType::Direct`]：直接映射（通常用于内核映射）
 //! - [`MapType::Framed`]：按需分配物理页帧并映射
 //! - [`MapType::Reserved`]：仅“保留虚拟地址范围”，不建立页表映射（便于延迟分配或占位）
+//! - [`MapType::Fixed`]：映射到内核持有的固定物理页（如 vDSO），可在多个地址空间间共享
 //!
 //! [`AreaType`] 用于标注区域用途（内核 text/data/heap、用户 stack/heap 等），
 //! 便于在 fork/clone、权限控制等场景做策略判断。
@@ -18,7 +19,7 @@
 //! ## 跨页读写
 //!
 //! 为支持系统调用在用户地址空间中跨页读写，本模块提供 `read_bytes_at`/`write_bytes_at`
-//! 等 helper：它们会逐页翻译虚拟地址并进行拷贝，自动处理页边界。
+//! 等 helper：它们会逐页翻译虚拟地址并进行拷贝，自动处理页边界；换出到交换区的页会先被换入。
 //!
 //! ## 文件映射与回写
 //!
@@ -34,6 +35,7 @@ use crate::memory_space::MmapFile;
 use crate::memory_space::mapping_area::{AreaType, MapType, MappingArea};
 use crate::mm_config;
 use crate::page_table::{PageTableEntry, PageTableInner, PagingError, UniversalPTEFlag};
+use crate::swap::ReclaimResult;
 use alloc::vec::Vec;
 
 /// 表示地址空间的内存空间结构体
@@ -117,6 +119,10 @@ impl<PT: PageTableInner<E>, E: PageTableEntry> MemorySpace<PT, E> {
         let mut written = 0usize;
         while written < bytes.len() {
             let cur_va = va.checked_add(written).ok_or(PagingError::InvalidAddress)?;
+            let vpn = Vpn::from_addr_floor(Vaddr::from_usize(cur_va));
+            self.swap_in_page(vpn)?;
+            // 直接写物理帧绕过了页表的写保护，写时复制共享的页须先变为独占
+            self.unshare_page(vpn)?;
             let paddr = self
                 .page_table
                 .translate(Vaddr::from_usize(cur_va))
@@ -136,8 +142,8 @@ impl<PT: PageTableInner<E>, E: PageTableEntry> MemorySpace<PT, E> {
         Ok(())
     }
 
-    /// 从指定虚拟地址读取字节序列（跨页安全）
-    pub fn read_bytes_at(&self, va: usize, out: &mut [u8]) -> Result<(), PagingError> {
+    /// 从指定虚拟地址读取字节序列（跨页安全），换出的页会先被换入
+    pub fn read_bytes_at(&mut self, va: usize, out: &mut [u8]) -> Result<(), PagingError> {
         if out.is_empty() {
             return Ok(());
         }
@@ -146,6 +152,7 @@ impl<PT: PageTableInner<E>, E: PageTableEntry> MemorySpace<PT, E> {
         let mut read = 0usize;
         while read < out.len() {
             let cur_va = va.checked_add(read).ok_or(PagingError::InvalidAddress)?;
+            self.swap_in_page(Vpn::from_addr_floor(Vaddr::from_usize(cur_va)))?;
             let paddr = self
                 .page_table
                 .translate(Vaddr::from_usize(cur_va))
@@ -165,14 +172,14 @@ impl<PT: PageTableInner<E>, E: PageTableEntry> MemorySpace<PT, E> {
     }
 
     /// 读取 u64
-    pub fn read_u64_at(&self, va: usize) -> Result<u64, PagingError> {
+    pub fn read_u64_at(&mut self, va: usize) -> Result<u64, PagingError> {
         let mut buf = [0u8; 8];
         self.read_bytes_at(va, &mut buf)?;
         Ok(u64::from_le_bytes(buf))
     }
 
     /// 读取 i64
-    pub fn read_i64_at(&self, va: usize) -> Result<i64, PagingError> {
+    pub fn read_i64_at(&mut self, va: usize) -> Result<i64, PagingError> {
         let mut buf = [0u8; 8];
         self.read_bytes_at(va, &mut buf)?;
         Ok(i64::from_le_bytes(buf))
@@ -332,10 +339,13 @@ impl<PT: PageTableInner<E>, E: PageTableEntry> MemorySpace<PT, E> {
     }
 
     /// 克隆内存空间（用于 fork）
-    pub fn clone_for_fork(&self) -> Result<Self, PagingError> {
+    ///
+    /// 用户私有映射以写时复制方式共享物理帧，父进程中这些页的写权限也会被撤销，
+    /// 之后的写缺页由 [`handle_cow_fault`](Self::handle_cow_fault) 处理。
+    pub fn clone_for_fork(&mut self) -> Result<Self, PagingError> {
         let mut new_space = Self::new();
 
-        for area in &self.areas {
+        for area in self.areas.iter_mut() {
             let is_kernel = matches!(
                 area.area_type(),
                 AreaType::KernelText
@@ -353,8 +363,8 @@ impl<PT: PageTableInner<E>, E: PageTableEntry> MemorySpace<PT, E> {
                 new_area.map(&mut new_space.page_table)?;
                 new_space.areas.push(new_area);
             } else {
-                // 用户区域：克隆数据
-                let new_area = area.clone_with_data(&mut new_space.page_table)?;
+                // 用户区域：写时复制
+                let new_area = area.clone_cow(&mut self.page_table, &mut new_space.page_table)?;
                 new_space.areas.push(new_area);
             }
         }
@@ -364,6 +374,80 @@ impl<PT: PageTableInner<E>, E: PageTableEntry> MemorySpace<PT, E> {
         Ok(new_space)
     }
 
+    /// 处理 `vaddr` 处的写缺页
+    ///
+    /// # 返回值
+    /// 缺页由写时复制引起并已解决时返回 `true`，调用者重新执行出错指令即可；
+    /// 否则是真正的访问违例，返回 `false`
+    pub fn handle_cow_fault(&mut self, vaddr: Vaddr) -> bool {
+        let vpn = Vpn::from_addr_floor(vaddr);
+        let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        else {
+            return false;
+        };
+        area.handle_cow_fault(&mut self.page_table, vpn)
+            .unwrap_or(false)
+    }
+
+    /// 让 `vpn` 处的写时复制共享页变为独占，`vpn` 不在任何区域内时什么都不做
+    fn unshare_page(&mut self, vpn: Vpn) -> Result<(), PagingError> {
+        if let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            area.unshare_page(&mut self.page_table, vpn)?;
+        }
+        Ok(())
+    }
+
+    /// 处理访问换出页引起的缺页：把 `vaddr` 所在的页换入
+    ///
+    /// # 返回值
+    /// 该页在交换区中并已换入时返回 `true`
+    pub fn handle_swap_fault(&mut self, vaddr: Vaddr) -> bool {
+        let vpn = Vpn::from_addr_floor(vaddr);
+        if self.page_table.swap_entry(vpn).is_none() {
+            return false;
+        }
+        let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        else {
+            return false;
+        };
+        area.swap_in_page(&mut self.page_table, vpn)
+            .unwrap_or(false)
+    }
+
+    /// 尝试把 `vpn` 处的页换出到交换区
+    pub fn swap_out_page(&mut self, vpn: Vpn) -> ReclaimResult {
+        match self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            Some(area) => area.swap_out_page(&mut self.page_table, vpn),
+            None => ReclaimResult::Gone,
+        }
+    }
+
+    /// 把 `vpn` 处换出的页换入，`vpn` 不在任何区域内或未被换出时什么都不做
+    fn swap_in_page(&mut self, vpn: Vpn) -> Result<(), PagingError> {
+        if let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            area.swap_in_page(&mut self.page_table, vpn)?;
+        }
+        Ok(())
+    }
+
     /// 扩展堆
     pub fn extend_heap(&mut self, new_end: Vpn) -> Result<(), PagingError> {
         let heap_area = self
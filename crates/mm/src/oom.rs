@@ -0,0 +1,125 @@
+//! 内存耗尽（OOM）处理
+//!
+//! 帧分配在回收之后仍然失败时调用 [`out_of_memory`]，由 os crate 注册的 [`OomOps`]
+//! 选出一个进程杀死并释放它的内存，随后分配重试。
+//!
+//! 进程的 badness 与 Linux 相同：常驻页数加上按总页数千分比折算的 `oom_score_adj`，
+//! `oom_score_adj` 为 [`OOM_SCORE_ADJ_MIN`] 的进程不会被选中。
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// `oom_score_adj` 的最小值，表示永不被 OOM killer 选中
+pub const OOM_SCORE_ADJ_MIN: i32 = -1000;
+/// `oom_score_adj` 的最大值
+pub const OOM_SCORE_ADJ_MAX: i32 = 1000;
+
+/// 由 os crate 实现的 OOM 操作
+pub trait OomOps: Send + Sync {
+    /// 选出 badness 最高的进程，发送 SIGKILL 并释放它的内存
+    ///
+    /// 与回收相同，可能发生在任意分配帧的位置，实现只能尝试加锁。
+    ///
+    /// # 返回值
+    /// 释放了物理帧、值得重试分配时返回 `true`
+    fn kill_victim(&self) -> bool;
+}
+
+static OOM_OPS_DATA: AtomicUsize = AtomicUsize::new(0);
+static OOM_OPS_VTABLE: AtomicUsize = AtomicUsize::new(0);
+
+/// 注册 OOM 操作实现
+///
+/// # Safety
+/// 必须在单线程环境下调用，且只能调用一次
+pub unsafe fn register_oom_ops(ops: &'static dyn OomOps) {
+    let ptr = ops as *const dyn OomOps;
+    // SAFETY: 将 fat pointer 拆分为 data 和 vtable 两部分存储
+    let (data, vtable) = unsafe { core::mem::transmute::<*const dyn OomOps, (usize, usize)>(ptr) };
+    OOM_OPS_VTABLE.store(vtable, Ordering::Release);
+    OOM_OPS_DATA.store(data, Ordering::Release);
+}
+
+fn oom_ops() -> Option<&'static dyn OomOps> {
+    let data = OOM_OPS_DATA.load(Ordering::Acquire);
+    let vtable = OOM_OPS_VTABLE.load(Ordering::Acquire);
+    if data == 0 {
+        return None;
+    }
+    // SAFETY: 重组 fat pointer
+    Some(unsafe { &*core::mem::transmute::<(usize, usize), *const dyn OomOps>((data, vtable)) })
+}
+
+/// 防止杀进程释放内存的过程中分配帧再次进入 OOM 处理
+static OOM_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 内存耗尽时杀死一个进程
+///
+/// 没有注册 [`OomOps`] 或已在处理中时什么都不做。
+///
+/// # 返回值
+/// 释放了物理帧、调用者应当重试分配时返回 `true`
+pub fn out_of_memory() -> bool {
+    kcov!();
+    let Some(ops) = oom_ops() else {
+        return false;
+    };
+    if OOM_RUNNING.swap(true, Ordering::Acquire) {
+        return false;
+    }
+    let freed = ops.kill_victim();
+    OOM_RUNNING.store(false, Ordering::Release);
+    freed
+}
+
+/// 计算进程的 badness
+///
+/// # 参数
+/// * `rss_pages` - 进程的常驻页数
+/// * `oom_score_adj` - 进程的 `oom_score_adj`
+/// * `total_pages` - 系统物理页总数
+///
+/// # 返回值
+/// 不可被选中（`oom_score_adj` 为 [`OOM_SCORE_ADJ_MIN`]）时返回 `None`；
+/// 否则返回 badness，可被选中的进程至少为 1。
+pub fn oom_badness(rss_pages: usize, oom_score_adj: i32, total_pages: usize) -> Option<usize> {
+    if oom_score_adj <= OOM_SCORE_ADJ_MIN {
+        return None;
+    }
+    let adj = oom_score_adj.min(OOM_SCORE_ADJ_MAX) as isize * (total_pages / 1000) as isize;
+    let points = (rss_pages as isize).saturating_add(adj);
+    Some(points.max(1) as usize)
+}
+
+/// 把 badness 折算为 `/proc/[pid]/oom_score` 显示的 0..=1000 分数
+pub fn oom_score(badness: usize, total_pages: usize) -> usize {
+    if total_pages == 0 {
+        return 0;
+    }
+    (badness.saturating_mul(1000) / total_pages).min(1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oom_badness_adj() {
+        // 没有调整时等于常驻页数
+        assert_eq!(oom_badness(300, 0, 100_000), Some(300));
+        // 调整值按总页数的千分比折算
+        assert_eq!(oom_badness(300, 500, 100_000), Some(50_300));
+        assert_eq!(oom_badness(300, -2, 100_000), Some(100));
+        // 减到非正数时仍可被选中
+        assert_eq!(oom_badness(300, -999, 100_000), Some(1));
+        // -1000 永不被选中
+        assert_eq!(oom_badness(300, OOM_SCORE_ADJ_MIN, 100_000), None);
+    }
+
+    #[test]
+    fn test_oom_score_scale() {
+        assert_eq!(oom_score(0, 0), 0);
+        assert_eq!(oom_score(500, 1000), 500);
+        assert_eq!(oom_score(50_300, 100_000), 503);
+        assert_eq!(oom_score(200_000, 100_000), 1000);
+    }
+}
@@ -0,0 +1,265 @@
+//! 页缓存
+//!
+//! [`PageCache`] 以“文件内页号”为键缓存一个文件的数据页。文件系统的读写、共享文件映射和
+//! 写回都经过同一份缓存，看到的是同一个物理页：
+//!
+//! - **读**：页不在缓存中时由文件系统提供的填充函数从存储设备读入，之后直接从缓存复制；
+//! - **写**：文件系统写穿到存储设备后，调用 [`update`](PageCache::update) 同步已缓存的页；
+//! - **共享映射**：缓存页直接映射进用户地址空间（[`TrackedFrames::Cached`]），
+//!   `msync`/`munmap` 时把硬件标记为脏的页记为脏页，再由 [`writeback`](PageCache::writeback) 写回。
+//!
+//! 页缓存不依赖具体文件系统，填充和写回都通过闭包完成。
+//! 缓存页与脏页的数量计入 [`vmstat`](crate::vmstat)。
+//! 同一个文件可能同时存在多个 inode 对象，它们应通过 [`PageCacheRegistry`] 共享同一个缓存实例。
+//!
+//! [`TrackedFrames::Cached`]: crate::frame_allocator::TrackedFrames::Cached
+
+use alloc::collections::btree_map::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use sync::SpinLock;
+
+use crate::address::{PageNum, Ppn, UsizeConvert};
+use crate::arch_ops::arch_ops;
+use crate::frame_allocator::{FrameTracker, alloc_frame};
+use crate::mm_config;
+use crate::vmstat::{self, NrItem};
+
+/// 缓存中的一个数据页
+#[derive(Debug)]
+pub struct CachedPage {
+    frame: FrameTracker,
+    dirty: AtomicBool,
+}
+
+impl CachedPage {
+    /// 页所在的物理页号
+    pub fn ppn(&self) -> Ppn {
+        self.frame.ppn()
+    }
+
+    /// 是否有尚未写回的修改
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// 标记为脏页，下一次写回时写入存储设备
+    pub fn mark_dirty(&self) {
+        if !self.dirty.swap(true, Ordering::AcqRel) {
+            vmstat::inc_state(NrItem::FileDirty);
+        }
+    }
+
+    /// 从页内 `offset` 处复制数据到 `buf`
+    pub fn read(&self, offset: usize, buf: &mut [u8]) {
+        assert!(offset + buf.len() <= mm_config().page_size());
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.as_ptr().add(offset), buf.as_mut_ptr(), buf.len());
+        }
+    }
+
+    /// 把 `data` 复制到页内 `offset` 处
+    pub fn write(&self, offset: usize, data: &[u8]) {
+        assert!(offset + data.len() <= mm_config().page_size());
+        // 调用者可能直接传入映射到同一页的用户缓冲区，源和目标可能重叠
+        unsafe {
+            core::ptr::copy(data.as_ptr(), self.as_ptr().add(offset), data.len());
+        }
+    }
+
+    fn as_ptr(&self) -> *mut u8 {
+        arch_ops().paddr_to_vaddr(self.ppn().start_addr().as_usize()) as *mut u8
+    }
+
+    /// 整页内容
+    ///
+    /// # Safety
+    /// 页可能同时映射在用户地址空间中被修改，调用者只能把它当作一次快照使用。
+    unsafe fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), mm_config().page_size()) }
+    }
+}
+
+impl Drop for CachedPage {
+    fn drop(&mut self) {
+        vmstat::dec_state(NrItem::FilePages);
+        if self.is_dirty() {
+            vmstat::dec_state(NrItem::FileDirty);
+        }
+    }
+}
+
+/// 单个文件的页缓存
+#[derive(Debug)]
+pub struct PageCache {
+    pages: SpinLock<BTreeMap<usize, Arc<CachedPage>>>,
+}
+
+impl PageCache {
+    /// 创建空的页缓存
+    pub const fn new() -> Self {
+        Self {
+            pages: SpinLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// 查找第 `index` 页，不在缓存中时返回 `None`
+    pub fn get(&self, index: usize) -> Option<Arc<CachedPage>> {
+        self.pages.lock().get(&index).cloned()
+    }
+
+    /// 获取第 `index` 页，不在缓存中时分配新页并调用 `fill` 填充
+    ///
+    /// `fill` 收到一个已清零的整页缓冲区，不需要填满（文件末尾之后保持为零）。
+    /// 并发填充同一页时只保留先插入的一份。
+    ///
+    /// # 返回值
+    /// 分配失败返回 `-ENOMEM`，填充失败原样返回 `fill` 的错误码
+    pub fn get_or_fill<F>(&self, index: usize, fill: F) -> Result<Arc<CachedPage>, isize>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), isize>,
+    {
+        if let Some(page) = self.get(index) {
+            return Ok(page);
+        }
+        kcov!();
+        let frame = alloc_frame().ok_or(-(uapi::errno::ENOMEM as isize))?;
+        let page = Arc::new(CachedPage {
+            frame,
+            dirty: AtomicBool::new(false),
+        });
+        vmstat::inc_state(NrItem::FilePages);
+        // 填充时不持有缓存锁，读存储设备可能睡眠
+        let buf =
+            unsafe { core::slice::from_raw_parts_mut(page.as_ptr(), mm_config().page_size()) };
+        fill(buf)?;
+        Ok(self.pages.lock().entry(index).or_insert(page).clone())
+    }
+
+    /// 用从文件偏移 `offset` 开始写入的 `data` 更新已缓存的页，不缓存新页
+    pub fn update(&self, offset: usize, data: &[u8]) {
+        let page_size = mm_config().page_size();
+        let mut done = 0;
+        while done < data.len() {
+            let pos = offset + done;
+            let page_off = pos % page_size;
+            let take = min(data.len() - done, page_size - page_off);
+            if let Some(page) = self.get(pos / page_size) {
+                page.write(page_off, &data[done..done + take]);
+            }
+            done += take;
+        }
+    }
+
+    /// 文件被截断到 `size` 字节：丢弃之后的页，并清零最后一页中超出 `size` 的部分
+    pub fn truncate(&self, size: usize) {
+        let page_size = mm_config().page_size();
+        let mut pages = self.pages.lock();
+        drop(pages.split_off(&size.div_ceil(page_size)));
+        let tail_off = size % page_size;
+        if tail_off == 0 {
+            return;
+        }
+        if let Some(page) = pages.get(&(size / page_size)) {
+            unsafe {
+                core::ptr::write_bytes(page.as_ptr().add(tail_off), 0, page_size - tail_off);
+            }
+        }
+    }
+
+    /// 把所有脏页交给 `write` 写回，`write` 收到页号和整页内容
+    ///
+    /// 写回前先清除脏标记，写回期间再次被弄脏的页留到下一次写回。
+    /// 某一页写回失败时恢复其脏标记并立即返回错误。
+    pub fn writeback<F>(&self, mut write: F) -> Result<(), isize>
+    where
+        F: FnMut(usize, &[u8]) -> Result<(), isize>,
+    {
+        let dirty: Vec<_> = self
+            .pages
+            .lock()
+            .iter()
+            .filter(|(_, page)| page.is_dirty())
+            .map(|(index, page)| (*index, page.clone()))
+            .collect();
+        for (index, page) in dirty {
+            if !page.dirty.swap(false, Ordering::AcqRel) {
+                continue;
+            }
+            vmstat::dec_state(NrItem::FileDirty);
+            // Safety: 写回的是一次快照，之后的修改会重新标记脏页
+            if let Err(e) = write(index, unsafe { page.as_slice() }) {
+                page.mark_dirty();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 丢弃未被映射且不脏的页，返回丢弃的页数
+    pub fn shrink(&self) -> usize {
+        let mut pages = self.pages.lock();
+        let before = pages.len();
+        pages.retain(|_, page| page.is_dirty() || Arc::strong_count(page) > 1);
+        before - pages.len()
+    }
+
+    /// 已缓存的页数
+    pub fn len(&self) -> usize {
+        self.pages.lock().len()
+    }
+
+    /// 缓存是否为空
+    pub fn is_empty(&self) -> bool {
+        self.pages.lock().is_empty()
+    }
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 按键共享页缓存实例，只持有弱引用：没有 inode 对象再使用时缓存随之释放
+#[derive(Debug)]
+pub struct PageCacheRegistry<K> {
+    caches: SpinLock<BTreeMap<K, Weak<PageCache>>>,
+}
+
+impl<K: Ord> PageCacheRegistry<K> {
+    /// 创建空的注册表
+    pub const fn new() -> Self {
+        Self {
+            caches: SpinLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// 返回 `key` 对应的页缓存，不存在时创建
+    pub fn get(&self, key: K) -> Arc<PageCache> {
+        let mut caches = self.caches.lock();
+        if let Some(cache) = caches.get(&key).and_then(Weak::upgrade) {
+            return cache;
+        }
+        // 顺带清理已释放的缓存
+        caches.retain(|_, cache| cache.strong_count() > 0);
+        let cache = Arc::new(PageCache::new());
+        caches.insert(key, Arc::downgrade(&cache));
+        cache
+    }
+
+    /// 解除 `key` 与其页缓存的关联（如文件被删除、inode 号可能被复用时），
+    /// 已持有该缓存的使用者不受影响
+    pub fn remove(&self, key: &K) {
+        self.caches.lock().remove(key);
+    }
+}
+
+impl<K: Ord> Default for PageCacheRegistry<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
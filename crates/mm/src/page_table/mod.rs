@@ -12,13 +12,36 @@ pub use table::*;
 pub enum PageSize {
     /// 4KB 页
     Size4K = 0x1000,
-    #[allow(dead_code)] // TODO(暂时注释): 大页支持已暂时禁用
     /// 2MB 大页
     Size2M = 0x20_0000,
-    #[allow(dead_code)] // TODO(暂时注释): 大页支持已暂时禁用
     /// 1GB 大页
     Size1G = 0x4000_0000,
-    // 暂时禁止更大的页大小
+}
+
+impl PageSize {
+    /// 该页大小包含的 4K 页数
+    pub const fn pages(self) -> usize {
+        self as usize / PageSize::Size4K as usize
+    }
+
+    /// 对应的页表级别（4K 页为 0，每大一级对应上一级页表）
+    pub const fn level(self) -> usize {
+        match self {
+            PageSize::Size4K => 0,
+            PageSize::Size2M => 1,
+            PageSize::Size1G => 2,
+        }
+    }
+
+    /// 第 `level` 级页表中叶子页表项映射的页大小
+    pub const fn from_level(level: usize) -> Option<Self> {
+        match level {
+            0 => Some(PageSize::Size4K),
+            1 => Some(PageSize::Size2M),
+            2 => Some(PageSize::Size1G),
+            _ => None,
+        }
+    }
 }
 
 /// 分页操作中可能发生的错误
@@ -31,7 +54,6 @@ pub enum PagingError {
     /// 提供了无效的地址
     InvalidAddress,
     /// 由于与现有的巨页（Huge Page）映射冲突，操作失败。
-    #[allow(dead_code)] // TODO(暂时注释): 大页支持已暂时禁用
     HugePageConflict,
     /// 提供了无效的标志（Flags）
     InvalidFlags,
@@ -43,11 +65,10 @@ pub enum PagingError {
     /// 区域不能收缩到其起始地址以下
     #[allow(dead_code)]
     ShrinkBelowStart,
-    /// 巨页拆分功能尚未实现
-    #[allow(dead_code)] // TODO(暂时注释): 大页支持已暂时禁用
-    HugePageSplitNotImplemented,
     /// 内存耗尽
     OutOfMemory,
+    /// 交换区读写失败
+    SwapFailed,
 }
 
 /// 分页操作的结果类型
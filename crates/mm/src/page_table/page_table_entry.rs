@@ -84,6 +84,12 @@ pub trait UniversalConvertableFlag {
     fn to_universal(&self) -> UniversalPTEFlag;
 }
 
+/// 换出项中交换槽位的起始位
+///
+/// 换出项是有效位清零的非空页表项，槽位放在物理页号所在的位上，低位的标志全部为零。
+/// 槽位 0 是交换区头部，不会出现在换出项中，因此换出项总是非空的。
+pub const SWAP_ENTRY_SHIFT: u32 = 12;
+
 /// 页表项（Page Table Entry, PTE）所需实现的核心接口
 pub trait PageTableEntry {
     /// 用于表示页表项的底层位模式类型
@@ -126,4 +132,25 @@ pub trait PageTableEntry {
     /// 添加指定的标志
     // current_flags | flags
     fn add_flags(&mut self, flags: UniversalPTEFlag);
+
+    /// 创建记录交换槽位 `slot` 的换出项，硬件访问时产生缺页
+    fn new_swap(slot: usize) -> Self
+    where
+        Self: Sized,
+        Self::Bits: From<u64>,
+    {
+        debug_assert!(slot != 0, "slot 0 holds the swap header");
+        Self::from_bits(((slot as u64) << SWAP_ENTRY_SHIFT).into())
+    }
+
+    /// 换出项中的交换槽位，不是换出项时返回 `None`
+    fn swap_slot(&self) -> Option<usize>
+    where
+        Self::Bits: Into<u64>,
+    {
+        if self.is_valid() || self.is_empty() {
+            return None;
+        }
+        Some((self.to_bits().into() >> SWAP_ENTRY_SHIFT) as usize)
+    }
 }
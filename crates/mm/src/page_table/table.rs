@@ -14,6 +14,18 @@
 //! 映射/解除映射通常需要配合 TLB 刷新；为减少频繁刷新带来的开销，
 //! 该接口提供 `*_with_batch` 版本，配合 [`crate::arch_ops::TlbBatchContextWrapper`]
 //! 在一次批处理中合并刷新操作（具体行为由架构实现决定）。
+//!
+//! ## 大页
+//!
+//! `map` 的 `page_size` 为 [`PageSize::Size2M`]/[`PageSize::Size1G`] 时在上层页表中直接建立叶子项，
+//! 虚拟页号与物理页号都必须按页大小对齐。`walk` 对大页中的任意虚拟页都返回该 4K 页对应的物理页号，
+//! 同时给出叶子项的页大小；`unmap`/`update_flags` 作用于整个大页。
+//! 只需修改大页中一部分时，先用 `split_huge_page` 把它拆成下一级的页。
+//!
+//! ## 换出项
+//!
+//! 被换出到交换区的页，其叶子页表项改写为记录交换槽位的换出项
+//! （见 [`PageTableEntry::new_swap`]），访问时产生缺页；换入时直接 `map` 覆盖即可。
 #![allow(dead_code)]
 use super::{PageSize, PageTableEntry, PagingResult, UniversalPTEFlag};
 use crate::address::{Paddr, Ppn, Vaddr, Vpn};
@@ -87,6 +99,8 @@ where
     fn update_flags(&mut self, vpn: Vpn, flags: UniversalPTEFlag) -> PagingResult<()>;
 
     /// 遍历页表获取映射信息
+    ///
+    /// 返回 `vpn` 所在 4K 页的物理页号（落在大页中时已加上页内偏移）、叶子项的页大小和标志位。
     fn walk(&self, vpn: Vpn) -> PagingResult<(Ppn, PageSize, UniversalPTEFlag)>;
 
     /// 映射虚拟页到物理页（支持 TLB 批处理）
@@ -113,4 +127,26 @@ where
         flags: UniversalPTEFlag,
         batch: Option<&mut TlbBatchContextWrapper>,
     ) -> PagingResult<()>;
+
+    /// 把覆盖 `vpn` 的大页拆分为下一级的 512 个页，映射关系和标志位不变
+    ///
+    /// 1G 页拆分为 2M 页，2M 页拆分为 4K 页；`vpn` 已是 4K 页时什么都不做。
+    /// 未映射时返回 [`PagingError::NotMapped`](super::PagingError::NotMapped)。
+    fn split_huge_page(&mut self, vpn: Vpn) -> PagingResult<()>;
+
+    /// 把 `vpn` 处的叶子页表项设为记录交换槽位 `slot` 的换出项，替换原有映射（支持 TLB 批处理）
+    ///
+    /// 中间级页表不存在时返回 [`PagingError::NotMapped`](super::PagingError::NotMapped)。
+    fn set_swap_entry(
+        &mut self,
+        vpn: Vpn,
+        slot: usize,
+        batch: Option<&mut TlbBatchContextWrapper>,
+    ) -> PagingResult<()>;
+
+    /// 读取 `vpn` 处换出项中的交换槽位，不是换出项时返回 `None`
+    fn swap_entry(&self, vpn: Vpn) -> Option<usize>;
+
+    /// 清除 `vpn` 处的换出项
+    fn clear_swap_entry(&mut self, vpn: Vpn) -> PagingResult<()>;
 }
@@ -0,0 +1,99 @@
+//! 反向映射（rmap）：从物理页找到映射它的地址空间与虚拟页
+//!
+//! 帧映射区域（[`MappingArea`](crate::memory_space::MappingArea)）每跟踪一个物理帧就在这里登记一项，
+//! 帧被释放、换出或移到别处时注销。地址空间以其根页表的物理页号标识。
+//!
+//! 换出、页面合并与迁移需要从物理页找到所有映射它的页表项；
+//! `/proc/[pid]/smaps` 按映射数区分共享页与私有页并计算 PSS。
+
+use alloc::collections::btree_map::BTreeMap;
+use alloc::vec::Vec;
+
+use sync::SpinLock;
+
+use crate::address::{Ppn, Vpn};
+
+/// 一项反向映射
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RmapEntry {
+    /// 映射该页的地址空间（根页表的物理页号）
+    pub owner: Ppn,
+    /// 映射该页的虚拟页
+    pub vpn: Vpn,
+}
+
+/// 物理页到其所有映射的表
+static RMAP: SpinLock<BTreeMap<Ppn, Vec<RmapEntry>>> = SpinLock::new(BTreeMap::new());
+
+/// 登记 `owner` 地址空间在 `vpn` 处映射了物理页 `ppn`
+///
+/// 返回这是否是该页的第一个映射。
+pub fn rmap_add(ppn: Ppn, owner: Ppn, vpn: Vpn) -> bool {
+    let mut rmap = RMAP.lock();
+    let entries = rmap.entry(ppn).or_default();
+    entries.push(RmapEntry { owner, vpn });
+    entries.len() == 1
+}
+
+/// 注销 `owner` 地址空间在 `vpn` 处对物理页 `ppn` 的映射
+///
+/// 返回该页是否已不再被任何地址空间映射。
+pub fn rmap_remove(ppn: Ppn, owner: Ppn, vpn: Vpn) -> bool {
+    let mut rmap = RMAP.lock();
+    let Some(entries) = rmap.get_mut(&ppn) else {
+        debug_assert!(false, "rmap_remove: page {:?} not mapped", ppn); // 物理页没有映射
+        return false;
+    };
+    if let Some(pos) = entries
+        .iter()
+        .position(|e| e.owner == owner && e.vpn == vpn)
+    {
+        entries.swap_remove(pos);
+    }
+    if entries.is_empty() {
+        rmap.remove(&ppn);
+        return true;
+    }
+    false
+}
+
+/// 物理页 `ppn` 被映射的次数
+pub fn page_mapcount(ppn: Ppn) -> usize {
+    RMAP.lock().get(&ppn).map_or(0, |entries| entries.len())
+}
+
+/// 映射物理页 `ppn` 的所有地址空间与虚拟页
+pub fn page_mappings(ppn: Ppn) -> Vec<RmapEntry> {
+    RMAP.lock().get(&ppn).cloned().unwrap_or_default()
+}
+
+/// 被至少一个地址空间映射的物理页数
+pub fn rmap_pages() -> usize {
+    RMAP.lock().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::UsizeConvert;
+    use sync::mock::init_arch_ops;
+
+    #[test]
+    fn test_rmap_add_remove() {
+        init_arch_ops();
+        // 使用不会被其它测试分配到的物理页号
+        let ppn = Ppn::from_usize(usize::MAX - 1);
+        let (a, b) = (Ppn::from_usize(1), Ppn::from_usize(2));
+        let vpn = Vpn::from_usize(0x10);
+
+        assert!(rmap_add(ppn, a, vpn));
+        assert!(!rmap_add(ppn, b, vpn));
+        assert_eq!(page_mapcount(ppn), 2);
+        assert!(page_mappings(ppn).contains(&RmapEntry { owner: b, vpn }));
+
+        assert!(!rmap_remove(ppn, a, vpn));
+        assert_eq!(page_mappings(ppn), [RmapEntry { owner: b, vpn }]);
+        assert!(rmap_remove(ppn, b, vpn));
+        assert_eq!(page_mapcount(ppn), 0);
+    }
+}
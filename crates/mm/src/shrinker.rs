@@ -0,0 +1,87 @@
+//! 可回收缓存的收缩回调
+//!
+//! 目录项缓存等内核对象缓存本身不占用可换出的页，但缓存的对象会间接持有内存
+//! （inode、页缓存等）。这些缓存通过 [`register_shrinker`] 登记一个 [`Shrinker`]，
+//! [`alloc_frame`](crate::frame_allocator::alloc_frame) 换出页之后仍然分配失败时
+//! 调用 [`shrink_slab`]，让每个缓存释放一批未使用的对象，再重试分配。
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use sync::SpinLock;
+
+/// 每次内存压力下要求每个缓存释放的对象数
+pub const SHRINK_BATCH: usize = 128;
+
+/// 可在内存压力下释放对象的缓存
+pub trait Shrinker: Send + Sync {
+    /// 当前可以释放的对象数（估计值）
+    fn count_objects(&self) -> usize;
+
+    /// 释放至多 `nr` 个对象，返回实际释放的个数
+    ///
+    /// 与换出相同，可能发生在任意分配帧的位置，实现只能尝试加锁。
+    fn scan_objects(&self, nr: usize) -> usize;
+}
+
+/// 登记的缓存
+static SHRINKERS: SpinLock<Vec<&'static dyn Shrinker>> = SpinLock::new(Vec::new());
+
+/// 正在收缩，释放对象的过程中再次分配失败时不重入
+static SHRINKING: AtomicBool = AtomicBool::new(false);
+
+/// 登记一个缓存
+pub fn register_shrinker(shrinker: &'static dyn Shrinker) {
+    SHRINKERS.lock().push(shrinker);
+}
+
+/// 要求每个登记的缓存释放至多 `nr` 个对象，返回释放的对象总数
+pub fn shrink_slab(nr: usize) -> usize {
+    if nr == 0 || SHRINKING.swap(true, Ordering::Acquire) {
+        return 0;
+    }
+    let mut freed = 0;
+    if let Some(shrinkers) = SHRINKERS.try_lock() {
+        for shrinker in shrinkers.iter() {
+            let count = shrinker.count_objects();
+            if count > 0 {
+                freed += shrinker.scan_objects(nr.min(count));
+            }
+        }
+    }
+    SHRINKING.store(false, Ordering::Release);
+    freed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use sync::mock::init_arch_ops;
+
+    struct CountingCache(AtomicUsize);
+
+    impl Shrinker for CountingCache {
+        fn count_objects(&self) -> usize {
+            self.0.load(Ordering::Relaxed)
+        }
+
+        fn scan_objects(&self, nr: usize) -> usize {
+            let nr = nr.min(self.0.load(Ordering::Relaxed));
+            self.0.fetch_sub(nr, Ordering::Relaxed);
+            nr
+        }
+    }
+
+    static CACHE: CountingCache = CountingCache(AtomicUsize::new(200));
+
+    #[test]
+    fn test_shrink_slab_batches() {
+        init_arch_ops();
+        register_shrinker(&CACHE);
+        assert_eq!(shrink_slab(SHRINK_BATCH), SHRINK_BATCH);
+        assert_eq!(shrink_slab(SHRINK_BATCH), 200 - SHRINK_BATCH);
+        assert_eq!(shrink_slab(SHRINK_BATCH), 0);
+        assert_eq!(shrink_slab(0), 0);
+    }
+}
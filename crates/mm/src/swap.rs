@@ -0,0 +1,500 @@
+//! 交换区
+//!
+//! 内存紧张时把匿名页写到块设备上的交换区，释放物理帧；再次访问时由缺页处理换入。
+//!
+//! - **交换区格式**：与 Linux `mkswap` 生成的格式兼容（`SWAPSPACE2`，版本 1）。
+//!   第 0 页是头部，记录最后一个可用页和坏页列表，其余每页是一个交换槽位。
+//! - **换出项**：换出页的叶子页表项改写为记录槽位的换出项
+//!   （[`PageTableEntry::new_swap`](crate::page_table::PageTableEntry::new_swap)），
+//!   映射区域用 [`TrackedFrames::Swapped`] 持有槽位，槽位随 [`SwapSlot`] 释放。
+//! - **LRU**：可换出的匿名页按加入顺序排成队列。回收时从队首扫描，
+//!   访问位已置位的页清除访问位后移到队尾（第二次机会），否则换出。
+//! - **回收**：[`alloc_frame`] 分配失败时调用 [`reclaim`] 换出页后重试，
+//!   内存压力不再直接表现为分配失败。
+//! - **可丢弃页**：`madvise(MADV_FREE)` 标记的页另排一个队列，回收时优先直接释放，
+//!   不需要交换区。
+//!
+//! 映射区域属于哪个地址空间、地址空间用什么锁保护由 os crate 决定：
+//! LRU 中只记录地址空间的弱引用和页号，换出通过 [`register_swap_ops`] 注册的 [`SwapOps`] 完成。
+//!
+//! [`TrackedFrames::Swapped`]: crate::frame_allocator::TrackedFrames::Swapped
+//! [`alloc_frame`]: crate::frame_allocator::alloc_frame
+
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use sync::SpinLock;
+
+use crate::address::Vpn;
+
+/// 交换区头部魔数，位于第 0 页末尾
+pub const SWAP_MAGIC: &[u8; 10] = b"SWAPSPACE2";
+
+/// 支持的头部版本
+const SWAP_VERSION: u32 = 1;
+/// 头部字段在第 0 页中的偏移（前 1024 字节留给引导扇区）
+const HEADER_VERSION_OFFSET: usize = 1024;
+const HEADER_LAST_PAGE_OFFSET: usize = 1028;
+const HEADER_NR_BADPAGES_OFFSET: usize = 1032;
+/// 坏页列表的偏移：version、last_page、nr_badpages、uuid\[16\]、volume_name\[16\]、padding\[117\] 之后
+const HEADER_BADPAGES_OFFSET: usize = 1536;
+
+/// 交换操作的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapError {
+    /// 没有启用交换区
+    NoDevice,
+    /// 已启用交换区，或交换区中还有被占用的槽位
+    Busy,
+    /// 设备上没有有效的交换区头部
+    InvalidHeader,
+    /// 设备读写失败
+    Io,
+    /// 交换区已满
+    NoSpace,
+}
+
+/// 交换区所在的设备，以页为单位读写
+pub trait SwapDevice: Send + Sync {
+    /// 设备容量（页）
+    fn nr_pages(&self) -> usize;
+
+    /// 把第 `index` 页读入 `buf`，`buf` 长度为一页
+    fn read_page(&self, index: usize, buf: &mut [u8]) -> Result<(), SwapError>;
+
+    /// 把 `buf` 写入第 `index` 页，`buf` 长度为一页
+    fn write_page(&self, index: usize, buf: &[u8]) -> Result<(), SwapError>;
+}
+
+/// 交换区头部（第 0 页）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapHeader {
+    /// 最后一个可用页的页号，槽位为 `1..=last_page`
+    pub last_page: u32,
+    /// 不可用的坏页
+    pub bad_pages: Vec<u32>,
+}
+
+impl SwapHeader {
+    /// 为 `nr_pages` 页的设备创建头部
+    pub fn new(nr_pages: usize) -> Self {
+        Self {
+            last_page: nr_pages.saturating_sub(1).min(u32::MAX as usize) as u32,
+            bad_pages: Vec::new(),
+        }
+    }
+
+    /// 解析第 0 页，魔数或版本不符时返回 `None`
+    pub fn parse(page: &[u8]) -> Option<Self> {
+        let page_size = page.len();
+        if page_size < HEADER_BADPAGES_OFFSET || &page[page_size - SWAP_MAGIC.len()..] != SWAP_MAGIC
+        {
+            return None;
+        }
+        if read_u32(page, HEADER_VERSION_OFFSET) != SWAP_VERSION {
+            return None;
+        }
+        let last_page = read_u32(page, HEADER_LAST_PAGE_OFFSET);
+        let nr_bad = read_u32(page, HEADER_NR_BADPAGES_OFFSET) as usize;
+        if last_page == 0 || HEADER_BADPAGES_OFFSET + nr_bad * 4 > page_size - SWAP_MAGIC.len() {
+            return None;
+        }
+        let bad_pages = (0..nr_bad)
+            .map(|i| read_u32(page, HEADER_BADPAGES_OFFSET + i * 4))
+            .collect();
+        Some(Self {
+            last_page,
+            bad_pages,
+        })
+    }
+
+    /// 把头部写入第 0 页（页的其余部分清零）
+    pub fn write(&self, page: &mut [u8]) {
+        let page_size = page.len();
+        page.fill(0);
+        write_u32(page, HEADER_VERSION_OFFSET, SWAP_VERSION);
+        write_u32(page, HEADER_LAST_PAGE_OFFSET, self.last_page);
+        write_u32(page, HEADER_NR_BADPAGES_OFFSET, self.bad_pages.len() as u32);
+        for (i, bad) in self.bad_pages.iter().enumerate() {
+            write_u32(page, HEADER_BADPAGES_OFFSET + i * 4, *bad);
+        }
+        page[page_size - SWAP_MAGIC.len()..].copy_from_slice(SWAP_MAGIC);
+    }
+}
+
+fn read_u32(page: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(page[offset..offset + 4].try_into().unwrap())
+}
+
+fn write_u32(page: &mut [u8], offset: usize, value: u32) {
+    page[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// 槽位位图：每个槽位一位，1 表示占用，头部和坏页始终占用
+#[derive(Debug)]
+struct SlotMap {
+    bits: Vec<u64>,
+    nr_slots: usize,
+    /// 可用于换出的槽位数
+    usable: usize,
+    /// 被换出页占用的槽位数
+    used: usize,
+    hint: usize,
+}
+
+impl SlotMap {
+    fn new(header: &SwapHeader) -> Self {
+        let nr_slots = header.last_page as usize + 1;
+        let mut map = Self {
+            bits: vec![0; nr_slots.div_ceil(64)],
+            nr_slots,
+            usable: nr_slots - 1,
+            used: 0,
+            hint: 1,
+        };
+        map.set(0);
+        for &bad in &header.bad_pages {
+            let bad = bad as usize;
+            if bad != 0 && bad < nr_slots && !map.test(bad) {
+                map.set(bad);
+                map.usable -= 1;
+            }
+        }
+        map
+    }
+
+    fn test(&self, slot: usize) -> bool {
+        self.bits[slot / 64] & (1 << (slot % 64)) != 0
+    }
+
+    fn set(&mut self, slot: usize) {
+        self.bits[slot / 64] |= 1 << (slot % 64);
+    }
+
+    fn alloc(&mut self) -> Option<usize> {
+        if self.used == self.usable {
+            return None;
+        }
+        let slot = (self.hint..self.nr_slots)
+            .chain(1..self.hint)
+            .find(|&slot| !self.test(slot))?;
+        self.set(slot);
+        self.used += 1;
+        self.hint = slot + 1;
+        Some(slot)
+    }
+
+    fn free(&mut self, slot: usize) {
+        debug_assert!(slot != 0 && self.test(slot), "mm: freeing free swap slot");
+        self.bits[slot / 64] &= !(1 << (slot % 64));
+        self.used -= 1;
+    }
+}
+
+/// 已启用的交换区
+struct SwapArea {
+    dev: Arc<dyn SwapDevice>,
+    slots: SlotMap,
+}
+
+/// 当前启用的交换区，同一时间最多一个
+static SWAP: SpinLock<Option<SwapArea>> = SpinLock::new(None);
+
+/// 交换区使用情况（页）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapInfo {
+    /// 可用于换出的槽位数
+    pub total: usize,
+    /// 已被换出页占用的槽位数
+    pub used: usize,
+}
+
+/// 在设备上创建交换区（覆盖第 0 页）
+pub fn mkswap(dev: &dyn SwapDevice) -> Result<(), SwapError> {
+    if dev.nr_pages() < 2 {
+        return Err(SwapError::NoSpace);
+    }
+    let mut page = vec![0u8; crate::mm_config().page_size()];
+    SwapHeader::new(dev.nr_pages()).write(&mut page);
+    dev.write_page(0, &page)
+}
+
+/// 启用设备上的交换区
+///
+/// # 错误
+/// - [`SwapError::InvalidHeader`]：设备上没有有效的头部，需要先 [`mkswap`]
+/// - [`SwapError::Busy`]：已经启用了交换区
+pub fn swapon(dev: Arc<dyn SwapDevice>) -> Result<(), SwapError> {
+    let mut page = vec![0u8; crate::mm_config().page_size()];
+    dev.read_page(0, &mut page)?;
+    let mut header = SwapHeader::parse(&page).ok_or(SwapError::InvalidHeader)?;
+    // 头部声明的大小可能超过设备实际容量（设备被缩小过）
+    let last_page = dev.nr_pages().saturating_sub(1).min(u32::MAX as usize) as u32;
+    header.last_page = header.last_page.min(last_page);
+    if header.last_page == 0 {
+        return Err(SwapError::InvalidHeader);
+    }
+    let mut swap = SWAP.lock();
+    if swap.is_some() {
+        return Err(SwapError::Busy);
+    }
+    *swap = Some(SwapArea {
+        dev,
+        slots: SlotMap::new(&header),
+    });
+    Ok(())
+}
+
+/// 停用交换区
+///
+/// 换出的页只在换入或所属映射释放时归还槽位，交换区中还有换出页时返回 [`SwapError::Busy`]。
+pub fn swapoff() -> Result<(), SwapError> {
+    let mut swap = SWAP.lock();
+    match swap.as_ref() {
+        None => Err(SwapError::NoDevice),
+        Some(area) if area.slots.used != 0 => Err(SwapError::Busy),
+        Some(_) => {
+            *swap = None;
+            Ok(())
+        }
+    }
+}
+
+/// 交换区使用情况，未启用时返回 `None`
+pub fn swap_info() -> Option<SwapInfo> {
+    SWAP.lock().as_ref().map(|area| SwapInfo {
+        total: area.slots.usable,
+        used: area.slots.used,
+    })
+}
+
+/// 交换区中的一个槽位，释放时归还
+#[derive(Debug)]
+pub struct SwapSlot(usize);
+
+impl SwapSlot {
+    /// 槽位号（设备上的页号）
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl Drop for SwapSlot {
+    fn drop(&mut self) {
+        // 有被占用的槽位时交换区不能停用
+        if let Some(area) = SWAP.lock().as_mut() {
+            area.slots.free(self.0);
+        }
+    }
+}
+
+/// 分配槽位并写入一页数据
+pub fn swap_write(page: &[u8]) -> Result<SwapSlot, SwapError> {
+    let (dev, slot) = {
+        let mut swap = SWAP.lock();
+        let area = swap.as_mut().ok_or(SwapError::NoDevice)?;
+        let slot = area.slots.alloc().ok_or(SwapError::NoSpace)?;
+        (area.dev.clone(), SwapSlot(slot))
+    };
+    // 设备读写可能睡眠，不持有交换区锁；失败时槽位随 slot 归还
+    dev.write_page(slot.0, page)?;
+    crate::vmstat::count_event(crate::vmstat::VmEvent::PswpOut);
+    Ok(slot)
+}
+
+/// 把槽位中的数据读入 `page`
+pub fn swap_read(slot: &SwapSlot, page: &mut [u8]) -> Result<(), SwapError> {
+    let dev = SWAP
+        .lock()
+        .as_ref()
+        .map(|area| area.dev.clone())
+        .ok_or(SwapError::NoDevice)?;
+    dev.read_page(slot.0, page)?;
+    crate::vmstat::count_event(crate::vmstat::VmEvent::PswpIn);
+    Ok(())
+}
+
+// ============================================================================
+// 匿名页 LRU 与回收
+// ============================================================================
+
+/// LRU 中页的所有者（os crate 中的地址空间），以 `Any` 擦除具体类型
+pub type SwapOwner = Arc<dyn Any + Send + Sync>;
+
+/// 尝试换出一页的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReclaimResult {
+    /// 已换出，释放了一个物理帧
+    Reclaimed,
+    /// 最近被访问过，已清除访问位，留在 LRU 中
+    Referenced,
+    /// 暂时不能换出（地址空间锁被占用、页被写时复制共享等），留在 LRU 中
+    Busy,
+    /// 页已不存在或不可换出，从 LRU 中移除
+    Gone,
+}
+
+/// 由 os crate 实现的换出操作
+pub trait SwapOps: Send + Sync {
+    /// 尝试换出 `owner` 地址空间中 `vpn` 处的页
+    ///
+    /// 回收可能发生在任意分配帧的位置，调用者可能已持有该地址空间的锁，
+    /// 实现只能尝试加锁，失败时返回 [`ReclaimResult::Busy`]。
+    fn try_swap_out(&self, owner: &SwapOwner, vpn: Vpn) -> ReclaimResult;
+}
+
+static SWAP_OPS_DATA: AtomicUsize = AtomicUsize::new(0);
+static SWAP_OPS_VTABLE: AtomicUsize = AtomicUsize::new(0);
+
+/// 注册换出操作实现
+///
+/// # Safety
+/// 必须在单线程环境下调用，且只能调用一次
+pub unsafe fn register_swap_ops(ops: &'static dyn SwapOps) {
+    let ptr = ops as *const dyn SwapOps;
+    // SAFETY: 将 fat pointer 拆分为 data 和 vtable 两部分存储
+    let (data, vtable) = unsafe { core::mem::transmute::<*const dyn SwapOps, (usize, usize)>(ptr) };
+    SWAP_OPS_VTABLE.store(vtable, Ordering::Release);
+    SWAP_OPS_DATA.store(data, Ordering::Release);
+}
+
+fn swap_ops() -> Option<&'static dyn SwapOps> {
+    let data = SWAP_OPS_DATA.load(Ordering::Acquire);
+    let vtable = SWAP_OPS_VTABLE.load(Ordering::Acquire);
+    if data == 0 {
+        return None;
+    }
+    // SAFETY: 重组 fat pointer
+    Some(unsafe { &*core::mem::transmute::<(usize, usize), *const dyn SwapOps>((data, vtable)) })
+}
+
+/// 可换出的匿名页，队首最久未被访问
+static LRU: SpinLock<VecDeque<(Weak<dyn Any + Send + Sync>, Vpn)>> = SpinLock::new(VecDeque::new());
+
+/// `madvise(MADV_FREE)` 标记的可丢弃页，不论是否启用交换区都可以回收
+static LAZYFREE: SpinLock<VecDeque<(Weak<dyn Any + Send + Sync>, Vpn)>> =
+    SpinLock::new(VecDeque::new());
+
+/// 防止回收过程中分配帧再次进入回收
+static RECLAIMING: AtomicBool = AtomicBool::new(false);
+
+/// 把 `owner` 地址空间中 `vpn` 处的匿名页加入 LRU 队尾
+pub fn lru_add(owner: &SwapOwner, vpn: Vpn) {
+    LRU.lock().push_back((Arc::downgrade(owner), vpn));
+}
+
+/// LRU 中的页数
+pub fn lru_len() -> usize {
+    LRU.lock().len()
+}
+
+/// 把 `owner` 地址空间中 `vpn` 处的可丢弃页加入回收队列
+pub fn lazyfree_add(owner: &SwapOwner, vpn: Vpn) {
+    LAZYFREE.lock().push_back((Arc::downgrade(owner), vpn));
+}
+
+/// 可丢弃页回收队列中的页数
+pub fn lazyfree_len() -> usize {
+    LAZYFREE.lock().len()
+}
+
+/// 回收最多 `nr` 页，返回实际释放的物理帧数
+///
+/// 先直接释放可丢弃的页，不够时再换出 LRU 中的页。
+/// 没有注册 [`SwapOps`] 或已在回收中时什么都不做，没有启用交换区时只释放可丢弃的页。
+/// 最多扫描两遍 LRU：第一遍清除访问位的页在第二遍可以被换出。
+pub fn reclaim(nr: usize) -> usize {
+    if nr == 0 {
+        return 0;
+    }
+    let Some(ops) = swap_ops() else {
+        return 0;
+    };
+    if RECLAIMING.swap(true, Ordering::Acquire) {
+        return 0;
+    }
+    let mut reclaimed = 0;
+    while reclaimed < nr {
+        let Some((owner, vpn)) = LAZYFREE.lock().pop_front() else {
+            break;
+        };
+        let Some(strong) = owner.upgrade() else {
+            continue;
+        };
+        // 标记后又被写入的页已不可丢弃，没能释放的页直接移出队列
+        if ops.try_swap_out(&strong, vpn) == ReclaimResult::Reclaimed {
+            kcov!();
+            reclaimed += 1;
+        }
+    }
+    if SWAP.lock().is_none() {
+        RECLAIMING.store(false, Ordering::Release);
+        return reclaimed;
+    }
+    kcov!();
+    let mut budget = lru_len() * 2;
+    while reclaimed < nr && budget > 0 {
+        budget -= 1;
+        // 换出时不持有 LRU 锁：写交换区可能睡眠，地址空间也可能同时加入新页
+        let Some((owner, vpn)) = LRU.lock().pop_front() else {
+            break;
+        };
+        let Some(strong) = owner.upgrade() else {
+            continue;
+        };
+        match ops.try_swap_out(&strong, vpn) {
+            ReclaimResult::Reclaimed => reclaimed += 1,
+            ReclaimResult::Referenced | ReclaimResult::Busy => {
+                LRU.lock().push_back((owner, vpn));
+            }
+            ReclaimResult::Gone => {}
+        }
+    }
+    RECLAIMING.store(false, Ordering::Release);
+    reclaimed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_header_roundtrip() {
+        let mut page = vec![0u8; 4096];
+        assert_eq!(SwapHeader::parse(&page), None);
+
+        let header = SwapHeader {
+            last_page: 255,
+            bad_pages: vec![3, 7],
+        };
+        header.write(&mut page);
+        assert_eq!(&page[4096 - 10..], SWAP_MAGIC);
+        assert_eq!(SwapHeader::parse(&page), Some(header));
+
+        // 版本不符的头部不被接受
+        write_u32(&mut page, HEADER_VERSION_OFFSET, 2);
+        assert_eq!(SwapHeader::parse(&page), None);
+    }
+
+    #[test]
+    fn test_slot_map_skips_header_and_bad_pages() {
+        let header = SwapHeader {
+            last_page: 4,
+            bad_pages: vec![2, 2, 9],
+        };
+        let mut slots = SlotMap::new(&header);
+        assert_eq!(slots.usable, 3);
+        assert_eq!(slots.alloc(), Some(1));
+        assert_eq!(slots.alloc(), Some(3));
+        assert_eq!(slots.alloc(), Some(4));
+        assert_eq!(slots.alloc(), None);
+
+        slots.free(3);
+        assert_eq!(slots.used, 2);
+        assert_eq!(slots.alloc(), Some(3));
+    }
+}
@@ -0,0 +1,252 @@
+//! 非连续内核内存分配（vmalloc）
+//!
+//! 大块内核内存（网络缓冲区、ext4 位图等）不必依赖物理连续的帧：
+//! [`vmalloc`] 逐页分配物理帧，把它们映射到内核专用虚拟地址区域（vmalloc 区）中的一段连续地址上，
+//! [`vfree`] 解除映射并归还帧与地址。
+//!
+//! vmalloc 区的位置与内核页表操作由 os crate 通过 [`register_vmalloc_ops`] 注册的
+//! [`VmallocOps`] 提供，建立的映射必须对所有地址空间可见。
+//! 每块分配之后留一页不映射的保护页，越界访问产生缺页，而不是写坏相邻的分配。
+//!
+//! vmalloc 区的内存不在线性映射中，不能用 `vaddr_to_paddr` 转换，也不能直接交给设备做 DMA。
+
+use alloc::collections::btree_map::BTreeMap;
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use sync::SpinLock;
+
+use crate::address::{PageNum, Ppn, UsizeConvert, Vaddr, Vpn};
+use crate::config::mm_config;
+use crate::frame_allocator::{FrameTracker, alloc_frame};
+use crate::page_table::PagingResult;
+
+/// 由 os crate 实现的 vmalloc 区操作
+pub trait VmallocOps: Send + Sync {
+    /// vmalloc 区的起始地址与大小（字节），均按页对齐
+    fn region(&self) -> (usize, usize);
+
+    /// 在内核页表中把 `vpn` 映射到 `ppn`（内核可读写）
+    fn map_page(&self, vpn: Vpn, ppn: Ppn) -> PagingResult<()>;
+
+    /// 解除从 `start` 开始 `pages` 页的映射并刷新所有 CPU 的 TLB，未映射的页跳过
+    fn unmap_pages(&self, start: Vpn, pages: usize);
+}
+
+static VMALLOC_OPS_DATA: AtomicUsize = AtomicUsize::new(0);
+static VMALLOC_OPS_VTABLE: AtomicUsize = AtomicUsize::new(0);
+
+/// 注册 vmalloc 区操作实现
+///
+/// # Safety
+/// 必须在单线程环境下调用，且只能调用一次
+pub unsafe fn register_vmalloc_ops(ops: &'static dyn VmallocOps) {
+    let ptr = ops as *const dyn VmallocOps;
+    // SAFETY: 将 fat pointer 拆分为 data 和 vtable 两部分存储
+    let (data, vtable) =
+        unsafe { core::mem::transmute::<*const dyn VmallocOps, (usize, usize)>(ptr) };
+    VMALLOC_OPS_VTABLE.store(vtable, Ordering::Release);
+    VMALLOC_OPS_DATA.store(data, Ordering::Release);
+}
+
+fn vmalloc_ops() -> Option<&'static dyn VmallocOps> {
+    let data = VMALLOC_OPS_DATA.load(Ordering::Acquire);
+    let vtable = VMALLOC_OPS_VTABLE.load(Ordering::Acquire);
+    if data == 0 {
+        return None;
+    }
+    // SAFETY: 重组 fat pointer
+    Some(unsafe { &*core::mem::transmute::<(usize, usize), *const dyn VmallocOps>((data, vtable)) })
+}
+
+/// vmalloc 区中空闲地址范围的分配器（首次适配）
+#[derive(Debug, Default)]
+struct VaAllocator {
+    /// 空闲范围：起始地址 -> 长度（字节），相邻范围总是合并
+    free: BTreeMap<usize, usize>,
+}
+
+impl VaAllocator {
+    fn new(start: usize, size: usize) -> Self {
+        let mut free = BTreeMap::new();
+        if size > 0 {
+            free.insert(start, size);
+        }
+        Self { free }
+    }
+
+    /// 取出长度为 `size` 的一段地址
+    fn alloc(&mut self, size: usize) -> Option<usize> {
+        let (&start, &len) = self.free.iter().find(|&(_, &len)| len >= size)?;
+        self.free.remove(&start);
+        if len > size {
+            self.free.insert(start + size, len - size);
+        }
+        Some(start)
+    }
+
+    /// 归还 `[start, start + size)`，与前后的空闲范围合并
+    fn free(&mut self, start: usize, size: usize) {
+        let (mut start, mut size) = (start, size);
+        if let Some((&prev, &len)) = self.free.range(..start).next_back() {
+            debug_assert!(prev + len <= start, "vmalloc: double free of {:#x}", start); // 重复释放
+            if prev + len == start {
+                self.free.remove(&prev);
+                start = prev;
+                size += len;
+            }
+        }
+        if let Some(len) = self.free.remove(&(start + size)) {
+            size += len;
+        }
+        self.free.insert(start, size);
+    }
+}
+
+/// 一块 vmalloc 分配
+#[derive(Debug)]
+struct VmArea {
+    /// 映射的页数（不含保护页）
+    pages: usize,
+    frames: Vec<FrameTracker>,
+}
+
+struct Vmalloc {
+    /// 第一次分配时按 [`VmallocOps::region`] 初始化
+    va: Option<VaAllocator>,
+    /// 已分配的块：起始地址 -> 块
+    areas: BTreeMap<usize, VmArea>,
+}
+
+static VMALLOC: SpinLock<Vmalloc> = SpinLock::new(Vmalloc {
+    va: None,
+    areas: BTreeMap::new(),
+});
+
+/// 已映射的 vmalloc 页数
+static VMALLOC_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+/// 分配 `size` 字节的内核内存，物理上不必连续
+///
+/// 返回的地址按页对齐，内容清零。`size` 为 0、帧不足、vmalloc 区耗尽
+/// 或尚未注册 [`VmallocOps`] 时返回 `None`。
+pub fn vmalloc(size: usize) -> Option<NonNull<u8>> {
+    kcov!();
+    let ops = vmalloc_ops()?;
+    if size == 0 {
+        return None;
+    }
+    let page_size = mm_config().page_size();
+    let pages = size.div_ceil(page_size);
+    // 分配帧可能触发回收，不能持有 VMALLOC 锁
+    let frames = (0..pages)
+        .map(|_| alloc_frame())
+        .collect::<Option<Vec<_>>>()?;
+
+    // 多占一页作为保护页
+    let span = (pages + 1) * page_size;
+    let start = {
+        let mut vmalloc = VMALLOC.lock();
+        let va = vmalloc.va.get_or_insert_with(|| {
+            let (start, size) = ops.region();
+            VaAllocator::new(start, size)
+        });
+        va.alloc(span)?
+    };
+
+    let base = Vpn::from_addr_floor(Vaddr::from_usize(start));
+    for (i, frame) in frames.iter().enumerate() {
+        if ops
+            .map_page(Vpn::from_usize(base.as_usize() + i), frame.ppn())
+            .is_err()
+        {
+            kcov!();
+            ops.unmap_pages(base, i);
+            if let Some(va) = VMALLOC.lock().va.as_mut() {
+                va.free(start, span);
+            }
+            return None;
+        }
+    }
+
+    VMALLOC.lock().areas.insert(start, VmArea { pages, frames });
+    VMALLOC_PAGES.fetch_add(pages, Ordering::Relaxed);
+    NonNull::new(start as *mut u8)
+}
+
+/// 释放 [`vmalloc`] 分配的内存
+///
+/// `ptr` 不是 [`vmalloc`] 返回的地址时只打印警告。
+pub fn vfree(ptr: NonNull<u8>) {
+    let start = ptr.as_ptr() as usize;
+    let Some(area) = VMALLOC.lock().areas.remove(&start) else {
+        log::warn!("vfree: {:#x} is not a vmalloc address", start);
+        return;
+    };
+    let Some(ops) = vmalloc_ops() else {
+        return;
+    };
+    // 先解除映射并刷新 TLB，之后帧才能被重新分配
+    ops.unmap_pages(Vpn::from_addr_floor(Vaddr::from_usize(start)), area.pages);
+    drop(area.frames);
+    VMALLOC_PAGES.fetch_sub(area.pages, Ordering::Relaxed);
+
+    let span = (area.pages + 1) * mm_config().page_size();
+    if let Some(va) = VMALLOC.lock().va.as_mut() {
+        va.free(start, span);
+    }
+}
+
+/// `addr` 是否位于 vmalloc 区
+pub fn is_vmalloc_addr(addr: usize) -> bool {
+    vmalloc_ops().is_some_and(|ops| {
+        let (start, size) = ops.region();
+        addr.wrapping_sub(start) < size
+    })
+}
+
+/// vmalloc 区中 `addr` 所在页映射的物理页号
+pub fn vmalloc_to_ppn(addr: usize) -> Option<Ppn> {
+    let page_size = mm_config().page_size();
+    let vmalloc = VMALLOC.lock();
+    let (&start, area) = vmalloc.areas.range(..=addr).next_back()?;
+    let index = (addr - start) / page_size;
+    area.frames.get(index).map(|frame| frame.ppn())
+}
+
+/// 已映射的 vmalloc 页数
+pub fn vmalloc_used_pages() -> usize {
+    VMALLOC_PAGES.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: usize = 4096;
+
+    #[test]
+    fn test_va_allocator_first_fit_and_merge() {
+        let base = 0x1000_0000;
+        let mut va = VaAllocator::new(base, 8 * PAGE);
+
+        let a = va.alloc(2 * PAGE).unwrap();
+        let b = va.alloc(3 * PAGE).unwrap();
+        let c = va.alloc(3 * PAGE).unwrap();
+        assert_eq!((a, b, c), (base, base + 2 * PAGE, base + 5 * PAGE));
+        assert_eq!(va.alloc(PAGE), None);
+
+        // 释放的空洞按首次适配重用
+        va.free(a, 2 * PAGE);
+        assert_eq!(va.alloc(3 * PAGE), None);
+        assert_eq!(va.alloc(PAGE), Some(base));
+
+        // 相邻范围释放后合并成一整段
+        va.free(base, PAGE);
+        va.free(c, 3 * PAGE);
+        va.free(b, 3 * PAGE);
+        assert_eq!(va.free.len(), 1);
+        assert_eq!(va.alloc(8 * PAGE), Some(base));
+    }
+}
@@ -0,0 +1,183 @@
+//! 内存统计计数器（vmstat）
+//!
+//! 计数分两类：
+//!
+//! - **状态计数**（[`NrItem`]）：当前值，随页的映射、缓存与释放增减，如匿名页数、页缓存页数；
+//! - **事件计数**（[`VmEvent`]）：自启动以来单调递增，如帧释放次数、缺页次数。
+//!
+//! 计数由帧分配器、反向映射、页缓存、交换区、内存规整与缺页处理路径维护，
+//! `/proc/meminfo` 与 `/proc/vmstat` 通过 [`snapshot`] 读取。空闲帧数、各区域的分配帧数、
+//! 交换区与 vmalloc 的用量直接取自各自的模块，不在这里重复计数。
+//!
+//! 计数器都是全局原子变量，只用 `Relaxed` 更新：各项之间不保证一致，只用于统计。
+
+use core::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+
+use crate::frame_allocator::NR_ZONES;
+
+/// 状态计数项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NrItem {
+    /// 被用户地址空间映射的匿名页（包括私有文件映射写时复制得到的页）
+    AnonPages = 0,
+    /// 被用户地址空间映射的页缓存页
+    FileMapped = 1,
+    /// 页缓存中的页
+    FilePages = 2,
+    /// 页缓存中尚未写回的脏页
+    FileDirty = 3,
+    /// 内核堆（slab）已分配的字节数
+    SlabBytes = 4,
+}
+
+/// 状态计数项的个数
+pub const NR_ITEMS: usize = 5;
+
+/// 事件计数项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmEvent {
+    /// 释放的物理帧数
+    PgFree = 0,
+    /// 处理的缺页次数
+    PgFault = 1,
+    /// 需要读交换区的缺页次数
+    PgMajFault = 2,
+    /// 从交换区读入的页数
+    PswpIn = 3,
+    /// 写入交换区的页数
+    PswpOut = 4,
+    /// 内存规整中迁移成功的页数
+    PgMigrateSuccess = 5,
+    /// 内存规整中迁移失败的页数
+    PgMigrateFail = 6,
+    /// 连续分配失败后进入规整的次数
+    CompactStall = 7,
+    /// 其中规整后拼出了所需空闲块的次数
+    CompactSuccess = 8,
+    /// 其中规整后仍没有所需空闲块的次数
+    CompactFail = 9,
+}
+
+/// 事件计数项的个数
+pub const NR_EVENTS: usize = 10;
+
+static STATE: [AtomicIsize; NR_ITEMS] = [const { AtomicIsize::new(0) }; NR_ITEMS];
+static EVENTS: [AtomicUsize; NR_EVENTS] = [const { AtomicUsize::new(0) }; NR_EVENTS];
+
+/// 状态计数 `item` 增加 `delta`（可为负）
+#[inline]
+pub fn mod_state(item: NrItem, delta: isize) {
+    STATE[item as usize].fetch_add(delta, Ordering::Relaxed);
+}
+
+/// 状态计数 `item` 加一
+#[inline]
+pub fn inc_state(item: NrItem) {
+    mod_state(item, 1);
+}
+
+/// 状态计数 `item` 减一
+#[inline]
+pub fn dec_state(item: NrItem) {
+    mod_state(item, -1);
+}
+
+/// 状态计数 `item` 的当前值
+///
+/// 不同 CPU 上的增减可能乱序到达，读到的负值按 0 处理。
+pub fn state(item: NrItem) -> usize {
+    STATE[item as usize].load(Ordering::Relaxed).max(0) as usize
+}
+
+/// 事件 `event` 发生一次
+#[inline]
+pub fn count_event(event: VmEvent) {
+    count_events(event, 1);
+}
+
+/// 事件 `event` 发生 `n` 次
+#[inline]
+pub fn count_events(event: VmEvent, n: usize) {
+    EVENTS[event as usize].fetch_add(n, Ordering::Relaxed);
+}
+
+/// 事件 `event` 自启动以来发生的次数
+pub fn event_count(event: VmEvent) -> usize {
+    EVENTS[event as usize].load(Ordering::Relaxed)
+}
+
+/// 某一时刻的内存统计（页数，另有说明的除外）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VmStat {
+    /// 帧分配器管理的总帧数
+    pub total_pages: usize,
+    /// 空闲帧数
+    pub free_pages: usize,
+    /// 见 [`NrItem::AnonPages`]
+    pub anon_pages: usize,
+    /// 见 [`NrItem::FileMapped`]
+    pub file_mapped: usize,
+    /// 见 [`NrItem::FilePages`]
+    pub file_pages: usize,
+    /// 见 [`NrItem::FileDirty`]
+    pub file_dirty: usize,
+    /// 内核堆已分配的字节数
+    pub slab_bytes: usize,
+    /// 已映射的 vmalloc 页数
+    pub vmalloc_pages: usize,
+    /// 交换区总页数（未启用时为 0）
+    pub swap_total: usize,
+    /// 交换区空闲页数
+    pub swap_free: usize,
+    /// 按 [`ZoneType`](crate::frame_allocator::ZoneType) 索引、各区域分配的帧数
+    pub pgalloc: [usize; NR_ZONES],
+    /// 按 [`VmEvent`] 索引的事件计数
+    pub events: [usize; NR_EVENTS],
+}
+
+/// 读取当前的内存统计
+pub fn snapshot() -> VmStat {
+    let (swap_total, swap_free) =
+        crate::swap::swap_info().map_or((0, 0), |info| (info.total, info.total - info.used));
+    let mut pgalloc = [0; NR_ZONES];
+    for zone in crate::frame_allocator::get_zone_info() {
+        pgalloc[zone.zone as usize] += zone.nr_alloc;
+    }
+    VmStat {
+        total_pages: crate::frame_allocator::get_total_frames(),
+        free_pages: crate::frame_allocator::get_free_frames(),
+        anon_pages: state(NrItem::AnonPages),
+        file_mapped: state(NrItem::FileMapped),
+        file_pages: state(NrItem::FilePages),
+        file_dirty: state(NrItem::FileDirty),
+        slab_bytes: state(NrItem::SlabBytes),
+        vmalloc_pages: crate::vmalloc::vmalloc_used_pages(),
+        swap_total,
+        swap_free,
+        pgalloc,
+        events: core::array::from_fn(|i| EVENTS[i].load(Ordering::Relaxed)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        // 计数器是全局的，其它测试可能并发修改，只检查本测试造成的最小变化
+        let before = event_count(VmEvent::PswpIn);
+        count_events(VmEvent::PswpIn, 3);
+        count_event(VmEvent::PswpIn);
+        assert!(event_count(VmEvent::PswpIn) >= before + 4);
+
+        mod_state(NrItem::SlabBytes, 100);
+        assert!(state(NrItem::SlabBytes) >= 100);
+        mod_state(NrItem::SlabBytes, -100);
+
+        // 乱序到达的减量使计数暂时为负时按 0 读出
+        STATE[NrItem::FileDirty as usize].fetch_sub(1 << 40, Ordering::Relaxed);
+        assert_eq!(state(NrItem::FileDirty), 0);
+        STATE[NrItem::FileDirty as usize].fetch_add(1 << 40, Ordering::Relaxed);
+    }
+}
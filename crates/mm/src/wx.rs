@@ -0,0 +1,116 @@
+//! W^X（写异或执行）策略
+//!
+//! 任何映射都不应同时可写且可执行：
+//! - 内核映射：.text 为 R+X，.rodata 为 R，其余区域一律不可执行；
+//! - 用户映射：mmap/mprotect 请求 PROT_WRITE | PROT_EXEC 时默认拒绝，
+//!   仅当 `vm.allow_wx` sysctl 打开时放行（兼容 JIT 等老程序）。
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::memory_space::AreaType;
+use crate::page_table::UniversalPTEFlag;
+
+/// `vm.allow_wx`：是否允许用户态建立同时可写可执行的映射
+static ALLOW_WX: AtomicBool = AtomicBool::new(false);
+
+/// 读取 `vm.allow_wx`
+pub fn allow_wx() -> bool {
+    ALLOW_WX.load(Ordering::Relaxed)
+}
+
+/// 设置 `vm.allow_wx`
+pub fn set_allow_wx(allow: bool) {
+    ALLOW_WX.store(allow, Ordering::Relaxed);
+}
+
+/// 权限是否同时可写且可执行
+pub fn is_wx(flags: UniversalPTEFlag) -> bool {
+    flags.contains(UniversalPTEFlag::WRITEABLE | UniversalPTEFlag::EXECUTABLE)
+}
+
+/// 检查一个内核区域的权限是否符合 W^X 布局
+///
+/// 返回违规原因；用户区域或合规区域返回 `None`。
+pub fn kernel_wx_violation(area_type: AreaType, flags: UniversalPTEFlag) -> Option<&'static str> {
+    let writable = flags.contains(UniversalPTEFlag::WRITEABLE);
+    let executable = flags.contains(UniversalPTEFlag::EXECUTABLE);
+    match area_type {
+        AreaType::KernelText if writable => Some("writable kernel text"),
+        AreaType::KernelText => None,
+        AreaType::KernelRodata if writable => Some("writable kernel rodata"),
+        AreaType::KernelRodata if executable => Some("executable kernel rodata"),
+        AreaType::KernelData
+        | AreaType::KernelBss
+        | AreaType::KernelStack
+        | AreaType::KernelHeap
+        | AreaType::KernelMmio
+            if executable =>
+        {
+            Some("executable kernel data")
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_wx() {
+        assert!(!is_wx(UniversalPTEFlag::user_rw()));
+        assert!(!is_wx(UniversalPTEFlag::user_rx()));
+        assert!(is_wx(
+            UniversalPTEFlag::user_rw() | UniversalPTEFlag::EXECUTABLE
+        ));
+    }
+
+    #[test]
+    fn test_kernel_wx_violation() {
+        assert_eq!(
+            kernel_wx_violation(AreaType::KernelText, UniversalPTEFlag::kernel_rx()),
+            None
+        );
+        assert_eq!(
+            kernel_wx_violation(AreaType::KernelRodata, UniversalPTEFlag::kernel_r()),
+            None
+        );
+        assert_eq!(
+            kernel_wx_violation(AreaType::KernelHeap, UniversalPTEFlag::kernel_rw()),
+            None
+        );
+        assert!(
+            kernel_wx_violation(
+                AreaType::KernelText,
+                UniversalPTEFlag::kernel_rx() | UniversalPTEFlag::WRITEABLE
+            )
+            .is_some()
+        );
+        assert!(
+            kernel_wx_violation(AreaType::KernelRodata, UniversalPTEFlag::kernel_rx()).is_some()
+        );
+        assert!(
+            kernel_wx_violation(
+                AreaType::KernelData,
+                UniversalPTEFlag::kernel_rw() | UniversalPTEFlag::EXECUTABLE
+            )
+            .is_some()
+        );
+        assert_eq!(
+            kernel_wx_violation(
+                AreaType::UserText,
+                UniversalPTEFlag::user_rw() | UniversalPTEFlag::EXECUTABLE
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_allow_wx_toggle() {
+        assert!(!allow_wx());
+        set_allow_wx(true);
+        assert!(allow_wx());
+        set_allow_wx(false);
+        assert!(!allow_wx());
+    }
+}
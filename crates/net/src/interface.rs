@@ -2,6 +2,7 @@
 //!
 //! - [`NetworkInterface`]：对单个网卡/设备的抽象封装，持有底层 [`device::NetDevice`]。
 //! - [`NETWORK_INTERFACE_MANAGER`]：全局接口管理器，保存系统中已注册的网络接口列表。
+//! - [`InterfaceStats`]：接口收发统计，由 [`SeqLock`] 保护，读取统计不会与收发路径争用。
 //! - [`SmoltcpInterface`]：对 `smoltcp::iface::Interface` 的封装，保证其借用的 device adapter
 //!   具备正确的生命周期（避免返回悬垂引用）。
 //!
@@ -11,12 +12,63 @@ use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use device::DeviceType;
-use device::NetDevice;
+use device::{NetDevice, NetDeviceError};
 use lazy_static::lazy_static;
 use smoltcp::iface::Interface;
 use smoltcp::time::Instant;
 use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv4Address};
-use sync::SpinLock;
+use sync::{SeqLock, SpinLock};
+
+/// 网络接口收发统计，字段含义与 `struct rtnl_link_stats64` 的同名字段一致
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterfaceStats {
+    /// 接收的数据包数
+    pub rx_packets: u64,
+    /// 发送的数据包数
+    pub tx_packets: u64,
+    /// 接收的字节数
+    pub rx_bytes: u64,
+    /// 发送的字节数
+    pub tx_bytes: u64,
+    /// 接收错误数
+    pub rx_errors: u64,
+    /// 发送错误数
+    pub tx_errors: u64,
+    /// 接收时丢弃的数据包数
+    pub rx_dropped: u64,
+    /// 发送时丢弃的数据包数
+    pub tx_dropped: u64,
+}
+
+impl InterfaceStats {
+    /// 创建全零的统计
+    pub const fn new() -> Self {
+        Self {
+            rx_packets: 0,
+            tx_packets: 0,
+            rx_bytes: 0,
+            tx_bytes: 0,
+            rx_errors: 0,
+            tx_errors: 0,
+            rx_dropped: 0,
+            tx_dropped: 0,
+        }
+    }
+}
+
+/// 在统计上记录一次接收
+fn account_rx(stats: &SeqLock<InterfaceStats>, len: usize) {
+    let mut stats = stats.write();
+    stats.rx_packets += 1;
+    stats.rx_bytes += len as u64;
+}
+
+/// 在统计上记录一次发送
+fn account_tx(stats: &SeqLock<InterfaceStats>, len: usize) {
+    let mut stats = stats.write();
+    stats.tx_packets += 1;
+    stats.tx_bytes += len as u64;
+}
 
 /// 网络接口管理器
 pub struct NetworkInterfaceManager {
@@ -50,54 +102,18 @@ impl NetworkInterfaceManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use core::sync::atomic::{AtomicUsize, Ordering};
+    use alloc::vec;
     use device::NullNetDevice;
-    use smoltcp::wire::Ipv4Cidr;
-    use sync::ArchOps;
-
-    struct DummyArchOps;
-
-    impl ArchOps for DummyArchOps {
-        unsafe fn read_and_disable_interrupts(&self) -> usize {
-            0
-        }
-
-        unsafe fn restore_interrupts(&self, _flags: usize) {}
-
-        fn sstatus_sie(&self) -> usize {
-            0
-        }
-
-        fn cpu_id(&self) -> usize {
-            0
-        }
-
-        fn max_cpu_count(&self) -> usize {
-            1
-        }
-    }
-
-    static DUMMY_ARCH_OPS: DummyArchOps = DummyArchOps;
-    static SYNC_INIT: AtomicUsize = AtomicUsize::new(0);
-
-    fn init_sync_arch_ops() {
-        match SYNC_INIT.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire) {
-            Ok(_) => {
-                // Safety: tests use a single global dummy ArchOps.
-                unsafe { sync::register_arch_ops(&DUMMY_ARCH_OPS) };
-                SYNC_INIT.store(2, Ordering::Release);
-            }
-            Err(_) => {
-                while SYNC_INIT.load(Ordering::Acquire) != 2 {
-                    core::hint::spin_loop();
-                }
-            }
-        }
-    }
+    use smoltcp::iface::SocketSet;
+    use smoltcp::socket::{tcp, udp};
+    use smoltcp::wire::{IpEndpoint, Ipv4Cidr};
+    use sync::mock::init_arch_ops;
+    use test_support::mock::net::{MOCK_NET_MAC, MockNetDevice};
+    use test_support::packet::{self, Frame, TcpFlags, TcpSegment};
 
     #[test]
     fn test_network_interface_manager_add_and_find() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let dev0 = NullNetDevice::new(0);
         let iface0 = Arc::new(NetworkInterface::new(String::from("eth0"), dev0));
 
@@ -116,7 +132,7 @@ mod tests {
 
     #[test]
     fn test_network_interface_ip_dedup_and_gateway() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let dev = NullNetDevice::new(0);
         let iface = NetworkInterface::new(String::from("eth0"), dev);
 
@@ -132,7 +148,7 @@ mod tests {
 
     #[test]
     fn test_network_interface_interrupt_toggle() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let dev = NullNetDevice::new(0);
         let iface = NetworkInterface::new(String::from("eth0"), dev);
 
@@ -145,7 +161,7 @@ mod tests {
 
     #[test]
     fn test_create_smoltcp_interface_sets_ip_addrs() {
-        init_sync_arch_ops();
+        init_arch_ops();
         let dev = NullNetDevice::new(0);
         let iface = NetworkInterface::new(String::from("eth0"), dev);
 
@@ -160,6 +176,181 @@ mod tests {
         assert!(addrs.iter().any(|a| *a == ip1));
         assert!(addrs.iter().any(|a| *a == ip2));
     }
+
+    const OUR_IP: [u8; 4] = [10, 0, 2, 15];
+    const PEER_IP: [u8; 4] = [10, 0, 2, 2];
+    const PEER_MAC: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x99];
+
+    /// 基于 MockNetDevice 的接口，并预先注入对端的 ARP 应答以填充邻居缓存
+    fn mock_iface() -> (Arc<MockNetDevice>, SmoltcpInterface) {
+        init_arch_ops();
+        let dev = MockNetDevice::new(0, MOCK_NET_MAC);
+        let iface = NetworkInterface::new(String::from("eth0"), dev.clone());
+        iface.add_ip_address(IpCidr::Ipv4(Ipv4Cidr::new(OUR_IP.into(), 24)));
+        dev.inject(packet::ether(PEER_MAC, MOCK_NET_MAC).arp_reply(PEER_IP, MOCK_NET_MAC, OUR_IP));
+        (dev, iface.create_smoltcp_interface())
+    }
+
+    fn from_peer() -> packet::Ipv4Builder {
+        packet::ether(PEER_MAC, MOCK_NET_MAC).ipv4(PEER_IP, OUR_IP)
+    }
+
+    fn tcp_socket() -> tcp::Socket<'static> {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 4096]),
+            tcp::SocketBuffer::new(vec![0; 4096]),
+        )
+    }
+
+    /// 轮询直到注入的帧全部被协议栈消费，返回期间发出的帧
+    fn pump(
+        dev: &MockNetDevice,
+        sm: &mut SmoltcpInterface,
+        sockets: &mut SocketSet<'static>,
+        now_ms: i64,
+    ) -> Vec<Vec<u8>> {
+        loop {
+            sm.poll(Instant::from_millis(now_ms), sockets);
+            if dev.pending_rx() == 0 {
+                break;
+            }
+        }
+        dev.take_sent()
+    }
+
+    fn sent_tcp(frames: &[Vec<u8>]) -> Vec<TcpSegment<'_>> {
+        frames
+            .iter()
+            .filter_map(|f| Frame::parse(f)?.tcp())
+            .collect()
+    }
+
+    #[test]
+    fn test_mock_device_tcp_handshake_and_data() {
+        let (dev, mut sm) = mock_iface();
+        let mut sockets = SocketSet::new(vec![]);
+        let mut listener = tcp_socket();
+        listener.listen(80).unwrap();
+        let handle = sockets.add(listener);
+
+        dev.inject(from_peer().tcp(40000, 80).syn().seq(1000).mss(1460).build());
+        let sent = pump(&dev, &mut sm, &mut sockets, 0);
+        let synack = *sent_tcp(&sent)
+            .iter()
+            .find(|s| s.flags.contains(TcpFlags::SYN | TcpFlags::ACK))
+            .expect("no SYN-ACK");
+        assert_eq!((synack.src_port, synack.dst_port), (80, 40000));
+        assert_eq!(synack.ack, 1001);
+        // MSS 由设备 MTU（IP 层 1500）推导
+        assert_eq!(synack.mss, Some(1460));
+
+        dev.inject(
+            from_peer()
+                .tcp(40000, 80)
+                .seq(1001)
+                .ack(synack.seq.wrapping_add(1))
+                .psh()
+                .payload(b"hello")
+                .build(),
+        );
+        pump(&dev, &mut sm, &mut sockets, 10);
+        let sock = sockets.get_mut::<tcp::Socket>(handle);
+        assert_eq!(sock.state(), tcp::State::Established);
+        let mut buf = [0u8; 16];
+        let n = sock.recv_slice(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+
+        sock.send_slice(b"world").unwrap();
+        let sent = pump(&dev, &mut sm, &mut sockets, 20);
+        assert!(sent_tcp(&sent).iter().any(|s| s.payload == b"world"));
+    }
+
+    #[test]
+    fn test_mock_device_tcp_backlog_overflow_resets() {
+        let (dev, mut sm) = mock_iface();
+        let mut sockets = SocketSet::new(vec![]);
+        // backlog = 2：同一端口上两个监听 socket
+        for _ in 0..2 {
+            let mut listener = tcp_socket();
+            listener.listen(80).unwrap();
+            sockets.add(listener);
+        }
+
+        for port in 40001..=40003 {
+            dev.inject(from_peer().tcp(port, 80).syn().seq(7).build());
+        }
+        let sent = pump(&dev, &mut sm, &mut sockets, 0);
+        let segs = sent_tcp(&sent);
+        let synacks = segs
+            .iter()
+            .filter(|s| s.flags.contains(TcpFlags::SYN | TcpFlags::ACK))
+            .count();
+        assert_eq!(synacks, 2);
+        assert!(
+            segs.iter()
+                .any(|s| s.dst_port == 40003 && s.flags.contains(TcpFlags::RST))
+        );
+    }
+
+    #[test]
+    fn test_mock_device_udp_roundtrip() {
+        let (dev, mut sm) = mock_iface();
+        let mut sockets = SocketSet::new(vec![]);
+        let mut socket = udp::Socket::new(
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1024]),
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1024]),
+        );
+        socket.bind(53).unwrap();
+        let handle = sockets.add(socket);
+
+        dev.inject(from_peer().udp(5353, 53).payload(b"query").build());
+        pump(&dev, &mut sm, &mut sockets, 0);
+        let sock = sockets.get_mut::<udp::Socket>(handle);
+        let mut buf = [0u8; 16];
+        let (n, meta) = sock.recv_slice(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"query");
+        assert_eq!(
+            meta.endpoint,
+            IpEndpoint::new(IpAddress::Ipv4(PEER_IP.into()), 5353)
+        );
+
+        sock.send_slice(b"answer", meta.endpoint).unwrap();
+        let sent = pump(&dev, &mut sm, &mut sockets, 10);
+        let reply = sent
+            .iter()
+            .find_map(|f| Frame::parse(f)?.udp())
+            .expect("no UDP reply");
+        assert_eq!((reply.src_port, reply.dst_port), (53, 5353));
+        assert_eq!(reply.payload, b"answer");
+    }
+
+    #[test]
+    fn test_interface_stats_count_rx_and_tx() {
+        init_arch_ops();
+        let dev = MockNetDevice::new(0, MOCK_NET_MAC);
+        let iface = NetworkInterface::new(String::from("eth0"), dev.clone());
+        iface.add_ip_address(IpCidr::Ipv4(Ipv4Cidr::new(OUR_IP.into(), 24)));
+        let mut sm = iface.create_smoltcp_interface();
+        let mut sockets = SocketSet::new(vec![]);
+        assert_eq!(iface.stats(), InterfaceStats::new());
+
+        let arp = packet::ether(PEER_MAC, MOCK_NET_MAC).arp_reply(PEER_IP, MOCK_NET_MAC, OUR_IP);
+        let ping = from_peer().udp(5353, 53).payload(b"query").build();
+        let rx_bytes = (arp.len() + ping.len()) as u64;
+        dev.inject(arp);
+        dev.inject(ping);
+        let sent = pump(&dev, &mut sm, &mut sockets, 0);
+
+        let stats = iface.stats();
+        assert_eq!(stats.rx_packets, 2);
+        assert_eq!(stats.rx_bytes, rx_bytes);
+        assert_eq!(stats.tx_packets, sent.len() as u64);
+        assert_eq!(
+            stats.tx_bytes,
+            sent.iter().map(|f| f.len() as u64).sum::<u64>()
+        );
+        assert_eq!(stats.rx_errors, 0);
+    }
 }
 
 lazy_static! {
@@ -176,8 +367,12 @@ pub struct SmoltcpInterface {
 
 impl SmoltcpInterface {
     /// 创建新的 smoltcp 接口包装器
-    fn new(device: Arc<dyn NetDevice>, mac_address: EthernetAddress) -> Self {
-        let mut device_adapter = NetDeviceAdapter::new(device);
+    fn new(
+        device: Arc<dyn NetDevice>,
+        mac_address: EthernetAddress,
+        stats: Arc<SeqLock<InterfaceStats>>,
+    ) -> Self {
+        let mut device_adapter = NetDeviceAdapter::with_stats(device, stats);
 
         let config =
             smoltcp::iface::Config::new(smoltcp::wire::HardwareAddress::Ethernet(mac_address));
@@ -241,6 +436,7 @@ pub struct NetworkInterface {
     ipv4_gateway: SpinLock<Option<Ipv4Address>>,
     interrupt_enabled: SpinLock<bool>,
     last_interrupt_time: SpinLock<Instant>,
+    stats: Arc<SeqLock<InterfaceStats>>,
 }
 
 impl NetworkInterface {
@@ -255,6 +451,7 @@ impl NetworkInterface {
             ipv4_gateway: SpinLock::new(None),
             interrupt_enabled: SpinLock::new(true),
             last_interrupt_time: SpinLock::new(Instant::from_millis(0)),
+            stats: Arc::new(SeqLock::new(InterfaceStats::new())),
         }
     }
 
@@ -273,6 +470,11 @@ impl NetworkInterface {
         &self.device
     }
 
+    /// 获取收发统计的一致快照
+    pub fn stats(&self) -> InterfaceStats {
+        self.stats.read()
+    }
+
     /// 设置IP地址
     pub fn add_ip_address(&self, ip_cidr: IpCidr) {
         let mut ip_addresses = self.ip_addresses.lock();
@@ -327,7 +529,8 @@ impl NetworkInterface {
     /// 确保两者有相同的生命周期，避免悬垂指针问题。
     pub fn create_smoltcp_interface(&self) -> SmoltcpInterface {
         // 创建包装器（内部会创建 device_adapter 和 interface）
-        let mut smoltcp_iface = SmoltcpInterface::new(self.device.clone(), self.mac_address());
+        let mut smoltcp_iface =
+            SmoltcpInterface::new(self.device.clone(), self.mac_address(), self.stats.clone());
 
         // 设置IP地址
         for ip_cidr in self.ip_addresses.lock().iter() {
@@ -355,15 +558,22 @@ pub struct NetDeviceAdapter {
     device: Arc<dyn NetDevice>,
     rx_buffer: [u8; 2048],
     loopback_queue: Arc<SpinLock<alloc::collections::VecDeque<alloc::vec::Vec<u8>>>>,
+    stats: Arc<SeqLock<InterfaceStats>>,
 }
 
 impl NetDeviceAdapter {
     /// 创建新的网络设备适配器
     pub fn new(device: Arc<dyn NetDevice>) -> Self {
+        Self::with_stats(device, Arc::new(SeqLock::new(InterfaceStats::new())))
+    }
+
+    /// 创建网络设备适配器，收发统计记录到 `stats`
+    pub fn with_stats(device: Arc<dyn NetDevice>, stats: Arc<SeqLock<InterfaceStats>>) -> Self {
         Self {
             device,
             rx_buffer: [0; 2048],
             loopback_queue: Arc::new(SpinLock::new(alloc::collections::VecDeque::new())),
+            stats,
         }
     }
 
@@ -386,9 +596,11 @@ impl smoltcp::phy::Device for NetDeviceAdapter {
         if let Some(packet) = self.loopback_queue.lock().pop_front() {
             if packet.len() > self.rx_buffer.len() {
                 // Drop oversized loopback frames to avoid panicking on buffer copy.
+                self.stats.write().rx_dropped += 1;
                 return None;
             }
             self.rx_buffer[..packet.len()].copy_from_slice(&packet);
+            account_rx(&self.stats, packet.len());
             return Some((
                 NetRxToken {
                     buffer: &self.rx_buffer[..packet.len()],
@@ -396,22 +608,31 @@ impl smoltcp::phy::Device for NetDeviceAdapter {
                 NetTxToken {
                     device: &self.device,
                     loopback_queue: self.loopback_queue.clone(),
+                    stats: &self.stats,
                 },
             ));
         }
 
         // 尝试从物理设备接收
         match self.device.receive(&mut self.rx_buffer) {
-            Ok(size) if size > 0 => Some((
-                NetRxToken {
-                    buffer: &self.rx_buffer[..size],
-                },
-                NetTxToken {
-                    device: &self.device,
-                    loopback_queue: self.loopback_queue.clone(),
-                },
-            )),
-            _ => None,
+            Ok(size) if size > 0 => {
+                account_rx(&self.stats, size);
+                Some((
+                    NetRxToken {
+                        buffer: &self.rx_buffer[..size],
+                    },
+                    NetTxToken {
+                        device: &self.device,
+                        loopback_queue: self.loopback_queue.clone(),
+                        stats: &self.stats,
+                    },
+                ))
+            }
+            Ok(_) | Err(NetDeviceError::QueueEmpty) => None,
+            Err(_) => {
+                self.stats.write().rx_errors += 1;
+                None
+            }
         }
     }
 
@@ -419,6 +640,7 @@ impl smoltcp::phy::Device for NetDeviceAdapter {
         Some(NetTxToken {
             device: &self.device,
             loopback_queue: self.loopback_queue.clone(),
+            stats: &self.stats,
         })
     }
 
@@ -457,6 +679,7 @@ impl smoltcp::phy::RxToken for NetRxToken<'_> {
 pub struct NetTxToken<'a> {
     device: &'a Arc<dyn NetDevice>,
     loopback_queue: Arc<SpinLock<alloc::collections::VecDeque<alloc::vec::Vec<u8>>>>,
+    stats: &'a SeqLock<InterfaceStats>,
 }
 
 impl smoltcp::phy::TxToken for NetTxToken<'_> {
@@ -491,9 +714,14 @@ impl smoltcp::phy::TxToken for NetTxToken<'_> {
         };
 
         if is_loopback {
+            account_tx(self.stats, buffer.len());
             self.loopback_queue.lock().push_back(buffer);
         } else {
-            let _ = self.device.send(&buffer);
+            match self.device.send(&buffer) {
+                Ok(()) => account_tx(self.stats, buffer.len()),
+                Err(NetDeviceError::QueueFull) => self.stats.write().tx_dropped += 1,
+                Err(_) => self.stats.write().tx_errors += 1,
+            }
         }
 
         result
@@ -531,16 +759,20 @@ impl device::Driver for NetworkInterface {
         let mut buffer = [0u8; 2048];
         match self.device.receive(&mut buffer) {
             Ok(size) if size > 0 => {
+                account_rx(&self.stats, size);
                 log::debug!(
                     "Received {} bytes of data on interface {}",
                     size,
                     self.name()
                 );
-                // Wake up tasks waiting in ppoll
-                crate::ops::net_ops().wake_poll_waiters();
+                // 唤醒等待 socket 就绪的 poll/select/epoll
+                crate::socket::wake_socket_pollers();
                 true
             }
             Err(e) => {
+                if !matches!(e, NetDeviceError::QueueEmpty) {
+                    self.stats.write().rx_errors += 1;
+                }
                 log::debug!("Error receiving data: {:?}", e);
                 true // 仍然返回true表示我们处理了这个中断
             }
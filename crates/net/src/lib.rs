@@ -28,7 +28,7 @@ pub use ops::{NetOps, net_ops, register_net_ops};
 
 // Re-export 主要接口
 pub use config::NetworkConfigManager;
-pub use interface::{NETWORK_INTERFACE_MANAGER, NetworkInterface};
+pub use interface::{InterfaceStats, NETWORK_INTERFACE_MANAGER, NetworkInterface};
 pub use socket::{
     SocketFile, SocketHandle, create_tcp_socket, create_udp_socket, init_network,
     poll_network_and_dispatch, poll_network_interfaces, register_socket_fd, unregister_socket_fd,
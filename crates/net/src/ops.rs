@@ -6,18 +6,13 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// 网络运行时操作
 ///
-/// 此 trait 抽象了网络层需要的运行时操作，包括时间获取和任务唤醒。
+/// 此 trait 抽象了网络层需要的运行时操作，目前只有时间获取。
 /// os crate 需要实现此 trait 并在启动时注册。
 pub trait NetOps: Send + Sync {
     /// 获取当前时间戳（毫秒）
     ///
     /// 用于 smoltcp 协议栈的时间戳计算
     fn get_time_ms(&self) -> u64;
-
-    /// 唤醒等待 poll 的任务
-    ///
-    /// 当网络事件发生时（如数据到达、连接建立等），调用此方法唤醒等待的任务
-    fn wake_poll_waiters(&self);
 }
 
 // 使用 AtomicUsize 存储 fat pointer 的两部分
@@ -67,13 +62,10 @@ mod test_mock {
         fn get_time_ms(&self) -> u64 {
             0
         }
-
-        fn wake_poll_waiters(&self) {}
     }
 
     #[test]
     fn test_net_ops_fallback_does_not_panic() {
         assert_eq!(super::net_ops().get_time_ms(), 0);
-        super::net_ops().wake_poll_waiters();
     }
 }
@@ -3,7 +3,8 @@
 //! 本模块负责把 `smoltcp` 的 TCP/UDP socket 暴露为“文件描述符可操作的对象”，主要包括：
 //! - [`SocketFile`]：实现 `vfs::File`，用于在 OS 的 FD 表中承载 socket；
 //! - [`SOCKET_SET`]：全局 `smoltcp::iface::SocketSet`，用于存放与管理协议栈 socket；
-//! - 轮询推进：通过 `poll_network_*` 驱动收发，必要时唤醒 `poll/select` 等等待者。
+//! - 轮询推进：通过 `poll_network_*` 驱动收发，必要时唤醒 `poll/select` 等等待者；
+//! - sendfile/splice：TCP socket 实现 `File::splice_from`，文件数据直接读入发送缓冲区。
 //!
 //! 系统调用入口在 `os/src/kernel/syscall/network.rs`；本文档以当前实现为准。
 
@@ -14,7 +15,19 @@ use smoltcp::iface::{Interface, SocketHandle as SmoltcpHandle, SocketSet};
 use smoltcp::socket::{tcp, udp};
 use smoltcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
 use sync::SpinLock;
-use vfs::{File, FsError, InodeMetadata};
+use uapi::epoll::EpollEvents;
+use vfs::{File, FsError, Inode, InodeMetadata, PollQueue, PollWaker};
+
+/// 所有 socket 共用的就绪等待队列
+///
+/// smoltcp 的 socket 状态只在推进协议栈时改变，无法区分是哪个 socket，
+/// 因此推进协议栈或收发数据后统一唤醒。
+static SOCKET_POLL_QUEUE: PollQueue = PollQueue::new();
+
+/// 唤醒等待 socket 就绪的 poll/select/epoll
+pub fn wake_socket_pollers() {
+    SOCKET_POLL_QUEUE.wake(EpollEvents::empty());
+}
 
 #[derive(Clone, Copy, Debug)]
 /// 指向全局 [`SOCKET_SET`] 中某个 socket 的句柄。
@@ -105,7 +118,7 @@ impl NetIfaceWrapper {
 
         let changed = result != smoltcp::iface::PollResult::None || delivered_udp;
         if changed {
-            crate::ops::net_ops().wake_poll_waiters();
+            wake_socket_pollers();
         }
         changed
     }
@@ -143,7 +156,9 @@ lazy_static! {
         SpinLock::new(alloc::vec::Vec::new());
 }
 
+use bpf::{BpfError, Packet};
 use uapi::fcntl::OpenFlags;
+use uapi::filter::SockFilter;
 use uapi::socket::SocketOptions;
 
 const UDP_RXQ_CAP: usize = 64;
@@ -179,6 +194,8 @@ pub struct SocketFile {
     shutdown_wr: SpinLock<bool>,
     flags: SpinLock<OpenFlags>,
     options: SpinLock<SocketOptions>,
+    /// SO_ATTACH_FILTER 挂载的 cBPF 过滤器
+    filter: SpinLock<Option<Arc<[SockFilter]>>>,
     is_listener: SpinLock<bool>,
 }
 
@@ -196,6 +213,7 @@ impl SocketFile {
             shutdown_wr: SpinLock::new(false),
             flags: SpinLock::new(OpenFlags::empty()),
             options: SpinLock::new(SocketOptions::default()),
+            filter: SpinLock::new(None),
             is_listener: SpinLock::new(false),
         }
     }
@@ -223,6 +241,7 @@ impl SocketFile {
             shutdown_wr: SpinLock::new(false),
             flags: SpinLock::new(flags),
             options: SpinLock::new(SocketOptions::default()),
+            filter: SpinLock::new(None),
             is_listener: SpinLock::new(false),
         }
     }
@@ -247,6 +266,29 @@ impl SocketFile {
         *self.options.lock() = opts;
     }
 
+    /// 校验并挂载 cBPF 过滤器（对应 `SO_ATTACH_FILTER`），替换已有的过滤器。
+    ///
+    /// 过滤器作用于投递到该 socket 的每个 UDP 数据报的载荷：返回 0 丢弃数据报，
+    /// 返回值小于载荷长度时截断到该长度。
+    pub fn attach_filter(&self, prog: alloc::vec::Vec<SockFilter>) -> Result<(), BpfError> {
+        bpf::check(&prog)?;
+        *self.filter.lock() = Some(Arc::from(prog));
+        Ok(())
+    }
+
+    /// 卸载 cBPF 过滤器（对应 `SO_DETACH_FILTER`），没有挂载过滤器时返回 `false`。
+    pub fn detach_filter(&self) -> bool {
+        self.filter.lock().take().is_some()
+    }
+
+    /// 用已挂载的过滤器处理一段数据，返回应保留的字节数（0 表示丢弃）。
+    fn run_filter(&self, data: &[u8]) -> usize {
+        match self.filter.lock().as_ref() {
+            Some(prog) => core::cmp::min(bpf::run(prog, &Packet(data)) as usize, data.len()),
+            None => data.len(),
+        }
+    }
+
     /// 获取当前持有的 socket 句柄。
     ///
     /// 若句柄已被移除（理论上仅发生于销毁流程），该函数会 panic。
@@ -547,7 +589,7 @@ pub fn socket_sendto(
     };
     if result.is_ok() {
         poll_network_interfaces();
-        crate::ops::net_ops().wake_poll_waiters();
+        wake_socket_pollers();
     }
     result
 }
@@ -621,6 +663,20 @@ impl File for SocketFile {
         result
     }
 
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            SOCKET_POLL_QUEUE.register(waker);
+        }
+        let mut events = EpollEvents::empty();
+        if self.readable() {
+            events |= EpollEvents::IN | EpollEvents::RDNORM;
+        }
+        if self.writable() {
+            events |= EpollEvents::OUT | EpollEvents::WRNORM;
+        }
+        events
+    }
+
     fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
         if self.is_shutdown_read() {
             return Ok(0); // EOF
@@ -699,7 +755,7 @@ impl File for SocketFile {
             None => Err(FsError::InvalidArgument),
         };
         if result.is_ok() {
-            crate::ops::net_ops().wake_poll_waiters();
+            wake_socket_pollers();
         }
         result
     }
@@ -746,7 +802,47 @@ impl File for SocketFile {
         };
         if result.is_ok() {
             poll_network_interfaces();
-            crate::ops::net_ops().wake_poll_waiters();
+            wake_socket_pollers();
+        }
+        result
+    }
+
+    fn splice_from(
+        &self,
+        inode: &Arc<dyn Inode>,
+        offset: usize,
+        len: usize,
+    ) -> Result<usize, FsError> {
+        if self.is_shutdown_write() {
+            return Err(FsError::BrokenPipe);
+        }
+
+        let result = {
+            let mut sockets = SOCKET_SET.lock();
+            let Some(SocketHandle::Tcp(h)) = *self.handle.lock() else {
+                // UDP 按数据报发送，由调用者经缓冲区中转
+                return Err(FsError::NotSupported);
+            };
+            let socket = sockets.get_mut::<tcp::Socket>(h);
+            if !socket.may_send() {
+                return Err(FsError::BrokenPipe);
+            }
+            // 文件数据直接读入发送缓冲区的空闲部分
+            let sent = socket.send(|tx| {
+                if tx.is_empty() {
+                    return (0, Err(FsError::WouldBlock));
+                }
+                let n = tx.len().min(len);
+                match inode.read_at(offset, &mut tx[..n]) {
+                    Ok(nread) => (nread, Ok(nread)),
+                    Err(e) => (0, Err(e)),
+                }
+            });
+            sent.map_err(|_| FsError::WouldBlock)?
+        };
+        if matches!(result, Ok(n) if n > 0) {
+            poll_network_interfaces();
+            wake_socket_pollers();
         }
         result
     }
@@ -1011,7 +1107,7 @@ pub fn poll_network_interfaces() {
 pub fn poll_network_and_dispatch() {
     poll_network_interfaces();
     if udp_dispatch() {
-        crate::ops::net_ops().wake_poll_waiters();
+        wake_socket_pollers();
     }
 }
 
@@ -1162,7 +1258,9 @@ fn udp_dispatch_drain_locked(sockets: &mut SocketSet<'static>) -> bool {
             let target = target.or(fallback);
             if let Some(f) = target {
                 if let Some(sf) = f.as_any().downcast_ref::<SocketFile>() {
-                    if sf.udp_push(d) {
+                    let mut d = d;
+                    d.len = sf.run_filter(&d.data[..d.len]);
+                    if d.len > 0 && sf.udp_push(d) {
                         delivered_any = true;
                     }
                 }
@@ -0,0 +1,159 @@
+//! 完成量
+//!
+//! [`Completion`] 用于“等待某件事做完”：等待方调用 [`wait_for_completion`](Completion::wait_for_completion)
+//! 睡眠，完成方（通常是中断处理程序）调用 [`complete`](Completion::complete) 唤醒一个等待者，
+//! 或调用 [`complete_all`](Completion::complete_all) 唤醒所有现在和将来的等待者。
+//!
+//! 完成量内部维护一个完成计数：`complete` 先于等待发生时不会丢失，之后的一次等待直接返回。
+//! 等待者以独占方式挂在 [`WaitQueue`] 上，一次 `complete` 只唤醒一个。
+//!
+//! # 示例
+//! ```ignore
+//! // 驱动提交请求后等待
+//! let done = Completion::new();
+//! submit(&request, &done);
+//! done.wait_for_completion();
+//!
+//! // 中断处理程序
+//! request.done.complete();
+//! ```
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::wait_queue::{WaitOptions, WaitQueue, WaitResult};
+
+/// `complete_all` 之后的完成计数，等待不再消耗它
+const COMPLETE_ALL: usize = usize::MAX;
+
+/// 完成量
+pub struct Completion {
+    done: AtomicUsize,
+    wait: WaitQueue,
+}
+
+impl Completion {
+    /// 创建未完成的完成量
+    pub const fn new() -> Self {
+        Self {
+            done: AtomicUsize::new(0),
+            wait: WaitQueue::new(),
+        }
+    }
+
+    /// 记一次完成并唤醒一个等待者（可在中断上下文调用）
+    pub fn complete(&self) {
+        let _ = self
+            .done
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |done| {
+                (done < COMPLETE_ALL - 1).then_some(done + 1)
+            });
+        self.wait.wake_one();
+    }
+
+    /// 标记为永久完成并唤醒所有等待者（可在中断上下文调用）
+    ///
+    /// 之后的等待都立即返回，直到调用 [`reinit`](Self::reinit)。
+    pub fn complete_all(&self) {
+        self.done.store(COMPLETE_ALL, Ordering::Release);
+        self.wait.wake_all();
+    }
+
+    /// 睡眠直到完成，并消耗一次完成
+    pub fn wait_for_completion(&self) {
+        self.wait
+            .wait_event_with(WaitOptions::new().exclusive(), || self.try_consume());
+    }
+
+    /// 与 [`wait_for_completion`](Self::wait_for_completion) 相同，但最多等待 `timeout` 纳秒
+    ///
+    /// # 返回值
+    /// 完成时返回 `true`，超时返回 `false`（不消耗完成）
+    pub fn wait_for_completion_timeout(&self, timeout: u64) -> bool {
+        self.wait
+            .wait_event_with(WaitOptions::new().exclusive().timeout(timeout), || {
+                self.try_consume()
+            })
+            == WaitResult::Ready
+    }
+
+    /// 不睡眠地尝试消耗一次完成
+    pub fn try_wait_for_completion(&self) -> bool {
+        self.try_consume()
+    }
+
+    /// 是否有未被消耗的完成（不消耗）
+    pub fn completion_done(&self) -> bool {
+        self.done.load(Ordering::Acquire) != 0
+    }
+
+    /// 重置为未完成状态，用于复用完成量；调用时不能有等待者
+    pub fn reinit(&self) {
+        self.done.store(0, Ordering::Release);
+    }
+
+    fn try_consume(&self) -> bool {
+        self.done
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |done| match done {
+                0 => None,
+                COMPLETE_ALL => Some(COMPLETE_ALL),
+                done => Some(done - 1),
+            })
+            .is_ok()
+    }
+}
+
+impl Default for Completion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_completion_complete_before_wait() {
+        let done = Completion::new();
+        done.complete();
+        assert!(done.completion_done());
+        done.wait_for_completion();
+        assert!(!done.try_wait_for_completion());
+    }
+
+    #[test]
+    fn test_completion_wakes_waiter() {
+        let done = Arc::new(Completion::new());
+        let waiter = {
+            let done = done.clone();
+            thread::spawn(move || done.wait_for_completion())
+        };
+        thread::sleep(Duration::from_millis(10));
+        done.complete();
+        waiter.join().unwrap();
+        assert!(!done.completion_done());
+    }
+
+    #[test]
+    fn test_completion_complete_all() {
+        let done = Arc::new(Completion::new());
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let done = done.clone();
+                thread::spawn(move || done.wait_for_completion())
+            })
+            .collect();
+        done.complete_all();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        // complete_all 之后的等待立即返回，reinit 后重新需要完成
+        assert!(done.try_wait_for_completion());
+        done.reinit();
+        assert!(!done.wait_for_completion_timeout(1_000_000));
+    }
+}
@@ -0,0 +1,297 @@
+//! 条件变量
+//!
+//! [`Condvar`] 让任务在持有锁时睡眠等待某个条件成立：`wait` 把当前任务登记为等待者后
+//! 释放锁并睡眠，被 [`notify_one`](Condvar::notify_one) / [`notify_all`](Condvar::notify_all)
+//! 唤醒后重新获取锁再返回。可以与 [`SpinLock`] 以及其它实现了 [`CondvarGuard`] 的锁配合使用。
+//!
+//! 睡眠与唤醒通过 [`SchedOps`](crate::SchedOps) 交给调度器完成，使用前必须调用
+//! [`register_sched_ops`](crate::register_sched_ops) 注册实现。
+//!
+//! 等待者节点位于等待任务自己的栈上，由条件变量内部的自旋锁保护的链表串起来，不需要堆分配。
+//!
+//! # 示例
+//! ```ignore
+//! let queue = SpinLock::new(VecDeque::new());
+//! let cond = Condvar::new();
+//!
+//! // 消费者
+//! let mut q = cond.wait_while(queue.lock(), |q| q.is_empty());
+//! let item = q.pop_front();
+//!
+//! // 生产者
+//! queue.lock().push_back(item);
+//! cond.notify_one();
+//! ```
+
+use core::ops::DerefMut;
+use core::ptr;
+
+use crate::sched_ops;
+use crate::spin_lock::SpinLock;
+
+/// 可以被 [`Condvar`] 临时释放并重新获取的锁保护器
+pub trait CondvarGuard<'a>: Sized {
+    /// 保护器对应的锁
+    type Lock: ?Sized + 'a;
+
+    /// 释放锁，返回锁本身以便之后重新获取
+    fn unlock(self) -> &'a Self::Lock;
+
+    /// 重新获取锁
+    fn relock(lock: &'a Self::Lock) -> Self;
+}
+
+/// 等待者节点，位于等待任务的栈上
+///
+/// 各字段只在持有 [`Condvar::waiters`] 锁时访问。
+struct Waiter {
+    /// 等待任务的句柄
+    task: usize,
+    /// 是否已被通知（被通知者从链表中移除）
+    notified: bool,
+    next: *mut Waiter,
+}
+
+/// 等待者链表（先进先出）
+struct WaitList {
+    head: *mut Waiter,
+    tail: *mut Waiter,
+}
+
+// Safety: 链表中的节点只在持有外层 SpinLock 时访问
+unsafe impl Send for WaitList {}
+
+impl WaitList {
+    const fn new() -> Self {
+        Self {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+        }
+    }
+
+    /// # Safety
+    /// `waiter` 必须有效，且在移出链表之前保持有效
+    unsafe fn push(&mut self, waiter: *mut Waiter) {
+        unsafe { (*waiter).next = ptr::null_mut() };
+        if self.tail.is_null() {
+            self.head = waiter;
+        } else {
+            unsafe { (*self.tail).next = waiter };
+        }
+        self.tail = waiter;
+    }
+
+    fn pop(&mut self) -> Option<*mut Waiter> {
+        if self.head.is_null() {
+            return None;
+        }
+        let waiter = self.head;
+        // Safety: 链表中的节点都有效
+        self.head = unsafe { (*waiter).next };
+        if self.head.is_null() {
+            self.tail = ptr::null_mut();
+        }
+        Some(waiter)
+    }
+
+    /// 移除指定节点（不在链表中时不做任何事）
+    fn remove(&mut self, waiter: *mut Waiter) {
+        let mut prev: *mut Waiter = ptr::null_mut();
+        let mut cur = self.head;
+        // Safety: 链表中的节点都有效
+        unsafe {
+            while !cur.is_null() {
+                if cur == waiter {
+                    let next = (*cur).next;
+                    if prev.is_null() {
+                        self.head = next;
+                    } else {
+                        (*prev).next = next;
+                    }
+                    if self.tail == cur {
+                        self.tail = prev;
+                    }
+                    return;
+                }
+                prev = cur;
+                cur = (*cur).next;
+            }
+        }
+    }
+}
+
+/// 条件变量
+///
+/// 一个条件变量应当始终与同一把锁配合使用。等待方应在循环中检查条件
+/// （或直接使用 [`wait_while`](Self::wait_while)），通知方在修改条件之后调用 `notify_*`。
+pub struct Condvar {
+    waiters: SpinLock<WaitList>,
+}
+
+impl Condvar {
+    /// 创建一个没有等待者的条件变量
+    pub const fn new() -> Self {
+        Self {
+            waiters: SpinLock::new(WaitList::new()),
+        }
+    }
+
+    /// 释放 `guard` 对应的锁并睡眠，直到被通知，返回前重新获取锁
+    pub fn wait<'a, G: CondvarGuard<'a>>(&self, guard: G) -> G {
+        self.wait_until(guard, None).0
+    }
+
+    /// 在 `condition` 返回 `true` 期间反复等待，返回时条件已不成立且持有锁
+    pub fn wait_while<'a, G, T, F>(&self, mut guard: G, mut condition: F) -> G
+    where
+        G: CondvarGuard<'a> + DerefMut<Target = T>,
+        T: ?Sized,
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    /// 与 [`wait`](Self::wait) 相同，但最多等待 `timeout` 纳秒
+    ///
+    /// # 返回值
+    /// 重新获取的保护器，以及是否因超时而返回
+    pub fn wait_timeout<'a, G: CondvarGuard<'a>>(&self, guard: G, timeout: u64) -> (G, bool) {
+        let deadline = sched_ops().now().saturating_add(timeout);
+        self.wait_until(guard, Some(deadline))
+    }
+
+    fn wait_until<'a, G: CondvarGuard<'a>>(&self, guard: G, deadline: Option<u64>) -> (G, bool) {
+        crate::might_sleep();
+        let ops = sched_ops();
+        let task = ops.current_task();
+        let mut waiter = Waiter {
+            task,
+            notified: false,
+            next: ptr::null_mut(),
+        };
+        let node: *mut Waiter = &mut waiter;
+        // 仍持有调用者的锁时登记并标记睡眠：通知方只能在我们释放锁之后看到条件变化，
+        // 而那时已经能在链表中找到我们，唤醒不会丢失
+        {
+            let mut list = self.waiters.lock();
+            // Safety: 返回前会确保节点已移出链表
+            unsafe { list.push(node) };
+            ops.block(task);
+        }
+        let lock = guard.unlock();
+        let timed_out = loop {
+            ops.schedule(task, deadline);
+            let mut list = self.waiters.lock();
+            // Safety: 持有 waiters 锁，节点仍在本函数的栈上
+            if unsafe { (*node).notified } {
+                break false;
+            }
+            if deadline.is_some_and(|deadline| ops.now() >= deadline) {
+                list.remove(node);
+                break true;
+            }
+            // 虚假唤醒：仍在链表中，继续睡眠
+            ops.block(task);
+        };
+        ops.release_task(task);
+        (G::relock(lock), timed_out)
+    }
+
+    /// 唤醒一个等待者（最早开始等待的）
+    pub fn notify_one(&self) {
+        let mut list = self.waiters.lock();
+        if let Some(waiter) = list.pop() {
+            // Safety: 等待者在看到 notified 之前不会离开 wait，而检查需要先获取 waiters 锁
+            unsafe { Self::notify(waiter) };
+        }
+    }
+
+    /// 唤醒所有等待者
+    pub fn notify_all(&self) {
+        let mut list = self.waiters.lock();
+        while let Some(waiter) = list.pop() {
+            // Safety: 同 notify_one
+            unsafe { Self::notify(waiter) };
+        }
+    }
+
+    /// # Safety
+    /// 必须持有 waiters 锁，且 `waiter` 刚从链表中移出
+    unsafe fn notify(waiter: *mut Waiter) {
+        unsafe {
+            (*waiter).notified = true;
+            sched_ops().wake((*waiter).task);
+        }
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_condvar_notify_one() {
+        let shared = Arc::new((SpinLock::new(false), Condvar::new()));
+        let notifier = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                *shared.0.lock() = true;
+                shared.1.notify_one();
+            })
+        };
+        let ready = shared.1.wait_while(shared.0.lock(), |ready| !*ready);
+        assert!(*ready);
+        drop(ready);
+        notifier.join().unwrap();
+    }
+
+    #[test]
+    fn test_condvar_notify_all() {
+        const WAITERS: usize = 4;
+        let shared = Arc::new((SpinLock::new((false, 0)), Condvar::new()));
+        let waiters: std::vec::Vec<_> = (0..WAITERS)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    let mut state = shared.1.wait_while(shared.0.lock(), |state| !state.0);
+                    state.1 += 1;
+                })
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(10));
+        shared.0.lock().0 = true;
+        shared.1.notify_all();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        assert_eq!(shared.0.lock().1, WAITERS);
+    }
+
+    #[test]
+    fn test_condvar_wait_timeout() {
+        let lock = SpinLock::new(1);
+        let cond = Condvar::new();
+        // 没有通知者时超时返回，且重新持有锁
+        let (mut guard, timed_out) = cond.wait_timeout(lock.lock(), 1_000_000);
+        assert!(timed_out);
+        *guard += 1;
+        drop(guard);
+        assert_eq!(*lock.lock(), 2);
+        // 超时的等待者已移出链表
+        assert!(cond.waiters.lock().head.is_null());
+        cond.notify_all();
+    }
+}
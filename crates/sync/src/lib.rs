@@ -1,33 +1,73 @@
 //! 同步原语
 //!
 //! 向其它内核模块提供基本的锁和同步原语
-//! 包括自旋锁、读写锁、中断保护等
+//! 包括自旋锁（任务上下文的 [`SpinLock`] 与中断安全的 [`SpinLockIrq`]）、读写锁、顺序锁、中断保护、一次性初始化、Per-CPU 引用计数等
+//!
+//! 开启 `lockdep` 特性后，基于 [`RawSpinLock`] 的锁会在运行时检查锁顺序与递归获取，见 [`LockClass`]；
+//! 开启 `lock-stat` 特性后，按锁类统计获取与竞争次数，见 [`for_each_lock_stat`]。
 //!
 //! # 架构依赖
 //!
 //! 此 crate 通过 `ArchOps` trait 抽象架构相关操作。
-//! 使用前必须调用 `register_arch_ops` 注册实现。
+//! 使用前必须调用 `register_arch_ops` 注册实现；下游 crate 的宿主机测试可开启 `mock` 特性，
+//! 调用 `mock::init_arch_ops` 注册单核的测试实现。
+//!
+//! [`Condvar`]、[`WaitQueue`]、[`Completion`]、[`synchronize_rcu`] 与 [`PercpuRef::kill`] 还需要通过 `SchedOps` trait 睡眠、唤醒任务和让出 CPU，
+//! 使用前必须调用 `register_sched_ops` 注册实现。
 
 #![no_std]
 
+mod completion;
+mod condvar;
 mod intr_guard;
+#[cfg(any(test, feature = "lock-stat"))]
+mod lock_stat;
+mod lockdep;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod once_lock;
+mod percpu_ref;
 mod preempt;
 mod raw_spin_lock;
 mod raw_spin_lock_without_guard;
+mod rcu;
 mod rwlock;
+mod seq_lock;
 mod spin_lock;
+mod spin_lock_irq;
 mod ticket_lock;
+mod wait_queue;
 
+pub use completion::Completion;
+pub use condvar::{Condvar, CondvarGuard};
 pub use intr_guard::*;
+#[cfg(any(test, feature = "lock-stat"))]
+pub use lock_stat::{LockStat, clear_lock_stats, for_each_lock_stat};
+pub use lockdep::{LockClass, MAX_LOCK_CLASSES};
+pub use once_lock::{LazyLock, OnceLock};
+pub use percpu_ref::PercpuRef;
 pub use preempt::{PreemptGuard, preempt_disable, preempt_disabled, preempt_enable};
 pub use raw_spin_lock::*;
 pub use raw_spin_lock_without_guard::*;
+pub use rcu::{
+    Rcu, RcuReadGuard, RcuRef, RcuWriteGuard, call_rcu, rcu_note_quiescent_state,
+    rcu_process_callbacks, rcu_read_lock, rcu_read_lock_held, rcu_read_unlock, rcu_tick,
+    synchronize_rcu,
+};
 pub use rwlock::*;
+pub use seq_lock::{SeqLock, SeqLockWriteGuard};
 pub use spin_lock::*;
+pub use spin_lock_irq::{
+    SpinLockIrq, SpinLockIrqGuard, in_interrupt, irq_enter, irq_exit, irq_locks_held, might_sleep,
+};
 pub use ticket_lock::*;
+pub use wait_queue::{WaitOptions, WaitQueue, WaitResult};
 
 use core::sync::atomic::{AtomicUsize, Ordering};
 
+extern crate alloc;
+#[cfg(test)]
+extern crate std;
 #[cfg(test)]
 extern crate test_support;
 
@@ -55,6 +95,18 @@ pub trait ArchOps: Send + Sync {
 
     /// 获取最大 CPU 数量
     fn max_cpu_count(&self) -> usize;
+
+    /// 读取单调递增的周期计数器，用于锁竞争统计计量自旋时间；不支持时返回 0
+    fn cycles(&self) -> u64 {
+        0
+    }
+
+    /// 自旋等锁中的一次让步
+    ///
+    /// 等锁时已关闭本地中断，需要在等待中响应的核间请求（如 TLB 刷新）在这里处理。
+    fn cpu_relax(&self) {
+        core::hint::spin_loop();
+    }
 }
 
 /// 全局架构操作实例（存储 fat pointer 的两个部分）
@@ -94,8 +146,96 @@ pub(crate) fn arch_ops() -> &'static dyn ArchOps {
     &test_support::mock::arch::MOCK_ARCH_OPS
 }
 
+/// 无锁操作各步骤之间的调度点
+///
+/// 宿主机测试中交给受控交错调度器（`test_support::interleave`）切换线程，其余情况下为空操作。
+#[inline(always)]
+pub(crate) fn interleave_point() {
+    #[cfg(test)]
+    test_support::interleave::yield_now();
+}
+
+/// 调度相关操作的 trait
+///
+/// 由 os crate 实现并注册，供 [`Condvar`] 与 [`WaitQueue`] 睡眠和唤醒任务。任务以不透明的 `usize` 句柄表示。
+pub trait SchedOps: Send + Sync {
+    /// 获取当前任务的句柄，句柄持有任务的一个引用，用完后交给 `release_task`
+    fn current_task(&self) -> usize;
+
+    /// 释放 `current_task` 返回的句柄
+    fn release_task(&self, task: usize);
+
+    /// 把任务标记为睡眠（只修改状态，不切换任务）
+    fn block(&self, task: usize);
+
+    /// 与 `block` 相同，但睡眠可以被信号打断
+    fn block_interruptible(&self, task: usize) {
+        self.block(task);
+    }
+
+    /// 任务是否有会打断可中断睡眠的待处理信号
+    fn signal_pending(&self, _task: usize) -> bool {
+        false
+    }
+
+    /// 唤醒任务，任务未睡眠时不做任何事
+    fn wake(&self, task: usize);
+
+    /// 让出 CPU，直到任务被唤醒或到达 `deadline`（单调时钟，纳秒）
+    ///
+    /// 在 `block` 与本调用之间已被唤醒时应尽快返回。
+    fn schedule(&self, task: usize, deadline: Option<u64>);
+
+    /// 让出 CPU，当前任务保持可运行
+    fn yield_now(&self);
+
+    /// 当前单调时钟（纳秒）
+    fn now(&self) -> u64;
+}
+
+/// 全局调度操作实例（存储 fat pointer 的两个部分）
+static SCHED_OPS_DATA: AtomicUsize = AtomicUsize::new(0);
+static SCHED_OPS_VTABLE: AtomicUsize = AtomicUsize::new(0);
+
+/// 注册调度操作实现
+///
+/// # Safety
+/// 必须在单线程环境下调用，且只能调用一次
+pub unsafe fn register_sched_ops(ops: &'static dyn SchedOps) {
+    let ptr = ops as *const dyn SchedOps;
+    // SAFETY: transmute 在这里是安全的，因为 fat pointer 的布局是 (data, vtable)
+    let (data, vtable) =
+        unsafe { core::mem::transmute::<*const dyn SchedOps, (usize, usize)>(ptr) };
+    SCHED_OPS_DATA.store(data, Ordering::Release);
+    SCHED_OPS_VTABLE.store(vtable, Ordering::Release);
+}
+
+/// 获取调度操作实例
+#[inline]
+#[cfg(not(test))]
+pub(crate) fn sched_ops() -> &'static dyn SchedOps {
+    let data = SCHED_OPS_DATA.load(Ordering::Acquire);
+    let vtable = SCHED_OPS_VTABLE.load(Ordering::Acquire);
+    if data == 0 {
+        panic!("sync: SchedOps not registered, call register_sched_ops first");
+    }
+    // SAFETY: data 和 vtable 是通过 register_sched_ops 设置的有效指针
+    unsafe { &*core::mem::transmute::<(usize, usize), *const dyn SchedOps>((data, vtable)) }
+}
+
+/// 获取调度操作实例（测试模式）
+#[inline]
+#[cfg(test)]
+pub(crate) fn sched_ops() -> &'static dyn SchedOps {
+    &test_mock::MOCK_SCHED_OPS
+}
+
 #[cfg(test)]
 mod test_mock {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use test_support::interleave;
+
     use super::ArchOps;
 
     impl ArchOps for test_support::mock::arch::MockArchOps {
@@ -112,11 +252,110 @@ mod test_mock {
         }
 
         fn cpu_id(&self) -> usize {
-            self.cpu_id()
+            CPU_ID
+                .with(|cpu| cpu.get())
+                .unwrap_or_else(|| self.cpu_id())
         }
 
         fn max_cpu_count(&self) -> usize {
             self.max_cpu_count()
         }
+
+        fn cpu_relax(&self) {
+            test_support::interleave::spin_loop();
+        }
+    }
+
+    std::thread_local! {
+        static CPU_ID: core::cell::Cell<Option<usize>> = const { core::cell::Cell::new(None) };
+    }
+
+    /// 让当前测试线程模拟运行在 `cpu` 上；未设置的线程共用 mock 的 CPU 编号
+    pub(crate) fn set_current_cpu(cpu: usize) {
+        CPU_ID.with(|id| id.set(Some(cpu)));
+    }
+
+    /// 用宿主线程模拟任务：句柄是装箱的 [`MockTask`]，睡眠/唤醒对应 park/unpark
+    ///
+    /// 运行在受控交错模型（`test_support::interleave`）中时，睡眠改为在调度点上自旋等待唤醒标志，
+    /// 各调度操作本身也是调度点，用来穷举 block / wake / schedule 之间的唤醒竞争。
+    pub(super) struct MockSchedOps;
+
+    pub(super) static MOCK_SCHED_OPS: MockSchedOps = MockSchedOps;
+
+    /// 任务句柄指向的对象：同一线程的所有句柄共享一个唤醒标志
+    struct MockTask {
+        thread: std::thread::Thread,
+        /// `block` 清除、`wake` 置位，对应内核中任务的睡眠 / 可运行状态
+        woken: std::sync::Arc<AtomicBool>,
+    }
+
+    std::thread_local! {
+        static WOKEN: std::sync::Arc<AtomicBool> = std::sync::Arc::new(AtomicBool::new(true));
+    }
+
+    fn task(task: usize) -> &'static MockTask {
+        unsafe { &*(task as *const MockTask) }
+    }
+
+    fn start() -> std::time::Instant {
+        static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        *START.get_or_init(std::time::Instant::now)
+    }
+
+    impl super::SchedOps for MockSchedOps {
+        fn current_task(&self) -> usize {
+            let task = MockTask {
+                thread: std::thread::current(),
+                woken: WOKEN.with(|woken| woken.clone()),
+            };
+            std::boxed::Box::into_raw(std::boxed::Box::new(task)) as usize
+        }
+
+        fn release_task(&self, task: usize) {
+            drop(unsafe { std::boxed::Box::from_raw(task as *mut MockTask) });
+        }
+
+        fn block(&self, task: usize) {
+            interleave::yield_now();
+            self::task(task).woken.store(false, Ordering::SeqCst);
+            interleave::yield_now();
+        }
+
+        fn wake(&self, task: usize) {
+            interleave::yield_now();
+            let task = self::task(task);
+            task.woken.store(true, Ordering::SeqCst);
+            task.thread.unpark();
+        }
+
+        fn schedule(&self, task: usize, deadline: Option<u64>) {
+            if interleave::in_model() {
+                // 模型中没有时钟推进：有截止时间时让出一次即视为超时，否则自旋到被唤醒
+                interleave::yield_now();
+                while deadline.is_none() && !self::task(task).woken.load(Ordering::SeqCst) {
+                    interleave::spin_loop();
+                }
+                return;
+            }
+            match deadline {
+                Some(deadline) => std::thread::park_timeout(std::time::Duration::from_nanos(
+                    deadline.saturating_sub(self.now()),
+                )),
+                None => std::thread::park(),
+            }
+        }
+
+        fn yield_now(&self) {
+            if interleave::in_model() {
+                interleave::spin_loop();
+            } else {
+                std::thread::yield_now();
+            }
+        }
+
+        fn now(&self) -> u64 {
+            start().elapsed().as_nanos() as u64
+        }
     }
 }
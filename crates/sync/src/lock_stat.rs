@@ -0,0 +1,142 @@
+//! 锁竞争统计
+//!
+//! 开启 `lock-stat` 特性后，指定了 [`LockClass`] 的 `SpinLock`/`RawSpinLock`、[`RwLock`](crate::RwLock)
+//! 与 [`TicketLock`](crate::TicketLock) 在每次阻塞式获取时按锁类记录：获取次数、发生竞争的获取次数、
+//! 单次获取的最长自旋周期数（由 [`ArchOps::cycles`](crate::ArchOps::cycles) 计量）。
+//!
+//! 计数按 CPU 分开存放，获取路径只写本 CPU 的缓存行；[`for_each_lock_stat`] 读取时汇总各 CPU 的计数，
+//! 供 procfs 生成 `/proc/lock_stat`。
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::arch_ops;
+use crate::lockdep::{LockClass, MAX_LOCK_CLASSES, for_each_class};
+use crate::preempt::MAX_CPUS;
+
+/// 一个锁类的竞争统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockStat {
+    /// 获取次数
+    pub acquisitions: u64,
+    /// 需要等待的获取次数
+    pub contentions: u64,
+    /// 单次获取的最长自旋周期数
+    pub max_spin_cycles: u64,
+}
+
+/// 单个 CPU 上一个锁类的计数
+struct ClassCounters {
+    acquisitions: AtomicU64,
+    contentions: AtomicU64,
+    max_spin_cycles: AtomicU64,
+}
+
+impl ClassCounters {
+    const fn new() -> Self {
+        Self {
+            acquisitions: AtomicU64::new(0),
+            contentions: AtomicU64::new(0),
+            max_spin_cycles: AtomicU64::new(0),
+        }
+    }
+}
+
+/// 单个 CPU 上所有锁类的计数，按缓存行对齐避免 CPU 之间伪共享
+#[repr(align(64))]
+struct CpuCounters([ClassCounters; MAX_LOCK_CLASSES]);
+
+static COUNTERS: [CpuCounters; MAX_CPUS] =
+    [const { CpuCounters([const { ClassCounters::new() }; MAX_LOCK_CLASSES]) }; MAX_CPUS];
+
+/// 当前周期计数，用于计量自旋时间
+#[inline]
+#[cfg_attr(not(feature = "lock-stat"), allow(dead_code))]
+pub(crate) fn cycles() -> u64 {
+    arch_ops().cycles()
+}
+
+/// 记录一次获取，`spin_cycles` 为 `None` 表示无需等待
+pub(crate) fn record(class: Option<&'static LockClass>, spin_cycles: Option<u64>) {
+    let Some(index) = class.and_then(LockClass::index) else {
+        return;
+    };
+    let Some(cpu) = COUNTERS.get(arch_ops().cpu_id()) else {
+        return;
+    };
+    let counters = &cpu.0[index];
+    counters.acquisitions.fetch_add(1, Ordering::Relaxed);
+    if let Some(spin_cycles) = spin_cycles {
+        counters.contentions.fetch_add(1, Ordering::Relaxed);
+        counters
+            .max_spin_cycles
+            .fetch_max(spin_cycles, Ordering::Relaxed);
+    }
+}
+
+/// 记录一次获取，`spin_start` 为开始等待时的周期计数，`None` 表示无需等待
+#[cfg_attr(not(feature = "lock-stat"), allow(dead_code))]
+pub(crate) fn record_since(class: Option<&'static LockClass>, spin_start: Option<u64>) {
+    record(
+        class,
+        spin_start.map(|start| cycles().saturating_sub(start)),
+    );
+}
+
+/// 汇总各 CPU 的计数，按锁类编号顺序对每个已使用的锁类调用 `f`
+pub fn for_each_lock_stat(mut f: impl FnMut(&'static LockClass, LockStat)) {
+    for_each_class(|index, class| {
+        let mut stat = LockStat::default();
+        for cpu in COUNTERS.iter() {
+            let counters = &cpu.0[index];
+            stat.acquisitions += counters.acquisitions.load(Ordering::Relaxed);
+            stat.contentions += counters.contentions.load(Ordering::Relaxed);
+            stat.max_spin_cycles = stat
+                .max_spin_cycles
+                .max(counters.max_spin_cycles.load(Ordering::Relaxed));
+        }
+        f(class, stat);
+    });
+}
+
+/// 清零所有锁类的统计
+pub fn clear_lock_stats() {
+    for counters in COUNTERS.iter().flat_map(|cpu| cpu.0.iter()) {
+        counters.acquisitions.store(0, Ordering::Relaxed);
+        counters.contentions.store(0, Ordering::Relaxed);
+        counters.max_spin_cycles.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static STAT_CLASS: LockClass = LockClass::new("lock_stat_test");
+
+    fn stat_of(class: &'static LockClass) -> Option<LockStat> {
+        let mut found = None;
+        for_each_lock_stat(|c, stat| {
+            if core::ptr::eq(c, class) {
+                found = Some(stat);
+            }
+        });
+        found
+    }
+
+    #[test]
+    fn test_lock_stat_record_and_clear() {
+        record(Some(&STAT_CLASS), None);
+        record(Some(&STAT_CLASS), Some(30));
+        record(Some(&STAT_CLASS), Some(10));
+        // 未指定锁类的锁不统计
+        record(None, Some(100));
+
+        let stat = stat_of(&STAT_CLASS).unwrap();
+        assert_eq!(stat.acquisitions, 3);
+        assert_eq!(stat.contentions, 2);
+        assert_eq!(stat.max_spin_cycles, 30);
+
+        clear_lock_stats();
+        assert_eq!(stat_of(&STAT_CLASS).unwrap(), LockStat::default());
+    }
+}
@@ -0,0 +1,496 @@
+//! 锁依赖检查（lockdep）
+//!
+//! 开启 `lockdep` 特性后，[`RawSpinLock`](crate::RawSpinLock)（以及基于它的 `SpinLock`、`SeqLock` 等）
+//! 在获取和释放时记录当前持有的锁，并检查：
+//!
+//! - 递归获取：再次获取已持有的同一把锁，这必然死锁；
+//! - 层级违例：持有层级较低的锁时获取层级较高的锁（层级即 `os/src/sync` 锁顺序表中的编号）；
+//! - 顺序反转：曾经观察到 B → … → A 的获取顺序，现在又在持有 A 时获取 B。
+//!
+//! 锁按 [`LockClass`] 归类，同一类的所有锁实例共享顺序记录；未指定类的锁只检查递归获取。
+//! 自旋锁持有期间本地中断关闭、不会切换任务，因此按 CPU 记录的持有栈就是当前任务的持有栈。
+//!
+//! 发现问题时通过 klog 报告一次并打印持有栈，随后关闭检查，避免一个问题引发成串的报告。
+//! 未开启特性时 [`LockClass`] 仍然可用，只是不做任何记录。
+
+use core::sync::atomic::AtomicUsize;
+#[cfg(any(test, feature = "lockdep", feature = "lock-stat"))]
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// 可以分配的锁类数量上限，超出的锁类不参与顺序检查与竞争统计
+pub const MAX_LOCK_CLASSES: usize = 64;
+
+/// 下一个锁类编号，0 保留表示尚未分配
+#[cfg(any(test, feature = "lockdep", feature = "lock-stat"))]
+static NEXT_CLASS_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// 已分配编号的锁类，下标为锁类编号
+#[cfg(any(test, feature = "lockdep", feature = "lock-stat"))]
+static CLASSES: [AtomicPtr<LockClass>; MAX_LOCK_CLASSES] =
+    [const { AtomicPtr::new(core::ptr::null_mut()) }; MAX_LOCK_CLASSES];
+
+/// 锁类：同一类的锁实例共享获取顺序记录
+///
+/// # 示例
+/// ```ignore
+/// static SCHEDULER_CLASS: LockClass = LockClass::with_level("scheduler", 3);
+/// static RQ: SpinLock<RunQueue> = SpinLock::with_class(RunQueue::new(), &SCHEDULER_CLASS);
+/// ```
+#[derive(Debug)]
+pub struct LockClass {
+    name: &'static str,
+    /// 层级，数值小的先获取；0 表示不参与层级检查
+    level: u32,
+    /// 锁类编号，首次使用时分配；`usize::MAX` 表示编号已耗尽
+    #[cfg_attr(
+        not(any(test, feature = "lockdep", feature = "lock-stat")),
+        allow(dead_code)
+    )]
+    id: AtomicUsize,
+}
+
+impl LockClass {
+    /// 创建不参与层级检查的锁类
+    pub const fn new(name: &'static str) -> Self {
+        Self::with_level(name, 0)
+    }
+
+    /// 创建指定层级的锁类，持有高层级数值的锁时不能获取低层级数值的锁
+    pub const fn with_level(name: &'static str, level: u32) -> Self {
+        Self {
+            name,
+            level,
+            id: AtomicUsize::new(0),
+        }
+    }
+
+    /// 锁类名称
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// 锁类层级
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// 锁类编号（从 0 开始），编号耗尽时返回 `None`
+    #[cfg(any(test, feature = "lockdep", feature = "lock-stat"))]
+    pub(crate) fn index(&'static self) -> Option<usize> {
+        let mut id = self.id.load(Ordering::Acquire);
+        if id == 0 {
+            let new = NEXT_CLASS_ID.fetch_add(1, Ordering::Relaxed);
+            let new = if new > MAX_LOCK_CLASSES {
+                usize::MAX
+            } else {
+                new
+            };
+            id = match self
+                .id
+                .compare_exchange(0, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    if new != usize::MAX {
+                        CLASSES[new - 1].store(self as *const Self as *mut Self, Ordering::Release);
+                    }
+                    new
+                }
+                Err(cur) => cur,
+            };
+        }
+        (id != usize::MAX).then_some(id - 1)
+    }
+}
+
+/// 按编号顺序遍历已分配编号的锁类
+#[cfg(any(test, feature = "lock-stat"))]
+pub(crate) fn for_each_class(mut f: impl FnMut(usize, &'static LockClass)) {
+    for (index, class) in CLASSES.iter().enumerate() {
+        let class = class.load(Ordering::Acquire);
+        if !class.is_null() {
+            // Safety: 只登记 'static 锁类
+            f(index, unsafe { &*class });
+        }
+    }
+}
+
+#[cfg(any(test, feature = "lockdep"))]
+pub(crate) use validate::*;
+
+#[cfg(any(test, feature = "lockdep"))]
+mod validate {
+    use super::*;
+    use core::fmt;
+    use core::sync::atomic::AtomicU64;
+
+    /// 每个 CPU 同时持有的锁数量上限
+    pub(crate) const MAX_HELD_LOCKS: usize = 16;
+
+    /// 锁类之间的获取顺序图：第 `i` 行的第 `j` 位表示曾在持有 `i` 类锁时获取 `j` 类锁
+    pub(crate) struct LockGraph {
+        deps: [AtomicU64; MAX_LOCK_CLASSES],
+    }
+
+    impl LockGraph {
+        pub(crate) const fn new() -> Self {
+            Self {
+                deps: [const { AtomicU64::new(0) }; MAX_LOCK_CLASSES],
+            }
+        }
+
+        /// 记录 `from` → `to` 的获取顺序
+        pub(crate) fn add(&self, from: usize, to: usize) {
+            self.deps[from].fetch_or(1 << to, Ordering::Relaxed);
+        }
+
+        /// 是否存在从 `from` 到 `to` 的获取顺序路径
+        pub(crate) fn reaches(&self, from: usize, to: usize) -> bool {
+            let mut visited = 1u64 << from;
+            let mut frontier = visited;
+            while frontier != 0 {
+                let node = frontier.trailing_zeros() as usize;
+                frontier &= frontier - 1;
+                let next = self.deps[node].load(Ordering::Relaxed);
+                if next & (1 << to) != 0 {
+                    return true;
+                }
+                frontier |= next & !visited;
+                visited |= next;
+            }
+            false
+        }
+    }
+
+    /// 检查发现的问题
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) enum Violation {
+        /// 再次获取已持有的锁
+        Recursive {
+            class: Option<&'static LockClass>,
+            addr: usize,
+        },
+        /// 违反锁类层级
+        Hierarchy {
+            held: &'static LockClass,
+            acquiring: &'static LockClass,
+        },
+        /// 与之前观察到的获取顺序相反
+        Inversion {
+            held: &'static LockClass,
+            acquiring: &'static LockClass,
+        },
+        /// 持有的锁超过 [`MAX_HELD_LOCKS`]
+        TooDeep,
+    }
+
+    impl fmt::Display for Violation {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Violation::Recursive { class, addr } => write!(
+                    f,
+                    "recursive locking of {} ({:#x})",
+                    class.map_or("<unclassed>", |c| c.name),
+                    addr
+                ),
+                Violation::Hierarchy { held, acquiring } => write!(
+                    f,
+                    "acquiring {} (level {}) while holding {} (level {}) violates lock hierarchy",
+                    acquiring.name, acquiring.level, held.name, held.level
+                ),
+                Violation::Inversion { held, acquiring } => write!(
+                    f,
+                    "possible circular locking: acquiring {} while holding {}, \
+                     but {} -> {} was seen before",
+                    acquiring.name, held.name, acquiring.name, held.name
+                ),
+                Violation::TooDeep => write!(f, "more than {} locks held", MAX_HELD_LOCKS),
+            }
+        }
+    }
+
+    /// 持有的一把锁
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct HeldLock {
+        pub(crate) addr: usize,
+        pub(crate) class: Option<&'static LockClass>,
+    }
+
+    /// 持有栈，按获取顺序记录当前持有的锁
+    pub(crate) struct HeldLocks {
+        depth: usize,
+        locks: [HeldLock; MAX_HELD_LOCKS],
+    }
+
+    impl HeldLocks {
+        pub(crate) const fn new() -> Self {
+            Self {
+                depth: 0,
+                locks: [HeldLock {
+                    addr: 0,
+                    class: None,
+                }; MAX_HELD_LOCKS],
+            }
+        }
+
+        /// 当前持有的锁
+        pub(crate) fn held(&self) -> &[HeldLock] {
+            &self.locks[..self.depth]
+        }
+
+        /// 检查获取 `addr` 处的锁是否会出问题
+        pub(crate) fn check(
+            &self,
+            addr: usize,
+            class: Option<&'static LockClass>,
+            graph: &LockGraph,
+        ) -> Result<(), Violation> {
+            if self.held().iter().any(|held| held.addr == addr) {
+                return Err(Violation::Recursive { class, addr });
+            }
+            let Some(acquiring) = class else {
+                return Ok(());
+            };
+            for held in self.held().iter().filter_map(|held| held.class) {
+                if core::ptr::eq(held, acquiring) {
+                    continue;
+                }
+                if held.level != 0 && acquiring.level != 0 && acquiring.level < held.level {
+                    return Err(Violation::Hierarchy { held, acquiring });
+                }
+                let inverted = match (acquiring.index(), held.index()) {
+                    (Some(from), Some(to)) => graph.reaches(from, to),
+                    _ => false,
+                };
+                if inverted {
+                    return Err(Violation::Inversion { held, acquiring });
+                }
+            }
+            Ok(())
+        }
+
+        /// 记录持有的每个锁类到 `class` 的获取顺序
+        pub(crate) fn record(&self, class: &'static LockClass, graph: &LockGraph) {
+            let Some(to) = class.index() else {
+                return;
+            };
+            for held in self.held().iter().filter_map(|held| held.class) {
+                match held.index() {
+                    Some(from) if from != to => graph.add(from, to),
+                    _ => {}
+                }
+            }
+        }
+
+        /// 压入获得的锁
+        pub(crate) fn push(
+            &mut self,
+            addr: usize,
+            class: Option<&'static LockClass>,
+        ) -> Result<(), Violation> {
+            if self.depth == MAX_HELD_LOCKS {
+                return Err(Violation::TooDeep);
+            }
+            self.locks[self.depth] = HeldLock { addr, class };
+            self.depth += 1;
+            Ok(())
+        }
+
+        /// 移除释放的锁，锁不必位于栈顶；不在栈中（例如检查关闭前获取的锁）时忽略
+        pub(crate) fn release(&mut self, addr: usize) {
+            if let Some(pos) = self.held().iter().rposition(|held| held.addr == addr) {
+                self.locks.copy_within(pos + 1..self.depth, pos);
+                self.depth -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "lockdep")]
+mod hooks {
+    use super::*;
+    use core::cell::UnsafeCell;
+    use core::sync::atomic::AtomicBool;
+
+    use crate::arch_ops;
+    #[cfg(not(test))]
+    use crate::preempt::MAX_CPUS;
+
+    /// 检查是否仍然开启，报告过一次问题后关闭
+    static ENABLED: AtomicBool = AtomicBool::new(true);
+
+    static GRAPH: LockGraph = LockGraph::new();
+
+    /// Per-CPU 持有栈，只在本地中断关闭时由本 CPU 访问
+    struct CpuHeldLocks(UnsafeCell<HeldLocks>);
+
+    // Safety: 每个 CPU 只访问自己的持有栈，且访问期间本地中断关闭
+    unsafe impl Sync for CpuHeldLocks {}
+
+    #[cfg(not(test))]
+    static HELD_LOCKS: [CpuHeldLocks; MAX_CPUS] =
+        [const { CpuHeldLocks(UnsafeCell::new(HeldLocks::new())) }; MAX_CPUS];
+
+    /// 本 CPU 的持有栈，检查已关闭时返回 `None`
+    ///
+    /// # Safety
+    /// 调用者必须关闭本地中断，且不能同时持有返回的另一个引用
+    #[cfg(not(test))]
+    unsafe fn this_cpu_held() -> Option<(usize, &'static mut HeldLocks)> {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        let cpu = arch_ops().cpu_id();
+        let held = HELD_LOCKS.get(cpu)?;
+        Some((cpu, unsafe { &mut *held.0.get() }))
+    }
+
+    /// 本线程的持有栈（测试模式）
+    ///
+    /// 宿主测试中所有线程的 CPU 编号相同，改用线程局部的持有栈。
+    ///
+    /// # Safety
+    /// 调用者不能同时持有返回的另一个引用
+    #[cfg(test)]
+    unsafe fn this_cpu_held() -> Option<(usize, &'static mut HeldLocks)> {
+        std::thread_local! {
+            static HELD: &'static CpuHeldLocks =
+                std::boxed::Box::leak(std::boxed::Box::new(CpuHeldLocks(UnsafeCell::new(HeldLocks::new()))));
+        }
+        if !ENABLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        let held = HELD.with(|held| *held);
+        Some((arch_ops().cpu_id(), unsafe { &mut *held.0.get() }))
+    }
+
+    /// 报告问题并关闭检查
+    fn report(cpu: usize, held: &HeldLocks, violation: Violation) {
+        if !ENABLED.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        klog::pr_err!("lockdep: {}", violation);
+        klog::pr_err!("lockdep: locks held by CPU{}:", cpu);
+        for lock in held.held() {
+            klog::pr_err!(
+                "lockdep:   {} ({:#x})",
+                lock.class.map_or("<unclassed>", |c| c.name),
+                lock.addr
+            );
+        }
+        klog::pr_err!("lockdep: turning off lock validation");
+    }
+
+    /// 获取锁之前调用；`trylock` 为真时锁已经获得，只记录持有状态
+    pub(crate) fn lock_acquire(addr: usize, class: Option<&'static LockClass>, trylock: bool) {
+        // Safety: 自旋锁在获取前已关闭本地中断
+        let Some((cpu, held)) = (unsafe { this_cpu_held() }) else {
+            return;
+        };
+        if !trylock {
+            if let Err(violation) = held.check(addr, class, &GRAPH) {
+                report(cpu, held, violation);
+                return;
+            }
+            if let Some(class) = class {
+                held.record(class, &GRAPH);
+            }
+        }
+        if let Err(violation) = held.push(addr, class) {
+            report(cpu, held, violation);
+        }
+    }
+
+    /// 释放锁之前调用
+    pub(crate) fn lock_release(addr: usize) {
+        // Safety: 自旋锁守卫在恢复中断之前释放锁
+        if let Some((_, held)) = unsafe { this_cpu_held() } {
+            held.release(addr);
+        }
+    }
+}
+
+#[cfg(feature = "lockdep")]
+pub(crate) use hooks::{lock_acquire, lock_release};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static A: LockClass = LockClass::new("a");
+    static B: LockClass = LockClass::new("b");
+    static C: LockClass = LockClass::new("c");
+    static OUTER: LockClass = LockClass::with_level("outer", 1);
+    static INNER: LockClass = LockClass::with_level("inner", 2);
+
+    #[test]
+    fn test_lockdep_recursive_acquisition() {
+        let graph = LockGraph::new();
+        let mut held = HeldLocks::new();
+        held.push(0x1000, None).unwrap();
+        assert!(held.check(0x2000, None, &graph).is_ok());
+        assert!(matches!(
+            held.check(0x1000, None, &graph),
+            Err(Violation::Recursive { addr: 0x1000, .. })
+        ));
+        // 同一类的不同实例可以嵌套
+        held.push(0x3000, Some(&A)).unwrap();
+        assert!(held.check(0x4000, Some(&A), &graph).is_ok());
+    }
+
+    #[test]
+    fn test_lockdep_detects_inversion_through_chain() {
+        let graph = LockGraph::new();
+        // 观察到 a -> b 与 b -> c
+        let mut held = HeldLocks::new();
+        held.push(1, Some(&A)).unwrap();
+        held.record(&B, &graph);
+        held.push(2, Some(&B)).unwrap();
+        held.record(&C, &graph);
+        held.release(2);
+        held.release(1);
+        assert!(held.held().is_empty());
+
+        // 持有 c 时获取 a 与 a -> b -> c 构成环
+        held.push(3, Some(&C)).unwrap();
+        assert!(matches!(
+            held.check(4, Some(&A), &graph),
+            Err(Violation::Inversion { held, acquiring })
+                if core::ptr::eq(held, &C) && core::ptr::eq(acquiring, &A)
+        ));
+        assert!(held.check(4, Some(&B), &graph).is_err());
+        held.release(3);
+
+        // 顺序一致的获取不报告
+        held.push(1, Some(&A)).unwrap();
+        assert!(held.check(3, Some(&C), &graph).is_ok());
+    }
+
+    #[test]
+    fn test_lockdep_hierarchy_and_release_order() {
+        let graph = LockGraph::new();
+        let mut held = HeldLocks::new();
+        held.push(1, Some(&OUTER)).unwrap();
+        assert!(held.check(2, Some(&INNER), &graph).is_ok());
+        held.push(2, Some(&INNER)).unwrap();
+
+        // 先释放外层锁后，仍持有内层锁，此时获取外层锁违反层级
+        held.release(1);
+        assert_eq!(held.held().len(), 1);
+        assert!(matches!(
+            held.check(1, Some(&OUTER), &graph),
+            Err(Violation::Hierarchy { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lockdep_held_stack_overflow() {
+        let mut held = HeldLocks::new();
+        for addr in 0..MAX_HELD_LOCKS {
+            held.push(addr, None).unwrap();
+        }
+        assert!(matches!(
+            held.push(usize::MAX, None),
+            Err(Violation::TooDeep)
+        ));
+    }
+}
@@ -0,0 +1,51 @@
+//! 宿主机测试用的架构操作
+//!
+//! 下游 crate 的单元测试运行在宿主机上，没有真实的中断与多核。
+//! [`init_arch_ops`] 注册一个单核、不操作中断的 [`ArchOps`] 实现，
+//! 由各测试在使用锁之前调用，多个测试线程并发调用时只注册一次。
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{ArchOps, register_arch_ops};
+
+struct DummyArchOps;
+
+impl ArchOps for DummyArchOps {
+    unsafe fn read_and_disable_interrupts(&self) -> usize {
+        0
+    }
+
+    unsafe fn restore_interrupts(&self, _flags: usize) {}
+
+    fn sstatus_sie(&self) -> usize {
+        0
+    }
+
+    fn cpu_id(&self) -> usize {
+        0
+    }
+
+    fn max_cpu_count(&self) -> usize {
+        1
+    }
+}
+
+static DUMMY_ARCH_OPS: DummyArchOps = DummyArchOps;
+// 0 = uninit, 1 = initializing, 2 = ready
+static INIT: AtomicUsize = AtomicUsize::new(0);
+
+/// 注册宿主机测试用的架构操作，可重复调用
+pub fn init_arch_ops() {
+    match INIT.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => {
+            // SAFETY: 只有抢到初始化权的线程注册，其它线程等待注册完成
+            unsafe { register_arch_ops(&DUMMY_ARCH_OPS) };
+            INIT.store(2, Ordering::Release);
+        }
+        Err(_) => {
+            while INIT.load(Ordering::Acquire) != 2 {
+                core::hint::spin_loop();
+            }
+        }
+    }
+}
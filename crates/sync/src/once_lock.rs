@@ -0,0 +1,302 @@
+//! 一次性初始化单元
+//!
+//! [`OnceLock`] 保存一个只初始化一次的值，[`LazyLock`] 在其上附带初始化函数，首次解引用时初始化。
+//! 两者都可以用在 `static` 中，取代 `lazy_static!`。
+//!
+//! 状态显式区分“未初始化 / 初始化中 / 已完成 / 已中毒”：
+//! - 初始化完成时以 Release 写入状态，读者以 Acquire 读取，保证看到完整的值；
+//! - 其它 CPU 在初始化进行中访问时自旋等待，初始化函数 panic 后单元进入中毒状态，
+//!   之后的访问直接 panic，而不是永远自旋。
+//!
+//! # 示例
+//! ```ignore
+//! static DRIVERS: LazyLock<Rcu<Vec<Arc<dyn Driver>>>> = LazyLock::new(|| Rcu::new(Vec::new()));
+//!
+//! static BOOT_ARGS: OnceLock<String> = OnceLock::new();
+//! BOOT_ARGS.set(args).unwrap();
+//! ```
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+const POISONED: u8 = 3;
+
+/// 只初始化一次的值
+pub struct OnceLock<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Safety: 值只在 RUNNING 状态下由唯一的初始化者写入，之后只读共享
+unsafe impl<T: Send> Send for OnceLock<T> {}
+unsafe impl<T: Send + Sync> Sync for OnceLock<T> {}
+
+/// 初始化函数 panic 时把单元标记为中毒
+struct PoisonOnPanic<'a>(&'a AtomicU8);
+
+impl Drop for PoisonOnPanic<'_> {
+    fn drop(&mut self) {
+        self.0.store(POISONED, Ordering::Release);
+    }
+}
+
+impl<T> OnceLock<T> {
+    /// 创建未初始化的单元
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(INCOMPLETE),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// 已初始化时返回值的引用
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        if self.state.load(Ordering::Acquire) == COMPLETE {
+            // Safety: COMPLETE 之后值不再被修改
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// 已初始化时返回值的可变引用
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == COMPLETE {
+            // Safety: 独占访问且值已初始化
+            Some(unsafe { self.value.get_mut().assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// 用 `value` 初始化，已初始化（或正在初始化）时原样返回 `value`
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.initialize(|| value.take().unwrap());
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    /// 返回值的引用，未初始化时先调用 `f` 初始化
+    ///
+    /// # Panics
+    /// 单元已中毒（之前的初始化函数 panic）时 panic
+    #[inline]
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        self.initialize(f);
+        self.wait()
+    }
+
+    /// 初始化是否因初始化函数 panic 而失败
+    pub fn is_poisoned(&self) -> bool {
+        self.state.load(Ordering::Acquire) == POISONED
+    }
+
+    /// 取出值，单元恢复为未初始化状态
+    pub fn take(&mut self) -> Option<T> {
+        if *self.state.get_mut() == COMPLETE {
+            *self.state.get_mut() = INCOMPLETE;
+            // Safety: 值已初始化，且状态已改为未初始化，不会被再次读取
+            Some(unsafe { self.value.get_mut().assume_init_read() })
+        } else {
+            None
+        }
+    }
+
+    /// 消耗单元并返回其中的值
+    pub fn into_inner(mut self) -> Option<T> {
+        self.take()
+    }
+
+    /// 抢到初始化权时调用 `f` 写入值；否则不做任何事
+    #[cold]
+    fn initialize<F: FnOnce() -> T>(&self, f: F) {
+        if self
+            .state
+            .compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire)
+            .is_err()
+        {
+            return;
+        }
+        let poison = PoisonOnPanic(&self.state);
+        let value = f();
+        core::mem::forget(poison);
+        // Safety: 处于 RUNNING 状态的只有本次调用，此时没有读者
+        unsafe { (*self.value.get()).write(value) };
+        self.state.store(COMPLETE, Ordering::Release);
+    }
+
+    /// 等待其它 CPU 上进行中的初始化完成
+    #[cold]
+    fn wait(&self) -> &T {
+        loop {
+            match self.state.load(Ordering::Acquire) {
+                // Safety: COMPLETE 之后值不再被修改
+                COMPLETE => return unsafe { (*self.value.get()).assume_init_ref() },
+                POISONED => panic!("sync: OnceLock poisoned by a panicking initializer"),
+                _ => core::hint::spin_loop(),
+            }
+        }
+    }
+}
+
+impl<T> Default for OnceLock<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<T> for OnceLock<T> {
+    fn from(value: T) -> Self {
+        Self {
+            state: AtomicU8::new(COMPLETE),
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_tuple("OnceLock");
+        match self.get() {
+            Some(value) => d.field(value),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
+
+impl<T> Drop for OnceLock<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == COMPLETE {
+            // Safety: 值已初始化且之后不再访问
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+/// 首次访问时初始化的值
+pub struct LazyLock<T, F = fn() -> T> {
+    once: OnceLock<T>,
+    init: UnsafeCell<Option<F>>,
+}
+
+// Safety: `init` 只由抢到初始化权的一方取出
+unsafe impl<T: Send + Sync, F: Send> Sync for LazyLock<T, F> {}
+
+impl<T, F: FnOnce() -> T> LazyLock<T, F> {
+    /// 创建以 `f` 作为初始化函数的惰性值
+    pub const fn new(f: F) -> Self {
+        Self {
+            once: OnceLock::new(),
+            init: UnsafeCell::new(Some(f)),
+        }
+    }
+
+    /// 强制初始化并返回值的引用
+    ///
+    /// # Panics
+    /// 初始化函数 panic 后再次访问时 panic
+    #[inline]
+    pub fn force(this: &Self) -> &T {
+        this.once.get_or_init(|| {
+            // Safety: 只有抢到初始化权的一方会执行此闭包
+            let init = unsafe { (*this.init.get()).take() };
+            match init {
+                Some(f) => f(),
+                None => panic!("sync: LazyLock poisoned by a panicking initializer"),
+            }
+        })
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for LazyLock<T, F> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for LazyLock<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LazyLock").field(&self.once).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_once_lock_set_and_get() {
+        let cell = OnceLock::new();
+        assert!(cell.get().is_none());
+        assert_eq!(cell.set(1), Ok(()));
+        assert_eq!(cell.set(2), Err(2));
+        assert_eq!(cell.get(), Some(&1));
+        assert_eq!(*cell.get_or_init(|| 3), 1);
+        assert_eq!(cell.into_inner(), Some(1));
+    }
+
+    #[test]
+    fn test_once_lock_concurrent_init_runs_once() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let cell = Arc::new(OnceLock::new());
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    *cell.get_or_init(|| {
+                        CALLS.fetch_add(1, Ordering::Relaxed);
+                        thread::yield_now();
+                        i
+                    })
+                })
+            })
+            .collect();
+        let values: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+        assert!(values.iter().all(|&v| v == values[0]));
+    }
+
+    #[test]
+    fn test_once_lock_poisoned_after_panic() {
+        let cell: OnceLock<usize> = OnceLock::new();
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            cell.get_or_init(|| panic!("init failed"));
+        }));
+        assert!(result.is_err());
+        assert!(cell.is_poisoned());
+        // 中毒后不会再次初始化，也不会自旋
+        assert_eq!(cell.set(1), Err(1));
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            cell.get_or_init(|| 2);
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_lazy_lock_deref() {
+        static VALUES: LazyLock<Vec<usize>> = LazyLock::new(|| (0..4).collect());
+        assert_eq!(VALUES.len(), 4);
+        assert_eq!(VALUES[3], 3);
+    }
+}
@@ -0,0 +1,243 @@
+//! Per-CPU 引用计数
+//!
+//! [`PercpuRef`] 面向被所有 CPU 频繁获取和释放的长寿命对象（如根目录项、网络接口）：
+//! 正常运行时每个 CPU 只修改自己缓存行上的计数，避免 `Arc` 式的单一计数器在多核间来回迁移。
+//! 代价是此时无法得知精确的总数，也就无法发现计数归零。
+//!
+//! 对象开始拆除时调用 [`kill`](PercpuRef::kill)：
+//! 1. 立即标记为正在拆除，之后 [`tryget_live`](PercpuRef::tryget_live) 失败；
+//! 2. 等待一个 RCU 宽限期，确认所有 CPU 都已看到拆除标记，不再修改 Per-CPU 计数；
+//! 3. 把各 CPU 的计数汇总到共享计数，切换为精确计数，并释放初始引用。
+//!
+//! 之后最后一次 [`put`](PercpuRef::put) 返回 `true`，由调用者释放对象。
+//!
+//! # 示例
+//! ```ignore
+//! let refs = PercpuRef::new(); // 持有初始引用
+//! refs.get();
+//! refs.put();
+//!
+//! // 拆除：kill 或之后的某次 put 返回 true 时引用已全部释放
+//! if refs.kill() {
+//!     release(obj);
+//! }
+//! ```
+
+use core::sync::atomic::{AtomicBool, AtomicIsize, Ordering, fence};
+
+use crate::arch_ops;
+use crate::preempt::MAX_CPUS;
+use crate::rcu::{rcu_read_lock, rcu_read_unlock, synchronize_rcu};
+
+/// 切换为精确计数前共享计数上的偏置，保证在途的释放不会让它提前归零
+const PERCPU_COUNT_BIAS: isize = 1 << (isize::BITS - 2);
+
+/// 缓存行对齐的 Per-CPU 计数
+#[repr(align(64))]
+struct PercpuCount(AtomicIsize);
+
+/// Per-CPU 引用计数
+pub struct PercpuRef {
+    /// 各 CPU 上获取与释放的差值，单个 CPU 上可以为负
+    percpu: [PercpuCount; MAX_CPUS],
+    /// 共享计数：拆除前带有 [`PERCPU_COUNT_BIAS`]，拆除后为精确的引用数
+    count: AtomicIsize,
+    /// 是否已开始拆除（已切换为共享计数）
+    dying: AtomicBool,
+}
+
+impl PercpuRef {
+    /// 创建持有一个初始引用的计数，初始引用由 [`kill`](Self::kill) 释放
+    pub const fn new() -> Self {
+        PercpuRef {
+            percpu: [const { PercpuCount(AtomicIsize::new(0)) }; MAX_CPUS],
+            count: AtomicIsize::new(PERCPU_COUNT_BIAS + 1),
+            dying: AtomicBool::new(false),
+        }
+    }
+
+    /// 获取一个引用，调用者必须已经持有引用
+    #[inline]
+    pub fn get(&self) {
+        rcu_read_lock();
+        if self.dying.load(Ordering::Relaxed) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.this_cpu().fetch_add(1, Ordering::Relaxed);
+        }
+        rcu_read_unlock();
+    }
+
+    /// 计数未归零时获取一个引用，拆除中的对象也可以获取
+    #[inline]
+    pub fn tryget(&self) -> bool {
+        rcu_read_lock();
+        let ok = if self.dying.load(Ordering::Relaxed) {
+            self.count
+                .fetch_update(Ordering::Acquire, Ordering::Relaxed, |count| {
+                    (count > 0).then_some(count + 1)
+                })
+                .is_ok()
+        } else {
+            self.this_cpu().fetch_add(1, Ordering::Relaxed);
+            true
+        };
+        rcu_read_unlock();
+        ok
+    }
+
+    /// 对象未开始拆除时获取一个引用
+    #[inline]
+    pub fn tryget_live(&self) -> bool {
+        rcu_read_lock();
+        let ok = !self.dying.load(Ordering::Relaxed);
+        if ok {
+            self.this_cpu().fetch_add(1, Ordering::Relaxed);
+        }
+        rcu_read_unlock();
+        ok
+    }
+
+    /// 释放一个引用
+    ///
+    /// # 返回值
+    /// 释放的是最后一个引用时返回 `true`，调用者负责释放对象；拆除前始终返回 `false`
+    #[inline]
+    pub fn put(&self) -> bool {
+        rcu_read_lock();
+        let last = if self.dying.load(Ordering::Relaxed) {
+            self.put_shared()
+        } else {
+            self.this_cpu().fetch_sub(1, Ordering::Release);
+            false
+        };
+        rcu_read_unlock();
+        last
+    }
+
+    /// 开始拆除：之后 [`tryget_live`](Self::tryget_live) 失败，等待一个宽限期后切换为精确计数并释放初始引用
+    ///
+    /// 会让出 CPU，不能在读临界区或原子上下文中调用。
+    ///
+    /// # 返回值
+    /// 初始引用是最后一个引用时返回 `true`，调用者负责释放对象
+    ///
+    /// # Panics
+    /// 重复调用时 panic
+    pub fn kill(&self) -> bool {
+        assert!(
+            !self.dying.swap(true, Ordering::Relaxed),
+            "sync: PercpuRef killed twice"
+        );
+        // 宽限期结束后，看到旧模式的 get/put 都已完成，Per-CPU 计数不再变化
+        synchronize_rcu();
+        let sum: isize = self
+            .percpu
+            .iter()
+            .map(|count| count.0.swap(0, Ordering::Acquire))
+            .sum();
+        self.count
+            .fetch_add(sum.wrapping_sub(PERCPU_COUNT_BIAS), Ordering::AcqRel);
+        self.put_shared()
+    }
+
+    /// 是否已开始拆除
+    #[inline]
+    pub fn is_dying(&self) -> bool {
+        self.dying.load(Ordering::Relaxed)
+    }
+
+    /// 引用是否已全部释放，拆除前始终返回 `false`
+    pub fn is_zero(&self) -> bool {
+        self.count.load(Ordering::Acquire) == 0
+    }
+
+    fn put_shared(&self) -> bool {
+        if self.count.fetch_sub(1, Ordering::Release) == 1 {
+            // 与其它 CPU 释放引用前的访问同步
+            fence(Ordering::Acquire);
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    fn this_cpu(&self) -> &AtomicIsize {
+        &self.percpu[arch_ops().cpu_id()].0
+    }
+}
+
+impl Default for PercpuRef {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for PercpuRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PercpuRef")
+            .field("dying", &self.is_dying())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_percpu_ref_kill_releases_initial_ref() {
+        let refs = PercpuRef::new();
+        refs.get();
+        assert!(refs.tryget_live());
+        assert!(!refs.put());
+        assert!(!refs.kill());
+        assert!(refs.is_dying());
+        assert!(!refs.tryget_live());
+        assert!(refs.tryget());
+        assert!(!refs.put());
+        assert!(refs.put());
+        assert!(refs.is_zero());
+        assert!(!refs.tryget());
+    }
+
+    #[test]
+    fn test_percpu_ref_concurrent_puts_across_kill() {
+        const THREADS: usize = 4;
+        const ITERS: usize = 1000;
+        let refs = Arc::new(PercpuRef::new());
+        // 每个线程额外持有一个引用，等拆除开始后释放
+        for _ in 0..THREADS {
+            refs.get();
+        }
+        let workers: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let refs = refs.clone();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..ITERS {
+                        if refs.tryget_live() {
+                            last += refs.put() as usize;
+                        }
+                    }
+                    while !refs.is_dying() {
+                        thread::yield_now();
+                    }
+                    last + refs.put() as usize
+                })
+            })
+            .collect();
+        let mut last = refs.kill() as usize;
+        last += workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .sum::<usize>();
+        // 只有一次释放看到计数归零
+        assert_eq!(last, 1);
+        assert!(refs.is_zero());
+    }
+}
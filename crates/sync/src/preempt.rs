@@ -7,7 +7,7 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 use crate::arch_ops;
 
 /// 最大支持的 CPU 数量（编译时常量）
-const MAX_CPUS: usize = 16;
+pub(crate) const MAX_CPUS: usize = 16;
 
 /// 缓存行对齐的原子计数器
 ///
@@ -1,16 +1,97 @@
 //! 自旋锁实现
 //!
-//! 基于原子操作实现自旋锁机制，结合 IntrGuard 实现中断保护。
+//! 排队自旋锁（qspinlock）：锁状态是一个 32 位字，最低位为锁标志，其余位记录等待队列队尾。
+//! 无竞争时一次 CAS 即可获得锁；有竞争时等待者取一个 Per-CPU 队列节点（MCS 节点）排到队尾，
+//! 只在自己的节点上自旋，直到成为队首后再去竞争锁标志。
+//! 这样每个等待者只读写本地缓存行，避免所有 CPU 在同一个缓存行上争抢，并按 FIFO 顺序获得锁。
+//!
+//! 队列节点只在等待期间使用，获得锁后立即归还，因此持有锁 A 时再获取锁 B 不会占用额外节点。
+//! 获取锁前已经关闭本地中断，正常情况下每个 CPU 同时只有一个节点在使用；
+//! 本 CPU 的节点耗尽时（例如 CPU 编号不可靠的宿主测试环境）退化为直接自旋竞争锁标志。
+//! 结合 IntrGuard 实现中断保护。
 
+use crate::arch_ops;
 use crate::intr_guard::IntrGuard;
+use crate::lockdep::LockClass;
+use crate::preempt::MAX_CPUS;
 use core::{
-    hint,
-    sync::atomic::{AtomicBool, Ordering},
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicUsize, Ordering},
+};
+
+/// 锁标志位
+const LOCKED: u32 = 1;
+/// 队尾编号的起始位，队尾编号为 `节点下标 + 1`，0 表示队列为空
+const TAIL_SHIFT: u32 = 1;
+/// 每个 CPU 的队列节点数
+const NODES_PER_CPU: usize = 4;
+
+/// 自旋等待中的一次让步
+#[inline]
+#[cfg(not(test))]
+fn cpu_relax() {
+    arch_ops().cpu_relax();
+}
+
+/// 自旋等待中的一次让步（测试模式）
+///
+/// 宿主测试的线程数可能多于 CPU 数，排队交接时让出 CPU，避免等待者的时间片被自旋耗尽；
+/// 在受控交错模型中改为模型的调度点。
+#[inline]
+#[cfg(test)]
+fn cpu_relax() {
+    if test_support::interleave::in_model() {
+        test_support::interleave::spin_loop();
+    } else {
+        std::thread::yield_now();
+    }
+}
+
+/// MCS 队列节点，独占一个缓存行
+#[repr(align(64))]
+struct QNode {
+    /// 节点是否正被某个等待者使用
+    busy: AtomicBool,
+    /// 前驱把锁交给本节点时置位，表示本节点已成为队首
+    locked: AtomicBool,
+    /// 后继节点
+    next: AtomicPtr<QNode>,
+}
+
+impl QNode {
+    const fn new() -> Self {
+        QNode {
+            busy: AtomicBool::new(false),
+            locked: AtomicBool::new(false),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+}
+
+/// Per-CPU 队列节点，CPU `c` 使用下标 `[c * NODES_PER_CPU, (c + 1) * NODES_PER_CPU)`
+static QNODES: [QNode; MAX_CPUS * NODES_PER_CPU] = {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: QNode = QNode::new();
+    [INIT; MAX_CPUS * NODES_PER_CPU]
 };
 
+/// 在当前 CPU 上取一个空闲的队列节点，返回节点下标
+fn claim_qnode() -> Option<usize> {
+    let cpu = arch_ops().cpu_id();
+    if cpu >= MAX_CPUS {
+        return None;
+    }
+    (cpu * NODES_PER_CPU..(cpu + 1) * NODES_PER_CPU).find(|&idx| {
+        QNODES[idx]
+            .busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    })
+}
+
 /// 自旋锁结构体，提供互斥访问临界区的能力。
 ///
-/// 基于原子操作实现自旋锁机制，结合 IntrGuard 实现中断保护。
+/// 基于排队自旋锁实现，等待者按 FIFO 顺序获得锁，结合 IntrGuard 实现中断保护。
 /// 不可重入 (即不能嵌套调用 RawSpinLock::lock())。
 ///
 /// # 示例
@@ -23,14 +104,34 @@ use core::{
 /// ```
 #[derive(Debug)]
 pub struct RawSpinLock {
-    lock: AtomicBool,
+    /// 锁状态：最低位为锁标志，其余位为队尾编号
+    state: AtomicU32,
+    /// 持有者的 `CPU 编号 + 1`，未被持有时为 0
+    owner: AtomicUsize,
+    /// 锁依赖检查与竞争统计使用的锁类
+    #[cfg(any(feature = "lockdep", feature = "lock-stat"))]
+    class: Option<&'static LockClass>,
 }
 
 impl RawSpinLock {
     /// 创建一个新的 RawSpinLock 实例。
     pub const fn new() -> Self {
         RawSpinLock {
-            lock: AtomicBool::new(false),
+            state: AtomicU32::new(0),
+            owner: AtomicUsize::new(0),
+            #[cfg(any(feature = "lockdep", feature = "lock-stat"))]
+            class: None,
+        }
+    }
+
+    /// 创建一个属于 `class` 锁类的 RawSpinLock 实例，未开启 `lockdep`/`lock-stat` 特性时与 [`Self::new`] 相同。
+    #[allow(unused_variables)]
+    pub const fn with_class(class: &'static LockClass) -> Self {
+        RawSpinLock {
+            state: AtomicU32::new(0),
+            owner: AtomicUsize::new(0),
+            #[cfg(any(feature = "lockdep", feature = "lock-stat"))]
+            class: Some(class),
         }
     }
 
@@ -39,14 +140,27 @@ impl RawSpinLock {
     /// 内部原子地获取锁，并在当前 CPU 禁用本地中断。
     pub fn lock(&self) -> RawSpinLockGuard<'_> {
         let guard = IntrGuard::new();
+        #[cfg(feature = "lockdep")]
+        crate::lockdep::lock_acquire(self.addr(), self.class, false);
 
-        while self
-            .lock
-            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
-            .is_err()
+        if self
+            .state
+            .compare_exchange(0, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
         {
-            hint::spin_loop();
+            #[cfg(feature = "lock-stat")]
+            crate::lock_stat::record(self.class, None);
+        } else {
+            #[cfg(feature = "lock-stat")]
+            let start = crate::lock_stat::cycles();
+            self.lock_slow();
+            #[cfg(feature = "lock-stat")]
+            crate::lock_stat::record(
+                self.class,
+                Some(crate::lock_stat::cycles().saturating_sub(start)),
+            );
         }
+        self.set_owner();
 
         RawSpinLockGuard {
             lock: self,
@@ -54,18 +168,93 @@ impl RawSpinLock {
         }
     }
 
+    /// 有竞争时排队等待锁
+    fn lock_slow(&self) {
+        let Some(idx) = claim_qnode() else {
+            // 没有可用节点：不排队，等锁和队列都空闲后直接抢占锁标志
+            while self
+                .state
+                .compare_exchange_weak(0, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                cpu_relax();
+            }
+            return;
+        };
+        let node = &QNODES[idx];
+        node.locked.store(false, Ordering::Relaxed);
+        node.next.store(ptr::null_mut(), Ordering::Relaxed);
+
+        // 把自己发布为队尾，保留锁标志
+        let tail = ((idx + 1) as u32) << TAIL_SHIFT;
+        let mut old = self.state.load(Ordering::Relaxed);
+        while let Err(cur) = self.state.compare_exchange_weak(
+            old,
+            (old & LOCKED) | tail,
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        ) {
+            old = cur;
+        }
+
+        // 有前驱时链接到前驱之后，在自己的节点上等待前驱交接
+        let prev = old >> TAIL_SHIFT;
+        if prev != 0 {
+            let prev = &QNODES[prev as usize - 1];
+            prev.next
+                .store(node as *const QNode as *mut QNode, Ordering::Release);
+            while !node.locked.load(Ordering::Acquire) {
+                cpu_relax();
+            }
+        }
+
+        // 已是队首：等待持有者释放锁标志
+        let mut val = self.state.load(Ordering::Acquire);
+        while val & LOCKED != 0 {
+            cpu_relax();
+            val = self.state.load(Ordering::Acquire);
+        }
+
+        // 自己仍是队尾时连同队列一起清空；否则只设置锁标志，并把队首交给后继。
+        // 队列非空时快速路径不会成功，只有队首会设置锁标志。
+        if val == tail
+            && self
+                .state
+                .compare_exchange(tail, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        {
+            node.busy.store(false, Ordering::Release);
+            return;
+        }
+        self.state.fetch_or(LOCKED, Ordering::Acquire);
+
+        // 后继可能已更新队尾但尚未完成链接
+        let mut next = node.next.load(Ordering::Acquire);
+        while next.is_null() {
+            cpu_relax();
+            next = node.next.load(Ordering::Acquire);
+        }
+        // Safety: 队列节点是静态的，后继在被交接前不会归还节点
+        unsafe { &*next }.locked.store(true, Ordering::Release);
+        node.busy.store(false, Ordering::Release);
+    }
+
     /// 尝试获取自旋锁，如果成功则返回 RAII 保护器，否则返回 None。
     ///
     /// 内部原子地尝试获取锁，并在当前 CPU 禁用本地中断。
+    /// 锁空闲但有等待者排队时同样失败，不会插队。
     /// 如果获取失败，会立即恢复中断状态（通过 Drop IntrGuard）。
     pub fn try_lock(&self) -> Option<RawSpinLockGuard<'_>> {
         let guard = IntrGuard::new();
 
         if self
-            .lock
-            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .state
+            .compare_exchange(0, LOCKED, Ordering::Acquire, Ordering::Relaxed)
             .is_ok()
         {
+            #[cfg(feature = "lockdep")]
+            crate::lockdep::lock_acquire(self.addr(), self.class, true);
+            self.set_owner();
             Some(RawSpinLockGuard {
                 lock: self,
                 intr_guard: guard,
@@ -77,7 +266,29 @@ impl RawSpinLock {
 
     /// 仅释放锁标志。
     fn unlock(&self) {
-        self.lock.store(false, Ordering::Release);
+        #[cfg(feature = "lockdep")]
+        crate::lockdep::lock_release(self.addr());
+        self.owner.store(0, Ordering::Relaxed);
+        self.state.fetch_and(!LOCKED, Ordering::Release);
+    }
+
+    /// 记录当前 CPU 为持有者
+    fn set_owner(&self) {
+        self.owner.store(arch_ops().cpu_id() + 1, Ordering::Relaxed);
+    }
+
+    /// 锁是否由当前 CPU 持有
+    ///
+    /// 持有自旋锁期间中断关闭、不会被调度走，因此当前 CPU 持有即当前任务持有。
+    /// 持有者字段只由持有者自己写入，其他 CPU 读到的值不会等于本 CPU 的编号。
+    pub fn is_held_by_current_cpu(&self) -> bool {
+        self.owner.load(Ordering::Relaxed) == arch_ops().cpu_id() + 1
+    }
+
+    /// 锁依赖检查中标识锁实例的地址
+    #[cfg(feature = "lockdep")]
+    fn addr(&self) -> usize {
+        self as *const Self as usize
     }
 
     /// 检查锁是否被占用 (仅用于调试/测试)
@@ -86,7 +297,7 @@ impl RawSpinLock {
     /// 锁是否被占用
     #[cfg(test)]
     pub fn is_locked(&self) -> bool {
-        self.lock.load(Ordering::Relaxed)
+        self.state.load(Ordering::Relaxed) & LOCKED != 0
     }
 }
 
@@ -107,3 +318,97 @@ impl Drop for RawSpinLockGuard<'_> {
         self.lock.unlock();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_raw_spinlock_try_lock() {
+        let lock = RawSpinLock::new();
+        let guard = lock.lock();
+        assert!(lock.is_locked());
+        assert!(lock.try_lock().is_none());
+        drop(guard);
+        assert!(!lock.is_locked());
+        assert!(lock.try_lock().is_some());
+        assert_eq!(lock.state.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_raw_spinlock_owner_tracking() {
+        let lock = RawSpinLock::new();
+        assert!(!lock.is_held_by_current_cpu());
+        let guard = lock.lock();
+        assert!(lock.is_held_by_current_cpu());
+        drop(guard);
+        assert!(!lock.is_held_by_current_cpu());
+        let guard = lock.try_lock().unwrap();
+        assert!(lock.is_held_by_current_cpu());
+        drop(guard);
+        assert!(!lock.is_held_by_current_cpu());
+    }
+
+    #[test]
+    fn test_raw_spinlock_owner_is_per_cpu() {
+        // 其他 CPU 持有锁时本 CPU 不是持有者，正常加锁等到对方释放即可获得
+        let lock = Arc::new(RawSpinLock::new());
+        let (held_tx, held_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let holder = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                crate::test_mock::set_current_cpu(1);
+                let guard = lock.lock();
+                assert!(lock.is_held_by_current_cpu());
+                held_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                drop(guard);
+            })
+        };
+        held_rx.recv().unwrap();
+        assert!(lock.is_locked());
+        assert!(!lock.is_held_by_current_cpu());
+        release_tx.send(()).unwrap();
+        let guard = lock.lock();
+        assert!(lock.is_held_by_current_cpu());
+        drop(guard);
+        holder.join().unwrap();
+    }
+
+    #[test]
+    fn test_raw_spinlock_contended_mutual_exclusion() {
+        // 宿主测试中所有线程的 CPU 编号相同，线程数超过节点数时部分等待者走退化路径
+        struct Shared {
+            lock: RawSpinLock,
+            counter: core::cell::UnsafeCell<usize>,
+        }
+        unsafe impl Sync for Shared {}
+
+        const THREADS: usize = NODES_PER_CPU * 2;
+        const ITERS: usize = 10_000;
+        let shared = Arc::new(Shared {
+            lock: RawSpinLock::new(),
+            counter: core::cell::UnsafeCell::new(0),
+        });
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    for _ in 0..ITERS {
+                        let _guard = shared.lock.lock();
+                        unsafe { *shared.counter.get() += 1 };
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(unsafe { *shared.counter.get() }, THREADS * ITERS);
+        assert_eq!(shared.lock.state.load(Ordering::Relaxed), 0);
+    }
+}
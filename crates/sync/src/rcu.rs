@@ -0,0 +1,479 @@
+//! RCU（Read-Copy-Update）
+//!
+//! 面向读多写少的全局数据：读者不加锁，只在读临界区内禁止抢占；写者复制一份数据修改后
+//! 原子地发布新版本，旧版本等到所有读者都离开后（一个宽限期之后）再释放。
+//!
+//! # 宽限期
+//!
+//! 读临界区不可抢占，因此某个 CPU 上读嵌套计数为 0 的任一时刻都是该 CPU 的静止状态：
+//! 此前开始的读临界区都已结束。静止状态有两个来源：
+//!
+//! - 时钟节拍与上下文切换时调用 [`rcu_note_quiescent_state`]，记录本 CPU 已经历的宽限期；
+//! - 等待宽限期的一方直接检查各 CPU 的读嵌套计数，空闲（节拍已停止）的 CPU 由此得到确认。
+//!
+//! 所有 CPU 都经历过静止状态后宽限期结束。[`synchronize_rcu`] 让出 CPU 直到宽限期结束；
+//! [`call_rcu`] 登记回调后立即返回，时钟节拍发现有回调就绪时（[`rcu_tick`] 返回 `true`）
+//! 由内核安排在任务上下文中调用 [`rcu_process_callbacks`] 执行。
+//!
+//! # 示例
+//! ```ignore
+//! static DRIVERS: Rcu<Vec<Arc<dyn Driver>>> = ...;
+//!
+//! // 读者：不加锁，临界区内不能睡眠
+//! if let Some(drv) = DRIVERS.read().first() { drv.poke(); }
+//!
+//! // 写者：修改副本，守卫离开作用域时发布，旧版本在宽限期后释放
+//! DRIVERS.write().push(new_driver);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering, fence};
+
+use crate::preempt::{MAX_CPUS, preempt_disable, preempt_enable};
+use crate::spin_lock::{SpinLock, SpinLockGuard};
+use crate::{arch_ops, interleave_point, sched_ops};
+
+/// 每个 CPU 的读临界区嵌套深度
+static RCU_NESTING: [AtomicUsize; MAX_CPUS] = [const { AtomicUsize::new(0) }; MAX_CPUS];
+
+/// 最近开始的宽限期序号
+static GP_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// 每个 CPU 已经历静止状态的最大宽限期序号
+static QS_SEQ: [AtomicUsize; MAX_CPUS] = [const { AtomicUsize::new(0) }; MAX_CPUS];
+
+/// RCU 回调，按宽限期序号递增排列
+type RcuCallback = Box<dyn FnOnce() + Send>;
+static CALLBACKS: SpinLock<VecDeque<(usize, RcuCallback)>> = SpinLock::new(VecDeque::new());
+
+/// 是否已请求执行就绪的回调
+static CALLBACKS_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// 进入读临界区
+///
+/// 可以嵌套，必须与 [`rcu_read_unlock`] 配对使用。临界区内禁止抢占，不能睡眠。
+#[inline]
+pub fn rcu_read_lock() {
+    preempt_disable();
+    interleave_point();
+    RCU_NESTING[arch_ops().cpu_id()].fetch_add(1, Ordering::Relaxed);
+    // 与宽限期开始时的屏障配对：要么写者看到本 CPU 在临界区内，要么本临界区读到新版本
+    fence(Ordering::SeqCst);
+}
+
+/// 离开读临界区
+#[inline]
+pub fn rcu_read_unlock() {
+    // Release：临界区内的读取先于写者看到计数归零（进而释放旧版本）
+    RCU_NESTING[arch_ops().cpu_id()].fetch_sub(1, Ordering::Release);
+    preempt_enable();
+}
+
+/// 当前 CPU 是否处于读临界区
+#[inline]
+pub fn rcu_read_lock_held() -> bool {
+    RCU_NESTING[arch_ops().cpu_id()].load(Ordering::Relaxed) > 0
+}
+
+/// RCU 读临界区 RAII 守卫
+///
+/// 创建时进入读临界区，销毁时离开。
+pub struct RcuReadGuard {
+    // 读临界区绑定在当前 CPU 上，守卫不能跨线程传递
+    _not_send: PhantomData<*const ()>,
+}
+
+impl RcuReadGuard {
+    /// 创建守卫并进入读临界区
+    #[inline]
+    pub fn new() -> Self {
+        rcu_read_lock();
+        RcuReadGuard {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Default for RcuReadGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for RcuReadGuard {
+    #[inline]
+    fn drop(&mut self) {
+        rcu_read_unlock();
+    }
+}
+
+/// 报告本 CPU 的静止状态（不在读临界区时）
+///
+/// 由时钟节拍和上下文切换调用。
+pub fn rcu_note_quiescent_state() {
+    let cpu = arch_ops().cpu_id();
+    // 先读序号：读到的宽限期开始时本 CPU 已经不在读临界区内
+    let seq = GP_SEQ.load(Ordering::SeqCst);
+    if RCU_NESTING[cpu].load(Ordering::Acquire) == 0 {
+        QS_SEQ[cpu].fetch_max(seq, Ordering::Release);
+    }
+}
+
+/// 开始一个新的宽限期，返回其序号
+fn start_grace_period() -> usize {
+    let seq = GP_SEQ.fetch_add(1, Ordering::SeqCst) + 1;
+    fence(Ordering::SeqCst);
+    seq
+}
+
+/// 序号为 `seq` 的宽限期是否已经结束
+fn grace_period_completed(seq: usize) -> bool {
+    let cpus = arch_ops().max_cpu_count().min(MAX_CPUS);
+    for cpu in 0..cpus {
+        if QS_SEQ[cpu].load(Ordering::Acquire) >= seq {
+            continue;
+        }
+        interleave_point();
+        if RCU_NESTING[cpu].load(Ordering::Acquire) == 0 {
+            QS_SEQ[cpu].fetch_max(seq, Ordering::Release);
+            continue;
+        }
+        return false;
+    }
+    true
+}
+
+/// 等待一个完整的宽限期：返回时调用前开始的读临界区都已结束
+///
+/// 会让出 CPU，不能在读临界区或原子上下文中调用。
+pub fn synchronize_rcu() {
+    let seq = start_grace_period();
+    while !grace_period_completed(seq) {
+        sched_ops().yield_now();
+    }
+}
+
+/// 在一个宽限期之后执行 `f`，不等待直接返回
+///
+/// `f` 在 [`rcu_process_callbacks`] 中于任务上下文执行，通常用于释放旧版本的数据。
+pub fn call_rcu(f: impl FnOnce() + Send + 'static) {
+    let callback: RcuCallback = Box::new(f);
+    let mut callbacks = CALLBACKS.lock();
+    // 持锁分配序号，保证队列按序号递增
+    let seq = start_grace_period();
+    callbacks.push_back((seq, callback));
+}
+
+/// 时钟节拍钩子：报告静止状态，并检查是否有回调就绪
+///
+/// 返回 `true` 时调用者应安排在任务上下文中调用一次 [`rcu_process_callbacks`]；
+/// 在此之前不会重复返回 `true`。
+pub fn rcu_tick() -> bool {
+    rcu_note_quiescent_state();
+    let ready = CALLBACKS
+        .lock()
+        .front()
+        .is_some_and(|(seq, _)| grace_period_completed(*seq));
+    ready && !CALLBACKS_REQUESTED.swap(true, Ordering::AcqRel)
+}
+
+/// 执行宽限期已结束的回调
+pub fn rcu_process_callbacks() {
+    CALLBACKS_REQUESTED.store(false, Ordering::Release);
+    loop {
+        let callback = {
+            let mut callbacks = CALLBACKS.lock();
+            match callbacks.front() {
+                Some((seq, _)) if grace_period_completed(*seq) => callbacks.pop_front(),
+                _ => None,
+            }
+        };
+        // 不持锁执行，回调中可以再次调用 call_rcu
+        match callback {
+            Some((_, f)) => f(),
+            None => break,
+        }
+    }
+}
+
+/// 已被替换、等待宽限期结束后释放的旧版本
+struct Retired<T>(*mut T);
+
+// Safety: 旧版本只在回调中释放一次，此时已没有读者
+unsafe impl<T: Send> Send for Retired<T> {}
+
+impl<T> Drop for Retired<T> {
+    fn drop(&mut self) {
+        // Safety: 指针来自 Box::into_raw，且已从 Rcu 中摘下
+        drop(unsafe { Box::from_raw(self.0) });
+    }
+}
+
+/// RCU 保护的数据
+///
+/// 读者通过 [`read`](Self::read) 无锁访问当前版本；写者通过 [`write`](Self::write)
+/// 修改当前版本的副本，写者之间由自旋锁互斥。
+pub struct Rcu<T> {
+    ptr: AtomicPtr<T>,
+    writer: SpinLock<()>,
+}
+
+// Safety: 读者在多个 CPU 上共享 &T，旧版本可能在其它 CPU 上释放
+unsafe impl<T: Send + Sync> Send for Rcu<T> {}
+unsafe impl<T: Send + Sync> Sync for Rcu<T> {}
+
+impl<T> Rcu<T> {
+    /// 以 `data` 作为初始版本创建
+    pub fn new(data: T) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Box::into_raw(Box::new(data))),
+            writer: SpinLock::new(()),
+        }
+    }
+
+    /// 进入读临界区并返回当前版本
+    ///
+    /// 返回的引用存活期间不能睡眠；需要长时间使用其中的数据时先克隆出来。
+    pub fn read(&self) -> RcuRef<'_, T> {
+        let guard = RcuReadGuard::new();
+        interleave_point();
+        // Safety: 指针总是指向有效的版本，旧版本在读临界区结束前不会被释放
+        let data = unsafe { &*self.ptr.load(Ordering::Acquire) };
+        RcuRef {
+            _guard: guard,
+            data,
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> Rcu<T> {
+    /// 复制当前版本以供修改，守卫销毁时发布修改后的版本
+    ///
+    /// 旧版本通过 [`call_rcu`] 在宽限期后释放。
+    pub fn write(&self) -> RcuWriteGuard<'_, T> {
+        let writer = self.writer.lock();
+        // Safety: 持有写者锁，当前版本不会被替换或释放
+        let copy = unsafe { &*self.ptr.load(Ordering::Acquire) }.clone();
+        RcuWriteGuard {
+            rcu: self,
+            _writer: writer,
+            copy: Some(Box::new(copy)),
+        }
+    }
+}
+
+impl<T: Default> Default for Rcu<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> Drop for Rcu<T> {
+    fn drop(&mut self) {
+        // Safety: 独占访问，不会再有读者
+        drop(unsafe { Box::from_raw(*self.ptr.get_mut()) });
+    }
+}
+
+/// [`Rcu::read`] 返回的引用，存活期间处于读临界区
+pub struct RcuRef<'a, T> {
+    _guard: RcuReadGuard,
+    data: &'a T,
+}
+
+impl<T> Deref for RcuRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.data
+    }
+}
+
+/// [`Rcu::write`] 返回的写守卫，持有写者锁与当前版本的副本
+pub struct RcuWriteGuard<'a, T: Send + 'static> {
+    rcu: &'a Rcu<T>,
+    _writer: SpinLockGuard<'a, ()>,
+    copy: Option<Box<T>>,
+}
+
+impl<T: Send + 'static> Deref for RcuWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.copy.as_ref().unwrap()
+    }
+}
+
+impl<T: Send + 'static> DerefMut for RcuWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.copy.as_mut().unwrap()
+    }
+}
+
+impl<T: Send + 'static> Drop for RcuWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        let new = Box::into_raw(self.copy.take().unwrap());
+        let old = Retired(self.rcu.ptr.swap(new, Ordering::AcqRel));
+        interleave_point();
+        call_rcu(move || drop(old));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use std::thread;
+    use std::time::Duration;
+    use test_support::interleave::{self, Model};
+
+    /// 处理回调直到 `done` 成立（并行的测试可能暂时处于读临界区）
+    fn process_until(done: impl Fn() -> bool) {
+        while !done() {
+            rcu_process_callbacks();
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn test_rcu_read_lock_nesting() {
+        let guard = RcuReadGuard::new();
+        rcu_read_lock();
+        assert!(rcu_read_lock_held());
+        rcu_read_unlock();
+        assert!(rcu_read_lock_held());
+        drop(guard);
+    }
+
+    #[test]
+    fn test_synchronize_rcu_waits_for_readers() {
+        let state = Arc::new(AtomicUsize::new(0));
+        let reader = {
+            let state = state.clone();
+            thread::spawn(move || {
+                let _guard = RcuReadGuard::new();
+                state.store(1, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                state.store(2, Ordering::SeqCst);
+            })
+        };
+        while state.load(Ordering::SeqCst) == 0 {
+            thread::yield_now();
+        }
+        synchronize_rcu();
+        assert_eq!(state.load(Ordering::SeqCst), 2);
+        reader.join().unwrap();
+    }
+
+    #[test]
+    fn test_call_rcu_runs_after_grace_period() {
+        let ran = Arc::new(AtomicBool::new(false));
+        let guard = RcuReadGuard::new();
+        {
+            let ran = ran.clone();
+            call_rcu(move || ran.store(true, Ordering::SeqCst));
+        }
+        // 仍在读临界区，回调不会执行
+        rcu_process_callbacks();
+        assert!(!ran.load(Ordering::SeqCst));
+        drop(guard);
+        process_until(|| ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_rcu_write_publishes_copy() {
+        let data = Rcu::new(vec![1]);
+        let marker = Arc::new(());
+        let tracked = Rcu::new(marker.clone());
+
+        let old = data.read();
+        data.write().push(2);
+        // 已有的读者仍然看到旧版本，新的读者看到新版本
+        assert_eq!(*old, [1]);
+        assert_eq!(*data.read(), [1, 2]);
+        drop(old);
+
+        // 旧版本在宽限期后释放
+        *tracked.write() = Arc::new(());
+        process_until(|| Arc::strong_count(&marker) == 1);
+    }
+
+    /// 带版本号的数据，释放时记录下来
+    #[derive(Clone)]
+    struct Version {
+        id: usize,
+        freed: Arc<[AtomicBool; 2]>,
+    }
+
+    impl Drop for Version {
+        fn drop(&mut self) {
+            self.freed[self.id].store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// 读者与发布新版本并等待回调释放旧版本的写者交错：读者持有的版本在读临界区结束前不会被释放
+    #[test]
+    fn test_interleaved_reader_never_sees_freed_version() {
+        let report = Model::exhaustive(2).check(|| {
+            let freed = Arc::new([const { AtomicBool::new(false) }; 2]);
+            let data = Arc::new(Rcu::new(Version {
+                id: 0,
+                freed: freed.clone(),
+            }));
+
+            let reader = {
+                let data = data.clone();
+                let freed = freed.clone();
+                interleave::spawn(move || {
+                    let version = data.read();
+                    let id = version.id;
+                    interleave::yield_now();
+                    assert!(!freed[id].load(Ordering::SeqCst));
+                    drop(version);
+                })
+            };
+
+            // 克隆出的副本不再代表旧版本，改成新版本号后发布
+            data.write().id = 1;
+            while !freed[0].load(Ordering::SeqCst) {
+                rcu_process_callbacks();
+                interleave::spin_loop();
+            }
+            reader.join();
+            assert!(!freed[1].load(Ordering::SeqCst));
+        });
+        assert!(report.complete);
+    }
+
+    /// 读者与 `synchronize_rcu` 交错：返回时不再有读者持有替换前的版本
+    #[test]
+    fn test_interleaved_synchronize_rcu() {
+        let report = Model::exhaustive(2).check(|| {
+            let current = Arc::new(AtomicUsize::new(0));
+            let old_readers = Arc::new(AtomicUsize::new(0));
+
+            let reader = {
+                let current = current.clone();
+                let old_readers = old_readers.clone();
+                interleave::spawn(move || {
+                    let _guard = RcuReadGuard::new();
+                    if current.load(Ordering::SeqCst) == 0 {
+                        old_readers.fetch_add(1, Ordering::SeqCst);
+                        interleave::yield_now();
+                        old_readers.fetch_sub(1, Ordering::SeqCst);
+                    }
+                })
+            };
+
+            current.store(1, Ordering::SeqCst);
+            synchronize_rcu();
+            assert_eq!(old_readers.load(Ordering::SeqCst), 0);
+            reader.join();
+        });
+        assert!(report.complete);
+    }
+}
@@ -1,32 +1,59 @@
 //! 读写锁实现
 //!
 //! 允许多个读者同时访问或单个写者独占访问。
-//! 使用单个 AtomicUsize 编码状态：[WRITER(1bit)][READERS(31bits)]。
+//! 使用单个 AtomicUsize 编码状态：[WRITER(1bit)][UPGRADABLE(1bit)][WRITER_WAITING(1bit)][READERS(29bits)]。
 //!
 //! # 不变量
-//! - WRITER_BIT=1 时，READERS 必须为 0（写者独占）
+//! - WRITER_BIT=1 时，READERS 必须为 0，UPGRADABLE_BIT 必须为 0（写者独占）
 //! - WRITER_BIT=0 时，READERS 可以 > 0（多读者共享）
-//! - 读者数量不超过 READER_MASK (2^31-1)
+//! - 至多一个可升级读者，它与普通读者共存，但排斥写者和其它可升级读者
+//! - 读者数量不超过 READER_MASK (2^29-1)
+//!
+//! # 公平性
+//! 默认读者优先：只要没有写者持锁，读者总能进入，连续的读者可能饿死写者。
+//! 用 [`RwLockFairness::WriterPreferred`] 创建的锁在写者等待时拒绝新读者，
+//! 已持有读锁的读者退出后写者即可进入；代价是同一执行流递归获取读锁可能死锁。
+//!
+//! # 锁升级
+//! [`RwLock::upgradeable_read`] 获取可升级读锁，之后可以在不释放锁的情况下
+//! [`upgrade`](RwLockUpgradableReadGuard::upgrade) 为写锁；普通读锁不能升级，尝试升级会死锁。
 //!
 //! # 已知限制
-//! - 读者溢出：超过 2^31-1 个读者时会 panic（实际不可能发生）
-//! - 写者饥饿：连续读者可能饿死写者（内核场景可接受）
-//! - 不支持锁升级/降级：尝试升级会死锁
+//! - 读者溢出：超过 2^29-1 个读者时会 panic（实际不可能发生）
 
 use crate::intr_guard::IntrGuard;
+use crate::lockdep::LockClass;
 use core::{
     cell::UnsafeCell,
     hint,
+    mem::ManuallyDrop,
     ops::{Deref, DerefMut},
+    ptr,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
 const WRITER_BIT: usize = 1 << 31;
-const READER_MASK: usize = WRITER_BIT - 1;
+const UPGRADABLE_BIT: usize = 1 << 30;
+const WRITER_WAITING_BIT: usize = 1 << 29;
+const READER_MASK: usize = WRITER_WAITING_BIT - 1;
+
+/// 读写锁的公平策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RwLockFairness {
+    /// 读者优先：写者只在没有任何读者时进入，读多写少且允许递归读的场景使用
+    #[default]
+    ReaderPreferred,
+    /// 写者优先：有写者等待时新读者不再进入，避免写者饥饿
+    WriterPreferred,
+}
 
 /// 读写锁，允许多个读者或单个写者
 pub struct RwLock<T> {
     state: AtomicUsize,
+    fairness: RwLockFairness,
+    /// 竞争统计使用的锁类
+    #[cfg(feature = "lock-stat")]
+    class: Option<&'static LockClass>,
     data: UnsafeCell<T>,
 }
 
@@ -44,6 +71,12 @@ pub struct RwLockWriteGuard<'a, T> {
     intr_guard: IntrGuard,
 }
 
+/// 可升级读锁的 RAII 保护器
+pub struct RwLockUpgradableReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    intr_guard: IntrGuard,
+}
+
 impl<T> RwLock<T> {
     /// 创建新的读写锁
     ///
@@ -52,27 +85,63 @@ impl<T> RwLock<T> {
     /// # 返回值
     /// - `RwLock<T>`: 新创建的读写锁
     pub const fn new(data: T) -> Self {
+        Self::with_fairness(data, RwLockFairness::ReaderPreferred)
+    }
+
+    /// 创建使用指定公平策略的读写锁
+    pub const fn with_fairness(data: T, fairness: RwLockFairness) -> Self {
+        RwLock {
+            state: AtomicUsize::new(0),
+            fairness,
+            #[cfg(feature = "lock-stat")]
+            class: None,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// 创建属于 `class` 锁类的读写锁，锁类用于 `lock-stat` 特性的竞争统计
+    #[allow(unused_variables)]
+    pub const fn with_class(data: T, class: &'static LockClass) -> Self {
         RwLock {
             state: AtomicUsize::new(0),
+            fairness: RwLockFairness::ReaderPreferred,
+            #[cfg(feature = "lock-stat")]
+            class: Some(class),
             data: UnsafeCell::new(data),
         }
     }
 
+    /// 会阻止新读者进入的状态位
+    #[inline]
+    fn reader_blockers(&self) -> usize {
+        match self.fairness {
+            RwLockFairness::ReaderPreferred => WRITER_BIT,
+            RwLockFairness::WriterPreferred => WRITER_BIT | WRITER_WAITING_BIT,
+        }
+    }
+
     /// 获取读锁，返回 RAII 保护器
     ///
-    /// 允许多个读者同时持有锁。如果有写者持有锁，则自旋等待。
+    /// 允许多个读者同时持有锁。如果有写者持有锁（写者优先时还包括有写者等待），则自旋等待。
     /// 自动禁用中断，离开作用域时恢复。
     ///
     /// # 返回值
     /// - `RwLockReadGuard`: RAII 保护器，提供共享数据访问
     pub fn read(&self) -> RwLockReadGuard<'_, T> {
         let intr_guard = IntrGuard::new();
+        #[cfg(feature = "lock-stat")]
+        let mut spin_start = None;
+        let blockers = self.reader_blockers();
 
         loop {
             let state = self.state.load(Ordering::Relaxed);
 
             // 检查是否有写者
-            if state & WRITER_BIT != 0 {
+            if state & blockers != 0 {
+                #[cfg(feature = "lock-stat")]
+                if spin_start.is_none() {
+                    spin_start = Some(crate::lock_stat::cycles());
+                }
                 hint::spin_loop();
                 continue;
             }
@@ -88,6 +157,8 @@ impl<T> RwLock<T> {
                 .compare_exchange_weak(state, state + 1, Ordering::Acquire, Ordering::Relaxed)
                 .is_ok()
             {
+                #[cfg(feature = "lock-stat")]
+                crate::lock_stat::record_since(self.class, spin_start);
                 return RwLockReadGuard {
                     lock: self,
                     intr_guard,
@@ -98,35 +169,89 @@ impl<T> RwLock<T> {
 
     /// 获取写锁，返回 RAII 保护器
     ///
-    /// 独占访问，等待所有读者和写者退出。
+    /// 独占访问，等待所有读者和写者退出。写者优先时，等待期间阻止新读者进入。
     /// 自动禁用中断，离开作用域时恢复。
     ///
     /// # 返回值
     /// - `RwLockWriteGuard`: RAII 保护器，提供独占数据访问
     pub fn write(&self) -> RwLockWriteGuard<'_, T> {
         let intr_guard = IntrGuard::new();
+        #[cfg(feature = "lock-stat")]
+        let mut spin_start = None;
 
         // 等待直到可以设置写者标志
         loop {
-            // 先检查 state 是否为 0，减少 CAS 失败时的总线争用
-            if self.state.load(Ordering::Relaxed) == 0
+            // 先检查锁是否空闲（只剩等待标志），减少 CAS 失败时的总线争用；
+            // 获取成功会清除等待标志，仍在等待的其它写者下一轮重新设置
+            let state = self.state.load(Ordering::Relaxed);
+            if state & !WRITER_WAITING_BIT == 0 {
+                if self
+                    .state
+                    .compare_exchange_weak(state, WRITER_BIT, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    #[cfg(feature = "lock-stat")]
+                    crate::lock_stat::record_since(self.class, spin_start);
+                    return RwLockWriteGuard {
+                        lock: self,
+                        intr_guard,
+                    };
+                }
+                continue;
+            }
+            if self.fairness == RwLockFairness::WriterPreferred && state & WRITER_WAITING_BIT == 0 {
+                self.state.fetch_or(WRITER_WAITING_BIT, Ordering::Relaxed);
+            }
+            #[cfg(feature = "lock-stat")]
+            if spin_start.is_none() {
+                spin_start = Some(crate::lock_stat::cycles());
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// 获取可升级读锁，返回 RAII 保护器
+    ///
+    /// 与普通读者共存，但同一时刻至多一个可升级读者，且排斥写者。
+    /// 持有期间可以调用 [`RwLockUpgradableReadGuard::upgrade`] 原子地升级为写锁。
+    /// 自动禁用中断，离开作用域时恢复。
+    pub fn upgradeable_read(&self) -> RwLockUpgradableReadGuard<'_, T> {
+        let intr_guard = IntrGuard::new();
+        #[cfg(feature = "lock-stat")]
+        let mut spin_start = None;
+        let blockers = self.reader_blockers() | UPGRADABLE_BIT;
+
+        loop {
+            let state = self.state.load(Ordering::Relaxed);
+            if state & blockers == 0
                 && self
                     .state
-                    .compare_exchange_weak(0, WRITER_BIT, Ordering::Acquire, Ordering::Relaxed)
+                    .compare_exchange_weak(
+                        state,
+                        state | UPGRADABLE_BIT,
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
                     .is_ok()
             {
-                return RwLockWriteGuard {
+                #[cfg(feature = "lock-stat")]
+                crate::lock_stat::record_since(self.class, spin_start);
+                return RwLockUpgradableReadGuard {
                     lock: self,
                     intr_guard,
                 };
             }
+            #[cfg(feature = "lock-stat")]
+            if spin_start.is_none() {
+                spin_start = Some(crate::lock_stat::cycles());
+            }
             hint::spin_loop();
         }
     }
 
     /// 尝试获取读锁，如果成功则返回 RAII 保护器，否则返回 None
     ///
-    /// 非阻塞版本，如果当前有写者则立即返回 None。
+    /// 非阻塞版本，如果当前有写者（写者优先时还包括有写者等待）则立即返回 None。
     ///
     /// # 返回值
     /// - `Some(RwLockReadGuard)`: 成功获取读锁
@@ -137,7 +262,7 @@ impl<T> RwLock<T> {
         let state = self.state.load(Ordering::Relaxed);
 
         // 检查是否有写者
-        if state & WRITER_BIT != 0 {
+        if state & self.reader_blockers() != 0 {
             return None;
         }
 
@@ -171,10 +296,12 @@ impl<T> RwLock<T> {
     pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
         let intr_guard = IntrGuard::new();
 
-        if self
-            .state
-            .compare_exchange(0, WRITER_BIT, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+        let state = self.state.load(Ordering::Relaxed);
+        if state & !WRITER_WAITING_BIT == 0
+            && self
+                .state
+                .compare_exchange(state, WRITER_BIT, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
         {
             Some(RwLockWriteGuard {
                 lock: self,
@@ -238,7 +365,81 @@ impl<T> DerefMut for RwLockWriteGuard<'_, T> {
 
 impl<T> Drop for RwLockWriteGuard<'_, T> {
     fn drop(&mut self) {
-        self.lock.state.store(0, Ordering::Release);
+        // 保留其它写者设置的等待标志
+        self.lock.state.fetch_and(!WRITER_BIT, Ordering::Release);
+    }
+}
+
+impl<'a, T> RwLockUpgradableReadGuard<'a, T> {
+    /// 等待其余读者退出后升级为写锁，期间不释放锁
+    pub fn upgrade(self) -> RwLockWriteGuard<'a, T> {
+        let this = ManuallyDrop::new(self);
+        let lock = this.lock;
+        // 持有 UPGRADABLE 位时写者和其它可升级读者都进不来，只需等普通读者退出
+        loop {
+            let state = lock.state.load(Ordering::Relaxed);
+            if state & READER_MASK == 0
+                && lock
+                    .state
+                    .compare_exchange_weak(
+                        state,
+                        (state & !UPGRADABLE_BIT) | WRITER_BIT,
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            {
+                break;
+            }
+            hint::spin_loop();
+        }
+        RwLockWriteGuard {
+            lock,
+            // SAFETY: this 不会再被 drop，中断保护器的所有权转移给写锁保护器
+            intr_guard: unsafe { ptr::read(&this.intr_guard) },
+        }
+    }
+
+    /// 没有其余读者时立即升级为写锁，否则原样返回
+    pub fn try_upgrade(self) -> Result<RwLockWriteGuard<'a, T>, Self> {
+        let state = self.lock.state.load(Ordering::Relaxed);
+        if state & READER_MASK != 0
+            || self
+                .lock
+                .state
+                .compare_exchange(
+                    state,
+                    (state & !UPGRADABLE_BIT) | WRITER_BIT,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            return Err(self);
+        }
+        let this = ManuallyDrop::new(self);
+        Ok(RwLockWriteGuard {
+            lock: this.lock,
+            // SAFETY: 同 upgrade
+            intr_guard: unsafe { ptr::read(&this.intr_guard) },
+        })
+    }
+}
+
+impl<T> Deref for RwLockUpgradableReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: 持有可升级读锁，保证无写者
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockUpgradableReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock
+            .state
+            .fetch_and(!UPGRADABLE_BIT, Ordering::Release);
     }
 }
 
@@ -276,4 +477,47 @@ mod tests {
         let _guard2 = lock.read();
         assert_eq!(lock.reader_count(), 2);
     }
+
+    #[test]
+    fn test_rwlock_upgradeable_read() {
+        let lock = RwLock::new(1);
+        let upgradable = lock.upgradeable_read();
+        // 与普通读者共存，但排斥写者和其它可升级读者
+        let reader = lock.try_read().unwrap();
+        assert!(lock.try_write().is_none());
+        let upgradable = upgradable.try_upgrade().err().unwrap();
+        drop(reader);
+        let mut guard = upgradable.upgrade();
+        assert!(lock.is_write_locked());
+        *guard += 1;
+        drop(guard);
+        assert_eq!(lock.state.load(Ordering::Relaxed), 0);
+        assert_eq!(*lock.read(), 2);
+    }
+
+    #[test]
+    fn test_rwlock_writer_preferred_blocks_new_readers() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let lock = Arc::new(RwLock::with_fairness(0, RwLockFairness::WriterPreferred));
+        let reader = lock.read();
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || *lock.write() += 1)
+        };
+        while lock.state.load(Ordering::Relaxed) & WRITER_WAITING_BIT == 0 {
+            thread::yield_now();
+        }
+        // 有写者等待时新读者不能进入，已持有的读锁不受影响
+        assert!(lock.try_read().is_none());
+        drop(reader);
+        writer.join().unwrap();
+        assert_eq!(*lock.read(), 1);
+
+        // 读者优先的锁不受等待写者影响
+        let lock = RwLock::new(0);
+        let _reader = lock.read();
+        assert!(lock.try_read().is_some());
+    }
 }
@@ -0,0 +1,170 @@
+//! 顺序锁
+//!
+//! 适合很少写入、频繁读取的小块数据（如计时数据、统计计数）：
+//! 写者之间用自旋锁互斥，写入前后各把序号加一（写入期间序号为奇数）；
+//! 读者不加锁，复制数据前后各读一次序号，序号为奇数或前后不一致时重试。
+//! 读者从不阻塞写者，写者也不会等待读者。
+
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering, fence};
+
+use crate::raw_spin_lock::{RawSpinLock, RawSpinLockGuard};
+
+/// 顺序锁，读者无锁读取数据副本，遇到并发写入时重试
+///
+/// 数据必须是 [`Copy`] 的：读者可能读到写到一半的数据，只在确认序号未变后才使用。
+///
+/// # 示例
+/// ```ignore
+/// static STATS: SeqLock<Stats> = SeqLock::new(Stats::new());
+///
+/// STATS.write().packets += 1; // 写者
+/// let stats = STATS.read();   // 读者，得到一致的副本
+/// ```
+pub struct SeqLock<T> {
+    /// 序号，奇数表示正在写入
+    seq: AtomicUsize,
+    /// 写者锁
+    lock: RawSpinLock,
+    data: UnsafeCell<T>,
+}
+
+// Safety: 写者由自旋锁互斥，读者只读取副本并在确认一致后使用
+unsafe impl<T: Copy + Send> Send for SeqLock<T> {}
+unsafe impl<T: Copy + Send> Sync for SeqLock<T> {}
+
+impl<T: Copy> SeqLock<T> {
+    /// 创建一个新的顺序锁
+    pub const fn new(data: T) -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            lock: RawSpinLock::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// 读取一致的数据副本
+    pub fn read(&self) -> T {
+        self.read_with(|data| *data)
+    }
+
+    /// 在读侧临界区内对数据副本执行 `f`，遇到并发写入时重新读取并重新执行
+    ///
+    /// `f` 可能被执行多次，不应有副作用。
+    pub fn read_with<R>(&self, f: impl Fn(&T) -> R) -> R {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq & 1 != 0 {
+                core::hint::spin_loop();
+                continue;
+            }
+            // Safety: 可能与写者并发，只读取按位副本，序号校验通过后才使用
+            let data = unsafe { core::ptr::read_volatile(self.data.get()) };
+            let result = f(&data);
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == seq {
+                return result;
+            }
+        }
+    }
+
+    /// 获取写者锁并开始写入，返回的守卫销毁时结束写入
+    ///
+    /// 写者锁会关闭本地中断，避免写者被本 CPU 上的读者（如中断处理）打断后读者永远重试。
+    pub fn write(&self) -> SeqLockWriteGuard<'_, T> {
+        let guard = self.lock.lock();
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        SeqLockWriteGuard {
+            lock: self,
+            _guard: guard,
+        }
+    }
+
+    /// 直接替换数据
+    pub fn set(&self, data: T) {
+        *self.write() = data;
+    }
+}
+
+/// [`SeqLock`] 的写守卫
+pub struct SeqLockWriteGuard<'a, T: Copy> {
+    lock: &'a SeqLock<T>,
+    _guard: RawSpinLockGuard<'a>,
+}
+
+impl<T: Copy> Deref for SeqLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: 持有写者锁
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: Copy> DerefMut for SeqLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // Safety: 持有写者锁
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: Copy> Drop for SeqLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // 在释放写者锁之前结束写入
+        let seq = self.lock.seq.load(Ordering::Relaxed);
+        self.lock.seq.store(seq.wrapping_add(1), Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    #[test]
+    fn test_seqlock_read_write() {
+        let lock = SeqLock::new((1u64, 2u64));
+        assert_eq!(lock.read(), (1, 2));
+        {
+            let mut data = lock.write();
+            data.0 = 10;
+            // 写入期间序号为奇数
+            assert_eq!(lock.seq.load(Ordering::Relaxed) & 1, 1);
+        }
+        lock.set((10, 20));
+        assert_eq!(lock.read(), (10, 20));
+        assert_eq!(lock.seq.load(Ordering::Relaxed), 4);
+        assert_eq!(lock.read_with(|data| data.0 + data.1), 30);
+    }
+
+    #[test]
+    fn test_seqlock_readers_see_consistent_copies() {
+        // 写者始终保持两个字段相等，读者不应看到不相等的副本
+        let lock = Arc::new(SeqLock::new((0u64, 0u64)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let lock = lock.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut n = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    n += 1;
+                    let mut data = lock.write();
+                    data.0 = n;
+                    data.1 = n;
+                }
+            })
+        };
+        for _ in 0..10_000 {
+            let (a, b) = lock.read();
+            assert_eq!(a, b);
+        }
+        stop.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+    }
+}
@@ -10,6 +10,7 @@
 
 use core::cell::UnsafeCell;
 
+use crate::lockdep::LockClass;
 use crate::raw_spin_lock::{RawSpinLock, RawSpinLockGuard};
 
 /// 提供对数据的互斥访问的自旋锁结构体。
@@ -44,10 +45,19 @@ impl<T> SpinLock<T> {
         }
     }
 
+    /// 创建一个属于 `class` 锁类的 SpinLock 实例，锁类用于 `lockdep` 特性的锁顺序检查。
+    pub const fn with_class(data: T, class: &'static LockClass) -> Self {
+        SpinLock {
+            raw_lock: RawSpinLock::with_class(class),
+            data: UnsafeCell::new(data),
+        }
+    }
+
     /// 获取自旋锁，并返回一个 RAII 保护器，用于访问和修改内部数据。
     pub fn lock(&self) -> SpinLockGuard<'_, T> {
         let _raw_guard = self.raw_lock.lock();
         SpinLockGuard {
+            lock: self,
             _raw_guard,
             data: unsafe { &mut *self.data.get() },
         }
@@ -56,11 +66,17 @@ impl<T> SpinLock<T> {
     /// 尝试获取自旋锁，如果成功则返回 RAII 保护器，否则返回 None。
     pub fn try_lock(&self) -> Option<SpinLockGuard<'_, T>> {
         self.raw_lock.try_lock().map(|_raw_guard| SpinLockGuard {
+            lock: self,
             _raw_guard,
             data: unsafe { &mut *self.data.get() },
         })
     }
 
+    /// 锁是否由当前 CPU 持有，见 [`RawSpinLock::is_held_by_current_cpu`]。
+    pub fn is_held_by_current_cpu(&self) -> bool {
+        self.raw_lock.is_held_by_current_cpu()
+    }
+
     /// 检查锁是否被占用 (仅用于调试/测试)
     ///
     /// # 返回值
@@ -75,6 +91,7 @@ impl<T> SpinLock<T> {
 ///
 /// 当保护器离开作用域时，自动释放锁。
 pub struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
     _raw_guard: RawSpinLockGuard<'a>,
     data: &'a mut T,
 }
@@ -93,6 +110,18 @@ impl<T> core::ops::DerefMut for SpinLockGuard<'_, T> {
     }
 }
 
+impl<'a, T> crate::condvar::CondvarGuard<'a> for SpinLockGuard<'a, T> {
+    type Lock = SpinLock<T>;
+
+    fn unlock(self) -> &'a SpinLock<T> {
+        self.lock
+    }
+
+    fn relock(lock: &'a SpinLock<T>) -> Self {
+        lock.lock()
+    }
+}
+
 // Safety: SpinLock 可以在线程间安全共享，
 // 因为它通过 RawSpinLock 保证了对数据的互斥访问。
 unsafe impl<T: Send> Send for SpinLock<T> {}
@@ -0,0 +1,276 @@
+//! 中断安全的自旋锁
+//!
+//! [`SpinLockIrq`] 用于保护**会被中断处理程序访问**的数据：临界区始终关闭本地中断，
+//! 并在调试构建中检查两类典型错误：
+//! - 在中断上下文里、中断处于开启状态时获取锁（嵌套中断可能再次获取同一把锁而死锁）；
+//! - 持有锁时进入调度点（睡眠或切换任务后，本 CPU 的中断一直保持关闭，其它 CPU 上的等待者长时间自旋）。
+//!
+//! 只在任务上下文使用的数据继续用 [`SpinLock`](crate::SpinLock)；在类型上区分两者，
+//! 中断处理程序需要的锁一眼就能看出来。
+//!
+//! 中断上下文由 [`irq_enter`]/[`irq_exit`] 标记，调度点通过 [`might_sleep`] 或
+//! [`irq_locks_held`] 检查。计数按 CPU 维护：持有锁期间中断关闭、任务不会迁移，
+//! 中断处理程序也不会在中途切换任务。
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::Ordering;
+
+use crate::lockdep::LockClass;
+use crate::raw_spin_lock::{RawSpinLock, RawSpinLockGuard};
+
+#[cfg(not(test))]
+mod counters {
+    use core::sync::atomic::AtomicUsize;
+
+    use crate::arch_ops;
+    use crate::preempt::MAX_CPUS;
+
+    /// 缓存行对齐的 Per-CPU 计数器
+    #[repr(align(64))]
+    pub(super) struct CpuCounters {
+        /// 中断处理嵌套深度
+        pub(super) hardirq: AtomicUsize,
+        /// 持有的 SpinLockIrq 数量
+        pub(super) irq_locks: AtomicUsize,
+    }
+
+    static COUNTERS: [CpuCounters; MAX_CPUS] = {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const INIT: CpuCounters = CpuCounters {
+            hardirq: AtomicUsize::new(0),
+            irq_locks: AtomicUsize::new(0),
+        };
+        [INIT; MAX_CPUS]
+    };
+
+    /// 当前 CPU 的计数器
+    #[inline]
+    pub(super) fn this_cpu() -> &'static CpuCounters {
+        &COUNTERS[arch_ops().cpu_id()]
+    }
+}
+
+/// 宿主测试中所有线程的 CPU 编号相同，以线程模拟 CPU，各线程使用自己的计数器
+#[cfg(test)]
+mod counters {
+    use core::sync::atomic::AtomicUsize;
+    use std::boxed::Box;
+
+    pub(super) struct CpuCounters {
+        pub(super) hardirq: AtomicUsize,
+        pub(super) irq_locks: AtomicUsize,
+    }
+
+    std::thread_local! {
+        static COUNTERS: &'static CpuCounters = Box::leak(Box::new(CpuCounters {
+            hardirq: AtomicUsize::new(0),
+            irq_locks: AtomicUsize::new(0),
+        }));
+    }
+
+    pub(super) fn this_cpu() -> &'static CpuCounters {
+        COUNTERS.with(|counters| *counters)
+    }
+}
+
+/// 进入中断处理，必须与 [`irq_exit`] 配对，可以嵌套
+#[inline]
+pub fn irq_enter() {
+    counters::this_cpu().hardirq.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 退出中断处理
+#[inline]
+pub fn irq_exit() {
+    let prev = counters::this_cpu().hardirq.fetch_sub(1, Ordering::Relaxed);
+    debug_assert!(prev != 0, "sync: irq_exit without matching irq_enter");
+}
+
+/// 当前 CPU 是否处于中断处理中
+#[inline]
+pub fn in_interrupt() -> bool {
+    counters::this_cpu().hardirq.load(Ordering::Relaxed) != 0
+}
+
+/// 当前 CPU 持有的 [`SpinLockIrq`] 数量
+#[inline]
+pub fn irq_locks_held() -> usize {
+    counters::this_cpu().irq_locks.load(Ordering::Relaxed)
+}
+
+/// 标记一个可能睡眠的位置
+///
+/// 调试构建中，在中断上下文或持有 [`SpinLockIrq`] 时调用会 panic；发布构建中不做任何事。
+#[inline]
+#[track_caller]
+pub fn might_sleep() {
+    if cfg!(debug_assertions) {
+        assert!(!in_interrupt(), "sync: sleeping in interrupt context");
+        let held = irq_locks_held();
+        assert!(held == 0, "sync: sleeping while holding {held} SpinLockIrq");
+    }
+}
+
+/// 始终关闭本地中断的自旋锁，可以在任务上下文和中断处理程序之间共享数据。
+///
+/// # 示例
+/// ```ignore
+/// static PENDING: SpinLockIrq<VecDeque<Request>> = SpinLockIrq::new(VecDeque::new());
+///
+/// // 任务上下文
+/// PENDING.lock().push_back(req);
+///
+/// // 中断处理程序
+/// irq_enter();
+/// let req = PENDING.lock().pop_front();
+/// irq_exit();
+/// ```
+///
+/// # 注意
+/// 与 [`SpinLock`](crate::SpinLock) 一样不可重入。持有锁时不能睡眠或调度，
+/// 因此没有实现 [`CondvarGuard`](crate::CondvarGuard)。
+#[derive(Debug)]
+pub struct SpinLockIrq<T> {
+    raw_lock: RawSpinLock,
+    data: UnsafeCell<T>,
+}
+
+impl<T> SpinLockIrq<T> {
+    /// 创建一个新的 SpinLockIrq 实例，初始化内部数据。
+    pub const fn new(data: T) -> Self {
+        SpinLockIrq {
+            raw_lock: RawSpinLock::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// 创建一个属于 `class` 锁类的 SpinLockIrq 实例，锁类用于 `lockdep` 特性的锁顺序检查。
+    pub const fn with_class(data: T, class: &'static LockClass) -> Self {
+        SpinLockIrq {
+            raw_lock: RawSpinLock::with_class(class),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// 关闭本地中断并获取锁，返回 RAII 保护器。
+    ///
+    /// # Panics
+    /// 调试构建中，在中断上下文且中断开启时获取锁会 panic
+    #[track_caller]
+    pub fn lock(&self) -> SpinLockIrqGuard<'_, T> {
+        let raw_guard = self.raw_lock.lock();
+        self.guard(raw_guard)
+    }
+
+    /// 尝试获取锁，如果成功则返回 RAII 保护器，否则返回 None。
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<SpinLockIrqGuard<'_, T>> {
+        self.raw_lock
+            .try_lock()
+            .map(|raw_guard| self.guard(raw_guard))
+    }
+
+    #[track_caller]
+    fn guard<'a>(&'a self, raw_guard: RawSpinLockGuard<'a>) -> SpinLockIrqGuard<'a, T> {
+        if cfg!(debug_assertions) && in_interrupt() && raw_guard.intr_guard.was_enabled() {
+            drop(raw_guard);
+            panic!("sync: SpinLockIrq taken with interrupts enabled in interrupt context");
+        }
+        counters::this_cpu()
+            .irq_locks
+            .fetch_add(1, Ordering::Relaxed);
+        SpinLockIrqGuard {
+            _raw_guard: raw_guard,
+            data: unsafe { &mut *self.data.get() },
+        }
+    }
+
+    /// 检查锁是否被占用 (仅用于调试/测试)
+    ///
+    /// # 返回值
+    /// 锁是否被占用
+    #[cfg(test)]
+    pub fn is_locked(&self) -> bool {
+        self.raw_lock.is_locked()
+    }
+}
+
+/// SpinLockIrq 的 RAII 保护器，离开作用域时释放锁并恢复中断状态。
+pub struct SpinLockIrqGuard<'a, T> {
+    _raw_guard: RawSpinLockGuard<'a>,
+    data: &'a mut T,
+}
+
+impl<T> core::ops::Deref for SpinLockIrqGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+
+impl<T> core::ops::DerefMut for SpinLockIrqGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data
+    }
+}
+
+impl<T> Drop for SpinLockIrqGuard<'_, T> {
+    /// 先减少持有计数，随后 `_raw_guard` 释放锁并恢复中断
+    fn drop(&mut self) {
+        counters::this_cpu()
+            .irq_locks
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Safety: 与 SpinLock 相同，RawSpinLock 保证了对数据的互斥访问。
+unsafe impl<T: Send> Send for SpinLockIrq<T> {}
+unsafe impl<T: Send> Sync for SpinLockIrq<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spinlock_irq_tracks_held_locks() {
+        let a = SpinLockIrq::new(1);
+        let b = SpinLockIrq::new(2);
+        let guard_a = a.lock();
+        assert!(a.is_locked());
+        assert!(a.try_lock().is_none());
+        let guard_b = b.lock();
+        assert_eq!(irq_locks_held(), 2);
+        assert_eq!(*guard_a + *guard_b, 3);
+        drop(guard_b);
+        drop(guard_a);
+        assert_eq!(irq_locks_held(), 0);
+        assert!(!a.is_locked());
+        might_sleep();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_might_sleep_panics_while_holding() {
+        let lock = SpinLockIrq::new(0);
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            let _guard = lock.lock();
+            might_sleep();
+        }));
+        assert!(result.is_err());
+        // 展开时保护器已释放
+        assert_eq!(irq_locks_held(), 0);
+        assert!(!lock.is_locked());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_might_sleep_panics_in_interrupt() {
+        irq_enter();
+        assert!(in_interrupt());
+        let result = std::panic::catch_unwind(might_sleep);
+        irq_exit();
+        assert!(result.is_err());
+        assert!(!in_interrupt());
+    }
+}
@@ -18,6 +18,7 @@
 //! - 票号溢出：next_ticket 在 usize::MAX 时回绕，可能导致死锁（实际不太可能发生）
 
 use crate::intr_guard::IntrGuard;
+use crate::lockdep::LockClass;
 use core::{
     cell::UnsafeCell,
     hint,
@@ -29,6 +30,9 @@ use core::{
 pub struct TicketLock<T> {
     next_ticket: AtomicUsize,
     serving_ticket: AtomicUsize,
+    /// 竞争统计使用的锁类
+    #[cfg(feature = "lock-stat")]
+    class: Option<&'static LockClass>,
     data: UnsafeCell<T>,
 }
 
@@ -50,6 +54,20 @@ impl<T> TicketLock<T> {
         TicketLock {
             next_ticket: AtomicUsize::new(0),
             serving_ticket: AtomicUsize::new(0),
+            #[cfg(feature = "lock-stat")]
+            class: None,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// 创建属于 `class` 锁类的票号锁，锁类用于 `lock-stat` 特性的竞争统计
+    #[allow(unused_variables)]
+    pub const fn with_class(data: T, class: &'static LockClass) -> Self {
+        TicketLock {
+            next_ticket: AtomicUsize::new(0),
+            serving_ticket: AtomicUsize::new(0),
+            #[cfg(feature = "lock-stat")]
+            class: Some(class),
             data: UnsafeCell::new(data),
         }
     }
@@ -68,9 +86,17 @@ impl<T> TicketLock<T> {
         let my_ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
 
         // 等待轮到自己
+        #[cfg(feature = "lock-stat")]
+        let mut spin_start = None;
         while self.serving_ticket.load(Ordering::Acquire) != my_ticket {
+            #[cfg(feature = "lock-stat")]
+            if spin_start.is_none() {
+                spin_start = Some(crate::lock_stat::cycles());
+            }
             hint::spin_loop();
         }
+        #[cfg(feature = "lock-stat")]
+        crate::lock_stat::record_since(self.class, spin_start);
 
         TicketLockGuard {
             lock: self,
@@ -0,0 +1,514 @@
+//! 等待队列
+//!
+//! [`WaitQueue`] 让任务睡眠直到某个条件成立，改变条件的一方随后调用
+//! [`wake_one`](WaitQueue::wake_one) 或 [`wake_all`](WaitQueue::wake_all) 唤醒等待者。
+//! 与 [`Condvar`](crate::Condvar) 不同，条件不需要由调用者的锁保护：条件在等待队列内部锁内
+//! 检查，唤醒方只要在修改条件之后调用 `wake_*`，就不会丢失唤醒。
+//!
+//! 等待者分为两类：
+//! - 非独占等待者：每次唤醒都会被唤醒；
+//! - 独占等待者：[`wake_one`](WaitQueue::wake_one) 只唤醒其中最早开始等待的一个，避免惊群。
+//!
+//! 睡眠、唤醒和超时都通过 [`SchedOps`](crate::SchedOps) 完成：截止时间交给 `schedule`，
+//! 与 `now` 使用同一单调时钟。使用前必须调用 [`register_sched_ops`](crate::register_sched_ops)。
+//!
+//! # 示例
+//! ```ignore
+//! static READY: AtomicBool = AtomicBool::new(false);
+//! static WQ: WaitQueue = WaitQueue::new();
+//!
+//! // 等待方
+//! WQ.wait_event(|| READY.load(Ordering::Acquire));
+//!
+//! // 唤醒方
+//! READY.store(true, Ordering::Release);
+//! WQ.wake_all();
+//! ```
+
+use core::ptr;
+
+use crate::lockdep::LockClass;
+use crate::sched_ops;
+use crate::spin_lock::SpinLock;
+
+/// 等待的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitResult {
+    /// 条件已成立
+    Ready,
+    /// 到达截止时间时条件仍不成立
+    TimedOut,
+    /// 可中断的等待被信号打断
+    Interrupted,
+}
+
+/// 等待方式，默认是不限时、不可中断的非独占等待
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WaitOptions {
+    exclusive: bool,
+    interruptible: bool,
+    deadline: Option<u64>,
+}
+
+impl WaitOptions {
+    /// 不限时、不可中断的非独占等待
+    pub const fn new() -> Self {
+        Self {
+            exclusive: false,
+            interruptible: false,
+            deadline: None,
+        }
+    }
+
+    /// 以独占方式等待
+    pub const fn exclusive(self) -> Self {
+        Self {
+            exclusive: true,
+            ..self
+        }
+    }
+
+    /// 等待可以被信号打断（见 [`SchedOps::signal_pending`](crate::SchedOps::signal_pending)）
+    pub const fn interruptible(self) -> Self {
+        Self {
+            interruptible: true,
+            ..self
+        }
+    }
+
+    /// 最多等待到 `deadline`（单调时钟，纳秒）
+    pub const fn deadline(self, deadline: u64) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// 从现在起最多等待 `timeout` 纳秒
+    pub fn timeout(self, timeout: u64) -> Self {
+        self.deadline(sched_ops().now().saturating_add(timeout))
+    }
+}
+
+/// 等待者节点，位于等待任务的栈上
+///
+/// 各字段只在持有 [`WaitQueue::waiters`] 锁时访问。
+struct Waiter {
+    /// 等待任务的句柄
+    task: usize,
+    exclusive: bool,
+    /// 是否在链表中（被唤醒者从链表中移除）
+    queued: bool,
+    next: *mut Waiter,
+}
+
+/// 等待者链表：非独占等待者在前，独占等待者按开始等待的顺序在后
+struct WaitList {
+    head: *mut Waiter,
+    tail: *mut Waiter,
+}
+
+// Safety: 链表中的节点只在持有外层 SpinLock 时访问
+unsafe impl Send for WaitList {}
+
+impl WaitList {
+    const fn new() -> Self {
+        Self {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+        }
+    }
+
+    /// # Safety
+    /// `waiter` 必须有效、不在链表中，且在移出链表之前保持有效
+    unsafe fn enqueue(&mut self, waiter: *mut Waiter) {
+        unsafe {
+            (*waiter).queued = true;
+            if (*waiter).exclusive {
+                (*waiter).next = ptr::null_mut();
+                if self.tail.is_null() {
+                    self.head = waiter;
+                } else {
+                    (*self.tail).next = waiter;
+                }
+                self.tail = waiter;
+            } else {
+                (*waiter).next = self.head;
+                self.head = waiter;
+                if self.tail.is_null() {
+                    self.tail = waiter;
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<*mut Waiter> {
+        if self.head.is_null() {
+            return None;
+        }
+        let waiter = self.head;
+        // Safety: 链表中的节点都有效
+        unsafe {
+            self.head = (*waiter).next;
+            (*waiter).queued = false;
+        }
+        if self.head.is_null() {
+            self.tail = ptr::null_mut();
+        }
+        Some(waiter)
+    }
+
+    /// 移除指定节点（不在链表中时不做任何事）
+    fn remove(&mut self, waiter: *mut Waiter) {
+        let mut prev: *mut Waiter = ptr::null_mut();
+        let mut cur = self.head;
+        // Safety: 链表中的节点都有效
+        unsafe {
+            while !cur.is_null() {
+                if cur == waiter {
+                    let next = (*cur).next;
+                    if prev.is_null() {
+                        self.head = next;
+                    } else {
+                        (*prev).next = next;
+                    }
+                    if self.tail == cur {
+                        self.tail = prev;
+                    }
+                    (*cur).queued = false;
+                    return;
+                }
+                prev = cur;
+                cur = (*cur).next;
+            }
+        }
+    }
+}
+
+/// 等待队列
+///
+/// 条件在内部锁内调用，因此不能睡眠；唤醒方不应在持有条件所用的锁时调用 `wake_*`，
+/// 否则可能与等待方形成锁顺序反转。
+pub struct WaitQueue {
+    waiters: SpinLock<WaitList>,
+}
+
+impl WaitQueue {
+    /// 创建一个空的等待队列
+    pub const fn new() -> Self {
+        Self {
+            waiters: SpinLock::new(WaitList::new()),
+        }
+    }
+
+    /// 创建一个内部锁属于锁类 `class` 的等待队列
+    pub const fn with_class(class: &'static LockClass) -> Self {
+        Self {
+            waiters: SpinLock::with_class(WaitList::new(), class),
+        }
+    }
+
+    /// 以非独占方式睡眠，直到 `condition` 返回 `true`
+    pub fn wait_event<F: FnMut() -> bool>(&self, condition: F) {
+        self.wait_event_with(WaitOptions::new(), condition);
+    }
+
+    /// 与 [`wait_event`](Self::wait_event) 相同，但最多等待 `timeout` 纳秒
+    ///
+    /// # 返回值
+    /// 条件成立时返回 `true`，超时返回 `false`
+    pub fn wait_event_timeout<F: FnMut() -> bool>(&self, condition: F, timeout: u64) -> bool {
+        self.wait_event_with(WaitOptions::new().timeout(timeout), condition) == WaitResult::Ready
+    }
+
+    /// 按 `options` 指定的方式睡眠，直到 `condition` 返回 `true`、超时或被信号打断
+    ///
+    /// `condition` 在持有等待队列内部锁时调用，可以有副作用（例如原子地消耗一个资源），
+    /// 返回 `true` 后不会再被调用。
+    pub fn wait_event_with<F: FnMut() -> bool>(
+        &self,
+        options: WaitOptions,
+        mut condition: F,
+    ) -> WaitResult {
+        let mut list = self.waiters.lock();
+        if condition() {
+            return WaitResult::Ready;
+        }
+        crate::might_sleep();
+        let ops = sched_ops();
+        let task = ops.current_task();
+        let mut waiter = Waiter {
+            task,
+            exclusive: options.exclusive,
+            queued: false,
+            next: ptr::null_mut(),
+        };
+        let node: *mut Waiter = &mut waiter;
+        let result = loop {
+            if options.interruptible && ops.signal_pending(task) {
+                break WaitResult::Interrupted;
+            }
+            if options
+                .deadline
+                .is_some_and(|deadline| ops.now() >= deadline)
+            {
+                break WaitResult::TimedOut;
+            }
+            // 在锁内登记并标记睡眠：唤醒方修改条件后要先获取同一把锁，
+            // 那时已经能在链表中找到我们，唤醒不会丢失
+            // Safety: 持有 waiters 锁，节点在本函数的栈上，返回前会确保已移出链表
+            if !unsafe { (*node).queued } {
+                unsafe { list.enqueue(node) };
+            }
+            if options.interruptible {
+                ops.block_interruptible(task);
+            } else {
+                ops.block(task);
+            }
+            drop(list);
+            ops.schedule(task, options.deadline);
+            list = self.waiters.lock();
+            if condition() {
+                break WaitResult::Ready;
+            }
+        };
+        // 超时、被打断或虚假唤醒后条件恰好成立时，节点可能仍在链表中
+        // Safety: 持有 waiters 锁
+        if unsafe { (*node).queued } {
+            list.remove(node);
+        }
+        drop(list);
+        ops.release_task(task);
+        result
+    }
+
+    /// 唤醒所有非独占等待者，以及最早开始等待的一个独占等待者
+    ///
+    /// # 返回值
+    /// 被唤醒的等待者数量
+    pub fn wake_one(&self) -> usize {
+        let mut list = self.waiters.lock();
+        let mut woken = 0;
+        while let Some(waiter) = list.pop() {
+            woken += 1;
+            // Safety: 等待者要重新获取 waiters 锁才能离开 wait_event_with
+            if unsafe { Self::wake(waiter) } {
+                break;
+            }
+        }
+        woken
+    }
+
+    /// 唤醒所有等待者
+    ///
+    /// # 返回值
+    /// 被唤醒的等待者数量
+    pub fn wake_all(&self) -> usize {
+        let mut list = self.waiters.lock();
+        let mut woken = 0;
+        while let Some(waiter) = list.pop() {
+            woken += 1;
+            // Safety: 同 wake_one
+            unsafe { Self::wake(waiter) };
+        }
+        woken
+    }
+
+    /// 是否没有等待者
+    pub fn is_empty(&self) -> bool {
+        self.waiters.lock().head.is_null()
+    }
+
+    /// 唤醒刚从链表中移出的等待者，返回它是否是独占等待者
+    ///
+    /// # Safety
+    /// 必须持有 waiters 锁，且 `waiter` 刚从链表中移出
+    unsafe fn wake(waiter: *mut Waiter) -> bool {
+        unsafe {
+            sched_ops().wake((*waiter).task);
+            (*waiter).exclusive
+        }
+    }
+}
+
+impl Default for WaitQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use std::vec::Vec;
+    use test_support::interleave::{self, Model};
+
+    fn queued(wq: &WaitQueue) -> usize {
+        let list = wq.waiters.lock();
+        let mut count = 0;
+        let mut cur = list.head;
+        while !cur.is_null() {
+            count += 1;
+            cur = unsafe { (*cur).next };
+        }
+        count
+    }
+
+    fn wait_for_waiters(wq: &WaitQueue, count: usize) {
+        while queued(wq) < count {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// 消耗一个令牌，没有令牌时返回 `false`
+    fn take(tokens: &AtomicUsize) -> bool {
+        tokens
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    #[test]
+    fn test_wait_queue_wake_all() {
+        const WAITERS: usize = 4;
+        let shared = Arc::new((WaitQueue::new(), AtomicUsize::new(0)));
+        let waiters: Vec<_> = (0..WAITERS)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    shared
+                        .0
+                        .wait_event(|| shared.1.load(Ordering::Acquire) != 0)
+                })
+            })
+            .collect();
+        wait_for_waiters(&shared.0, WAITERS);
+        shared.1.store(1, Ordering::Release);
+        assert_eq!(shared.0.wake_all(), WAITERS);
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        assert!(shared.0.is_empty());
+    }
+
+    #[test]
+    fn test_wait_queue_wake_one_exclusive() {
+        const WAITERS: usize = 3;
+        let shared = Arc::new((WaitQueue::new(), AtomicUsize::new(0)));
+        let waiters: Vec<_> = (0..WAITERS)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    shared
+                        .0
+                        .wait_event_with(WaitOptions::new().exclusive(), || take(&shared.1))
+                })
+            })
+            .collect();
+        wait_for_waiters(&shared.0, WAITERS);
+        // 一个令牌只唤醒一个独占等待者
+        shared.1.fetch_add(1, Ordering::AcqRel);
+        assert_eq!(shared.0.wake_one(), 1);
+        assert_eq!(queued(&shared.0), WAITERS - 1);
+        shared.1.fetch_add(WAITERS - 1, Ordering::AcqRel);
+        shared.0.wake_all();
+        for waiter in waiters {
+            assert_eq!(waiter.join().unwrap(), WaitResult::Ready);
+        }
+        assert_eq!(shared.1.load(Ordering::Acquire), 0);
+    }
+
+    #[test]
+    fn test_wait_queue_wake_one_wakes_non_exclusive() {
+        let shared = Arc::new((WaitQueue::new(), AtomicUsize::new(0)));
+        let spawn = |options: WaitOptions| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                shared
+                    .0
+                    .wait_event_with(options, || shared.1.load(Ordering::Acquire) != 0)
+            })
+        };
+        let waiters = [
+            spawn(WaitOptions::new().exclusive()),
+            spawn(WaitOptions::new().exclusive()),
+        ];
+        wait_for_waiters(&shared.0, 2);
+        let shared_waiter = spawn(WaitOptions::new());
+        wait_for_waiters(&shared.0, 3);
+        // 后到的非独占等待者排在独占等待者之前，两者各唤醒一个
+        shared.1.store(1, Ordering::Release);
+        assert_eq!(shared.0.wake_one(), 2);
+        assert_eq!(shared_waiter.join().unwrap(), WaitResult::Ready);
+        assert_eq!(queued(&shared.0), 1);
+        shared.0.wake_all();
+        for waiter in waiters {
+            assert_eq!(waiter.join().unwrap(), WaitResult::Ready);
+        }
+    }
+
+    #[test]
+    fn test_wait_queue_timeout() {
+        let wq = WaitQueue::new();
+        assert!(!wq.wait_event_timeout(|| false, 1_000_000));
+        // 超时的等待者已移出链表
+        assert!(wq.is_empty());
+        // 条件已成立时不睡眠
+        assert!(wq.wait_event_timeout(|| true, 0));
+        assert_eq!(wq.wake_all(), 0);
+    }
+
+    /// 唤醒方修改条件并唤醒，与等待方的检查 / 登记 / 睡眠交错：任何交错下都不会丢失唤醒
+    #[test]
+    fn test_interleaved_wait_event_no_lost_wakeup() {
+        let report = Model::exhaustive(2).check(|| {
+            let shared = Arc::new((WaitQueue::new(), AtomicUsize::new(0)));
+            let waker = {
+                let shared = shared.clone();
+                interleave::spawn(move || {
+                    shared.1.store(1, Ordering::Release);
+                    shared.0.wake_all()
+                })
+            };
+            shared
+                .0
+                .wait_event(|| shared.1.load(Ordering::Acquire) != 0);
+            assert!(waker.join() <= 1);
+            assert!(shared.0.is_empty());
+        });
+        assert!(report.complete);
+        assert!(report.executions > 1);
+    }
+
+    /// 两个独占等待者与两次“放入令牌 + wake_one”交错：每个等待者都被唤醒且各消耗一个令牌
+    #[test]
+    fn test_interleaved_exclusive_wake_one() {
+        let report = Model::exhaustive(1).check(|| {
+            let shared = Arc::new((WaitQueue::new(), AtomicUsize::new(0)));
+            let waiters: Vec<_> = (0..2)
+                .map(|_| {
+                    let shared = shared.clone();
+                    interleave::spawn(move || {
+                        shared
+                            .0
+                            .wait_event_with(WaitOptions::new().exclusive(), || take(&shared.1))
+                    })
+                })
+                .collect();
+            for _ in 0..2 {
+                shared.1.fetch_add(1, Ordering::AcqRel);
+                shared.0.wake_one();
+            }
+            for waiter in waiters {
+                assert_eq!(waiter.join(), WaitResult::Ready);
+            }
+            assert_eq!(shared.1.load(Ordering::Acquire), 0);
+            assert!(shared.0.is_empty());
+        });
+        assert!(report.complete);
+        assert!(report.executions > 1);
+    }
+}
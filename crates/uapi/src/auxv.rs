@@ -0,0 +1,118 @@
+//! ELF 辅助向量（auxv）定义
+//!
+//! 源于 Linux 内核头文件 <linux/auxvec.h> 与各架构的 <asm/hwcap.h>。
+//! execve 在用户栈上 envp 之后放置 `(AT_*, value)` 键值对，以 `AT_NULL` 结束。
+
+/// 向量结束
+pub const AT_NULL: usize = 0;
+/// 忽略该条目
+pub const AT_IGNORE: usize = 1;
+/// 程序的文件描述符
+pub const AT_EXECFD: usize = 2;
+/// 程序头表地址
+pub const AT_PHDR: usize = 3;
+/// 程序头表项大小
+pub const AT_PHENT: usize = 4;
+/// 程序头数量
+pub const AT_PHNUM: usize = 5;
+/// 页大小
+pub const AT_PAGESZ: usize = 6;
+/// 解释器（动态链接器）加载基址
+pub const AT_BASE: usize = 7;
+/// 标志（目前总为 0）
+pub const AT_FLAGS: usize = 8;
+/// 程序入口
+pub const AT_ENTRY: usize = 9;
+/// 程序不是 ELF
+pub const AT_NOTELF: usize = 10;
+/// 真实用户 ID
+pub const AT_UID: usize = 11;
+/// 有效用户 ID
+pub const AT_EUID: usize = 12;
+/// 真实组 ID
+pub const AT_GID: usize = 13;
+/// 有效组 ID
+pub const AT_EGID: usize = 14;
+/// 平台名字符串地址
+pub const AT_PLATFORM: usize = 15;
+/// 处理器能力位（见各架构 `HWCAP_*`）
+pub const AT_HWCAP: usize = 16;
+/// times() 的时钟频率
+pub const AT_CLKTCK: usize = 17;
+/// 以安全模式执行（set-uid / 能力提升），libc 据此忽略危险的环境变量
+pub const AT_SECURE: usize = 23;
+/// 实际平台名字符串地址
+pub const AT_BASE_PLATFORM: usize = 24;
+/// 16 字节随机数地址
+pub const AT_RANDOM: usize = 25;
+/// 扩展处理器能力位
+pub const AT_HWCAP2: usize = 26;
+/// rseq 支持的特性大小
+pub const AT_RSEQ_FEATURE_SIZE: usize = 27;
+/// rseq 结构的对齐要求
+pub const AT_RSEQ_ALIGN: usize = 28;
+/// 扩展处理器能力位
+pub const AT_HWCAP3: usize = 29;
+/// 扩展处理器能力位
+pub const AT_HWCAP4: usize = 30;
+/// 可执行文件路径字符串地址
+pub const AT_EXECFN: usize = 31;
+/// vDSO 映像地址
+pub const AT_SYSINFO_EHDR: usize = 33;
+/// 信号栈的最小大小
+pub const AT_MINSIGSTKSZ: usize = 51;
+
+// 缓存几何信息（RISC-V 使用）
+pub const AT_L1I_CACHESIZE: usize = 40;
+pub const AT_L1I_CACHEGEOMETRY: usize = 41;
+pub const AT_L1D_CACHESIZE: usize = 42;
+pub const AT_L1D_CACHEGEOMETRY: usize = 43;
+pub const AT_L2_CACHESIZE: usize = 44;
+pub const AT_L2_CACHEGEOMETRY: usize = 45;
+pub const AT_L3_CACHESIZE: usize = 46;
+pub const AT_L3_CACHEGEOMETRY: usize = 47;
+
+/// 用户态 times() 的时钟频率（AT_CLKTCK）
+pub const USER_HZ: usize = 100;
+
+/// RISC-V 的 AT_HWCAP 位：单字母扩展 `X` 对应第 `X - 'A'` 位
+pub mod riscv {
+    const fn isa(ext: u8) -> usize {
+        1 << (ext - b'A')
+    }
+
+    pub const COMPAT_HWCAP_ISA_I: usize = isa(b'I');
+    pub const COMPAT_HWCAP_ISA_M: usize = isa(b'M');
+    pub const COMPAT_HWCAP_ISA_A: usize = isa(b'A');
+    pub const COMPAT_HWCAP_ISA_F: usize = isa(b'F');
+    pub const COMPAT_HWCAP_ISA_D: usize = isa(b'D');
+    pub const COMPAT_HWCAP_ISA_C: usize = isa(b'C');
+    pub const COMPAT_HWCAP_ISA_V: usize = isa(b'V');
+
+    /// RV64GC（IMAFD + C）
+    pub const HWCAP_RV64GC: usize = COMPAT_HWCAP_ISA_I
+        | COMPAT_HWCAP_ISA_M
+        | COMPAT_HWCAP_ISA_A
+        | COMPAT_HWCAP_ISA_F
+        | COMPAT_HWCAP_ISA_D
+        | COMPAT_HWCAP_ISA_C;
+}
+
+/// LoongArch 的 AT_HWCAP 位
+pub mod loongarch {
+    pub const HWCAP_LOONGARCH_CPUCFG: usize = 1 << 0;
+    pub const HWCAP_LOONGARCH_LAM: usize = 1 << 1;
+    pub const HWCAP_LOONGARCH_UAL: usize = 1 << 2;
+    pub const HWCAP_LOONGARCH_FPU: usize = 1 << 3;
+    pub const HWCAP_LOONGARCH_LSX: usize = 1 << 4;
+    pub const HWCAP_LOONGARCH_LASX: usize = 1 << 5;
+    pub const HWCAP_LOONGARCH_CRC32: usize = 1 << 6;
+    pub const HWCAP_LOONGARCH_COMPLEX: usize = 1 << 7;
+    pub const HWCAP_LOONGARCH_CRYPTO: usize = 1 << 8;
+    pub const HWCAP_LOONGARCH_LVZ: usize = 1 << 9;
+    pub const HWCAP_LOONGARCH_LBT_X86: usize = 1 << 10;
+    pub const HWCAP_LOONGARCH_LBT_ARM: usize = 1 << 11;
+    pub const HWCAP_LOONGARCH_LBT_MIPS: usize = 1 << 12;
+    pub const HWCAP_LOONGARCH_PTW: usize = 1 << 13;
+    pub const HWCAP_LOONGARCH_LSPW: usize = 1 << 14;
+}
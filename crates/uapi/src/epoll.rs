@@ -0,0 +1,77 @@
+//! epoll 相关常量与结构体
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/eventpoll.h）。
+//! 事件位同时用作 `poll` 的 `revents`（低 16 位与 POLL* 取值相同）。
+
+use bitflags::bitflags;
+
+use crate::fcntl::OpenFlags;
+
+bitflags! {
+    /// 就绪事件与 epoll 监视选项（struct epoll_event 的 `events`）
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct EpollEvents: u32 {
+        /// 可读 (EPOLLIN)
+        const IN = 0x0000_0001;
+        /// 有紧急数据可读 (EPOLLPRI)
+        const PRI = 0x0000_0002;
+        /// 可写 (EPOLLOUT)
+        const OUT = 0x0000_0004;
+        /// 出错，总是被报告 (EPOLLERR)
+        const ERR = 0x0000_0008;
+        /// 挂断，总是被报告 (EPOLLHUP)
+        const HUP = 0x0000_0010;
+        /// 文件描述符无效，仅用于 poll (POLLNVAL)
+        const NVAL = 0x0000_0020;
+        /// 普通数据可读 (EPOLLRDNORM)
+        const RDNORM = 0x0000_0040;
+        /// 优先数据可读 (EPOLLRDBAND)
+        const RDBAND = 0x0000_0080;
+        /// 普通数据可写 (EPOLLWRNORM)
+        const WRNORM = 0x0000_0100;
+        /// 优先数据可写 (EPOLLWRBAND)
+        const WRBAND = 0x0000_0200;
+        /// (EPOLLMSG)
+        const MSG = 0x0000_0400;
+        /// 对端关闭了写方向 (EPOLLRDHUP)
+        const RDHUP = 0x0000_2000;
+        /// 多个 epoll 实例监视同一文件时只唤醒其中一个 (EPOLLEXCLUSIVE)
+        const EXCLUSIVE = 1 << 28;
+        /// 阻止系统挂起 (EPOLLWAKEUP)
+        const WAKEUP = 1 << 29;
+        /// 报告一次后停用监视，直到 EPOLL_CTL_MOD 重新启用 (EPOLLONESHOT)
+        const ONESHOT = 1 << 30;
+        /// 边沿触发 (EPOLLET)
+        const ET = 1 << 31;
+    }
+}
+
+impl EpollEvents {
+    /// 监视选项位（不是就绪事件）
+    pub const INPUT_FLAGS: Self = Self::EXCLUSIVE
+        .union(Self::WAKEUP)
+        .union(Self::ONESHOT)
+        .union(Self::ET);
+}
+
+/// epoll_create1 标志：执行 exec 时关闭 (EPOLL_CLOEXEC)
+pub const EPOLL_CLOEXEC: u32 = OpenFlags::O_CLOEXEC.bits();
+
+/// 加入监视 (EPOLL_CTL_ADD)
+pub const EPOLL_CTL_ADD: i32 = 1;
+/// 移除监视 (EPOLL_CTL_DEL)
+pub const EPOLL_CTL_DEL: i32 = 2;
+/// 修改监视 (EPOLL_CTL_MOD)
+pub const EPOLL_CTL_MOD: i32 = 3;
+
+/// struct epoll_event
+///
+/// 只有 x86_64 上是 packed 布局，RISC-V 与 LoongArch 上按自然对齐，共 16 字节。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EpollEvent {
+    /// 事件掩码（[`EpollEvents`]）
+    pub events: u32,
+    /// 用户数据，原样返回
+    pub data: u64,
+}
@@ -283,3 +283,13 @@ pub const ERFKILL: i32 = 132;
 
 /// Memory page has hardware error
 pub const EHWPOISON: i32 = 133;
+
+/* 以下错误码只在内核内部使用，返回用户态前会被转换，不会出现在用户态 */
+/// 被信号打断，投递信号后按 SA_RESTART 决定重启或返回 EINTR
+pub const ERESTARTSYS: i32 = 512;
+/// 被信号打断，总是重启
+pub const ERESTARTNOINTR: i32 = 513;
+/// 被信号打断，没有用户处理函数时重启，否则返回 EINTR
+pub const ERESTARTNOHAND: i32 = 514;
+/// 被信号打断，没有用户处理函数时通过 restart_syscall 继续，否则返回 EINTR
+pub const ERESTART_RESTARTBLOCK: i32 = 516;
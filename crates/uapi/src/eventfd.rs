@@ -0,0 +1,12 @@
+//! eventfd 相关常量
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/eventfd.h）。
+
+use crate::fcntl::OpenFlags;
+
+/// 信号量语义：每次读取只减 1 (EFD_SEMAPHORE)
+pub const EFD_SEMAPHORE: u32 = 1;
+/// 执行 exec 时关闭 (EFD_CLOEXEC)
+pub const EFD_CLOEXEC: u32 = OpenFlags::O_CLOEXEC.bits();
+/// 非阻塞读写 (EFD_NONBLOCK)
+pub const EFD_NONBLOCK: u32 = OpenFlags::O_NONBLOCK.bits();
@@ -57,6 +57,16 @@ pub enum FcntlCmd {
 
     /// 获取管道大小 (F_GETPIPE_SZ)
     GetPipeSz = 1032,
+
+    // === 打开文件描述锁 (OFD locks) ===
+    /// 获取 OFD 锁信息 (F_OFD_GETLK)
+    OfdGetLk = 36,
+
+    /// 设置 OFD 锁（非阻塞）(F_OFD_SETLK)
+    OfdSetLk = 37,
+
+    /// 设置 OFD 锁（阻塞）(F_OFD_SETLKW)
+    OfdSetLkW = 38,
 }
 
 impl FcntlCmd {
@@ -79,6 +89,9 @@ impl FcntlCmd {
             9 => Some(Self::GetOwn),
             10 => Some(Self::SetSig),
             11 => Some(Self::GetSig),
+            36 => Some(Self::OfdGetLk),
+            37 => Some(Self::OfdSetLk),
+            38 => Some(Self::OfdSetLkW),
             1030 => Some(Self::DupFdCloexec),
             1031 => Some(Self::SetPipeSz),
             1032 => Some(Self::GetPipeSz),
@@ -173,7 +186,7 @@ pub struct Flock {
     /// 锁的起始偏移量
     pub l_start: i64,
 
-    /// 锁的长度（0 表示到文件末尾）
+    /// 锁的长度（0 表示到文件末尾，负数表示起始偏移之前的区间）
     pub l_len: i64,
 
     /// 持有锁的进程 PID（仅用于 F_GETLK）
@@ -217,14 +230,18 @@ impl Flock {
             return Err(());
         }
 
-        let start = start as usize;
-        let len = if self.l_len == 0 {
+        let (start, len) = if self.l_len == 0 {
             // 0 表示锁定到文件末尾
-            usize::MAX - start
+            (start as usize, usize::MAX - start as usize)
         } else if self.l_len > 0 {
-            self.l_len as usize
+            (start as usize, self.l_len as usize)
         } else {
-            return Err(());
+            // 负数表示 [start + l_len, start)
+            let begin = start.checked_add(self.l_len).ok_or(())?;
+            if begin < 0 {
+                return Err(());
+            }
+            (begin as usize, self.l_len.unsigned_abs() as usize)
         };
 
         Ok((start, len))
@@ -256,6 +273,9 @@ bitflags! {
         /// 与 O_CREAT 配合，文件必须不存在 (O_EXCL)
         const O_EXCL      = 0o200;
 
+        /// 打开终端时不使其成为控制终端 (O_NOCTTY)
+        const O_NOCTTY    = 0o400;
+
         /// 截断文件到 0 (O_TRUNC)
         const O_TRUNC     = 0o1000;
 
@@ -273,6 +293,12 @@ bitflags! {
 
         /// 大文件 (O_LARGEFILE) (空操作)
         const O_LARGEFILE = 0o100000;
+
+        /// O_TMPFILE 的内部位，必须与 O_DIRECTORY 一起出现 (__O_TMPFILE)
+        const __O_TMPFILE = 0o20000000;
+
+        /// 在指定目录中创建未链接的匿名普通文件 (O_TMPFILE)
+        const O_TMPFILE   = Self::__O_TMPFILE.bits() | Self::O_DIRECTORY.bits();
     }
 }
 
@@ -290,6 +316,52 @@ impl OpenFlags {
     }
 }
 
+/// openat2 的参数结构体（对应 Linux struct open_how）
+///
+/// 参考：include/uapi/linux/openat2.h
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpenHow {
+    /// O_* 打开标志
+    pub flags: u64,
+    /// O_CREAT / O_TMPFILE 时的文件权限，其余情况必须为 0
+    pub mode: u64,
+    /// RESOLVE_* 路径解析限制
+    pub resolve: u64,
+}
+
+// 结构体大小版本常量 (用于兼容性检查)
+pub const OPEN_HOW_SIZE_VER0: usize = 24;
+pub const OPEN_HOW_SIZE_LATEST: usize = OPEN_HOW_SIZE_VER0;
+
+const _: () = assert!(core::mem::size_of::<OpenHow>() == OPEN_HOW_SIZE_LATEST);
+
+bitflags! {
+    /// openat2 路径解析限制（open_how.resolve）
+    ///
+    /// 参考：include/uapi/linux/openat2.h
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ResolveFlags: u64 {
+        /// 不允许跨越挂载点（含绑定挂载）(RESOLVE_NO_XDEV)
+        const NO_XDEV       = 0x01;
+
+        /// 不允许跟随 procfs 风格的"魔法链接" (RESOLVE_NO_MAGICLINKS)
+        const NO_MAGICLINKS = 0x02;
+
+        /// 不允许跟随任何符号链接，隐含 NO_MAGICLINKS (RESOLVE_NO_SYMLINKS)
+        const NO_SYMLINKS   = 0x04;
+
+        /// 解析过程不得离开 dirfd 之下 (RESOLVE_BENEATH)
+        const BENEATH       = 0x08;
+
+        /// 将 dirfd 视为根目录进行解析，类似 chroot (RESOLVE_IN_ROOT)
+        const IN_ROOT       = 0x10;
+
+        /// 只使用已缓存的目录项，否则返回 EAGAIN (RESOLVE_CACHED)
+        const CACHED        = 0x20;
+    }
+}
+
 /// 文件偏移量设置模式
 ///
 /// 用于 lseek() 系统调用
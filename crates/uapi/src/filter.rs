@@ -0,0 +1,146 @@
+//! 经典 BPF（cBPF）程序定义
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/filter.h 与 linux/bpf_common.h）。
+//! seccomp 过滤器与 socket 过滤器（SO_ATTACH_FILTER）共用这些定义。
+
+/// 一条 cBPF 指令（struct sock_filter）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SockFilter {
+    /// 操作码
+    pub code: u16,
+    /// 条件成立时的跳转偏移
+    pub jt: u8,
+    /// 条件不成立时的跳转偏移
+    pub jf: u8,
+    /// 通用字段（立即数、偏移等）
+    pub k: u32,
+}
+
+impl SockFilter {
+    /// 构造一条无跳转的指令（BPF_STMT）
+    pub const fn stmt(code: u16, k: u32) -> Self {
+        Self {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    /// 构造一条条件跳转指令（BPF_JUMP）
+    pub const fn jump(code: u16, k: u32, jt: u8, jf: u8) -> Self {
+        Self { code, jt, jf, k }
+    }
+}
+
+/// cBPF 程序描述（struct sock_fprog）
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SockFprog {
+    /// 指令条数
+    pub len: u16,
+    /// 指向指令数组的用户空间指针
+    pub filter: *const SockFilter,
+}
+
+/// 单个程序的最大指令数
+pub const BPF_MAXINSNS: usize = 4096;
+
+/// 暂存存储器的字数
+pub const BPF_MEMWORDS: usize = 16;
+
+// 指令类别
+
+pub const BPF_LD: u16 = 0x00;
+pub const BPF_LDX: u16 = 0x01;
+pub const BPF_ST: u16 = 0x02;
+pub const BPF_STX: u16 = 0x03;
+pub const BPF_ALU: u16 = 0x04;
+pub const BPF_JMP: u16 = 0x05;
+pub const BPF_RET: u16 = 0x06;
+pub const BPF_MISC: u16 = 0x07;
+
+// 载入宽度
+
+pub const BPF_W: u16 = 0x00;
+pub const BPF_H: u16 = 0x08;
+pub const BPF_B: u16 = 0x10;
+
+// 寻址模式
+
+pub const BPF_IMM: u16 = 0x00;
+pub const BPF_ABS: u16 = 0x20;
+pub const BPF_IND: u16 = 0x40;
+pub const BPF_MEM: u16 = 0x60;
+pub const BPF_LEN: u16 = 0x80;
+pub const BPF_MSH: u16 = 0xa0;
+
+// 算术 / 跳转操作
+
+pub const BPF_ADD: u16 = 0x00;
+pub const BPF_SUB: u16 = 0x10;
+pub const BPF_MUL: u16 = 0x20;
+pub const BPF_DIV: u16 = 0x30;
+pub const BPF_OR: u16 = 0x40;
+pub const BPF_AND: u16 = 0x50;
+pub const BPF_LSH: u16 = 0x60;
+pub const BPF_RSH: u16 = 0x70;
+pub const BPF_NEG: u16 = 0x80;
+pub const BPF_MOD: u16 = 0x90;
+pub const BPF_XOR: u16 = 0xa0;
+
+pub const BPF_JA: u16 = 0x00;
+pub const BPF_JEQ: u16 = 0x10;
+pub const BPF_JGT: u16 = 0x20;
+pub const BPF_JGE: u16 = 0x30;
+pub const BPF_JSET: u16 = 0x40;
+
+// 操作数来源
+
+pub const BPF_K: u16 = 0x00;
+pub const BPF_X: u16 = 0x08;
+
+// BPF_RET 的返回值来源（BPF_K / BPF_X 或累加器）
+
+pub const BPF_A: u16 = 0x10;
+
+// BPF_MISC 操作
+
+pub const BPF_TAX: u16 = 0x00;
+pub const BPF_TXA: u16 = 0x80;
+
+/// 取指令类别
+pub const fn bpf_class(code: u16) -> u16 {
+    code & 0x07
+}
+
+/// 取载入宽度
+pub const fn bpf_size(code: u16) -> u16 {
+    code & 0x18
+}
+
+/// 取寻址模式
+pub const fn bpf_mode(code: u16) -> u16 {
+    code & 0xe0
+}
+
+/// 取算术 / 跳转操作
+pub const fn bpf_op(code: u16) -> u16 {
+    code & 0xf0
+}
+
+/// 取操作数来源
+pub const fn bpf_src(code: u16) -> u16 {
+    code & 0x08
+}
+
+/// 取 BPF_RET 的返回值来源
+pub const fn bpf_rval(code: u16) -> u16 {
+    code & 0x18
+}
+
+/// 取 BPF_MISC 操作
+pub const fn bpf_miscop(code: u16) -> u16 {
+    code & 0xf8
+}
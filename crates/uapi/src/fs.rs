@@ -12,6 +12,10 @@ pub enum FileSystemType {
     /// 魔数来自：include/uapi/linux/magic.h
     Ext4 = 0xEF53,
 
+    /// overlayfs
+    /// 魔数来自：include/uapi/linux/magic.h
+    Overlay = 0x794C7630,
+
     /// 未知或不支持的文件系统
     Unknown = 0,
 }
@@ -29,6 +33,7 @@ impl FileSystemType {
     pub fn from_str(fs_type: &str) -> Self {
         match fs_type {
             "ext4" | "ext3" | "ext2" => Self::Ext4,
+            "overlay" => Self::Overlay,
             _ => Self::Unknown,
         }
     }
@@ -79,7 +84,7 @@ bitflags! {
     /// mount 系统调用标志位（与 Linux ABI 完全一致）
     ///
     /// 参考：include/uapi/linux/mount.h
-    /// 这些标志在当前实现中会被忽略，但保留以保持 ABI 兼容性
+    /// 目前只处理只读等挂载点标志与 MS_REMOUNT/MS_BIND/MS_MOVE，其余标志被忽略
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct SysMountFlags: u64 {
         /// 只读挂载
@@ -124,6 +129,18 @@ bitflags! {
         /// 递归操作
         const MS_REC         = 16384;
 
+        /// 不可绑定挂载
+        const MS_UNBINDABLE  = 1 << 17;
+
+        /// 私有传播
+        const MS_PRIVATE     = 1 << 18;
+
+        /// 从属传播
+        const MS_SLAVE       = 1 << 19;
+
+        /// 共享传播
+        const MS_SHARED      = 1 << 20;
+
         /// 相对访问时间
         const MS_RELATIME    = 2097152;
 
@@ -199,6 +216,9 @@ bitflags! {
         /// 移除目录（AT_REMOVEDIR，用于 unlinkat）
         const REMOVEDIR = 0x200;
 
+        /// 跟随符号链接（AT_SYMLINK_FOLLOW，用于 linkat）
+        const SYMLINK_FOLLOW = 0x400;
+
         /// 路径为空时操作 dirfd 本身（AT_EMPTY_PATH）
         const EMPTY_PATH = 0x1000;
 
@@ -481,3 +501,174 @@ pub const STATX_INO: u32 = 0x0000_0100;
 pub const STATX_SIZE: u32 = 0x0000_0200;
 pub const STATX_BLOCKS: u32 = 0x0000_0400;
 pub const STATX_BASIC_STATS: u32 = 0x0000_07ff;
+pub const STATX_BTIME: u32 = 0x0000_0800;
+/// 已废弃，仅为兼容保留（等于 BASIC_STATS | BTIME）
+pub const STATX_ALL: u32 = 0x0000_0fff;
+pub const STATX_MNT_ID: u32 = 0x0000_1000;
+pub const STATX_DIOALIGN: u32 = 0x0000_2000;
+pub const STATX_MNT_ID_UNIQUE: u32 = 0x0000_4000;
+pub const STATX_SUBVOL: u32 = 0x0000_8000;
+pub const STATX_WRITE_ATOMIC: u32 = 0x0001_0000;
+pub const STATX_DIO_READ_ALIGN: u32 = 0x0002_0000;
+/// 保留位，用户传入时返回 EINVAL
+pub const STATX__RESERVED: u32 = 0x8000_0000;
+
+// stx_attributes 位（参考 include/uapi/linux/stat.h）
+pub const STATX_ATTR_COMPRESSED: u64 = 0x0000_0004;
+pub const STATX_ATTR_IMMUTABLE: u64 = 0x0000_0010;
+pub const STATX_ATTR_APPEND: u64 = 0x0000_0020;
+pub const STATX_ATTR_NODUMP: u64 = 0x0000_0040;
+pub const STATX_ATTR_ENCRYPTED: u64 = 0x0000_0800;
+pub const STATX_ATTR_AUTOMOUNT: u64 = 0x0000_1000;
+pub const STATX_ATTR_MOUNT_ROOT: u64 = 0x0000_2000;
+pub const STATX_ATTR_VERITY: u64 = 0x0010_0000;
+pub const STATX_ATTR_DAX: u64 = 0x0020_0000;
+pub const STATX_ATTR_WRITE_ATOMIC: u64 = 0x0040_0000;
+
+// statx 的同步语义（flags 中的 AT_STATX_SYNC_TYPE 字段）
+pub const AT_STATX_SYNC_TYPE: u32 = 0x6000;
+/// 与 stat() 相同
+pub const AT_STATX_SYNC_AS_STAT: u32 = 0x0000;
+/// 强制与远端同步属性
+pub const AT_STATX_FORCE_SYNC: u32 = 0x2000;
+/// 不同步，返回缓存的属性
+pub const AT_STATX_DONT_SYNC: u32 = 0x4000;
+
+/// 对整个子树递归生效（open_tree / mount_setattr）
+pub const AT_RECURSIVE: u32 = 0x8000;
+
+// ========== 新式挂载 API（fsopen / fsconfig / fsmount / move_mount / mount_setattr）==========
+//
+// 参考：include/uapi/linux/mount.h
+
+/// fsopen 标志：返回的 fd 设置 close-on-exec
+pub const FSOPEN_CLOEXEC: u32 = 0x0000_0001;
+
+// fspick 标志
+pub const FSPICK_CLOEXEC: u32 = 0x0000_0001;
+pub const FSPICK_SYMLINK_NOFOLLOW: u32 = 0x0000_0002;
+pub const FSPICK_NO_AUTOMOUNT: u32 = 0x0000_0004;
+pub const FSPICK_EMPTY_PATH: u32 = 0x0000_0008;
+
+/// fsmount 标志：返回的 fd 设置 close-on-exec
+pub const FSMOUNT_CLOEXEC: u32 = 0x0000_0001;
+
+// open_tree 标志
+/// 克隆挂载树而不是引用原挂载
+pub const OPEN_TREE_CLONE: u32 = 0x0000_0001;
+/// 返回的 fd 设置 close-on-exec（与 O_CLOEXEC 相同）
+pub const OPEN_TREE_CLOEXEC: u32 = 0o2000000;
+
+// move_mount 标志
+pub const MOVE_MOUNT_F_SYMLINKS: u32 = 0x0000_0001;
+pub const MOVE_MOUNT_F_AUTOMOUNTS: u32 = 0x0000_0002;
+pub const MOVE_MOUNT_F_EMPTY_PATH: u32 = 0x0000_0004;
+pub const MOVE_MOUNT_T_SYMLINKS: u32 = 0x0000_0010;
+pub const MOVE_MOUNT_T_AUTOMOUNTS: u32 = 0x0000_0020;
+pub const MOVE_MOUNT_T_EMPTY_PATH: u32 = 0x0000_0040;
+pub const MOVE_MOUNT_SET_GROUP: u32 = 0x0000_0100;
+pub const MOVE_MOUNT_BENEATH: u32 = 0x0000_0200;
+
+/// fsconfig 命令
+///
+/// 参考：include/uapi/linux/mount.h enum fsconfig_command
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsconfigCmd {
+    /// 设置布尔参数（key 非空，value / aux 为空）
+    SetFlag = 0,
+    /// 设置字符串参数（value 指向字符串）
+    SetString = 1,
+    /// 设置二进制参数（value 指向数据，aux 为长度）
+    SetBinary = 2,
+    /// 设置路径参数（aux 为 dirfd）
+    SetPath = 3,
+    /// 设置可为空的路径参数
+    SetPathEmpty = 4,
+    /// 设置 fd 参数（aux 为 fd）
+    SetFd = 5,
+    /// 创建超级块
+    CmdCreate = 6,
+    /// 重新配置已有超级块
+    CmdReconfigure = 7,
+    /// 创建新超级块，不复用已有的
+    CmdCreateExcl = 8,
+}
+
+impl FsconfigCmd {
+    /// 从系统调用参数解析
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::SetFlag),
+            1 => Some(Self::SetString),
+            2 => Some(Self::SetBinary),
+            3 => Some(Self::SetPath),
+            4 => Some(Self::SetPathEmpty),
+            5 => Some(Self::SetFd),
+            6 => Some(Self::CmdCreate),
+            7 => Some(Self::CmdReconfigure),
+            8 => Some(Self::CmdCreateExcl),
+            _ => None,
+        }
+    }
+}
+
+bitflags! {
+    /// 挂载属性（fsmount 的 attr_flags 与 mount_attr 的 attr_set / attr_clr）
+    ///
+    /// 参考：include/uapi/linux/mount.h
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MountAttrFlags: u64 {
+        /// 只读挂载 (MOUNT_ATTR_RDONLY)
+        const RDONLY      = 0x0000_0001;
+
+        /// 忽略 suid/sgid 位 (MOUNT_ATTR_NOSUID)
+        const NOSUID      = 0x0000_0002;
+
+        /// 禁止访问设备文件 (MOUNT_ATTR_NODEV)
+        const NODEV       = 0x0000_0004;
+
+        /// 禁止执行程序 (MOUNT_ATTR_NOEXEC)
+        const NOEXEC      = 0x0000_0008;
+
+        /// 访问时间更新策略掩码 (MOUNT_ATTR__ATIME)；RELATIME 为 0
+        const ATIME       = 0x0000_0070;
+
+        /// 不更新访问时间 (MOUNT_ATTR_NOATIME)
+        const NOATIME     = 0x0000_0010;
+
+        /// 每次访问都更新访问时间 (MOUNT_ATTR_STRICTATIME)
+        const STRICTATIME = 0x0000_0020;
+
+        /// 不更新目录访问时间 (MOUNT_ATTR_NODIRATIME)
+        const NODIRATIME  = 0x0000_0080;
+
+        /// ID 映射挂载，userns_fd 指定映射 (MOUNT_ATTR_IDMAP)
+        const IDMAP       = 0x0010_0000;
+
+        /// 不跟随符号链接 (MOUNT_ATTR_NOSYMFOLLOW)
+        const NOSYMFOLLOW = 0x0020_0000;
+    }
+}
+
+/// 相对访问时间（MOUNT_ATTR__ATIME 字段取 0）
+pub const MOUNT_ATTR_RELATIME: u64 = 0;
+
+/// mount_setattr 的参数结构体（对应 Linux struct mount_attr）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MountAttr {
+    /// 要设置的 MOUNT_ATTR_* 位
+    pub attr_set: u64,
+    /// 要清除的 MOUNT_ATTR_* 位
+    pub attr_clr: u64,
+    /// 传播类型（MS_SHARED / MS_SLAVE / MS_PRIVATE / MS_UNBINDABLE）
+    pub propagation: u64,
+    /// MOUNT_ATTR_IDMAP 使用的用户命名空间 fd
+    pub userns_fd: u64,
+}
+
+// 结构体大小版本常量 (用于兼容性检查)
+pub const MOUNT_ATTR_SIZE_VER0: usize = 32;
+
+const _: () = assert!(core::mem::size_of::<MountAttr>() == MOUNT_ATTR_SIZE_VER0);
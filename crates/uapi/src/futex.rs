@@ -53,6 +53,30 @@ pub const FUTEX_PRIVATE: FutexOp = 128; // 0x80
 /// （默认为 CLOCK_MONOTONIC）。
 pub const FUTEX_CLOCK_REALTIME: FutexOp = 256; // 0x100
 
+// --- Robust Futex ---
+
+/// futex 字中表示存在等待者的位
+pub const FUTEX_WAITERS: u32 = 0x8000_0000;
+
+/// futex 字中表示持有者已退出的位，由内核在处理健壮列表时设置
+pub const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+
+/// futex 字中持有者 TID 所占的位
+pub const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
+
+/// 内核处理健壮列表时最多遍历的节点数，防止用户构造的环形链表
+pub const ROBUST_LIST_LIMIT: usize = 2048;
+
+/// 健壮列表节点（struct robust_list）
+///
+/// 嵌在用户态的锁结构中，`next` 的最低位表示该锁是 PI futex。
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RobustList {
+    /// 下一个节点，链表以指回 [`RobustListHead`] 自身结束
+    pub next: *mut RobustList,
+}
+
 /// 健壮列表头部结构体（struct robust_list_head）
 ///
 /// 这个结构体是用户空间维护的，用于告诉内核当前线程持有健壮 futex 锁的列表信息。
@@ -70,6 +94,7 @@ pub const FUTEX_CLOCK_REALTIME: FutexOp = 256; // 0x100
 /// }
 /// ```
 #[repr(C)]
+#[derive(Debug, Clone, Copy)]
 pub struct RobustListHead {
     /// head: volatile void *volatile head;
     /// 指向当前线程拥有的第一个健壮 futex 锁。
@@ -85,3 +110,6 @@ pub struct RobustListHead {
     /// 指向一个正在等待被释放或修复的 futex 锁。
     pub pending: *mut c_void,
 }
+
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(core::mem::size_of::<RobustListHead>() == 24);
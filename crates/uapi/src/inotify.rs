@@ -0,0 +1,82 @@
+//! inotify 相关常量与结构体
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/inotify.h）。
+
+use bitflags::bitflags;
+
+use crate::fcntl::OpenFlags;
+
+bitflags! {
+    /// 监视掩码与事件掩码（inotify_add_watch / struct inotify_event 的 `mask`）
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct InotifyMask: u32 {
+        /// 文件被读取 (IN_ACCESS)
+        const ACCESS = 0x0000_0001;
+        /// 文件被写入或截断 (IN_MODIFY)
+        const MODIFY = 0x0000_0002;
+        /// 元数据改变 (IN_ATTRIB)
+        const ATTRIB = 0x0000_0004;
+        /// 以可写方式打开的文件被关闭 (IN_CLOSE_WRITE)
+        const CLOSE_WRITE = 0x0000_0008;
+        /// 以只读方式打开的文件被关闭 (IN_CLOSE_NOWRITE)
+        const CLOSE_NOWRITE = 0x0000_0010;
+        /// 文件被打开 (IN_OPEN)
+        const OPEN = 0x0000_0020;
+        /// 文件被移出被监视的目录 (IN_MOVED_FROM)
+        const MOVED_FROM = 0x0000_0040;
+        /// 文件被移入被监视的目录 (IN_MOVED_TO)
+        const MOVED_TO = 0x0000_0080;
+        /// 在被监视的目录中创建了文件 (IN_CREATE)
+        const CREATE = 0x0000_0100;
+        /// 被监视的目录中的文件被删除 (IN_DELETE)
+        const DELETE = 0x0000_0200;
+        /// 被监视的文件本身被删除 (IN_DELETE_SELF)
+        const DELETE_SELF = 0x0000_0400;
+        /// 被监视的文件本身被移动 (IN_MOVE_SELF)
+        const MOVE_SELF = 0x0000_0800;
+
+        /// 所在文件系统被卸载 (IN_UNMOUNT)
+        const UNMOUNT = 0x0000_2000;
+        /// 事件队列溢出 (IN_Q_OVERFLOW)
+        const Q_OVERFLOW = 0x0000_4000;
+        /// 监视被移除 (IN_IGNORED)
+        const IGNORED = 0x0000_8000;
+
+        /// 只监视目录 (IN_ONLYDIR)
+        const ONLYDIR = 0x0100_0000;
+        /// 不跟随符号链接 (IN_DONT_FOLLOW)
+        const DONT_FOLLOW = 0x0200_0000;
+        /// 不报告已从目录删除的子项的事件 (IN_EXCL_UNLINK)
+        const EXCL_UNLINK = 0x0400_0000;
+        /// 只在监视不存在时创建，否则返回 EEXIST (IN_MASK_CREATE)
+        const MASK_CREATE = 0x1000_0000;
+        /// 与已有监视的掩码合并而不是替换 (IN_MASK_ADD)
+        const MASK_ADD = 0x2000_0000;
+        /// 事件的对象是目录 (IN_ISDIR)
+        const ISDIR = 0x4000_0000;
+        /// 报告一次事件后移除监视 (IN_ONESHOT)
+        const ONESHOT = 0x8000_0000;
+
+        /// 所有可监视的事件 (IN_ALL_EVENTS)
+        const ALL_EVENTS = 0x0000_0fff;
+    }
+}
+
+/// inotify_init1 的标志：设置 close-on-exec (IN_CLOEXEC)
+pub const IN_CLOEXEC: u32 = OpenFlags::O_CLOEXEC.bits();
+/// inotify_init1 的标志：非阻塞读取 (IN_NONBLOCK)
+pub const IN_NONBLOCK: u32 = OpenFlags::O_NONBLOCK.bits();
+
+/// `read` 返回的事件头部，其后紧跟 `len` 字节以 NUL 结尾并补齐的文件名
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InotifyEvent {
+    /// 监视描述符
+    pub wd: i32,
+    /// 事件掩码
+    pub mask: u32,
+    /// 关联同一次重命名的 IN_MOVED_FROM 与 IN_MOVED_TO
+    pub cookie: u32,
+    /// 文件名（含补齐）的长度
+    pub len: u32,
+}
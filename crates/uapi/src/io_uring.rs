@@ -0,0 +1,204 @@
+//! io_uring 相关常量与结构体
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/io_uring.h）。
+
+/// 提交队列项（struct io_uring_sqe）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IoUringSqe {
+    /// 操作码（`IORING_OP_*`）
+    pub opcode: u8,
+    /// `IOSQE_*` 标志
+    pub flags: u8,
+    /// I/O 优先级
+    pub ioprio: u16,
+    /// 文件描述符，`IOSQE_FIXED_FILE` 时为已登记文件的下标
+    pub fd: i32,
+    /// 文件偏移量，-1 表示使用文件当前偏移量；ACCEPT 时为 `addrlen` 指针（addr2）
+    pub off: u64,
+    /// 缓冲区或 iovec 数组地址；ACCEPT 时为 `sockaddr` 指针
+    pub addr: u64,
+    /// 缓冲区长度或 iovec 个数
+    pub len: u32,
+    /// 操作相关的标志（rw_flags / fsync_flags / accept_flags 等）
+    pub op_flags: u32,
+    /// 原样带回完成队列项的用户数据
+    pub user_data: u64,
+    /// `READ_FIXED`/`WRITE_FIXED` 使用的已登记缓冲区下标
+    pub buf_index: u16,
+    /// 执行操作使用的凭据
+    pub personality: u16,
+    /// splice 的输入 fd 或直接登记的文件下标
+    pub splice_fd_in: i32,
+    /// 扩展参数
+    pub addr3: u64,
+    /// 保留
+    pub __pad2: [u64; 1],
+}
+
+/// 完成队列项（struct io_uring_cqe）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IoUringCqe {
+    /// 提交时的 `user_data`
+    pub user_data: u64,
+    /// 结果：成功时为操作的返回值，失败时为负的错误码
+    pub res: i32,
+    /// `IORING_CQE_F_*` 标志
+    pub flags: u32,
+}
+
+/// 提交队列环中各字段的偏移量（struct io_sqring_offsets）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IoSqringOffsets {
+    /// 消费者位置（内核写）
+    pub head: u32,
+    /// 生产者位置（用户写）
+    pub tail: u32,
+    /// 下标掩码
+    pub ring_mask: u32,
+    /// 环的容量
+    pub ring_entries: u32,
+    /// `IORING_SQ_*` 标志
+    pub flags: u32,
+    /// 因下标无效被丢弃的提交数
+    pub dropped: u32,
+    /// 下标数组（指向 SQE 数组）
+    pub array: u32,
+    /// 保留
+    pub resv1: u32,
+    /// 用户提供环内存时的地址（未使用）
+    pub user_addr: u64,
+}
+
+/// 完成队列环中各字段的偏移量（struct io_cqring_offsets）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IoCqringOffsets {
+    /// 消费者位置（用户写）
+    pub head: u32,
+    /// 生产者位置（内核写）
+    pub tail: u32,
+    /// 下标掩码
+    pub ring_mask: u32,
+    /// 环的容量
+    pub ring_entries: u32,
+    /// 因环满而溢出的完成数
+    pub overflow: u32,
+    /// 完成队列项数组
+    pub cqes: u32,
+    /// `IORING_CQ_*` 标志
+    pub flags: u32,
+    /// 保留
+    pub resv1: u32,
+    /// 用户提供环内存时的地址（未使用）
+    pub user_addr: u64,
+}
+
+/// io_uring_setup 的参数（struct io_uring_params）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IoUringParams {
+    /// 提交队列容量（内核写回实际值）
+    pub sq_entries: u32,
+    /// 完成队列容量（`IORING_SETUP_CQSIZE` 时由用户指定）
+    pub cq_entries: u32,
+    /// `IORING_SETUP_*` 标志
+    pub flags: u32,
+    /// SQPOLL 线程绑定的 CPU
+    pub sq_thread_cpu: u32,
+    /// SQPOLL 线程空闲多久后睡眠（毫秒）
+    pub sq_thread_idle: u32,
+    /// 内核支持的 `IORING_FEAT_*`（内核写）
+    pub features: u32,
+    /// 共享工作队列的 io_uring fd
+    pub wq_fd: u32,
+    /// 保留
+    pub resv: [u32; 3],
+    /// 提交队列环的布局（内核写）
+    pub sq_off: IoSqringOffsets,
+    /// 完成队列环的布局（内核写）
+    pub cq_off: IoCqringOffsets,
+}
+
+/// 空操作 (IORING_OP_NOP)
+pub const IORING_OP_NOP: u8 = 0;
+/// 向量读 (IORING_OP_READV)
+pub const IORING_OP_READV: u8 = 1;
+/// 向量写 (IORING_OP_WRITEV)
+pub const IORING_OP_WRITEV: u8 = 2;
+/// 同步文件 (IORING_OP_FSYNC)
+pub const IORING_OP_FSYNC: u8 = 3;
+/// 读到已登记的缓冲区 (IORING_OP_READ_FIXED)
+pub const IORING_OP_READ_FIXED: u8 = 4;
+/// 从已登记的缓冲区写 (IORING_OP_WRITE_FIXED)
+pub const IORING_OP_WRITE_FIXED: u8 = 5;
+/// 接受连接 (IORING_OP_ACCEPT)
+pub const IORING_OP_ACCEPT: u8 = 13;
+/// 读 (IORING_OP_READ)
+pub const IORING_OP_READ: u8 = 22;
+/// 写 (IORING_OP_WRITE)
+pub const IORING_OP_WRITE: u8 = 23;
+
+/// `fd` 是已登记文件的下标 (IOSQE_FIXED_FILE)
+pub const IOSQE_FIXED_FILE: u8 = 1 << 0;
+/// 等之前的提交全部完成后再执行 (IOSQE_IO_DRAIN)
+pub const IOSQE_IO_DRAIN: u8 = 1 << 1;
+/// 与下一项链接 (IOSQE_IO_LINK)
+pub const IOSQE_IO_LINK: u8 = 1 << 2;
+/// 与下一项链接，出错也不断开 (IOSQE_IO_HARDLINK)
+pub const IOSQE_IO_HARDLINK: u8 = 1 << 3;
+/// 总是异步执行 (IOSQE_ASYNC)
+pub const IOSQE_ASYNC: u8 = 1 << 4;
+
+/// FSYNC 只同步数据 (IORING_FSYNC_DATASYNC)
+pub const IORING_FSYNC_DATASYNC: u32 = 1 << 0;
+
+/// 轮询式 I/O (IORING_SETUP_IOPOLL)
+pub const IORING_SETUP_IOPOLL: u32 = 1 << 0;
+/// 内核线程轮询提交队列 (IORING_SETUP_SQPOLL)
+pub const IORING_SETUP_SQPOLL: u32 = 1 << 1;
+/// SQPOLL 线程绑定 CPU (IORING_SETUP_SQ_AFF)
+pub const IORING_SETUP_SQ_AFF: u32 = 1 << 2;
+/// 由 `cq_entries` 指定完成队列容量 (IORING_SETUP_CQSIZE)
+pub const IORING_SETUP_CQSIZE: u32 = 1 << 3;
+/// 容量超过上限时截断而不是报错 (IORING_SETUP_CLAMP)
+pub const IORING_SETUP_CLAMP: u32 = 1 << 4;
+
+/// 等待完成 (IORING_ENTER_GETEVENTS)
+pub const IORING_ENTER_GETEVENTS: u32 = 1 << 0;
+/// 唤醒 SQPOLL 线程 (IORING_ENTER_SQ_WAKEUP)
+pub const IORING_ENTER_SQ_WAKEUP: u32 = 1 << 1;
+
+/// SQPOLL 线程已睡眠 (IORING_SQ_NEED_WAKEUP)
+pub const IORING_SQ_NEED_WAKEUP: u32 = 1 << 0;
+/// 完成队列溢出，需要 io_uring_enter 刷新 (IORING_SQ_CQ_OVERFLOW)
+pub const IORING_SQ_CQ_OVERFLOW: u32 = 1 << 1;
+/// 有完成需要在提交者上下文中处理，需要 io_uring_enter (IORING_SQ_TASKRUN)
+pub const IORING_SQ_TASKRUN: u32 = 1 << 2;
+
+/// 提交队列环与完成队列环共用一次映射 (IORING_FEAT_SINGLE_MMAP)
+pub const IORING_FEAT_SINGLE_MMAP: u32 = 1 << 0;
+/// 完成队列满时不丢弃完成 (IORING_FEAT_NODROP)
+pub const IORING_FEAT_NODROP: u32 = 1 << 1;
+/// 提交时即读取 SQE 引用的数据 (IORING_FEAT_SUBMIT_STABLE)
+pub const IORING_FEAT_SUBMIT_STABLE: u32 = 1 << 2;
+/// 偏移量 -1 表示使用文件当前偏移量 (IORING_FEAT_RW_CUR_POS)
+pub const IORING_FEAT_RW_CUR_POS: u32 = 1 << 3;
+
+/// 映射提交队列环的 mmap 偏移量 (IORING_OFF_SQ_RING)
+pub const IORING_OFF_SQ_RING: u64 = 0;
+/// 映射完成队列环的 mmap 偏移量 (IORING_OFF_CQ_RING)
+pub const IORING_OFF_CQ_RING: u64 = 0x800_0000;
+/// 映射 SQE 数组的 mmap 偏移量 (IORING_OFF_SQES)
+pub const IORING_OFF_SQES: u64 = 0x1000_0000;
+
+/// 登记固定缓冲区 (IORING_REGISTER_BUFFERS)
+pub const IORING_REGISTER_BUFFERS: u32 = 0;
+/// 注销固定缓冲区 (IORING_UNREGISTER_BUFFERS)
+pub const IORING_UNREGISTER_BUFFERS: u32 = 1;
+/// 登记固定文件 (IORING_REGISTER_FILES)
+pub const IORING_REGISTER_FILES: u32 = 2;
+/// 注销固定文件 (IORING_UNREGISTER_FILES)
+pub const IORING_UNREGISTER_FILES: u32 = 3;
@@ -86,6 +86,15 @@ pub const FIONREAD: u32 = 0x541B;
 /// 设置/清除异步 I/O 通知（int）
 pub const FIOASYNC: u32 = 0x5452;
 
+/// 清除 close-on-exec 标志（void）
+pub const FIONCLEX: u32 = 0x5450;
+
+/// 设置 close-on-exec 标志（void）
+pub const FIOCLEX: u32 = 0x5451;
+
+/// 获取文件/目录大小（loff_t）
+pub const FIOQSIZE: u32 = 0x5460;
+
 /// 获取文件系统块大小（long）
 pub const FIGETBSZ: u32 = 2;
 
@@ -97,6 +106,63 @@ pub const TCSETS: u32 = 0x5402;
 pub const TCSETSW: u32 = 0x5403;
 pub const TCSETSF: u32 = 0x5404;
 
+/// 旧式 termio 接口（struct termio）
+pub const TCGETA: u32 = 0x5405;
+pub const TCSETA: u32 = 0x5406;
+pub const TCSETAW: u32 = 0x5407;
+pub const TCSETAF: u32 = 0x5408;
+
+/// 带任意波特率的 termios 接口（struct termios2）
+pub const TCGETS2: u32 = _IOR(b'T' as u32, 0x2A, 44);
+pub const TCSETS2: u32 = _IOW(b'T' as u32, 0x2B, 44);
+pub const TCSETSW2: u32 = _IOW(b'T' as u32, 0x2C, 44);
+pub const TCSETSF2: u32 = _IOW(b'T' as u32, 0x2D, 44);
+
+/// 锁定的 termios 位（struct termios）
+pub const TIOCGLCKTRMIOS: u32 = 0x5456;
+pub const TIOCSLCKTRMIOS: u32 = 0x5457;
+
+/// 发送 break；arg 非 0 时等价于 tcdrain（int）
+pub const TCSBRK: u32 = 0x5409;
+
+/// 发送 break，时长由 arg 指定（单位 0.1 秒，int）
+pub const TCSBRKP: u32 = 0x5425;
+
+/// 开始发送 break（void）
+pub const TIOCSBRK: u32 = 0x5427;
+
+/// 停止发送 break（void）
+pub const TIOCCBRK: u32 = 0x5428;
+
+/// 暂停/恢复输入或输出（int，取值 TCOOFF/TCOON/TCIOFF/TCION）
+pub const TCXONC: u32 = 0x540A;
+
+/// 清空输入/输出队列（int，取值 TCIFLUSH/TCOFLUSH/TCIOFLUSH）
+pub const TCFLSH: u32 = 0x540B;
+
+/// TCFLSH 参数：清空输入队列
+pub const TCIFLUSH: usize = 0;
+/// TCFLSH 参数：清空输出队列
+pub const TCOFLUSH: usize = 1;
+/// TCFLSH 参数：同时清空输入与输出队列
+pub const TCIOFLUSH: usize = 2;
+
+/// TCXONC 参数：暂停输出
+pub const TCOOFF: usize = 0;
+/// TCXONC 参数：恢复输出
+pub const TCOON: usize = 1;
+/// TCXONC 参数：向设备发送 STOP 字符
+pub const TCIOFF: usize = 2;
+/// TCXONC 参数：向设备发送 START 字符
+pub const TCION: usize = 3;
+
+/// tcsetattr 的 optional_actions：立即生效（对应 TCSETS）
+pub const TCSANOW: usize = 0;
+/// tcsetattr 的 optional_actions：输出排空后生效（对应 TCSETSW）
+pub const TCSADRAIN: usize = 1;
+/// tcsetattr 的 optional_actions：输出排空并丢弃输入后生效（对应 TCSETSF）
+pub const TCSAFLUSH: usize = 2;
+
 /// 获取终端窗口大小（struct winsize）
 pub const TIOCGWINSZ: u32 = 0x5413;
 
@@ -121,9 +187,143 @@ pub const TIOCEXCL: u32 = 0x540C;
 /// 取消独占使用（void）
 pub const TIOCNXCL: u32 = 0x540D;
 
-/// 设置控制终端（void）- busybox init 需要
+/// 设置控制终端（int，1 表示可以抢占其它会话的控制终端）- busybox init 需要
 pub const TIOCSCTTY: u32 = 0x540E;
 
+/// 放弃控制终端（void）
+pub const TIOCNOTTY: u32 = 0x5422;
+
+/// 获取终端所属会话 ID（pid_t *）
+pub const TIOCGSID: u32 = 0x5429;
+
+/// 模拟终端输入一个字符（char *）
+pub const TIOCSTI: u32 = 0x5412;
+
+/// 获取调制解调器状态位（int *，TIOCM_*）
+pub const TIOCMGET: u32 = 0x5415;
+/// 置位调制解调器状态位（int *）
+pub const TIOCMBIS: u32 = 0x5416;
+/// 清除调制解调器状态位（int *）
+pub const TIOCMBIC: u32 = 0x5417;
+/// 设置调制解调器状态位（int *）
+pub const TIOCMSET: u32 = 0x5418;
+
+/// 获取/设置 CLOCAL 软件载波标志（int *）
+pub const TIOCGSOFTCAR: u32 = 0x5419;
+pub const TIOCSSOFTCAR: u32 = 0x541A;
+
+/// Linux 控制台专用请求（char *）
+pub const TIOCLINUX: u32 = 0x541C;
+
+/// 将 /dev/console 的输出重定向到该终端（void）
+pub const TIOCCONS: u32 = 0x541D;
+
+/// 获取/设置串口配置（struct serial_struct）
+pub const TIOCGSERIAL: u32 = 0x541E;
+pub const TIOCSSERIAL: u32 = 0x541F;
+
+/// 启用/关闭 PTY 包模式（int *）
+pub const TIOCPKT: u32 = 0x5420;
+
+/// 设置/获取行规程（int *，N_*）
+pub const TIOCSETD: u32 = 0x5423;
+pub const TIOCGETD: u32 = 0x5424;
+
+/// 获取/设置 RS-485 配置（struct serial_rs485）
+pub const TIOCGRS485: u32 = 0x542E;
+pub const TIOCSRS485: u32 = 0x542F;
+
+/// 获取终端的真实设备号（unsigned int *）
+pub const TIOCGDEV: u32 = _IOR(b'T' as u32, 0x32, 4);
+
+/// 向 PTY 从设备前台进程组发送信号（int）
+pub const TIOCSIG: u32 = _IOW(b'T' as u32, 0x36, 4);
+
+/// 挂断终端（void）
+pub const TIOCVHANGUP: u32 = 0x5437;
+
+/// 获取包模式状态（int *）
+pub const TIOCGPKT: u32 = _IOR(b'T' as u32, 0x38, 4);
+
+/// 获取独占模式状态（int *）
+pub const TIOCGEXCL: u32 = _IOR(b'T' as u32, 0x40, 4);
+
+/// 由 PTY 主设备打开对应从设备（int，打开标志）
+pub const TIOCGPTPEER: u32 = _IO(b'T' as u32, 0x41);
+
+/// 串口扩展请求
+pub const TIOCSERCONFIG: u32 = 0x5453;
+pub const TIOCSERGWILD: u32 = 0x5454;
+pub const TIOCSERSWILD: u32 = 0x5455;
+pub const TIOCSERGSTRUCT: u32 = 0x5458;
+/// 获取线路状态寄存器（int *，TIOCSER_TEMT）
+pub const TIOCSERGETLSR: u32 = 0x5459;
+pub const TIOCSERGETMULTI: u32 = 0x545A;
+pub const TIOCSERSETMULTI: u32 = 0x545B;
+
+/// 等待调制解调器状态位变化（int，TIOCM_* 掩码）
+pub const TIOCMIWAIT: u32 = 0x545C;
+/// 获取串口中断计数（struct serial_icounter_struct）
+pub const TIOCGICOUNT: u32 = 0x545D;
+
+/// TIOCSERGETLSR 结果：发送器空闲
+pub const TIOCSER_TEMT: u32 = 0x01;
+
+// TIOCPKT 包模式下每次读取的首字节
+/// 普通数据
+pub const TIOCPKT_DATA: u8 = 0;
+/// 输入队列已清空
+pub const TIOCPKT_FLUSHREAD: u8 = 1;
+/// 输出队列已清空
+pub const TIOCPKT_FLUSHWRITE: u8 = 2;
+/// 输出已暂停
+pub const TIOCPKT_STOP: u8 = 4;
+/// 输出已恢复
+pub const TIOCPKT_START: u8 = 8;
+/// 流控字符不再是 ^S/^Q
+pub const TIOCPKT_NOSTOP: u8 = 16;
+/// 流控字符恢复为 ^S/^Q
+pub const TIOCPKT_DOSTOP: u8 = 32;
+/// termios 已改变（EXTPROC）
+pub const TIOCPKT_IOCTL: u8 = 64;
+
+// TIOCMGET/TIOCMSET 等使用的调制解调器状态位
+pub const TIOCM_LE: u32 = 0x001;
+pub const TIOCM_DTR: u32 = 0x002;
+pub const TIOCM_RTS: u32 = 0x004;
+pub const TIOCM_ST: u32 = 0x008;
+pub const TIOCM_SR: u32 = 0x010;
+pub const TIOCM_CTS: u32 = 0x020;
+pub const TIOCM_CAR: u32 = 0x040;
+pub const TIOCM_RNG: u32 = 0x080;
+pub const TIOCM_DSR: u32 = 0x100;
+pub const TIOCM_CD: u32 = TIOCM_CAR;
+pub const TIOCM_RI: u32 = TIOCM_RNG;
+pub const TIOCM_OUT1: u32 = 0x2000;
+pub const TIOCM_OUT2: u32 = 0x4000;
+pub const TIOCM_LOOP: u32 = 0x8000;
+
+// 行规程编号（TIOCSETD/TIOCGETD 与 termios.c_line）
+/// 标准终端行规程
+pub const N_TTY: u8 = 0;
+/// SLIP
+pub const N_SLIP: u8 = 1;
+/// 鼠标
+pub const N_MOUSE: u8 = 2;
+/// PPP
+pub const N_PPP: u8 = 3;
+/// 最大行规程数
+pub const NR_LDISCS: u8 = 31;
+
+/// 获取 PTY 编号（unsigned int *）- ptsname() 使用
+pub const TIOCGPTN: u32 = _IOR(b'T' as u32, 0x30, 4);
+
+/// 锁定/解锁 PTY 从设备（int *）- unlockpt() 使用
+pub const TIOCSPTLCK: u32 = _IOW(b'T' as u32, 0x31, 4);
+
+/// 获取 PTY 从设备锁定状态（int *）
+pub const TIOCGPTLCK: u32 = _IOR(b'T' as u32, 0x39, 4);
+
 /// 查询可用的虚拟终端（int *）- 可选，用于 VT 切换
 pub const VT_OPENQRY: u32 = 0x5600;
 
@@ -184,6 +384,25 @@ pub const SIOCGIFNAME_BY_INDEX: u32 = 0x8910;
 pub const RTC_RD_TIME: u32 = _IOR(b'p' as u32, 0x09, 36);
 pub const RTC_SET_TIME: u32 = _IOW(b'p' as u32, 0x0A, 36);
 
+/// 随机数设备（/dev/random）：读取熵估计（int）
+pub const RNDGETENTCNT: u32 = _IOR(b'R' as u32, 0x00, 4);
+/// 注入带熵估计的数据（struct rand_pool_info，头部为 2 * sizeof(int)）
+pub const RNDADDENTROPY: u32 = _IOW(b'R' as u32, 0x03, 8);
+/// 立即重新播种
+pub const RNDRESEEDCRNG: u32 = _IO(b'R' as u32, 0x07);
+
+/// RNDADDENTROPY 的参数头（对应 Linux struct rand_pool_info）
+///
+/// 头部之后紧跟 `buf_size` 字节的数据。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandPoolInfo {
+    /// 数据包含的熵（位）
+    pub entropy_count: i32,
+    /// 数据长度（字节）
+    pub buf_size: i32,
+}
+
 /// RTC 时间结构体（对应 Linux struct rtc_time）
 ///
 /// 参考：include/uapi/linux/rtc.h
@@ -219,7 +438,7 @@ pub const BLKFLSBUF: u32 = _IO(0x12, 97);
 
 /// 终端窗口大小（用于 TIOCGWINSZ/TIOCSWINSZ）
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct WinSize {
     /// 窗口行数（字符）
     pub ws_row: u16,
@@ -231,11 +450,303 @@ pub struct WinSize {
     pub ws_ypixel: u16,
 }
 
+const _: () = assert!(core::mem::size_of::<WinSize>() == 8);
+
 // ========== 终端属性结构体 (termios) ==========
 
 /// 特殊控制字符数量（Linux asm-generic 标准）
 pub const NCCS: usize = 19;
 
+/// 旧式 struct termio 的控制字符数量
+pub const NCC: usize = 8;
+
+/// 禁用某个控制字符时写入 c_cc 的值（_POSIX_VDISABLE）
+pub const VDISABLE: u8 = 0;
+
+// c_cc 下标（值为 0 表示禁用该控制字符，即 _POSIX_VDISABLE）
+/// 中断字符（产生 SIGINT）
+pub const VINTR: usize = 0;
+/// 退出字符（产生 SIGQUIT）
+pub const VQUIT: usize = 1;
+/// 删除一个字符
+pub const VERASE: usize = 2;
+/// 删除整行
+pub const VKILL: usize = 3;
+/// 文件结束
+pub const VEOF: usize = 4;
+/// 非规范模式读超时（十分之一秒）
+pub const VTIME: usize = 5;
+/// 非规范模式最少读取字节数
+pub const VMIN: usize = 6;
+/// 切换字符（Linux 未使用）
+pub const VSWTC: usize = 7;
+/// 恢复输出（IXON）
+pub const VSTART: usize = 8;
+/// 暂停输出（IXON）
+pub const VSTOP: usize = 9;
+/// 挂起字符（产生 SIGTSTP）
+pub const VSUSP: usize = 10;
+/// 额外的行结束符
+pub const VEOL: usize = 11;
+/// 重新显示当前行（IEXTEN）
+pub const VREPRINT: usize = 12;
+/// 丢弃输出（IEXTEN）
+pub const VDISCARD: usize = 13;
+/// 删除一个单词（IEXTEN）
+pub const VWERASE: usize = 14;
+/// 按字面输入下一个字符（IEXTEN）
+pub const VLNEXT: usize = 15;
+/// 第二个额外的行结束符（IEXTEN）
+pub const VEOL2: usize = 16;
+
+// c_iflag 输入模式标志
+/// 忽略 break
+pub const IGNBRK: u32 = 0x0001;
+/// break 产生 SIGINT 并清空队列
+pub const BRKINT: u32 = 0x0002;
+/// 忽略奇偶校验错误的字符
+pub const IGNPAR: u32 = 0x0004;
+/// 标记奇偶校验错误
+pub const PARMRK: u32 = 0x0008;
+/// 启用输入奇偶校验
+pub const INPCK: u32 = 0x0010;
+/// 剥除第 8 位
+pub const ISTRIP: u32 = 0x0020;
+/// 将 NL 转换为 CR
+pub const INLCR: u32 = 0x0040;
+/// 忽略 CR
+pub const IGNCR: u32 = 0x0080;
+/// 将 CR 转换为 NL
+pub const ICRNL: u32 = 0x0100;
+/// 大写转小写
+pub const IUCLC: u32 = 0x0200;
+/// 启用输出软件流控（^S/^Q）
+pub const IXON: u32 = 0x0400;
+/// 任意字符均可恢复输出
+pub const IXANY: u32 = 0x0800;
+/// 启用输入软件流控
+pub const IXOFF: u32 = 0x1000;
+/// 输入队列满时响铃
+pub const IMAXBEL: u32 = 0x2000;
+/// 输入为 UTF-8（影响规范模式下的字符删除）
+pub const IUTF8: u32 = 0x4000;
+
+// c_oflag 输出模式标志
+/// 启用输出处理
+pub const OPOST: u32 = 0x0001;
+/// 小写转大写
+pub const OLCUC: u32 = 0x0002;
+/// 将 NL 转换为 CR-NL
+pub const ONLCR: u32 = 0x0004;
+/// 将 CR 转换为 NL
+pub const OCRNL: u32 = 0x0008;
+/// 第 0 列不输出 CR
+pub const ONOCR: u32 = 0x0010;
+/// NL 同时执行 CR 的功能
+pub const ONLRET: u32 = 0x0020;
+/// 用填充字符代替延时
+pub const OFILL: u32 = 0x0040;
+/// 填充字符为 DEL
+pub const OFDEL: u32 = 0x0080;
+/// 换行延时掩码
+pub const NLDLY: u32 = 0x0100;
+pub const NL0: u32 = 0x0000;
+pub const NL1: u32 = 0x0100;
+/// 回车延时掩码
+pub const CRDLY: u32 = 0x0600;
+pub const CR0: u32 = 0x0000;
+pub const CR1: u32 = 0x0200;
+pub const CR2: u32 = 0x0400;
+pub const CR3: u32 = 0x0600;
+/// 水平制表延时掩码
+pub const TABDLY: u32 = 0x1800;
+pub const TAB0: u32 = 0x0000;
+pub const TAB1: u32 = 0x0800;
+pub const TAB2: u32 = 0x1000;
+pub const TAB3: u32 = 0x1800;
+/// 将制表符展开为空格
+pub const XTABS: u32 = 0x1800;
+/// 退格延时掩码
+pub const BSDLY: u32 = 0x2000;
+pub const BS0: u32 = 0x0000;
+pub const BS1: u32 = 0x2000;
+/// 垂直制表延时掩码
+pub const VTDLY: u32 = 0x4000;
+pub const VT0: u32 = 0x0000;
+pub const VT1: u32 = 0x4000;
+/// 换页延时掩码
+pub const FFDLY: u32 = 0x8000;
+pub const FF0: u32 = 0x0000;
+pub const FF1: u32 = 0x8000;
+
+// c_cflag 控制模式标志
+/// 波特率掩码（含 CBAUDEX）
+pub const CBAUD: u32 = 0x0000_100F;
+/// 字符大小掩码
+pub const CSIZE: u32 = 0x0030;
+/// 5 位字符
+pub const CS5: u32 = 0x0000;
+/// 6 位字符
+pub const CS6: u32 = 0x0010;
+/// 7 位字符
+pub const CS7: u32 = 0x0020;
+/// 8 位字符
+pub const CS8: u32 = 0x0030;
+/// 两个停止位
+pub const CSTOPB: u32 = 0x0040;
+/// 允许接收
+pub const CREAD: u32 = 0x0080;
+/// 启用奇偶校验
+pub const PARENB: u32 = 0x0100;
+/// 奇校验
+pub const PARODD: u32 = 0x0200;
+/// 最后一个进程关闭后挂断
+pub const HUPCL: u32 = 0x0400;
+/// 忽略调制解调器控制线
+pub const CLOCAL: u32 = 0x0800;
+/// 扩展波特率标志（B57600 及以上）
+pub const CBAUDEX: u32 = 0x0000_1000;
+/// 波特率由 termios2 的 c_ispeed/c_ospeed 给出
+pub const BOTHER: u32 = 0x0000_1000;
+/// 输入波特率掩码（为 0 时与输出波特率相同）
+pub const CIBAUD: u32 = 0x100F_0000;
+/// 输入波特率在 c_cflag 中的位移
+pub const IBSHIFT: u32 = 16;
+/// 固定（mark/space）奇偶校验
+pub const CMSPAR: u32 = 0x4000_0000;
+/// 硬件流控
+pub const CRTSCTS: u32 = 0x8000_0000;
+
+// 波特率（c_cflag & CBAUD）
+pub const B0: u32 = 0x0000_0000;
+pub const B50: u32 = 0x0000_0001;
+pub const B75: u32 = 0x0000_0002;
+pub const B110: u32 = 0x0000_0003;
+pub const B134: u32 = 0x0000_0004;
+pub const B150: u32 = 0x0000_0005;
+pub const B200: u32 = 0x0000_0006;
+pub const B300: u32 = 0x0000_0007;
+pub const B600: u32 = 0x0000_0008;
+pub const B1200: u32 = 0x0000_0009;
+pub const B1800: u32 = 0x0000_000A;
+pub const B2400: u32 = 0x0000_000B;
+pub const B4800: u32 = 0x0000_000C;
+pub const B9600: u32 = 0x0000_000D;
+pub const B19200: u32 = 0x0000_000E;
+pub const B38400: u32 = 0x0000_000F;
+pub const EXTA: u32 = B19200;
+pub const EXTB: u32 = B38400;
+pub const B57600: u32 = 0x0000_1001;
+pub const B115200: u32 = 0x0000_1002;
+pub const B230400: u32 = 0x0000_1003;
+pub const B460800: u32 = 0x0000_1004;
+pub const B500000: u32 = 0x0000_1005;
+pub const B576000: u32 = 0x0000_1006;
+pub const B921600: u32 = 0x0000_1007;
+pub const B1000000: u32 = 0x0000_1008;
+pub const B1152000: u32 = 0x0000_1009;
+pub const B1500000: u32 = 0x0000_100A;
+pub const B2000000: u32 = 0x0000_100B;
+pub const B2500000: u32 = 0x0000_100C;
+pub const B3000000: u32 = 0x0000_100D;
+pub const B3500000: u32 = 0x0000_100E;
+pub const B4000000: u32 = 0x0000_100F;
+
+/// 波特率编码与实际速率（bps）对照表
+const BAUD_TABLE: [(u32, u32); 31] = [
+    (B0, 0),
+    (B50, 50),
+    (B75, 75),
+    (B110, 110),
+    (B134, 134),
+    (B150, 150),
+    (B200, 200),
+    (B300, 300),
+    (B600, 600),
+    (B1200, 1200),
+    (B1800, 1800),
+    (B2400, 2400),
+    (B4800, 4800),
+    (B9600, 9600),
+    (B19200, 19200),
+    (B38400, 38400),
+    (B57600, 57600),
+    (B115200, 115200),
+    (B230400, 230400),
+    (B460800, 460800),
+    (B500000, 500000),
+    (B576000, 576000),
+    (B921600, 921600),
+    (B1000000, 1000000),
+    (B1152000, 1152000),
+    (B1500000, 1500000),
+    (B2000000, 2000000),
+    (B2500000, 2500000),
+    (B3000000, 3000000),
+    (B3500000, 3500000),
+    (B4000000, 4000000),
+];
+
+/// 将波特率编码（`c_cflag & CBAUD`）转换为速率（bps）
+///
+/// `BOTHER` 与未定义的编码返回 `None`。
+pub const fn baud_to_speed(baud: u32) -> Option<u32> {
+    let mut i = 0;
+    while i < BAUD_TABLE.len() {
+        if BAUD_TABLE[i].0 == baud {
+            return Some(BAUD_TABLE[i].1);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// 将速率（bps）转换为波特率编码，非标准速率返回 `None`
+pub const fn speed_to_baud(speed: u32) -> Option<u32> {
+    let mut i = 0;
+    while i < BAUD_TABLE.len() {
+        if BAUD_TABLE[i].1 == speed {
+            return Some(BAUD_TABLE[i].0);
+        }
+        i += 1;
+    }
+    None
+}
+
+// c_lflag 本地模式标志
+/// 识别 INTR/QUIT/SUSP 并产生信号
+pub const ISIG: u32 = 0x0001;
+/// 规范模式（行编辑）
+pub const ICANON: u32 = 0x0002;
+/// 规范大小写显示
+pub const XCASE: u32 = 0x0004;
+/// 回显输入
+pub const ECHO: u32 = 0x0008;
+/// ERASE/WERASE 回显为擦除前一字符
+pub const ECHOE: u32 = 0x0010;
+/// KILL 后回显换行
+pub const ECHOK: u32 = 0x0020;
+/// 即使未设置 ECHO 也回显 NL
+pub const ECHONL: u32 = 0x0040;
+/// 产生信号时不清空输入队列
+pub const NOFLSH: u32 = 0x0080;
+/// 后台进程写终端时发送 SIGTTOU
+pub const TOSTOP: u32 = 0x0100;
+/// 控制字符回显为 `^X`
+pub const ECHOCTL: u32 = 0x0200;
+/// 以硬拷贝方式回显擦除的字符
+pub const ECHOPRT: u32 = 0x0400;
+/// KILL 回显为逐字符擦除
+pub const ECHOKE: u32 = 0x0800;
+/// 正在丢弃输出（VDISCARD）
+pub const FLUSHO: u32 = 0x1000;
+/// 下次读取时重新打印挂起的输入
+pub const PENDIN: u32 = 0x4000;
+/// 启用扩展输入处理（WERASE/REPRINT/LNEXT/EOL2）
+pub const IEXTEN: u32 = 0x8000;
+/// 外部处理行编辑（PTY 包模式）
+pub const EXTPROC: u32 = 0x10000;
+
 /// 终端属性结构（用于 TCGETS/TCSETS）
 ///
 /// **注意**：Linux `TCGETS/TCSETS` 使用的是 `include/uapi/asm-generic/termbits.h` 里的
@@ -266,15 +777,16 @@ impl Termios {
     /// 默认终端配置常量
     ///
     /// 提供标准的终端默认设置，适用于交互式 shell 和一般终端应用。
+    /// 与 Linux `tty_std_termios` 一致（流控除外）。
     pub const DEFAULT: Self = Self {
-        // 输入模式：ICRNL (将 CR 转换为 NL)
-        c_iflag: 0x0100,
-        // 输出模式：OPOST | ONLCR (启用输出处理，将 NL 转换为 CR-NL)
-        c_oflag: 0x0001 | 0x0004,
-        // 控制模式：CS8 | CREAD (8位字符，允许接收)
-        c_cflag: 0x0030 | 0x0080,
-        // 本地模式：ISIG | ICANON | ECHO | ECHOE
-        c_lflag: 0x0001 | 0x0002 | 0x0008 | 0x0010,
+        // 输入模式：将 CR 转换为 NL，启用 XON/XOFF 输出流控
+        c_iflag: ICRNL | IXON,
+        // 输出模式：启用输出处理，将 NL 转换为 CR-NL
+        c_oflag: OPOST | ONLCR,
+        // 控制模式：8位字符，允许接收
+        c_cflag: CS8 | CREAD,
+        // 本地模式：信号、规范模式、回显与行编辑回显
+        c_lflag: ISIG | ICANON | ECHO | ECHOE | ECHOK | ECHOCTL | ECHOKE | IEXTEN,
         // 行规程：0 (N_TTY)
         c_line: 0,
         // 特殊控制字符（使用常见默认值）
@@ -305,12 +817,143 @@ impl Termios {
     };
 }
 
+impl Termios {
+    /// 输出波特率（bps），编码无法识别时返回 `None`
+    pub const fn ospeed(&self) -> Option<u32> {
+        baud_to_speed(self.c_cflag & CBAUD)
+    }
+
+    /// 输入波特率（bps）；CIBAUD 为 0 时与输出波特率相同
+    pub const fn ispeed(&self) -> Option<u32> {
+        match (self.c_cflag & CIBAUD) >> IBSHIFT {
+            0 => self.ospeed(),
+            baud => baud_to_speed(baud),
+        }
+    }
+}
+
 impl Default for Termios {
     fn default() -> Self {
         Self::DEFAULT
     }
 }
 
+const _: () = assert!(core::mem::size_of::<Termios>() == 36);
+
+/// 带显式波特率的终端属性（用于 TCGETS2/TCSETS2）
+///
+/// 与 [`Termios`] 相同，尾部追加输入/输出速率；`c_cflag` 的波特率字段为 `BOTHER` 时生效。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Termios2 {
+    /// 输入模式标志
+    pub c_iflag: u32,
+    /// 输出模式标志
+    pub c_oflag: u32,
+    /// 控制模式标志
+    pub c_cflag: u32,
+    /// 本地模式标志
+    pub c_lflag: u32,
+    /// 行规程
+    pub c_line: u8,
+    /// 特殊控制字符
+    pub c_cc: [u8; NCCS],
+    /// 输入速率（bps）
+    pub c_ispeed: u32,
+    /// 输出速率（bps）
+    pub c_ospeed: u32,
+}
+
+const _: () = assert!(core::mem::size_of::<Termios2>() == 44);
+
+impl From<Termios> for Termios2 {
+    /// 由 `Termios` 的波特率编码填充速率字段，无法识别的编码记为 0
+    fn from(t: Termios) -> Self {
+        Self {
+            c_iflag: t.c_iflag,
+            c_oflag: t.c_oflag,
+            c_cflag: t.c_cflag,
+            c_lflag: t.c_lflag,
+            c_line: t.c_line,
+            c_cc: t.c_cc,
+            c_ispeed: t.ispeed().unwrap_or(0),
+            c_ospeed: t.ospeed().unwrap_or(0),
+        }
+    }
+}
+
+impl From<Termios2> for Termios {
+    /// 丢弃速率字段；`BOTHER` 时若速率是标准值则换回对应编码
+    fn from(t: Termios2) -> Self {
+        let mut c_cflag = t.c_cflag;
+        let obaud = speed_to_baud(t.c_ospeed).filter(|_| c_cflag & CBAUD == BOTHER);
+        if let Some(baud) = obaud {
+            c_cflag = (c_cflag & !CBAUD) | baud;
+        }
+        let ibaud = speed_to_baud(t.c_ispeed).filter(|_| (c_cflag & CIBAUD) >> IBSHIFT == BOTHER);
+        if let Some(baud) = ibaud {
+            c_cflag = (c_cflag & !CIBAUD) | (baud << IBSHIFT);
+        }
+        Self {
+            c_iflag: t.c_iflag,
+            c_oflag: t.c_oflag,
+            c_cflag,
+            c_lflag: t.c_lflag,
+            c_line: t.c_line,
+            c_cc: t.c_cc,
+        }
+    }
+}
+
+/// 旧式终端属性（用于 TCGETA/TCSETA）
+///
+/// 标志位只有低 16 位，控制字符只有前 [`NCC`] 个。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Termio {
+    /// 输入模式标志
+    pub c_iflag: u16,
+    /// 输出模式标志
+    pub c_oflag: u16,
+    /// 控制模式标志
+    pub c_cflag: u16,
+    /// 本地模式标志
+    pub c_lflag: u16,
+    /// 行规程
+    pub c_line: u8,
+    /// 特殊控制字符
+    pub c_cc: [u8; NCC],
+}
+
+const _: () = assert!(core::mem::size_of::<Termio>() == 18);
+
+impl Termio {
+    /// 用 `termio` 的内容更新 `termios` 的低 16 位标志与前 NCC 个控制字符
+    pub fn apply_to(&self, termios: &mut Termios) {
+        termios.c_iflag = (termios.c_iflag & 0xFFFF_0000) | self.c_iflag as u32;
+        termios.c_oflag = (termios.c_oflag & 0xFFFF_0000) | self.c_oflag as u32;
+        termios.c_cflag = (termios.c_cflag & 0xFFFF_0000) | self.c_cflag as u32;
+        termios.c_lflag = (termios.c_lflag & 0xFFFF_0000) | self.c_lflag as u32;
+        termios.c_line = self.c_line;
+        termios.c_cc[..NCC].copy_from_slice(&self.c_cc);
+    }
+}
+
+impl From<Termios> for Termio {
+    fn from(t: Termios) -> Self {
+        let mut c_cc = [0; NCC];
+        c_cc.copy_from_slice(&t.c_cc[..NCC]);
+        Self {
+            c_iflag: t.c_iflag as u16,
+            c_oflag: t.c_oflag as u16,
+            c_cflag: t.c_cflag as u16,
+            c_lflag: t.c_lflag as u16,
+            c_line: t.c_line,
+            c_cc,
+        }
+    }
+}
+
 // ========== 网络接口结构体 ==========
 
 /// 最大接口名称长度
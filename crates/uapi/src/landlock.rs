@@ -0,0 +1,57 @@
+//! Landlock 相关常量与结构体
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/landlock.h），本内核实现 ABI 版本 1。
+
+/// 本内核支持的 Landlock ABI 版本
+pub const LANDLOCK_ABI_VERSION: i32 = 1;
+
+// landlock_create_ruleset(2) 的标志
+
+/// 不创建规则集，只返回支持的 ABI 版本
+pub const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+
+// landlock_add_rule(2) 的规则类型
+
+/// `struct landlock_path_beneath_attr`：限制某个目录（或文件）之下的访问
+pub const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+// 文件系统访问权限（ABI 1）
+
+pub const LANDLOCK_ACCESS_FS_EXECUTE: u64 = 1 << 0;
+pub const LANDLOCK_ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+pub const LANDLOCK_ACCESS_FS_READ_FILE: u64 = 1 << 2;
+pub const LANDLOCK_ACCESS_FS_READ_DIR: u64 = 1 << 3;
+pub const LANDLOCK_ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+pub const LANDLOCK_ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+pub const LANDLOCK_ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+pub const LANDLOCK_ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+pub const LANDLOCK_ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+pub const LANDLOCK_ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+pub const LANDLOCK_ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+pub const LANDLOCK_ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+pub const LANDLOCK_ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+
+/// ABI 1 的全部文件系统访问权限
+pub const LANDLOCK_ACCESS_FS_ALL: u64 = (LANDLOCK_ACCESS_FS_MAKE_SYM << 1) - 1;
+
+/// 可作用于普通文件（而非目录）的访问权限
+pub const LANDLOCK_ACCESS_FS_FILE: u64 =
+    LANDLOCK_ACCESS_FS_EXECUTE | LANDLOCK_ACCESS_FS_WRITE_FILE | LANDLOCK_ACCESS_FS_READ_FILE;
+
+/// `struct landlock_ruleset_attr`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LandlockRulesetAttr {
+    /// 规则集负责限制的文件系统访问权限
+    pub handled_access_fs: u64,
+}
+
+/// `struct landlock_path_beneath_attr`（packed）
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LandlockPathBeneathAttr {
+    /// 在 `parent_fd` 之下允许的访问权限
+    pub allowed_access: u64,
+    /// 目录（或文件）的文件描述符
+    pub parent_fd: i32,
+}
@@ -7,23 +7,38 @@
 // uapi 中包含大量与 Linux 兼容的常量/结构体字段定义；逐项补 `///` 噪声较大。
 #![allow(missing_docs)]
 
+pub mod auxv;
 pub mod cred;
+pub mod epoll;
 pub mod errno;
+pub mod eventfd;
 pub mod fcntl;
+pub mod filter;
 pub mod fs;
 pub mod futex;
+pub mod inotify;
 pub mod ioctl;
+pub mod io_uring;
 pub mod iovec;
+pub mod landlock;
 pub mod log;
 pub mod mm;
+pub mod posix_acl;
+pub mod prctl;
+pub mod random;
 pub mod reboot;
 pub mod resource;
+pub mod rseq;
 pub mod sched;
+pub mod seccomp;
 pub mod select;
 pub mod signal;
 pub mod socket;
+pub mod splice;
 pub mod sysinfo;
 pub mod time;
 pub mod types;
+pub mod userfaultfd;
 pub mod uts_namespace;
 pub mod wait;
+pub mod xattr;
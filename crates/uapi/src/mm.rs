@@ -63,6 +63,119 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// 重新映射标志（mremap）
+    ///
+    /// 参考：include/uapi/linux/mman.h
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MremapFlags: i32 {
+        /// 原地址放不下时允许移动到新地址 (MREMAP_MAYMOVE)
+        const MAYMOVE = 0x1;
+
+        /// 移动到指定的新地址，必须与 MAYMOVE 同时使用 (MREMAP_FIXED)
+        const FIXED = 0x2;
+
+        /// 移动后保留原映射（不支持）(MREMAP_DONTUNMAP)
+        const DONTUNMAP = 0x4;
+    }
+}
+
+bitflags! {
+    /// 锁定内存标志（mlock2）
+    ///
+    /// 参考：include/uapi/asm-generic/mman-common.h
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MlockFlags: u32 {
+        /// 不预先建立映射，页在第一次访问时才被锁定 (MLOCK_ONFAULT)
+        const ONFAULT = 0x1;
+    }
+}
+
+bitflags! {
+    /// 锁定整个地址空间的标志（mlockall）
+    ///
+    /// 参考：include/uapi/asm-generic/mman.h
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MlockallFlags: i32 {
+        /// 锁定当前所有映射 (MCL_CURRENT)
+        const CURRENT = 0x1;
+
+        /// 锁定之后新建的映射 (MCL_FUTURE)
+        const FUTURE = 0x2;
+
+        /// 与前两者同时使用，不预先建立映射 (MCL_ONFAULT)
+        const ONFAULT = 0x4;
+    }
+}
+
+/// madvise 建议
+///
+/// 参考：include/uapi/asm-generic/mman-common.h
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum MadviseAdvice {
+    /// 无特别建议 (MADV_NORMAL)
+    Normal = 0,
+
+    /// 随机访问 (MADV_RANDOM)
+    Random = 1,
+
+    /// 顺序访问 (MADV_SEQUENTIAL)
+    Sequential = 2,
+
+    /// 即将访问，预先建立映射 (MADV_WILLNEED)
+    WillNeed = 3,
+
+    /// 不再需要，立即丢弃页内容 (MADV_DONTNEED)
+    DontNeed = 4,
+
+    /// 内容可丢弃，内存紧张时再回收 (MADV_FREE)
+    Free = 8,
+
+    /// 允许与内容相同的页合并 (MADV_MERGEABLE)
+    Mergeable = 12,
+
+    /// 取消合并，已合并的页重新变为独占 (MADV_UNMERGEABLE)
+    Unmergeable = 13,
+
+    /// 使用透明大页 (MADV_HUGEPAGE)
+    HugePage = 14,
+
+    /// 不使用透明大页 (MADV_NOHUGEPAGE)
+    NoHugePage = 15,
+
+    /// 不写入核心转储 (MADV_DONTDUMP)
+    DontDump = 16,
+
+    /// 写入核心转储 (MADV_DODUMP)
+    DoDump = 17,
+}
+
+impl MadviseAdvice {
+    /// 从 i32 转换为 MadviseAdvice
+    ///
+    /// # 返回值
+    /// - `Some(advice)`: 识别的建议
+    /// - `None`: 未知建议
+    pub fn from_raw(advice: i32) -> Option<Self> {
+        match advice {
+            0 => Some(Self::Normal),
+            1 => Some(Self::Random),
+            2 => Some(Self::Sequential),
+            3 => Some(Self::WillNeed),
+            4 => Some(Self::DontNeed),
+            8 => Some(Self::Free),
+            12 => Some(Self::Mergeable),
+            13 => Some(Self::Unmergeable),
+            14 => Some(Self::HugePage),
+            15 => Some(Self::NoHugePage),
+            16 => Some(Self::DontDump),
+            17 => Some(Self::DoDump),
+            _ => None,
+        }
+    }
+}
+
 impl MapFlags {
     /// 检查标志组合是否合法
     ///
@@ -0,0 +1,39 @@
+//! POSIX ACL 相关常量
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/posix_acl.h、
+//! include/uapi/linux/posix_acl_xattr.h）。
+
+/// 文件属主 (ACL_USER_OBJ)
+pub const ACL_USER_OBJ: u16 = 0x01;
+/// 指定用户 (ACL_USER)
+pub const ACL_USER: u16 = 0x02;
+/// 文件属组 (ACL_GROUP_OBJ)
+pub const ACL_GROUP_OBJ: u16 = 0x04;
+/// 指定组 (ACL_GROUP)
+pub const ACL_GROUP: u16 = 0x08;
+/// 指定用户与组能获得的最大权限 (ACL_MASK)
+pub const ACL_MASK: u16 = 0x10;
+/// 其他用户 (ACL_OTHER)
+pub const ACL_OTHER: u16 = 0x20;
+
+/// 读权限 (ACL_READ)
+pub const ACL_READ: u16 = 0x04;
+/// 写权限 (ACL_WRITE)
+pub const ACL_WRITE: u16 = 0x02;
+/// 执行/搜索权限 (ACL_EXECUTE)
+pub const ACL_EXECUTE: u16 = 0x01;
+
+/// 不带 ID 的表项使用的 e_id (ACL_UNDEFINED_ID)
+pub const ACL_UNDEFINED_ID: u32 = u32::MAX;
+
+/// 扩展属性中 ACL 的格式版本 (POSIX_ACL_XATTR_VERSION)
+pub const POSIX_ACL_XATTR_VERSION: u32 = 0x0002;
+/// 扩展属性格式的头部长度（`a_version: u32`）
+pub const POSIX_ACL_XATTR_HEADER_SIZE: usize = 4;
+/// 扩展属性格式的表项长度（`e_tag: u16, e_perm: u16, e_id: u32`）
+pub const POSIX_ACL_XATTR_ENTRY_SIZE: usize = 8;
+
+/// 访问 ACL 的属性名 (XATTR_NAME_POSIX_ACL_ACCESS)
+pub const XATTR_NAME_POSIX_ACL_ACCESS: &str = "system.posix_acl_access";
+/// 默认 ACL 的属性名 (XATTR_NAME_POSIX_ACL_DEFAULT)
+pub const XATTR_NAME_POSIX_ACL_DEFAULT: &str = "system.posix_acl_default";
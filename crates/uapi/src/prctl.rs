@@ -0,0 +1,99 @@
+//! prctl(2) 选项定义
+//!
+//! 源于 Linux 内核头文件 <linux/prctl.h>。
+
+/// 父进程退出时向本进程发送的信号
+pub const PR_SET_PDEATHSIG: i32 = 1;
+pub const PR_GET_PDEATHSIG: i32 = 2;
+
+/// 进程是否可产生 core dump / 被 ptrace 附加
+pub const PR_GET_DUMPABLE: i32 = 3;
+pub const PR_SET_DUMPABLE: i32 = 4;
+
+/// 切换 uid 时是否保留能力集
+pub const PR_GET_KEEPCAPS: i32 = 7;
+pub const PR_SET_KEEPCAPS: i32 = 8;
+
+/// 线程名（comm），最长 [`TASK_COMM_LEN`] 字节（含 NUL）
+pub const PR_SET_NAME: i32 = 15;
+pub const PR_GET_NAME: i32 = 16;
+
+/// seccomp 模式
+pub const PR_GET_SECCOMP: i32 = 21;
+pub const PR_SET_SECCOMP: i32 = 22;
+
+/// 能力边界集
+pub const PR_CAPBSET_READ: i32 = 23;
+pub const PR_CAPBSET_DROP: i32 = 24;
+
+/// 定时器松弛量（纳秒）
+pub const PR_SET_TIMERSLACK: i32 = 29;
+pub const PR_GET_TIMERSLACK: i32 = 30;
+
+/// securebits
+pub const PR_GET_SECUREBITS: i32 = 27;
+pub const PR_SET_SECUREBITS: i32 = 28;
+
+/// 修改内存描述符字段（需要 CAP_SYS_RESOURCE）
+pub const PR_SET_MM: i32 = 35;
+
+/// 子进程回收器（subreaper）
+pub const PR_SET_CHILD_SUBREAPER: i32 = 36;
+pub const PR_GET_CHILD_SUBREAPER: i32 = 37;
+
+/// no_new_privs：设置后 execve 不再提升权限，不可清除
+pub const PR_SET_NO_NEW_PRIVS: i32 = 38;
+pub const PR_GET_NO_NEW_PRIVS: i32 = 39;
+
+/// 获取 set_tid_address 设置的地址
+pub const PR_GET_TID_ADDRESS: i32 = 40;
+
+/// 透明大页开关
+pub const PR_SET_THP_DISABLE: i32 = 41;
+pub const PR_GET_THP_DISABLE: i32 = 42;
+
+/// 环境能力集（arg2 为 `PR_CAP_AMBIENT_*`）
+pub const PR_CAP_AMBIENT: i32 = 47;
+pub const PR_CAP_AMBIENT_IS_SET: u64 = 1;
+pub const PR_CAP_AMBIENT_RAISE: u64 = 2;
+pub const PR_CAP_AMBIENT_LOWER: u64 = 3;
+pub const PR_CAP_AMBIENT_CLEAR_ALL: u64 = 4;
+
+/// 推测执行缓解控制
+pub const PR_GET_SPECULATION_CTRL: i32 = 52;
+pub const PR_SET_SPECULATION_CTRL: i32 = 53;
+
+/// 命名匿名 VMA（arg2 为 `PR_SET_VMA_ANON_NAME`）
+pub const PR_SET_VMA: i32 = 0x53564d41;
+pub const PR_SET_VMA_ANON_NAME: u64 = 0;
+
+/// RISC-V 向量扩展启用控制
+pub const PR_RISCV_V_SET_CONTROL: i32 = 69;
+pub const PR_RISCV_V_GET_CONTROL: i32 = 70;
+
+/// RISC-V 用户态 icache 刷新上下文
+pub const PR_RISCV_SET_ICACHE_FLUSH_CTX: i32 = 71;
+
+// PR_SET_DUMPABLE 的取值
+pub const SUID_DUMP_DISABLE: u64 = 0;
+pub const SUID_DUMP_USER: u64 = 1;
+
+// PR_SET_MM 的子操作
+pub const PR_SET_MM_START_CODE: u64 = 1;
+pub const PR_SET_MM_END_CODE: u64 = 2;
+pub const PR_SET_MM_START_DATA: u64 = 3;
+pub const PR_SET_MM_END_DATA: u64 = 4;
+pub const PR_SET_MM_START_STACK: u64 = 5;
+pub const PR_SET_MM_START_BRK: u64 = 6;
+pub const PR_SET_MM_BRK: u64 = 7;
+pub const PR_SET_MM_ARG_START: u64 = 8;
+pub const PR_SET_MM_ARG_END: u64 = 9;
+pub const PR_SET_MM_ENV_START: u64 = 10;
+pub const PR_SET_MM_ENV_END: u64 = 11;
+pub const PR_SET_MM_AUXV: u64 = 12;
+pub const PR_SET_MM_EXE_FILE: u64 = 13;
+pub const PR_SET_MM_MAP: u64 = 14;
+pub const PR_SET_MM_MAP_SIZE: u64 = 15;
+
+/// 线程名缓冲区长度（含结尾 NUL）
+pub const TASK_COMM_LEN: usize = 16;
@@ -0,0 +1,12 @@
+//! 随机数相关常量
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/random.h）。
+
+/// getrandom(2)：随机数生成器未播种时不阻塞，返回 EAGAIN
+pub const GRND_NONBLOCK: u32 = 0x0001;
+
+/// getrandom(2)：从 /dev/random 而不是 /dev/urandom 取数（现与默认行为相同）
+pub const GRND_RANDOM: u32 = 0x0002;
+
+/// getrandom(2)：即使未播种也立即返回，输出不保证密码学强度
+pub const GRND_INSECURE: u32 = 0x0004;
@@ -0,0 +1,64 @@
+//! 可重启序列（rseq）相关的常量和结构体定义
+//!
+//! 源于 Linux 内核头文件 <linux/rseq.h>。用户态在每个线程注册一块 [`Rseq`]，
+//! 内核在线程返回用户态时刷新其中的 CPU 编号；线程在临界区内被抢占或收到信号时，
+//! 内核把返回地址改到 [`RseqCs::abort_ip`]。
+
+/// 注册时 `cpu_id` 的初始值：尚未注册
+pub const RSEQ_CPU_ID_UNINITIALIZED: i32 = -1;
+/// 注册失败时由用户态写入 `cpu_id` 的值
+pub const RSEQ_CPU_ID_REGISTRATION_FAILED: i32 = -2;
+
+/// rseq() 的 flags：注销当前线程的注册
+pub const RSEQ_FLAG_UNREGISTER: i32 = 1 << 0;
+
+/// [`RseqCs::flags`]：抢占时不中止（已废弃）
+pub const RSEQ_CS_FLAG_NO_RESTART_ON_PREEMPT: u32 = 1 << 0;
+/// [`RseqCs::flags`]：信号投递时不中止（已废弃）
+pub const RSEQ_CS_FLAG_NO_RESTART_ON_SIGNAL: u32 = 1 << 1;
+/// [`RseqCs::flags`]：迁移时不中止（已废弃）
+pub const RSEQ_CS_FLAG_NO_RESTART_ON_MIGRATE: u32 = 1 << 2;
+
+/// 最初版本 `struct rseq` 的大小，也是注册地址的最小对齐
+pub const ORIG_RSEQ_SIZE: u32 = 32;
+/// 内核支持的 `struct rseq` 特性大小（`offsetof(struct rseq, end)`），经 AT_RSEQ_FEATURE_SIZE 告知用户态
+pub const RSEQ_FEATURE_SIZE: usize = 28;
+
+/// 每线程的 rseq 区域（struct rseq）
+///
+/// 由用户态分配（glibc 放在 TCB 中），内核只写 CPU 相关字段、读写 `rseq_cs`。
+#[repr(C, align(32))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rseq {
+    /// 线程最近运行的 CPU，注册前为 0
+    pub cpu_id_start: u32,
+    /// 线程当前运行的 CPU，未注册时为 [`RSEQ_CPU_ID_UNINITIALIZED`]
+    pub cpu_id: u32,
+    /// 当前临界区描述符 [`RseqCs`] 的用户地址，不在临界区时为 0
+    pub rseq_cs: u64,
+    /// 已废弃，必须为 0
+    pub flags: u32,
+    /// 当前 CPU 所在的 NUMA 节点
+    pub node_id: u32,
+    /// 内存描述符内的并发 ID
+    pub mm_cid: u32,
+}
+
+/// 临界区描述符（struct rseq_cs）
+#[repr(C, align(32))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RseqCs {
+    /// 结构版本，目前为 0
+    pub version: u32,
+    /// `RSEQ_CS_FLAG_*`
+    pub flags: u32,
+    /// 临界区第一条指令的地址
+    pub start_ip: u64,
+    /// 临界区长度：`[start_ip, start_ip + post_commit_offset)` 内被打断时中止
+    pub post_commit_offset: u64,
+    /// 中止处理入口，前 4 字节必须是注册时给出的签名
+    pub abort_ip: u64,
+}
+
+const _: () = assert!(core::mem::size_of::<Rseq>() == ORIG_RSEQ_SIZE as usize);
+const _: () = assert!(core::mem::size_of::<RseqCs>() == 32);
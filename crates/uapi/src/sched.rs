@@ -91,7 +91,9 @@ const CURRENTLY_SUPPORTED_FLAGS: CloneFlags = CloneFlags::from_bits_truncate(
         | CloneFlags::THREAD.bits()
         | CloneFlags::PARENT_SETTID.bits()
         | CloneFlags::CHILD_CLEARTID.bits()
-        | CloneFlags::CHILD_SETTID.bits(),
+        | CloneFlags::CHILD_SETTID.bits()
+        | CloneFlags::NEWUSER.bits()
+        | CloneFlags::NEWNS.bits(),
 );
 
 impl CloneFlags {
@@ -155,9 +157,74 @@ pub enum SchedulingPolicy {
     Ext = 7,      // SCHED_EXT (保留用于外部调度器)
 }
 
+impl SchedulingPolicy {
+    /// 由 sched_setscheduler 的 policy 参数解析（已去掉 SCHED_RESET_ON_FORK）
+    pub fn from_raw(policy: c_int) -> Option<Self> {
+        match policy {
+            0 => Some(Self::Normal),
+            1 => Some(Self::Fifo),
+            2 => Some(Self::Rr),
+            3 => Some(Self::Batch),
+            5 => Some(Self::Idle),
+            6 => Some(Self::Deadline),
+            7 => Some(Self::Ext),
+            _ => None,
+        }
+    }
+
+    /// 是否为实时策略（使用 sched_priority）
+    pub fn is_realtime(self) -> bool {
+        matches!(self, Self::Fifo | Self::Rr)
+    }
+}
+
 /// 调度标志：在 fork 时重置为 SCHED_NORMAL。
 pub const SCHED_RESET_ON_FORK: c_int = 0x40000000;
 
+/// 实时优先级上界（不含），有效范围为 1..=MAX_USER_RT_PRIO-1
+pub const MAX_USER_RT_PRIO: c_int = 100;
+/// 最小 nice 值（最高优先级）
+pub const MIN_NICE: c_int = -20;
+/// 最大 nice 值（最低优先级）
+pub const MAX_NICE: c_int = 19;
+
+/// sched_setparam / sched_getparam 的参数结构体
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedParam {
+    /// 实时优先级，非实时策略必须为 0
+    pub sched_priority: c_int,
+}
+
+/// sched_setattr / sched_getattr 的参数结构体
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedAttr {
+    /// 结构体大小，用于版本兼容
+    pub size: u32,
+    /// 调度策略（SchedulingPolicy）
+    pub sched_policy: u32,
+    /// 调度标志（SchedFlags）
+    pub sched_flags: u64,
+    /// SCHED_NORMAL / SCHED_BATCH 的 nice 值
+    pub sched_nice: i32,
+    /// SCHED_FIFO / SCHED_RR 的实时优先级
+    pub sched_priority: u32,
+    /// SCHED_DEADLINE 参数（纳秒）
+    pub sched_runtime: u64,
+    pub sched_deadline: u64,
+    pub sched_period: u64,
+    /// 利用率钳位（SCHED_FLAG_UTIL_CLAMP_*）
+    pub sched_util_min: u32,
+    pub sched_util_max: u32,
+}
+
+// 结构体大小版本常量 (用于兼容性检查)
+pub const SCHED_ATTR_SIZE_VER0: usize = 48;
+pub const SCHED_ATTR_SIZE_VER1: usize = 56;
+
+const _: () = assert!(core::mem::size_of::<SchedAttr>() == SCHED_ATTR_SIZE_VER1);
+
 bitflags! {
     /// 用于 sched_{set,get}attr() 系统调用的标志位。
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
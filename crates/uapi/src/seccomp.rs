@@ -0,0 +1,64 @@
+//! seccomp 与 prctl 相关常量
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/seccomp.h、linux/audit.h 与 linux/prctl.h）。
+
+// seccomp 模式
+
+/// 未启用 seccomp
+pub const SECCOMP_MODE_DISABLED: u32 = 0;
+/// 严格模式：只允许 read / write / exit / rt_sigreturn
+pub const SECCOMP_MODE_STRICT: u32 = 1;
+/// 过滤器模式：由用户提供的 cBPF 程序决定
+pub const SECCOMP_MODE_FILTER: u32 = 2;
+
+// seccomp(2) 操作
+
+pub const SECCOMP_SET_MODE_STRICT: u32 = 0;
+pub const SECCOMP_SET_MODE_FILTER: u32 = 1;
+pub const SECCOMP_GET_ACTION_AVAIL: u32 = 2;
+pub const SECCOMP_GET_NOTIF_SIZES: u32 = 3;
+
+// SECCOMP_SET_MODE_FILTER 的标志
+
+/// 把过滤器同步到线程组内的所有线程
+pub const SECCOMP_FILTER_FLAG_TSYNC: u32 = 1 << 0;
+/// 记录除 SECCOMP_RET_ALLOW 以外的所有动作
+pub const SECCOMP_FILTER_FLAG_LOG: u32 = 1 << 1;
+/// 不启用推测执行缓解（本内核中无效果）
+pub const SECCOMP_FILTER_FLAG_SPEC_ALLOW: u32 = 1 << 2;
+
+// 过滤器返回值：高 16 位为动作，低 16 位为数据（按优先级从高到低排列）
+
+pub const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+pub const SECCOMP_RET_KILL_THREAD: u32 = 0x0000_0000;
+pub const SECCOMP_RET_KILL: u32 = SECCOMP_RET_KILL_THREAD;
+pub const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+pub const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+pub const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+pub const SECCOMP_RET_TRACE: u32 = 0x7ff0_0000;
+pub const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+pub const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+/// 返回值中的动作部分
+pub const SECCOMP_RET_ACTION_FULL: u32 = 0xffff_0000;
+/// 返回值中的数据部分
+pub const SECCOMP_RET_DATA: u32 = 0x0000_ffff;
+
+/// 过滤器看到的系统调用信息（struct seccomp_data）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeccompData {
+    /// 系统调用号
+    pub nr: i32,
+    /// 调用约定，取 AUDIT_ARCH_* 之一
+    pub arch: u32,
+    /// 发起系统调用的指令地址
+    pub instruction_pointer: u64,
+    /// 系统调用参数
+    pub args: [u64; 6],
+}
+
+// 审计架构标识：EM_* | __AUDIT_ARCH_64BIT | __AUDIT_ARCH_LE
+
+pub const AUDIT_ARCH_RISCV64: u32 = 0xc000_00f3;
+pub const AUDIT_ARCH_LOONGARCH64: u32 = 0xc000_0102;
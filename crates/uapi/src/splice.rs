@@ -0,0 +1,14 @@
+//! splice 相关常量
+//!
+//! 对应于 Linux 用户空间 API 定义（include/linux/splice.h）。
+
+/// 尽量移动页而不是复制（仅作提示） (SPLICE_F_MOVE)
+pub const SPLICE_F_MOVE: u32 = 0x01;
+/// 管道端不阻塞 (SPLICE_F_NONBLOCK)
+pub const SPLICE_F_NONBLOCK: u32 = 0x02;
+/// 之后还有数据（仅作提示） (SPLICE_F_MORE)
+pub const SPLICE_F_MORE: u32 = 0x04;
+/// 页交给内核（仅用于 vmsplice） (SPLICE_F_GIFT)
+pub const SPLICE_F_GIFT: u32 = 0x08;
+/// 所有合法标志
+pub const SPLICE_F_ALL: u32 = SPLICE_F_MOVE | SPLICE_F_NONBLOCK | SPLICE_F_MORE | SPLICE_F_GIFT;
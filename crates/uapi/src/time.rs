@@ -276,6 +276,122 @@ pub struct timezone {
     pub tz_dsttime: c_int,
 }
 
+/// adjtimex / clock_adjtime 使用的时钟调整参数（`struct timex`）。
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Timex {
+    /// 要修改的字段（ADJ_* 组合）
+    pub modes: c_int,
+    /// 时间偏移（默认微秒，STA_NANO 时为纳秒）
+    pub offset: c_long,
+    /// 频率偏差（单位 2^-16 ppm）
+    pub freq: c_long,
+    /// 最大误差（微秒）
+    pub maxerror: c_long,
+    /// 估计误差（微秒）
+    pub esterror: c_long,
+    /// 时钟状态（STA_* 组合）
+    pub status: c_int,
+    /// PLL 时间常数
+    pub constant: c_long,
+    /// 时钟精度（微秒，只读）
+    pub precision: c_long,
+    /// 最大频率容差（单位 2^-16 ppm，只读）
+    pub tolerance: c_long,
+    /// 当前墙上时钟时间（只读；ADJ_SETOFFSET 时为要注入的偏移）
+    pub time: timeval,
+    /// 每个时钟节拍的微秒数
+    pub tick: c_long,
+    /// PPS 频率（只读）
+    pub ppsfreq: c_long,
+    /// PPS 抖动（只读）
+    pub jitter: c_long,
+    /// PPS 间隔（只读）
+    pub shift: c_int,
+    /// PPS 稳定度（只读）
+    pub stabil: c_long,
+    /// PPS 抖动超限次数（只读）
+    pub jitcnt: c_long,
+    /// PPS 校准次数（只读）
+    pub calcnt: c_long,
+    /// PPS 校准错误次数（只读）
+    pub errcnt: c_long,
+    /// PPS 稳定度超限次数（只读）
+    pub stbcnt: c_long,
+    /// TAI 与 UTC 的偏移（秒）
+    pub tai: c_int,
+    _reserved: [c_int; 11],
+}
+
+const _: () = assert!(core::mem::size_of::<Timex>() == 208);
+
+/// adjtimex 的 modes 位、status 位与返回的时钟状态。
+pub mod adjtimex {
+    use super::c_int;
+
+    /// 调整时间偏移
+    pub const ADJ_OFFSET: c_int = 0x0001;
+    /// 调整频率偏差
+    pub const ADJ_FREQUENCY: c_int = 0x0002;
+    /// 设置最大误差
+    pub const ADJ_MAXERROR: c_int = 0x0004;
+    /// 设置估计误差
+    pub const ADJ_ESTERROR: c_int = 0x0008;
+    /// 设置状态位
+    pub const ADJ_STATUS: c_int = 0x0010;
+    /// 设置 PLL 时间常数
+    pub const ADJ_TIMECONST: c_int = 0x0020;
+    /// 设置 TAI 偏移
+    pub const ADJ_TAI: c_int = 0x0080;
+    /// 向墙上时钟注入 `time` 表示的偏移（一次性跳变）
+    pub const ADJ_SETOFFSET: c_int = 0x0100;
+    /// 以微秒解释时间偏移
+    pub const ADJ_MICRO: c_int = 0x1000;
+    /// 以纳秒解释时间偏移
+    pub const ADJ_NANO: c_int = 0x2000;
+    /// 设置每节拍微秒数
+    pub const ADJ_TICK: c_int = 0x4000;
+    /// 传统 adjtime：以固定速率补偿偏移
+    pub const ADJ_OFFSET_SINGLESHOT: c_int = 0x8001;
+    /// 传统 adjtime：只读取剩余偏移
+    pub const ADJ_OFFSET_SS_READ: c_int = 0xa001;
+
+    /// 启用 PLL
+    pub const STA_PLL: c_int = 0x0001;
+    /// 启用 PPS 频率校准
+    pub const STA_PPSFREQ: c_int = 0x0002;
+    /// 启用 PPS 时间校准
+    pub const STA_PPSTIME: c_int = 0x0004;
+    /// 选择频率锁定模式
+    pub const STA_FLL: c_int = 0x0008;
+    /// 插入闰秒
+    pub const STA_INS: c_int = 0x0010;
+    /// 删除闰秒
+    pub const STA_DEL: c_int = 0x0020;
+    /// 时钟未同步
+    pub const STA_UNSYNC: c_int = 0x0040;
+    /// 保持频率
+    pub const STA_FREQHOLD: c_int = 0x0080;
+    /// 时间偏移以纳秒表示（只读）
+    pub const STA_NANO: c_int = 0x2000;
+    /// 用户可以修改的状态位
+    pub const STA_RWMASK: c_int = 0x00ff;
+
+    /// 时钟同步，没有闰秒
+    pub const TIME_OK: c_int = 0;
+    /// 时钟未同步
+    pub const TIME_ERROR: c_int = 5;
+
+    /// 最大频率偏差（单位 2^-16 ppm，即 500 ppm）
+    pub const MAXFREQ_SCALED: i64 = 500 << 16;
+    /// 最大时间偏移（纳秒）
+    pub const MAXPHASE: i64 = 500_000_000;
+    /// 最大 PLL 时间常数
+    pub const MAXTC: i64 = 10;
+    /// 未同步时的最大误差（微秒）
+    pub const NTP_PHASE_LIMIT: i64 = 16_000_000;
+}
+
 /// 传统 BSD 风格间隔定时器 ID。
 pub mod itimer_id {
     use super::c_int;
@@ -339,3 +455,38 @@ pub mod clock_flags {
     /// TIMER_ABSTIME: 将时间解释为绝对时间而非相对时间。
     pub const TIMER_ABSTIME: c_int = 0x01;
 }
+
+/// clock_getcpuclockid / pthread_getcpuclockid 生成的 CPU 时间时钟 ID。
+///
+/// 时钟 ID 为负数，编码方式为 `(!pid << 3) | 类型`，第 2 位表示线程时钟。
+pub mod cpuclock {
+    use super::c_int;
+
+    /// 用户态与内核态 CPU 时间
+    pub const CPUCLOCK_PROF: c_int = 0;
+    /// 用户态 CPU 时间
+    pub const CPUCLOCK_VIRT: c_int = 1;
+    /// 调度器统计的运行时间
+    pub const CPUCLOCK_SCHED: c_int = 2;
+    /// 非法的类型
+    pub const CPUCLOCK_MAX: c_int = 3;
+    /// 类型掩码
+    pub const CPUCLOCK_CLOCK_MASK: c_int = 3;
+    /// 线程时钟标志
+    pub const CPUCLOCK_PERTHREAD_MASK: c_int = 4;
+
+    /// 从时钟 ID 中取出进程号或线程号，为 0 表示调用者自身
+    pub const fn cpuclock_pid(clock: c_int) -> u32 {
+        !(clock >> 3) as u32
+    }
+
+    /// 构造进程 CPU 时间时钟 ID
+    pub const fn make_process_cpuclock(pid: u32, clock: c_int) -> c_int {
+        (!(pid as c_int) << 3) | clock
+    }
+
+    /// 构造线程 CPU 时间时钟 ID
+    pub const fn make_thread_cpuclock(tid: u32, clock: c_int) -> c_int {
+        make_process_cpuclock(tid, clock) | CPUCLOCK_PERTHREAD_MASK
+    }
+}
@@ -0,0 +1,144 @@
+//! userfaultfd 相关常量与结构体
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/userfaultfd.h）。
+//! 本内核只支持缺页（MISSING）模式。
+
+use core::mem::size_of;
+
+use crate::ioctl::{_IOR, _IOWR};
+
+/// API 版本，`UFFDIO_API` 的 `api` 字段必须等于它
+pub const UFFD_API: u64 = 0xAA;
+
+// userfaultfd(2) 的标志
+
+/// 只处理用户态访问引起的缺页
+pub const UFFD_USER_MODE_ONLY: u32 = 1;
+
+// ioctl 编号（`_UFFDIO_*`），也是 `ioctls` 位图中的位号
+
+pub const _UFFDIO_REGISTER: u32 = 0x00;
+pub const _UFFDIO_UNREGISTER: u32 = 0x01;
+pub const _UFFDIO_WAKE: u32 = 0x02;
+pub const _UFFDIO_COPY: u32 = 0x03;
+pub const _UFFDIO_ZEROPAGE: u32 = 0x04;
+pub const _UFFDIO_API: u32 = 0x3F;
+
+/// ioctl 类型字节
+pub const UFFDIO: u32 = 0xAA;
+
+pub const UFFDIO_API: u32 = _IOWR(UFFDIO, _UFFDIO_API, size_of::<UffdioApi>() as u32);
+pub const UFFDIO_REGISTER: u32 =
+    _IOWR(UFFDIO, _UFFDIO_REGISTER, size_of::<UffdioRegister>() as u32);
+pub const UFFDIO_UNREGISTER: u32 =
+    _IOR(UFFDIO, _UFFDIO_UNREGISTER, size_of::<UffdioRange>() as u32);
+pub const UFFDIO_WAKE: u32 = _IOR(UFFDIO, _UFFDIO_WAKE, size_of::<UffdioRange>() as u32);
+pub const UFFDIO_COPY: u32 = _IOWR(UFFDIO, _UFFDIO_COPY, size_of::<UffdioCopy>() as u32);
+pub const UFFDIO_ZEROPAGE: u32 =
+    _IOWR(UFFDIO, _UFFDIO_ZEROPAGE, size_of::<UffdioZeropage>() as u32);
+
+/// 完成 `UFFDIO_API` 之后可用的 ioctl
+pub const UFFD_API_IOCTLS: u64 = 1 << _UFFDIO_REGISTER | 1 << _UFFDIO_UNREGISTER | 1 << _UFFDIO_API;
+/// 已注册区间上可用的 ioctl
+pub const UFFD_API_RANGE_IOCTLS: u64 =
+    1 << _UFFDIO_WAKE | 1 << _UFFDIO_COPY | 1 << _UFFDIO_ZEROPAGE;
+
+const _: () = assert!(UFFDIO_API == 0xc018_aa3f && UFFDIO_COPY == 0xc028_aa03);
+
+// UFFDIO_REGISTER 的模式
+
+pub const UFFDIO_REGISTER_MODE_MISSING: u64 = 1 << 0;
+pub const UFFDIO_REGISTER_MODE_WP: u64 = 1 << 1;
+pub const UFFDIO_REGISTER_MODE_MINOR: u64 = 1 << 2;
+
+// UFFDIO_COPY / UFFDIO_ZEROPAGE 的模式
+
+pub const UFFDIO_COPY_MODE_DONTWAKE: u64 = 1 << 0;
+pub const UFFDIO_COPY_MODE_WP: u64 = 1 << 1;
+pub const UFFDIO_ZEROPAGE_MODE_DONTWAKE: u64 = 1 << 0;
+
+// 事件类型
+
+pub const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+
+// 缺页事件的标志
+
+pub const UFFD_PAGEFAULT_FLAG_WRITE: u64 = 1 << 0;
+pub const UFFD_PAGEFAULT_FLAG_WP: u64 = 1 << 1;
+
+/// `struct uffd_msg` 中缺页事件的参数
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UffdPagefault {
+    /// UFFD_PAGEFAULT_FLAG_*
+    pub flags: u64,
+    /// 缺页地址（按页对齐）
+    pub address: u64,
+    /// 缺页线程的 tid（UFFD_FEATURE_THREAD_ID，本内核不填写）
+    pub ptid: u32,
+    pub _pad: u32,
+}
+
+/// `struct uffd_msg`（32 字节），`read` 读出的事件
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UffdMsg {
+    /// UFFD_EVENT_*
+    pub event: u8,
+    pub reserved1: u8,
+    pub reserved2: u16,
+    pub reserved3: u32,
+    pub pagefault: UffdPagefault,
+}
+
+const _: () = assert!(size_of::<UffdMsg>() == 32);
+
+/// `struct uffdio_api`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UffdioApi {
+    pub api: u64,
+    pub features: u64,
+    pub ioctls: u64,
+}
+
+/// `struct uffdio_range`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UffdioRange {
+    pub start: u64,
+    pub len: u64,
+}
+
+/// `struct uffdio_register`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UffdioRegister {
+    pub range: UffdioRange,
+    pub mode: u64,
+    /// 内核写回：区间上可用的 ioctl
+    pub ioctls: u64,
+}
+
+/// `struct uffdio_copy`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UffdioCopy {
+    pub dst: u64,
+    pub src: u64,
+    pub len: u64,
+    pub mode: u64,
+    /// 内核写回：已复制的字节数或负的错误码
+    pub copy: i64,
+}
+
+/// `struct uffdio_zeropage`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UffdioZeropage {
+    pub range: UffdioRange,
+    pub mode: u64,
+    /// 内核写回：已填充的字节数或负的错误码
+    pub zeropage: i64,
+}
+
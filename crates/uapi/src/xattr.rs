@@ -0,0 +1,24 @@
+//! 扩展属性相关常量
+//!
+//! 对应于 Linux 用户空间 API 定义（include/uapi/linux/xattr.h、include/uapi/linux/limits.h）。
+
+/// 属性必须不存在，否则返回 EEXIST (XATTR_CREATE)
+pub const XATTR_CREATE: u32 = 0x1;
+/// 属性必须已存在，否则返回 ENODATA (XATTR_REPLACE)
+pub const XATTR_REPLACE: u32 = 0x2;
+
+/// 属性名（含命名空间前缀）的最大长度 (XATTR_NAME_MAX)
+pub const XATTR_NAME_MAX: usize = 255;
+/// 属性值的最大长度 (XATTR_SIZE_MAX)
+pub const XATTR_SIZE_MAX: usize = 65536;
+/// listxattr 返回的名字列表的最大长度 (XATTR_LIST_MAX)
+pub const XATTR_LIST_MAX: usize = 65536;
+
+/// 安全模块使用的命名空间
+pub const XATTR_SECURITY_PREFIX: &str = "security.";
+/// 内核自身使用的命名空间（如 POSIX ACL）
+pub const XATTR_SYSTEM_PREFIX: &str = "system.";
+/// 只有 CAP_SYS_ADMIN 可以访问的命名空间
+pub const XATTR_TRUSTED_PREFIX: &str = "trusted.";
+/// 普通用户的命名空间
+pub const XATTR_USER_PREFIX: &str = "user.";
@@ -0,0 +1,578 @@
+//! POSIX ACL
+//!
+//! ACL 保存在扩展属性 `system.posix_acl_access`（访问 ACL）与 `system.posix_acl_default`
+//! （目录的默认 ACL）中，属性值采用 Linux 的 xattr 格式（版本号 + 8 字节表项）。文件系统只负责
+//! 存取属性，磁盘格式不同的文件系统（如 ext4）在自己的 xattr 实现里转换。
+//!
+//! 访问 ACL 的 USER_OBJ、GROUP_OBJ（有 MASK 时为 MASK）与 OTHER 表项和文件模式的 rwx 位
+//! 始终保持一致：
+//!
+//! - 设置访问 ACL 时同步更新模式，能用模式完整表示的 ACL 不单独保存（[`set_acl`]）
+//! - chmod 时同步更新访问 ACL（[`posix_acl_chmod`]）
+//! - 在带默认 ACL 的目录下创建文件时，新文件继承默认 ACL，模式由 ACL 与请求的模式共同决定，
+//!   不再应用 umask（[`posix_acl_create`]）
+
+use alloc::vec::Vec;
+
+use uapi::posix_acl::{
+    ACL_EXECUTE, ACL_GROUP, ACL_GROUP_OBJ, ACL_MASK, ACL_OTHER, ACL_READ, ACL_UNDEFINED_ID,
+    ACL_USER, ACL_USER_OBJ, ACL_WRITE, POSIX_ACL_XATTR_ENTRY_SIZE, POSIX_ACL_XATTR_HEADER_SIZE,
+    POSIX_ACL_XATTR_VERSION, XATTR_NAME_POSIX_ACL_ACCESS, XATTR_NAME_POSIX_ACL_DEFAULT,
+};
+
+use crate::permission::FsCred;
+use crate::{FileMode, FsError, Inode, InodeType, XattrFlags};
+
+/// ACL 表项类型，声明顺序即表项在 ACL 中必须出现的顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AclTag {
+    /// 文件属主
+    UserObj,
+    /// 指定用户
+    User,
+    /// 文件属组
+    GroupObj,
+    /// 指定组
+    Group,
+    /// 指定用户、文件属组与指定组能获得的最大权限
+    Mask,
+    /// 其他用户
+    Other,
+}
+
+impl AclTag {
+    /// 从 xattr 格式中的 `e_tag` 解析
+    pub fn from_raw(tag: u16) -> Option<Self> {
+        match tag {
+            ACL_USER_OBJ => Some(Self::UserObj),
+            ACL_USER => Some(Self::User),
+            ACL_GROUP_OBJ => Some(Self::GroupObj),
+            ACL_GROUP => Some(Self::Group),
+            ACL_MASK => Some(Self::Mask),
+            ACL_OTHER => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// xattr 格式中的 `e_tag`
+    pub fn raw(self) -> u16 {
+        match self {
+            Self::UserObj => ACL_USER_OBJ,
+            Self::User => ACL_USER,
+            Self::GroupObj => ACL_GROUP_OBJ,
+            Self::Group => ACL_GROUP,
+            Self::Mask => ACL_MASK,
+            Self::Other => ACL_OTHER,
+        }
+    }
+
+    /// 表项是否带用户/组 ID
+    pub fn has_id(self) -> bool {
+        matches!(self, Self::User | Self::Group)
+    }
+}
+
+/// ACL 表项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AclEntry {
+    /// 表项类型
+    pub tag: AclTag,
+    /// rwx 权限（ACL_READ / ACL_WRITE / ACL_EXECUTE）
+    pub perm: u16,
+    /// 用户/组 ID，只对 [`AclTag::User`] 与 [`AclTag::Group`] 有意义
+    pub id: u32,
+}
+
+impl AclEntry {
+    /// 创建表项，不带 ID 的类型忽略 `id`
+    pub fn new(tag: AclTag, perm: u16, id: u32) -> Self {
+        let id = if tag.has_id() { id } else { ACL_UNDEFINED_ID };
+        Self { tag, perm, id }
+    }
+}
+
+/// ACL 的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclType {
+    /// 访问 ACL，参与权限检查
+    Access,
+    /// 目录的默认 ACL，由新建的子项继承
+    Default,
+}
+
+impl AclType {
+    /// 保存 ACL 的扩展属性名
+    pub fn xattr_name(self) -> &'static str {
+        match self {
+            Self::Access => XATTR_NAME_POSIX_ACL_ACCESS,
+            Self::Default => XATTR_NAME_POSIX_ACL_DEFAULT,
+        }
+    }
+
+    /// 从扩展属性名识别 ACL 种类
+    pub fn from_xattr_name(name: &str) -> Option<Self> {
+        match name {
+            XATTR_NAME_POSIX_ACL_ACCESS => Some(Self::Access),
+            XATTR_NAME_POSIX_ACL_DEFAULT => Some(Self::Default),
+            _ => None,
+        }
+    }
+}
+
+/// 经过校验的 POSIX ACL
+///
+/// 表项按 USER_OBJ、USER...、GROUP_OBJ、GROUP...、MASK、OTHER 排列，带指定用户或组时
+/// 必须有 MASK。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PosixAcl {
+    entries: Vec<AclEntry>,
+}
+
+const ACL_PERM_MASK: u16 = ACL_READ | ACL_WRITE | ACL_EXECUTE;
+
+impl PosixAcl {
+    /// 由表项构造 ACL，表项不合法时返回 [`FsError::InvalidArgument`]
+    pub fn from_entries(entries: Vec<AclEntry>) -> Result<Self, FsError> {
+        let mut last: Option<AclTag> = None;
+        let mut needs_mask = false;
+        for entry in &entries {
+            if entry.perm & !ACL_PERM_MASK != 0 {
+                return Err(FsError::InvalidArgument);
+            }
+            // 带 ID 的类型可以重复出现，其余类型各至多一个，且整体按类型顺序排列
+            let in_order = match last {
+                None => entry.tag == AclTag::UserObj,
+                Some(prev) => prev < entry.tag || (prev == entry.tag && entry.tag.has_id()),
+            };
+            if !in_order {
+                return Err(FsError::InvalidArgument);
+            }
+            needs_mask |= entry.tag.has_id();
+            last = Some(entry.tag);
+        }
+
+        let has = |tag| entries.iter().any(|e| e.tag == tag);
+        if last != Some(AclTag::Other)
+            || !has(AclTag::GroupObj)
+            || (needs_mask && !has(AclTag::Mask))
+        {
+            return Err(FsError::InvalidArgument);
+        }
+        Ok(Self { entries })
+    }
+
+    /// 与模式 `mode` 等价的最小 ACL
+    pub fn from_mode(mode: u32) -> Self {
+        let perm = |shift: u32| ((mode >> shift) & 0o7) as u16;
+        Self {
+            entries: alloc::vec![
+                AclEntry::new(AclTag::UserObj, perm(6), 0),
+                AclEntry::new(AclTag::GroupObj, perm(3), 0),
+                AclEntry::new(AclTag::Other, perm(0), 0),
+            ],
+        }
+    }
+
+    /// 解析 xattr 格式的 ACL
+    ///
+    /// 没有表项时返回 `None`（表示删除 ACL）；版本不符返回 [`FsError::NotSupported`]，
+    /// 其余格式错误返回 [`FsError::InvalidArgument`]。
+    pub fn from_xattr(value: &[u8]) -> Result<Option<Self>, FsError> {
+        if value.len() < POSIX_ACL_XATTR_HEADER_SIZE {
+            return Err(FsError::InvalidArgument);
+        }
+        let version = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+        if version != POSIX_ACL_XATTR_VERSION {
+            return Err(FsError::NotSupported);
+        }
+        let raw_entries =
+            value[POSIX_ACL_XATTR_HEADER_SIZE..].chunks_exact(POSIX_ACL_XATTR_ENTRY_SIZE);
+        if !raw_entries.remainder().is_empty() {
+            return Err(FsError::InvalidArgument);
+        }
+        if raw_entries.len() == 0 {
+            return Ok(None);
+        }
+
+        let mut entries = Vec::with_capacity(raw_entries.len());
+        for raw in raw_entries {
+            let tag = AclTag::from_raw(u16::from_le_bytes([raw[0], raw[1]]))
+                .ok_or(FsError::InvalidArgument)?;
+            let perm = u16::from_le_bytes([raw[2], raw[3]]);
+            let id = u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]);
+            entries.push(AclEntry::new(tag, perm, id));
+        }
+        Self::from_entries(entries).map(Some)
+    }
+
+    /// 编码为 xattr 格式
+    pub fn to_xattr(&self) -> Vec<u8> {
+        let mut value = Vec::with_capacity(
+            POSIX_ACL_XATTR_HEADER_SIZE + self.entries.len() * POSIX_ACL_XATTR_ENTRY_SIZE,
+        );
+        value.extend_from_slice(&POSIX_ACL_XATTR_VERSION.to_le_bytes());
+        for entry in &self.entries {
+            value.extend_from_slice(&entry.tag.raw().to_le_bytes());
+            value.extend_from_slice(&entry.perm.to_le_bytes());
+            value.extend_from_slice(&entry.id.to_le_bytes());
+        }
+        value
+    }
+
+    /// 全部表项
+    pub fn entries(&self) -> &[AclEntry] {
+        &self.entries
+    }
+
+    /// 是否包含模式无法表示的表项（指定用户、指定组或 MASK）
+    pub fn is_extended(&self) -> bool {
+        self.entries.len() > 3
+    }
+
+    fn entry_mut(&mut self, tag: AclTag) -> Option<&mut AclEntry> {
+        self.entries.iter_mut().find(|e| e.tag == tag)
+    }
+
+    /// ACL 对应的模式 rwx 位，属组位取 MASK（没有 MASK 时取 GROUP_OBJ）
+    pub fn mode(&self) -> u32 {
+        let mut mode = 0;
+        let mut mask = None;
+        for entry in &self.entries {
+            let perm = entry.perm as u32;
+            match entry.tag {
+                AclTag::UserObj => mode |= perm << 6,
+                AclTag::GroupObj => mode |= perm << 3,
+                AclTag::Other => mode |= perm,
+                AclTag::Mask => mask = Some(perm),
+                AclTag::User | AclTag::Group => {}
+            }
+        }
+        match mask {
+            Some(mask) => (mode & !0o070) | (mask << 3),
+            None => mode,
+        }
+    }
+
+    /// 按新模式更新 USER_OBJ、MASK（没有 MASK 时为 GROUP_OBJ）与 OTHER
+    pub fn chmod(&mut self, mode: u32) {
+        let perm = |shift: u32| ((mode >> shift) & 0o7) as u16;
+        let group_tag = if self.entries.iter().any(|e| e.tag == AclTag::Mask) {
+            AclTag::Mask
+        } else {
+            AclTag::GroupObj
+        };
+        for (tag, perm) in [
+            (AclTag::UserObj, perm(6)),
+            (group_tag, perm(3)),
+            (AclTag::Other, perm(0)),
+        ] {
+            if let Some(entry) = self.entry_mut(tag) {
+                entry.perm = perm;
+            }
+        }
+    }
+
+    /// 把继承来的默认 ACL 与新文件请求的模式 `mode` 相互约束
+    ///
+    /// ACL 的 USER_OBJ、MASK（或 GROUP_OBJ）、OTHER 与 `mode` 对应的位取交集，返回新文件的模式
+    /// （rwx 以外的位保持不变）。
+    pub fn create_masq(&mut self, mode: u32) -> u32 {
+        let mut rwx = mode & 0o777;
+        let group_tag = if self.entries.iter().any(|e| e.tag == AclTag::Mask) {
+            AclTag::Mask
+        } else {
+            AclTag::GroupObj
+        };
+        for (tag, shift) in [(AclTag::UserObj, 6), (group_tag, 3), (AclTag::Other, 0)] {
+            if let Some(entry) = self.entry_mut(tag) {
+                entry.perm &= ((rwx >> shift) & 0o7) as u16;
+                rwx = (rwx & !(0o7 << shift)) | ((entry.perm as u32) << shift);
+            }
+        }
+        (mode & !0o777) | rwx
+    }
+
+    /// 按 ACL 判断非属主调用者能否获得 `want`（rwx 位）
+    ///
+    /// `owner` / `group` 为文件属主与属组。指定用户与组的权限受 MASK 限制，匹配到组但
+    /// 没有一个组给出全部权限时拒绝，什么都没匹配时取 OTHER。
+    pub fn permission(&self, owner: u32, group: u32, cred: &FsCred, want: u32) -> bool {
+        let want = want as u16 & ACL_PERM_MASK;
+        let mask = self
+            .entries
+            .iter()
+            .find(|e| e.tag == AclTag::Mask)
+            .map_or(ACL_PERM_MASK, |e| e.perm);
+        let granted = |perm: u16| perm & want == want;
+
+        let mut group_found = false;
+        for entry in &self.entries {
+            match entry.tag {
+                AclTag::UserObj if owner == cred.fsuid => return granted(entry.perm),
+                AclTag::User if entry.id == cred.fsuid => return granted(entry.perm & mask),
+                AclTag::GroupObj | AclTag::Group => {
+                    let gid = if entry.tag == AclTag::GroupObj {
+                        group
+                    } else {
+                        entry.id
+                    };
+                    if cred.in_group(gid) {
+                        group_found = true;
+                        if granted(entry.perm) {
+                            return granted(entry.perm & mask);
+                        }
+                    }
+                }
+                AclTag::Other => return !group_found && granted(entry.perm),
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+/// 读取 inode 的 ACL，没有 ACL 或文件系统不支持扩展属性时返回 `None`
+pub fn get_acl(inode: &dyn Inode, ty: AclType) -> Result<Option<PosixAcl>, FsError> {
+    match inode.getxattr(ty.xattr_name()) {
+        Ok(value) => PosixAcl::from_xattr(&value),
+        Err(FsError::NoData) | Err(FsError::NotSupported) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn remove_acl_xattr(inode: &dyn Inode, ty: AclType) -> Result<(), FsError> {
+    match inode.removexattr(ty.xattr_name()) {
+        Err(FsError::NoData) => Ok(()),
+        result => result,
+    }
+}
+
+/// 设置或删除（`acl` 为 `None`）inode 的 ACL
+///
+/// 只有属主或持有 CAP_FOWNER 的调用者可以修改 ACL，否则返回 [`FsError::NotPermitted`]；
+/// 默认 ACL 只能设置在目录上。设置访问 ACL 会同步更新文件模式，调用者不在文件属组且没有
+/// CAP_FSETID 时清除 set-group-ID 位。
+pub fn set_acl(
+    inode: &dyn Inode,
+    ty: AclType,
+    acl: Option<&PosixAcl>,
+    cred: &FsCred,
+) -> Result<(), FsError> {
+    let meta = inode.metadata()?;
+    if !cred.owner_or_capable(meta.uid) {
+        return Err(FsError::NotPermitted);
+    }
+    if ty == AclType::Default && meta.inode_type != InodeType::Directory {
+        return match acl {
+            Some(_) => Err(FsError::PermissionDenied),
+            None => Ok(()),
+        };
+    }
+    let Some(acl) = acl else {
+        return remove_acl_xattr(inode, ty);
+    };
+    if ty == AclType::Default {
+        return inode.setxattr(ty.xattr_name(), &acl.to_xattr(), XattrFlags::empty());
+    }
+
+    let mut mode = (meta.mode.bits() & !0o777) | acl.mode();
+    if !cred.in_group(meta.gid) && !cred.fsetid {
+        mode &= !FileMode::S_ISGID.bits();
+    }
+    if acl.is_extended() {
+        inode.setxattr(ty.xattr_name(), &acl.to_xattr(), XattrFlags::empty())?;
+    } else {
+        remove_acl_xattr(inode, ty)?;
+    }
+    if mode != meta.mode.bits() {
+        inode.chmod(FileMode::from_bits_truncate(mode))?;
+    }
+    Ok(())
+}
+
+/// 修改 inode 的模式并同步访问 ACL
+pub fn posix_acl_chmod(inode: &dyn Inode, mode: FileMode) -> Result<(), FsError> {
+    inode.chmod(mode)?;
+    let Some(mut acl) = get_acl(inode, AclType::Access)? else {
+        return Ok(());
+    };
+    acl.chmod(mode.bits());
+    inode.setxattr(
+        AclType::Access.xattr_name(),
+        &acl.to_xattr(),
+        XattrFlags::empty(),
+    )
+}
+
+/// 新建 inode 从父目录继承的 ACL，由 [`posix_acl_create`] 计算
+#[derive(Debug, Default)]
+pub struct AclInherit {
+    default: Option<PosixAcl>,
+    access: Option<PosixAcl>,
+}
+
+impl AclInherit {
+    /// 把继承的 ACL 写到新建的 `inode` 上
+    pub fn apply(&self, inode: &dyn Inode) -> Result<(), FsError> {
+        for (ty, acl) in [
+            (AclType::Default, &self.default),
+            (AclType::Access, &self.access),
+        ] {
+            if let Some(acl) = acl {
+                inode.setxattr(ty.xattr_name(), &acl.to_xattr(), XattrFlags::empty())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 计算在目录 `dir` 下以模式 `mode`（含文件类型位）新建 inode 时的实际模式与继承的 ACL
+///
+/// 目录没有默认 ACL 时只应用 `umask`；有默认 ACL 时忽略 `umask`，由默认 ACL 约束模式，
+/// 新建的子目录还会继承默认 ACL 本身。符号链接不参与 ACL。
+pub fn posix_acl_create(
+    dir: &dyn Inode,
+    mode: FileMode,
+    umask: u32,
+) -> Result<(FileMode, AclInherit), FsError> {
+    let file_type = mode & FileMode::S_IFMT;
+    let default = if file_type.bits() == FileMode::S_IFLNK.bits() {
+        None
+    } else {
+        get_acl(dir, AclType::Default)?
+    };
+    let Some(default) = default else {
+        let mode = FileMode::from_bits_truncate(mode.bits() & !(umask & 0o777));
+        return Ok((mode, AclInherit::default()));
+    };
+
+    let mut access = default.clone();
+    let mode = FileMode::from_bits_truncate(access.create_masq(mode.bits()));
+    let inherit = AclInherit {
+        default: (file_type.bits() == FileMode::S_IFDIR.bits()).then_some(default),
+        access: access.is_extended().then_some(access),
+    };
+    Ok((mode, inherit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn cred(uid: u32, gid: u32) -> FsCred {
+        FsCred {
+            fsuid: uid,
+            fsgid: gid,
+            dac_override: false,
+            dac_read_search: false,
+            fowner: false,
+            fsetid: false,
+        }
+    }
+
+    fn extended() -> PosixAcl {
+        PosixAcl::from_entries(vec![
+            AclEntry::new(AclTag::UserObj, 0o7, 0),
+            AclEntry::new(AclTag::User, 0o6, 1000),
+            AclEntry::new(AclTag::GroupObj, 0o5, 0),
+            AclEntry::new(AclTag::Group, 0o7, 50),
+            AclEntry::new(AclTag::Mask, 0o6, 0),
+            AclEntry::new(AclTag::Other, 0o4, 0),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_xattr_round_trip() {
+        let acl = extended();
+        let value = acl.to_xattr();
+        assert_eq!(value.len(), 4 + 6 * 8);
+        assert_eq!(PosixAcl::from_xattr(&value), Ok(Some(acl)));
+        assert_eq!(PosixAcl::from_xattr(&2u32.to_le_bytes()), Ok(None));
+        assert_eq!(
+            PosixAcl::from_xattr(&1u32.to_le_bytes()),
+            Err(FsError::NotSupported)
+        );
+        assert_eq!(
+            PosixAcl::from_xattr(&value[..9]),
+            Err(FsError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        let base = |tags: &[AclTag]| {
+            PosixAcl::from_entries(tags.iter().map(|&t| AclEntry::new(t, 0o4, 7)).collect())
+        };
+        use AclTag::*;
+        assert!(base(&[UserObj, GroupObj, Other]).is_ok());
+        assert!(base(&[UserObj, GroupObj, Mask, Other]).is_ok());
+        // 缺少 MASK
+        assert!(base(&[UserObj, User, GroupObj, Other]).is_err());
+        // 顺序错误、重复、缺项
+        assert!(base(&[GroupObj, UserObj, Other]).is_err());
+        assert!(base(&[UserObj, UserObj, GroupObj, Other]).is_err());
+        assert!(base(&[UserObj, Other]).is_err());
+        assert!(base(&[UserObj, GroupObj, Other, Other]).is_err());
+        assert!(
+            PosixAcl::from_entries(vec![
+                AclEntry::new(UserObj, 0o10, 0),
+                AclEntry::new(GroupObj, 0, 0),
+                AclEntry::new(Other, 0, 0),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_mode_and_chmod() {
+        let mut acl = extended();
+        assert!(acl.is_extended());
+        assert_eq!(acl.mode(), 0o764);
+
+        acl.chmod(0o750);
+        assert_eq!(acl.mode(), 0o750);
+        // GROUP_OBJ 保持不变，只改 MASK
+        assert_eq!(acl.entries()[2].perm, 0o5);
+        assert_eq!(acl.entries()[4].perm, 0o5);
+
+        let minimal = PosixAcl::from_mode(0o640);
+        assert!(!minimal.is_extended());
+        assert_eq!(minimal.mode(), 0o640);
+    }
+
+    #[test]
+    fn test_create_masq() {
+        let mut acl = extended();
+        let mode = acl.create_masq(0o100644);
+        assert_eq!(mode, 0o100644);
+        assert_eq!(acl.mode(), 0o644);
+        // 指定组的表项本身不变，只由 MASK 约束
+        assert_eq!(acl.entries()[3].perm, 0o7);
+
+        let mut minimal = PosixAcl::from_mode(0o750);
+        assert_eq!(minimal.create_masq(0o666), 0o640);
+    }
+
+    #[test]
+    fn test_permission() {
+        let acl = extended();
+        let rw = (ACL_READ | ACL_WRITE) as u32;
+        let x = ACL_EXECUTE as u32;
+        // 指定用户：rw 受 MASK rw 限制后仍可读写
+        assert!(acl.permission(0, 100, &cred(1000, 1), rw));
+        assert!(!acl.permission(0, 100, &cred(1000, 1), x));
+        // 指定组：rwx 被 MASK 截为 rw
+        assert!(acl.permission(0, 100, &cred(2000, 50), rw));
+        assert!(!acl.permission(0, 100, &cred(2000, 50), x));
+        // 其他用户
+        assert!(acl.permission(0, 100, &cred(2000, 1), ACL_READ as u32));
+        assert!(!acl.permission(0, 100, &cred(2000, 1), rw));
+        // 文件属组：r-x 被 MASK 截为 r--
+        assert!(acl.permission(0, 100, &cred(2000, 100), ACL_READ as u32));
+        assert!(!acl.permission(0, 100, &cred(2000, 100), x));
+        // 属主
+        assert!(acl.permission(2000, 100, &cred(2000, 1), rw | x));
+    }
+}
@@ -1,15 +1,41 @@
 //! 目录项（Dentry）与全局缓存
 //!
 //! 该模块实现了 VFS 路径层的核心组件，提供目录树结构管理和路径到 Inode 的映射缓存。
+//!
+//! # 缓存回收
+//!
+//! 查找过的目录项挂在父目录项的子项表中，由父目录项持有。[`DentryCache`] 按加入顺序把它们
+//! 排成 LRU 队列，并估计它们占用的内存；超过内存预算（[`DentryCache::set_budget`]）或
+//! 内存紧张（[`DentryCache::shrink`]）时从队首回收：
+//!
+//! - 查找命中过的目录项清除引用标记后移到队尾（第二次机会）；
+//! - 目录树之外还有引用（打开的文件、当前目录等）或还有缓存的子项的目录项移到队尾；
+//! - 其余的从父目录项中摘下，下次查找时重新经由 inode 查找。
+//!
+//! # 负目录项
+//!
+//! 在目录中查找不到的名字记为负目录项，再次查找时直接返回 [`FsError::NotFound`](crate::FsError::NotFound)，
+//! 不再访问文件系统（如 shell 在 `PATH` 中逐个目录查找命令）。负目录项按目录 inode 的地址
+//! 索引，只在 [`Inode::cache_negative`] 为真的目录中缓存；在目录中创建或移入同名文件时
+//! 由 [`fsnotify_create`](crate::fsnotify_create) / [`fsnotify_move`](crate::fsnotify_move)
+//! 使其失效。负目录项与正目录项排在同一个 LRU 队列中。
 
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, VecDeque};
 use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use core::fmt;
-use sync::SpinLock;
+use core::mem::size_of;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use sync::{RwLock, RwLockFairness, SpinLock};
 
 use crate::Inode;
 
+/// 目录项缓存默认的内存预算（字节）
+pub const DEFAULT_DENTRY_BUDGET: usize = 4 * 1024 * 1024;
+
+/// 超出预算时每次最多扫描的 LRU 项数，避免所有目录项都在使用时每次插入都扫描整个队列
+const BUDGET_SCAN_BATCH: usize = 64;
+
 /// 目录项（Dentry）
 ///
 /// 表示路径中的一个组件，缓存文件名到 inode 的映射
@@ -26,8 +52,8 @@ pub struct Dentry {
     /// 子 dentry 映射（文件名 -> dentry）
     children: SpinLock<BTreeMap<String, Arc<Dentry>>>,
 
-    /// 如果此 dentry 是挂载点，指向挂载的根 dentry
-    mount_point: SpinLock<Option<Weak<Dentry>>>,
+    /// 上次 LRU 扫描之后被查找命中过
+    referenced: AtomicBool,
 }
 
 impl fmt::Debug for Dentry {
@@ -54,7 +80,7 @@ impl Dentry {
             inode,
             parent: SpinLock::new(Weak::new()),
             children: SpinLock::new(BTreeMap::new()),
-            mount_point: SpinLock::new(None),
+            referenced: AtomicBool::new(false),
         });
 
         dentry.inode.set_dentry(Arc::downgrade(&dentry));
@@ -74,7 +100,9 @@ impl Dentry {
 
     /// 查找子 dentry
     pub fn lookup_child(&self, name: &str) -> Option<Arc<Dentry>> {
-        self.children.lock().get(name).cloned()
+        let child = self.children.lock().get(name).cloned()?;
+        child.referenced.store(true, Ordering::Relaxed);
+        Some(child)
     }
 
     /// 添加子 dentry
@@ -120,64 +148,371 @@ impl Dentry {
             String::from("/") + &components.join("/")
         }
     }
+}
+
+// 全局 dentry 缓存实例
+lazy_static::lazy_static! {
+    /// 全局 Dentry 缓存
+    pub static ref DENTRY_CACHE: DentryCache = DentryCache::new();
+}
+
+/// 目录 inode 在负目录项表中的键
+fn inode_key(inode: &dyn Inode) -> usize {
+    inode as *const dyn Inode as *const () as usize
+}
+
+/// 一个目录中的负目录项
+struct NegativeDir {
+    /// 持有弱引用，目录 inode 的地址在负目录项存在期间不会被复用
+    _dir: Weak<dyn Inode>,
+    /// 文件名 -> 上次 LRU 扫描之后是否被查找命中过
+    names: BTreeMap<String, bool>,
+}
+
+/// LRU 队列中的一项
+enum LruEntry {
+    /// 目录项与它在路径表中的键，回收时不必再分配内存拼接路径
+    Positive(Weak<Dentry>, String),
+    /// 目录 inode 的键与文件名
+    Negative(usize, String),
+}
+
+struct DentryLru {
+    /// 按加入顺序排列，每项附带估计占用的内存
+    queue: VecDeque<(LruEntry, usize)>,
+    /// 目录 inode 的键 -> 该目录中的负目录项
+    negative: BTreeMap<usize, NegativeDir>,
+    /// 队列中各项估计占用的内存之和
+    bytes: usize,
+    /// 队列中的正目录项数
+    nr_positive: usize,
+    /// 负目录项数
+    nr_negative: usize,
+}
 
-    /// 设置挂载点
-    pub fn set_mount(&self, mounted_root: &Arc<Dentry>) {
-        *self.mount_point.lock() = Some(Arc::downgrade(mounted_root));
+impl DentryLru {
+    fn push(&mut self, entry: LruEntry, cost: usize) {
+        if matches!(entry, LruEntry::Positive(..)) {
+            self.nr_positive += 1;
+        }
+        self.bytes += cost;
+        self.queue.push_back((entry, cost));
     }
 
-    /// 清除挂载点
-    pub fn clear_mount(&self) {
-        *self.mount_point.lock() = None;
+    /// 队列中的一个正目录项被移除
+    fn forget_positive(&mut self, cost: usize) {
+        self.nr_positive -= 1;
+        self.bytes -= cost;
     }
 
-    /// 获取挂载的根 dentry（如果有）
-    pub fn get_mount(&self) -> Option<Arc<Dentry>> {
-        self.mount_point.lock().as_ref()?.upgrade()
+    /// 移除负目录项，返回它是否存在
+    fn remove_negative(&mut self, key: usize, name: &str) -> bool {
+        let Some(dir) = self.negative.get_mut(&key) else {
+            return false;
+        };
+        if dir.names.remove(name).is_none() {
+            return false;
+        }
+        if dir.names.is_empty() {
+            self.negative.remove(&key);
+        }
+        self.nr_negative -= 1;
+        true
     }
 }
 
-// 全局 dentry 缓存实例
-lazy_static::lazy_static! {
-    /// 全局 Dentry 缓存
-    pub static ref DENTRY_CACHE: DentryCache = DentryCache::new();
+/// 回收一个正目录项的结果
+enum Detach {
+    /// 已从目录树中摘下，由调用者在锁外释放
+    Detached(Arc<Dentry>),
+    /// 正在使用或最近被引用，留在队列中
+    Keep,
+    /// 已不在目录树中
+    Gone,
+}
+
+/// 目录项缓存的统计（`/proc/sys/fs/dentry-state`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DentryStats {
+    /// 缓存的正目录项数
+    pub nr_dentry: usize,
+    /// 其中只被目录树引用、可以回收的目录项数
+    pub nr_unused: usize,
+    /// 负目录项数
+    pub nr_negative: usize,
+    /// 估计占用的内存（字节）
+    pub bytes: usize,
+    /// 内存预算（字节）
+    pub budget: usize,
 }
 
 /// 全局 Dentry 缓存
 pub struct DentryCache {
     /// 路径 -> dentry 的弱引用映射
-    cache: SpinLock<BTreeMap<String, Weak<Dentry>>>,
+    ///
+    /// 查找远多于插入，使用写者优先的读写锁，避免路径解析密集时插入者饥饿。
+    cache: RwLock<BTreeMap<String, Weak<Dentry>>>,
+    /// 回收队列与负目录项
+    lru: SpinLock<DentryLru>,
+    /// 内存预算（字节）
+    budget: AtomicUsize,
 }
 
 impl DentryCache {
     /// 创建新的缓存
     pub const fn new() -> Self {
         Self {
-            cache: SpinLock::new(BTreeMap::new()),
+            cache: RwLock::with_fairness(BTreeMap::new(), RwLockFairness::WriterPreferred),
+            lru: SpinLock::new(DentryLru {
+                queue: VecDeque::new(),
+                negative: BTreeMap::new(),
+                bytes: 0,
+                nr_positive: 0,
+                nr_negative: 0,
+            }),
+            budget: AtomicUsize::new(DEFAULT_DENTRY_BUDGET),
         }
     }
 
     /// 从缓存中查找 dentry
+    ///
+    /// 对应的 dentry 已被释放时，在不释放锁的情况下升级为写锁并移除失效条目。
     pub fn lookup(&self, path: &str) -> Option<Arc<Dentry>> {
-        let cache = self.cache.lock();
+        let cache = self.cache.upgradeable_read();
         let weak = cache.get(path)?;
-        weak.upgrade()
+        if let Some(dentry) = weak.upgrade() {
+            dentry.referenced.store(true, Ordering::Relaxed);
+            return Some(dentry);
+        }
+        cache.upgrade().remove(path);
+        None
     }
 
     /// 插入 dentry 到缓存
+    ///
+    /// 超出内存预算时回收最久未使用的目录项。
     pub fn insert(&self, dentry: &Arc<Dentry>) {
         let path = dentry.full_path();
-        self.cache.lock().insert(path, Arc::downgrade(dentry));
+        // 目录项本身、文件名与路径表、队列中各一份完整路径
+        let cost = size_of::<Dentry>() + dentry.name.len() + 2 * path.len();
+        self.cache
+            .write()
+            .insert(path.clone(), Arc::downgrade(dentry));
+        self.lru
+            .lock()
+            .push(LruEntry::Positive(Arc::downgrade(dentry), path), cost);
+        self.enforce_budget();
     }
 
     /// 从缓存中移除
     pub fn remove(&self, path: &str) {
-        self.cache.lock().remove(path);
+        self.cache.write().remove(path);
     }
 
     /// 清空缓存
+    ///
+    /// 只清空路径表与负目录项，目录树中的目录项仍由父目录项持有。
     pub fn clear(&self) {
-        self.cache.lock().clear();
+        self.cache.write().clear();
+        let mut lru = self.lru.lock();
+        lru.negative.clear();
+        lru.nr_negative = 0;
+    }
+
+    /// 目录 `dir` 中是否缓存了 `name` 不存在
+    pub fn lookup_negative(&self, dir: &dyn Inode, name: &str) -> bool {
+        let mut lru = self.lru.lock();
+        match lru
+            .negative
+            .get_mut(&inode_key(dir))
+            .and_then(|d| d.names.get_mut(name))
+        {
+            Some(referenced) => {
+                *referenced = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 记录目录 `dir` 中不存在 `name`
+    ///
+    /// `dir` 不允许缓存负目录项（[`Inode::cache_negative`]）时什么都不做。
+    pub fn insert_negative(&self, dir: &Arc<dyn Inode>, name: &str) {
+        if !dir.cache_negative() {
+            return;
+        }
+        let key = inode_key(dir.as_ref());
+        {
+            let mut lru = self.lru.lock();
+            let negative = lru.negative.entry(key).or_insert_with(|| NegativeDir {
+                _dir: Arc::downgrade(dir),
+                names: BTreeMap::new(),
+            });
+            if negative.names.insert(String::from(name), false).is_some() {
+                return;
+            }
+            lru.nr_negative += 1;
+            // 队列项与负目录项表各存一份文件名
+            let cost = size_of::<(LruEntry, usize)>() + 2 * name.len();
+            lru.push(LruEntry::Negative(key, String::from(name)), cost);
+        }
+        self.enforce_budget();
+    }
+
+    /// 目录 `dir` 中出现了 `name`，丢弃对应的负目录项
+    pub fn forget_negative(&self, dir: &dyn Inode, name: &str) {
+        self.lru.lock().remove_negative(inode_key(dir), name);
+    }
+
+    /// 内存预算（字节）
+    pub fn budget(&self) -> usize {
+        self.budget.load(Ordering::Relaxed)
+    }
+
+    /// 设置内存预算（字节），立即回收超出的部分
+    pub fn set_budget(&self, bytes: usize) {
+        self.budget.store(bytes, Ordering::Relaxed);
+        let len = self.lru.lock().queue.len();
+        self.scan(usize::MAX, bytes, 2 * len);
+    }
+
+    /// 统计信息
+    pub fn stats(&self) -> DentryStats {
+        let lru = self.lru.lock();
+        let nr_unused = lru
+            .queue
+            .iter()
+            .filter(
+                |(entry, _)| matches!(entry, LruEntry::Positive(weak, _) if weak.strong_count() == 1),
+            )
+            .count();
+        DentryStats {
+            nr_dentry: lru.nr_positive,
+            nr_unused,
+            nr_negative: lru.nr_negative,
+            bytes: lru.bytes,
+            budget: self.budget(),
+        }
+    }
+
+    /// 可以回收的缓存项数（估计值）
+    pub fn count_reclaimable(&self) -> usize {
+        self.lru.try_lock().map_or(0, |lru| lru.queue.len())
+    }
+
+    /// 内存紧张时回收至多 `nr` 项，返回回收的项数
+    ///
+    /// 可能在帧分配路径上被调用，只尝试加锁。最多扫描两遍队列：
+    /// 第一遍清除引用标记的项在第二遍可以被回收。
+    pub fn shrink(&self, nr: usize) -> usize {
+        let Some(len) = self.lru.try_lock().map(|lru| lru.queue.len()) else {
+            return 0;
+        };
+        self.scan(nr, 0, 2 * len)
+    }
+
+    /// 超出内存预算时回收一批目录项
+    fn enforce_budget(&self) {
+        let budget = self.budget();
+        if self.lru.lock().bytes > budget {
+            self.scan(usize::MAX, budget, BUDGET_SCAN_BATCH);
+        }
+    }
+
+    /// 从 LRU 队首回收，直到回收了 `nr` 项、估计内存不超过 `target` 或扫描了 `max_scan` 项
+    fn scan(&self, nr: usize, target: usize, max_scan: usize) -> usize {
+        let mut freed = 0;
+        for _ in 0..max_scan {
+            if freed >= nr {
+                break;
+            }
+            let Some(mut lru) = self.lru.try_lock() else {
+                break;
+            };
+            if lru.bytes <= target {
+                break;
+            }
+            let Some((entry, cost)) = lru.queue.pop_front() else {
+                break;
+            };
+            let (weak, path) = match entry {
+                LruEntry::Negative(key, name) => {
+                    let referenced = lru
+                        .negative
+                        .get_mut(&key)
+                        .and_then(|d| d.names.get_mut(&name))
+                        .map(|r| core::mem::replace(r, false));
+                    match referenced {
+                        Some(true) => lru.queue.push_back((LruEntry::Negative(key, name), cost)),
+                        Some(false) => {
+                            lru.remove_negative(key, &name);
+                            lru.bytes -= cost;
+                            freed += 1;
+                        }
+                        // 已经失效
+                        None => lru.bytes -= cost,
+                    }
+                    continue;
+                }
+                LruEntry::Positive(weak, path) => (weak, path),
+            };
+            let Some(dentry) = weak.upgrade() else {
+                lru.forget_positive(cost);
+                continue;
+            };
+            match self.try_detach(dentry, &path) {
+                Detach::Detached(dentry) => {
+                    lru.forget_positive(cost);
+                    freed += 1;
+                    // 释放目录项可能连带释放 inode，在锁外进行
+                    drop(lru);
+                    drop(dentry);
+                }
+                Detach::Keep => lru.queue.push_back((LruEntry::Positive(weak, path), cost)),
+                Detach::Gone => lru.forget_positive(cost),
+            }
+        }
+        freed
+    }
+
+    /// 把未被使用的目录项从目录树与路径表（键为 `path`）中摘下
+    ///
+    /// 可能在帧分配路径上被调用，不分配内存，只尝试加锁。
+    fn try_detach(&self, dentry: Arc<Dentry>, path: &str) -> Detach {
+        let Some(parent) = dentry.parent() else {
+            return Detach::Gone;
+        };
+        if dentry.referenced.swap(false, Ordering::Relaxed) {
+            return Detach::Keep;
+        }
+        let Some(mut siblings) = parent.children.try_lock() else {
+            return Detach::Keep;
+        };
+        match siblings.get(&dentry.name) {
+            Some(child) if Arc::ptr_eq(child, &dentry) => {}
+            // 已被删除或替换
+            _ => return Detach::Gone,
+        }
+        // 除子项表与这里之外还有引用，或者还缓存着子项
+        let busy = Arc::strong_count(&dentry) > 2
+            || !dentry.children.try_lock().is_some_and(|c| c.is_empty());
+        if busy {
+            return Detach::Keep;
+        }
+        siblings.remove(&dentry.name);
+        drop(siblings);
+
+        // 拿不到锁时留下失效条目，由下次查找时移除
+        if let Some(mut cache) = self.cache.try_write() {
+            let same = cache
+                .get(path)
+                .is_some_and(|weak| core::ptr::eq(weak.as_ptr(), Arc::as_ptr(&dentry)));
+            if same {
+                cache.remove(path);
+            }
+        }
+        Detach::Detached(dentry)
     }
 }
 
@@ -186,3 +521,119 @@ impl Default for DentryCache {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InodeType;
+    use crate::impls::inotify_file::tests::dummy;
+    use alloc::format;
+    use alloc::vec::Vec;
+    use sync::mock::init_arch_ops;
+
+    /// 在 `parent` 下创建 `n` 个文件目录项并加入 `cache`
+    fn populate(cache: &DentryCache, parent: &Arc<Dentry>, n: usize) -> Vec<Weak<Dentry>> {
+        (0..n)
+            .map(|i| {
+                let child = Dentry::new(format!("f{}", i), dummy(InodeType::File));
+                parent.add_child(child.clone());
+                cache.insert(&child);
+                Arc::downgrade(&child)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dentry_budget_evicts_unused() {
+        init_arch_ops();
+        let cache = DentryCache::new();
+        let root = Dentry::new(String::from("/"), dummy(InodeType::Directory));
+        let children = populate(&cache, &root, 8);
+        assert_eq!(cache.stats().nr_dentry, 8);
+        assert_eq!(cache.stats().nr_unused, 8);
+
+        // 打开的文件与最近查找过的目录项留下，其余按 LRU 顺序回收
+        let held = children[0].upgrade().unwrap();
+        assert!(root.lookup_child("f1").is_some());
+        let per_entry = cache.stats().bytes / 8;
+        cache.set_budget(3 * per_entry);
+
+        let stats = cache.stats();
+        assert_eq!(stats.nr_dentry, 3);
+        assert!(stats.bytes <= 3 * per_entry);
+        assert!(root.lookup_child("f0").is_some());
+        assert!(root.lookup_child("f1").is_some());
+        assert!(root.lookup_child("f2").is_none());
+        assert!(root.lookup_child("f7").is_some());
+        assert!(cache.lookup("/f2").is_none());
+        assert!(children[2].upgrade().is_none());
+
+        // 内存紧张时最近被引用过的也在第二遍扫描中回收，使用中的除外
+        drop(held);
+        let held = children[7].upgrade().unwrap();
+        assert_eq!(cache.shrink(usize::MAX), 2);
+        assert_eq!(cache.stats().nr_dentry, 1);
+        assert!(root.lookup_child("f7").is_some());
+        drop(held);
+    }
+
+    #[test]
+    fn test_dentry_keeps_directories_with_children() {
+        init_arch_ops();
+        let cache = DentryCache::new();
+        let root = Dentry::new(String::from("/"), dummy(InodeType::Directory));
+        let dir = Dentry::new(String::from("dir"), dummy(InodeType::Directory));
+        root.add_child(dir.clone());
+        cache.insert(&dir);
+        populate(&cache, &dir, 2);
+        drop(dir);
+
+        // 目录排在队首，但要等叶子回收之后才能回收
+        assert_eq!(cache.shrink(2), 2);
+        assert!(root.lookup_child("dir").is_some());
+        assert_eq!(cache.shrink(usize::MAX), 1);
+        assert!(root.lookup_child("dir").is_none());
+        assert_eq!(
+            cache.stats(),
+            DentryStats {
+                budget: DEFAULT_DENTRY_BUDGET,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_negative_dentry() {
+        init_arch_ops();
+        let cache = DentryCache::new();
+        let dir = dummy(InodeType::Directory);
+        let other = dummy(InodeType::Directory);
+
+        cache.insert_negative(&dir, "ls");
+        cache.insert_negative(&dir, "ls");
+        assert_eq!(cache.stats().nr_negative, 1);
+        assert!(cache.lookup_negative(dir.as_ref(), "ls"));
+        assert!(!cache.lookup_negative(dir.as_ref(), "cat"));
+        assert!(!cache.lookup_negative(other.as_ref(), "ls"));
+
+        // 文件系统不允许时不缓存
+        cache.insert_negative(&dummy(InodeType::File), "x");
+        assert_eq!(cache.stats().nr_negative, 1);
+
+        // 创建后失效
+        cache.forget_negative(dir.as_ref(), "ls");
+        assert!(!cache.lookup_negative(dir.as_ref(), "ls"));
+        assert_eq!(cache.stats().nr_negative, 0);
+
+        // 被查找过的负目录项在第二遍扫描中回收
+        cache.insert_negative(&dir, "a");
+        cache.insert_negative(&dir, "b");
+        assert!(cache.lookup_negative(dir.as_ref(), "b"));
+        assert_eq!(cache.shrink(1), 1);
+        assert!(!cache.lookup_negative(dir.as_ref(), "a"));
+        assert!(cache.lookup_negative(dir.as_ref(), "b"));
+        assert_eq!(cache.shrink(usize::MAX), 1);
+        assert_eq!(cache.stats().nr_negative, 0);
+        assert_eq!(cache.stats().bytes, 0);
+    }
+}
@@ -19,6 +19,32 @@ pub mod chrdev_major {
     pub const MISC: u32 = 10;
     /// /dev/input/*
     pub const INPUT: u32 = 13;
+    /// /dev/pts/*（UNIX98 PTY 从设备）
+    pub const PTY_SLAVE: u32 = 136;
+}
+
+/// MEM major 下的 minor 号
+pub mod mem_minor {
+    /// /dev/null
+    pub const NULL: u32 = 3;
+    /// /dev/zero
+    pub const ZERO: u32 = 5;
+    /// /dev/random
+    pub const RANDOM: u32 = 8;
+    /// /dev/urandom
+    pub const URANDOM: u32 = 9;
+    /// /dev/kmsg
+    pub const KMSG: u32 = 11;
+}
+
+/// CONSOLE major 下的 minor 号
+pub mod console_minor {
+    /// /dev/tty（当前进程的控制终端）
+    pub const TTY: u32 = 0;
+    /// /dev/console
+    pub const CONSOLE: u32 = 1;
+    /// /dev/ptmx（PTY 主设备复用器）
+    pub const PTMX: u32 = 2;
 }
 
 /// MISC 设备 minor 号
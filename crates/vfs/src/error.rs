@@ -22,6 +22,8 @@ pub enum FsError {
     // 权限相关
     /// 权限被拒绝 (-EACCES)
     PermissionDenied,
+    /// 操作不被允许，如非属主修改 ACL (-EPERM)
+    NotPermitted,
 
     // 文件描述符相关
     /// 无效的文件描述符 (-EBADF)
@@ -44,12 +46,20 @@ pub enum FsError {
     IoError,
     /// 设备不存在 (-ENODEV)
     NoDevice,
+    /// 设备或地址不存在 (-ENXIO)
+    NoDeviceOrAddress,
+    /// 不能跨文件系统链接或重命名 (-EXDEV)
+    CrossDevice,
+    /// 扩展属性不存在 (-ENODATA)
+    NoData,
 
     // 管道相关
     /// 管道破裂 (-EPIPE)
     BrokenPipe,
     /// 非阻塞操作将阻塞 (-EAGAIN)
     WouldBlock,
+    /// 阻塞操作被信号打断 (-EINTR)
+    Interrupted,
 
     // 网络相关
     /// 套接字未连接 (-ENOTCONN)
@@ -62,18 +72,24 @@ pub enum FsError {
     TooManyLinks,
     /// 符号链接层级过多 (-ELOOP)
     TooManySymlinks,
+    /// 等待会形成死锁 (-EDEADLK)
+    Deadlock,
 }
 
 impl FsError {
     /// 转换为系统调用错误码（负数）
     pub fn to_errno(&self) -> isize {
         match self {
+            FsError::NotPermitted => -1,
             FsError::NotFound => -2,
+            FsError::Interrupted => -4,
             FsError::IoError => -5,
+            FsError::NoDeviceOrAddress => -6,
             FsError::BadFileDescriptor => -9,
             FsError::WouldBlock => -11,
             FsError::PermissionDenied => -13,
             FsError::AlreadyExists => -17,
+            FsError::CrossDevice => -18,
             FsError::NoDevice => -19,
             FsError::NotDirectory => -20,
             FsError::IsDirectory => -21,
@@ -84,8 +100,10 @@ impl FsError {
             FsError::TooManyLinks => -31,
             FsError::TooManySymlinks => -40,
             FsError::BrokenPipe => -32,
+            FsError::Deadlock => -35,
             FsError::NameTooLong => -36,
             FsError::DirectoryNotEmpty => -39,
+            FsError::NoData => -61,
             FsError::NotSupported => -95,
             FsError::NotConnected => -107,
         }
@@ -7,6 +7,7 @@
 //! - `alloc()` 通常分配“最小可用 fd”（0/1/2 在用户进程中多用于 stdio）
 //! - `dup/dup2` 等操作会共享底层 `Arc<dyn File>`（因此可能共享 offset）
 //! - `FD_CLOEXEC` 用于控制 exec 时是否关闭 fd（由 `FdFlags` 表示）
+//! - 关闭 fd（含 dup2 覆盖、exec 关闭 CLOEXEC fd）时释放相应的文件锁
 
 use alloc::sync::Arc;
 use alloc::vec::Vec;
@@ -14,6 +15,7 @@ use core::fmt;
 use sync::SpinLock;
 use uapi::fcntl::{FdFlags, OpenFlags};
 
+use crate::file_lock::release_locks_on_close;
 use crate::{File, FsError, vfs_ops};
 
 /// 文件描述符表
@@ -78,6 +80,12 @@ impl FDTable {
         for f in fd_flags.iter_mut() {
             *f = FdFlags::empty();
         }
+        drop(fd_flags);
+        drop(files);
+
+        for (_, file) in &out {
+            release_locks_on_close(file);
+        }
         out
     }
 
@@ -155,12 +163,14 @@ impl FDTable {
         let mut files = self.files.lock();
         let mut fd_flags = self.fd_flags.lock();
 
-        if fd >= files.len() || files[fd].is_none() {
+        let Some(file) = files.get_mut(fd).and_then(Option::take) else {
             return Err(FsError::BadFileDescriptor);
-        }
-
-        files[fd] = None;
+        };
         fd_flags[fd] = FdFlags::empty();
+        drop(fd_flags);
+        drop(files);
+
+        release_locks_on_close(&file);
         Ok(())
     }
 
@@ -237,12 +247,19 @@ impl FDTable {
         let mut files = self.files.lock();
         let mut fd_flags = self.fd_flags.lock();
 
+        let mut closed = Vec::new();
         for (slot, flags) in files.iter_mut().zip(fd_flags.iter_mut()) {
             if flags.contains(FdFlags::CLOEXEC) {
-                *slot = None;
+                closed.extend(slot.take());
                 *flags = FdFlags::empty();
             }
         }
+        drop(fd_flags);
+        drop(files);
+
+        for file in &closed {
+            release_locks_on_close(file);
+        }
     }
 
     /// 获取文件描述符标志 (F_GETFD)
@@ -9,8 +9,11 @@
 //! - `Inode` 更偏“无状态存储接口”，用于提供底层随机访问与元数据操作。
 
 use alloc::sync::Arc;
+use uapi::epoll::EpollEvents;
 use uapi::fcntl::{OpenFlags, SeekWhence};
 
+use crate::poll::{GENERIC_POLL_QUEUE, PollWaker, readiness};
+use crate::splice::copy_through_buffer;
 use crate::{Dentry, FsError, Inode, InodeMetadata};
 
 /// 文件操作的统一接口
@@ -32,6 +35,17 @@ pub trait File: Send + Sync {
     /// 获取文件元数据
     fn metadata(&self) -> Result<InodeMetadata, FsError>;
 
+    /// 查询就绪事件；给出 `waker` 时先把它登记到文件状态改变时唤醒的队列
+    ///
+    /// 先登记后查询，查询之后发生的变化不会被错过。默认由 [`readable`](Self::readable) /
+    /// [`writable`](Self::writable) 得出，登记到 [`GENERIC_POLL_QUEUE`]。
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            GENERIC_POLL_QUEUE.register(waker);
+        }
+        readiness(self.readable(), self.writable())
+    }
+
     /// 设置文件偏移量（可选方法）
     fn lseek(&self, _offset: isize, _whence: SeekWhence) -> Result<usize, FsError> {
         Err(FsError::NotSupported)
@@ -92,6 +106,32 @@ pub trait File: Send + Sync {
         Err(FsError::NotSupported)
     }
 
+    /// 把至多 `len` 字节送到 `out`（用于 sendfile/splice）
+    ///
+    /// `offset` / `out_offset` 为 `Some` 时在该位置读 / 写并前移，不改变文件偏移量，否则使用
+    /// 各自的当前偏移量。默认经共享的内核缓冲区中转；输出端写不下时返回已送出的字节数。
+    fn splice_to(
+        &self,
+        offset: Option<&mut usize>,
+        out: &dyn File,
+        out_offset: Option<&mut usize>,
+        len: usize,
+    ) -> Result<usize, FsError> {
+        copy_through_buffer(self, offset, out, out_offset, len)
+    }
+
+    /// 直接接收 `inode` 从 `offset` 开始的至多 `len` 字节文件页（可选方法，用于 sendfile/splice）
+    ///
+    /// 返回 0 表示已到文件末尾。不支持时普通文件的 [`splice_to`](Self::splice_to) 退回缓冲区中转。
+    fn splice_from(
+        &self,
+        _inode: &Arc<dyn Inode>,
+        _offset: usize,
+        _len: usize,
+    ) -> Result<usize, FsError> {
+        Err(FsError::NotSupported)
+    }
+
     /// 执行设备特定的控制操作（可选方法，用于 ioctl）
     fn ioctl(&self, _request: u32, _arg: usize) -> Result<isize, FsError> {
         Err(FsError::NotSupported)
@@ -2,48 +2,80 @@
 //!
 //! 实现 POSIX 文件锁（advisory locks）语义
 //!
-//! 当前实现提供 `F_GETLK`/`F_SETLK` 风格的基础能力：
-//!
-//! - 以（dev, ino）标识文件，以（start, len, pid）标识锁区间与持有者
-//! - 读锁共享、写锁独占；同一 pid 的锁不视为冲突
-//! - 阻塞等待（`F_SETLKW`）与死锁检测属于后续增强点
+//! - 以（文件系统，inode 号）（[`FileId`]）标识文件，以（start, end, 持有者）标识锁区间
+//! - 持有者是进程（传统 POSIX 记录锁，`F_SETLK` 等）或打开文件描述（OFD 锁，`F_OFD_SETLK` 等）；
+//!   同一持有者的锁不冲突，读锁之间不冲突
+//! - 同一持有者加锁或解锁时，原有的锁按区间拆分，相邻的同类型锁合并
+//! - `F_SETLKW` 在冲突时睡眠到冲突解除；进程间的等待形成环时返回 `EDEADLK`（只检查进程持有的锁）
+//! - 进程关闭文件的任一描述符时释放它在该文件上的全部进程锁，进程退出时释放所有进程锁；
+//!   OFD 锁在打开文件描述被释放时释放
 
 use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use sync::SpinLock;
 use uapi::fcntl::{Flock, LockType};
 
-use crate::FsError;
+use crate::{Dentry, File, FsError, MountNamespace, current_mnt_ns, vfs_ops};
+
+/// 死锁检测沿等待链最多前进的步数（与 Linux 的 MAX_DEADLK_ITERATIONS 一致）
+const MAX_DEADLK_ITERATIONS: usize = 10;
+
+/// 锁的持有者
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockOwner {
+    /// 进程（以 PID 标识）
+    Process(i32),
+    /// 打开文件描述（以文件对象的地址标识）
+    OpenFile(usize),
+}
+
+impl LockOwner {
+    /// `file` 对应的打开文件描述
+    pub fn open_file(file: &Arc<dyn File>) -> Self {
+        Self::of_file(file.as_ref())
+    }
+
+    fn of_file(file: &dyn File) -> Self {
+        Self::OpenFile(file as *const dyn File as *const () as usize)
+    }
+
+    /// F_GETLK 报告的 `l_pid`：OFD 锁为 -1
+    fn l_pid(&self) -> i32 {
+        match self {
+            Self::Process(pid) => *pid,
+            Self::OpenFile(_) => -1,
+        }
+    }
+}
 
 /// 文件锁条目
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 struct FileLockEntry {
     /// 锁类型
     lock_type: LockType,
     /// 起始位置（绝对偏移）
     start: usize,
-    /// 长度
-    len: usize,
-    /// 持有锁的进程 PID
-    pid: i32,
+    /// 结束位置（不含），`usize::MAX` 表示到文件末尾
+    end: usize,
+    /// 持有者
+    owner: LockOwner,
 }
 
 impl FileLockEntry {
     /// 检查锁范围是否重叠
-    fn overlaps(&self, start: usize, len: usize) -> bool {
-        let self_end = self.start.saturating_add(self.len);
-        let other_end = start.saturating_add(len);
-        !(self_end <= start || other_end <= self.start)
+    fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.start < end && start < self.end
     }
 
     /// 检查与另一个锁是否冲突
     fn conflicts_with(&self, other: &FileLockEntry) -> bool {
-        if !self.overlaps(other.start, other.len) {
+        if !self.overlaps(other.start, other.end) {
             return false;
         }
 
-        // 同一进程的锁不冲突
-        if self.pid == other.pid {
+        // 同一持有者的锁不冲突
+        if self.owner == other.owner {
             return false;
         }
 
@@ -57,44 +89,173 @@ impl FileLockEntry {
     }
 }
 
-/// 文件标识符（设备号 + inode 号）
+/// 加锁的文件，以（文件系统，inode 号）标识
+///
+/// 文件系统还没有各自的设备号，以文件系统对象的地址代替；绑定挂载与复制的挂载命名空间
+/// 共享同一个文件系统对象。同一文件的 inode 对象可能每次查找都不同，不能以其地址标识。
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct FileId {
-    dev: u64,
-    ino: u64,
+pub struct FileId {
+    /// 文件系统对象的地址
+    fs: usize,
+    /// inode 号
+    ino: usize,
+}
+
+impl FileId {
+    /// `file` 对应的文件
+    ///
+    /// 不在文件系统中的文件（管道、套接字等）返回错误。
+    pub fn of(file: &Arc<dyn File>) -> Result<Self, FsError> {
+        Self::in_ns(&current_mnt_ns(), &file.dentry()?)
+    }
+
+    /// `dentry` 对应的文件，文件系统由它在 `ns` 中所在的挂载点确定
+    ///
+    /// 文件系统已从 `ns` 中卸载时退回到 dentry 树根的地址，此后关闭描述符不再释放
+    /// 卸载前加的进程锁，要到进程退出时才释放。
+    fn in_ns(ns: &MountNamespace, dentry: &Arc<Dentry>) -> Result<Self, FsError> {
+        let ino = dentry.inode.metadata()?.inode_no;
+        let fs = match ns.mount_of(dentry) {
+            Some(mp) => Arc::as_ptr(&mp.fs) as *const () as usize,
+            None => {
+                let mut top = dentry.clone();
+                while let Some(parent) = top.parent() {
+                    top = parent;
+                }
+                Arc::as_ptr(&top) as usize
+            }
+        };
+        Ok(Self { fs, ino })
+    }
+}
+
+/// 去掉 `owner` 在 `[start, end)` 内的锁，部分重叠的锁被截断或拆成两段
+fn carve(locks: &mut Vec<FileLockEntry>, owner: LockOwner, start: usize, end: usize) {
+    let mut tails = Vec::new();
+    locks.retain_mut(|lock| {
+        if lock.owner != owner || !lock.overlaps(start, end) {
+            return true;
+        }
+        if lock.start < start && end < lock.end {
+            tails.push(FileLockEntry {
+                start: end,
+                ..*lock
+            });
+            lock.end = start;
+            true
+        } else if lock.start < start {
+            lock.end = start;
+            true
+        } else if end < lock.end {
+            lock.start = end;
+            true
+        } else {
+            false
+        }
+    });
+    locks.extend(tails);
+}
+
+/// 锁表与等待关系
+struct LockState {
+    /// 文件锁表：文件 -> 锁列表
+    files: BTreeMap<FileId, Vec<FileLockEntry>>,
+    /// 阻塞在 F_SETLKW 上的进程 -> 阻塞它的锁的持有者（用于死锁检测）
+    blocked: BTreeMap<i32, LockOwner>,
+}
+
+impl LockState {
+    /// 查找与 `request` 冲突的锁
+    fn find_conflict(&self, file_id: FileId, request: &FileLockEntry) -> Option<FileLockEntry> {
+        self.files
+            .get(&file_id)?
+            .iter()
+            .find(|lock| lock.conflicts_with(request))
+            .copied()
+    }
+
+    /// 进程 `pid` 等待 `blocker` 是否会形成等待环
+    fn would_deadlock(&self, pid: i32, blocker: LockOwner) -> bool {
+        let mut owner = blocker;
+        for _ in 0..MAX_DEADLK_ITERATIONS {
+            let LockOwner::Process(holder) = owner else {
+                return false;
+            };
+            if holder == pid {
+                return true;
+            }
+            match self.blocked.get(&holder) {
+                Some(next) => owner = *next,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// 加入锁：先去掉持有者在该区间内的旧锁，再与相邻的同类型锁合并
+    fn insert(&mut self, file_id: FileId, lock: FileLockEntry) {
+        let locks = self.files.entry(file_id).or_default();
+        carve(locks, lock.owner, lock.start, lock.end);
+
+        let (mut start, mut end) = (lock.start, lock.end);
+        locks.retain(|other| {
+            let adjacent = other.end == lock.start || lock.end == other.start;
+            if other.owner == lock.owner && other.lock_type == lock.lock_type && adjacent {
+                start = start.min(other.start);
+                end = end.max(other.end);
+                false
+            } else {
+                true
+            }
+        });
+        locks.push(FileLockEntry { start, end, ..lock });
+    }
+
+    /// 去掉满足 `pred` 的锁，返回是否有锁被去掉
+    fn remove_where(&mut self, mut pred: impl FnMut(&FileId, &FileLockEntry) -> bool) -> bool {
+        let mut removed = false;
+        for (file_id, locks) in self.files.iter_mut() {
+            let before = locks.len();
+            locks.retain(|lock| !pred(file_id, lock));
+            removed |= locks.len() != before;
+        }
+        self.files.retain(|_, locks| !locks.is_empty());
+        removed
+    }
 }
 
 /// 全局文件锁管理器
 pub struct FileLockManager {
-    /// 文件锁表：FileId -> 锁列表
-    locks: SpinLock<BTreeMap<FileId, Vec<FileLockEntry>>>,
+    state: SpinLock<LockState>,
 }
 
 impl FileLockManager {
     /// 创建新的文件锁管理器
     pub const fn new() -> Self {
         Self {
-            locks: SpinLock::new(BTreeMap::new()),
+            state: SpinLock::new(LockState {
+                files: BTreeMap::new(),
+                blocked: BTreeMap::new(),
+            }),
         }
     }
 
-    /// 测试锁（F_GETLK）
+    /// 等待通道：锁被释放或降级时唤醒
+    fn wait_chan(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// 测试锁（F_GETLK / F_OFD_GETLK）
     pub fn test_lock(
         &self,
-        dev: u64,
-        ino: u64,
+        file_id: FileId,
         start: usize,
         len: usize,
         flock: &mut Flock,
-        pid: i32,
+        owner: LockOwner,
     ) -> Result<(), FsError> {
-        let file_id = FileId { dev, ino };
-        let locks = self.locks.lock();
-
         let lock_type = match LockType::from_raw(flock.l_type) {
-            Some(LockType::Read) | Some(LockType::Write) => {
-                LockType::from_raw(flock.l_type).unwrap()
-            }
+            Some(t @ (LockType::Read | LockType::Write)) => t,
             _ => return Err(FsError::InvalidArgument),
         };
 
@@ -102,90 +263,145 @@ impl FileLockManager {
         let requested_lock = FileLockEntry {
             lock_type,
             start,
-            len,
-            pid,
+            end: start.saturating_add(len),
+            owner,
         };
 
         // 检查是否有冲突的锁
-        if let Some(file_locks) = locks.get(&file_id) {
-            for existing_lock in file_locks {
-                if existing_lock.conflicts_with(&requested_lock) {
-                    // 找到冲突的锁，填充 flock 结构
-                    flock.l_type = existing_lock.lock_type as i16;
-                    flock.l_start = existing_lock.start as i64;
-                    flock.l_len = existing_lock.len as i64;
-                    flock.l_pid = existing_lock.pid;
-                    flock.l_whence = 0; // SEEK_SET
-                    return Ok(());
-                }
+        match self.state.lock().find_conflict(file_id, &requested_lock) {
+            Some(existing_lock) => {
+                // 找到冲突的锁，填充 flock 结构
+                flock.l_type = existing_lock.lock_type as i16;
+                flock.l_start = existing_lock.start as i64;
+                flock.l_len = if existing_lock.end == usize::MAX {
+                    0
+                } else {
+                    (existing_lock.end - existing_lock.start) as i64
+                };
+                flock.l_pid = existing_lock.owner.l_pid();
+                flock.l_whence = 0; // SEEK_SET
+            }
+            None => {
+                // 没有冲突，设置为 F_UNLCK
+                flock.l_type = LockType::Unlock as i16;
             }
         }
-
-        // 没有冲突，设置为 F_UNLCK
-        flock.l_type = LockType::Unlock as i16;
         Ok(())
     }
 
-    /// 设置锁（F_SETLK / F_SETLKW）
+    /// 设置锁（F_SETLK / F_SETLKW / F_OFD_SETLK / F_OFD_SETLKW）
+    ///
+    /// 冲突时 `blocking` 为假返回 `EAGAIN`，为真则睡眠到冲突解除；
+    /// 等待会形成死锁时返回 `EDEADLK`，被信号打断时返回 `EINTR`。
     pub fn set_lock(
         &self,
-        dev: u64,
-        ino: u64,
+        file_id: FileId,
         start: usize,
         len: usize,
         lock_type: LockType,
-        pid: i32,
-        _blocking: bool,
+        owner: LockOwner,
+        blocking: bool,
     ) -> Result<(), FsError> {
-        let file_id = FileId { dev, ino };
-        let mut locks = self.locks.lock();
-
-        match lock_type {
-            LockType::Unlock => {
-                // 释放锁：移除指定范围的锁
-                if let Some(file_locks) = locks.get_mut(&file_id) {
-                    file_locks.retain(|lock| !(lock.pid == pid && lock.overlaps(start, len)));
-                    if file_locks.is_empty() {
-                        locks.remove(&file_id);
+        let end = start.saturating_add(len);
+
+        if lock_type == LockType::Unlock {
+            // 释放锁：去掉指定范围内的锁
+            {
+                let mut state = self.state.lock();
+                if let Some(locks) = state.files.get_mut(&file_id) {
+                    carve(locks, owner, start, end);
+                    if locks.is_empty() {
+                        state.files.remove(&file_id);
                     }
                 }
-                Ok(())
             }
-            LockType::Read | LockType::Write => {
-                // 检查是否有冲突
-                let file_locks = locks.entry(file_id).or_insert_with(Vec::new);
-
-                let new_lock = FileLockEntry {
-                    lock_type,
-                    start,
-                    len,
-                    pid,
-                };
+            vfs_ops().wake_event(self.wait_chan());
+            return Ok(());
+        }
 
-                // 检查冲突
-                for existing_lock in file_locks.iter() {
-                    if existing_lock.conflicts_with(&new_lock) {
-                        return Err(FsError::WouldBlock);
+        let new_lock = FileLockEntry {
+            lock_type,
+            start,
+            end,
+            owner,
+        };
+        loop {
+            {
+                let mut state = self.state.lock();
+                let Some(blocker) = state.find_conflict(file_id, &new_lock) else {
+                    if let LockOwner::Process(pid) = owner {
+                        state.blocked.remove(&pid);
                     }
+                    state.insert(file_id, new_lock);
+                    drop(state);
+                    // 写锁降级为读锁等变化可能让等待者得以继续
+                    vfs_ops().wake_event(self.wait_chan());
+                    return Ok(());
+                };
+                if !blocking {
+                    return Err(FsError::WouldBlock);
                 }
+                if let LockOwner::Process(pid) = owner {
+                    if state.would_deadlock(pid, blocker.owner) {
+                        state.blocked.remove(&pid);
+                        return Err(FsError::Deadlock);
+                    }
+                    state.blocked.insert(pid, blocker.owner);
+                }
+            }
 
-                // 移除同一进程在重叠范围内的旧锁
-                file_locks.retain(|lock| !(lock.pid == pid && lock.overlaps(start, len)));
-
-                // 添加新锁
-                file_locks.push(new_lock);
-                Ok(())
+            let cond = || {
+                self.state
+                    .lock()
+                    .find_conflict(file_id, &new_lock)
+                    .is_none()
+            };
+            if !vfs_ops().wait_event(self.wait_chan(), None, &cond) {
+                if let LockOwner::Process(pid) = owner {
+                    self.state.lock().blocked.remove(&pid);
+                }
+                return Err(FsError::Interrupted);
             }
         }
     }
 
+    /// 释放进程在文件 `file_id` 上的所有锁（关闭该文件的任一描述符时调用）
+    pub fn release_posix_locks(&self, file_id: FileId, pid: i32) {
+        let removed = self
+            .state
+            .lock()
+            .remove_where(|id, lock| *id == file_id && lock.owner == LockOwner::Process(pid));
+        if removed {
+            vfs_ops().wake_event(self.wait_chan());
+        }
+    }
+
+    /// 释放打开文件描述持有的所有 OFD 锁
+    pub fn release_ofd_locks(&self, owner: LockOwner) {
+        let removed = self
+            .state
+            .lock()
+            .remove_where(|_, lock| lock.owner == owner);
+        if removed {
+            vfs_ops().wake_event(self.wait_chan());
+        }
+    }
+
     /// 释放进程持有的所有锁（进程退出时调用）
     pub fn release_all_locks(&self, pid: i32) {
-        let mut locks = self.locks.lock();
-        for file_locks in locks.values_mut() {
-            file_locks.retain(|lock| lock.pid != pid);
+        let removed = {
+            let mut state = self.state.lock();
+            state.blocked.remove(&pid);
+            state.remove_where(|_, lock| lock.owner == LockOwner::Process(pid))
+        };
+        if removed {
+            vfs_ops().wake_event(self.wait_chan());
         }
-        locks.retain(|_, file_locks| !file_locks.is_empty());
+    }
+
+    /// 是否没有任何锁
+    fn is_empty(&self) -> bool {
+        self.state.lock().files.is_empty()
     }
 }
 
@@ -202,3 +418,223 @@ static FILE_LOCK_MANAGER: FileLockManager = FileLockManager::new();
 pub fn file_lock_manager() -> &'static FileLockManager {
     &FILE_LOCK_MANAGER
 }
+
+/// 文件描述符 `file` 被关闭时释放当前进程在该文件上的进程锁（由 [`FDTable`](crate::FDTable) 调用）
+pub(crate) fn release_locks_on_close(file: &Arc<dyn File>) {
+    let manager = file_lock_manager();
+    if manager.is_empty() {
+        return;
+    }
+    let Ok(file_id) = FileId::of(file) else {
+        return;
+    };
+    let pid = vfs_ops().current_pid() as i32;
+    manager.release_posix_locks(file_id, pid);
+}
+
+/// 打开文件描述被释放时释放它持有的 OFD 锁
+///
+/// 最后一个引用可能不在描述符表中（epoll、io_uring 等也持有打开文件描述），
+/// 所以由可以加锁的文件类型在 `Drop` 中调用。
+pub fn release_ofd_locks_on_drop(file: &dyn File) {
+    let manager = file_lock_manager();
+    if !manager.is_empty() {
+        manager.release_ofd_locks(LockOwner::of_file(file));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impls::inotify_file::tests::dummy;
+    use crate::{FileSystem, Inode, InodeType, MountFlags, OpenFlags, RegFile, StatFs};
+    use alloc::string::String;
+    use sync::mock::init_arch_ops;
+
+    const F: FileId = FileId { fs: 1, ino: 1 };
+    const P1: LockOwner = LockOwner::Process(100);
+    const P2: LockOwner = LockOwner::Process(200);
+
+    fn probe(
+        manager: &FileLockManager,
+        lock_type: LockType,
+        start: usize,
+        len: usize,
+        owner: LockOwner,
+    ) -> Flock {
+        let mut flock = Flock::new(lock_type, 0, 0, 0);
+        manager.test_lock(F, start, len, &mut flock, owner).unwrap();
+        flock
+    }
+
+    #[test]
+    fn test_split_and_merge() {
+        init_arch_ops();
+        let manager = FileLockManager::new();
+        manager
+            .set_lock(F, 0, 100, LockType::Write, P1, false)
+            .unwrap();
+        // 解锁中间一段后两端仍被锁住
+        manager
+            .set_lock(F, 40, 20, LockType::Unlock, P1, false)
+            .unwrap();
+        assert_eq!(
+            probe(&manager, LockType::Read, 40, 20, P2).l_type,
+            LockType::Unlock as i16
+        );
+        let flock = probe(&manager, LockType::Read, 60, 1, P2);
+        assert_eq!((flock.l_start, flock.l_len, flock.l_pid), (60, 40, 100));
+
+        // 重新加锁后与两端合并
+        manager
+            .set_lock(F, 40, 20, LockType::Write, P1, false)
+            .unwrap();
+        let flock = probe(&manager, LockType::Read, 50, 1, P2);
+        assert_eq!((flock.l_start, flock.l_len), (0, 100));
+        assert_eq!(manager.state.lock().files[&F].len(), 1);
+    }
+
+    #[test]
+    fn test_conflicts_between_owners() {
+        init_arch_ops();
+        let manager = FileLockManager::new();
+        let ofd = LockOwner::OpenFile(0x1000);
+        manager
+            .set_lock(F, 0, usize::MAX, LockType::Read, P1, false)
+            .unwrap();
+        manager
+            .set_lock(F, 0, usize::MAX, LockType::Read, ofd, false)
+            .unwrap();
+        assert_eq!(
+            manager.set_lock(F, 10, 1, LockType::Write, P2, false),
+            Err(FsError::WouldBlock)
+        );
+
+        // 进程锁与 OFD 锁互相冲突，OFD 锁的 l_pid 为 -1
+        manager.release_all_locks(100);
+        let flock = probe(&manager, LockType::Write, 0, 1, P2);
+        assert_eq!((flock.l_pid, flock.l_len), (-1, 0));
+        manager.release_ofd_locks(ofd);
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_release_posix_locks_only_touches_closed_file() {
+        init_arch_ops();
+        let manager = FileLockManager::new();
+        let other = FileId { fs: 1, ino: 2 };
+        manager
+            .set_lock(F, 0, 10, LockType::Write, P1, false)
+            .unwrap();
+        manager
+            .set_lock(other, 0, 10, LockType::Write, P1, false)
+            .unwrap();
+        // 关闭 F 的描述符不影响同一进程在其它文件上的锁
+        manager.release_posix_locks(F, 100);
+        assert_eq!(
+            probe(&manager, LockType::Write, 0, 10, P2).l_type,
+            LockType::Unlock as i16
+        );
+        assert_eq!(
+            manager.set_lock(other, 0, 1, LockType::Write, P2, false),
+            Err(FsError::WouldBlock)
+        );
+    }
+
+    #[test]
+    fn test_deadlock_detection() {
+        init_arch_ops();
+        let manager = FileLockManager::new();
+        manager
+            .set_lock(F, 0, 10, LockType::Write, P1, false)
+            .unwrap();
+        manager
+            .set_lock(F, 10, 10, LockType::Write, P2, false)
+            .unwrap();
+        // P1 正在等待 P2 持有的锁，P2 再等待 P1 就形成环
+        manager.state.lock().blocked.insert(100, P2);
+        assert_eq!(
+            manager.set_lock(F, 0, 1, LockType::Write, P2, true),
+            Err(FsError::Deadlock)
+        );
+        assert!(!manager.state.lock().blocked.contains_key(&200));
+    }
+
+    /// 只有根目录的文件系统
+    struct TestFs(Arc<dyn Inode>);
+
+    impl FileSystem for TestFs {
+        fn fs_type(&self) -> &'static str {
+            "testfs"
+        }
+
+        fn root_inode(&self) -> Arc<dyn Inode> {
+            self.0.clone()
+        }
+
+        fn sync(&self) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn statfs(&self) -> Result<StatFs, FsError> {
+            Err(FsError::NotSupported)
+        }
+    }
+
+    #[test]
+    fn test_file_id_keys_on_filesystem_and_ino() {
+        init_arch_ops();
+        let ns = MountNamespace::new();
+        for path in ["/", "/mnt"] {
+            let fs = Arc::new(TestFs(dummy(InodeType::Directory)));
+            ns.mount(fs, path, MountFlags::empty(), None).unwrap();
+        }
+        let root = ns.find_mount("/").unwrap().root.clone();
+        let file_in = |parent: &Arc<Dentry>| {
+            let dentry = Dentry::new(String::from("f"), dummy(InodeType::File));
+            parent.add_child(dentry.clone());
+            FileId::in_ns(&ns, &dentry).unwrap()
+        };
+
+        // 同一文件再次查找得到新的 inode 对象，经绑定挂载访问时也是同一个文件
+        let id = file_in(&root);
+        assert_eq!(file_in(&root), id);
+        ns.bind(&root, "/bind").unwrap();
+        assert_eq!(file_in(&ns.find_mount("/bind").unwrap().root), id);
+
+        // 另一个文件系统中 inode 号相同的文件不是同一个文件
+        assert_ne!(file_in(&ns.find_mount("/mnt").unwrap().root), id);
+    }
+
+    #[test]
+    fn test_ofd_locks_released_when_file_dropped() {
+        init_arch_ops();
+        let manager = file_lock_manager();
+        let id = FileId { fs: 2, ino: 1 };
+        let dentry = Dentry::new(String::from("f"), dummy(InodeType::File));
+        let file: Arc<dyn File> = Arc::new(RegFile::new(dentry, OpenFlags::O_RDWR));
+        manager
+            .set_lock(
+                id,
+                0,
+                10,
+                LockType::Write,
+                LockOwner::open_file(&file),
+                false,
+            )
+            .unwrap();
+
+        // 最后一个引用不在描述符表中（如 epoll 持有）时，释放它同样释放 OFD 锁
+        let held = file.clone();
+        drop(file);
+        assert_eq!(
+            manager.set_lock(id, 0, 1, LockType::Write, P2, false),
+            Err(FsError::WouldBlock)
+        );
+        drop(held);
+        manager
+            .set_lock(id, 0, 1, LockType::Write, P2, false)
+            .unwrap();
+        manager.release_all_locks(200);
+    }
+}
@@ -0,0 +1,520 @@
+//! 文件系统事件通知（inotify 的内核侧）
+//!
+//! 每个 inotify 实例是一个 [`InotifyGroup`]，它登记的监视（watch）按被监视 inode 的地址
+//! 索引在全局表中。路径层在修改文件系统之后调用本模块的 `fsnotify_*` 函数：
+//!
+//! - 创建、删除、重命名：由系统调用在对应的 [`Inode`] 操作成功后调用，
+//!   事件投递给父目录的监视（带文件名），删除与移动还投递给对象本身的监视；
+//! - 写入与截断：由 [`RegFile`](crate::RegFile) 等在写入成功后调用 [`fsnotify_modify`]，
+//!   事件同时投递给文件本身与所在目录的监视。
+//!
+//! 监视持有被监视 inode 的引用，inode 地址在监视存在期间不会被复用。
+//! 没有任何监视时通知只读一次原子计数器。
+//!
+//! 创建与移入还使目标目录中同名的负目录项（见 [`DentryCache`](crate::DentryCache)）失效，
+//! 因此绕过系统调用直接创建文件的代码也必须调用 [`fsnotify_create`]。
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use sync::SpinLock;
+use uapi::epoll::EpollEvents;
+use uapi::inotify::{InotifyEvent, InotifyMask};
+
+use crate::poll::{PollQueue, PollWaker};
+use crate::{DENTRY_CACHE, Dentry, FsError, Inode, InodeType, vfs_ops};
+
+/// 每个实例最多排队的事件数（`/proc/sys/fs/inotify/max_queued_events` 的默认值）
+pub const MAX_QUEUED_EVENTS: usize = 16384;
+/// 每个实例最多登记的监视数
+pub const MAX_WATCHES: usize = 8192;
+
+/// 被监视 inode 的地址 -> 监视它的实例
+static WATCHED: SpinLock<BTreeMap<usize, Vec<Weak<InotifyGroup>>>> = SpinLock::new(BTreeMap::new());
+/// 所有实例登记的监视总数，为 0 时通知直接返回
+static NR_WATCHES: AtomicUsize = AtomicUsize::new(0);
+/// 重命名事件的 cookie
+static NEXT_COOKIE: AtomicU32 = AtomicU32::new(1);
+
+/// inode 在监视表中的键
+fn inode_key(inode: &dyn Inode) -> usize {
+    inode as *const dyn Inode as *const () as usize
+}
+
+/// 一个排队中的事件
+#[derive(PartialEq, Eq)]
+struct QueuedEvent {
+    wd: i32,
+    mask: InotifyMask,
+    cookie: u32,
+    name: Option<String>,
+}
+
+impl QueuedEvent {
+    /// 文件名部分的长度：含结尾 NUL，补齐到事件头部大小的整数倍
+    fn name_len(&self) -> usize {
+        self.name.as_ref().map_or(0, |name| {
+            (name.len() + 1).next_multiple_of(size_of::<InotifyEvent>())
+        })
+    }
+
+    /// 事件在 `read` 结果中占用的字节数
+    fn len(&self) -> usize {
+        size_of::<InotifyEvent>() + self.name_len()
+    }
+
+    /// 按 `struct inotify_event` 的布局写入 `buf`（长度为 [`len`](Self::len)）
+    fn write_to(&self, buf: &mut [u8]) {
+        let header = [
+            self.wd.to_ne_bytes(),
+            self.mask.bits().to_ne_bytes(),
+            self.cookie.to_ne_bytes(),
+            (self.name_len() as u32).to_ne_bytes(),
+        ];
+        let (head, name) = buf.split_at_mut(size_of::<InotifyEvent>());
+        for (dst, field) in head.chunks_exact_mut(4).zip(header) {
+            dst.copy_from_slice(&field);
+        }
+        name.fill(0);
+        if let Some(n) = &self.name {
+            name[..n.len()].copy_from_slice(n.as_bytes());
+        }
+    }
+}
+
+/// 一个监视
+struct Watch {
+    /// 被监视的 inode，监视存在期间保持它不被释放
+    inode: Arc<dyn Inode>,
+    mask: InotifyMask,
+}
+
+struct GroupState {
+    next_wd: i32,
+    watches: BTreeMap<i32, Watch>,
+    /// inode 键 -> 监视描述符
+    by_inode: BTreeMap<usize, i32>,
+    events: VecDeque<QueuedEvent>,
+}
+
+impl GroupState {
+    /// 追加一个事件：与队尾相同的事件合并，队列满时只留一个 IN_Q_OVERFLOW
+    fn push(&mut self, event: QueuedEvent) {
+        if self.events.back() == Some(&event) {
+            return;
+        }
+        if self.events.len() >= MAX_QUEUED_EVENTS {
+            if self
+                .events
+                .back()
+                .is_none_or(|last| last.mask != InotifyMask::Q_OVERFLOW)
+            {
+                self.events.push_back(QueuedEvent {
+                    wd: -1,
+                    mask: InotifyMask::Q_OVERFLOW,
+                    cookie: 0,
+                    name: None,
+                });
+            }
+            return;
+        }
+        self.events.push_back(event);
+    }
+
+    /// 移除监视 `wd` 并排入 IN_IGNORED，返回被监视 inode 的键
+    fn remove_watch(&mut self, wd: i32) -> Option<usize> {
+        let watch = self.watches.remove(&wd)?;
+        let key = inode_key(watch.inode.as_ref());
+        self.by_inode.remove(&key);
+        self.push(QueuedEvent {
+            wd,
+            mask: InotifyMask::IGNORED,
+            cookie: 0,
+            name: None,
+        });
+        NR_WATCHES.fetch_sub(1, Ordering::Relaxed);
+        Some(key)
+    }
+}
+
+/// 一个 inotify 实例：登记的监视与待读取的事件
+pub struct InotifyGroup {
+    state: SpinLock<GroupState>,
+    poll_queue: PollQueue,
+}
+
+impl InotifyGroup {
+    /// 创建一个没有监视的实例
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: SpinLock::new(GroupState {
+                next_wd: 1,
+                watches: BTreeMap::new(),
+                by_inode: BTreeMap::new(),
+                events: VecDeque::new(),
+            }),
+            poll_queue: PollQueue::new(),
+        })
+    }
+
+    /// 等待通道：有新事件时唤醒
+    fn wait_chan(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// 唤醒读取者与 poll/epoll 的等待者
+    fn wake(&self) {
+        vfs_ops().wake_event(self.wait_chan());
+        self.poll_queue.wake(EpollEvents::IN);
+    }
+
+    /// 登记 poll/epoll 的等待者
+    pub fn register_poll(&self, waker: &Arc<dyn PollWaker>) {
+        self.poll_queue.register(waker);
+    }
+
+    /// 为 `inode` 登记或修改监视（inotify_add_watch），返回监视描述符
+    ///
+    /// # 错误
+    /// - 掩码中没有任何事件，或同时给出 IN_MASK_ADD 与 IN_MASK_CREATE：[`FsError::InvalidArgument`]
+    /// - 给出 IN_ONLYDIR 而 `inode` 不是目录：[`FsError::NotDirectory`]
+    /// - 给出 IN_MASK_CREATE 而 `inode` 已被本实例监视：[`FsError::AlreadyExists`]
+    /// - 监视数达到 [`MAX_WATCHES`]：[`FsError::NoSpace`]
+    pub fn add_watch(
+        self: &Arc<Self>,
+        inode: Arc<dyn Inode>,
+        mask: InotifyMask,
+    ) -> Result<i32, FsError> {
+        if !mask.intersects(InotifyMask::ALL_EVENTS)
+            || mask.contains(InotifyMask::MASK_ADD | InotifyMask::MASK_CREATE)
+        {
+            return Err(FsError::InvalidArgument);
+        }
+        if mask.contains(InotifyMask::ONLYDIR)
+            && inode.metadata()?.inode_type != InodeType::Directory
+        {
+            return Err(FsError::NotDirectory);
+        }
+        let stored = mask
+            - (InotifyMask::MASK_ADD
+                | InotifyMask::MASK_CREATE
+                | InotifyMask::ONLYDIR
+                | InotifyMask::DONT_FOLLOW);
+        let key = inode_key(inode.as_ref());
+
+        let wd = {
+            let mut state = self.state.lock();
+            if let Some(&wd) = state.by_inode.get(&key) {
+                if mask.contains(InotifyMask::MASK_CREATE) {
+                    return Err(FsError::AlreadyExists);
+                }
+                let watch = state.watches.get_mut(&wd).unwrap();
+                if mask.contains(InotifyMask::MASK_ADD) {
+                    watch.mask |= stored;
+                } else {
+                    watch.mask = stored;
+                }
+                return Ok(wd);
+            }
+            if state.watches.len() >= MAX_WATCHES {
+                return Err(FsError::NoSpace);
+            }
+            let wd = state.next_wd;
+            state.next_wd += 1;
+            state.watches.insert(
+                wd,
+                Watch {
+                    inode,
+                    mask: stored,
+                },
+            );
+            state.by_inode.insert(key, wd);
+            wd
+        };
+        NR_WATCHES.fetch_add(1, Ordering::Relaxed);
+        WATCHED
+            .lock()
+            .entry(key)
+            .or_default()
+            .push(Arc::downgrade(self));
+        Ok(wd)
+    }
+
+    /// 移除监视 `wd`（inotify_rm_watch），排入 IN_IGNORED 事件
+    pub fn rm_watch(self: &Arc<Self>, wd: i32) -> Result<(), FsError> {
+        let key = self
+            .state
+            .lock()
+            .remove_watch(wd)
+            .ok_or(FsError::InvalidArgument)?;
+        self.unindex(key);
+        self.wake();
+        Ok(())
+    }
+
+    /// 是否有待读取的事件
+    pub fn has_events(&self) -> bool {
+        !self.state.lock().events.is_empty()
+    }
+
+    /// 待读取事件的总字节数（FIONREAD）
+    pub fn pending_bytes(&self) -> usize {
+        self.state.lock().events.iter().map(QueuedEvent::len).sum()
+    }
+
+    /// 把尽可能多的完整事件写入 `buf`，返回写入的字节数
+    ///
+    /// 没有事件时 `nonblock` 为真返回 [`FsError::WouldBlock`]，否则等待；
+    /// 第一个事件都放不下时返回 [`FsError::InvalidArgument`]。
+    pub fn read_events(&self, buf: &mut [u8], nonblock: bool) -> Result<usize, FsError> {
+        loop {
+            {
+                let mut state = self.state.lock();
+                if !state.events.is_empty() {
+                    let mut written = 0;
+                    while let Some(event) = state.events.front() {
+                        let len = event.len();
+                        if written + len > buf.len() {
+                            break;
+                        }
+                        event.write_to(&mut buf[written..written + len]);
+                        written += len;
+                        state.events.pop_front();
+                    }
+                    return if written == 0 {
+                        Err(FsError::InvalidArgument)
+                    } else {
+                        Ok(written)
+                    };
+                }
+            }
+            if nonblock {
+                return Err(FsError::WouldBlock);
+            }
+            if !vfs_ops().wait_event(self.wait_chan(), None, &|| self.has_events()) {
+                return Err(FsError::Interrupted);
+            }
+        }
+    }
+
+    /// 向本实例中监视 `key` 的监视投递事件
+    ///
+    /// # 返回值
+    /// IN_ONESHOT 监视在投递后被移除时返回 `true`
+    fn deliver(&self, key: usize, mask: InotifyMask, cookie: u32, name: Option<&str>) -> bool {
+        let mut state = self.state.lock();
+        let Some(&wd) = state.by_inode.get(&key) else {
+            return false;
+        };
+        let watch_mask = state.watches[&wd].mask;
+        let events = mask & watch_mask & InotifyMask::ALL_EVENTS;
+        if events.is_empty() {
+            return false;
+        }
+        state.push(QueuedEvent {
+            wd,
+            mask: events | (mask & InotifyMask::ISDIR),
+            cookie,
+            name: name.map(String::from),
+        });
+        watch_mask.contains(InotifyMask::ONESHOT) && state.remove_watch(wd).is_some()
+    }
+
+    /// 被监视的 inode 被删除：移除对它的监视并排入 IN_IGNORED
+    fn forget(&self, key: usize) {
+        let mut state = self.state.lock();
+        if let Some(&wd) = state.by_inode.get(&key) {
+            state.remove_watch(wd);
+        }
+    }
+
+    /// 从全局表中删去本实例对 `key` 的登记
+    fn unindex(&self, key: usize) {
+        let mut watched = WATCHED.lock();
+        if let Some(groups) = watched.get_mut(&key) {
+            groups.retain(|g| g.strong_count() > 0 && !core::ptr::eq(g.as_ptr(), self));
+            if groups.is_empty() {
+                watched.remove(&key);
+            }
+        }
+    }
+}
+
+impl Drop for InotifyGroup {
+    /// 实例关闭时移除所有监视
+    fn drop(&mut self) {
+        let keys: Vec<usize> = {
+            let state = self.state.lock();
+            NR_WATCHES.fetch_sub(state.watches.len(), Ordering::Relaxed);
+            state.by_inode.keys().copied().collect()
+        };
+        for key in keys {
+            self.unindex(key);
+        }
+    }
+}
+
+/// 向监视 `inode` 的所有实例投递事件
+fn notify(inode: &dyn Inode, mask: InotifyMask, cookie: u32, name: Option<&str>) {
+    if NR_WATCHES.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let key = inode_key(inode);
+    let Some(groups) = WATCHED.lock().get(&key).cloned() else {
+        return;
+    };
+    for group in groups.iter().filter_map(Weak::upgrade) {
+        if group.deliver(key, mask, cookie, name) {
+            group.unindex(key);
+        }
+        group.wake();
+    }
+}
+
+/// 移除所有实例对 `inode` 的监视
+fn forget(inode: &dyn Inode) {
+    let key = inode_key(inode);
+    let Some(groups) = WATCHED.lock().remove(&key) else {
+        return;
+    };
+    for group in groups.iter().filter_map(Weak::upgrade) {
+        group.forget(key);
+        group.wake();
+    }
+}
+
+/// 目录项类型对应的 IN_ISDIR
+fn isdir(is_dir: bool) -> InotifyMask {
+    if is_dir {
+        InotifyMask::ISDIR
+    } else {
+        InotifyMask::empty()
+    }
+}
+
+/// 在目录 `dir` 中创建了 `name`（文件、目录、设备节点或符号链接）
+pub fn fsnotify_create(dir: &dyn Inode, name: &str, is_dir: bool) {
+    DENTRY_CACHE.forget_negative(dir, name);
+    notify(dir, InotifyMask::CREATE | isdir(is_dir), 0, Some(name));
+}
+
+/// 目录 `dir` 中的 `name`（即 `victim`）被删除
+///
+/// `victim` 的最后一个链接被删除时，对它的监视收到 IN_DELETE_SELF 并被移除；
+/// 否则只是链接数改变，收到 IN_ATTRIB。
+pub fn fsnotify_delete(dir: &dyn Inode, name: &str, victim: &dyn Inode, is_dir: bool) {
+    if NR_WATCHES.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    notify(dir, InotifyMask::DELETE | isdir(is_dir), 0, Some(name));
+    let gone = is_dir || victim.metadata().is_ok_and(|meta| meta.nlinks == 0);
+    if gone {
+        notify(victim, InotifyMask::DELETE_SELF, 0, None);
+        forget(victim);
+    } else {
+        notify(victim, InotifyMask::ATTRIB, 0, None);
+    }
+}
+
+/// `moved` 从 `old_dir` 中的 `old_name` 移动为 `new_dir` 中的 `new_name`
+///
+/// 两个目录分别收到 cookie 相同的 IN_MOVED_FROM 与 IN_MOVED_TO，`moved` 本身收到 IN_MOVE_SELF。
+pub fn fsnotify_move(
+    old_dir: &dyn Inode,
+    old_name: &str,
+    new_dir: &dyn Inode,
+    new_name: &str,
+    moved: &dyn Inode,
+    is_dir: bool,
+) {
+    DENTRY_CACHE.forget_negative(new_dir, new_name);
+    if NR_WATCHES.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let cookie = NEXT_COOKIE.fetch_add(1, Ordering::Relaxed);
+    notify(
+        old_dir,
+        InotifyMask::MOVED_FROM | isdir(is_dir),
+        cookie,
+        Some(old_name),
+    );
+    notify(
+        new_dir,
+        InotifyMask::MOVED_TO | isdir(is_dir),
+        cookie,
+        Some(new_name),
+    );
+    notify(moved, InotifyMask::MOVE_SELF, 0, None);
+}
+
+/// `dentry` 对应的文件内容被写入或截断
+pub fn fsnotify_modify(dentry: &Dentry) {
+    if NR_WATCHES.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    notify(dentry.inode.as_ref(), InotifyMask::MODIFY, 0, None);
+    if let Some(parent) = dentry.parent() {
+        notify(
+            parent.inode.as_ref(),
+            InotifyMask::MODIFY,
+            0,
+            Some(&dentry.name),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sync::mock::init_arch_ops;
+
+    #[test]
+    fn test_event_layout() {
+        let event = QueuedEvent {
+            wd: 3,
+            mask: InotifyMask::CREATE,
+            cookie: 0,
+            name: Some(String::from("a.txt")),
+        };
+        // 文件名含 NUL 共 6 字节，补齐到 16
+        assert_eq!(event.len(), 32);
+        let mut buf = [0xffu8; 32];
+        event.write_to(&mut buf);
+        assert_eq!(i32::from_ne_bytes(buf[0..4].try_into().unwrap()), 3);
+        assert_eq!(u32::from_ne_bytes(buf[4..8].try_into().unwrap()), 0x100);
+        assert_eq!(u32::from_ne_bytes(buf[12..16].try_into().unwrap()), 16);
+        assert_eq!(&buf[16..21], b"a.txt");
+        assert!(buf[21..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_queue_merges_and_overflows() {
+        init_arch_ops();
+        let group = InotifyGroup::new();
+        let mut state = group.state.lock();
+        let event = |wd| QueuedEvent {
+            wd,
+            mask: InotifyMask::MODIFY,
+            cookie: 0,
+            name: None,
+        };
+
+        // 与队尾相同的事件合并
+        state.push(event(1));
+        state.push(event(1));
+        assert_eq!(state.events.len(), 1);
+
+        // 队列满后只追加一个溢出事件
+        for wd in 2..=MAX_QUEUED_EVENTS as i32 + 2 {
+            state.push(event(wd));
+        }
+        assert_eq!(state.events.len(), MAX_QUEUED_EVENTS + 1);
+        let last = state.events.back().unwrap();
+        assert_eq!(last.wd, -1);
+        assert_eq!(last.mask, InotifyMask::Q_OVERFLOW);
+    }
+}
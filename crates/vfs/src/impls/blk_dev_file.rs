@@ -4,7 +4,10 @@ use alloc::sync::Arc;
 use sync::SpinLock;
 
 use crate::devno::get_blkdev_index;
-use crate::{Dentry, File, FsError, Inode, InodeMetadata, OpenFlags, SeekWhence, device_ops};
+use crate::{
+    Dentry, File, FsError, Inode, InodeMetadata, OpenFlags, SeekWhence, device_ops,
+    release_ofd_locks_on_drop,
+};
 
 /// 块设备文件
 pub struct BlkDeviceFile {
@@ -189,3 +192,9 @@ impl File for BlkDeviceFile {
         self
     }
 }
+
+impl Drop for BlkDeviceFile {
+    fn drop(&mut self) {
+        release_ofd_locks_on_drop(self);
+    }
+}
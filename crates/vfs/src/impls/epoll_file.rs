@@ -0,0 +1,440 @@
+//! epoll 实例文件
+//!
+//! `epoll_create1` 返回的文件描述符指向一个 [`EpollFile`]。每个监视项以
+//! （文件，文件描述符）为键，把自己作为 [`PollWaker`] 登记到被监视文件的等待队列：
+//! 文件状态改变时监视项进入就绪链表，并唤醒 epoll 实例自己的等待队列。
+//!
+//! [`collect`](EpollFile::collect) 取出就绪链表时重新查询每个文件，只报告仍然就绪的事件。
+//! 水平触发的监视项报告后留在就绪链表中，边沿触发的等下一次唤醒；
+//! `EPOLLONESHOT` 的监视项报告一次后停用，直到 `EPOLL_CTL_MOD` 重新启用。
+//!
+//! 监视项只持有文件的弱引用，文件的最后一个引用释放后监视项在下次收集时移除。
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use sync::SpinLock;
+use uapi::epoll::{EpollEvent, EpollEvents};
+
+use crate::poll::{PollQueue, PollWaker, readiness};
+use crate::{File, FileMode, FsError, InodeMetadata, InodeType, OpenFlags, TimeSpec};
+
+/// 嵌套 epoll 实例的最大深度（与 Linux 的 EP_MAX_NESTS 一致）
+const EP_MAX_NESTS: usize = 4;
+
+/// 监视项的键：被监视文件的地址与文件描述符
+type ItemKey = (usize, i32);
+
+fn item_key(fd: i32, file: &Arc<dyn File>) -> ItemKey {
+    (Arc::as_ptr(file) as *const () as usize, fd)
+}
+
+/// 监视项的可变状态
+struct ItemState {
+    /// 关注的事件与标志（`EPOLLERR`/`EPOLLHUP` 总是关注）
+    events: EpollEvents,
+    data: u64,
+    /// `EPOLLONESHOT` 报告后停用
+    disabled: bool,
+    /// 是否已在就绪链表中
+    queued: bool,
+}
+
+/// 一个监视项
+struct EpollItem {
+    key: ItemKey,
+    file: Weak<dyn File>,
+    ep: Weak<EpollShared>,
+    state: SpinLock<ItemState>,
+}
+
+impl PollWaker for EpollItem {
+    fn wake(&self, events: EpollEvents) {
+        {
+            let mut state = self.state.lock();
+            if state.disabled || state.queued {
+                return;
+            }
+            // 唤醒者给出了具体事件时，与关注的事件无关的唤醒可以忽略
+            if !events.is_empty() && !events.intersects(state.events) {
+                return;
+            }
+            state.queued = true;
+        }
+        if let Some(ep) = self.ep.upgrade() {
+            ep.inner.lock().ready.push_back(self.key);
+            ep.poll_queue.wake(EpollEvents::IN);
+        }
+    }
+}
+
+struct EpollInner {
+    items: BTreeMap<ItemKey, Arc<EpollItem>>,
+    ready: VecDeque<ItemKey>,
+}
+
+/// 监视项与 epoll 实例共享的部分
+struct EpollShared {
+    inner: SpinLock<EpollInner>,
+    /// 等待本实例就绪的一方（epoll_wait 或外层 epoll 实例）
+    poll_queue: PollQueue,
+}
+
+/// epoll 实例文件
+pub struct EpollFile {
+    shared: Arc<EpollShared>,
+    flags: SpinLock<OpenFlags>,
+}
+
+impl EpollFile {
+    /// 创建一个没有监视项的实例
+    pub fn new(flags: OpenFlags) -> Self {
+        Self {
+            shared: Arc::new(EpollShared {
+                inner: SpinLock::new(EpollInner {
+                    items: BTreeMap::new(),
+                    ready: VecDeque::new(),
+                }),
+                poll_queue: PollQueue::new(),
+            }),
+            flags: SpinLock::new(flags | OpenFlags::O_RDONLY),
+        }
+    }
+
+    /// EPOLL_CTL_ADD：监视文件描述符 `fd` 指向的 `file`
+    pub fn ctl_add(&self, fd: i32, file: &Arc<dyn File>, event: EpollEvent) -> Result<(), FsError> {
+        if let Some(target) = file.as_any().downcast_ref::<EpollFile>() {
+            if Arc::ptr_eq(&target.shared, &self.shared) {
+                return Err(FsError::InvalidArgument);
+            }
+            if target.nest_depth(&self.shared, 1)? {
+                return Err(FsError::TooManySymlinks);
+            }
+        }
+        let key = item_key(fd, file);
+        let item = Arc::new(EpollItem {
+            key,
+            file: Arc::downgrade(file),
+            ep: Arc::downgrade(&self.shared),
+            state: SpinLock::new(ItemState {
+                events: Self::interest(event.events),
+                data: event.data,
+                disabled: false,
+                queued: false,
+            }),
+        });
+        {
+            let mut inner = self.shared.inner.lock();
+            if inner.items.contains_key(&key) {
+                return Err(FsError::AlreadyExists);
+            }
+            inner.items.insert(key, item.clone());
+        }
+        Self::arm(&item, file);
+        Ok(())
+    }
+
+    /// EPOLL_CTL_MOD：修改监视项关注的事件，并重新启用 `EPOLLONESHOT` 停用的监视项
+    pub fn ctl_mod(&self, fd: i32, file: &Arc<dyn File>, event: EpollEvent) -> Result<(), FsError> {
+        let item = self
+            .shared
+            .inner
+            .lock()
+            .items
+            .get(&item_key(fd, file))
+            .cloned()
+            .ok_or(FsError::NotFound)?;
+        {
+            let mut state = item.state.lock();
+            let events = Self::interest(event.events);
+            if (events | state.events).contains(EpollEvents::EXCLUSIVE) {
+                return Err(FsError::InvalidArgument);
+            }
+            state.events = events;
+            state.data = event.data;
+            state.disabled = false;
+        }
+        Self::arm(&item, file);
+        Ok(())
+    }
+
+    /// EPOLL_CTL_DEL：移除监视项
+    pub fn ctl_del(&self, fd: i32, file: &Arc<dyn File>) -> Result<(), FsError> {
+        let key = item_key(fd, file);
+        let mut inner = self.shared.inner.lock();
+        inner.items.remove(&key).ok_or(FsError::NotFound)?;
+        inner.ready.retain(|k| *k != key);
+        Ok(())
+    }
+
+    /// 收集至多 `max` 个就绪事件，没有时返回空
+    pub fn collect(&self, max: usize) -> Vec<EpollEvent> {
+        let mut out = Vec::new();
+        let mut requeue = Vec::new();
+        while out.len() < max {
+            let item = {
+                let mut inner = self.shared.inner.lock();
+                let Some(key) = inner.ready.pop_front() else {
+                    break;
+                };
+                inner.items.get(&key).cloned()
+            };
+            // 已移除的监视项可能还留在链表中
+            let Some(item) = item else {
+                continue;
+            };
+            item.state.lock().queued = false;
+            let Some(file) = item.file.upgrade() else {
+                self.shared.inner.lock().items.remove(&item.key);
+                continue;
+            };
+            let revents = file.poll(None);
+
+            let mut state = item.state.lock();
+            if state.disabled {
+                continue;
+            }
+            let revents = revents & state.events & !EpollEvents::INPUT_FLAGS;
+            if revents.is_empty() {
+                continue;
+            }
+            out.push(EpollEvent {
+                events: revents.bits(),
+                data: state.data,
+            });
+            if state.events.contains(EpollEvents::ONESHOT) {
+                state.disabled = true;
+            } else if !state.events.contains(EpollEvents::ET) && !state.queued {
+                // 水平触发：下次收集时再查询
+                state.queued = true;
+                requeue.push(item.key);
+            }
+        }
+        if !requeue.is_empty() {
+            self.shared.inner.lock().ready.extend(requeue);
+        }
+        out
+    }
+
+    /// 关注的事件：用户给出的事件与标志，加上总是报告的 `EPOLLERR`/`EPOLLHUP`
+    fn interest(events: u32) -> EpollEvents {
+        EpollEvents::from_bits_truncate(events) | EpollEvents::ERR | EpollEvents::HUP
+    }
+
+    /// 把监视项登记到文件的等待队列，文件已经就绪时直接进入就绪链表
+    fn arm(item: &Arc<EpollItem>, file: &Arc<dyn File>) {
+        let waker: Arc<dyn PollWaker> = item.clone();
+        let revents = file.poll(Some(&waker));
+        if revents.intersects(item.state.lock().events) {
+            item.wake(revents);
+        }
+    }
+
+    /// 本实例（及其嵌套的实例）是否监视了 `target`，嵌套过深时返回 `ELOOP`
+    fn nest_depth(&self, target: &Arc<EpollShared>, depth: usize) -> Result<bool, FsError> {
+        if depth > EP_MAX_NESTS {
+            return Err(FsError::TooManySymlinks);
+        }
+        let files: Vec<Arc<dyn File>> = self
+            .shared
+            .inner
+            .lock()
+            .items
+            .values()
+            .filter_map(|item| item.file.upgrade())
+            .collect();
+        for ep in files
+            .iter()
+            .filter_map(|file| file.as_any().downcast_ref::<EpollFile>())
+        {
+            if Arc::ptr_eq(&ep.shared, target) || ep.nest_depth(target, depth + 1)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// 就绪链表中是否有仍然就绪的监视项
+    fn has_ready(&self) -> bool {
+        let items: Vec<Arc<EpollItem>> = {
+            let inner = self.shared.inner.lock();
+            inner
+                .ready
+                .iter()
+                .filter_map(|key| inner.items.get(key).cloned())
+                .collect()
+        };
+        items.iter().any(|item| {
+            let Some(file) = item.file.upgrade() else {
+                return false;
+            };
+            let revents = file.poll(None);
+            let state = item.state.lock();
+            !state.disabled && revents.intersects(state.events & !EpollEvents::INPUT_FLAGS)
+        })
+    }
+}
+
+impl File for EpollFile {
+    fn readable(&self) -> bool {
+        self.has_ready()
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            self.shared.poll_queue.register(waker);
+        }
+        readiness(self.has_ready(), false)
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        Ok(InodeMetadata {
+            inode_no: 0,
+            inode_type: InodeType::File,
+            size: 0,
+            mode: FileMode::S_IRUSR | FileMode::S_IWUSR,
+            uid: 0,
+            gid: 0,
+            atime: TimeSpec::zero(),
+            mtime: TimeSpec::zero(),
+            ctime: TimeSpec::zero(),
+            nlinks: 1,
+            blocks: 0,
+            rdev: 0,
+        })
+    }
+
+    fn flags(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impls::EventFdFile;
+    use sync::mock::init_arch_ops;
+
+    fn eventfd() -> Arc<dyn File> {
+        Arc::new(EventFdFile::new(0, false, OpenFlags::O_NONBLOCK))
+    }
+
+    fn event(events: EpollEvents, data: u64) -> EpollEvent {
+        EpollEvent {
+            events: events.bits(),
+            data,
+        }
+    }
+
+    fn signal(file: &Arc<dyn File>) {
+        file.write(&1u64.to_ne_bytes()).unwrap();
+    }
+
+    fn drain(file: &Arc<dyn File>) {
+        let mut buf = [0u8; 8];
+        file.read(&mut buf).unwrap();
+    }
+
+    #[test]
+    fn test_epoll_level_and_edge() {
+        init_arch_ops();
+        let ep = EpollFile::new(OpenFlags::empty());
+        let lt = eventfd();
+        let et = eventfd();
+        ep.ctl_add(3, &lt, event(EpollEvents::IN, 3)).unwrap();
+        ep.ctl_add(4, &et, event(EpollEvents::IN | EpollEvents::ET, 4))
+            .unwrap();
+        assert_eq!(
+            ep.ctl_add(3, &lt, event(EpollEvents::IN, 3)),
+            Err(FsError::AlreadyExists)
+        );
+        assert!(ep.collect(8).is_empty());
+
+        signal(&lt);
+        signal(&et);
+        assert!(ep.poll(None).contains(EpollEvents::IN));
+        let events = ep.collect(8);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.events == EpollEvents::IN.bits()));
+
+        // 水平触发仍然报告，边沿触发要等下一次状态变化
+        let events = ep.collect(8);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, 3);
+
+        drain(&lt);
+        assert!(ep.collect(8).is_empty());
+        signal(&et);
+        let events = ep.collect(8);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, 4);
+    }
+
+    #[test]
+    fn test_epoll_oneshot_and_del() {
+        init_arch_ops();
+        let ep = EpollFile::new(OpenFlags::empty());
+        let file = eventfd();
+        ep.ctl_add(5, &file, event(EpollEvents::IN | EpollEvents::ONESHOT, 5))
+            .unwrap();
+        signal(&file);
+        assert_eq!(ep.collect(8).len(), 1);
+        signal(&file);
+        assert!(ep.collect(8).is_empty());
+
+        // MOD 重新启用后立即就绪
+        ep.ctl_mod(5, &file, event(EpollEvents::IN, 5)).unwrap();
+        assert_eq!(ep.collect(8).len(), 1);
+
+        ep.ctl_del(5, &file).unwrap();
+        assert!(ep.collect(8).is_empty());
+        assert_eq!(ep.ctl_del(5, &file), Err(FsError::NotFound));
+    }
+
+    #[test]
+    fn test_epoll_nesting() {
+        init_arch_ops();
+        let outer: Arc<dyn File> = Arc::new(EpollFile::new(OpenFlags::empty()));
+        let inner: Arc<dyn File> = Arc::new(EpollFile::new(OpenFlags::empty()));
+        let outer_ep = outer.as_any().downcast_ref::<EpollFile>().unwrap();
+        let inner_ep = inner.as_any().downcast_ref::<EpollFile>().unwrap();
+
+        assert_eq!(
+            outer_ep.ctl_add(1, &outer, event(EpollEvents::IN, 1)),
+            Err(FsError::InvalidArgument)
+        );
+        outer_ep
+            .ctl_add(2, &inner, event(EpollEvents::IN, 2))
+            .unwrap();
+        assert_eq!(
+            inner_ep.ctl_add(3, &outer, event(EpollEvents::IN, 3)),
+            Err(FsError::TooManySymlinks)
+        );
+
+        // 内层实例就绪时外层实例收到通知
+        let file = eventfd();
+        inner_ep
+            .ctl_add(4, &file, event(EpollEvents::IN, 4))
+            .unwrap();
+        signal(&file);
+        let events = outer_ep.collect(8);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, 2);
+    }
+}
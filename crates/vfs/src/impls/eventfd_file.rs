@@ -0,0 +1,206 @@
+//! eventfd 文件
+//!
+//! [`EventFdFile`] 是一个 64 位计数器：`write` 加上写入的值，`read` 取出整个计数
+//! （`EFD_SEMAPHORE` 时每次只取 1）并清零。计数为 0 时读阻塞，加上写入值会超过
+//! `u64::MAX - 1` 时写阻塞，两者都在 `O_NONBLOCK` 下返回 `EAGAIN`。
+//! 计数变化时唤醒读写者与 poll/epoll 的等待者。
+
+use alloc::sync::Arc;
+use core::mem::size_of;
+use sync::SpinLock;
+use uapi::epoll::EpollEvents;
+
+use crate::poll::{PollQueue, PollWaker, readiness};
+use crate::{File, FileMode, FsError, InodeMetadata, InodeType, OpenFlags, TimeSpec, vfs_ops};
+
+/// 计数器的上限
+const EVENTFD_MAX: u64 = u64::MAX - 1;
+
+/// eventfd 文件
+pub struct EventFdFile {
+    count: SpinLock<u64>,
+    /// 信号量语义（EFD_SEMAPHORE）
+    semaphore: bool,
+    flags: SpinLock<OpenFlags>,
+    poll_queue: PollQueue,
+}
+
+impl EventFdFile {
+    /// 创建初值为 `initval` 的 eventfd，`flags` 中只有 `O_NONBLOCK` 影响读写
+    pub fn new(initval: u32, semaphore: bool, flags: OpenFlags) -> Self {
+        Self {
+            count: SpinLock::new(initval as u64),
+            semaphore,
+            flags: SpinLock::new(flags | OpenFlags::O_RDWR),
+            poll_queue: PollQueue::new(),
+        }
+    }
+
+    /// 等待通道：计数变化时唤醒
+    fn wait_chan(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn nonblock(&self) -> bool {
+        self.flags.lock().contains(OpenFlags::O_NONBLOCK)
+    }
+
+    /// 计数变化后唤醒等待者
+    fn wake(&self, events: EpollEvents) {
+        vfs_ops().wake_event(self.wait_chan());
+        self.poll_queue.wake(events);
+    }
+}
+
+impl File for EventFdFile {
+    fn readable(&self) -> bool {
+        *self.count.lock() > 0
+    }
+
+    fn writable(&self) -> bool {
+        *self.count.lock() < EVENTFD_MAX
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
+        if buf.len() < size_of::<u64>() {
+            return Err(FsError::InvalidArgument);
+        }
+        loop {
+            let value = {
+                let mut count = self.count.lock();
+                if *count > 0 {
+                    let value = if self.semaphore { 1 } else { *count };
+                    *count -= value;
+                    Some(value)
+                } else {
+                    None
+                }
+            };
+            if let Some(value) = value {
+                self.wake(EpollEvents::OUT);
+                buf[..size_of::<u64>()].copy_from_slice(&value.to_ne_bytes());
+                return Ok(size_of::<u64>());
+            }
+            if self.nonblock() {
+                return Err(FsError::WouldBlock);
+            }
+            if !vfs_ops().wait_event(self.wait_chan(), None, &|| self.readable()) {
+                return Err(FsError::Interrupted);
+            }
+        }
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        let Some(bytes) = buf.first_chunk::<8>() else {
+            return Err(FsError::InvalidArgument);
+        };
+        let value = u64::from_ne_bytes(*bytes);
+        if value == u64::MAX {
+            return Err(FsError::InvalidArgument);
+        }
+        loop {
+            let added = {
+                let mut count = self.count.lock();
+                if EVENTFD_MAX - *count >= value {
+                    *count += value;
+                    true
+                } else {
+                    false
+                }
+            };
+            if added {
+                if value > 0 {
+                    self.wake(EpollEvents::IN);
+                }
+                return Ok(size_of::<u64>());
+            }
+            if self.nonblock() {
+                return Err(FsError::WouldBlock);
+            }
+            let fits = || EVENTFD_MAX - *self.count.lock() >= value;
+            if !vfs_ops().wait_event(self.wait_chan(), None, &fits) {
+                return Err(FsError::Interrupted);
+            }
+        }
+    }
+
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            self.poll_queue.register(waker);
+        }
+        let count = *self.count.lock();
+        readiness(count > 0, count < EVENTFD_MAX)
+    }
+
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        Ok(InodeMetadata {
+            inode_no: 0,
+            inode_type: InodeType::File,
+            size: 0,
+            mode: FileMode::S_IRUSR | FileMode::S_IWUSR,
+            uid: 0,
+            gid: 0,
+            atime: TimeSpec::zero(),
+            mtime: TimeSpec::zero(),
+            ctime: TimeSpec::zero(),
+            nlinks: 1,
+            blocks: 0,
+            rdev: 0,
+        })
+    }
+
+    fn flags(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn set_status_flags(&self, flags: OpenFlags) -> Result<(), FsError> {
+        let mut cur = self.flags.lock();
+        cur.set(OpenFlags::O_NONBLOCK, flags.contains(OpenFlags::O_NONBLOCK));
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sync::mock::init_arch_ops;
+
+    fn read_u64(file: &EventFdFile) -> Result<u64, FsError> {
+        let mut buf = [0u8; 8];
+        file.read(&mut buf)?;
+        Ok(u64::from_ne_bytes(buf))
+    }
+
+    #[test]
+    fn test_eventfd_counter() {
+        init_arch_ops();
+        let file = EventFdFile::new(3, false, OpenFlags::O_NONBLOCK);
+        assert_eq!(file.write(&4u64.to_ne_bytes()), Ok(8));
+        assert_eq!(read_u64(&file), Ok(7));
+        assert_eq!(read_u64(&file), Err(FsError::WouldBlock));
+        assert_eq!(file.poll(None), EpollEvents::OUT | EpollEvents::WRNORM);
+
+        // 写满后不可写
+        assert_eq!(file.write(&EVENTFD_MAX.to_ne_bytes()), Ok(8));
+        assert_eq!(file.write(&1u64.to_ne_bytes()), Err(FsError::WouldBlock));
+        assert_eq!(
+            file.write(&u64::MAX.to_ne_bytes()),
+            Err(FsError::InvalidArgument)
+        );
+        assert_eq!(file.poll(None), EpollEvents::IN | EpollEvents::RDNORM);
+        assert_eq!(file.write(&[0u8; 4]), Err(FsError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_eventfd_semaphore() {
+        init_arch_ops();
+        let file = EventFdFile::new(2, true, OpenFlags::O_NONBLOCK);
+        assert_eq!(read_u64(&file), Ok(1));
+        assert_eq!(read_u64(&file), Ok(1));
+        assert_eq!(read_u64(&file), Err(FsError::WouldBlock));
+    }
+}
@@ -0,0 +1,366 @@
+//! inotify 实例文件
+//!
+//! `inotify_init1` 返回的文件描述符指向一个 [`InotifyFile`]：监视由
+//! [`add_watch`](InotifyFile::add_watch) / [`rm_watch`](InotifyFile::rm_watch) 管理，
+//! 事件以 `struct inotify_event` 序列的形式 `read` 出来，有事件时可读。
+//! 事件的产生见 [`fsnotify`](crate::fsnotify)。
+
+use alloc::sync::Arc;
+use sync::SpinLock;
+use uapi::epoll::EpollEvents;
+use uapi::inotify::InotifyMask;
+
+use crate::fsnotify::InotifyGroup;
+use crate::poll::{PollWaker, readiness};
+use crate::{
+    File, FileMode, FsError, Inode, InodeMetadata, InodeType, OpenFlags, TimeSpec, UserAccessGuard,
+};
+
+/// inotify 实例文件
+pub struct InotifyFile {
+    group: Arc<InotifyGroup>,
+    flags: SpinLock<OpenFlags>,
+}
+
+impl InotifyFile {
+    /// 创建一个没有监视的实例，`flags` 中只有 `O_NONBLOCK` 影响读取
+    pub fn new(flags: OpenFlags) -> Self {
+        Self {
+            group: InotifyGroup::new(),
+            flags: SpinLock::new(flags | OpenFlags::O_RDONLY),
+        }
+    }
+
+    /// 为 `inode` 登记或修改监视，返回监视描述符
+    pub fn add_watch(&self, inode: Arc<dyn Inode>, mask: InotifyMask) -> Result<i32, FsError> {
+        self.group.add_watch(inode, mask)
+    }
+
+    /// 移除监视描述符 `wd`
+    pub fn rm_watch(&self, wd: i32) -> Result<(), FsError> {
+        self.group.rm_watch(wd)
+    }
+}
+
+impl File for InotifyFile {
+    fn readable(&self) -> bool {
+        self.group.has_events()
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            self.group.register_poll(waker);
+        }
+        readiness(self.group.has_events(), false)
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
+        let nonblock = self.flags.lock().contains(OpenFlags::O_NONBLOCK);
+        self.group.read_events(buf, nonblock)
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        Ok(InodeMetadata {
+            inode_no: 0,
+            inode_type: InodeType::File,
+            size: 0,
+            mode: FileMode::S_IRUSR | FileMode::S_IWUSR,
+            uid: 0,
+            gid: 0,
+            atime: TimeSpec::zero(),
+            mtime: TimeSpec::zero(),
+            ctime: TimeSpec::zero(),
+            nlinks: 1,
+            blocks: 0,
+            rdev: 0,
+        })
+    }
+
+    fn flags(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn set_status_flags(&self, flags: OpenFlags) -> Result<(), FsError> {
+        let mut cur = self.flags.lock();
+        cur.set(OpenFlags::O_NONBLOCK, flags.contains(OpenFlags::O_NONBLOCK));
+        Ok(())
+    }
+
+    fn ioctl(&self, request: u32, arg: usize) -> Result<isize, FsError> {
+        use uapi::errno::{EINVAL, ENOTTY};
+        use uapi::ioctl::FIONREAD;
+
+        match request {
+            FIONREAD if arg == 0 => Ok(-EINVAL as isize),
+            FIONREAD => {
+                let pending = self.group.pending_bytes() as i32;
+                let _guard = UserAccessGuard::new();
+                // SAFETY: arg 非空，由系统调用层保证指向用户空间可写内存
+                unsafe { core::ptr::write_volatile(arg as *mut i32, pending) };
+                Ok(0)
+            }
+            _ => Ok(-ENOTTY as isize),
+        }
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::fsnotify::{fsnotify_create, fsnotify_delete, fsnotify_move};
+    use crate::{XattrFlags, XattrMap};
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::any::Any;
+    use sync::mock::init_arch_ops;
+
+    /// 只有元数据与扩展属性的 inode，目录允许缓存负目录项
+    struct DummyInode {
+        meta: InodeMetadata,
+        xattrs: XattrMap,
+    }
+
+    pub(crate) fn dummy(inode_type: InodeType) -> Arc<dyn Inode> {
+        dummy_with(InodeMetadata {
+            inode_no: 1,
+            inode_type,
+            size: 0,
+            mode: FileMode::empty(),
+            uid: 0,
+            gid: 0,
+            atime: TimeSpec::zero(),
+            mtime: TimeSpec::zero(),
+            ctime: TimeSpec::zero(),
+            nlinks: 0,
+            blocks: 0,
+            rdev: 0,
+        })
+    }
+
+    /// 按给定元数据（类型、权限位、属主等）构造
+    pub(crate) fn dummy_with(meta: InodeMetadata) -> Arc<dyn Inode> {
+        Arc::new(DummyInode {
+            meta,
+            xattrs: XattrMap::new(),
+        })
+    }
+
+    impl Inode for DummyInode {
+        fn metadata(&self) -> Result<InodeMetadata, FsError> {
+            Ok(self.meta.clone())
+        }
+        fn read_at(&self, _: usize, _: &mut [u8]) -> Result<usize, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn write_at(&self, _: usize, _: &[u8]) -> Result<usize, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn lookup(&self, _: &str) -> Result<Arc<dyn Inode>, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn create(&self, _: &str, _: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn mkdir(&self, _: &str, _: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn symlink(&self, _: &str, _: &str) -> Result<Arc<dyn Inode>, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn link(&self, _: &str, _: &Arc<dyn Inode>) -> Result<(), FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn unlink(&self, _: &str) -> Result<(), FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn rmdir(&self, _: &str) -> Result<(), FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn rename(&self, _: &str, _: Arc<dyn Inode>, _: &str) -> Result<(), FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn readdir(&self) -> Result<Vec<crate::DirEntry>, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn truncate(&self, _: usize) -> Result<(), FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn sync(&self) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn cache_negative(&self) -> bool {
+            self.meta.inode_type == InodeType::Directory
+        }
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        fn set_times(&self, _: Option<TimeSpec>, _: Option<TimeSpec>) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn readlink(&self) -> Result<String, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn mknod(&self, _: &str, _: FileMode, _: u64) -> Result<Arc<dyn Inode>, FsError> {
+            Err(FsError::NotSupported)
+        }
+        fn chown(&self, _: u32, _: u32) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn chmod(&self, _: FileMode) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn getxattr(&self, name: &str) -> Result<Vec<u8>, FsError> {
+            self.xattrs.get(name)
+        }
+        fn setxattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> Result<(), FsError> {
+            self.xattrs.set(name, value, flags)
+        }
+        fn listxattr(&self) -> Result<Vec<String>, FsError> {
+            Ok(self.xattrs.list())
+        }
+        fn removexattr(&self, name: &str) -> Result<(), FsError> {
+            self.xattrs.remove(name)
+        }
+    }
+
+    /// 把 `read` 的结果拆成 (wd, mask, cookie, name)
+    fn parse(buf: &[u8]) -> Vec<(i32, u32, u32, String)> {
+        let word = |at: usize| u32::from_ne_bytes(buf[at..at + 4].try_into().unwrap());
+        let mut events = Vec::new();
+        let mut at = 0;
+        while at < buf.len() {
+            let len = word(at + 12) as usize;
+            let name = &buf[at + 16..at + 16 + len];
+            let end = name.iter().position(|&b| b == 0).unwrap_or(len);
+            events.push((
+                word(at) as i32,
+                word(at + 4),
+                word(at + 8),
+                String::from_utf8(name[..end].to_vec()).unwrap(),
+            ));
+            at += 16 + len;
+        }
+        events
+    }
+
+    #[test]
+    fn test_inotify_create_and_nonblock() {
+        init_arch_ops();
+        let file = InotifyFile::new(OpenFlags::O_NONBLOCK);
+        let dir = dummy(InodeType::Directory);
+        let wd = file
+            .add_watch(dir.clone(), InotifyMask::CREATE | InotifyMask::ONLYDIR)
+            .unwrap();
+        let mut buf = [0u8; 256];
+        assert!(!file.readable());
+        assert!(matches!(file.read(&mut buf), Err(FsError::WouldBlock)));
+
+        fsnotify_create(dir.as_ref(), "new", false);
+        fsnotify_create(dir.as_ref(), "sub", true);
+        assert!(file.readable());
+        let n = file.read(&mut buf).unwrap();
+        let events = parse(&buf[..n]);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], (wd, 0x100, 0, String::from("new")));
+        assert_eq!(events[1], (wd, 0x4000_0100, 0, String::from("sub")));
+
+        // 缓冲区放不下第一个事件
+        fsnotify_create(dir.as_ref(), "x", false);
+        assert!(matches!(
+            file.read(&mut buf[..16]),
+            Err(FsError::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn test_inotify_watch_flags() {
+        init_arch_ops();
+        let file = InotifyFile::new(OpenFlags::O_NONBLOCK);
+        let reg = dummy(InodeType::File);
+        assert!(matches!(
+            file.add_watch(reg.clone(), InotifyMask::MODIFY | InotifyMask::ONLYDIR),
+            Err(FsError::NotDirectory)
+        ));
+        assert!(matches!(
+            file.add_watch(reg.clone(), InotifyMask::ONLYDIR),
+            Err(FsError::InvalidArgument)
+        ));
+        let wd = file.add_watch(reg.clone(), InotifyMask::MODIFY).unwrap();
+        // 同一 inode 返回同一个监视描述符
+        assert_eq!(
+            file.add_watch(reg.clone(), InotifyMask::ATTRIB | InotifyMask::MASK_ADD),
+            Ok(wd)
+        );
+        assert!(matches!(
+            file.add_watch(reg.clone(), InotifyMask::ATTRIB | InotifyMask::MASK_CREATE),
+            Err(FsError::AlreadyExists)
+        ));
+
+        file.rm_watch(wd).unwrap();
+        assert!(matches!(file.rm_watch(wd), Err(FsError::InvalidArgument)));
+        let mut buf = [0u8; 64];
+        let n = file.read(&mut buf).unwrap();
+        assert_eq!(parse(&buf[..n]), [(wd, 0x8000, 0, String::new())]);
+    }
+
+    #[test]
+    fn test_inotify_delete_and_move() {
+        init_arch_ops();
+        let file = InotifyFile::new(OpenFlags::O_NONBLOCK);
+        let dir = dummy(InodeType::Directory);
+        let victim = dummy(InodeType::File);
+        let dir_wd = file
+            .add_watch(dir.clone(), InotifyMask::ALL_EVENTS)
+            .unwrap();
+        let wd = file
+            .add_watch(victim.clone(), InotifyMask::ALL_EVENTS)
+            .unwrap();
+
+        fsnotify_move(dir.as_ref(), "a", dir.as_ref(), "b", victim.as_ref(), false);
+        // 链接数为 0：删除后监视被移除
+        fsnotify_delete(dir.as_ref(), "b", victim.as_ref(), false);
+        let mut buf = [0u8; 512];
+        let n = file.read(&mut buf).unwrap();
+        let events = parse(&buf[..n]);
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[0].1, 0x40);
+        assert_eq!(events[1].1, 0x80);
+        assert_eq!(events[0].2, events[1].2);
+        assert_ne!(events[0].2, 0);
+        assert_eq!(events[2], (wd, 0x800, 0, String::new()));
+        assert_eq!(events[3], (dir_wd, 0x200, 0, String::from("b")));
+        assert_eq!(events[4], (wd, 0x400, 0, String::new()));
+        assert_eq!(events[5], (wd, 0x8000, 0, String::new()));
+        assert!(matches!(file.rm_watch(wd), Err(FsError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_inotify_oneshot() {
+        init_arch_ops();
+        let file = InotifyFile::new(OpenFlags::O_NONBLOCK);
+        let dir = dummy(InodeType::Directory);
+        let wd = file
+            .add_watch(dir.clone(), InotifyMask::CREATE | InotifyMask::ONESHOT)
+            .unwrap();
+        fsnotify_create(dir.as_ref(), "a", false);
+        fsnotify_create(dir.as_ref(), "b", false);
+        let mut buf = [0u8; 256];
+        let n = file.read(&mut buf).unwrap();
+        let events = parse(&buf[..n]);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], (wd, 0x100, 0, String::from("a")));
+        assert_eq!(events[1], (wd, 0x8000, 0, String::new()));
+    }
+}
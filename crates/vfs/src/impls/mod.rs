@@ -2,12 +2,18 @@
 
 mod blk_dev_file;
 mod char_dev_file;
+mod epoll_file;
+mod eventfd_file;
+pub(crate) mod inotify_file;
 mod pipe_file;
 mod reg_file;
 mod stdio_file;
 
 pub use blk_dev_file::BlkDeviceFile;
 pub use char_dev_file::CharDeviceFile;
+pub use epoll_file::EpollFile;
+pub use eventfd_file::EventFdFile;
+pub use inotify_file::InotifyFile;
 pub use pipe_file::PipeFile;
 pub use reg_file::RegFile;
 pub use stdio_file::{StderrFile, StdinFile, StdoutFile, create_stdio_files};
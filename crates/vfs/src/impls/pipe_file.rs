@@ -11,11 +11,16 @@
 //! - `OpenFlags::O_NONBLOCK` 等标志位会被保存，但暂未影响读写行为。
 //!
 //! 若需要更接近 POSIX 的阻塞/唤醒、`EAGAIN`、`SIGPIPE` 等语义，需要在此基础上补齐。
+//!
+//! 两端共享一个 [`PollQueue`]：读写与关闭端点时唤醒 poll/epoll 的等待者。
 
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
 use sync::SpinLock;
+use uapi::epoll::EpollEvents;
 
+use crate::poll::{PollQueue, PollWaker};
+use crate::splice::{SpliceBuffer, write_all};
 use crate::{File, FileMode, FsError, InodeMetadata, InodeType, OpenFlags, TimeSpec};
 
 /// 管道环形缓冲区（仅内存结构）
@@ -79,6 +84,20 @@ impl PipeRingBuffer {
         Ok(nread)
     }
 
+    /// 复制开头的数据到 `buf` 但不取出
+    fn peek(&self, buf: &mut [u8]) -> usize {
+        let npeek = buf.len().min(self.buffer.len());
+        for (dst, &src) in buf.iter_mut().zip(self.buffer.iter()) {
+            *dst = src;
+        }
+        npeek
+    }
+
+    /// 丢弃开头的 `n` 字节
+    fn consume(&mut self, n: usize) {
+        self.buffer.drain(..n.min(self.buffer.len()));
+    }
+
     fn write(&mut self, buf: &[u8]) -> Result<usize, FsError> {
         if self.read_end_count == 0 {
             return Err(FsError::BrokenPipe);
@@ -107,6 +126,8 @@ impl PipeRingBuffer {
 pub struct PipeFile {
     /// 共享的环形缓冲区
     buffer: Arc<SpinLock<PipeRingBuffer>>,
+    /// 两端共享的就绪等待队列
+    poll_queue: Arc<PollQueue>,
     /// 文件端点类型
     end_type: PipeEnd,
     /// 打开标志位 (支持 O_NONBLOCK 等)
@@ -125,6 +146,7 @@ impl PipeFile {
     /// 创建管道对 (返回 [读端, 写端])
     pub fn create_pair() -> (Self, Self) {
         let buffer = Arc::new(SpinLock::new(PipeRingBuffer::new()));
+        let poll_queue = Arc::new(PollQueue::new());
 
         {
             let mut buf = buffer.lock();
@@ -134,6 +156,7 @@ impl PipeFile {
 
         let read_end = Self {
             buffer: buffer.clone(),
+            poll_queue: poll_queue.clone(),
             end_type: PipeEnd::Read,
             flags: SpinLock::new(OpenFlags::empty()),
             owner: SpinLock::new(None),
@@ -141,6 +164,7 @@ impl PipeFile {
 
         let write_end = Self {
             buffer,
+            poll_queue,
             end_type: PipeEnd::Write,
             flags: SpinLock::new(OpenFlags::empty()),
             owner: SpinLock::new(None),
@@ -189,8 +213,11 @@ impl File for PipeFile {
             return Err(FsError::InvalidArgument);
         }
 
-        let mut ring_buf = self.buffer.lock();
-        ring_buf.read(buf)
+        let nread = self.buffer.lock().read(buf)?;
+        if nread > 0 {
+            self.poll_queue.wake(EpollEvents::OUT);
+        }
+        Ok(nread)
     }
 
     fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
@@ -198,8 +225,74 @@ impl File for PipeFile {
             return Err(FsError::InvalidArgument);
         }
 
-        let mut ring_buf = self.buffer.lock();
-        ring_buf.write(buf)
+        let nwritten = self.buffer.lock().write(buf)?;
+        if nwritten > 0 {
+            self.poll_queue.wake(EpollEvents::IN);
+        }
+        Ok(nwritten)
+    }
+
+    /// 先复制再按写出的字节数取出，输出端写不下的数据留在管道中
+    fn splice_to(
+        &self,
+        _offset: Option<&mut usize>,
+        out: &dyn File,
+        mut out_offset: Option<&mut usize>,
+        len: usize,
+    ) -> Result<usize, FsError> {
+        if self.end_type != PipeEnd::Read {
+            return Err(FsError::InvalidArgument);
+        }
+
+        let mut buf = SpliceBuffer::take();
+        let mut total = 0;
+        while total < len {
+            let chunk = (len - total).min(buf.len());
+            let n = self.buffer.lock().peek(&mut buf[..chunk]);
+            if n == 0 {
+                break;
+            }
+            let written = match write_all(out, out_offset.as_deref_mut(), &buf[..n]) {
+                Ok(written) => written,
+                Err(e) if total == 0 => return Err(e),
+                Err(_) => break,
+            };
+            if written > 0 {
+                self.buffer.lock().consume(written);
+                self.poll_queue.wake(EpollEvents::OUT);
+            }
+            total += written;
+            if written < n {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            self.poll_queue.register(waker);
+        }
+        let buf = self.buffer.lock();
+        let mut events = EpollEvents::empty();
+        match self.end_type {
+            PipeEnd::Read => {
+                if !buf.buffer.is_empty() {
+                    events |= EpollEvents::IN | EpollEvents::RDNORM;
+                }
+                if buf.write_end_count == 0 {
+                    events |= EpollEvents::HUP;
+                }
+            }
+            PipeEnd::Write => {
+                if buf.read_end_count == 0 {
+                    events |= EpollEvents::ERR;
+                } else if buf.buffer.len() < buf.capacity {
+                    events |= EpollEvents::OUT | EpollEvents::WRNORM;
+                }
+            }
+        }
+        events
     }
 
     fn metadata(&self) -> Result<InodeMetadata, FsError> {
@@ -251,10 +344,13 @@ impl File for PipeFile {
 
 impl Drop for PipeFile {
     fn drop(&mut self) {
-        let mut buf = self.buffer.lock();
-        match self.end_type {
-            PipeEnd::Read => buf.read_end_count -= 1,
-            PipeEnd::Write => buf.write_end_count -= 1,
+        {
+            let mut buf = self.buffer.lock();
+            match self.end_type {
+                PipeEnd::Read => buf.read_end_count -= 1,
+                PipeEnd::Write => buf.write_end_count -= 1,
+            }
         }
+        self.poll_queue.wake(EpollEvents::HUP | EpollEvents::ERR);
     }
 }
@@ -3,7 +3,11 @@
 use alloc::sync::Arc;
 use sync::SpinLock;
 
-use crate::{Dentry, File, FsError, Inode, InodeMetadata, OpenFlags, SeekWhence};
+use crate::splice::{copy_through_buffer, splice_file_pages};
+use crate::{
+    Dentry, File, FsError, Inode, InodeMetadata, OpenFlags, SeekWhence, fsnotify_modify,
+    release_ofd_locks_on_drop,
+};
 
 /// 普通文件的 File 实现
 ///
@@ -106,7 +110,11 @@ impl File for RegFile {
         let nwritten = self.inode.write_at(write_offset, buf)?;
 
         *offset_guard = write_offset + nwritten;
+        drop(offset_guard);
 
+        if nwritten > 0 {
+            fsnotify_modify(&self.dentry);
+        }
         Ok(nwritten)
     }
 
@@ -167,10 +175,47 @@ impl File for RegFile {
     }
 
     fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize, FsError> {
-        self.inode.write_at(offset, buf)
+        let nwritten = self.inode.write_at(offset, buf)?;
+        if nwritten > 0 {
+            fsnotify_modify(&self.dentry);
+        }
+        Ok(nwritten)
+    }
+
+    /// 输出端支持时直接交出文件页，否则经缓冲区中转；始终按偏移量读取，只前移送出的部分
+    fn splice_to(
+        &self,
+        offset: Option<&mut usize>,
+        out: &dyn File,
+        out_offset: Option<&mut usize>,
+        len: usize,
+    ) -> Result<usize, FsError> {
+        if !self.readable() {
+            return Err(FsError::PermissionDenied);
+        }
+
+        let mut offset_guard = None;
+        let pos = match offset {
+            Some(pos) => pos,
+            None => &mut **offset_guard.insert(self.offset.lock()),
+        };
+
+        if out_offset.is_none() {
+            match splice_file_pages(&self.inode, pos, out, len) {
+                Err(FsError::NotSupported) => {}
+                result => return result,
+            }
+        }
+        copy_through_buffer(self, Some(pos), out, out_offset, len)
     }
 
     fn as_any(&self) -> &dyn core::any::Any {
         self
     }
 }
+
+impl Drop for RegFile {
+    fn drop(&mut self) {
+        release_ofd_locks_on_drop(self);
+    }
+}
@@ -1,27 +1,18 @@
 //! 标准 I/O 文件实现
 //!
-//! 提供标准输入、输出、错误输出的文件接口，直接操作控制台，不依赖 Inode。
+//! 提供标准输入、输出、错误输出的文件接口，不依赖 Inode。
+//! 读写与 ioctl 都经由控制台终端（[`console_tty`]），与 `/dev/console` 共享 termios 和行规程。
 
 use alloc::sync::Arc;
-use sync::SpinLock;
-use uapi::ioctl::Termios;
+use uapi::epoll::EpollEvents;
 
-use crate::{File, FileMode, FsError, InodeMetadata, InodeType, UserAccessGuard, vfs_ops};
-
-/// 全局终端设置（所有标准I/O文件共享）
-static STDIO_TERMIOS: SpinLock<Termios> = SpinLock::new(Termios::DEFAULT);
-
-/// 全局窗口大小（所有标准I/O文件共享）
-static STDIO_WINSIZE: SpinLock<uapi::ioctl::WinSize> = SpinLock::new(uapi::ioctl::WinSize {
-    ws_row: 24,
-    ws_col: 80,
-    ws_xpixel: 0,
-    ws_ypixel: 0,
-});
+use crate::poll::PollWaker;
+use crate::tty::console_tty;
+use crate::{File, FileMode, FsError, InodeMetadata, InodeType, vfs_ops};
 
 /// 标准输入文件
 ///
-/// 从控制台读取输入，行缓冲模式。
+/// 从控制台终端读取输入，默认为规范模式（行缓冲）。
 pub struct StdinFile;
 
 impl File for StdinFile {
@@ -34,47 +25,7 @@ impl File for StdinFile {
     }
 
     fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
-        const ICRNL: u32 = 0x0100;
-        const INLCR: u32 = 0x0040;
-        const IGNCR: u32 = 0x0080;
-        const ICANON: u32 = 0x0002;
-        const ECHO: u32 = 0x0008;
-
-        let term = *STDIO_TERMIOS.lock();
-        let canonical = (term.c_lflag & ICANON) != 0;
-        let do_echo = (term.c_lflag & ECHO) != 0;
-
-        let mut count = 0usize;
-
-        while count < buf.len() {
-            let ch_opt = vfs_ops().console_getchar();
-            let mut ch = match ch_opt {
-                Some(c) => c,
-                None => break,
-            };
-
-            if (term.c_iflag & IGNCR) != 0 && ch == b'\r' {
-                continue;
-            }
-            if (term.c_iflag & ICRNL) != 0 && ch == b'\r' {
-                ch = b'\n';
-            } else if (term.c_iflag & INLCR) != 0 && ch == b'\n' {
-                ch = b'\r';
-            }
-
-            if do_echo {
-                vfs_ops().console_putchar(ch);
-            }
-
-            buf[count] = ch;
-            count += 1;
-
-            if !canonical || ch == b'\n' {
-                break;
-            }
-        }
-
-        Ok(count)
+        console_tty().read(buf, false)
     }
 
     fn write(&self, _buf: &[u8]) -> Result<usize, FsError> {
@@ -99,7 +50,11 @@ impl File for StdinFile {
     }
 
     fn ioctl(&self, request: u32, arg: usize) -> Result<isize, FsError> {
-        stdio_ioctl(request, arg)
+        console_tty().ioctl(request, arg)
+    }
+
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        console_tty().poll(waker) & (EpollEvents::IN | EpollEvents::RDNORM | EpollEvents::HUP)
     }
 
     fn as_any(&self) -> &dyn core::any::Any {
@@ -126,14 +81,7 @@ impl File for StdoutFile {
     }
 
     fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
-        if let Ok(s) = core::str::from_utf8(buf) {
-            vfs_ops().console_write_str(s);
-        } else {
-            for &byte in buf {
-                vfs_ops().console_putchar(byte);
-            }
-        }
-        Ok(buf.len())
+        console_tty().write(buf)
     }
 
     fn metadata(&self) -> Result<InodeMetadata, FsError> {
@@ -154,7 +102,11 @@ impl File for StdoutFile {
     }
 
     fn ioctl(&self, request: u32, arg: usize) -> Result<isize, FsError> {
-        stdio_ioctl(request, arg)
+        console_tty().ioctl(request, arg)
+    }
+
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        console_tty().poll(waker) & (EpollEvents::OUT | EpollEvents::WRNORM | EpollEvents::HUP)
     }
 
     fn as_any(&self) -> &dyn core::any::Any {
@@ -181,14 +133,7 @@ impl File for StderrFile {
     }
 
     fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
-        if let Ok(s) = core::str::from_utf8(buf) {
-            vfs_ops().console_write_str(s);
-        } else {
-            for &byte in buf {
-                vfs_ops().console_putchar(byte);
-            }
-        }
-        Ok(buf.len())
+        console_tty().write(buf)
     }
 
     fn metadata(&self) -> Result<InodeMetadata, FsError> {
@@ -209,101 +154,15 @@ impl File for StderrFile {
     }
 
     fn ioctl(&self, request: u32, arg: usize) -> Result<isize, FsError> {
-        stdio_ioctl(request, arg)
+        console_tty().ioctl(request, arg)
     }
 
-    fn as_any(&self) -> &dyn core::any::Any {
-        self
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        console_tty().poll(waker) & (EpollEvents::OUT | EpollEvents::WRNORM | EpollEvents::HUP)
     }
-}
 
-/// 通用的 stdio ioctl 实现
-fn stdio_ioctl(request: u32, arg: usize) -> Result<isize, FsError> {
-    use uapi::errno::{EINVAL, ENOTTY};
-    use uapi::ioctl::*;
-
-    match request {
-        TCGETS => {
-            if arg == 0 {
-                return Ok(-EINVAL as isize);
-            }
-
-            unsafe {
-                let _guard = UserAccessGuard::new();
-                let termios_ptr = arg as *mut Termios;
-                if termios_ptr.is_null() {
-                    return Ok(-EINVAL as isize);
-                }
-
-                core::ptr::write_bytes(termios_ptr as *mut u8, 0, core::mem::size_of::<Termios>());
-
-                let termios = *STDIO_TERMIOS.lock();
-                core::ptr::write_volatile(termios_ptr, termios);
-            }
-            Ok(0)
-        }
-
-        TCSETS | TCSETSW | TCSETSF => {
-            if arg == 0 {
-                return Ok(-EINVAL as isize);
-            }
-
-            unsafe {
-                let _guard = UserAccessGuard::new();
-                let termios_ptr = arg as *const Termios;
-                if termios_ptr.is_null() {
-                    return Ok(-EINVAL as isize);
-                }
-
-                let new_termios = core::ptr::read_volatile(termios_ptr);
-                *STDIO_TERMIOS.lock() = new_termios;
-            }
-            Ok(0)
-        }
-
-        TIOCGWINSZ => {
-            if arg == 0 {
-                return Ok(-EINVAL as isize);
-            }
-
-            unsafe {
-                let _guard = UserAccessGuard::new();
-                let winsize_ptr = arg as *mut uapi::ioctl::WinSize;
-                if winsize_ptr.is_null() {
-                    return Ok(-EINVAL as isize);
-                }
-
-                core::ptr::write_bytes(
-                    winsize_ptr as *mut u8,
-                    0,
-                    core::mem::size_of::<uapi::ioctl::WinSize>(),
-                );
-
-                let winsize = *STDIO_WINSIZE.lock();
-                core::ptr::write_volatile(winsize_ptr, winsize);
-            }
-            Ok(0)
-        }
-
-        TIOCSWINSZ => {
-            if arg == 0 {
-                return Ok(-EINVAL as isize);
-            }
-
-            unsafe {
-                let _guard = UserAccessGuard::new();
-                let winsize_ptr = arg as *const uapi::ioctl::WinSize;
-                if winsize_ptr.is_null() {
-                    return Ok(-EINVAL as isize);
-                }
-
-                let new_winsize = core::ptr::read_volatile(winsize_ptr);
-                *STDIO_WINSIZE.lock() = new_winsize;
-            }
-            Ok(0)
-        }
-
-        _ => Ok(-ENOTTY as isize),
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
     }
 }
 
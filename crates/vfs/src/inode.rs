@@ -12,7 +12,7 @@ use alloc::vec::Vec;
 use core::any::Any;
 use uapi::time::TimeSpec;
 
-use crate::{Dentry, FsError};
+use crate::{Dentry, FsError, XattrFlags};
 
 /// 文件类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -163,6 +163,13 @@ pub trait Inode: Send + Sync + Any {
     /// 在目录中创建文件
     fn create(&self, name: &str, mode: FileMode) -> Result<Arc<dyn Inode>, FsError>;
 
+    /// 在目录中创建不在任何目录项中的匿名普通文件（O_TMPFILE）
+    ///
+    /// 新文件的链接数为 0，最后一个引用释放时回收，之前可以用 [`link`](Inode::link) 为它命名。
+    fn tmpfile(&self, _mode: FileMode) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::NotSupported)
+    }
+
     /// 在目录中创建子目录
     fn mkdir(&self, name: &str, mode: FileMode) -> Result<Arc<dyn Inode>, FsError>;
 
@@ -208,6 +215,14 @@ pub trait Inode: Send + Sync + Any {
         true
     }
 
+    /// 是否允许 VFS 缓存在该目录中查找不到的名字（负目录项）
+    ///
+    /// 只有目录内容只会经由 VFS 改变的文件系统才能返回 `true`；
+    /// 子项会凭空出现的目录（procfs、devpts 等）必须保持默认值。
+    fn cache_negative(&self) -> bool {
+        false
+    }
+
     /// 向下转型为 &dyn Any，用于支持 downcast
     fn as_any(&self) -> &dyn Any;
 
@@ -225,6 +240,28 @@ pub trait Inode: Send + Sync + Any {
 
     /// 修改文件权限模式
     fn chmod(&self, _mode: FileMode) -> Result<(), FsError>;
+
+    /// 读取扩展属性的值（`name` 带命名空间前缀）
+    ///
+    /// 属性不存在时返回 [`FsError::NoData`]，文件系统不支持扩展属性时返回 [`FsError::NotSupported`]。
+    fn getxattr(&self, _name: &str) -> Result<Vec<u8>, FsError> {
+        Err(FsError::NotSupported)
+    }
+
+    /// 设置扩展属性，`flags` 的检查见 [`check_xattr_flags`](crate::check_xattr_flags)
+    fn setxattr(&self, _name: &str, _value: &[u8], _flags: XattrFlags) -> Result<(), FsError> {
+        Err(FsError::NotSupported)
+    }
+
+    /// 列出全部扩展属性名（带命名空间前缀）
+    fn listxattr(&self) -> Result<Vec<String>, FsError> {
+        Err(FsError::NotSupported)
+    }
+
+    /// 删除扩展属性，属性不存在时返回 [`FsError::NoData`]
+    fn removexattr(&self, _name: &str) -> Result<(), FsError> {
+        Err(FsError::NotSupported)
+    }
 }
 
 /// 为 `Arc<dyn Inode>` 提供向下转型辅助方法
@@ -18,9 +18,39 @@
 //! ## 路径、挂载与缓存
 //!
 //! - 路径解析位于 [`path`]，核心入口是 [`vfs_lookup`] 等函数。
-//! - 挂载表位于 [`mount`]，支持“同一路径多次挂载”的栈式语义，并在路径解析中自动跟随挂载点。
+//! - 挂载表位于 [`mount`]，每个挂载命名空间一张，支持“同一路径多次挂载”的栈式语义与绑定/移动/重新挂载，并在路径解析中自动跟随挂载点。
 //! - 目录项缓存（[`DentryCache`]）用于减少重复路径解析开销。
 //!
+//! ## 文件变化通知
+//!
+//! [`fsnotify`] 维护 inotify 实例登记的监视，路径层在创建、删除、重命名与写入之后调用
+//! `fsnotify_*` 投递事件；实例本身是 [`InotifyFile`]。
+//!
+//! ## 就绪通知
+//!
+//! [`File::poll`] 返回文件的就绪事件，并把等待者登记到文件的 [`PollQueue`]（见 [`poll`]），
+//! poll/select 与 epoll（[`EpollFile`]）都建立在它之上。
+//!
+//! ## 零拷贝传输
+//!
+//! [`File::splice_to`] 是 sendfile/splice 的数据通路：默认经共享的内核缓冲区中转，
+//! 普通文件到支持 [`File::splice_from`] 的输出端（TCP socket）时直接交出文件页。
+//!
+//! ## 扩展属性
+//!
+//! [`Inode`] 的 `getxattr` 等方法读写扩展属性，命名空间与 setxattr 标志见 [`xattr`]。
+//!
+//! ## 权限检查与 ACL
+//!
+//! [`inode_permission`] 按模式位、访问 ACL 与能力判断调用者能否访问 inode（见 [`permission`]），
+//! 路径遍历按 [`VfsOps::current_fs_cred`] 给出的身份检查目录搜索权限；
+//! POSIX ACL 建立在扩展属性之上，chmod、umask 与默认 ACL 继承的规则见 [`acl`]。
+//!
+//! ## 终端
+//!
+//! [`tty`] 提供终端对象与 N_TTY 行规程，`/dev/tty*`、`/dev/console` 与标准 I/O 文件共用；
+//! 伪终端由 `/dev/ptmx`（[`open_ptmx`]）创建，从设备出现在 devpts 中。
+//!
 //! ## 运行时依赖
 //!
 //! VFS 通过 [`ops::VfsOps`] / [`ops::DeviceOps`] 抽象运行时能力（时间、控制台、设备访问、用户态访问保护等），
@@ -31,9 +61,19 @@
 
 extern crate alloc;
 
+/// 覆盖率计数点：启用 `coverage` feature 时展开为 `test_support::cov_point!()`，否则为空
+macro_rules! kcov {
+    () => {
+        #[cfg(feature = "coverage")]
+        test_support::cov_point!();
+    };
+}
+
 pub mod dev;
 pub mod error;
+pub mod fsnotify;
 pub mod ops;
+pub mod poll;
 
 // 先声明基础模块，后续会添加更多
 mod adapter;
@@ -47,6 +87,11 @@ pub mod impls;
 mod inode;
 mod mount;
 mod path;
+mod splice;
+pub mod tty;
+pub mod xattr;
+pub mod acl;
+pub mod permission;
 
 // Re-export ops
 pub use ops::{
@@ -69,36 +114,65 @@ pub use file::File;
 // Re-export inode
 pub use inode::{DirEntry, FileMode, Inode, InodeMetadata, InodeType};
 
+// Re-export xattr
+pub use xattr::{XattrFlags, XattrMap, XattrNamespace, check_xattr_flags};
+// Re-export acl & permission
+pub use acl::{
+    AclEntry, AclInherit, AclTag, AclType, PosixAcl, get_acl, posix_acl_chmod, posix_acl_create,
+    set_acl,
+};
+pub use permission::{
+    FsCred, generic_permission, inode_permission, may_lookup, may_modify_dir, may_open,
+};
+
 // Re-export file_system
 pub use file_system::{FileSystem, StatFs};
 
 // Re-export dentry
-pub use dentry::{DENTRY_CACHE, Dentry, DentryCache};
+pub use dentry::{DEFAULT_DENTRY_BUDGET, DENTRY_CACHE, Dentry, DentryCache, DentryStats};
 
 // Re-export mount
-pub use mount::{MOUNT_TABLE, MountFlags, MountPoint, MountTable, get_root_dentry};
+pub use mount::{
+    MountFlags, MountNamespace, MountPoint, current_mnt_ns, get_root_dentry, init_mnt_ns,
+    mnt_want_write,
+};
 
 // Re-export path
 pub use path::{
-    PathComponent, normalize_path, parse_path, split_path, vfs_lookup, vfs_lookup_from,
-    vfs_lookup_no_follow, vfs_lookup_no_follow_from,
+    PathComponent, ResolvePolicy, normalize_path, parse_path, split_path, vfs_lookup,
+    vfs_lookup_from, vfs_lookup_from_with, vfs_lookup_no_follow, vfs_lookup_no_follow_from,
 };
 
 // Re-export fd_table
 pub use fd_table::{FDTable, FdFlagsExt};
 
 // Re-export file_lock
-pub use file_lock::file_lock_manager;
+pub use file_lock::{FileId, LockOwner, file_lock_manager, release_ofd_locks_on_drop};
 
 // Re-export devno
-pub use devno::{blkdev_major, chrdev_major, get_blkdev_index, get_chrdev_driver, misc_minor};
+pub use devno::{
+    blkdev_major, chrdev_major, console_minor, get_blkdev_index, get_chrdev_driver, mem_minor,
+    misc_minor,
+};
+
+// Re-export tty
+pub use tty::{PtyMasterFile, Tty, console_tty, open_ptmx, tty_for_device};
 
 // Re-export impls
 pub use impls::{
-    BlkDeviceFile, CharDeviceFile, PipeFile, RegFile, StderrFile, StdinFile, StdoutFile,
-    create_stdio_files,
+    BlkDeviceFile, CharDeviceFile, EpollFile, EventFdFile, InotifyFile, PipeFile, RegFile,
+    StderrFile, StdinFile, StdoutFile, create_stdio_files,
 };
 
+// Re-export poll
+pub use poll::{
+    GENERIC_POLL_QUEUE, PollQueue, PollWaker, TICK_POLL_QUEUE, wake_generic_pollers,
+    wake_tick_pollers,
+};
+
+// Re-export fsnotify
+pub use fsnotify::{fsnotify_create, fsnotify_delete, fsnotify_modify, fsnotify_move};
+
 // Re-export uapi types for convenience
 pub use uapi::fcntl::{FdFlags, OpenFlags, SeekWhence};
 pub use uapi::fs::{LinuxDirent64, Stat, Statx};
@@ -7,14 +7,40 @@
 //! - 挂载点以路径为 key，并维护一个“挂载栈”（同一路径可重复挂载，栈顶为当前可见）
 //! - 路径解析时会自动跟随挂载点切换到目标文件系统的根 dentry
 //! - 卸载时弹出栈顶挂载点；若栈为空则移除该路径条目
+//! - 挂载表由 RCU 保护：路径解析等读路径不加锁，挂载/卸载复制整张表修改后发布
+//! - [`MountPoint`] 发布后不再修改：重新挂载（改标志）和移动挂载都用新的挂载点替换表项
+//!
+//! # 绑定挂载
+//!
+//! 绑定挂载（[`MountNamespace::bind`]）把已有目录（或文件）挂到另一路径，和普通挂载一样压入
+//! 目标路径的挂载栈。绑定挂载点与源挂载点共享文件系统，但有自己的根 dentry（源 dentry 的
+//! 别名，指向同一 inode），因此 [`MountNamespace::mount_of`] 仍能通过根 dentry 区分二者。
+//! 源目录下的子挂载不会被带到绑定挂载点（相当于不带 `MS_REC`）。
+//!
+//! 同一文件系统的所有挂载点（含绑定挂载和其他命名空间中的副本）共享一个计数，
+//! 最后一个挂载点离开挂载表时才执行 [`FileSystem::umount`]。
+//!
+//! # 挂载命名空间
+//!
+//! 每个任务属于一个挂载命名空间（[`MountNamespace`]），路径解析只看当前任务所在命名空间的
+//! 挂载表（[`current_mnt_ns`]）。`clone`/`unshare` 带 `CLONE_NEWNS` 时复制一份挂载表
+//! （[`MountNamespace::copy`]），此后两边的挂载与卸载互不可见（相当于 Linux 的私有传播）。
+//!
+//! 复制出的挂载表与原表共享 [`MountPoint`]：文件系统只有在所有命名空间都卸载它
+//! （或命名空间随最后一个任务销毁）之后才真正执行 [`FileSystem::umount`]。重新挂载与
+//! 移动挂载只替换本命名空间的表项，不影响其他命名空间。
+//!
+//! dentry 树由所有命名空间共享，因此挂载信息不记录在 dentry 上：跨越挂载点时由
+//! [`MountNamespace::path_of`] 求出 dentry 在命名空间中的绝对路径，再查挂载表。
 
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use sync::SpinLock;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use sync::{LazyLock, Rcu};
 
-use crate::{DENTRY_CACHE, Dentry, FileSystem, FsError, normalize_path};
+use crate::{Dentry, FileSystem, FsError, normalize_path, vfs_ops};
 
 /// 挂载标志
 bitflags::bitflags! {
@@ -46,6 +72,8 @@ pub struct MountPoint {
     pub device: Option<String>,
     /// 挂载路径
     pub mount_path: String,
+    /// 挂载表中引用此文件系统的表项数，与绑定挂载点、移动/重新挂载后的挂载点共享
+    users: Arc<AtomicUsize>,
 }
 
 impl MountPoint {
@@ -65,22 +93,54 @@ impl MountPoint {
             flags,
             device,
             mount_path,
+            users: Arc::new(AtomicUsize::new(0)),
         })
     }
+
+    /// 以 `root` 为根、挂载到 `mount_path` 的同一文件系统的挂载点
+    fn derive(&self, root: Arc<Dentry>, mount_path: String, flags: MountFlags) -> Arc<Self> {
+        Arc::new(Self {
+            fs: self.fs.clone(),
+            root,
+            flags,
+            device: self.device.clone(),
+            mount_path,
+            users: self.users.clone(),
+        })
+    }
+
+    /// 挂载表不再包含此挂载点，最后一个引用此文件系统的表项离开时卸载文件系统
+    fn release(&self) -> Result<(), FsError> {
+        if self.users.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.fs.umount()?;
+        }
+        Ok(())
+    }
 }
 
-/// 全局挂载表
-pub struct MountTable {
+/// 挂载命名空间
+pub struct MountNamespace {
     /// 挂载路径 -> 挂载点栈（最后一个是当前可见的）
-    mounts: SpinLock<BTreeMap<String, Vec<Arc<MountPoint>>>>,
+    mounts: Rcu<BTreeMap<String, Vec<Arc<MountPoint>>>>,
 }
 
-impl MountTable {
-    /// 创建新的挂载表
-    pub const fn new() -> Self {
+impl MountNamespace {
+    /// 创建空的命名空间
+    pub fn new() -> Self {
         Self {
-            mounts: SpinLock::new(BTreeMap::new()),
+            mounts: Rcu::new(BTreeMap::new()),
+        }
+    }
+
+    /// 复制挂载表，得到新的命名空间（CLONE_NEWNS）
+    pub fn copy(&self) -> Arc<Self> {
+        let mounts = self.mounts.read().clone();
+        for mount_point in mounts.values().flatten() {
+            mount_point.users.fetch_add(1, Ordering::Relaxed);
         }
+        Arc::new(Self {
+            mounts: Rcu::new(mounts),
+        })
     }
 
     /// 挂载文件系统
@@ -95,23 +155,103 @@ impl MountTable {
 
         // 创建挂载点
         let mount_point = MountPoint::new(fs, normalized_path.clone(), flags, device);
+        mount_point.users.store(1, Ordering::Relaxed);
 
         // 添加到挂载栈
-        let mut mounts = self.mounts.lock();
-        mounts
-            .entry(normalized_path.clone())
+        self.mounts
+            .write()
+            .entry(normalized_path)
             .or_insert_with(Vec::new)
-            .push(mount_point.clone());
+            .push(mount_point);
+
+        Ok(())
+    }
+
+    /// 把 `source` 所在的目录树绑定挂载到 `path`
+    ///
+    /// 新挂载点沿用源挂载点的标志，需要只读等属性时再 [`remount`](Self::remount)。
+    /// `source` 不在本命名空间的挂载中时返回 [`FsError::InvalidArgument`]。
+    pub fn bind(&self, source: &Arc<Dentry>, path: &str) -> Result<(), FsError> {
+        let source_mount = self.mount_of(source).ok_or(FsError::InvalidArgument)?;
+        let normalized_path = normalize_path(path);
+
+        // 根 dentry 是源 dentry 的别名，使两个挂载点下的 dentry 树互不相交
+        let root = Dentry::new(String::from("/"), source.inode.clone());
+        let mount_point = source_mount.derive(root, normalized_path.clone(), source_mount.flags);
+        mount_point.users.fetch_add(1, Ordering::Relaxed);
+
+        self.mounts
+            .write()
+            .entry(normalized_path)
+            .or_default()
+            .push(mount_point);
+
+        Ok(())
+    }
+
+    /// 修改 `path` 上可见挂载点的标志（MS_REMOUNT）
+    ///
+    /// `path` 不是挂载点时返回 [`FsError::InvalidArgument`]。
+    pub fn remount(&self, path: &str, flags: MountFlags) -> Result<(), FsError> {
+        let normalized_path = normalize_path(path);
+        let mut mounts = self.mounts.write();
+        let top = mounts
+            .get_mut(&normalized_path)
+            .and_then(|stack| stack.last_mut())
+            .ok_or(FsError::InvalidArgument)?;
+        *top = top.derive(top.root.clone(), normalized_path, flags);
+        Ok(())
+    }
+
+    /// 把 `from` 上可见的挂载点及其下的子挂载移动到 `to`（MS_MOVE）
+    ///
+    /// `from` 原先被覆盖的挂载点重新可见；`to` 上已有的挂载点被新挂载覆盖。
+    /// `from` 不是挂载点、是根，或 `to` 位于 `from` 之下时返回 [`FsError::InvalidArgument`]。
+    pub fn move_mount(&self, from: &str, to: &str) -> Result<(), FsError> {
+        let from = normalize_path(from);
+        let to = normalize_path(to);
+        let prefix = from.clone() + "/";
+        if from == "/" || to == from || to.starts_with(&prefix) {
+            return Err(FsError::InvalidArgument);
+        }
 
-        // 如果挂载点的 dentry 已经存在于缓存中，更新其挂载信息
-        if let Some(dentry) = DENTRY_CACHE.lookup(&normalized_path) {
-            dentry.set_mount(&mount_point.root);
+        let mut mounts = self.mounts.write();
+        let stack = mounts.get_mut(&from).ok_or(FsError::InvalidArgument)?;
+        let mount_point = stack.pop().ok_or(FsError::InvalidArgument)?;
+        if stack.is_empty() {
+            mounts.remove(&from);
+        }
+
+        // 子挂载跟随移动，挂载路径换成新前缀
+        let submounts: Vec<String> = mounts
+            .keys()
+            .filter(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        let mut moved = Vec::new();
+        for path in submounts {
+            let new_path = to.clone() + &path[from.len()..];
+            let stack = mounts.remove(&path).unwrap_or_default();
+            let stack: Vec<_> = stack
+                .iter()
+                .map(|mp| mp.derive(mp.root.clone(), new_path.clone(), mp.flags))
+                .collect();
+            moved.push((new_path, stack));
+        }
+
+        let mount_point =
+            mount_point.derive(mount_point.root.clone(), to.clone(), mount_point.flags);
+        mounts.entry(to).or_default().push(mount_point);
+        for (path, stack) in moved {
+            mounts.entry(path).or_default().extend(stack);
         }
 
         Ok(())
     }
 
     /// 卸载文件系统
+    ///
+    /// 其他命名空间或绑定挂载仍引用该文件系统时只同步，不卸载文件系统。
     pub fn umount(&self, path: &str) -> Result<(), FsError> {
         let normalized_path = normalize_path(path);
 
@@ -120,7 +260,7 @@ impl MountTable {
             return Err(FsError::NotSupported);
         }
 
-        let mut mounts = self.mounts.lock();
+        let mut mounts = self.mounts.write();
         let stack = mounts.get_mut(&normalized_path).ok_or(FsError::NotFound)?;
 
         // 弹出栈顶的挂载点
@@ -131,31 +271,14 @@ impl MountTable {
             mounts.remove(&normalized_path);
         }
 
-        // 释放锁，避免在同步/卸载时持有锁
+        // 发布新的挂载表并释放写者锁，避免在同步/卸载时持有锁
         drop(mounts);
 
         // 同步文件系统
         mount_point.fs.sync()?;
 
         // 执行卸载清理
-        mount_point.fs.umount()?;
-
-        // 更新 dentry 缓存
-        if let Some(dentry) = DENTRY_CACHE.lookup(&normalized_path) {
-            // 如果还有下层挂载，更新为下层挂载点
-            let mounts = self.mounts.lock();
-            if let Some(stack) = mounts.get(&normalized_path) {
-                if let Some(underlying_mount) = stack.last() {
-                    dentry.set_mount(&underlying_mount.root);
-                } else {
-                    dentry.clear_mount();
-                }
-            } else {
-                dentry.clear_mount();
-            }
-        }
-
-        Ok(())
+        mount_point.release()
     }
 
     /// 查找给定路径的挂载点
@@ -163,7 +286,7 @@ impl MountTable {
     /// 返回最长匹配的挂载点（栈顶）
     pub fn find_mount(&self, path: &str) -> Option<Arc<MountPoint>> {
         let normalized_path = normalize_path(path);
-        let mounts = self.mounts.lock();
+        let mounts = self.mounts.read();
 
         // 查找最长匹配的挂载点
         let mut best_match = None;
@@ -182,18 +305,77 @@ impl MountTable {
         best_match
     }
 
+    /// 查找 `dentry` 所在的挂载点
+    ///
+    /// `dentry` 所在的文件系统不在本命名空间中挂载时返回 `None`。
+    pub fn mount_of(&self, dentry: &Arc<Dentry>) -> Option<Arc<MountPoint>> {
+        let mut top = dentry.clone();
+        while let Some(parent) = top.parent() {
+            top = parent;
+        }
+        self.mounts
+            .read()
+            .values()
+            .flatten()
+            .find(|mp| Arc::ptr_eq(&mp.root, &top))
+            .cloned()
+    }
+
+    /// `dentry` 在本命名空间中的绝对路径
+    ///
+    /// `dentry` 所在的文件系统不在本命名空间中挂载时返回相对于其文件系统根的路径。
+    pub fn path_of(&self, dentry: &Arc<Dentry>) -> String {
+        let relative = dentry.full_path();
+        match self.mount_of(dentry) {
+            Some(mp) if mp.mount_path != "/" => {
+                if relative == "/" {
+                    mp.mount_path.clone()
+                } else {
+                    mp.mount_path.clone() + &relative
+                }
+            }
+            _ => relative,
+        }
+    }
+
+    /// 挂载在 `dentry` 上的文件系统的根 dentry（栈顶）
+    pub fn mounted_root(&self, dentry: &Arc<Dentry>) -> Option<Arc<Dentry>> {
+        // 快速路径：没有以该名字结尾的挂载路径时不必求完整路径
+        let named = self
+            .mounts
+            .read()
+            .keys()
+            .any(|path| path.rsplit('/').next() == Some(dentry.name.as_str()));
+        if !named {
+            return None;
+        }
+        let path = self.path_of(dentry);
+        self.mounts
+            .read()
+            .get(&path)
+            .and_then(|stack| stack.last())
+            .map(|mp| mp.root.clone())
+    }
+
     /// 获取根挂载点
     pub fn root_mount(&self) -> Option<Arc<MountPoint>> {
         self.mounts
-            .lock()
+            .read()
             .get("/")
             .and_then(|stack| stack.last())
             .cloned()
     }
 
+    /// 获取根 dentry
+    pub fn root_dentry(&self) -> Result<Arc<Dentry>, FsError> {
+        self.root_mount()
+            .map(|mp| mp.root.clone())
+            .ok_or(FsError::NotSupported)
+    }
+
     /// 列出所有挂载点（用于调试）
     pub fn list_mounts(&self) -> Vec<(String, String)> {
-        let mounts = self.mounts.lock();
+        let mounts = self.mounts.read();
         mounts
             .iter()
             .flat_map(|(path, stack)| {
@@ -206,7 +388,7 @@ impl MountTable {
 
     /// 列出所有挂载点（返回完整信息）
     pub fn list_all(&self) -> BTreeMap<String, Arc<MountPoint>> {
-        let mounts = self.mounts.lock();
+        let mounts = self.mounts.read();
         mounts
             .iter()
             .filter_map(|(key, stack)| {
@@ -218,22 +400,277 @@ impl MountTable {
     }
 }
 
-impl Default for MountTable {
+impl Default for MountNamespace {
     fn default() -> Self {
         Self::new()
     }
 }
 
-// 全局挂载表
-lazy_static::lazy_static! {
-    /// 全局挂载表
-    pub static ref MOUNT_TABLE: MountTable = MountTable::new();
+impl Drop for MountNamespace {
+    /// 命名空间随最后一个任务销毁，释放其中的挂载点
+    fn drop(&mut self) {
+        // 卸载可能睡眠，先离开 RCU 读临界区
+        let mounts: Vec<Arc<MountPoint>> = self.mounts.read().values().flatten().cloned().collect();
+        for mount_point in mounts {
+            let _ = mount_point.release();
+        }
+    }
+}
+
+/// 初始挂载命名空间，启动阶段的挂载都在其中
+static INIT_MNT_NS: LazyLock<Arc<MountNamespace>> =
+    LazyLock::new(|| Arc::new(MountNamespace::new()));
+
+/// 获取初始挂载命名空间
+pub fn init_mnt_ns() -> Arc<MountNamespace> {
+    INIT_MNT_NS.clone()
 }
 
-/// 获取根 dentry
+/// 获取当前任务的挂载命名空间，没有任务上下文时为初始命名空间
+pub fn current_mnt_ns() -> Arc<MountNamespace> {
+    vfs_ops().current_mnt_ns().unwrap_or_else(init_mnt_ns)
+}
+
+/// 获取当前命名空间的根 dentry
 pub fn get_root_dentry() -> Result<Arc<Dentry>, FsError> {
-    MOUNT_TABLE
-        .root_mount()
-        .map(|mp| mp.root.clone())
-        .ok_or(FsError::NotSupported)
+    current_mnt_ns().root_dentry()
+}
+
+/// 检查能否通过 `dentry` 修改文件系统
+///
+/// `dentry` 在当前命名空间中所在的挂载点为只读时返回 [`FsError::ReadOnlyFs`]。
+/// 创建、删除类操作传入父目录的 dentry。
+pub fn mnt_want_write(dentry: &Arc<Dentry>) -> Result<(), FsError> {
+    match current_mnt_ns().mount_of(dentry) {
+        Some(mp) if mp.flags.contains(MountFlags::READ_ONLY) => Err(FsError::ReadOnlyFs),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impls::inotify_file::tests::dummy;
+    use crate::{Inode, InodeType, StatFs};
+    use sync::mock::init_arch_ops;
+
+    /// 记录卸载次数的文件系统
+    struct TestFs {
+        root: Arc<dyn Inode>,
+        umounts: AtomicUsize,
+    }
+
+    impl TestFs {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                root: dummy(InodeType::Directory),
+                umounts: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    impl FileSystem for TestFs {
+        fn fs_type(&self) -> &'static str {
+            "testfs"
+        }
+
+        fn root_inode(&self) -> Arc<dyn Inode> {
+            self.root.clone()
+        }
+
+        fn sync(&self) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn statfs(&self) -> Result<StatFs, FsError> {
+            Err(FsError::NotSupported)
+        }
+
+        fn umount(&self) -> Result<(), FsError> {
+            self.umounts.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    /// 在 `parent` 下创建目录 dentry
+    fn subdir(parent: &Arc<Dentry>, name: &str) -> Arc<Dentry> {
+        let child = Dentry::new(String::from(name), dummy(InodeType::Directory));
+        parent.add_child(child.clone());
+        child
+    }
+
+    #[test]
+    fn test_nested_mounts_resolve_by_namespace_path() {
+        init_arch_ops();
+        let ns = MountNamespace::new();
+        ns.mount(TestFs::new(), "/", MountFlags::empty(), None)
+            .unwrap();
+        ns.mount(TestFs::new(), "/dev", MountFlags::empty(), None)
+            .unwrap();
+        ns.mount(TestFs::new(), "/dev/pts", MountFlags::empty(), None)
+            .unwrap();
+
+        let root = ns.root_dentry().unwrap();
+        let dev = ns.mounted_root(&subdir(&root, "dev")).unwrap();
+        assert_eq!(ns.path_of(&dev), "/dev");
+        // devpts 的挂载点在 /dev 的文件系统中，其 dentry 路径只有 "/pts"
+        let pts_dir = subdir(&dev, "pts");
+        assert_eq!(pts_dir.full_path(), "/pts");
+        assert_eq!(ns.path_of(&pts_dir), "/dev/pts");
+        let pts = ns.mounted_root(&pts_dir).unwrap();
+        assert!(Arc::ptr_eq(&pts, &ns.find_mount("/dev/pts").unwrap().root));
+        assert!(ns.mounted_root(&subdir(&root, "pts")).is_none());
+        assert_eq!(ns.mount_of(&pts_dir).unwrap().mount_path, "/dev");
+    }
+
+    #[test]
+    fn test_copied_namespace_is_isolated() {
+        init_arch_ops();
+        let parent = MountNamespace::new();
+        parent
+            .mount(TestFs::new(), "/", MountFlags::empty(), None)
+            .unwrap();
+        let shared = TestFs::new();
+        parent
+            .mount(shared.clone(), "/mnt", MountFlags::empty(), None)
+            .unwrap();
+        let mnt = subdir(&parent.root_dentry().unwrap(), "mnt");
+
+        let child = parent.copy();
+        child
+            .mount(TestFs::new(), "/tmp", MountFlags::empty(), None)
+            .unwrap();
+        assert_eq!(child.find_mount("/tmp").unwrap().mount_path, "/tmp");
+        assert_eq!(parent.find_mount("/tmp").unwrap().mount_path, "/");
+
+        // 子命名空间卸载共享的挂载点，父命名空间仍然可见，文件系统不卸载
+        child.umount("/mnt").unwrap();
+        assert!(child.mounted_root(&mnt).is_none());
+        assert!(parent.mounted_root(&mnt).is_some());
+        assert_eq!(shared.umounts.load(Ordering::Relaxed), 0);
+
+        // 最后一个命名空间卸载时才卸载文件系统
+        parent.umount("/mnt").unwrap();
+        assert_eq!(shared.umounts.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_dropped_namespace_releases_mounts() {
+        init_arch_ops();
+        let parent = MountNamespace::new();
+        let shared = TestFs::new();
+        parent
+            .mount(shared.clone(), "/mnt", MountFlags::empty(), None)
+            .unwrap();
+        let private = TestFs::new();
+        let child = parent.copy();
+        child
+            .mount(private.clone(), "/mnt", MountFlags::empty(), None)
+            .unwrap();
+
+        drop(child);
+        assert_eq!(private.umounts.load(Ordering::Relaxed), 1);
+        assert_eq!(shared.umounts.load(Ordering::Relaxed), 0);
+        drop(parent);
+        assert_eq!(shared.umounts.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_bind_mount_keeps_filesystem_until_last_user() {
+        init_arch_ops();
+        let ns = MountNamespace::new();
+        ns.mount(TestFs::new(), "/", MountFlags::empty(), None)
+            .unwrap();
+        let fs = TestFs::new();
+        ns.mount(fs.clone(), "/mnt", MountFlags::empty(), None)
+            .unwrap();
+        let mnt = ns
+            .mounted_root(&subdir(&ns.root_dentry().unwrap(), "mnt"))
+            .unwrap();
+        let data = subdir(&mnt, "data");
+
+        ns.bind(&data, "/srv").unwrap();
+        let srv = ns
+            .mounted_root(&subdir(&ns.root_dentry().unwrap(), "srv"))
+            .unwrap();
+        assert!(Arc::ptr_eq(&srv.inode, &data.inode));
+        assert!(!Arc::ptr_eq(&srv, &data));
+        assert_eq!(ns.path_of(&srv), "/srv");
+        assert_eq!(ns.path_of(&data), "/mnt/data");
+
+        // 源挂载点卸载后绑定挂载仍然可用
+        ns.umount("/mnt").unwrap();
+        assert_eq!(fs.umounts.load(Ordering::Relaxed), 0);
+        assert!(Arc::ptr_eq(
+            &ns.mount_of(&srv).unwrap().fs,
+            &(fs.clone() as Arc<dyn FileSystem>)
+        ));
+        ns.umount("/srv").unwrap();
+        assert_eq!(fs.umounts.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_remount_replaces_visible_mount_only() {
+        init_arch_ops();
+        let ns = MountNamespace::new();
+        ns.mount(TestFs::new(), "/mnt", MountFlags::empty(), None)
+            .unwrap();
+        ns.mount(TestFs::new(), "/mnt", MountFlags::empty(), None)
+            .unwrap();
+        let other = ns.copy();
+
+        ns.remount("/mnt", MountFlags::READ_ONLY).unwrap();
+        let top = ns.find_mount("/mnt").unwrap();
+        assert!(top.flags.contains(MountFlags::READ_ONLY));
+        assert!(
+            !other
+                .find_mount("/mnt")
+                .unwrap()
+                .flags
+                .contains(MountFlags::READ_ONLY)
+        );
+
+        // 被覆盖的挂载点保持原标志
+        ns.umount("/mnt").unwrap();
+        assert!(
+            !ns.find_mount("/mnt")
+                .unwrap()
+                .flags
+                .contains(MountFlags::READ_ONLY)
+        );
+        assert_eq!(
+            ns.remount("/none", MountFlags::empty()),
+            Err(FsError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_move_mount_carries_submounts() {
+        init_arch_ops();
+        let ns = MountNamespace::new();
+        ns.mount(TestFs::new(), "/", MountFlags::empty(), None)
+            .unwrap();
+        let lower = TestFs::new();
+        ns.mount(lower.clone(), "/a", MountFlags::empty(), None)
+            .unwrap();
+        ns.mount(TestFs::new(), "/a", MountFlags::empty(), None)
+            .unwrap();
+        ns.mount(TestFs::new(), "/a/sub", MountFlags::empty(), None)
+            .unwrap();
+        let sub_root = ns.find_mount("/a/sub").unwrap().root.clone();
+
+        assert_eq!(
+            ns.move_mount("/a", "/a/sub/x"),
+            Err(FsError::InvalidArgument)
+        );
+        ns.move_mount("/a", "/b").unwrap();
+
+        // 被覆盖的挂载点重新可见，子挂载随之移动
+        let a = ns.find_mount("/a").unwrap();
+        assert!(Arc::ptr_eq(&a.fs, &(lower as Arc<dyn FileSystem>)));
+        assert_eq!(ns.find_mount("/a/sub").unwrap().mount_path, "/a");
+        assert_eq!(ns.find_mount("/b/sub").unwrap().mount_path, "/b/sub");
+        assert_eq!(ns.path_of(&sub_root), "/b/sub");
+        assert_eq!(ns.move_mount("/c", "/d"), Err(FsError::InvalidArgument));
+    }
 }
@@ -6,7 +6,8 @@ use alloc::sync::Arc;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use uapi::time::TimeSpec;
 
-use crate::Dentry;
+use crate::tty::Tty;
+use crate::{Dentry, FsCred, FsError, MountNamespace};
 
 /// VFS 运行时操作
 ///
@@ -21,6 +22,12 @@ pub trait VfsOps: Send + Sync {
     /// 获取当前任务的根目录
     fn current_root(&self) -> Option<Arc<Dentry>>;
 
+    /// 获取当前任务的挂载命名空间，没有任务上下文（启动早期）时返回 `None`
+    fn current_mnt_ns(&self) -> Option<Arc<MountNamespace>>;
+
+    /// 获取当前任务进行文件访问检查时使用的身份，没有任务上下文（启动早期）时返回 `None`，不做检查
+    fn current_fs_cred(&self) -> Option<FsCred>;
+
     // ========== 配置 ==========
 
     /// 获取默认最大文件描述符数
@@ -34,10 +41,12 @@ pub trait VfsOps: Send + Sync {
     // ========== 用户空间访问保护 ==========
 
     /// 进入用户空间访问模式（替代 SumGuard::new()）
-    fn enter_user_access(&self);
+    ///
+    /// 返回进入前访问窗口是否已打开，交给配对的 [`VfsOps::exit_user_access`]。
+    fn enter_user_access(&self) -> bool;
 
-    /// 退出用户空间访问模式
-    fn exit_user_access(&self);
+    /// 退出用户空间访问模式，`was_enabled` 为配对的 `enter_user_access` 的返回值
+    fn exit_user_access(&self, was_enabled: bool);
 
     // ========== 控制台操作 ==========
 
@@ -49,6 +58,46 @@ pub trait VfsOps: Send + Sync {
 
     /// 向控制台输出字符串
     fn console_write_str(&self, s: &str);
+
+    // ========== 进程组 ==========
+
+    /// 获取当前任务的进程组 ID
+    fn current_pgid(&self) -> u32;
+
+    /// 向进程组中的所有进程发送信号（终端行规程产生 SIGINT 等信号时使用）
+    fn kill_pgrp(&self, pgid: u32, sig: usize);
+
+    // ========== 会话与控制终端 ==========
+
+    /// 获取当前任务的进程 ID
+    fn current_pid(&self) -> u32;
+
+    /// 获取当前任务的会话 ID
+    fn current_sid(&self) -> u32;
+
+    /// 进程组 `pgid` 是否存在且属于会话 `sid`（TIOCSPGRP 校验）
+    fn pgrp_in_session(&self, pgid: u32, sid: u32) -> bool;
+
+    /// 获取当前任务记录的控制终端
+    fn controlling_tty(&self) -> Option<Arc<Tty>>;
+
+    /// 设置（或清除）当前任务的控制终端
+    fn set_controlling_tty(&self, tty: Option<Arc<Tty>>);
+
+    // ========== 阻塞与唤醒 ==========
+
+    /// 在等待通道 `chan` 上睡眠，直到 `cond` 为真
+    ///
+    /// `cond` 在等待队列的锁内检查（为真则不睡眠），以免丢失唤醒；每次被
+    /// [`wake_event`](Self::wake_event) 唤醒后重新检查。到达 `deadline_ms`（与 `timespec_now`
+    /// 同一时钟的毫秒数）或收到信号时提前返回，调用者仍需自行检查条件。
+    ///
+    /// # 返回值
+    /// 有待处理的信号时返回 `false`
+    fn wait_event(&self, chan: usize, deadline_ms: Option<i64>, cond: &dyn Fn() -> bool) -> bool;
+
+    /// 唤醒在等待通道 `chan` 上睡眠的所有任务（可在中断上下文调用）
+    fn wake_event(&self, chan: usize);
 }
 
 /// 字符设备驱动接口
@@ -58,11 +107,43 @@ pub trait CharDriver: Send + Sync {
     /// 尝试读取一个字符（非阻塞）
     fn try_read(&self) -> Option<u8>;
 
+    /// 一次读取多个字节，`nonblock` 为真时不阻塞
+    ///
+    /// 默认逐字节调用 [`try_read`](Self::try_read)，直到没有数据可读。
+    fn read(&self, buf: &mut [u8], _nonblock: bool) -> Result<usize, FsError> {
+        let mut count = 0;
+        while count < buf.len() {
+            match self.try_read() {
+                Some(b) => {
+                    buf[count] = b;
+                    count += 1;
+                }
+                None => break,
+            }
+        }
+        Ok(count)
+    }
+
     /// 写入数据
     fn write(&self, data: &[u8]);
 
     /// 执行 ioctl 操作
     fn ioctl(&self, request: u32, arg: usize) -> Result<isize, i32>;
+
+    /// 终端设备被打开时调用，可拒绝打开（如已锁定的 PTY 从设备）
+    fn open(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    /// 终端设备的一个打开实例被关闭时调用
+    fn release(&self) {}
+
+    /// 输入是否由驱动主动推送（中断中调用 [`Tty::receive`]）
+    ///
+    /// 为假时终端读取者需要定期调用 [`try_read`](Self::try_read) 轮询设备。
+    fn pushes_input(&self) -> bool {
+        false
+    }
 }
 
 /// 设备操作
@@ -83,6 +164,13 @@ pub trait DeviceOps: Send + Sync {
 
     /// 获取块设备总块数
     fn blkdev_total_blocks(&self, idx: usize) -> usize;
+
+    /// `/dev/console` 实际对应的终端设备号
+    ///
+    /// 返回 `Some` 时控制台与该设备共享同一个 [`Tty`]，使中断送达的输入只进入一个缓冲区。
+    fn console_device(&self) -> Option<u64> {
+        None
+    }
 }
 
 // ========== VfsOps 注册 ==========
@@ -165,21 +253,24 @@ pub fn device_ops() -> &'static dyn DeviceOps {
 /// 用户空间访问保护 guard
 ///
 /// 在作用域结束时自动退出用户空间访问模式
-pub struct UserAccessGuard;
+pub struct UserAccessGuard {
+    was_enabled: bool,
+}
 
 impl UserAccessGuard {
     /// 创建新的用户空间访问保护
     #[inline]
     pub fn new() -> Self {
-        vfs_ops().enter_user_access();
-        Self
+        Self {
+            was_enabled: vfs_ops().enter_user_access(),
+        }
     }
 }
 
 impl Drop for UserAccessGuard {
     #[inline]
     fn drop(&mut self) {
-        vfs_ops().exit_user_access();
+        vfs_ops().exit_user_access(self.was_enabled);
     }
 }
 
@@ -194,7 +285,8 @@ mod test_mock {
     extern crate test_support;
 
     use super::{CharDriver, DeviceOps, VfsOps};
-    use crate::Dentry;
+    use crate::tty::Tty;
+    use crate::{Dentry, FsCred, MountNamespace};
     use alloc::sync::Arc;
     use uapi::time::TimeSpec;
 
@@ -207,6 +299,14 @@ mod test_mock {
             None
         }
 
+        fn current_mnt_ns(&self) -> Option<Arc<MountNamespace>> {
+            None
+        }
+
+        fn current_fs_cred(&self) -> Option<FsCred> {
+            None
+        }
+
         fn default_max_fds(&self) -> usize {
             1024
         }
@@ -215,9 +315,11 @@ mod test_mock {
             TimeSpec::zero()
         }
 
-        fn enter_user_access(&self) {}
+        fn enter_user_access(&self) -> bool {
+            false
+        }
 
-        fn exit_user_access(&self) {}
+        fn exit_user_access(&self, _was_enabled: bool) {}
 
         fn console_getchar(&self) -> Option<u8> {
             None
@@ -226,6 +328,42 @@ mod test_mock {
         fn console_putchar(&self, _c: u8) {}
 
         fn console_write_str(&self, _s: &str) {}
+
+        fn current_pgid(&self) -> u32 {
+            1
+        }
+
+        fn kill_pgrp(&self, _pgid: u32, _sig: usize) {}
+
+        fn current_pid(&self) -> u32 {
+            1
+        }
+
+        fn current_sid(&self) -> u32 {
+            1
+        }
+
+        fn pgrp_in_session(&self, pgid: u32, _sid: u32) -> bool {
+            pgid != 0
+        }
+
+        fn controlling_tty(&self) -> Option<Arc<Tty>> {
+            None
+        }
+
+        fn set_controlling_tty(&self, _tty: Option<Arc<Tty>>) {}
+
+        fn wait_event(
+            &self,
+            _chan: usize,
+            _deadline_ms: Option<i64>,
+            _cond: &dyn Fn() -> bool,
+        ) -> bool {
+            core::hint::spin_loop();
+            true
+        }
+
+        fn wake_event(&self, _chan: usize) {}
     }
 
     impl DeviceOps for test_support::mock::vfs::MockDeviceOps {
@@ -7,15 +7,66 @@
 //! - 绝对路径以 `/` 开头，从根目录开始解析；相对路径从“当前工作目录”开始解析
 //! - `.` 表示当前目录，解析时跳过；`..` 表示父目录（绝对路径不允许越过根）
 //! - 支持符号链接解析：`vfs_lookup` 默认跟随；`vfs_lookup_no_follow` 不跟随最后一个组件
+//! - 根目录与挂载点取自当前任务的挂载命名空间（[`current_mnt_ns`]），一次解析中不变
+//! - 在每个目录中查找组件前检查当前任务的搜索权限（[`may_lookup`]），没有时返回
+//!   [`FsError::PermissionDenied`]
+//!
+//! # 解析限制
+//!
+//! [`vfs_lookup_from_with`] 按 [`ResolvePolicy`] 限制解析过程（openat2 的 `RESOLVE_*`），
+//! 违反限制时整个解析失败，不会退回到不受限的结果：
+//!
+//! - `no_symlinks`：遇到需要跟随的符号链接返回 [`FsError::TooManySymlinks`]（ELOOP）
+//! - `no_xdev`：离开起始目录所在的挂载（进入子挂载或经绝对符号链接跳到其他挂载）返回
+//!   [`FsError::CrossDevice`]（EXDEV）
+//! - `beneath`：绝对路径、绝对符号链接或越过起始目录的 `..` 返回 [`FsError::CrossDevice`]
+//! - `in_root`：把起始目录当作根目录，绝对路径与绝对符号链接从它开始，它的 `..` 是它自己
 
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 
-use crate::{DENTRY_CACHE, Dentry, FsError, InodeType, MOUNT_TABLE, get_root_dentry, vfs_ops};
+use uapi::fcntl::ResolveFlags;
+
+use crate::{
+    DENTRY_CACHE, Dentry, FsError, InodeType, MountNamespace, current_mnt_ns, get_root_dentry,
+    may_lookup, vfs_ops,
+};
 
 const MAX_SYMLINK_DEPTH: usize = 8;
 
+/// 路径解析限制，见[模块文档](self#解析限制)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResolvePolicy {
+    /// 不跟随任何符号链接（RESOLVE_NO_SYMLINKS）
+    pub no_symlinks: bool,
+    /// 不跨越挂载点（RESOLVE_NO_XDEV）
+    pub no_xdev: bool,
+    /// 不离开起始目录（RESOLVE_BENEATH）
+    pub beneath: bool,
+    /// 以起始目录为根目录（RESOLVE_IN_ROOT）
+    pub in_root: bool,
+}
+
+impl ResolvePolicy {
+    /// 由 openat2 的 `resolve` 标志构造
+    ///
+    /// 没有魔法链接，RESOLVE_NO_MAGICLINKS 不需要额外处理；RESOLVE_CACHED 由调用者处理。
+    pub fn from_flags(flags: ResolveFlags) -> Self {
+        Self {
+            no_symlinks: flags.contains(ResolveFlags::NO_SYMLINKS),
+            no_xdev: flags.contains(ResolveFlags::NO_XDEV),
+            beneath: flags.contains(ResolveFlags::BENEATH),
+            in_root: flags.contains(ResolveFlags::IN_ROOT),
+        }
+    }
+
+    /// 解析被限制在起始目录之内，绝对路径也从起始目录开始解析
+    pub fn confined(&self) -> bool {
+        self.beneath || self.in_root
+    }
+}
+
 /// 路径组件
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PathComponent {
@@ -141,18 +192,24 @@ mod tests {
     #[test]
     fn test_parse_path_components() {
         assert_eq!(parse_path("/"), vec![PathComponent::Root]);
-        assert_eq!(parse_path("/a/b/./c/.."), vec![
-            PathComponent::Root,
-            PathComponent::Normal(String::from("a")),
-            PathComponent::Normal(String::from("b")),
-            PathComponent::Current,
-            PathComponent::Normal(String::from("c")),
-            PathComponent::Parent,
-        ]);
-        assert_eq!(parse_path("a//b"), vec![
-            PathComponent::Normal(String::from("a")),
-            PathComponent::Normal(String::from("b")),
-        ]);
+        assert_eq!(
+            parse_path("/a/b/./c/.."),
+            vec![
+                PathComponent::Root,
+                PathComponent::Normal(String::from("a")),
+                PathComponent::Normal(String::from("b")),
+                PathComponent::Current,
+                PathComponent::Normal(String::from("c")),
+                PathComponent::Parent,
+            ]
+        );
+        assert_eq!(
+            parse_path("a//b"),
+            vec![
+                PathComponent::Normal(String::from("a")),
+                PathComponent::Normal(String::from("b")),
+            ]
+        );
     }
 
     #[test]
@@ -202,49 +259,86 @@ pub fn vfs_lookup(path: &str) -> Result<Arc<Dentry>, FsError> {
         get_cur_dir()?
     };
 
-    vfs_walk(current_dentry, components, true)
+    vfs_walk(current_dentry, components, true, &ResolvePolicy::default())
 }
 
 /// 从指定的 base dentry 开始解析路径
 pub fn vfs_lookup_from(base: Arc<Dentry>, path: &str) -> Result<Arc<Dentry>, FsError> {
-    let components: Vec<PathComponent> = parse_path(path)
-        .into_iter()
-        .filter(|c| *c != PathComponent::Root)
-        .collect();
-    vfs_walk(base, components, true)
+    vfs_lookup_from_with(base, path, true, &ResolvePolicy::default())
+}
+
+/// 从指定的 base dentry 开始按 `policy` 限制解析路径
+///
+/// 开头的 `/` 被忽略（调用者已按绝对路径选好 `base`），但 `policy.beneath` 时返回
+/// [`FsError::CrossDevice`]。`follow_last_symlink` 决定是否跟随最后一个组件的符号链接。
+pub fn vfs_lookup_from_with(
+    base: Arc<Dentry>,
+    path: &str,
+    follow_last_symlink: bool,
+    policy: &ResolvePolicy,
+) -> Result<Arc<Dentry>, FsError> {
+    let mut components = parse_path(path);
+    if components.first() == Some(&PathComponent::Root) {
+        if policy.beneath {
+            return Err(FsError::CrossDevice);
+        }
+        components.remove(0);
+    }
+    vfs_walk(base, components, follow_last_symlink, policy)
 }
 
 /// 解析单个路径组件
-fn resolve_component(base: Arc<Dentry>, component: PathComponent) -> Result<Arc<Dentry>, FsError> {
+fn resolve_component(
+    ns: &MountNamespace,
+    base: Arc<Dentry>,
+    component: PathComponent,
+) -> Result<Arc<Dentry>, FsError> {
     match component {
-        PathComponent::Root => get_root_dentry(),
+        PathComponent::Root => ns.root_dentry(),
         PathComponent::Current => Ok(base),
         PathComponent::Parent => {
+            kcov!();
             match base.parent() {
-                Some(parent) => check_mount_point(parent),
+                Some(parent) => Ok(check_mount_point(ns, parent)),
                 None => Ok(base), // 根目录的父目录是自己
             }
         }
         PathComponent::Normal(name) => {
             // 1. 先检查 dentry 缓存
             if let Some(child) = base.lookup_child(&name) {
-                return check_mount_point(child);
+                kcov!();
+                return Ok(check_mount_point(ns, child));
             }
 
-            // 2. 缓存未命中，通过 inode 查找
-            let child_inode = base.inode.lookup(&name)?;
+            // 2. 已知不存在的名字
+            if DENTRY_CACHE.lookup_negative(base.inode.as_ref(), &name) {
+                kcov!();
+                return Err(FsError::NotFound);
+            }
 
-            // 3. 创建新的 dentry 并加入缓存
+            // 3. 缓存未命中，通过 inode 查找
+            let child_inode = match base.inode.lookup(&name) {
+                Ok(inode) => inode,
+                Err(FsError::NotFound) => {
+                    DENTRY_CACHE.insert_negative(&base.inode, &name);
+                    return Err(FsError::NotFound);
+                }
+                Err(e) => return Err(e),
+            };
+            kcov!();
+
+            // 4. 创建新的 dentry 并加入缓存
             let child_dentry = Dentry::new(name.clone(), child_inode);
             if child_dentry.inode.cacheable() {
                 base.add_child(child_dentry.clone());
                 DENTRY_CACHE.insert(&child_dentry);
             } else {
+                kcov!();
                 child_dentry.set_parent(&base);
             }
 
-            // 4. 检查是否有挂载点
-            check_mount_point(child_dentry)
+            // 5. 检查是否有挂载点
+            Ok(check_mount_point(ns, child_dentry))
         }
     }
 }
@@ -253,7 +347,22 @@ fn vfs_walk(
     mut current_dentry: Arc<Dentry>,
     mut components: Vec<PathComponent>,
     follow_last_symlink: bool,
+    policy: &ResolvePolicy,
 ) -> Result<Arc<Dentry>, FsError> {
+    let ns = current_mnt_ns();
+    let cred = vfs_ops().current_fs_cred();
+    let base = current_dentry.clone();
+    let base_tree = policy.no_xdev.then(|| tree_root(&base));
+    // 绝对路径与绝对符号链接的起点
+    let root = || -> Result<Arc<Dentry>, FsError> {
+        if policy.beneath {
+            Err(FsError::CrossDevice)
+        } else if policy.in_root {
+            Ok(base.clone())
+        } else {
+            ns.root_dentry()
+        }
+    };
     let mut i = 0usize;
     let mut symlink_depth = 0usize;
 
@@ -261,23 +370,43 @@ fn vfs_walk(
         let component = components[i].clone();
         let is_last = i + 1 == components.len();
 
-        current_dentry = resolve_component(current_dentry, component)?;
+        if let Some(cred) = cred.as_ref().filter(|_| component != PathComponent::Root) {
+            may_lookup(current_dentry.inode.as_ref(), cred)?;
+        }
+        current_dentry = match component {
+            PathComponent::Root if policy.confined() => root()?,
+            PathComponent::Parent if policy.confined() && Arc::ptr_eq(&current_dentry, &base) => {
+                if policy.beneath {
+                    return Err(FsError::CrossDevice);
+                }
+                current_dentry
+            }
+            component => resolve_component(&ns, current_dentry, component)?,
+        };
+        if base_tree
+            .as_ref()
+            .is_some_and(|t| !Arc::ptr_eq(&tree_root(&current_dentry), t))
+        {
+            return Err(FsError::CrossDevice);
+        }
 
         let inode_type = current_dentry.inode.metadata()?.inode_type;
         if inode_type == InodeType::Symlink && (follow_last_symlink || !is_last) {
-            if symlink_depth >= MAX_SYMLINK_DEPTH {
+            if symlink_depth >= MAX_SYMLINK_DEPTH || policy.no_symlinks {
+                kcov!();
                 return Err(FsError::TooManySymlinks);
             }
             symlink_depth += 1;
+            kcov!();
 
             let target = current_dentry.inode.readlink()?;
 
             current_dentry = if target.starts_with('/') {
-                get_root_dentry()?
+                root()?
             } else {
                 match current_dentry.parent() {
                     Some(parent) => parent,
-                    None => get_root_dentry()?,
+                    None => root()?,
                 }
             };
 
@@ -295,23 +424,24 @@ fn vfs_walk(
     Ok(current_dentry)
 }
 
-/// 检查给定的 dentry 是否有挂载点
-fn check_mount_point(dentry: Arc<Dentry>) -> Result<Arc<Dentry>, FsError> {
-    // 快速路径：检查 dentry 本地缓存
-    if let Some(mounted_root) = dentry.get_mount() {
-        return Ok(mounted_root);
+/// `dentry` 所在 dentry 树的根，即所在挂载点的根 dentry
+fn tree_root(dentry: &Arc<Dentry>) -> Arc<Dentry> {
+    let mut top = dentry.clone();
+    while let Some(parent) = top.parent() {
+        top = parent;
     }
+    top
+}
 
-    // 慢速路径：查找挂载表
-    let full_path = dentry.full_path();
-    if let Some(mount_point) = MOUNT_TABLE.find_mount(&full_path) {
-        if mount_point.mount_path == full_path {
-            dentry.set_mount(&mount_point.root);
-            return Ok(mount_point.root.clone());
+/// 检查给定的 dentry 是否有挂载点，有则返回挂载的文件系统的根
+fn check_mount_point(ns: &MountNamespace, dentry: Arc<Dentry>) -> Arc<Dentry> {
+    match ns.mounted_root(&dentry) {
+        Some(mounted_root) => {
+            kcov!();
+            mounted_root
         }
+        None => dentry,
     }
-
-    Ok(dentry)
 }
 
 /// 获取当前任务的工作目录
@@ -334,14 +464,10 @@ pub fn vfs_lookup_no_follow(path: &str) -> Result<Arc<Dentry>, FsError> {
         get_cur_dir()?
     };
 
-    vfs_walk(current_dentry, components, false)
+    vfs_walk(current_dentry, components, false, &ResolvePolicy::default())
 }
 
 /// 从指定的 base dentry 开始查找路径，但不跟随最后一个符号链接
 pub fn vfs_lookup_no_follow_from(base: Arc<Dentry>, path: &str) -> Result<Arc<Dentry>, FsError> {
-    let components: Vec<PathComponent> = parse_path(path)
-        .into_iter()
-        .filter(|c| *c != PathComponent::Root)
-        .collect();
-    vfs_walk(base, components, false)
+    vfs_lookup_from_with(base, path, false, &ResolvePolicy::default())
 }
@@ -0,0 +1,308 @@
+//! 文件访问权限检查
+//!
+//! [`inode_permission`] 按 POSIX 规则判断调用者能否以给定方式访问 inode：
+//!
+//! 1. 调用者是属主时只看属主位；
+//! 2. 否则 inode 带访问 ACL（且属组位不全为 0）时由 ACL 决定，见 [`acl`](crate::acl)；
+//! 3. 否则调用者属于文件属组时看属组位，不属于时看其他位；
+//! 4. 被拒绝后再由 CAP_DAC_READ_SEARCH / CAP_DAC_OVERRIDE 放宽。
+//!
+//! 调用者身份由系统调用层从任务凭证构造为 [`FsCred`]。路径遍历、打开文件与修改目录项时分别用
+//! [`may_lookup`]、[`may_open`] 与 [`may_modify_dir`] 检查。
+
+use uapi::fcntl::OpenFlags;
+use uapi::fs::AccessMode;
+
+use crate::acl::{AclType, PosixAcl, get_acl};
+use crate::{FsError, Inode, InodeMetadata, InodeType};
+
+/// 文件系统操作使用的调用者身份
+#[derive(Debug, Clone, Copy)]
+pub struct FsCred {
+    /// 文件系统用户 ID
+    pub fsuid: u32,
+    /// 文件系统组 ID
+    pub fsgid: u32,
+    /// 持有 CAP_DAC_OVERRIDE：忽略读写执行权限（执行仍要求至少一个 x 位）
+    pub dac_override: bool,
+    /// 持有 CAP_DAC_READ_SEARCH：忽略读权限与目录搜索权限
+    pub dac_read_search: bool,
+    /// 持有 CAP_FOWNER：可以执行只允许属主进行的操作（如设置 ACL）
+    pub fowner: bool,
+    /// 持有 CAP_FSETID：修改模式时保留 set-group-ID 位
+    pub fsetid: bool,
+}
+
+impl FsCred {
+    /// 调用者是否属于组 `gid`
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.fsgid == gid
+    }
+
+    /// 调用者是否为属主 `uid` 或持有 CAP_FOWNER
+    pub fn owner_or_capable(&self, uid: u32) -> bool {
+        self.fsuid == uid || self.fowner
+    }
+}
+
+/// 按模式位与访问 ACL 检查权限，不考虑能力
+fn acl_permission_check(
+    meta: &InodeMetadata,
+    acl: Option<&PosixAcl>,
+    cred: &FsCred,
+    want: u32,
+) -> bool {
+    let mut mode = meta.mode.bits();
+    if cred.fsuid == meta.uid {
+        return want & !(mode >> 6) & 0o7 == 0;
+    }
+    if let Some(acl) = acl.filter(|_| mode & 0o070 != 0) {
+        return acl.permission(meta.uid, meta.gid, cred, want);
+    }
+    if cred.in_group(meta.gid) {
+        mode >>= 3;
+    }
+    want & !mode & 0o7 == 0
+}
+
+/// 检查调用者能否以 `want` 方式访问元数据为 `meta`、访问 ACL 为 `acl` 的 inode
+///
+/// 拒绝时返回 [`FsError::PermissionDenied`]。`want` 为空（F_OK）时总是允许。
+pub fn generic_permission(
+    meta: &InodeMetadata,
+    acl: Option<&PosixAcl>,
+    cred: &FsCred,
+    want: AccessMode,
+) -> Result<(), FsError> {
+    let want = want.bits() as u32 & 0o7;
+    if acl_permission_check(meta, acl, cred, want) {
+        return Ok(());
+    }
+
+    let write = want & AccessMode::WRITE.bits() as u32 != 0;
+    if meta.inode_type == InodeType::Directory {
+        if (!write && cred.dac_read_search) || cred.dac_override {
+            return Ok(());
+        }
+        return Err(FsError::PermissionDenied);
+    }
+
+    if want == AccessMode::READ.bits() as u32 && cred.dac_read_search {
+        return Ok(());
+    }
+    let exec = want & AccessMode::EXECUTE.bits() as u32 != 0;
+    if (!exec || meta.mode.bits() & 0o111 != 0) && cred.dac_override {
+        return Ok(());
+    }
+    Err(FsError::PermissionDenied)
+}
+
+/// 检查调用者能否以 `want` 方式访问 `inode`
+///
+/// 只在模式位无法直接决定时读取访问 ACL。
+pub fn inode_permission(inode: &dyn Inode, cred: &FsCred, want: AccessMode) -> Result<(), FsError> {
+    let meta = inode.metadata()?;
+    let acl = if cred.fsuid != meta.uid && meta.mode.bits() & 0o070 != 0 {
+        get_acl(inode, AclType::Access)?
+    } else {
+        None
+    };
+    generic_permission(&meta, acl.as_ref(), cred, want)
+}
+
+/// 路径遍历时检查能否在 `dir` 中查找下一个组件：需要搜索（执行）权限
+///
+/// `dir` 不是目录时不检查，由查找本身报告 [`FsError::NotDirectory`]。
+pub fn may_lookup(dir: &dyn Inode, cred: &FsCred) -> Result<(), FsError> {
+    if dir.metadata()?.inode_type != InodeType::Directory {
+        return Ok(());
+    }
+    inode_permission(dir, cred, AccessMode::EXECUTE)
+}
+
+/// 检查调用者能否以 `flags` 打开已存在的 `inode`
+///
+/// 按访问模式要求读或写权限，`O_TRUNC` 同样要求写权限。
+pub fn may_open(inode: &dyn Inode, cred: &FsCred, flags: OpenFlags) -> Result<(), FsError> {
+    let mut want = AccessMode::empty();
+    if flags.readable() {
+        want |= AccessMode::READ;
+    }
+    if flags.writable() || flags.contains(OpenFlags::O_TRUNC) {
+        want |= AccessMode::WRITE;
+    }
+    inode_permission(inode, cred, want)
+}
+
+/// 检查调用者能否在目录 `dir` 中创建、删除或重命名目录项：需要写与搜索权限
+pub fn may_modify_dir(dir: &dyn Inode, cred: &FsCred) -> Result<(), FsError> {
+    inode_permission(dir, cred, AccessMode::WRITE | AccessMode::EXECUTE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acl::{AclEntry, AclTag};
+    use crate::impls::inotify_file::tests::dummy_with;
+    use crate::{FileMode, TimeSpec, XattrFlags};
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use sync::mock::init_arch_ops;
+
+    fn meta(inode_type: InodeType, mode: u32, uid: u32, gid: u32) -> InodeMetadata {
+        InodeMetadata {
+            inode_no: 1,
+            inode_type,
+            mode: FileMode::from_bits_truncate(mode),
+            uid,
+            gid,
+            size: 0,
+            atime: TimeSpec::zero(),
+            mtime: TimeSpec::zero(),
+            ctime: TimeSpec::zero(),
+            nlinks: 1,
+            blocks: 0,
+            rdev: 0,
+        }
+    }
+
+    fn user(uid: u32, gid: u32) -> FsCred {
+        FsCred {
+            fsuid: uid,
+            fsgid: gid,
+            dac_override: false,
+            dac_read_search: false,
+            fowner: false,
+            fsetid: false,
+        }
+    }
+
+    fn acl_inode(meta: InodeMetadata, acl: Option<&PosixAcl>) -> Arc<dyn Inode> {
+        init_arch_ops();
+        let inode = dummy_with(meta);
+        if let Some(acl) = acl {
+            let name = AclType::Access.xattr_name();
+            inode
+                .setxattr(name, &acl.to_xattr(), XattrFlags::empty())
+                .unwrap();
+        }
+        inode
+    }
+
+    #[test]
+    fn test_mode_bits() {
+        let file = meta(InodeType::File, 0o640, 1000, 100);
+        let rw = AccessMode::READ | AccessMode::WRITE;
+        assert!(generic_permission(&file, None, &user(1000, 1), rw).is_ok());
+        assert!(generic_permission(&file, None, &user(2000, 100), AccessMode::READ).is_ok());
+        assert_eq!(
+            generic_permission(&file, None, &user(2000, 100), rw),
+            Err(FsError::PermissionDenied)
+        );
+        assert_eq!(
+            generic_permission(&file, None, &user(2000, 1), AccessMode::READ),
+            Err(FsError::PermissionDenied)
+        );
+        // 属主位优先于其他位
+        let other_only = meta(InodeType::File, 0o007, 1000, 100);
+        assert!(generic_permission(&other_only, None, &user(1000, 1), AccessMode::READ).is_err());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let root = FsCred {
+            dac_override: true,
+            dac_read_search: true,
+            ..user(0, 0)
+        };
+        let file = meta(InodeType::File, 0o600, 1000, 100);
+        assert!(generic_permission(&file, None, &root, AccessMode::WRITE).is_ok());
+        // 没有任何 x 位时 CAP_DAC_OVERRIDE 也不能执行
+        assert!(generic_permission(&file, None, &root, AccessMode::EXECUTE).is_err());
+
+        let dir = meta(InodeType::Directory, 0o700, 1000, 100);
+        let search = FsCred {
+            dac_read_search: true,
+            ..user(2000, 2000)
+        };
+        let rx = AccessMode::READ | AccessMode::EXECUTE;
+        assert!(generic_permission(&dir, None, &search, rx).is_ok());
+        assert!(generic_permission(&dir, None, &search, AccessMode::WRITE).is_err());
+    }
+
+    #[test]
+    fn test_acl_overrides_group_bits() {
+        let acl = PosixAcl::from_entries(vec![
+            AclEntry::new(AclTag::UserObj, 0o6, 0),
+            AclEntry::new(AclTag::User, 0o6, 2000),
+            AclEntry::new(AclTag::GroupObj, 0o4, 0),
+            AclEntry::new(AclTag::Mask, 0o4, 0),
+            AclEntry::new(AclTag::Other, 0o0, 0),
+        ])
+        .unwrap();
+        let file = meta(InodeType::File, 0o640, 1000, 100);
+
+        // 指定用户的写权限被 MASK 屏蔽
+        assert!(generic_permission(&file, Some(&acl), &user(2000, 1), AccessMode::READ).is_ok());
+        assert!(generic_permission(&file, Some(&acl), &user(2000, 1), AccessMode::WRITE).is_err());
+        // 没有匹配表项时落到 OTHER
+        assert!(generic_permission(&file, Some(&acl), &user(3000, 1), AccessMode::READ).is_err());
+        // 属主不受 ACL 影响
+        assert!(generic_permission(&file, Some(&acl), &user(1000, 1), AccessMode::WRITE).is_ok());
+    }
+
+    #[test]
+    fn test_may_open_denied_by_acl() {
+        // 属组位允许读写，但 ACL 中的指定用户表项拒绝用户 2000
+        let acl = PosixAcl::from_entries(vec![
+            AclEntry::new(AclTag::UserObj, 0o6, 0),
+            AclEntry::new(AclTag::User, 0o0, 2000),
+            AclEntry::new(AclTag::GroupObj, 0o6, 0),
+            AclEntry::new(AclTag::Mask, 0o6, 0),
+            AclEntry::new(AclTag::Other, 0o4, 0),
+        ])
+        .unwrap();
+        let file = acl_inode(meta(InodeType::File, 0o664, 1000, 100), Some(&acl));
+
+        let denied = user(2000, 100);
+        assert_eq!(
+            may_open(file.as_ref(), &denied, OpenFlags::O_RDONLY),
+            Err(FsError::PermissionDenied)
+        );
+        assert!(may_open(file.as_ref(), &user(2001, 100), OpenFlags::O_RDWR).is_ok());
+
+        // 其他用户只能读，O_TRUNC 需要写权限
+        let other = user(3000, 1);
+        assert!(may_open(file.as_ref(), &other, OpenFlags::O_RDONLY).is_ok());
+        assert!(may_open(file.as_ref(), &other, OpenFlags::O_WRONLY).is_err());
+        assert!(
+            may_open(
+                file.as_ref(),
+                &other,
+                OpenFlags::O_RDONLY | OpenFlags::O_TRUNC
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_may_lookup_and_modify_dir() {
+        let dir = acl_inode(meta(InodeType::Directory, 0o755, 1000, 100), None);
+        let other = user(2000, 2000);
+        assert!(may_lookup(dir.as_ref(), &other).is_ok());
+        assert_eq!(
+            may_modify_dir(dir.as_ref(), &other),
+            Err(FsError::PermissionDenied)
+        );
+        assert!(may_modify_dir(dir.as_ref(), &user(1000, 1)).is_ok());
+
+        // 没有搜索权限时不能遍历；不是目录时交给查找本身报错
+        let private = acl_inode(meta(InodeType::Directory, 0o700, 1000, 100), None);
+        assert_eq!(
+            may_lookup(private.as_ref(), &other),
+            Err(FsError::PermissionDenied)
+        );
+        let file = acl_inode(meta(InodeType::File, 0o600, 1000, 100), None);
+        assert!(may_lookup(file.as_ref(), &other).is_ok());
+    }
+}
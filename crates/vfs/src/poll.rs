@@ -0,0 +1,130 @@
+//! 文件就绪通知
+//!
+//! poll/select/epoll 通过 [`File::poll`](crate::File::poll) 查询文件的就绪事件，同时把一个
+//! [`PollWaker`] 登记到文件的 [`PollQueue`]；文件状态改变时唤醒队列，等待者再重新查询。
+//! 队列只持有等待者的弱引用：一次 poll 调用结束后它登记的等待者自然失效，
+//! epoll 的监视项则在移除之前一直有效。
+//!
+//! 没有自己队列的文件登记到 [`GENERIC_POLL_QUEUE`]，由 [`VfsOps::wake_event`](crate::VfsOps::wake_event)
+//! 等全局事件唤醒；状态只能靠轮询发现的设备（轮询式终端驱动）登记到 [`TICK_POLL_QUEUE`]，
+//! 由 os crate 在每个时钟节拍唤醒。
+
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+use sync::SpinLock;
+use uapi::epoll::EpollEvents;
+
+/// 等待文件就绪的一方（一次 poll/select 调用，或 epoll 实例中的一个监视项）
+pub trait PollWaker: Send + Sync {
+    /// 被等待的文件状态可能改变，`events` 是可能变为就绪的事件（空表示未知）
+    ///
+    /// 可能在中断上下文或持有文件内部锁以外的任意位置调用，不能睡眠。
+    fn wake(&self, events: EpollEvents);
+}
+
+/// 文件的就绪等待队列
+pub struct PollQueue {
+    wakers: SpinLock<Vec<Weak<dyn PollWaker>>>,
+}
+
+impl PollQueue {
+    /// 创建一个空队列
+    pub const fn new() -> Self {
+        Self {
+            wakers: SpinLock::new(Vec::new()),
+        }
+    }
+
+    /// 登记 `waker`，已登记的不重复登记
+    pub fn register(&self, waker: &Arc<dyn PollWaker>) {
+        let ptr = Arc::as_ptr(waker) as *const ();
+        let mut wakers = self.wakers.lock();
+        wakers.retain(|w| w.strong_count() > 0);
+        if !wakers.iter().any(|w| w.as_ptr() as *const () == ptr) {
+            wakers.push(Arc::downgrade(waker));
+        }
+    }
+
+    /// 唤醒所有登记的等待者
+    pub fn wake(&self, events: EpollEvents) {
+        let wakers: Vec<Arc<dyn PollWaker>> = {
+            let mut wakers = self.wakers.lock();
+            wakers.retain(|w| w.strong_count() > 0);
+            wakers.iter().filter_map(Weak::upgrade).collect()
+        };
+        // 在队列锁外回调：等待者可能反过来查询同一文件
+        for waker in wakers {
+            waker.wake(events);
+        }
+    }
+}
+
+impl Default for PollQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 没有自己等待队列的文件共用的队列
+pub static GENERIC_POLL_QUEUE: PollQueue = PollQueue::new();
+
+/// 状态只能靠轮询发现的设备共用的队列，每个时钟节拍唤醒一次
+pub static TICK_POLL_QUEUE: PollQueue = PollQueue::new();
+
+/// 唤醒 [`GENERIC_POLL_QUEUE`] 上的等待者
+pub fn wake_generic_pollers() {
+    GENERIC_POLL_QUEUE.wake(EpollEvents::empty());
+}
+
+/// 唤醒 [`TICK_POLL_QUEUE`] 上的等待者（由时钟节拍调用）
+pub fn wake_tick_pollers() {
+    TICK_POLL_QUEUE.wake(EpollEvents::empty());
+}
+
+/// 由 `readable`/`writable` 得出的就绪事件
+pub(crate) fn readiness(readable: bool, writable: bool) -> EpollEvents {
+    let mut events = EpollEvents::empty();
+    if readable {
+        events |= EpollEvents::IN | EpollEvents::RDNORM;
+    }
+    if writable {
+        events |= EpollEvents::OUT | EpollEvents::WRNORM;
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use sync::mock::init_arch_ops;
+
+    struct CountingWaker(AtomicUsize);
+
+    impl PollWaker for CountingWaker {
+        fn wake(&self, _events: EpollEvents) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_poll_queue_dedup_and_expiry() {
+        init_arch_ops();
+        let queue = PollQueue::new();
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker: Arc<dyn PollWaker> = counter.clone();
+
+        // 重复登记只唤醒一次
+        queue.register(&waker);
+        queue.register(&waker);
+        queue.wake(EpollEvents::IN);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+
+        // 等待者释放后自动失效
+        drop(waker);
+        drop(counter);
+        queue.wake(EpollEvents::IN);
+        assert!(queue.wakers.lock().is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//! sendfile/splice 的数据搬运
+//!
+//! [`File::splice_to`] 的默认实现经一个页大小的内核缓冲区中转：从输入端读入，再写到输出端。
+//! 缓冲区取自全局缓冲池，用完归还，避免每次调用都分配。
+//!
+//! 输出端实现了 [`File::splice_from`]（如 TCP socket）时，普通文件直接把文件页交给输出端，
+//! 由它从 inode 读入自己的发送缓冲区，省去中转这一次复制。
+//!
+//! 输入端没有偏移量（管道除外，见 [`PipeFile`](crate::PipeFile)）时，读出但没能写出的数据会丢失；
+//! 有偏移量时只按写出的字节数前移。
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+use sync::SpinLock;
+
+use crate::{File, FsError, Inode};
+
+/// 中转缓冲区大小
+pub(crate) const SPLICE_BUFFER_SIZE: usize = 4096;
+
+/// 缓冲池最多保留的空闲缓冲区数
+const SPLICE_POOL_MAX: usize = 8;
+
+/// 空闲的中转缓冲区
+static SPLICE_POOL: SpinLock<Vec<Box<[u8]>>> = SpinLock::new(Vec::new());
+
+/// 从缓冲池取出的中转缓冲区，drop 时归还
+pub(crate) struct SpliceBuffer(Option<Box<[u8]>>);
+
+impl SpliceBuffer {
+    /// 取出一个缓冲区，池空时新分配
+    pub(crate) fn take() -> Self {
+        let buf = SPLICE_POOL
+            .lock()
+            .pop()
+            .unwrap_or_else(|| vec![0u8; SPLICE_BUFFER_SIZE].into_boxed_slice());
+        Self(Some(buf))
+    }
+}
+
+impl Deref for SpliceBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.0.as_deref().unwrap()
+    }
+}
+
+impl DerefMut for SpliceBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.0.as_deref_mut().unwrap()
+    }
+}
+
+impl Drop for SpliceBuffer {
+    fn drop(&mut self) {
+        if let Some(buf) = self.0.take() {
+            let mut pool = SPLICE_POOL.lock();
+            if pool.len() < SPLICE_POOL_MAX {
+                pool.push(buf);
+            }
+        }
+    }
+}
+
+/// 已搬运部分数据后出错时返回已搬运的字节数，否则返回错误
+fn partial(total: usize, err: FsError) -> Result<usize, FsError> {
+    if total > 0 { Ok(total) } else { Err(err) }
+}
+
+/// 把 `data` 尽量全部写到 `out`，`offset` 为 `Some` 时在该位置写并前移
+///
+/// 输出端写不下时返回已写出的字节数（可能为 0）。
+pub(crate) fn write_all(
+    out: &dyn File,
+    mut offset: Option<&mut usize>,
+    data: &[u8],
+) -> Result<usize, FsError> {
+    let mut written = 0;
+    while written < data.len() {
+        let result = match offset.as_deref_mut() {
+            Some(off) => out.write_at(*off, &data[written..]),
+            None => out.write(&data[written..]),
+        };
+        let n = match result {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) => return partial(written, e),
+        };
+        if let Some(off) = offset.as_deref_mut() {
+            *off += n;
+        }
+        written += n;
+    }
+    Ok(written)
+}
+
+/// 经中转缓冲区把 `input` 的至多 `len` 字节写到 `out`（[`File::splice_to`] 的默认实现）
+pub(crate) fn copy_through_buffer<F: File + ?Sized>(
+    input: &F,
+    mut offset: Option<&mut usize>,
+    out: &dyn File,
+    mut out_offset: Option<&mut usize>,
+    len: usize,
+) -> Result<usize, FsError> {
+    let mut buf = SpliceBuffer::take();
+    let mut total = 0;
+    while total < len {
+        let chunk = (len - total).min(buf.len());
+        let result = match offset.as_deref() {
+            Some(&off) => input.read_at(off, &mut buf[..chunk]),
+            None => input.read(&mut buf[..chunk]),
+        };
+        let n = match result {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) => return partial(total, e),
+        };
+        let written = match write_all(out, out_offset.as_deref_mut(), &buf[..n]) {
+            Ok(written) => written,
+            Err(e) => return partial(total, e),
+        };
+        if let Some(off) = offset.as_deref_mut() {
+            *off += written;
+        }
+        total += written;
+        if written < n {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+/// 把 `inode` 从 `*offset` 开始的至多 `len` 字节的文件页直接交给 `out`，并前移 `*offset`
+///
+/// 输出端不支持 [`File::splice_from`] 时返回 [`FsError::NotSupported`]，调用者应退回缓冲区中转。
+pub(crate) fn splice_file_pages(
+    inode: &Arc<dyn Inode>,
+    offset: &mut usize,
+    out: &dyn File,
+    len: usize,
+) -> Result<usize, FsError> {
+    let mut total = 0;
+    while total < len {
+        let n = match out.splice_from(inode, *offset, len - total) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) => return partial(total, e),
+        };
+        *offset += n;
+        total += n;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PipeFile;
+    use sync::mock::init_arch_ops;
+
+    #[test]
+    fn test_splice_pipe_keeps_unwritten_data() {
+        init_arch_ops();
+        let (src_r, src_w) = PipeFile::create_pair();
+        let (dst_r, dst_w) = PipeFile::create_pair();
+        let data: Vec<u8> = (0..4096).map(|i| i as u8).collect();
+        assert_eq!(src_w.write(&data), Ok(4096));
+        assert_eq!(dst_w.write(&[0u8; 1000]), Ok(1000));
+
+        // 目标管道只剩 3096 字节空间，其余数据留在源管道中
+        assert_eq!(src_r.splice_to(None, &dst_w, None, 4096), Ok(3096));
+        let mut rest = [0u8; 4096];
+        assert_eq!(src_r.read(&mut rest), Ok(1000));
+        assert_eq!(&rest[..1000], &data[3096..]);
+
+        let mut moved = [0u8; 4096];
+        assert_eq!(dst_r.read(&mut moved), Ok(4096));
+        assert_eq!(&moved[1000..], &data[..3096]);
+    }
+
+    #[test]
+    fn test_copy_through_buffer_limits() {
+        init_arch_ops();
+        let (src_r, src_w) = PipeFile::create_pair();
+        let (dst_r, dst_w) = PipeFile::create_pair();
+        assert_eq!(src_w.write(b"hello"), Ok(5));
+
+        // 长度限制生效，输入端在 EOF 前停止
+        assert_eq!(copy_through_buffer(&src_r, None, &dst_w, None, 3), Ok(3));
+        drop(src_w);
+        assert_eq!(copy_through_buffer(&src_r, None, &dst_w, None, 100), Ok(2));
+        let mut buf = [0u8; 8];
+        assert_eq!(dst_r.read(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"hello");
+
+        // 管道不支持按偏移读取
+        let mut off = 0;
+        assert_eq!(
+            copy_through_buffer(&dst_r, Some(&mut off), &dst_w, None, 1),
+            Err(FsError::NotSupported)
+        );
+    }
+}
@@ -0,0 +1,1014 @@
+//! TTY 子系统
+//!
+//! 每个终端设备对应一个 [`Tty`]（Linux 的 `tty_struct`），持有 termios / 窗口大小，
+//! 并通过 [`NTty`] 行规程完成规范模式编辑、回显和信号生成：
+//!
+//! ```text
+//!   CharDeviceFile (/dev/tty*, /dev/console) / StdinFile / StdoutFile
+//!                            │ read / write / ioctl
+//!                           Tty ── termios, winsize
+//!                            │
+//!                     NTty 行规程
+//!                            │
+//!                CharDriver（串口 / 控制台）
+//! ```
+//!
+//! 同一设备号的所有打开文件共享同一个 `Tty`（[`tty_for_device`]），
+//! 因此一个进程修改 termios 对其它打开同一终端的进程可见，与 Linux 一致。
+//!
+//! 终端可以成为一个会话的控制终端（TIOCSCTTY，或会话首进程不带 `O_NOCTTY` 打开），
+//! 此后 ^C / ^\ / ^Z 产生的信号投递给它的前台进程组（TIOCSPGRP 设置）。
+//!
+//! 伪终端（[`pty`]）的从设备同样是一个 `Tty`，其驱动把输出交给主设备读取。
+//!
+//! 输入由驱动在中断中通过 [`Tty::receive`] 推送进行规程，阻塞的读取者在终端的等待通道上睡眠、
+//! 由输入唤醒；不支持推送的驱动（[`CharDriver::pushes_input`] 为假）由读取者定期轮询。
+//! IXON 下 ^S / ^Q 暂停、恢复输出：暂停期间写入者阻塞，回显暂存到恢复为止；
+//! IXOFF 下输入缓冲区将满时向设备发送 STOP 字符，读走数据后再发送 START。
+
+mod n_tty;
+pub mod pty;
+
+pub use n_tty::{N_TTY_BUF_SIZE, NTty, process_output};
+pub use pty::{Pty, PtyMasterFile, open_ptmx};
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use sync::SpinLock;
+use uapi::epoll::EpollEvents;
+use uapi::ioctl::{Termios, WinSize};
+
+use crate::dev::makedev;
+use crate::devno::{chrdev_major, console_minor, get_chrdev_driver};
+use crate::poll::{PollQueue, PollWaker, TICK_POLL_QUEUE, readiness};
+use crate::{CharDriver, FsError, UserAccessGuard, vfs_ops};
+
+/// 一个终端实例
+pub struct Tty {
+    /// 设备名（如 `ttyS0`、`console`）
+    name: String,
+    /// 底层驱动
+    driver: Arc<dyn CharDriver>,
+    /// 终端属性
+    termios: SpinLock<Termios>,
+    /// 窗口大小
+    winsize: SpinLock<WinSize>,
+    /// 行规程状态
+    ldisc: SpinLock<NTty>,
+    /// 是否已挂断（PTY 主设备关闭）；挂断后读返回 EOF，写返回 EIO
+    hung_up: AtomicBool,
+    /// 以该终端为控制终端的会话 ID（0 表示不是控制终端）
+    session: AtomicU32,
+    /// 前台进程组 ID（0 表示未设置）
+    pgrp: AtomicU32,
+    /// 输出暂停期间积压的回显，恢复输出时写出
+    held: SpinLock<Vec<u8>>,
+    /// 已因 IXOFF 向设备发送 STOP 字符，等待输入被读走
+    throttled: AtomicBool,
+    /// poll/epoll 的等待队列，与等待通道同时唤醒
+    poll_queue: PollQueue,
+}
+
+/// 轮询式驱动的输入检查间隔（毫秒）
+const POLL_INTERVAL_MS: i64 = 10;
+/// IXOFF：缓冲的输入超过该值时向设备发送 STOP 字符
+const THROTTLE_HIGH: usize = N_TTY_BUF_SIZE - 512;
+/// IXOFF：缓冲的输入降到该值以下时向设备发送 START 字符
+const THROTTLE_LOW: usize = N_TTY_BUF_SIZE / 4;
+
+impl Tty {
+    /// 创建终端
+    pub fn new(name: String, driver: Arc<dyn CharDriver>) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            driver,
+            termios: SpinLock::new(Termios::DEFAULT),
+            winsize: SpinLock::new(WinSize {
+                ws_row: 24,
+                ws_col: 80,
+                ws_xpixel: 0,
+                ws_ypixel: 0,
+            }),
+            ldisc: SpinLock::new(NTty::new()),
+            hung_up: AtomicBool::new(false),
+            session: AtomicU32::new(0),
+            pgrp: AtomicU32::new(0),
+            held: SpinLock::new(Vec::new()),
+            throttled: AtomicBool::new(false),
+            poll_queue: PollQueue::new(),
+        })
+    }
+
+    /// 设备名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 当前终端属性
+    pub fn termios(&self) -> Termios {
+        *self.termios.lock()
+    }
+
+    /// 设置终端属性，并让行规程适应新的模式
+    pub fn set_termios(&self, new: Termios) {
+        let old = core::mem::replace(&mut *self.termios.lock(), new);
+        self.ldisc.lock().set_termios(&old, &new);
+    }
+
+    /// 当前窗口大小
+    pub fn winsize(&self) -> WinSize {
+        *self.winsize.lock()
+    }
+
+    /// 设置窗口大小
+    pub fn set_winsize(&self, winsize: WinSize) {
+        *self.winsize.lock() = winsize;
+    }
+
+    /// 打开终端（每个打开该终端的文件调用一次）
+    pub fn open(&self) -> Result<(), FsError> {
+        self.driver.open()
+    }
+
+    /// 关闭终端的一个打开实例
+    pub fn release(&self) {
+        self.driver.release();
+    }
+
+    /// 挂断终端：向前台进程组发送 SIGHUP / SIGCONT 并解除与会话的关联
+    pub fn hangup(&self) {
+        self.hung_up.store(true, Ordering::Release);
+        self.disassociate();
+        self.wake_waiters();
+    }
+
+    /// 终端是否已挂断
+    pub fn is_hung_up(&self) -> bool {
+        self.hung_up.load(Ordering::Acquire)
+    }
+
+    /// 等待通道：输入到达、输出恢复或挂断时唤醒
+    fn wait_chan(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// 唤醒在等待通道上睡眠的读写者与 poll/epoll 的等待者
+    fn wake_waiters(&self) {
+        vfs_ops().wake_event(self.wait_chan());
+        self.poll_queue.wake(EpollEvents::empty());
+    }
+
+    /// 输出是否被暂停（^S 或 TCOOFF）
+    pub fn is_stopped(&self) -> bool {
+        self.ldisc.lock().is_stopped()
+    }
+
+    /// 暂停输出
+    fn stop_output(&self) {
+        self.ldisc.lock().set_stopped(true);
+    }
+
+    /// 恢复输出：写出积压的回显并唤醒被阻塞的写入者
+    fn start_output(&self) {
+        self.ldisc.lock().set_stopped(false);
+        self.flush_held();
+        self.wake_waiters();
+    }
+
+    /// 暂存输出暂停期间的回显（超出缓冲区上限的部分被丢弃）
+    fn hold(&self, data: &[u8]) {
+        let mut held = self.held.lock();
+        let room = N_TTY_BUF_SIZE.saturating_sub(held.len());
+        held.extend_from_slice(&data[..data.len().min(room)]);
+    }
+
+    /// 写出积压的回显
+    fn flush_held(&self) {
+        let held = core::mem::take(&mut *self.held.lock());
+        if !held.is_empty() {
+            self.driver.write(&held);
+        }
+    }
+
+    /// 输入被读走后，若此前因 IXOFF 暂停了设备发送，则发送 START 字符
+    fn unthrottle(&self, termios: &Termios) {
+        if self.throttled.load(Ordering::Acquire)
+            && self.ldisc.lock().buffered() <= THROTTLE_LOW
+            && self.throttled.swap(false, Ordering::AcqRel)
+        {
+            self.driver.write(&[termios.c_cc[uapi::ioctl::VSTART]]);
+        }
+    }
+
+    /// 以该终端为控制终端的会话 ID（0 表示无）
+    pub fn session(&self) -> u32 {
+        self.session.load(Ordering::Acquire)
+    }
+
+    /// 前台进程组 ID（0 表示未设置）
+    pub fn foreground_pgrp(&self) -> u32 {
+        self.pgrp.load(Ordering::Acquire)
+    }
+
+    /// 该终端是否为当前进程的控制终端
+    fn is_current_ctty(&self) -> bool {
+        let session = self.session();
+        session != 0 && session == vfs_ops().current_sid()
+    }
+
+    /// 使该终端成为当前会话的控制终端（TIOCSCTTY）
+    ///
+    /// 调用者必须是没有控制终端的会话首进程；终端已属于其它会话时，
+    /// 只有 `steal` 为真才会被抢占。前台进程组初始化为调用者所在的进程组。
+    pub fn set_controlling(self: &Arc<Self>, steal: bool) -> Result<(), i32> {
+        use uapi::errno::EPERM;
+
+        let ops = vfs_ops();
+        let sid = ops.current_sid();
+        if let Some(cur) = current_ctty() {
+            return if Arc::ptr_eq(&cur, self) {
+                Ok(())
+            } else {
+                Err(EPERM)
+            };
+        }
+        if sid != ops.current_pid() {
+            return Err(EPERM);
+        }
+        let owner = self.session();
+        if owner != 0 && owner != sid && !steal {
+            return Err(EPERM);
+        }
+        self.session.store(sid, Ordering::Release);
+        self.pgrp.store(ops.current_pgid(), Ordering::Release);
+        ops.set_controlling_tty(Some(self.clone()));
+        Ok(())
+    }
+
+    /// 打开时自动获取控制终端
+    ///
+    /// 与 Linux 相同：没有控制终端的会话首进程不带 `O_NOCTTY` 打开一个
+    /// 不属于任何会话的终端时，该终端成为它的控制终端。
+    pub fn open_as_controlling(self: &Arc<Self>) {
+        let ops = vfs_ops();
+        if self.session() == 0 && current_ctty().is_none() && ops.current_sid() == ops.current_pid()
+        {
+            let _ = self.set_controlling(false);
+        }
+    }
+
+    /// 解除与会话的关联（会话首进程退出、TIOCNOTTY 或挂断）
+    ///
+    /// 若存在前台进程组，向其发送 SIGHUP 与 SIGCONT。
+    pub fn disassociate(&self) {
+        use uapi::signal::{NUM_SIGCONT, NUM_SIGHUP};
+
+        if self.session.swap(0, Ordering::AcqRel) == 0 {
+            return;
+        }
+        let pgrp = self.pgrp.swap(0, Ordering::AcqRel);
+        if pgrp != 0 {
+            let ops = vfs_ops();
+            ops.kill_pgrp(pgrp, NUM_SIGHUP);
+            ops.kill_pgrp(pgrp, NUM_SIGCONT);
+        }
+    }
+
+    /// 将设备收到的字节送入行规程（可在中断上下文调用）
+    ///
+    /// 回显经输出处理后写回设备（输出被暂停时暂存）；随后唤醒等待的读取者，
+    /// 产生的信号在释放行规程锁之后投递给前台进程组。
+    pub fn receive(&self, data: &[u8]) {
+        self.input(data, false);
+    }
+
+    /// 行规程输入的实现
+    ///
+    /// `from_reader` 表示输入是在读取者上下文中从驱动拉取的：此时若终端还没有
+    /// 前台进程组（例如直接在控制台上运行、未建立会话的 shell），信号退而投递给读取者所在的进程组。
+    fn input(&self, data: &[u8], from_reader: bool) {
+        use uapi::ioctl::{IXOFF, VSTOP};
+
+        let termios = self.termios();
+        let mut echo = Vec::new();
+        let mut signals = Vec::new();
+        let (stopped, buffered) = {
+            let mut ldisc = self.ldisc.lock();
+            for &ch in data {
+                if let Some(sig) = ldisc.receive_char(ch, &termios, &mut echo) {
+                    signals.push(sig);
+                }
+            }
+            (ldisc.is_stopped(), ldisc.buffered())
+        };
+        let echo = (!echo.is_empty()).then(|| process_output(&echo, &termios));
+        if stopped {
+            if let Some(echo) = echo {
+                self.hold(&echo);
+            }
+        } else {
+            // 输出刚被恢复时，先写出暂停期间积压的回显
+            self.flush_held();
+            if let Some(echo) = echo {
+                self.driver.write(&echo);
+            }
+        }
+        if termios.c_iflag & IXOFF != 0
+            && buffered >= THROTTLE_HIGH
+            && !self.throttled.swap(true, Ordering::AcqRel)
+        {
+            self.driver.write(&[termios.c_cc[VSTOP]]);
+        }
+        self.wake_waiters();
+        for sig in signals {
+            self.send_signal(sig, from_reader);
+        }
+    }
+
+    /// 向终端的前台进程组投递信号
+    fn send_signal(&self, sig: usize, from_reader: bool) {
+        let ops = vfs_ops();
+        let pgrp = match self.foreground_pgrp() {
+            0 if from_reader => ops.current_pgid(),
+            pgrp => pgrp,
+        };
+        ops.kill_pgrp(pgrp, sig);
+    }
+
+    /// 等待输入到达、挂断或超时
+    ///
+    /// 轮询式驱动最多睡眠 [`POLL_INTERVAL_MS`] 后返回，由调用者重新拉取输入。
+    fn wait_input(&self, deadline: Option<i64>) -> Result<(), FsError> {
+        let deadline = if self.driver.pushes_input() {
+            deadline
+        } else {
+            let poll = now_ms() + POLL_INTERVAL_MS;
+            Some(deadline.map_or(poll, |d| d.min(poll)))
+        };
+        let ready = || self.is_hung_up() || self.ldisc.lock().readable(&self.termios());
+        if vfs_ops().wait_event(self.wait_chan(), deadline, &ready) {
+            Ok(())
+        } else {
+            Err(FsError::Interrupted)
+        }
+    }
+
+    /// 从驱动拉取所有已到达的字节（轮询式驱动）
+    fn pull_input(&self) {
+        let mut buf = [0u8; 64];
+        loop {
+            let mut n = 0;
+            while n < buf.len() {
+                match self.driver.try_read() {
+                    Some(ch) => {
+                        buf[n] = ch;
+                        n += 1;
+                    }
+                    None => break,
+                }
+            }
+            if n == 0 {
+                return;
+            }
+            self.input(&buf[..n], true);
+            if n < buf.len() {
+                return;
+            }
+        }
+    }
+
+    /// 读取
+    ///
+    /// 规范模式下等待一整行（EOF 返回 0）。非规范模式按 termios 的 VMIN / VTIME：
+    ///
+    /// | VMIN | VTIME | 返回时机 |
+    /// |------|-------|----------|
+    /// | 0    | 0     | 立即返回已有数据（可能为 0） |
+    /// | 0    | > 0   | 有数据或超时（返回 0） |
+    /// | > 0  | 0     | 至少读到 min(VMIN, buf.len()) 字节 |
+    /// | > 0  | > 0   | 读满 VMIN，或读到首字节后字节间隔超时 |
+    ///
+    /// VTIME 的单位为 0.1 秒。没有数据时在终端的等待通道上睡眠；
+    /// 尚未读到数据就被信号打断时返回 [`FsError::Interrupted`]。
+    pub fn read(&self, buf: &mut [u8], nonblock: bool) -> Result<usize, FsError> {
+        use uapi::ioctl::{ICANON, VMIN, VTIME};
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let termios = self.termios();
+        let canonical = termios.c_lflag & ICANON != 0;
+        let vmin = termios.c_cc[VMIN] as usize;
+        let vtime_ms = termios.c_cc[VTIME] as i64 * 100;
+        let want = vmin.min(buf.len()).max(1);
+        let mut deadline = (!canonical && vmin == 0 && vtime_ms > 0).then(|| now_ms() + vtime_ms);
+
+        let mut count = 0;
+        loop {
+            self.pull_input();
+            // 先释放行规程锁：下面的 unthrottle 需要再次获取
+            let read = self.ldisc.lock().read(&mut buf[count..], &termios);
+            if let Some(n) = read {
+                if canonical {
+                    self.unthrottle(&termios);
+                    return Ok(n);
+                }
+                if n > 0 {
+                    count += n;
+                    if vmin > 0 && vtime_ms > 0 {
+                        // 字节间隔计时器：每收到数据重新计时
+                        deadline = Some(now_ms() + vtime_ms);
+                    }
+                }
+                self.unthrottle(&termios);
+            }
+            if count >= want {
+                return Ok(count);
+            }
+            if self.is_hung_up() {
+                return Ok(count);
+            }
+            if !canonical && vmin == 0 && vtime_ms == 0 {
+                return Ok(count);
+            }
+            if deadline.is_some_and(|d| now_ms() >= d) {
+                return Ok(count);
+            }
+            if nonblock {
+                return if count > 0 {
+                    Ok(count)
+                } else {
+                    Err(FsError::WouldBlock)
+                };
+            }
+            if let Err(e) = self.wait_input(deadline) {
+                return if count > 0 { Ok(count) } else { Err(e) };
+            }
+        }
+    }
+
+    /// 写入（经 OPOST 输出处理）
+    ///
+    /// 输出被暂停时阻塞到恢复为止。
+    pub fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        loop {
+            if self.is_hung_up() {
+                return Err(FsError::IoError);
+            }
+            if !self.is_stopped() {
+                break;
+            }
+            let resumed = || self.is_hung_up() || !self.is_stopped();
+            if !vfs_ops().wait_event(self.wait_chan(), None, &resumed) {
+                return Err(FsError::Interrupted);
+            }
+        }
+        let termios = self.termios();
+        self.driver.write(&process_output(buf, &termios));
+        Ok(buf.len())
+    }
+
+    /// 是否有可读数据
+    pub fn readable(&self) -> bool {
+        if self.is_hung_up() {
+            return true;
+        }
+        self.pull_input();
+        let termios = self.termios();
+        self.ldisc.lock().readable(&termios)
+    }
+
+    /// 查询就绪事件（poll/epoll），给出 `waker` 时登记到终端的等待队列
+    ///
+    /// 轮询式驱动的输入到达时没有通知，`waker` 还登记到 [`TICK_POLL_QUEUE`] 定期重新查询。
+    pub fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            self.poll_queue.register(waker);
+            if !self.driver.pushes_input() {
+                TICK_POLL_QUEUE.register(waker);
+            }
+        }
+        if self.is_hung_up() {
+            return EpollEvents::IN | EpollEvents::RDNORM | EpollEvents::HUP;
+        }
+        readiness(self.readable(), !self.is_stopped())
+    }
+
+    /// 丢弃输入队列（行规程中尚未读取的全部数据）
+    pub fn flush_input(&self) {
+        self.ldisc.lock().flush();
+        self.unthrottle(&self.termios());
+    }
+
+    /// 可读字节数（FIONREAD）
+    pub fn input_available(&self) -> usize {
+        self.pull_input();
+        let termios = self.termios();
+        self.ldisc.lock().available(&termios)
+    }
+
+    /// 终端 ioctl
+    ///
+    /// 输出是同步写入驱动的，只有输出暂停期间积压的回显构成输出队列：TCSETSW / TCSBRK 无需等待，
+    /// TCOFLUSH 丢弃积压的回显，TIOCOUTQ 返回其长度。
+    ///
+    /// 作业控制请求（TIOCGPGRP / TIOCSPGRP / TIOCGSID / TIOCNOTTY）要求该终端是调用者的控制终端，
+    /// 否则返回 ENOTTY。
+    pub fn ioctl(self: &Arc<Self>, request: u32, arg: usize) -> Result<isize, FsError> {
+        use uapi::errno::{EINVAL, ENOTTY, EPERM};
+        use uapi::ioctl::*;
+        use uapi::signal::NUM_SIGWINCH;
+
+        let needs_ptr = matches!(
+            request,
+            TCGETS
+                | TCSETS
+                | TCSETSW
+                | TCSETSF
+                | TIOCGWINSZ
+                | TIOCSWINSZ
+                | FIONREAD
+                | TIOCOUTQ
+                | TIOCGPGRP
+                | TIOCSPGRP
+                | TIOCGSID
+        );
+        if needs_ptr && arg == 0 {
+            return Ok(-EINVAL as isize);
+        }
+
+        match request {
+            TCGETS => {
+                write_user(arg, self.termios());
+                Ok(0)
+            }
+            TCSETS | TCSETSW => {
+                self.set_termios(read_user(arg));
+                Ok(0)
+            }
+            TCSETSF => {
+                let termios = read_user(arg);
+                self.flush_input();
+                self.set_termios(termios);
+                Ok(0)
+            }
+            TCFLSH => match arg {
+                TCIFLUSH | TCOFLUSH | TCIOFLUSH => {
+                    if arg != TCOFLUSH {
+                        self.flush_input();
+                    }
+                    if arg != TCIFLUSH {
+                        self.held.lock().clear();
+                    }
+                    Ok(0)
+                }
+                _ => Ok(-EINVAL as isize),
+            },
+            TCXONC => match arg {
+                TCOOFF => {
+                    self.stop_output();
+                    Ok(0)
+                }
+                TCOON => {
+                    self.start_output();
+                    Ok(0)
+                }
+                TCIOFF | TCION => {
+                    let cc = if arg == TCIOFF { VSTOP } else { VSTART };
+                    let ch = self.termios().c_cc[cc];
+                    if ch != 0 {
+                        self.driver.write(&[ch]);
+                    }
+                    Ok(0)
+                }
+                _ => Ok(-EINVAL as isize),
+            },
+            TCSBRK => Ok(0),
+            FIONREAD => {
+                write_user(arg, self.input_available() as i32);
+                Ok(0)
+            }
+            TIOCOUTQ => {
+                write_user(arg, self.held.lock().len() as i32);
+                Ok(0)
+            }
+            TIOCGWINSZ => {
+                write_user(arg, self.winsize());
+                Ok(0)
+            }
+            TIOCSWINSZ => {
+                let winsize: WinSize = read_user(arg);
+                let changed = core::mem::replace(&mut *self.winsize.lock(), winsize) != winsize;
+                if changed {
+                    self.send_signal(NUM_SIGWINCH, false);
+                }
+                Ok(0)
+            }
+            TIOCSCTTY => match self.set_controlling(arg == 1) {
+                Ok(()) => Ok(0),
+                Err(errno) => Ok(-errno as isize),
+            },
+            TIOCNOTTY => {
+                if !self.is_current_ctty() {
+                    return Ok(-ENOTTY as isize);
+                }
+                let ops = vfs_ops();
+                if ops.current_sid() == ops.current_pid() {
+                    self.disassociate();
+                }
+                ops.set_controlling_tty(None);
+                Ok(0)
+            }
+            TIOCGPGRP | TIOCSPGRP | TIOCGSID if !self.is_current_ctty() => Ok(-ENOTTY as isize),
+            TIOCGPGRP => {
+                write_user(arg, self.foreground_pgrp() as i32);
+                Ok(0)
+            }
+            TIOCSPGRP => {
+                let pgrp: i32 = read_user(arg);
+                if pgrp < 0 {
+                    return Ok(-EINVAL as isize);
+                }
+                if !vfs_ops().pgrp_in_session(pgrp as u32, self.session()) {
+                    return Ok(-EPERM as isize);
+                }
+                self.pgrp.store(pgrp as u32, Ordering::Release);
+                Ok(0)
+            }
+            TIOCGSID => {
+                write_user(arg, self.session() as i32);
+                Ok(0)
+            }
+            _ => Ok(-ENOTTY as isize),
+        }
+    }
+}
+
+/// 当前进程仍然有效的控制终端
+///
+/// 任务记录的控制终端可能已被挂断或被其它会话抢占，此时视为没有控制终端。
+pub fn current_ctty() -> Option<Arc<Tty>> {
+    let ops = vfs_ops();
+    let sid = ops.current_sid();
+    ops.controlling_tty()
+        .filter(|tty| sid != 0 && tty.session() == sid)
+}
+
+/// 从用户空间读取 ioctl 参数结构
+fn read_user<T: Copy>(arg: usize) -> T {
+    let _guard = UserAccessGuard::new();
+    // SAFETY: arg 非空，由系统调用层保证指向用户空间可读内存
+    unsafe { core::ptr::read_volatile(arg as *const T) }
+}
+
+/// 向用户空间写回 ioctl 结果
+fn write_user<T: Copy>(arg: usize, value: T) {
+    let _guard = UserAccessGuard::new();
+    // SAFETY: arg 非空，由系统调用层保证指向用户空间可写内存
+    unsafe { core::ptr::write_volatile(arg as *mut T, value) };
+}
+
+/// 当前时间（毫秒），用于 VTIME 计时
+fn now_ms() -> i64 {
+    let now = vfs_ops().timespec_now();
+    now.tv_sec * 1000 + now.tv_nsec / 1_000_000
+}
+
+/// 已创建的终端（按设备号）
+static TTYS: SpinLock<BTreeMap<u64, Arc<Tty>>> = SpinLock::new(BTreeMap::new());
+
+/// 获取设备号对应的终端，首次访问时创建
+///
+/// 仅 TTY / CONSOLE / PTY_SLAVE major 有终端；没有驱动时返回 None。
+/// `/dev/tty`（5, 0）解析为调用者当前的控制终端（见 [`current_ctty`]），没有时返回 None；
+/// `/dev/console` 由 [`DeviceOps::console_device`](crate::DeviceOps::console_device)
+/// 指定实际设备时与该设备共享终端。
+/// `/dev/ptmx`（5, 2）每次打开都创建新的 PTY，见 [`open_ptmx`]。
+pub fn tty_for_device(dev: u64) -> Option<Arc<Tty>> {
+    let maj = crate::dev::major(dev);
+    if maj == chrdev_major::PTY_SLAVE {
+        return pty::pty_slave(crate::dev::minor(dev));
+    }
+    if maj != chrdev_major::TTY && maj != chrdev_major::CONSOLE {
+        return None;
+    }
+    if dev == makedev(chrdev_major::CONSOLE, console_minor::TTY) {
+        return current_ctty();
+    }
+    if dev == makedev(chrdev_major::CONSOLE, console_minor::PTMX) {
+        return None;
+    }
+    let dev = if maj == chrdev_major::CONSOLE {
+        match crate::device_ops().console_device() {
+            Some(target) if crate::dev::major(target) == chrdev_major::TTY => {
+                return tty_for_device(target);
+            }
+            _ => makedev(chrdev_major::CONSOLE, console_minor::CONSOLE),
+        }
+    } else {
+        dev
+    };
+    if let Some(tty) = TTYS.lock().get(&dev) {
+        return Some(tty.clone());
+    }
+    let driver = get_chrdev_driver(dev)?;
+    let min = crate::dev::minor(dev);
+    let name = match maj {
+        chrdev_major::CONSOLE => String::from("console"),
+        _ if min >= 64 => alloc::format!("ttyS{}", min - 64),
+        _ => alloc::format!("tty{}", min),
+    };
+    // 并发首次打开时以先插入者为准
+    Some(
+        TTYS.lock()
+            .entry(dev)
+            .or_insert_with(|| Tty::new(name, driver))
+            .clone(),
+    )
+}
+
+/// 控制台终端（标准 I/O 文件使用）
+///
+/// 与 `/dev/console` 共享同一个 [`Tty`]；控制台设备尚未注册时，退回到基于
+/// [`VfsOps`](crate::VfsOps) 控制台操作的终端。
+pub fn console_tty() -> Arc<Tty> {
+    if let Some(tty) = tty_for_device(makedev(chrdev_major::CONSOLE, 1)) {
+        return tty;
+    }
+    static FALLBACK: SpinLock<Option<Arc<Tty>>> = SpinLock::new(None);
+    FALLBACK
+        .lock()
+        .get_or_insert_with(|| Tty::new(String::from("console"), Arc::new(OpsConsoleDriver)))
+        .clone()
+}
+
+/// 基于 `VfsOps` 控制台操作的驱动
+struct OpsConsoleDriver;
+
+impl CharDriver for OpsConsoleDriver {
+    fn try_read(&self) -> Option<u8> {
+        vfs_ops().console_getchar()
+    }
+
+    fn write(&self, data: &[u8]) {
+        match core::str::from_utf8(data) {
+            Ok(s) => vfs_ops().console_write_str(s),
+            Err(_) => data.iter().for_each(|&b| vfs_ops().console_putchar(b)),
+        }
+    }
+
+    fn ioctl(&self, _request: u32, _arg: usize) -> Result<isize, i32> {
+        Err(uapi::errno::ENOTTY)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloc::collections::VecDeque;
+    use core::sync::atomic::Ordering;
+    use sync::mock::init_arch_ops;
+    use uapi::ioctl::*;
+
+    /// 输入来自预置队列、输出记录下来的驱动
+    struct QueueDriver {
+        input: SpinLock<VecDeque<u8>>,
+        output: SpinLock<Vec<u8>>,
+    }
+
+    impl QueueDriver {
+        fn take_output(&self) -> Vec<u8> {
+            core::mem::take(&mut *self.output.lock())
+        }
+    }
+
+    impl CharDriver for QueueDriver {
+        fn try_read(&self) -> Option<u8> {
+            self.input.lock().pop_front()
+        }
+
+        fn write(&self, data: &[u8]) {
+            self.output.lock().extend_from_slice(data);
+        }
+
+        fn ioctl(&self, _request: u32, _arg: usize) -> Result<isize, i32> {
+            Err(uapi::errno::ENOTTY)
+        }
+    }
+
+    fn tty_with_driver(input: &[u8]) -> (Arc<Tty>, Arc<QueueDriver>) {
+        init_arch_ops();
+        let driver = Arc::new(QueueDriver {
+            input: SpinLock::new(input.iter().copied().collect()),
+            output: SpinLock::new(Vec::new()),
+        });
+        (Tty::new(String::from("test"), driver.clone()), driver)
+    }
+
+    fn tty_with_input(input: &[u8]) -> Arc<Tty> {
+        tty_with_driver(input).0
+    }
+
+    fn get_termios(tty: &Arc<Tty>) -> Termios {
+        let mut termios = Termios::DEFAULT;
+        let arg = &mut termios as *mut Termios as usize;
+        assert_eq!(tty.ioctl(TCGETS, arg).unwrap(), 0);
+        termios
+    }
+
+    fn set_termios(tty: &Arc<Tty>, request: u32, mut termios: Termios) {
+        let arg = &mut termios as *mut Termios as usize;
+        assert_eq!(tty.ioctl(request, arg).unwrap(), 0);
+    }
+
+    fn fionread(tty: &Arc<Tty>) -> i32 {
+        let mut n = -1i32;
+        assert_eq!(tty.ioctl(FIONREAD, &mut n as *mut i32 as usize).unwrap(), 0);
+        n
+    }
+
+    #[test]
+    fn test_termios_roundtrip() {
+        let tty = tty_with_input(b"");
+        let mut raw = get_termios(&tty);
+        raw.c_lflag &= !(ICANON | ECHO);
+        set_termios(&tty, TCSETS, raw);
+        assert_eq!(get_termios(&tty).c_lflag, raw.c_lflag);
+        assert_eq!(tty.ioctl(TCGETS, 0).unwrap(), -uapi::errno::EINVAL as isize);
+    }
+
+    #[test]
+    fn test_tcsetsf_and_tcflsh_discard_input() {
+        let tty = tty_with_input(b"abc\n");
+        assert_eq!(fionread(&tty), 4);
+
+        // TCSETSF 先丢弃输入再生效
+        set_termios(&tty, TCSETSF, get_termios(&tty));
+        assert_eq!(fionread(&tty), 0);
+
+        tty.receive(b"xyz\n");
+        assert_eq!(tty.ioctl(TCFLSH, TCOFLUSH).unwrap(), 0);
+        assert_eq!(fionread(&tty), 4);
+        assert_eq!(tty.ioctl(TCFLSH, TCIFLUSH).unwrap(), 0);
+        assert_eq!(fionread(&tty), 0);
+        assert_eq!(tty.ioctl(TCFLSH, 7).unwrap(), -uapi::errno::EINVAL as isize);
+    }
+
+    #[test]
+    fn test_winsize() {
+        let tty = tty_with_input(b"");
+        let mut ws = WinSize {
+            ws_row: 40,
+            ws_col: 120,
+            ..WinSize::default()
+        };
+        assert_eq!(
+            tty.ioctl(TIOCSWINSZ, &mut ws as *mut WinSize as usize)
+                .unwrap(),
+            0
+        );
+
+        let mut out = WinSize::default();
+        assert_eq!(
+            tty.ioctl(TIOCGWINSZ, &mut out as *mut WinSize as usize)
+                .unwrap(),
+            0
+        );
+        assert_eq!(out, ws);
+    }
+
+    fn ioctl_int(tty: &Arc<Tty>, request: u32, value: i32) -> (isize, i32) {
+        let mut v = value;
+        let ret = tty.ioctl(request, &mut v as *mut i32 as usize).unwrap();
+        (ret, v)
+    }
+
+    #[test]
+    fn test_controlling_tty_and_foreground_pgrp() {
+        use uapi::errno::{EINVAL, ENOTTY, EPERM};
+
+        let tty = tty_with_input(b"");
+        // 尚不是控制终端
+        assert_eq!(ioctl_int(&tty, TIOCGPGRP, 0).0, -ENOTTY as isize);
+
+        // Mock 中当前进程为会话 1 的首进程，进程组为 1
+        assert_eq!(tty.ioctl(TIOCSCTTY, 0).unwrap(), 0);
+        assert_eq!(tty.session(), 1);
+        assert_eq!(ioctl_int(&tty, TIOCGPGRP, 0), (0, 1));
+        assert_eq!(ioctl_int(&tty, TIOCGSID, 0), (0, 1));
+
+        assert_eq!(ioctl_int(&tty, TIOCSPGRP, 7).0, 0);
+        assert_eq!(tty.foreground_pgrp(), 7);
+        assert_eq!(ioctl_int(&tty, TIOCSPGRP, -1).0, -EINVAL as isize);
+        assert_eq!(ioctl_int(&tty, TIOCSPGRP, 0).0, -EPERM as isize);
+
+        // 放弃控制终端后作业控制请求不再可用
+        assert_eq!(tty.ioctl(TIOCNOTTY, 0).unwrap(), 0);
+        assert_eq!(tty.session(), 0);
+        assert_eq!(tty.foreground_pgrp(), 0);
+        assert_eq!(ioctl_int(&tty, TIOCGPGRP, 0).0, -ENOTTY as isize);
+    }
+
+    #[test]
+    fn test_dev_tty_requires_ctty() {
+        init_arch_ops();
+        // Mock 中当前进程没有控制终端
+        assert!(tty_for_device(makedev(chrdev_major::CONSOLE, console_minor::TTY)).is_none());
+    }
+
+    #[test]
+    fn test_steal_controlling_tty() {
+        let tty = tty_with_input(b"");
+        tty.session.store(2, Ordering::Release);
+        assert_eq!(
+            tty.ioctl(TIOCSCTTY, 0).unwrap(),
+            -uapi::errno::EPERM as isize
+        );
+        assert_eq!(tty.ioctl(TIOCSCTTY, 1).unwrap(), 0);
+        assert_eq!(tty.session(), 1);
+    }
+
+    #[test]
+    fn test_noncanonical_vmin() {
+        let tty = tty_with_input(b"");
+        let mut raw = get_termios(&tty);
+        raw.c_lflag &= !(ICANON | ECHO);
+        raw.c_cc[VMIN] = 0;
+        raw.c_cc[VTIME] = 0;
+        set_termios(&tty, TCSETS, raw);
+
+        // VMIN = 0, VTIME = 0：无数据时立即返回 0
+        let mut buf = [0u8; 8];
+        assert_eq!(tty.read(&mut buf, false).unwrap(), 0);
+
+        // VMIN = 3：数据足够时一次读满
+        raw.c_cc[VMIN] = 3;
+        set_termios(&tty, TCSETS, raw);
+        tty.receive(b"abcd");
+        assert_eq!(tty.read(&mut buf, false).unwrap(), 4);
+        assert_eq!(&buf[..4], b"abcd");
+
+        // 非阻塞且不足 VMIN 时返回已有数据
+        tty.receive(b"z");
+        assert_eq!(tty.read(&mut buf, true).unwrap(), 1);
+        assert!(matches!(tty.read(&mut buf, true), Err(FsError::WouldBlock)));
+    }
+
+    #[test]
+    fn test_ixon_holds_echo_until_start() {
+        let (tty, driver) = tty_with_driver(b"");
+        tty.receive(b"\x13ab");
+        assert!(tty.is_stopped());
+        // 暂停期间回显被积压，TIOCOUTQ 报告其长度
+        assert!(driver.take_output().is_empty());
+        assert_eq!(ioctl_int(&tty, TIOCOUTQ, -1), (0, 2));
+
+        tty.receive(b"\x11");
+        assert!(!tty.is_stopped());
+        assert_eq!(driver.take_output(), b"ab");
+        assert_eq!(ioctl_int(&tty, TIOCOUTQ, -1), (0, 0));
+
+        // TCOFLUSH 丢弃积压的回显
+        tty.receive(b"\x13c");
+        assert_eq!(tty.ioctl(TCFLSH, TCOFLUSH).unwrap(), 0);
+        tty.receive(b"\x11");
+        assert!(driver.take_output().is_empty());
+    }
+
+    #[test]
+    fn test_tcxonc() {
+        let (tty, driver) = tty_with_driver(b"");
+        assert_eq!(tty.ioctl(TCXONC, TCOOFF).unwrap(), 0);
+        assert!(tty.is_stopped());
+        assert_eq!(tty.ioctl(TCXONC, TCOON).unwrap(), 0);
+        assert!(!tty.is_stopped());
+        assert_eq!(tty.write(b"x").unwrap(), 1);
+
+        assert_eq!(tty.ioctl(TCXONC, TCIOFF).unwrap(), 0);
+        assert_eq!(tty.ioctl(TCXONC, TCION).unwrap(), 0);
+        assert_eq!(driver.take_output(), b"x\x13\x11");
+        assert_eq!(tty.ioctl(TCXONC, 9).unwrap(), -uapi::errno::EINVAL as isize);
+    }
+
+    #[test]
+    fn test_ixoff_throttles_device() {
+        let (tty, driver) = tty_with_driver(b"");
+        let mut raw = get_termios(&tty);
+        raw.c_lflag &= !(ICANON | ECHO);
+        raw.c_iflag |= IXOFF;
+        set_termios(&tty, TCSETS, raw);
+
+        tty.receive(&[b'a'; THROTTLE_HIGH]);
+        assert_eq!(driver.take_output(), b"\x13");
+        // 已发送过 STOP，不重复发送
+        tty.receive(b"b");
+        assert!(driver.take_output().is_empty());
+
+        let mut buf = [0u8; N_TTY_BUF_SIZE];
+        assert_eq!(tty.read(&mut buf, true).unwrap(), THROTTLE_HIGH + 1);
+        assert_eq!(driver.take_output(), b"\x11");
+    }
+}
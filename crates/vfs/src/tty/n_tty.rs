@@ -0,0 +1,545 @@
+//! N_TTY 行规程
+//!
+//! 对应 Linux `drivers/tty/n_tty.c` 的核心语义：
+//!
+//! - 输入映射：ISTRIP / IGNCR / ICRNL / INLCR
+//! - 软件流控：IXON 下 STOP/START（^S/^Q）暂停/恢复输出，IXANY 下任意字符恢复输出
+//! - 信号：ISIG 下 INTR/QUIT/SUSP 产生 SIGINT/SIGQUIT/SIGTSTP，并按 NOFLSH 清空输入
+//! - 规范模式（ICANON）：ERASE / KILL / WERASE / EOF / EOL / LNEXT / REPRINT 行编辑，
+//!   读操作每次最多返回一行
+//! - 回显：ECHO / ECHOE / ECHOK / ECHOKE / ECHONL / ECHOCTL
+//! - 输出处理：OPOST 下的 ONLCR / OCRNL / OLCUC
+//!
+//! 行规程本身不持有 termios，也不直接访问设备：调用方传入当前 termios，
+//! 并负责把回显字节写回设备、把产生的信号投递给进程组。
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use uapi::ioctl::*;
+use uapi::signal::{NUM_SIGINT, NUM_SIGQUIT, NUM_SIGTSTP};
+
+/// 输入缓冲区上限（与 Linux `N_TTY_BUF_SIZE` 一致）
+pub const N_TTY_BUF_SIZE: usize = 4096;
+
+/// 制表位宽度
+const TAB_WIDTH: usize = 8;
+
+/// N_TTY 行规程状态
+pub struct NTty {
+    /// 规范模式下正在编辑的行
+    line: Vec<u8>,
+    /// 已可被读取的数据
+    read_buf: VecDeque<u8>,
+    /// 规范模式下 `read_buf` 中各完整行的剩余长度（EOF 产生长度为 0 的行）
+    line_lens: VecDeque<usize>,
+    /// 上一个字符是 LNEXT，下一个字符按字面输入
+    lnext: bool,
+    /// 输出被 STOP 字符（或 TCOOFF）暂停
+    stopped: bool,
+}
+
+impl NTty {
+    /// 创建空的行规程状态
+    pub const fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            read_buf: VecDeque::new(),
+            line_lens: VecDeque::new(),
+            lnext: false,
+            stopped: false,
+        }
+    }
+
+    /// 输出是否被暂停
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// 暂停或恢复输出（TCOOFF / TCOON）
+    pub fn set_stopped(&mut self, stopped: bool) {
+        self.stopped = stopped;
+    }
+
+    /// 处理一个输入字节
+    ///
+    /// 回显内容追加到 `echo`（尚未经过输出处理）。
+    ///
+    /// # 返回值
+    /// 需要投递给前台进程组的信号编号
+    pub fn receive_char(&mut self, ch: u8, termios: &Termios, echo: &mut Vec<u8>) -> Option<usize> {
+        let lflag = termios.c_lflag;
+        let iflag = termios.c_iflag;
+
+        if self.lnext {
+            self.lnext = false;
+            if lflag & ECHO != 0 {
+                // 擦除 LNEXT 回显的 "^\b"
+                echo.push(b'\x08');
+            }
+            self.put_char(ch, termios, echo);
+            return None;
+        }
+
+        let mut ch = ch;
+        if iflag & ISTRIP != 0 {
+            ch &= 0x7f;
+        }
+        if ch == b'\r' {
+            if iflag & IGNCR != 0 {
+                return None;
+            }
+            if iflag & ICRNL != 0 {
+                ch = b'\n';
+            }
+        } else if ch == b'\n' && iflag & INLCR != 0 {
+            ch = b'\r';
+        }
+
+        if iflag & IXON != 0 {
+            // STOP 与 START 相同时按切换处理
+            if is_cc(termios, VSTOP, ch) && !(self.stopped && is_cc(termios, VSTART, ch)) {
+                self.stopped = true;
+                return None;
+            }
+            if is_cc(termios, VSTART, ch) {
+                self.stopped = false;
+                return None;
+            }
+            if iflag & IXANY != 0 {
+                self.stopped = false;
+            }
+        }
+
+        if lflag & ISIG != 0 {
+            let sig = if is_cc(termios, VINTR, ch) {
+                Some(NUM_SIGINT)
+            } else if is_cc(termios, VQUIT, ch) {
+                Some(NUM_SIGQUIT)
+            } else if is_cc(termios, VSUSP, ch) {
+                Some(NUM_SIGTSTP)
+            } else {
+                None
+            };
+            if let Some(sig) = sig {
+                // 与 Linux 相同：产生信号时恢复被暂停的输出
+                self.stopped = false;
+                if lflag & NOFLSH == 0 {
+                    self.flush();
+                }
+                if lflag & ECHO != 0 {
+                    echo_char(ch, termios, echo);
+                }
+                return Some(sig);
+            }
+        }
+
+        if lflag & ICANON == 0 {
+            if self.read_buf.len() < N_TTY_BUF_SIZE {
+                self.read_buf.push_back(ch);
+                if lflag & ECHO != 0 {
+                    echo_char(ch, termios, echo);
+                }
+            }
+            return None;
+        }
+
+        let iexten = lflag & IEXTEN != 0;
+        if iexten && is_cc(termios, VLNEXT, ch) {
+            self.lnext = true;
+            if lflag & ECHO != 0 {
+                echo.extend_from_slice(b"^\x08");
+            }
+        } else if is_cc(termios, VERASE, ch) {
+            self.erase(termios, echo, EraseKind::Char);
+        } else if iexten && is_cc(termios, VWERASE, ch) {
+            self.erase(termios, echo, EraseKind::Word);
+        } else if is_cc(termios, VKILL, ch) {
+            self.erase(termios, echo, EraseKind::Line);
+        } else if is_cc(termios, VEOF, ch) {
+            self.commit_line();
+        } else if iexten && is_cc(termios, VREPRINT, ch) {
+            if lflag & ECHO != 0 {
+                echo_char(ch, termios, echo);
+                echo.push(b'\n');
+                for &c in &self.line {
+                    echo_char(c, termios, echo);
+                }
+            }
+        } else if ch == b'\n' || is_cc(termios, VEOL, ch) || (iexten && is_cc(termios, VEOL2, ch)) {
+            if lflag & ECHO != 0 || (ch == b'\n' && lflag & ECHONL != 0) {
+                echo_char(ch, termios, echo);
+            }
+            self.line.push(ch);
+            self.commit_line();
+        } else {
+            self.put_char(ch, termios, echo);
+        }
+        None
+    }
+
+    /// 普通字符进入当前行（规范模式）或读缓冲区（非规范模式）
+    fn put_char(&mut self, ch: u8, termios: &Termios, echo: &mut Vec<u8>) {
+        if termios.c_lflag & ICANON == 0 {
+            if self.read_buf.len() < N_TTY_BUF_SIZE {
+                self.read_buf.push_back(ch);
+            }
+        } else if self.line.len() < N_TTY_BUF_SIZE - 1 {
+            // 保留一个字节给行结束符
+            self.line.push(ch);
+        } else {
+            return;
+        }
+        if termios.c_lflag & ECHO != 0 {
+            echo_char(ch, termios, echo);
+        }
+    }
+
+    /// 当前行（可能为空）成为可读的一行
+    fn commit_line(&mut self) {
+        let len = self.line.len();
+        self.read_buf.extend(self.line.drain(..));
+        self.line_lens.push_back(len);
+    }
+
+    fn erase(&mut self, termios: &Termios, echo: &mut Vec<u8>, kind: EraseKind) {
+        let lflag = termios.c_lflag;
+        if self.line.is_empty() {
+            return;
+        }
+
+        let keep = match kind {
+            EraseKind::Char => {
+                let mut n = self.line.len() - 1;
+                if termios.c_iflag & IUTF8 != 0 {
+                    // 删除完整的 UTF-8 字符：跳过续字节
+                    while n > 0 && self.line[n] & 0xc0 == 0x80 {
+                        n -= 1;
+                    }
+                }
+                n
+            }
+            EraseKind::Word => {
+                let trimmed = self
+                    .line
+                    .iter()
+                    .rposition(|c| !c.is_ascii_whitespace())
+                    .map_or(0, |i| i + 1);
+                self.line[..trimmed]
+                    .iter()
+                    .rposition(|c| c.is_ascii_whitespace())
+                    .map_or(0, |i| i + 1)
+            }
+            EraseKind::Line => 0,
+        };
+
+        if lflag & ECHO != 0 {
+            let visual = match kind {
+                EraseKind::Line => lflag & ECHOKE != 0,
+                _ => lflag & ECHOE != 0,
+            };
+            if visual {
+                let ctl = lflag & ECHOCTL != 0;
+                let before = display_width(&self.line, ctl);
+                let after = display_width(&self.line[..keep], ctl);
+                let tab_erased = self.line[keep..].contains(&b'\t');
+                for _ in after..before {
+                    // 制表符占据的位置无法用空格精确还原，只回退光标
+                    echo.extend_from_slice(if tab_erased { b"\x08" } else { b"\x08 \x08" });
+                }
+            } else {
+                let cc = match kind {
+                    EraseKind::Char => VERASE,
+                    EraseKind::Word => VWERASE,
+                    EraseKind::Line => VKILL,
+                };
+                echo_char(termios.c_cc[cc], termios, echo);
+                if kind == EraseKind::Line && lflag & ECHOK != 0 {
+                    echo.push(b'\n');
+                }
+            }
+        }
+        self.line.truncate(keep);
+    }
+
+    /// 读取已就绪的数据
+    ///
+    /// # 返回值
+    /// - `None`：没有可读数据（规范模式下表示还没有完整的行）
+    /// - `Some(0)`：规范模式下读到 EOF
+    /// - `Some(n)`：读取了 n 字节；规范模式下不会跨越行边界
+    pub fn read(&mut self, buf: &mut [u8], termios: &Termios) -> Option<usize> {
+        if termios.c_lflag & ICANON != 0 {
+            let line_len = self.line_lens.front_mut()?;
+            let n = (*line_len).min(buf.len());
+            for (dst, src) in buf.iter_mut().zip(self.read_buf.drain(..n)) {
+                *dst = src;
+            }
+            *line_len -= n;
+            if *line_len == 0 {
+                self.line_lens.pop_front();
+            }
+            Some(n)
+        } else {
+            if self.read_buf.is_empty() {
+                return None;
+            }
+            let n = self.read_buf.len().min(buf.len());
+            for (dst, src) in buf.iter_mut().zip(self.read_buf.drain(..n)) {
+                *dst = src;
+            }
+            Some(n)
+        }
+    }
+
+    /// 是否有可读数据（规范模式下需要有完整的行）
+    pub fn readable(&self, termios: &Termios) -> bool {
+        if termios.c_lflag & ICANON != 0 {
+            !self.line_lens.is_empty()
+        } else {
+            !self.read_buf.is_empty()
+        }
+    }
+
+    /// 可读字节数（FIONREAD）
+    pub fn available(&self, termios: &Termios) -> usize {
+        if termios.c_lflag & ICANON != 0 {
+            self.line_lens.iter().sum()
+        } else {
+            self.read_buf.len()
+        }
+    }
+
+    /// 已缓冲的输入字节数（含正在编辑的行，用于 IXOFF 输入流控）
+    pub fn buffered(&self) -> usize {
+        self.read_buf.len() + self.line.len()
+    }
+
+    /// 丢弃全部输入（含正在编辑的行）
+    pub fn flush(&mut self) {
+        self.line.clear();
+        self.read_buf.clear();
+        self.line_lens.clear();
+        self.lnext = false;
+    }
+
+    /// termios 变化时调整缓冲区
+    ///
+    /// 离开规范模式时，已完成的行与正在编辑的行都变为可直接读取的数据；
+    /// 进入规范模式时，尚未读取的数据并入下一行。
+    pub fn set_termios(&mut self, old: &Termios, new: &Termios) {
+        let was_canon = old.c_lflag & ICANON != 0;
+        let is_canon = new.c_lflag & ICANON != 0;
+        if was_canon && !is_canon {
+            self.read_buf.extend(self.line.drain(..));
+            self.line_lens.clear();
+            self.lnext = false;
+        } else if !was_canon && is_canon {
+            let mut line: Vec<u8> = self.read_buf.drain(..).collect();
+            line.append(&mut self.line);
+            self.line = line;
+        }
+    }
+}
+
+impl Default for NTty {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EraseKind {
+    Char,
+    Word,
+    Line,
+}
+
+/// 特殊字符匹配（0 表示禁用）
+#[inline]
+fn is_cc(termios: &Termios, idx: usize, ch: u8) -> bool {
+    let cc = termios.c_cc[idx];
+    cc != 0 && cc == ch
+}
+
+/// 是否按 `^X` 形式回显
+#[inline]
+fn is_echoctl(ch: u8, ctl: bool) -> bool {
+    ctl && (ch < 0x20 || ch == 0x7f) && ch != b'\n' && ch != b'\t'
+}
+
+/// 回显一个字符（ECHOCTL 下控制字符显示为 `^X`）
+fn echo_char(ch: u8, termios: &Termios, echo: &mut Vec<u8>) {
+    if is_echoctl(ch, termios.c_lflag & ECHOCTL != 0) {
+        echo.push(b'^');
+        echo.push(ch ^ 0x40);
+    } else {
+        echo.push(ch);
+    }
+}
+
+/// 一行回显后占据的列数（从行首开始计算）
+fn display_width(line: &[u8], ctl: bool) -> usize {
+    line.iter().fold(0, |col, &c| match c {
+        b'\t' => (col / TAB_WIDTH + 1) * TAB_WIDTH,
+        c if is_echoctl(c, ctl) => col + 2,
+        // UTF-8 续字节不占列
+        c if c & 0xc0 == 0x80 => col,
+        _ => col + 1,
+    })
+}
+
+/// 输出处理（OPOST）
+///
+/// 未设置 OPOST 时原样返回。
+pub fn process_output(data: &[u8], termios: &Termios) -> Vec<u8> {
+    let oflag = termios.c_oflag;
+    if oflag & OPOST == 0 {
+        return data.to_vec();
+    }
+    let mut out = Vec::with_capacity(data.len() + data.len() / 8);
+    for &ch in data {
+        match ch {
+            b'\n' if oflag & ONLCR != 0 => out.extend_from_slice(b"\r\n"),
+            b'\r' if oflag & OCRNL != 0 => out.push(b'\n'),
+            c if oflag & OLCUC != 0 => out.push(c.to_ascii_uppercase()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(ld: &mut NTty, termios: &Termios, input: &[u8]) -> (Vec<u8>, Vec<usize>) {
+        let mut echo = Vec::new();
+        let mut sigs = Vec::new();
+        for &b in input {
+            if let Some(sig) = ld.receive_char(b, termios, &mut echo) {
+                sigs.push(sig);
+            }
+        }
+        (echo, sigs)
+    }
+
+    fn read_all(ld: &mut NTty, termios: &Termios) -> Option<Vec<u8>> {
+        let mut buf = [0u8; 64];
+        ld.read(&mut buf, termios).map(|n| buf[..n].to_vec())
+    }
+
+    #[test]
+    fn test_canonical_line_editing() {
+        let t = Termios::DEFAULT;
+        let mut ld = NTty::new();
+
+        // "helo" DEL "lo wrld" ^W "world"：行未结束前不可读
+        let (mut echo, _) = feed(&mut ld, &t, b"helo\x7flo wrld\x17world");
+        assert!(!ld.readable(&t));
+        assert_eq!(read_all(&mut ld, &t), None);
+
+        // CR 经 ICRNL 变为 NL，结束该行
+        echo.extend(feed(&mut ld, &t, b"\r").0);
+        assert_eq!(read_all(&mut ld, &t).unwrap(), b"hello world\n");
+        // ECHOE 擦除回显为 "\b \b"
+        assert!(echo.windows(3).any(|w| w == b"\x08 \x08"));
+        assert!(echo.ends_with(b"world\n"));
+
+        // KILL 清空整行；只有完整的行才可读，且每次最多读一行
+        feed(&mut ld, &t, b"garbage\x15ab\ncd\n");
+        assert_eq!(ld.available(&t), 6);
+        let mut small = [0u8; 2];
+        assert_eq!(ld.read(&mut small, &t), Some(2));
+        assert_eq!(&small, b"ab");
+        assert_eq!(read_all(&mut ld, &t).unwrap(), b"\n");
+        assert_eq!(read_all(&mut ld, &t).unwrap(), b"cd\n");
+    }
+
+    #[test]
+    fn test_eof_and_lnext() {
+        let t = Termios::DEFAULT;
+        let mut ld = NTty::new();
+
+        // 非空行上的 EOF 提交该行（不含 EOF 字符），空行上的 EOF 读到 0
+        feed(&mut ld, &t, b"abc\x04\x04");
+        assert_eq!(read_all(&mut ld, &t).unwrap(), b"abc");
+        assert_eq!(read_all(&mut ld, &t).unwrap(), b"");
+        assert_eq!(read_all(&mut ld, &t), None);
+
+        // LNEXT 使下一个字符失去特殊含义
+        feed(&mut ld, &t, b"\x16\x7f\x16\x03\n");
+        assert_eq!(read_all(&mut ld, &t).unwrap(), b"\x7f\x03\n");
+    }
+
+    #[test]
+    fn test_isig_flushes_and_echoes() {
+        let t = Termios::DEFAULT;
+        let mut ld = NTty::new();
+
+        let (echo, sigs) = feed(&mut ld, &t, b"partial\x03");
+        assert_eq!(sigs, [NUM_SIGINT]);
+        assert!(echo.ends_with(b"^C"));
+        assert_eq!(ld.available(&t), 0);
+
+        let (_, sigs) = feed(&mut ld, &t, b"\x1c\x1a");
+        assert_eq!(sigs, [NUM_SIGQUIT, NUM_SIGTSTP]);
+
+        // 关闭 ISIG 后 ^C 是普通字符
+        let mut raw = t;
+        raw.c_lflag &= !(ISIG | ICANON | ECHO);
+        let (echo, sigs) = feed(&mut ld, &raw, b"\x03x");
+        assert!(sigs.is_empty() && echo.is_empty());
+        assert_eq!(read_all(&mut ld, &raw).unwrap(), b"\x03x");
+    }
+
+    #[test]
+    fn test_mode_switch_and_output() {
+        let t = Termios::DEFAULT;
+        let mut raw = t;
+        raw.c_lflag &= !ICANON;
+        let mut ld = NTty::new();
+
+        feed(&mut ld, &t, b"ab");
+        ld.set_termios(&t, &raw);
+        assert_eq!(read_all(&mut ld, &raw).unwrap(), b"ab");
+
+        assert_eq!(process_output(b"a\nb", &t), b"a\r\nb");
+        let mut no_post = t;
+        no_post.c_oflag &= !OPOST;
+        assert_eq!(process_output(b"a\nb", &no_post), b"a\nb");
+    }
+
+    #[test]
+    fn test_ixon_flow_control() {
+        let t = Termios::DEFAULT;
+        let mut ld = NTty::new();
+
+        // STOP / START 被消费，不进入输入也不回显
+        let (echo, _) = feed(&mut ld, &t, b"a\x13");
+        assert!(ld.is_stopped());
+        assert_eq!(echo, b"a");
+        feed(&mut ld, &t, b"b");
+        assert!(ld.is_stopped());
+        feed(&mut ld, &t, b"\x11\n");
+        assert!(!ld.is_stopped());
+        assert_eq!(read_all(&mut ld, &t).unwrap(), b"ab\n");
+
+        // IXANY：任意字符恢复输出并照常输入
+        let mut any = t;
+        any.c_iflag |= IXANY;
+        feed(&mut ld, &any, b"\x13c");
+        assert!(!ld.is_stopped());
+
+        // 信号字符同样恢复输出
+        feed(&mut ld, &t, b"\x13\x03");
+        assert!(!ld.is_stopped());
+
+        // 未设置 IXON 时 ^S 是普通字符
+        let mut no_ixon = t;
+        no_ixon.c_iflag &= !IXON;
+        ld.flush();
+        feed(&mut ld, &no_ixon, b"\x13\n");
+        assert!(!ld.is_stopped());
+        assert_eq!(read_all(&mut ld, &no_ixon).unwrap(), b"\x13\n");
+    }
+}
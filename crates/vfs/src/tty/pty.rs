@@ -0,0 +1,453 @@
+//! 伪终端（UNIX98 PTY）
+//!
+//! 打开 `/dev/ptmx` 分配一对主从设备：主设备由 [`PtyMasterFile`] 表示，
+//! 从设备是一个普通的 [`Tty`]（`/dev/pts/N`，major 136），二者之间的数据流为：
+//!
+//! ```text
+//!   主设备 write ──► 从设备行规程（回显、规范模式、信号） ──► 从设备 read
+//!   从设备 write ──► OPOST 输出处理 ──► 输出队列 ──► 主设备 read
+//! ```
+//!
+//! 语义与 Linux 一致：
+//! - 新分配的 PTY 处于锁定状态，`unlockpt()`（TIOCSPTLCK）之前打开从设备返回 EIO；
+//! - 主设备关闭后从设备被挂断：读返回 EOF，写返回 EIO，且不能再被打开；
+//! - 从设备被打开过且所有打开实例都已关闭后，主设备读返回 EIO。
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use sync::SpinLock;
+use uapi::epoll::EpollEvents;
+
+use super::{Tty, read_user, write_user};
+use crate::poll::{PollQueue, PollWaker};
+use crate::{
+    CharDriver, Dentry, File, FsError, Inode, InodeMetadata, OpenFlags, release_ofd_locks_on_drop,
+    vfs_ops,
+};
+
+/// 最多同时存在的 PTY 数量（Linux `kernel.pty.max` 默认值）
+pub const PTY_MAX: u32 = 4096;
+
+/// 主从设备共享的状态
+struct PtyLink {
+    /// 从设备输出、等待主设备读取的数据
+    output: SpinLock<VecDeque<u8>>,
+    /// 从设备是否被锁定
+    locked: AtomicBool,
+    /// 从设备当前的打开实例数
+    slave_opens: AtomicUsize,
+    /// 从设备的最后一个打开实例已关闭
+    slave_closed: AtomicBool,
+    /// 主设备已关闭
+    master_closed: AtomicBool,
+    /// 主设备的 poll/epoll 等待队列
+    poll_queue: PollQueue,
+}
+
+impl PtyLink {
+    /// 主设备读取者的等待通道
+    fn wait_chan(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// 唤醒主设备的读取者与 poll/epoll 的等待者
+    fn wake_master(&self) {
+        vfs_ops().wake_event(self.wait_chan());
+        self.poll_queue.wake(EpollEvents::IN);
+    }
+}
+
+/// 从设备驱动：输出进入主设备的读队列，输入由主设备写入行规程
+struct PtySlaveDriver(Arc<PtyLink>);
+
+impl CharDriver for PtySlaveDriver {
+    fn try_read(&self) -> Option<u8> {
+        None
+    }
+
+    fn write(&self, data: &[u8]) {
+        if !self.0.master_closed.load(Ordering::Acquire) {
+            self.0.output.lock().extend(data.iter().copied());
+            self.0.wake_master();
+        }
+    }
+
+    fn ioctl(&self, _request: u32, _arg: usize) -> Result<isize, i32> {
+        Err(uapi::errno::ENOTTY)
+    }
+
+    fn open(&self) -> Result<(), FsError> {
+        if self.0.master_closed.load(Ordering::Acquire) || self.0.locked.load(Ordering::Acquire) {
+            return Err(FsError::IoError);
+        }
+        self.0.slave_opens.fetch_add(1, Ordering::AcqRel);
+        self.0.slave_closed.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    fn release(&self) {
+        if self.0.slave_opens.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.slave_closed.store(true, Ordering::Release);
+            self.0.wake_master();
+        }
+    }
+
+    fn pushes_input(&self) -> bool {
+        // 主设备写入直接进入从设备的行规程
+        true
+    }
+}
+
+/// 一对伪终端
+pub struct Pty {
+    /// PTY 编号（`/dev/pts/N` 的 N）
+    index: u32,
+    /// 从设备终端
+    slave: Arc<Tty>,
+    /// 主从共享状态
+    link: Arc<PtyLink>,
+}
+
+/// 已分配的 PTY（按编号）；主设备关闭时移除
+static PTYS: SpinLock<BTreeMap<u32, Arc<Pty>>> = SpinLock::new(BTreeMap::new());
+
+impl Pty {
+    /// 分配一对新的 PTY，使用最小的空闲编号
+    pub fn alloc() -> Result<Arc<Self>, FsError> {
+        let mut ptys = PTYS.lock();
+        let index = (0..PTY_MAX)
+            .find(|i| !ptys.contains_key(i))
+            .ok_or(FsError::NoSpace)?;
+        let link = Arc::new(PtyLink {
+            output: SpinLock::new(VecDeque::new()),
+            locked: AtomicBool::new(true),
+            slave_opens: AtomicUsize::new(0),
+            slave_closed: AtomicBool::new(false),
+            master_closed: AtomicBool::new(false),
+            poll_queue: PollQueue::new(),
+        });
+        let slave = Tty::new(
+            format!("pts/{}", index),
+            Arc::new(PtySlaveDriver(link.clone())),
+        );
+        let pty = Arc::new(Self { index, slave, link });
+        ptys.insert(index, pty.clone());
+        Ok(pty)
+    }
+
+    /// PTY 编号
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// 从设备终端
+    pub fn slave(&self) -> &Arc<Tty> {
+        &self.slave
+    }
+
+    /// 从设备是否被锁定
+    pub fn is_locked(&self) -> bool {
+        self.link.locked.load(Ordering::Acquire)
+    }
+
+    /// 读取从设备的输出，没有数据时睡眠到从设备写入或关闭
+    pub fn master_read(&self, buf: &mut [u8], nonblock: bool) -> Result<usize, FsError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            {
+                let mut output = self.link.output.lock();
+                if !output.is_empty() {
+                    let n = output.len().min(buf.len());
+                    for (dst, src) in buf.iter_mut().zip(output.drain(..n)) {
+                        *dst = src;
+                    }
+                    return Ok(n);
+                }
+            }
+            if self.link.slave_closed.load(Ordering::Acquire) {
+                return Err(FsError::IoError);
+            }
+            if nonblock {
+                return Err(FsError::WouldBlock);
+            }
+            let ready = || self.master_readable();
+            if !vfs_ops().wait_event(self.link.wait_chan(), None, &ready) {
+                return Err(FsError::Interrupted);
+            }
+        }
+    }
+
+    /// 向从设备输入数据（经过从设备的行规程）
+    pub fn master_write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        self.slave.receive(buf);
+        Ok(buf.len())
+    }
+
+    /// 查询主设备的就绪事件（poll/epoll）
+    ///
+    /// 主设备的写入直接进入从设备的行规程，总是可写；从设备关闭后报告挂断。
+    pub fn master_poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            self.link.poll_queue.register(waker);
+        }
+        let mut events = EpollEvents::OUT | EpollEvents::WRNORM;
+        if !self.link.output.lock().is_empty() {
+            events |= EpollEvents::IN | EpollEvents::RDNORM;
+        }
+        if self.link.slave_closed.load(Ordering::Acquire) {
+            events |= EpollEvents::HUP;
+        }
+        events
+    }
+
+    /// 主设备是否有可读数据（或已可返回 EIO）
+    pub fn master_readable(&self) -> bool {
+        !self.link.output.lock().is_empty() || self.link.slave_closed.load(Ordering::Acquire)
+    }
+
+    /// 主设备 ioctl
+    ///
+    /// 处理 PTY 专有请求，其余（termios、窗口大小等）作用于从设备终端。
+    pub fn master_ioctl(&self, request: u32, arg: usize) -> Result<isize, FsError> {
+        use uapi::errno::EINVAL;
+        use uapi::ioctl::{FIONREAD, TIOCGPTLCK, TIOCGPTN, TIOCSPTLCK};
+
+        match request {
+            TIOCGPTN | TIOCSPTLCK | TIOCGPTLCK | FIONREAD if arg == 0 => Ok(-EINVAL as isize),
+            FIONREAD => {
+                write_user(arg, self.link.output.lock().len() as i32);
+                Ok(0)
+            }
+            TIOCGPTN => {
+                write_user(arg, self.index);
+                Ok(0)
+            }
+            TIOCSPTLCK => {
+                let lock: i32 = read_user(arg);
+                self.link.locked.store(lock != 0, Ordering::Release);
+                Ok(0)
+            }
+            TIOCGPTLCK => {
+                write_user(arg, self.is_locked() as i32);
+                Ok(0)
+            }
+            _ => self.slave.ioctl(request, arg),
+        }
+    }
+
+    /// 关闭主设备：挂断从设备并释放编号
+    pub fn close_master(&self) {
+        self.link.master_closed.store(true, Ordering::Release);
+        self.link.output.lock().clear();
+        self.slave.hangup();
+        PTYS.lock().remove(&self.index);
+    }
+}
+
+/// 编号对应的从设备终端
+pub fn pty_slave(index: u32) -> Option<Arc<Tty>> {
+    PTYS.lock().get(&index).map(|pty| pty.slave.clone())
+}
+
+/// 当前已分配的 PTY 编号（升序，供 devpts 列目录）
+pub fn pty_indices() -> Vec<u32> {
+    PTYS.lock().keys().copied().collect()
+}
+
+/// PTY 主设备文件（打开 `/dev/ptmx` 得到）
+pub struct PtyMasterFile {
+    /// 关联的 dentry（`/dev/ptmx`）
+    pub dentry: Arc<Dentry>,
+
+    /// 关联的 inode
+    pub inode: Arc<dyn Inode>,
+
+    /// 打开标志位
+    pub flags: OpenFlags,
+
+    /// 对应的 PTY
+    pty: Arc<Pty>,
+}
+
+impl PtyMasterFile {
+    /// 对应的 PTY
+    pub fn pty(&self) -> &Arc<Pty> {
+        &self.pty
+    }
+}
+
+/// 打开 `/dev/ptmx`：分配新的 PTY 并返回其主设备文件
+pub fn open_ptmx(dentry: Arc<Dentry>, flags: OpenFlags) -> Result<PtyMasterFile, FsError> {
+    let inode = dentry.inode.clone();
+    Ok(PtyMasterFile {
+        dentry,
+        inode,
+        flags,
+        pty: Pty::alloc()?,
+    })
+}
+
+impl File for PtyMasterFile {
+    fn readable(&self) -> bool {
+        self.flags.readable()
+    }
+
+    fn writable(&self) -> bool {
+        self.flags.writable()
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
+        if !self.readable() {
+            return Err(FsError::PermissionDenied);
+        }
+        self.pty
+            .master_read(buf, self.flags.contains(OpenFlags::O_NONBLOCK))
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        if !self.writable() {
+            return Err(FsError::PermissionDenied);
+        }
+        self.pty.master_write(buf)
+    }
+
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        self.inode.metadata()
+    }
+
+    fn flags(&self) -> OpenFlags {
+        self.flags
+    }
+
+    fn inode(&self) -> Result<Arc<dyn Inode>, FsError> {
+        Ok(self.inode.clone())
+    }
+
+    fn dentry(&self) -> Result<Arc<Dentry>, FsError> {
+        Ok(self.dentry.clone())
+    }
+
+    fn ioctl(&self, request: u32, arg: usize) -> Result<isize, FsError> {
+        self.pty.master_ioctl(request, arg)
+    }
+
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        self.pty.master_poll(waker)
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+impl Drop for PtyMasterFile {
+    fn drop(&mut self) {
+        release_ofd_locks_on_drop(self);
+        self.pty.close_master();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sync::mock::init_arch_ops;
+    use uapi::ioctl::{TIOCGPTLCK, TIOCGPTN, TIOCSPTLCK};
+
+    fn master_read_all(pty: &Pty) -> Vec<u8> {
+        let mut buf = [0u8; 64];
+        match pty.master_read(&mut buf, true) {
+            Ok(n) => buf[..n].to_vec(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn unlock(pty: &Pty) {
+        let mut lock = 0i32;
+        assert_eq!(
+            pty.master_ioctl(TIOCSPTLCK, &mut lock as *mut i32 as usize)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_locked_until_unlockpt() {
+        init_arch_ops();
+        let pty = Pty::alloc().unwrap();
+        let mut n = u32::MAX;
+        assert_eq!(
+            pty.master_ioctl(TIOCGPTN, &mut n as *mut u32 as usize)
+                .unwrap(),
+            0
+        );
+        assert_eq!(n, pty.index());
+        assert!(Arc::ptr_eq(&pty_slave(n).unwrap(), pty.slave()));
+
+        let mut locked = 0i32;
+        pty.master_ioctl(TIOCGPTLCK, &mut locked as *mut i32 as usize)
+            .unwrap();
+        assert_eq!(locked, 1);
+        assert!(matches!(pty.slave().open(), Err(FsError::IoError)));
+
+        unlock(&pty);
+        assert!(pty.slave().open().is_ok());
+        pty.slave().release();
+        pty.close_master();
+    }
+
+    #[test]
+    fn test_data_flow_both_directions() {
+        init_arch_ops();
+        let pty = Pty::alloc().unwrap();
+        unlock(&pty);
+        let slave = pty.slave().clone();
+        slave.open().unwrap();
+
+        // 主设备输入经过从设备行规程：ICRNL + 回显
+        pty.master_write(b"hi\r").unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(slave.read(&mut buf, true).unwrap(), 3);
+        assert_eq!(&buf[..3], b"hi\n");
+        assert_eq!(master_read_all(&pty), b"hi\r\n");
+
+        // 从设备输出经过 OPOST/ONLCR
+        slave.write(b"ok\n").unwrap();
+        assert_eq!(master_read_all(&pty), b"ok\r\n");
+        assert!(matches!(
+            pty.master_read(&mut buf, true),
+            Err(FsError::WouldBlock)
+        ));
+
+        // 从设备全部关闭后主设备读返回 EIO
+        slave.release();
+        assert!(matches!(
+            pty.master_read(&mut buf, true),
+            Err(FsError::IoError)
+        ));
+        pty.close_master();
+    }
+
+    #[test]
+    fn test_master_close_hangs_up_slave() {
+        init_arch_ops();
+        let pty = Pty::alloc().unwrap();
+        unlock(&pty);
+        let slave = pty.slave().clone();
+        slave.open().unwrap();
+
+        pty.close_master();
+        assert!(pty_slave(pty.index()).is_none());
+        assert!(!pty_indices().contains(&pty.index()));
+
+        let mut buf = [0u8; 8];
+        assert_eq!(slave.read(&mut buf, false).unwrap(), 0);
+        assert!(matches!(slave.write(b"x"), Err(FsError::IoError)));
+        assert!(matches!(slave.open(), Err(FsError::IoError)));
+        slave.release();
+    }
+}
@@ -0,0 +1,173 @@
+//! 扩展属性（xattr）
+//!
+//! 扩展属性是附加在 inode 上的“名字 -> 值”对，名字带命名空间前缀（`user.`、`trusted.`、
+//! `security.`、`system.`）。文件系统通过 [`Inode`](crate::Inode) 的 `getxattr` /
+//! `setxattr` / `listxattr` / `removexattr` 提供存储，方法收到的是带前缀的完整名字；
+//! 命名空间的访问控制由系统调用层负责。内存文件系统可以直接使用 [`XattrMap`]。
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use sync::SpinLock;
+use uapi::xattr::{
+    XATTR_CREATE, XATTR_REPLACE, XATTR_SECURITY_PREFIX, XATTR_SYSTEM_PREFIX, XATTR_TRUSTED_PREFIX,
+    XATTR_USER_PREFIX,
+};
+
+use crate::FsError;
+
+bitflags::bitflags! {
+    /// setxattr 标志
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct XattrFlags: u32 {
+        /// 属性必须不存在
+        const CREATE = XATTR_CREATE;
+        /// 属性必须已存在
+        const REPLACE = XATTR_REPLACE;
+    }
+}
+
+/// 扩展属性的命名空间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XattrNamespace {
+    /// `user.`：受文件权限控制，只能设置在普通文件和目录上
+    User,
+    /// `trusted.`：需要 CAP_SYS_ADMIN
+    Trusted,
+    /// `security.`：安全模块使用
+    Security,
+    /// `system.`：内核自身使用（如 POSIX ACL）
+    System,
+}
+
+impl XattrNamespace {
+    /// 把属性名拆分为命名空间与去掉前缀的名字
+    ///
+    /// 前缀未知或前缀后的名字为空时返回 `None`。
+    pub fn parse(name: &str) -> Option<(Self, &str)> {
+        let namespaces = [
+            (XATTR_USER_PREFIX, Self::User),
+            (XATTR_TRUSTED_PREFIX, Self::Trusted),
+            (XATTR_SECURITY_PREFIX, Self::Security),
+            (XATTR_SYSTEM_PREFIX, Self::System),
+        ];
+        namespaces.into_iter().find_map(|(prefix, ns)| {
+            name.strip_prefix(prefix)
+                .filter(|suffix| !suffix.is_empty())
+                .map(|suffix| (ns, suffix))
+        })
+    }
+
+    /// 命名空间的前缀
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::User => XATTR_USER_PREFIX,
+            Self::Trusted => XATTR_TRUSTED_PREFIX,
+            Self::Security => XATTR_SECURITY_PREFIX,
+            Self::System => XATTR_SYSTEM_PREFIX,
+        }
+    }
+}
+
+/// 按 `flags` 检查属性能否写入，`exists` 为属性当前是否存在
+pub fn check_xattr_flags(exists: bool, flags: XattrFlags) -> Result<(), FsError> {
+    if exists && flags.contains(XattrFlags::CREATE) {
+        return Err(FsError::AlreadyExists);
+    }
+    if !exists && flags.contains(XattrFlags::REPLACE) {
+        return Err(FsError::NoData);
+    }
+    Ok(())
+}
+
+/// 内存中的扩展属性表
+pub struct XattrMap {
+    attrs: SpinLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl XattrMap {
+    /// 创建空表
+    pub const fn new() -> Self {
+        Self {
+            attrs: SpinLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// 读取属性值
+    pub fn get(&self, name: &str) -> Result<Vec<u8>, FsError> {
+        self.attrs.lock().get(name).cloned().ok_or(FsError::NoData)
+    }
+
+    /// 设置属性值
+    pub fn set(&self, name: &str, value: &[u8], flags: XattrFlags) -> Result<(), FsError> {
+        let mut attrs = self.attrs.lock();
+        check_xattr_flags(attrs.contains_key(name), flags)?;
+        attrs.insert(String::from(name), value.to_vec());
+        Ok(())
+    }
+
+    /// 按名字顺序列出全部属性名
+    pub fn list(&self) -> Vec<String> {
+        self.attrs.lock().keys().cloned().collect()
+    }
+
+    /// 删除属性
+    pub fn remove(&self, name: &str) -> Result<(), FsError> {
+        self.attrs
+            .lock()
+            .remove(name)
+            .map(|_| ())
+            .ok_or(FsError::NoData)
+    }
+}
+
+impl Default for XattrMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sync::mock::init_arch_ops;
+
+    #[test]
+    fn test_parse_namespace() {
+        assert_eq!(
+            XattrNamespace::parse("user.mime_type"),
+            Some((XattrNamespace::User, "mime_type"))
+        );
+        assert_eq!(
+            XattrNamespace::parse("system.posix_acl_access"),
+            Some((XattrNamespace::System, "posix_acl_access"))
+        );
+        assert_eq!(XattrNamespace::parse("user."), None);
+        assert_eq!(XattrNamespace::parse("foo.bar"), None);
+        assert_eq!(XattrNamespace::Trusted.prefix(), "trusted.");
+    }
+
+    #[test]
+    fn test_map_create_and_replace() {
+        init_arch_ops();
+        let map = XattrMap::new();
+        assert_eq!(
+            map.set("user.a", b"1", XattrFlags::REPLACE),
+            Err(FsError::NoData)
+        );
+        map.set("user.a", b"1", XattrFlags::CREATE).unwrap();
+        assert_eq!(
+            map.set("user.a", b"2", XattrFlags::CREATE),
+            Err(FsError::AlreadyExists)
+        );
+        map.set("user.a", b"2", XattrFlags::REPLACE).unwrap();
+        map.set("trusted.b", b"", XattrFlags::empty()).unwrap();
+
+        assert_eq!(map.get("user.a").unwrap(), b"2");
+        assert_eq!(map.list(), ["trusted.b", "user.a"]);
+        map.remove("user.a").unwrap();
+        assert_eq!(map.get("user.a"), Err(FsError::NoData));
+        assert_eq!(map.remove("user.a"), Err(FsError::NoData));
+    }
+}
@@ -1,7 +1,7 @@
 use vfs::{
     blkdev_major, chrdev_major,
     dev::{major, makedev, minor},
-    get_blkdev_index,
+    get_blkdev_index, mem_minor,
 };
 
 #[test]
@@ -49,6 +49,15 @@ fn test_chrdev_major_constants() {
     assert_eq!(chrdev_major::TTY, 4);
     assert_eq!(chrdev_major::CONSOLE, 5);
     assert_eq!(chrdev_major::INPUT, 13);
+    assert_eq!(chrdev_major::PTY_SLAVE, 136);
+}
+
+#[test]
+fn test_mem_minor_constants() {
+    assert_eq!(mem_minor::NULL, 3);
+    assert_eq!(mem_minor::ZERO, 5);
+    assert_eq!(mem_minor::RANDOM, 8);
+    assert_eq!(mem_minor::URANDOM, 9);
 }
 
 #[test]
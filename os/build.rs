@@ -12,6 +12,8 @@ fn main() {
     // Make sure Cargo re-runs this build script when that env changes,
     // otherwise EXT4_FS_IMAGE may keep pointing at the dummy image.
     println!("cargo:rerun-if-env-changed=TEST");
+    // LTP 子集（`ltp` feature）：指向 LTP 安装目录时，镜像中会加入 /ltp
+    println!("cargo:rerun-if-env-changed=LTP_DIR");
 
     // 获取环境变量
     let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
@@ -82,7 +84,13 @@ fn main() {
 
         // 检查依赖
         println!("cargo:rerun-if-changed={}", data_dir.display());
-        let dependencies = vec![data_dir.clone()];
+        let mut dependencies = vec![data_dir.clone()];
+        if let Some(ltp_dir) = ltp_dir() {
+            let ltp_support = project_root.join("test-support").join("ltp");
+            println!("cargo:rerun-if-changed={}", ltp_support.display());
+            dependencies.push(ltp_support);
+            dependencies.push(ltp_dir);
+        }
 
         let saved_arch = fs::read_to_string(&arch_stamp).ok();
         let force_rebuild = saved_arch
@@ -250,7 +258,7 @@ fn create_empty_ext4_image(path: &PathBuf, size_mb: usize) {
 }
 
 /// 创建完整的 ext4 镜像 (包含 data/)
-fn create_full_ext4_image(path: &PathBuf, data_dir: &Path, _project_root: &Path) {
+fn create_full_ext4_image(path: &PathBuf, data_dir: &Path, project_root: &Path) {
     const IMG_SIZE_MB: usize = 4096; // 4GB
     const BLOCK_SIZE: usize = 1024 * 1024;
 
@@ -277,6 +285,16 @@ fn create_full_ext4_image(path: &PathBuf, data_dir: &Path, _project_root: &Path)
         );
     }
 
+    // 2.1 LTP 子集：运行器、用例列表与用例二进制放到 /ltp
+    if let Some(ltp_dir) = ltp_dir() {
+        install_ltp_subset(
+            &ltp_dir,
+            &project_root.join("test-support").join("ltp"),
+            &temp_root,
+        )
+        .expect("Failed to install LTP subset");
+    }
+
     // 3. 创建空镜像
     let dd_status = Command::new("dd")
         .arg("if=/dev/zero")
@@ -317,6 +335,50 @@ fn create_full_ext4_image(path: &PathBuf, data_dir: &Path, _project_root: &Path)
     println!("cargo:warning=[build.rs] Full ext4 image created successfully (1GB).");
 }
 
+/// LTP 安装目录（环境变量 `LTP_DIR`，如 `/opt/ltp`）
+fn ltp_dir() -> Option<PathBuf> {
+    env::var_os("LTP_DIR")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 将 `cases.txt` 中列出的 LTP 用例及运行器复制到镜像根目录的 `ltp/` 下
+///
+/// 找不到的用例只给出警告，运行器会将其报告为 CONF。
+fn install_ltp_subset(ltp_dir: &Path, support_dir: &Path, root: &Path) -> std::io::Result<()> {
+    let dst = root.join("ltp");
+    let bin_dst = dst.join("bin");
+    fs::create_dir_all(&bin_dst)?;
+
+    let cases = fs::read_to_string(support_dir.join("cases.txt"))?;
+    fs::write(dst.join("cases.txt"), &cases)?;
+    fs::copy(support_dir.join("run_ltp.sh"), dst.join("run_ltp.sh"))?;
+
+    let bin_src = ltp_dir.join("testcases").join("bin");
+    let mut installed = 0;
+    for name in cases
+        .lines()
+        .filter_map(|l| l.split('#').next()?.split_whitespace().next())
+    {
+        let src = bin_src.join(name);
+        if src.is_file() {
+            fs::copy(&src, bin_dst.join(name))?;
+            installed += 1;
+        } else {
+            println!(
+                "cargo:warning=[build.rs] LTP case not found: {}",
+                src.display()
+            );
+        }
+    }
+    println!(
+        "cargo:warning=[build.rs] Installed {} LTP cases from {}",
+        installed,
+        bin_src.display()
+    );
+    Ok(())
+}
+
 /// 递归复制目录
 fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
     if !dst.exists() {
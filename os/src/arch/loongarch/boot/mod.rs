@@ -19,10 +19,7 @@ use crate::{
         FsStruct, Scheduler, TASK_MANAGER, TaskManagerTrait, TaskStruct, current_cpu, current_task,
         kernel_execve, kthread_spawn, kworker, sleep_task_with_block, time, yield_task,
     },
-    mm::{
-        self,
-        frame_allocator::{alloc_contig_frames, alloc_frame},
-    },
+    mm::{self, frame_allocator::alloc_frame, kstack::KernelStack},
     pr_err, pr_info, println,
     sync::SpinLock,
     uapi::{
@@ -41,7 +38,7 @@ pub fn rest_init() {
     earlyprintln!("[Boot] rest_init: creating init task");
     // init 进程必须使用 TID/PID 1，不从分配器获取（分配器从 2 开始）。
     let tid = 1;
-    let kstack_tracker = alloc_contig_frames(4).expect("kthread_spawn: failed to alloc kstack");
+    let kstack = KernelStack::new().expect("kthread_spawn: failed to alloc kstack");
     let trap_frame_tracker = alloc_frame().expect("kthread_spawn: failed to alloc trap_frame");
     let fd_table = FDTable::new();
     let (stdin, stdout, stderr) = create_stdio_files();
@@ -76,7 +73,7 @@ pub fn rest_init() {
         tid,
         0,
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         Arc::new(SpinLock::new(SignalHandlerTable::new())),
         SignalFlags::empty(),
@@ -139,12 +136,14 @@ pub fn rest_init() {
 /// Idle 循环：等待中断；被定时器中断唤醒后由 trap/scheduler 决定是否调度。
 fn idle_loop() -> ! {
     loop {
-        if !crate::arch::intr::are_interrupts_enabled() {
-            unsafe { crate::arch::intr::enable_interrupts() };
-        }
+        unsafe { crate::arch::intr::disable_interrupts() };
+        crate::kernel::tick_nohz_idle_enter();
+        // IDLE 需要在开中断时才会被中断唤醒
+        unsafe { crate::arch::intr::enable_interrupts() };
         unsafe {
             core::arch::asm!("idle 0");
         }
+        crate::kernel::tick_nohz_idle_exit();
     }
 }
 
@@ -152,14 +151,12 @@ fn idle_loop() -> ! {
 fn create_idle_task(cpu_id: usize) -> crate::kernel::SharedTask {
     use crate::arch::trap::TrapFrame;
     use crate::vfs::FDTable;
-    use mm::frame_allocator::alloc_contig_frames;
 
     // idle 任务从 TID 分配器正常分配（从 2 开始）
     let tid = TASK_MANAGER.lock().allocate_tid();
 
     // 分配最小资源
-    let kstack_tracker =
-        alloc_contig_frames(1).expect("Failed to allocate kernel stack for idle task");
+    let kstack = KernelStack::new().expect("Failed to allocate kernel stack for idle task");
     let trap_frame_tracker = alloc_frame().expect("Failed to allocate trap frame for idle task");
 
     // 创建最小化的内核线程
@@ -168,7 +165,7 @@ fn create_idle_task(cpu_id: usize) -> crate::kernel::SharedTask {
         tid, // pid = tid
         0,   // ppid = 0 (no parent)
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         Arc::new(SpinLock::new(SignalHandlerTable::new())),
         SignalFlags::empty(),
@@ -234,9 +231,20 @@ fn init() {
     // /dev(/proc,/sys,/tmp) 的挂载交给用户态 rcS：
     // - rcS 会执行 `mount -t tmpfs none /dev` 等
     // - 内核在 mount("/dev") 的系统调用里会自动 init_dev() 创建设备节点
+    // syscall-fuzz：文件系统就绪后直接运行系统调用模糊测试并关机，不进入用户态
+    #[cfg(feature = "syscall-fuzz")]
+    crate::test::sysfuzz::run_and_shutdown();
+
     // Always enter user-space BusyBox init. In OSCOMP mode, /tests is mounted by
     // init_oscomp_filesystems() and rcS is responsible for running the test scripts.
+    #[cfg(not(feature = "ltp"))]
     kernel_execve("/sbin/init", &["/sbin/init"], &[]);
+
+    // ltp：用 BusyBox sh 运行 LTP 子集（/ltp/run_ltp.sh 结束后自行关机）
+    #[cfg(feature = "ltp")]
+    kernel_execve("/bin/sh", &["/bin/sh", "/ltp/run_ltp.sh"], &[
+        "PATH=/ltp/bin:/bin:/sbin:/usr/bin",
+    ]);
 }
 
 /// 内核守护线程
@@ -244,6 +252,11 @@ fn init() {
 /// 负责创建内核任务，回收僵尸任务等工作
 fn kthreadd() {
     kthread_spawn(kworker);
+    kthread_spawn(crate::log::console_flusher);
+    kthread_spawn(crate::mm::ksm::ksmd);
+    if crate::log::netconsole::enabled() {
+        kthread_spawn(crate::log::netconsole_thread);
+    }
     loop {
         // 休眠等待任务
         sleep_task_with_block(current_task(), true);
@@ -254,7 +267,7 @@ fn kthreadd() {
 /// 创建内核守护线程 kthreadd
 fn create_kthreadd() {
     let tid = TASK_MANAGER.lock().allocate_tid();
-    let kstack_tracker = alloc_contig_frames(4).expect("kthread_spawn: failed to alloc kstack");
+    let kstack = KernelStack::new().expect("kthread_spawn: failed to alloc kstack");
     let trap_frame_tracker = alloc_frame().expect("kthread_spawn: failed to alloc trap_frame");
     let (uts, rlimit, fd_table, fs) = {
         let task = current_task();
@@ -271,7 +284,7 @@ fn create_kthreadd() {
         tid,
         0,
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         Arc::new(SpinLock::new(SignalHandlerTable::new())),
         SignalFlags::empty(),
@@ -304,9 +317,15 @@ pub fn main(hartid: usize) {
 
     // 初始化 sync crate 的架构操作（必须在任何使用 sync 原语之前）
     unsafe { crate::arch::init_sync_arch_ops() };
+    // Condvar 睡眠与唤醒使用的调度操作
+    unsafe { crate::sync::init_sync_sched_ops() };
 
     earlyprintln!("[Boot] Hello, world!");
     earlyprintln!("[Boot] LoongArch CPU {} is up!", hartid);
+    if crate::mm::kaslr::kaslr_slide() != 0 {
+        // 不打印偏移本身，避免经日志泄露内核地址
+        earlyprintln!("[Boot] KASLR: kernel virtual base randomized");
+    }
 
     // 注册 mm crate 的配置和架构操作（必须在 phase1_early_parse() 之前，因为需要地址转换）
     unsafe {
@@ -341,6 +360,9 @@ pub fn main(hartid: usize) {
         current_cpu().switch_space(kernel_space);
     }
 
+    // 启动时核对内核映射的 W^X 布局
+    crate::mm::check_wx_mappings();
+
     // 测试模式下：
     // - 提前注册 FsOps，避免 tests/ 中的 TmpFs/Ext4 等依赖 fs_ops() 时 panic。
     // - 提前创建并切换到一个可用的 current_task，避免 /proc/self 等依赖 current_task() 的用例直接 panic。
@@ -377,10 +399,18 @@ pub fn main(hartid: usize) {
     crate::device::init_device_ops();
 
     platform::init();
+    crate::log::pstore::init_blk();
+    crate::mm::swap::init();
+    crate::mm::oom::init();
+    crate::vfs::init_dentry_shrinker();
+    crate::mm::compaction::init();
+    crate::mm::hugetlb::init();
     time::init();
     earlyprintln!("[Boot] time::init finished");
+    crate::security::random::init();
     timer::init();
     earlyprintln!("[Boot] timer::init finished");
+    crate::arch::ipi::init();
 
     // 初始化 VFS 操作（必须在使用 VFS 之前）
     crate::vfs::init_vfs_ops();
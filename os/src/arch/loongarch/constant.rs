@@ -4,6 +4,9 @@
 /// 架构名称字符串
 pub const ARCH: &str = "loongarch64";
 
+/// seccomp_data.arch 中报告的审计架构标识
+pub const AUDIT_ARCH: u32 = uapi::seccomp::AUDIT_ARCH_LOONGARCH64;
+
 /// QEMU virt 平台 UART 基地址
 pub const UART_BASE: usize = 0x1fe001e0;
 
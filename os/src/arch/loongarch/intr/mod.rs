@@ -12,6 +12,7 @@ const ECFG_CSR: u32 = 0x4;
 
 // 本地中断位
 const TIMER_LIE_BIT: usize = 1 << 11; // LIT（Local Interrupt Timer）对应的使能位
+const IPI_LIE_BIT: usize = 1 << 12; // IPI 核间中断对应的使能位
 
 #[inline(always)]
 unsafe fn set_crmd_ie(enable: bool) -> usize {
@@ -90,6 +91,13 @@ pub unsafe fn disable_timer_interrupt() {
     unsafe { update_ecfg(TIMER_LIE_BIT, false) };
 }
 
+/// 启用 IPI 中断（仅设置本地 IPI 使能位，不开启全局 IE）
+/// # Safety
+/// 直接操作 CSR，调用者需确保时序正确
+pub unsafe fn enable_ipi_interrupt() {
+    unsafe { update_ecfg(IPI_LIE_BIT, true) };
+}
+
 /// 启用全局中断
 /// # Safety
 /// 直接操作 CSR 寄存器
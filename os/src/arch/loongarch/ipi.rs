@@ -1,31 +1,146 @@
-//! LoongArch64 IPI（单核下的最小实现）
+//! LoongArch64 IPI (Inter-Processor Interrupt) 核间中断
 //!
-//! 目前 LoongArch 端尚未接入多核 IPI 硬件。
-//! 单核环境下 IPI 调用不需要实际发起中断，保留接口以复用通用调度/任务迁移逻辑。
+//! 通过 Loongson IOCSR 中的 IPI 寄存器组实现，与 RISC-V 端保持相同接口。
+//!
+//! # 设计说明
+//!
+//! - 每种 IPI 类型对应 IPI 状态寄存器中的一位（向量号）
+//! - 发送时写 `IOCSR_IPI_SEND`，由硬件在目标 CPU 的状态寄存器中置位并触发 IPI 中断（ESTAT.IS bit 12）
+//! - 目标 CPU 在中断处理中读取并清除状态寄存器，按位执行相应操作
+//!
+//! 状态位由硬件按 CPU 维护，因此不需要像 RISC-V 端那样额外的 per-CPU 软件标志。
+
+use crate::arch::intr::enable_ipi_interrupt;
 
-/// IPI 类型（用于与 RISC-V 端保持接口一致）
+/// IPI 状态寄存器（只读，每位对应一个待处理的向量）
+const IOCSR_IPI_STATUS: usize = 0x1000;
+/// IPI 使能寄存器
+const IOCSR_IPI_EN: usize = 0x1004;
+/// IPI 清除寄存器（写 1 清除对应状态位）
+const IOCSR_IPI_CLEAR: usize = 0x100c;
+/// IPI 发送寄存器：bits\[25:16\] 为目标 CPU，bits\[4:0\] 为向量号
+const IOCSR_IPI_SEND: usize = 0x1040;
+/// 发送寄存器中目标 CPU 字段的偏移
+const IPI_SEND_CPU_SHIFT: u32 = 16;
+
+/// IPI 类型（取值为 IPI 状态寄存器中的向量号）
+#[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IpiType {
     /// 通知目标 CPU 进行 reschedule
-    Reschedule,
+    Reschedule = 0,
     /// 通知目标 CPU 刷新 TLB
-    TlbFlush,
+    TlbFlush = 1,
 }
 
-/// 发送单个 IPI（单核下为 no-op）
-#[inline]
-pub fn send_ipi(_target_cpu: usize, _ipi_type: IpiType) {}
+#[inline(always)]
+fn iocsr_read_w(reg: usize) -> u32 {
+    let value: u32;
+    // SAFETY: IOCSR IPI 寄存器组是每个 CPU 私有的，读取没有副作用
+    unsafe {
+        core::arch::asm!(
+            "iocsrrd.w {0}, {1}",
+            out(reg) value,
+            in(reg) reg,
+            options(nostack, preserves_flags)
+        );
+    }
+    value
+}
 
-/// 按 hart mask 发送 IPI（单核下为 no-op）
-#[inline]
-pub fn send_ipi_many(_hart_mask: usize, _ipi_type: IpiType) {}
+#[inline(always)]
+fn iocsr_write_w(reg: usize, value: u32) {
+    // SAFETY: 只写 IPI 寄存器组，不影响内存安全
+    unsafe {
+        core::arch::asm!(
+            "iocsrwr.w {0}, {1}",
+            in(reg) value,
+            in(reg) reg,
+            options(nostack, preserves_flags)
+        );
+    }
+}
+
+/// 使能当前 CPU 的所有 IPI 向量并打开 IPI 中断
+///
+/// 每个 CPU 启动时调用一次，之后本 CPU 参与 TLB 同步刷新。
+pub fn init() {
+    iocsr_write_w(IOCSR_IPI_EN, u32::MAX);
+    // SAFETY: 只设置 ECFG 中的 IPI 使能位
+    unsafe { enable_ipi_interrupt() };
+    crate::arch::tlb::cpu_ready();
+}
+
+/// 发送单个 IPI
+///
+/// # Panics
+///
+/// 如果 target_cpu >= NUM_CPU，会 panic
+pub fn send_ipi(target_cpu: usize, ipi_type: IpiType) {
+    let num_cpu = unsafe { crate::kernel::NUM_CPU };
+    core::assert!(target_cpu < num_cpu, "Invalid target CPU: {}", target_cpu);
+
+    iocsr_write_w(
+        IOCSR_IPI_SEND,
+        ((target_cpu as u32) << IPI_SEND_CPU_SHIFT) | ipi_type as u32,
+    );
+}
+
+/// 按 CPU 位掩码发送 IPI
+pub fn send_ipi_many(hart_mask: usize, ipi_type: IpiType) {
+    let num_cpu = unsafe { crate::kernel::NUM_CPU };
+    for cpu in (0..num_cpu).filter(|cpu| hart_mask & (1 << cpu) != 0) {
+        send_ipi(cpu, ipi_type);
+    }
+}
 
-/// 发送 reschedule IPI（单核下为 no-op）
+/// 发送 reschedule IPI
 #[inline]
 pub fn send_reschedule_ipi(cpu: usize) {
     send_ipi(cpu, IpiType::Reschedule);
 }
 
-/// 向所有 CPU 发送 TLB flush IPI（单核下为 no-op）
-#[inline]
-pub fn send_tlb_flush_ipi_all() {}
+/// 广播 TLB 刷新 IPI
+///
+/// 通知除当前 CPU 外的所有 CPU 刷新整个 TLB，等它们全部完成后返回（见 [`crate::arch::tlb`]）
+pub fn send_tlb_flush_ipi_all() {
+    crate::arch::tlb::shootdown(None);
+}
+
+/// 处理 IPI（在 IPI 中断处理中调用）
+///
+/// 读取并清除当前 CPU 的 IPI 状态，执行相应操作
+pub fn handle_ipi() {
+    let pending = iocsr_read_w(IOCSR_IPI_STATUS);
+    if pending == 0 {
+        return;
+    }
+    iocsr_write_w(IOCSR_IPI_CLEAR, pending);
+
+    crate::pr_debug!(
+        "[IPI] CPU {} handling IPI: {:#x}",
+        super::kernel::cpu::cpu_id(),
+        pending
+    );
+
+    // 调度将在中断返回时处理，这里只需要确认
+    if pending & (1 << IpiType::Reschedule as u32) != 0 {
+        crate::pr_debug!("[IPI] received Reschedule IPI");
+    }
+
+    if pending & (1 << IpiType::TlbFlush as u32) != 0 {
+        crate::arch::tlb::handle_shootdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试 IPI 向量号互不相同且在状态寄存器范围内
+    #[test_case]
+    fn test_ipi_type_vectors() {
+        assert!(IpiType::Reschedule as u32 != IpiType::TlbFlush as u32);
+        assert!((IpiType::TlbFlush as u32) < 32);
+    }
+}
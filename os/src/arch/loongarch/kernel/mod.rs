@@ -6,12 +6,25 @@ pub mod context;
 pub mod task;
 
 global_asm!(include_str!("switch.S"));
+global_asm!(include_str!("vdso.S"));
 
 // 上下文切换函数
 unsafe extern "C" {
     pub fn switch(old: *mut Context, new: *const Context);
 }
 
+unsafe extern "C" {
+    fn __vdso_start();
+    fn __vdso_end();
+}
+
+/// vDSO 镜像（页对齐的 ELF 共享对象，映射到用户地址空间的 `USER_VDSO_BASE`）
+pub fn vdso_image() -> &'static [u8] {
+    let start = __vdso_start as usize;
+    let end = __vdso_end as usize;
+    unsafe { core::slice::from_raw_parts(start as *const u8, end - start) }
+}
+
 /// CPU 相关
 pub mod cpu {
     /// 获取当前 Hart ID（当前仅单核）
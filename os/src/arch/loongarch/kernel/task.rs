@@ -2,11 +2,17 @@
 
 use alloc::vec::Vec;
 use core::{mem::size_of, ptr};
+use uapi::auxv::{
+    AT_BASE, AT_CLKTCK, AT_EGID, AT_ENTRY, AT_EUID, AT_EXECFN, AT_FLAGS, AT_GID, AT_HWCAP, AT_NULL,
+    AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM, AT_PLATFORM, AT_RANDOM, AT_RSEQ_ALIGN,
+    AT_RSEQ_FEATURE_SIZE, AT_SECURE, AT_SYSINFO_EHDR, AT_UID, USER_HZ,
+};
+use uapi::rseq::{ORIG_RSEQ_SIZE, RSEQ_FEATURE_SIZE};
 
 use super::context::TaskContext;
 use crate::{
     arch::{constant::STACK_ALIGN_MASK, mm::paddr_to_vaddr},
-    config::PAGE_SIZE,
+    config::{PAGE_SIZE, USER_VDSO_BASE},
     mm::{
         address::{UsizeConvert, Vaddr},
         frame_allocator::FrameTracker,
@@ -90,10 +96,8 @@ pub fn setup_stack_layout(
     // --- 构建 argc, argv, envp 数组 ---
 
     // AT_RANDOM 数据
-    let random_bytes = [
-        0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45,
-        0x67,
-    ];
+    let mut random_bytes = [0u8; 16];
+    crate::security::random::get_random_bytes(&mut random_bytes);
     let random_ptr = sp - 16;
     write_user_bytes(&space, random_ptr, &random_bytes);
     sp = random_ptr;
@@ -111,24 +115,27 @@ pub fn setup_stack_layout(
     let execfn = arg_ptrs.last().copied().unwrap_or(0);
 
     let auxv = [
-        (3, phdr_addr),     // AT_PHDR
-        (4, phent),         // AT_PHENT
-        (5, phnum),         // AT_PHNUM
-        (6, 4096),          // AT_PAGESZ
-        (7, at_base),       // AT_BASE
-        (8, 0),             // AT_FLAGS
-        (9, at_entry),      // AT_ENTRY
-        (11, 0),            // AT_UID
-        (12, 0),            // AT_EUID
-        (13, 0),            // AT_GID
-        (14, 0),            // AT_EGID
-        (15, platform_ptr), // AT_PLATFORM
-        (16, 0),            // AT_HWCAP
-        (17, 100),          // AT_CLKTCK
-        (23, 0),            // AT_SECURE
-        (25, random_ptr),   // AT_RANDOM
-        (31, execfn),       // AT_EXECFN
-        (0, 0),             // AT_NULL
+        (AT_PHDR, phdr_addr),
+        (AT_PHENT, phent),
+        (AT_PHNUM, phnum),
+        (AT_PAGESZ, PAGE_SIZE),
+        (AT_BASE, at_base),
+        (AT_FLAGS, 0),
+        (AT_ENTRY, at_entry),
+        (AT_UID, 0),
+        (AT_EUID, 0),
+        (AT_GID, 0),
+        (AT_EGID, 0),
+        (AT_PLATFORM, platform_ptr),
+        (AT_HWCAP, 0),
+        (AT_CLKTCK, USER_HZ),
+        (AT_SECURE, 0),
+        (AT_RSEQ_FEATURE_SIZE, RSEQ_FEATURE_SIZE),
+        (AT_RSEQ_ALIGN, ORIG_RSEQ_SIZE as usize),
+        (AT_RANDOM, random_ptr),
+        (AT_EXECFN, execfn),
+        (AT_SYSINFO_EHDR, USER_VDSO_BASE),
+        (AT_NULL, 0),
     ];
 
     for (i, (k, v)) in auxv.iter().enumerate() {
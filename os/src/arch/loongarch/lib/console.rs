@@ -19,6 +19,8 @@ pub struct Stdin;
 
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
+        // 记录下来，切换到运行时控制台时回放到帧缓冲等控制台
+        crate::device::console::EARLY_LOG.record(s);
         for c in s.bytes() {
             unsafe {
                 // 等待 UART 发送缓冲区空闲 (LSR bit 5, THRE)
@@ -18,18 +18,24 @@
 //! # 直接映射
 //!
 //! 本模块使用**直接映射 (direct mapping)** 进行地址转换：
-//! - 虚拟地址起始: `0x9000_0000_0000_0000`
+//! - 虚拟地址起始: `0x9000_0000_0000_0000` 加上 KASLR 偏移
 //! - **提取物理地址**: 虚拟地址 & `PADDR_MASK`
-//! - **创建虚拟地址**: 物理地址 | `VADDR_START`
+//! - **创建虚拟地址**: 物理地址 | (`VADDR_START` + 偏移)
+//!
+//! DMW 窗口内的低位就是物理地址，KASLR 只能改变窗口所在的段（VSEG），
+//! 偏移以 `1 << 60` 为单位，见 [`crate::mm::kaslr`]。
 
 mod page_table;
 mod page_table_entry;
 
-pub use page_table::{PageTableInner, TlbBatchContext};
+pub use page_table::{PageTableInner, TlbBatchContext, flush_tlb_all, flush_tlb_entry, tlb_entry};
 pub use page_table_entry::PageTableEntry;
 
+use mm::address::{Ppn, Vpn};
 use mm::{ArchMmOps, TlbBatchContextTrait, TlbBatchContextWrapper};
 
+use crate::mm::kaslr::kaslr_slide;
+
 /// LoongArch64 直接映射窗口起始地址
 ///
 /// 内核虚拟地址空间从此地址开始，通过 DMW (Direct Mapping Window) 配置。
@@ -43,6 +49,20 @@ pub const MMIO_VADDR_START: usize = 0x8000_0000_0000_0000;
 /// 用于从虚拟地址提取物理地址，保留低 48 位。
 pub const PADDR_MASK: usize = 0x0000_FFFF_FFFF_FFFF;
 
+/// KASLR 偏移的对齐（一个 DMW 段）
+pub const KASLR_ALIGN: usize = 1 << 60;
+
+/// KASLR 偏移的候选个数：缓存窗口可以位于 VSEG 0x9 到 0xe
+///
+/// VSEG 0x8 是 MMIO 使用的非缓存窗口（DMW0），VSEG 0xf 经 PGDH 页表翻译（内核栈区），
+/// 都不参与随机化。
+pub const KASLR_SLOTS: usize = 6;
+
+/// 内核栈区的起始地址：高半区根页表的最后一项，位于 VSEG 0xf，不被任何 DMW 窗口覆盖
+///
+/// 见 [`crate::mm::kstack`]。
+pub const KSTACK_AREA_START: usize = 0xffff_ff80_0000_0000;
+
 /// 虚拟地址转物理地址
 ///
 /// # 参数
@@ -71,8 +91,8 @@ pub const unsafe fn vaddr_to_paddr(vaddr: usize) -> usize {
 ///
 /// 对应的虚拟地址（在直接映射区域内）
 #[inline]
-pub const fn paddr_to_vaddr(paddr: usize) -> usize {
-    paddr | VADDR_START
+pub fn paddr_to_vaddr(paddr: usize) -> usize {
+    paddr | (VADDR_START + kaslr_slide())
 }
 
 // ============ ArchMmOps trait 实现 ============
@@ -82,11 +102,22 @@ struct LoongArchMmOps;
 
 impl ArchMmOps for LoongArchMmOps {
     unsafe fn vaddr_to_paddr(&self, vaddr: usize) -> usize {
-        vaddr & PADDR_MASK
+        unsafe { vaddr_to_paddr(vaddr) }
     }
 
     fn paddr_to_vaddr(&self, paddr: usize) -> usize {
-        paddr | VADDR_START
+        paddr_to_vaddr(paddr)
+    }
+
+    fn dma_coherent_vaddr(&self, paddr: usize) -> usize {
+        // 龙芯 3 系列的 IO 访问由硬件维护缓存一致性，使用缓存的 DMW 窗口；
+        // 非缓存窗口（DMW0）只用于 MMIO，与缓存窗口混用同一帧会被缓存中的脏行覆盖
+        paddr_to_vaddr(paddr)
+    }
+
+    fn phys_to_dma(&self, paddr: usize) -> usize {
+        // 没有 IOMMU，总线地址就是物理地址
+        paddr
     }
 
     fn sigreturn_trampoline_bytes(&self) -> &'static [u8] {
@@ -98,8 +129,7 @@ impl ArchMmOps for LoongArchMmOps {
     }
 
     fn send_tlb_flush_ipi_all(&self) {
-        // LoongArch 多核 IPI 尚未实现，暂时只刷新本地 TLB
-        // TODO: 实现 LoongArch 多核 TLB shootdown
+        crate::arch::ipi::send_tlb_flush_ipi_all();
     }
 
     fn create_tlb_batch_context(&self) -> TlbBatchContextWrapper {
@@ -108,6 +138,10 @@ impl ArchMmOps for LoongArchMmOps {
 }
 
 impl TlbBatchContextTrait for TlbBatchContext {
+    fn add_page(&mut self, root: Ppn, vpn: Vpn) {
+        TlbBatchContext::add_page(self, root, vpn);
+    }
+
     fn flush(&mut self) {
         TlbBatchContext::flush(self);
     }
@@ -10,8 +10,12 @@
 //! - Level 0 (Dir1/PT):  虚拟地址 bits \[20:12\]，9 位索引
 //!
 //! 每级页表有 512 个条目（2^9），每个条目 8 字节。
+//!
+//! Dir2 和 Dir1 级的目录项可以是 1G/2M 巨页项（见 [`PageTableEntry::new_huge`]），
+//! 本模块读取巨页项时先用 [`PageTableEntry::huge_as_leaf`] 还原为叶子项。
 
 use super::PageTableEntry;
+use crate::arch::tlb::{self, MAX_FLUSH_PAGES};
 use alloc::vec::Vec;
 use mm::TlbBatchContextWrapper;
 use mm::address::{ConvertablePaddr, Paddr, PageNum, Ppn, UsizeConvert, Vaddr, Vpn};
@@ -21,6 +25,9 @@ use mm::page_table::{
     PagingError, PagingResult, UniversalPTEFlag,
 };
 
+/// ASID 的位数对应的掩码，地址空间的 ASID 取根页表物理页号的低位
+const ASID_MASK: usize = 0x3ff;
+
 /// 页表内部结构
 #[derive(Debug)]
 pub struct PageTableInner {
@@ -101,7 +108,7 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
                 options(nostack, preserves_flags)
             );
             // 设置 ASID (CSR 0x18)
-            let asid = ppn.as_usize() & 0x3ff;
+            let asid = ppn.as_usize() & ASID_MASK;
             core::arch::asm!(
                 "csrwr {0}, 0x18",
                 in(reg) asid,
@@ -221,7 +228,12 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
             }
 
             if current_level == level {
-                let page_size = PageSize::Size4K; // 当前仅支持 4K 页
+                let page_size = PageSize::from_level(level)?;
+                let pte = if level > 0 && pte.is_huge() {
+                    pte.huge_as_leaf()
+                } else {
+                    pte
+                };
                 return Some((pte, page_size));
             }
 
@@ -236,13 +248,10 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         let vpn = Vpn::from_addr_floor(vaddr);
         let offset = vaddr.as_usize() & 0xfff; // 页内偏移
 
+        // walk 返回的已是 vpn 所在 4K 页的物理页号（巨页内的偏移已计入）
         match self.walk(vpn) {
-            Ok((ppn, page_size, _flags)) => {
-                let paddr_base = match page_size {
-                    PageSize::Size4K => ppn.start_addr().as_usize(),
-                    _ => ppn.start_addr().as_usize(), // 暂时按 4K 处理
-                };
-                Some(Paddr::from_usize(paddr_base + offset))
+            Ok((ppn, _page_size, _flags)) => {
+                Some(Paddr::from_usize(ppn.start_addr().as_usize() + offset))
             }
             Err(_) => None,
         }
@@ -253,7 +262,7 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         &mut self,
         vpn: Vpn,
         ppn: Ppn,
-        _page_size: PageSize,
+        page_size: PageSize,
         flags: UniversalPTEFlag,
     ) -> PagingResult<()> {
         // 验证标志位：叶子节点必须至少设置可读或可执行
@@ -263,8 +272,11 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
             return Err(PagingError::InvalidFlags);
         }
 
-        // 当前仅支持 4K 页（level 0）
-        let target_level = 0;
+        // 巨页映射在目录级，虚拟页号和物理页号都必须按页大小对齐
+        let target_level = page_size.level();
+        if vpn.as_usize() % page_size.pages() != 0 || ppn.as_usize() % page_size.pages() != 0 {
+            return Err(PagingError::InvalidAddress);
+        }
 
         let mut current_ppn = self.root_ppn;
         let vpn_value = vpn.as_usize();
@@ -276,11 +288,16 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
 
             if level == target_level {
                 // 已到达目标级别，创建叶子节点
-                if pte.is_valid() {
+                // 目录项不设置 PRESENT，巨页的目标级别上只要非空就已被映射
+                if pte.is_valid() || (level > 0 && !pte.is_empty()) {
                     return Err(PagingError::AlreadyMapped);
                 }
 
-                let new_pte = PageTableEntry::new_leaf(ppn, flags | UniversalPTEFlag::VALID);
+                let new_pte = if level > 0 {
+                    PageTableEntry::new_huge(ppn, flags | UniversalPTEFlag::VALID)
+                } else {
+                    PageTableEntry::new_leaf(ppn, flags | UniversalPTEFlag::VALID)
+                };
                 Self::write_pte(current_ppn, idx, new_pte);
                 Self::tlb_flush(vpn);
                 return Ok(());
@@ -362,12 +379,19 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
                 return Err(PagingError::NotMapped);
             }
 
-            if pte.is_huge() || level == 0 {
+            if level == 0 {
                 pte.set_flags(flags | UniversalPTEFlag::VALID);
                 Self::write_pte(current_ppn, idx, pte);
                 Self::tlb_flush(vpn);
                 return Ok(());
             }
+            if pte.is_huge() {
+                let mut leaf = pte.huge_as_leaf();
+                leaf.set_flags(flags | UniversalPTEFlag::VALID);
+                Self::write_pte(current_ppn, idx, leaf.leaf_as_huge());
+                Self::tlb_flush(vpn);
+                return Ok(());
+            }
 
             current_ppn = pte.ppn();
         }
@@ -388,9 +412,19 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
                 return Err(PagingError::NotMapped);
             }
 
-            if pte.is_huge() || level == 0 {
-                let page_size = PageSize::Size4K; // 当前仅支持 4K
-                return Ok((pte.ppn(), page_size, pte.flags()));
+            if level == 0 {
+                return Ok((pte.ppn(), PageSize::Size4K, pte.flags()));
+            }
+            if pte.is_huge() {
+                // 巨页返回 vpn 所在 4K 页的物理页号
+                let page_size = PageSize::from_level(level).ok_or(PagingError::NotMapped)?;
+                let leaf = pte.huge_as_leaf();
+                let offset = vpn_value & (page_size.pages() - 1);
+                return Ok((
+                    Ppn::from_usize(leaf.ppn().as_usize() + offset),
+                    page_size,
+                    leaf.flags(),
+                ));
             }
 
             ppn = pte.ppn();
@@ -406,10 +440,10 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         ppn: Ppn,
         page_size: PageSize,
         flags: UniversalPTEFlag,
-        _batch: Option<&mut TlbBatchContextWrapper>,
+        batch: Option<&mut TlbBatchContextWrapper>,
     ) -> PagingResult<()> {
         self.map(vpn, ppn, page_size, flags)?;
-        Self::tlb_flush(vpn);
+        self.flush_page(vpn, batch);
         Ok(())
     }
 
@@ -417,10 +451,10 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
     fn unmap_with_batch(
         &mut self,
         vpn: Vpn,
-        _batch: Option<&mut TlbBatchContextWrapper>,
+        batch: Option<&mut TlbBatchContextWrapper>,
     ) -> PagingResult<()> {
         self.unmap(vpn)?;
-        Self::tlb_flush(vpn);
+        self.flush_page(vpn, batch);
         Ok(())
     }
 
@@ -429,26 +463,113 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         &mut self,
         vpn: Vpn,
         flags: UniversalPTEFlag,
-        _batch: Option<&mut TlbBatchContextWrapper>,
+        batch: Option<&mut TlbBatchContextWrapper>,
     ) -> PagingResult<()> {
         self.update_flags(vpn, flags)?;
+        self.flush_page(vpn, batch);
+        Ok(())
+    }
+
+    // 把巨页拆分为下一级的 512 个页
+    fn split_huge_page(&mut self, vpn: Vpn) -> PagingResult<()> {
+        let mut current_ppn = self.root_ppn;
+        let vpn_value = vpn.as_usize();
+
+        for level in (1..Self::LEVELS).rev() {
+            let idx = Self::vpn_index(vpn_value, level);
+            let pte = Self::read_pte(current_ppn, idx);
+
+            if pte.is_empty() {
+                return Err(PagingError::NotMapped);
+            }
+            if !pte.is_huge() {
+                current_ppn = pte.ppn();
+                continue;
+            }
+
+            let new_frame = alloc_frame().ok_or(PagingError::FrameAllocFailed)?;
+            let new_ppn = new_frame.ppn();
+            // 下一级每个页表项覆盖的 4K 页数
+            let stride = 1usize << (9 * (level - 1));
+            let leaf = pte.huge_as_leaf();
+            let base = leaf.ppn().as_usize();
+            for i in 0..512 {
+                let mut child = leaf;
+                child.set_ppn(Ppn::from_usize(base + i * stride));
+                // 拆到 Dir1 级的仍是巨页项
+                let child = if level > 1 {
+                    child.leaf_as_huge()
+                } else {
+                    child
+                };
+                Self::write_pte(new_ppn, i, child);
+            }
+
+            Self::write_pte(current_ppn, idx, PageTableEntry::new_table(new_ppn));
+            self.frames.push(new_frame);
+            // 巨页可能以任意粒度缓存在 TLB 中，全部刷新
+            Self::tlb_flush_all();
+            tlb::shootdown(None);
+            return Ok(());
+        }
+
+        // 已是 4K 页，确认已映射
+        self.walk(vpn).map(|_| ())
+    }
+
+    // 把叶子页表项设为换出项（支持 TLB 批处理）
+    fn set_swap_entry(
+        &mut self,
+        vpn: Vpn,
+        slot: usize,
+        batch: Option<&mut TlbBatchContextWrapper>,
+    ) -> PagingResult<()> {
+        let (table_ppn, idx) = self.leaf_slot(vpn).ok_or(PagingError::NotMapped)?;
+        Self::write_pte(table_ppn, idx, PageTableEntry::new_swap(slot));
+        self.flush_page(vpn, batch);
+        Ok(())
+    }
+
+    // 读取换出项中的交换槽位
+    fn swap_entry(&self, vpn: Vpn) -> Option<usize> {
+        let (table_ppn, idx) = self.leaf_slot(vpn)?;
+        Self::read_pte(table_ppn, idx).swap_slot()
+    }
+
+    // 清除换出项
+    fn clear_swap_entry(&mut self, vpn: Vpn) -> PagingResult<()> {
+        let (table_ppn, idx) = self.leaf_slot(vpn).ok_or(PagingError::NotMapped)?;
+        if Self::read_pte(table_ppn, idx).swap_slot().is_none() {
+            return Err(PagingError::NotMapped);
+        }
+        Self::write_pte(table_ppn, idx, PageTableEntry::empty());
+        // 重填时可能已把 V=0 的换出项装入 TLB
         Self::tlb_flush(vpn);
         Ok(())
     }
 }
 
-/// TLB 批量刷新上下文（占位符实现）
+/// TLB 批量刷新上下文
 ///
-/// 用于在批量页表操作期间延迟 TLB 刷新，减少跨核通知次数。
-/// LoongArch 端多核 IPI 尚未接入，因此目前只保证本地 TLB 刷新。
+/// 批处理期间修改的页只在本 CPU 上立即刷新，这里记录它们的 TLB 项，
+/// 结束时向其它 CPU 发送一次刷新请求；记录的页超过 [`MAX_FLUSH_PAGES`] 时改为刷新整个 TLB。
 pub struct TlbBatchContext {
-    enabled: bool,
+    /// 待刷新的 TLB 项（见 [`tlb_entry`]）
+    entries: [usize; MAX_FLUSH_PAGES],
+    /// 已记录的 TLB 项数
+    count: usize,
+    /// 记录的页超过上限
+    overflow: bool,
 }
 
 impl TlbBatchContext {
     /// 创建新的批处理上下文
     pub fn new() -> Self {
-        Self { enabled: true }
+        Self {
+            entries: [0; MAX_FLUSH_PAGES],
+            count: 0,
+            overflow: false,
+        }
     }
 
     /// 在批处理上下文中执行操作
@@ -462,12 +583,25 @@ impl TlbBatchContext {
         result
     }
 
+    /// 记录需要在其它 CPU 上刷新的页
+    pub fn add_page(&mut self, root: Ppn, vpn: Vpn) {
+        if self.count < MAX_FLUSH_PAGES {
+            self.entries[self.count] = tlb_entry(root, vpn);
+            self.count += 1;
+        } else {
+            self.overflow = true;
+        }
+    }
+
     /// 刷新所有待处理的 TLB 条目
     pub fn flush(&mut self) {
-        if self.enabled {
-            <PageTableInner as PageTableInnerTrait<PageTableEntry>>::tlb_flush_all();
-            self.enabled = false;
+        if self.overflow {
+            tlb::shootdown(None);
+        } else if self.count > 0 {
+            tlb::shootdown(Some(&self.entries[..self.count]));
         }
+        self.count = 0;
+        self.overflow = false;
     }
 }
 
@@ -477,7 +611,58 @@ impl Drop for TlbBatchContext {
     }
 }
 
+/// 根页表为 `root` 的地址空间中 `vpn` 处的页在 TLB 刷新请求中的编码：页对齐的虚拟地址与 ASID
+pub fn tlb_entry(root: Ppn, vpn: Vpn) -> usize {
+    vpn.start_addr().as_usize() | (root.as_usize() & ASID_MASK)
+}
+
+/// 在本 CPU 上刷新 [`tlb_entry`] 编码的 TLB 项
+pub fn flush_tlb_entry(entry: usize) {
+    let asid = entry & ASID_MASK;
+    let vaddr = entry & !0xfff;
+    // SAFETY: INVTLB op=0x6 清除 G=1 或 ASID 匹配、且 VA 匹配的条目，不影响内存安全
+    unsafe {
+        core::arch::asm!(
+            "invtlb 0x6, {0}, {1}",
+            in(reg) asid,
+            in(reg) vaddr,
+            options(nostack, preserves_flags)
+        );
+    }
+}
+
+/// 在本 CPU 上刷新整个 TLB
+pub fn flush_tlb_all() {
+    <PageTableInner as PageTableInnerTrait<PageTableEntry>>::tlb_flush_all();
+}
+
 impl PageTableInner {
+    /// 刷新 `vpn` 处的页：本 CPU 立即刷新，其它 CPU 记入批处理或立即同步刷新
+    fn flush_page(&self, vpn: Vpn, batch: Option<&mut TlbBatchContextWrapper>) {
+        let entry = tlb_entry(self.root_ppn, vpn);
+        flush_tlb_entry(entry);
+        match batch {
+            Some(batch) => batch.add_page(self.root_ppn, vpn),
+            None => tlb::shootdown(Some(&[entry])),
+        }
+    }
+
+    /// 查找 `vpn` 对应的 4K 叶子页表项所在的页表页和索引，中间级目录不存在时返回 `None`
+    fn leaf_slot(&self, vpn: Vpn) -> Option<(Ppn, usize)> {
+        let mut ppn = self.root_ppn;
+        let vpn_value = vpn.as_usize();
+
+        for level in (1..<Self as PageTableInnerTrait<PageTableEntry>>::LEVELS).rev() {
+            let pte = Self::read_pte(ppn, Self::vpn_index(vpn_value, level));
+            if pte.is_empty() || pte.is_huge() {
+                return None;
+            }
+            ppn = pte.ppn();
+        }
+
+        Some((ppn, Self::vpn_index(vpn_value, 0)))
+    }
+
     /// 从 VPN 计算指定级别的索引
     ///
     /// 每级 9 位索引：
@@ -681,4 +866,31 @@ mod page_table_tests {
             assert!(mapped_ppn == expected_ppn);
         }
     }
+
+    // 8. 巨页映射与拆分测试
+    #[test_case]
+    fn test_pt_huge_page_split() {
+        let mut pt = PageTableInner::new();
+        let vpn = Vpn::from_usize(0x40000);
+        let ppn = Ppn::from_usize(0x80200);
+
+        pt.map(vpn, ppn, PageSize::Size2M, UniversalPTEFlag::kernel_rw())
+            .unwrap();
+        let inner = Vpn::from_usize(0x40000 + 7);
+        let (mapped_ppn, size, flags) = pt.walk(inner).unwrap();
+        assert!(size == PageSize::Size2M);
+        assert!(mapped_ppn.as_usize() == 0x80200 + 7);
+        assert!(flags.contains(UniversalPTEFlag::WRITEABLE));
+
+        // 拆分后映射和标志位不变，页大小变为 4K
+        pt.split_huge_page(inner).unwrap();
+        let (mapped_ppn, size, flags) = pt.walk(inner).unwrap();
+        assert!(size == PageSize::Size4K);
+        assert!(mapped_ppn.as_usize() == 0x80200 + 7);
+        assert!(flags.contains(UniversalPTEFlag::WRITEABLE));
+
+        pt.unmap(inner).unwrap();
+        assert!(pt.walk(inner).is_err());
+        assert!(pt.walk(vpn).is_ok());
+    }
 }
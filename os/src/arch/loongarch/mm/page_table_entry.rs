@@ -21,6 +21,12 @@
 //! | 61 | NR | Non-Readable (0=可读，1=不可读) |
 //! | 62 | NX | Non-eXecutable (0=可执行，1=不可执行) |
 //! | 63 | RPLV | Restricted PLV |
+//!
+//! # 巨页项
+//!
+//! 目录级（Dir1/Dir2）的页表项可以直接映射 2M/1G 巨页：bit 6 作为 HUGE 位，
+//! 原来 bit 6 上的 G 位移到 bit 12（HGLOBAL），其余位与叶子项相同。
+//! 硬件的 LDDIR 遇到 HUGE 位时不再向下查找，由 LDPTE 按巨页装入 TLB。
 
 use mm::address::{Ppn, UsizeConvert};
 use mm::page_table::PageTableEntry as PageTableEntryTrait;
@@ -239,6 +245,12 @@ const LA64_PTE_PPN_MASK: u64 = 0x0000_FFFF_FFFF_F000;
 const LA64_PTE_FLAG_MASK_LOW: u64 = 0x0FFF; // bits 0-11
 const LA64_PTE_FLAG_MASK_HIGH: u64 = 0xE000_0000_0000_0000; // bits 61-63
 
+/// 巨页标志位 (bit 6)，只对目录项有意义，与叶子项的 G 位重叠
+const LA64_PTE_HUGE: u64 = 1 << 6;
+
+/// 巨页的全局位 (bit 12)，巨页项中的 G 位移到这里
+const LA64_PTE_HGLOBAL: u64 = 1 << 12;
+
 /// LoongArch 页表项
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct PageTableEntry(u64);
@@ -282,12 +294,9 @@ impl PageTableEntryTrait for PageTableEntry {
 
     /// 检查是否为巨页（Huge Page）
     ///
-    /// LoongArch 中，需要通过页表级别来判断巨页，
-    /// 单独从 PTE 无法直接判断。这里返回 false 作为保守实现。
+    /// 只对目录项有意义：叶子项的 bit 6 是 G 位，调用者需结合页表级别判断。
     fn is_huge(&self) -> bool {
-        // LoongArch 不像 RISC-V 那样通过 R/W/X 位区分叶子节点
-        // 巨页检测需要结合页表级别信息
-        false
+        (self.0 & LA64_PTE_HUGE) != 0
     }
 
     fn is_empty(&self) -> bool {
@@ -334,6 +343,35 @@ impl PageTableEntryTrait for PageTableEntry {
     }
 }
 
+impl PageTableEntry {
+    /// 创建目录级的巨页项
+    pub fn new_huge(ppn: Ppn, flags: UniversalPTEFlag) -> Self {
+        Self::new_leaf(ppn, flags).leaf_as_huge()
+    }
+
+    /// 把叶子项编码为巨页项：设置 HUGE 位，G 位移到 HGLOBAL
+    pub fn leaf_as_huge(&self) -> Self {
+        let global = self.0 & LAPTEFlags::GLOBAL.bits() != 0;
+        let bits = (self.0 & !LAPTEFlags::GLOBAL.bits()) | LA64_PTE_HUGE;
+        PageTableEntry(if global {
+            bits | LA64_PTE_HGLOBAL
+        } else {
+            bits
+        })
+    }
+
+    /// 把巨页项还原为等价的叶子项，以便按叶子项读取物理页号和标志位
+    pub fn huge_as_leaf(&self) -> Self {
+        let global = self.0 & LA64_PTE_HGLOBAL != 0;
+        let bits = self.0 & !(LA64_PTE_HUGE | LA64_PTE_HGLOBAL);
+        PageTableEntry(if global {
+            bits | LAPTEFlags::GLOBAL.bits()
+        } else {
+            bits
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -381,4 +419,19 @@ mod tests {
         assert!(converted_back.contains(UniversalPTEFlag::WRITEABLE));
         assert!(converted_back.contains(UniversalPTEFlag::USER_ACCESSIBLE));
     }
+
+    // 4. 巨页项编码测试
+    #[test_case]
+    fn test_huge_entry_roundtrip() {
+        let ppn = Ppn::from_usize(0x80200);
+        let flags = UniversalPTEFlag::kernel_rw() | UniversalPTEFlag::GLOBAL;
+        let leaf = PageTableEntry::new_leaf(ppn, flags);
+        let huge = PageTableEntry::new_huge(ppn, flags);
+
+        assert!(huge.is_huge());
+        // G 位移到 HGLOBAL（与物理页号最低位重叠），还原后与叶子项一致
+        assert!(huge.to_bits() & LA64_PTE_HGLOBAL != 0);
+        assert!(huge.huge_as_leaf() == leaf);
+        assert!(huge.huge_as_leaf().ppn() == ppn);
+    }
 }
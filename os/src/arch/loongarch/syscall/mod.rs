@@ -26,8 +26,27 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         frame.regs[9]
     );
 
+    // seccomp 拒绝的系统调用不再分发，返回值已由过滤器动作写入
+    if !crate::security::seccomp::secure_computing(frame) {
+        crate::pr_debug!("[syscall] id={} skipped by seccomp", syscall_id);
+        return;
+    }
+
+    let args = frame.syscall_args();
     match syscall_id {
         // 文件系统/目录操作 (Filesystem/Directory Operations)
+        SYS_SETXATTR => sys_setxattr(frame),
+        SYS_LSETXATTR => sys_lsetxattr(frame),
+        SYS_FSETXATTR => sys_fsetxattr(frame),
+        SYS_GETXATTR => sys_getxattr(frame),
+        SYS_LGETXATTR => sys_lgetxattr(frame),
+        SYS_FGETXATTR => sys_fgetxattr(frame),
+        SYS_LISTXATTR => sys_listxattr(frame),
+        SYS_LLISTXATTR => sys_llistxattr(frame),
+        SYS_FLISTXATTR => sys_flistxattr(frame),
+        SYS_REMOVEXATTR => sys_removexattr(frame),
+        SYS_LREMOVEXATTR => sys_lremovexattr(frame),
+        SYS_FREMOVEXATTR => sys_fremovexattr(frame),
         SYS_GETCWD => sys_getcwd(frame),
 
         // Epoll & Duplication
@@ -41,6 +60,7 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         SYS_MKDIRAT => sys_mkdirat(frame),
         SYS_UNLINKAT => sys_unlinkat(frame),
         SYS_SYMLINKAT => sys_symlinkat(frame),
+        SYS_LINKAT => sys_linkat(frame),
 
         // 挂载/文件系统信息 (Mount/Filesystem Info)
         SYS_MOUNT => sys_mount(frame),
@@ -55,10 +75,15 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
 
         // 文件描述符操作 (File Descriptor Operations)
         SYS_OPENAT => sys_openat(frame),
+        SYS_OPENAT2 => sys_openat2(frame),
         SYS_CLOSE => sys_close(frame),
         SYS_PIPE2 => sys_pipe2(frame),
+        SYS_EVENTFD2 => sys_eventfd2(frame),
         SYS_GETDENTS64 => sys_getdents64(frame),
         SYS_LSEEK => sys_lseek(frame),
+        SYS_INOTIFY_INIT1 => sys_inotify_init1(frame),
+        SYS_INOTIFY_ADD_WATCH => sys_inotify_add_watch(frame),
+        SYS_INOTIFY_RM_WATCH => sys_inotify_rm_watch(frame),
 
         // I/O 操作 (Input/Output Operations)
         SYS_READ => sys_read(frame),
@@ -70,8 +95,12 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         SYS_PREADV => sys_preadv(frame),
         SYS_PWRITEV => sys_pwritev(frame),
         SYS_SENDFILE => sys_sendfile(frame),
+        SYS_SPLICE => sys_splice(frame),
         SYS_PSELECT6 => sys_pselect6(frame),
         SYS_PPOLL => sys_ppoll(frame),
+        SYS_EPOLL_CREATE1 => sys_epoll_create1(frame),
+        SYS_EPOLL_CTL => sys_epoll_ctl(frame),
+        SYS_EPOLL_PWAIT => sys_epoll_pwait(frame),
 
         // 文件元数据与同步 (File Metadata and Synchronization)
         SYS_READLINKAT => sys_readlinkat(frame),
@@ -93,6 +122,7 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         SYS_FUTEX => sys_futex(frame),
         SYS_SET_ROBUST_LIST => sys_set_robust_list(frame),
         SYS_GET_ROBUST_LIST => sys_get_robust_list(frame),
+        SYS_RSEQ => sys_rseq(frame),
         SYS_NANOSLEEP => sys_nanosleep(frame),
         SYS_GETITIMER => sys_getitimmer(frame),
         SYS_SETITIMER => sys_setitimmer(frame),
@@ -101,6 +131,10 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         SYS_CLOCK_SETTIME => sys_clock_settime(frame),
         SYS_CLOCK_GETTIME => sys_clock_gettime(frame),
         SYS_CLOCK_GETRES => sys_clock_getres(frame),
+        SYS_CLOCK_NANOSLEEP => sys_clock_nanosleep(frame),
+        SYS_CLOCK_ADJTIME => sys_clock_adjtime(frame),
+        SYS_ADJTIMEX => sys_adjtimex(frame),
+        SYS_GETTIMEOFDAY => sys_gettimeofday(frame),
         SYS_SYSLOG => sys_syslog(frame),
 
         // 信号 (Signals)
@@ -114,6 +148,7 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         SYS_RT_SIGPENDING => sys_rt_sigpending(frame),
         SYS_RT_SIGTIMEDWAIT => sys_rt_sigtimedwait(frame),
         SYS_RT_SIGRETURN => sys_rt_sigreturn(frame),
+        SYS_RESTART_SYSCALL => sys_restart_syscall(frame),
 
         // 进程属性 (Process Attributes)
         SYS_REBOOT => sys_reboot(frame),
@@ -141,6 +176,8 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         SYS_GETEGID => sys_getegid(frame),
         SYS_GETTID => sys_gettid(frame),
         SYS_SYSINFO => sys_sysinfo(frame),
+        SYS_GETCPU => sys_getcpu(frame),
+        SYS_PRCTL => sys_prctl(frame),
 
         // 网络 (Networking/Sockets)
         SYS_SOCKET => sys_socket(frame),
@@ -158,6 +195,7 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
 
         // 进程创建/执行 (Process Creation/Execution)
         SYS_CLONE => sys_clone(frame),
+        SYS_UNSHARE => sys_unshare(frame),
         SYS_EXECVE => sys_execve(frame),
 
         // 网络/I/O (续)
@@ -172,6 +210,14 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         SYS_MUNMAP => sys_munmap(frame),
         SYS_MMAP => sys_mmap(frame),
         SYS_MPROTECT => sys_mprotect(frame),
+        SYS_MREMAP => sys_mremap(frame),
+        SYS_MADVISE => sys_madvise(frame),
+        SYS_MLOCK => sys_mlock(frame),
+        SYS_MLOCK2 => sys_mlock2(frame),
+        SYS_MUNLOCK => sys_munlock(frame),
+        SYS_MLOCKALL => sys_mlockall(frame),
+        SYS_MUNLOCKALL => sys_munlockall(frame),
+        SYS_USERFAULTFD => sys_userfaultfd(frame),
 
         // 文件系统同步 (续)
         SYS_SYNCFS => sys_syncfs(frame),
@@ -182,6 +228,17 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         // 随机数与内存文件
         SYS_GETRANDOM => sys_getrandom(frame),
 
+        // 安全 (Security)
+        SYS_SECCOMP => sys_seccomp(frame),
+        SYS_LANDLOCK_CREATE_RULESET => sys_landlock_create_ruleset(frame),
+        SYS_LANDLOCK_ADD_RULE => sys_landlock_add_rule(frame),
+        SYS_LANDLOCK_RESTRICT_SELF => sys_landlock_restrict_self(frame),
+
+        // 异步 I/O (io_uring)
+        SYS_IO_URING_SETUP => sys_io_uring_setup(frame),
+        SYS_IO_URING_ENTER => sys_io_uring_enter(frame),
+        SYS_IO_URING_REGISTER => sys_io_uring_register(frame),
+
         // 扩展文件元数据
         SYS_STATX => sys_statx(frame),
 
@@ -201,6 +258,7 @@ pub fn dispatch_syscall(frame: &mut TrapFrame) {
         }
     }
     crate::pr_debug!("syscall exit, return: {}", frame.regs[4] as isize);
+    crate::security::audit::syscall_exit(syscall_id, args, frame.regs[4] as isize);
 }
 
 /// 宏：实现系统调用函数的自动包装器 (LoongArch 版)
@@ -211,7 +211,7 @@ pub const SYS_PRCTL: usize = 167;
 pub const SYS_GETCPU: usize = 168;
 pub const SYS_GETTIMEOFDAY: usize = 169;
 pub const SYS_SETTIMEOFDAY: usize = 170;
-pub const SYS_ADJTIME: usize = 171;
+pub const SYS_ADJTIMEX: usize = 171;
 pub const SYS_GETPID: usize = 172;
 pub const SYS_GETPPID: usize = 173;
 pub const SYS_GETUID: usize = 174;
@@ -251,6 +251,16 @@ pub const SYS_MMAP: usize = 222;
 pub const SYS_MPROTECT: usize = 226;
 pub const SYS_MUNMAP: usize = 215;
 pub const SYS_BRK: usize = 214;
+pub const SYS_MREMAP: usize = 216;
+pub const SYS_MADVISE: usize = 233;
+pub const SYS_MLOCK: usize = 228;
+pub const SYS_MUNLOCK: usize = 229;
+pub const SYS_MLOCKALL: usize = 230;
+pub const SYS_MUNLOCKALL: usize = 231;
+pub const SYS_MLOCK2: usize = 284;
+
+/// 时间 (续)
+pub const SYS_CLOCK_ADJTIME: usize = 266;
 
 /// 文件系统同步 (续)
 pub const SYS_SYNCFS: usize = 267;
@@ -258,11 +268,33 @@ pub const SYS_SYNCFS: usize = 267;
 /// 调度 (续)
 pub const SYS_RENAMEAT2: usize = 276;
 
+/// 安全 (Security)
+pub const SYS_SECCOMP: usize = 277;
+
 /// 随机数与内存文件
 pub const SYS_GETRANDOM: usize = 278;
 
+/// 内存故障处理
+pub const SYS_USERFAULTFD: usize = 282;
+
 /// 扩展文件元数据 (Extended File Attributes)
 pub const SYS_STATX: usize = 291;
 
+/// 可重启序列
+pub const SYS_RSEQ: usize = 293;
+
+/// 异步 I/O (io_uring)
+pub const SYS_IO_URING_SETUP: usize = 425;
+pub const SYS_IO_URING_ENTER: usize = 426;
+pub const SYS_IO_URING_REGISTER: usize = 427;
+
+/// 带扩展参数的 openat
+pub const SYS_OPENAT2: usize = 437;
+
+/// Landlock
+pub const SYS_LANDLOCK_CREATE_RULESET: usize = 444;
+pub const SYS_LANDLOCK_ADD_RULE: usize = 445;
+pub const SYS_LANDLOCK_RESTRICT_SELF: usize = 446;
+
 /// 获取网络接口地址列表 (非标准系统调用)
 pub const SYS_GETIFADDRS: usize = 1000;
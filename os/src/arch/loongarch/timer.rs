@@ -24,7 +24,7 @@ pub fn init() {
     // 允许外部在平台层设置真实频率；此处仅开启本地定时器中断
     unsafe { enable_timer_interrupt() };
     earlyprintln!("[Timer] Timer interrupt enabled");
-    set_next_trigger();
+    crate::kernel::tick_init();
 }
 
 /// 读取当前时间（硬件计数器）
@@ -47,9 +47,13 @@ pub fn clock_freq() -> usize {
     CLOCK_FREQ.load(Ordering::Relaxed)
 }
 
-/// 设置下一次定时器中断
-pub fn set_next_trigger() {
-    let delta = (clock_freq() / TICKS_PER_SEC).max(1);
+/// 设置下一次定时器中断的绝对时间（硬件时钟周期数）
+///
+/// 硬件定时器按倒计时工作，这里换算为相对当前时间的周期数；已经过期的时间点立即触发。
+pub fn set_next_event(deadline: usize) {
+    let delta = deadline
+        .saturating_sub(get_time())
+        .clamp(1, u32::MAX as usize);
 
     unsafe {
         // 1. 先彻底关闭定时器并清除周期模式 (TCFG bit 0 and 1 = 0) 防止配置过程中的竞争
@@ -81,5 +85,5 @@ pub fn ack_timer_interrupt() {
 
 /// 获取当前时间（毫秒）
 pub fn get_time_ms() -> usize {
-    get_ticks() * 1000 / TICKS_PER_SEC
+    (crate::kernel::hrtimer::ktime_get() / 1_000_000) as usize
 }
@@ -1,32 +1,104 @@
-//! SUM 位保护器（LoongArch 占位实现）
+//! 用户内存访问守卫（LoongArch 实现）
 //!
-//! LoongArch 平台目前未实现用户内存访问的硬件开关，本守卫提供与 RISC-V
-//! 版本一致的接口以便上层代码编译。未来接入实际 CSR 控制时可在此处完善。
+//! LoongArch 没有与 RISC-V SUM 位对应的硬件开关，内核态始终可以访问用户地址。
+//! 这里以每 CPU 的嵌套深度记录访问窗口，提供与 RISC-V 版本一致的接口。
+//!
+//! 与 RISC-V 的差距：窗口外对**已映射**用户页的访问不会产生异常，因此
+//! `user-access-debug` 模式在 LoongArch 上无法像 RISC-V 那样让任意窗口外的解引用
+//! 当场报错。目前只能发现两类问题：
+//! - 窗口外触发缺页 / 写时复制等异常时，陷阱处理不会修复，而是报告“outside SumGuard”、
+//!   打印调用栈并 panic；
+//! - [`SumGuard::assert_inactive`] 在返回用户态时发现未配对的守卫。
+//!
+//! 要完全覆盖需要让用户页表项对内核不可访问（例如置 RPLV 位），守卫打开窗口时再放行，
+//! 目前尚未实现。
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::arch::kernel::cpu::cpu_id;
+use crate::config::MAX_CPU_COUNT;
 
-/// RAII 样式的 SUM 位守卫
-#[derive(Debug, Default, Clone)]
+/// 每个 CPU 当前嵌套持有的守卫数
+static DEPTH: [AtomicUsize; MAX_CPU_COUNT] = [const { AtomicUsize::new(0) }; MAX_CPU_COUNT];
+
+fn depth() -> &'static AtomicUsize {
+    &DEPTH[cpu_id() % MAX_CPU_COUNT]
+}
+
+/// RAII 样式的用户内存访问守卫
+#[derive(Debug)]
 pub struct SumGuard {
     was_enabled: bool,
 }
 
 impl SumGuard {
-    /// 创建新的守卫。
-    ///
-    /// 当前实现仅记录占位状态，未对硬件寄存器进行修改。
+    /// 创建新的守卫，打开本 CPU 的访问窗口。
     #[inline]
     pub fn new() -> Self {
-        Self { was_enabled: true }
+        let was_enabled = depth().fetch_add(1, Ordering::Relaxed) != 0;
+        Self { was_enabled }
     }
 
-    /// 返回守卫创建前是否认为 SUM 已开启。
+    /// 返回守卫创建前访问窗口是否已打开。
     #[inline]
     pub fn was_enabled(&self) -> bool {
         self.was_enabled
     }
+
+    /// 当前是否处于用户内存访问窗口内
+    #[inline]
+    pub fn is_active() -> bool {
+        depth().load(Ordering::Relaxed) != 0
+    }
+
+    /// 拆解为创建前的窗口状态，不关闭窗口
+    ///
+    /// 之后必须用 [`SumGuard::from_raw`] 重建并丢弃。
+    #[inline]
+    pub fn into_raw(self) -> bool {
+        let was_enabled = self.was_enabled;
+        core::mem::forget(self);
+        was_enabled
+    }
+
+    /// 由 [`SumGuard::into_raw`] 的返回值重建守卫
+    ///
+    /// # Safety
+    ///
+    /// `was_enabled` 必须来自同一 CPU 上尚未重建的 `into_raw`。
+    #[inline]
+    pub unsafe fn from_raw(was_enabled: bool) -> Self {
+        Self { was_enabled }
+    }
+
+    /// 任务切换前调用：取走本 CPU 的窗口深度，返回值交给切回后的 [`SumGuard::resume`]
+    #[inline]
+    pub fn suspend() -> usize {
+        depth().swap(0, Ordering::Relaxed)
+    }
+
+    /// 任务切回后调用：恢复 [`SumGuard::suspend`] 保存的窗口深度
+    #[inline]
+    pub fn resume(saved: usize) {
+        depth().store(saved, Ordering::Relaxed);
+    }
+
+    /// 调试模式（`user-access-debug`）下检查访问窗口已关闭，否则打印调用栈并 panic
+    #[inline]
+    pub fn assert_inactive(_context: &str) {
+        #[cfg(feature = "user-access-debug")]
+        if Self::is_active() {
+            crate::earlyprintln!("[user-access] guard leaked at {}", _context);
+            crate::util::backtrace::print_backtrace();
+            panic!("user access window leaked at {}", _context);
+        }
+    }
 }
 
 impl Drop for SumGuard {
-    /// 作用域结束时恢复占位状态。
+    /// 作用域结束时关闭本层访问窗口。
     #[inline]
-    fn drop(&mut self) {}
+    fn drop(&mut self) {
+        depth().fetch_sub(1, Ordering::Relaxed);
+    }
 }
@@ -60,6 +60,12 @@ impl TrapFrame {
         ]
     }
 
+    /// 按系统调用约定设置调用号 (a7) 与参数 (a0-a5)
+    pub fn set_syscall(&mut self, id: usize, args: [usize; 6]) {
+        self.regs[11] = id;
+        self.regs[4..10].copy_from_slice(&args);
+    }
+
     /// 设置系统调用返回值
     pub fn set_syscall_ret(&mut self, ret: usize) {
         self.regs[4] = ret; // a0
@@ -113,6 +119,12 @@ impl TrapFrame {
         self.regs[6] = val;
     }
 
+    /// 设置系统调用号寄存器 (a7)
+    #[inline]
+    pub fn set_a7(&mut self, val: usize) {
+        self.regs[11] = val;
+    }
+
     /// 设置返回地址寄存器 (ra)
     #[inline]
     pub fn set_ra(&mut self, val: usize) {
@@ -3,16 +3,15 @@
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use crate::arch::constant::{
-    CSR_BADI, CSR_BADV, CSR_CRMD_PLV_MASK, CSR_EENTRY, CSR_ESTAT_IS_MASK, CSR_TLBRENT,
+    CSR_BADI, CSR_BADV, CSR_CRMD_PLV_MASK, CSR_EENTRY, CSR_ESTAT_IS_MASK, CSR_TLBRENT, USER_TOP,
 };
 use crate::arch::syscall::dispatch_syscall;
-use crate::arch::timer::{
-    TIMER_TICKS, ack_timer_interrupt, clock_freq, get_time, set_next_trigger,
-};
-use crate::arch::trap::restore;
+use crate::arch::timer::ack_timer_interrupt;
+use crate::arch::trap::{SumGuard, restore};
 use crate::earlyprintln;
 use crate::ipc::check_signal;
-use crate::kernel::{TIMER, TIMER_QUEUE, schedule, send_signal_process, wake_up_with_block};
+use crate::kernel::schedule;
+use crate::util::backtrace::print_backtrace_from;
 
 use super::TrapFrame;
 
@@ -25,7 +24,12 @@ static FIRST_USER_TIMER_LOGGED: AtomicBool = AtomicBool::new(false);
 static USER_SYSCALL_LOG_BUDGET: AtomicUsize = AtomicUsize::new(16);
 
 const ECODE_SYSCALL: usize = 0xb; // LoongArch syscall 异常码
+const ECODE_PIL: usize = 0x1; // load 操作页无效例外
+const ECODE_PIS: usize = 0x2; // store 操作页无效例外
+const ECODE_PIF: usize = 0x3; // 取指操作页无效例外
+const ECODE_PME: usize = 0x4; // 页修改例外：写入 D 位为 0 的页
 const TIMER_INT_BIT: usize = 1 << 11; // ESTAT.IS 中的本地定时器位
+const IPI_INT_BIT: usize = 1 << 12; // ESTAT.IS 中的核间中断位
 
 unsafe extern "C" {
     unsafe fn __restore(tf: &TrapFrame);
@@ -66,6 +70,9 @@ pub extern "C" fn trap_handler(trap_frame: &mut TrapFrame) {
 
     if (prmd & CSR_CRMD_PLV_MASK) != 0 {
         user_trap(estat, era, trap_frame);
+        SumGuard::assert_inactive("return to user");
+        // 须在投递信号前处理，信号帧里保存的应是中止后的返回地址
+        crate::kernel::rseq_handle_notify_resume();
     } else {
         kernel_trap(estat, era, trap_frame);
     }
@@ -184,7 +191,25 @@ fn user_trap(estat: usize, era: usize, trap_frame: &mut TrapFrame) {
                 crate::pr_debug!("[user_trap] syscall id={}, era={:#x}", syscall_id, era);
             }
             trap_frame.era = era.wrapping_add(4);
+            let orig_a0 = trap_frame.get_a0();
             dispatch_syscall(trap_frame);
+            crate::ipc::handle_syscall_restart(trap_frame, orig_a0);
+        }
+        ECODE_PIL | ECODE_PIS | ECODE_PIF
+            if crate::mm::swap::handle_swap_fault(read_badv(), false) =>
+        {
+            // 换出的页已换入，重新执行出错的指令
+        }
+        ECODE_PME if crate::mm::handle_cow_fault(read_badv(), false) => {
+            // 写时复制缺页已解决，重新执行出错的存储指令
+        }
+        code @ (ECODE_PIL | ECODE_PIS | ECODE_PIF)
+            if crate::mm::userfaultfd::handle_userfault(read_badv(), code == ECODE_PIS) =>
+        {
+            // 缺页交给 userfaultfd 处理完毕（或等待被打断），重新执行出错的指令
+        }
+        ECODE_PIL | ECODE_PIS | ECODE_PIF if crate::mm::handle_demand_fault(read_badv(), false) => {
+            // 被 madvise 丢弃的页已重新填充，重新执行出错的指令
         }
         _ => user_panic(estat, era, trap_frame),
     }
@@ -203,6 +228,50 @@ fn kernel_trap(estat: usize, era: usize, tf: &TrapFrame) {
         core::arch::asm!("csrrd {0}, {csr}", out(reg) badv, csr = const CSR_BADV, options(nostack, preserves_flags));
         core::arch::asm!("csrrd {0}, {csr}", out(reg) badi, csr = const CSR_BADI, options(nostack, preserves_flags));
     }
+    // 内核经 SumGuard 访问用户内存时命中换出的页
+    if (ecode == ECODE_PIL || ecode == ECODE_PIS)
+        && badv <= USER_TOP
+        && SumGuard::is_active()
+        && crate::mm::swap::handle_swap_fault(badv, true)
+    {
+        return;
+    }
+    // 内核经 SumGuard 写用户内存时命中写时复制页
+    if ecode == ECODE_PME
+        && badv <= USER_TOP
+        && SumGuard::is_active()
+        && crate::mm::handle_cow_fault(badv, true)
+    {
+        return;
+    }
+    // 内核经 SumGuard 访问用户内存时命中被 madvise 丢弃的页
+    if (ecode == ECODE_PIL || ecode == ECODE_PIS)
+        && badv <= USER_TOP
+        && SumGuard::is_active()
+        && crate::mm::handle_demand_fault(badv, true)
+    {
+        return;
+    }
+    // 访问内核栈下方的保护页：内核栈溢出，陷阱入口已切换到溢出栈
+    if matches!(ecode, ECODE_PIL | ECODE_PIS | ECODE_PIF)
+        && crate::mm::kstack::is_kstack_guard(badv)
+    {
+        print_backtrace_from(tf.regs[22]);
+        crate::mm::kstack::kstack_overflow(badv, era);
+    }
+    if badv != 0 && badv <= USER_TOP {
+        // LoongArch 没有硬件访问窗口，只能报告故障时守卫是否持有
+        earlyprintln!(
+            "[kernel_trap] user address {:#x} accessed {} SumGuard",
+            badv,
+            if SumGuard::is_active() {
+                "inside"
+            } else {
+                "outside"
+            }
+        );
+    }
+    print_backtrace_from(tf.regs[22]);
     panic!(
         "Unexpected trap in kernel: ecode={:#x}, estat={:#x}, era={:#x}, badv={:#x}, badi={:#x}, crmd={:#x}, prmd={:#x}, a0={:#x}, a1={:#x}",
         ecode,
@@ -217,12 +286,22 @@ fn kernel_trap(estat: usize, era: usize, tf: &TrapFrame) {
     );
 }
 
+fn read_badv() -> usize {
+    let badv: usize;
+    unsafe {
+        core::arch::asm!("csrrd {0}, {csr}", out(reg) badv, csr = const CSR_BADV, options(nostack, preserves_flags));
+    }
+    badv
+}
+
 fn handle_interrupt(estat: usize) {
     if estat & TIMER_INT_BIT != 0 {
         ack_timer_interrupt();
-        set_next_trigger();
         check_timer();
     }
+    if estat & IPI_INT_BIT != 0 {
+        crate::arch::ipi::handle_ipi();
+    }
 }
 
 fn user_panic(estat: usize, era: usize, trap_frame: &TrapFrame) {
@@ -250,24 +329,20 @@ fn user_panic(estat: usize, era: usize, trap_frame: &TrapFrame) {
     crate::kernel::terminate_task(128 + sig);
 }
 
-/// 处理时钟中断
+/// 处理时钟中断：执行到期的高精度定时器，经过时钟节拍时更新时间片
 fn check_timer() {
-    let _ticks = TIMER_TICKS.fetch_add(1, Ordering::Relaxed);
-    while let Some(task) = TIMER_QUEUE.lock().pop_due_task(get_time()) {
-        wake_up_with_block(task);
-    }
-    while let Some(entry) = TIMER.lock().pop_due_entry(get_time()) {
-        send_signal_process(&entry.task, entry.sig);
-        if !entry.it_interval.is_zero() {
-            let next_trigger = get_time() + entry.it_interval.into_freq(clock_freq());
-            TIMER.lock().push(next_trigger, entry);
-        }
+    crate::kernel::hrtimer::hrtimer_interrupt();
+    if !crate::kernel::take_tick() {
+        return;
     }
+    // 轮询式设备（如无中断的串口终端）的 poll/epoll 等待者每个节拍唤醒一次
+    crate::vfs::wake_tick_pollers();
     let should_preempt = {
         let mut sched = crate::kernel::current_scheduler().lock();
         sched.update_time_slice() && !sched.is_empty()
     };
-    if should_preempt {
+    // 禁止抢占期间（如 RCU 读临界区）不切换任务
+    if should_preempt && !crate::sync::preempt_disabled() {
         schedule();
     }
 }
@@ -25,6 +25,8 @@ pub use loongarch::{
 #[cfg(target_arch = "riscv64")]
 pub use riscv::{boot, constant, info, intr, ipi, kernel, lib, mm, platform, syscall, timer, trap};
 
+pub mod tlb;
+
 /// sync crate 的 ArchOps 实现
 struct SyncArchOps;
 
@@ -48,6 +50,16 @@ impl sync::ArchOps for SyncArchOps {
     fn max_cpu_count(&self) -> usize {
         unsafe { crate::kernel::NUM_CPU }
     }
+
+    fn cycles(&self) -> u64 {
+        self::timer::get_time() as u64
+    }
+
+    fn cpu_relax(&self) {
+        // 关中断自旋等锁时仍要响应 TLB 刷新请求，持锁者可能正在等本 CPU
+        self::tlb::handle_shootdown();
+        core::hint::spin_loop();
+    }
 }
 
 /// 全局 ArchOps 实例
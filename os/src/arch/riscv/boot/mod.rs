@@ -16,7 +16,7 @@
 //! 说明：多核上线与调度唤醒可能依赖 IPI（见 `os/src/arch/riscv/ipi.rs`）。
 
 use core::arch::global_asm;
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::Ordering;
 
 use alloc::sync::Arc;
 use riscv::register::sscratch;
@@ -28,14 +28,11 @@ use crate::{
     earlyprintln,
     ipc::{SignalHandlerTable, SignalPending},
     kernel::{
-        FsStruct, NUM_CPU, Scheduler, TASK_MANAGER, TaskManagerTrait, TaskStruct, current_cpu,
-        current_memory_space, current_task, kernel_execve, kthread_spawn, kworker,
-        sleep_task_with_block, time, yield_task,
-    },
-    mm::{
-        self,
-        frame_allocator::{alloc_contig_frames, alloc_frame},
+        FsStruct, NUM_CPU, Scheduler, TASK_MANAGER, TaskManagerTrait, TaskStruct, cpu_online_mask,
+        current_cpu, current_memory_space, current_task, kernel_execve, kthread_spawn, kworker,
+        set_cpu_online, sleep_task_with_block, time, yield_task,
     },
+    mm::{self, frame_allocator::alloc_frame, kstack::KernelStack},
     pr_debug, pr_err, pr_info, pr_warn,
     sync::SpinLock,
     uapi::{
@@ -48,12 +45,6 @@ use crate::{
 // Needed for Ppn::as_usize
 use mm::address::UsizeConvert;
 
-/// 已上线 CPU 位掩码
-///
-/// 每个位代表一个 CPU，位 i 为 1 表示 CPU i 已上线。
-/// 使用原子操作确保多核环境下的线程安全。
-static CPU_ONLINE_MASK: AtomicUsize = AtomicUsize::new(0);
-
 /// 从核启动标志（在 entry.S 中定义）
 ///
 /// 主核设置此标志为 1 后，所有从核将从 WFI 中唤醒并开始启动。
@@ -73,7 +64,7 @@ pub fn rest_init() {
     // init进程必须使用TID 1，不从分配器获取
     // TID分配器从2开始，所以idle任务会获得TID 2, 3, ...
     let tid = 1;
-    let kstack_tracker = alloc_contig_frames(4).expect("kthread_spawn: failed to alloc kstack");
+    let kstack = KernelStack::new().expect("kthread_spawn: failed to alloc kstack");
     let trap_frame_tracker = alloc_frame().expect("kthread_spawn: failed to alloc trap_frame");
     let fd_table = FDTable::new();
     let (stdin, stdout, stderr) = create_stdio_files();
@@ -108,7 +99,7 @@ pub fn rest_init() {
         tid,
         0,
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         Arc::new(SpinLock::new(SignalHandlerTable::new())),
         SignalFlags::empty(),
@@ -170,6 +161,7 @@ fn init() {
 
     // 启用中断（在设置好 trap 处理和 sscratch 之后）
     unsafe { intr::enable_interrupts() };
+    crate::arch::tlb::cpu_ready();
 
     create_kthreadd();
 
@@ -210,9 +202,20 @@ fn init() {
     // - rcS 会执行 `mount -t tmpfs none /dev`
     // - 内核在 mount("/dev") 的系统调用中对该挂载点做了特殊处理，会在挂载 tmpfs 后自动 init_dev()
 
+    // syscall-fuzz：文件系统就绪后直接运行系统调用模糊测试并关机，不进入用户态
+    #[cfg(feature = "syscall-fuzz")]
+    crate::test::sysfuzz::run_and_shutdown();
+
     // Always enter user-space BusyBox init. In OSCOMP mode, /tests is mounted by
     // init_oscomp_filesystems() and rcS is responsible for running the test scripts.
+    #[cfg(not(feature = "ltp"))]
     kernel_execve("/sbin/init", &["/sbin/init"], &[]);
+
+    // ltp：用 BusyBox sh 运行 LTP 子集（/ltp/run_ltp.sh 结束后自行关机）
+    #[cfg(feature = "ltp")]
+    kernel_execve("/bin/sh", &["/bin/sh", "/ltp/run_ltp.sh"], &[
+        "PATH=/ltp/bin:/bin:/sbin:/usr/bin",
+    ]);
 }
 
 /// 内核守护线程
@@ -220,6 +223,11 @@ fn init() {
 /// 负责创建内核任务，回收僵尸任务等工作
 fn kthreadd() {
     kthread_spawn(kworker);
+    kthread_spawn(crate::log::console_flusher);
+    kthread_spawn(crate::mm::ksm::ksmd);
+    if crate::log::netconsole::enabled() {
+        kthread_spawn(crate::log::netconsole_thread);
+    }
     loop {
         // 休眠等待任务
         sleep_task_with_block(current_task(), true);
@@ -230,7 +238,7 @@ fn kthreadd() {
 /// 创建内核守护线程 kthreadd
 fn create_kthreadd() {
     let tid = TASK_MANAGER.lock().allocate_tid();
-    let kstack_tracker = alloc_contig_frames(4).expect("kthread_spawn: failed to alloc kstack");
+    let kstack = KernelStack::new().expect("kthread_spawn: failed to alloc kstack");
     let trap_frame_tracker = alloc_frame().expect("kthread_spawn: failed to alloc trap_frame");
     let (uts, rlimit, fd_table, fs) = {
         let task = current_task();
@@ -247,7 +255,7 @@ fn create_kthreadd() {
         tid,
         0,
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         Arc::new(SpinLock::new(SignalHandlerTable::new())),
         SignalFlags::empty(),
@@ -277,12 +285,18 @@ pub fn main(hartid: usize) {
 
     // 初始化 sync crate 的架构操作（必须在任何使用 sync 原语之前）
     unsafe { crate::arch::init_sync_arch_ops() };
+    // Condvar 睡眠与唤醒使用的调度操作
+    unsafe { crate::sync::init_sync_sched_ops() };
 
     // 初始化日志系统（必须在使用 pr_* 宏之前）
     crate::log::init();
 
     earlyprintln!("[Boot] Hello, world!");
     earlyprintln!("[Boot] RISC-V Hart {} is up!", hartid);
+    if crate::mm::kaslr::kaslr_slide() != 0 {
+        // 不打印偏移本身，避免经日志泄露内核地址
+        earlyprintln!("[Boot] KASLR: kernel virtual base randomized");
+    }
 
     // 注册 mm crate 的配置和架构操作（必须在 phase1_early_parse() 之前，因为需要地址转换）
     unsafe {
@@ -328,6 +342,9 @@ pub fn main(hartid: usize) {
         earlyprintln!("[Boot] Activated kernel address space");
     }
 
+    // 启动时核对内核映射的 W^X 布局（boot_pagetable 的 RWX 大页此后不再使用）
+    crate::mm::check_wx_mappings();
+
     // 测试模式下：
     // - 提前注册 FsOps，避免 tests/ 中的 TmpFs/Ext4 等依赖 fs_ops() 时 panic。
     // - 提前创建并切换到一个可用的 current_task，避免 /proc/self 等依赖 current_task() 的用例直接 panic。
@@ -361,7 +378,14 @@ pub fn main(hartid: usize) {
     crate::device::init_device_ops();
 
     platform::init(); // 完整的平台初始化 (包括 device_tree::init())
+    crate::log::pstore::init_blk();
+    crate::mm::swap::init();
+    crate::mm::oom::init();
+    crate::vfs::init_dentry_shrinker();
+    crate::mm::compaction::init();
+    crate::mm::hugetlb::init();
     time::init();
+    crate::security::random::init();
 
     // 初始化 VFS 操作（必须在使用 VFS 之前）
     crate::vfs::init_vfs_ops();
@@ -426,10 +450,8 @@ mod tests {
     /// 测试 CPU 上线掩码（多核环境）
     #[test_case]
     fn test_cpu_online_mask() {
-        use core::sync::atomic::Ordering;
-
         let num_cpu = unsafe { crate::kernel::NUM_CPU };
-        let actual_mask = CPU_ONLINE_MASK.load(Ordering::Acquire);
+        let actual_mask = cpu_online_mask();
 
         // 在测试模式下，如果 CPU_ONLINE_MASK 为 0，说明 boot_secondary_cpus 未被调用
         // 这是正常的，因为测试框架跳过了正常的启动流程
@@ -462,17 +484,23 @@ mod tests {
 /// Idle循环：等待中断；被 S 态时钟中断唤醒后，trap_handler 会决定是否调度
 fn idle_loop() -> ! {
     loop {
-        // 确保中断启用
-        if !crate::arch::intr::are_interrupts_enabled() {
-            unsafe {
-                crate::arch::intr::enable_interrupts();
-            }
+        // 关中断后停止时钟节拍并等待：WFI 不受 sstatus.SIE 影响，
+        // 在 SIE 关闭时也会因待处理的中断返回，避免检查运行队列后丢失唤醒
+        unsafe {
+            crate::arch::intr::disable_interrupts();
         }
+        crate::kernel::tick_nohz_idle_enter();
 
         // 等待中断（timer或IPI会触发调度）
         unsafe {
             core::arch::asm!("wfi");
         }
+
+        // 先恢复时钟节拍，再开中断处理唤醒我们的中断
+        crate::kernel::tick_nohz_idle_exit();
+        unsafe {
+            crate::arch::intr::enable_interrupts();
+        }
     }
 }
 
@@ -486,7 +514,6 @@ fn create_idle_task(cpu_id: usize) -> crate::kernel::SharedTask {
     use crate::vfs::FDTable;
     use alloc::sync::Arc;
     use core::sync::atomic::Ordering;
-    use mm::frame_allocator::alloc_contig_frames;
     use uapi::resource::{INIT_RLIMITS, RlimitStruct};
     use uapi::signal::SignalFlags;
     use uapi::uts_namespace::UtsNamespace;
@@ -497,8 +524,7 @@ fn create_idle_task(cpu_id: usize) -> crate::kernel::SharedTask {
     let tid = TASK_MANAGER.lock().allocate_tid();
 
     // 分配最小资源
-    let kstack_tracker =
-        alloc_contig_frames(1).expect("Failed to allocate kernel stack for idle task");
+    let kstack = KernelStack::new().expect("Failed to allocate kernel stack for idle task");
     let trap_frame_tracker = alloc_frame().expect("Failed to allocate trap frame for idle task");
 
     // 创建最小化的任务结构
@@ -507,7 +533,7 @@ fn create_idle_task(cpu_id: usize) -> crate::kernel::SharedTask {
         tid, // pid = tid
         0,   // ppid = 0 (no parent)
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         Arc::new(SpinLock::new(SignalHandlerTable::new())),
         SignalFlags::empty(),
@@ -568,7 +594,7 @@ pub extern "C" fn secondary_start(hartid: usize) -> ! {
     }
 
     // 标记当前 CPU 上线
-    CPU_ONLINE_MASK.fetch_or(1 << hartid, Ordering::Release);
+    set_cpu_online(hartid, true);
 
     pr_info!("[SMP] CPU {} is online", hartid);
 
@@ -618,6 +644,7 @@ pub extern "C" fn secondary_start(hartid: usize) -> ! {
     unsafe {
         intr::enable_interrupts();
     }
+    crate::arch::tlb::cpu_ready();
 
     // 检查中断配置状态
     unsafe {
@@ -671,7 +698,7 @@ pub fn boot_secondary_cpus(num_cpus: usize) {
     if num_cpus <= 1 {
         pr_info!("[SMP] Single CPU mode, skipping secondary boot");
         // 标记主核在线
-        CPU_ONLINE_MASK.fetch_or(1, Ordering::Release);
+        set_cpu_online(0, true);
         unsafe { NUM_CPU = 1 };
         return;
     }
@@ -679,7 +706,7 @@ pub fn boot_secondary_cpus(num_cpus: usize) {
     pr_info!("[SMP] Booting up to {} secondary CPUs...", num_cpus - 1);
 
     // 主核标记在线
-    CPU_ONLINE_MASK.fetch_or(1, Ordering::Release);
+    set_cpu_online(0, true);
 
     // 尝试启动每个从核，记录预期应当在线的掩码（仅统计成功发起的启动请求）
     let mut expected_mask: usize = 1; // CPU0 已在线
@@ -717,9 +744,9 @@ pub fn boot_secondary_cpus(num_cpus: usize) {
     // 基于时间的超时等待（避免与主机性能相关的固定次数循环）
     // 设定 2 秒的上线等待窗口
     let deadline = get_time().saturating_add(clock_freq() * 2);
-    while CPU_ONLINE_MASK.load(Ordering::Acquire) != expected_mask {
+    while cpu_online_mask() != expected_mask {
         if get_time() >= deadline {
-            let current_mask = CPU_ONLINE_MASK.load(Ordering::Acquire);
+            let current_mask = cpu_online_mask();
             pr_warn!(
                 "[SMP] Timeout waiting secondary CPUs. Expected: {:#b}, got: {:#b}",
                 expected_mask,
@@ -731,7 +758,7 @@ pub fn boot_secondary_cpus(num_cpus: usize) {
     }
 
     // 以实际在线核数为准，更新 NUM_CPU，避免后续调度把任务分配到离线 CPU
-    let online_mask = CPU_ONLINE_MASK.load(Ordering::Acquire);
+    let online_mask = cpu_online_mask();
     let online_cnt = online_mask.count_ones() as usize;
     unsafe { NUM_CPU = core::cmp::max(online_cnt, 1) };
 
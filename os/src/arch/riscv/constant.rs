@@ -4,6 +4,9 @@
 /// 架构名称字符串
 pub const ARCH: &str = "riscv64";
 
+/// seccomp_data.arch 中报告的审计架构标识
+pub const AUDIT_ARCH: u32 = uapi::seccomp::AUDIT_ARCH_RISCV64;
+
 /// riscv sstatus 寄存器中 SIE 位的掩码
 pub const SSTATUS_SIE: usize = 1 << 1;
 /// riscv sstatus 寄存器中 SPIE 位的掩码
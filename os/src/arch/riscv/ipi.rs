@@ -103,15 +103,9 @@ pub fn send_reschedule_ipi(cpu: usize) {
 
 /// 广播 TLB 刷新 IPI
 ///
-/// 通知所有其他 CPU 刷新 TLB
+/// 通知所有其他 CPU 刷新整个 TLB，等它们全部完成后返回（见 [`crate::arch::tlb`]）
 pub fn send_tlb_flush_ipi_all() {
-    let current_cpu_id = super::kernel::cpu::cpu_id();
-    let num_cpu = unsafe { crate::kernel::NUM_CPU };
-    let mask = ((1 << num_cpu) - 1) & !(1 << current_cpu_id);
-
-    if mask != 0 {
-        send_ipi_many(mask, IpiType::TlbFlush);
-    }
+    crate::arch::tlb::shootdown(None);
 }
 
 /// 处理 IPI（在软件中断处理中调用）
@@ -144,10 +138,7 @@ pub fn handle_ipi() {
 
     // 处理 TLB 刷新 IPI
     if pending & (IpiType::TlbFlush as u32) != 0 {
-        // SAFETY: sfence.vma 是安全的 RISC-V 指令，用于刷新 TLB
-        unsafe {
-            core::arch::asm!("sfence.vma");
-        }
+        crate::arch::tlb::handle_shootdown();
     }
 
     // 处理停止 IPI
@@ -8,6 +8,7 @@ pub mod cpu;
 pub mod task;
 
 global_asm!(include_str!("switch.S"));
+global_asm!(include_str!("vdso.S"));
 
 unsafe extern "C" {
     /// 上下文切换函数
@@ -16,3 +17,15 @@ unsafe extern "C" {
     /// 然后从 new 指向的 context 结构体中恢复寄存器状态，切换到新任务执行。
     pub unsafe fn switch(old: *mut Context, new: *const Context);
 }
+
+unsafe extern "C" {
+    fn __vdso_start();
+    fn __vdso_end();
+}
+
+/// vDSO 镜像（页对齐的 ELF 共享对象，映射到用户地址空间的 `USER_VDSO_BASE`）
+pub fn vdso_image() -> &'static [u8] {
+    let start = __vdso_start as usize;
+    let end = __vdso_end as usize;
+    unsafe { core::slice::from_raw_parts(start as *const u8, end - start) }
+}
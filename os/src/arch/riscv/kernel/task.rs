@@ -17,9 +17,16 @@ use core::mem::size_of;
 use core::ptr;
 
 use alloc::vec::Vec;
-use riscv::register::sstatus;
+use uapi::auxv::{
+    AT_BASE, AT_CLKTCK, AT_EGID, AT_ENTRY, AT_EUID, AT_EXECFN, AT_FLAGS, AT_GID, AT_HWCAP, AT_NULL,
+    AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM, AT_PLATFORM, AT_RANDOM, AT_RSEQ_ALIGN,
+    AT_RSEQ_FEATURE_SIZE, AT_SECURE, AT_SYSINFO_EHDR, AT_UID, USER_HZ,
+};
+use uapi::rseq::{ORIG_RSEQ_SIZE, RSEQ_FEATURE_SIZE};
 
 use crate::arch::constant::STACK_ALIGN_MASK;
+use crate::arch::trap::SumGuard;
+use crate::config::{PAGE_SIZE, USER_VDSO_BASE};
 
 /// 为新任务构造用户态初始栈布局（argv/envp/auxv）。
 ///
@@ -43,9 +50,7 @@ pub fn setup_stack_layout(
     let mut sp = sp;
     let mut arg_ptrs: Vec<usize> = Vec::with_capacity(argv.len());
     let mut env_ptrs: Vec<usize> = Vec::with_capacity(envp.len());
-    unsafe {
-        sstatus::set_sum();
-    }
+    let user_access = SumGuard::new();
 
     for &env in envp.iter().rev() {
         let bytes = env.as_bytes();
@@ -77,10 +82,9 @@ pub fn setup_stack_layout(
     // 0. 写入 auxv (Auxiliary Vector)
     // 必须位于 envp NULL 之后（高地址），但在 envp 数组之前。
     // 常见的 auxv 条目：AT_PAGESZ(6), AT_NULL(0), AT_RANDOM(25)
-    let random_bytes = [
-        0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45,
-        0x67,
-    ];
+    // AT_RANDOM 指向的 16 字节供 libc 初始化栈保护与指针混淆
+    let mut random_bytes = [0u8; 16];
+    crate::security::random::get_random_bytes(&mut random_bytes);
     let random_ptr = sp - 16;
     unsafe { ptr::copy_nonoverlapping(random_bytes.as_ptr(), random_ptr as *mut u8, 16) };
     sp = random_ptr;
@@ -99,24 +103,27 @@ pub fn setup_stack_layout(
     let execfn = arg_ptrs.last().copied().unwrap_or(0);
 
     let auxv = [
-        (3, phdr_addr),     // AT_PHDR
-        (4, phent),         // AT_PHENT
-        (5, phnum),         // AT_PHNUM
-        (6, 4096),          // AT_PAGESZ
-        (7, at_base),       // AT_BASE
-        (8, 0),             // AT_FLAGS
-        (9, at_entry),      // AT_ENTRY
-        (11, 0),            // AT_UID
-        (12, 0),            // AT_EUID
-        (13, 0),            // AT_GID
-        (14, 0),            // AT_EGID
-        (15, platform_ptr), // AT_PLATFORM
-        (16, 0),            // AT_HWCAP
-        (17, 100),          // AT_CLKTCK
-        (23, 0),            // AT_SECURE
-        (25, random_ptr),   // AT_RANDOM
-        (31, execfn),       // AT_EXECFN
-        (0, 0),             // AT_NULL
+        (AT_PHDR, phdr_addr),
+        (AT_PHENT, phent),
+        (AT_PHNUM, phnum),
+        (AT_PAGESZ, PAGE_SIZE),
+        (AT_BASE, at_base),
+        (AT_FLAGS, 0),
+        (AT_ENTRY, at_entry),
+        (AT_UID, 0),
+        (AT_EUID, 0),
+        (AT_GID, 0),
+        (AT_EGID, 0),
+        (AT_PLATFORM, platform_ptr),
+        (AT_HWCAP, 0),
+        (AT_CLKTCK, USER_HZ),
+        (AT_SECURE, 0),
+        (AT_RSEQ_FEATURE_SIZE, RSEQ_FEATURE_SIZE),
+        (AT_RSEQ_ALIGN, ORIG_RSEQ_SIZE as usize),
+        (AT_RANDOM, random_ptr),
+        (AT_EXECFN, execfn),
+        (AT_SYSINFO_EHDR, USER_VDSO_BASE),
+        (AT_NULL, 0),
     ];
 
     // Debug print auxv
@@ -190,10 +197,8 @@ pub fn setup_stack_layout(
         ptr::write(sp as *mut usize, argc);
     }
 
-    // 拷贝完成，恢复 SUM
-    unsafe {
-        sstatus::clear_sum();
-    }
+    // 拷贝完成，关闭用户内存访问窗口
+    drop(user_access);
 
     // 6. 最终 sp 应该已经是 16 字节对齐的
     // sp &= !STACK_ALIGN_MASK;
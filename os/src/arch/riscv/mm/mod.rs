@@ -13,18 +13,23 @@
 //! # 地址转换
 //!
 //! 此模块使用**直接映射 (direct mapping)** 进行地址转换：
-//! - 虚拟地址起始点 (Virtual address start): `0xffff_ffc0_0000_0000`
-//! - **提取物理地址** 使用与 `PADDR_MASK` 进行**位与 (bitwise AND)** 操作
-//! - **创建虚拟地址** 使用与 `VADDR_START` 进行**位或 (bitwise OR)** 操作
+//! - 虚拟地址起始点 (Virtual address start): `0xffff_ffc0_0000_0000` 加上 KASLR 偏移
+//! - **提取物理地址** 先减去偏移，再与 `PADDR_MASK` 进行**位与 (bitwise AND)** 操作
+//! - **创建虚拟地址** 与 `VADDR_START` 进行**位或 (bitwise OR)** 操作，再加上偏移
+//!
+//! KASLR 偏移以 1 GiB 为单位（启动页表用 1 GiB 大页映射内核），见 [`crate::mm::kaslr`]。
 
 mod page_table; // 模块：页表
 mod page_table_entry; // 模块：页表项
 
-pub use page_table::{PageTableInner, TlbBatchContext}; // 导出：页表内部结构和TLB批处理上下文
+pub use page_table::{PageTableInner, TlbBatchContext, flush_tlb_all, flush_tlb_entry, tlb_entry}; // 导出：页表内部结构和TLB批处理上下文
 pub use page_table_entry::PageTableEntry; // 导出：页表项结构体
 
+use mm::address::{Ppn, Vpn};
 use mm::{ArchMmOps, TlbBatchContextTrait, TlbBatchContextWrapper};
 
+use crate::mm::kaslr::kaslr_slide;
+
 /// SV39 中虚拟地址空间的起始地址
 ///
 /// 此常量定义了内核高位虚拟地址空间的起始位置。
@@ -37,6 +42,20 @@ pub const VADDR_START: usize = 0xffff_ffc0_0000_0000;
 /// SV39 中的物理地址空间大小。
 pub const PADDR_MASK: usize = 0x0000_003f_ffff_ffff;
 
+/// KASLR 偏移的对齐（启动页表中一个 1 GiB 大页）
+pub const KASLR_ALIGN: usize = 1 << 30;
+
+/// KASLR 偏移的候选个数
+///
+/// 高半区共 256 GiB，最后 1 GiB 留给内核栈区，为物理内存保留 64 GiB 的直接映射，
+/// 偏移最多 191 GiB。
+pub const KASLR_SLOTS: usize = 191;
+
+/// 内核栈区的起始地址：高半区的最后 1 GiB（根页表的最后一项），不属于线性映射
+///
+/// 见 [`crate::mm::kstack`]。
+pub const KSTACK_AREA_START: usize = 0xffff_ffff_c000_0000;
+
 /// 转换虚拟地址到物理地址
 ///
 /// # 参数
@@ -50,8 +69,9 @@ pub const PADDR_MASK: usize = 0x0000_003f_ffff_ffff;
 /// # 注意
 ///
 /// 此函数必须在所有架构特定的内存管理模块中实现。
-pub const unsafe fn vaddr_to_paddr(vaddr: usize) -> usize {
-    vaddr & PADDR_MASK
+#[inline]
+pub unsafe fn vaddr_to_paddr(vaddr: usize) -> usize {
+    vaddr.wrapping_sub(kaslr_slide()) & PADDR_MASK
 }
 
 /// 转换物理地址到虚拟地址
@@ -67,8 +87,9 @@ pub const unsafe fn vaddr_to_paddr(vaddr: usize) -> usize {
 /// # 注意
 ///
 /// 此函数必须在所有架构特定的内存管理模块中实现。
-pub const fn paddr_to_vaddr(paddr: usize) -> usize {
-    paddr | VADDR_START
+#[inline]
+pub fn paddr_to_vaddr(paddr: usize) -> usize {
+    (paddr | VADDR_START) + kaslr_slide()
 }
 
 // ============ ArchMmOps trait 实现 ============
@@ -78,11 +99,21 @@ struct RiscvMmOps;
 
 impl ArchMmOps for RiscvMmOps {
     unsafe fn vaddr_to_paddr(&self, vaddr: usize) -> usize {
-        vaddr & PADDR_MASK
+        unsafe { vaddr_to_paddr(vaddr) }
     }
 
     fn paddr_to_vaddr(&self, paddr: usize) -> usize {
-        paddr | VADDR_START
+        paddr_to_vaddr(paddr)
+    }
+
+    fn dma_coherent_vaddr(&self, paddr: usize) -> usize {
+        // virt 平台的设备访问与 CPU 缓存一致，直接使用线性映射
+        paddr_to_vaddr(paddr)
+    }
+
+    fn phys_to_dma(&self, paddr: usize) -> usize {
+        // 没有 IOMMU，总线地址就是物理地址
+        paddr
     }
 
     fn sigreturn_trampoline_bytes(&self) -> &'static [u8] {
@@ -103,6 +134,10 @@ impl ArchMmOps for RiscvMmOps {
 }
 
 impl TlbBatchContextTrait for TlbBatchContext {
+    fn add_page(&mut self, root: Ppn, vpn: Vpn) {
+        TlbBatchContext::add_page(self, root, vpn);
+    }
+
     fn flush(&mut self) {
         TlbBatchContext::flush(self);
     }
@@ -1,6 +1,6 @@
 // TODO: 这个模块的安全性论证没有完成
 use super::PageTableEntry;
-use crate::arch::ipi::send_tlb_flush_ipi_all;
+use crate::arch::tlb::{self, MAX_FLUSH_PAGES};
 use alloc::vec::Vec;
 use mm::TlbBatchContextWrapper;
 use mm::address::{ConvertablePaddr, Paddr, PageNum, Ppn, UsizeConvert, Vaddr, Vpn};
@@ -146,14 +146,7 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
 
             if current_level == level {
                 // 已到达目标级别
-                // TODO(暂时注释): 当前仅支持4K页
-                // let page_size = match level {
-                //     2 => PageSize::Size1G,
-                //     1 => PageSize::Size2M,
-                //     0 => PageSize::Size4K,
-                //     _ => unreachable!(),
-                // };
-                let page_size = PageSize::Size4K; // 仅支持4K页
+                let page_size = PageSize::from_level(level)?;
                 return Some((*pte, page_size));
             }
 
@@ -171,26 +164,11 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         // 页内偏移量：低 12 位
         let offset = vaddr.as_usize() & 0xfff;
 
-        // TODO(暂时注释): 当前仅支持4K页，大页translation逻辑已禁用
+        // walk 返回的已是 vpn 所在 4K 页的物理页号（大页内的偏移已计入）
         match self.walk(vpn) {
-            Ok((ppn, page_size, _flags)) => {
-                let paddr_base = match page_size {
-                    PageSize::Size4K => ppn.start_addr().as_usize(),
-                    // TODO(暂时注释): 大页偏移计算
-                    // PageSize::Size2M => {
-                    //     // 对于 2M 页，保留 vaddr 的低 21 位作为页内偏移
-                    //     let offset_2m = vaddr.as_usize() & 0x1f_ffff;
-                    //     ppn.start_addr().as_usize() + offset_2m - offset
-                    // }
-                    // PageSize::Size1G => {
-                    //     // 对于 1G 页，保留 vaddr 的低 30 位作为页内偏移
-                    //     let offset_1g = vaddr.as_usize() & 0x3fff_ffff;
-                    //     ppn.start_addr().as_usize() + offset_1g - offset
-                    // }
-                    _ => ppn.start_addr().as_usize(), // 默认按 4K 页处理基地址
-                };
+            Ok((ppn, _page_size, _flags)) => {
                 // 物理地址 = 物理页基地址 + 页内偏移
-                Some(Paddr::from_usize(paddr_base + offset))
+                Some(Paddr::from_usize(ppn.start_addr().as_usize() + offset))
             }
             Err(_) => None,
         }
@@ -201,7 +179,7 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         &mut self,
         vpn: Vpn,
         ppn: Ppn,
-        _page_size: PageSize,
+        page_size: PageSize,
         flags: UniversalPTEFlag,
     ) -> PagingResult<()> {
         // 验证标志位：叶子节点必须至少有 R/W/X 之一被设置
@@ -211,14 +189,11 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
             return Err(PagingError::InvalidFlags);
         }
 
-        // TODO(暂时注释): 当前仅支持4K页，强制使用 level 0
-        // 根据页大小确定目标级别
-        // let target_level = match page_size {
-        //     PageSize::Size1G => 2,
-        //     PageSize::Size2M => 1,
-        //     PageSize::Size4K => 0,
-        // };
-        let target_level = 0; // 仅支持 4K 页
+        // 根据页大小确定目标级别，大页的虚拟页号和物理页号都必须按页大小对齐
+        let target_level = page_size.level();
+        if vpn.as_usize() % page_size.pages() != 0 || ppn.as_usize() % page_size.pages() != 0 {
+            return Err(PagingError::InvalidAddress);
+        }
 
         let mut current_ppn = self.root;
         let vpn_value = vpn.as_usize();
@@ -238,6 +213,7 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
 
             if level == target_level {
                 // 已到达目标级别，创建叶子节点
+                // 大页的目标级别上已有下一级页表时同样视为已被映射
                 if pte.is_valid() {
                     return Err(PagingError::AlreadyMapped); // 已被映射
                 }
@@ -391,16 +367,14 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
 
             // 检查是否为叶子节点
             if pte.is_huge() || level == 0 {
-                // 找到叶子节点
-                // TODO(暂时注释): 当前仅支持4K页
-                // let page_size = match level {
-                //     2 => PageSize::Size1G,
-                //     1 => PageSize::Size2M,
-                //     0 => PageSize::Size4K,
-                //     _ => unreachable!(),
-                // };
-                let page_size = PageSize::Size4K; // 仅支持 4K 页
-                return Ok((pte.ppn(), page_size, pte.flags()));
+                // 找到叶子节点，大页返回 vpn 所在 4K 页的物理页号
+                let page_size = PageSize::from_level(level).ok_or(PagingError::NotMapped)?;
+                let offset = vpn_value & (page_size.pages() - 1);
+                return Ok((
+                    Ppn::from_usize(pte.ppn().as_usize() + offset),
+                    page_size,
+                    pte.flags(),
+                ));
             }
 
             // 继续下一级页表
@@ -420,15 +394,7 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         batch: Option<&mut TlbBatchContextWrapper>,
     ) -> PagingResult<()> {
         self.map(vpn, ppn, page_size, flags)?;
-        // 总是刷新本地 TLB
-        Self::tlb_flush(vpn);
-        // 只有在非批处理模式下才发送 IPI
-        if batch.is_none() {
-            let num_cpu = unsafe { crate::kernel::NUM_CPU };
-            if num_cpu > 1 {
-                send_tlb_flush_ipi_all();
-            }
-        }
+        self.flush_page(vpn, batch);
         Ok(())
     }
 
@@ -439,15 +405,7 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         batch: Option<&mut TlbBatchContextWrapper>,
     ) -> PagingResult<()> {
         self.unmap(vpn)?;
-        // 总是刷新本地 TLB
-        Self::tlb_flush(vpn);
-        // 只有在非批处理模式下才发送 IPI
-        if batch.is_none() {
-            let num_cpu = unsafe { crate::kernel::NUM_CPU };
-            if num_cpu > 1 {
-                send_tlb_flush_ipi_all();
-            }
-        }
+        self.flush_page(vpn, batch);
         Ok(())
     }
 
@@ -459,60 +417,167 @@ impl PageTableInnerTrait<PageTableEntry> for PageTableInner {
         batch: Option<&mut TlbBatchContextWrapper>,
     ) -> PagingResult<()> {
         self.update_flags(vpn, flags)?;
-        // 总是刷新本地 TLB
-        Self::tlb_flush(vpn);
-        // 只有在非批处理模式下才发送 IPI
-        if batch.is_none() {
-            let num_cpu = unsafe { crate::kernel::NUM_CPU };
-            if num_cpu > 1 {
-                send_tlb_flush_ipi_all();
-            }
+        self.flush_page(vpn, batch);
+        Ok(())
+    }
+
+    // 把大页拆分为下一级的 512 个页
+    fn split_huge_page(&mut self, vpn: Vpn) -> PagingResult<()> {
+        let (pte, level) = self.huge_leaf_pte(vpn).ok_or(PagingError::NotMapped)?;
+        if level == 0 {
+            return Ok(());
+        }
+
+        let new_frame = alloc_frame().ok_or(PagingError::FrameAllocFailed)?;
+        let new_ppn = new_frame.ppn();
+        // 下一级每个页表项覆盖的 4K 页数
+        let stride = 1usize << (9 * (level - 1));
+        let base = pte.ppn().as_usize();
+        let flags = pte.flags();
+
+        // Unsafe: 新分配的页表页由本页表独占
+        let new_table = unsafe {
+            core::slice::from_raw_parts_mut(
+                new_ppn.start_addr().to_vaddr().as_usize() as *mut PageTableEntry,
+                512,
+            )
+        };
+        for (i, entry) in new_table.iter_mut().enumerate() {
+            *entry = PageTableEntry::new_leaf(Ppn::from_usize(base + i * stride), flags.clone());
+        }
+
+        *pte = PageTableEntry::new_table(new_ppn);
+        self.frames.push(new_frame);
+
+        // 大页可能以任意粒度缓存在 TLB 中，全部刷新
+        Self::tlb_flush_all();
+        tlb::shootdown(None);
+        Ok(())
+    }
+
+    // 把叶子页表项设为换出项（支持 TLB 批处理）
+    fn set_swap_entry(
+        &mut self,
+        vpn: Vpn,
+        slot: usize,
+        batch: Option<&mut TlbBatchContextWrapper>,
+    ) -> PagingResult<()> {
+        let pte = self.leaf_pte(vpn).ok_or(PagingError::NotMapped)?;
+        *pte = PageTableEntry::new_swap(slot);
+        self.flush_page(vpn, batch);
+        Ok(())
+    }
+
+    // 读取换出项中的交换槽位
+    fn swap_entry(&self, vpn: Vpn) -> Option<usize> {
+        self.leaf_pte(vpn).and_then(|pte| pte.swap_slot())
+    }
+
+    // 清除换出项（无效页表项不在 TLB 中，无需刷新）
+    fn clear_swap_entry(&mut self, vpn: Vpn) -> PagingResult<()> {
+        let pte = self.leaf_pte(vpn).ok_or(PagingError::NotMapped)?;
+        if pte.swap_slot().is_none() {
+            return Err(PagingError::NotMapped);
         }
+        pte.clear();
         Ok(())
     }
 }
 
 // PageTableInner 的额外实现（非 trait 方法）
 impl PageTableInner {
-    /// 刷新所有 CPU 的 TLB（多核 TLB Shootdown）
-    ///
-    /// 此函数执行以下操作：
-    /// 1. 刷新当前 CPU 的 TLB（针对指定 VPN）
-    /// 2. 通过 IPI 通知所有其他 CPU 刷新其 TLB
-    ///
-    /// # 参数
-    /// - vpn: 需要刷新的虚拟页号
-    ///
-    /// # 注意
-    /// - 单核系统：只刷新本地 TLB，无 IPI 开销
-    /// - 多核系统：异步刷新，不等待其他 CPU 确认
-    /// - 测试模式：也会发送 IPI（如果是多核环境）
-    #[allow(dead_code)]
-    fn tlb_flush_all_cpus(vpn: Vpn) {
-        // 1. 刷新当前 CPU 的 TLB
-        <Self as PageTableInnerTrait<PageTableEntry>>::tlb_flush(vpn);
-
-        // 2. 通知所有其他 CPU 刷新 TLB
-        // send_tlb_flush_ipi_all 内部会检查是否为多核环境
-        // 单核环境下不会发送 IPI
-        let num_cpu = unsafe { crate::kernel::NUM_CPU };
-        if num_cpu > 1 {
-            send_tlb_flush_ipi_all();
+    /// 刷新 `vpn` 处的页：本 CPU 立即刷新，其它 CPU 记入批处理或立即同步刷新
+    fn flush_page(&self, vpn: Vpn, batch: Option<&mut TlbBatchContextWrapper>) {
+        let entry = tlb_entry(self.root, vpn);
+        flush_tlb_entry(entry);
+        match batch {
+            Some(batch) => batch.add_page(self.root, vpn),
+            None => tlb::shootdown(Some(&[entry])),
+        }
+    }
+
+    /// 查找 `vpn` 对应的 4K 叶子页表项（可能无效），中间级页表不存在或是巨页时返回 `None`
+    #[allow(clippy::mut_from_ref)]
+    fn leaf_pte(&self, vpn: Vpn) -> Option<&mut PageTableEntry> {
+        let mut ppn = self.root;
+        let vpn_value = vpn.as_usize();
+
+        for level in (0..<Self as PageTableInnerTrait<PageTableEntry>>::LEVELS).rev() {
+            let idx = (vpn_value >> (9 * level)) & 0x1ff;
+
+            // Unsafe: 页表页由本页表独占，调用者持有地址空间锁
+            let pte_array = unsafe {
+                core::slice::from_raw_parts_mut(
+                    ppn.start_addr().to_vaddr().as_usize() as *mut PageTableEntry,
+                    512,
+                )
+            };
+            let pte = &mut pte_array[idx];
+
+            if level == 0 {
+                return Some(pte);
+            }
+            if !pte.is_valid() || pte.is_huge() {
+                return None;
+            }
+            ppn = pte.ppn();
         }
+
+        None
+    }
+
+    /// 查找覆盖 `vpn` 的有效叶子页表项及其所在级别
+    #[allow(clippy::mut_from_ref)]
+    fn huge_leaf_pte(&self, vpn: Vpn) -> Option<(&mut PageTableEntry, usize)> {
+        let mut ppn = self.root;
+        let vpn_value = vpn.as_usize();
+
+        for level in (0..<Self as PageTableInnerTrait<PageTableEntry>>::LEVELS).rev() {
+            let idx = (vpn_value >> (9 * level)) & 0x1ff;
+
+            // Unsafe: 页表页由本页表独占，调用者持有地址空间锁
+            let pte_array = unsafe {
+                core::slice::from_raw_parts_mut(
+                    ppn.start_addr().to_vaddr().as_usize() as *mut PageTableEntry,
+                    512,
+                )
+            };
+            let pte = &mut pte_array[idx];
+
+            if !pte.is_valid() {
+                return None;
+            }
+            if pte.is_huge() || level == 0 {
+                return Some((pte, level));
+            }
+            ppn = pte.ppn();
+        }
+
+        None
     }
 }
 
 /// TLB 批量刷新上下文
 ///
-/// 用于在批量页表操作期间延迟 TLB 刷新，减少 IPI 数量
+/// 批处理期间修改的页只在本 CPU 上立即刷新，这里记录它们的 TLB 项，
+/// 结束时向其它 CPU 发送一次刷新请求；记录的页超过 [`MAX_FLUSH_PAGES`] 时改为刷新整个 TLB。
 pub struct TlbBatchContext {
-    enabled: bool,
+    /// 待刷新的 TLB 项（见 [`tlb_entry`]）
+    entries: [usize; MAX_FLUSH_PAGES],
+    /// 已记录的 TLB 项数
+    count: usize,
+    /// 记录的页超过上限
+    overflow: bool,
 }
 
 impl TlbBatchContext {
     /// 创建新的批处理上下文
     pub fn new() -> Self {
-        Self { enabled: true }
+        Self {
+            entries: [0; MAX_FLUSH_PAGES],
+            count: 0,
+            overflow: false,
+        }
     }
 
     /// 在批处理上下文中执行操作
@@ -526,20 +591,25 @@ impl TlbBatchContext {
         result
     }
 
+    /// 记录需要在其它 CPU 上刷新的页
+    pub fn add_page(&mut self, root: Ppn, vpn: Vpn) {
+        if self.count < MAX_FLUSH_PAGES {
+            self.entries[self.count] = tlb_entry(root, vpn);
+            self.count += 1;
+        } else {
+            self.overflow = true;
+        }
+    }
+
     /// 刷新所有待处理的 TLB 条目
     pub fn flush(&mut self) {
-        if self.enabled {
-            // 刷新本地 TLB
-            unsafe {
-                core::arch::asm!("sfence.vma");
-            }
-            // 发送一次 IPI 到所有其他 CPU
-            let num_cpu = unsafe { crate::kernel::NUM_CPU };
-            if num_cpu > 1 {
-                send_tlb_flush_ipi_all();
-            }
-            self.enabled = false;
+        if self.overflow {
+            tlb::shootdown(None);
+        } else if self.count > 0 {
+            tlb::shootdown(Some(&self.entries[..self.count]));
         }
+        self.count = 0;
+        self.overflow = false;
     }
 }
 
@@ -549,6 +619,24 @@ impl Drop for TlbBatchContext {
     }
 }
 
+/// 根页表为 `root` 的地址空间中 `vpn` 处的页在 TLB 刷新请求中的编码
+///
+/// satp 中的 ASID 总是 0，编码只有页对齐的虚拟地址。
+pub fn tlb_entry(_root: Ppn, vpn: Vpn) -> usize {
+    vpn.start_addr().as_usize()
+}
+
+/// 在本 CPU 上刷新 [`tlb_entry`] 编码的 TLB 项
+pub fn flush_tlb_entry(entry: usize) {
+    // Safe: sfence.vma vaddr, zero 刷新所有地址空间中该虚拟地址的 TLB 条目
+    unsafe { core::arch::asm!("sfence.vma {0}, zero", in(reg) entry) }
+}
+
+/// 在本 CPU 上刷新整个 TLB
+pub fn flush_tlb_all() {
+    <PageTableInner as PageTableInnerTrait<PageTableEntry>>::tlb_flush_all();
+}
+
 // 辅助函数：将 PPN 转换为 satp 寄存器的值
 fn ppn_to_satp(ppn: Ppn) -> usize {
     // 设置 MODE=8 (SV39) 并与 PPN 进行位或操作
@@ -704,6 +792,74 @@ mod page_table_tests {
         }
     }
 
+    // 8. 换出项测试
+    #[test_case]
+    fn test_pt_swap_entry() {
+        let mut pt = PageTableInner::new();
+        let vpn = Vpn::from_usize(0x1000);
+        let ppn = Ppn::from_usize(0x80000);
+
+        // 中间级页表还不存在
+        assert!(pt.set_swap_entry(vpn, 3, None).is_err());
+
+        pt.map(vpn, ppn, PageSize::Size4K, UniversalPTEFlag::user_rw())
+            .unwrap();
+        pt.set_swap_entry(vpn, 3, None).unwrap();
+        assert_eq!(pt.swap_entry(vpn), Some(3));
+        // 换出项不是有效映射
+        assert!(pt.walk(vpn).is_err());
+        assert!(pt.translate(vpn.start_addr()).is_none());
+
+        // 换入时直接映射覆盖换出项
+        pt.map(vpn, ppn, PageSize::Size4K, UniversalPTEFlag::user_rw())
+            .unwrap();
+        assert_eq!(pt.swap_entry(vpn), None);
+
+        pt.set_swap_entry(vpn, 5, None).unwrap();
+        pt.clear_swap_entry(vpn).unwrap();
+        assert_eq!(pt.swap_entry(vpn), None);
+        assert!(pt.clear_swap_entry(vpn).is_err());
+    }
+
+    // 9. 大页映射与拆分测试
+    #[test_case]
+    fn test_pt_huge_page_split() {
+        let mut pt = PageTableInner::new();
+        let vpn = Vpn::from_usize(0x40000);
+        let ppn = Ppn::from_usize(0x80200);
+
+        // 未对齐的大页映射被拒绝
+        assert!(
+            pt.map(
+                Vpn::from_usize(0x40001),
+                ppn,
+                PageSize::Size2M,
+                UniversalPTEFlag::kernel_rw()
+            )
+            .is_err()
+        );
+
+        pt.map(vpn, ppn, PageSize::Size2M, UniversalPTEFlag::kernel_rw())
+            .unwrap();
+        let inner = Vpn::from_usize(0x40000 + 7);
+        let (mapped_ppn, size, _) = pt.walk(inner).unwrap();
+        assert!(size == PageSize::Size2M);
+        assert!(mapped_ppn.as_usize() == 0x80200 + 7);
+        let paddr = pt.translate(Vaddr::from_usize(inner.start_addr().as_usize() + 0x123));
+        assert!(paddr.unwrap().as_usize() == ((0x80200 + 7) << 12) + 0x123);
+
+        // 拆分后映射不变，页大小变为 4K
+        pt.split_huge_page(inner).unwrap();
+        let (mapped_ppn, size, _) = pt.walk(inner).unwrap();
+        assert!(size == PageSize::Size4K);
+        assert!(mapped_ppn.as_usize() == 0x80200 + 7);
+
+        // 4K 页可以单独解除映射
+        pt.unmap(inner).unwrap();
+        assert!(pt.walk(inner).is_err());
+        assert!(pt.walk(vpn).is_ok());
+    }
+
     // TLB Shootdown 测试
 
     /// 测试 TLB flush IPI 发送（基础功能）
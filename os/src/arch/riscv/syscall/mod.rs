@@ -10,6 +10,8 @@ use uapi::errno::ENOSYS;
 
 mod syscall_number;
 
+pub use syscall_number::*;
+
 /// 分发系统调用
 /// 按照系统调用号顺序排列，参考 syscall_number.rs 中的分类
 pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
@@ -23,8 +25,26 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         frame.x14_a4,
         frame.x15_a5
     );
+    // seccomp 拒绝的系统调用不再分发，返回值已由过滤器动作写入
+    if !crate::security::seccomp::secure_computing(frame) {
+        crate::pr_debug!("syscall skipped by seccomp: {}", frame.x17_a7);
+        return;
+    }
+    let (nr, args) = (frame.syscall_id(), frame.syscall_args());
     match frame.x17_a7 {
         // 文件系统/目录操作 (Filesystem/Directory Operations)
+        syscall_number::SYS_SETXATTR => sys_setxattr(frame),
+        syscall_number::SYS_LSETXATTR => sys_lsetxattr(frame),
+        syscall_number::SYS_FSETXATTR => sys_fsetxattr(frame),
+        syscall_number::SYS_GETXATTR => sys_getxattr(frame),
+        syscall_number::SYS_LGETXATTR => sys_lgetxattr(frame),
+        syscall_number::SYS_FGETXATTR => sys_fgetxattr(frame),
+        syscall_number::SYS_LISTXATTR => sys_listxattr(frame),
+        syscall_number::SYS_LLISTXATTR => sys_llistxattr(frame),
+        syscall_number::SYS_FLISTXATTR => sys_flistxattr(frame),
+        syscall_number::SYS_REMOVEXATTR => sys_removexattr(frame),
+        syscall_number::SYS_LREMOVEXATTR => sys_lremovexattr(frame),
+        syscall_number::SYS_FREMOVEXATTR => sys_fremovexattr(frame),
         syscall_number::SYS_GETCWD => sys_getcwd(frame),
 
         // Epoll & Duplication
@@ -38,6 +58,7 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         syscall_number::SYS_MKDIRAT => sys_mkdirat(frame),
         syscall_number::SYS_UNLINKAT => sys_unlinkat(frame),
         syscall_number::SYS_SYMLINKAT => sys_symlinkat(frame),
+        syscall_number::SYS_LINKAT => sys_linkat(frame),
 
         // 挂载/文件系统信息 (Mount/Filesystem Info)
         syscall_number::SYS_MOUNT => sys_mount(frame),
@@ -52,11 +73,16 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
 
         // 文件描述符操作 (File Descriptor Operations)
         syscall_number::SYS_OPENAT => sys_openat(frame),
+        syscall_number::SYS_OPENAT2 => sys_openat2(frame),
         syscall_number::SYS_CLOSE => sys_close(frame),
         syscall_number::SYS_PIPE2 => sys_pipe2(frame),
+        syscall_number::SYS_EVENTFD2 => sys_eventfd2(frame),
         syscall_number::SYS_GETDENTS64 => sys_getdents64(frame),
         syscall_number::SYS_LSEEK => sys_lseek(frame),
         syscall_number::SYS_FTRUNCATE => sys_ftruncate(frame),
+        syscall_number::SYS_INOTIFY_INIT1 => sys_inotify_init1(frame),
+        syscall_number::SYS_INOTIFY_ADD_WATCH => sys_inotify_add_watch(frame),
+        syscall_number::SYS_INOTIFY_RM_WATCH => sys_inotify_rm_watch(frame),
 
         // I/O 操作 (Input/Output Operations)
         syscall_number::SYS_READ => sys_read(frame),
@@ -68,8 +94,12 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         syscall_number::SYS_PREADV => sys_preadv(frame),
         syscall_number::SYS_PWRITEV => sys_pwritev(frame),
         syscall_number::SYS_SENDFILE => sys_sendfile(frame),
+        syscall_number::SYS_SPLICE => sys_splice(frame),
         syscall_number::SYS_PSELECT6 => sys_pselect6(frame),
         syscall_number::SYS_PPOLL => sys_ppoll(frame),
+        syscall_number::SYS_EPOLL_CREATE1 => sys_epoll_create1(frame),
+        syscall_number::SYS_EPOLL_CTL => sys_epoll_ctl(frame),
+        syscall_number::SYS_EPOLL_PWAIT => sys_epoll_pwait(frame),
 
         // 文件元数据与同步 (File Metadata and Synchronization)
         syscall_number::SYS_READLINKAT => sys_readlinkat(frame),
@@ -91,6 +121,7 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         syscall_number::SYS_FUTEX => sys_futex(frame),
         syscall_number::SYS_SET_ROBUST_LIST => sys_set_robust_list(frame),
         syscall_number::SYS_GET_ROBUST_LIST => sys_get_robust_list(frame),
+        syscall_number::SYS_RSEQ => sys_rseq(frame),
         syscall_number::SYS_NANOSLEEP => sys_nanosleep(frame),
         syscall_number::SYS_GETITIMER => sys_getitimmer(frame),
         syscall_number::SYS_SETITIMER => sys_setitimmer(frame),
@@ -99,6 +130,10 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         syscall_number::SYS_CLOCK_SETTIME => sys_clock_settime(frame),
         syscall_number::SYS_CLOCK_GETTIME => sys_clock_gettime(frame),
         syscall_number::SYS_CLOCK_GETRES => sys_clock_getres(frame),
+        syscall_number::SYS_CLOCK_NANOSLEEP => sys_clock_nanosleep(frame),
+        syscall_number::SYS_CLOCK_ADJTIME => sys_clock_adjtime(frame),
+        syscall_number::SYS_ADJTIMEX => sys_adjtimex(frame),
+        syscall_number::SYS_GETTIMEOFDAY => sys_gettimeofday(frame),
         syscall_number::SYS_SYSLOG => sys_syslog(frame),
 
         // 信号 (Signals)
@@ -112,6 +147,7 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         syscall_number::SYS_RT_SIGPENDING => sys_rt_sigpending(frame),
         syscall_number::SYS_RT_SIGTIMEDWAIT => sys_rt_sigtimedwait(frame),
         syscall_number::SYS_RT_SIGRETURN => sys_rt_sigreturn(frame),
+        syscall_number::SYS_RESTART_SYSCALL => sys_restart_syscall(frame),
 
         // 进程属性 (Process Attributes)
         syscall_number::SYS_REBOOT => sys_reboot(frame),
@@ -139,6 +175,8 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         syscall_number::SYS_GETEGID => sys_getegid(frame),
         syscall_number::SYS_GETTID => sys_gettid(frame),
         syscall_number::SYS_SYSINFO => sys_sysinfo(frame),
+        syscall_number::SYS_GETCPU => sys_getcpu(frame),
+        syscall_number::SYS_PRCTL => sys_prctl(frame),
 
         // 网络 (Networking/Sockets)
         syscall_number::SYS_SOCKET => sys_socket(frame),
@@ -156,6 +194,7 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
 
         // 进程创建/执行 (Process Creation/Execution)
         syscall_number::SYS_CLONE => sys_clone(frame),
+        syscall_number::SYS_UNSHARE => sys_unshare(frame),
         syscall_number::SYS_EXECVE => sys_execve(frame),
 
         // 网络/I/O (续)
@@ -170,6 +209,14 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         syscall_number::SYS_MUNMAP => sys_munmap(frame),
         syscall_number::SYS_MMAP => sys_mmap(frame),
         syscall_number::SYS_MPROTECT => sys_mprotect(frame),
+        syscall_number::SYS_MREMAP => sys_mremap(frame),
+        syscall_number::SYS_MADVISE => sys_madvise(frame),
+        syscall_number::SYS_MLOCK => sys_mlock(frame),
+        syscall_number::SYS_MLOCK2 => sys_mlock2(frame),
+        syscall_number::SYS_MUNLOCK => sys_munlock(frame),
+        syscall_number::SYS_MLOCKALL => sys_mlockall(frame),
+        syscall_number::SYS_MUNLOCKALL => sys_munlockall(frame),
+        syscall_number::SYS_USERFAULTFD => sys_userfaultfd(frame),
 
         // 文件系统同步 (续)
         syscall_number::SYS_SYNCFS => sys_syncfs(frame),
@@ -180,6 +227,17 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         // 随机数与内存文件
         syscall_number::SYS_GETRANDOM => sys_getrandom(frame),
 
+        // 安全 (Security)
+        syscall_number::SYS_SECCOMP => sys_seccomp(frame),
+        syscall_number::SYS_LANDLOCK_CREATE_RULESET => sys_landlock_create_ruleset(frame),
+        syscall_number::SYS_LANDLOCK_ADD_RULE => sys_landlock_add_rule(frame),
+        syscall_number::SYS_LANDLOCK_RESTRICT_SELF => sys_landlock_restrict_self(frame),
+
+        // 异步 I/O (io_uring)
+        syscall_number::SYS_IO_URING_SETUP => sys_io_uring_setup(frame),
+        syscall_number::SYS_IO_URING_ENTER => sys_io_uring_enter(frame),
+        syscall_number::SYS_IO_URING_REGISTER => sys_io_uring_register(frame),
+
         // 扩展文件元数据
         syscall_number::SYS_STATX => sys_statx(frame),
 
@@ -204,6 +262,7 @@ pub fn dispatch_syscall(frame: &mut super::trap::TrapFrame) {
         }
     }
     crate::pr_debug!("syscall exit, return: {}", frame.x10_a0 as isize);
+    crate::security::audit::syscall_exit(nr, args, frame.x10_a0 as isize);
 }
 
 /// 宏：实现系统调用函数的自动包装器
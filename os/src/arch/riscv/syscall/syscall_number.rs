@@ -374,6 +374,22 @@ pub const SYS_PKEY_ALLOC: usize = 289;
 pub const SYS_PKEY_FREE: usize = 290;
 pub const SYS_STATX: usize = 291;
 
+/// 可重启序列
+pub const SYS_RSEQ: usize = 293;
+
+/// 异步 I/O (io_uring)
+pub const SYS_IO_URING_SETUP: usize = 425;
+pub const SYS_IO_URING_ENTER: usize = 426;
+pub const SYS_IO_URING_REGISTER: usize = 427;
+
+/// 带扩展参数的 openat
+pub const SYS_OPENAT2: usize = 437;
+
+/// Landlock
+pub const SYS_LANDLOCK_CREATE_RULESET: usize = 444;
+pub const SYS_LANDLOCK_ADD_RULE: usize = 445;
+pub const SYS_LANDLOCK_RESTRICT_SELF: usize = 446;
+
 /// RISC-V 架构特定系统调用
 pub const SYS_SYSRISCV: usize = SYS_ARCH_SPECIFIC_SYSCALL; // 244
 pub const SYS_RISCV_FLUSH_ICACHE: usize = 259; // 244 + 15
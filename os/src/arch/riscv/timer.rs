@@ -31,19 +31,20 @@ pub fn get_time() -> usize {
 /// 获取当前时间（以毫秒为单位）
 #[inline]
 pub fn get_time_ms() -> usize {
-    (time::read() as u128 * MSEC_PER_SEC as u128 / clock_freq() as u128) as usize
+    (crate::kernel::hrtimer::ktime_get() / 1_000_000) as usize
 }
 
-/// 设置定时器中断
+/// 设置下一次定时器中断的绝对时间（硬件时钟周期数）
 #[inline]
-pub fn set_next_trigger() {
-    let next = get_time() + clock_freq() / TICKS_PER_SEC;
-    set_timer(next);
+pub fn set_next_event(deadline: usize) {
+    set_timer(deadline);
 }
 
 /// 初始化定时器
 pub fn init() {
-    set_next_trigger();
+    // 允许用户态读取 time CSR（vDSO 直接用 rdtime 计算时间）
+    unsafe { core::arch::asm!("csrs scounteren, {}", in(reg) 1usize << 1) };
+    crate::kernel::tick_init();
     // Safe: 只在内核初始化阶段调用，确保唯一性
     unsafe { crate::arch::intr::enable_timer_interrupt() };
 }
@@ -60,11 +61,13 @@ mod tests {
     use super::*;
     use crate::println;
     #[test_case]
-    fn test_set_next_trigger() {
+    fn test_set_next_event() {
         let current_time = get_time();
-        set_next_trigger();
+        set_next_event(current_time + clock_freq() / TICKS_PER_SEC);
         let next_time = get_time();
         assert!(next_time > current_time);
+        // 恢复为定时器子系统期望的到期时间
+        crate::kernel::hrtimer::hrtimer_interrupt();
     }
 
     #[test_case]
@@ -27,7 +27,21 @@ use riscv::register::{
 pub use sum_guard::SumGuard;
 pub use trap_frame::TrapFrame;
 
-global_asm!(include_str!("trap_entry.S"));
+use crate::mm::kstack::{
+    KERNEL_STACK_SIZE, KSTACK_AREA_SIZE, KSTACK_AREA_START, KSTACK_GUARD_SIZE,
+    KSTACK_OVERFLOW_LOCK, KSTACK_OVERFLOW_STACK, KSTACK_SLOT_SIZE,
+};
+
+global_asm!(
+    include_str!("trap_entry.S"),
+    KSTACK_AREA_START = const KSTACK_AREA_START as isize,
+    KSTACK_AREA_SIZE = const KSTACK_AREA_SIZE,
+    KSTACK_SLOT_MASK = const KSTACK_SLOT_SIZE - 1,
+    KSTACK_GUARD_SIZE = const KSTACK_GUARD_SIZE,
+    KERNEL_STACK_SIZE = const KERNEL_STACK_SIZE,
+    overflow_stack = sym KSTACK_OVERFLOW_STACK,
+    overflow_lock = sym KSTACK_OVERFLOW_LOCK,
+);
 global_asm!(include_str!("boot_trap_entry.S"));
 global_asm!(include_str!("sigreturn.S"));
 
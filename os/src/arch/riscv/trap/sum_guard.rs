@@ -71,6 +71,68 @@ impl SumGuard {
     pub fn was_set(&self) -> bool {
         self.was_set
     }
+
+    /// 当前是否处于用户内存访问窗口内（SUM 位已设置）
+    #[inline]
+    pub fn is_active() -> bool {
+        sstatus::read().sum()
+    }
+
+    /// 拆解为创建前的 SUM 状态，不恢复 SUM 位
+    ///
+    /// 供无法持有 guard 对象的进入/退出式接口使用，之后必须用 [`SumGuard::from_raw`] 重建并丢弃。
+    #[inline]
+    pub fn into_raw(self) -> bool {
+        let was_set = self.was_set;
+        core::mem::forget(self);
+        was_set
+    }
+
+    /// 由 [`SumGuard::into_raw`] 的返回值重建 guard
+    ///
+    /// # Safety
+    ///
+    /// `was_set` 必须来自同一 CPU 上尚未重建的 `into_raw`，且两者之间的嵌套关系保持后进先出。
+    #[inline]
+    pub unsafe fn from_raw(was_set: bool) -> Self {
+        SumGuard { was_set }
+    }
+
+    /// 任务切换前调用：保存并关闭当前任务的用户内存访问窗口
+    ///
+    /// SUM 位属于 CPU 状态而不随上下文切换保存，持有 guard 的任务睡眠时
+    /// 窗口不能泄漏给下一个任务。返回值交给切回后的 [`SumGuard::resume`]。
+    #[inline]
+    pub fn suspend() -> bool {
+        let active = Self::is_active();
+        if active {
+            // SAFETY: 关闭 SUM 位只会收紧内核访问权限
+            unsafe { sstatus::clear_sum() };
+        }
+        active
+    }
+
+    /// 任务切回后调用：恢复 [`SumGuard::suspend`] 保存的访问窗口
+    #[inline]
+    pub fn resume(active: bool) {
+        if active {
+            // SAFETY: 恢复本任务切换前持有的访问窗口
+            unsafe { sstatus::set_sum() };
+        }
+    }
+
+    /// 调试模式（`user-access-debug`）下检查访问窗口已关闭，否则打印调用栈并 panic
+    ///
+    /// 用于系统调用返回等不应持有 guard 的位置，捕获未配对的 SUM 置位。
+    #[inline]
+    pub fn assert_inactive(_context: &str) {
+        #[cfg(feature = "user-access-debug")]
+        if Self::is_active() {
+            crate::earlyprintln!("[user-access] SUM leaked at {}", _context);
+            crate::util::backtrace::print_backtrace();
+            panic!("user access window leaked at {}", _context);
+        }
+    }
 }
 
 impl Drop for SumGuard {
@@ -137,6 +199,30 @@ mod tests {
         assert!(!sstatus::read().sum());
     }
 
+    // 测试任务切换时访问窗口的保存与恢复，以及 into_raw / from_raw 往返
+    #[test_case]
+    fn test_guard_suspend_resume_and_raw() {
+        unsafe { sstatus::clear_sum() };
+
+        let guard = SumGuard::new();
+        let active = SumGuard::suspend();
+        assert!(active);
+        assert!(!SumGuard::is_active());
+        SumGuard::resume(active);
+        assert!(SumGuard::is_active());
+        drop(guard);
+        assert!(!SumGuard::is_active());
+
+        // 窗口关闭时 suspend / resume 不应打开 SUM
+        SumGuard::resume(SumGuard::suspend());
+        assert!(!SumGuard::is_active());
+
+        let raw = SumGuard::new().into_raw();
+        assert!(SumGuard::is_active());
+        drop(unsafe { SumGuard::from_raw(raw) });
+        assert!(!SumGuard::is_active());
+    }
+
     // 测试 panic 时 SumGuard 是否能正确清理
     // 注意：此测试需要 panic handler 支持
 }
@@ -136,6 +136,43 @@ impl TrapFrame {
         self.x12_a2 = val;
     }
 
+    /// 设置系统调用号寄存器 (a7)
+    #[inline]
+    pub fn set_a7(&mut self, val: usize) {
+        self.x17_a7 = val;
+    }
+
+    /// 获取系统调用号 (a7)
+    #[inline]
+    pub fn syscall_id(&self) -> usize {
+        self.x17_a7
+    }
+
+    /// 获取系统调用参数 (a0-a5)
+    #[inline]
+    pub fn syscall_args(&self) -> [usize; 6] {
+        [
+            self.x10_a0,
+            self.x11_a1,
+            self.x12_a2,
+            self.x13_a3,
+            self.x14_a4,
+            self.x15_a5,
+        ]
+    }
+
+    /// 按系统调用约定设置调用号 (a7) 与参数 (a0-a5)
+    #[inline]
+    pub fn set_syscall(&mut self, id: usize, args: [usize; 6]) {
+        self.x17_a7 = id;
+        self.x10_a0 = args[0];
+        self.x11_a1 = args[1];
+        self.x12_a2 = args[2];
+        self.x13_a3 = args[3];
+        self.x14_a4 = args[4];
+        self.x15_a5 = args[5];
+    }
+
     /// 设置返回地址寄存器 (ra)
     #[inline]
     pub fn set_ra(&mut self, val: usize) {
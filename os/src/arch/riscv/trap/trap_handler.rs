@@ -11,12 +11,12 @@ use riscv::register::scause::{self, Trap};
 use riscv::register::sstatus::SPP;
 use riscv::register::{sepc, sscratch, sstatus, stval};
 
-use crate::arch::constant::SUPERVISOR_EXTERNAL;
+use crate::arch::constant::{SUPERVISOR_EXTERNAL, USER_TOP};
 use crate::arch::syscall::dispatch_syscall;
-use crate::arch::timer::{TIMER_TICKS, clock_freq, get_time};
-use crate::arch::trap::restore;
+use crate::arch::trap::{SumGuard, restore};
 use crate::device::IRQ_MANAGER;
-use crate::kernel::{TIMER, TIMER_QUEUE, schedule, send_signal_process, wake_up_with_block};
+use crate::kernel::schedule;
+use crate::util::backtrace::print_backtrace_from;
 
 static FIRST_USER_TIMER_TICK: AtomicUsize = AtomicUsize::new(0);
 
@@ -45,10 +45,13 @@ pub extern "C" fn trap_handler(trap_frame: &mut super::TrapFrame) {
     match sstatus_old.spp() {
         SPP::User => {
             user_trap(scause, sepc_old, sstatus_old, trap_frame);
+            SumGuard::assert_inactive("return to user");
+            // 须在投递信号前处理，信号帧里保存的应是中止后的返回地址
+            crate::kernel::rseq_handle_notify_resume();
             // 仅在返回用户态时检查信号
             check_signal();
         }
-        SPP::Supervisor => kernel_trap(scause, sepc_old, sstatus_old),
+        SPP::Supervisor => kernel_trap(scause, sepc_old, sstatus_old, trap_frame),
     }
     // 恢复“当前任务”的陷阱帧。
     // 注意：在陷阱处理中可能发生了调度（例如用户态定时器中断），
@@ -77,7 +80,27 @@ pub fn user_trap(
             // 设置返回地址为下一个指令
             trap_frame.sepc = sepc_old.wrapping_add(4);
             // 处理系统调用
+            let orig_a0 = trap_frame.x10_a0;
             dispatch_syscall(trap_frame);
+            crate::ipc::handle_syscall_restart(trap_frame, orig_a0);
+        }
+        Trap::Exception(12) | Trap::Exception(13) | Trap::Exception(15)
+            if crate::mm::swap::handle_swap_fault(stval::read(), false) =>
+        {
+            // 换出的页已换入，重新执行出错的指令
+        }
+        Trap::Exception(15) if crate::mm::handle_cow_fault(stval::read(), false) => {
+            // 写时复制缺页已解决，重新执行出错的存储指令
+        }
+        Trap::Exception(code @ (12 | 13 | 15))
+            if crate::mm::userfaultfd::handle_userfault(stval::read(), code == 15) =>
+        {
+            // 缺页交给 userfaultfd 处理完毕（或等待被打断），重新执行出错的指令
+        }
+        Trap::Exception(12) | Trap::Exception(13) | Trap::Exception(15)
+            if crate::mm::handle_demand_fault(stval::read(), false) =>
+        {
+            // 被 madvise 丢弃的页已重新填充，重新执行出错的指令
         }
         Trap::Exception(3) => {
             // Breakpoint (EBREAK / C.EBREAK) in U-mode.
@@ -86,7 +109,6 @@ pub fn user_trap(
         }
         Trap::Interrupt(5) => {
             // 处理时钟中断
-            crate::arch::timer::set_next_trigger();
             // Debug aid: confirm user-mode timer interrupts are firing at least once.
             if FIRST_USER_TIMER_TICK.fetch_add(1, Ordering::Relaxed) == 0 {
                 crate::earlyprintln!("[OSCOMP][DBG] first user timer tick");
@@ -263,16 +285,17 @@ pub fn user_trap(
 }
 
 /// 处理来自内核态的陷阱（中断、异常）
-pub fn kernel_trap(scause: scause::Scause, sepc_old: usize, sstatus_old: sstatus::Sstatus) {
+pub fn kernel_trap(
+    scause: scause::Scause,
+    sepc_old: usize,
+    sstatus_old: sstatus::Sstatus,
+    trap_frame: &super::TrapFrame,
+) {
     match scause.cause() {
         Trap::Interrupt(5) => {
             // 时钟中断（内核态）
-            // 1) 设置下一次触发
-            // 2) 驱动内核定时器与唤醒队列（与用户态路径一致），避免 CPU 停在 idle 时错过唤醒
-            // 3) 若有可运行任务，或当前正处于 idle 任务，则立即调度
-            crate::arch::timer::set_next_trigger();
-
-            // 驱动 TIMER/TIMER_QUEUE，唤醒超时任务
+            // 1) 执行到期的定时器（与用户态路径一致），避免 CPU 停在 idle 时错过唤醒
+            // 2) 若有可运行任务，或当前正处于 idle 任务，则立即调度
             check_timer();
 
             // 是否需要在内核态进行一次调度：
@@ -309,6 +332,28 @@ pub fn kernel_trap(scause: scause::Scause, sepc_old: usize, sstatus_old: sstatus
             // 外部中断（设备）
             check_device();
         }
+        // 内核经 SumGuard 访问用户内存时命中换出的页
+        Trap::Exception(13) | Trap::Exception(15)
+            if sstatus_old.sum()
+                && stval::read() <= USER_TOP
+                && crate::mm::swap::handle_swap_fault(stval::read(), true) => {}
+        // 内核经 SumGuard 写用户内存时命中写时复制页
+        Trap::Exception(15)
+            if sstatus_old.sum()
+                && stval::read() <= USER_TOP
+                && crate::mm::handle_cow_fault(stval::read(), true) => {}
+        // 内核经 SumGuard 访问用户内存时命中被 madvise 丢弃的页
+        Trap::Exception(13) | Trap::Exception(15)
+            if sstatus_old.sum()
+                && stval::read() <= USER_TOP
+                && crate::mm::handle_demand_fault(stval::read(), true) => {}
+        // 访问内核栈下方的保护页：内核栈溢出，陷阱入口已切换到溢出栈
+        Trap::Exception(12) | Trap::Exception(13) | Trap::Exception(15)
+            if crate::mm::kstack::is_kstack_guard(stval::read()) =>
+        {
+            print_backtrace_from(trap_frame.x8_s0);
+            crate::mm::kstack::kstack_overflow(stval::read(), sepc_old);
+        }
         // 中断处理时发生异常一般是致命的
         Trap::Exception(e) => {
             // 立即读取 sscratch 和 stval 寄存器的当前值
@@ -326,7 +371,18 @@ pub fn kernel_trap(scause: scause::Scause, sepc_old: usize, sstatus_old: sstatus
             earlyprintln!("  Faulting PC (sepc):  {:#x}", sepc_old);
             earlyprintln!("  sstatus:             {:#x}", sstatus_old.bits());
             earlyprintln!("  sscratch:            {:#x}", sscratch_val);
+            if stval_val != 0 && stval_val <= USER_TOP {
+                earlyprintln!(
+                    "  User address accessed {} SumGuard",
+                    if sstatus_old.sum() {
+                        "inside"
+                    } else {
+                        "outside"
+                    }
+                );
+            }
             earlyprintln!("==============================================");
+            print_backtrace_from(trap_frame.x8_s0);
             // sbi::shutdown(true);
             panic!("Kernel exception in S-Mode");
         }
@@ -340,30 +396,28 @@ pub fn kernel_trap(scause: scause::Scause, sepc_old: usize, sstatus_old: sstatus
 }
 
 /// 处理时钟中断
+///
+/// 执行本 CPU 上所有到期的高精度定时器（唤醒睡眠任务、发送定时器信号、推进时钟节拍），
+/// 并重新编程硬件定时器；只有经过时钟节拍时才推进网络栈与时间片。
 pub fn check_timer() {
-    let _ticks = TIMER_TICKS.fetch_add(1, Ordering::Relaxed);
+    crate::kernel::hrtimer::hrtimer_interrupt();
+    if !crate::kernel::take_tick() {
+        return;
+    }
 
     // 推进网络栈，避免在仅有 loopback/null-net 且任务阻塞在 select/poll 时网络停滞。
     // 在时钟中断里推进一次网络栈，保证即便缺少真实网卡中断也能推进 TCP 状态机/重传等。
+    // 协议栈状态改变时会唤醒 socket 的等待者；轮询式设备的等待者每个节拍唤醒一次。
     crate::net::socket::poll_network_interfaces();
-    crate::kernel::syscall::io::wake_poll_waiters();
+    crate::vfs::wake_tick_pollers();
 
-    while let Some(task) = TIMER_QUEUE.lock().pop_due_task(get_time()) {
-        wake_up_with_block(task);
-    }
-    while let Some(entry) = TIMER.lock().pop_due_entry(get_time()) {
-        send_signal_process(&entry.task, entry.sig);
-        if !entry.it_interval.is_zero() {
-            let next_trigger = get_time() + entry.it_interval.into_freq(clock_freq());
-            TIMER.lock().push(next_trigger, entry);
-        }
-    }
     // 仅在时间片用尽且运行队列非空时才触发调度，避免空转日志刷屏
     let do_sched = {
         let mut sched = crate::kernel::current_scheduler().lock();
         sched.update_time_slice() && !sched.is_empty()
     };
-    if do_sched {
+    // 禁止抢占期间（如 RCU 读临界区）不切换任务
+    if do_sched && !crate::sync::preempt_disabled() {
         schedule();
     }
 }
@@ -371,7 +425,10 @@ pub fn check_timer() {
 #[allow(dead_code)]
 /// 处理设备中断
 pub fn check_device() {
+    // 设备中断处理程序不会调度，在此期间标记为中断上下文
+    crate::sync::irq_enter();
     IRQ_MANAGER
         .read()
         .try_handle_interrupt(Some(SUPERVISOR_EXTERNAL));
+    crate::sync::irq_exit();
 }
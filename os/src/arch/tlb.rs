@@ -0,0 +1,205 @@
+//! 跨 CPU 的 TLB 同步刷新（TLB shootdown）
+//!
+//! 修改页表后本 CPU 直接刷新自己的 TLB，其它 CPU 上可能还缓存着旧的页表项。
+//! [`shootdown`] 把要刷新的 TLB 项写入全局请求，向其它已就绪的 CPU 发送 TLB 刷新 IPI，
+//! 等它们全部刷新完才返回：解除映射的物理帧必须在此之后才能复用，
+//! 否则其它 CPU 可能经过旧的 TLB 项写入已被重新分配的帧。
+//!
+//! 同一时刻只有一个请求在进行，等待发起请求的 CPU 同时处理发给自己的请求。
+//! 持锁发起请求的 CPU 可能在等一个关中断自旋等同一把锁的 CPU，后者通过
+//! sync crate 的 [`cpu_relax`](sync::ArchOps::cpu_relax) 钩子处理请求，两者不会互相等待。
+//!
+//! 一个请求最多携带 [`MAX_FLUSH_PAGES`] 个 TLB 项，更多时退化为刷新整个 TLB。
+//! TLB 项的编码（虚拟地址与地址空间标识）由各架构的 `tlb_entry` 给出。
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::arch::ipi::{IpiType, send_ipi_many};
+use crate::arch::kernel::cpu::cpu_id;
+use crate::arch::mm::{flush_tlb_all, flush_tlb_entry};
+
+/// 一个请求最多携带的 TLB 项数
+pub const MAX_FLUSH_PAGES: usize = 16;
+
+/// [`REQUEST_COUNT`] 取此值时刷新整个 TLB
+const FLUSH_ALL: usize = usize::MAX;
+
+/// 当前请求要刷新的 TLB 项
+static REQUEST_ENTRIES: [AtomicUsize; MAX_FLUSH_PAGES] =
+    [const { AtomicUsize::new(0) }; MAX_FLUSH_PAGES];
+/// 当前请求的 TLB 项数
+static REQUEST_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// 尚未完成当前请求的 CPU 位掩码
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+/// 是否有请求正在进行
+static IN_FLIGHT: AtomicBool = AtomicBool::new(false);
+/// 已能响应 TLB 刷新 IPI 的 CPU 位掩码
+static READY: AtomicUsize = AtomicUsize::new(0);
+
+/// 标记当前 CPU 已能响应 TLB 刷新 IPI
+///
+/// 每个 CPU 在使能 IPI 中断之后调用一次。此前其它 CPU 修改页表时不会通知本 CPU，
+/// 标记后刷新一次整个 TLB 丢弃这期间可能缓存的旧页表项。
+pub fn cpu_ready() {
+    READY.fetch_or(1 << cpu_id(), Ordering::AcqRel);
+    flush_tlb_all();
+}
+
+/// 在其它 CPU 上刷新 `entries` 中的 TLB 项（`None` 表示整个 TLB），全部完成后返回
+///
+/// 本 CPU 的 TLB 由调用者刷新。
+pub fn shootdown(entries: Option<&[usize]>) {
+    let targets = READY.load(Ordering::Acquire) & !(1 << cpu_id());
+    if targets == 0 {
+        return;
+    }
+    while !try_begin_request() {
+        core::hint::spin_loop();
+    }
+
+    post_request(targets, entries);
+    send_ipi_many(targets, IpiType::TlbFlush);
+    while PENDING.load(Ordering::Acquire) != 0 {
+        core::hint::spin_loop();
+    }
+
+    IN_FLIGHT.store(false, Ordering::Release);
+}
+
+/// 尝试成为唯一进行中的请求
+///
+/// 已有请求在进行时返回 `false`，并先处理其中发给本 CPU 的部分：那个请求可能正在等本 CPU。
+fn try_begin_request() -> bool {
+    if IN_FLIGHT
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_ok()
+    {
+        return true;
+    }
+    handle_shootdown();
+    false
+}
+
+/// 写入请求的 TLB 项并把 `targets` 标记为未完成，调用者已通过 [`try_begin_request`] 占有请求
+fn post_request(targets: usize, entries: Option<&[usize]>) {
+    let count = match entries {
+        Some(entries) if entries.len() <= MAX_FLUSH_PAGES => {
+            for (slot, &entry) in REQUEST_ENTRIES.iter().zip(entries) {
+                slot.store(entry, Ordering::Relaxed);
+            }
+            entries.len()
+        }
+        _ => FLUSH_ALL,
+    };
+    REQUEST_COUNT.store(count, Ordering::Relaxed);
+    PENDING.store(targets, Ordering::Release);
+}
+
+/// 处理发给当前 CPU 的刷新请求，没有时什么都不做
+///
+/// 在 TLB 刷新 IPI 的处理与自旋等待中调用。
+pub fn handle_shootdown() {
+    let me = 1 << cpu_id();
+    if PENDING.load(Ordering::Acquire) & me == 0 {
+        return;
+    }
+    match REQUEST_COUNT.load(Ordering::Relaxed) {
+        FLUSH_ALL => flush_tlb_all(),
+        count => {
+            for slot in &REQUEST_ENTRIES[..count] {
+                flush_tlb_entry(slot.load(Ordering::Relaxed));
+            }
+        }
+    }
+    PENDING.fetch_and(!me, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试超过上限的请求退化为整体刷新，返回时所有 CPU 都已完成
+    #[test_case]
+    fn test_shootdown_overflow() {
+        let entries = [0usize; MAX_FLUSH_PAGES + 1];
+        shootdown(Some(&entries));
+        shootdown(Some(&entries[..1]));
+        assert!(PENDING.load(Ordering::Acquire) == 0);
+        assert!(!IN_FLIGHT.load(Ordering::Acquire));
+    }
+
+    // 测试没有发给本 CPU 的请求时处理函数什么都不做
+    #[test_case]
+    fn test_handle_shootdown_idle() {
+        handle_shootdown();
+        assert!(PENDING.load(Ordering::Acquire) & (1 << cpu_id()) == 0);
+    }
+
+    // 测试请求的握手：发布后本 CPU 处于未完成状态，处理一次后清除，再处理不做任何事
+    #[test_case]
+    fn test_request_handshake() {
+        let me = 1 << cpu_id();
+        assert!(try_begin_request());
+        assert!(IN_FLIGHT.load(Ordering::Acquire));
+        // 已有请求在进行时不能再占有
+        assert!(!try_begin_request());
+
+        post_request(me, Some(&[0x1000, 0x2000]));
+        assert!(PENDING.load(Ordering::Acquire) == me);
+        assert!(REQUEST_COUNT.load(Ordering::Relaxed) == 2);
+        assert!(REQUEST_ENTRIES[0].load(Ordering::Relaxed) == 0x1000);
+        assert!(REQUEST_ENTRIES[1].load(Ordering::Relaxed) == 0x2000);
+
+        handle_shootdown();
+        assert!(PENDING.load(Ordering::Acquire) == 0);
+        handle_shootdown();
+        assert!(PENDING.load(Ordering::Acquire) == 0);
+        IN_FLIGHT.store(false, Ordering::Release);
+    }
+
+    // 测试超过 MAX_FLUSH_PAGES 个 TLB 项或不指定 TLB 项时退化为刷新整个 TLB
+    #[test_case]
+    fn test_request_flush_all_fallback() {
+        let me = 1 << cpu_id();
+        let entries = [0x1000usize; MAX_FLUSH_PAGES + 1];
+        assert!(try_begin_request());
+
+        post_request(me, Some(&entries[..MAX_FLUSH_PAGES]));
+        assert!(REQUEST_COUNT.load(Ordering::Relaxed) == MAX_FLUSH_PAGES);
+        handle_shootdown();
+
+        post_request(me, Some(&entries));
+        assert!(REQUEST_COUNT.load(Ordering::Relaxed) == FLUSH_ALL);
+        handle_shootdown();
+
+        post_request(me, None);
+        assert!(REQUEST_COUNT.load(Ordering::Relaxed) == FLUSH_ALL);
+        handle_shootdown();
+
+        assert!(PENDING.load(Ordering::Acquire) == 0);
+        IN_FLIGHT.store(false, Ordering::Release);
+    }
+
+    // 测试等待发起请求或自旋等锁时，发给本 CPU 的请求会被处理：
+    // 模拟另一个 CPU 的请求正在进行且等待本 CPU
+    #[test_case]
+    fn test_request_serviced_while_spinning() {
+        let me = 1 << cpu_id();
+        assert!(try_begin_request());
+
+        // 等待发起自己的请求时
+        post_request(me, Some(&[0x1000]));
+        assert!(!try_begin_request());
+        assert!(PENDING.load(Ordering::Acquire) == 0);
+
+        // 关中断自旋等锁时（sync 的 cpu_relax 钩子）
+        post_request(me, None);
+        sync::ArchOps::cpu_relax(&super::super::SYNC_ARCH_OPS);
+        assert!(PENDING.load(Ordering::Acquire) == 0);
+
+        // 对方完成后本 CPU 可以发起请求
+        IN_FLIGHT.store(false, Ordering::Release);
+        assert!(try_begin_request());
+        IN_FLIGHT.store(false, Ordering::Release);
+    }
+}
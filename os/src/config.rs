@@ -31,6 +31,15 @@ pub const USER_STACK_TOP: usize = SV39_BOT_HALF_TOP - PAGE_SIZE; // leave anothe
 /// the stack mapping ends at `align_down(SV39_BOT_HALF_TOP, PAGE_SIZE)`, so we place the trampoline there.
 pub const USER_SIGRETURN_TRAMPOLINE: usize = SV39_BOT_HALF_TOP & !(PAGE_SIZE - 1);
 
+/// vDSO 代码页的用户态地址（即 AT_SYSINFO_EHDR）。
+///
+/// 位于用户栈底之下，中间隔一个保护页；vDSO 数据页（vvar）紧挨在它前面一页。
+pub const USER_VDSO_BASE: usize =
+    ((USER_STACK_TOP - USER_STACK_SIZE) & !(PAGE_SIZE - 1)) - 2 * PAGE_SIZE;
+
+/// vDSO 数据页（vvar）的用户态地址
+pub const USER_VVAR_BASE: usize = USER_VDSO_BASE - PAGE_SIZE;
+
 /// Maximum heap size (prevent OOM)
 pub const MAX_USER_HEAP_SIZE: usize = 64 * 1024 * 1024; // 64MB
 
@@ -42,6 +51,9 @@ pub const MEMORY_END: usize = crate::arch::platform::virt::MEMORY_END;
 
 pub const DEFAULT_MAX_FDS: usize = 256;
 
+/// pstore 保留内存大小（紧跟内核镜像之后，见 `log::pstore`）
+pub const PSTORE_SIZE: usize = 64 * 1024;
+
 // Ext4 filesystem constants
 /// Ext4 文件系统块大小 (必须与 mkfs.ext4 -b 参数一致)
 pub const EXT4_BLOCK_SIZE: usize = 4096;
@@ -52,7 +64,7 @@ pub const FS_IMAGE_SIZE: usize = 1024 * 1024 * 1024; // 1 GB
 
 // ============ MmConfig trait 实现 ============
 
-use mm::MmConfig;
+use mm::{MemoryNode, MmConfig};
 
 /// OS 内存配置实现
 struct OsMmConfig;
@@ -76,6 +88,12 @@ impl MmConfig for OsMmConfig {
     fn user_sigreturn_trampoline(&self) -> usize {
         USER_SIGRETURN_TRAMPOLINE
     }
+    fn memory_nodes(&self) -> &[MemoryNode] {
+        crate::device::device_tree::early_memory_nodes()
+    }
+    fn current_node(&self) -> usize {
+        crate::device::device_tree::early_cpu_node(crate::arch::kernel::cpu::cpu_id())
+    }
 }
 
 static OS_MM_CONFIG: OsMmConfig = OsMmConfig;
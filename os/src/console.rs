@@ -1,12 +1,16 @@
 //! 统一的控制台抽象
 //!
 //! 提供两阶段控制台：
-//! - 早期阶段：使用 arch::sbi 直接输出
-//! - 运行时阶段：使用 device::console::MAIN_CONSOLE
+//! - 早期阶段：使用 arch::sbi 直接输出，同时记录到 `EARLY_LOG`
+//! - 运行时阶段：输出写到 device::console::CONSOLES 中的所有控制台，
+//!   输入从 device::console::MAIN_CONSOLE 读取
+//!
+//! 切换时早期输出被回放到 SBI 到达不了的控制台（如帧缓冲），见 [`init`]。
 
 use core::fmt::{self, Write};
 use core::sync::atomic::{AtomicBool, Ordering};
 
+use crate::device::console::{CONSOLES, EARLY_LOG};
 use crate::sync::SpinLock;
 
 /// 控制台是否已切换到运行时模式
@@ -16,47 +20,48 @@ static CONSOLE_RUNTIME: AtomicBool = AtomicBool::new(false);
 static CONSOLE_LOCK: SpinLock<()> = SpinLock::new(());
 
 /// 切换到运行时控制台（设备初始化完成后调用）
+///
+/// 在控制台锁内回放早期输出，保证回放内容出现在所有运行时输出之前。
 pub fn init() {
+    let _guard = CONSOLE_LOCK.lock();
+    CONSOLES.handoff(&EARLY_LOG);
     CONSOLE_RUNTIME.store(true, Ordering::Release);
 }
 
+/// 是否已有运行时控制台可用
 #[inline]
-fn write_str_unlocked(s: &str) {
-    if CONSOLE_RUNTIME.load(Ordering::Acquire) {
-        if let Some(console) = crate::device::console::MAIN_CONSOLE.read().as_ref() {
-            console.write_str(s);
-            return;
-        }
-    }
+fn runtime_ready() -> bool {
+    CONSOLE_RUNTIME.load(Ordering::Acquire) && !CONSOLES.is_empty()
+}
 
+/// 早期输出：经 SBI 写出并记录，以便之后回放
+#[inline]
+fn early_write_str(s: &str) {
+    EARLY_LOG.record(s);
     for b in s.bytes() {
         crate::arch::lib::sbi::console_putchar(b as usize);
     }
 }
 
+#[inline]
+fn write_str_unlocked(s: &str) {
+    if runtime_ready() {
+        CONSOLES.write_str(s);
+    } else {
+        early_write_str(s);
+    }
+}
+
 /// 无锁的单字符输出（内部使用）
 #[inline]
 fn putchar_unlocked(c: u8) {
-    if CONSOLE_RUNTIME.load(Ordering::Acquire) {
-        // 运行时：使用 device console
-        if let Some(console) = crate::device::console::MAIN_CONSOLE.read().as_ref() {
-            // `Console::write_str` 只接受 UTF-8 字符串，因此这里只对 ASCII 走 runtime console；
-            // 对于非 ASCII 字节，直接降级到 SBI，避免破坏 UTF-8 多字节序列。
-            if c.is_ascii() {
-                let buf = [c];
-                let s = core::str::from_utf8(&buf).unwrap();
-                console.write_str(s);
-            } else {
-                crate::arch::lib::sbi::console_putchar(c as usize);
-            }
-        } else {
-            // 降级到 SBI
-            crate::arch::lib::sbi::console_putchar(c as usize);
-        }
-    } else {
-        // 早期：使用 arch SBI
+    if !c.is_ascii() {
+        // `Console::write_str` 只接受 UTF-8 字符串，非 ASCII 字节直接走 SBI，避免破坏多字节序列
         crate::arch::lib::sbi::console_putchar(c as usize);
+        return;
     }
+    let buf = [c];
+    write_str_unlocked(core::str::from_utf8(&buf).unwrap());
 }
 
 /// 无锁的单字符输入（内部使用）
@@ -93,6 +98,23 @@ pub fn write_str(s: &str) {
     write_str_unlocked(s);
 }
 
+/// 带锁的日志输出
+///
+/// 运行时按每个控制台的日志级别过滤；`console` 表示 `level` 满足全局控制台级别。
+pub fn write_log(level: u8, s: &str, console: bool) {
+    let _guard = CONSOLE_LOCK.lock();
+    if runtime_ready() {
+        CONSOLES.write_log(level, s, console);
+    } else if console {
+        early_write_str(s);
+    }
+}
+
+/// 是否有运行时控制台单独要求输出 `level` 级别的日志
+pub fn wants_log_level(level: u8) -> bool {
+    runtime_ready() && CONSOLES.wants_level(level)
+}
+
 /// 带锁的单字符输出（公开接口，用于兼容性）
 pub fn putchar(c: u8) {
     let _guard = CONSOLE_LOCK.lock();
@@ -9,3 +9,6 @@ pub use device::block::BlockDriver;
 
 // Re-export device crate 的 RamDisk（用于测试与开发）
 pub use device::block::RamDisk;
+
+// Re-export device crate 的 FaultyDisk（用于崩溃一致性测试）
+pub use device::block::{BadBlockKind, FaultyDisk, PowerCut};
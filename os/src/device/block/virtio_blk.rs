@@ -1,27 +1,136 @@
+//! VirtIO 块设备驱动
+//!
+//! 读写请求以非阻塞方式提交到虚拟队列，提交者在请求对应的 [`Completion`] 上睡眠，
+//! 设备完成请求后由中断处理程序唤醒。还不能睡眠时（启动早期、没有当前任务或中断关闭）退化为轮询。
+
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use alloc::{format, string::String};
-use virtio_drivers::device::blk::VirtIOBlk;
-use virtio_drivers::transport::InterruptStatus;
+use virtio_drivers::device::blk::{BlkReq, BlkResp, VirtIOBlk};
+use virtio_drivers::transport::{InterruptStatus, Transport};
 use virtio_drivers::transport::{mmio::MmioTransport, pci::PciTransport};
 
 use crate::device::virtio_hal::VirtIOHal;
 
 use crate::device::{BLK_DRIVERS, DRIVERS, IRQ_MANAGER, NetDevice};
 use crate::pr_info;
-use crate::sync::Mutex;
+use crate::sync::{Completion, SpinLockIrq};
 
 use super::{
     super::{DeviceType, Driver},
     BlockDriver,
 };
 
+/// 等待完成时的兜底轮询间隔（纳秒），防止丢失中断导致永久睡眠
+const COMPLETION_POLL_INTERVAL: u64 = 10_000_000;
+
+/// VirtIO 块设备及其在途请求
+struct VirtIOBlkDevice<T: Transport> {
+    /// 设备与在途表都会被中断处理程序访问
+    blk: SpinLockIrq<VirtIOBlk<VirtIOHal, T>>,
+    /// 在途请求：描述符链头 token -> 请求的完成量
+    inflight: SpinLockIrq<BTreeMap<u16, Arc<Completion>>>,
+}
+
+impl<T: Transport> VirtIOBlkDevice<T> {
+    fn new(blk: VirtIOBlk<VirtIOHal, T>) -> Self {
+        Self {
+            blk: SpinLockIrq::new(blk),
+            inflight: SpinLockIrq::new(BTreeMap::new()),
+        }
+    }
+
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) -> bool {
+        let mut req = BlkReq::default();
+        let mut resp = BlkResp::default();
+        let done = Arc::new(Completion::new());
+        let token = {
+            let mut blk = self.blk.lock();
+            // SAFETY: req、buf、resp 在 complete_read_blocks 之前保持有效且不被访问
+            let Ok(token) = (unsafe { blk.read_blocks_nb(block_id, &mut req, buf, &mut resp) })
+            else {
+                return false;
+            };
+            // 释放设备锁之前登记，中断处理程序才能找到它
+            self.inflight.lock().insert(token, done.clone());
+            token
+        };
+        self.wait(token, &done);
+        let mut blk = self.blk.lock();
+        // SAFETY: 同上，请求已由设备完成
+        let ok = unsafe { blk.complete_read_blocks(token, &req, buf, &mut resp) }.is_ok();
+        self.complete_used(&mut blk);
+        ok
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) -> bool {
+        let mut req = BlkReq::default();
+        let mut resp = BlkResp::default();
+        let done = Arc::new(Completion::new());
+        let token = {
+            let mut blk = self.blk.lock();
+            // SAFETY: req、buf、resp 在 complete_write_blocks 之前保持有效且不被访问
+            let Ok(token) = (unsafe { blk.write_blocks_nb(block_id, &mut req, buf, &mut resp) })
+            else {
+                return false;
+            };
+            self.inflight.lock().insert(token, done.clone());
+            token
+        };
+        self.wait(token, &done);
+        let mut blk = self.blk.lock();
+        // SAFETY: 同上，请求已由设备完成
+        let ok = unsafe { blk.complete_write_blocks(token, &req, buf, &mut resp) }.is_ok();
+        self.complete_used(&mut blk);
+        ok
+    }
+
+    /// 等待 `token` 对应的请求完成，返回时已从在途表中移除
+    fn wait(&self, token: u16, done: &Completion) {
+        if Self::can_sleep() {
+            while !done.wait_for_completion_timeout(COMPLETION_POLL_INTERVAL) {
+                if self.blk.lock().peek_used() == Some(token) {
+                    break;
+                }
+            }
+        } else {
+            while self.blk.lock().peek_used() != Some(token) {
+                core::hint::spin_loop();
+            }
+        }
+        self.inflight.lock().remove(&token);
+    }
+
+    /// 唤醒已用环队首请求的提交者
+    ///
+    /// 已用环只能按顺序取出，队首请求被取走后要继续唤醒下一个已完成的请求。
+    fn complete_used(&self, blk: &mut VirtIOBlk<VirtIOHal, T>) {
+        let Some(token) = blk.peek_used() else {
+            return;
+        };
+        if let Some(done) = self.inflight.lock().get(&token) {
+            done.complete();
+        }
+    }
+
+    fn handle_interrupt(&self) -> bool {
+        let mut blk = self.blk.lock();
+        let status = blk.ack_interrupt();
+        self.complete_used(&mut blk);
+        status.contains(InterruptStatus::QUEUE_INTERRUPT)
+    }
+
+    fn can_sleep() -> bool {
+        crate::kernel::try_current_task().is_some() && crate::arch::intr::are_interrupts_enabled()
+    }
+}
+
 /// VirtIO 块设备驱动结构体
-pub struct VirtIOBlkDriver(Mutex<VirtIOBlk<VirtIOHal, MmioTransport<'static>>>);
+pub struct VirtIOBlkDriver(VirtIOBlkDevice<MmioTransport<'static>>);
 
 impl Driver for VirtIOBlkDriver {
     fn try_handle_interrupt(&self, _irq: Option<usize>) -> bool {
-        let status = self.0.lock().ack_interrupt();
-        status.contains(InterruptStatus::QUEUE_INTERRUPT)
+        self.0.handle_interrupt()
     }
 
     fn device_type(&self) -> DeviceType {
@@ -51,15 +160,15 @@ impl Driver for VirtIOBlkDriver {
 
 impl BlockDriver for VirtIOBlkDriver {
     fn read_block(&self, block_id: usize, buf: &mut [u8]) -> bool {
-        self.0.lock().read_blocks(block_id, buf).is_ok()
+        self.0.read_block(block_id, buf)
     }
 
     fn write_block(&self, block_id: usize, buf: &[u8]) -> bool {
-        self.0.lock().write_blocks(block_id, buf).is_ok()
+        self.0.write_block(block_id, buf)
     }
 
     fn flush(&self) -> bool {
-        self.0.lock().flush().is_ok()
+        self.0.blk.lock().flush().is_ok()
     }
 
     fn block_size(&self) -> usize {
@@ -67,17 +176,16 @@ impl BlockDriver for VirtIOBlkDriver {
     }
 
     fn total_blocks(&self) -> usize {
-        self.0.lock().capacity() as usize
+        self.0.blk.lock().capacity() as usize
     }
 }
 
 /// VirtIO 块设备驱动结构体（PCI）
-pub struct VirtIOBlkPciDriver(Mutex<VirtIOBlk<VirtIOHal, PciTransport>>);
+pub struct VirtIOBlkPciDriver(VirtIOBlkDevice<PciTransport>);
 
 impl Driver for VirtIOBlkPciDriver {
     fn try_handle_interrupt(&self, _irq: Option<usize>) -> bool {
-        let status = self.0.lock().ack_interrupt();
-        status.contains(InterruptStatus::QUEUE_INTERRUPT)
+        self.0.handle_interrupt()
     }
 
     fn device_type(&self) -> DeviceType {
@@ -107,15 +215,15 @@ impl Driver for VirtIOBlkPciDriver {
 
 impl BlockDriver for VirtIOBlkPciDriver {
     fn read_block(&self, block_id: usize, buf: &mut [u8]) -> bool {
-        self.0.lock().read_blocks(block_id, buf).is_ok()
+        self.0.read_block(block_id, buf)
     }
 
     fn write_block(&self, block_id: usize, buf: &[u8]) -> bool {
-        self.0.lock().write_blocks(block_id, buf).is_ok()
+        self.0.write_block(block_id, buf)
     }
 
     fn flush(&self) -> bool {
-        self.0.lock().flush().is_ok()
+        self.0.blk.lock().flush().is_ok()
     }
 
     fn block_size(&self) -> usize {
@@ -123,14 +231,14 @@ impl BlockDriver for VirtIOBlkPciDriver {
     }
 
     fn total_blocks(&self) -> usize {
-        self.0.lock().capacity() as usize
+        self.0.blk.lock().capacity() as usize
     }
 }
 
 /// 初始化 VirtIO 块设备驱动
 pub fn init(transport: MmioTransport<'static>) {
     let blk = VirtIOBlk::new(transport).expect("failed to init blk driver");
-    let driver = Arc::new(VirtIOBlkDriver(Mutex::new(blk)));
+    let driver = Arc::new(VirtIOBlkDriver(VirtIOBlkDevice::new(blk)));
     DRIVERS.write().push(driver.clone());
     IRQ_MANAGER.write().register_all(driver.clone());
     BLK_DRIVERS.write().push(driver);
@@ -140,7 +248,7 @@ pub fn init(transport: MmioTransport<'static>) {
 /// 初始化 VirtIO 块设备驱动（PCI）
 pub fn init_pci(transport: PciTransport) {
     let blk = VirtIOBlk::new(transport).expect("failed to init pci blk driver");
-    let driver = Arc::new(VirtIOBlkPciDriver(Mutex::new(blk)));
+    let driver = Arc::new(VirtIOBlkPciDriver(VirtIOBlkDevice::new(blk)));
     DRIVERS.write().push(driver.clone());
     IRQ_MANAGER.write().register_all(driver.clone());
     BLK_DRIVERS.write().push(driver);
@@ -212,8 +320,12 @@ mod tests {
     #[test_case]
     fn test_virtioblk_interrupt_path() {
         // 如果系统已完成 init()，则可以遍历全局驱动集合取出 virtio_block 测试。
-        let list = BLK_DRIVERS.read();
-        if let Some(drv) = list.iter().find(|d| d.get_id() == "virtio_block") {
+        let drv = BLK_DRIVERS
+            .read()
+            .iter()
+            .find(|d| d.get_id() == "virtio_block")
+            .cloned();
+        if let Some(drv) = drv {
             // 调用中断处理函数，预期返回 true
             assert!(drv.try_handle_interrupt(None));
         } else {
@@ -225,8 +337,12 @@ mod tests {
     // 读写轮询逻辑测试：若存在真实 virtio-blk 驱动则执行，否则跳过
     #[test_case]
     fn test_virtioblk_read_write_roundtrip() {
-        let list = BLK_DRIVERS.read();
-        if let Some(drv) = list.iter().find(|d| d.get_id() == "virtio_block") {
+        let drv = BLK_DRIVERS
+            .read()
+            .iter()
+            .find(|d| d.get_id() == "virtio_block")
+            .cloned();
+        if let Some(drv) = drv {
             // check_common_driver_behavior(drv.as_ref()); // 需要 trait_upcasting 特性
             let block_iface = drv.as_block().unwrap();
             // 尝试测试第 0 号块（实际系统中可根据分配策略选择安全块号）
@@ -242,8 +358,12 @@ mod tests {
     // 额外：可重复多块写读测试（提高覆盖率），仅在存在设备时执行
     #[test_case]
     fn test_virtioblk_multi_block_pattern() {
-        let list = BLK_DRIVERS.read();
-        if let Some(drv) = list.iter().find(|d| d.get_id() == "virtio_block") {
+        let drv = BLK_DRIVERS
+            .read()
+            .iter()
+            .find(|d| d.get_id() == "virtio_block")
+            .cloned();
+        if let Some(drv) = drv {
             let block_iface = drv.as_block().unwrap();
             // 测试前 4 个块（根据实际介质大小，避免越界；这里假设安全）
             for bid in 0..4 {
@@ -3,7 +3,7 @@
 //! 该模块负责：
 //! - 在设备树注册 `compatible = "virtio,mmio"` 的探测函数；
 //! - 解析节点 `reg`，映射 MMIO 区域并构造 `MmioTransport`；
-//! - 根据 `device_type()` 将初始化流程分发到对应设备驱动（blk/net/gpu/input）。
+//! - 根据 `device_type()` 将初始化流程分发到对应设备驱动（blk/net/gpu/input/rng）。
 //!
 //! 说明：这里只负责“传输层探测 + 分发”，具体设备语义由各子模块实现。
 use core::ptr::NonNull;
@@ -17,7 +17,7 @@ use virtio_drivers::transport::{
 use crate::{
     device::{
         block::virtio_blk, device_tree::DEVICE_TREE_REGISTRY, gpu::virtio_gpu, input::virtio_input,
-        net::virtio_net,
+        net::virtio_net, rng::virtio_rng,
     },
     kernel::current_memory_space,
     mm::address::{Paddr, UsizeConvert},
@@ -71,6 +71,7 @@ fn virtio_device(transport: MmioTransport<'static>) {
         DeviceType::GPU => virtio_gpu::init(transport),
         DeviceType::Input => virtio_input::init(transport),
         DeviceType::Network => virtio_net::init(transport),
+        DeviceType::EntropySource => virtio_rng::init(transport),
         t => pr_warn!("Unrecognized virtio device: {:?}", t),
     }
 }
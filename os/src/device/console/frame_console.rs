@@ -1,15 +1,51 @@
-// Console -> TextBuffer -> FrameBuffer
-
-/// 初始化控制台设备
-pub fn init() {
-    // if cfg!(feature = "consolegraphic") {
-    //     if let Some(fb) = FRAME_BUFFER.write().take() {
-    //         // TODO: now take FrameBuffer out of global variable, then move into Console
-    //         let console = Console::on_frame_buffer(fb.fb_info.xres, fb.fb_info.yres, fb);
-    //         *CONSOLE.lock() = Some(console);
-    //         pr_info!("console: init end");
-    //     } else {
-    //         pr_warn!("console: init failed");
-    //     }
-    // }
+//! 帧缓冲控制台
+//!
+//! Console -> FrameConsole (VT) -> FrameBuffer
+//!
+//! 显示设备驱动在获得帧缓冲后调用 [`init`]，输入设备驱动通过 [`handle_key`] 转发快捷键。
+
+use alloc::{boxed::Box, sync::Arc};
+use lazy_static::lazy_static;
+
+use crate::device::{CMDLINE, console::CONSOLES};
+use crate::pr_info;
+use crate::sync::RwLock;
+use device::console::{
+    ConsoleTarget, EARLY_LOG, FrameBuffer, FrameConsole, Key, Modifiers, console_params,
+};
+
+/// 虚拟终端数量
+const VT_COUNT: usize = 6;
+
+lazy_static! {
+    /// 帧缓冲控制台实例，未初始化显示设备时为 `None`
+    pub static ref FRAME_CONSOLE: RwLock<Option<Arc<FrameConsole>>> = RwLock::new(None);
+}
+
+/// 在帧缓冲上初始化控制台并登记到全局控制台列表
+///
+/// 早期输出到达不了帧缓冲，切换到运行时控制台时回放给它。
+pub fn init(fb: Box<dyn FrameBuffer>) {
+    let (width, height) = (fb.width(), fb.height());
+    let console = Arc::new(FrameConsole::new(fb, VT_COUNT));
+    FRAME_CONSOLE.write().replace(console.clone());
+    let loglevel = console_params(&CMDLINE.read())
+        .into_iter()
+        .filter(|param| matches!(param.target, ConsoleTarget::Vt(_)))
+        .find_map(|param| param.loglevel);
+    CONSOLES.register(console, loglevel, true, &EARLY_LOG);
+    pr_info!(
+        "[Console] Frame console initialized ({}x{}, {} VTs)",
+        width,
+        height,
+        VT_COUNT
+    );
+}
+
+/// 处理虚拟终端切换等控制台快捷键，返回按键是否已被消费
+pub fn handle_key(key: Key, modifiers: Modifiers) -> bool {
+    FRAME_CONSOLE
+        .read()
+        .as_ref()
+        .is_some_and(|console| console.handle_key(key, modifiers))
 }
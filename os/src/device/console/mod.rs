@@ -4,14 +4,54 @@ pub mod frame_console;
 pub mod uart_console;
 
 // Re-export device crate 的 Console 类型
-pub use device::console::{CONSOLES, Console, MAIN_CONSOLE};
+pub use device::console::{CONSOLES, Console, EARLY_LOG, MAIN_CONSOLE};
+
+use device::console::{ConsoleTarget, console_targets};
+use vfs::{chrdev_major, dev::makedev};
+
+use crate::device::{CMDLINE, SERIAL_DRIVERS};
 
 /// 初始化控制台设备
+///
+/// 内核输出写到所有已登记的控制台（串口、帧缓冲），输入从第一个控制台读取。
 pub fn init() {
-    MAIN_CONSOLE.write().replace(CONSOLES.read()[0].clone());
-    // frame_console::init();
+    MAIN_CONSOLE.write().replace(
+        CONSOLES
+            .get(0)
+            .expect("[Console] No console device registered"),
+    );
+    // 帧缓冲控制台由显示设备驱动在获得帧缓冲后通过 frame_console::init 注册
 
-    // 切换到运行时控制台
+    // 切换到运行时控制台，早期输出回放到帧缓冲等控制台
     crate::console::init();
-    crate::pr_info!("[Console] Switched to runtime console");
+    crate::pr_info!(
+        "[Console] Switched to runtime console ({} consoles)",
+        CONSOLES.len()
+    );
+}
+
+/// 控制台目标对应的终端设备号，设备不存在时返回 `None`
+fn target_device(target: ConsoleTarget) -> Option<u64> {
+    match target {
+        ConsoleTarget::Serial(n) if n < SERIAL_DRIVERS.read().len() => {
+            Some(makedev(chrdev_major::TTY, 64 + n as u32))
+        }
+        ConsoleTarget::Vt(n) => {
+            let console = frame_console::FRAME_CONSOLE.read().clone()?;
+            (n <= console.count()).then(|| makedev(chrdev_major::TTY, n as u32))
+        }
+        _ => None,
+    }
+}
+
+/// `/dev/console` 对应的终端设备号
+///
+/// 取启动参数中最后一个存在的 `console=` 设备；未指定时依次退回到 ttyS0、tty0。
+pub fn console_device() -> Option<u64> {
+    console_targets(&CMDLINE.read())
+        .into_iter()
+        .rev()
+        .find_map(target_device)
+        .or_else(|| target_device(ConsoleTarget::Serial(0)))
+        .or_else(|| target_device(ConsoleTarget::Vt(0)))
 }
@@ -2,7 +2,10 @@
 
 use alloc::{string::String, sync::Arc};
 
+use device::console::{ConsoleTarget, EARLY_LOG, console_params};
+
 use crate::device::{
+    CMDLINE,
     console::{CONSOLES, Console},
     serial::SerialDriver,
 };
@@ -37,7 +40,14 @@ impl Console for UARTConsole {
     }
 }
 
-pub fn init(uart: Arc<dyn SerialDriver>) {
+/// 把第 `index` 个串口登记为控制台
+///
+/// 早期输出经 SBI 写到的就是这个串口，交接时无需回放。
+pub fn init(uart: Arc<dyn SerialDriver>, index: usize) {
+    let loglevel = console_params(&CMDLINE.read())
+        .into_iter()
+        .filter(|param| param.target == ConsoleTarget::Serial(index))
+        .find_map(|param| param.loglevel);
     let console = Arc::new(UARTConsole { uart });
-    CONSOLES.write().push(console);
+    CONSOLES.register(console, loglevel, false, &EARLY_LOG);
 }
@@ -14,7 +14,7 @@
 //! ## Phase 1: 早期解析（无堆分配）
 //! `phase1_early_parse()` 在 `mm::init()` 之前调用，直接解析设备树二进制数据：
 //! - 提取 CPU 数量、时钟频率
-//! - 提取内存区域信息
+//! - 提取内存区域信息及其所属的 NUMA 节点（`numa-node-id`）
 //! - 存储到固定大小的静态数组（不使用堆分配）
 //!
 //! ## Phase 2: 完整初始化（可用堆分配）
@@ -23,6 +23,7 @@
 //! 2) 初始化其余设备节点（例如 virtio-mmio、rtc、net 等）。
 
 use crate::{
+    config::MAX_CPU_COUNT,
     device::{CMDLINE, irq::IntcDriver},
     kernel::{CLOCK_FREQ, NUM_CPU},
     mm::address::{ConvertablePaddr, Paddr, UsizeConvert},
@@ -31,6 +32,7 @@ use crate::{
 };
 use alloc::{collections::btree_map::BTreeMap, string::String, sync::Arc};
 use fdt::{Fdt, node::FdtNode};
+use mm::MemoryNode;
 /// 指向设备树的指针，在启动时由引导程序设置
 #[unsafe(no_mangle)]
 pub static mut DTP: usize = 0x114514; // 占位地址，实际由引导程序设置
@@ -38,13 +40,6 @@ pub static mut DTP: usize = 0x114514; // 占位地址，实际由引导程序设
 /// Phase 1 提取的内存区域信息（最多支持 8 个区域）
 const MAX_MEMORY_REGIONS: usize = 8;
 
-/// 内存区域描述
-#[derive(Copy, Clone)]
-struct MemoryRegion {
-    start: usize,
-    size: usize,
-}
-
 /// Phase 1 提取的早期设备树信息
 struct EarlyDtInfo {
     /// CPU 核心数量
@@ -53,8 +48,10 @@ struct EarlyDtInfo {
     clock_freq: usize,
     /// 内存区域数量
     memory_region_count: usize,
-    /// 内存区域列表
-    memory_regions: [MemoryRegion; MAX_MEMORY_REGIONS],
+    /// 内存区域列表（物理地址区间及所属 NUMA 节点）
+    memory_regions: [MemoryNode; MAX_MEMORY_REGIONS],
+    /// 各 CPU（按设备树 cpu 节点顺序）所属的 NUMA 节点
+    cpu_nodes: [usize; MAX_CPU_COUNT],
 }
 
 impl EarlyDtInfo {
@@ -63,7 +60,12 @@ impl EarlyDtInfo {
             num_cpus: 1,
             clock_freq: 12_500_000,
             memory_region_count: 0,
-            memory_regions: [MemoryRegion { start: 0, size: 0 }; MAX_MEMORY_REGIONS],
+            memory_regions: [MemoryNode {
+                node: 0,
+                start: 0,
+                end: 0,
+            }; MAX_MEMORY_REGIONS],
+            cpu_nodes: [0; MAX_CPU_COUNT],
         }
     }
 }
@@ -134,24 +136,51 @@ pub unsafe fn phase1_early_parse() {
         }
     }
 
-    // 提取内存区域信息
+    // 提取各 CPU 所属的 NUMA 节点
+    for (i, cpu) in fdt.cpus().take(MAX_CPU_COUNT).enumerate() {
+        EARLY_DT_INFO.cpu_nodes[i] = numa_node_id(cpu.property("numa-node-id"));
+    }
+
+    // 提取内存区域信息：多插槽平台每个节点各有一个 memory 节点
     let mut count = 0;
-    for region in fdt.memory().regions() {
-        if count >= MAX_MEMORY_REGIONS {
-            break;
+    for node in fdt.all_nodes() {
+        if !is_memory_node(&node) {
+            continue;
         }
-        let size = region.size.unwrap_or(0) as usize;
-        if size > 0 {
-            EARLY_DT_INFO.memory_regions[count] = MemoryRegion {
-                start: region.starting_address as usize,
-                size,
-            };
-            count += 1;
+        let numa = numa_node_id(node.property("numa-node-id"));
+        for region in node.reg().into_iter().flatten() {
+            if count >= MAX_MEMORY_REGIONS {
+                break;
+            }
+            let size = region.size.unwrap_or(0);
+            if size > 0 {
+                let start = region.starting_address as usize;
+                EARLY_DT_INFO.memory_regions[count] = MemoryNode {
+                    node: numa,
+                    start,
+                    end: start.saturating_add(size),
+                };
+                count += 1;
+            }
         }
     }
     EARLY_DT_INFO.memory_region_count = count;
 }
 
+/// 是否为描述物理内存的节点（`device_type = "memory"`）
+fn is_memory_node(node: &FdtNode) -> bool {
+    match node.property("device_type") {
+        Some(p) => p.value.split(|b| *b == 0).next() == Some(b"memory".as_slice()),
+        None => node.name == "memory" || node.name.starts_with("memory@"),
+    }
+}
+
+/// 解析 `numa-node-id` 属性（大端 u32），缺省为节点 0
+fn numa_node_id(prop: Option<fdt::node::NodeProperty<'_>>) -> usize {
+    prop.and_then(|p| Some(u32::from_be_bytes(p.value.get(..4)?.try_into().ok()?) as usize))
+        .unwrap_or(0)
+}
+
 /// 获取 Phase 1 提取的 CPU 数量
 pub fn early_num_cpus() -> usize {
     unsafe { EARLY_DT_INFO.num_cpus }
@@ -175,7 +204,7 @@ pub fn early_dram_info() -> Option<(usize, usize)> {
         for i in 0..EARLY_DT_INFO.memory_region_count {
             let region = &EARLY_DT_INFO.memory_regions[i];
             let s = region.start;
-            let e = s.saturating_add(region.size);
+            let e = region.end;
             if s < start {
                 start = s;
             }
@@ -192,6 +221,20 @@ pub fn early_dram_info() -> Option<(usize, usize)> {
     }
 }
 
+/// 获取 Phase 1 提取的各内存区域及其所属的 NUMA 节点
+pub fn early_memory_nodes() -> &'static [MemoryNode] {
+    // SAFETY: Phase 1 之后 EARLY_DT_INFO 不再被修改
+    unsafe {
+        let info = &*core::ptr::addr_of!(EARLY_DT_INFO);
+        &info.memory_regions[..info.memory_region_count]
+    }
+}
+
+/// 获取 Phase 1 提取的 CPU `cpu` 所属的 NUMA 节点
+pub fn early_cpu_node(cpu: usize) -> usize {
+    unsafe { EARLY_DT_INFO.cpu_nodes[cpu % MAX_CPU_COUNT] }
+}
+
 /// 早期初始化: 只解析 CPU 数量和时钟频率
 ///
 /// 此函数在堆分配器初始化之前调用,因此不能使用任何需要堆分配的操作。
@@ -250,9 +293,10 @@ pub fn phase2_full_init() {
         for i in 0..EARLY_DT_INFO.memory_region_count {
             let region = &EARLY_DT_INFO.memory_regions[i];
             pr_info!(
-                "[Device] Memory Region: Start = {:#X}, Size = {:#X}",
+                "[Device] Memory Region: Start = {:#X}, Size = {:#X}, Node = {}",
                 region.start,
-                region.size
+                region.end - region.start,
+                region.node
             );
         }
     }
@@ -261,6 +305,7 @@ pub fn phase2_full_init() {
         if !bootargs.is_empty() {
             pr_info!("Kernel cmdline: {}", bootargs);
             *CMDLINE.write() = String::from(bootargs);
+            crate::log::apply_cmdline();
         }
     }
 
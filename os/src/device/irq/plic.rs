@@ -34,6 +34,7 @@ impl Driver for Plic {
         let pending: u32 = read(self.base + 0x1000);
         if pending != 0 {
             let claim: u32 = read(self.base + 0x201004);
+            crate::security::random::add_interrupt_randomness(claim as usize);
             let manager = self.manager.lock();
             let res = manager.try_handle_interrupt(Some(claim as usize));
             write(self.base + 0x201004, claim);
@@ -30,6 +30,7 @@ pub mod gpu;
 pub mod input;
 pub mod irq;
 pub mod net;
+pub mod rng;
 pub mod rtc;
 pub mod serial;
 pub mod virtio_hal;
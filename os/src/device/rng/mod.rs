@@ -0,0 +1,18 @@
+//! 硬件随机数发生器
+//!
+//! 目前只支持 virtio-rng。设备输出由 [`crate::security::random`] 混合进熵池。
+
+pub mod virtio_rng;
+
+use crate::sync::SpinLock;
+use virtio_rng::VirtIORng;
+
+/// 已探测到的硬件随机数发生器
+static HWRNG: SpinLock<Option<VirtIORng>> = SpinLock::new(None);
+
+/// 从硬件随机数发生器读取随机字节，返回实际读到的字节数
+///
+/// 没有可用设备时返回 0。
+pub fn hwrng_read(buf: &mut [u8]) -> usize {
+    HWRNG.lock().as_mut().map_or(0, |rng| rng.read(buf))
+}
@@ -0,0 +1,161 @@
+//! virtio-rng 驱动
+//!
+//! 设备只有一个请求队列：驱动提交一块设备可写的缓冲区，设备填入随机字节后归还。
+//! 这里使用只含一个描述符的队列，按请求同步轮询完成，不依赖中断。
+//!
+//! 队列按传统（legacy）布局放在两页连续的 DMA 内存中：描述符表与可用环在第一页，
+//! 已用环从第二页开始；第三页是数据缓冲区。该布局同样满足现代设备的对齐要求。
+
+use core::ptr::NonNull;
+use core::sync::atomic::{Ordering, fence};
+
+use virtio_drivers::transport::{DeviceStatus, Transport, mmio::MmioTransport};
+use virtio_drivers::{BufferDirection, Hal, PhysAddr};
+
+use super::HWRNG;
+use crate::config::PAGE_SIZE;
+use crate::device::virtio_hal::VirtIOHal;
+use crate::{pr_info, pr_warn};
+
+/// 请求队列编号
+const QUEUE_REQUEST: u16 = 0;
+/// 队列长度
+const QUEUE_SIZE: u16 = 1;
+/// DMA 区域的页数：两页队列 + 一页数据缓冲区
+const DMA_PAGES: usize = 3;
+/// 可用环在 DMA 区域中的偏移（紧跟描述符表）
+const AVAIL_OFFSET: usize = 16 * QUEUE_SIZE as usize;
+/// 已用环在 DMA 区域中的偏移
+const USED_OFFSET: usize = PAGE_SIZE;
+/// 数据缓冲区在 DMA 区域中的偏移
+const BUFFER_OFFSET: usize = 2 * PAGE_SIZE;
+/// 单次请求的最大字节数
+const BUFFER_SIZE: usize = 64;
+/// 描述符标志：设备可写
+const VRING_DESC_F_WRITE: u16 = 2;
+/// VIRTIO_F_VERSION_1 特性位
+const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+/// 等待请求完成的最大轮询次数
+const POLL_LIMIT: usize = 1_000_000;
+
+/// virtio-rng 设备
+pub struct VirtIORng {
+    transport: MmioTransport<'static>,
+    paddr: PhysAddr,
+    vaddr: NonNull<u8>,
+    /// 已提交的请求数（可用环的 idx）
+    avail_idx: u16,
+    /// 请求超时后不再使用设备，避免与迟到的完成错位
+    broken: bool,
+}
+
+// SAFETY: DMA 区域只通过 HWRNG 锁访问
+unsafe impl Send for VirtIORng {}
+
+impl VirtIORng {
+    fn new(mut transport: MmioTransport<'static>) -> Option<Self> {
+        // 协商特性：不需要任何设备特性，现代设备必须确认 VERSION_1
+        transport.set_status(DeviceStatus::empty());
+        transport.set_status(DeviceStatus::ACKNOWLEDGE | DeviceStatus::DRIVER);
+        let features = transport.read_device_features();
+        transport.write_driver_features(features & VIRTIO_F_VERSION_1);
+        transport.set_status(
+            DeviceStatus::ACKNOWLEDGE | DeviceStatus::DRIVER | DeviceStatus::FEATURES_OK,
+        );
+        transport.set_guest_page_size(PAGE_SIZE as u32);
+
+        if transport.max_queue_size(QUEUE_REQUEST) < QUEUE_SIZE as u32 {
+            return None;
+        }
+        let (paddr, vaddr) = VirtIOHal::dma_alloc(DMA_PAGES, BufferDirection::Both);
+        if paddr == 0 as PhysAddr {
+            return None;
+        }
+        transport.queue_set(
+            QUEUE_REQUEST,
+            QUEUE_SIZE as u32,
+            paddr,
+            paddr + AVAIL_OFFSET as PhysAddr,
+            paddr + USED_OFFSET as PhysAddr,
+        );
+        transport.finish_init();
+
+        Some(Self {
+            transport,
+            paddr,
+            vaddr,
+            avail_idx: 0,
+            broken: false,
+        })
+    }
+
+    fn ptr<T>(&self, offset: usize) -> *mut T {
+        // SAFETY: 偏移都在 DMA_PAGES 页的范围内
+        unsafe { self.vaddr.as_ptr().add(offset) as *mut T }
+    }
+
+    /// 提交一次请求并等待设备填充，返回读到的字节数
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        if self.broken {
+            return 0;
+        }
+        let len = buf.len().min(BUFFER_SIZE);
+        let next = self.avail_idx.wrapping_add(1);
+        unsafe {
+            // 描述符：addr(u64) len(u32) flags(u16) next(u16)
+            self.ptr::<u64>(0)
+                .write_volatile((self.paddr + BUFFER_OFFSET as PhysAddr) as u64);
+            self.ptr::<u32>(8).write_volatile(len as u32);
+            self.ptr::<u16>(12).write_volatile(VRING_DESC_F_WRITE);
+            self.ptr::<u16>(14).write_volatile(0);
+            // 可用环：flags(u16) idx(u16) ring[QUEUE_SIZE](u16)
+            let slot = (self.avail_idx % QUEUE_SIZE) as usize;
+            self.ptr::<u16>(AVAIL_OFFSET + 4 + 2 * slot)
+                .write_volatile(0);
+            fence(Ordering::SeqCst);
+            self.ptr::<u16>(AVAIL_OFFSET + 2).write_volatile(next);
+            fence(Ordering::SeqCst);
+        }
+        self.transport.notify(QUEUE_REQUEST);
+
+        // 已用环：flags(u16) idx(u16) ring[QUEUE_SIZE]{id(u32) len(u32)}
+        let mut done = false;
+        for _ in 0..POLL_LIMIT {
+            fence(Ordering::SeqCst);
+            if unsafe { self.ptr::<u16>(USED_OFFSET + 2).read_volatile() } == next {
+                done = true;
+                break;
+            }
+            core::hint::spin_loop();
+        }
+        self.avail_idx = next;
+        let _ = self.transport.ack_interrupt();
+        if !done {
+            pr_warn!("[Device] virtio-rng request timed out");
+            self.broken = true;
+            return 0;
+        }
+
+        let slot = (next.wrapping_sub(1) % QUEUE_SIZE) as usize;
+        let used_len = unsafe {
+            self.ptr::<u32>(USED_OFFSET + 4 + 8 * slot + 4)
+                .read_volatile()
+        };
+        let n = (used_len as usize).min(len);
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.ptr::<u8>(BUFFER_OFFSET), buf.as_mut_ptr(), n);
+        }
+        n
+    }
+}
+
+/// 初始化 virtio-rng 设备并登记为硬件随机数发生器
+pub fn init(transport: MmioTransport<'static>) {
+    match VirtIORng::new(transport) {
+        Some(rng) => {
+            *HWRNG.lock() = Some(rng);
+            pr_info!("[Device] Entropy source (virtio-rng) is initialized");
+        }
+        None => pr_warn!("[Device] Failed to initialize virtio-rng"),
+    }
+}
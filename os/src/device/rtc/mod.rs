@@ -2,5 +2,70 @@
 
 pub mod rtc_goldfish;
 
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike, Utc};
+use uapi::ioctl::RtcTime;
+
 // Re-export device crate 的 RTC 类型
 pub use device::rtc::{DateTime, RTC_DRIVERS, RtcDriver};
+
+/// 把自纪元以来的秒数转换为 RTC ioctl 使用的 UTC 时间
+pub fn rtc_time_from_epoch(epoch: u64) -> RtcTime {
+    let Some(time) = Utc.timestamp_opt(epoch as i64, 0).single() else {
+        return RtcTime::default();
+    };
+    RtcTime {
+        tm_sec: time.second() as i32,
+        tm_min: time.minute() as i32,
+        tm_hour: time.hour() as i32,
+        tm_mday: time.day() as i32,
+        tm_mon: time.month0() as i32,
+        tm_year: time.year() - 1900,
+        tm_wday: time.weekday().num_days_from_sunday() as i32,
+        tm_yday: time.ordinal0() as i32,
+        tm_isdst: 0,
+    }
+}
+
+/// 把 RTC ioctl 传入的 UTC 时间转换为自纪元以来的秒数
+///
+/// 字段超出范围或早于纪元时返回 `None`；`tm_wday`、`tm_yday` 与 `tm_isdst` 被忽略。
+pub fn rtc_time_to_epoch(tm: &RtcTime) -> Option<u64> {
+    let time = NaiveDate::from_ymd_opt(
+        tm.tm_year.checked_add(1900)?,
+        u32::try_from(tm.tm_mon).ok()? + 1,
+        u32::try_from(tm.tm_mday).ok()?,
+    )?
+    .and_hms_opt(
+        u32::try_from(tm.tm_hour).ok()?,
+        u32::try_from(tm.tm_min).ok()?,
+        u32::try_from(tm.tm_sec).ok()?,
+    )?;
+    u64::try_from(time.and_utc().timestamp()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 纪元秒数与 RTC 时间互相转换
+    #[test_case]
+    fn test_rtc_time_roundtrip() {
+        // 2024-02-29 12:34:56 UTC，星期四
+        let tm = rtc_time_from_epoch(1_709_210_096);
+        assert!(tm.tm_year == 124 && tm.tm_mon == 1 && tm.tm_mday == 29);
+        assert!(tm.tm_hour == 12 && tm.tm_min == 34 && tm.tm_sec == 56);
+        assert!(tm.tm_wday == 4 && tm.tm_yday == 59);
+        assert!(rtc_time_to_epoch(&tm) == Some(1_709_210_096));
+    }
+
+    // 非法日期和纪元之前的时间被拒绝
+    #[test_case]
+    fn test_rtc_time_invalid() {
+        let mut tm = rtc_time_from_epoch(0);
+        tm.tm_mon = 12;
+        assert!(rtc_time_to_epoch(&tm).is_none());
+        let mut tm = rtc_time_from_epoch(0);
+        tm.tm_year = 69;
+        assert!(rtc_time_to_epoch(&tm).is_none());
+    }
+}
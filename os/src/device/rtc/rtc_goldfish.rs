@@ -8,7 +8,7 @@ use crate::{
     kernel::current_memory_space,
     mm::address::{Paddr, UsizeConvert},
     pr_info, pr_warn,
-    util::read,
+    util::{read, write},
 };
 
 const TIMER_TIME_LOW: usize = 0x00;
@@ -44,6 +44,14 @@ impl RtcDriver for RtcGoldfish {
         let ns = ((high as u64) << 32) | (low as u64);
         ns / 1_000_000_000u64
     }
+
+    // 先写高 32 位，写低 32 位时设备才会更新时间
+    fn set_epoch(&self, epoch: u64) -> bool {
+        let ns = epoch.saturating_mul(1_000_000_000u64);
+        write(self.base + TIMER_TIME_HIGH, (ns >> 32) as u32);
+        write(self.base + TIMER_TIME_LOW, ns as u32);
+        true
+    }
 }
 
 fn init_dt(dt: &FdtNode) {
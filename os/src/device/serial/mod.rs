@@ -8,3 +8,14 @@ pub mod virtio_console;
 
 // Re-export device crate 的 SerialDriver trait
 pub use device::serial::{SERIAL_DRIVERS, SerialDriver};
+
+/// 把第 `index` 个串口收到的数据送入对应终端（ttyS`index`）的行规程
+///
+/// 由串口驱动在接收中断中调用。
+pub fn receive_input(index: usize, data: &[u8]) {
+    use vfs::{chrdev_major, dev::makedev};
+
+    if let Some(tty) = vfs::tty_for_device(makedev(chrdev_major::TTY, 64 + index as u32)) {
+        tty.receive(data);
+    }
+}
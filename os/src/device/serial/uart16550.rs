@@ -1,13 +1,16 @@
 //! 16550 UART 串行端口驱动程序模块
 
 use alloc::{format, sync::Arc};
+use core::sync::atomic::{AtomicBool, Ordering};
 use fdt::node::FdtNode;
 use uart_16550::MmioSerialPort;
 
 use crate::{
     device::{
-        DRIVERS, DeviceType, Driver, SERIAL_DRIVERS, console::uart_console,
-        device_tree::DEVICE_TREE_REGISTRY, serial::SerialDriver,
+        DRIVERS, DeviceType, Driver, SERIAL_DRIVERS,
+        console::uart_console,
+        device_tree::{DEVICE_TREE_INTC, DEVICE_TREE_REGISTRY},
+        serial::{self, SerialDriver},
     },
     kernel::current_memory_space,
     mm::address::{Paddr, UsizeConvert},
@@ -18,11 +21,37 @@ use crate::{
 /// 16550 UART 串行端口驱动程序结构体
 pub struct Uart16550 {
     serial_port: SpinLock<MmioSerialPort>,
+    /// 在 `SERIAL_DRIVERS` 中的下标（即 ttyS 编号）
+    index: usize,
+    /// 接收中断是否已注册到中断控制器
+    rx_irq: AtomicBool,
 }
 
 impl Driver for Uart16550 {
-    fn try_handle_interrupt(&self, irq: Option<usize>) -> bool {
-        todo!()
+    fn try_handle_interrupt(&self, _irq: Option<usize>) -> bool {
+        // 先取空接收 FIFO 再交给终端，避免持有串口锁时回显
+        let mut buf = [0u8; 16];
+        let mut received = false;
+        loop {
+            let mut n = 0;
+            {
+                let mut port = self.serial_port.lock();
+                while n < buf.len() {
+                    match port.try_receive() {
+                        Ok(byte) => {
+                            buf[n] = byte;
+                            n += 1;
+                        }
+                        Err(_) => break,
+                    }
+                }
+            }
+            if n == 0 {
+                return received;
+            }
+            received = true;
+            serial::receive_input(self.index, &buf[..n]);
+        }
     }
 
     fn device_type(&self) -> crate::device::DeviceType {
@@ -56,6 +85,29 @@ impl SerialDriver for Uart16550 {
             Err(_) => None,
         }
     }
+
+    fn rx_irq_enabled(&self) -> bool {
+        self.rx_irq.load(Ordering::Acquire)
+    }
+}
+
+/// 把串口的接收中断注册到设备树中声明的中断控制器
+fn register_irq(node: &FdtNode, driver: Arc<Uart16550>) -> bool {
+    let Some(irq) = node.interrupts().and_then(|mut irqs| irqs.next()) else {
+        return false;
+    };
+    let Some(phandle) = node
+        .interrupt_parent()
+        .and_then(|parent| parent.property("phandle"))
+        .and_then(|prop| prop.as_usize())
+    else {
+        return false;
+    };
+    let Some(intc) = DEVICE_TREE_INTC.read().get(&(phandle as u32)).cloned() else {
+        return false;
+    };
+    intc.register_local_irq(irq, driver);
+    true
 }
 
 pub fn init(node: &FdtNode) {
@@ -81,10 +133,20 @@ pub fn init(node: &FdtNode) {
     serial_port.init();
     let driver = Arc::new(Uart16550 {
         serial_port: SpinLock::new(serial_port),
+        index: SERIAL_DRIVERS.read().len(),
+        rx_irq: AtomicBool::new(false),
     });
     DRIVERS.write().push(driver.clone());
     SERIAL_DRIVERS.write().push(driver.clone());
-    uart_console::init(driver);
+    if register_irq(node, driver.clone()) {
+        driver.rx_irq.store(true, Ordering::Release);
+    } else {
+        pr_warn!(
+            "[Device] ns16550a {} has no usable interrupt, falling back to polling",
+            node.name
+        );
+    }
+    uart_console::init(driver.clone(), driver.index);
     pr_info!("[Device] Serial driver (uart16550) is initialized");
 }
 
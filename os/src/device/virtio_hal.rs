@@ -1,17 +1,22 @@
 //! HAL (硬件抽象层) 实现，用于适配 virtio-drivers 0.12.0 库
 
-use crate::arch::mm::{paddr_to_vaddr, vaddr_to_paddr};
+use crate::arch::mm::paddr_to_vaddr;
+use crate::config::PAGE_SIZE;
+use crate::mm::kstack::is_kstack_addr;
+use crate::mm::vmalloc::is_vmalloc_addr;
 use crate::sync::SpinLock;
 use alloc::collections::btree_map::BTreeMap;
 use core::ptr::NonNull;
 use lazy_static::lazy_static;
-use mm::address::{ConvertablePaddr, PageNum, UsizeConvert};
-use mm::frame_allocator::FrameRangeTracker;
+use mm::dma::{DMA_BIT_MASK_32, DmaRegion, dma_alloc_coherent, virt_to_dma};
 use virtio_drivers::{BufferDirection, Hal, PhysAddr};
 
-// 全局映射表，用于跟踪物理地址到分配的帧范围的映射
+// 全局映射表，用于跟踪总线地址到分配的 DMA 区域的映射
 lazy_static! {
-    static ref DMA_ALLOCATIONS: SpinLock<BTreeMap<PhysAddr, FrameRangeTracker>> =
+    static ref DMA_ALLOCATIONS: SpinLock<BTreeMap<PhysAddr, DmaRegion>> =
+        SpinLock::new(BTreeMap::new());
+    /// 共享内核栈上的缓冲区时使用的弹跳缓冲区
+    static ref BOUNCE_BUFFERS: SpinLock<BTreeMap<PhysAddr, DmaRegion>> =
         SpinLock::new(BTreeMap::new());
 }
 
@@ -21,54 +26,31 @@ pub struct VirtIOHal;
 unsafe impl Hal for VirtIOHal {
     /// 分配并清零指定数量的连续物理页用于DMA
     fn dma_alloc(pages: usize, _direction: BufferDirection) -> (PhysAddr, NonNull<u8>) {
-        // 使用系统的连续物理帧分配器
-        let frame_range = match mm::frame_allocator::alloc_contig_frames(pages) {
-            Some(range) => range,
-            None => {
-                // 返回空指针，让上层代码处理错误
-                return (PhysAddr::from(0u64), NonNull::dangling());
-            }
+        // legacy virtio 的队列地址寄存器只有 32 位，DMA 缓冲区位于 32 位总线地址以内
+        let Some(region) = dma_alloc_coherent(pages * PAGE_SIZE, DMA_BIT_MASK_32) else {
+            // 返回空指针，让上层代码处理错误
+            return (PhysAddr::from(0u64), NonNull::dangling());
         };
+        let dma_addr = region.dma_addr() as PhysAddr;
+        let vaddr = region.vaddr();
 
-        // 获取起始物理页号
-        let start_ppn = frame_range.start_ppn();
-
-        // 计算物理地址
-        let phys_addr = PhysAddr::from(start_ppn.start_addr().as_usize() as u64);
-
-        // 将物理地址转换为虚拟地址
-        let virt_addr = unsafe { start_ppn.start_addr().to_vaddr().as_mut_ptr::<u8>() };
-        let virt_ptr = NonNull::new(virt_addr).unwrap();
-
-        // 清零 DMA 缓冲区（VirtIO HAL trait 要求）
-        unsafe {
-            for page_idx in 0..pages {
-                let page_start = virt_addr.add(page_idx * crate::config::PAGE_SIZE);
-
-                // 逐字节清零整个页面
-                for offset in 0..crate::config::PAGE_SIZE {
-                    core::ptr::write_volatile(page_start.add(offset), 0);
-                }
-            }
-        }
-
-        // 将帧范围存储到全局映射表中
-        DMA_ALLOCATIONS.lock().insert(phys_addr, frame_range);
+        // 将 DMA 区域存储到全局映射表中
+        DMA_ALLOCATIONS.lock().insert(dma_addr, region);
 
-        (phys_addr, virt_ptr)
+        (dma_addr, vaddr)
     }
 
     /// 释放之前分配的DMA内存
     unsafe fn dma_dealloc(paddr: PhysAddr, _vaddr: NonNull<u8>, _pages: usize) -> i32 {
-        // 从全局映射表中查找并移除对应的帧范围
-        // 注意：必须先释放DMA_ALLOCATIONS锁，再drop FrameRangeTracker
-        // 因为FrameRangeTracker::drop()会获取FRAME_ALLOCATOR锁
+        // 从全局映射表中查找并移除对应的 DMA 区域
+        // 注意：必须先释放DMA_ALLOCATIONS锁，再drop DmaRegion
+        // 因为DmaRegion::drop()会获取FRAME_ALLOCATOR锁
         // 锁顺序要求：FRAME_ALLOCATOR(层级0) 必须在 DMA_ALLOCATIONS(层级7) 之前
-        let frame_range = DMA_ALLOCATIONS.lock().remove(&paddr);
+        let region = DMA_ALLOCATIONS.lock().remove(&paddr);
         // DMA_ALLOCATIONS锁已释放
 
-        // 现在可以安全地drop frame_range，它会获取FRAME_ALLOCATOR锁
-        if frame_range.is_some() {
+        // 现在可以安全地drop region，它会获取FRAME_ALLOCATOR锁
+        if region.is_some() {
             0 // 成功释放
         } else {
             -1 // 未找到对应的分配记录
@@ -87,17 +69,45 @@ unsafe impl Hal for VirtIOHal {
     }
 
     /// 共享内存区域给设备，并返回设备可访问的物理地址
-    unsafe fn share(buffer: NonNull<[u8]>, _direction: BufferDirection) -> PhysAddr {
+    ///
+    /// 驱动会把栈上的请求头与状态交给设备。内核栈与 vmalloc 区不在线性映射中，物理帧也不连续，
+    /// 这类缓冲区经弹跳缓冲区共享，在 [`unshare`](Hal::unshare) 时复制回来。
+    unsafe fn share(buffer: NonNull<[u8]>, direction: BufferDirection) -> PhysAddr {
         let vaddr = buffer.as_ptr() as *const u8 as usize;
-        let paddr = unsafe { vaddr_to_paddr(vaddr) };
-
-        let result = PhysAddr::from(paddr as u64);
-        result
+        if is_kstack_addr(vaddr) || is_vmalloc_addr(vaddr) {
+            let len = buffer.len();
+            let region = dma_alloc_coherent(len.max(1), DMA_BIT_MASK_32)
+                .expect("virtio share: failed to alloc bounce buffer");
+            if !matches!(direction, BufferDirection::DeviceToDriver) {
+                // SAFETY: 弹跳缓冲区至少 len 字节，与原缓冲区不重叠
+                unsafe {
+                    core::ptr::copy_nonoverlapping(vaddr as *const u8, region.vaddr().as_ptr(), len)
+                };
+            }
+            let dma_addr = region.dma_addr() as PhysAddr;
+            BOUNCE_BUFFERS.lock().insert(dma_addr, region);
+            return dma_addr;
+        }
+        // SAFETY: 其余缓冲区都位于线性映射中
+        unsafe { virt_to_dma(vaddr) as PhysAddr }
     }
 
     /// 取消共享内存区域，并在必要时将数据复制回原始缓冲区
-    unsafe fn unshare(_paddr: PhysAddr, _buffer: NonNull<[u8]>, _direction: BufferDirection) {
-        // 简化实现，由于使用直接映射，不需要额外操作
+    unsafe fn unshare(paddr: PhysAddr, buffer: NonNull<[u8]>, direction: BufferDirection) {
+        // 线性映射中的缓冲区直接共享，不需要额外操作
+        let Some(region) = BOUNCE_BUFFERS.lock().remove(&paddr) else {
+            return;
+        };
+        if !matches!(direction, BufferDirection::DriverToDevice) {
+            // SAFETY: 弹跳缓冲区由 share 按 buffer 的长度分配
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    region.vaddr().as_ptr(),
+                    buffer.as_ptr() as *mut u8,
+                    buffer.len(),
+                )
+            };
+        }
     }
 }
 
@@ -0,0 +1,258 @@
+//! /dev/kmsg：按记录读写内核日志
+//!
+//! 每次打开持有独立的读者（[`LogReader`]），从缓冲区中最旧的记录开始，每次 `read` 返回一条记录：
+//!
+//! ```text
+//! <level>,<seq>,<timestamp_us>,-;<message>\n
+//! ```
+//!
+//! 条目携带的结构化字段（见 `pr_fields!`）紧随其后，每个字段一行，以空格开头：
+//!
+//! ```text
+//!  KEY=value\n
+//! ```
+//!
+//! 消息与字段值中的不可打印字符与 `\` 按 Linux 的方式转义为 `\xNN`。
+//! - 游标之后暂无新记录时阻塞等待；`O_NONBLOCK` 下返回 EAGAIN；
+//! - 游标指向的记录已被覆盖时返回 EPIPE，并跳到最旧的记录；`syslog` 的破坏性读取不影响游标；
+//! - 用户缓冲区放不下一整条记录时返回 EINVAL，游标不动。
+//!
+//! 写入的每一行作为一条日志，可以带 `<N>` 前缀指定级别（取低 3 位），缺省为 Warning。
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt::Write;
+
+use crate::kernel::current_task;
+use crate::kernel::hrtimer::NSEC_PER_USEC;
+use crate::log::{LogField, LogLevel, LogReader, log_impl, log_reader};
+use crate::sync::SpinLock;
+use crate::vfs::{
+    Dentry, File, FsError, Inode, InodeMetadata, OpenFlags, SeekWhence, release_ofd_locks_on_drop,
+};
+
+/// 写入未指定级别时使用的级别（Linux 的 default_message_loglevel）
+const DEFAULT_MESSAGE_LEVEL: LogLevel = LogLevel::Warning;
+
+/// /dev/kmsg 的一次打开
+pub struct KmsgFile {
+    dentry: Arc<Dentry>,
+    inode: Arc<dyn Inode>,
+    flags: SpinLock<OpenFlags>,
+    /// 本次打开的读者
+    reader: SpinLock<LogReader<'static>>,
+}
+
+impl KmsgFile {
+    /// 打开 /dev/kmsg，游标指向缓冲区中最旧的记录
+    pub fn new(dentry: Arc<Dentry>, flags: OpenFlags) -> Self {
+        let inode = dentry.inode.clone();
+        Self {
+            dentry,
+            inode,
+            flags: SpinLock::new(flags),
+            reader: SpinLock::new(log_reader()),
+        }
+    }
+
+    /// 尝试取出游标处的记录并格式化到 `buf`
+    ///
+    /// 暂无新记录时返回 `Ok(None)`。
+    fn try_read(&self, buf: &mut [u8]) -> Result<Option<usize>, FsError> {
+        let mut reader = self.reader.lock();
+        // 序号已分配但写者尚未发布时按暂无记录处理
+        let entry = reader.peek();
+        if reader.take_lost() > 0 {
+            return Err(FsError::BrokenPipe);
+        }
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        let mut record = String::new();
+        format_record(
+            &mut record,
+            entry.level().to_u8(),
+            entry.seq(),
+            entry.timestamp() as u64,
+            entry.message(),
+        );
+        format_fields(&mut record, entry.fields());
+        let bytes = record.as_bytes();
+        if bytes.len() > buf.len() {
+            return Err(FsError::InvalidArgument);
+        }
+        buf[..bytes.len()].copy_from_slice(bytes);
+        reader.advance();
+        Ok(Some(bytes.len()))
+    }
+}
+
+/// 格式化一条记录（含结尾换行）
+fn format_record(out: &mut String, level: u8, seq: usize, timestamp_ns: u64, message: &str) {
+    let _ = write!(out, "{},{},{},-;", level, seq, timestamp_ns / NSEC_PER_USEC);
+    push_escaped(out, message.trim_end_matches('\n'));
+    out.push('\n');
+}
+
+/// 把结构化字段格式化为 ` KEY=value` 续行
+fn format_fields(out: &mut String, fields: &[LogField]) {
+    for field in fields {
+        out.push(' ');
+        out.push_str(field.key());
+        out.push('=');
+        let mut value = String::new();
+        let _ = write!(value, "{}", field.value());
+        push_escaped(out, &value);
+        out.push('\n');
+    }
+}
+
+/// 追加 `s`，不可打印字符与 `\` 转义为 `\xNN`
+fn push_escaped(out: &mut String, s: &str) {
+    for &b in s.as_bytes() {
+        if b < b' ' || b >= 0x7f || b == b'\\' {
+            let _ = write!(out, "\\x{:02x}", b);
+        } else {
+            out.push(b as char);
+        }
+    }
+}
+
+/// 拆出写入内容的 `<N>` 级别前缀
+///
+/// 没有合法前缀时返回缺省级别与原内容。
+fn parse_prefix(line: &[u8]) -> (LogLevel, &[u8]) {
+    if let Some(rest) = line.strip_prefix(b"<") {
+        if let Some(end) = rest.iter().position(|&b| b == b'>') {
+            let prio = core::str::from_utf8(&rest[..end])
+                .ok()
+                .and_then(|s| s.parse::<u32>().ok());
+            if let Some(prio) = prio {
+                return (LogLevel::from_u8((prio & 7) as u8), &rest[end + 1..]);
+            }
+        }
+    }
+    (DEFAULT_MESSAGE_LEVEL, line)
+}
+
+impl File for KmsgFile {
+    fn readable(&self) -> bool {
+        self.flags.lock().readable()
+    }
+
+    fn writable(&self) -> bool {
+        self.flags.lock().writable()
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
+        loop {
+            if let Some(n) = self.try_read(buf)? {
+                return Ok(n);
+            }
+            if self.flags.lock().contains(OpenFlags::O_NONBLOCK) {
+                return Err(FsError::WouldBlock);
+            }
+            let task = current_task();
+            if crate::ipc::signal_interrupts_syscall(&task) {
+                return Err(FsError::Interrupted);
+            }
+            crate::kernel::yield_task();
+        }
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        for line in buf.split(|&b| b == b'\n').filter(|l| !l.is_empty()) {
+            let (level, message) = parse_prefix(line);
+            log_impl(level, format_args!("{}", String::from_utf8_lossy(message)));
+        }
+        Ok(buf.len())
+    }
+
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        self.inode.metadata()
+    }
+
+    /// 只支持偏移为 0 的 SEEK_SET（回到最旧的记录）与 SEEK_END（跳过已有记录）
+    fn lseek(&self, offset: isize, whence: SeekWhence) -> Result<usize, FsError> {
+        if offset != 0 {
+            return Err(FsError::NotSupported);
+        }
+        let mut reader = self.reader.lock();
+        match whence {
+            SeekWhence::Set => reader.seek_oldest(),
+            SeekWhence::End => reader.seek_end(),
+            SeekWhence::Cur => return Err(FsError::InvalidArgument),
+        }
+        Ok(0)
+    }
+
+    fn flags(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn set_status_flags(&self, new_flags: OpenFlags) -> Result<(), FsError> {
+        *self.flags.lock() = new_flags;
+        Ok(())
+    }
+
+    fn inode(&self) -> Result<Arc<dyn Inode>, FsError> {
+        Ok(self.inode.clone())
+    }
+
+    fn dentry(&self) -> Result<Arc<Dentry>, FsError> {
+        Ok(self.dentry.clone())
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+impl Drop for KmsgFile {
+    fn drop(&mut self) {
+        release_ofd_locks_on_drop(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_kmsg_format_record() {
+        let mut out = String::new();
+        format_record(&mut out, 6, 42, 5_140_900_123, "eth0: link up\n");
+        assert_eq!(out, "6,42,5140900,-;eth0: link up\n");
+
+        let mut out = String::new();
+        format_record(&mut out, 3, 1, 0, "a\tb\\c\nd");
+        assert_eq!(out, "3,1,0,-;a\\x09b\\x5cc\\x0ad\n");
+    }
+
+    #[test_case]
+    fn test_kmsg_format_fields() {
+        let mut out = String::new();
+        let fields = [
+            LogField::new("DEVICE", "eth0"),
+            LogField::new("LEN", 64u32),
+            LogField::new("NOTE", "a\nb"),
+        ];
+        format_fields(&mut out, &fields);
+        assert_eq!(out, " DEVICE=eth0\n LEN=64\n NOTE=a\\x0ab\n");
+    }
+
+    #[test_case]
+    fn test_kmsg_parse_prefix() {
+        assert_eq!(parse_prefix(b"<3>oops"), (LogLevel::Error, &b"oops"[..]));
+        // 带设施号的优先级只取级别
+        assert_eq!(parse_prefix(b"<14>hi"), (LogLevel::Info, &b"hi"[..]));
+        assert_eq!(
+            parse_prefix(b"<x>hi"),
+            (DEFAULT_MESSAGE_LEVEL, &b"<x>hi"[..])
+        );
+        assert_eq!(
+            parse_prefix(b"plain"),
+            (DEFAULT_MESSAGE_LEVEL, &b"plain"[..])
+        );
+    }
+}
@@ -2,17 +2,20 @@
 //!
 //! 本模块 re-export fs crate 的内容，并提供初始化函数。
 
+pub mod kmsg;
 mod ops_impl;
 
 // Re-export fs crate (使用 :: 前缀引用外部 crate，避免与本模块名冲突)
 pub use ::fs::*;
 
 use alloc::string::String;
+use alloc::sync::Arc;
 
-use crate::device::BLK_DRIVERS;
+use crate::device::console::frame_console::FRAME_CONSOLE;
+use crate::device::{BLK_DRIVERS, SERIAL_DRIVERS};
 use crate::pr_info;
-use crate::vfs::{FileMode, FsError, MOUNT_TABLE, MountFlags, vfs_lookup};
-use crate::vfs::{blkdev_major, chrdev_major, makedev};
+use crate::vfs::{FileMode, FileSystem, FsError, Inode, MountFlags, current_mnt_ns, vfs_lookup};
+use crate::vfs::{blkdev_major, chrdev_major, console_minor, makedev, mem_minor};
 
 /// 初始化 FS 操作实现
 pub fn init_fs_ops() {
@@ -30,6 +33,7 @@ fn ensure_top_level_dir(path: &str, mode: FileMode) -> Result<(), FsError> {
     }
     let root = crate::vfs::get_root_dentry()?;
     root.inode.mkdir(name, mode)?;
+    crate::vfs::fsnotify_create(root.inode.as_ref(), name, true);
     Ok(())
 }
 
@@ -76,7 +80,7 @@ pub fn init_ext4_from_block_device() -> Result<(), FsError> {
     let ext4_fs = Ext4FileSystem::open(block_driver, ext4_block_size, total_blocks, 0)?;
 
     pr_info!("[Ext4] Mounting Ext4 as root filesystem");
-    MOUNT_TABLE.mount(
+    current_mnt_ns().mount(
         ext4_fs,
         "/",
         MountFlags::empty(),
@@ -133,7 +137,7 @@ pub fn init_oscomp_filesystems() -> Result<(), FsError> {
         let Ok(fs) = Ext4FileSystem::open(dev.clone(), EXT4_BLOCK_SIZE, total_blocks, idx) else {
             continue;
         };
-        MOUNT_TABLE.mount(
+        current_mnt_ns().mount(
             fs,
             "/",
             MountFlags::empty(),
@@ -203,7 +207,7 @@ pub fn init_oscomp_filesystems() -> Result<(), FsError> {
         let Ok(fs) = Ext4FileSystem::open(dev.clone(), EXT4_BLOCK_SIZE, total_blocks, idx) else {
             continue;
         };
-        MOUNT_TABLE.mount(
+        current_mnt_ns().mount(
             fs,
             "/tests",
             MountFlags::empty(),
@@ -244,7 +248,7 @@ pub fn mount_tmpfs(mount_point: &str, max_size_mb: usize) -> Result<(), FsError>
 
     let tmpfs = TmpFs::new(max_size_mb);
 
-    MOUNT_TABLE.mount(
+    current_mnt_ns().mount(
         tmpfs,
         mount_point,
         MountFlags::empty(),
@@ -256,6 +260,53 @@ pub fn mount_tmpfs(mount_point: &str, max_size_mb: usize) -> Result<(), FsError>
     Ok(())
 }
 
+/// 挂载 devpts 到指定路径
+pub fn mount_devpts(mount_point: &str) -> Result<(), FsError> {
+    current_mnt_ns().mount(
+        DevPtsFs::new(),
+        mount_point,
+        MountFlags::empty(),
+        Some(String::from("devpts")),
+    )?;
+
+    pr_info!("[DevPts] Devpts mounted at {}", mount_point);
+
+    Ok(())
+}
+
+/// 挂载以 `lower` 目录为下层、`upper` 目录为上层的 overlayfs 到指定路径
+///
+/// `upper` 为 `None` 时以新建的 tmpfs 为上层，用于在只读根文件系统上提供可写的
+/// `/etc`、`/var` 等目录（`lower` 可以与挂载路径相同）。
+pub fn mount_overlay(mount_point: &str, lower: &str, upper: Option<&str>) -> Result<(), FsError> {
+    let lower = vfs_lookup(lower)?;
+    let (upper_root, upper_fs): (Arc<dyn Inode>, Arc<dyn FileSystem>) = match upper {
+        Some(path) => {
+            let upper = vfs_lookup(path)?;
+            let mount = current_mnt_ns()
+                .mount_of(&upper)
+                .ok_or(FsError::InvalidArgument)?;
+            (upper.inode.clone(), mount.fs.clone())
+        }
+        None => {
+            let tmpfs = TmpFs::new(0);
+            (tmpfs.root_inode(), tmpfs)
+        }
+    };
+
+    let overlay = OverlayFs::new(lower.inode.clone(), upper_root, upper_fs)?;
+    current_mnt_ns().mount(
+        overlay,
+        mount_point,
+        MountFlags::empty(),
+        Some(String::from("overlay")),
+    )?;
+
+    pr_info!("[Overlay] Overlayfs mounted at {}", mount_point);
+
+    Ok(())
+}
+
 /// 初始化 /dev 目录下的设备文件
 pub fn init_dev() -> Result<(), FsError> {
     if let Err(e) = vfs_lookup("/dev") {
@@ -263,6 +314,7 @@ pub fn init_dev() -> Result<(), FsError> {
     }
 
     create_devices()?;
+    mount_devpts("/dev/pts")?;
 
     Ok(())
 }
@@ -273,19 +325,67 @@ fn create_devices() -> Result<(), FsError> {
 
     let char_mode = FileMode::S_IFCHR | FileMode::from_bits_truncate(0o666);
 
-    dev_inode.mknod("null", char_mode, makedev(chrdev_major::MEM, 3))?;
-    dev_inode.mknod("zero", char_mode, makedev(chrdev_major::MEM, 5))?;
-    dev_inode.mknod("random", char_mode, makedev(chrdev_major::MEM, 8))?;
-    dev_inode.mknod("urandom", char_mode, makedev(chrdev_major::MEM, 9))?;
+    dev_inode.mknod(
+        "null",
+        char_mode,
+        makedev(chrdev_major::MEM, mem_minor::NULL),
+    )?;
+    dev_inode.mknod(
+        "zero",
+        char_mode,
+        makedev(chrdev_major::MEM, mem_minor::ZERO),
+    )?;
+    dev_inode.mknod(
+        "random",
+        char_mode,
+        makedev(chrdev_major::MEM, mem_minor::RANDOM),
+    )?;
+    dev_inode.mknod(
+        "urandom",
+        char_mode,
+        makedev(chrdev_major::MEM, mem_minor::URANDOM),
+    )?;
+    dev_inode.mknod(
+        "kmsg",
+        FileMode::S_IFCHR | FileMode::from_bits_truncate(0o644),
+        makedev(chrdev_major::MEM, mem_minor::KMSG),
+    )?;
 
     let console_mode = FileMode::S_IFCHR | FileMode::from_bits_truncate(0o600);
     dev_inode.mknod("tty", console_mode, makedev(chrdev_major::CONSOLE, 0))?;
     dev_inode.mknod("console", console_mode, makedev(chrdev_major::CONSOLE, 1))?;
+    dev_inode.mknod(
+        "ptmx",
+        char_mode,
+        makedev(chrdev_major::CONSOLE, console_minor::PTMX),
+    )?;
 
-    dev_inode.mknod("ttyS0", char_mode, makedev(chrdev_major::TTY, 64))?;
+    // ttyS<n> 对应第 n 个串口驱动，没有串口时仍保留 ttyS0
+    for i in 0..SERIAL_DRIVERS.read().len().max(1) {
+        dev_inode.mknod(
+            &alloc::format!("ttyS{}", i),
+            char_mode,
+            makedev(chrdev_major::TTY, 64 + i as u32),
+        )?;
+    }
+    // tty0 为前台虚拟终端，tty<n> 为帧缓冲控制台的第 n 个虚拟终端
+    let vt_count = FRAME_CONSOLE
+        .read()
+        .as_ref()
+        .map_or(0, |console| console.count());
+    if vt_count > 0 {
+        for i in 0..=vt_count {
+            dev_inode.mknod(
+                &alloc::format!("tty{}", i),
+                char_mode,
+                makedev(chrdev_major::TTY, i as u32),
+            )?;
+        }
+    }
 
     let dir_mode = FileMode::S_IFDIR | FileMode::from_bits_truncate(0o755);
     dev_inode.mkdir("misc", dir_mode)?;
+    dev_inode.mkdir("pts", dir_mode)?;
 
     let misc_dentry = vfs_lookup("/dev/misc")?;
     misc_dentry
@@ -305,7 +405,7 @@ pub fn init_procfs() -> Result<(), FsError> {
     let procfs = ProcFS::new();
     procfs.init_tree()?;
 
-    MOUNT_TABLE.mount(
+    current_mnt_ns().mount(
         procfs,
         "/proc",
         MountFlags::empty(),
@@ -324,7 +424,7 @@ pub fn init_sysfs() -> Result<(), FsError> {
     let sysfs = SysFS::new();
     sysfs.init_tree()?;
 
-    MOUNT_TABLE.mount(
+    current_mnt_ns().mount(
         sysfs,
         "/sys",
         MountFlags::empty(),
@@ -5,16 +5,17 @@
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
 
-use ::fs::{FsOps, MemoryAreaInfo, MountInfo, TaskInfo, TaskState, VmStats};
+use ::fs::{FsOps, IdMapKind, MemoryAreaInfo, MountInfo, SmapsInfo, TaskInfo, TaskState, VmStats};
 use uapi::time::TimeSpec;
 
 use crate::config::{EXT4_BLOCK_SIZE, FS_IMAGE_SIZE, PAGE_SIZE, VIRTIO_BLK_SECTOR_SIZE};
-use crate::kernel::{TASK_MANAGER, TaskManagerTrait};
-use crate::mm::AreaType;
+use crate::kernel::{Capabilities, TASK_MANAGER, TaskManagerTrait};
 use crate::mm::frame_allocator::{get_free_frames, get_total_frames};
+use crate::mm::{AreaType, MappingArea};
 use crate::time_ext::timespec_now;
-use crate::vfs::{MOUNT_TABLE, MountFlags};
+use crate::vfs::{FsError, MountFlags, current_mnt_ns};
 
 /// FsOps 实现
 struct FsOpsImpl;
@@ -70,7 +71,7 @@ impl FsOps for FsOpsImpl {
     }
 
     fn list_mounts(&self) -> Vec<MountInfo> {
-        MOUNT_TABLE
+        current_mnt_ns()
             .list_all()
             .into_iter()
             .map(|(path, mp)| MountInfo {
@@ -81,6 +82,19 @@ impl FsOps for FsOpsImpl {
             })
             .collect()
     }
+
+    fn audit_log(&self) -> Vec<u8> {
+        crate::security::audit::read_log()
+    }
+
+    fn audit_rules(&self) -> Vec<u8> {
+        crate::security::audit::read_rules()
+    }
+
+    fn set_audit_rules(&self, cmds: &[u8]) -> Result<(), FsError> {
+        let cmds = core::str::from_utf8(cmds).map_err(|_| FsError::InvalidArgument)?;
+        crate::security::audit::write_rules(cmds).map_err(|_| FsError::InvalidArgument)
+    }
 }
 
 /// TaskInfo 包装器
@@ -181,76 +195,35 @@ impl TaskInfo for TaskInfoWrapper {
     }
 
     fn memory_areas(&self) -> Vec<MemoryAreaInfo> {
-        use crate::mm::address::{PageNum, UsizeConvert};
-        use crate::mm::page_table::UniversalPTEFlag;
-
         let task = self.task.lock();
         let Some(ms) = task.memory_space.as_ref() else {
             return Vec::new();
         };
         let ms = ms.lock();
 
-        let mut areas: Vec<_> = ms
-            .areas()
-            .iter()
-            .filter(|a| {
-                matches!(
-                    a.area_type(),
-                    AreaType::UserText
-                        | AreaType::UserRodata
-                        | AreaType::UserData
-                        | AreaType::UserBss
-                        | AreaType::UserStack
-                        | AreaType::UserHeap
-                        | AreaType::UserMmap
-                )
-            })
-            .collect();
+        user_areas(ms.areas()).into_iter().map(area_info).collect()
+    }
 
-        areas.sort_by_key(|a| a.vpn_range().start().start_addr().as_usize());
+    fn smaps(&self) -> Vec<SmapsInfo> {
+        let task = self.task.lock();
+        let Some(ms) = task.memory_space.as_ref() else {
+            return Vec::new();
+        };
+        let ms = ms.lock();
 
-        areas
+        user_areas(ms.areas())
             .into_iter()
             .map(|a| {
-                let start = a.vpn_range().start().start_addr().as_usize();
-                let end = a.vpn_range().end().start_addr().as_usize();
-
-                let perm = a.permission();
-                let r = if perm.contains(UniversalPTEFlag::READABLE) {
-                    'r'
-                } else {
-                    '-'
-                };
-                let w = if perm.contains(UniversalPTEFlag::WRITEABLE) {
-                    'w'
-                } else {
-                    '-'
-                };
-                let x = if perm.contains(UniversalPTEFlag::EXECUTABLE) {
-                    'x'
-                } else {
-                    '-'
-                };
-
-                let label = match a.area_type() {
-                    AreaType::UserText => "[text]",
-                    AreaType::UserRodata => "[rodata]",
-                    AreaType::UserData => "[data]",
-                    AreaType::UserBss => "[bss]",
-                    AreaType::UserHeap => "[heap]",
-                    AreaType::UserStack => "[stack]",
-                    AreaType::UserMmap => "[mmap]",
-                    _ => "[kernel]",
-                };
-
-                MemoryAreaInfo {
-                    start,
-                    end,
-                    perm: alloc::format!("{}{}{}p", r, w, x),
-                    offset: 0,
-                    dev: "00:00".to_string(),
-                    inode: 0,
-                    path: Some(label.to_string()),
+                let usage = a.usage(ms.page_table());
+                SmapsInfo {
+                    area: area_info(a),
+                    rss_bytes: usage.rss,
+                    pss_bytes: usage.pss,
+                    shared_clean_bytes: usage.shared_clean,
+                    shared_dirty_bytes: usage.shared_dirty,
+                    private_clean_bytes: usage.private_clean,
+                    private_dirty_bytes: usage.private_dirty,
+                    swap_bytes: usage.swap,
                 }
             })
             .collect()
@@ -271,6 +244,134 @@ impl TaskInfo for TaskInfoWrapper {
     fn start_time(&self) -> u64 {
         0 // TODO
     }
+
+    fn id_map(&self, kind: IdMapKind) -> Vec<u8> {
+        let ns = self.task.lock().user_ns.clone();
+        ns.read_map(user_ns_kind(kind)).into_bytes()
+    }
+
+    fn write_id_map(&self, kind: IdMapKind, data: &[u8]) -> Result<(), FsError> {
+        let text = core::str::from_utf8(data).map_err(|_| FsError::InvalidArgument)?;
+        // 先取目标命名空间再锁写入者，目标与写入者可能是同一任务
+        let ns = self.task.lock().user_ns.clone();
+        let writer = crate::kernel::current_task();
+        let writer = writer.lock();
+        ns.write_map(user_ns_kind(kind), text, &writer)
+            .map_err(|e| match e {
+                uapi::errno::EINVAL => FsError::InvalidArgument,
+                _ => FsError::PermissionDenied,
+            })
+    }
+
+    fn oom_score_adj(&self) -> i32 {
+        self.task.lock().oom_score_adj.load(Ordering::Relaxed)
+    }
+
+    fn set_oom_score_adj(&self, adj: i32) -> Result<(), FsError> {
+        let target = self.task.lock().oom_score_adj.clone();
+        // 与 Linux 一致：降低调整值需要 CAP_SYS_RESOURCE
+        if adj < target.load(Ordering::Relaxed) {
+            let writer = crate::kernel::current_task();
+            if !writer
+                .lock()
+                .credential
+                .capabilities
+                .has(Capabilities::SYS_RESOURCE)
+            {
+                return Err(FsError::PermissionDenied);
+            }
+        }
+        target.store(adj, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn oom_score(&self) -> usize {
+        crate::mm::oom::task_badness(&self.task).map_or(0, |badness| {
+            ::mm::oom::oom_score(badness, get_total_frames())
+        })
+    }
+}
+
+/// 用户可见的映射区域（`/proc/[pid]/maps` 与 `smaps`），按起始地址排序
+fn user_areas(areas: &[MappingArea]) -> Vec<&MappingArea> {
+    use crate::mm::address::{PageNum, UsizeConvert};
+
+    let mut areas: Vec<_> = areas
+        .iter()
+        .filter(|a| {
+            matches!(
+                a.area_type(),
+                AreaType::UserText
+                    | AreaType::UserRodata
+                    | AreaType::UserData
+                    | AreaType::UserBss
+                    | AreaType::UserStack
+                    | AreaType::UserHeap
+                    | AreaType::UserMmap
+                    | AreaType::UserVvar
+                    | AreaType::UserVdso
+            )
+        })
+        .collect();
+
+    areas.sort_by_key(|a| a.vpn_range().start().start_addr().as_usize());
+    areas
+}
+
+/// 映射区域在 maps 中的一行
+fn area_info(a: &MappingArea) -> MemoryAreaInfo {
+    use crate::mm::address::{PageNum, UsizeConvert};
+    use crate::mm::page_table::UniversalPTEFlag;
+
+    let start = a.vpn_range().start().start_addr().as_usize();
+    let end = a.vpn_range().end().start_addr().as_usize();
+
+    let perm = a.permission();
+    let r = if perm.contains(UniversalPTEFlag::READABLE) {
+        'r'
+    } else {
+        '-'
+    };
+    let w = if perm.contains(UniversalPTEFlag::WRITEABLE) {
+        'w'
+    } else {
+        '-'
+    };
+    let x = if perm.contains(UniversalPTEFlag::EXECUTABLE) {
+        'x'
+    } else {
+        '-'
+    };
+
+    let label = match a.area_type() {
+        AreaType::UserText => "[text]",
+        AreaType::UserRodata => "[rodata]",
+        AreaType::UserData => "[data]",
+        AreaType::UserBss => "[bss]",
+        AreaType::UserHeap => "[heap]",
+        AreaType::UserStack => "[stack]",
+        AreaType::UserMmap => "[mmap]",
+        AreaType::UserVvar => "[vvar]",
+        AreaType::UserVdso => "[vdso]",
+        _ => "[kernel]",
+    };
+
+    MemoryAreaInfo {
+        start,
+        end,
+        perm: alloc::format!("{}{}{}p", r, w, x),
+        offset: 0,
+        dev: "00:00".to_string(),
+        inode: 0,
+        path: Some(label.to_string()),
+    }
+}
+
+fn user_ns_kind(kind: IdMapKind) -> crate::kernel::IdMapKind {
+    match kind {
+        IdMapKind::Uid => crate::kernel::IdMapKind::Uid,
+        IdMapKind::Gid => crate::kernel::IdMapKind::Gid,
+    }
 }
 
 static FS_OPS: FsOpsImpl = FsOpsImpl;
@@ -0,0 +1,44 @@
+//! Devpts 测试
+
+use crate::fs::DevPtsFs;
+use crate::vfs::tty::pty::Pty;
+use crate::vfs::{FileSystem, FsError, InodeType, chrdev_major, console_minor, makedev};
+use alloc::string::ToString;
+
+#[test_case]
+fn test_devpts_root_and_ptmx() {
+    let devpts = DevPtsFs::new();
+    assert!(devpts.fs_type() == "devpts");
+
+    let root = devpts.root_inode();
+    assert!(root.metadata().unwrap().inode_type == InodeType::Directory);
+
+    let ptmx = root.lookup("ptmx").unwrap().metadata().unwrap();
+    assert!(ptmx.inode_type == InodeType::CharDevice);
+    assert!(ptmx.rdev == makedev(chrdev_major::CONSOLE, console_minor::PTMX));
+    assert!(root.readdir().unwrap().iter().any(|e| e.name == "ptmx"));
+}
+
+#[test_case]
+fn test_devpts_follows_pty_lifetime() {
+    let devpts = DevPtsFs::new();
+    let root = devpts.root_inode();
+
+    let pty = Pty::alloc().unwrap();
+    let name = pty.index().to_string();
+    let slave = root.lookup(&name).unwrap().metadata().unwrap();
+    assert!(slave.rdev == makedev(chrdev_major::PTY_SLAVE, pty.index()));
+    assert!(root.readdir().unwrap().iter().any(|e| e.name == name));
+
+    pty.close_master();
+    assert!(matches!(root.lookup(&name), Err(FsError::NotFound)));
+    assert!(!root.readdir().unwrap().iter().any(|e| e.name == name));
+}
+
+#[test_case]
+fn test_devpts_read_only() {
+    let devpts = DevPtsFs::new();
+    let root = devpts.root_inode();
+    assert!(root.create("x", crate::vfs::FileMode::empty()).is_err());
+    assert!(matches!(root.lookup("01"), Err(FsError::NotFound)));
+}
@@ -0,0 +1,66 @@
+use super::*;
+use crate::config::EXT4_BLOCK_SIZE;
+use crate::device::block::{FaultyDisk, PowerCut};
+use crate::vfs::FileSystem;
+
+// P2 掉电一致性测试：借助 FaultyDisk 的写缓存模拟崩溃
+
+fn open_ext4_on(driver: Arc<dyn BlockDriver>) -> Result<Arc<Ext4FileSystem>, FsError> {
+    let total_blocks = driver.total_blocks() * driver.block_size() / EXT4_BLOCK_SIZE;
+    Ext4FileSystem::open(driver, EXT4_BLOCK_SIZE, total_blocks, 0)
+}
+
+#[test_case]
+fn test_ext4_synced_data_survives_power_cut() {
+    let ramdisk = create_test_ramdisk();
+    let disk = FaultyDisk::new(ramdisk.clone(), 0);
+    disk.set_reorder_window(64);
+
+    let fs = open_ext4_on(disk.clone()).unwrap();
+    create_test_file_with_content(&fs, "durable.txt", b"hello").unwrap();
+    assert!(fs.sync().is_ok());
+    assert!(disk.pending_writes() == 0);
+
+    disk.power_cut(PowerCut::DropPending);
+
+    // 直接在底层介质上重新挂载
+    let fs = open_ext4_on(ramdisk).unwrap();
+    let inode = fs.root_inode().lookup("durable.txt").unwrap();
+    let mut buf = [0u8; 5];
+    assert!(inode.read_at(0, &mut buf).unwrap() == 5);
+    assert!(&buf == b"hello");
+}
+
+#[test_case]
+fn test_ext4_unsynced_power_cut_still_mountable() {
+    let ramdisk = create_test_ramdisk();
+    let disk = FaultyDisk::new(ramdisk.clone(), 0);
+    disk.set_reorder_window(64);
+    disk.set_seed(0x5eed);
+
+    let fs = open_ext4_on(disk.clone()).unwrap();
+    let _ = create_test_file_with_content(&fs, "volatile.txt", &[0x42u8; 8192]);
+    assert!(disk.pending_writes() > 0);
+
+    let lost = disk.power_cut(PowerCut::TornWrite { bytes: 256 });
+    assert!(lost > 0);
+    assert!(disk.stats().lost == lost as u64);
+
+    // 超级块在格式化时已落盘，掉电后仍应能挂载
+    assert!(open_ext4_on(ramdisk).is_ok());
+}
+
+#[test_case]
+fn test_ext4_write_latency_is_accounted() {
+    let ramdisk = create_test_ramdisk();
+    let disk = FaultyDisk::new(ramdisk, 0);
+    disk.set_latency(1, 10);
+
+    let fs = open_ext4_on(disk.clone()).unwrap();
+    let before = disk.elapsed();
+    let writes_before = disk.stats().writes;
+    create_test_file_with_content(&fs, "slow.txt", b"x").unwrap();
+    let writes = disk.stats().writes - writes_before;
+    assert!(writes > 0);
+    assert!(disk.elapsed() - before >= writes * 10);
+}
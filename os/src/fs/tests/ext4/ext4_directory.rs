@@ -231,3 +231,24 @@ fn test_ext4_lookup_in_file() {
     assert!(result.is_err());
     assert!(matches!(result, Err(FsError::NotDirectory)));
 }
+
+#[test_case]
+fn test_ext4_tmpfile_link() {
+    let fs = create_test_ext4();
+    let root = fs.root_inode();
+    let tmp = root.tmpfile(FileMode::from_bits_truncate(0o600)).unwrap();
+
+    // 匿名文件不在目录中，链接数为 0
+    assert!(tmp.metadata().unwrap().nlinks == 0);
+    assert!(tmp.write_at(0, b"tmpdata").unwrap() == 7);
+    let entries = root.readdir().unwrap();
+    assert!(!entries.iter().any(|e| e.name.starts_with(".tmpfile")));
+
+    // 链接后可以按名字访问
+    root.link("linked.txt", &tmp).unwrap();
+    assert!(tmp.metadata().unwrap().nlinks == 1);
+    let linked = root.lookup("linked.txt").unwrap();
+    let mut buf = vec![0u8; 7];
+    linked.read_at(0, &mut buf).unwrap();
+    assert!(buf == b"tmpdata");
+}
@@ -0,0 +1,217 @@
+use super::*;
+use crate::vfs::{
+    AclEntry, AclTag, AclType, FsCred, OpenFlags, PosixAcl, XattrFlags, get_acl, may_open,
+    posix_acl_chmod, posix_acl_create, set_acl,
+};
+use alloc::vec;
+
+// 扩展属性测试
+
+#[test_case]
+fn test_ext4_xattr_set_get() {
+    let fs = create_test_ext4();
+    let inode = create_test_file(&fs, "xattr.txt").unwrap();
+
+    inode
+        .setxattr("user.comment", b"hello", XattrFlags::empty())
+        .unwrap();
+    assert!(inode.getxattr("user.comment").unwrap() == b"hello");
+
+    // 覆盖为更长的值
+    inode
+        .setxattr("user.comment", b"hello, world", XattrFlags::empty())
+        .unwrap();
+    assert!(inode.getxattr("user.comment").unwrap() == b"hello, world");
+    assert!(inode.getxattr("user.missing") == Err(FsError::NoData));
+}
+
+#[test_case]
+fn test_ext4_xattr_flags() {
+    let fs = create_test_ext4();
+    let inode = create_test_file(&fs, "flags.txt").unwrap();
+
+    assert!(inode.setxattr("user.a", b"1", XattrFlags::REPLACE) == Err(FsError::NoData));
+    inode.setxattr("user.a", b"1", XattrFlags::CREATE).unwrap();
+    assert!(inode.setxattr("user.a", b"2", XattrFlags::CREATE) == Err(FsError::AlreadyExists));
+    inode.setxattr("user.a", b"2", XattrFlags::REPLACE).unwrap();
+    assert!(inode.getxattr("user.a").unwrap() == b"2");
+}
+
+#[test_case]
+fn test_ext4_xattr_list_remove() {
+    let fs = create_test_ext4();
+    let inode = create_test_file(&fs, "list.txt").unwrap();
+
+    inode.setxattr("user.b", b"2", XattrFlags::empty()).unwrap();
+    inode
+        .setxattr("trusted.a", b"1", XattrFlags::empty())
+        .unwrap();
+    inode
+        .setxattr("security.c", b"", XattrFlags::empty())
+        .unwrap();
+
+    let mut names = inode.listxattr().unwrap();
+    names.sort();
+    assert!(names == ["security.c", "trusted.a", "user.b"]);
+
+    inode.removexattr("user.b").unwrap();
+    assert!(inode.removexattr("user.b") == Err(FsError::NoData));
+    assert!(inode.getxattr("user.b") == Err(FsError::NoData));
+    assert!(inode.listxattr().unwrap().len() == 2);
+}
+
+#[test_case]
+fn test_ext4_xattr_block() {
+    // inode 内空间放不下的值落到属性块中
+    let fs = create_test_ext4();
+    let inode = create_test_file(&fs, "block.txt").unwrap();
+    let blocks_before = inode.metadata().unwrap().blocks;
+
+    let big = vec![0x5a_u8; 1000];
+    inode
+        .setxattr("user.big", &big, XattrFlags::empty())
+        .unwrap();
+    inode
+        .setxattr("user.small", b"s", XattrFlags::empty())
+        .unwrap();
+    assert!(inode.getxattr("user.big").unwrap() == big);
+    assert!(inode.getxattr("user.small").unwrap() == b"s");
+    assert!(inode.metadata().unwrap().blocks > blocks_before);
+
+    // 删除大属性后属性块被释放
+    inode.removexattr("user.big").unwrap();
+    assert!(inode.getxattr("user.small").unwrap() == b"s");
+    assert!(inode.metadata().unwrap().blocks == blocks_before);
+}
+
+#[test_case]
+fn test_ext4_xattr_on_directory() {
+    let fs = create_test_ext4();
+    let dir = create_test_dir(&fs, "xattr_dir").unwrap();
+
+    dir.setxattr("user.tag", b"dir", XattrFlags::empty())
+        .unwrap();
+    assert!(dir.getxattr("user.tag").unwrap() == b"dir");
+    // 目录内容不受影响
+    dir.create("child", FileMode::from_bits_truncate(0o644))
+        .unwrap();
+    assert!(dir.lookup("child").is_ok());
+}
+
+fn root_cred() -> FsCred {
+    FsCred {
+        fsuid: 0,
+        fsgid: 0,
+        dac_override: true,
+        dac_read_search: true,
+        fowner: true,
+        fsetid: true,
+    }
+}
+
+#[test_case]
+fn test_ext4_acl_access() {
+    let fs = create_test_ext4();
+    let inode = create_test_file(&fs, "acl.txt").unwrap();
+    let acl = PosixAcl::from_entries(vec![
+        AclEntry::new(AclTag::UserObj, 0o6, 0),
+        AclEntry::new(AclTag::User, 0o7, 1000),
+        AclEntry::new(AclTag::GroupObj, 0o4, 0),
+        AclEntry::new(AclTag::Mask, 0o6, 0),
+        AclEntry::new(AclTag::Other, 0o0, 0),
+    ])
+    .unwrap();
+
+    // 设置访问 ACL 后属组位反映 MASK
+    set_acl(inode.as_ref(), AclType::Access, Some(&acl), &root_cred()).unwrap();
+    assert!(inode.metadata().unwrap().mode.bits() & 0o777 == 0o660);
+    assert!(get_acl(inode.as_ref(), AclType::Access).unwrap() == Some(acl));
+
+    // chmod 修改 MASK 而不是属组表项
+    posix_acl_chmod(inode.as_ref(), FileMode::from_bits_truncate(0o640)).unwrap();
+    let acl = get_acl(inode.as_ref(), AclType::Access).unwrap().unwrap();
+    assert!(acl.mode() == 0o640);
+    assert!(
+        acl.entries()
+            .iter()
+            .any(|e| e.tag == AclTag::GroupObj && e.perm == 0o4)
+    );
+
+    // 删除后退回模式位
+    set_acl(inode.as_ref(), AclType::Access, None, &root_cred()).unwrap();
+    assert!(get_acl(inode.as_ref(), AclType::Access).unwrap().is_none());
+}
+
+#[test_case]
+fn test_ext4_acl_default_inherit() {
+    let fs = create_test_ext4();
+    let dir = create_test_dir(&fs, "acl_dir").unwrap();
+
+    // 没有默认 ACL 时应用 umask
+    let (mode, _) =
+        posix_acl_create(dir.as_ref(), FileMode::from_bits_truncate(0o666), 0o022).unwrap();
+    assert!(mode.bits() & 0o777 == 0o644);
+
+    let default = PosixAcl::from_entries(vec![
+        AclEntry::new(AclTag::UserObj, 0o7, 0),
+        AclEntry::new(AclTag::User, 0o5, 1000),
+        AclEntry::new(AclTag::GroupObj, 0o5, 0),
+        AclEntry::new(AclTag::Mask, 0o7, 0),
+        AclEntry::new(AclTag::Other, 0o0, 0),
+    ])
+    .unwrap();
+    set_acl(dir.as_ref(), AclType::Default, Some(&default), &root_cred()).unwrap();
+
+    // 有默认 ACL 时忽略 umask，子目录继承默认 ACL
+    let (mode, inherit) = posix_acl_create(
+        dir.as_ref(),
+        FileMode::from_bits_truncate(FileMode::S_IFDIR.bits() | 0o755),
+        0o077,
+    )
+    .unwrap();
+    assert!(mode.bits() & 0o777 == 0o750);
+    let child = dir.mkdir("child", mode).unwrap();
+    inherit.apply(child.as_ref()).unwrap();
+    assert!(get_acl(child.as_ref(), AclType::Default).unwrap() == Some(default));
+    let access = get_acl(child.as_ref(), AclType::Access).unwrap().unwrap();
+    assert!(access.mode() == 0o750);
+
+    // 默认 ACL 不能设置在普通文件上
+    let file = create_test_file(&fs, "plain.txt").unwrap();
+    assert!(
+        set_acl(file.as_ref(), AclType::Default, Some(&access), &root_cred())
+            == Err(FsError::PermissionDenied)
+    );
+}
+
+#[test_case]
+fn test_ext4_acl_denies_open() {
+    let fs = create_test_ext4();
+    let inode = create_test_file(&fs, "acl_deny.txt").unwrap();
+    // 其他用户可读，但 ACL 中的指定用户表项拒绝用户 1000
+    let acl = PosixAcl::from_entries(vec![
+        AclEntry::new(AclTag::UserObj, 0o6, 0),
+        AclEntry::new(AclTag::User, 0o0, 1000),
+        AclEntry::new(AclTag::GroupObj, 0o4, 0),
+        AclEntry::new(AclTag::Mask, 0o4, 0),
+        AclEntry::new(AclTag::Other, 0o4, 0),
+    ])
+    .unwrap();
+    set_acl(inode.as_ref(), AclType::Access, Some(&acl), &root_cred()).unwrap();
+
+    let user = |uid| FsCred {
+        fsuid: uid,
+        fsgid: 1000,
+        dac_override: false,
+        dac_read_search: false,
+        fowner: false,
+        fsetid: false,
+    };
+    assert!(
+        may_open(inode.as_ref(), &user(1000), OpenFlags::O_RDONLY)
+            == Err(FsError::PermissionDenied)
+    );
+    assert!(may_open(inode.as_ref(), &user(1001), OpenFlags::O_RDONLY).is_ok());
+    assert!(may_open(inode.as_ref(), &user(1001), OpenFlags::O_WRONLY).is_err());
+    assert!(may_open(inode.as_ref(), &root_cred(), OpenFlags::O_RDWR).is_ok());
+}
@@ -171,6 +171,7 @@ pub fn create_test_file(fs: &Arc<Ext4FileSystem>, name: &str) -> Result<Arc<dyn
 
 // Export test modules
 pub mod ext4_basic;
+pub mod ext4_crash;
 pub mod ext4_directory;
 pub mod ext4_error;
 pub mod ext4_integration;
@@ -178,3 +179,4 @@ pub mod ext4_io;
 pub mod ext4_metadata;
 pub mod ext4_permissions;
 pub mod ext4_rename;
+pub mod ext4_xattr;
@@ -1,4 +1,6 @@
+mod devpts;
 mod ext4;
+mod overlay;
 mod proc;
 mod sysfs;
 mod tmpfs;
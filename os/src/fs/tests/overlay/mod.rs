@@ -0,0 +1,111 @@
+//! Overlayfs 测试
+
+use crate::fs::OverlayFs;
+use crate::fs::tmpfs::TmpFs;
+use crate::vfs::{FileMode, FileSystem, FsError, Inode, InodeType};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// 创建下层含有 `etc/passwd`、`etc/hosts` 的 overlayfs，返回 (overlay, 下层根, 上层根)
+fn create_test_overlay() -> (Arc<OverlayFs>, Arc<dyn Inode>, Arc<dyn Inode>) {
+    let lower_fs = TmpFs::new(0);
+    let lower = lower_fs.root_inode();
+    let etc = lower
+        .mkdir("etc", FileMode::from_bits_truncate(0o755))
+        .unwrap();
+    etc.create("passwd", FileMode::from_bits_truncate(0o644))
+        .unwrap()
+        .write_at(0, b"root")
+        .unwrap();
+    etc.create("hosts", FileMode::from_bits_truncate(0o644))
+        .unwrap();
+
+    let upper_fs = TmpFs::new(0);
+    let upper = upper_fs.root_inode();
+    let overlay = OverlayFs::new(lower.clone(), upper.clone(), upper_fs).unwrap();
+    (overlay, lower, upper)
+}
+
+fn names(dir: &Arc<dyn Inode>) -> Vec<String> {
+    let mut names: Vec<String> = dir
+        .readdir()
+        .unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .filter(|n| n != "." && n != "..")
+        .collect();
+    names.sort();
+    names
+}
+
+#[test_case]
+fn test_overlay_copy_up_on_write() {
+    let (overlay, lower, upper) = create_test_overlay();
+    assert!(overlay.fs_type() == "overlay");
+
+    let passwd = overlay
+        .root_inode()
+        .lookup("etc")
+        .unwrap()
+        .lookup("passwd")
+        .unwrap();
+    passwd.write_at(4, b":x").unwrap();
+
+    let mut buf = [0u8; 6];
+    assert!(passwd.read_at(0, &mut buf).unwrap() == 6);
+    assert!(&buf == b"root:x");
+
+    // 下层保持不变，修改后的文件连同父目录出现在上层
+    let mut buf = [0u8; 6];
+    let lower_passwd = lower.lookup("etc").unwrap().lookup("passwd").unwrap();
+    assert!(lower_passwd.read_at(0, &mut buf).unwrap() == 4);
+    let upper_passwd = upper.lookup("etc").unwrap().lookup("passwd").unwrap();
+    assert!(upper_passwd.metadata().unwrap().size == 6);
+}
+
+#[test_case]
+fn test_overlay_whiteout_hides_lower() {
+    let (overlay, lower, upper) = create_test_overlay();
+    let etc = overlay.root_inode().lookup("etc").unwrap();
+
+    etc.unlink("hosts").unwrap();
+    assert!(matches!(etc.lookup("hosts"), Err(FsError::NotFound)));
+    assert!(names(&etc) == ["passwd"]);
+    assert!(lower.lookup("etc").unwrap().lookup("hosts").is_ok());
+
+    // 上层留下 0/0 字符设备作为白化文件
+    let whiteout = upper
+        .lookup("etc")
+        .unwrap()
+        .lookup("hosts")
+        .unwrap()
+        .metadata()
+        .unwrap();
+    assert!(whiteout.inode_type == InodeType::CharDevice);
+    assert!(whiteout.rdev == 0);
+
+    // 在白化处重新创建得到全新的空文件
+    let hosts = etc
+        .create("hosts", FileMode::from_bits_truncate(0o644))
+        .unwrap();
+    assert!(hosts.metadata().unwrap().size == 0);
+    assert!(names(&etc) == ["hosts", "passwd"]);
+}
+
+#[test_case]
+fn test_overlay_readdir_merges_layers() {
+    let (overlay, _lower, upper) = create_test_overlay();
+    let root = overlay.root_inode();
+
+    upper
+        .mkdir("var", FileMode::from_bits_truncate(0o755))
+        .unwrap();
+    root.lookup("etc")
+        .unwrap()
+        .create("resolv.conf", FileMode::from_bits_truncate(0o644))
+        .unwrap();
+
+    assert!(names(&root) == ["etc", "var"]);
+    assert!(names(&root.lookup("etc").unwrap()) == ["hosts", "passwd", "resolv.conf"]);
+}
@@ -148,3 +148,70 @@ fn test_procfs_dynamic_file_regenerate() {
     assert!(read1 > 0);
     assert!(read2 > 0);
 }
+
+#[test_case]
+fn test_procfs_sysctl_allow_wx_roundtrip() {
+    let procfs = create_test_procfs_with_tree().unwrap();
+    let root = procfs.root_inode();
+    let allow_wx = root
+        .lookup("sys")
+        .and_then(|sys| sys.lookup("vm"))
+        .and_then(|vm| vm.lookup("allow_wx"))
+        .unwrap();
+
+    let mut buf = [0u8; 8];
+    let n = allow_wx.read_at(0, &mut buf).unwrap();
+    assert!(&buf[..n] == b"0\n");
+
+    assert!(allow_wx.write_at(0, b"1\n").unwrap() == 2);
+    assert!(mm::wx::allow_wx());
+    assert!(allow_wx.write_at(0, b"2").is_err());
+
+    allow_wx.write_at(0, b"0").unwrap();
+    assert!(!mm::wx::allow_wx());
+}
+
+#[test_case]
+fn test_procfs_dynamic_debug_toggle() {
+    let procfs = create_test_procfs_with_tree().unwrap();
+    let root = procfs.root_inode();
+    let control = root
+        .lookup("sys")
+        .and_then(|sys| sys.lookup("kernel"))
+        .and_then(|kernel| kernel.lookup("dynamic_debug"))
+        .unwrap();
+
+    assert!(control.write_at(0, b"proctest +p\n").unwrap() == 12);
+    let mut buf = [0u8; 1024];
+    let n = control.read_at(0, &mut buf).unwrap();
+    let text = core::str::from_utf8(&buf[..n]).unwrap();
+    assert!(text.lines().any(|l| l == "proctest =p"));
+    assert!(crate::log::is_target_level_enabled(
+        "proctest",
+        crate::log::LogLevel::Debug
+    ));
+
+    assert!(control.write_at(0, b"proctest ~p").is_err());
+    control.write_at(0, b"proctest -p").unwrap();
+    assert!(!crate::log::is_target_level_enabled(
+        "proctest",
+        crate::log::LogLevel::Debug
+    ));
+}
+
+#[test_case]
+fn test_procfs_audit_rules_roundtrip() {
+    let procfs = create_test_procfs_with_tree().unwrap();
+    let root = procfs.root_inode();
+    let rules = root.lookup("audit_rules").unwrap();
+    assert!(root.lookup("audit").is_ok());
+
+    assert!(rules.write_at(0, b"add uid=0 syscall=64\n").is_ok());
+    let mut buf = [0u8; 64];
+    let n = rules.read_at(0, &mut buf).unwrap();
+    assert!(&buf[..n] == b"uid=0 syscall=64\n");
+
+    assert!(rules.write_at(0, b"add gid=0").is_err());
+    assert!(rules.write_at(0, b"clear").is_ok());
+    assert!(rules.read_at(0, &mut buf).unwrap() == 0);
+}
@@ -19,6 +19,7 @@ fn test_sysfs_full_initialization() {
     assert!(root.lookup("devices").is_ok());
     assert!(root.lookup("kernel").is_ok());
     assert!(root.lookup("block").is_ok()); // symlink
+    assert!(root.lookup("fs").and_then(|fs| fs.lookup("pstore")).is_ok());
 }
 
 #[test_case]
@@ -138,3 +138,49 @@ fn test_tmpfs_many_entries() {
     assert!(root.lookup("file_50.txt").is_ok());
     assert!(root.lookup("file_99.txt").is_ok());
 }
+
+#[test_case]
+fn test_tmpfs_hard_link() {
+    let fs = create_test_tmpfs();
+    let root = fs.root_inode();
+    let file = create_test_file_with_content(&fs, "orig.txt", b"shared").unwrap();
+
+    root.link("alias.txt", &file).unwrap();
+    assert!(file.metadata().unwrap().nlinks == 2);
+    assert!(root.link("alias.txt", &file) == Err(FsError::AlreadyExists));
+
+    // 两个名字指向同一个 inode
+    let alias = root.lookup("alias.txt").unwrap();
+    assert!(alias.metadata().unwrap().inode_no == file.metadata().unwrap().inode_no);
+
+    // 删除一个名字后数据仍可通过另一个名字访问
+    root.unlink("orig.txt").unwrap();
+    assert!(alias.metadata().unwrap().nlinks == 1);
+    let mut buf = [0u8; 6];
+    alias.read_at(0, &mut buf).unwrap();
+    assert!(&buf == b"shared");
+
+    // 目录不能硬链接
+    let dir = create_test_dir(&fs, "dir").unwrap();
+    assert!(root.link("dir_alias", &dir) == Err(FsError::NotPermitted));
+}
+
+#[test_case]
+fn test_tmpfs_tmpfile() {
+    let fs = create_test_tmpfs();
+    let root = fs.root_inode();
+    let tmp = root.tmpfile(FileMode::from_bits_truncate(0o600)).unwrap();
+
+    // 匿名文件可读写，但不出现在目录中
+    assert!(tmp.metadata().unwrap().nlinks == 0);
+    tmp.write_at(0, b"anonymous").unwrap();
+    assert!(root.readdir().unwrap().len() == 2);
+
+    // 链接后获得名字
+    root.link("named.txt", &tmp).unwrap();
+    assert!(tmp.metadata().unwrap().nlinks == 1);
+    let named = root.lookup("named.txt").unwrap();
+    let mut buf = [0u8; 9];
+    named.read_at(0, &mut buf).unwrap();
+    assert!(&buf == b"anonymous");
+}
@@ -14,15 +14,20 @@ use bitflags::bitflags;
 use crate::{
     arch::{
         kernel::cpu,
+        syscall::SYS_RESTART_SYSCALL,
         trap::{TrapFrame, sigreturn_trampoline_address},
     },
     kernel::{
         SharedTask, TASK_MANAGER, TaskManagerTrait, TaskState, current_cpu, current_task,
-        exit_process, exit_task_with_block, sleep_task_with_block, wake_up_with_block, yield_task,
+        exit_process, exit_task_with_block, hrtimer::Ktime, sleep_task_with_block,
+        wake_up_with_block, yield_task,
     },
     pr_err,
-    uapi::signal::*,
-    util::{address::align_down, user_buffer::write_to_user},
+    uapi::{
+        errno::{EINTR, ERESTART_RESTARTBLOCK, ERESTARTNOHAND, ERESTARTNOINTR, ERESTARTSYS},
+        signal::*,
+    },
+    util::{address::align_down, user_buffer::copy_to_user},
 };
 
 /// 信号的动作表
@@ -103,6 +108,30 @@ fn handle_one_signal(sig_flag: SignalFlags, action: SignalAction, task: &SharedT
     }
 }
 
+/// 查看（`take` 为真时取出）下一个要投递的信号及其处理动作
+///
+/// 私有待处理信号优先于共享待处理信号。
+fn next_signal(task: &SharedTask, take: bool) -> Option<(SignalFlags, SignalAction)> {
+    let mut t = task.lock();
+    let blocked = t.blocked;
+    let (flag, shared) = if let Some(flag) = first_deliverable_signal(t.pending.signals, blocked) {
+        (flag, false)
+    } else {
+        let flag = first_deliverable_signal(t.shared_pending.lock().signals, blocked)?;
+        (flag, true)
+    };
+    let num = signal_from_flag(flag).unwrap();
+    let action = t.signal_handlers.lock().actions[num];
+    if take {
+        if shared {
+            t.shared_pending.lock().signals.remove(flag);
+        } else {
+            t.pending.signals.remove(flag);
+        }
+    }
+    Some((flag, action))
+}
+
 /// 在返回用户态前检查信号并处理
 /// # 说明:
 /// 该函数会检查当前任务的私有和共享待处理信号集合，
@@ -110,35 +139,77 @@ fn handle_one_signal(sig_flag: SignalFlags, action: SignalAction, task: &SharedT
 /// 如果没有可投递的信号，则直接返回。
 pub fn check_signal() {
     let task = current_task();
-    let (sig_flag, action) = {
-        let mut t = task.lock();
-        let pending_copy = t.pending.clone();
-        let shared_pending_copy = t.shared_pending.lock().clone();
-        let blocked_copy = t.blocked.clone();
-        if let Some(flag) = first_deliverable_signal(pending_copy.signals, blocked_copy) {
-            let num = signal_from_flag(flag).unwrap();
-            let action = {
-                let handlers = t.signal_handlers.lock();
-                handlers.actions[num]
-            };
-            t.pending.signals.remove(flag);
-            (flag, action)
-        } else if let Some(flag) =
-            first_deliverable_signal(shared_pending_copy.signals, blocked_copy)
-        {
-            let num = signal_from_flag(flag).unwrap();
-            let action = {
-                let handlers = t.signal_handlers.lock();
-                handlers.actions[num]
-            };
-            t.shared_pending.lock().signals.remove(flag);
-            (flag, action)
-        } else {
-            return;
-        }
+    if let Some((sig_flag, action)) = next_signal(&task, true) {
+        handle_one_signal(sig_flag, action, &task);
+    }
+}
+
+/// 被信号打断、需要通过 restart_syscall 继续执行的系统调用状态
+#[derive(Debug, Clone, Copy)]
+pub enum RestartBlock {
+    /// 相对时间的 nanosleep / clock_nanosleep
+    Nanosleep {
+        /// 到期时间（单调时钟，纳秒）
+        deadline: Ktime,
+        /// 用户态剩余时间指针，可为 0
+        rem: usize,
+    },
+}
+
+/// 系统调用返回用户态前处理内核内部的重启错误码
+/// # 说明:
+/// 被信号打断的系统调用返回 `-ERESTART*`，这里根据即将投递的信号决定
+/// 重新执行系统调用、改为执行 restart_syscall，或者返回 `-EINTR`：
+/// - 将要运行用户处理函数时，只有 `ERESTARTNOINTR` 以及带 `SA_RESTART` 的 `ERESTARTSYS` 重启；
+/// - 信号被忽略、停止任务或已经不存在时，总是继续执行。
+///
+/// 必须在 [`check_signal`] 之前调用，使信号上下文中保存的是重启后的 pc 与参数。
+/// # 参数:
+/// * `tf`: 系统调用的陷阱帧，pc 已经指向系统调用指令的下一条指令
+/// * `orig_a0`: 系统调用的第一个参数（a0 已被返回值覆盖）
+pub fn handle_syscall_restart(tf: &mut TrapFrame, orig_a0: usize) {
+    let Ok(code) = i32::try_from(-(tf.get_a0() as isize)) else {
+        return;
     };
+    if !matches!(
+        code,
+        ERESTARTSYS | ERESTARTNOINTR | ERESTARTNOHAND | ERESTART_RESTARTBLOCK
+    ) {
+        return;
+    }
 
-    handle_one_signal(sig_flag, action, &task);
+    let task = current_task();
+    // 将要运行用户处理函数时对应的 sa_flags
+    let handler = next_signal(&task, false).and_then(|(_, action)| {
+        let handler = unsafe { action.sa_handler() } as isize;
+        (handler != SIG_DFL && handler != SIG_IGN)
+            .then(|| SaFlags::from_bits_truncate(action.sa_flags as u32))
+    });
+
+    // 系统调用指令长度在两种架构上都是 4 字节
+    let syscall_pc = tf.get_sepc() - 4;
+    match (code, handler) {
+        (ERESTARTNOINTR, _) => {
+            tf.set_a0(orig_a0);
+            tf.set_sepc(syscall_pc);
+        }
+        (ERESTARTSYS, Some(flags)) if flags.contains(SaFlags::RESTART) => {
+            tf.set_a0(orig_a0);
+            tf.set_sepc(syscall_pc);
+        }
+        (_, Some(_)) => {
+            task.lock().restart_block = None;
+            tf.set_a0(-(EINTR as isize) as usize);
+        }
+        (ERESTART_RESTARTBLOCK, None) => {
+            tf.set_a7(SYS_RESTART_SYSCALL);
+            tf.set_sepc(syscall_pc);
+        }
+        (_, None) => {
+            tf.set_a0(orig_a0);
+            tf.set_sepc(syscall_pc);
+        }
+    }
 }
 
 /// 为信号创建 siginfo_t 结构体
@@ -194,8 +265,14 @@ fn install_user_signal_trap_frame(
         let sig_info_addr = sp + core::mem::offset_of!(RtSigFrame, info);
         let ucontext_addr = sp + core::mem::offset_of!(RtSigFrame, uc);
 
-        write_to_user(sig_info_addr as *mut SigInfoT, siginfo);
-        write_to_user(ucontext_addr as *mut UContextT, uc);
+        // 用户栈无法写入信号帧时与 Linux 一致，按 SIGSEGV 的默认动作处理
+        if copy_to_user(sig_info_addr as *mut SigInfoT, siginfo).is_err()
+            || copy_to_user(ucontext_addr as *mut UContextT, uc).is_err()
+        {
+            drop(t);
+            sig_dump(NUM_SIGSEGV);
+            return;
+        }
 
         // 更新 blocked（跳过不可屏蔽信号）
         if sig_num != NUM_SIGKILL && sig_num != NUM_SIGSTOP {
@@ -0,0 +1,122 @@
+//! 时钟源
+//!
+//! 时钟源是一个单调递增的硬件周期计数器。周期数通过定点乘法换算为纳秒：
+//! `ns = (cycles * mult) >> shift`，读取时间的热路径上不需要除法。
+//! `mult` / `shift` 在注册时根据计数器频率计算（校准），频率来自设备树。
+
+use crate::kernel::hrtimer::NSEC_PER_SEC;
+
+/// 计算 mult / shift 时保证不溢出的最长换算区间（秒）
+const CLOCKSOURCE_MAXSEC: u64 = 600;
+
+/// 硬件周期计数器
+#[derive(Clone, Copy)]
+pub struct ClockSource {
+    /// 名称，仅用于日志
+    pub name: &'static str,
+    /// 读取当前周期数
+    read: fn() -> u64,
+    /// 计数器有效位掩码，周期差按此回绕
+    pub mask: u64,
+    /// 计数器频率（Hz）
+    pub freq: u64,
+    /// 周期到纳秒的乘数
+    pub mult: u32,
+    /// 周期到纳秒的移位
+    pub shift: u32,
+}
+
+impl ClockSource {
+    /// 以给定频率校准时钟源
+    ///
+    /// 频率为 0 时无法换算，返回 `None`。
+    pub fn new(name: &'static str, read: fn() -> u64, mask: u64, freq: u64) -> Option<Self> {
+        if freq == 0 {
+            return None;
+        }
+        let (mult, shift) = clocks_calc_mult_shift(freq, NSEC_PER_SEC, CLOCKSOURCE_MAXSEC);
+        Some(Self {
+            name,
+            read,
+            mask,
+            freq,
+            mult,
+            shift,
+        })
+    }
+
+    /// 读取当前周期数
+    #[inline]
+    pub fn read(&self) -> u64 {
+        (self.read)()
+    }
+
+    /// 周期差换算为纳秒（向下取整）
+    #[inline]
+    pub fn cyc2ns(&self, cycles: u64) -> u64 {
+        ((cycles as u128 * self.mult as u128) >> self.shift) as u64
+    }
+
+    /// 纳秒换算为周期差（向上取整）
+    #[inline]
+    pub fn ns2cyc(&self, ns: u64) -> u64 {
+        ((ns as u128) << self.shift).div_ceil(self.mult as u128) as u64
+    }
+}
+
+/// 计算把频率 `from` 换算到频率 `to` 的 mult / shift
+///
+/// 在保证 `maxsec` 秒内的周期数乘以 mult 不超过 64 位的前提下，选取尽可能大的 shift 以提高精度。
+pub fn clocks_calc_mult_shift(from: u64, to: u64, maxsec: u64) -> (u32, u32) {
+    // mult 可用的位数：64 位减去 maxsec 秒内周期数所占的位数
+    let mut tmp = (maxsec as u128 * from as u128) >> 32;
+    let mut sftacc = 32u32;
+    while tmp != 0 {
+        tmp >>= 1;
+        sftacc = sftacc.saturating_sub(1);
+    }
+
+    for shift in (1..=32u32).rev() {
+        let mult = ((to as u128) << shift).saturating_add(from as u128 / 2) / from as u128;
+        if mult >> sftacc == 0 {
+            return (mult as u32, shift);
+        }
+    }
+    (((to + from / 2) / from) as u32, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dummy_read() -> u64 {
+        0
+    }
+
+    // 常见频率下一秒的周期数换算误差不超过 1 纳秒
+    #[test_case]
+    fn test_clocksource_mult_shift_accuracy() {
+        for freq in [10_000_000u64, 12_500_000, 100_000_000, 1_000_000_000] {
+            let cs = ClockSource::new("test", dummy_read, u64::MAX, freq).unwrap();
+            assert!(cs.shift > 0);
+            let ns = cs.cyc2ns(freq);
+            assert!(ns.abs_diff(NSEC_PER_SEC) <= 1);
+            // maxsec 秒内的周期数乘以 mult 不溢出 64 位
+            assert!(
+                (CLOCKSOURCE_MAXSEC * freq)
+                    .checked_mul(cs.mult as u64)
+                    .is_some()
+            );
+        }
+    }
+
+    // 纳秒换算为周期向上取整，换算回来不早于原时间
+    #[test_case]
+    fn test_clocksource_ns2cyc_roundtrip() {
+        let cs = ClockSource::new("test", dummy_read, u64::MAX, 12_500_000).unwrap();
+        for ns in [1u64, 79, 1_000_000, 3 * NSEC_PER_SEC + 7] {
+            assert!(cs.cyc2ns(cs.ns2cyc(ns)) >= ns);
+        }
+        assert!(ClockSource::new("test", dummy_read, u64::MAX, 0).is_none());
+    }
+}
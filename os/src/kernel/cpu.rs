@@ -2,11 +2,12 @@
 //!
 //! 包含 CPU 结构体及其相关操作
 use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::mm::MemorySpace;
 use crate::mm::activate;
 use crate::{
-    kernel::task::SharedTask,
+    kernel::{hrtimer::ktime_get, task::SharedTask},
     sync::{PerCpu, SpinLock},
 };
 use lazy_static::lazy_static;
@@ -14,6 +15,34 @@ use lazy_static::lazy_static;
 pub static mut NUM_CPU: usize = 1;
 pub static mut CLOCK_FREQ: usize = 12_500_000;
 
+/// 已上线 CPU 位掩码
+///
+/// 每个位代表一个 CPU，位 i 为 1 表示 CPU i 已上线。
+/// 为 0 时表示尚未进行 SMP 启动（例如测试环境或未维护掩码的架构），此时认为 `0..NUM_CPU` 都在线。
+static CPU_ONLINE_MASK: AtomicUsize = AtomicUsize::new(0);
+
+/// 标记 CPU 上线或下线
+pub fn set_cpu_online(cpu_id: usize, online: bool) {
+    if online {
+        CPU_ONLINE_MASK.fetch_or(1 << cpu_id, Ordering::Release);
+    } else {
+        CPU_ONLINE_MASK.fetch_and(!(1 << cpu_id), Ordering::Release);
+    }
+}
+
+/// 读取已上线 CPU 位掩码（原始值，可能为 0）
+pub fn cpu_online_mask() -> usize {
+    CPU_ONLINE_MASK.load(Ordering::Acquire)
+}
+
+/// 检查 CPU 是否在线
+pub fn cpu_online(cpu_id: usize) -> bool {
+    match cpu_online_mask() {
+        0 => cpu_id < unsafe { NUM_CPU },
+        mask => cpu_id < usize::BITS as usize && mask & (1 << cpu_id) != 0,
+    }
+}
+
 lazy_static! {
     /// Per-CPU 数据: 每个 CPU 的状态
     ///
@@ -67,6 +96,21 @@ impl Cpu {
     /// # 参数
     /// * `task` - 要切换到的任务
     pub fn switch_task(&mut self, task: SharedTask) {
+        // 结算旧任务的运行时间，新任务从此刻开始计时
+        let now = ktime_get();
+        if let Some(old) = self.current_task.take() {
+            // 空闲 CPU 在中断中直接切换到被唤醒的任务时，idle 循环来不及恢复时钟节拍
+            if self
+                .idle_task
+                .as_ref()
+                .is_some_and(|idle| Arc::ptr_eq(idle, &old))
+            {
+                crate::kernel::tick_nohz_idle_exit();
+            }
+            old.lock().stop_exec(now);
+        }
+        task.lock().start_exec(now);
+
         // 切换当前任务，并在必要时切换到其地址空间
         self.current_task = Some(task.clone());
         if !task.lock().is_kernel_thread() {
@@ -0,0 +1,294 @@
+//! 高精度定时器（hrtimer）
+//!
+//! 每个 CPU 维护一棵按到期时间排序的定时器树，时间单位为自启动以来的纳秒（[`Ktime`]）。
+//! 定时器总是挂到启动它的 CPU 上；每次树的最早到期时间变化时，重新编程本 CPU 的硬件定时器，
+//! 使中断恰好在最近的到期点到来，而不是等到下一个时钟节拍。
+//!
+//! 定时器中断中调用 [`hrtimer_interrupt`] 执行本 CPU 上所有已到期的回调。回调在不持有
+//! 定时器树锁的情况下运行，可以返回 [`HrTimerRestart::Restart`] 以新的到期时间重新入队。
+//!
+//! # 锁顺序
+//! 启动/取消定时器时先持有定时器自身的 `modify` 锁，再持有 CPU 定时器树锁；中断路径只持有树锁。
+
+use alloc::{boxed::Box, collections::btree_map::BTreeMap, sync::Arc};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use uapi::time::TimeSpec;
+
+use crate::arch::timer::set_next_event;
+use crate::config::MAX_CPU_COUNT;
+use crate::sync::SpinLock;
+
+/// 自启动以来的单调时间（纳秒）
+pub type Ktime = u64;
+
+/// 每秒的纳秒数
+pub const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+/// 每毫秒的纳秒数
+pub const NSEC_PER_MSEC: u64 = 1_000_000;
+
+/// 每微秒的纳秒数
+pub const NSEC_PER_USEC: u64 = 1_000;
+
+/// 表示定时器未挂在任何 CPU 上
+const NO_CPU: usize = usize::MAX;
+
+/// 获取当前单调时间（纳秒）
+pub fn ktime_get() -> Ktime {
+    crate::kernel::time::ktime_get_mono()
+}
+
+/// 硬件时钟周期数转换为纳秒（向下取整）
+pub fn cycles_to_ktime(cycles: usize) -> Ktime {
+    crate::kernel::time::cycles_to_mono(cycles as u64)
+}
+
+/// 纳秒转换为硬件时钟周期数（向上取整，保证中断到来时定时器已经到期）
+pub fn ktime_to_cycles(ktime: Ktime) -> usize {
+    crate::kernel::time::mono_to_cycles(ktime).min(usize::MAX as u64) as usize
+}
+
+/// TimeSpec 转换为纳秒，负值视为 0
+pub fn timespec_to_ktime(ts: &TimeSpec) -> Ktime {
+    if ts.tv_sec < 0 {
+        return 0;
+    }
+    (ts.tv_sec as u64)
+        .saturating_mul(NSEC_PER_SEC)
+        .saturating_add(ts.tv_nsec.max(0) as u64)
+}
+
+/// 纳秒转换为 TimeSpec
+pub fn ktime_to_timespec(ktime: Ktime) -> TimeSpec {
+    TimeSpec::new((ktime / NSEC_PER_SEC) as i64, (ktime % NSEC_PER_SEC) as i64)
+}
+
+/// 定时器回调的返回值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HrTimerRestart {
+    /// 不再触发
+    NoRestart,
+    /// 以给定的绝对到期时间重新入队
+    Restart(Ktime),
+}
+
+/// 定时器回调，参数为本次触发对应的到期时间
+type HrTimerFn = Box<dyn Fn(Ktime) -> HrTimerRestart + Send + Sync>;
+
+/// 高精度定时器
+pub struct HrTimer {
+    /// 定时器树中的次序键，保证同一到期时间的定时器互不覆盖
+    id: u64,
+    function: HrTimerFn,
+    /// 串行化启动与取消
+    modify: SpinLock<()>,
+    /// 所在的 CPU（[`NO_CPU`] 表示未入队），只在持有该 CPU 定时器树锁时修改
+    cpu: AtomicUsize,
+    /// 到期时间，只在持有所在 CPU 定时器树锁时修改
+    expires: AtomicU64,
+    /// 每次启动/取消加一，用于判断回调执行期间定时器是否被重新设置
+    generation: AtomicU64,
+}
+
+struct HrTimerBase {
+    queue: BTreeMap<(Ktime, u64), Arc<HrTimer>>,
+    /// 当前编程到硬件的到期时间
+    programmed: Option<Ktime>,
+}
+
+impl HrTimerBase {
+    const fn new() -> Self {
+        Self {
+            queue: BTreeMap::new(),
+            programmed: None,
+        }
+    }
+
+    fn next_expiry(&self) -> Option<Ktime> {
+        self.queue.keys().next().map(|&(expires, _)| expires)
+    }
+
+    /// 按最早到期时间重新编程本 CPU 的硬件定时器
+    ///
+    /// `force` 为假时，最早到期时间没有变化就跳过；中断处理之后必须强制编程，
+    /// 因为硬件定时器已经到期。
+    fn reprogram(&mut self, force: bool) {
+        let next = self.next_expiry();
+        if !force && next == self.programmed {
+            return;
+        }
+        self.programmed = next;
+        // 没有等待中的定时器时推到最远，避免已到期的硬件定时器反复触发中断
+        set_next_event(next.map_or(usize::MAX, ktime_to_cycles));
+    }
+}
+
+static HRTIMER_BASES: [SpinLock<HrTimerBase>; MAX_CPU_COUNT] =
+    [const { SpinLock::new(HrTimerBase::new()) }; MAX_CPU_COUNT];
+
+static NEXT_TIMER_ID: AtomicU64 = AtomicU64::new(0);
+
+impl HrTimer {
+    /// 创建一个未启动的定时器
+    pub fn new(function: impl Fn(Ktime) -> HrTimerRestart + Send + Sync + 'static) -> Arc<Self> {
+        Arc::new(Self {
+            id: NEXT_TIMER_ID.fetch_add(1, Ordering::Relaxed),
+            function: Box::new(function),
+            modify: SpinLock::new(()),
+            cpu: AtomicUsize::new(NO_CPU),
+            expires: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+        })
+    }
+
+    /// 以绝对到期时间启动定时器，已经启动的定时器会先被取消
+    pub fn start(self: &Arc<Self>, expires: Ktime) {
+        let _modify = self.modify.lock();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.dequeue();
+
+        let cpu = crate::arch::kernel::cpu::cpu_id();
+        let mut base = HRTIMER_BASES[cpu].lock();
+        self.enqueue(&mut base, cpu, expires);
+        base.reprogram(false);
+    }
+
+    /// 以相对当前时间的时长启动定时器
+    pub fn start_after(self: &Arc<Self>, delta: Ktime) {
+        self.start(ktime_get().saturating_add(delta));
+    }
+
+    /// 取消定时器
+    ///
+    /// # 返回值
+    /// 定时器在取消前仍处于等待状态时返回 `true`；已经到期（或从未启动）时返回 `false`。
+    pub fn cancel(&self) -> bool {
+        let _modify = self.modify.lock();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        let cpu = self.cpu.load(Ordering::Acquire);
+        let removed = self.dequeue();
+        // 取消的可能是本 CPU 最早到期的定时器，重新编程以免硬件定时器空跑一次
+        if removed && cpu == crate::arch::kernel::cpu::cpu_id() {
+            HRTIMER_BASES[cpu].lock().reprogram(false);
+        }
+        removed
+    }
+
+    /// 定时器是否在等待到期
+    pub fn is_active(&self) -> bool {
+        self.cpu.load(Ordering::Acquire) != NO_CPU
+    }
+
+    /// 等待中的定时器的到期时间
+    pub fn expires(&self) -> Option<Ktime> {
+        self.is_active()
+            .then(|| self.expires.load(Ordering::Acquire))
+    }
+
+    /// 距离到期的剩余时间，未启动或已到期时为 0
+    pub fn remaining(&self) -> Ktime {
+        self.expires()
+            .map_or(0, |expires| expires.saturating_sub(ktime_get()))
+    }
+
+    fn enqueue(self: &Arc<Self>, base: &mut HrTimerBase, cpu: usize, expires: Ktime) {
+        base.queue.insert((expires, self.id), self.clone());
+        self.expires.store(expires, Ordering::Release);
+        self.cpu.store(cpu, Ordering::Release);
+    }
+
+    /// 从所在 CPU 的定时器树中移除，调用者必须持有 `modify` 锁
+    fn dequeue(&self) -> bool {
+        loop {
+            let cpu = self.cpu.load(Ordering::Acquire);
+            if cpu == NO_CPU {
+                return false;
+            }
+            let mut base = HRTIMER_BASES[cpu].lock();
+            // 中断路径可能在加锁前把定时器取出，需要重新确认
+            if self.cpu.load(Ordering::Acquire) != cpu {
+                continue;
+            }
+            let expires = self.expires.load(Ordering::Acquire);
+            base.queue.remove(&(expires, self.id));
+            self.cpu.store(NO_CPU, Ordering::Release);
+            return true;
+        }
+    }
+}
+
+/// 本 CPU 上最早的到期时间
+pub fn next_expiry() -> Option<Ktime> {
+    let cpu = crate::arch::kernel::cpu::cpu_id();
+    HRTIMER_BASES[cpu].lock().next_expiry()
+}
+
+/// 定时器中断处理：执行本 CPU 上所有已到期的定时器，并为下一个到期时间编程硬件定时器
+pub fn hrtimer_interrupt() {
+    let cpu = crate::arch::kernel::cpu::cpu_id();
+    let base_lock = &HRTIMER_BASES[cpu];
+
+    loop {
+        let now = ktime_get();
+        let (timer, expires, generation) = {
+            let mut base = base_lock.lock();
+            let Some(entry) = base.queue.first_entry() else {
+                break;
+            };
+            let expires = entry.key().0;
+            if expires > now {
+                break;
+            }
+            let timer = entry.remove();
+            timer.cpu.store(NO_CPU, Ordering::Release);
+            let generation = timer.generation.load(Ordering::Acquire);
+            (timer, expires, generation)
+        };
+
+        if let HrTimerRestart::Restart(next) = (timer.function)(expires) {
+            let mut base = base_lock.lock();
+            // 回调期间定时器被重新启动或取消时，以那次设置为准
+            if timer.generation.load(Ordering::Acquire) == generation && !timer.is_active() {
+                timer.enqueue(&mut base, cpu, next);
+            }
+        }
+    }
+
+    base_lock.lock().reprogram(true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 纳秒与 TimeSpec 互相转换
+    #[test_case]
+    fn test_ktime_timespec_roundtrip() {
+        let ts = TimeSpec::new(3, 250_000_000);
+        let ktime = timespec_to_ktime(&ts);
+        assert!(ktime == 3_250_000_000);
+        assert!(ktime_to_timespec(ktime) == ts);
+        assert!(timespec_to_ktime(&TimeSpec::new(-1, 0)) == 0);
+    }
+
+    // 周期数换算为纳秒后再换算回来不会提前
+    #[test_case]
+    fn test_ktime_cycles_round_up() {
+        let ktime = 1_234_567;
+        assert!(cycles_to_ktime(ktime_to_cycles(ktime)) >= ktime);
+    }
+
+    // 取消等待中的定时器返回 true，重复取消返回 false
+    #[test_case]
+    fn test_hrtimer_start_cancel() {
+        let timer = HrTimer::new(|_| HrTimerRestart::NoRestart);
+        assert!(!timer.is_active());
+        timer.start_after(NSEC_PER_SEC);
+        assert!(timer.is_active());
+        assert!(timer.remaining() > 0);
+        assert!(timer.cancel());
+        assert!(!timer.is_active());
+        assert!(!timer.cancel());
+    }
+}
@@ -9,8 +9,13 @@ mod scheduler;
 mod task;
 mod timer;
 
+pub mod clocksource;
+pub mod hrtimer;
+pub mod ntp;
 pub mod syscall;
 pub mod time;
+pub mod vdso;
+pub mod watchdog;
 
 pub use cpu::*;
 pub use scheduler::*;
@@ -0,0 +1,375 @@
+//! NTP 风格的墙上时钟校准
+//!
+//! 维护 adjtimex 可见的时钟状态。频率偏差与剩余的时间偏移不会直接跳变墙上时钟，
+//! 而是在时钟节拍中折算为对 REALTIME 偏移量的微小调整，使墙上时钟平滑地追上参考时间。
+
+use core::ffi::{c_int, c_long};
+
+use crate::arch::timer::TICKS_PER_SEC;
+use crate::kernel::hrtimer::{Ktime, NSEC_PER_SEC, ktime_get};
+use crate::kernel::time::{adjust_realtime, realtime_now};
+use crate::sync::SpinLock;
+use uapi::errno::{EINVAL, EPERM};
+use uapi::time::{Timex, adjtimex::*};
+
+/// 传统 adjtime 的模式位，用于区分 ADJ_OFFSET_SINGLESHOT 与 ADJ_OFFSET
+const ADJ_ADJTIME: c_int = 0x8000;
+/// 只读取传统 adjtime 剩余偏移的模式位
+const ADJ_OFFSET_READONLY: c_int = 0x2000;
+/// PLL 时间常数的基础移位
+const SHIFT_PLL: i64 = 2;
+/// 默认每节拍微秒数
+const DEFAULT_TICK_USEC: i64 = 1_000_000 / TICKS_PER_SEC as i64;
+/// 频率偏差的定点单位：1 ppm = 2^16，换算到纳秒还需除以 10^6
+const FREQ_DIVISOR: i128 = 1_000_000 << 16;
+/// 补偿时间偏移的最大速率，与最大频率偏差相同（500 ppm）
+const MAX_SLEW_PPM: i128 = (MAXFREQ_SCALED >> 16) as i128;
+
+/// 时钟校准状态
+struct NtpState {
+    /// 频率偏差（单位 2^-16 ppm）
+    freq: i64,
+    /// PLL 模式下尚未补偿的时间偏移（纳秒）
+    offset: i64,
+    /// 传统 adjtime 尚未补偿的时间偏移（纳秒）
+    adjust: i64,
+    /// 时钟状态（STA_* 组合）
+    status: c_int,
+    /// 最大误差（微秒）
+    maxerror: i64,
+    /// 估计误差（微秒）
+    esterror: i64,
+    /// PLL 时间常数
+    constant: i64,
+    /// 每节拍微秒数
+    tick: i64,
+    /// TAI 与 UTC 的偏移（秒）
+    tai: c_int,
+    /// 上次校准时的单调时间
+    last: Ktime,
+    /// 上次设置 PLL 偏移时的单调时间，用于估计频率
+    last_offset: Ktime,
+    /// 频率调整中不足 1 纳秒的部分（单位 2^-16 ppm · 纳秒）
+    frac: i128,
+    /// 最大误差增长中不足 1 微秒的部分（纳秒 · 500 ppm）
+    error_frac: i128,
+}
+
+impl NtpState {
+    const fn new() -> Self {
+        Self {
+            freq: 0,
+            offset: 0,
+            adjust: 0,
+            status: STA_UNSYNC,
+            maxerror: NTP_PHASE_LIMIT,
+            esterror: NTP_PHASE_LIMIT,
+            constant: SHIFT_PLL,
+            tick: DEFAULT_TICK_USEC,
+            tai: 0,
+            last: 0,
+            last_offset: 0,
+            frac: 0,
+            error_frac: 0,
+        }
+    }
+
+    /// 推进到单调时间 `now`，返回墙上时钟应调整的纳秒数
+    fn advance(&mut self, now: Ktime) -> i64 {
+        let elapsed = now.saturating_sub(self.last) as i128;
+        self.last = now;
+        if elapsed == 0 {
+            return 0;
+        }
+
+        // 频率偏差；每节拍微秒数偏离默认值同样折算为频率偏差
+        let tick_freq =
+            (self.tick - DEFAULT_TICK_USEC) as i128 * FREQ_DIVISOR / DEFAULT_TICK_USEC as i128;
+        self.frac += elapsed * (self.freq as i128 + tick_freq);
+        let mut delta = self.frac / FREQ_DIVISOR;
+        self.frac -= delta * FREQ_DIVISOR;
+
+        let limit = (elapsed * MAX_SLEW_PPM / 1_000_000).max(1);
+
+        // PLL 偏移按时间常数指数衰减，速率不超过最大频率偏差
+        if self.offset != 0 {
+            let tau = (NSEC_PER_SEC as i128) << (SHIFT_PLL + self.constant);
+            let mut step = self.offset as i128 * elapsed / tau;
+            if step == 0 {
+                step = self.offset.signum() as i128;
+            }
+            let step = step.clamp(-limit, limit);
+            self.offset -= step as i64;
+            delta += step;
+        }
+
+        // 传统 adjtime 以固定速率补偿
+        if self.adjust != 0 {
+            let step = (self.adjust as i128).clamp(-limit, limit);
+            self.adjust -= step as i64;
+            delta += step;
+        }
+
+        // 没有新的同步信息时，最大误差按最大频率偏差增长
+        self.error_frac += elapsed * MAX_SLEW_PPM;
+        let grow = self.error_frac / 1_000_000_000;
+        self.error_frac -= grow * 1_000_000_000;
+        self.maxerror += grow as i64;
+        if self.maxerror >= NTP_PHASE_LIMIT {
+            self.maxerror = NTP_PHASE_LIMIT;
+            self.status |= STA_UNSYNC;
+        }
+
+        delta as i64
+    }
+
+    /// 以纳秒为单位的偏移与 `txc` 中单位之间的换算因子
+    fn offset_unit(&self) -> i64 {
+        if self.status & STA_NANO != 0 { 1 } else { 1000 }
+    }
+
+    /// 设置 PLL 偏移，并在未冻结频率时据此修正频率偏差
+    fn update_offset(&mut self, offset: i64, now: Ktime) {
+        if self.status & STA_PLL == 0 {
+            return;
+        }
+        let offset = offset
+            .saturating_mul(self.offset_unit())
+            .clamp(-MAXPHASE, MAXPHASE);
+
+        let secs = if self.status & STA_FREQHOLD != 0 || self.last_offset == 0 {
+            0
+        } else {
+            (now.saturating_sub(self.last_offset) / NSEC_PER_SEC) as i128
+        };
+        self.last_offset = now;
+
+        if secs > 0 {
+            // 偏移 / τ² 即需要补偿的频率（纳秒每秒，即 10^-3 ppm）
+            let tau = 1i128 << (SHIFT_PLL + self.constant);
+            let adj = offset as i128 * secs * (1 << 16) / (1000 * tau * tau);
+            self.freq = (self.freq as i128 + adj)
+                .clamp(-MAXFREQ_SCALED as i128, MAXFREQ_SCALED as i128)
+                as i64;
+        }
+        self.offset = offset;
+    }
+
+    /// 按 `txc.modes` 修改状态，并把当前状态写回 `txc`
+    fn apply(&mut self, txc: &mut Timex, now: Ktime) -> c_int {
+        let modes = txc.modes;
+        if modes & ADJ_ADJTIME != 0 {
+            // 传统 adjtime：offset 以微秒为单位，返回旧的剩余偏移
+            let old = self.adjust / 1000;
+            if modes & ADJ_OFFSET_READONLY == 0 {
+                self.adjust = (txc.offset as i64)
+                    .saturating_mul(1000)
+                    .clamp(-MAXPHASE, MAXPHASE);
+            }
+            self.fill(txc);
+            txc.offset = old as c_long;
+        } else {
+            if modes & ADJ_STATUS != 0 {
+                if self.status & STA_PLL == 0 && txc.status & STA_PLL != 0 {
+                    self.last_offset = now;
+                }
+                self.status = (self.status & !STA_RWMASK) | (txc.status & STA_RWMASK);
+            }
+            if modes & ADJ_NANO != 0 {
+                self.status |= STA_NANO;
+            }
+            if modes & ADJ_MICRO != 0 {
+                self.status &= !STA_NANO;
+            }
+            if modes & ADJ_FREQUENCY != 0 {
+                self.freq = (txc.freq as i64).clamp(-MAXFREQ_SCALED, MAXFREQ_SCALED);
+            }
+            if modes & ADJ_MAXERROR != 0 {
+                self.maxerror = (txc.maxerror as i64).clamp(0, NTP_PHASE_LIMIT);
+            }
+            if modes & ADJ_ESTERROR != 0 {
+                self.esterror = (txc.esterror as i64).clamp(0, NTP_PHASE_LIMIT);
+            }
+            if modes & ADJ_TIMECONST != 0 {
+                self.constant = (txc.constant as i64).clamp(0, MAXTC);
+            }
+            // 与 Linux 一致，TAI 偏移通过 constant 字段传入
+            if modes & ADJ_TAI != 0 && txc.constant >= 0 {
+                self.tai = txc.constant as c_int;
+            }
+            if modes & ADJ_OFFSET != 0 {
+                self.update_offset(txc.offset as i64, now);
+            }
+            if modes & ADJ_TICK != 0 {
+                self.tick = txc.tick as i64;
+            }
+            self.fill(txc);
+        }
+
+        if self.status & STA_UNSYNC != 0 {
+            TIME_ERROR
+        } else {
+            TIME_OK
+        }
+    }
+
+    /// 把当前状态写回 `txc`（不含时间）
+    fn fill(&self, txc: &mut Timex) {
+        txc.offset = (self.offset / self.offset_unit()) as c_long;
+        txc.freq = self.freq as c_long;
+        txc.maxerror = self.maxerror as c_long;
+        txc.esterror = self.esterror as c_long;
+        txc.status = self.status;
+        txc.constant = self.constant as c_long;
+        txc.precision = 1;
+        txc.tolerance = MAXFREQ_SCALED as c_long;
+        txc.tick = self.tick as c_long;
+        txc.tai = self.tai;
+        txc.ppsfreq = 0;
+        txc.jitter = 0;
+        txc.shift = 0;
+        txc.stabil = 0;
+        txc.jitcnt = 0;
+        txc.calcnt = 0;
+        txc.errcnt = 0;
+        txc.stbcnt = 0;
+    }
+}
+
+static NTP: SpinLock<NtpState> = SpinLock::new(NtpState::new());
+
+/// 按频率偏差与剩余偏移调整墙上时钟，由时钟节拍调用
+pub fn tick() {
+    let delta = NTP.lock().advance(ktime_get());
+    if delta != 0 {
+        adjust_realtime(delta);
+    }
+}
+
+/// 检查 `txc` 的参数是否合法
+fn validate(txc: &Timex, privileged: bool) -> Result<(), i32> {
+    let modes = txc.modes;
+    if modes & ADJ_ADJTIME != 0 {
+        // 传统 adjtime 只允许 ADJ_OFFSET_SINGLESHOT 与 ADJ_OFFSET_SS_READ
+        if modes & ADJ_OFFSET_SINGLESHOT != ADJ_OFFSET_SINGLESHOT {
+            return Err(EINVAL);
+        }
+        if modes & ADJ_OFFSET_READONLY == 0 && !privileged {
+            return Err(EPERM);
+        }
+        return Ok(());
+    }
+
+    if modes != 0 && !privileged {
+        return Err(EPERM);
+    }
+    if modes & ADJ_TICK != 0 {
+        let tick = txc.tick as i64;
+        if tick < DEFAULT_TICK_USEC * 9 / 10 || tick > DEFAULT_TICK_USEC * 11 / 10 {
+            return Err(EINVAL);
+        }
+    }
+    if modes & ADJ_SETOFFSET != 0 {
+        let limit = if modes & ADJ_NANO != 0 {
+            NSEC_PER_SEC as c_long
+        } else {
+            1_000_000
+        };
+        if txc.time.tv_usec < 0 || txc.time.tv_usec >= limit {
+            return Err(EINVAL);
+        }
+    }
+    Ok(())
+}
+
+/// 读取或调整时钟校准参数
+///
+/// # 参数
+/// - `txc`: 输入为要修改的参数，返回时被填充为当前状态
+/// - `privileged`: 调用者是否拥有 CAP_SYS_TIME
+///
+/// # 返回值
+/// 成功时返回时钟状态（TIME_OK 或 TIME_ERROR），失败时返回 errno
+pub fn adjtimex(txc: &mut Timex, privileged: bool) -> Result<c_int, i32> {
+    validate(txc, privileged)?;
+
+    if txc.modes & ADJ_ADJTIME == 0 && txc.modes & ADJ_SETOFFSET != 0 {
+        let unit = if txc.modes & ADJ_NANO != 0 { 1 } else { 1000 };
+        let delta = (txc.time.tv_sec as i64)
+            .saturating_mul(NSEC_PER_SEC as i64)
+            .saturating_add(txc.time.tv_usec as i64 * unit);
+        adjust_realtime(delta);
+    }
+
+    let state = NTP.lock().apply(txc, ktime_get());
+
+    let now = realtime_now();
+    txc.time.tv_sec = now.tv_sec;
+    txc.time.tv_usec = if txc.status & STA_NANO != 0 {
+        now.tv_nsec
+    } else {
+        now.tv_nsec / 1000
+    };
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synced() -> NtpState {
+        let mut ntp = NtpState::new();
+        ntp.status = STA_PLL;
+        ntp.maxerror = 0;
+        ntp
+    }
+
+    /// 测试频率偏差按比例调整时钟
+    #[test_case]
+    fn test_ntp_frequency() {
+        let mut ntp = synced();
+        ntp.freq = 100 << 16;
+        ntp.advance(0);
+        assert!(ntp.advance(NSEC_PER_SEC) == 100_000);
+        ntp.freq = -(100 << 16);
+        assert!(ntp.advance(2 * NSEC_PER_SEC) == -100_000);
+    }
+
+    /// 测试偏移补偿速率不超过 500 ppm
+    #[test_case]
+    fn test_ntp_slew_bounded() {
+        let mut ntp = synced();
+        ntp.constant = 0;
+        ntp.offset = 100_000_000;
+        ntp.advance(0);
+        let delta = ntp.advance(10_000_000);
+        assert!(delta == 5_000);
+        assert!(ntp.offset == 100_000_000 - 5_000);
+    }
+
+    /// 测试传统 adjtime 返回旧的剩余偏移
+    #[test_case]
+    fn test_ntp_singleshot() {
+        let mut ntp = synced();
+        let mut txc: Timex = unsafe { core::mem::zeroed() };
+        txc.modes = ADJ_OFFSET_SINGLESHOT;
+        txc.offset = 2_000;
+        ntp.apply(&mut txc, 0);
+        assert!(txc.offset == 0);
+
+        txc.modes = ADJ_OFFSET_SS_READ;
+        ntp.apply(&mut txc, 0);
+        assert!(txc.offset == 2_000);
+        assert!(ntp.adjust == 2_000_000);
+    }
+
+    /// 测试长时间未同步后时钟被标记为未同步
+    #[test_case]
+    fn test_ntp_unsync_after_error_grows() {
+        let mut ntp = synced();
+        ntp.advance(0);
+        ntp.advance(40 * NSEC_PER_SEC);
+        assert!(ntp.status & STA_UNSYNC != 0);
+        assert!(ntp.maxerror == NTP_PHASE_LIMIT);
+    }
+}
@@ -13,9 +13,10 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::{
     arch::kernel::{context::Context, switch},
+    arch::trap::SumGuard,
     config::MAX_CPU_COUNT,
     kernel::{TaskState, TaskStruct, scheduler::rr_scheduler::RRScheduler, task::SharedTask},
-    sync::{SpinLock, SpinLockGuard},
+    sync::{SpinLock, SpinLockGuard, lock_class},
 };
 
 pub use task_queue::TaskQueue;
@@ -24,7 +25,7 @@ pub use wait_queue::WaitQueue;
 /// Per-CPU 调度器数组
 /// 每个 CPU 拥有独立的运行队列和调度器实例
 static SCHEDULERS: [SpinLock<RRScheduler>; MAX_CPU_COUNT] =
-    [const { SpinLock::new(RRScheduler::empty()) }; MAX_CPU_COUNT];
+    [const { SpinLock::with_class(RRScheduler::empty(), &lock_class::SCHEDULER) }; MAX_CPU_COUNT];
 
 /// 负载均衡计数器
 /// 用于简单轮转选择目标 CPU
@@ -104,6 +105,10 @@ pub fn pick_cpu() -> usize {
 
 /// 执行一次调度操作，切换到下一个任务
 pub fn schedule() {
+    debug_assert!(
+        crate::sync::irq_locks_held() == 0,
+        "schedule: called while holding a SpinLockIrq"
+    );
     // 读取并禁用中断，保护整个调度过程，并在返回时恢复原状态
     let flags = unsafe { crate::arch::intr::read_and_disable_interrupts() };
 
@@ -124,6 +129,8 @@ pub fn schedule() {
     };
 
     if should_try_switch {
+        // 读临界区不可抢占，主动调度时一定不在读临界区内
+        crate::sync::rcu_note_quiescent_state();
         let plan = {
             let mut sched = current_scheduler().lock();
             // NOTE: next_task 内部会更新 current_task 与 current_memory_space 并切换页表
@@ -131,8 +138,12 @@ pub fn schedule() {
         }; // 调度器锁在这里释放
 
         if let Some(plan) = plan {
+            // 用户内存访问窗口属于 CPU 状态，不能随切换带给下一个任务
+            let user_access = SumGuard::suspend();
             // SAFETY: next_task 生成的上下文指针有效
             unsafe { switch(plan.old, plan.new) };
+            SumGuard::resume(user_access);
+            crate::kernel::rseq_preempt();
             // 通常不会立即返回；返回时再继续当前上下文后续逻辑
         }
     }
@@ -193,7 +204,9 @@ pub fn wake_up_with_block(task: SharedTask) {
             t.on_cpu = Some(target_cpu);
         }
 
-        crate::pr_debug!(
+        // 持有运行队列锁，不能直接写控制台
+        crate::printk_deferred!(
+            crate::log::LogLevel::Debug,
             "[Scheduler] Waking up task {} on CPU {}",
             task_tid,
             target_cpu
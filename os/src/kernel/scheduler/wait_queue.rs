@@ -1,9 +1,11 @@
 //! 等待队列模块
 //!
-//! 定义了等待队列结构体及其相关操作
+//! 定义了按任务操作的等待队列结构体及其相关操作
+//!
+//! 只需要“睡眠直到条件成立”的场景请使用 [`crate::sync::WaitQueue`]，它支持独占等待与超时。
 use crate::kernel::task::SharedTask;
 use crate::kernel::{TaskQueue, sleep_task_with_block, wake_up_with_block, yield_task};
-use crate::sync::RawSpinLock;
+use crate::sync::{RawSpinLock, lock_class};
 use alloc::vec::Vec;
 
 /// 等待队列结构体
@@ -29,7 +31,7 @@ impl WaitQueue {
     pub fn new() -> Self {
         WaitQueue {
             tasks: TaskQueue::new(),
-            lock: RawSpinLock::new(),
+            lock: RawSpinLock::with_class(&lock_class::WAIT_QUEUE),
         }
     }
 
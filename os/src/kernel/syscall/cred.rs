@@ -1,11 +1,26 @@
 //! 用户凭证和权限相关的系统调用
 //!
-//! 在单 root 用户系统中，这些系统调用存储值但不实际限制权限
+//! 在单 root 用户系统中，这些系统调用存储值但不实际限制权限。
+//! 用户态看到的 ID 均经当前任务所属用户命名空间的 `uid_map` / `gid_map` 换算。
 
-use crate::kernel::task::current_task;
-use crate::util::user_buffer::{validate_user_ptr_mut, write_to_user};
+use crate::kernel::task::{IdMapKind, current_task};
+use crate::security::audit;
+use crate::util::user_buffer::{copy_to_user, validate_user_ptr_mut};
 use uapi::cred::{GID_UNCHANGED, ROOT_GID, ROOT_UID, UID_UNCHANGED};
-use uapi::errno::{EFAULT, EPERM};
+use uapi::errno::{EFAULT, EINVAL, EPERM};
+
+/// 把当前命名空间内的 ID 换算为内核 ID 并检查：未映射返回 -EINVAL，非 root 返回 -EPERM
+fn check_id(kind: IdMapKind, id: u32) -> isize {
+    let root = match kind {
+        IdMapKind::Uid => ROOT_UID,
+        IdMapKind::Gid => ROOT_GID,
+    };
+    match current_task().lock().user_ns.make_kid(kind, id) {
+        None => -(EINVAL as isize),
+        Some(kid) if kid == root => 0,
+        Some(_) => -(EPERM as isize),
+    }
+}
 
 /// 获取真实用户 ID
 ///
@@ -13,7 +28,9 @@ use uapi::errno::{EFAULT, EPERM};
 pub fn getuid() -> isize {
     let task = current_task();
     let task_inner = task.lock();
-    task_inner.credential.uid as isize
+    task_inner
+        .user_ns
+        .from_kuid_munged(task_inner.credential.uid) as isize
 }
 
 /// 获取有效用户 ID
@@ -22,7 +39,9 @@ pub fn getuid() -> isize {
 pub fn geteuid() -> isize {
     let task = current_task();
     let task_inner = task.lock();
-    task_inner.credential.euid as isize
+    task_inner
+        .user_ns
+        .from_kuid_munged(task_inner.credential.euid) as isize
 }
 
 /// 获取真实组 ID
@@ -31,7 +50,9 @@ pub fn geteuid() -> isize {
 pub fn getgid() -> isize {
     let task = current_task();
     let task_inner = task.lock();
-    task_inner.credential.gid as isize
+    task_inner
+        .user_ns
+        .from_kgid_munged(task_inner.credential.gid) as isize
 }
 
 /// 获取有效组 ID
@@ -40,37 +61,43 @@ pub fn getgid() -> isize {
 pub fn getegid() -> isize {
     let task = current_task();
     let task_inner = task.lock();
-    task_inner.credential.egid as isize
+    task_inner
+        .user_ns
+        .from_kgid_munged(task_inner.credential.egid) as isize
 }
 
 /// 设置用户 ID
 ///
 /// 在单 root 用户系统中，只允许设置为 0（root）
 pub fn setuid(uid: u32) -> isize {
-    if uid == ROOT_UID {
-        // 在单 root 用户系统中，uid 始终是 0，不需要实际修改
-        0
-    } else {
-        // 假装没有权限设置非 root 用户
-        -EPERM as isize
-    }
+    // 在单 root 用户系统中，uid 始终是 0，不需要实际修改；
+    // 假装没有权限设置非 root 用户
+    let ret = check_id(IdMapKind::Uid, uid);
+    audit::cred_change("setuid", [uid, UID_UNCHANGED, UID_UNCHANGED], ret);
+    ret
 }
 
 /// 设置组 ID
 ///
 /// 在单 root 用户系统中，只允许设置为 0（root 组）
 pub fn setgid(gid: u32) -> isize {
-    if gid == ROOT_GID { 0 } else { -EPERM as isize }
+    let ret = check_id(IdMapKind::Gid, gid);
+    audit::cred_change("setgid", [gid, GID_UNCHANGED, GID_UNCHANGED], ret);
+    ret
 }
 
 /// 设置有效用户 ID
 pub fn seteuid(euid: u32) -> isize {
-    if euid == ROOT_UID { 0 } else { -EPERM as isize }
+    let ret = check_id(IdMapKind::Uid, euid);
+    audit::cred_change("seteuid", [euid, UID_UNCHANGED, UID_UNCHANGED], ret);
+    ret
 }
 
 /// 设置有效组 ID
 pub fn setegid(egid: u32) -> isize {
-    if egid == ROOT_GID { 0 } else { -EPERM as isize }
+    let ret = check_id(IdMapKind::Gid, egid);
+    audit::cred_change("setegid", [egid, GID_UNCHANGED, GID_UNCHANGED], ret);
+    ret
 }
 
 /// 同时设置真实、有效和保存的用户 ID
@@ -85,16 +112,16 @@ pub fn setegid(egid: u32) -> isize {
 /// * -EPERM - 权限不足
 /// * -EINVAL - 无效参数
 pub fn setresuid(ruid: u32, euid: u32, suid: u32) -> isize {
-    // 检查参数有效性：要么是 ROOT_UID，要么是 UID_UNCHANGED
-    if (ruid != ROOT_UID && ruid != UID_UNCHANGED)
-        || (euid != ROOT_UID && euid != UID_UNCHANGED)
-        || (suid != ROOT_UID && suid != UID_UNCHANGED)
-    {
-        return -EPERM as isize;
-    }
-
+    // 检查参数有效性：要么映射到 ROOT_UID，要么是 UID_UNCHANGED
     // 在单 root 用户系统中，所有 ID 始终是 0，不需要实际修改
-    0
+    let ret = [ruid, euid, suid]
+        .into_iter()
+        .filter(|&id| id != UID_UNCHANGED)
+        .map(|id| check_id(IdMapKind::Uid, id))
+        .find(|&r| r != 0)
+        .unwrap_or(0);
+    audit::cred_change("setresuid", [ruid, euid, suid], ret);
+    ret
 }
 
 /// 同时设置真实、有效和保存的组 ID
@@ -109,16 +136,16 @@ pub fn setresuid(ruid: u32, euid: u32, suid: u32) -> isize {
 /// * -EPERM - 权限不足
 /// * -EINVAL - 无效参数
 pub fn setresgid(rgid: u32, egid: u32, sgid: u32) -> isize {
-    // 检查参数有效性：要么是 ROOT_GID，要么是 GID_UNCHANGED
-    if (rgid != ROOT_GID && rgid != GID_UNCHANGED)
-        || (egid != ROOT_GID && egid != GID_UNCHANGED)
-        || (sgid != ROOT_GID && sgid != GID_UNCHANGED)
-    {
-        return -EPERM as isize;
-    }
-
+    // 检查参数有效性：要么映射到 ROOT_GID，要么是 GID_UNCHANGED
     // 在单 root 用户系统中，所有 ID 始终是 0，不需要实际修改
-    0
+    let ret = [rgid, egid, sgid]
+        .into_iter()
+        .filter(|&id| id != GID_UNCHANGED)
+        .map(|id| check_id(IdMapKind::Gid, id))
+        .find(|&r| r != 0)
+        .unwrap_or(0);
+    audit::cred_change("setresgid", [rgid, egid, sgid], ret);
+    ret
 }
 
 /// 获取真实、有效和保存的用户 ID
@@ -150,17 +177,19 @@ pub fn getresuid(ruid: *mut u32, euid: *mut u32, suid: *mut u32) -> isize {
     let task = current_task();
     let task_inner = task.lock();
     let cred = &task_inner.credential;
+    let ns = &task_inner.user_ns;
 
-    // 安全地写入用户空间
-    unsafe {
-        if !ruid.is_null() {
-            write_to_user(ruid, cred.uid);
-        }
-        if !euid.is_null() {
-            write_to_user(euid, cred.euid);
+    // 指针已检查，写入失败时返回 EFAULT
+    for (ptr, id) in [
+        (ruid, ns.from_kuid_munged(cred.uid)),
+        (euid, ns.from_kuid_munged(cred.euid)),
+        (suid, ns.from_kuid_munged(cred.suid)),
+    ] {
+        if ptr.is_null() {
+            continue;
         }
-        if !suid.is_null() {
-            write_to_user(suid, cred.suid);
+        if let Err(e) = copy_to_user(ptr, id) {
+            return -(e as isize);
         }
     }
     0
@@ -195,17 +224,19 @@ pub fn getresgid(rgid: *mut u32, egid: *mut u32, sgid: *mut u32) -> isize {
     let task = current_task();
     let task_inner = task.lock();
     let cred = &task_inner.credential;
+    let ns = &task_inner.user_ns;
 
-    // 安全地写入用户空间
-    unsafe {
-        if !rgid.is_null() {
-            write_to_user(rgid, cred.gid);
-        }
-        if !egid.is_null() {
-            write_to_user(egid, cred.egid);
+    // 指针已检查，写入失败时返回 EFAULT
+    for (ptr, id) in [
+        (rgid, ns.from_kgid_munged(cred.gid)),
+        (egid, ns.from_kgid_munged(cred.egid)),
+        (sgid, ns.from_kgid_munged(cred.sgid)),
+    ] {
+        if ptr.is_null() {
+            continue;
         }
-        if !sgid.is_null() {
-            write_to_user(sgid, cred.sgid);
+        if let Err(e) = copy_to_user(ptr, id) {
+            return -(e as isize);
         }
     }
     0
@@ -220,7 +251,8 @@ pub fn getresgid(rgid: *mut u32, egid: *mut u32, sgid: *mut u32) -> isize {
 /// 返回之前的 umask 值
 ///
 /// # 注意
-/// 在当前方案中，umask 值会被存储但不会实际应用到文件创建过程z
+/// 创建文件、目录与设备节点时从请求的模式中去掉 umask 中的位；父目录带默认 ACL 时改由
+/// ACL 决定模式，不应用 umask
 pub fn umask(mask: u32) -> isize {
     let task = current_task();
     let mut task_inner = task.lock();
@@ -0,0 +1,144 @@
+//! epoll 系统调用实现
+//!
+//! 监视项与就绪链表由 [`EpollFile`] 维护；`epoll_pwait` 把一个 [`PollWaiter`]
+//! 登记到 epoll 实例上，没有就绪事件时睡眠到有监视项就绪、超时或收到信号。
+
+use crate::kernel::current_task;
+use crate::util::user_buffer::{copy_from_user, copy_to_user};
+use crate::vfs::{EpollFile, FdFlags, FdFlagsExt, File, FsError, OpenFlags, PollWaker, RegFile};
+use alloc::sync::Arc;
+use uapi::epoll::{EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD, EpollEvent};
+use uapi::errno::{EFAULT, EINTR, EINVAL, EPERM};
+
+use super::io::PollWaiter;
+use crate::sync::WaitResult;
+
+/// epoll_create1 - 创建 epoll 实例
+///
+/// # 返回值
+/// * 成功返回文件描述符
+/// * -EINVAL - flags 含有 EPOLL_CLOEXEC 以外的位
+pub fn epoll_create1(flags: u32) -> isize {
+    if flags & !EPOLL_CLOEXEC != 0 {
+        return -(EINVAL as isize);
+    }
+    let open_flags = OpenFlags::from_bits_truncate(flags);
+    let file = Arc::new(EpollFile::new(OpenFlags::empty()));
+
+    let fd_table = current_task().lock().fd_table.clone();
+    match fd_table.alloc_with_flags(file as Arc<dyn File>, FdFlags::from_open_flags(open_flags)) {
+        Ok(fd) => fd as isize,
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// epoll_ctl - 增加、修改或移除 epoll 实例的监视项
+///
+/// # 返回值
+/// * -EBADF - epfd 或 fd 无效
+/// * -EINVAL - epfd 不是 epoll 实例、fd 与 epfd 相同或 op 无效
+/// * -EPERM - fd 指向不支持 epoll 的普通文件或目录
+/// * -EEXIST / -ENOENT - 增加已存在的监视项 / 修改或移除不存在的监视项
+/// * -ELOOP - 监视 epoll 实例会形成环或嵌套过深
+pub fn epoll_ctl(epfd: usize, op: i32, fd: usize, event: *const EpollEvent) -> isize {
+    let (ep_file, file) = {
+        let task = current_task();
+        let task = task.lock();
+        match (task.fd_table.get(epfd), task.fd_table.get(fd)) {
+            (Ok(ep_file), Ok(file)) => (ep_file, file),
+            (Err(e), _) | (_, Err(e)) => return e.to_errno(),
+        }
+    };
+    let Some(ep) = ep_file.as_any().downcast_ref::<EpollFile>() else {
+        return -(EINVAL as isize);
+    };
+    // 普通文件总是就绪，Linux 不允许监视
+    if file.as_any().is::<RegFile>() {
+        return -(EPERM as isize);
+    }
+
+    let event = if op == EPOLL_CTL_DEL {
+        EpollEvent::default()
+    } else {
+        match copy_from_user(event) {
+            Ok(event) => event,
+            Err(_) => return -(EFAULT as isize),
+        }
+    };
+    let result = match op {
+        EPOLL_CTL_ADD => ep.ctl_add(fd as i32, &file, event),
+        EPOLL_CTL_MOD => ep.ctl_mod(fd as i32, &file, event),
+        EPOLL_CTL_DEL => ep.ctl_del(fd as i32, &file),
+        _ => Err(FsError::InvalidArgument),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// epoll_pwait - 等待 epoll 实例上的事件
+///
+/// `timeout` 以毫秒计，负数表示无限等待。信号掩码暂不处理（同 ppoll）。
+///
+/// # 返回值
+/// * 成功返回写入 `events` 的事件数，超时返回 0
+/// * -EBADF - epfd 无效
+/// * -EINVAL - epfd 不是 epoll 实例或 maxevents 不为正
+/// * -EFAULT - events 不可写
+/// * -EINTR - 被信号打断
+pub fn epoll_pwait(
+    epfd: usize,
+    events: *mut EpollEvent,
+    maxevents: i32,
+    timeout: i32,
+    _sigmask: usize,
+    _sigsetsize: usize,
+) -> isize {
+    use crate::kernel::hrtimer::ktime_get;
+
+    if maxevents <= 0 {
+        return -(EINVAL as isize);
+    }
+    let task = current_task();
+    let file = match task.lock().fd_table.get(epfd) {
+        Ok(f) => f,
+        Err(e) => return e.to_errno(),
+    };
+    let Some(ep) = file.as_any().downcast_ref::<EpollFile>() else {
+        return -(EINVAL as isize);
+    };
+
+    // 超时的到期时间（单调时钟，纳秒）
+    let deadline = (timeout >= 0).then(|| ktime_get().saturating_add(timeout as u64 * 1_000_000));
+
+    // 等待者在整个调用期间登记在 epoll 实例上，任一监视项就绪时被唤醒
+    let waiter = PollWaiter::new();
+    let waker: Arc<dyn PollWaker> = waiter.clone();
+    ep.poll(Some(&waker));
+
+    loop {
+        // 同 ppoll：推进网络栈，让 socket 监视项有机会就绪
+        crate::net::socket::poll_network_and_dispatch();
+
+        let ready = ep.collect(maxevents as usize);
+        if !ready.is_empty() {
+            for (i, event) in ready.iter().enumerate() {
+                if copy_to_user(events.wrapping_add(i), *event).is_err() {
+                    return -(EFAULT as isize);
+                }
+            }
+            return ready.len() as isize;
+        }
+
+        if crate::ipc::signal_interrupts_syscall(&task) {
+            return -(EINTR as isize);
+        }
+
+        match waiter.wait(deadline) {
+            WaitResult::Ready => {}
+            WaitResult::TimedOut => return 0,
+            WaitResult::Interrupted => return -(EINTR as isize),
+        }
+    }
+}
@@ -1,8 +1,8 @@
 //! fcntl 系统调用实现
 
-use crate::arch::trap::SumGuard;
 use crate::kernel::{current_cpu, current_task};
-use crate::vfs::{FsError, OpenFlags, file_lock_manager};
+use crate::util::user_buffer::{copy_from_user, copy_to_user};
+use crate::vfs::{FileId, FsError, LockOwner, OpenFlags, file_lock_manager};
 use alloc::sync::Arc;
 use uapi::errno::EINVAL;
 use uapi::fcntl::{FcntlCmd, FdFlags, FileStatusFlags, Flock, LockType};
@@ -99,18 +99,23 @@ pub fn fcntl(fd: usize, cmd_raw: i32, arg: usize) -> isize {
         }
 
         // === 文件锁操作 ===
-        FcntlCmd::GetLk => {
-            // F_GETLK: 测试锁
+        FcntlCmd::GetLk | FcntlCmd::OfdGetLk => {
+            // F_GETLK / F_OFD_GETLK: 测试锁
             let flock_ptr = arg as *mut Flock;
             if flock_ptr.is_null() {
                 return -(EINVAL as isize);
             }
 
             // 读取用户空间的 flock 结构
-            let mut flock = {
-                let _guard = SumGuard::new();
-                unsafe { core::ptr::read(flock_ptr) }
+            let mut flock = match copy_from_user(flock_ptr) {
+                Ok(f) => f,
+                Err(e) => return -(e as isize),
             };
+            // OFD 锁命令要求 l_pid 为 0
+            let ofd = matches!(cmd, FcntlCmd::OfdGetLk);
+            if ofd && flock.l_pid != 0 {
+                return -(EINVAL as isize);
+            }
 
             // 获取文件对象
             let file = match task.lock().fd_table.get(fd) {
@@ -118,7 +123,7 @@ pub fn fcntl(fd: usize, cmd_raw: i32, arg: usize) -> isize {
                 Err(e) => return e.to_errno(),
             };
 
-            // 获取 inode 元数据（需要设备号和 inode 号）
+            // 获取 inode 元数据（需要文件大小换算锁区间）
             let inode = match file.inode() {
                 Ok(i) => i,
                 Err(_) => {
@@ -131,9 +136,16 @@ pub fn fcntl(fd: usize, cmd_raw: i32, arg: usize) -> isize {
                 Ok(m) => m,
                 Err(e) => return e.to_errno(),
             };
+            let file_id = match FileId::of(&file) {
+                Ok(id) => id,
+                Err(_) => return FsError::InvalidArgument.to_errno(),
+            };
 
-            // 获取当前进程 PID
-            let pid = task.lock().pid as i32;
+            let owner = if ofd {
+                LockOwner::open_file(&file)
+            } else {
+                LockOwner::Process(task.lock().pid as i32)
+            };
 
             // 将相对偏移转换为绝对偏移
             let file_offset = file.offset();
@@ -144,40 +156,34 @@ pub fn fcntl(fd: usize, cmd_raw: i32, arg: usize) -> isize {
             };
 
             // 测试锁
-            // TODO: 获取真实设备号
-            // 当前使用设备号 0，在单一文件系统场景下 inode 号足够区分文件。
-            // 未来改进：
-            // 1. 在 FileSystem trait 中添加 dev_id() 方法返回设备号
-            // 2. 在挂载时为每个文件系统分配唯一的设备号
-            // 3. 通过 dentry -> mount_point -> fs 获取设备号
-            let dev = 0;
-            let ino = metadata.inode_no as u64;
-            if let Err(e) = file_lock_manager().test_lock(dev, ino, start, len, &mut flock, pid) {
+            if let Err(e) = file_lock_manager().test_lock(file_id, start, len, &mut flock, owner) {
                 return e.to_errno();
             }
 
             // 将结果写回用户空间
-            {
-                let _guard = SumGuard::new();
-                unsafe { core::ptr::write(flock_ptr, flock) };
+            match copy_to_user(flock_ptr, flock) {
+                Ok(()) => 0,
+                Err(e) => -(e as isize),
             }
-
-            0
         }
 
-        FcntlCmd::SetLk | FcntlCmd::SetLkW => {
-            // F_SETLK / F_SETLKW: 设置或释放锁
-            let blocking = matches!(cmd, FcntlCmd::SetLkW);
+        FcntlCmd::SetLk | FcntlCmd::SetLkW | FcntlCmd::OfdSetLk | FcntlCmd::OfdSetLkW => {
+            // F_SETLK / F_SETLKW / F_OFD_SETLK / F_OFD_SETLKW: 设置或释放锁
+            let blocking = matches!(cmd, FcntlCmd::SetLkW | FcntlCmd::OfdSetLkW);
+            let ofd = matches!(cmd, FcntlCmd::OfdSetLk | FcntlCmd::OfdSetLkW);
             let flock_ptr = arg as *const Flock;
             if flock_ptr.is_null() {
                 return -(EINVAL as isize);
             }
 
             // 读取用户空间的 flock 结构
-            let flock = {
-                let _guard = SumGuard::new();
-                unsafe { core::ptr::read(flock_ptr) }
+            let flock = match copy_from_user(flock_ptr) {
+                Ok(f) => f,
+                Err(e) => return -(e as isize),
             };
+            if ofd && flock.l_pid != 0 {
+                return -(EINVAL as isize);
+            }
 
             // 解析锁类型
             let lock_type = match LockType::from_raw(flock.l_type) {
@@ -191,6 +197,16 @@ pub fn fcntl(fd: usize, cmd_raw: i32, arg: usize) -> isize {
                 Err(e) => return e.to_errno(),
             };
 
+            // 读锁要求以读方式打开，写锁要求以写方式打开
+            let mode_ok = match lock_type {
+                LockType::Read => file.flags().readable(),
+                LockType::Write => file.flags().writable(),
+                LockType::Unlock => true,
+            };
+            if !mode_ok {
+                return FsError::BadFileDescriptor.to_errno();
+            }
+
             // 获取 inode
             let inode = match file.inode() {
                 Ok(i) => i,
@@ -204,6 +220,10 @@ pub fn fcntl(fd: usize, cmd_raw: i32, arg: usize) -> isize {
                 Ok(m) => m,
                 Err(e) => return e.to_errno(),
             };
+            let file_id = match FileId::of(&file) {
+                Ok(id) => id,
+                Err(_) => return FsError::InvalidArgument.to_errno(),
+            };
 
             // 转换为绝对偏移
             let file_offset = file.offset();
@@ -213,14 +233,15 @@ pub fn fcntl(fd: usize, cmd_raw: i32, arg: usize) -> isize {
                 Err(_) => return -(EINVAL as isize),
             };
 
-            // 获取当前进程 PID
-            let pid = task.lock().pid as i32;
+            // 锁的持有者：当前进程，或 OFD 锁的打开文件描述
+            let owner = if ofd {
+                LockOwner::open_file(&file)
+            } else {
+                LockOwner::Process(task.lock().pid as i32)
+            };
 
             // 设置锁
-            // TODO: 获取真实设备号（与上面 F_GETLK 的 TODO 相同）
-            let dev = 0;
-            let ino = metadata.inode_no as u64;
-            match file_lock_manager().set_lock(dev, ino, start, len, lock_type, pid, blocking) {
+            match file_lock_manager().set_lock(file_id, start, len, lock_type, owner, blocking) {
                 Ok(()) => 0,
                 Err(e) => e.to_errno(),
             }
@@ -6,21 +6,27 @@ use alloc::{string::ToString, sync::Arc};
 
 use crate::{
     arch::trap::SumGuard,
+    config::PAGE_SIZE,
     kernel::{
-        current_cpu, current_task,
+        Capabilities, capable, current_cpu, current_fs_cred, current_task,
         syscall::util::{
-            create_file_at, create_file_from_dentry, get_path_safe, resolve_at_path,
-            resolve_at_path_with_flags,
+            create_file_at, create_file_from_dentry, create_mode, create_tmpfile_at, get_path_safe,
+            init_new_inode, resolve_at_path, resolve_at_path_with, resolve_at_path_with_flags,
         },
     },
+    security::landlock,
     uapi::{
-        errno::{EACCES, EINVAL, ENOENT},
-        fs::{AtFlags, F_OK, FileSystemType, LinuxStatFs, R_OK, W_OK, X_OK},
+        errno::{E2BIG, EAGAIN, EINVAL, ENOENT},
+        fcntl::{OPEN_HOW_SIZE_LATEST, OPEN_HOW_SIZE_VER0, OpenHow, ResolveFlags},
+        fs::{AccessMode, AtFlags, F_OK, FileSystemType, LinuxStatFs},
         time::TimeSpec,
     },
+    util::user_buffer::{copy_from_user, copy_to_user},
     vfs::{
-        DENTRY_CACHE, Dentry, FileMode, FsError, InodeType, OpenFlags, RegFile, SeekWhence, Stat,
-        StatExt, Statx, StatxExt, split_path, vfs_lookup,
+        DENTRY_CACHE, Dentry, FdFlags, FdFlagsExt, File, FileMode, FsError, InodeType, InotifyFile,
+        OpenFlags, RegFile, ResolvePolicy, SeekWhence, Stat, StatExt, Statx, StatxExt,
+        fsnotify_create, fsnotify_delete, fsnotify_modify, fsnotify_move, inode_permission,
+        may_modify_dir, may_open, mnt_want_write, posix_acl_chmod, split_path, vfs_lookup,
     },
 };
 
@@ -31,12 +37,15 @@ pub const O_CLOEXEC: u32 = 0o2000000;
 
 pub fn close(fd: usize) -> isize {
     let task = current_task();
-    let mut task_lock = task.lock();
-    let tid = task_lock.tid as usize;
+    // 关闭时会释放文件锁（需要查询当前进程），不能持有任务锁
+    let (tid, fd_table) = {
+        let task_lock = task.lock();
+        (task_lock.tid as usize, task_lock.fd_table.clone())
+    };
 
     // If this fd is a socket, also remove the (tid, fd) -> socket handle mapping.
     // Otherwise, fd reuse can accidentally refer to a stale socket handle.
-    if let Ok(file) = task_lock.fd_table.get(fd) {
+    if let Ok(file) = fd_table.get(fd) {
         if file
             .as_any()
             .downcast_ref::<crate::net::socket::SocketFile>()
@@ -46,7 +55,7 @@ pub fn close(fd: usize) -> isize {
         }
     }
 
-    match task_lock.fd_table.close(fd) {
+    match fd_table.close(fd) {
         Ok(()) => 0,
         Err(e) => e.to_errno(),
     }
@@ -100,7 +109,12 @@ pub fn ftruncate(fd: usize, length: i64) -> isize {
     };
 
     match inode.truncate(new_size) {
-        Ok(()) => 0,
+        Ok(()) => {
+            if let Ok(dentry) = file.dentry() {
+                fsnotify_modify(&dentry);
+            }
+            0
+        }
         Err(e) => e.to_errno(),
     }
 }
@@ -126,14 +140,112 @@ pub fn openat(dirfd: i32, pathname: *const c_char, flags: u32, mode: u32) -> isi
 
     // crate::println!("[openat] path: {}, flags: {:?} (raw: 0x{:x})", path_str, open_flags, flags);
 
-    // 解析路径（处理AT_FDCWD和相对路径）
-    let dentry = match resolve_at_path(dirfd, &path_str) {
+    do_openat(
+        dirfd,
+        &path_str,
+        open_flags,
+        mode,
+        &ResolvePolicy::default(),
+    )
+}
+
+/// openat2 - 带扩展参数的 openat
+///
+/// `how` 指向大小为 `size` 的 [`OpenHow`]；比内核认识的版本更大时多出的字节必须全为 0。
+/// 与 openat 不同，未知的标志位、O_CREAT/O_TMPFILE 以外非零的 `mode` 都返回 EINVAL。
+pub fn openat2(dirfd: i32, pathname: *const c_char, how: *const OpenHow, size: usize) -> isize {
+    if size < OPEN_HOW_SIZE_VER0 {
+        return -(EINVAL as isize);
+    }
+    if size > PAGE_SIZE {
+        return -(E2BIG as isize);
+    }
+    // 更新版本的结构体：内核不认识的尾部必须为 0
+    let tail = (how as *const u8).wrapping_add(OPEN_HOW_SIZE_LATEST);
+    for i in 0..size - OPEN_HOW_SIZE_LATEST {
+        match copy_from_user(tail.wrapping_add(i)) {
+            Ok(0u8) => {}
+            Ok(_) => return -(E2BIG as isize),
+            Err(e) => return -(e as isize),
+        }
+    }
+    let how = match copy_from_user(how) {
+        Ok(h) => h,
+        Err(e) => return -(e as isize),
+    };
+
+    let open_flags = match u32::try_from(how.flags).ok().and_then(OpenFlags::from_bits) {
+        Some(f) => f,
+        None => return -(EINVAL as isize),
+    };
+    let creates =
+        open_flags.contains(OpenFlags::O_CREAT) || open_flags.contains(OpenFlags::O_TMPFILE);
+    if how.mode & !0o7777 != 0 || (how.mode != 0 && !creates) {
+        return -(EINVAL as isize);
+    }
+    let resolve = match ResolveFlags::from_bits(how.resolve) {
+        Some(r) => r,
+        None => return -(EINVAL as isize),
+    };
+    if resolve.contains(ResolveFlags::BENEATH | ResolveFlags::IN_ROOT) {
+        return -(EINVAL as isize);
+    }
+    // 没有无阻塞的 dcache 快速路径，RESOLVE_CACHED 总是要求调用者改用普通解析
+    if resolve.contains(ResolveFlags::CACHED) {
+        return -(EAGAIN as isize);
+    }
+
+    let _guard = SumGuard::new();
+    let path_str = match get_path_safe(pathname) {
+        Ok(s) => s.to_string(),
+        Err(_) => {
+            return FsError::InvalidArgument.to_errno();
+        }
+    };
+
+    do_openat(
+        dirfd,
+        &path_str,
+        open_flags,
+        how.mode as u32,
+        &ResolvePolicy::from_flags(resolve),
+    )
+}
+
+/// openat 与 openat2 的公共部分：按 `policy` 解析路径并打开（必要时创建）文件
+fn do_openat(
+    dirfd: i32,
+    path_str: &str,
+    open_flags: OpenFlags,
+    mode: u32,
+    policy: &ResolvePolicy,
+) -> isize {
+    // O_TMPFILE 的内部位必须与 O_DIRECTORY 一起出现，且只能以写方式打开，不能与 O_CREAT 同用
+    let tmpfile = open_flags.contains(OpenFlags::O_TMPFILE);
+    if open_flags.contains(OpenFlags::__O_TMPFILE)
+        && (!tmpfile || !open_flags.writable() || open_flags.contains(OpenFlags::O_CREAT))
+    {
+        return FsError::InvalidArgument.to_errno();
+    }
+
+    // 解析路径（处理AT_FDCWD和相对路径），O_TMPFILE 时在该目录中创建匿名文件
+    let resolved = if tmpfile {
+        create_tmpfile_at(dirfd, path_str, mode, policy).map(Some)
+    } else {
+        resolve_at_path_with(dirfd, path_str, policy)
+    };
+    let dentry = match resolved {
+        Ok(Some(d)) if tmpfile => d,
         Ok(Some(d)) => {
             // 文件已存在
             // 检查 O_EXCL (与 O_CREAT 一起使用时，文件必须不存在)
             if open_flags.contains(OpenFlags::O_CREAT) && open_flags.contains(OpenFlags::O_EXCL) {
                 return FsError::AlreadyExists.to_errno();
             }
+            // 按访问模式检查权限；新建的文件不检查
+            if let Err(e) = may_open(d.inode.as_ref(), &current_fs_cred(), open_flags) {
+                return e.to_errno();
+            }
             d
         }
         Ok(None) => {
@@ -143,7 +255,7 @@ pub fn openat(dirfd: i32, pathname: *const c_char, flags: u32, mode: u32) -> isi
             }
 
             // 创建新文件
-            match create_file_at(dirfd, &path_str, mode) {
+            match create_file_at(dirfd, path_str, mode, policy) {
                 Ok(d) => d,
                 Err(e) => return e.to_errno(),
             }
@@ -158,18 +270,34 @@ pub fn openat(dirfd: i32, pathname: *const c_char, flags: u32, mode: u32) -> isi
     };
 
     // 检查 O_DIRECTORY (必须是目录)
-    if open_flags.contains(OpenFlags::O_DIRECTORY) {
+    if open_flags.contains(OpenFlags::O_DIRECTORY) && !tmpfile {
         if meta.inode_type != InodeType::Directory {
             return FsError::NotDirectory.to_errno();
         }
     }
 
+    // Landlock 访问检查
+    if let Err(e) = landlock::file_open(&dentry, open_flags) {
+        return e.to_errno();
+    }
+
+    // 只读挂载上不能以写方式打开
+    let write_check = if open_flags.writable() {
+        mnt_want_write(&dentry)
+    } else {
+        Ok(())
+    };
+    if let Err(e) = write_check {
+        return e.to_errno();
+    }
+
     // 处理 O_TRUNC (截断文件)
     if open_flags.contains(OpenFlags::O_TRUNC) && open_flags.writable() {
         if meta.inode_type == InodeType::File {
             if let Err(e) = dentry.inode.truncate(0) {
                 return e.to_errno();
             }
+            fsnotify_modify(&dentry);
         }
     }
 
@@ -212,8 +340,23 @@ pub fn mkdirat(dirfd: i32, pathname: *const c_char, mode: u32) -> isize {
 
     // 创建目录
     let dir_mode = FileMode::from_bits_truncate(mode) | FileMode::S_IFDIR;
-    match parent_dentry.inode.mkdir(&dirname, dir_mode) {
-        Ok(_) => 0,
+    let (dir_mode, inherit) = match may_modify_dir(parent_dentry.inode.as_ref(), &current_fs_cred())
+        .and_then(|_| landlock::path_mknod(&parent_dentry, dir_mode))
+        .and_then(|_| mnt_want_write(&parent_dentry))
+        .and_then(|_| create_mode(&parent_dentry, dir_mode))
+    {
+        Ok(r) => r,
+        Err(e) => return e.to_errno(),
+    };
+    match parent_dentry
+        .inode
+        .mkdir(&dirname, dir_mode)
+        .and_then(|inode| init_new_inode(inode.as_ref(), dir_mode, &inherit))
+    {
+        Ok(()) => {
+            fsnotify_create(parent_dentry.inode.as_ref(), &dirname, true);
+            0
+        }
         Err(e) => e.to_errno(),
     }
 }
@@ -267,11 +410,24 @@ pub fn unlinkat(dirfd: i32, pathname: *const c_char, flags: u32) -> isize {
         }
     }
 
+    if let Err(e) = may_modify_dir(parent_dentry.inode.as_ref(), &current_fs_cred())
+        .and_then(|_| landlock::path_unlink(&parent_dentry, is_rmdir))
+        .and_then(|_| mnt_want_write(&parent_dentry))
+    {
+        return e.to_errno();
+    }
+
     // 删除目录项
     match parent_dentry.inode.unlink(&filename) {
         Ok(()) => {
             // 从缓存中移除
             parent_dentry.remove_child(&filename);
+            fsnotify_delete(
+                parent_dentry.inode.as_ref(),
+                &filename,
+                target_inode.as_ref(),
+                is_rmdir,
+            );
             0
         }
         Err(e) => e.to_errno(),
@@ -360,14 +516,10 @@ pub fn fstat(fd: usize, statbuf: *mut Stat) -> isize {
     let stat = crate::vfs::Stat::from_metadata(&metadata);
 
     // 写回用户空间
-    {
-        let _guard = SumGuard::new();
-        unsafe {
-            core::ptr::write(statbuf, stat);
-        }
+    match copy_to_user(statbuf, stat) {
+        Ok(()) => 0,
+        Err(e) => -(e as isize),
     }
-
-    0
 }
 
 pub fn getdents64(fd: usize, dirp: *mut u8, count: usize) -> isize {
@@ -484,13 +636,13 @@ pub fn statfs(path: *const c_char, buf: *mut LinuxStatFs) -> isize {
     };
 
     // 验证路径存在
-    if vfs_lookup(&path_str).is_err() {
-        return -(EINVAL as isize);
-    }
+    let dentry = match vfs_lookup(&path_str) {
+        Ok(d) => d,
+        Err(_) => return -(EINVAL as isize),
+    };
 
-    // 通过 MOUNT_TABLE 查找文件系统
-    use crate::vfs::MOUNT_TABLE;
-    let mount_point = match MOUNT_TABLE.find_mount(&path_str) {
+    // 在当前挂载命名空间中查找文件系统
+    let mount_point = match crate::vfs::current_mnt_ns().mount_of(&dentry) {
         Some(mp) => mp,
         None => return -(EINVAL as isize),
     };
@@ -523,14 +675,10 @@ pub fn statfs(path: *const c_char, buf: *mut LinuxStatFs) -> isize {
     };
 
     // 写回用户空间
-    {
-        let _guard = SumGuard::new();
-        unsafe {
-            core::ptr::write(buf, statfs_buf);
-        }
+    match copy_to_user(buf, statfs_buf) {
+        Ok(()) => 0,
+        Err(e) => -(e as isize),
     }
-
-    0
 }
 
 pub fn faccessat(dirfd: i32, pathname: *const c_char, mode: i32, flags: u32) -> isize {
@@ -556,58 +704,33 @@ pub fn faccessat(dirfd: i32, pathname: *const c_char, mode: i32, flags: u32) ->
         Err(e) => return e.to_errno(),
     };
 
-    // 获取文件元数据
-    let meta = match dentry.inode.metadata() {
-        Ok(m) => m,
-        Err(e) => return e.to_errno(),
-    };
-
     // F_OK 模式：仅检查文件是否存在
     if mode == F_OK {
         return 0;
     }
+    let Some(want) = AccessMode::from_bits(mode) else {
+        return -(EINVAL as isize);
+    };
 
-    // 检查权限
-    // TODO: 完整的权限检查需要考虑：
-    // - 进程的 uid/euid/gid/egid
-    // - 文件的 uid/gid/mode
-    // - Capabilities (CAP_DAC_OVERRIDE, CAP_DAC_READ_SEARCH)
-    // - AT_EACCESS 标志（使用有效 UID 而非实际 UID）
-    //
-    // 临时实现：简化的权限检查
-
-    // 简化版：只检查文件权限位，假设当前用户有权限
-    if (mode & R_OK) != 0 {
-        // 检查是否有任何读权限位
-        if !meta.mode.contains(FileMode::S_IRUSR)
-            && !meta.mode.contains(FileMode::S_IRGRP)
-            && !meta.mode.contains(FileMode::S_IROTH)
-        {
-            return -(EACCES as isize);
-        }
-    }
-
-    if (mode & W_OK) != 0 {
-        // 检查是否有任何写权限位
-        if !meta.mode.contains(FileMode::S_IWUSR)
-            && !meta.mode.contains(FileMode::S_IWGRP)
-            && !meta.mode.contains(FileMode::S_IWOTH)
-        {
-            return -(EACCES as isize);
+    // 默认以实际 UID/GID 检查；实际 UID 不是 root 时不带任何能力
+    let mut cred = current_fs_cred();
+    if !at_flags.contains(AtFlags::EACCESS) {
+        let task = current_task();
+        let task = task.lock();
+        let credential = &task.credential;
+        cred.fsuid = credential.uid;
+        cred.fsgid = credential.gid;
+        if credential.uid != 0 {
+            cred.dac_override = false;
+            cred.dac_read_search = false;
         }
     }
 
-    if (mode & X_OK) != 0 {
-        // 检查是否有任何执行权限位
-        if !meta.mode.contains(FileMode::S_IXUSR)
-            && !meta.mode.contains(FileMode::S_IXGRP)
-            && !meta.mode.contains(FileMode::S_IXOTH)
-        {
-            return -(EACCES as isize);
-        }
+    // 按模式位、访问 ACL 与能力检查
+    match inode_permission(dentry.inode.as_ref(), &cred, want) {
+        Ok(()) => 0,
+        Err(e) => e.to_errno(),
     }
-
-    0
 }
 
 pub fn readlinkat(dirfd: i32, pathname: *const c_char, buf: *mut u8, bufsiz: usize) -> isize {
@@ -706,14 +829,10 @@ pub fn newfstatat(dirfd: i32, pathname: *const c_char, statbuf: *mut Stat, flags
     let stat = Stat::from_metadata(&metadata);
 
     // 写回用户空间
-    {
-        let _guard = SumGuard::new();
-        unsafe {
-            core::ptr::write(statbuf, stat);
-        }
+    match copy_to_user(statbuf, stat) {
+        Ok(()) => 0,
+        Err(e) => -(e as isize),
     }
-
-    0
 }
 
 pub fn statx(
@@ -728,11 +847,13 @@ pub fn statx(
     }
 
     // 解析路径
-    let _guard = SumGuard::new();
-    let path_str = match get_path_safe(pathname) {
-        Ok(s) => s.to_string(),
-        Err(_) => {
-            return -(EINVAL as isize);
+    let path_str = {
+        let _guard = SumGuard::new();
+        match get_path_safe(pathname) {
+            Ok(s) => s.to_string(),
+            Err(_) => {
+                return -(EINVAL as isize);
+            }
         }
     };
 
@@ -755,11 +876,10 @@ pub fn statx(
         };
 
         let stx = crate::vfs::Statx::from_metadata(&metadata);
-        let _guard = SumGuard::new();
-        unsafe {
-            core::ptr::write(statxbuf, stx);
-        }
-        return 0;
+        return match copy_to_user(statxbuf, stx) {
+            Ok(()) => 0,
+            Err(e) => -(e as isize),
+        };
     }
 
     // 查找文件
@@ -777,21 +897,21 @@ pub fn statx(
     let stx = crate::vfs::Statx::from_metadata(&metadata);
 
     // 写回用户空间
-    let _guard = SumGuard::new();
-    unsafe {
-        core::ptr::write(statxbuf, stx);
+    match copy_to_user(statxbuf, stx) {
+        Ok(()) => 0,
+        Err(e) => -(e as isize),
     }
-
-    0
 }
 
 pub fn utimensat(dirfd: i32, pathname: *const c_char, times: *const TimeSpec, flags: u32) -> isize {
     // 解析路径
-    let _guard = SumGuard::new();
-    let path_str = match get_path_safe(pathname) {
-        Ok(s) => s.to_string(),
-        Err(_) => {
-            return -(EINVAL as isize);
+    let path_str = {
+        let _guard = SumGuard::new();
+        match get_path_safe(pathname) {
+            Ok(s) => s.to_string(),
+            Err(_) => {
+                return -(EINVAL as isize);
+            }
         }
     };
 
@@ -814,38 +934,38 @@ pub fn utimensat(dirfd: i32, pathname: *const c_char, times: *const TimeSpec, fl
         let now = crate::time_ext::timespec_now();
         (Some(now), Some(now))
     } else {
-        unsafe {
-            let _guard = SumGuard::new();
-            let user_times = core::slice::from_raw_parts(times, 2);
+        let user_times = match copy_from_user(times as *const [TimeSpec; 2]) {
+            Ok(t) => t,
+            Err(e) => return -(e as isize),
+        };
 
-            // 验证时间结构
-            if let Err(e) = user_times[0].validate() {
-                return -(e as isize);
-            }
-            if let Err(e) = user_times[1].validate() {
-                return -(e as isize);
-            }
+        // 验证时间结构
+        if let Err(e) = user_times[0].validate() {
+            return -(e as isize);
+        }
+        if let Err(e) = user_times[1].validate() {
+            return -(e as isize);
+        }
 
-            // 处理访问时间
-            let atime_opt = if user_times[0].is_omit() {
-                None // 不修改
-            } else if user_times[0].is_now() {
-                Some(crate::time_ext::timespec_now())
-            } else {
-                Some(user_times[0])
-            };
+        // 处理访问时间
+        let atime_opt = if user_times[0].is_omit() {
+            None // 不修改
+        } else if user_times[0].is_now() {
+            Some(crate::time_ext::timespec_now())
+        } else {
+            Some(user_times[0])
+        };
 
-            // 处理修改时间
-            let mtime_opt = if user_times[1].is_omit() {
-                None
-            } else if user_times[1].is_now() {
-                Some(crate::time_ext::timespec_now())
-            } else {
-                Some(user_times[1])
-            };
+        // 处理修改时间
+        let mtime_opt = if user_times[1].is_omit() {
+            None
+        } else if user_times[1].is_now() {
+            Some(crate::time_ext::timespec_now())
+        } else {
+            Some(user_times[1])
+        };
 
-            (atime_opt, mtime_opt)
-        }
+        (atime_opt, mtime_opt)
     };
 
     // 设置时间戳
@@ -939,11 +1059,27 @@ pub fn renameat2(
     }
 
     // 查找源文件(验证存在)
-    let _old_inode = match old_parent.inode.lookup(&old_name) {
+    let old_inode = match old_parent.inode.lookup(&old_name) {
         Ok(inode) => inode,
         Err(e) => return e.to_errno(),
     };
 
+    // Landlock：从旧目录移除、在新目录创建
+    let old_type = match old_inode.metadata() {
+        Ok(m) => m.inode_type,
+        Err(e) => return e.to_errno(),
+    };
+    let cred = current_fs_cred();
+    if let Err(e) = may_modify_dir(old_parent.inode.as_ref(), &cred)
+        .and_then(|_| may_modify_dir(new_parent.inode.as_ref(), &cred))
+        .and_then(|_| landlock::check_access(&old_parent, landlock::remove_access(old_type)))
+        .and_then(|_| landlock::check_access(&new_parent, landlock::make_access(old_type)))
+        .and_then(|_| mnt_want_write(&old_parent))
+        .and_then(|_| mnt_want_write(&new_parent))
+    {
+        return e.to_errno();
+    }
+
     // 处理不同的重命名标志
     if rename_flags.contains(RenameFlags::EXCHANGE) {
         // ⚠️ 非原子交换实现警告 ⚠️
@@ -969,7 +1105,7 @@ pub fn renameat2(
         );
 
         // 验证目标文件存在
-        let _new_inode = match new_parent.inode.lookup(&new_name) {
+        let new_inode = match new_parent.inode.lookup(&new_name) {
             Ok(inode) => inode,
             Err(e) => {
                 crate::pr_err!(
@@ -1149,6 +1285,26 @@ pub fn renameat2(
         old_parent.remove_child(&old_name);
         old_parent.remove_child(&temp_name);
         new_parent.remove_child(&new_name);
+
+        let new_is_dir = new_inode
+            .metadata()
+            .is_ok_and(|m| m.inode_type == InodeType::Directory);
+        fsnotify_move(
+            old_parent.inode.as_ref(),
+            &old_name,
+            new_parent.inode.as_ref(),
+            &new_name,
+            old_inode.as_ref(),
+            old_type == InodeType::Directory,
+        );
+        fsnotify_move(
+            new_parent.inode.as_ref(),
+            &new_name,
+            old_parent.inode.as_ref(),
+            &old_name,
+            new_inode.as_ref(),
+            new_is_dir,
+        );
     } else if rename_flags.contains(RenameFlags::NOREPLACE) {
         // 目标存在时失败
         if new_parent.inode.lookup(&new_name).is_ok() {
@@ -1165,6 +1321,14 @@ pub fn renameat2(
 
         // 更新 dentry 缓存
         old_parent.remove_child(&old_name);
+        fsnotify_move(
+            old_parent.inode.as_ref(),
+            &old_name,
+            new_parent.inode.as_ref(),
+            &new_name,
+            old_inode.as_ref(),
+            old_type == InodeType::Directory,
+        );
     } else if rename_flags.contains(RenameFlags::WHITEOUT) {
         // WHITEOUT 暂不支持(需要 Union FS 支持)
         return FsError::NotSupported.to_errno();
@@ -1180,6 +1344,14 @@ pub fn renameat2(
         // 更新 dentry 缓存
         old_parent.remove_child(&old_name);
         new_parent.remove_child(&new_name);
+        fsnotify_move(
+            old_parent.inode.as_ref(),
+            &old_name,
+            new_parent.inode.as_ref(),
+            &new_name,
+            old_inode.as_ref(),
+            old_type == InodeType::Directory,
+        );
     }
 
     0
@@ -1191,24 +1363,29 @@ pub fn renameat2(
 /// 40 (SYS_MOUNT)
 ///
 /// # 简化实现说明
-/// - 只支持 ext4 文件系统（忽略 filesystemtype 参数）
-/// - 使用第一个可用的块设备（忽略 source 参数）
-/// - 忽略所有 mountflags（但保留以保持 ABI 兼容）
-/// - 忽略 data 参数
+/// - 新挂载只支持 ext4、overlay 文件系统以及 proc/sys/tmp/dev/devpts 等特殊挂载点
+/// - overlay 的 data 为 `lowerdir=<dir>[,upperdir=<dir>]`，只支持一个下层，省略上层时使用新建的 tmpfs
+/// - 支持 MS_REMOUNT（修改挂载标志）、MS_BIND（绑定挂载，忽略 MS_REC）和 MS_MOVE
+/// - 挂载命名空间之间没有传播，MS_SHARED 等传播类型的修改直接返回成功
+/// - mountflags 中只有 MS_RDONLY/MS_NOSUID/MS_NODEV/MS_NOEXEC/MS_SYNCHRONOUS 会记录到挂载点
+/// - 其余文件系统忽略 data 参数
 pub fn mount(
     source: *const c_char,
     target: *const c_char,
     filesystemtype: *const c_char,
-    _mountflags: u64,
-    _data: *const core::ffi::c_void,
+    mountflags: u64,
+    data: *const core::ffi::c_void,
 ) -> isize {
     use crate::config::EXT4_BLOCK_SIZE;
     use crate::fs::ext4::Ext4FileSystem;
     use crate::fs::sysfs::find_block_device;
-    use crate::fs::{init_dev, init_procfs, init_sysfs, mount_tmpfs};
-    use crate::vfs::{MOUNT_TABLE, MountFlags as VfsMountFlags};
+    use crate::fs::{init_dev, init_procfs, init_sysfs, mount_devpts, mount_overlay, mount_tmpfs};
+    use crate::uapi::fs::SysMountFlags;
+    use crate::vfs::{MountFlags as VfsMountFlags, current_mnt_ns};
     use alloc::string::String;
 
+    let flags = SysMountFlags::from_bits_truncate(mountflags);
+
     // 启用用户空间内存访问
     let _guard = SumGuard::new();
 
@@ -1245,12 +1422,50 @@ pub fn mount(
     };
 
     crate::pr_debug!(
-        "[SYSCALL] mount: source='{}', target='{}', type='{}'",
+        "[SYSCALL] mount: source='{}', target='{}', type='{}', flags={:?}",
         source_str,
         target_str,
-        fstype_str
+        fstype_str,
+        flags
     );
 
+    fn vfs_mount_flags(flags: SysMountFlags) -> VfsMountFlags {
+        let mut vfs_flags = VfsMountFlags::empty();
+        vfs_flags.set(
+            VfsMountFlags::READ_ONLY,
+            flags.contains(SysMountFlags::MS_RDONLY),
+        );
+        vfs_flags.set(
+            VfsMountFlags::NO_SUID,
+            flags.contains(SysMountFlags::MS_NOSUID),
+        );
+        vfs_flags.set(
+            VfsMountFlags::NO_DEV,
+            flags.contains(SysMountFlags::MS_NODEV),
+        );
+        vfs_flags.set(
+            VfsMountFlags::NO_EXEC,
+            flags.contains(SysMountFlags::MS_NOEXEC),
+        );
+        vfs_flags.set(
+            VfsMountFlags::SYNC,
+            flags.contains(SysMountFlags::MS_SYNCHRONOUS),
+        );
+        vfs_flags
+    }
+
+    fn bind_mount(source: &str, target: &str) -> Result<(), FsError> {
+        let source_dentry = vfs_lookup(source)?;
+        let target_dentry = vfs_lookup(target)?;
+        let source_is_dir = source_dentry.inode.metadata()?.inode_type == InodeType::Directory;
+        let target_is_dir = target_dentry.inode.metadata()?.inode_type == InodeType::Directory;
+        match (source_is_dir, target_is_dir) {
+            (true, false) => Err(FsError::NotDirectory),
+            (false, true) => Err(FsError::IsDirectory),
+            _ => current_mnt_ns().bind(&source_dentry, target),
+        }
+    }
+
     fn ensure_dir_exists(path: &str) -> Result<(), FsError> {
         use crate::vfs::{FileMode, split_path, vfs_lookup};
 
@@ -1267,12 +1482,44 @@ pub fn mount(
                 let parent = vfs_lookup(&parent_path)?;
                 let dir_mode = FileMode::S_IFDIR | FileMode::from_bits_truncate(0o755);
                 parent.inode.mkdir(&name, dir_mode)?;
+                fsnotify_create(parent.inode.as_ref(), &name, true);
                 Ok(())
             }
             Err(e) => Err(e),
         }
     }
 
+    // 修改已有的挂载：重新挂载、绑定挂载和移动挂载
+    let result = if flags.contains(SysMountFlags::MS_REMOUNT) {
+        Some(current_mnt_ns().remount(&target_str, vfs_mount_flags(flags)))
+    } else if flags.contains(SysMountFlags::MS_BIND) {
+        Some(bind_mount(&source_str, &target_str))
+    } else if flags.contains(SysMountFlags::MS_MOVE) {
+        Some(
+            vfs_lookup(&target_str)
+                .and_then(|_| current_mnt_ns().move_mount(&source_str, &target_str)),
+        )
+    } else if flags.intersects(
+        SysMountFlags::MS_SHARED
+            | SysMountFlags::MS_PRIVATE
+            | SysMountFlags::MS_SLAVE
+            | SysMountFlags::MS_UNBINDABLE,
+    ) {
+        // 命名空间之间没有挂载传播，所有挂载本来就是私有的
+        Some(Ok(()))
+    } else {
+        None
+    };
+    if let Some(result) = result {
+        return match result {
+            Ok(()) => 0,
+            Err(e) => {
+                crate::pr_debug!("[SYSCALL] mount: failed: {:?}", e);
+                e.to_errno()
+            }
+        };
+    }
+
     // 特殊挂载点处理
     match target_str.as_str() {
         "/proc" => {
@@ -1319,6 +1566,42 @@ pub fn mount(
         _ => {}
     }
 
+    if fstype_str == "devpts" {
+        return match mount_devpts(&target_str) {
+            Ok(()) => 0,
+            Err(e) => e.to_errno(),
+        };
+    }
+
+    if fstype_str == "overlay" {
+        let data_str = if !data.is_null() {
+            match get_path_safe(data as *const c_char) {
+                Ok(s) => s.to_string(),
+                Err(_) => return FsError::InvalidArgument.to_errno(),
+            }
+        } else {
+            String::new()
+        };
+        let mut lower = None;
+        let mut upper = None;
+        for option in data_str.split(',') {
+            if let Some(dir) = option.strip_prefix("lowerdir=") {
+                lower = Some(dir);
+            } else if let Some(dir) = option.strip_prefix("upperdir=") {
+                upper = Some(dir);
+            }
+        }
+        // 只支持一个下层目录
+        let lower = match lower {
+            Some(dir) if !dir.contains(':') => dir,
+            _ => return -(EINVAL as isize),
+        };
+        return match mount_overlay(&target_str, lower, upper) {
+            Ok(()) => 0,
+            Err(e) => e.to_errno(),
+        };
+    }
+
     // 通用挂载逻辑 (目前只支持 ext4)
     if fstype_str == "ext4" {
         // 查找块设备
@@ -1351,10 +1634,10 @@ pub fn mount(
         };
 
         // 挂载文件系统
-        match MOUNT_TABLE.mount(
+        match current_mnt_ns().mount(
             ext4_fs,
             &target_str,
-            VfsMountFlags::empty(),
+            vfs_mount_flags(flags),
             Some(source_str),
         ) {
             Ok(()) => {
@@ -1387,9 +1670,9 @@ pub fn mount(
 /// # 简化实现说明
 /// - 忽略 flags 参数（但保留以保持 ABI 兼容）
 /// - 不检查文件是否被占用
-/// - 直接调用 MOUNT_TABLE.umount()
+/// - 只卸载当前挂载命名空间中的挂载点，其他命名空间仍包含它时文件系统保持挂载
 pub fn umount2(target: *const c_char, _flags: i32) -> isize {
-    use crate::vfs::MOUNT_TABLE;
+    use crate::vfs::current_mnt_ns;
 
     // 启用用户空间内存访问
     let _guard = SumGuard::new();
@@ -1406,8 +1689,8 @@ pub fn umount2(target: *const c_char, _flags: i32) -> isize {
 
     // 卸载文件系统
 
-    // 注意：MOUNT_TABLE.umount() 会自动调用 fs.sync()
-    match MOUNT_TABLE.umount(&target_str) {
+    // 注意：MountNamespace::umount() 会自动调用 fs.sync()
+    match current_mnt_ns().umount(&target_str) {
         Ok(()) => {
             crate::pr_debug!("[SYSCALL] umount2: successfully unmounted '{}'", target_str);
             0
@@ -1571,8 +1854,8 @@ pub fn fchmodat(dirfd: i32, pathname: *const c_char, mode: u32, flags: u32) -> i
         Err(e) => return e.to_errno(),
     };
 
-    // 调用 inode 的 chmod 方法
-    match dentry.inode.chmod(file_mode) {
+    // 修改模式并同步访问 ACL
+    match posix_acl_chmod(dentry.inode.as_ref(), file_mode) {
         Ok(()) => 0,
         Err(e) => e.to_errno(),
     }
@@ -1614,14 +1897,152 @@ pub fn mknodat(dirfd: i32, pathname: *const c_char, mode: u32, dev: u64) -> isiz
 
     // 构造文件模式
     let file_mode = FileMode::from_bits_truncate(mode);
+    let (file_mode, inherit) =
+        match may_modify_dir(parent_dentry.inode.as_ref(), &current_fs_cred())
+            .and_then(|_| landlock::path_mknod(&parent_dentry, file_mode))
+            .and_then(|_| mnt_want_write(&parent_dentry))
+            .and_then(|_| create_mode(&parent_dentry, file_mode))
+        {
+            Ok(r) => r,
+            Err(e) => return e.to_errno(),
+        };
 
     // 调用 inode.mknod()
-    match parent_dentry.inode.mknod(&filename, file_mode, dev) {
+    let created = parent_dentry
+        .inode
+        .mknod(&filename, file_mode, dev)
+        .and_then(|inode| init_new_inode(inode.as_ref(), file_mode, &inherit).map(|_| inode));
+    match created {
         Ok(child_inode) => {
             // 创建 dentry 并加入缓存
             let child_dentry = Dentry::new(filename.clone(), child_inode);
             parent_dentry.add_child(child_dentry.clone());
             DENTRY_CACHE.insert(&child_dentry);
+            fsnotify_create(parent_dentry.inode.as_ref(), &filename, false);
+            0
+        }
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// linkat - 创建硬链接
+///
+/// # 参数
+/// * `olddirfd` / `oldpath` - 源文件
+/// * `newdirfd` / `newpath` - 新的名字
+/// * `flags` - AT_SYMLINK_FOLLOW：跟随 `oldpath` 末尾的符号链接；
+///   AT_EMPTY_PATH：`oldpath` 为空时链接 `olddirfd` 指向的文件（需要 CAP_DAC_READ_SEARCH）
+///
+/// # 返回值
+/// * 0 - 成功
+/// * -EEXIST - `newpath` 已存在
+/// * -EPERM - 源文件是目录
+/// * -EXDEV - 源文件与新名字不在同一挂载上
+/// * -ENOENT - 源文件的链接数为 0（以 O_TMPFILE 且不带 O_EXCL 打开的除外）
+///
+/// # 注意
+/// 以 AT_EMPTY_PATH 链接 O_TMPFILE 创建的文件即可为它命名。
+pub fn linkat(
+    olddirfd: i32,
+    oldpath: *const c_char,
+    newdirfd: i32,
+    newpath: *const c_char,
+    flags: u32,
+) -> isize {
+    let at_flags = match AtFlags::from_bits(flags) {
+        Some(f) if (f - (AtFlags::SYMLINK_FOLLOW | AtFlags::EMPTY_PATH)).is_empty() => f,
+        _ => return FsError::InvalidArgument.to_errno(),
+    };
+
+    let _guard = SumGuard::new();
+    let old_path_str = match get_path_safe(oldpath) {
+        Ok(s) => s.to_string(),
+        Err(_) => return FsError::InvalidArgument.to_errno(),
+    };
+    let new_path_str = match get_path_safe(newpath) {
+        Ok(s) => s.to_string(),
+        Err(_) => return FsError::InvalidArgument.to_errno(),
+    };
+
+    // 查找源文件
+    let old_dentry = if old_path_str.is_empty() && at_flags.contains(AtFlags::EMPTY_PATH) {
+        if !capable(Capabilities::DAC_READ_SEARCH) {
+            return FsError::NotFound.to_errno();
+        }
+        let file = match current_task().lock().fd_table.get(olddirfd as usize) {
+            Ok(f) => f,
+            Err(e) => return e.to_errno(),
+        };
+        // 链接数为 0 的文件只有 O_TMPFILE 且不带 O_EXCL 打开时可以重新链接
+        let linkable = file.flags().contains(OpenFlags::O_TMPFILE)
+            && !file.flags().contains(OpenFlags::O_EXCL);
+        match file.dentry() {
+            Ok(d) => match d.inode.metadata() {
+                Ok(meta) if meta.nlinks == 0 && !linkable => return FsError::NotFound.to_errno(),
+                Ok(_) => d,
+                Err(e) => return e.to_errno(),
+            },
+            Err(e) => return e.to_errno(),
+        }
+    } else {
+        let follow = at_flags.contains(AtFlags::SYMLINK_FOLLOW);
+        match resolve_at_path_with_flags(olddirfd, &old_path_str, follow) {
+            Ok(d) => d,
+            Err(e) => return e.to_errno(),
+        }
+    };
+
+    let old_meta = match old_dentry.inode.metadata() {
+        Ok(m) => m,
+        Err(e) => return e.to_errno(),
+    };
+    if old_meta.inode_type == InodeType::Directory {
+        return FsError::NotPermitted.to_errno();
+    }
+
+    // 查找新名字所在的目录
+    let (new_dir_path, new_name) = match split_path(&new_path_str) {
+        Ok(p) => p,
+        Err(e) => return e.to_errno(),
+    };
+    let new_parent = match resolve_at_path(newdirfd, &new_dir_path) {
+        Ok(Some(d)) => d,
+        Ok(None) => return FsError::NotFound.to_errno(),
+        Err(e) => return e.to_errno(),
+    };
+    match new_parent.inode.metadata() {
+        Ok(m) if m.inode_type != InodeType::Directory => return FsError::NotDirectory.to_errno(),
+        Ok(_) => {}
+        Err(e) => return e.to_errno(),
+    }
+    if new_parent.inode.lookup(&new_name).is_ok() {
+        return FsError::AlreadyExists.to_errno();
+    }
+
+    let mnt_ns = crate::vfs::current_mnt_ns();
+    if let (Some(old_mnt), Some(new_mnt)) =
+        (mnt_ns.mount_of(&old_dentry), mnt_ns.mount_of(&new_parent))
+    {
+        if !Arc::ptr_eq(&old_mnt, &new_mnt) {
+            return FsError::CrossDevice.to_errno();
+        }
+    }
+
+    if let Err(e) = may_modify_dir(new_parent.inode.as_ref(), &current_fs_cred())
+        .and_then(|_| {
+            landlock::check_access(&new_parent, landlock::make_access(old_meta.inode_type))
+        })
+        .and_then(|_| mnt_want_write(&new_parent))
+    {
+        return e.to_errno();
+    }
+
+    match new_parent.inode.link(&new_name, &old_dentry.inode) {
+        Ok(()) => {
+            let new_dentry = Dentry::new(new_name.clone(), old_dentry.inode.clone());
+            new_parent.add_child(new_dentry.clone());
+            DENTRY_CACHE.insert(&new_dentry);
+            fsnotify_create(new_parent.inode.as_ref(), &new_name, false);
             0
         }
         Err(e) => e.to_errno(),
@@ -1672,6 +2093,15 @@ pub fn symlinkat(target: *const c_char, newdirfd: i32, linkpath: *const c_char)
         Err(e) => return e.to_errno(),
     };
 
+    if let Err(e) = may_modify_dir(parent_dentry.inode.as_ref(), &current_fs_cred())
+        .and_then(|_| {
+            landlock::check_access(&parent_dentry, landlock::make_access(InodeType::Symlink))
+        })
+        .and_then(|_| mnt_want_write(&parent_dentry))
+    {
+        return e.to_errno();
+    }
+
     // 创建符号链接
     match parent_dentry.inode.symlink(&link_name, &target_str) {
         Ok(symlink_inode) => {
@@ -1679,8 +2109,90 @@ pub fn symlinkat(target: *const c_char, newdirfd: i32, linkpath: *const c_char)
             let symlink_dentry = Dentry::new(link_name.clone(), symlink_inode);
             parent_dentry.add_child(symlink_dentry.clone());
             DENTRY_CACHE.insert(&symlink_dentry);
+            fsnotify_create(parent_dentry.inode.as_ref(), &link_name, false);
             0
         }
         Err(e) => e.to_errno(),
     }
 }
+
+/// inotify_init1 - 创建 inotify 实例
+///
+/// # 参数
+/// * `flags` - `IN_NONBLOCK` 与 `IN_CLOEXEC` 的组合
+///
+/// # 返回值
+/// * 成功返回实例的文件描述符
+/// * -EINVAL - flags 含有其它位
+pub fn inotify_init1(flags: u32) -> isize {
+    use uapi::inotify::{IN_CLOEXEC, IN_NONBLOCK};
+
+    if flags & !(IN_CLOEXEC | IN_NONBLOCK) != 0 {
+        return FsError::InvalidArgument.to_errno();
+    }
+    let open_flags = OpenFlags::from_bits_truncate(flags);
+    let file = Arc::new(InotifyFile::new(open_flags & OpenFlags::O_NONBLOCK));
+
+    let fd_table = current_task().lock().fd_table.clone();
+    match fd_table.alloc_with_flags(file as Arc<dyn File>, FdFlags::from_open_flags(open_flags)) {
+        Ok(fd) => fd as isize,
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// inotify_add_watch - 为 `pathname` 登记或修改监视
+///
+/// # 返回值
+/// * 成功返回监视描述符
+/// * -EBADF - fd 无效
+/// * -EINVAL - fd 不是 inotify 实例，或 mask 无效
+/// * -ENOTDIR - 给出 IN_ONLYDIR 而路径不是目录
+/// * -EEXIST - 给出 IN_MASK_CREATE 而路径已被监视
+pub fn inotify_add_watch(fd: usize, pathname: *const c_char, mask: u32) -> isize {
+    use uapi::inotify::InotifyMask;
+
+    let file = match current_task().lock().fd_table.get(fd) {
+        Ok(f) => f,
+        Err(e) => return e.to_errno(),
+    };
+    let Some(inotify) = file.as_any().downcast_ref::<InotifyFile>() else {
+        return FsError::InvalidArgument.to_errno();
+    };
+    let mask = InotifyMask::from_bits_retain(mask);
+
+    let _guard = SumGuard::new();
+    let path_str = match get_path_safe(pathname) {
+        Ok(s) => s.to_string(),
+        Err(_) => return FsError::InvalidArgument.to_errno(),
+    };
+    let follow = !mask.contains(InotifyMask::DONT_FOLLOW);
+    let dentry = match resolve_at_path_with_flags(AT_FDCWD, &path_str, follow) {
+        Ok(d) => d,
+        Err(e) => return e.to_errno(),
+    };
+
+    match inotify.add_watch(dentry.inode.clone(), mask) {
+        Ok(wd) => wd as isize,
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// inotify_rm_watch - 移除监视描述符 `wd`
+///
+/// # 返回值
+/// * 0 - 成功，实例中排入 IN_IGNORED 事件
+/// * -EBADF - fd 无效
+/// * -EINVAL - fd 不是 inotify 实例，或 wd 无效
+pub fn inotify_rm_watch(fd: usize, wd: i32) -> isize {
+    let file = match current_task().lock().fd_table.get(fd) {
+        Ok(f) => f,
+        Err(e) => return e.to_errno(),
+    };
+    let Some(inotify) = file.as_any().downcast_ref::<InotifyFile>() else {
+        return FsError::InvalidArgument.to_errno();
+    };
+    match inotify.rm_watch(wd) {
+        Ok(()) => 0,
+        Err(e) => e.to_errno(),
+    }
+}
@@ -2,11 +2,15 @@
 
 use crate::arch::trap::SumGuard;
 use crate::kernel::current_task;
-use crate::util::user_buffer::{validate_user_ptr, validate_user_ptr_mut};
-use crate::vfs::File;
+use crate::util::user_buffer::{
+    copy_from_user, copy_to_user, validate_user_ptr, validate_user_ptr_mut,
+};
+use crate::vfs::{File, PipeFile};
 use uapi::errno::EFAULT;
 use uapi::errno::EINVAL;
+use uapi::errno::ESPIPE;
 use uapi::iovec::IoVec;
+use uapi::splice::SPLICE_F_ALL;
 
 /// 向文件描述符写入数据
 /// # 参数
@@ -406,97 +410,114 @@ pub fn pwritev(fd: usize, iov: *const IoVec, iovcnt: usize, offset: i64) -> isiz
     total_written as isize
 }
 
+/// 读取用户提供的文件偏移量，空指针表示使用文件自身的偏移量
+fn read_user_offset(offset: *mut i64) -> Result<Option<usize>, isize> {
+    if offset.is_null() {
+        return Ok(None);
+    }
+    match copy_from_user(offset) {
+        Ok(off) if off < 0 => Err(-(EINVAL as isize)),
+        Ok(off) => Ok(Some(off as usize)),
+        Err(e) => Err(-(e as isize)),
+    }
+}
+
+/// 把前移后的偏移量写回用户空间
+fn write_user_offset(offset: *mut i64, pos: Option<usize>) -> Result<(), isize> {
+    match pos {
+        Some(pos) => copy_to_user(offset, pos as i64).map_err(|e| -(e as isize)),
+        None => Ok(()),
+    }
+}
+
 /// 零拷贝文件传输：从一个文件描述符传输数据到另一个
+///
+/// 数据经 [`File::splice_to`] 搬运：普通文件到 TCP socket 时直接交出文件页，
+/// 其余情况经内核缓冲区中转。
 /// # 参数
 /// - `out_fd`: 输出文件描述符
 /// - `in_fd`: 输入文件描述符
-/// - `offset`: 输入文件偏移量指针（如果非空，从该位置读取并更新）
+/// - `offset`: 输入文件偏移量指针（如果非空，从该位置读取并更新，不改变文件偏移量）
 /// - `count`: 要传输的字节数
 pub fn sendfile(out_fd: usize, in_fd: usize, offset: *mut i64, count: usize) -> isize {
-    let task = current_task();
-
-    let in_file = match task.lock().fd_table.get(in_fd) {
-        Ok(f) => f,
-        Err(e) => return e.to_errno(),
+    let (in_file, out_file) = {
+        let task = current_task();
+        let task = task.lock();
+        match (task.fd_table.get(in_fd), task.fd_table.get(out_fd)) {
+            (Ok(in_file), Ok(out_file)) => (in_file, out_file),
+            (Err(e), _) | (_, Err(e)) => return e.to_errno(),
+        }
     };
 
-    let out_file = match task.lock().fd_table.get(out_fd) {
-        Ok(f) => f,
-        Err(e) => return e.to_errno(),
+    let mut pos = match read_user_offset(offset) {
+        Ok(pos) => pos,
+        Err(e) => return e,
     };
+    if pos.is_some() && in_file.as_any().is::<PipeFile>() {
+        return -(ESPIPE as isize);
+    }
 
-    // 如果 offset 非空，使用 pread；否则使用 read
-    let use_offset = !offset.is_null();
-    let mut current_offset = if use_offset {
-        let off = {
-            let _guard = SumGuard::new();
-            unsafe { *offset }
-        };
-        if off < 0 {
-            return -(EINVAL as isize);
+    let result = in_file.splice_to(pos.as_mut(), out_file.as_ref(), None, count);
+    if let Err(e) = write_user_offset(offset, pos) {
+        return e;
+    }
+    match result {
+        Ok(n) => n as isize,
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// 在管道与文件描述符之间搬运数据
+///
+/// 至少一端必须是管道，管道端不能指定偏移量。`flags` 只作提示：管道读写本身不阻塞，
+/// `SPLICE_F_NONBLOCK` 不改变行为。
+/// # 参数
+/// - `fd_in` / `off_in`: 输入文件描述符与偏移量指针（非空时从该位置读取并更新）
+/// - `fd_out` / `off_out`: 输出文件描述符与偏移量指针（非空时写到该位置并更新）
+/// - `len`: 要搬运的字节数
+/// - `flags`: `SPLICE_F_*`
+pub fn splice(
+    fd_in: usize,
+    off_in: *mut i64,
+    fd_out: usize,
+    off_out: *mut i64,
+    len: usize,
+    flags: u32,
+) -> isize {
+    if flags & !SPLICE_F_ALL != 0 {
+        return -(EINVAL as isize);
+    }
+    let (in_file, out_file) = {
+        let task = current_task();
+        let task = task.lock();
+        match (task.fd_table.get(fd_in), task.fd_table.get(fd_out)) {
+            (Ok(in_file), Ok(out_file)) => (in_file, out_file),
+            (Err(e), _) | (_, Err(e)) => return e.to_errno(),
         }
-        off as usize
-    } else {
-        0
     };
 
-    // 使用 8KB 缓冲区进行传输
-    const BUFFER_SIZE: usize = 8192;
-    let mut buffer = [0u8; BUFFER_SIZE];
-    let mut total_sent = 0usize;
-    let mut remaining = count;
-
-    while remaining > 0 {
-        let to_read = core::cmp::min(remaining, BUFFER_SIZE);
-
-        // 读取数据
-        let read_result = if use_offset {
-            in_file.read_at(current_offset, &mut buffer[..to_read])
-        } else {
-            in_file.read(&mut buffer[..to_read])
-        };
+    let in_pipe = in_file.as_any().is::<PipeFile>();
+    let out_pipe = out_file.as_any().is::<PipeFile>();
+    if !in_pipe && !out_pipe {
+        return -(EINVAL as isize);
+    }
+    if (in_pipe && !off_in.is_null()) || (out_pipe && !off_out.is_null()) {
+        return -(ESPIPE as isize);
+    }
 
-        let n_read = match read_result {
-            Ok(0) => break, // EOF
-            Ok(n) => n,
-            Err(e) => {
-                return if total_sent > 0 {
-                    total_sent as isize
-                } else {
-                    e.to_errno()
-                };
-            }
-        };
+    let (mut in_pos, mut out_pos) = match (read_user_offset(off_in), read_user_offset(off_out)) {
+        (Ok(in_pos), Ok(out_pos)) => (in_pos, out_pos),
+        (Err(e), _) | (_, Err(e)) => return e,
+    };
 
-        // 写入数据
-        match out_file.write(&buffer[..n_read]) {
-            Ok(n_written) => {
-                total_sent += n_written;
-                if use_offset {
-                    current_offset += n_written;
-                }
-                remaining -= n_written;
-                if n_written < n_read {
-                    break; // 输出端写不完
-                }
-            }
-            Err(e) => {
-                return if total_sent > 0 {
-                    total_sent as isize
-                } else {
-                    e.to_errno()
-                };
-            }
-        }
+    let result = in_file.splice_to(in_pos.as_mut(), out_file.as_ref(), out_pos.as_mut(), len);
+    if let Err(e) = write_user_offset(off_in, in_pos).and(write_user_offset(off_out, out_pos)) {
+        return e;
     }
-
-    // 更新 offset 指针
-    if use_offset {
-        let _guard = SumGuard::new();
-        unsafe { *offset = current_offset as i64 };
+    match result {
+        Ok(n) => n as isize,
+        Err(e) => e.to_errno(),
     }
-
-    total_sent as isize
 }
 
 /// pollfd 结构体
@@ -515,22 +536,58 @@ pub const POLLERR: i16 = 0x0008;
 pub const POLLHUP: i16 = 0x0010;
 pub const POLLNVAL: i16 = 0x0020;
 
-use crate::kernel::scheduler::WaitQueue;
-use crate::sync::SpinLock;
-use lazy_static::lazy_static;
+use crate::kernel::hrtimer::Ktime;
+use crate::sync::{WaitOptions, WaitQueue, WaitResult};
+use crate::vfs::PollWaker;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use uapi::epoll::EpollEvents;
 
-lazy_static! {
-    static ref POLL_WAIT_QUEUE: SpinLock<WaitQueue> = SpinLock::new(WaitQueue::new());
+/// 唤醒登记在通用就绪队列上的 poll/select/epoll 等待者
+///
+/// 没有自己就绪队列的文件（见 [`crate::vfs::GENERIC_POLL_QUEUE`]）状态改变时调用。
+pub fn wake_poll_waiters() {
+    crate::vfs::wake_generic_pollers();
 }
 
-/// Wake up all tasks waiting in poll
-pub fn wake_poll_waiters() {
-    POLL_WAIT_QUEUE.lock().wake_up_all();
+/// 一次 poll/select/epoll_wait 调用的等待者
+///
+/// 登记到每个被等待文件的就绪队列，任一文件状态改变时置位并唤醒调用者，
+/// 调用者醒来后重新查询所有文件。置位在等待之前发生时等待立即返回，不会丢失唤醒。
+pub(super) struct PollWaiter {
+    woken: AtomicBool,
+    queue: WaitQueue,
+}
+
+impl PollWaiter {
+    pub(super) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            woken: AtomicBool::new(false),
+            queue: WaitQueue::new(),
+        })
+    }
+
+    /// 等待被唤醒，`deadline` 为单调时钟的到期时间（纳秒）
+    pub(super) fn wait(&self, deadline: Option<Ktime>) -> WaitResult {
+        let mut options = WaitOptions::new().interruptible();
+        if let Some(deadline) = deadline {
+            options = options.deadline(deadline);
+        }
+        self.queue
+            .wait_event_with(options, || self.woken.swap(false, Ordering::AcqRel))
+    }
+}
+
+impl PollWaker for PollWaiter {
+    fn wake(&self, _events: EpollEvents) {
+        self.woken.store(true, Ordering::Release);
+        self.queue.wake_all();
+    }
 }
 
 fn poll_with_timeout(fds: usize, nfds: usize, timeout: Option<uapi::time::TimeSpec>) -> isize {
-    use crate::arch::timer::{clock_freq, get_time};
     use crate::arch::trap::SumGuard;
+    use crate::kernel::hrtimer::{ktime_get, timespec_to_ktime};
     use uapi::errno::{EINTR, EINVAL};
 
     if nfds > 0 && fds == 0 {
@@ -539,18 +596,20 @@ fn poll_with_timeout(fds: usize, nfds: usize, timeout: Option<uapi::time::TimeSp
 
     let task = current_task();
 
-    let timeout_trigger = match timeout {
+    // 超时的到期时间（单调时钟，纳秒）
+    let deadline = match timeout {
         None => None,
         Some(ts) => {
             if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec >= 1_000_000_000 {
                 return -(EINVAL as isize);
             }
-            let duration_ns = (ts.tv_sec as u64 * 1_000_000_000) + ts.tv_nsec as u64;
-            let duration_ticks = (duration_ns * clock_freq() as u64 / 1_000_000_000) as usize;
-            Some(get_time() + duration_ticks)
+            Some(ktime_get().saturating_add(timespec_to_ktime(&ts)))
         }
     };
 
+    let waiter = PollWaiter::new();
+    let waker: Arc<dyn PollWaker> = waiter.clone();
+
     loop {
         // 关键：在阻塞等待前主动推进网络栈，并分发 UDP 到每个 fd 的队列，避免“永远等不到”
         crate::net::socket::poll_network_and_dispatch();
@@ -577,13 +636,9 @@ fn poll_with_timeout(fds: usize, nfds: usize, timeout: Option<uapi::time::TimeSp
                     }
                 };
 
-                if (pollfd.events & POLLIN) != 0 && file.readable() {
-                    pollfd.revents |= POLLIN;
-                }
-
-                if (pollfd.events & POLLOUT) != 0 && file.writable() {
-                    pollfd.revents |= POLLOUT;
-                }
+                // 查询的同时登记等待者；POLLERR/POLLHUP 不论是否请求都报告
+                let events = file.poll(Some(&waker)).bits() as i16;
+                pollfd.revents = events & (pollfd.events | POLLERR | POLLHUP);
 
                 if pollfd.revents != 0 {
                     ready_count += 1;
@@ -599,40 +654,16 @@ fn poll_with_timeout(fds: usize, nfds: usize, timeout: Option<uapi::time::TimeSp
             return -(EINTR as isize);
         }
 
-        if let Some(trigger) = timeout_trigger {
-            use crate::kernel::timer::TIMER_QUEUE;
-            let mut timer_q = TIMER_QUEUE.lock();
-            timer_q.push(trigger, task.clone());
-            drop(timer_q);
-        }
-
-        POLL_WAIT_QUEUE.lock().sleep(task.clone());
-        crate::kernel::schedule();
-
-        if timeout_trigger.is_some() {
-            use crate::kernel::timer::TIMER_QUEUE;
-            TIMER_QUEUE.lock().remove_task(&task);
-        }
-
-        if crate::ipc::signal_interrupts_syscall(&task) {
-            return -(EINTR as isize);
-        }
-
-        // 被唤醒后再推进一次网络栈，并把“刚到的数据包”分发成 socket 可读事件
-        crate::net::socket::poll_network_and_dispatch();
-
-        // Check if woken by timeout
-        if let Some(trigger) = timeout_trigger {
-            if get_time() >= trigger {
-                return 0;
-            }
+        match waiter.wait(deadline) {
+            WaitResult::Ready => {}
+            WaitResult::TimedOut => return 0,
+            WaitResult::Interrupted => return -(EINTR as isize),
         }
     }
 }
 
 /// ppoll - poll 的变体，支持信号掩码
 pub fn ppoll(fds: usize, nfds: usize, timeout: usize, _sigmask: usize) -> isize {
-    use crate::arch::trap::SumGuard;
     use uapi::errno::EINVAL;
 
     if nfds > 0 && fds == 0 {
@@ -642,15 +673,14 @@ pub fn ppoll(fds: usize, nfds: usize, timeout: usize, _sigmask: usize) -> isize
     let timeout_spec = if timeout == 0 {
         None
     } else {
-        let _guard = SumGuard::new();
-        unsafe {
-            let timespec = timeout as *const uapi::time::TimeSpec;
-            let ts = *timespec;
-            if ts.tv_nsec < 0 || ts.tv_nsec >= 1_000_000_000 {
-                return -(EINVAL as isize);
-            }
-            Some(ts)
+        let ts = match copy_from_user(timeout as *const uapi::time::TimeSpec) {
+            Ok(ts) => ts,
+            Err(e) => return -(e as isize),
+        };
+        if ts.tv_nsec < 0 || ts.tv_nsec >= 1_000_000_000 {
+            return -(EINVAL as isize);
         }
+        Some(ts)
     };
 
     poll_with_timeout(fds, nfds, timeout_spec)
@@ -666,7 +696,6 @@ pub fn pselect6(
     timeout: usize,
     _sigmask: usize,
 ) -> isize {
-    use crate::arch::trap::SumGuard;
     use uapi::errno::EINVAL;
     use uapi::time::TimeSpec;
 
@@ -674,19 +703,18 @@ pub fn pselect6(
     let timeout_trigger = if timeout == 0 {
         None // Infinite timeout
     } else {
-        let _guard = SumGuard::new();
-        unsafe {
-            let ts = &*(timeout as *const TimeSpec);
-            if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec >= 1_000_000_000 {
-                return -(EINVAL as isize);
-            }
-            if ts.is_zero() {
-                Some(0) // Poll mode (no wait)
-            } else {
-                use crate::arch::timer::{clock_freq, get_time};
-                let duration_ticks = ts.into_freq(clock_freq());
-                Some(get_time() + duration_ticks)
-            }
+        let ts = match copy_from_user(timeout as *const TimeSpec) {
+            Ok(ts) => ts,
+            Err(e) => return -(e as isize),
+        };
+        if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec >= 1_000_000_000 {
+            return -(EINVAL as isize);
+        }
+        if ts.is_zero() {
+            Some(0) // Poll mode (no wait)
+        } else {
+            use crate::kernel::hrtimer::{ktime_get, timespec_to_ktime};
+            Some(ktime_get().saturating_add(timespec_to_ktime(&ts)))
         }
     };
 
@@ -702,7 +730,6 @@ pub fn select(
     exceptfds: usize,
     timeout: usize,
 ) -> isize {
-    use crate::arch::trap::SumGuard;
     use uapi::errno::EINVAL;
     use uapi::time::timeval;
 
@@ -710,33 +737,41 @@ pub fn select(
     let timeout_trigger = if timeout == 0 {
         None // Infinite timeout
     } else {
-        let _guard = SumGuard::new();
-        unsafe {
-            let tv = &*(timeout as *const timeval);
-            if tv.tv_sec < 0 || tv.tv_usec < 0 || tv.tv_usec >= 1_000_000 {
-                return -(EINVAL as isize);
-            }
-            if tv.is_zero() {
-                Some(0) // Poll mode (no wait)
-            } else {
-                use crate::arch::timer::{clock_freq, get_time};
-                let duration_ticks = tv.into_freq(clock_freq());
-                Some(get_time() + duration_ticks)
-            }
+        let tv = match copy_from_user(timeout as *const timeval) {
+            Ok(tv) => tv,
+            Err(e) => return -(e as isize),
+        };
+        if tv.tv_sec < 0 || tv.tv_usec < 0 || tv.tv_usec >= 1_000_000 {
+            return -(EINVAL as isize);
+        }
+        if tv.is_zero() {
+            Some(0) // Poll mode (no wait)
+        } else {
+            use crate::kernel::hrtimer::{ktime_get, timespec_to_ktime};
+            Some(ktime_get().saturating_add(timespec_to_ktime(&tv.to_timespec())))
         }
     };
 
     select_common(nfds, readfds, writefds, exceptfds, timeout_trigger)
 }
 
+/// select 中算作可读的事件
+const SELECT_READ_EVENTS: EpollEvents = EpollEvents::IN
+    .union(EpollEvents::RDNORM)
+    .union(EpollEvents::HUP)
+    .union(EpollEvents::ERR);
+/// select 中算作可写的事件
+const SELECT_WRITE_EVENTS: EpollEvents = EpollEvents::OUT
+    .union(EpollEvents::WRNORM)
+    .union(EpollEvents::ERR);
+
 fn select_common(
     nfds: usize,
     readfds: usize,
     writefds: usize,
     exceptfds: usize,
-    timeout_trigger: Option<usize>,
+    timeout_trigger: Option<Ktime>,
 ) -> isize {
-    use crate::arch::trap::SumGuard;
     use crate::kernel::current_task;
     use uapi::errno::{EBADF, EINTR, EINVAL};
     use uapi::select::FdSet;
@@ -748,99 +783,98 @@ fn select_common(
     let task = current_task();
 
     // Copy input fd_sets once before loop
-    let (input_read, input_write, input_except) = {
-        let _guard = SumGuard::new();
-        (
-            if readfds != 0 {
-                Some(unsafe { *(readfds as *const FdSet) })
-            } else {
-                None
-            },
-            if writefds != 0 {
-                Some(unsafe { *(writefds as *const FdSet) })
-            } else {
-                None
-            },
-            if exceptfds != 0 {
-                Some(unsafe { *(exceptfds as *const FdSet) })
-            } else {
-                None
-            },
-        )
+    let copy_set = |ptr: usize| -> Result<Option<FdSet>, i32> {
+        if ptr == 0 {
+            Ok(None)
+        } else {
+            copy_from_user(ptr as *const FdSet).map(Some)
+        }
     };
+    let (input_read, input_write, input_except) =
+        match (copy_set(readfds), copy_set(writefds), copy_set(exceptfds)) {
+            (Ok(r), Ok(w), Ok(e)) => (r, w, e),
+            _ => return -(EFAULT as isize),
+        };
 
-    // Helper to check fds
-    let check_fds = || -> (isize, Option<FdSet>, Option<FdSet>, Option<FdSet>) {
-        let mut ready_count = 0;
-        let mut read_set = input_read.as_ref().map(|_| FdSet::new());
-        let mut write_set = input_write.as_ref().map(|_| FdSet::new());
-        let mut except_set = input_except.as_ref().map(|_| FdSet::new());
-
-        let task_lock = task.lock();
-        for fd in 0..nfds {
-            let check_read = input_read.as_ref().map_or(false, |s| s.is_set(fd));
-            let check_write = input_write.as_ref().map_or(false, |s| s.is_set(fd));
-            let check_except = input_except.as_ref().map_or(false, |s| s.is_set(fd));
-
-            if !check_read && !check_write && !check_except {
-                continue;
-            }
-
-            let file = match task_lock.fd_table.get(fd) {
-                Ok(f) => f,
-                Err(_) => {
-                    crate::pr_warn!(
-                        "select: EBADF tid={}, fd={}, check_read={}, check_write={}, check_except={}",
-                        task_lock.tid,
-                        fd,
-                        check_read,
-                        check_write,
-                        check_except
-                    );
-                    return (-(EBADF as isize), None, None, None);
+    // Helper to check fds, registering `waker` on every polled file
+    let check_fds =
+        |waker: &Arc<dyn PollWaker>| -> (isize, Option<FdSet>, Option<FdSet>, Option<FdSet>) {
+            let mut ready_count = 0;
+            let mut read_set = input_read.as_ref().map(|_| FdSet::new());
+            let mut write_set = input_write.as_ref().map(|_| FdSet::new());
+            let mut except_set = input_except.as_ref().map(|_| FdSet::new());
+
+            let task_lock = task.lock();
+            for fd in 0..nfds {
+                let check_read = input_read.as_ref().map_or(false, |s| s.is_set(fd));
+                let check_write = input_write.as_ref().map_or(false, |s| s.is_set(fd));
+                let check_except = input_except.as_ref().map_or(false, |s| s.is_set(fd));
+
+                if !check_read && !check_write && !check_except {
+                    continue;
                 }
-            };
 
-            let mut fd_ready = false;
-            if check_read && file.readable() {
-                if let Some(ref mut set) = read_set {
-                    set.set(fd);
-                    fd_ready = true;
+                let file = match task_lock.fd_table.get(fd) {
+                    Ok(f) => f,
+                    Err(_) => {
+                        crate::pr_warn!(
+                            "select: EBADF tid={}, fd={}, check_read={}, check_write={}, check_except={}",
+                            task_lock.tid,
+                            fd,
+                            check_read,
+                            check_write,
+                            check_except
+                        );
+                        return (-(EBADF as isize), None, None, None);
+                    }
+                };
+
+                // 与 Linux 相同：挂断与错误算作可读，错误算作可写
+                let events = file.poll(Some(waker));
+                let mut fd_ready = false;
+                if check_read && events.intersects(SELECT_READ_EVENTS) {
+                    if let Some(ref mut set) = read_set {
+                        set.set(fd);
+                        fd_ready = true;
+                    }
                 }
-            }
-            if check_write && file.writable() {
-                if let Some(ref mut set) = write_set {
-                    set.set(fd);
-                    fd_ready = true;
+                if check_write && events.intersects(SELECT_WRITE_EVENTS) {
+                    if let Some(ref mut set) = write_set {
+                        set.set(fd);
+                        fd_ready = true;
+                    }
+                }
+                // exceptfds: OOB data, errors (not implemented yet)
+                if fd_ready {
+                    ready_count += 1;
                 }
             }
-            // exceptfds: OOB data, errors (not implemented yet)
-            if fd_ready {
-                ready_count += 1;
-            }
-        }
-        (ready_count, read_set, write_set, except_set)
-    };
+            (ready_count, read_set, write_set, except_set)
+        };
+
+    let waiter = PollWaiter::new();
+    let waker: Arc<dyn PollWaker> = waiter.clone();
 
     loop {
         // 关键：在阻塞等待前主动推进网络栈（同 ppoll），并分发 UDP
         crate::net::socket::poll_network_and_dispatch();
 
-        let (ready_count, read_set, write_set, except_set) = check_fds();
+        let (ready_count, read_set, write_set, except_set) = check_fds(&waker);
         if ready_count < 0 {
             return ready_count;
         } // EBADF
 
         if ready_count > 0 {
-            let _guard = SumGuard::new();
-            if let Some(set) = read_set {
-                unsafe { *(readfds as *mut FdSet) = set };
-            }
-            if let Some(set) = write_set {
-                unsafe { *(writefds as *mut FdSet) = set };
-            }
-            if let Some(set) = except_set {
-                unsafe { *(exceptfds as *mut FdSet) = set };
+            for (ptr, set) in [
+                (readfds, read_set),
+                (writefds, write_set),
+                (exceptfds, except_set),
+            ] {
+                if let Some(set) = set {
+                    if let Err(e) = copy_to_user(ptr as *mut FdSet, set) {
+                        return -(e as isize);
+                    }
+                }
             }
             return ready_count;
         }
@@ -855,41 +889,11 @@ fn select_common(
             return 0;
         }
 
-        if let Some(trigger) = timeout_trigger {
-            use crate::kernel::timer::TIMER_QUEUE;
-            TIMER_QUEUE.lock().push(trigger, task.clone());
-        }
-
-        // Atomic check-and-sleep to prevent lost wakeup
-        let slept = {
-            let mut wq = POLL_WAIT_QUEUE.lock();
-            wq.sleep_if(task.clone(), || {
-                let (ready, _, _, _) = check_fds();
-                ready > 0
-            })
-        };
-
-        if slept {
-            crate::kernel::schedule();
-
-            if timeout_trigger.is_some() {
-                use crate::kernel::timer::TIMER_QUEUE;
-                TIMER_QUEUE.lock().remove_task(&task);
-            }
-
-            if crate::ipc::signal_interrupts_syscall(&task) {
-                return -(EINTR as isize);
-            }
-
-            // 被唤醒后再推进一次网络栈，并分发 UDP
-            crate::net::socket::poll_network_and_dispatch();
-
-            if let Some(trigger) = timeout_trigger {
-                use crate::arch::timer::get_time;
-                if get_time() >= trigger {
-                    return 0;
-                }
-            }
+        // 登记过的文件状态改变时被唤醒，醒来后重新检查
+        match waiter.wait(timeout_trigger) {
+            WaitResult::Ready => {}
+            WaitResult::TimedOut => return 0,
+            WaitResult::Interrupted => return -(EINTR as isize),
         }
     }
 }
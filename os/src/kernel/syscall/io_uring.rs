@@ -0,0 +1,1075 @@
+//! io_uring 系统调用实现
+//!
+//! `io_uring_setup` 创建一个实例并返回 fd。提交队列环（SQ）、完成队列环（CQ）与 SQE 数组
+//! 放在内核分配的页中，用户态以 `MAP_SHARED` 映射该 fd（偏移量为 `IORING_OFF_*`）后与内核
+//! 直接共享；SQ 环与 CQ 环共用一组页（`IORING_FEAT_SINGLE_MMAP`）。
+//!
+//! `io_uring_enter` 在提交者上下文中消费 SQE：解析文件，读入要写出的数据
+//! （`IORING_FEAT_SUBMIT_STABLE`），文件已就绪的请求当场执行。未就绪的请求登记到文件的
+//! 就绪队列上，就绪后交给内核工作队列执行；`IOSQE_ASYNC` 与 FSYNC 直接交给工作队列。
+//!
+//! 工作线程不在提交者的地址空间中，只在内核缓冲区上做文件 I/O。读到的数据要写回用户内存，
+//! ACCEPT 要在提交者的 fd 表中分配 fd，这两类工作留到提交者下一次进入 `io_uring_enter`
+//! 时完成，期间 SQ 环置 `IORING_SQ_TASKRUN` 提示用户态进入内核。
+//!
+//! 完成队列满时完成暂存在内核中（`IORING_FEAT_NODROP`），SQ 环置 `IORING_SQ_CQ_OVERFLOW`，
+//! 下一次 `io_uring_enter` 时写回。
+//!
+//! 不支持 SQPOLL/IOPOLL、链接请求与 `IOSQE_IO_DRAIN`。
+
+use alloc::collections::vec_deque::VecDeque;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::ptr;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use mm::address::{PageNum, UsizeConvert};
+use mm::{CachedPage, MmFile, MmInode, PageCache};
+use uapi::epoll::EpollEvents;
+use uapi::errno::{EBADF, EBUSY, EFAULT, EINTR, EINVAL, ENXIO, EOPNOTSUPP};
+use uapi::io_uring::*;
+use uapi::iovec::IoVec;
+
+use super::io::PollWaiter;
+use super::network::accept4;
+use super::util::flush_block_device_by_file;
+use crate::arch::constant::USER_TOP;
+use crate::arch::mm::paddr_to_vaddr;
+use crate::config::PAGE_SIZE;
+use crate::kernel::{GLOBAL_WORK_QUEUE, WorkItem, current_memory_space, current_task};
+use crate::mm::MemorySpace;
+use crate::sync::{SpinLock, WaitResult};
+use crate::util::user_buffer::{UserBuffer, copy_from_user, copy_to_user};
+use crate::vfs::{FdFlags, File, FsError, InodeMetadata, OpenFlags, PollQueue, PollWaker, RegFile};
+
+/// 提交队列容量上限
+const IORING_MAX_ENTRIES: u32 = 4096;
+
+/// 完成队列容量上限
+const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+
+/// READV/WRITEV 的 iovec 个数上限，也是可登记的缓冲区/文件个数上限
+const UIO_MAXIOV: usize = 1024;
+
+/// 单个读写请求的字节数上限，数据经内核缓冲区中转
+const MAX_RW_COUNT: usize = 1 << 20;
+
+// 环页中各字段的偏移量，SQ 环与 CQ 环共用一组页
+const SQ_HEAD: usize = 0;
+const SQ_TAIL: usize = 4;
+const SQ_RING_MASK: usize = 8;
+const SQ_RING_ENTRIES: usize = 12;
+const SQ_FLAGS: usize = 16;
+const SQ_DROPPED: usize = 20;
+const CQ_HEAD: usize = 24;
+const CQ_TAIL: usize = 28;
+const CQ_RING_MASK: usize = 32;
+const CQ_RING_ENTRIES: usize = 36;
+const CQ_OVERFLOW: usize = 40;
+const CQ_FLAGS: usize = 44;
+/// CQE 数组，其后是 SQ 的下标数组
+const CQES: usize = 64;
+
+/// 与用户态共享的一组内核页
+///
+/// 页放在一个不属于任何文件的页缓存中，映射 fd 时按共享文件映射的方式直接映射这些页。
+struct SharedPages {
+    cache: Arc<PageCache>,
+    pages: Vec<Arc<CachedPage>>,
+}
+
+impl SharedPages {
+    /// 分配至少 `size` 字节的清零页
+    fn new(size: usize) -> Result<Self, isize> {
+        let cache = Arc::new(PageCache::new());
+        let pages = (0..size.div_ceil(PAGE_SIZE))
+            .map(|index| cache.get_or_fill(index, |_| Ok(())))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { cache, pages })
+    }
+
+    /// 总字节数
+    fn len(&self) -> usize {
+        self.pages.len() * PAGE_SIZE
+    }
+
+    /// `off` 处的内核地址；调用者保证要访问的字段不跨页
+    fn ptr(&self, off: usize) -> *mut u8 {
+        let page = &self.pages[off / PAGE_SIZE];
+        (paddr_to_vaddr(page.ppn().start_addr().as_usize()) + off % PAGE_SIZE) as *mut u8
+    }
+
+    /// 与用户态并发访问的 32 位字段
+    fn atomic(&self, off: usize) -> &AtomicU32 {
+        // SAFETY: off 按 4 字节对齐，页在 self 存活期间有效
+        unsafe { &*(self.ptr(off) as *const AtomicU32) }
+    }
+
+    fn read<T: Copy>(&self, off: usize) -> T {
+        // SAFETY: 同 ptr；内容可能被用户态同时修改，只作为一次快照使用
+        unsafe { ptr::read_volatile(self.ptr(off) as *const T) }
+    }
+
+    fn write<T>(&self, off: usize, value: T) {
+        // SAFETY: 同 ptr
+        unsafe { ptr::write_volatile(self.ptr(off) as *mut T, value) }
+    }
+}
+
+/// 映射 io_uring fd 时的“文件”：页缓存就是共享页本身
+struct SharedMapping(Arc<PageCache>);
+
+impl MmInode for SharedMapping {
+    // 共享页在映射前已全部存在，不会经由 read_at/write_at 填充或写回
+    fn read_at(&self, _offset: usize, _buf: &mut [u8]) -> Result<usize, isize> {
+        Err(-(EINVAL as isize))
+    }
+
+    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize, isize> {
+        Err(-(EINVAL as isize))
+    }
+
+    fn page_cache(&self) -> Option<Arc<PageCache>> {
+        Some(self.0.clone())
+    }
+
+    /// 没有后备存储，只清除脏标记
+    fn writeback(&self) -> Result<(), isize> {
+        self.0.writeback(|_, _| Ok(()))
+    }
+}
+
+impl MmFile for SharedMapping {
+    fn inode(&self) -> Result<Arc<dyn MmInode>, isize> {
+        Ok(Arc::new(SharedMapping(self.0.clone())))
+    }
+}
+
+/// 已解析的请求
+struct Request {
+    user_data: u64,
+    op: Op,
+}
+
+enum Op {
+    Nop,
+    /// 读入 `len` 字节后依次写回 `dst` 中的用户缓冲区
+    Read {
+        file: Arc<dyn File>,
+        offset: Option<usize>,
+        len: usize,
+        dst: Vec<(usize, usize)>,
+    },
+    /// `data` 在提交时已从用户缓冲区读入
+    Write {
+        file: Arc<dyn File>,
+        offset: Option<usize>,
+        data: Vec<u8>,
+    },
+    Fsync {
+        file: Arc<dyn File>,
+    },
+    Accept {
+        file: Arc<dyn File>,
+        fd: i32,
+        addr: usize,
+        addrlen: usize,
+        flags: i32,
+    },
+}
+
+impl Op {
+    /// 请求等待的文件与就绪事件
+    fn waits_on(&self) -> Option<(&Arc<dyn File>, EpollEvents)> {
+        match self {
+            Op::Read { file, .. } | Op::Accept { file, .. } => Some((file, EpollEvents::IN)),
+            Op::Write { file, .. } => Some((file, EpollEvents::OUT)),
+            Op::Nop | Op::Fsync { .. } => None,
+        }
+    }
+}
+
+/// 执行一次请求的结果
+enum Outcome {
+    /// 已完成，结果可直接写入完成队列
+    Done(isize),
+    /// 读到的数据，需在提交者上下文中写回用户缓冲区
+    CopyOut(Vec<u8>),
+    /// 文件未就绪
+    NotReady,
+    /// 需在提交者上下文中执行
+    NeedTask,
+}
+
+/// 留给提交者上下文的工作
+enum TaskWork {
+    CopyOut(Request, Vec<u8>),
+    Retry(Request),
+}
+
+/// 普通文件总是就绪；其余文件按 poll 的结果判断
+fn is_ready(file: &Arc<dyn File>, events: EpollEvents) -> bool {
+    file.as_any().is::<RegFile>()
+        || file
+            .poll(None)
+            .intersects(events | EpollEvents::ERR | EpollEvents::HUP)
+}
+
+/// 执行请求，`in_task` 表示是否在提交者上下文中
+fn perform(op: &Op, in_task: bool) -> Outcome {
+    let result = match op {
+        Op::Nop => Ok(0),
+        Op::Read {
+            file, offset, len, ..
+        } => {
+            if !is_ready(file, EpollEvents::IN) {
+                return Outcome::NotReady;
+            }
+            let mut buf = vec![0u8; *len];
+            let result = match offset {
+                Some(off) => file.read_at(*off, &mut buf),
+                None => file.read(&mut buf),
+            };
+            match result {
+                Ok(n) => {
+                    buf.truncate(n);
+                    return Outcome::CopyOut(buf);
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Op::Write { file, offset, data } => {
+            if !is_ready(file, EpollEvents::OUT) {
+                return Outcome::NotReady;
+            }
+            match offset {
+                Some(off) => file.write_at(*off, data),
+                None => file.write(data),
+            }
+        }
+        Op::Fsync { file } => {
+            return Outcome::Done(flush_block_device_by_file(file).map_or_else(|e| e, |()| 0));
+        }
+        Op::Accept {
+            file,
+            fd,
+            addr,
+            addrlen,
+            flags,
+        } => {
+            if !is_ready(file, EpollEvents::IN) {
+                return Outcome::NotReady;
+            }
+            if !in_task {
+                return Outcome::NeedTask;
+            }
+            return Outcome::Done(accept4(*fd, *addr as *mut u8, *addrlen as *mut u32, *flags));
+        }
+    };
+    match result {
+        Ok(n) => Outcome::Done(n as isize),
+        Err(FsError::WouldBlock) => Outcome::NotReady,
+        Err(e) => Outcome::Done(e.to_errno()),
+    }
+}
+
+/// `[addr, addr + len)` 是否在用户地址空间内
+fn user_range_ok(addr: usize, len: usize) -> bool {
+    addr != 0 && matches!(addr.checked_add(len), Some(end) if end <= USER_TOP + 1)
+}
+
+/// 从用户态读入 `nr` 个 iovec，返回（地址, 长度）列表
+fn read_iovecs(iov: *const IoVec, nr: usize) -> Result<Vec<(usize, usize)>, isize> {
+    (0..nr)
+        .map(|i| {
+            copy_from_user(iov.wrapping_add(i))
+                .map(|v| (v.iov_base as usize, v.iov_len))
+                .map_err(|_| -(EFAULT as isize))
+        })
+        .collect()
+}
+
+/// 等待文件就绪的请求，就绪时交给工作队列
+struct ArmedRequest {
+    ring: Weak<IoUring>,
+    req: SpinLock<Option<Request>>,
+}
+
+impl PollWaker for ArmedRequest {
+    fn wake(&self, _events: EpollEvents) {
+        // 只触发一次，是否真的就绪由工作线程重新判断
+        let Some(req) = self.req.lock().take() else {
+            return;
+        };
+        if let Some(ring) = self.ring.upgrade() {
+            ring.disarm(self);
+            ring.queue_work(req);
+        }
+    }
+}
+
+struct RingState {
+    /// 完成队列满时暂存的完成
+    overflow: VecDeque<IoUringCqe>,
+    /// 留给提交者上下文的工作
+    task_work: VecDeque<TaskWork>,
+    /// 正在等待文件就绪的请求（就绪队列只持有弱引用）
+    armed: Vec<Arc<ArmedRequest>>,
+    /// IORING_REGISTER_FILES 登记的文件
+    files: Option<Vec<Option<Arc<dyn File>>>>,
+    /// IORING_REGISTER_BUFFERS 登记的缓冲区（地址, 长度）
+    buffers: Option<Vec<(usize, usize)>>,
+}
+
+/// 一个 io_uring 实例
+struct IoUring {
+    sq_entries: u32,
+    cq_entries: u32,
+    /// SQ 环与 CQ 环
+    rings: SharedPages,
+    /// SQE 数组
+    sqes: SharedPages,
+    /// 创建者的地址空间，提交与写回用户内存都在其中进行
+    owner: Weak<SpinLock<MemorySpace>>,
+    state: SpinLock<RingState>,
+    /// 有完成或待处理工作时唤醒等待者
+    poll_queue: PollQueue,
+}
+
+impl IoUring {
+    fn new(sq_entries: u32, cq_entries: u32) -> Result<Arc<Self>, isize> {
+        let rings = SharedPages::new(Self::sq_array_off(cq_entries) + sq_entries as usize * 4)?;
+        let sqes = SharedPages::new(sq_entries as usize * size_of::<IoUringSqe>())?;
+        rings.write(SQ_RING_MASK, sq_entries - 1);
+        rings.write(SQ_RING_ENTRIES, sq_entries);
+        rings.write(CQ_RING_MASK, cq_entries - 1);
+        rings.write(CQ_RING_ENTRIES, cq_entries);
+        Ok(Arc::new(Self {
+            sq_entries,
+            cq_entries,
+            rings,
+            sqes,
+            owner: Arc::downgrade(&current_memory_space()),
+            state: SpinLock::new(RingState {
+                overflow: VecDeque::new(),
+                task_work: VecDeque::new(),
+                armed: Vec::new(),
+                files: None,
+                buffers: None,
+            }),
+            poll_queue: PollQueue::new(),
+        }))
+    }
+
+    fn sq_array_off(cq_entries: u32) -> usize {
+        CQES + cq_entries as usize * size_of::<IoUringCqe>()
+    }
+
+    fn sq_offsets(&self) -> IoSqringOffsets {
+        IoSqringOffsets {
+            head: SQ_HEAD as u32,
+            tail: SQ_TAIL as u32,
+            ring_mask: SQ_RING_MASK as u32,
+            ring_entries: SQ_RING_ENTRIES as u32,
+            flags: SQ_FLAGS as u32,
+            dropped: SQ_DROPPED as u32,
+            array: Self::sq_array_off(self.cq_entries) as u32,
+            ..Default::default()
+        }
+    }
+
+    fn cq_offsets(&self) -> IoCqringOffsets {
+        IoCqringOffsets {
+            head: CQ_HEAD as u32,
+            tail: CQ_TAIL as u32,
+            ring_mask: CQ_RING_MASK as u32,
+            ring_entries: CQ_RING_ENTRIES as u32,
+            overflow: CQ_OVERFLOW as u32,
+            cqes: CQES as u32,
+            flags: CQ_FLAGS as u32,
+            ..Default::default()
+        }
+    }
+
+    /// 当前任务是否在创建者的地址空间中
+    fn owned_by_current(&self) -> bool {
+        ptr::eq(self.owner.as_ptr(), Arc::as_ptr(&current_memory_space()))
+    }
+
+    fn set_sq_flag(&self, flag: u32, on: bool) {
+        let flags = self.rings.atomic(SQ_FLAGS);
+        if on {
+            flags.fetch_or(flag, Ordering::Release);
+        } else {
+            flags.fetch_and(!flag, Ordering::Release);
+        }
+    }
+
+    /// 完成队列中尚未被用户态取走的完成数
+    fn cq_ready(&self) -> u32 {
+        let head = self.rings.atomic(CQ_HEAD).load(Ordering::Acquire);
+        let tail = self.rings.atomic(CQ_TAIL).load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    /// 写入一个完成，队列满时返回 false；调用者持有 state 锁
+    fn push_cqe(&self, cqe: IoUringCqe) -> bool {
+        let head = self.rings.atomic(CQ_HEAD).load(Ordering::Acquire);
+        let tail = self.rings.atomic(CQ_TAIL).load(Ordering::Relaxed);
+        if tail.wrapping_sub(head) >= self.cq_entries {
+            return false;
+        }
+        let index = (tail & (self.cq_entries - 1)) as usize;
+        self.rings
+            .write(CQES + index * size_of::<IoUringCqe>(), cqe);
+        self.rings
+            .atomic(CQ_TAIL)
+            .store(tail.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// 提交一个完成
+    fn complete(&self, user_data: u64, res: isize) {
+        let cqe = IoUringCqe {
+            user_data,
+            res: res as i32,
+            flags: 0,
+        };
+        {
+            let mut state = self.state.lock();
+            // 已有溢出时也排在后面，保持完成的顺序
+            if !state.overflow.is_empty() || !self.push_cqe(cqe) {
+                state.overflow.push_back(cqe);
+                self.set_sq_flag(IORING_SQ_CQ_OVERFLOW, true);
+            }
+        }
+        self.poll_queue.wake(EpollEvents::IN | EpollEvents::RDNORM);
+    }
+
+    /// 把暂存的完成写回完成队列
+    fn flush_overflow(&self) {
+        let mut state = self.state.lock();
+        while let Some(&cqe) = state.overflow.front() {
+            if !self.push_cqe(cqe) {
+                return;
+            }
+            state.overflow.pop_front();
+        }
+        self.set_sq_flag(IORING_SQ_CQ_OVERFLOW, false);
+    }
+
+    /// 取出下一个 SQE，跳过下标无效的项
+    fn next_sqe(&self) -> Option<IoUringSqe> {
+        // state 锁串行化并发的提交者
+        let _state = self.state.lock();
+        let sq_head = self.rings.atomic(SQ_HEAD);
+        loop {
+            let head = sq_head.load(Ordering::Relaxed);
+            if head == self.rings.atomic(SQ_TAIL).load(Ordering::Acquire) {
+                return None;
+            }
+            let slot = (head & (self.sq_entries - 1)) as usize;
+            let index: u32 = self
+                .rings
+                .read(Self::sq_array_off(self.cq_entries) + slot * 4);
+            sq_head.store(head.wrapping_add(1), Ordering::Release);
+            if index < self.sq_entries {
+                return Some(self.sqes.read(index as usize * size_of::<IoUringSqe>()));
+            }
+            self.rings
+                .atomic(SQ_DROPPED)
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 消费至多 `to_submit` 个 SQE，返回消费的个数
+    fn submit(self: &Arc<Self>, to_submit: u32) -> u32 {
+        let mut submitted = 0;
+        while submitted < to_submit {
+            let Some(sqe) = self.next_sqe() else {
+                break;
+            };
+            submitted += 1;
+            match self.prepare(&sqe) {
+                Ok(req) => self.issue(req, sqe.flags & IOSQE_ASYNC != 0),
+                Err(e) => self.complete(sqe.user_data, e),
+            }
+        }
+        submitted
+    }
+
+    /// 解析 SQE：取文件，校验缓冲区，读入要写出的数据
+    fn prepare(&self, sqe: &IoUringSqe) -> Result<Request, isize> {
+        if sqe.flags & !(IOSQE_FIXED_FILE | IOSQE_ASYNC) != 0 {
+            return Err(-(EINVAL as isize));
+        }
+        let op = match sqe.opcode {
+            IORING_OP_NOP => Op::Nop,
+            IORING_OP_READ | IORING_OP_READV | IORING_OP_READ_FIXED => {
+                let dst = self.buffers_of(sqe)?;
+                let len = dst.iter().map(|&(_, len)| len).sum::<usize>();
+                Op::Read {
+                    file: self.file_of(sqe)?,
+                    offset: rw_offset(sqe)?,
+                    len: len.min(MAX_RW_COUNT),
+                    dst,
+                }
+            }
+            IORING_OP_WRITE | IORING_OP_WRITEV | IORING_OP_WRITE_FIXED => {
+                let src = self.buffers_of(sqe)?;
+                let file = self.file_of(sqe)?;
+                let mut data = Vec::new();
+                for (addr, len) in src {
+                    let len = len.min(MAX_RW_COUNT - data.len());
+                    if len > 0 {
+                        // SAFETY: buffers_of 已检查地址范围
+                        data.extend(unsafe {
+                            UserBuffer::new(addr as *mut u8, len).copy_from_user()
+                        });
+                    }
+                }
+                Op::Write {
+                    file,
+                    offset: rw_offset(sqe)?,
+                    data,
+                }
+            }
+            IORING_OP_FSYNC => {
+                // 块设备缓存没有只写回数据的方式，DATASYNC 与完整同步相同
+                if sqe.op_flags & !IORING_FSYNC_DATASYNC != 0 {
+                    return Err(-(EINVAL as isize));
+                }
+                Op::Fsync {
+                    file: self.file_of(sqe)?,
+                }
+            }
+            IORING_OP_ACCEPT => {
+                // accept4 按 fd 查找 socket，不能使用已登记文件的下标
+                if sqe.flags & IOSQE_FIXED_FILE != 0 {
+                    return Err(-(EINVAL as isize));
+                }
+                Op::Accept {
+                    file: self.file_of(sqe)?,
+                    fd: sqe.fd,
+                    addr: sqe.addr as usize,
+                    addrlen: sqe.off as usize,
+                    flags: sqe.op_flags as i32,
+                }
+            }
+            _ => return Err(-(EINVAL as isize)),
+        };
+        Ok(Request {
+            user_data: sqe.user_data,
+            op,
+        })
+    }
+
+    fn file_of(&self, sqe: &IoUringSqe) -> Result<Arc<dyn File>, isize> {
+        if sqe.flags & IOSQE_FIXED_FILE != 0 {
+            let state = self.state.lock();
+            return state
+                .files
+                .as_ref()
+                .and_then(|files| files.get(sqe.fd as usize))
+                .and_then(Option::clone)
+                .ok_or(-(EBADF as isize));
+        }
+        let file = current_task()
+            .lock()
+            .fd_table
+            .get(sqe.fd as usize)
+            .map_err(|e| e.to_errno())?;
+        // 等待中的请求持有文件，以 io_uring 实例为目标会形成引用环
+        if file.as_any().is::<IoUringFile>() {
+            return Err(-(EBADF as isize));
+        }
+        Ok(file)
+    }
+
+    /// 读写请求的用户缓冲区（地址, 长度）
+    fn buffers_of(&self, sqe: &IoUringSqe) -> Result<Vec<(usize, usize)>, isize> {
+        let addr = sqe.addr as usize;
+        let len = sqe.len as usize;
+        let ranges = match sqe.opcode {
+            IORING_OP_READV | IORING_OP_WRITEV => {
+                if len > UIO_MAXIOV {
+                    return Err(-(EINVAL as isize));
+                }
+                read_iovecs(addr as *const IoVec, len)?
+            }
+            IORING_OP_READ_FIXED | IORING_OP_WRITE_FIXED => {
+                let state = self.state.lock();
+                let (base, size) = state
+                    .buffers
+                    .as_ref()
+                    .and_then(|buffers| buffers.get(sqe.buf_index as usize).copied())
+                    .ok_or(-(EFAULT as isize))?;
+                // 必须落在登记的缓冲区内
+                if addr < base || !matches!(addr.checked_add(len), Some(end) if end <= base + size)
+                {
+                    return Err(-(EFAULT as isize));
+                }
+                vec![(addr, len)]
+            }
+            _ => vec![(addr, len)],
+        };
+        if ranges
+            .iter()
+            .any(|&(addr, len)| len > 0 && !user_range_ok(addr, len))
+        {
+            return Err(-(EFAULT as isize));
+        }
+        Ok(ranges)
+    }
+
+    /// 在提交者上下文中发起请求
+    fn issue(self: &Arc<Self>, req: Request, force_async: bool) {
+        if force_async || matches!(req.op, Op::Fsync { .. }) {
+            self.queue_work(req);
+            return;
+        }
+        match perform(&req.op, true) {
+            Outcome::Done(res) => self.complete(req.user_data, res),
+            Outcome::CopyOut(data) => self.copy_out(&req, &data),
+            Outcome::NotReady | Outcome::NeedTask => self.arm(req),
+        }
+    }
+
+    /// 交给内核工作队列执行
+    fn queue_work(self: &Arc<Self>, req: Request) {
+        let ring = self.clone();
+        GLOBAL_WORK_QUEUE
+            .lock()
+            .schedule_work(WorkItem::from_closure(move || ring.run_async(req)));
+    }
+
+    /// 在工作线程中执行
+    fn run_async(self: &Arc<Self>, req: Request) {
+        match perform(&req.op, false) {
+            Outcome::Done(res) => self.complete(req.user_data, res),
+            Outcome::CopyOut(data) => self.defer(TaskWork::CopyOut(req, data)),
+            Outcome::NotReady => self.arm(req),
+            Outcome::NeedTask => self.defer(TaskWork::Retry(req)),
+        }
+    }
+
+    /// 登记到文件的就绪队列，等文件就绪
+    fn arm(self: &Arc<Self>, req: Request) {
+        let Some((file, events)) = req.op.waits_on() else {
+            unreachable!("io_uring: request without a file is never pending");
+        };
+        let file = file.clone();
+        let armed = Arc::new(ArmedRequest {
+            ring: Arc::downgrade(self),
+            req: SpinLock::new(Some(req)),
+        });
+        self.state.lock().armed.push(armed.clone());
+        let waker: Arc<dyn PollWaker> = armed.clone();
+        // 先登记再检查，登记之前已经就绪的情况不会被错过
+        let ready = file.poll(Some(&waker));
+        if ready.intersects(events | EpollEvents::ERR | EpollEvents::HUP) {
+            armed.wake(ready);
+        }
+    }
+
+    fn disarm(&self, armed: &ArmedRequest) {
+        self.state
+            .lock()
+            .armed
+            .retain(|a| !ptr::eq(Arc::as_ptr(a), armed));
+    }
+
+    /// 留给提交者上下文，提示用户态进入内核
+    fn defer(&self, work: TaskWork) {
+        {
+            let mut state = self.state.lock();
+            state.task_work.push_back(work);
+            self.set_sq_flag(IORING_SQ_TASKRUN, true);
+        }
+        self.poll_queue.wake(EpollEvents::IN | EpollEvents::RDNORM);
+    }
+
+    /// 在提交者上下文中处理留下的工作
+    fn run_task_work(self: &Arc<Self>) {
+        loop {
+            let work = {
+                let mut state = self.state.lock();
+                let work = state.task_work.pop_front();
+                if state.task_work.is_empty() {
+                    self.set_sq_flag(IORING_SQ_TASKRUN, false);
+                }
+                work
+            };
+            match work {
+                Some(TaskWork::CopyOut(req, data)) => self.copy_out(&req, &data),
+                Some(TaskWork::Retry(req)) => self.issue(req, false),
+                None => return,
+            }
+        }
+    }
+
+    /// 把读到的数据写回读请求的用户缓冲区并完成请求
+    fn copy_out(&self, req: &Request, data: &[u8]) {
+        let Op::Read { dst, .. } = &req.op else {
+            return;
+        };
+        let mut copied = 0;
+        for &(addr, len) in dst {
+            let len = len.min(data.len() - copied);
+            if len > 0 {
+                // SAFETY: prepare 已检查地址范围
+                unsafe {
+                    UserBuffer::new(addr as *mut u8, len).copy_to_user(&data[copied..copied + len])
+                };
+                copied += len;
+            }
+        }
+        self.complete(req.user_data, data.len() as isize);
+    }
+
+    fn register_buffers(&self, iov: *const IoVec, nr: usize) -> Result<(), isize> {
+        if nr == 0 || nr > UIO_MAXIOV {
+            return Err(-(EINVAL as isize));
+        }
+        if self.state.lock().buffers.is_some() {
+            return Err(-(EBUSY as isize));
+        }
+        let buffers = read_iovecs(iov, nr)?;
+        if buffers
+            .iter()
+            .any(|&(addr, len)| len == 0 || !user_range_ok(addr, len))
+        {
+            return Err(-(EFAULT as isize));
+        }
+        let mut state = self.state.lock();
+        if state.buffers.is_some() {
+            return Err(-(EBUSY as isize));
+        }
+        state.buffers = Some(buffers);
+        Ok(())
+    }
+
+    fn register_files(&self, fds: *const i32, nr: usize) -> Result<(), isize> {
+        if nr == 0 || nr > UIO_MAXIOV {
+            return Err(-(EINVAL as isize));
+        }
+        if self.state.lock().files.is_some() {
+            return Err(-(EBUSY as isize));
+        }
+        let fd_table = current_task().lock().fd_table.clone();
+        let mut files = Vec::with_capacity(nr);
+        for i in 0..nr {
+            let fd = copy_from_user(fds.wrapping_add(i)).map_err(|_| -(EFAULT as isize))?;
+            if fd == -1 {
+                files.push(None);
+                continue;
+            }
+            let file = fd_table.get(fd as usize).map_err(|e| e.to_errno())?;
+            // 登记 io_uring 实例自身会形成引用环
+            if file.as_any().is::<IoUringFile>() {
+                return Err(-(EBADF as isize));
+            }
+            files.push(Some(file));
+        }
+        let mut state = self.state.lock();
+        if state.files.is_some() {
+            return Err(-(EBUSY as isize));
+        }
+        state.files = Some(files);
+        Ok(())
+    }
+}
+
+/// io_uring 实例的 fd
+pub struct IoUringFile {
+    ring: Arc<IoUring>,
+}
+
+impl IoUringFile {
+    /// mmap 偏移量 `offset` 对应的共享页，映射长度不能超出这组页
+    pub fn mmap_region(&self, offset: u64, len: usize) -> Result<Arc<dyn MmFile>, isize> {
+        let pages = match offset {
+            IORING_OFF_SQ_RING | IORING_OFF_CQ_RING => &self.ring.rings,
+            IORING_OFF_SQES => &self.ring.sqes,
+            _ => return Err(-(EINVAL as isize)),
+        };
+        if len > pages.len() {
+            return Err(-(EINVAL as isize));
+        }
+        Ok(Arc::new(SharedMapping(pages.cache.clone())))
+    }
+}
+
+impl Drop for IoUringFile {
+    fn drop(&mut self) {
+        // 取消等待中的请求，释放登记的文件；已交给工作队列的请求执行完后随之释放。
+        // 请求持有的文件在锁外释放
+        let (armed, task_work, files) = {
+            let mut state = self.ring.state.lock();
+            (
+                core::mem::take(&mut state.armed),
+                core::mem::take(&mut state.task_work),
+                state.files.take(),
+            )
+        };
+        let cancelled: Vec<_> = armed.iter().filter_map(|a| a.req.lock().take()).collect();
+        drop((cancelled, task_work, files));
+    }
+}
+
+impl File for IoUringFile {
+    // 表示访问模式而不是就绪状态，mmap 据此检查权限
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    /// 有完成或待处理的工作时可读，提交队列未满时可写
+    fn poll(&self, waker: Option<&Arc<dyn PollWaker>>) -> EpollEvents {
+        if let Some(waker) = waker {
+            self.ring.poll_queue.register(waker);
+        }
+        let ring = &self.ring;
+        let mut events = EpollEvents::empty();
+        let pending = {
+            let state = ring.state.lock();
+            !state.overflow.is_empty() || !state.task_work.is_empty()
+        };
+        if pending || ring.cq_ready() > 0 {
+            events |= EpollEvents::IN | EpollEvents::RDNORM;
+        }
+        let sq_head = ring.rings.atomic(SQ_HEAD).load(Ordering::Acquire);
+        let sq_tail = ring.rings.atomic(SQ_TAIL).load(Ordering::Acquire);
+        if sq_tail.wrapping_sub(sq_head) < ring.sq_entries {
+            events |= EpollEvents::OUT | EpollEvents::WRNORM;
+        }
+        events
+    }
+
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        Err(FsError::NotSupported)
+    }
+
+    fn flags(&self) -> OpenFlags {
+        OpenFlags::O_RDWR
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+/// 读写请求的偏移量，-1 表示使用文件当前偏移量
+fn rw_offset(sqe: &IoUringSqe) -> Result<Option<usize>, isize> {
+    match sqe.off as i64 {
+        -1 => Ok(None),
+        off if off < 0 => Err(-(EINVAL as isize)),
+        off => Ok(Some(off as usize)),
+    }
+}
+
+/// 按上限检查队列容量，`IORING_SETUP_CLAMP` 时截断，并向上取整到 2 的幂
+fn ring_entries(entries: u32, max: u32, clamp: bool) -> Result<u32, isize> {
+    if entries == 0 || (entries > max && !clamp) {
+        return Err(-(EINVAL as isize));
+    }
+    Ok(entries.min(max).next_power_of_two())
+}
+
+/// 取 `fd` 对应的 io_uring 实例
+fn ring_of(fd: usize) -> Result<Arc<IoUring>, isize> {
+    let file = current_task()
+        .lock()
+        .fd_table
+        .get(fd)
+        .map_err(|e| e.to_errno())?;
+    let Some(file) = file.as_any().downcast_ref::<IoUringFile>() else {
+        return Err(-(EOPNOTSUPP as isize));
+    };
+    // 读写用户内存都在创建者的地址空间中进行
+    if !file.ring.owned_by_current() {
+        return Err(-(EINVAL as isize));
+    }
+    Ok(file.ring.clone())
+}
+
+/// io_uring_setup - 创建 io_uring 实例
+///
+/// 支持 `IORING_SETUP_CQSIZE` 与 `IORING_SETUP_CLAMP`。成功时把实际的队列容量、支持的特性
+/// 与环的布局写回 `params`。
+///
+/// # 返回值
+/// * 成功返回 io_uring 实例的文件描述符（带 FD_CLOEXEC）
+/// * -EFAULT - params 不可访问
+/// * -EINVAL - 容量为 0 或超过上限、含有不支持的标志或保留字段非零
+/// * -ENOMEM - 分配环页失败
+pub fn io_uring_setup(entries: u32, params: *mut IoUringParams) -> isize {
+    let mut p = match copy_from_user(params) {
+        Ok(p) => p,
+        Err(_) => return -(EFAULT as isize),
+    };
+    if p.flags & !(IORING_SETUP_CQSIZE | IORING_SETUP_CLAMP) != 0 || p.resv != [0; 3] {
+        return -(EINVAL as isize);
+    }
+    let clamp = p.flags & IORING_SETUP_CLAMP != 0;
+    let sq_entries = match ring_entries(entries, IORING_MAX_ENTRIES, clamp) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+    let cq_entries = if p.flags & IORING_SETUP_CQSIZE != 0 {
+        match ring_entries(p.cq_entries, IORING_MAX_CQ_ENTRIES, clamp) {
+            Ok(n) if n >= sq_entries => n,
+            Ok(_) => return -(EINVAL as isize),
+            Err(e) => return e,
+        }
+    } else {
+        2 * sq_entries
+    };
+
+    let ring = match IoUring::new(sq_entries, cq_entries) {
+        Ok(ring) => ring,
+        Err(e) => return e,
+    };
+    p.sq_entries = sq_entries;
+    p.cq_entries = cq_entries;
+    p.features = IORING_FEAT_SINGLE_MMAP
+        | IORING_FEAT_NODROP
+        | IORING_FEAT_SUBMIT_STABLE
+        | IORING_FEAT_RW_CUR_POS;
+    p.sq_off = ring.sq_offsets();
+    p.cq_off = ring.cq_offsets();
+    if copy_to_user(params, p).is_err() {
+        return -(EFAULT as isize);
+    }
+
+    let file = Arc::new(IoUringFile { ring });
+    let fd_table = current_task().lock().fd_table.clone();
+    match fd_table.alloc_with_flags(file as Arc<dyn File>, FdFlags::CLOEXEC) {
+        Ok(fd) => fd as isize,
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// io_uring_enter - 提交请求并等待完成
+///
+/// 先处理留给提交者上下文的工作并写回溢出的完成，再消费至多 `to_submit` 个 SQE；
+/// 带 `IORING_ENTER_GETEVENTS` 时等到完成队列中至少有 `min_complete` 个完成。
+/// 信号掩码暂不处理（同 epoll_pwait）。
+///
+/// # 返回值
+/// * 成功返回消费的 SQE 个数
+/// * -EBADF - fd 无效
+/// * -EOPNOTSUPP - fd 不是 io_uring 实例
+/// * -EINVAL - flags 含有不支持的位，或调用者不在实例创建者的地址空间中
+/// * -EINTR - 没有提交任何请求时等待被信号打断
+pub fn io_uring_enter(
+    fd: usize,
+    to_submit: u32,
+    min_complete: u32,
+    flags: u32,
+    _sig: usize,
+    _sigsz: usize,
+) -> isize {
+    if flags & !(IORING_ENTER_GETEVENTS | IORING_ENTER_SQ_WAKEUP) != 0 {
+        return -(EINVAL as isize);
+    }
+    let ring = match ring_of(fd) {
+        Ok(ring) => ring,
+        Err(e) => return e,
+    };
+
+    ring.run_task_work();
+    ring.flush_overflow();
+    let submitted = ring.submit(to_submit);
+    if flags & IORING_ENTER_GETEVENTS == 0 {
+        return submitted as isize;
+    }
+
+    let interrupted = if submitted > 0 {
+        submitted as isize
+    } else {
+        -(EINTR as isize)
+    };
+    let wanted = min_complete.min(ring.cq_entries);
+    let task = current_task();
+    // 等待者在整个等待期间登记在实例上，有新的完成或待处理工作时被唤醒
+    let waiter = PollWaiter::new();
+    let waker: Arc<dyn PollWaker> = waiter.clone();
+    ring.poll_queue.register(&waker);
+
+    loop {
+        // 同 epoll_pwait：推进网络栈，让等待中的 socket 请求有机会就绪
+        crate::net::socket::poll_network_and_dispatch();
+
+        ring.run_task_work();
+        ring.flush_overflow();
+        if ring.cq_ready() >= wanted {
+            return submitted as isize;
+        }
+
+        if crate::ipc::signal_interrupts_syscall(&task) {
+            return interrupted;
+        }
+
+        match waiter.wait(None) {
+            WaitResult::Ready | WaitResult::TimedOut => {}
+            WaitResult::Interrupted => return interrupted,
+        }
+    }
+}
+
+/// io_uring_register - 登记或注销固定缓冲区与固定文件
+///
+/// # 返回值
+/// * 成功返回 0
+/// * -EBADF - fd 无效，或登记的文件中有无效的 fd 或 io_uring 实例
+/// * -EOPNOTSUPP - fd 不是 io_uring 实例
+/// * -EINVAL - opcode 不支持，或个数为 0 / 超过上限
+/// * -EBUSY - 已经登记过
+/// * -ENXIO - 注销时没有登记
+/// * -EFAULT - 参数不可访问或缓冲区不在用户地址空间内
+pub fn io_uring_register(fd: usize, opcode: u32, arg: usize, nr_args: u32) -> isize {
+    let ring = match ring_of(fd) {
+        Ok(ring) => ring,
+        Err(e) => return e,
+    };
+    let nr = nr_args as usize;
+    let result = match opcode {
+        IORING_REGISTER_BUFFERS => ring.register_buffers(arg as *const IoVec, nr),
+        IORING_REGISTER_FILES => ring.register_files(arg as *const i32, nr),
+        IORING_UNREGISTER_BUFFERS | IORING_UNREGISTER_FILES => {
+            if arg != 0 || nr != 0 {
+                return -(EINVAL as isize);
+            }
+            let mut state = ring.state.lock();
+            let removed = if opcode == IORING_UNREGISTER_BUFFERS {
+                state.buffers.take().is_some()
+            } else {
+                state.files.take().is_some()
+            };
+            if removed {
+                Ok(())
+            } else {
+                Err(-(ENXIO as isize))
+            }
+        }
+        _ => Err(-(EINVAL as isize)),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => e,
+    }
+}
@@ -4,8 +4,9 @@
 
 use crate::arch::trap::SumGuard;
 use crate::kernel::current_task;
+use crate::util::user_buffer::{copy_from_user, copy_to_user};
 use crate::vfs::FsError;
-use crate::{pr_debug, pr_err, pr_warn};
+use crate::{pr_debug, pr_err, pr_warn, pr_warn_once};
 use uapi::errno::{EBADF, EINVAL, ENOTTY, EOPNOTSUPP};
 use uapi::ioctl::*;
 
@@ -32,6 +33,8 @@ use uapi::ioctl::*;
 /// - `TIOCSWINSZ` - 设置终端窗口大小
 /// - `TCGETS` - 获取终端属性
 /// - `TCSETS` - 设置终端属性
+/// - `TIOCSCTTY` / `TIOCNOTTY` - 设置/放弃控制终端
+/// - `TIOCGPGRP` / `TIOCSPGRP` - 获取/设置前台进程组
 ///
 /// ## 网络操作
 /// - `SIOCGIFCONF` - 获取网络接口列表
@@ -74,11 +77,16 @@ pub fn ioctl(fd: i32, request: u32, arg: usize) -> isize {
     let result = match request {
         //  通用文件 I/O 控制
         FIONBIO => handle_fionbio(&file, arg),
-        FIONREAD => handle_fionread(&file, arg),
+        FIONREAD => match file.ioctl(request, arg) {
+            // 终端等设备自行报告可读字节数
+            Ok(ret) => ret,
+            Err(_) => handle_fionread(&file, arg),
+        },
         FIOASYNC => handle_fioasync(&file, arg),
 
-        //  终端控制 - 委托给文件对象的 ioctl 方法
-        TIOCGWINSZ | TIOCSWINSZ | TCGETS | TCSETS | TCSETSW | TCSETSF => {
+        //  终端控制（含控制终端与前台进程组）- 委托给文件对象的 ioctl 方法
+        TIOCGWINSZ | TIOCSWINSZ | TCGETS | TCSETS | TCSETSW | TCSETSF | TIOCGPGRP | TIOCSPGRP
+        | TIOCGSID | TIOCSCTTY | TIOCNOTTY => {
             match file.ioctl(request, arg) {
                 Ok(ret) => ret,
                 Err(FsError::NotSupported) => {
@@ -90,13 +98,6 @@ pub fn ioctl(fd: i32, request: u32, arg: usize) -> isize {
             }
         }
 
-        //  终端进程组控制 - 读取/设置任务的 pgid
-        TIOCGPGRP => handle_tiocgpgrp(&task, arg),
-        TIOCSPGRP => handle_tiocspgrp(&task, arg),
-
-        //  控制终端设置
-        TIOCSCTTY => handle_tiocsctty(&file, arg),
-
         //  虚拟终端查询
         VT_OPENQRY => handle_vt_openqry(arg),
 
@@ -113,7 +114,7 @@ pub fn ioctl(fd: i32, request: u32, arg: usize) -> isize {
             match file.ioctl(request, arg) {
                 Ok(ret) => ret,
                 Err(FsError::NotSupported) => {
-                    pr_warn!(
+                    pr_warn_once!(
                         "ioctl: unsupported request {:#x} (type={:#x}, nr={}, size={})",
                         request,
                         _IOC_TYPE(request),
@@ -143,136 +144,80 @@ pub fn ioctl(fd: i32, request: u32, arg: usize) -> isize {
 
 /// FIONBIO - 设置/清除非阻塞 I/O 标志
 fn handle_fionbio(file: &alloc::sync::Arc<dyn crate::vfs::File>, arg: usize) -> isize {
-    unsafe {
-        let _guard = SumGuard::new();
-        let value_ptr = arg as *const i32;
-        if value_ptr.is_null() {
-            return -EINVAL as isize;
-        }
+    let value_ptr = arg as *const i32;
+    if value_ptr.is_null() {
+        return -EINVAL as isize;
+    }
 
-        let value = core::ptr::read_volatile(value_ptr);
+    let value = match copy_from_user(value_ptr) {
+        Ok(v) => v,
+        Err(e) => return -e as isize,
+    };
 
-        // 设置文件的 O_NONBLOCK 标志
-        let mut flags = file.flags();
-        if value != 0 {
-            flags |= uapi::fcntl::OpenFlags::O_NONBLOCK;
-        } else {
-            flags &= !uapi::fcntl::OpenFlags::O_NONBLOCK;
-        }
+    // 设置文件的 O_NONBLOCK 标志
+    let mut flags = file.flags();
+    if value != 0 {
+        flags |= uapi::fcntl::OpenFlags::O_NONBLOCK;
+    } else {
+        flags &= !uapi::fcntl::OpenFlags::O_NONBLOCK;
+    }
 
-        match file.set_status_flags(flags) {
-            Ok(_) => 0,
-            Err(e) => {
-                pr_warn!("ioctl: FIONBIO failed: {:?}", e);
-                -EOPNOTSUPP as isize
-            }
+    match file.set_status_flags(flags) {
+        Ok(_) => 0,
+        Err(e) => {
+            pr_warn!("ioctl: FIONBIO failed: {:?}", e);
+            -EOPNOTSUPP as isize
         }
     }
 }
 
 /// FIONREAD - 获取可读字节数
 fn handle_fionread(file: &alloc::sync::Arc<dyn crate::vfs::File>, arg: usize) -> isize {
-    unsafe {
-        let _guard = SumGuard::new();
-        let value_ptr = arg as *mut i32;
-        if value_ptr.is_null() {
-            return -EINVAL as isize;
-        }
+    let value_ptr = arg as *mut i32;
+    if value_ptr.is_null() {
+        return -EINVAL as isize;
+    }
 
-        // 对于普通文件，可读字节数 = 文件大小 - 当前偏移量
-        let available = match file.metadata() {
-            Ok(meta) => {
-                let size = meta.size;
-                let offset = file.offset();
-                if size > offset {
-                    (size - offset) as i32
-                } else {
-                    0
-                }
-            }
-            Err(_) => {
-                // 对于不支持 metadata 的设备，返回 0
+    // 对于普通文件，可读字节数 = 文件大小 - 当前偏移量
+    let available = match file.metadata() {
+        Ok(meta) => {
+            let size = meta.size;
+            let offset = file.offset();
+            if size > offset {
+                (size - offset) as i32
+            } else {
                 0
             }
-        };
-
-        core::ptr::write_volatile(value_ptr, available);
-        0
-    }
-}
-
-/// FIOASYNC - 设置/清除异步 I/O 通知
-fn handle_fioasync(file: &alloc::sync::Arc<dyn crate::vfs::File>, arg: usize) -> isize {
-    unsafe {
-        let _guard = SumGuard::new();
-        let value_ptr = arg as *const i32;
-        if value_ptr.is_null() {
-            return -EINVAL as isize;
         }
+        Err(_) => {
+            // 对于不支持 metadata 的设备，返回 0
+            0
+        }
+    };
 
-        let _value = core::ptr::read_volatile(value_ptr);
-
-        // TODO: 实现异步 I/O 支持
-        pr_warn!("ioctl: FIOASYNC not yet implemented");
-        -EOPNOTSUPP as isize
+    match copy_to_user(value_ptr, available) {
+        Ok(()) => 0,
+        Err(e) => -e as isize,
     }
 }
 
-//  终端控制处理函数
-
-/// TIOCGPGRP - 获取终端前台进程组 ID
-fn handle_tiocgpgrp(
-    task: &alloc::sync::Arc<crate::sync::SpinLock<crate::kernel::task::TaskStruct>>,
-    arg: usize,
-) -> isize {
-    if arg == 0 {
+/// FIOASYNC - 设置/清除异步 I/O 通知
+fn handle_fioasync(_file: &alloc::sync::Arc<dyn crate::vfs::File>, arg: usize) -> isize {
+    let value_ptr = arg as *const i32;
+    if value_ptr.is_null() {
         return -EINVAL as isize;
     }
 
-    let pgid = task.lock().pgid as i32;
-    pr_debug!("ioctl: TIOCGPGRP writing pgid={} to {:#x}", pgid, arg);
-
-    unsafe {
-        crate::util::user_buffer::write_to_user(arg as *mut i32, pgid);
+    if let Err(e) = copy_from_user(value_ptr) {
+        return -e as isize;
     }
 
-    pr_debug!("ioctl: TIOCGPGRP completed");
-    0
+    // TODO: 实现异步 I/O 支持
+    pr_warn_once!("ioctl: FIOASYNC not yet implemented");
+    -EOPNOTSUPP as isize
 }
 
-/// TIOCSPGRP - 设置终端前台进程组 ID
-fn handle_tiocspgrp(
-    task: &alloc::sync::Arc<crate::sync::SpinLock<crate::kernel::task::TaskStruct>>,
-    arg: usize,
-) -> isize {
-    unsafe {
-        let _guard = SumGuard::new();
-        let pid_ptr = arg as *const i32;
-        if pid_ptr.is_null() {
-            return -EINVAL as isize;
-        }
-
-        let pgid = core::ptr::read_volatile(pid_ptr);
-
-        // 设置当前任务的进程组 ID
-        task.lock().pgid = pgid as u32;
-
-        pr_debug!("ioctl: TIOCSPGRP set pgid={}", pgid);
-        0
-    }
-}
-
-/// TIOCSCTTY - 设置控制终端
-///
-/// 这个 ioctl 用于使当前终端成为调用进程的控制终端。
-/// busybox init 在启动时会调用这个函数。
-/// 目前我们只是简单返回成功，因为我们还没有完整的会话管理。
-fn handle_tiocsctty(_file: &alloc::sync::Arc<dyn crate::vfs::File>, _arg: usize) -> isize {
-    // TODO: 实现完整的控制终端管理
-    // 目前只是返回成功，让 init 可以继续运行
-    pr_debug!("ioctl: TIOCSCTTY accepted (not fully implemented)");
-    0
-}
+//  终端控制处理函数
 
 /// VT_OPENQRY - 查询可用的虚拟终端
 ///
@@ -3,11 +3,36 @@
 use alloc::sync::Arc;
 
 use crate::{
-    arch::trap::SumGuard,
     kernel::{current_cpu, current_task},
-    vfs::{FdFlags, FdFlagsExt, File, FsError, OpenFlags, PipeFile},
+    util::user_buffer::copy_to_user,
+    vfs::{EventFdFile, FdFlags, FdFlagsExt, File, FsError, OpenFlags, PipeFile},
 };
 
+/// eventfd2 - 创建初值为 `initval` 的 eventfd
+///
+/// # 返回值
+/// * 成功返回文件描述符
+/// * -EINVAL - flags 含有 EFD_SEMAPHORE、EFD_CLOEXEC、EFD_NONBLOCK 以外的位
+pub fn eventfd2(initval: u32, flags: u32) -> isize {
+    use uapi::eventfd::{EFD_CLOEXEC, EFD_NONBLOCK, EFD_SEMAPHORE};
+
+    if flags & !(EFD_SEMAPHORE | EFD_CLOEXEC | EFD_NONBLOCK) != 0 {
+        return FsError::InvalidArgument.to_errno();
+    }
+    let open_flags = OpenFlags::from_bits_truncate(flags & (EFD_CLOEXEC | EFD_NONBLOCK));
+    let file = Arc::new(EventFdFile::new(
+        initval,
+        flags & EFD_SEMAPHORE != 0,
+        open_flags & OpenFlags::O_NONBLOCK,
+    ));
+
+    let fd_table = current_task().lock().fd_table.clone();
+    match fd_table.alloc_with_flags(file as Arc<dyn File>, FdFlags::from_open_flags(open_flags)) {
+        Ok(fd) => fd as isize,
+        Err(e) => e.to_errno(),
+    }
+}
+
 pub fn dup(oldfd: usize) -> isize {
     let task = current_task();
     match task.lock().fd_table.dup(oldfd) {
@@ -28,7 +53,8 @@ pub fn dup3(oldfd: usize, newfd: usize, flags: u32) -> isize {
         return FsError::InvalidArgument.to_errno();
     }
 
-    match task.lock().fd_table.dup3(oldfd, newfd, open_flags) {
+    let fd_table = task.lock().fd_table.clone();
+    match fd_table.dup3(oldfd, newfd, open_flags) {
         Ok(newfd) => newfd as isize,
         Err(e) => e.to_errno(),
     }
@@ -69,14 +95,13 @@ pub fn pipe2(pipefd: *mut i32, flags: u32) -> isize {
         }
     };
 
-    // 将 FD 写回用户空间
-    {
-        let _guard = SumGuard::new();
-        unsafe {
-            core::ptr::write(pipefd.offset(0), read_fd as i32);
-            core::ptr::write(pipefd.offset(1), write_fd as i32);
+    // 将 FD 写回用户空间，失败时回滚两端 FD
+    match copy_to_user(pipefd as *mut [i32; 2], [read_fd as i32, write_fd as i32]) {
+        Ok(()) => 0,
+        Err(e) => {
+            let _ = fd_table.close(read_fd);
+            let _ = fd_table.close(write_fd);
+            -(e as isize)
         }
     }
-
-    0
 }
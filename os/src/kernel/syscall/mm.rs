@@ -1,7 +1,8 @@
 use core::ffi::c_void;
 
+use super::io_uring::IoUringFile;
 use crate::config::PAGE_SIZE;
-use crate::kernel::{current_memory_space, current_task};
+use crate::kernel::{Capabilities, capable, current_memory_space, current_task};
 use crate::vfs::FileWrapper;
 use crate::{pr_err, pr_warn};
 use alloc::sync::Arc;
@@ -9,9 +10,13 @@ use mm::MmFile;
 use mm::address::{PageNum, UsizeConvert, Vaddr, Vpn, VpnRange};
 use mm::memory_space::MmapFile;
 use mm::memory_space::mapping_area::AreaType;
-use mm::page_table::UniversalPTEFlag;
-use uapi::errno::{EACCES, EBADF, EEXIST, EINVAL, EIO, ENOMEM, EOPNOTSUPP};
-use uapi::mm::{MAP_FAILED, MapFlags, ProtFlags};
+use mm::page_table::PagingError;
+use mm::page_table::{PageSize, UniversalPTEFlag};
+use uapi::errno::{EACCES, EAGAIN, EBADF, EEXIST, EFAULT, EINVAL, EIO, ENOMEM, EOPNOTSUPP, EPERM};
+use uapi::mm::{
+    MAP_FAILED, MadviseAdvice, MapFlags, MlockFlags, MlockallFlags, MremapFlags, ProtFlags,
+};
+use uapi::resource::{ResourceId, rlimit_value::RLIM_INFINITY};
 
 /// brk - 改变数据段的结束地址（堆顶）
 ///
@@ -28,7 +33,9 @@ use uapi::mm::{MAP_FAILED, MapFlags, ProtFlags};
 /// - 如果 new_brk 小于堆起始地址，失败并返回当前 brk
 /// - 如果 new_brk 超过最大堆大小限制，失败并返回当前 brk
 /// - 如果 new_brk 与栈或其他区域重叠，失败并返回当前 brk
+/// - mlockall(MCL_FUTURE) 之后扩展的堆页被锁定，超过 RLIMIT_MEMLOCK 时失败并返回当前 brk
 pub fn brk(new_brk: usize) -> isize {
+    let memlock_limit = memlock_limit_pages();
     let memory_space = current_memory_space();
     let mut space = memory_space.lock();
 
@@ -40,9 +47,37 @@ pub fn brk(new_brk: usize) -> isize {
         return current as isize;
     }
 
+    // mlockall(MCL_FUTURE) 之后扩展的部分计入锁定的页数
+    let old_end = Vpn::from_addr_ceil(Vaddr::from_usize(current));
+    let new_end = Vpn::from_addr_ceil(Vaddr::from_usize(new_brk));
+    let lock_flags = space.mlock_future();
+    if lock_flags.is_some() && current != 0 && new_end > old_end {
+        let grow = new_end.as_usize() - old_end.as_usize();
+        if memlock_limit.is_some_and(|limit| space.locked_pages() + grow > limit) {
+            return current as isize;
+        }
+    }
+
     // 尝试设置新的 brk
     match space.brk(new_brk) {
-        Ok(addr) => addr as isize,
+        Ok(addr) => {
+            if current != 0 && new_end > old_end {
+                let grow_range = VpnRange::new(old_end, new_end);
+                match lock_flags {
+                    Some(flags) => {
+                        let start = old_end.start_addr().as_usize();
+                        let len = grow_range.len() * PAGE_SIZE;
+                        let populate = !flags.contains(MlockFlags::ONFAULT);
+                        if let Err(e) = space.mlock(start, len, populate) {
+                            pr_warn!("brk: failed to lock new heap pages: {:?}", e);
+                        }
+                    }
+                    // 新扩展的堆页可以换出到交换区
+                    None => crate::mm::swap::lru_add_range(&memory_space, grow_range),
+                }
+            }
+            addr as isize
+        }
         Err(e) => {
             pr_err!(
                 "brk failed: {:?}, new_brk=0x{:x}, current=0x{:x}",
@@ -80,12 +115,16 @@ pub fn brk(new_brk: usize) -> isize {
 /// - ✅ MAP_FIXED - 固定地址映射（覆盖现有）
 /// - ✅ MAP_FIXED_NOREPLACE - 固定地址映射（不覆盖）
 /// - ✅ 地址 hint 机制
+/// - ✅ W^X：PROT_WRITE | PROT_EXEC 需 `/proc/sys/vm/allow_wx` 为 1，否则返回 EACCES
+/// - ✅ 透明大页：不小于 2M 的匿名映射由内核选址时按 2M 对齐，对齐部分用 2M 大页映射
+/// - ✅ mlockall(MCL_FUTURE) 之后的映射自动锁定，超过 RLIMIT_MEMLOCK 时返回 EAGAIN
+/// - ✅ MAP_HUGETLB：仅限匿名映射，地址与长度按 2M 对齐，从预留大页池取页，
+///   池中空闲大页不足时返回 ENOMEM（池的大小见 `hugepages=` 启动参数与 `/proc/sys/vm/nr_hugepages`）
 ///
 /// # 当前限制
 /// - ❌ 文件映射（需要 VFS 支持）
 /// - ❌ MAP_POPULATE（预分配，当前默认立即分配）
 /// - ❌ MAP_NORESERVE（延迟分配，当前默认立即分配）
-/// - ❌ 大页 (MAP_HUGETLB)
 pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> isize {
     let hint = addr as usize;
 
@@ -111,20 +150,47 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
         return -EINVAL as isize;
     }
 
+    // W^X：除非 vm.allow_wx 打开，否则拒绝同时可写可执行的映射
+    if prot_flags.contains(ProtFlags::WRITE | ProtFlags::EXEC) && !mm::wx::allow_wx() {
+        pr_warn!("mmap: refusing PROT_WRITE|PROT_EXEC mapping (vm.allow_wx=0)");
+        return -EACCES as isize;
+    }
+
     // 检查 MAP_FIXED 和 MAP_FIXED_NOREPLACE 互斥
     if map_flags.contains(MapFlags::FIXED) && map_flags.contains(MapFlags::FIXED_NOREPLACE) {
         pr_err!("mmap: MAP_FIXED and MAP_FIXED_NOREPLACE are mutually exclusive");
         return -EINVAL as isize;
     }
 
+    // MAP_HUGETLB 的地址按 2M 对齐，长度向上取整到 2M
+    let huge_size = PageSize::Size2M.pages() * PAGE_SIZE;
+    let hugetlb = map_flags.contains(MapFlags::HUGETLB);
+    let min_align = if hugetlb { huge_size } else { PAGE_SIZE };
+    let len = if hugetlb {
+        match len.checked_next_multiple_of(huge_size) {
+            Some(len) if hint.checked_add(len).is_some() => len,
+            _ => {
+                pr_err!("mmap: address overflow");
+                return -EINVAL as isize;
+            }
+        }
+    } else {
+        len
+    };
+
     // 检查 MAP_FIXED 的地址对齐
-    if map_flags.contains(MapFlags::FIXED) && hint & (PAGE_SIZE - 1) != 0 {
+    if map_flags.contains(MapFlags::FIXED) && hint & (min_align - 1) != 0 {
         pr_err!("mmap: MAP_FIXED requires page-aligned address");
         return -EINVAL as isize;
     }
 
     // 创建 MmapFile（如果是文件映射）
     let mmap_file = if !map_flags.contains(MapFlags::ANONYMOUS) {
+        if hugetlb {
+            pr_err!("mmap: MAP_HUGETLB is only supported for anonymous mappings");
+            return -EINVAL as isize;
+        }
+
         // 文件映射：验证文件描述符和偏移量
         if offset < 0 || (offset as usize) % PAGE_SIZE != 0 {
             pr_err!("mmap: file offset must be non-negative and page-aligned");
@@ -154,9 +220,27 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
             return -EACCES as isize;
         }
 
+        // io_uring 实例：映射与内核共享的环页，偏移量只用于选择映射哪一组页
+        let (file, offset) = match file.as_any().downcast_ref::<IoUringFile>() {
+            Some(ring) => {
+                if !map_flags.contains(MapFlags::SHARED) {
+                    pr_err!("mmap: io_uring rings require MAP_SHARED");
+                    return -EINVAL as isize;
+                }
+                match ring.mmap_region(offset as u64, len) {
+                    Ok(region) => (region, 0),
+                    Err(e) => return e,
+                }
+            }
+            None => (
+                Arc::new(FileWrapper(file)) as Arc<dyn MmFile>,
+                offset as usize,
+            ),
+        };
+
         Some(MmapFile {
-            file: Arc::new(FileWrapper(file)) as Arc<dyn MmFile>,
-            offset: offset as usize,
+            file,
+            offset,
             len,
             prot: prot_flags,
             flags: map_flags,
@@ -174,10 +258,33 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
         None
     };
 
+    // 池中的空闲大页不足时直接失败，不回退到 4K 页
+    if hugetlb && mm::hugetlb::free_hugepages() < len / huge_size {
+        pr_err!("mmap: not enough free huge pages for MAP_HUGETLB");
+        return -ENOMEM as isize;
+    }
+
+    // 内核选址时，大的匿名映射优先按 2M 对齐，以便用大页映射；找不到时退回页对齐
+    let region_align = if mmap_file.is_none() && len >= huge_size {
+        huge_size
+    } else {
+        PAGE_SIZE
+    };
+
     // 确定映射地址
+    let memlock_limit = memlock_limit_pages();
     let memory_space = current_memory_space();
     let mut space = memory_space.lock();
 
+    // mlockall(MCL_FUTURE) 之后的映射计入锁定的页数
+    let lock_flags = space.mlock_future();
+    if lock_flags.is_some()
+        && memlock_limit.is_some_and(|limit| space.locked_pages() + len.div_ceil(PAGE_SIZE) > limit)
+    {
+        pr_err!("mmap: RLIMIT_MEMLOCK exceeded with MCL_FUTURE");
+        return -EAGAIN as isize;
+    }
+
     let start_addr = if map_flags.contains(MapFlags::FIXED) {
         // MAP_FIXED: 强制使用指定地址，覆盖现有映射
         match space.munmap(hint, len) {
@@ -189,7 +296,7 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
         }
     } else if map_flags.contains(MapFlags::FIXED_NOREPLACE) {
         // MAP_FIXED_NOREPLACE: 强制使用指定地址，不覆盖
-        if hint & (PAGE_SIZE - 1) != 0 {
+        if hint & (min_align - 1) != 0 {
             pr_err!("mmap: MAP_FIXED_NOREPLACE requires page-aligned address");
             return -EINVAL as isize;
         }
@@ -211,7 +318,10 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
         // 正常分配（使用 hint）
         if hint == 0 {
             // hint == 0: 内核选择地址
-            match space.find_free_region(len, PAGE_SIZE) {
+            match space
+                .find_free_region(len, region_align)
+                .or_else(|| space.find_free_region(len, min_align))
+            {
                 Some(addr) => addr,
                 None => {
                     pr_err!("mmap: out of memory");
@@ -220,7 +330,7 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
             }
         } else {
             // hint != 0: 尝试使用 hint，失败则内核选择
-            let aligned_hint = hint & !(PAGE_SIZE - 1);
+            let aligned_hint = hint & !(min_align - 1);
 
             let start_vpn = Vpn::from_addr_floor(Vaddr::from_usize(aligned_hint));
             let end_vpn = Vpn::from_addr_ceil(Vaddr::from_usize(aligned_hint + len));
@@ -232,7 +342,10 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
                 aligned_hint
             } else {
                 // hint 不可用，内核选择
-                match space.find_free_region(len, PAGE_SIZE) {
+                match space
+                    .find_free_region(len, region_align)
+                    .or_else(|| space.find_free_region(len, min_align))
+                {
                     Some(addr) => addr,
                     None => {
                         pr_err!("mmap: out of memory");
@@ -274,7 +387,9 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
     let vpn_range = VpnRange::new(start_vpn, end_vpn);
 
     // 插入映射区域（PROT_NONE 用 Reserved 占位，不建立页表映射）
-    let insert_result = if wants_mapping {
+    let insert_result = if wants_mapping && hugetlb {
+        space.insert_hugetlb_area(vpn_range, pte_flags)
+    } else if wants_mapping {
         space.insert_framed_area(vpn_range, AreaType::UserMmap, pte_flags, None, mmap_file)
     } else {
         space.insert_reserved_area(vpn_range, AreaType::UserMmap, pte_flags, mmap_file)
@@ -293,24 +408,32 @@ pub fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offse
     }
 
     // 如果是文件映射，立即加载数据
-    if let Some(area) = space.areas_mut().last_mut() {
-        if let Err(e) = area.load_from_file() {
-            pr_err!(
-                "mmap failed to load file data: {:?}, addr=0x{:x}, len=0x{:x}, fd={}",
-                e,
-                start_addr,
-                len,
-                fd
+    if let Err(e) = space.load_area_from_file(start_vpn) {
+        pr_err!(
+            "mmap failed to load file data: {:?}, addr=0x{:x}, len=0x{:x}, fd={}",
+            e,
+            start_addr,
+            len,
+            fd
+        );
+        // 加载失败，清理已创建的映射
+        if let Err(unmap_err) = space.munmap(start_addr, len) {
+            pr_warn!(
+                "mmap: failed to clean up mapping on load error: {:?}",
+                unmap_err
             );
-            // 加载失败，清理已创建的映射
-            if let Err(unmap_err) = space.munmap(start_addr, len) {
-                pr_warn!(
-                    "mmap: failed to clean up mapping on load error: {:?}",
-                    unmap_err
-                );
-            }
-            return -EIO as isize;
         }
+        return -EIO as isize;
+    }
+
+    if let Some(flags) = lock_flags {
+        let populate = !flags.contains(MlockFlags::ONFAULT);
+        if let Err(e) = space.mlock(start_addr, len, populate) {
+            pr_warn!("mmap: failed to lock new mapping: {:?}", e);
+        }
+    } else if wants_mapping && !hugetlb && !map_flags.contains(MapFlags::SHARED) {
+        // 私有映射的页可以换出到交换区（大页池的页不换出）
+        crate::mm::swap::lru_add_range(&memory_space, vpn_range);
     }
 
     start_addr as isize
@@ -356,6 +479,383 @@ pub fn munmap(addr: *mut c_void, len: usize) -> isize {
     }
 }
 
+/// mremap - 扩大、缩小或移动内存映射
+///
+/// # 参数
+/// - `old_addr`: 原映射的起始地址（必须页对齐）
+/// - `old_size`: 原映射的长度（字节）
+/// - `new_size`: 新的长度（字节）
+/// - `flags`: MREMAP_MAYMOVE | MREMAP_FIXED
+/// - `new_addr`: MREMAP_FIXED 时的目标地址
+///
+/// # 返回值
+/// - 成功: 返回映射新的起始地址
+/// - 失败: 返回 -errno
+///
+/// # 支持的特性
+/// - ✅ 原地缩小 / 原地扩大
+/// - ✅ MREMAP_MAYMOVE - 原地放不下时移动（只搬移页表项，不复制数据）
+/// - ✅ MREMAP_FIXED - 移动到指定地址，替换目标范围原有的映射
+/// - ✅ 锁定的映射在新地址上保持锁定，扩大后超过 RLIMIT_MEMLOCK 时返回 EAGAIN
+/// - ❌ MREMAP_DONTUNMAP
+/// - ❌ old_size 为 0 时复制共享映射
+pub fn mremap(
+    old_addr: *mut c_void,
+    old_size: usize,
+    new_size: usize,
+    flags: i32,
+    new_addr: *mut c_void,
+) -> isize {
+    let old_addr = old_addr as usize;
+    let Some(flags) = MremapFlags::from_bits(flags) else {
+        return -EINVAL as isize;
+    };
+
+    let memlock_limit = memlock_limit_pages();
+    let memory_space = current_memory_space();
+    let mut space = memory_space.lock();
+
+    // 原范围锁定时，新范围也锁定
+    let old_range = VpnRange::new(
+        Vpn::from_addr_floor(Vaddr::from_usize(old_addr)),
+        Vpn::from_addr_ceil(Vaddr::from_usize(old_addr.saturating_add(old_size))),
+    );
+    let old_locked = space.locked_pages_in(old_range);
+    if old_locked != 0 {
+        let new_locked = space.locked_pages() - old_locked + new_size.div_ceil(PAGE_SIZE);
+        if memlock_limit.is_some_and(|limit| new_locked > limit) {
+            return -EAGAIN as isize;
+        }
+    }
+
+    match space.mremap(old_addr, old_size, new_size, flags, new_addr as usize) {
+        Ok(addr) => {
+            if old_locked != 0 {
+                if let Err(e) = space.mlock(addr, new_size, true) {
+                    pr_warn!("mremap: failed to lock remapped range: {:?}", e);
+                }
+                return addr as isize;
+            }
+            // 移动或扩大后的私有页重新加入 LRU（旧地址的 LRU 项在回收时自然失效）
+            let start = Vpn::from_addr_floor(Vaddr::from_usize(addr));
+            let end = Vpn::from_addr_ceil(Vaddr::from_usize(addr + new_size));
+            let swappable = space.find_area(start).is_some_and(|a| a.is_swappable());
+            if swappable && (addr != old_addr || new_size > old_size) {
+                crate::mm::swap::lru_add_range(&memory_space, VpnRange::new(start, end));
+            }
+            addr as isize
+        }
+        Err(e) => {
+            pr_err!(
+                "mremap failed: {:?}, addr=0x{:x}, old=0x{:x}, new=0x{:x}, flags={:?}",
+                e,
+                old_addr,
+                old_size,
+                new_size,
+                flags
+            );
+            match e {
+                PagingError::NotMapped => -EFAULT as isize,
+                PagingError::OutOfMemory
+                | PagingError::FrameAllocFailed
+                | PagingError::AlreadyMapped => -ENOMEM as isize,
+                _ => -EINVAL as isize,
+            }
+        }
+    }
+}
+
+/// madvise - 向内核提供内存使用建议
+///
+/// # 参数
+/// - `addr`: 起始地址（必须页对齐）
+/// - `len`: 长度（字节），向上取整到页
+/// - `advice`: MADV_*
+///
+/// # 返回值
+/// - 成功: 返回 0
+/// - 失败: 返回 -errno
+///
+/// # 注意
+/// - 地址未页对齐或建议未知时返回 EINVAL
+/// - 范围内有未映射的地址时返回 ENOMEM，不做任何修改
+///
+/// # 支持的特性
+/// - ✅ MADV_DONTNEED - 立即丢弃页，之后访问时匿名页填零、文件映射重新读入
+/// - ✅ MADV_WILLNEED - 换入换出的页，重新填充被丢弃的页
+/// - ✅ MADV_FREE - 私有匿名页标记为可丢弃，内存紧张时直接释放，之前写入则保留
+/// - 锁定的页不能 MADV_DONTNEED / MADV_FREE，返回 EINVAL
+/// - ✅ MADV_MERGEABLE / UNMERGEABLE - 标记区域由 KSM 合并相同的页，取消时已合并的页重新复制
+/// - ✅ MADV_NORMAL / RANDOM / SEQUENTIAL / HUGEPAGE / NOHUGEPAGE / DONTDUMP / DODUMP - 接受但忽略
+pub fn madvise(addr: *mut c_void, len: usize, advice: i32) -> isize {
+    let start = addr as usize;
+
+    // 参数验证
+    if start % PAGE_SIZE != 0 {
+        return -EINVAL as isize;
+    }
+    let Some(advice) = MadviseAdvice::from_raw(advice) else {
+        return -EINVAL as isize;
+    };
+    if len == 0 {
+        return 0; // len=0 是合法的，什么都不做
+    }
+
+    let memory_space = current_memory_space();
+    let mut space = memory_space.lock();
+
+    let result = match advice {
+        MadviseAdvice::DontNeed => space.madvise_dontneed(start, len),
+        MadviseAdvice::WillNeed => space.madvise_willneed(start, len).map(|()| {
+            // 换入与重新填充的页重新加入 LRU
+            let vpn_range = VpnRange::new(
+                Vpn::from_addr_floor(Vaddr::from_usize(start)),
+                Vpn::from_addr_ceil(Vaddr::from_usize(start + len)),
+            );
+            if space
+                .find_area(vpn_range.start())
+                .is_some_and(|a| a.is_swappable())
+            {
+                crate::mm::swap::lru_add_range(&memory_space, vpn_range);
+            }
+        }),
+        MadviseAdvice::Free => space
+            .madvise_free(start, len)
+            .map(|marked| crate::mm::swap::lazyfree_add_pages(&memory_space, &marked)),
+        MadviseAdvice::Mergeable => space.madvise_mergeable(start, len, true),
+        MadviseAdvice::Unmergeable => space.madvise_mergeable(start, len, false),
+        _ => Ok(()),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            pr_err!(
+                "madvise failed: {:?}, addr=0x{:x}, len=0x{:x}, advice={:?}",
+                e,
+                start,
+                len,
+                advice
+            );
+            match e {
+                PagingError::NotMapped => -ENOMEM as isize,
+                PagingError::OutOfMemory | PagingError::FrameAllocFailed => -EAGAIN as isize,
+                _ => -EINVAL as isize,
+            }
+        }
+    }
+}
+
+/// RLIMIT_MEMLOCK 允许锁定的页数，有 CAP_IPC_LOCK 或不限制时为 `None`
+///
+/// 需要在锁地址空间之前调用。
+fn memlock_limit_pages() -> Option<usize> {
+    if capable(Capabilities::IPC_LOCK) {
+        return None;
+    }
+    let limit = current_task().lock().rlimit.lock().limits[ResourceId::Memlock as usize];
+    (limit.rlim_cur != RLIM_INFINITY).then_some(limit.rlim_cur / PAGE_SIZE)
+}
+
+/// mlock - 锁定内存，使其中的页不会被换出
+///
+/// # 参数
+/// - `addr`: 起始地址，向下对齐到页
+/// - `len`: 长度（字节），连同 `addr` 的页内偏移向上取整到页
+///
+/// # 返回值
+/// - 成功: 返回 0
+/// - 失败: 返回 -errno
+///
+/// # 注意
+/// - 范围内的页预先建立映射（换出的页换入）
+/// - 锁定的总页数超过 RLIMIT_MEMLOCK 时返回 ENOMEM（有 CAP_IPC_LOCK 时不限制），
+///   RLIMIT_MEMLOCK 为 0 时返回 EPERM
+/// - 范围内有未映射的地址时返回 ENOMEM，不做任何修改
+/// - 锁定不叠加：一次 munlock 解除范围内的所有锁定
+pub fn mlock(addr: *const c_void, len: usize) -> isize {
+    do_mlock(addr as usize, len, MlockFlags::empty())
+}
+
+/// mlock2 - 带标志的 mlock
+///
+/// # 参数
+/// - `flags`: MLOCK_ONFAULT - 不预先建立映射，页在第一次访问时锁定
+///
+/// 其余同 [`mlock`]，未知标志返回 EINVAL。
+pub fn mlock2(addr: *const c_void, len: usize, flags: u32) -> isize {
+    let Some(flags) = MlockFlags::from_bits(flags) else {
+        return -EINVAL as isize;
+    };
+    do_mlock(addr as usize, len, flags)
+}
+
+fn do_mlock(start: usize, len: usize, flags: MlockFlags) -> isize {
+    let Some(end) = start.checked_add(len) else {
+        return -EINVAL as isize;
+    };
+    if len == 0 {
+        return 0;
+    }
+    let memlock_limit = memlock_limit_pages();
+    if memlock_limit == Some(0) {
+        return -EPERM as isize;
+    }
+
+    let memory_space = current_memory_space();
+    let mut space = memory_space.lock();
+
+    // 已锁定的页不重复计数
+    let range = VpnRange::new(
+        Vpn::from_addr_floor(Vaddr::from_usize(start)),
+        Vpn::from_addr_ceil(Vaddr::from_usize(end)),
+    );
+    let locked = space.locked_pages() - space.locked_pages_in(range) + range.len();
+    if memlock_limit.is_some_and(|limit| locked > limit) {
+        return -ENOMEM as isize;
+    }
+
+    match space.mlock(start, len, !flags.contains(MlockFlags::ONFAULT)) {
+        Ok(()) => 0,
+        Err(e) => {
+            pr_err!(
+                "mlock failed: {:?}, addr=0x{:x}, len=0x{:x}, flags={:?}",
+                e,
+                start,
+                len,
+                flags
+            );
+            match e {
+                PagingError::NotMapped => -ENOMEM as isize,
+                PagingError::OutOfMemory | PagingError::FrameAllocFailed => -EAGAIN as isize,
+                _ => -EINVAL as isize,
+            }
+        }
+    }
+}
+
+/// munlock - 解除内存锁定
+///
+/// # 参数
+/// - `addr`: 起始地址，向下对齐到页
+/// - `len`: 长度（字节），连同 `addr` 的页内偏移向上取整到页
+///
+/// # 返回值
+/// - 成功: 返回 0
+/// - 失败: 返回 -errno（范围内有未映射的地址时返回 ENOMEM）
+pub fn munlock(addr: *const c_void, len: usize) -> isize {
+    let start = addr as usize;
+    let Some(end) = start.checked_add(len) else {
+        return -EINVAL as isize;
+    };
+    if len == 0 {
+        return 0;
+    }
+
+    let memory_space = current_memory_space();
+    let mut space = memory_space.lock();
+
+    match space.munlock(start, len) {
+        Ok(()) => {
+            // 解除锁定的私有页重新可以换出
+            let range = VpnRange::new(
+                Vpn::from_addr_floor(Vaddr::from_usize(start)),
+                Vpn::from_addr_ceil(Vaddr::from_usize(end)),
+            );
+            crate::mm::swap::lru_add_unlocked(&memory_space, &space, range);
+            0
+        }
+        Err(PagingError::NotMapped) => -ENOMEM as isize,
+        Err(_) => -EINVAL as isize,
+    }
+}
+
+/// mlockall - 锁定整个地址空间
+///
+/// # 参数
+/// - `flags`: MCL_CURRENT | MCL_FUTURE | MCL_ONFAULT
+///
+/// # 返回值
+/// - 成功: 返回 0
+/// - 失败: 返回 -errno
+///
+/// # 注意
+/// - 必须指定 MCL_CURRENT 或 MCL_FUTURE 之一，MCL_ONFAULT 不能单独使用，否则返回 EINVAL
+/// - MCL_CURRENT 锁定后的总页数超过 RLIMIT_MEMLOCK 时返回 ENOMEM，RLIMIT_MEMLOCK 为 0 时返回 EPERM
+/// - MCL_FUTURE 之后的 mmap / brk / mremap 超过 RLIMIT_MEMLOCK 时失败
+pub fn mlockall(flags: i32) -> isize {
+    let Some(flags) = MlockallFlags::from_bits(flags) else {
+        return -EINVAL as isize;
+    };
+    if !flags.intersects(MlockallFlags::CURRENT | MlockallFlags::FUTURE) {
+        return -EINVAL as isize;
+    }
+    let memlock_limit = memlock_limit_pages();
+    if memlock_limit == Some(0) {
+        return -EPERM as isize;
+    }
+
+    let memory_space = current_memory_space();
+    let mut space = memory_space.lock();
+
+    if flags.contains(MlockallFlags::CURRENT) {
+        let locked: usize = space
+            .lockable_ranges()
+            .into_iter()
+            .map(|r| r.len() - space.locked_pages_in(r))
+            .sum::<usize>()
+            + space.locked_pages();
+        if memlock_limit.is_some_and(|limit| locked > limit) {
+            return -ENOMEM as isize;
+        }
+    }
+
+    match space.mlockall(flags) {
+        Ok(()) => 0,
+        Err(e) => {
+            pr_err!("mlockall failed: {:?}, flags={:?}", e, flags);
+            match e {
+                PagingError::OutOfMemory | PagingError::FrameAllocFailed => -EAGAIN as isize,
+                _ => -ENOMEM as isize,
+            }
+        }
+    }
+}
+
+/// munlockall - 解除地址空间的所有锁定，并取消 MCL_FUTURE
+///
+/// # 返回值
+/// 总是返回 0
+pub fn munlockall() -> isize {
+    let memory_space = current_memory_space();
+    let mut space = memory_space.lock();
+    for range in space.munlockall() {
+        crate::mm::swap::lru_add_unlocked(&memory_space, &space, range);
+    }
+    0
+}
+
+/// userfaultfd - 创建把缺页交给用户态处理的 fd
+///
+/// # 参数
+/// - `flags`: O_CLOEXEC | O_NONBLOCK | UFFD_USER_MODE_ONLY
+///
+/// # 返回值
+/// - 成功: 返回新的 fd
+/// - 失败: 返回 -errno
+///
+/// # 注意
+/// - 未设置 UFFD_USER_MODE_ONLY 时需要 CAP_SYS_PTRACE，否则返回 EPERM
+///
+/// # 支持的特性
+/// - ✅ UFFDIO_API / REGISTER / UNREGISTER / WAKE / COPY / ZEROPAGE
+/// - ✅ UFFDIO_REGISTER_MODE_MISSING - 私有匿名映射中缺失页的缺页事件
+/// - ❌ UFFDIO_REGISTER_MODE_WP / MINOR、fork / remap 等非缺页事件
+/// - ❌ 内核态访问引起的缺页（直接填零）
+pub fn userfaultfd(flags: i32) -> isize {
+    crate::mm::userfaultfd::do_userfaultfd(flags)
+}
+
 /// mprotect - 修改内存区域的保护权限
 ///
 /// # 参数
@@ -378,6 +878,7 @@ pub fn munmap(addr: *mut c_void, len: usize) -> isize {
 /// - ✅ PROT_WRITE - 可写（自动包含可读，RISC-V 特性）
 /// - ✅ PROT_EXEC - 可执行
 /// - ✅ 跨多个映射区域的权限修改
+/// - ✅ W^X：PROT_WRITE | PROT_EXEC 需 `/proc/sys/vm/allow_wx` 为 1，否则返回 EACCES
 pub fn mprotect(addr: *mut c_void, len: usize, prot: i32) -> isize {
     let start = addr as usize;
 
@@ -395,6 +896,12 @@ pub fn mprotect(addr: *mut c_void, len: usize, prot: i32) -> isize {
     // 解析保护标志
     let prot_flags = ProtFlags::from_bits_truncate(prot);
 
+    // W^X：除非 vm.allow_wx 打开，否则拒绝同时可写可执行的映射
+    if prot_flags.contains(ProtFlags::WRITE | ProtFlags::EXEC) && !mm::wx::allow_wx() {
+        pr_warn!("mprotect: refusing PROT_WRITE|PROT_EXEC (vm.allow_wx=0)");
+        return -EACCES as isize;
+    }
+
     // 转换为页表标志
     let mut pte_flags = UniversalPTEFlag::USER_ACCESSIBLE | UniversalPTEFlag::VALID;
 
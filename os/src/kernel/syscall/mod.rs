@@ -22,19 +22,24 @@
 //! # 文件拆分
 //!
 //! 主要按领域拆分：
-//! - `io.rs`：read/write/readv/writev 等基础 I/O
+//! - `io.rs`：read/write/readv/writev 等基础 I/O，以及 poll/select
+//! - `epoll.rs`：epoll 实例的创建、控制与等待
+//! - `io_uring.rs`：io_uring 实例、SQ/CQ 共享环与请求的异步执行
 //! - `fs.rs` / `fcntl.rs` / `ioctl.rs`：文件系统与 fd 操作
 //! - `mm.rs`：内存管理相关
 //! - `ipc.rs` / `signal.rs`：进程间通信与信号
-//! - `task.rs` / `cred.rs`：任务管理与凭证相关
+//! - `task.rs` / `cred.rs`：任务管理与凭证相关（含 prctl / seccomp / landlock）
 //! - `network.rs`：socket/网络相关
 //! - `sys.rs`：uname/sysinfo/syslog 等系统信息类调用
+//! - `xattr.rs`：扩展属性的读取、设置、列举与删除
 
 #![allow(dead_code)]
 mod cred;
+mod epoll;
 mod fcntl;
 mod fs;
 pub mod io;
+mod io_uring;
 mod ioctl;
 mod ipc;
 mod mm;
@@ -43,6 +48,7 @@ mod signal;
 mod sys;
 mod task;
 mod util;
+mod xattr;
 
 // Allow in-kernel components (e.g. oscomp runner) to reuse wait4 logic without going through
 // the trapframe-based sys_* wrappers.
@@ -53,22 +59,28 @@ use core::ffi::{c_char, c_int, c_uint, c_ulong, c_void};
 use crate::{
     impl_syscall,
     uapi::{
+        epoll::EpollEvent,
+        fcntl::OpenHow,
         fs::LinuxStatFs,
         futex::RobustListHead,
+        io_uring::IoUringParams,
         iovec::IoVec,
         resource::{Rlimit, Rusage},
+        rseq::Rseq,
         signal::{SigInfoT, SignalAction},
         sysinfo::SysInfo,
-        time::{Itimerval, TimeSpec},
+        time::{Itimerval, TimeSpec, Timex, timeval, timezone},
         types::{SigSetT, SizeT, StackT},
         uts_namespace::UtsNamespace,
     },
     vfs::{Stat, Statx},
 };
 use cred::*;
+use epoll::*;
 use fcntl::*;
 use fs::*;
 use io::*;
+use io_uring::*;
 use ioctl::*;
 use ipc::*;
 use mm::*;
@@ -76,11 +88,52 @@ use network::*;
 use signal::*;
 use sys::*;
 use task::*;
+use xattr::*;
 
 // 系统调用实现注册
 // 分类顺序与 arch/riscv/syscall/syscall_number.rs 保持一致
 
 // 文件系统/目录操作 (Filesystem/Directory Operations)
+impl_syscall!(
+    sys_setxattr,
+    setxattr,
+    (*const c_char, *const c_char, *const u8, usize, u32)
+);
+impl_syscall!(
+    sys_lsetxattr,
+    lsetxattr,
+    (*const c_char, *const c_char, *const u8, usize, u32)
+);
+impl_syscall!(
+    sys_fsetxattr,
+    fsetxattr,
+    (usize, *const c_char, *const u8, usize, u32)
+);
+impl_syscall!(
+    sys_getxattr,
+    getxattr,
+    (*const c_char, *const c_char, *mut u8, usize)
+);
+impl_syscall!(
+    sys_lgetxattr,
+    lgetxattr,
+    (*const c_char, *const c_char, *mut u8, usize)
+);
+impl_syscall!(
+    sys_fgetxattr,
+    fgetxattr,
+    (usize, *const c_char, *mut u8, usize)
+);
+impl_syscall!(sys_listxattr, listxattr, (*const c_char, *mut u8, usize));
+impl_syscall!(sys_llistxattr, llistxattr, (*const c_char, *mut u8, usize));
+impl_syscall!(sys_flistxattr, flistxattr, (usize, *mut u8, usize));
+impl_syscall!(sys_removexattr, removexattr, (*const c_char, *const c_char));
+impl_syscall!(
+    sys_lremovexattr,
+    lremovexattr,
+    (*const c_char, *const c_char)
+);
+impl_syscall!(sys_fremovexattr, fremovexattr, (usize, *const c_char));
 impl_syscall!(sys_getcwd, getcwd, (*mut u8, usize));
 
 // Epoll & Duplication
@@ -98,6 +151,11 @@ impl_syscall!(
     symlinkat,
     (*const c_char, i32, *const c_char)
 );
+impl_syscall!(
+    sys_linkat,
+    linkat,
+    (i32, *const c_char, i32, *const c_char, u32)
+);
 
 // 挂载/文件系统信息 (Mount/Filesystem Info)
 impl_syscall!(sys_statfs, statfs, (*const c_char, *mut LinuxStatFs));
@@ -110,11 +168,24 @@ impl_syscall!(sys_fchownat, fchownat, (i32, *const c_char, u32, u32, u32));
 
 // 文件描述符操作 (File Descriptor Operations)
 impl_syscall!(sys_openat, openat, (i32, *const c_char, u32, u32));
+impl_syscall!(
+    sys_openat2,
+    openat2,
+    (i32, *const c_char, *const OpenHow, usize)
+);
 impl_syscall!(sys_close, close, (usize));
 impl_syscall!(sys_pipe2, pipe2, (*mut i32, u32));
+impl_syscall!(sys_eventfd2, eventfd2, (u32, u32));
 impl_syscall!(sys_getdents64, getdents64, (usize, *mut u8, usize));
 impl_syscall!(sys_lseek, lseek, (usize, isize, usize));
 impl_syscall!(sys_ftruncate, ftruncate, (usize, i64));
+impl_syscall!(sys_inotify_init1, inotify_init1, (u32));
+impl_syscall!(
+    sys_inotify_add_watch,
+    inotify_add_watch,
+    (usize, *const c_char, u32)
+);
+impl_syscall!(sys_inotify_rm_watch, inotify_rm_watch, (usize, i32));
 
 // I/O 操作 (Input/Output Operations)
 impl_syscall!(sys_read, read, (usize, *mut u8, usize));
@@ -126,6 +197,11 @@ impl_syscall!(sys_pwrite64, pwrite64, (usize, *const u8, usize, i64));
 impl_syscall!(sys_preadv, preadv, (usize, *const IoVec, usize, i64));
 impl_syscall!(sys_pwritev, pwritev, (usize, *const IoVec, usize, i64));
 impl_syscall!(sys_sendfile, sendfile, (usize, usize, *mut i64, usize));
+impl_syscall!(
+    sys_splice,
+    splice,
+    (usize, *mut i64, usize, *mut i64, usize, u32)
+);
 impl_syscall!(
     sys_pselect6,
     pselect6,
@@ -133,6 +209,17 @@ impl_syscall!(
 );
 impl_syscall!(sys_ppoll, ppoll, (usize, usize, usize, usize));
 impl_syscall!(sys_poll, poll, (usize, usize, i32));
+impl_syscall!(sys_epoll_create1, epoll_create1, (u32));
+impl_syscall!(
+    sys_epoll_ctl,
+    epoll_ctl,
+    (usize, i32, usize, *const EpollEvent)
+);
+impl_syscall!(
+    sys_epoll_pwait,
+    epoll_pwait,
+    (usize, *mut EpollEvent, i32, i32, usize, usize)
+);
 
 // 文件元数据与同步 (File Metadata and Synchronization)
 impl_syscall!(
@@ -165,6 +252,12 @@ impl_syscall!(sys_set_tid_address, set_tid_address, (*mut c_int));
 
 // 同步/休眠 (Synchronization/Sleeping)
 impl_syscall!(sys_nanosleep, nanosleep, (*const TimeSpec, *mut TimeSpec));
+impl_syscall!(
+    sys_clock_nanosleep,
+    clock_nanosleep,
+    (c_int, c_int, *const TimeSpec, *mut TimeSpec)
+);
+impl_syscall!(sys_restart_syscall, restart_syscall, ());
 impl_syscall!(
     sys_futex,
     futex,
@@ -180,6 +273,7 @@ impl_syscall!(
     get_robust_list,
     (c_int, *mut *mut RobustListHead, *mut SizeT)
 );
+impl_syscall!(sys_rseq, rseq, (*mut Rseq, u32, c_int, u32));
 impl_syscall!(sys_getitimmer, getitimer, (c_int, *mut Itimerval));
 impl_syscall!(
     sys_setitimmer,
@@ -191,6 +285,13 @@ impl_syscall!(
 impl_syscall!(sys_clock_settime, clock_settime, (c_int, *const TimeSpec));
 impl_syscall!(sys_clock_gettime, clock_gettime, (c_int, *mut TimeSpec));
 impl_syscall!(sys_clock_getres, clock_getres, (c_int, *mut TimeSpec));
+impl_syscall!(sys_clock_adjtime, clock_adjtime, (c_int, *mut Timex));
+impl_syscall!(sys_adjtimex, adjtimex, (*mut Timex));
+impl_syscall!(
+    sys_gettimeofday,
+    gettimeofday,
+    (*mut timeval, *mut timezone)
+);
 impl_syscall!(sys_syslog, syslog, (i32, *mut u8, i32));
 
 // 信号 (Signals)
@@ -243,6 +344,12 @@ impl_syscall!(sys_getgid, getgid, ());
 impl_syscall!(sys_getegid, getegid, ());
 impl_syscall!(sys_gettid, gettid, ());
 impl_syscall!(sys_sysinfo, sysinfo, (*mut SysInfo));
+impl_syscall!(sys_getcpu, getcpu, (*mut c_uint, *mut c_uint));
+impl_syscall!(
+    sys_prctl,
+    prctl,
+    (c_int, c_ulong, c_ulong, c_ulong, c_ulong)
+);
 
 // 网络 (Networking/Sockets)
 impl_syscall!(sys_socket, socket, (i32, i32, i32));
@@ -281,6 +388,7 @@ impl_syscall!(
         *mut c_void  // tls (a4)
     )
 );
+impl_syscall!(sys_unshare, unshare, (c_ulong));
 impl_syscall!(
     sys_execve,
     execve,
@@ -304,6 +412,18 @@ impl_syscall!(sys_brk, brk, (usize));
 impl_syscall!(sys_mmap, mmap, (*mut c_void, usize, i32, i32, i32, i64));
 impl_syscall!(sys_munmap, munmap, (*mut c_void, usize));
 impl_syscall!(sys_mprotect, mprotect, (*mut c_void, usize, i32));
+impl_syscall!(
+    sys_mremap,
+    mremap,
+    (*mut c_void, usize, usize, i32, *mut c_void)
+);
+impl_syscall!(sys_madvise, madvise, (*mut c_void, usize, i32));
+impl_syscall!(sys_mlock, mlock, (*const c_void, usize));
+impl_syscall!(sys_mlock2, mlock2, (*const c_void, usize, u32));
+impl_syscall!(sys_munlock, munlock, (*const c_void, usize));
+impl_syscall!(sys_mlockall, mlockall, (i32));
+impl_syscall!(sys_munlockall, munlockall, ());
+impl_syscall!(sys_userfaultfd, userfaultfd, (i32));
 
 // 文件系统同步 (续)
 impl_syscall!(sys_syncfs, syncfs, (usize));
@@ -332,6 +452,41 @@ impl_syscall!(
 // 随机数与内存文件
 impl_syscall!(sys_getrandom, getrandom, (*mut c_void, SizeT, c_uint));
 
+// 安全 (Security)
+impl_syscall!(sys_seccomp, seccomp, (c_uint, c_uint, *mut c_void));
+impl_syscall!(
+    sys_landlock_create_ruleset,
+    landlock_create_ruleset,
+    (*const c_void, SizeT, c_uint)
+);
+impl_syscall!(
+    sys_landlock_add_rule,
+    landlock_add_rule,
+    (c_int, c_uint, *const c_void, c_uint)
+);
+impl_syscall!(
+    sys_landlock_restrict_self,
+    landlock_restrict_self,
+    (c_int, c_uint)
+);
+
+// 异步 I/O (io_uring)
+impl_syscall!(
+    sys_io_uring_setup,
+    io_uring_setup,
+    (u32, *mut IoUringParams)
+);
+impl_syscall!(
+    sys_io_uring_enter,
+    io_uring_enter,
+    (usize, u32, u32, u32, usize, usize)
+);
+impl_syscall!(
+    sys_io_uring_register,
+    io_uring_register,
+    (usize, u32, usize, u32)
+);
+
 // 获取网络接口地址列表 (非标准系统调用)
 impl_syscall!(sys_getifaddrs, getifaddrs, (*mut *mut u8));
 impl_syscall!(sys_freeifaddrs, freeifaddrs, (*mut u8));
@@ -910,12 +910,14 @@ pub fn recv(sockfd: i32, buf: *mut u8, len: usize, _flags: i32) -> isize {
 /// 关闭套接字
 pub fn close_sock(sockfd: i32) -> isize {
     let task = current_task();
-    let mut task_lock = task.lock();
-    let tid = task_lock.tid;
+    let (tid, fd_table) = {
+        let task_lock = task.lock();
+        (task_lock.tid, task_lock.fd_table.clone())
+    };
 
     unregister_socket_fd(tid as usize, sockfd as usize);
 
-    match task_lock.fd_table.close(sockfd as usize) {
+    match fd_table.close(sockfd as usize) {
         Ok(_) => 0,
         Err(_) => -9,
     }
@@ -973,8 +975,9 @@ fn get_interface_stats(ifname: *const c_char, stats: *mut u8, size: usize) -> is
         None => return -(ENODEV as isize),
     };
 
-    // 获取设备统计信息
-    let _device = interface.device();
+    // 读取统计快照，不与收发路径争用
+    let if_stats = interface.stats();
+    drop(iface_manager);
 
     // 填充统计信息结构 (struct rtnl_link_stats64)
     // 结构体布局（简化版）：
@@ -986,9 +989,17 @@ fn get_interface_stats(ifname: *const c_char, stats: *mut u8, size: usize) -> is
     // offset 40:  tx_errors (u64)
     // offset 48:  rx_dropped (u64)
     // offset 56:  tx_dropped (u64)
-    // offset 64:  multicast (u64)
-    // offset 72:  collisions (u64)
-    // ... 更多字段
+    // ... 更多字段（未统计，保持为零）
+    let fields = [
+        if_stats.rx_packets,
+        if_stats.tx_packets,
+        if_stats.rx_bytes,
+        if_stats.tx_bytes,
+        if_stats.rx_errors,
+        if_stats.tx_errors,
+        if_stats.rx_dropped,
+        if_stats.tx_dropped,
+    ];
 
     unsafe {
         let stats_slice = core::slice::from_raw_parts_mut(stats, size);
@@ -996,16 +1007,9 @@ fn get_interface_stats(ifname: *const c_char, stats: *mut u8, size: usize) -> is
         // 清零整个结构
         stats_slice.fill(0);
 
-        // TODO: 当 NetDevice trait 扩展后，从设备获取真实的统计数据
-        // 目前返回零值表示统计信息不可用
-        //
-        // 未来的实现示例：
-        // let stats_ptr = stats as *mut u64;
-        // *stats_ptr.add(0) = device.get_rx_packets();
-        // *stats_ptr.add(1) = device.get_tx_packets();
-        // *stats_ptr.add(2) = device.get_rx_bytes();
-        // *stats_ptr.add(3) = device.get_tx_bytes();
-        // ... 等等
+        for (chunk, value) in stats_slice.chunks_exact_mut(8).zip(fields) {
+            chunk.copy_from_slice(&value.to_ne_bytes());
+        }
     }
 
     0 // 成功
@@ -1357,7 +1361,9 @@ pub fn setsockopt(sockfd: i32, level: i32, optname: i32, optval: *const u8, optl
     use uapi::errno::{EBADF, EINVAL, ENOPROTOOPT, ENOTSOCK};
     use uapi::socket::*;
 
-    if sockfd < 0 || optval.is_null() {
+    // SO_DETACH_FILTER 不使用 optval
+    let detach = level == SOL_SOCKET && optname == SO_DETACH_FILTER;
+    if sockfd < 0 || (optval.is_null() && !detach) {
         return -(EINVAL as isize);
     }
 
@@ -1375,6 +1381,10 @@ pub fn setsockopt(sockfd: i32, level: i32, optname: i32, optval: *const u8, optl
         None => return -(ENOTSOCK as isize),
     };
 
+    if level == SOL_SOCKET && (optname == SO_ATTACH_FILTER || detach) {
+        return set_socket_filter(socket_file, detach, optval, optlen);
+    }
+
     let mut opts = socket_file.get_socket_options();
 
     {
@@ -1417,6 +1427,36 @@ pub fn setsockopt(sockfd: i32, level: i32, optname: i32, optval: *const u8, optl
     0
 }
 
+/// 处理 SO_ATTACH_FILTER / SO_DETACH_FILTER
+fn set_socket_filter(
+    socket_file: &crate::net::socket::SocketFile,
+    detach: bool,
+    optval: *const u8,
+    optlen: u32,
+) -> isize {
+    use uapi::errno::{EINVAL, ENOENT};
+    use uapi::filter::SockFprog;
+
+    if detach {
+        return if socket_file.detach_filter() {
+            0
+        } else {
+            -(ENOENT as isize)
+        };
+    }
+    if (optlen as usize) < core::mem::size_of::<SockFprog>() {
+        return -(EINVAL as isize);
+    }
+    let prog = match crate::security::seccomp::copy_fprog_from_user(optval as *const SockFprog) {
+        Ok(prog) => prog,
+        Err(e) => return -(e as isize),
+    };
+    match socket_file.attach_filter(prog) {
+        Ok(()) => 0,
+        Err(e) => e.to_errno(),
+    }
+}
+
 // 获取网络接口配置
 pub fn getsockopt(
     sockfd: i32,
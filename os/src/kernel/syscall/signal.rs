@@ -8,24 +8,25 @@ use core::{
 use alloc::{sync::Arc, vec::Vec};
 
 use crate::{
-    arch::{timer::clock_freq, trap::restore},
-    ipc::{create_siginfo_for_signal, do_sigpending},
+    arch::trap::restore,
+    ipc::{RestartBlock, create_siginfo_for_signal, do_sigpending},
     kernel::{
-        SharedTask, TASK_MANAGER, TIMER_QUEUE, TaskManagerTrait, current_task,
-        sleep_task_with_guard_and_block, yield_task,
+        SharedTask, TASK_MANAGER, TaskManagerTrait, current_task,
+        hrtimer::{ktime_get, timespec_to_ktime},
+        sleep_task_with_guard_and_block, wake_task_at, yield_task,
     },
     sync::SpinLock,
     uapi::{
         errno::{EAGAIN, EINTR, EINVAL, ENOMEM, ENOSYS, ESRCH},
         signal::{
-            MINSIGSTKSZ, NSIG, RtSigFrame, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK, SIGSET_SIZE,
-            SS_AUTODISARM, SS_DISABLE, SS_ONSTACK, SaFlags, SigInfoT, SignalAction, SignalFlags,
-            UContextT,
+            MINSIGSTKSZ, NSIG, NUM_SIGSEGV, RtSigFrame, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK,
+            SIGSET_SIZE, SS_AUTODISARM, SS_DISABLE, SS_ONSTACK, SaFlags, SigInfoT, SignalAction,
+            SignalFlags, UContextT,
         },
         time::TimeSpec,
         types::{SigSetT, StackT},
     },
-    util::user_buffer::{read_from_user, write_to_user},
+    util::user_buffer::{copy_from_user, copy_to_user},
 };
 
 /// 修改当前任务的信号屏蔽字
@@ -50,13 +51,16 @@ pub fn rt_sigprocmask(
 
     if !oset.is_null() {
         let old_set = t.blocked.bits() as c_ulong;
-        unsafe {
-            write_to_user(oset, old_set);
+        if let Err(e) = copy_to_user(oset, old_set) {
+            return -e;
         }
     }
 
     if !set.is_null() {
-        let new_set = unsafe { read_from_user(set) };
+        let new_set = match copy_from_user(set) {
+            Ok(s) => s,
+            Err(e) => return -e,
+        };
         let new_flags = if let Some(flag) = SignalFlags::from_bits(new_set as usize) {
             flag
         } else {
@@ -90,10 +94,10 @@ pub fn rt_sigpending(uset: *mut SigSetT, sigsetsize: c_uint) -> c_int {
         return -EINVAL;
     }
     let pending = do_sigpending();
-    unsafe {
-        write_to_user(uset, pending.bits() as SigSetT);
+    match copy_to_user(uset, pending.bits() as SigSetT) {
+        Ok(()) => 0,
+        Err(e) => -e,
     }
-    0
 }
 
 /// 更改指定信号的处理动作
@@ -114,13 +118,16 @@ pub fn rt_sigaction(signum: c_int, act: *const SignalAction, oldact: *mut Signal
 
     if !oldact.is_null() {
         let current_action = t.signal_handlers.lock().actions[signum as usize].clone();
-        unsafe {
-            write_to_user(oldact, current_action);
+        if let Err(e) = copy_to_user(oldact, current_action) {
+            return -e;
         }
     }
 
     if !act.is_null() {
-        let mut new_action = unsafe { read_from_user(act) };
+        let mut new_action = match copy_from_user(act) {
+            Ok(a) => a,
+            Err(e) => return -e,
+        };
         // Linux ABI compatibility:
         // - libc may pass SA_RESTORER and/or reserved bits.
         // - Rejecting unknown bits causes musl netperf to fail with EINVAL.
@@ -157,7 +164,10 @@ pub fn rt_sigtimedwait(
         return -EINVAL;
     }
 
-    let wait_set_bits = unsafe { read_from_user(set) };
+    let wait_set_bits = match copy_from_user(set) {
+        Ok(s) => s,
+        Err(e) => return -e,
+    };
     let wait_set = if let Some(flags) = SignalFlags::from_bits(wait_set_bits as usize) {
         flags
     } else {
@@ -165,8 +175,10 @@ pub fn rt_sigtimedwait(
     };
 
     let timeout_opt = if !timeout.is_null() {
-        let ts = unsafe { read_from_user(timeout) };
-        Some(ts)
+        match copy_from_user(timeout) {
+            Ok(ts) => Some(ts),
+            Err(e) => return -e,
+        }
     } else {
         None
     };
@@ -174,8 +186,8 @@ pub fn rt_sigtimedwait(
     match wait_for_signal(current_task(), wait_set, timeout_opt) {
         Ok((sig_num, sig_info)) => {
             if !info.is_null() {
-                unsafe {
-                    write_to_user(info, sig_info);
+                if let Err(e) = copy_to_user(info, sig_info) {
+                    return -e;
                 }
             }
             sig_num as c_int
@@ -199,7 +211,10 @@ pub fn rt_sigsuspend(unewset: *const SigSetT, sigsetsize: c_uint) -> c_int {
     if sigsetsize as usize != SIGSET_SIZE {
         return -EINVAL;
     }
-    let new_set_bits = unsafe { read_from_user(unewset) };
+    let new_set_bits = match copy_from_user(unewset) {
+        Ok(s) => s,
+        Err(e) => return -e,
+    };
     let new_set = if let Some(flags) = SignalFlags::from_bits(new_set_bits as usize) {
         flags
     } else {
@@ -231,7 +246,11 @@ pub fn rt_sigreturn() -> ! {
     // Linux ABI: SP points to rt_sigframe { siginfo, ucontext }.
     let frame_addr = tf.get_sp();
     let ucontext_addr = frame_addr + core::mem::offset_of!(RtSigFrame, uc);
-    let ucontext: UContextT = unsafe { read_from_user(ucontext_addr as *const UContextT) };
+    // 信号帧无法读取时与 Linux 一致，以 SIGSEGV 终止任务
+    let ucontext: UContextT = match copy_from_user(ucontext_addr as *const UContextT) {
+        Ok(uc) => uc,
+        Err(_) => crate::kernel::terminate_task(128 + NUM_SIGSEGV),
+    };
 
     // Restore blocked mask from saved ucontext.
     {
@@ -258,13 +277,16 @@ pub fn signal_stack(uss: *const StackT, uoss: *mut StackT) -> c_int {
 
     if !uoss.is_null() {
         let old_ss = t.signal_stack.lock().clone();
-        unsafe {
-            write_to_user(uoss, old_ss);
+        if let Err(e) = copy_to_user(uoss, old_ss) {
+            return -e;
         }
     }
 
     if !uss.is_null() {
-        let new_ss = unsafe { read_from_user(uss) };
+        let new_ss = match copy_from_user(uss) {
+            Ok(s) => s,
+            Err(e) => return -e,
+        };
         if new_ss.ss_size < MINSIGSTKSZ as u64 {
             return -ENOMEM;
         }
@@ -417,23 +439,20 @@ fn wait_for_signal(
             }
         } else {
             // 带超时的阻塞等待
-            let start = crate::time_ext::timespec_now();
+            let deadline = ktime_get().saturating_add(timespec_to_ktime(&timeout));
             while !t.pending.has_deliverable_signal(signal)
                 && !t.shared_pending.lock().has_deliverable_signal(signal)
             {
-                let now = crate::time_ext::timespec_now();
-                if now - start > timeout {
+                if ktime_get() >= deadline {
                     return Err(-EAGAIN); // 超时返回
                 }
-                TIMER_QUEUE
-                    .lock()
-                    .push(timeout.into_freq(clock_freq()), task.clone());
                 sleep_task_with_guard_and_block(&mut t, task.clone(), true);
+                let timer = wake_task_at(task.clone(), deadline);
                 drop(t);
                 yield_task();
+                timer.cancel();
                 t = task.lock();
             }
-            TIMER_QUEUE.lock().remove_task(&task);
             let flag = t
                 .pending
                 .first_deliverable_signal(signal)
@@ -466,3 +485,19 @@ fn wait_for_signal(
         return Ok((sig_num, create_siginfo_for_signal(flag)));
     }
 }
+
+/// 继续执行被信号打断的系统调用
+/// # 说明:
+/// 被打断的系统调用登记了 restart_block 且没有运行用户处理函数时，
+/// 内核把返回地址退回系统调用指令并把调用号改为 restart_syscall，由这里接着执行。
+/// # 返回值:
+/// - 被继续执行的系统调用的返回值；没有登记 restart_block 时返回 `-EINTR`
+pub fn restart_syscall() -> c_int {
+    let block = current_task().lock().restart_block.take();
+    match block {
+        Some(RestartBlock::Nanosleep { deadline, rem }) => {
+            super::task::nanosleep_restart(deadline, rem)
+        }
+        None => -EINTR,
+    }
+}
@@ -1,46 +1,48 @@
 //! 系统相关系统调用实现
 
-use core::{
-    ffi::{c_char, c_int, c_long, c_uint, c_ulong, c_void},
-    sync::atomic::Ordering,
-};
+use core::ffi::{c_char, c_int, c_long, c_uint, c_ulong, c_void};
+
+use alloc::vec;
 
 use crate::{
-    arch::{
-        constant::USER_TOP,
-        lib::sbi::shutdown,
-        timer::{TICKS_PER_SEC, TIMER_TICKS, clock_freq},
-        trap::SumGuard,
-    },
+    arch::{constant::USER_TOP, lib::sbi::shutdown, timer::clock_freq},
     kernel::{
-        current_task,
+        Capabilities, TASK_MANAGER, TaskManagerTrait, capable, current_task,
+        hrtimer::{NSEC_PER_SEC, ktime_get, ktime_to_timespec},
+        ntp,
         syscall::util::{check_syslog_permission, validate_syslog_args},
         time::update_realtime,
     },
     log::{
-        DEFAULT_CONSOLE_LEVEL, LogLevel, format_log_entry, get_console_level, read_log,
-        set_console_level,
+        DEFAULT_CONSOLE_LEVEL, LOG_BUFFER_SIZE, LogLevel, clear_log, get_console_level, log_len,
+        read_all_log_into, read_log_into, set_console_level,
     },
     pr_alert,
-    security::{BiogasPoll, EntropyPool},
+    security::random,
     uapi::{
-        errno::{EINVAL, ENOSYS},
+        errno::{EAGAIN, EFAULT, EINTR, EINVAL, ENOSYS, EOPNOTSUPP},
         log::SyslogAction,
         reboot::{
             REBOOT_CMD_POWER_OFF, REBOOT_MAGIC1, REBOOT_MAGIC2, REBOOT_MAGIC2A, REBOOT_MAGIC2B,
             REBOOT_MAGIC2C,
         },
         sysinfo::SysInfo,
-        time::clock_id::{
-            CLOCK_MONOTONIC, CLOCK_MONOTONIC_COARSE, CLOCK_MONOTONIC_RAW, CLOCK_REALTIME,
-            CLOCK_REALTIME_COARSE, MAX_CLOCKS,
+        time::{
+            Timex,
+            clock_id::{
+                CLOCK_BOOTTIME, CLOCK_MONOTONIC, CLOCK_MONOTONIC_COARSE, CLOCK_MONOTONIC_RAW,
+                CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME, CLOCK_REALTIME_COARSE,
+                CLOCK_THREAD_CPUTIME_ID, MAX_CLOCKS,
+            },
+            cpuclock::{CPUCLOCK_CLOCK_MASK, CPUCLOCK_MAX, CPUCLOCK_PERTHREAD_MASK, cpuclock_pid},
+            timeval, timezone,
         },
         types::SizeT,
         uts_namespace::{UTS_NAME_LEN, UtsNamespace},
     },
     util::{
         cstr_copy,
-        user_buffer::{UserBuffer, write_to_user},
+        user_buffer::{UserBuffer, copy_from_user, copy_to_user},
     },
     vfs::TimeSpec,
 };
@@ -93,12 +95,11 @@ pub fn uname(buf: *mut UtsNamespace) -> c_int {
         let t = task.lock();
         t.uts_namespace.clone()
     };
-    let uts_lock = uts.lock();
-    unsafe {
-        write_to_user(buf, uts_lock.clone());
+    let uts_name = uts.lock().clone();
+    match copy_to_user(buf, uts_name) {
+        Ok(()) => 0,
+        Err(e) => -e,
     }
-    0
-    // TODO: EPERM 和 EFAULT
 }
 
 /// 设置主机名系统调用
@@ -135,11 +136,11 @@ pub fn set_hostname(name: *const c_char, len: usize) -> c_int {
 pub fn sysinfo(info: *mut SysInfo) -> c_int {
     // TODO: 填充更多系统信息字段
     let mut sys_info = SysInfo::new();
-    sys_info.uptime = (TIMER_TICKS.load(Ordering::SeqCst) / TICKS_PER_SEC) as c_long;
-    unsafe {
-        write_to_user(info, sys_info);
+    sys_info.uptime = (ktime_get() / NSEC_PER_SEC) as c_long;
+    match copy_to_user(info, sys_info) {
+        Ok(()) => 0,
+        Err(e) => -e,
     }
-    0
 }
 
 /// 获取指定时钟的时间系统调用
@@ -152,10 +153,17 @@ pub fn sysinfo(info: *mut SysInfo) -> c_int {
 pub fn clock_gettime(clk_id: c_int, tp: *mut TimeSpec) -> c_int {
     let ts = match clk_id {
         CLOCK_REALTIME | CLOCK_REALTIME_COARSE => crate::time_ext::timespec_now(),
-        CLOCK_MONOTONIC | CLOCK_MONOTONIC_COARSE | CLOCK_MONOTONIC_RAW => {
+        // 内核不会休眠，启动时间与单调时间相同；NTP 只校准墙上时钟，原始单调时钟同样如此
+        CLOCK_MONOTONIC | CLOCK_MONOTONIC_COARSE | CLOCK_MONOTONIC_RAW | CLOCK_BOOTTIME => {
             crate::time_ext::timespec_monotonic_now()
         }
-        id if id < MAX_CLOCKS as c_int && id >= 0 => {
+        id if id < 0 || id == CLOCK_PROCESS_CPUTIME_ID || id == CLOCK_THREAD_CPUTIME_ID => {
+            match cpu_clock_now(id) {
+                Ok(ts) => ts,
+                Err(e) => return -e,
+            }
+        }
+        id if id < MAX_CLOCKS as c_int => {
             return -ENOSYS;
         }
         _ => {
@@ -163,10 +171,102 @@ pub fn clock_gettime(clk_id: c_int, tp: *mut TimeSpec) -> c_int {
         }
     };
 
-    unsafe {
-        write_to_user(tp, ts);
+    match copy_to_user(tp, ts) {
+        Ok(()) => 0,
+        Err(e) => -e,
+    }
+}
+
+/// 读取 CPU 时间时钟
+///
+/// 支持 CLOCK_PROCESS_CPUTIME_ID、CLOCK_THREAD_CPUTIME_ID，以及
+/// clock_getcpuclockid / pthread_getcpuclockid 返回的负数时钟 ID。
+/// 内核不区分用户态与内核态时间，三种 CPU 时钟类型都返回总运行时间。
+fn cpu_clock_now(clk_id: c_int) -> Result<TimeSpec, i32> {
+    let now = ktime_get();
+    let current = current_task();
+    let (task, thread) = match clk_id {
+        CLOCK_PROCESS_CPUTIME_ID => (current, false),
+        CLOCK_THREAD_CPUTIME_ID => (current, true),
+        id => {
+            if id & CPUCLOCK_CLOCK_MASK == CPUCLOCK_MAX {
+                return Err(EINVAL);
+            }
+            let thread = id & CPUCLOCK_PERTHREAD_MASK != 0;
+            let pid = cpuclock_pid(id);
+            if pid == 0 {
+                (current, thread)
+            } else {
+                let task = TASK_MANAGER.lock().get_task(pid).ok_or(EINVAL)?;
+                let caller_pid = current.lock().pid;
+                let t = task.lock();
+                // 线程时钟只能读取同一线程组内的线程，进程时钟必须指向主线程
+                let valid = if thread {
+                    t.pid == caller_pid
+                } else {
+                    t.is_process()
+                };
+                if !valid {
+                    return Err(EINVAL);
+                }
+                drop(t);
+                (task, thread)
+            }
+        }
+    };
+
+    let t = task.lock();
+    let ns = if thread {
+        t.thread_cputime(now)
+    } else {
+        t.process_cputime(now)
+    };
+    Ok(ktime_to_timespec(ns))
+}
+
+/// 获取墙上时钟时间（微秒精度）系统调用
+/// # 参数
+/// * `tv` - 指向用户空间 timeval 结构体的指针，可以为空
+/// * `tz` - 指向用户空间 timezone 结构体的指针，可以为空；内核不维护时区，总是填 0
+/// # 返回值
+/// * **成功**：返回 0
+pub fn gettimeofday(tv: *mut timeval, tz: *mut timezone) -> c_int {
+    if !tv.is_null() {
+        let tv_now = crate::time_ext::timespec_now().to_timeval();
+        if let Err(e) = copy_to_user(tv, tv_now) {
+            return -e;
+        }
     }
+    if !tz.is_null() {
+        let tz_now = timezone {
+            tz_minuteswest: 0,
+            tz_dsttime: 0,
+        };
+        if let Err(e) = copy_to_user(tz, tz_now) {
+            return -e;
+        }
+    }
+    0
+}
 
+/// 获取当前所在 CPU 与 NUMA 节点系统调用
+/// # 参数
+/// * `cpu` - 用于存储 CPU 编号的用户指针，可以为空
+/// * `node` - 用于存储 NUMA 节点编号的用户指针，可以为空；当前只有一个节点
+/// # 返回值
+/// * **成功**：返回 0
+pub fn getcpu(cpu: *mut c_uint, node: *mut c_uint) -> c_int {
+    if !cpu.is_null() {
+        let id = crate::arch::kernel::cpu::cpu_id() as c_uint;
+        if let Err(e) = copy_to_user(cpu, id) {
+            return -e;
+        }
+    }
+    if !node.is_null() {
+        if let Err(e) = copy_to_user(node, 0 as c_uint) {
+            return -e;
+        }
+    }
     0
 }
 
@@ -180,7 +280,10 @@ pub fn clock_gettime(clk_id: c_int, tp: *mut TimeSpec) -> c_int {
 pub fn clock_settime(clk_id: c_int, tp: *const TimeSpec) -> c_int {
     match clk_id {
         CLOCK_REALTIME | CLOCK_REALTIME_COARSE => {
-            let ts: TimeSpec = unsafe { core::ptr::read(tp) };
+            let ts = match copy_from_user(tp) {
+                Ok(ts) => ts,
+                Err(e) => return -e,
+            };
             update_realtime(&ts);
             0
         }
@@ -193,6 +296,45 @@ pub fn clock_settime(clk_id: c_int, tp: *const TimeSpec) -> c_int {
     }
 }
 
+/// 读取或调整墙上时钟校准参数系统调用
+/// # 参数
+/// * `txc` - 指向用户空间 Timex 结构体的指针，返回时被填充为当前状态
+/// # 返回值
+/// * **成功**：返回时钟状态（TIME_OK 或 TIME_ERROR）
+/// * **失败**：返回负的 errno
+///   - `-EPERM`: 修改参数但没有 CAP_SYS_TIME
+///   - `-EINVAL`: 参数无效
+pub fn adjtimex(txc: *mut Timex) -> c_int {
+    clock_adjtime(CLOCK_REALTIME, txc)
+}
+
+/// 读取或调整指定时钟的校准参数系统调用
+/// # 参数
+/// * `clk_id` - 时钟 ID，目前只有 CLOCK_REALTIME 可以调整
+/// * `txc` - 指向用户空间 Timex 结构体的指针，返回时被填充为当前状态
+/// # 返回值
+/// * **成功**：返回时钟状态（TIME_OK 或 TIME_ERROR）
+/// * **失败**：返回负的 errno
+pub fn clock_adjtime(clk_id: c_int, txc: *mut Timex) -> c_int {
+    match clk_id {
+        CLOCK_REALTIME => {}
+        id if id < MAX_CLOCKS as c_int && id >= 0 => return -EOPNOTSUPP,
+        _ => return -EINVAL,
+    }
+    let mut timex = match copy_from_user(txc) {
+        Ok(t) => t,
+        Err(e) => return -e,
+    };
+    let privileged = capable(Capabilities::SYS_TIME);
+    match ntp::adjtimex(&mut timex, privileged) {
+        Ok(state) => match copy_to_user(txc, timex) {
+            Ok(()) => state,
+            Err(e) => -e,
+        },
+        Err(e) => -e,
+    }
+}
+
 /// 获取指定时钟的分辨率系统调用
 /// # 参数
 /// * `clk_id` - 时钟 ID（如 CLOCK_REALTIME）
@@ -206,10 +348,18 @@ pub fn clock_getres(clk_id: c_int, tp: *mut TimeSpec) -> c_int {
             tv_sec: 0,
             tv_nsec: 1_000_000_000 / (clock_freq() as c_long),
         },
-        CLOCK_MONOTONIC | CLOCK_MONOTONIC_COARSE | CLOCK_MONOTONIC_RAW => TimeSpec {
-            tv_sec: 0,
-            tv_nsec: 1_000_000_000 / (clock_freq() as c_long),
-        },
+        CLOCK_MONOTONIC | CLOCK_MONOTONIC_COARSE | CLOCK_MONOTONIC_RAW | CLOCK_BOOTTIME => {
+            TimeSpec {
+                tv_sec: 0,
+                tv_nsec: 1_000_000_000 / (clock_freq() as c_long),
+            }
+        }
+        id if id < 0 || id == CLOCK_PROCESS_CPUTIME_ID || id == CLOCK_THREAD_CPUTIME_ID => {
+            if let Err(e) = cpu_clock_now(id) {
+                return -e;
+            }
+            TimeSpec::new(0, 1)
+        }
         id if id < MAX_CLOCKS as c_int && id >= 0 => {
             return -ENOSYS;
         }
@@ -218,11 +368,14 @@ pub fn clock_getres(clk_id: c_int, tp: *mut TimeSpec) -> c_int {
         }
     };
 
-    unsafe {
-        write_to_user(tp, res);
+    // tp 为空时只检查时钟 ID
+    if tp.is_null() {
+        return 0;
+    }
+    match copy_to_user(tp, res) {
+        Ok(()) => 0,
+        Err(e) => -e,
     }
-
-    0
 }
 
 /// 读取和控制内核日志缓冲区
@@ -259,166 +412,34 @@ pub fn syslog(type_: i32, bufp: *mut u8, len: i32) -> isize {
     }
 
     match action {
-        // 破坏性读取操作
+        // 破坏性读取：没有未读日志时阻塞，只返回完整的日志行
         SyslogAction::Read => {
-            // Read: 破坏性读取（移除已读条目）
-            let buf_len = len as usize;
-            let mut total_written = 0;
-
-            // 开启用户空间访问
-            let _guard = SumGuard::new();
-
-            while total_written < buf_len {
-                // TODO: 暂时移除，等待信号系统实现
-                /*
-                // 检查信号中断
-                if has_pending_signal() {
-                    if total_written == 0 {
-                        // 未读取任何数据，返回 EINTR
-                        return -(EINTR as isize);
-                    } else {
-                        // 已读取部分数据，返回已读字节数
-                        break;
-                    }
-                }
-                */
-
-                // 读取下一条日志
-                let entry = match read_log() {
-                    Some(e) => e,
-                    None => break, // 没有更多日志
-                };
-
-                // 格式化日志条目
-                let formatted = format_log_entry(&entry);
-                let bytes = formatted.as_bytes();
-
-                // 检查剩余空间
-                if total_written + bytes.len() > buf_len {
-                    // 缓冲区不足，停止读取
-                    // 注意：此条目已从缓冲区移除但未返回给用户
-                    // 这是 Linux 的行为
-                    break;
-                }
-
-                // 复制到用户空间
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        bytes.as_ptr(),
-                        bufp.add(total_written),
-                        bytes.len(),
-                    );
+            if len == 0 {
+                return 0;
+            }
+            while log_len() == 0 {
+                let task = current_task();
+                if crate::ipc::signal_interrupts_syscall(&task) {
+                    return -(EINTR as isize);
                 }
-
-                total_written += bytes.len();
+                crate::kernel::yield_task();
             }
-
-            // 关闭用户空间访问
-
-            total_written as isize
+            syslog_copy_out(bufp, len as usize, read_log_into)
         }
 
-        // 非破坏性读取操作
+        // 非破坏性读取：返回清除点之后最新的日志
         SyslogAction::ReadAll => {
-            // ReadAll: 非破坏性读取（保留条目）
-            use crate::log::{log_reader_index, log_writer_index, peek_log};
-
-            let buf_len = len as usize;
-            let mut total_written = 0;
-
-            // 获取当前可读范围
-            let start_index = log_reader_index();
-            let end_index = log_writer_index();
-
-            // 开启用户空间访问
-            let _guard = SumGuard::new();
-
-            // 遍历所有可用的日志条目
-            let mut current_index = start_index;
-            while current_index < end_index && total_written < buf_len {
-                // TODO: 暂时移除，等待信号系统实现
-                /*
-                // 检查信号中断
-                if has_pending_signal() {
-                    if total_written == 0 {
-                        return -(EINTR as isize);
-                    } else {
-                        break;
-                    }
-                }
-                */
-
-                let entry = match peek_log(current_index) {
-                    Some(e) => e,
-                    None => break, // 条目已被覆盖或无效
-                };
-
-                let formatted = format_log_entry(&entry);
-                let bytes = formatted.as_bytes();
-
-                if total_written + bytes.len() > buf_len {
-                    // 缓冲区不足，停止读取
-                    break;
-                }
-
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        bytes.as_ptr(),
-                        bufp.add(total_written),
-                        bytes.len(),
-                    );
-                }
-
-                total_written += bytes.len();
-                current_index += 1;
-            }
-
-            // 关闭用户空间访问
-
-            total_written as isize
+            syslog_copy_out(bufp, len as usize, |buf| read_all_log_into(buf, false))
         }
 
+        // 与 ReadAll 相同，随后清除已返回的日志
         SyslogAction::ReadClear => {
-            // 先读取所有日志（使用与 ReadAll 相同的逻辑）
-            let buf_len = len as usize;
-            let mut total_written = 0;
-
-            let _guard = SumGuard::new();
-
-            while total_written < buf_len {
-                let entry = match read_log() {
-                    Some(e) => e,
-                    None => break,
-                };
-
-                let formatted = format_log_entry(&entry);
-                let bytes = formatted.as_bytes();
-
-                if total_written + bytes.len() > buf_len {
-                    break;
-                }
-
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        bytes.as_ptr(),
-                        bufp.add(total_written),
-                        bytes.len(),
-                    );
-                }
-
-                total_written += bytes.len();
-            }
-
-            // 清空剩余的日志
-            while read_log().is_some() {}
-
-            total_written as isize
+            syslog_copy_out(bufp, len as usize, |buf| read_all_log_into(buf, true))
         }
 
-        // 缓冲区控制
+        // 清除：只推进非破坏性读取的起点，不丢弃未读日志
         SyslogAction::Clear => {
-            // 清空日志缓冲区
-            while read_log().is_some() {}
+            clear_log();
             0
         }
 
@@ -479,8 +500,7 @@ pub fn syslog(type_: i32, bufp: *mut u8, len: i32) -> isize {
 
         SyslogAction::SizeBuffer => {
             // 返回日志缓冲区的总大小
-            use crate::log::GLOBAL_LOG_BUFFER_SIZE;
-            GLOBAL_LOG_BUFFER_SIZE as isize
+            LOG_BUFFER_SIZE as isize
         }
 
         // 空操作（这些操作在 Linux 中也是 NOP）
@@ -488,43 +508,80 @@ pub fn syslog(type_: i32, bufp: *mut u8, len: i32) -> isize {
     }
 }
 
+/// 格式化后的全部日志不会超过环形缓冲区大小的两倍，内核缓冲区按此封顶
+const SYSLOG_COPY_MAX: usize = 2 * LOG_BUFFER_SIZE;
+
+/// 用 `fill` 把日志写入内核缓冲区，再复制到用户缓冲区 `bufp`
+///
+/// 返回复制的字节数；用户缓冲区越出用户空间时返回 `-EFAULT`。
+fn syslog_copy_out(bufp: *mut u8, len: usize, fill: impl FnOnce(&mut [u8]) -> usize) -> isize {
+    let len = len.min(SYSLOG_COPY_MAX);
+    if len == 0 {
+        return 0;
+    }
+    match (bufp as usize).checked_add(len) {
+        Some(end) if end <= USER_TOP + 1 => {}
+        _ => return -(EFAULT as isize),
+    }
+    let mut kbuf = vec![0u8; len];
+    let n = fill(&mut kbuf);
+    unsafe { UserBuffer::new(bufp, n).copy_to_user(&kbuf[..n]) };
+    n as isize
+}
+
 /// 获取随机字节系统调用
 /// # 参数
 /// * `buf`: 指向用户空间缓冲区的指针，用于存储随机字节
 /// * `len`: 最大需要填充的字节数
-/// * `_flags`: 标志位（当前未使用）
+/// * `flags`: GRND_NONBLOCK / GRND_RANDOM / GRND_INSECURE 的组合
 /// # 返回值
 /// * **成功**：返回填充的字节数
-/// * **失败**：返回负的 errno
-pub fn getrandom(buf: *mut c_void, len: SizeT, _flags: c_uint) -> c_int {
-    if buf.is_null() {
+/// * **失败**：返回负的 errno；随机数生成器尚未播种时，
+///   带 GRND_NONBLOCK 返回 `EAGAIN`，否则阻塞直到播种完成
+pub fn getrandom(buf: *mut c_void, len: SizeT, flags: c_uint) -> c_int {
+    use uapi::random::{GRND_INSECURE, GRND_NONBLOCK, GRND_RANDOM};
+
+    if flags & !(GRND_NONBLOCK | GRND_RANDOM | GRND_INSECURE) != 0 {
+        return -EINVAL;
+    }
+    if flags & GRND_INSECURE != 0 && flags & GRND_RANDOM != 0 {
         return -EINVAL;
     }
 
-    let len = len as usize;
+    // 返回值是 int，单次最多填充 i32::MAX 字节
+    let len = core::cmp::min(len as usize, i32::MAX as usize);
     if len == 0 {
         return 0;
     }
+    if buf.is_null() {
+        return -EFAULT;
+    }
 
     // Basic range sanity check (does not guarantee mapped pages).
     let start = buf as usize;
     let end = match start.checked_add(len) {
         Some(v) => v,
-        None => return -EINVAL,
+        None => return -EFAULT,
     };
     if end > USER_TOP + 1 {
-        return -EINVAL;
+        return -EFAULT;
+    }
+
+    if flags & GRND_INSECURE == 0 && !random::rng_is_initialized() {
+        if flags & GRND_NONBLOCK != 0 {
+            return -EAGAIN;
+        }
+        if let Err(e) = random::wait_for_random_bytes() {
+            return -e;
+        }
     }
 
-    let mut pool = BiogasPoll::new();
     let mut offset = 0usize;
     let mut tmp = [0u8; 256];
 
     while offset < len {
         let n = core::cmp::min(tmp.len(), len - offset);
-        if pool.try_fill(&mut tmp[..n]).is_err() {
-            return -EINVAL;
-        }
+        random::get_random_bytes(&mut tmp[..n]);
         // Copy into user memory with SUM enabled.
         unsafe {
             UserBuffer::new((buf as *mut u8).add(offset), n).copy_to_user(&tmp[..n]);
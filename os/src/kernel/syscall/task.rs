@@ -1,40 +1,51 @@
 //! 任务相关的系统调用实现
 
 use core::{
-    ffi::{c_char, c_int, c_ulong, c_void},
-    sync::atomic::Ordering,
+    ffi::{c_char, c_int, c_uint, c_ulong, c_void},
+    sync::atomic::{AtomicU32, Ordering},
 };
 
 use alloc::{string::ToString, sync::Arc, vec::Vec};
 
 use crate::{
-    arch::{
-        timer::{clock_freq, get_time},
-        trap::{SumGuard, restore},
-    },
-    ipc::{SignalHandlerTable, SignalPending, signal_pending},
+    arch::trap::{SumGuard, restore},
+    ipc::{RestartBlock, SignalHandlerTable, SignalPending, signal_pending},
     kernel::{
-        FUTEX_MANAGER, Scheduler, SharedTask, TASK_MANAGER, TIMER, TIMER_QUEUE, TaskManagerTrait,
-        TaskState, TaskStruct, TimerEntry, current_cpu, current_task, exit_process, schedule,
-        sleep_task_with_block, sleep_task_with_guard_and_block,
+        Capabilities, FUTEX_MANAGER, RseqArea, Scheduler, SharedTask, TASK_MANAGER,
+        TaskManagerTrait, TaskState, TaskStruct, UserNamespace, current_cpu, current_task,
+        exit_process, get_itimer,
+        hrtimer::{Ktime, ktime_get, ktime_to_timespec, timespec_to_ktime},
+        ns_capable, schedule, set_itimer, sleep_task_with_block, sleep_task_with_guard_and_block,
         syscall::util::{get_args_safe, get_path_safe},
-        time::{REALTIME, realtime_now},
-        yield_task,
+        time::realtime_offset,
+        wake_task_at, yield_task,
     },
     mm::{
         MemorySpace,
         address::{UsizeConvert, Vaddr},
-        frame_allocator::{alloc_contig_frames, alloc_frame},
+        frame_allocator::alloc_frame,
+        kstack::KernelStack,
     },
+    security::{landlock, seccomp::do_seccomp},
     sync::SpinLock,
     uapi::{
         errno::{
-            EAGAIN, EFAULT, EINTR, EINVAL, EIO, EISDIR, ENOENT, ENOEXEC, ENOMEM, ENOSYS, EPERM,
-            ESRCH, ETIMEDOUT,
+            EACCES, EAGAIN, EBUSY, EFAULT, EINTR, EINVAL, EIO, EISDIR, ENOENT, ENOEXEC, ENOMEM,
+            ENOSYS, EPERM, ERESTART_RESTARTBLOCK, ERESTARTNOHAND, ESRCH, ETIMEDOUT,
+        },
+        fs::AccessMode,
+        futex::{
+            FUTEX_CLOCK_REALTIME, FUTEX_OWNER_DIED, FUTEX_PRIVATE, FUTEX_TID_MASK, FUTEX_WAIT,
+            FUTEX_WAITERS, FUTEX_WAKE, ROBUST_LIST_LIMIT, RobustListHead,
         },
-        futex::{FUTEX_CLOCK_REALTIME, FUTEX_PRIVATE, FUTEX_WAIT, FUTEX_WAKE, RobustListHead},
+        prctl::{PR_GET_NO_NEW_PRIVS, PR_GET_SECCOMP, PR_SET_NO_NEW_PRIVS, PR_SET_SECCOMP},
         resource::{RLIM_NLIMITS, Rlimit, Rusage},
+        rseq::{ORIG_RSEQ_SIZE, RSEQ_FLAG_UNREGISTER, Rseq},
         sched::CloneFlags,
+        seccomp::{
+            SECCOMP_MODE_FILTER, SECCOMP_MODE_STRICT, SECCOMP_SET_MODE_FILTER,
+            SECCOMP_SET_MODE_STRICT,
+        },
         signal::{NUM_SIGALRM, NUM_SIGPROF, NUM_SIGVTALRM},
         time::{
             Itimerval, TimeSpec,
@@ -48,7 +59,7 @@ use crate::{
         types::{SizeT, StackT},
         wait::{WaitFlags, WaitStatus},
     },
-    util::user_buffer::{read_from_user, write_to_user},
+    util::user_buffer::{copy_from_user, copy_to_user, validate_user_ptr},
     vfs::FsError,
 };
 
@@ -62,7 +73,7 @@ use crate::{
 /// # 参数
 /// - `code`: 退出代码
 pub fn exit(code: c_int) -> c_int {
-    // TODO: 处理 tid_addr 和 robust_list
+    exit_robust_list();
     clear_child_tid_and_wake();
     let task = current_task();
     // Linux 语义：退出时释放用户地址空间并关闭打开文件。
@@ -88,7 +99,7 @@ pub fn exit(code: c_int) -> c_int {
 /// # 参数
 /// - `code`: 退出代码
 pub fn exit_group(code: c_int) -> ! {
-    // TODO: 处理 tid_addr 和 robust_list
+    exit_robust_list();
     clear_child_tid_and_wake();
     crate::kernel::task::cleanup_current_process_resources_on_exit();
     exit_process(current_task(), code & 0xFF);
@@ -96,6 +107,80 @@ pub fn exit_group(code: c_int) -> ! {
     unreachable!("exit: exit_task should not return.");
 }
 
+/// 处理当前线程的健壮列表
+///
+/// 列表中仍由本线程持有的 futex 被标记为 `FUTEX_OWNER_DIED`，有等待者时唤醒一个，
+/// 使其他线程能发现锁的持有者已经退出。节点地址的最低位是 PI 标志，遍历时忽略。
+fn exit_robust_list() {
+    let task = current_task();
+    let (head_addr, tid) = {
+        let mut t = task.lock();
+        let Some(head) = t.robust_list.take() else {
+            return;
+        };
+        (head, t.tid)
+    };
+    let Ok(head) = copy_from_user(head_addr as *const RobustListHead) else {
+        return;
+    };
+    let offset = head.off as isize;
+    let pending = head.pending as usize & !1;
+    let mut entry = head.head as usize & !1;
+    for _ in 0..ROBUST_LIST_LIMIT {
+        if entry == head_addr {
+            break;
+        }
+        let Ok(next) = copy_from_user(entry as *const usize) else {
+            return;
+        };
+        // pending 在最后单独处理，避免重复
+        if entry != pending {
+            handle_futex_death(entry.wrapping_add_signed(offset), tid);
+        }
+        entry = next & !1;
+    }
+    if pending != 0 {
+        handle_futex_death(pending.wrapping_add_signed(offset), tid);
+    }
+}
+
+/// 若 `uaddr` 处的 futex 由 `tid` 持有，标记持有者已退出并唤醒一个等待者
+fn handle_futex_death(uaddr: usize, tid: u32) {
+    if uaddr % size_of::<u32>() != 0 || !validate_user_ptr(uaddr as *const u32) {
+        return;
+    }
+    let old = {
+        let _guard = SumGuard::new();
+        // SAFETY: 地址已检查对齐且位于用户空间，用户态以原子操作访问 futex 字
+        let word = unsafe { &*(uaddr as *const AtomicU32) };
+        let mut val = word.load(Ordering::SeqCst);
+        loop {
+            if val & FUTEX_TID_MASK != tid {
+                return;
+            }
+            let new = (val & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
+            match word.compare_exchange(val, new, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => break val,
+                Err(cur) => val = cur,
+            }
+        }
+    };
+    if old & FUTEX_WAITERS == 0 {
+        return;
+    }
+    let Some(memory_space) = current_task().lock().memory_space.clone() else {
+        return;
+    };
+    let Some(paddr) = memory_space
+        .lock()
+        .translate(Vaddr::from_usize(uaddr))
+        .map(|p| p.as_usize())
+    else {
+        return;
+    };
+    FUTEX_MANAGER.lock().get_wait_queue(paddr).wake_up_one();
+}
+
 fn clear_child_tid_and_wake() {
     let task = current_task();
     let clear_addr = {
@@ -121,10 +206,8 @@ fn clear_child_tid_and_wake() {
     };
 
     // 1) write 0 to userspace tid address
-    unsafe {
-        let _guard = SumGuard::new();
-        write_to_user(clear_addr as *mut c_int, 0);
-    }
+    // 与 Linux 一致，地址无效时只跳过写回，仍然唤醒等待者
+    let _ = copy_to_user(clear_addr as *mut c_int, 0);
 
     // 2) futex wake
     let Some(paddr) = memory_space
@@ -199,6 +282,15 @@ pub fn clone(
         c_pid,
         c_ppid,
         c_pgid,
+        c_sid,
+        c_ctty,
+        c_no_new_privs,
+        c_seccomp,
+        c_landlock,
+        c_rseq,
+        c_user_ns,
+        c_owner,
+        c_mnt_ns,
         space,
         signal_handlers,
         blocked,
@@ -209,6 +301,8 @@ pub fn clone(
         fs,
         uts,
         rlimit,
+        group_runtime,
+        oom_score_adj,
     ) = {
         let _guard = crate::sync::PreemptGuard::new();
         let cpu = current_cpu();
@@ -217,6 +311,15 @@ pub fn clone(
             task.pid,
             task.ppid,
             task.pgid,
+            task.sid,
+            task.ctty.clone(),
+            task.no_new_privs,
+            task.seccomp.clone(),
+            task.landlock.clone(),
+            task.rseq,
+            task.user_ns.clone(),
+            (task.credential.euid, task.credential.egid),
+            task.mnt_ns.clone(),
             task.memory_space
                 .clone()
                 .expect("fork: can only call fork on a user task."),
@@ -229,8 +332,34 @@ pub fn clone(
             task.fs.clone(),
             task.uts_namespace.clone(),
             task.rlimit.clone(),
+            task.group_runtime.clone(),
+            task.oom_score_adj.clone(),
         )
     };
+    let user_ns = if requested_flags.contains(CloneFlags::NEWUSER) {
+        // 与 Linux 一致：新用户命名空间不能与父任务共享线程组或文件系统信息
+        if requested_flags.intersects(CloneFlags::THREAD | CloneFlags::FS) {
+            return -EINVAL;
+        }
+        match UserNamespace::new_child(&c_user_ns, c_owner.0, c_owner.1) {
+            Ok(ns) => ns,
+            Err(e) => return -e,
+        }
+    } else {
+        c_user_ns
+    };
+    let mnt_ns = if requested_flags.contains(CloneFlags::NEWNS) {
+        // 与 Linux 一致：新挂载命名空间不能与父任务共享根目录和工作目录
+        if requested_flags.contains(CloneFlags::FS) {
+            return -EINVAL;
+        }
+        if !ns_capable(&current_task().lock(), &user_ns, Capabilities::SYS_ADMIN) {
+            return -EPERM;
+        }
+        c_mnt_ns.copy()
+    } else {
+        c_mnt_ns
+    };
     let exit_signal = requested_flags.get_exit_signal();
     let space = if requested_flags.contains(CloneFlags::VM) {
         space
@@ -242,7 +371,9 @@ pub fn clone(
                 return -ENOMEM;
             }
         };
-        Arc::new(SpinLock::new(cloned))
+        let space = Arc::new(SpinLock::new(cloned));
+        crate::mm::swap::lru_add_space(&space);
+        space
     };
     let fd_table = if requested_flags.contains(CloneFlags::FILES) {
         fd_table
@@ -274,7 +405,7 @@ pub fn clone(
         )
     };
 
-    let kstack_tracker = alloc_contig_frames(4).expect("fork: alloc kstack failed.");
+    let kstack = KernelStack::new().expect("fork: alloc kstack failed.");
     let trap_frame_tracker = alloc_frame().expect("fork: alloc trap frame failed");
     let mut child_task = TaskStruct::utask_create(
         tid,
@@ -282,7 +413,7 @@ pub fn clone(
         ppid,
         c_pgid,
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         space,
         signal_handler,
@@ -295,14 +426,36 @@ pub fn clone(
         fd_table,
         fs,
     );
+    child_task.sid = c_sid;
+    if requested_flags.contains(CloneFlags::THREAD) {
+        child_task.group_runtime = group_runtime;
+        child_task.oom_score_adj = oom_score_adj;
+    } else {
+        child_task
+            .oom_score_adj
+            .store(oom_score_adj.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+    child_task.ctty = c_ctty;
+    child_task.no_new_privs = c_no_new_privs;
+    child_task.seccomp = c_seccomp;
+    child_task.landlock = c_landlock;
+    // 与 Linux 一致：共享地址空间的新线程需要自己注册 rseq
+    if !requested_flags.contains(CloneFlags::VM) {
+        child_task.rseq = c_rseq;
+        child_task.rseq_pending = c_rseq.is_some();
+    }
+    child_task.user_ns = user_ns;
+    child_task.mnt_ns = mnt_ns;
 
     if requested_flags.contains(CloneFlags::CHILD_SETTID) {
-        // SAFETY: we validated ctid != NULL above.
-        unsafe { write_to_user(ctid, tid as c_int) };
+        if let Err(e) = copy_to_user(ctid, tid as c_int) {
+            return -e;
+        }
     }
     if requested_flags.contains(CloneFlags::PARENT_SETTID) {
-        // SAFETY: we validated ptid != NULL above.
-        unsafe { write_to_user(ptid, tid as c_int) };
+        if let Err(e) = copy_to_user(ptid, tid as c_int) {
+            return -e;
+        }
     }
 
     let tf = child_task.trap_frame_ptr.load(Ordering::SeqCst);
@@ -369,6 +522,54 @@ pub fn clone(
     tid as c_int
 }
 
+/// 使当前任务不再与其他任务共享部分执行上下文
+/// # 参数
+/// - `flags`: CLONE_FILES / CLONE_FS / CLONE_SYSVSEM / CLONE_NEWUSER / CLONE_NEWNS 的组合
+/// # 返回值
+/// - 成功返回 0, 失败返回负错误码
+pub fn unshare(flags: c_ulong) -> c_int {
+    let Some(flags) = CloneFlags::from_bits(flags as usize) else {
+        return -EINVAL;
+    };
+    let supported = CloneFlags::FILES
+        | CloneFlags::FS
+        | CloneFlags::SYSVSEM
+        | CloneFlags::NEWUSER
+        | CloneFlags::NEWNS;
+    if !supported.contains(flags) {
+        return -EINVAL;
+    }
+
+    let task = current_task();
+    if flags.contains(CloneFlags::NEWUSER) {
+        // 与 Linux 一致：多线程进程不能进入新的用户命名空间
+        if TASK_MANAGER.lock().get_process_threads(task.clone()).len() > 1 {
+            return -EINVAL;
+        }
+        let mut t = task.lock();
+        match UserNamespace::new_child(&t.user_ns, t.credential.euid, t.credential.egid) {
+            Ok(ns) => t.user_ns = ns,
+            Err(e) => return -e,
+        }
+    }
+
+    let mut t = task.lock();
+    if flags.contains(CloneFlags::NEWNS) {
+        if !ns_capable(&t, &t.user_ns, Capabilities::SYS_ADMIN) {
+            return -EPERM;
+        }
+        t.mnt_ns = t.mnt_ns.copy();
+    }
+    if flags.contains(CloneFlags::FILES) {
+        t.fd_table = Arc::new(t.fd_table.clone_table());
+    }
+    // 与 Linux 一致：CLONE_NEWUSER 和 CLONE_NEWNS 隐含 CLONE_FS
+    if flags.intersects(CloneFlags::FS | CloneFlags::NEWUSER | CloneFlags::NEWNS) {
+        t.fs = Arc::new(SpinLock::new(t.fs.lock().clone()));
+    }
+    0
+}
+
 /// 执行一个新程序（execve）
 /// # 参数
 /// - `path`: 可执行文件路径
@@ -400,6 +601,7 @@ pub fn execve(
         let dentry = match crate::vfs::vfs_lookup(&path_str) {
             Ok(d) => d,
             Err(FsError::NotFound) => return -ENOENT,
+            Err(FsError::PermissionDenied) => return -EACCES,
             Err(FsError::IsDirectory) => return -EISDIR,
             Err(_) => return -EIO,
         };
@@ -413,6 +615,14 @@ pub fn execve(
         if meta.inode_type != crate::vfs::InodeType::File {
             return -EISDIR;
         }
+        // 需要执行权限，CAP_DAC_OVERRIDE 也要求至少一个 x 位
+        let cred = crate::kernel::current_fs_cred();
+        if crate::vfs::inode_permission(inode.as_ref(), &cred, AccessMode::EXECUTE).is_err() {
+            return -EACCES;
+        }
+        if landlock::bprm_check(&dentry).is_err() {
+            return -EACCES;
+        }
 
         let prefix_len = core::cmp::min(meta.size, 256);
         if prefix_len == 0 {
@@ -602,8 +812,8 @@ pub fn wait4(pid: c_int, wstatus: *mut c_int, options: c_int, _rusage: *mut Rusa
 
     // wstatus 允许为 NULL（例如 waitpid(-1, NULL, 0)），此时不写回状态
     if !wstatus.is_null() {
-        unsafe {
-            write_to_user(wstatus, status.raw());
+        if let Err(e) = copy_to_user(wstatus, status.raw()) {
+            return -e;
         }
     }
 
@@ -729,11 +939,10 @@ pub fn getrlimit(resource: c_int, rlim: *mut Rlimit) -> c_int {
         return -EINVAL;
     }
     let rlimit = current_task().lock().rlimit.lock().limits[resource as usize];
-    unsafe {
-        write_to_user(rlim, rlimit);
+    match copy_to_user(rlim, rlimit) {
+        Ok(()) => 0,
+        Err(e) => -e,
     }
-    0
-    // TODO: EPERM 和 EFAULT
 }
 
 /// 设置资源限制
@@ -746,7 +955,10 @@ pub fn setrlimit(resource: c_int, rlim: *const Rlimit) -> c_int {
     if resource as usize >= RLIM_NLIMITS {
         return -EINVAL;
     }
-    let new_limit = unsafe { read_from_user(rlim) };
+    let new_limit = match copy_from_user(rlim) {
+        Ok(l) => l,
+        Err(e) => return -e,
+    };
     if new_limit.rlim_cur > new_limit.rlim_max {
         return -EINVAL;
     }
@@ -755,7 +967,7 @@ pub fn setrlimit(resource: c_int, rlim: *const Rlimit) -> c_int {
         rlimit_lock.lock().limits[resource as usize] = new_limit;
     }
     0
-    // TODO: EPERM, EPERM 和 EFAULT
+    // TODO: EPERM
 }
 
 /// 获取或设置资源限制
@@ -787,13 +999,16 @@ pub fn prlimit(
 
     if !old_limit.is_null() {
         let rlimit = target_task.lock().rlimit.lock().limits[resource as usize];
-        unsafe {
-            write_to_user(old_limit, rlimit);
+        if let Err(e) = copy_to_user(old_limit, rlimit) {
+            return -e;
         }
     }
 
     if !new_limit.is_null() {
-        let new_rlim = unsafe { read_from_user(new_limit) };
+        let new_rlim = match copy_from_user(new_limit) {
+            Ok(l) => l,
+            Err(e) => return -e,
+        };
         if new_rlim.rlim_cur > new_rlim.rlim_max {
             return -EINVAL;
         }
@@ -802,7 +1017,7 @@ pub fn prlimit(
     }
 
     0
-    // TODO: EPERM, EPERM 和 EFAULT
+    // TODO: EPERM
 }
 
 /// 高精度睡眠（纳秒级别）
@@ -812,45 +1027,62 @@ pub fn prlimit(
 /// # 返回值
 /// - 成功返回 0, 失败返回负错误码
 pub fn nanosleep(duration: *const TimeSpec, rem: *mut TimeSpec) -> c_int {
-    let req = unsafe { read_from_user(duration) };
+    let req = match copy_from_user(duration) {
+        Ok(ts) => ts,
+        Err(e) => return -e,
+    };
     if req.tv_sec == 0 && req.tv_nsec == 0 {
         return 0;
     }
     if req.tv_sec < 0 || req.tv_nsec < 0 || req.tv_nsec > 999999999 {
         return -EINVAL;
     }
-    let mut result = 0;
-    let task = current_task();
-    let trigger = get_time() + req.into_freq(clock_freq());
-
-    let mut timer_q = TIMER_QUEUE.lock();
-    timer_q.push(trigger, task.clone());
-    sleep_task_with_block(task.clone(), true);
-    drop(timer_q);
-    yield_task();
+    let deadline = ktime_get().saturating_add(timespec_to_ktime(&req));
+    do_nanosleep(deadline, Some(rem))
+}
 
-    // 被唤醒后（可能是超时，也可能是信号），必须确保将任务从定时器队列中清理掉
-    // 如果是超时唤醒，pop_due_task 已经移除了
-    // 如果是信号唤醒，任务还在队列中，需要手动移除以避免 Arc 泄漏
-    TIMER_QUEUE.lock().remove_task(&task);
+/// 睡眠到单调时间 `deadline`
+/// # 参数
+/// - `deadline`: 到期时间（单调时钟，纳秒）
+/// - `rem`: 相对睡眠时为用户态剩余时间指针（可为 NULL）；绝对时间睡眠为 None
+/// # 返回值
+/// - 到期返回 0
+/// - 被信号打断时，相对睡眠写回剩余时间并登记 restart_block，返回 `-ERESTART_RESTARTBLOCK`；
+///   绝对时间睡眠返回 `-ERESTARTNOHAND`，重启时按原参数重新计算
+fn do_nanosleep(deadline: Ktime, rem: Option<*mut TimeSpec>) -> c_int {
+    let task = current_task();
+    loop {
+        sleep_task_with_block(task.clone(), true);
+        let timer = wake_task_at(task.clone(), deadline);
+        yield_task();
+        if !timer.cancel() {
+            return 0;
+        }
+        // 没有待处理信号的提前唤醒继续睡眠
+        if signal_pending(&task) {
+            break;
+        }
+    }
 
+    let Some(rem) = rem else {
+        return -ERESTARTNOHAND;
+    };
     if !rem.is_null() {
-        let dur = trigger.saturating_sub(get_time());
-        let remaining_ticks = if dur > 0 {
-            // XXX: 提前唤醒是否一定是因为信号？
-            result = -EINTR;
-            dur
-        } else {
-            0
-        };
-        let rem_ts = TimeSpec::from_freq(remaining_ticks, clock_freq());
-        unsafe {
-            write_to_user(rem, rem_ts);
+        let remaining = deadline.saturating_sub(ktime_get());
+        if let Err(e) = copy_to_user(rem, ktime_to_timespec(remaining)) {
+            return -e;
         }
     }
+    task.lock().restart_block = Some(RestartBlock::Nanosleep {
+        deadline,
+        rem: rem as usize,
+    });
+    -ERESTART_RESTARTBLOCK
+}
 
-    result
-    // TODO: EFAULT
+/// restart_syscall 继续被打断的相对睡眠
+pub(super) fn nanosleep_restart(deadline: Ktime, rem: usize) -> c_int {
+    do_nanosleep(deadline, Some(rem as *mut TimeSpec))
 }
 
 pub fn gettid() -> c_int {
@@ -871,49 +1103,26 @@ pub fn clock_nanosleep(
     req: *const TimeSpec,
     rem: *mut TimeSpec,
 ) -> c_int {
-    let time_req = unsafe { read_from_user(req) };
-    let is_abstime = (flags & TIMER_ABSTIME) != 0;
-    let sleep_ticks = time_req.into_freq(clock_freq());
-    let trigger = if is_abstime {
-        sleep_ticks
-    } else {
-        let now = match clk_id {
-            CLOCK_REALTIME => REALTIME.read().into_freq(clock_freq()),
-            CLOCK_MONOTONIC => get_time(),
-            CLOCK_TAI | CLOCK_BOOTTIME | CLOCK_PROCESS_CPUTIME_ID => return -ENOSYS,
-            _ => return -EINVAL,
-        };
-        now.saturating_add(sleep_ticks)
+    let time_req = match copy_from_user(req) {
+        Ok(ts) => ts,
+        Err(e) => return -e,
     };
-
-    let mut result = 0;
-    let task = current_task();
-
-    let mut timer_q = TIMER_QUEUE.lock();
-    timer_q.push(trigger, task.clone());
-    sleep_task_with_block(task.clone(), true);
-    drop(timer_q);
-    yield_task();
-
-    // 同 sys_nanosleep，防止信号唤醒导致的 Arc 泄漏
-    TIMER_QUEUE.lock().remove_task(&task);
-
-    if !rem.is_null() {
-        let dur = trigger.saturating_sub(get_time());
-        let remaining_ticks = if dur > 0 {
-            // XXX: 提前唤醒是否一定是因为信号？
-            result = -EINTR;
-            dur
-        } else {
-            0
-        };
-        let rem_ts = TimeSpec::from_freq(remaining_ticks, clock_freq());
-        unsafe {
-            write_to_user(rem, rem_ts);
-        }
+    if time_req.tv_sec < 0 || time_req.tv_nsec < 0 || time_req.tv_nsec > 999999999 {
+        return -EINVAL;
     }
+    let is_abstime = (flags & TIMER_ABSTIME) != 0;
+    let req = timespec_to_ktime(&time_req);
+    // 统一换算为单调时钟上的到期时间
+    let deadline = match clk_id {
+        CLOCK_REALTIME if is_abstime => req.saturating_sub(timespec_to_ktime(&realtime_offset())),
+        CLOCK_MONOTONIC | CLOCK_BOOTTIME if is_abstime => req,
+        CLOCK_REALTIME | CLOCK_MONOTONIC | CLOCK_BOOTTIME => ktime_get().saturating_add(req),
+        CLOCK_TAI | CLOCK_PROCESS_CPUTIME_ID => return -ENOSYS,
+        _ => return -EINVAL,
+    };
 
-    result
+    // 绝对时间睡眠被打断时不回写剩余时间
+    do_nanosleep(deadline, (!is_abstime).then_some(rem))
 }
 
 /// 获取间隔定时器的当前值
@@ -934,29 +1143,13 @@ pub fn getitimer(which: c_int, curr_value: *mut Itimerval) -> c_int {
         _ => unreachable!("getitimer: unreachable which case."),
     };
     // Linux semantics: ITIMER_* are per-process (thread group), not per-thread.
-    // Use the thread-group leader (pid) as the timer owner.
-    let owner = {
-        let pid = current_task().lock().pid;
-        TASK_MANAGER
-            .lock()
-            .get_task(pid)
-            .unwrap_or_else(current_task)
-    };
-    let mut val = Itimerval::zero();
-    if let Some(timer) = TIMER.lock().find_entry(&owner, sig) {
-        let now = get_time();
-        let remaining = if *timer.0 > now { *timer.0 - now } else { 0 };
-        let it_value = TimeSpec::from_freq(remaining, clock_freq()).to_timeval();
-        let it_interval = timer.1.it_interval.to_timeval();
-        val = Itimerval {
-            it_value,
-            it_interval,
-        };
-    }
-    unsafe {
-        write_to_user(curr_value, val);
+    let pid = current_task().lock().pid;
+    let (value, interval) = get_itimer(pid, sig);
+    let val = ktime_to_itimerval(value, interval);
+    match copy_to_user(curr_value, val) {
+        Ok(()) => 0,
+        Err(e) => -e,
     }
-    0
 }
 
 /// 设置间隔定时器的值
@@ -987,44 +1180,35 @@ pub fn setitimer(which: c_int, new_value: *const Itimerval, old_value: *mut Itim
             .unwrap_or_else(current_task)
     };
 
-    let mut binding = TIMER.lock();
-
     // Linux semantics: return the previous timer value, then replace it with the new one.
-    let mut old = Itimerval::zero();
-    if let Some(timer) = binding.find_entry(&owner, sig) {
-        let now = get_time();
-        let remaining = if *timer.0 > now { *timer.0 - now } else { 0 };
-        let it_value = TimeSpec::from_freq(remaining, clock_freq()).to_timeval();
-        let it_interval = timer.1.it_interval.to_timeval();
-        old = Itimerval {
-            it_value,
-            it_interval,
-        };
-    }
-
-    // Disarm any existing timers for this (task, sig) so we don't accumulate duplicates.
-    while binding.remove_entry(&owner, sig).is_some() {}
-
-    let new_itimer = unsafe { read_from_user(new_value) };
-    if !new_itimer.it_value.is_zero() {
-        let trigger = get_time() + new_itimer.it_value.into_freq(clock_freq());
-        let interval = new_itimer.it_interval.to_timespec();
-        let entry = TimerEntry {
-            task: owner,
-            sig,
-            it_interval: interval,
-        };
-        binding.push(trigger, entry);
-    }
+    let new_itimer = match copy_from_user(new_value) {
+        Ok(v) => v,
+        Err(e) => return -e,
+    };
+    let (value, interval) = set_itimer(
+        &owner,
+        sig,
+        timespec_to_ktime(&new_itimer.it_value.to_timespec()),
+        timespec_to_ktime(&new_itimer.it_interval.to_timespec()),
+    );
+    let old = ktime_to_itimerval(value, interval);
     if !old_value.is_null() {
-        unsafe {
-            write_to_user(old_value, old);
+        if let Err(e) = copy_to_user(old_value, old) {
+            return -e;
         }
     }
 
     0
 }
 
+/// 以纳秒表示的（剩余时间，重复周期）转换为 Itimerval
+fn ktime_to_itimerval(value: Ktime, interval: Ktime) -> Itimerval {
+    Itimerval {
+        it_value: ktime_to_timespec(value).to_timeval(),
+        it_interval: ktime_to_timespec(interval).to_timeval(),
+    }
+}
+
 /// Futex 系统调用实现
 /// # 参数
 /// - `uaddr`: 指向用户空间中 futex 变量的指针
@@ -1044,14 +1228,17 @@ pub fn futex(
     _val3: u32,
 ) -> c_int {
     let _private = (op & FUTEX_PRIVATE as c_int) != 0; // TODO: 目前不区分 PRIVATE 和 SHARED
-    let realtime = (op & FUTEX_CLOCK_REALTIME as c_int) != 0;
+    // FUTEX_WAIT 的超时是相对时间，与所用时钟无关
     let op = op & !(FUTEX_PRIVATE as c_int) & !(FUTEX_CLOCK_REALTIME as c_int);
     // HACK: 其实只需要锁定与 uaddr 对应的 Futex 等待队列
     let mut fm = FUTEX_MANAGER.lock();
     match op as u32 {
         FUTEX_WAIT => {
             // 必须保证获 取锁 → 读取用户数据 → 比较 → 释放锁 整个序列是原子的
-            let user_val = unsafe { read_from_user(uaddr) };
+            let user_val = match copy_from_user(uaddr as *const u32) {
+                Ok(v) => v,
+                Err(e) => return -e,
+            };
             let memory_space = current_task()
                 .lock()
                 .memory_space
@@ -1069,29 +1256,31 @@ pub fn futex(
             if user_val != val as u32 {
                 return -EAGAIN;
             }
+            // 超时参数在入队前读取，出错时任务还没有睡眠
+            let timeout = if timeout.is_null() {
+                None
+            } else {
+                let ts = match copy_from_user(timeout) {
+                    Ok(ts) => ts,
+                    Err(e) => return -e,
+                };
+                if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec > 999999999 {
+                    return -EINVAL;
+                }
+                Some(ts)
+            };
 
             let task = current_task();
             let waitq = fm.get_wait_queue(paddr);
             waitq.sleep(task.clone());
             sleep_task_with_block(task.clone(), true);
 
-            if !timeout.is_null() {
-                let ts = unsafe { read_from_user(timeout) };
-                if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec > 999999999 {
-                    return -EINVAL;
-                }
-                let sleep_ticks = ts.into_freq(clock_freq());
-                let trigger = if realtime {
-                    let now = realtime_now().into_freq(clock_freq());
-                    now.saturating_add(sleep_ticks)
-                } else {
-                    let now = get_time();
-                    now.saturating_add(sleep_ticks)
-                };
-                TIMER_QUEUE.lock().push(trigger, task.clone());
+            if let Some(ts) = timeout {
+                let deadline = ktime_get().saturating_add(timespec_to_ktime(&ts));
+                let timer = wake_task_at(task.clone(), deadline);
                 drop(fm);
                 yield_task();
-                if TIMER_QUEUE.lock().remove_task(&task).is_none() {
+                if !timer.cancel() {
                     // 超时唤醒
                     let mut fm = FUTEX_MANAGER.lock();
                     let waitq = fm.get_wait_queue(paddr);
@@ -1186,11 +1375,13 @@ pub fn get_robust_list(pid: c_int, head_ptr: *mut *mut RobustListHead, sizep: *m
         let size = size_of::<RobustListHead>() as SizeT;
         (head, size)
     };
-    unsafe {
-        write_to_user(head_ptr, head);
-        write_to_user(sizep, size);
+    if let Err(e) = copy_to_user(head_ptr, head) {
+        return -e;
+    }
+    match copy_to_user(sizep, size) {
+        Ok(()) => 0,
+        Err(e) => -e,
     }
-    0
 }
 
 /// 设置线程的 robust futex 列表头指针
@@ -1208,7 +1399,72 @@ pub fn set_robust_list(head: *const RobustListHead, size: SizeT) -> c_int {
     0
 }
 
+/// 注册或注销当前线程的 rseq 区域
+/// # 参数
+/// - `rseq`: 用户态 `struct rseq` 的地址，按 32 字节对齐
+/// - `rseq_len`: 区域长度，至少为 `ORIG_RSEQ_SIZE`
+/// - `flags`: 0 或 `RSEQ_FLAG_UNREGISTER`
+/// - `sig`: 中止入口前的签名，注销时必须与注册时一致
+/// # 返回值
+/// - 成功返回 0；重复注册同一区域返回 -EBUSY，失败返回负错误码
+pub fn rseq(rseq: *mut Rseq, rseq_len: u32, flags: c_int, sig: u32) -> c_int {
+    let area = RseqArea {
+        addr: rseq as usize,
+        len: rseq_len,
+        sig,
+    };
+    let task = current_task();
+    let registered = task.lock().rseq;
+
+    if flags & RSEQ_FLAG_UNREGISTER != 0 {
+        if flags != RSEQ_FLAG_UNREGISTER {
+            return -EINVAL;
+        }
+        let Some(cur) = registered else {
+            return -EINVAL;
+        };
+        if cur.addr != area.addr || cur.len != rseq_len {
+            return -EINVAL;
+        }
+        if cur.sig != sig {
+            return -EPERM;
+        }
+        if let Err(e) = cur.reset_cpu_id() {
+            return -e;
+        }
+        let mut t = task.lock();
+        t.rseq = None;
+        t.rseq_pending = false;
+        return 0;
+    }
+    if flags != 0 {
+        return -EINVAL;
+    }
+    if let Some(cur) = registered {
+        if cur.addr != area.addr || cur.len != rseq_len {
+            return -EINVAL;
+        }
+        if cur.sig != sig {
+            return -EPERM;
+        }
+        return -EBUSY;
+    }
+    if rseq_len < ORIG_RSEQ_SIZE || area.addr % ORIG_RSEQ_SIZE as usize != 0 {
+        return -EINVAL;
+    }
+    if !validate_user_ptr(rseq as *const Rseq) {
+        return -EFAULT;
+    }
+    if let Err(e) = area.update_cpu_id() {
+        return -e;
+    }
+    task.lock().rseq = Some(area);
+    0
+}
+
 /// 创建一个新的会话并设置进程组 ID
+///
+/// 调用者成为新会话的首进程，且没有控制终端。
 /// # 返回值
 /// - 成功返回新会话的进程组 ID, 失败返回负错误码
 pub fn setsid() -> c_int {
@@ -1219,9 +1475,97 @@ pub fn setsid() -> c_int {
     }
     let new_pgid = t.pid;
     t.pgid = new_pgid;
+    t.sid = new_pgid;
+    t.ctty = None;
     new_pgid as c_int
 }
 
+/// 进程控制系统调用
+///
+/// 目前支持 no_new_privs 与 seccomp 相关的选项，其余选项返回 EINVAL。
+/// # 参数
+/// - `option`: PR_* 操作
+/// - `arg2` ~ `arg5`: 操作参数
+/// # 返回值
+/// - 成功返回 0 或查询到的值, 失败返回负错误码
+pub fn prctl(option: c_int, arg2: c_ulong, arg3: c_ulong, arg4: c_ulong, arg5: c_ulong) -> c_int {
+    match option {
+        PR_SET_NO_NEW_PRIVS => {
+            if arg2 != 1 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                return -EINVAL;
+            }
+            current_task().lock().no_new_privs = true;
+            0
+        }
+        PR_GET_NO_NEW_PRIVS => {
+            if arg2 != 0 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                return -EINVAL;
+            }
+            current_task().lock().no_new_privs as c_int
+        }
+        PR_GET_SECCOMP => current_task().lock().seccomp.mode as c_int,
+        PR_SET_SECCOMP => {
+            let op = match arg2 as u32 {
+                SECCOMP_MODE_STRICT => SECCOMP_SET_MODE_STRICT,
+                SECCOMP_MODE_FILTER => SECCOMP_SET_MODE_FILTER,
+                _ => return -EINVAL,
+            };
+            do_seccomp(op, 0, arg3 as usize) as c_int
+        }
+        _ => -EINVAL,
+    }
+}
+
+/// 设置或查询 seccomp 状态
+/// # 参数
+/// - `op`: SECCOMP_SET_MODE_STRICT / SECCOMP_SET_MODE_FILTER / SECCOMP_GET_ACTION_AVAIL
+/// - `flags`: SECCOMP_FILTER_FLAG_* 标志
+/// - `args`: 与操作相关的用户指针（如 `struct sock_fprog`）
+/// # 返回值
+/// - 成功返回 0, 失败返回负错误码；
+///   TSYNC 无法同步某个线程时返回该线程的 tid
+pub fn seccomp(op: c_uint, flags: c_uint, args: *mut c_void) -> c_int {
+    do_seccomp(op, flags, args as usize) as c_int
+}
+
+/// 创建 Landlock 规则集
+/// # 参数
+/// - `attr`: 指向 `struct landlock_ruleset_attr`
+/// - `size`: `attr` 的大小
+/// - `flags`: 0，或 LANDLOCK_CREATE_RULESET_VERSION 查询 ABI 版本
+/// # 返回值
+/// - 成功返回规则集 fd（或 ABI 版本）, 失败返回负错误码
+pub fn landlock_create_ruleset(attr: *const c_void, size: usize, flags: c_uint) -> c_int {
+    landlock::do_create_ruleset(attr as *const _, size, flags) as c_int
+}
+
+/// 向 Landlock 规则集添加规则
+/// # 参数
+/// - `ruleset_fd`: 规则集 fd
+/// - `rule_type`: LANDLOCK_RULE_PATH_BENEATH
+/// - `attr`: 指向 `struct landlock_path_beneath_attr`
+/// - `flags`: 必须为 0
+/// # 返回值
+/// - 成功返回 0, 失败返回负错误码
+pub fn landlock_add_rule(
+    ruleset_fd: c_int,
+    rule_type: c_uint,
+    attr: *const c_void,
+    flags: c_uint,
+) -> c_int {
+    landlock::do_add_rule(ruleset_fd, rule_type, attr as *const _, flags) as c_int
+}
+
+/// 把规则集作为新的一层施加到当前任务
+/// # 参数
+/// - `ruleset_fd`: 规则集 fd
+/// - `flags`: 必须为 0
+/// # 返回值
+/// - 成功返回 0, 失败返回负错误码
+pub fn landlock_restrict_self(ruleset_fd: c_int, flags: c_uint) -> c_int {
+    landlock::do_restrict_self(ruleset_fd, flags) as c_int
+}
+
 /// 辅助函数：解析 Hashbang 行
 fn parse_hashbang(data: &[u8]) -> Result<(&str, Option<&str>), ()> {
     // 查找第一个换行符 ('\n')，只读取第一行
@@ -1285,6 +1629,7 @@ fn do_execve_prepare(
     };
 
     let space = Arc::new(SpinLock::new(prepared.space));
+    crate::mm::swap::lru_add_space(&space);
     Ok((
         space,
         prepared.initial_pc,
@@ -1314,7 +1659,8 @@ fn do_execve_switch(
 ) -> c_int {
     let task = current_task();
 
-    task.lock().fd_table.close_exec();
+    let fd_table = task.lock().fd_table.clone();
+    fd_table.close_exec();
 
     // 换掉当前任务的地址空间，e.g. 切换 satp
     {
@@ -10,11 +10,17 @@ use alloc::{
 };
 
 use crate::{
-    kernel::current_task,
-    uapi::{errno::EINVAL, log::SyslogAction},
+    fs::kmsg::KmsgFile,
+    kernel::{Capabilities, capable, current_fs_cred, current_task},
+    uapi::{
+        errno::{EINVAL, EPERM},
+        log::SyslogAction,
+    },
     vfs::{
-        BlkDeviceFile, CharDeviceFile, DENTRY_CACHE, Dentry, File, FileMode, FsError, InodeType,
-        OpenFlags, RegFile, get_root_dentry, split_path, vfs_lookup_from,
+        AclInherit, BlkDeviceFile, CharDeviceFile, DENTRY_CACHE, Dentry, File, FileMode, FsError,
+        Inode, InodeType, OpenFlags, RegFile, ResolvePolicy, chrdev_major, console_minor,
+        get_root_dentry, makedev, mem_minor, open_ptmx, posix_acl_create, split_path,
+        vfs_lookup_from_with,
     },
 };
 
@@ -91,7 +97,18 @@ pub fn get_args_safe(
 ///
 /// 这是系统调用层的辅助函数，处理 AT_FDCWD 和相对路径逻辑
 pub fn resolve_at_path(dirfd: i32, path: &str) -> Result<Option<Arc<Dentry>>, FsError> {
-    let base_dentry = if path.starts_with('/') {
+    resolve_at_path_with(dirfd, path, &ResolvePolicy::default())
+}
+
+/// 按 `policy` 限制解析at系列系统调用的路径（openat2）
+///
+/// 受限解析（RESOLVE_BENEATH / RESOLVE_IN_ROOT）时绝对路径同样以 dirfd 为起点。
+pub fn resolve_at_path_with(
+    dirfd: i32,
+    path: &str,
+    policy: &ResolvePolicy,
+) -> Result<Option<Arc<Dentry>>, FsError> {
+    let base_dentry = if path.starts_with('/') && !policy.confined() {
         get_root_dentry()?
     } else if dirfd == super::fs::AT_FDCWD {
         current_task()
@@ -119,7 +136,7 @@ pub fn resolve_at_path(dirfd: i32, path: &str) -> Result<Option<Arc<Dentry>>, Fs
         }
     };
 
-    match vfs_lookup_from(base_dentry, path) {
+    match vfs_lookup_from_with(base_dentry, path, true, policy) {
         Ok(d) => Ok(Some(d)),
         Err(FsError::NotFound) => Ok(None),
         Err(e) => Err(e),
@@ -131,10 +148,16 @@ pub fn resolve_at_path(dirfd: i32, path: &str) -> Result<Option<Arc<Dentry>>, Fs
 /// - `dirfd`: 目录文件描述符，或 AT_FDCWD
 /// - `path`: 要创建的文件路径（相对于 dirfd）
 /// - `mode`: 文件权限模式
+/// - `policy`: 查找父目录时的解析限制
 /// 返回新创建的文件的 Dentry
-pub fn create_file_at(dirfd: i32, path: &str, mode: u32) -> Result<Arc<Dentry>, FsError> {
+pub fn create_file_at(
+    dirfd: i32,
+    path: &str,
+    mode: u32,
+    policy: &ResolvePolicy,
+) -> Result<Arc<Dentry>, FsError> {
     let (dir_path, filename) = split_path(path)?;
-    let parent_dentry = match resolve_at_path(dirfd, &dir_path)? {
+    let parent_dentry = match resolve_at_path_with(dirfd, &dir_path, policy)? {
         Some(d) => d,
         None => return Err(FsError::NotFound),
     };
@@ -145,15 +168,84 @@ pub fn create_file_at(dirfd: i32, path: &str, mode: u32) -> Result<Arc<Dentry>,
     }
 
     let file_mode = FileMode::from_bits_truncate(mode) | FileMode::S_IFREG;
+    crate::vfs::may_modify_dir(parent_dentry.inode.as_ref(), &current_fs_cred())?;
+    crate::security::landlock::path_mknod(&parent_dentry, file_mode)?;
+    crate::vfs::mnt_want_write(&parent_dentry)?;
+    let (file_mode, inherit) = create_mode(&parent_dentry, file_mode)?;
     let child_inode = parent_dentry.inode.create(&filename, file_mode)?;
+    init_new_inode(child_inode.as_ref(), file_mode, &inherit)?;
 
     let child_dentry = Dentry::new(filename.clone(), child_inode);
     parent_dentry.add_child(child_dentry.clone());
     DENTRY_CACHE.insert(&child_dentry);
+    crate::vfs::fsnotify_create(parent_dentry.inode.as_ref(), &filename, false);
 
     Ok(child_dentry)
 }
 
+/// 在 `path` 指定的目录中创建未链接的匿名普通文件（O_TMPFILE）
+///
+/// 返回的 dentry 名为 `#<inode 号>`，父目录项指向所在目录（用于挂载查找和显示路径），
+/// 但不加入目录的子项和缓存，只能经由打开的文件访问或由 linkat 命名。
+pub fn create_tmpfile_at(
+    dirfd: i32,
+    path: &str,
+    mode: u32,
+    policy: &ResolvePolicy,
+) -> Result<Arc<Dentry>, FsError> {
+    let dir = resolve_at_path_with(dirfd, path, policy)?.ok_or(FsError::NotFound)?;
+    if dir.inode.metadata()?.inode_type != InodeType::Directory {
+        return Err(FsError::NotDirectory);
+    }
+
+    let file_mode = FileMode::from_bits_truncate(mode) | FileMode::S_IFREG;
+    crate::vfs::may_modify_dir(dir.inode.as_ref(), &current_fs_cred())?;
+    crate::security::landlock::path_mknod(&dir, file_mode)?;
+    crate::vfs::mnt_want_write(&dir)?;
+    let (file_mode, inherit) = create_mode(&dir, file_mode)?;
+    let inode = dir.inode.tmpfile(file_mode)?;
+    init_new_inode(inode.as_ref(), file_mode, &inherit)?;
+
+    let name = format!("#{}", inode.metadata()?.inode_no);
+    let dentry = Dentry::new(name, inode);
+    dentry.set_parent(&dir);
+    Ok(dentry)
+}
+
+/// 计算在 `parent` 下新建 inode 的实际模式
+///
+/// 父目录带默认 ACL 时由 ACL 约束模式，否则应用当前任务的 umask；返回的 [`AclInherit`]
+/// 在创建后交给 [`init_new_inode`]。
+pub fn create_mode(parent: &Dentry, mode: FileMode) -> Result<(FileMode, AclInherit), FsError> {
+    let umask = current_task().lock().umask;
+    posix_acl_create(parent.inode.as_ref(), mode, umask)
+}
+
+/// 初始化新建的 inode：属主设为当前任务的 fsuid/fsgid，权限位设为 `mode`，写入从父目录继承的 ACL
+///
+/// 部分文件系统创建时忽略请求的模式，这里补一次 chmod；不支持修改属主或模式的文件系统保持原样。
+pub fn init_new_inode(
+    inode: &dyn Inode,
+    mode: FileMode,
+    inherit: &AclInherit,
+) -> Result<(), FsError> {
+    let cred = current_fs_cred();
+    let meta = inode.metadata()?;
+    if meta.mode.bits() & 0o7777 != mode.bits() & 0o7777 {
+        match inode.chmod(mode) {
+            Ok(()) | Err(FsError::NotSupported) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if meta.uid != cred.fsuid || meta.gid != cred.fsgid {
+        match inode.chown(cred.fsuid, cred.fsgid) {
+            Ok(()) | Err(FsError::NotSupported) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    inherit.apply(inode)
+}
+
 /// 验证 syslog 系统调用参数
 ///
 /// 根据操作类型检查参数的有效性。
@@ -202,8 +294,7 @@ pub fn validate_syslog_args(action: SyslogAction, bufp: *mut u8, len: i32) -> Re
 ///    - 如果 `dmesg_restrict == 0`：允许所有用户访问
 ///    - 如果 `dmesg_restrict != 0`：需要特权
 /// 2. **其他操作**：
-///    - 需要以下任一权限：
-///      - `euid == 0` (root 用户)
+///    - 需要以下任一能力（root 默认拥有全部能力）：
 ///      - `CAP_SYSLOG` (推荐)
 ///      - `CAP_SYS_ADMIN` (向后兼容)
 ///
@@ -211,53 +302,17 @@ pub fn validate_syslog_args(action: SyslogAction, bufp: *mut u8, len: i32) -> Re
 /// * `Ok(())` - 有权限
 /// * `Err(EPERM)` - 权限不足
 pub fn check_syslog_permission(action: SyslogAction) -> Result<(), i32> {
-    // TODO: 等待完成用户管理和能力模型后再实现完整的权限检查
-    //
-    // 完整实现应该包括：
-    // 1. 检查 dmesg_restrict sysctl (ReadAll 和 SizeBuffer 特殊处理)
-    // 2. 检查 euid == 0 (root 用户)
-    // 3. 检查 CAP_SYSLOG 能力
-    // 4. 检查 CAP_SYS_ADMIN 能力 (向后兼容)
-    //
-    // 临时实现：允许所有操作（开发阶段）
-
-    // 特殊处理：ReadAll 和 SizeBuffer 可能允许非特权访问
-    if matches!(action, SyslogAction::ReadAll | SyslogAction::SizeBuffer) {
-        // 检查 dmesg_restrict sysctl
-        let dmesg_restrict = get_dmesg_restrict();
-        if dmesg_restrict == 0 {
-            return Ok(()); // 允许非特权访问
-        }
-    }
-
-    // TODO: 完整的权限检查实现
-    // 临时方案：暂时允许所有操作
-    Ok(())
-
-    /* 完整实现参考（等待用户管理模块）：
-
-    // 获取当前任务
-    let task = current_task();
-    let task_locked = task.lock();
-
-    // 检查是否为 root
-    if task_locked.euid == 0 {
+    // ReadAll 和 SizeBuffer 在 dmesg_restrict == 0 时允许非特权访问
+    if matches!(action, SyslogAction::ReadAll | SyslogAction::SizeBuffer)
+        && get_dmesg_restrict() == 0
+    {
         return Ok(());
     }
-
-    // 检查 CAP_SYSLOG (Linux 2.6.38+)
-    if task_locked.capabilities.has_effective(Capability::CAP_SYSLOG) {
+    // CAP_SYS_ADMIN 为向后兼容保留
+    if capable(Capabilities::SYSLOG) || capable(Capabilities::SYS_ADMIN) {
         return Ok(());
     }
-
-    // 检查 CAP_SYS_ADMIN (向后兼容)
-    if task_locked.capabilities.has_effective(Capability::CAP_SYS_ADMIN) {
-        return Ok(());
-    }
-
-    // 权限不足
     Err(EPERM)
-    */
 }
 
 /// 获取 dmesg_restrict sysctl 值
@@ -303,7 +358,8 @@ pub fn flush_all_block_devices() -> Result<(), isize> {
     use crate::device::BLK_DRIVERS;
     use uapi::errno::EIO;
 
-    let drivers = BLK_DRIVERS.read();
+    // 刷新会睡眠，不能在 RCU 读临界区内进行
+    let drivers = BLK_DRIVERS.read().clone();
 
     if drivers.is_empty() {
         // 没有块设备也算成功(无事可做)
@@ -322,7 +378,7 @@ pub fn flush_all_block_devices() -> Result<(), isize> {
 
 /// 从文件描述符获取对应的块设备并刷新
 ///
-/// 通过 fd -> dentry -> 路径 -> 挂载点 -> 文件系统 -> 同步
+/// 通过 fd -> dentry -> 挂载点 -> 文件系统 -> 同步
 ///
 /// # 参数
 /// - `fd`: 文件描述符
@@ -333,26 +389,27 @@ pub fn flush_all_block_devices() -> Result<(), isize> {
 /// - Err(-EINVAL): fd 不支持同步(如 pipe、socket)
 /// - Err(-EIO): 块设备刷新失败
 pub fn flush_block_device_by_fd(fd: usize) -> Result<(), isize> {
-    use crate::vfs::MOUNT_TABLE;
-    use uapi::errno::EIO;
-
-    // 1. 获取文件对象
     let task = current_task();
     let file = task.lock().fd_table.get(fd).map_err(|e| e.to_errno())?;
+    flush_block_device_by_file(&file)
+}
 
-    // 2. 获取 dentry (如果不支持则说明是管道等特殊文件)
-    let dentry = file.dentry().map_err(|e| e.to_errno())?;
-
-    // 3. 获取文件的完整路径
-    let path = dentry.full_path();
+/// 刷新文件所在文件系统的块设备，见 [`flush_block_device_by_fd`]
+///
+/// 可在工作线程中调用（如 io_uring 的 FSYNC）。工作线程可能不在文件所属的
+/// 挂载命名空间中，此时找不到挂载点，退化为只同步文件自身的 inode。
+pub fn flush_block_device_by_file(file: &Arc<dyn File>) -> Result<(), isize> {
+    use uapi::errno::EIO;
 
-    // 4. 通过路径查找对应的挂载点
-    let mount_point = MOUNT_TABLE
-        .find_mount(&path)
-        .ok_or_else(|| FsError::NotSupported.to_errno())?;
+    // 1. 获取 dentry (如果不支持则说明是管道等特殊文件)
+    let dentry = file.dentry().map_err(|e| e.to_errno())?;
 
-    // 5. 调用文件系统的 sync 方法
-    mount_point.fs.sync().map_err(|_| -EIO as isize)?;
+    // 2. 在当前挂载命名空间中查找 dentry 所属的挂载点
+    match crate::vfs::current_mnt_ns().mount_of(&dentry) {
+        // 3. 调用文件系统的 sync 方法
+        Some(mount_point) => mount_point.fs.sync().map_err(|_| -EIO as isize)?,
+        None => dentry.inode.sync().map_err(|_| -EIO as isize)?,
+    }
 
     Ok(())
 }
@@ -424,13 +481,28 @@ pub fn create_file_from_dentry(
     dentry: Arc<Dentry>,
     flags: OpenFlags,
 ) -> Result<Arc<dyn File>, FsError> {
-    let inode_type = dentry.inode.metadata()?.inode_type;
+    let metadata = dentry.inode.metadata()?;
+    let inode_type = metadata.inode_type;
 
     let file: Arc<dyn File> = match inode_type {
         InodeType::File | InodeType::Directory | InodeType::Symlink => {
             // 普通文件、目录、符号链接
             Arc::new(RegFile::new(dentry, flags))
         }
+        InodeType::CharDevice
+            if metadata.rdev == makedev(chrdev_major::CONSOLE, console_minor::PTMX) =>
+        {
+            // /dev/ptmx：每次打开分配一对新的 PTY
+            Arc::new(open_ptmx(dentry, flags)?)
+        }
+        InodeType::CharDevice if metadata.rdev == makedev(chrdev_major::MEM, mem_minor::KMSG) => {
+            // /dev/kmsg：每次打开持有独立的读游标，读端与 syslog ReadAll 同样受限
+            if flags.readable() {
+                check_syslog_permission(SyslogAction::ReadAll)
+                    .map_err(|_| FsError::PermissionDenied)?;
+            }
+            Arc::new(KmsgFile::new(dentry, flags))
+        }
         InodeType::CharDevice => {
             // 字符设备
             Arc::new(CharDeviceFile::new(dentry, flags)?)
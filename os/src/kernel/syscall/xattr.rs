@@ -0,0 +1,375 @@
+//! 扩展属性系统调用实现
+//!
+//! get/set/list/removexattr 各有三个变体：按路径（跟随末尾符号链接）、`l` 前缀按路径
+//! （不跟随）与 `f` 前缀按文件描述符。属性存储由文件系统的 [`Inode`](crate::vfs::Inode)
+//! 提供，这里负责参数检查、用户内存拷贝与命名空间的访问控制（与 Linux `xattr_permission` 一致）：
+//!
+//! - `trusted.*` 需要 CAP_SYS_ADMIN，没有能力时读取得到 ENODATA、写入得到 EPERM，
+//!   listxattr 也不列出它们
+//! - `user.*` 只能附加在普通文件和目录上
+//! - `security.*` 不做限制
+//! - `system.*` 只支持 `system.posix_acl_access` / `system.posix_acl_default`，
+//!   由 [`acl`](crate::vfs::acl) 解析、校验后存储，设置访问 ACL 会同步更新模式位；
+//!   其余名字返回 EOPNOTSUPP
+//!
+//! 修改属性前要求所在挂载可写。
+
+use core::ffi::c_char;
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::{
+    arch::trap::SumGuard,
+    kernel::{
+        Capabilities, capable, current_fs_cred, current_task,
+        syscall::util::{get_path_safe, resolve_at_path_with_flags},
+    },
+    uapi::{
+        errno::{E2BIG, EFAULT, EINVAL, ENODATA, EOPNOTSUPP, EPERM, ERANGE},
+        xattr::{XATTR_LIST_MAX, XATTR_NAME_MAX, XATTR_SIZE_MAX},
+    },
+    vfs::{
+        AclType, Dentry, FsError, InodeType, PosixAcl, XattrFlags, XattrNamespace, get_acl,
+        mnt_want_write, set_acl,
+    },
+};
+
+use super::fs::AT_FDCWD;
+
+/// 按路径解析操作对象
+fn path_dentry(pathname: *const c_char, follow_symlink: bool) -> Result<Arc<Dentry>, isize> {
+    let _guard = SumGuard::new();
+    let path = get_path_safe(pathname).map_err(|_| FsError::InvalidArgument.to_errno())?;
+    resolve_at_path_with_flags(AT_FDCWD, path, follow_symlink).map_err(|e| e.to_errno())
+}
+
+/// 按文件描述符解析操作对象
+fn fd_dentry(fd: usize) -> Result<Arc<Dentry>, isize> {
+    let file = current_task()
+        .lock()
+        .fd_table
+        .get(fd)
+        .map_err(|e| e.to_errno())?;
+    file.dentry().map_err(|e| e.to_errno())
+}
+
+/// 从用户空间读取属性名，长度为 0 或超过 `XATTR_NAME_MAX` 时返回 ERANGE
+fn read_name(name: *const c_char) -> Result<String, isize> {
+    let _guard = SumGuard::new();
+    let name = get_path_safe(name).map_err(|_| -(EFAULT as isize))?;
+    if name.is_empty() || name.len() > XATTR_NAME_MAX {
+        return Err(-(ERANGE as isize));
+    }
+    Ok(String::from(name))
+}
+
+/// 按命名空间检查能否访问 `dentry` 上的属性 `name`
+fn xattr_permission(dentry: &Dentry, name: &str, write: bool) -> Result<(), isize> {
+    let denied = if write { EPERM } else { ENODATA };
+    let Some((namespace, _)) = XattrNamespace::parse(name) else {
+        return Err(-(EOPNOTSUPP as isize));
+    };
+    match namespace {
+        XattrNamespace::Trusted => {
+            if !capable(Capabilities::SYS_ADMIN) {
+                return Err(-(denied as isize));
+            }
+        }
+        XattrNamespace::User => {
+            let inode_type = dentry
+                .inode
+                .metadata()
+                .map_err(|e| e.to_errno())?
+                .inode_type;
+            if inode_type != InodeType::File && inode_type != InodeType::Directory {
+                return Err(-(denied as isize));
+            }
+        }
+        XattrNamespace::Security => {}
+        XattrNamespace::System => {
+            if AclType::from_xattr_name(name).is_none() {
+                return Err(-(EOPNOTSUPP as isize));
+            }
+        }
+    }
+    if write {
+        mnt_want_write(dentry).map_err(|e| e.to_errno())?;
+    }
+    Ok(())
+}
+
+fn do_setxattr(
+    dentry: &Dentry,
+    name: *const c_char,
+    value: *const u8,
+    size: usize,
+    flags: u32,
+) -> isize {
+    let Some(flags) = XattrFlags::from_bits(flags) else {
+        return -(EINVAL as isize);
+    };
+    let name = match read_name(name) {
+        Ok(name) => name,
+        Err(e) => return e,
+    };
+    if size > XATTR_SIZE_MAX {
+        return -(E2BIG as isize);
+    }
+    if size > 0 && value.is_null() {
+        return -(EFAULT as isize);
+    }
+    if let Err(e) = xattr_permission(dentry, &name, true) {
+        return e;
+    }
+
+    let value = if size > 0 {
+        let _guard = SumGuard::new();
+        // SAFETY: 指针非空，长度不超过 XATTR_SIZE_MAX，未映射的页由缺页处理负责
+        unsafe { core::slice::from_raw_parts(value, size) }.to_vec()
+    } else {
+        Vec::new()
+    };
+
+    let result = match AclType::from_xattr_name(&name) {
+        Some(ty) => PosixAcl::from_xattr(&value)
+            .and_then(|acl| set_acl(dentry.inode.as_ref(), ty, acl.as_ref(), &current_fs_cred())),
+        None => dentry.inode.setxattr(&name, &value, flags),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => e.to_errno(),
+    }
+}
+
+fn do_getxattr(dentry: &Dentry, name: *const c_char, value: *mut u8, size: usize) -> isize {
+    let name = match read_name(name) {
+        Ok(name) => name,
+        Err(e) => return e,
+    };
+    if let Err(e) = xattr_permission(dentry, &name, false) {
+        return e;
+    }
+
+    let data = match AclType::from_xattr_name(&name) {
+        Some(ty) => get_acl(dentry.inode.as_ref(), ty)
+            .and_then(|acl| acl.map(|acl| acl.to_xattr()).ok_or(FsError::NoData)),
+        None => dentry.inode.getxattr(&name),
+    };
+    let data = match data {
+        Ok(data) => data,
+        Err(e) => return e.to_errno(),
+    };
+    if size == 0 {
+        return data.len() as isize;
+    }
+    if data.len() > size {
+        return -(ERANGE as isize);
+    }
+    if value.is_null() {
+        return -(EFAULT as isize);
+    }
+
+    let _guard = SumGuard::new();
+    // SAFETY: 指针非空，用户缓冲区长度 size 不小于 data.len()
+    unsafe { core::ptr::copy_nonoverlapping(data.as_ptr(), value, data.len()) };
+    data.len() as isize
+}
+
+fn do_listxattr(dentry: &Dentry, list: *mut u8, size: usize) -> isize {
+    let names = match dentry.inode.listxattr() {
+        Ok(names) => names,
+        // 不支持扩展属性的文件系统上列表为空
+        Err(FsError::NotSupported) => Vec::new(),
+        Err(e) => return e.to_errno(),
+    };
+
+    let privileged = capable(Capabilities::SYS_ADMIN);
+    let mut buf = Vec::new();
+    for name in names {
+        if !privileged
+            && XattrNamespace::parse(&name).map(|(ns, _)| ns) == Some(XattrNamespace::Trusted)
+        {
+            continue;
+        }
+        buf.extend_from_slice(name.as_bytes());
+        buf.push(0);
+    }
+    if buf.len() > XATTR_LIST_MAX {
+        return -(E2BIG as isize);
+    }
+    if size == 0 {
+        return buf.len() as isize;
+    }
+    if buf.len() > size {
+        return -(ERANGE as isize);
+    }
+    if list.is_null() {
+        return -(EFAULT as isize);
+    }
+
+    let _guard = SumGuard::new();
+    // SAFETY: 指针非空，用户缓冲区长度 size 不小于 buf.len()
+    unsafe { core::ptr::copy_nonoverlapping(buf.as_ptr(), list, buf.len()) };
+    buf.len() as isize
+}
+
+fn do_removexattr(dentry: &Dentry, name: *const c_char) -> isize {
+    let name = match read_name(name) {
+        Ok(name) => name,
+        Err(e) => return e,
+    };
+    if let Err(e) = xattr_permission(dentry, &name, true) {
+        return e;
+    }
+
+    let result = match AclType::from_xattr_name(&name) {
+        Some(ty) => set_acl(dentry.inode.as_ref(), ty, None, &current_fs_cred()),
+        None => dentry.inode.removexattr(&name),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => e.to_errno(),
+    }
+}
+
+/// setxattr - 设置扩展属性
+///
+/// # 参数
+/// * `pathname` - 文件路径（跟随末尾符号链接）
+/// * `name` - 带命名空间前缀的属性名
+/// * `value` / `size` - 属性值
+/// * `flags` - XATTR_CREATE / XATTR_REPLACE
+///
+/// # 返回值
+/// * 0 - 成功
+/// * -EEXIST - 指定 XATTR_CREATE 而属性已存在
+/// * -ENODATA - 指定 XATTR_REPLACE 而属性不存在
+/// * -EOPNOTSUPP - 命名空间或文件系统不支持
+pub fn setxattr(
+    pathname: *const c_char,
+    name: *const c_char,
+    value: *const u8,
+    size: usize,
+    flags: u32,
+) -> isize {
+    match path_dentry(pathname, true) {
+        Ok(dentry) => do_setxattr(&dentry, name, value, size, flags),
+        Err(e) => e,
+    }
+}
+
+/// lsetxattr - 设置扩展属性，不跟随末尾符号链接
+pub fn lsetxattr(
+    pathname: *const c_char,
+    name: *const c_char,
+    value: *const u8,
+    size: usize,
+    flags: u32,
+) -> isize {
+    match path_dentry(pathname, false) {
+        Ok(dentry) => do_setxattr(&dentry, name, value, size, flags),
+        Err(e) => e,
+    }
+}
+
+/// fsetxattr - 设置打开文件的扩展属性
+pub fn fsetxattr(
+    fd: usize,
+    name: *const c_char,
+    value: *const u8,
+    size: usize,
+    flags: u32,
+) -> isize {
+    match fd_dentry(fd) {
+        Ok(dentry) => do_setxattr(&dentry, name, value, size, flags),
+        Err(e) => e,
+    }
+}
+
+/// getxattr - 读取扩展属性
+///
+/// `size` 为 0 时只返回值的长度；缓冲区不够时返回 -ERANGE。
+pub fn getxattr(
+    pathname: *const c_char,
+    name: *const c_char,
+    value: *mut u8,
+    size: usize,
+) -> isize {
+    match path_dentry(pathname, true) {
+        Ok(dentry) => do_getxattr(&dentry, name, value, size),
+        Err(e) => e,
+    }
+}
+
+/// lgetxattr - 读取扩展属性，不跟随末尾符号链接
+pub fn lgetxattr(
+    pathname: *const c_char,
+    name: *const c_char,
+    value: *mut u8,
+    size: usize,
+) -> isize {
+    match path_dentry(pathname, false) {
+        Ok(dentry) => do_getxattr(&dentry, name, value, size),
+        Err(e) => e,
+    }
+}
+
+/// fgetxattr - 读取打开文件的扩展属性
+pub fn fgetxattr(fd: usize, name: *const c_char, value: *mut u8, size: usize) -> isize {
+    match fd_dentry(fd) {
+        Ok(dentry) => do_getxattr(&dentry, name, value, size),
+        Err(e) => e,
+    }
+}
+
+/// listxattr - 列出扩展属性名
+///
+/// 名字以 `\0` 分隔依次写入 `list`；`size` 为 0 时只返回所需长度。
+pub fn listxattr(pathname: *const c_char, list: *mut u8, size: usize) -> isize {
+    match path_dentry(pathname, true) {
+        Ok(dentry) => do_listxattr(&dentry, list, size),
+        Err(e) => e,
+    }
+}
+
+/// llistxattr - 列出扩展属性名，不跟随末尾符号链接
+pub fn llistxattr(pathname: *const c_char, list: *mut u8, size: usize) -> isize {
+    match path_dentry(pathname, false) {
+        Ok(dentry) => do_listxattr(&dentry, list, size),
+        Err(e) => e,
+    }
+}
+
+/// flistxattr - 列出打开文件的扩展属性名
+pub fn flistxattr(fd: usize, list: *mut u8, size: usize) -> isize {
+    match fd_dentry(fd) {
+        Ok(dentry) => do_listxattr(&dentry, list, size),
+        Err(e) => e,
+    }
+}
+
+/// removexattr - 删除扩展属性，属性不存在时返回 -ENODATA
+pub fn removexattr(pathname: *const c_char, name: *const c_char) -> isize {
+    match path_dentry(pathname, true) {
+        Ok(dentry) => do_removexattr(&dentry, name),
+        Err(e) => e,
+    }
+}
+
+/// lremovexattr - 删除扩展属性，不跟随末尾符号链接
+pub fn lremovexattr(pathname: *const c_char, name: *const c_char) -> isize {
+    match path_dentry(pathname, false) {
+        Ok(dentry) => do_removexattr(&dentry, name),
+        Err(e) => e,
+    }
+}
+
+/// fremovexattr - 删除打开文件的扩展属性
+pub fn fremovexattr(fd: usize, name: *const c_char) -> isize {
+    match fd_dentry(fd) {
+        Ok(dentry) => do_removexattr(&dentry, name),
+        Err(e) => e,
+    }
+}
@@ -16,7 +16,7 @@ use crate::{
         scheduler::Scheduler,
         task::{TASK_MANAGER, TaskStruct, task_manager::TaskManagerTrait},
     },
-    mm::frame_allocator::{alloc_contig_frames, alloc_frame},
+    mm::{frame_allocator::alloc_frame, kstack::KernelStack},
     sync::SpinLock,
 };
 
@@ -55,7 +55,7 @@ pub fn kthread_spawn(entry_point: fn()) -> u32 {
         )
     };
 
-    let kstack_tracker = alloc_contig_frames(4).expect("kthread_spawn: failed to alloc kstack");
+    let kstack = KernelStack::new().expect("kthread_spawn: failed to alloc kstack");
     let trap_frame_tracker = alloc_frame().expect("kthread_spawn: failed to alloc trap_frame");
 
     // 分配 Task 结构体和内核栈
@@ -64,7 +64,7 @@ pub fn kthread_spawn(entry_point: fn()) -> u32 {
         pid,
         ppid,
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         signal_handlers,
         blocked,
@@ -175,6 +175,7 @@ pub fn kernel_execve(path: &str, argv: &[&str], envp: &[&str]) -> ! {
 
     // 2. 包装内存空间
     let space = Arc::new(SpinLock::new(prepared.space));
+    crate::mm::swap::lru_add_space(&space);
     // 换掉当前任务的地址空间，e.g. 切换 satp
     {
         // 先切换到新地址空间，再写入用户栈布局
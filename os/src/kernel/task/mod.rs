@@ -6,7 +6,7 @@
 //! - 任务管理器与任务表（`task_manager`）
 //! - TID 分配（`tid_allocator`）
 //! - Futex 与工作队列等辅助机制（`futex` / `work_queue`）
-//! - 凭证与能力（`cred` / `cap`）
+//! - 凭证与能力（`cred` / `cap`），用户命名空间（`user_ns`）
 //!
 //! 调度与阻塞/唤醒逻辑主要位于 `os/src/kernel/scheduler/`；本模块会在合适的路径调用调度器
 //! 提供的接口完成状态迁移与上下文切换。
@@ -18,10 +18,12 @@ mod exec_loader;
 mod futex;
 mod ktask;
 mod process;
+mod rseq;
 mod task_manager;
 mod task_state;
 mod task_struct;
 mod tid_allocator;
+mod user_ns;
 mod work_queue;
 
 pub use cap::*;
@@ -30,11 +32,13 @@ pub use exec_loader::*;
 pub use futex::*;
 pub use ktask::*;
 pub use process::*;
+pub use rseq::*;
 pub use task_manager::{TASK_MANAGER, TaskManagerTrait};
 pub use task_state::TaskState;
 pub use task_struct::FsStruct;
 pub use task_struct::SharedTask;
 pub use task_struct::Task as TaskStruct;
+pub use user_ns::*;
 pub use work_queue::*;
 
 use alloc::sync::Arc;
@@ -21,6 +21,15 @@ pub fn exit_process(task: SharedTask, code: i32) {
     if !task.lock().is_process() {
         panic!("exit_process called on a non-process task");
     }
+    // 会话首进程退出：控制终端与会话解除关联（前台进程组收到 SIGHUP）
+    let (sid, ctty) = {
+        let mut t = task.lock();
+        let ctty = if t.pid == t.sid { t.ctty.take() } else { None };
+        (t.sid, ctty)
+    };
+    if let Some(tty) = ctty.filter(|tty| tty.session() == sid) {
+        tty.disassociate();
+    }
     let (children, threads, init_task) = {
         let mut t = TASK_MANAGER.lock();
         t.exit_task(task.clone(), code);
@@ -48,6 +57,10 @@ pub fn exit_process(task: SharedTask, code: i32) {
             t.exit_task(thread, 0);
         }
     }
+    let pid = task.lock().pid;
+    crate::kernel::clear_itimers(pid);
+    // 进程持有的记录锁随进程退出释放
+    crate::vfs::file_lock_manager().release_all_locks(pid as i32);
     notify_parent(task);
 }
 
@@ -0,0 +1,166 @@
+//! 可重启序列（rseq）
+//!
+//! 线程经 `rseq(2)` 注册一块用户态 [`Rseq`] 后，内核在它返回用户态前：
+//! - 把当前 CPU 编号写入 `cpu_id_start` / `cpu_id`；
+//! - 若它在 `rseq_cs` 描述的临界区内被抢占或即将处理信号，把返回地址改到 `abort_ip`。
+//!
+//! 任务被切换出去再切回时由调度器调用 [`rseq_preempt`] 打上标记，
+//! 两个架构的陷阱返回路径在投递信号前调用 [`rseq_handle_notify_resume`]。
+
+use core::mem::offset_of;
+use core::sync::atomic::Ordering;
+
+use uapi::errno::{EFAULT, EINVAL};
+use uapi::rseq::{RSEQ_CPU_ID_UNINITIALIZED, Rseq, RseqCs};
+
+use crate::arch::kernel::cpu::cpu_id;
+use crate::arch::trap::TrapFrame;
+use crate::kernel::try_current_task;
+use crate::util::user_buffer::{copy_from_user, copy_to_user};
+
+/// 任务注册的 rseq 区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RseqArea {
+    /// 用户态 `struct rseq` 的地址
+    pub addr: usize,
+    /// 注册时给出的长度
+    pub len: u32,
+    /// 中止处理入口前的签名
+    pub sig: u32,
+}
+
+impl RseqArea {
+    fn field<T>(&self, offset: usize) -> *mut T {
+        (self.addr + offset) as *mut T
+    }
+
+    /// 把当前 CPU 写入用户态区域
+    pub fn update_cpu_id(&self) -> Result<(), i32> {
+        let cpu = cpu_id() as u32;
+        copy_to_user(self.field(offset_of!(Rseq, cpu_id_start)), cpu)?;
+        copy_to_user(self.field(offset_of!(Rseq, cpu_id)), cpu)?;
+        copy_to_user(self.field(offset_of!(Rseq, node_id)), 0u32)?;
+        copy_to_user(self.field(offset_of!(Rseq, mm_cid)), cpu)
+    }
+
+    /// 注销时把 CPU 字段恢复为未注册状态
+    pub fn reset_cpu_id(&self) -> Result<(), i32> {
+        copy_to_user(self.field(offset_of!(Rseq, cpu_id_start)), 0u32)?;
+        copy_to_user(
+            self.field(offset_of!(Rseq, cpu_id)),
+            RSEQ_CPU_ID_UNINITIALIZED as u32,
+        )?;
+        copy_to_user(self.field(offset_of!(Rseq, node_id)), 0u32)?;
+        copy_to_user(self.field(offset_of!(Rseq, mm_cid)), 0u32)
+    }
+
+    /// 若返回地址落在当前临界区内，改为跳到中止入口
+    ///
+    /// 处理后清空 `rseq_cs`。描述符非法或签名不符时返回错误。
+    fn ip_fixup(&self, tf: &mut TrapFrame) -> Result<(), i32> {
+        let cs_field = self.field::<u64>(offset_of!(Rseq, rseq_cs));
+        let cs_addr = copy_from_user(cs_field as *const u64)?;
+        if cs_addr == 0 {
+            return Ok(());
+        }
+        let cs = copy_from_user(cs_addr as usize as *const RseqCs)?;
+        let abort_ip = abort_target(&cs, tf.get_sepc())?;
+        copy_to_user(cs_field, 0u64)?;
+        let Some(abort_ip) = abort_ip else {
+            return Ok(());
+        };
+        let sig = copy_from_user((abort_ip - 4) as *const u32)?;
+        if sig != self.sig {
+            return Err(EINVAL);
+        }
+        tf.set_sepc(abort_ip);
+        Ok(())
+    }
+}
+
+/// 检查临界区描述符，`ip` 落在临界区内时返回中止入口
+fn abort_target(cs: &RseqCs, ip: usize) -> Result<Option<usize>, i32> {
+    if cs.version != 0 || cs.flags != 0 {
+        return Err(EINVAL);
+    }
+    let start = cs.start_ip as usize;
+    let end = start
+        .checked_add(cs.post_commit_offset as usize)
+        .ok_or(EINVAL)?;
+    let abort_ip = cs.abort_ip as usize;
+    // 中止入口不能落在临界区内，且前面要放得下签名
+    if (start..end).contains(&abort_ip) || abort_ip < 4 {
+        return Err(EFAULT);
+    }
+    Ok((start..end).contains(&ip).then_some(abort_ip))
+}
+
+/// 当前任务被切换出去后重新运行时调用
+pub fn rseq_preempt() {
+    if let Some(task) = try_current_task() {
+        let mut t = task.lock();
+        if t.rseq.is_some() {
+            t.rseq_pending = true;
+        }
+    }
+}
+
+/// 返回用户态前处理 rseq：必要时中止临界区并刷新 CPU 编号
+///
+/// 用户态区域无法访问或描述符非法时按 Linux 的做法以 SIGSEGV 终止任务。
+pub fn rseq_handle_notify_resume() {
+    let Some(task) = try_current_task() else {
+        return;
+    };
+    let (area, tf_ptr) = {
+        let mut t = task.lock();
+        let Some(area) = t.rseq else {
+            return;
+        };
+        let signal = t.pending.has_deliverable_signal(t.blocked)
+            || t.shared_pending.lock().has_deliverable_signal(t.blocked);
+        if !t.rseq_pending && !signal {
+            return;
+        }
+        t.rseq_pending = false;
+        (area, t.trap_frame_ptr.load(Ordering::SeqCst))
+    };
+    // SAFETY: trap_frame_ptr 指向当前任务自己的陷阱帧
+    let tf = unsafe { &mut *tf_ptr };
+    if area
+        .ip_fixup(tf)
+        .and_then(|_| area.update_cpu_id())
+        .is_err()
+    {
+        crate::kernel::terminate_task(128 + uapi::signal::NUM_SIGSEGV);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_rseq_abort_target() {
+        let cs = RseqCs {
+            version: 0,
+            flags: 0,
+            start_ip: 0x1000,
+            post_commit_offset: 0x20,
+            abort_ip: 0x2004,
+        };
+        assert_eq!(abort_target(&cs, 0x1000), Ok(Some(0x2004)));
+        assert_eq!(abort_target(&cs, 0x101c), Ok(Some(0x2004)));
+        // 提交点及之后不再中止
+        assert_eq!(abort_target(&cs, 0x1020), Ok(None));
+        assert_eq!(abort_target(&cs, 0xffc), Ok(None));
+
+        let inside = RseqCs {
+            abort_ip: 0x1010,
+            ..cs
+        };
+        assert_eq!(abort_target(&inside, 0x1000), Err(EFAULT));
+        let bad_version = RseqCs { version: 1, ..cs };
+        assert_eq!(abort_target(&bad_version, 0x1000), Err(EINVAL));
+    }
+}
@@ -12,13 +12,14 @@ use alloc::vec::Vec;
 use crate::kernel::task::SharedTask;
 use crate::kernel::task::tid_allocator::TidAllocator;
 use crate::kernel::{TaskState, exit_task_with_block, wake_up_with_block};
-use crate::sync::SpinLock;
+use crate::sync::{SpinLock, lock_class};
 use uapi::signal::SignalFlags;
 
 use lazy_static::lazy_static;
 
 lazy_static! {
-    pub static ref TASK_MANAGER: SpinLock<TaskManager> = SpinLock::new(TaskManager::new());
+    pub static ref TASK_MANAGER: SpinLock<TaskManager> =
+        SpinLock::with_class(TaskManager::new(), &lock_class::TASK_MANAGER);
 }
 
 /// 任务管理器接口
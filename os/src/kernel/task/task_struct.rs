@@ -2,7 +2,7 @@
 //!
 //! 包含任务的核心信息，如上下文、状态、内存空间等
 #![allow(dead_code)]
-use core::sync::atomic::{AtomicPtr, Ordering};
+use core::sync::atomic::{AtomicI32, AtomicPtr, AtomicU64, Ordering};
 
 use alloc::{string::String, sync::Arc, vec::Vec};
 
@@ -11,24 +11,27 @@ use crate::{
         kernel::{context::Context, task::setup_stack_layout},
         trap::TrapFrame,
     },
-    ipc::{SignalHandlerTable, SignalPending},
+    ipc::{RestartBlock, SignalHandlerTable, SignalPending},
     kernel::{
         WaitQueue,
+        hrtimer::Ktime,
         task::{forkret, task_state::TaskState},
     },
     mm::{
         MemorySpace,
         address::{ConvertablePaddr, PageNum, UsizeConvert},
-        frame_allocator::{FrameRangeTracker, FrameTracker},
+        frame_allocator::FrameTracker,
+        kstack::KernelStack,
     },
     pr_debug,
-    sync::SpinLock,
+    security::{landlock::LandlockDomain, seccomp::Seccomp},
+    sync::{SpinLock, lock_class},
     uapi::{
         resource::RlimitStruct,
         signal::{SignalFlags, SignalStack},
         uts_namespace::UtsNamespace,
     },
-    vfs::{Dentry, FDTable},
+    vfs::{Dentry, FDTable, MountNamespace, Tty},
 };
 
 /// 共享任务句柄
@@ -69,6 +72,12 @@ pub struct Task {
     /// CPU 亲和性掩码
     /// -1 表示可以在任何 CPU 上运行
     pub cpu_affinity: i32,
+    /// 已累计的 CPU 时间（纳秒），不含正在进行的这次运行
+    pub exec_runtime: Ktime,
+    /// 本次开始在 CPU 上运行的单调时间，未运行时为 None
+    pub exec_start: Option<Ktime>,
+    /// 线程组共享的累计 CPU 时间（纳秒）
+    pub group_runtime: Arc<AtomicU64>,
     /// 任务当前的状态
     pub state: TaskState,
     /// 任务的id
@@ -84,6 +93,10 @@ pub struct Task {
     pub ppid: u32,
     /// 任务的进程组id
     pub pgid: u32,
+    /// 任务的会话id
+    pub sid: u32,
+    /// 控制终端（由 TIOCSCTTY 或会话首进程打开终端时设置，fork 时继承，setsid 时清除）
+    pub ctty: Option<Arc<Tty>>,
     /// 任务的子任务列表
     pub children: Arc<SpinLock<Vec<SharedTask>>>,
     /// 任务的等待队列
@@ -100,8 +113,8 @@ pub struct Task {
     /// 由 exit 接口设置
     /// 对应于 waitpid 的 exit_status
     pub exit_code: Option<i32>,
-    /// 内核栈跟踪器
-    kstack_tracker: FrameRangeTracker,
+    /// 内核栈（带保护页）
+    kstack: KernelStack,
     /// 任务的 TrapFrame 跟踪器
     trap_frame_tracker: FrameTracker,
     /// 信号屏蔽字
@@ -114,14 +127,22 @@ pub struct Task {
     pub signal_handlers: Arc<SpinLock<SignalHandlerTable>>,
     /// 备用信号栈信息
     pub signal_stack: Arc<SpinLock<SignalStack>>,
+    /// 被信号打断、等待 restart_syscall 继续的系统调用
+    pub restart_block: Option<RestartBlock>,
     /// 退出信号, 当任务退出时发送给父任务的信号
     pub exit_signal: u8,
     /// UTS 命名空间
     pub uts_namespace: Arc<SpinLock<UtsNamespace>>,
     /// 资源限制结构体
     pub rlimit: Arc<SpinLock<RlimitStruct>>,
+    /// OOM killer 的 badness 调整值（`/proc/[pid]/oom_score_adj`），线程组共享，fork 时复制
+    pub oom_score_adj: Arc<AtomicI32>,
     /// 健壮列表头地址及其大小
     pub robust_list: Option<usize>,
+    /// 注册的 rseq 区域（fork 时继承，CLONE_VM 与 execve 时清除）
+    pub rseq: Option<super::RseqArea>,
+    /// 返回用户态前需要处理 rseq（被抢占后或刚继承注册）
+    pub rseq_pending: bool,
     /// 线程ID地址
     pub set_child_tid: usize,
     /// 线程退出时清除的线程ID地址
@@ -132,8 +153,18 @@ pub struct Task {
     pub credential: super::Credential,
     /// 文件创建掩码
     pub umask: u32,
+    /// PR_SET_NO_NEW_PRIVS 标志，fork 时继承，设置后不可清除
+    pub no_new_privs: bool,
+    /// seccomp 模式与过滤器链，fork 时继承
+    pub seccomp: Seccomp,
+    /// Landlock 域，fork 时继承，execve 时保留
+    pub landlock: Option<Arc<LandlockDomain>>,
+    /// 所属用户命名空间，fork 时继承（CLONE_NEWUSER 时为新建的子命名空间）
+    pub user_ns: Arc<super::UserNamespace>,
 
     // === 文件系统 ===
+    /// 挂载命名空间，fork 时共享（CLONE_NEWNS 时复制）
+    pub mnt_ns: Arc<MountNamespace>,
     /// 文件描述符表
     pub fd_table: Arc<FDTable>,
     /// 文件系统信息
@@ -161,7 +192,7 @@ impl Task {
     /// * `tid`: 任务ID
     /// * `pid`: 进程ID
     /// * `ppid`: 父任务ID
-    /// * `kstack_tracker`: 内核栈的帧跟踪器
+    /// * `kstack`: 内核栈
     /// * `trap_frame_tracker`: TrapFrame 的帧跟踪器
     /// * `entry`: 任务的入口地址
     /// # 返回值
@@ -172,7 +203,7 @@ impl Task {
         pid: u32,
         ppid: u32,
         children: Arc<SpinLock<Vec<Arc<SpinLock<Task>>>>>,
-        kstack_tracker: FrameRangeTracker,
+        kstack: KernelStack,
         trap_frame_tracker: FrameTracker,
         signal_handlers: Arc<SpinLock<SignalHandlerTable>>,
         blocked: SignalFlags,
@@ -188,7 +219,7 @@ impl Task {
             ppid,
             tid, // 内核线程不属于常规意义的进程组
             children,
-            kstack_tracker,
+            kstack,
             trap_frame_tracker,
             None,
             signal_handlers,
@@ -219,7 +250,7 @@ impl Task {
         ppid: u32,
         pgid: u32,
         children: Arc<SpinLock<Vec<Arc<SpinLock<Task>>>>>,
-        kstack_tracker: FrameRangeTracker,
+        kstack: KernelStack,
         trap_frame_tracker: FrameTracker,
         memory_space: Arc<SpinLock<MemorySpace>>,
         signal_handlers: Arc<SpinLock<SignalHandlerTable>>,
@@ -238,7 +269,7 @@ impl Task {
             ppid,
             pgid,
             children,
-            kstack_tracker,
+            kstack,
             trap_frame_tracker,
             Some(memory_space),
             signal_handlers,
@@ -286,6 +317,11 @@ impl Task {
         new_fd_table.close_exec();
         self.fd_table = Arc::new(new_fd_table);
 
+        // 旧映像的 rseq 区域与健壮列表不再有效
+        self.rseq = None;
+        self.rseq_pending = false;
+        self.robust_list = None;
+
         let tf_ptr = self.trap_frame_ptr.load(Ordering::SeqCst);
 
         // 注意：以下拷贝时对sp进行的操作均要求已经可以访问用户栈空间
@@ -381,11 +417,45 @@ impl Task {
     /// 把已初始化的 TaskStruct 包装为共享任务句柄
     /// 返回值: 包装后的 SharedTask
     pub fn into_shared(self) -> SharedTask {
-        Arc::new(SpinLock::new(self))
+        Arc::new(SpinLock::with_class(self, &lock_class::TASK))
     }
 
     /// 返回一个空的子任务列表
     /// 用于创建新任务时初始化 children 字段
+    /// 任务开始在 CPU 上运行
+    /// # 参数
+    /// * `now`: 当前单调时间
+    pub fn start_exec(&mut self, now: Ktime) {
+        self.exec_start = Some(now);
+    }
+
+    /// 任务离开 CPU，把本次运行时间计入线程与线程组
+    /// # 参数
+    /// * `now`: 当前单调时间
+    pub fn stop_exec(&mut self, now: Ktime) {
+        if let Some(start) = self.exec_start.take() {
+            let delta = now.saturating_sub(start);
+            self.exec_runtime += delta;
+            self.group_runtime.fetch_add(delta, Ordering::Relaxed);
+        }
+    }
+
+    /// 线程的 CPU 时间（纳秒），包括正在进行的这次运行
+    pub fn thread_cputime(&self, now: Ktime) -> Ktime {
+        self.exec_runtime + self.running_time(now)
+    }
+
+    /// 线程组的 CPU 时间（纳秒）
+    ///
+    /// 只包括本线程正在进行的这次运行，其他线程在下次离开 CPU 时计入。
+    pub fn process_cputime(&self, now: Ktime) -> Ktime {
+        self.group_runtime.load(Ordering::Relaxed) + self.running_time(now)
+    }
+
+    fn running_time(&self, now: Ktime) -> Ktime {
+        self.exec_start.map_or(0, |start| now.saturating_sub(start))
+    }
+
     pub fn empty_children() -> Arc<SpinLock<Vec<SharedTask>>> {
         Arc::new(SpinLock::new(Vec::new()))
     }
@@ -396,7 +466,7 @@ impl Task {
         ppid: u32,
         pgid: u32,
         children: Arc<SpinLock<Vec<SharedTask>>>,
-        kstack_tracker: FrameRangeTracker,
+        kstack: KernelStack,
         trap_frame_tracker: FrameTracker,
         memory_space: Option<Arc<SpinLock<MemorySpace>>>,
         signal_handlers: Arc<SpinLock<SignalHandlerTable>>,
@@ -410,7 +480,8 @@ impl Task {
         fs: Arc<SpinLock<FsStruct>>,
     ) -> Self {
         let trap_frame_ptr = trap_frame_tracker.ppn().start_addr().to_vaddr().as_usize();
-        let kstack_base = kstack_tracker.end_ppn().start_addr().to_vaddr().as_usize();
+        let kstack_base = kstack.top();
+        kstack.set_owner(tid);
 
         Task {
             context: Context::zero_init(),
@@ -419,33 +490,47 @@ impl Task {
             processor_id: 0,
             on_cpu: None,
             cpu_affinity: -1,
+            exec_runtime: 0,
+            exec_start: None,
+            group_runtime: Arc::new(AtomicU64::new(0)),
             state: TaskState::Running,
             tid,
             pid,
             exe_path: None,
             ppid,
             pgid,
+            sid: pgid,
+            ctty: None,
             children,
             wait_child: Arc::new(SpinLock::new(WaitQueue::new())),
             kstack_base,
-            kstack_tracker,
+            kstack,
             trap_frame_tracker,
             trap_frame_ptr: AtomicPtr::new(trap_frame_ptr as *mut TrapFrame),
             memory_space,
             exit_code: None,
             signal_handlers,
             signal_stack,
+            restart_block: None,
             exit_signal,
             uts_namespace,
             rlimit,
+            oom_score_adj: Arc::new(AtomicI32::new(0)),
             blocked,
             pending: SignalPending::empty(),
             shared_pending,
             robust_list: None,
+            rseq: None,
+            rseq_pending: false,
             set_child_tid: 0,
             clear_child_tid: 0,
             credential: super::Credential::root(),
             umask: 0o022,
+            no_new_privs: false,
+            seccomp: Seccomp::default(),
+            landlock: None,
+            user_ns: super::init_user_ns(),
+            mnt_ns: crate::vfs::init_mnt_ns(),
             fd_table,
             fs,
         }
@@ -453,12 +538,8 @@ impl Task {
 
     #[cfg(test)]
     pub fn new_dummy_task(tid: u32) -> Self {
-        use crate::{
-            mm::frame_allocator::{alloc_contig_frames, alloc_frame},
-            uapi::resource::INIT_RLIMITS,
-        };
-        let kstack_tracker =
-            alloc_contig_frames(1).expect("new_dummy_task: failed to alloc kstack");
+        use crate::{mm::frame_allocator::alloc_frame, uapi::resource::INIT_RLIMITS};
+        let kstack = KernelStack::new().expect("new_dummy_task: failed to alloc kstack");
         let trap_frame_tracker = alloc_frame().expect("new_dummy_task: failed to alloc trap_frame");
         Self::new(
             tid,
@@ -466,7 +547,7 @@ impl Task {
             0,
             0,
             Task::empty_children(),
-            kstack_tracker,
+            kstack,
             trap_frame_tracker,
             None,
             Arc::new(SpinLock::new(SignalHandlerTable::new())),
@@ -513,4 +594,23 @@ mod tests {
         assert!(t.is_process());
         assert!(matches!(t.state, TaskState::Running));
     }
+
+    // CPU 时间：离开 CPU 时计入线程与线程组，线程组时间在线程间共享
+    #[test_case]
+    fn test_task_cputime_accounting() {
+        let mut t = Task::new_dummy_task(8);
+        let mut peer = Task::new_dummy_task(9);
+        peer.group_runtime = t.group_runtime.clone();
+
+        t.start_exec(100);
+        assert!(t.thread_cputime(150) == 50);
+        t.stop_exec(200);
+        assert!(t.exec_start.is_none());
+        assert!(t.thread_cputime(1000) == 100);
+
+        peer.start_exec(300);
+        peer.stop_exec(330);
+        assert!(peer.thread_cputime(1000) == 30);
+        assert!(t.process_cputime(1000) == 130);
+    }
 }
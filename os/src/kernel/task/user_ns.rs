@@ -0,0 +1,357 @@
+//! 用户命名空间
+//!
+//! 每个任务属于一个用户命名空间（[`UserNamespace`]），命名空间组成以初始命名空间为根的树。
+//! 凭证中保存的始终是内核（初始命名空间）ID，系统调用边界处经 `uid_map` / `gid_map`
+//! 在命名空间内 ID 与内核 ID 之间转换；未映射的内核 ID 显示为溢出 ID（65534）。
+//!
+//! 映射只能写入一次，每行 `<ns 内起始 ID> <父命名空间起始 ID> <数量>`，写入时父命名空间 ID
+//! 经父命名空间的映射换算为内核 ID 保存。
+//!
+//! 能力按命名空间评估（[`ns_capable`]）：任务在自己的命名空间及其后代中拥有凭证中的能力；
+//! 命名空间创建者（父命名空间中 euid 相同的任务）在其中拥有全部能力；
+//! 对祖先命名空间则没有任何能力，全局特权操作使用 [`capable`]，即相对初始命名空间检查。
+
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
+
+use lazy_static::lazy_static;
+use uapi::errno::{EINVAL, EPERM, EUSERS};
+
+use super::{Capabilities, TaskStruct, current_task};
+use crate::sync::SpinLock;
+use crate::vfs::FsCred;
+
+/// 未映射 UID 的显示值
+pub const OVERFLOW_UID: u32 = 65534;
+/// 未映射 GID 的显示值
+pub const OVERFLOW_GID: u32 = 65534;
+
+/// 命名空间最大嵌套深度（与 Linux 一致）
+const MAX_USER_NS_LEVEL: u32 = 32;
+
+/// 单个映射最多的行数（与 Linux 一致）
+const UID_GID_MAP_MAX_EXTENTS: usize = 340;
+
+/// 映射种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdMapKind {
+    /// `/proc/[pid]/uid_map`
+    Uid,
+    /// `/proc/[pid]/gid_map`
+    Gid,
+}
+
+/// 映射中的一段：`[first, first + count)` 映射到 `[lower_first, lower_first + count)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdMapExtent {
+    /// 命名空间内的起始 ID
+    pub first: u32,
+    /// 对应的下层起始 ID
+    pub lower_first: u32,
+    /// 长度
+    pub count: u32,
+}
+
+/// ID 映射
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdMap {
+    extents: Vec<IdMapExtent>,
+}
+
+impl IdMap {
+    /// 初始命名空间使用的恒等映射
+    fn identity() -> Self {
+        Self {
+            extents: vec![IdMapExtent {
+                first: 0,
+                lower_first: 0,
+                count: u32::MAX,
+            }],
+        }
+    }
+
+    /// 命名空间内 ID 到下层 ID
+    pub fn map_down(&self, id: u32) -> Option<u32> {
+        self.extents
+            .iter()
+            .find(|e| id.wrapping_sub(e.first) < e.count)
+            .map(|e| e.lower_first + (id - e.first))
+    }
+
+    /// 下层 ID 到命名空间内 ID
+    pub fn map_up(&self, lower: u32) -> Option<u32> {
+        self.extents
+            .iter()
+            .find(|e| lower.wrapping_sub(e.lower_first) < e.count)
+            .map(|e| e.first + (lower - e.lower_first))
+    }
+
+    /// 解析写入 `uid_map` / `gid_map` 的文本
+    ///
+    /// 各段长度非零、不回绕，命名空间侧与下层侧均互不重叠。失败返回正的 errno。
+    pub fn parse(text: &str) -> Result<Self, i32> {
+        let mut extents: Vec<IdMapExtent> = Vec::new();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let mut fields = line.split_whitespace().map(|f| f.parse::<u32>());
+            let (Some(Ok(first)), Some(Ok(lower_first)), Some(Ok(count)), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(EINVAL);
+            };
+            if count == 0
+                || first.checked_add(count).is_none()
+                || lower_first.checked_add(count).is_none()
+            {
+                return Err(EINVAL);
+            }
+            if extents.iter().any(|e| {
+                (first < e.first + e.count && e.first < first + count)
+                    || (lower_first < e.lower_first + e.count
+                        && e.lower_first < lower_first + count)
+            }) {
+                return Err(EINVAL);
+            }
+            if extents.len() >= UID_GID_MAP_MAX_EXTENTS {
+                return Err(EINVAL);
+            }
+            extents.push(IdMapExtent {
+                first,
+                lower_first,
+                count,
+            });
+        }
+        if extents.is_empty() {
+            return Err(EINVAL);
+        }
+        Ok(Self { extents })
+    }
+
+    /// 按 Linux 格式输出
+    pub fn format(&self) -> String {
+        let mut out = String::new();
+        for e in self.extents.iter() {
+            out.push_str(&format!(
+                "{:>10} {:>10} {:>10}\n",
+                e.first, e.lower_first, e.count
+            ));
+        }
+        out
+    }
+}
+
+/// 用户命名空间
+pub struct UserNamespace {
+    parent: Option<Arc<UserNamespace>>,
+    level: u32,
+    /// 创建者的有效 UID（内核 ID）
+    owner: u32,
+    /// 命名空间内 UID 到内核 UID，未写入时为 `None`
+    uid_map: SpinLock<Option<IdMap>>,
+    /// 命名空间内 GID 到内核 GID，未写入时为 `None`
+    gid_map: SpinLock<Option<IdMap>>,
+}
+
+lazy_static! {
+    /// 初始用户命名空间，UID/GID 恒等映射
+    static ref INIT_USER_NS: Arc<UserNamespace> = Arc::new(UserNamespace {
+        parent: None,
+        level: 0,
+        owner: 0,
+        uid_map: SpinLock::new(Some(IdMap::identity())),
+        gid_map: SpinLock::new(Some(IdMap::identity())),
+    });
+}
+
+/// 获取初始用户命名空间
+pub fn init_user_ns() -> Arc<UserNamespace> {
+    INIT_USER_NS.clone()
+}
+
+impl UserNamespace {
+    /// 以 `parent` 为父创建新的命名空间，`owner` / `group` 为创建者的有效 UID / GID（内核 ID）
+    ///
+    /// 嵌套过深返回 EUSERS；创建者 ID 在父命名空间中未映射返回 EPERM。
+    pub fn new_child(parent: &Arc<Self>, owner: u32, group: u32) -> Result<Arc<Self>, i32> {
+        if parent.level >= MAX_USER_NS_LEVEL {
+            return Err(EUSERS);
+        }
+        if parent.from_kid(IdMapKind::Uid, owner).is_none()
+            || parent.from_kid(IdMapKind::Gid, group).is_none()
+        {
+            return Err(EPERM);
+        }
+        Ok(Arc::new(Self {
+            parent: Some(parent.clone()),
+            level: parent.level + 1,
+            owner,
+            uid_map: SpinLock::new(None),
+            gid_map: SpinLock::new(None),
+        }))
+    }
+
+    fn map(&self, kind: IdMapKind) -> &SpinLock<Option<IdMap>> {
+        match kind {
+            IdMapKind::Uid => &self.uid_map,
+            IdMapKind::Gid => &self.gid_map,
+        }
+    }
+
+    /// 命名空间内 ID 到内核 ID，未映射返回 `None`
+    pub fn make_kid(&self, kind: IdMapKind, id: u32) -> Option<u32> {
+        self.map(kind).lock().as_ref()?.map_down(id)
+    }
+
+    /// 内核 ID 到命名空间内 ID，未映射返回 `None`
+    pub fn from_kid(&self, kind: IdMapKind, kid: u32) -> Option<u32> {
+        self.map(kind).lock().as_ref()?.map_up(kid)
+    }
+
+    /// 内核 UID 到命名空间内 UID，未映射时为 [`OVERFLOW_UID`]
+    pub fn from_kuid_munged(&self, kuid: u32) -> u32 {
+        self.from_kid(IdMapKind::Uid, kuid).unwrap_or(OVERFLOW_UID)
+    }
+
+    /// 内核 GID 到命名空间内 GID，未映射时为 [`OVERFLOW_GID`]
+    pub fn from_kgid_munged(&self, kgid: u32) -> u32 {
+        self.from_kid(IdMapKind::Gid, kgid).unwrap_or(OVERFLOW_GID)
+    }
+
+    /// 读取映射（`/proc/[pid]/uid_map` 的内容），未写入时为空
+    pub fn read_map(&self, kind: IdMapKind) -> String {
+        self.map(kind)
+            .lock()
+            .as_ref()
+            .map_or_else(String::new, IdMap::format)
+    }
+
+    /// 由 `writer` 写入映射
+    ///
+    /// - 映射只能写入一次，且 `writer` 必须位于本命名空间或其父命名空间；
+    /// - `writer` 在父命名空间拥有 CAP_SETUID（CAP_SETGID）时可写入任意已在父命名空间映射的范围；
+    /// - 否则只能由创建者写入一行、把单个 ID 映射到自己的有效 UID（GID）。
+    ///
+    /// 失败返回正的 errno。
+    pub fn write_map(&self, kind: IdMapKind, text: &str, writer: &TaskStruct) -> Result<(), i32> {
+        let parent = self.parent.as_ref().ok_or(EPERM)?;
+        if !core::ptr::eq(Arc::as_ptr(&writer.user_ns), self)
+            && !Arc::ptr_eq(&writer.user_ns, parent)
+        {
+            return Err(EPERM);
+        }
+        let map = IdMap::parse(text)?;
+
+        let cap = match kind {
+            IdMapKind::Uid => Capabilities::SETUID,
+            IdMapKind::Gid => Capabilities::SETGID,
+        };
+        if !ns_capable(writer, parent, cap) {
+            let own = match kind {
+                IdMapKind::Uid => writer.credential.euid,
+                IdMapKind::Gid => writer.credential.egid,
+            };
+            let own = parent.from_kid(kind, own).ok_or(EPERM)?;
+            let only_self =
+                matches!(map.extents.as_slice(), [e] if e.count == 1 && e.lower_first == own);
+            if !only_self || writer.credential.euid != self.owner {
+                return Err(EPERM);
+            }
+        }
+
+        // 下层 ID 换算为内核 ID，每段必须完整落在父命名空间的同一段映射内
+        let mut map = map;
+        {
+            let parent_map = parent.map(kind).lock();
+            let parent_map = parent_map.as_ref().ok_or(EPERM)?;
+            for e in map.extents.iter_mut() {
+                let start = parent_map.map_down(e.lower_first).ok_or(EPERM)?;
+                let last = parent_map
+                    .map_down(e.lower_first + (e.count - 1))
+                    .ok_or(EPERM)?;
+                if last - start != e.count - 1 {
+                    return Err(EPERM);
+                }
+                e.lower_first = start;
+            }
+        }
+
+        let mut slot = self.map(kind).lock();
+        if slot.is_some() {
+            return Err(EPERM);
+        }
+        *slot = Some(map);
+        Ok(())
+    }
+}
+
+/// `task` 在命名空间 `ns` 中是否拥有能力 `cap`
+pub fn ns_capable(task: &TaskStruct, ns: &Arc<UserNamespace>, cap: Capabilities) -> bool {
+    let mut ns = ns.clone();
+    loop {
+        if Arc::ptr_eq(&ns, &task.user_ns) {
+            return task.credential.capabilities.has(cap);
+        }
+        let Some(parent) = ns.parent.clone() else {
+            return false;
+        };
+        // 父命名空间中的创建者在子命名空间中拥有全部能力
+        if Arc::ptr_eq(&parent, &task.user_ns) && ns.owner == task.credential.euid {
+            return true;
+        }
+        ns = parent;
+    }
+}
+
+/// 当前任务是否拥有全局能力 `cap`（相对初始命名空间）
+pub fn capable(cap: Capabilities) -> bool {
+    let task = current_task();
+    let t = task.lock();
+    ns_capable(&t, &INIT_USER_NS, cap)
+}
+
+/// 当前任务进行文件访问检查时使用的身份：fsuid/fsgid 与相关的全局能力
+pub fn current_fs_cred() -> FsCred {
+    let task = current_task();
+    let t = task.lock();
+    let has = |cap| ns_capable(&t, &INIT_USER_NS, cap);
+    FsCred {
+        fsuid: t.credential.fsuid,
+        fsgid: t.credential.fsgid,
+        dac_override: has(Capabilities::DAC_OVERRIDE),
+        dac_read_search: has(Capabilities::DAC_READ_SEARCH),
+        fowner: has(Capabilities::FOWNER),
+        fsetid: has(Capabilities::FSETID),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_id_map_parse_and_translate() {
+        let map = IdMap::parse("0 1000 1\n1 100000 65536\n").unwrap();
+        assert_eq!(map.map_down(0), Some(1000));
+        assert_eq!(map.map_down(1), Some(100000));
+        assert_eq!(map.map_down(65536), Some(165535));
+        assert_eq!(map.map_down(65537), None);
+        assert_eq!(map.map_up(1000), Some(0));
+        assert_eq!(map.map_up(100001), Some(2));
+        assert_eq!(map.map_up(999), None);
+        assert_eq!(
+            map.format(),
+            "         0       1000          1\n         1     100000      65536\n"
+        );
+    }
+
+    #[test_case]
+    fn test_id_map_parse_rejects_invalid() {
+        assert_eq!(IdMap::parse(""), Err(EINVAL));
+        assert_eq!(IdMap::parse("0 0 0"), Err(EINVAL));
+        assert_eq!(IdMap::parse("0 0"), Err(EINVAL));
+        assert_eq!(IdMap::parse("0 0 1 1"), Err(EINVAL));
+        assert_eq!(IdMap::parse("4294967295 0 2"), Err(EINVAL));
+        // 命名空间侧重叠
+        assert_eq!(IdMap::parse("0 0 10\n5 100 1"), Err(EINVAL));
+        // 下层侧重叠
+        assert_eq!(IdMap::parse("0 0 10\n20 5 1"), Err(EINVAL));
+    }
+}
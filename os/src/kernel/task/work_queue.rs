@@ -5,41 +5,61 @@
 //! 由专门的工作线程在合适的时机执行。
 #![allow(dead_code)]
 
-use alloc::{collections::vec_deque::VecDeque, vec::Vec};
+use alloc::boxed::Box;
+use alloc::collections::vec_deque::VecDeque;
 
-use crate::{
-    kernel::{
-        SharedTask, TaskState, current_task, sleep_task_with_block, wake_up_with_block, yield_task,
-    },
-    sync::SpinLock,
-};
+use crate::sync::{Condvar, SpinLock};
 
 lazy_static::lazy_static! {
     /// 全局工作队列实例。
     ///
-    /// `kworker()` 会在启动后将自己注册为 worker，并循环从队列中取出工作项执行。
+    /// `kworker()` 循环从队列中取出工作项执行，队列为空时在 `WORK_AVAILABLE` 上等待。
     pub static ref GLOBAL_WORK_QUEUE: SpinLock<WorkQueue> = SpinLock::new(WorkQueue::new());
 }
 
+/// 工作队列非空时通知等待中的工作线程
+static WORK_AVAILABLE: Condvar = Condvar::new();
+
+/// 工作项要执行的内容
+enum WorkFn {
+    /// 普通函数
+    Fn(fn()),
+    /// 携带状态的闭包（如 io_uring 的异步请求）
+    Closure(Box<dyn FnOnce() + Send>),
+}
+
 /// 工作项结构体
 pub struct WorkItem {
     /// 工作项要执行的函数。
-    pub task: fn(),
+    task: WorkFn,
 }
 
 impl WorkItem {
     /// 创建一个新的工作项
     pub fn new(task: fn()) -> Self {
-        WorkItem { task }
+        WorkItem {
+            task: WorkFn::Fn(task),
+        }
+    }
+
+    /// 创建执行闭包 `f` 的工作项
+    pub fn from_closure(f: impl FnOnce() + Send + 'static) -> Self {
+        WorkItem {
+            task: WorkFn::Closure(Box::new(f)),
+        }
+    }
+
+    /// 执行工作项
+    fn run(self) {
+        match self.task {
+            WorkFn::Fn(f) => f(),
+            WorkFn::Closure(f) => f(),
+        }
     }
 }
 
 /// 工作队列结构体
 pub struct WorkQueue {
-    /// 当前处于休眠状态的工作线程数量
-    sleeping: usize,
-    /// 工作线程列表
-    worker: Vec<SharedTask>,
     /// 待处理的工作项队列
     work_queue: VecDeque<WorkItem>,
 }
@@ -48,28 +68,14 @@ impl WorkQueue {
     /// 创建一个新的工作队列实例
     pub fn new() -> Self {
         WorkQueue {
-            worker: Vec::new(),
             work_queue: VecDeque::new(),
-            sleeping: 0,
         }
     }
 
-    /// 将工作项加入工作队列，并尝试唤醒一个处于可中断睡眠的工作线程。
+    /// 将工作项加入工作队列，并唤醒一个等待中的工作线程。
     pub fn schedule_work(&mut self, work: WorkItem) {
         self.work_queue.push_back(work);
-        if self.sleeping > 0 {
-            for task in &self.worker {
-                if task.lock().state == TaskState::Interruptible {
-                    wake_up_with_block(task.clone());
-                    break;
-                }
-            }
-        }
-    }
-
-    /// 将一个工作线程登记到该工作队列。
-    pub fn add_worker(&mut self, task: SharedTask) {
-        self.worker.push(task);
+        WORK_AVAILABLE.notify_one();
     }
 }
 
@@ -77,21 +83,16 @@ impl WorkQueue {
 ///
 /// 工作线程会不断从 [`GLOBAL_WORK_QUEUE`] 中拉取任务：
 ///
-/// - 若队列非空：弹出一个工作项并执行
-/// - 若队列为空：将自身置为可中断睡眠并让出 CPU，等待被 `schedule_work` 唤醒
+/// - 若队列非空：弹出一个工作项，释放队列锁后执行
+/// - 若队列为空：在条件变量上睡眠，等待被 `schedule_work` 唤醒
 pub fn kworker() {
-    GLOBAL_WORK_QUEUE.lock().add_worker(current_task());
     loop {
-        let mut queue = GLOBAL_WORK_QUEUE.lock();
-
-        if let Some(work) = queue.work_queue.pop_front() {
-            (work.task)();
-        } else {
-            queue.sleeping += 1;
-            sleep_task_with_block(current_task(), true);
-            drop(queue);
-            yield_task();
-            GLOBAL_WORK_QUEUE.lock().sleeping -= 1;
+        let mut queue =
+            WORK_AVAILABLE.wait_while(GLOBAL_WORK_QUEUE.lock(), |q| q.work_queue.is_empty());
+        let work = queue.work_queue.pop_front();
+        drop(queue);
+        if let Some(work) = work {
+            work.run();
         }
     }
 }
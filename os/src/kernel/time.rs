@@ -1,47 +1,309 @@
 //! 时间相关功能
+//!
+//! 计时核心（timekeeper）基于一个 [`ClockSource`] 维护单调时钟与墙上时钟：
+//! 时钟节拍中把自上次累积以来的周期数折算为纳秒，累加到单调时钟基准上；
+//! 读者用 `基准 + ((当前周期 - 上次累积的周期) × mult + 小数部分) >> shift` 得到当前时间。
+//! 折算的小数部分会被保留，因此结果与累积的时机无关，所有 CPU 读到的都是同一条时间线。
+//!
+//! 计时数据（含墙上时钟偏移）由 [`SeqLock`] 发布：写者持有写锁修改后整体发布，
+//! 读者不加锁，遇到并发写入时重试，gettimeofday 一类的读取从不与写者争用。
+//! vDSO 数据页在同一把写锁下同步更新。
+//!
+//! 时钟源校准完成前（[`init`] 之前）按计数器频率直接做除法换算，两种换算在切换点衔接。
 
+use crate::arch::timer::{clock_freq, get_time};
 use crate::device::RTC_DRIVERS;
-use crate::pr_info;
-use crate::sync::RwLock;
-use crate::time_ext::timespec_monotonic_now;
+use crate::kernel::clocksource::ClockSource;
+use crate::kernel::hrtimer::{Ktime, NSEC_PER_SEC, ktime_to_timespec};
+use crate::kernel::vdso;
+use crate::sync::{SeqLock, SpinLock};
+use crate::{pr_info, pr_warn};
 use uapi::time::TimeSpec;
 
-lazy_static::lazy_static! {
-    /// 墙上时钟，记录自 1970-01-01 00:00:00 UTC 以来的时间（以秒为单位）
-    /// XXX: 使用锁会不会影响精度？
-    pub static ref REALTIME: RwLock<TimeSpec> = RwLock::new(TimeSpec::zero());
+/// 计时数据
+#[derive(Debug, Clone, Copy)]
+pub struct ClockData {
+    /// 上次累积时的周期数
+    pub cycle_last: u64,
+    /// 上次累积时的单调时间（纳秒）
+    pub mono_base: Ktime,
+    /// 累积时不足 1 纳秒的部分（左移 `shift` 位的纳秒）
+    pub frac: u64,
+    /// 计数器有效位掩码
+    pub mask: u64,
+    /// 周期到纳秒的乘数，为 0 表示时钟源尚未校准
+    pub mult: u32,
+    /// 周期到纳秒的移位
+    pub shift: u32,
+    /// 墙上时钟相对单调时钟的偏移（秒）
+    pub real_sec: i64,
+    /// 墙上时钟相对单调时钟的偏移（纳秒）
+    pub real_nsec: i64,
+}
+
+impl ClockData {
+    const fn new() -> Self {
+        Self {
+            cycle_last: 0,
+            mono_base: 0,
+            frac: 0,
+            mask: u64::MAX,
+            mult: 0,
+            shift: 0,
+            real_sec: 0,
+            real_nsec: 0,
+        }
+    }
+
+    /// 墙上时钟相对单调时钟的偏移
+    pub fn realtime_offset(&self) -> TimeSpec {
+        TimeSpec::new(self.real_sec, self.real_nsec)
+    }
+
+    fn set_realtime_offset(&mut self, offset: &TimeSpec) {
+        self.real_sec = offset.tv_sec;
+        self.real_nsec = offset.tv_nsec;
+    }
+
+    /// 周期数换算为单调时间
+    fn cycles_to_ns(&self, cycles: u64) -> Ktime {
+        if self.mult == 0 {
+            return cycles_to_ns_slow(cycles);
+        }
+        let delta = cycles.wrapping_sub(self.cycle_last) & self.mask;
+        // 周期数早于上次累积（调用方读取计数器较早）时反向换算
+        if delta > self.mask >> 1 {
+            let back = self.cycle_last.wrapping_sub(cycles) & self.mask;
+            let ns = (back as u128 * self.mult as u128)
+                .saturating_sub(self.frac as u128)
+                .div_ceil(1u128 << self.shift);
+            return self.mono_base.saturating_sub(ns as u64);
+        }
+        let acc = delta as u128 * self.mult as u128 + self.frac as u128;
+        self.mono_base + (acc >> self.shift) as u64
+    }
+
+    /// 单调时间换算为周期数（向上取整），已经过去的时间换算为上次累积的周期数
+    fn ns_to_cycles(&self, ns: Ktime) -> u64 {
+        if self.mult == 0 {
+            return (ns as u128 * clock_freq() as u128).div_ceil(NSEC_PER_SEC as u128) as u64;
+        }
+        if ns <= self.mono_base {
+            return self.cycle_last;
+        }
+        let target = (((ns - self.mono_base) as u128) << self.shift) - self.frac as u128;
+        let delta = target.div_ceil(self.mult as u128);
+        self.cycle_last
+            .saturating_add(delta.min(u64::MAX as u128) as u64)
+    }
+}
+
+/// 供读者无锁读取的计时数据副本
+static CLOCK_DATA: SeqLock<ClockData> = SeqLock::new(ClockData::new());
+
+/// 计时核心的写侧状态
+struct Timekeeper {
+    /// 当前时钟源，校准前为 `None`
+    clock: Option<ClockSource>,
+    data: ClockData,
+}
+
+/// 写锁，串行化所有对计时数据与 vDSO 数据页的更新
+///
+/// 自旋锁会关闭中断，避免写者被本 CPU 上读取时间的中断打断而使读者永远重试。
+static TIMEKEEPER: SpinLock<Timekeeper> = SpinLock::new(Timekeeper {
+    clock: None,
+    data: ClockData::new(),
+});
+
+/// 读取硬件计数器
+fn read_counter() -> u64 {
+    get_time() as u64
+}
+
+/// 时钟源校准前的换算：按计数器频率做除法
+fn cycles_to_ns_slow(cycles: u64) -> Ktime {
+    (cycles as u128 * NSEC_PER_SEC as u128 / clock_freq() as u128) as Ktime
+}
+
+/// 持有写锁修改计时数据，并同步到读者副本与 vDSO 数据页
+fn write_clock(f: impl FnOnce(&mut Timekeeper)) {
+    let mut tk = TIMEKEEPER.lock();
+    f(&mut tk);
+    CLOCK_DATA.set(tk.data);
+    vdso::update(&tk.data);
+}
+
+/// 在顺序锁保护下读取计时数据，并在同一读侧临界区内执行 `f`
+fn read_clock<R>(f: impl Fn(&ClockData) -> R) -> R {
+    CLOCK_DATA.read_with(f)
 }
 
 /// 初始化时间子系统
+///
+/// 以设备树给出的计数器频率校准时钟源，再从 RTC 读取墙上时钟。
 pub fn init() {
-    // 初始化墙上时钟为 0
+    let clock = ClockSource::new("arch_counter", read_counter, u64::MAX, clock_freq() as u64);
+    match clock {
+        Some(cs) => pr_info!(
+            "Clocksource {}: {} Hz, mult {}, shift {}",
+            cs.name,
+            cs.freq,
+            cs.mult,
+            cs.shift
+        ),
+        None => pr_warn!("Clock frequency unknown, timekeeping stays uncalibrated."),
+    }
+
+    // 从 RTC 读取当前时间；没有 RTC 时只能从纪元开始计时
     pr_info!("Initializing REALTIME clock...");
-    let mut realtime = REALTIME.write();
-    let sec = RTC_DRIVERS
-        .read()
-        .first()
-        .map(|rtc| rtc.read_epoch() as usize)
-        .unwrap_or(0);
-    let mtime = timespec_monotonic_now();
-    // 这里减去 mtime 是为简化后续的时间计算
-    let time = TimeSpec::new(sec as i64, 0) - mtime;
-    *realtime = time;
+    let sec = match RTC_DRIVERS.read().first() {
+        Some(rtc) => rtc.read_epoch() as i64,
+        None => {
+            pr_warn!("No RTC found, REALTIME clock starts at the epoch.");
+            0
+        }
+    };
+
+    let mut offset = TimeSpec::zero();
+    write_clock(|tk| {
+        if let Some(cs) = clock {
+            // 以除法换算的当前时间作为基准，保证切换前后单调时钟连续
+            let now = cs.read();
+            tk.data.cycle_last = now;
+            tk.data.mono_base = cycles_to_ns_slow(now);
+            tk.data.frac = 0;
+            tk.data.mask = cs.mask;
+            tk.data.mult = cs.mult;
+            tk.data.shift = cs.shift;
+        }
+        tk.clock = clock;
+        // 这里减去单调时间是为简化后续的时间计算
+        let mono = tk.data.cycles_to_ns(read_counter());
+        offset = TimeSpec::new(sec, 0) - ktime_to_timespec(mono);
+        tk.data.set_realtime_offset(&offset);
+    });
     pr_info!(
         "REALTIME clock initialized to {:?} seconds since epoch.",
-        time
+        offset
     );
 }
 
+/// 累积自上次累积以来经过的时间，由 0 号 CPU 的时钟节拍调用
+///
+/// 缩短读者需要换算的周期差，使乘法保持在 64 位以内的精度范围。
+pub fn timekeeping_tick() {
+    write_clock(|tk| {
+        let Some(cs) = tk.clock else {
+            return;
+        };
+        let data = &mut tk.data;
+        let now = cs.read();
+        let delta = now.wrapping_sub(data.cycle_last) & data.mask;
+        let acc = delta as u128 * data.mult as u128 + data.frac as u128;
+        data.mono_base += (acc >> data.shift) as u64;
+        data.frac = (acc & ((1u128 << data.shift) - 1)) as u64;
+        data.cycle_last = now;
+    });
+}
+
+/// 获取当前单调时间（纳秒）
+pub fn ktime_get_mono() -> Ktime {
+    read_clock(|data| data.cycles_to_ns(read_counter()))
+}
+
+/// 硬件周期数换算为单调时间（纳秒，向下取整）
+pub fn cycles_to_mono(cycles: u64) -> Ktime {
+    read_clock(|data| data.cycles_to_ns(cycles))
+}
+
+/// 单调时间换算为硬件周期数（向上取整）
+pub fn mono_to_cycles(ns: Ktime) -> u64 {
+    read_clock(|data| data.ns_to_cycles(ns))
+}
+
+/// 读取当前的计时数据
+pub fn read_clock_data() -> ClockData {
+    read_clock(|data| *data)
+}
+
+/// 墙上时钟相对单调时钟的偏移
+pub fn realtime_offset() -> TimeSpec {
+    read_clock(|data| data.realtime_offset())
+}
+
 /// 更新墙上时钟时间
 /// # 参数:
 /// - `time`: 新的墙上时钟时间
 pub fn update_realtime(time: &TimeSpec) {
-    let mut realtime = REALTIME.write();
-    *realtime = *time - timespec_monotonic_now();
+    write_clock(|tk| {
+        let mono = tk.data.cycles_to_ns(read_counter());
+        tk.data
+            .set_realtime_offset(&(*time - ktime_to_timespec(mono)));
+    });
 }
 
 /// 获取当前墙上时钟时间
 pub fn realtime_now() -> TimeSpec {
-    let realtime = REALTIME.read();
-    *realtime + timespec_monotonic_now()
+    read_clock(|data| data.realtime_offset() + ktime_to_timespec(data.cycles_to_ns(read_counter())))
+}
+
+/// 将墙上时钟前移 `delta_ns` 纳秒（为负时后移），单调时钟不受影响
+pub fn adjust_realtime(delta_ns: i64) {
+    const NSEC_PER_SEC: i64 = 1_000_000_000;
+    let delta = TimeSpec::new(
+        delta_ns.div_euclid(NSEC_PER_SEC),
+        delta_ns.rem_euclid(NSEC_PER_SEC),
+    );
+    write_clock(|tk| {
+        let offset = tk.data.realtime_offset() + delta;
+        tk.data.set_realtime_offset(&offset);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 任意时刻累积，换算结果都与一次性换算相同
+    #[test_case]
+    fn test_clock_data_accumulation_exact() {
+        let mut data = ClockData::new();
+        data.mult = 1_342_177_280; // 12.5 MHz，shift = 24
+        data.shift = 24;
+        let once = data.cycles_to_ns(1_000_003);
+        for step in [7u64, 123_456, 876_540] {
+            let acc = (step - data.cycle_last) as u128 * data.mult as u128 + data.frac as u128;
+            data.mono_base += (acc >> data.shift) as u64;
+            data.frac = (acc & ((1u128 << data.shift) - 1)) as u64;
+            data.cycle_last = step;
+        }
+        assert!(data.cycles_to_ns(1_000_003) == once);
+        // 早于上次累积的周期数不会得到更晚的时间
+        assert!(data.cycles_to_ns(876_000) <= data.mono_base);
+    }
+
+    // 周期数换算向上取整，换算回来不早于原时间
+    #[test_case]
+    fn test_clock_data_ns_to_cycles() {
+        let mut data = ClockData::new();
+        data.mult = 1_342_177_280;
+        data.shift = 24;
+        data.cycle_last = 1000;
+        data.mono_base = 80_000;
+        data.frac = 12345;
+        for ns in [80_001u64, 123_456_789, 5 * NSEC_PER_SEC] {
+            assert!(data.cycles_to_ns(data.ns_to_cycles(ns)) >= ns);
+        }
+        assert!(data.ns_to_cycles(10) == data.cycle_last);
+    }
+
+    // 单调时钟不会倒退
+    #[test_case]
+    fn test_ktime_get_mono_monotonic() {
+        let a = ktime_get_mono();
+        timekeeping_tick();
+        let b = ktime_get_mono();
+        assert!(b >= a);
+    }
 }
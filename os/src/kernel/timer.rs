@@ -1,160 +1,212 @@
-//! 定时器队列模块
+//! 内核定时器
 //!
-//! 该模块实现了一个简单的定时器队列，用于管理和调度定时任务。
-
-use alloc::{collections::btree_map::BTreeMap, sync::Arc};
-
-use crate::kernel::SharedTask;
-use crate::sync::SpinLock;
-use crate::vfs::TimeSpec;
-
-lazy_static::lazy_static! {
-    /// 全局等待队列实例
-    /// 使用硬件时钟周期数作为时间单位
-    /// 在定时器触发时唤醒任务
-    pub static ref TIMER_QUEUE: SpinLock<TimerQueue> = SpinLock::new(TimerQueue::new());
-    /// 定时器队列
-    /// 使用硬件时钟周期数作为时间单位
-    /// 在定时器触发时向任务发送对应信号
-    pub static ref TIMER: SpinLock<TimerEntries> = SpinLock::new(TimerEntries::new());
+//! 基于 [`hrtimer`](crate::kernel::hrtimer) 提供三类定时器：
+//! - 调度时钟节拍：每个 CPU 一个周期性定时器，驱动时间片、看门狗与 RCU 静止状态检测；
+//!   CPU 空闲时停止节拍，只为下一个等待中的定时器编程硬件（NO_HZ），唤醒后补齐错过的节拍数；
+//! - 任务唤醒定时器：睡眠类系统调用在到期时间唤醒任务；
+//! - 间隔定时器（itimer）：到期时向进程发送信号，可按周期重复。
+
+use alloc::{
+    collections::btree_map::BTreeMap,
+    sync::{Arc, Weak},
+};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::arch::timer::{TICKS_PER_SEC, TIMER_TICKS};
+use crate::config::MAX_CPU_COUNT;
+use crate::kernel::hrtimer::{HrTimer, HrTimerRestart, Ktime, NSEC_PER_SEC, ktime_get};
+use crate::kernel::{
+    GLOBAL_WORK_QUEUE, SharedTask, WorkItem, send_signal_process, wake_up_with_block,
+};
+use crate::sync::{SpinLock, rcu_process_callbacks, rcu_tick};
+
+/// 时钟节拍周期（纳秒）
+pub const TICK_NSEC: Ktime = NSEC_PER_SEC / TICKS_PER_SEC as Ktime;
+
+/// 每个 CPU 的时钟节拍定时器
+static TICK_TIMERS: [SpinLock<Option<Arc<HrTimer>>>; MAX_CPU_COUNT] =
+    [const { SpinLock::new(None) }; MAX_CPU_COUNT];
+
+/// 每个 CPU 上自上次检查以来是否经过了时钟节拍
+static TICK_PENDING: [AtomicBool; MAX_CPU_COUNT] =
+    [const { AtomicBool::new(false) }; MAX_CPU_COUNT];
+
+/// 每个 CPU 的时钟节拍是否因空闲而停止
+static TICK_STOPPED: [AtomicBool; MAX_CPU_COUNT] =
+    [const { AtomicBool::new(false) }; MAX_CPU_COUNT];
+
+/// 每个 CPU 停止节拍时下一个节拍原本的到期时间
+static TICK_NEXT: [AtomicU64; MAX_CPU_COUNT] = [const { AtomicU64::new(0) }; MAX_CPU_COUNT];
+
+/// 按单调时间推进节拍计数
+///
+/// 节拍数由时间换算而来，多个 CPU 的节拍或停顿后的补齐都不会重复计数。
+fn update_jiffies(now: Ktime) {
+    TIMER_TICKS.fetch_max((now / TICK_NSEC) as usize, Ordering::Relaxed);
 }
 
-/// 定时器队列，用于管理定时任务
-pub struct TimerQueue {
-    /// 以触发时间为键，任务为值的有序映射
-    queue: BTreeMap<usize, SharedTask>,
+/// 报告本 CPU 的 RCU 静止状态，有回调就绪时交给工作线程执行
+fn rcu_check_callbacks() {
+    if rcu_tick() {
+        GLOBAL_WORK_QUEUE
+            .lock()
+            .schedule_work(WorkItem::new(rcu_process_callbacks));
+    }
 }
 
-impl TimerQueue {
-    /// 创建一个新的定时器队列
-    pub fn new() -> Self {
-        Self {
-            queue: BTreeMap::new(),
+/// 启动本 CPU 的时钟节拍定时器，由各架构的定时器初始化调用
+pub fn tick_init() {
+    let cpu = crate::arch::kernel::cpu::cpu_id();
+    let timer = HrTimer::new(move |expires| {
+        update_jiffies(ktime_get());
+        crate::kernel::watchdog::tick();
+        crate::security::random::add_timer_randomness();
+        rcu_check_callbacks();
+        // 计时累积与墙上时钟校准是全局的，只在 0 号 CPU 上进行
+        if cpu == 0 {
+            crate::kernel::time::timekeeping_tick();
+            crate::kernel::ntp::tick();
         }
-    }
+        TICK_PENDING[cpu].store(true, Ordering::Release);
+        // 中断被长时间屏蔽时跳过错过的节拍，避免连续补发
+        let next = expires + TICK_NSEC;
+        HrTimerRestart::Restart(next.max(ktime_get() + TICK_NSEC / 2))
+    });
+    timer.start_after(TICK_NSEC);
+    *TICK_TIMERS[cpu].lock() = Some(timer);
+}
 
-    /// 向队列中添加一个定时任务
-    /// # 参数:
-    /// - `trigger_time`: 任务触发的时间点
-    /// - `task`: 需要执行的任务
-    pub fn push(&mut self, mut trigger_time: usize, task: SharedTask) {
-        while self.queue.contains_key(&trigger_time) {
-            trigger_time += 1;
-        }
-        self.queue.insert(trigger_time, task);
+/// CPU 进入空闲：运行队列为空时停止本 CPU 的时钟节拍
+///
+/// 由 idle 循环在关中断后、等待中断前调用。节拍定时器被取消后，
+/// 硬件定时器只会为下一个等待中的定时器编程。
+pub fn tick_nohz_idle_enter() {
+    let cpu = crate::arch::kernel::cpu::cpu_id();
+    if TICK_STOPPED[cpu].load(Ordering::Acquire)
+        || !crate::kernel::current_scheduler().lock().is_empty()
+    {
+        return;
     }
+    // 网络协议栈没有网卡中断，依赖时钟节拍轮询；存在网络接口时保留 0 号 CPU 的节拍
+    if cpu == 0 && crate::net::socket::NET_IFACE.lock().is_some() {
+        return;
+    }
+    let tick_timer = TICK_TIMERS[cpu].lock();
+    let Some(timer) = tick_timer.as_ref() else {
+        return;
+    };
+    let next = timer.expires().unwrap_or_else(|| ktime_get() + TICK_NSEC);
+    TICK_NEXT[cpu].store(next, Ordering::Relaxed);
+    timer.cancel();
+    TICK_STOPPED[cpu].store(true, Ordering::Release);
+}
 
-    /// 弹出已到期的任务
-    /// # 参数:
-    /// - `current_time`: 当前时间点
-    /// # 返回值:
-    /// - 已到期的任务（如果有）
-    pub fn pop_due_task(&mut self, current_time: usize) -> Option<SharedTask> {
-        if let Some((&trigger_time, _)) = self.queue.iter().next() {
-            if trigger_time <= current_time {
-                return self.queue.remove(&trigger_time);
-            }
-        }
-        None
+/// CPU 退出空闲：补齐停顿期间的节拍数，并按原来的相位重新启动时钟节拍
+///
+/// 节拍未停止时直接返回，可以在 idle 循环和离开 idle 任务时重复调用。
+pub fn tick_nohz_idle_exit() {
+    let cpu = crate::arch::kernel::cpu::cpu_id();
+    if !TICK_STOPPED[cpu].swap(false, Ordering::AcqRel) {
+        return;
     }
+    let now = ktime_get();
+    update_jiffies(now);
 
-    /// 移除指定任务
-    /// # 参数:
-    /// - `task`: 需要移除的任务
-    /// # 返回值:
-    /// - 被移除的任务（如果存在）
-    pub fn remove_task(&mut self, task: &SharedTask) -> Option<SharedTask> {
-        let key = self.queue.iter().find_map(|(time, t)| {
-            if Arc::ptr_eq(task, t) {
-                Some(*time)
-            } else {
-                None
-            }
-        })?;
-        self.queue.remove(&key)
+    let mut next = TICK_NEXT[cpu].load(Ordering::Relaxed);
+    if next <= now {
+        next += ((now - next) / TICK_NSEC + 1) * TICK_NSEC;
+    }
+    if let Some(timer) = TICK_TIMERS[cpu].lock().as_ref() {
+        timer.start(next);
     }
 }
 
-/// 定时器条目
-pub struct TimerEntry {
-    /// 信号编号
-    pub sig: usize,
-    /// 关联的任务
-    pub task: SharedTask,
-    /// 定时器周期
-    pub it_interval: TimeSpec,
+/// 取出并清除本 CPU 的时钟节拍标记
+///
+/// 定时器中断处理完到期定时器后调用，返回 `true` 时应更新时间片并考虑调度。
+pub fn take_tick() -> bool {
+    let cpu = crate::arch::kernel::cpu::cpu_id();
+    TICK_PENDING[cpu].swap(false, Ordering::AcqRel)
 }
 
-impl TimerEntry {
-    /// 创建一个新的定时器条目
-    pub fn new(sig: usize, task: SharedTask, it_interval: TimeSpec) -> Self {
-        Self {
-            sig,
-            task,
-            it_interval,
-        }
-    }
+/// 创建一个在 `deadline` 唤醒 `task` 的定时器
+///
+/// 调用者应先把任务置为睡眠状态再让出 CPU；被唤醒后调用 [`HrTimer::cancel`]，
+/// 返回 `false` 表示定时器已经到期（超时），返回 `true` 表示被其他原因提前唤醒。
+pub fn wake_task_at(task: SharedTask, deadline: Ktime) -> Arc<HrTimer> {
+    let timer = HrTimer::new(move |_| {
+        wake_up_with_block(task.clone());
+        HrTimerRestart::NoRestart
+    });
+    timer.start(deadline);
+    timer
 }
 
-/// 定时器条目集合
-pub struct TimerEntries {
-    pub entries: BTreeMap<usize, TimerEntry>,
+/// 间隔定时器
+struct ITimer {
+    timer: Arc<HrTimer>,
+    /// 重复周期（纳秒），为 0 表示只触发一次
+    interval: Ktime,
 }
 
-impl TimerEntries {
-    /// 创建一个新的定时器条目集合
-    pub fn new() -> Self {
-        Self {
-            entries: BTreeMap::new(),
-        }
-    }
-
-    /// 向集合中添加一个定时器条目
-    /// # 参数:
-    /// - `trigger_time`: 触发时间点
-    /// - `entry`: 定时器条目
-    pub fn push(&mut self, mut trigger_time: usize, entry: TimerEntry) {
-        while self.entries.contains_key(&trigger_time) {
-            trigger_time += 1;
-        }
-        self.entries.insert(trigger_time, entry);
-    }
+/// 以（进程号，信号）为键的间隔定时器
+static ITIMERS: SpinLock<BTreeMap<(u32, usize), ITimer>> = SpinLock::new(BTreeMap::new());
+
+/// 查询进程的间隔定时器
+///
+/// # 返回值
+/// （剩余时间，重复周期），单位为纳秒；没有设置时均为 0。
+pub fn get_itimer(pid: u32, sig: usize) -> (Ktime, Ktime) {
+    ITIMERS
+        .lock()
+        .get(&(pid, sig))
+        .map_or((0, 0), |it| (it.timer.remaining(), it.interval))
+}
 
-    /// 弹出已到期的定时器条目
-    /// # 参数:
-    /// - `current_time`: 当前时间点
-    /// # 返回值:
-    /// - 已到期的定时器条目（如果有）
-    pub fn pop_due_entry(&mut self, current_time: usize) -> Option<TimerEntry> {
-        if let Some((&trigger_time, _)) = self.entries.iter().next() {
-            if trigger_time <= current_time {
-                return self.entries.remove(&trigger_time);
+/// 设置进程的间隔定时器，`value` 为 0 时解除
+///
+/// # 参数
+/// - `owner`: 线程组首任务，到期时向它发送信号
+/// - `value`: 首次到期的相对时间（纳秒）
+/// - `interval`: 重复周期（纳秒）
+///
+/// # 返回值
+/// 旧的（剩余时间，重复周期）
+pub fn set_itimer(owner: &SharedTask, sig: usize, value: Ktime, interval: Ktime) -> (Ktime, Ktime) {
+    let pid = owner.lock().pid;
+    let mut itimers = ITIMERS.lock();
+    let old = itimers.remove(&(pid, sig)).map_or((0, 0), |it| {
+        let remaining = it.timer.remaining();
+        it.timer.cancel();
+        (remaining, it.interval)
+    });
+
+    if value != 0 {
+        // 定时器不持有进程，进程退出后自然失效
+        let owner: Weak<_> = Arc::downgrade(owner);
+        let timer = HrTimer::new(move |expires| {
+            let Some(owner) = owner.upgrade() else {
+                return HrTimerRestart::NoRestart;
+            };
+            send_signal_process(&owner, sig);
+            if interval == 0 {
+                HrTimerRestart::NoRestart
+            } else {
+                HrTimerRestart::Restart(expires.saturating_add(interval).max(ktime_get()))
             }
-        }
-        None
+        });
+        timer.start_after(value);
+        itimers.insert((pid, sig), ITimer { timer, interval });
     }
+    old
+}
 
-    /// 查找与指定任务关联的定时器条目
-    /// # 参数:
-    /// - `task`: 目标任务
-    /// # 返回值:
-    /// - 关联的定时器条目（如果存在）
-    pub fn find_entry(&self, task: &SharedTask, sig: usize) -> Option<(&usize, &TimerEntry)> {
-        for (time, entry) in self.entries.iter() {
-            if Arc::ptr_eq(task, &entry.task) && entry.sig == sig {
-                return Some((time, entry));
-            }
+/// 进程退出时解除它的所有间隔定时器
+pub fn clear_itimers(pid: u32) {
+    ITIMERS.lock().retain(|&(owner, _), it| {
+        if owner == pid {
+            it.timer.cancel();
         }
-        None
-    }
-
-    /// 移除与指定任务关联的定时器条目
-    /// # 参数:
-    /// - `task`: 目标任务
-    /// # 返回值:
-    /// - 被移除的定时器条目（如果存在）
-    pub fn remove_entry(&mut self, task: &SharedTask, sig: usize) -> Option<TimerEntry> {
-        let key = self.find_entry(task, sig).map(|(time, _)| *time)?;
-        self.entries.remove(&key)
-    }
+        owner != pid
+    });
 }
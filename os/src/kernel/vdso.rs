@@ -0,0 +1,142 @@
+//! vDSO（虚拟动态共享对象）
+//!
+//! 内核把时钟参数导出到一页只读的数据页（vvar），与 vDSO 代码页一起映射到每个用户进程，
+//! 用户态的 `clock_gettime` / `gettimeofday` 可以直接读取计数器计算时间，无需陷入内核。
+//! vDSO 代码由各架构在 `arch/*/kernel/vdso.S` 中提供，通过 `AT_SYSINFO_EHDR` 告知用户态。
+//!
+//! 数据页由 seqlock 保护：写者先把序号加到奇数，更新数据后再加到偶数；
+//! 读者在序号为奇数或前后两次读取不一致时重试。写者由计时核心的写锁串行化。
+//!
+//! 用户态与内核使用同一套 mult/shift 换算与累积基准，两条路径读到的时间完全一致。
+
+use core::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, Ordering, fence};
+
+use mm::address::{Paddr, PageNum, Ppn, UsizeConvert};
+
+use crate::arch::mm::vaddr_to_paddr;
+use crate::arch::timer::clock_freq;
+use crate::config::PAGE_SIZE;
+use crate::kernel::time::ClockData;
+
+/// vDSO 数据页
+///
+/// 字段偏移与 `arch/*/kernel/vdso.S` 中的 `VVAR_*` 常量一一对应，修改时必须同步。
+#[repr(C, align(4096))]
+struct VdsoData {
+    /// seqlock 序号，奇数表示正在更新
+    seq: AtomicU32,
+    /// 非零时用户态可以直接读取计数器，否则回退到系统调用
+    clock_mode: AtomicU32,
+    /// 计数器频率（Hz）
+    freq: AtomicU64,
+    /// 墙上时钟相对单调时钟的偏移（秒）
+    realtime_sec: AtomicI64,
+    /// 墙上时钟相对单调时钟的偏移（纳秒）
+    realtime_nsec: AtomicI64,
+    /// 上次累积时的周期数
+    cycle_last: AtomicU64,
+    /// 上次累积时的单调时间（纳秒）
+    mono_base: AtomicU64,
+    /// 累积时不足 1 纳秒的部分（左移 `shift` 位的纳秒）
+    frac: AtomicU64,
+    /// 周期到纳秒的乘数
+    mult: AtomicU32,
+    /// 周期到纳秒的移位
+    shift: AtomicU32,
+}
+
+static VDSO_DATA: VdsoData = VdsoData {
+    seq: AtomicU32::new(0),
+    clock_mode: AtomicU32::new(0),
+    freq: AtomicU64::new(0),
+    realtime_sec: AtomicI64::new(0),
+    realtime_nsec: AtomicI64::new(0),
+    cycle_last: AtomicU64::new(0),
+    mono_base: AtomicU64::new(0),
+    frac: AtomicU64::new(0),
+    mult: AtomicU32::new(0),
+    shift: AtomicU32::new(0),
+};
+
+const _: () = assert!(core::mem::size_of::<VdsoData>() == PAGE_SIZE);
+
+/// 在 seqlock 写侧临界区内更新数据页
+fn write_data(f: impl FnOnce(&VdsoData)) {
+    let seq = VDSO_DATA.seq.load(Ordering::Relaxed);
+    VDSO_DATA.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+    fence(Ordering::Release);
+    f(&VDSO_DATA);
+    VDSO_DATA.seq.store(seq.wrapping_add(2), Ordering::Release);
+}
+
+/// 导出计时数据，时钟源校准完成后开启用户态快速路径
+///
+/// 两种架构的计数器都是 64 位，用户态不处理掩码。
+pub fn update(clock: &ClockData) {
+    let freq = clock_freq() as u64;
+    write_data(|data| {
+        data.freq.store(freq, Ordering::Relaxed);
+        data.realtime_sec.store(clock.real_sec, Ordering::Relaxed);
+        data.realtime_nsec.store(clock.real_nsec, Ordering::Relaxed);
+        data.cycle_last.store(clock.cycle_last, Ordering::Relaxed);
+        data.mono_base.store(clock.mono_base, Ordering::Relaxed);
+        data.frac.store(clock.frac, Ordering::Relaxed);
+        data.mult.store(clock.mult, Ordering::Relaxed);
+        data.shift.store(clock.shift, Ordering::Relaxed);
+        // 时钟源未校准时无法换算，保持回退到系统调用
+        data.clock_mode
+            .store((clock.mult != 0) as u32, Ordering::Relaxed);
+    });
+}
+
+fn kernel_ppn(vaddr: usize) -> Ppn {
+    let paddr = unsafe { vaddr_to_paddr(vaddr) };
+    Ppn::from_addr_floor(Paddr::from_usize(paddr))
+}
+
+/// 数据页的物理页号
+pub fn data_ppn() -> Ppn {
+    kernel_ppn(&VDSO_DATA as *const VdsoData as usize)
+}
+
+/// vDSO 代码页的起始物理页号与页数
+pub fn image_pages() -> (Ppn, usize) {
+    let image = crate::arch::kernel::vdso_image();
+    let start = image.as_ptr() as usize;
+    (kernel_ppn(start), image.len().div_ceil(PAGE_SIZE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 镜像是页对齐的 ELF 共享对象，且不超过映射的页数
+    #[test_case]
+    fn test_vdso_image_header() {
+        let image = crate::arch::kernel::vdso_image();
+        assert!(image.as_ptr() as usize % PAGE_SIZE == 0);
+        assert!(&image[..4] == b"\x7fELF");
+        // e_type = ET_DYN
+        assert!(u16::from_le_bytes([image[16], image[17]]) == 3);
+        let (_, pages) = image_pages();
+        assert!(pages >= 1 && image.len() <= pages * PAGE_SIZE);
+    }
+
+    // 写侧临界区结束后序号为偶数，计时数据对读者可见
+    #[test_case]
+    fn test_vdso_seqlock_update() {
+        let before = VDSO_DATA.seq.load(Ordering::Acquire);
+        let mut clock = crate::kernel::time::read_clock_data();
+        clock.real_sec = 100;
+        clock.real_nsec = 5;
+        update(&clock);
+        let after = VDSO_DATA.seq.load(Ordering::Acquire);
+        assert!(after == before.wrapping_add(2));
+        assert!(after % 2 == 0);
+        assert!(VDSO_DATA.realtime_sec.load(Ordering::Relaxed) == 100);
+        assert!(VDSO_DATA.realtime_nsec.load(Ordering::Relaxed) == 5);
+        assert!(VDSO_DATA.mult.load(Ordering::Relaxed) == clock.mult);
+        // 恢复为计时核心当前的数据
+        update(&crate::kernel::time::read_clock_data());
+    }
+}
@@ -0,0 +1,65 @@
+//! 软锁死检测（soft lockup watchdog）
+//!
+//! 调用方在进入可能长时间不返回的区间前 [`arm`]，离开时 [`disarm`]；
+//! 时钟中断中的 [`tick`] 发现超过期限仍未解除时打印告警并调用报告回调（每次 arm 至多触发一次）。
+//!
+//! 目前只有一个全局槽位，供系统调用模糊测试等测试工具使用。
+//! 若被检测的代码关中断自旋，时钟中断无法到达，本机制无法发现。
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::arch::timer::get_time_ms;
+
+/// 截止时间（毫秒，0 表示未启用）
+static DEADLINE_MS: AtomicUsize = AtomicUsize::new(0);
+/// 启用时刻（毫秒）
+static ARMED_AT_MS: AtomicUsize = AtomicUsize::new(0);
+/// 报告回调（0 表示无回调）
+static REPORT: AtomicUsize = AtomicUsize::new(0);
+/// 本次 arm 是否已经报告过
+static FIRED: AtomicBool = AtomicBool::new(false);
+
+/// 启用看门狗
+///
+/// # 参数
+/// - `timeout_ms`: 超时时间（毫秒）
+/// - `report`: 超时时在时钟中断上下文中调用的报告函数，不能阻塞或获取睡眠锁
+pub fn arm(timeout_ms: usize, report: Option<fn()>) {
+    let now = get_time_ms();
+    REPORT.store(report.map_or(0, |f| f as usize), Ordering::Relaxed);
+    FIRED.store(false, Ordering::Relaxed);
+    ARMED_AT_MS.store(now, Ordering::Relaxed);
+    DEADLINE_MS.store(now.saturating_add(timeout_ms).max(1), Ordering::Release);
+}
+
+/// 解除看门狗
+pub fn disarm() {
+    DEADLINE_MS.store(0, Ordering::Release);
+}
+
+/// 是否已因超时触发过（自上次 [`arm`] 起）
+pub fn fired() -> bool {
+    FIRED.load(Ordering::Relaxed)
+}
+
+/// 时钟中断钩子
+pub fn tick() {
+    let deadline = DEADLINE_MS.load(Ordering::Acquire);
+    if deadline == 0 {
+        return;
+    }
+    let now = get_time_ms();
+    if now < deadline || FIRED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    crate::pr_err!(
+        "watchdog: soft lockup detected, stuck for {} ms",
+        now - ARMED_AT_MS.load(Ordering::Relaxed)
+    );
+    let report = REPORT.load(Ordering::Relaxed);
+    if report != 0 {
+        // SAFETY: 非零值只可能来自 arm() 存入的 fn()
+        let f: fn() = unsafe { core::mem::transmute(report) };
+        f();
+    }
+}
@@ -0,0 +1,25 @@
+//! 控制台刷写线程
+//!
+//! 线程启动后把 klog 切换到延迟控制台输出模式：`pr_*!` 只写环形缓冲区，
+//! 控制台输出由本线程周期性地按序完成，中断上下文中的日志不再等待串口。
+//! Emergency 级别仍同步输出；panic 时关闭延迟模式并同步输出积压的日志。
+//! 经 `printk_deferred!` 写入的日志（包括 Emergency）同样由本线程输出。
+
+use crate::kernel::hrtimer::{Ktime, NSEC_PER_MSEC, ktime_get};
+use crate::kernel::{current_task, sleep_task_with_block, wake_task_at, yield_task};
+
+/// 刷写间隔
+const CONSOLE_FLUSH_INTERVAL: Ktime = 10 * NSEC_PER_MSEC;
+
+/// 控制台刷写线程主函数（由 kthreadd 通过 `kthread_spawn` 创建）
+pub fn console_flusher() {
+    klog::set_console_deferred(true);
+    let task = current_task();
+    loop {
+        klog::flush_console();
+        sleep_task_with_block(task.clone(), true);
+        let timer = wake_task_at(task.clone(), ktime_get() + CONSOLE_FLUSH_INTERVAL);
+        yield_task();
+        timer.cancel();
+    }
+}
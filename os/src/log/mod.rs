@@ -4,15 +4,29 @@
 
 #![allow(unused)]
 
+mod flusher;
+pub mod netconsole;
+pub mod pstore;
+
+pub use flusher::console_flusher;
+pub use netconsole::netconsole_thread;
+
 // 重新导出 klog crate 的所有公共 API
 pub use klog::{
-    DEFAULT_CONSOLE_LEVEL, DEFAULT_LOG_LEVEL, GLOBAL_LOG_BUFFER_SIZE, LogContextProvider, LogEntry,
-    LogLevel, LogOutput, MAX_LOG_MESSAGE_LENGTH, format_log_entry, get_console_level,
-    get_global_level, is_level_enabled, log_dropped_count, log_impl, log_len, log_reader_index,
-    log_unread_bytes, log_writer_index, peek_log, read_log, set_console_level, set_global_level,
+    BootLogParams, DEFAULT_CONSOLE_LEVEL, DEFAULT_LOG_LEVEL, DEFAULT_RATELIMIT_BURST,
+    DEFAULT_RATELIMIT_INTERVAL, GLOBAL_LOG_BUFFER_SIZE, HEX_DUMP_ROW_SIZE, LOG_BUFFER_SIZE,
+    LogContextProvider, LogEntry, LogField, LogLevel, LogOutput, LogReader, LogStyle, LogValue,
+    MAX_LOG_MESSAGE_LENGTH, RateLimitState, apply_boot_params, clear_log, console_pending,
+    console_style, flush_console, format_log_entry, get_console_level, get_global_level,
+    hex_dump_impl, is_console_color, is_console_deferred, is_console_time, is_level_enabled,
+    is_target_level_enabled, log_cont_impl, log_dropped_count, log_fields_impl, log_filtered_impl,
+    log_impl, log_impl_deferred, log_len, log_ratelimited, log_reader, log_reader_index,
+    log_snapshot, log_target_impl, log_unread_bytes, log_writer_index, peek_log, read_all_log_into,
+    read_log, read_log_into, set_console_color, set_console_deferred, set_console_level,
+    set_console_time, set_global_level, set_target_debug, warn_impl,
 };
 
-use crate::arch::{kernel::cpu::cpu_id, timer};
+use crate::arch::kernel::cpu::cpu_id;
 use crate::console::Stdout;
 use crate::sync::PreemptGuard;
 use core::fmt::Write;
@@ -25,6 +39,20 @@ macro_rules! __log_impl_filtered {
     ($level:expr, $args:expr) => {
         if $crate::log::is_level_enabled($level) {
             $crate::log::log_impl($level, $args);
+        } else {
+            $crate::log::log_filtered_impl();
+        }
+    };
+}
+
+/// 带目标（子系统）标记的内部实现宏
+#[macro_export]
+macro_rules! __log_impl_target {
+    ($target:expr, $level:expr, $args:expr) => {
+        if $crate::log::is_target_level_enabled($target, $level) {
+            $crate::log::log_target_impl($target, $level, $args);
+        } else {
+            $crate::log::log_filtered_impl();
         }
     };
 }
@@ -32,6 +60,13 @@ macro_rules! __log_impl_filtered {
 /// 以 **EMERGENCY (紧急)** 级别记录消息
 #[macro_export]
 macro_rules! pr_emerg {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Emergency,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Emergency,
@@ -43,6 +78,13 @@ macro_rules! pr_emerg {
 /// 以 **ALERT (警报)** 级别记录消息
 #[macro_export]
 macro_rules! pr_alert {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Alert,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Alert,
@@ -54,6 +96,13 @@ macro_rules! pr_alert {
 /// 以 **CRITICAL (关键)** 级别记录消息
 #[macro_export]
 macro_rules! pr_crit {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Critical,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Critical,
@@ -65,6 +114,13 @@ macro_rules! pr_crit {
 /// 以 **ERROR (错误)** 级别记录消息
 #[macro_export]
 macro_rules! pr_err {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Error,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Error,
@@ -76,6 +132,13 @@ macro_rules! pr_err {
 /// 以 **WARNING (警告)** 级别记录消息
 #[macro_export]
 macro_rules! pr_warn {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Warning,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Warning,
@@ -87,6 +150,13 @@ macro_rules! pr_warn {
 /// 以 **NOTICE (通知)** 级别记录消息
 #[macro_export]
 macro_rules! pr_notice {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Notice,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Notice,
@@ -98,6 +168,13 @@ macro_rules! pr_notice {
 /// 以 **INFO (信息)** 级别记录消息
 #[macro_export]
 macro_rules! pr_info {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Info,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Info,
@@ -109,6 +186,13 @@ macro_rules! pr_info {
 /// 以 **DEBUG (调试)** 级别记录消息
 #[macro_export]
 macro_rules! pr_debug {
+    (target: $target:expr, $($arg:tt)*) => {
+        $crate::__log_impl_target!(
+            $target,
+            $crate::log::LogLevel::Debug,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)*) => {
         $crate::__log_impl_filtered!(
             $crate::log::LogLevel::Debug,
@@ -117,6 +201,287 @@ macro_rules! pr_debug {
     }
 }
 
+/// 以指定级别记录消息，控制台输出延迟到安全的上下文（类似 Linux 的 `printk_deferred`）
+///
+/// 只写环形缓冲区，用于调度器内部、持有运行队列锁或陷入处理等路径，
+/// 避免在串口锁上死锁。
+#[macro_export]
+macro_rules! printk_deferred {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::log::is_level_enabled($level) {
+            $crate::log::log_impl_deferred($level, format_args!($($arg)*));
+        } else {
+            $crate::log::log_filtered_impl();
+        }
+    };
+}
+
+/// 以指定级别按 `hexdump -C` 的格式转储一段字节，每行一条日志
+#[macro_export]
+macro_rules! pr_hex_dump {
+    ($level:expr, $prefix:expr, $data:expr $(,)?) => {
+        if $crate::log::is_level_enabled($level) {
+            $crate::log::hex_dump_impl($level, $prefix, $data);
+        } else {
+            $crate::log::log_filtered_impl();
+        }
+    };
+}
+
+/// 把消息接在本 CPU 的上一条日志之后（**KERN_CONT**）
+#[macro_export]
+macro_rules! pr_cont {
+    ($($arg:tt)*) => {
+        $crate::log::log_cont_impl(format_args!($($arg)*))
+    };
+}
+
+/// 以指定级别记录一条携带结构化字段的消息
+///
+/// 字段写作 `[键 => 值, ...]`，经 /dev/kmsg 以 ` 键=值` 续行导出。
+#[macro_export]
+macro_rules! pr_fields {
+    ($level:expr, [$($key:literal => $value:expr),* $(,)?], $($arg:tt)*) => {
+        if $crate::log::is_level_enabled($level) {
+            $crate::log::log_fields_impl(
+                $level,
+                &[$($crate::log::LogField::new($key, $value)),*],
+                format_args!($($arg)*),
+            );
+        } else {
+            $crate::log::log_filtered_impl();
+        }
+    };
+}
+
+/// 带有级别过滤和按调用点限速的内部实现宏
+#[macro_export]
+macro_rules! __log_impl_ratelimited {
+    ($level:expr, $args:expr) => {{
+        static STATE: $crate::log::RateLimitState = $crate::log::RateLimitState::new(
+            $crate::log::DEFAULT_RATELIMIT_INTERVAL,
+            $crate::log::DEFAULT_RATELIMIT_BURST,
+        );
+        if $crate::log::is_level_enabled($level) {
+            $crate::log::log_ratelimited(&STATE, $level, concat!(file!(), ":", line!()), $args);
+        } else {
+            $crate::log::log_filtered_impl();
+        }
+    }};
+}
+
+/// 以 **EMERGENCY (紧急)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_emerg_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Emergency,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ALERT (警报)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_alert_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Alert,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **CRITICAL (关键)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_crit_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Critical,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ERROR (错误)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_err_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Error,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **WARNING (警告)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_warn_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Warning,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **NOTICE (通知)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_notice_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Notice,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **INFO (信息)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_info_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Info,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **DEBUG (调试)** 级别记录消息，同一调用点按窗口限速
+#[macro_export]
+macro_rules! pr_debug_ratelimited {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_ratelimited!(
+            $crate::log::LogLevel::Debug,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 带有级别过滤、每个调用点只记录一次的内部实现宏
+#[macro_export]
+macro_rules! __log_impl_once {
+    ($level:expr, $args:expr) => {{
+        static DONE: ::core::sync::atomic::AtomicBool =
+            ::core::sync::atomic::AtomicBool::new(false);
+        if $crate::log::is_level_enabled($level)
+            && !DONE.swap(true, ::core::sync::atomic::Ordering::Relaxed)
+        {
+            $crate::log::log_impl($level, $args);
+        } else {
+            $crate::log::log_filtered_impl();
+        }
+    }};
+}
+
+/// 条件成立时发出一次带调用点上下文的警告，返回条件的值（类似 Linux 的 `WARN_ONCE`）
+#[macro_export]
+macro_rules! warn_once {
+    ($cond:expr $(,)?) => {
+        $crate::warn_once!($cond, "{}", stringify!($cond))
+    };
+    ($cond:expr, $($arg:tt)+) => {{
+        static WARNED: ::core::sync::atomic::AtomicBool = ::core::sync::atomic::AtomicBool::new(false);
+        let cond: bool = $cond;
+        if cond && !WARNED.swap(true, ::core::sync::atomic::Ordering::Relaxed) {
+            $crate::log::warn_impl(
+                concat!(file!(), ":", line!()),
+                module_path!(),
+                format_args!($($arg)+),
+            );
+        }
+        cond
+    }};
+}
+
+/// 以 **EMERGENCY (紧急)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_emerg_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Emergency,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ALERT (警报)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_alert_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Alert,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **CRITICAL (关键)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_crit_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Critical,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **ERROR (错误)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_err_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Error,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **WARNING (警告)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_warn_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Warning,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **NOTICE (通知)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_notice_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Notice,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **INFO (信息)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_info_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Info,
+            format_args!($($arg)*)
+        )
+    }
+}
+
+/// 以 **DEBUG (调试)** 级别记录消息，每个调用点只记录一次
+#[macro_export]
+macro_rules! pr_debug_once {
+    ($($arg:tt)*) => {
+        $crate::__log_impl_once!(
+            $crate::log::LogLevel::Debug,
+            format_args!($($arg)*)
+        )
+    }
+}
+
 // ========== LogContextProvider 实现 ==========
 
 /// OS 层的日志上下文提供者
@@ -140,8 +505,9 @@ impl LogContextProvider for OsLogContextProvider {
             .unwrap_or(0)
     }
 
+    /// 自启动以来的纳秒数
     fn timestamp(&self) -> usize {
-        timer::get_time()
+        crate::kernel::hrtimer::ktime_get() as usize
     }
 }
 
@@ -155,6 +521,14 @@ impl LogOutput for OsLogOutput {
         let mut stdout = Stdout;
         let _ = stdout.write_str(s);
     }
+
+    fn wants_level(&self, level: LogLevel) -> bool {
+        crate::console::wants_log_level(level as u8)
+    }
+
+    fn write_log(&self, level: LogLevel, s: &str, console: bool) {
+        crate::console::write_log(level as u8, s, console);
+    }
 }
 
 // ========== 全局实例 ==========
@@ -174,6 +548,23 @@ pub fn init() {
     }
 }
 
+/// 按内核命令行（[`crate::device::CMDLINE`]）中的 `loglevel=`、`quiet`、`debug` 调整日志级别，
+/// 并按 `netconsole=` 启用 netconsole
+///
+/// 在解析设备树、保存命令行之后调用。
+pub fn apply_cmdline() {
+    let cmdline = crate::device::CMDLINE.read();
+    netconsole::init(&cmdline);
+    let params = apply_boot_params(&cmdline);
+    if params != BootLogParams::default() {
+        crate::pr_info!(
+            "log: console level {:?}, global level {:?}",
+            get_console_level(),
+            get_global_level()
+        );
+    }
+}
+
 // NOTE: os crate 的日志实现是对 klog crate 的封装层。
 // 原有测试依赖 klog 的内部模块（LogCore/level 等），在 crate 拆分后无法直接访问。
 // 这些测试应迁移到 crates/klog 中以使用标准 `#[test]` 运行。
@@ -0,0 +1,233 @@
+//! netconsole：通过 UDP 发送内核日志
+//!
+//! 命令行给出 Linux 格式的 `netconsole=[src-port]@[src-ip]/[dev],[tgt-port]@<tgt-ip>/[tgt-mac]`
+//! 时启用（只使用两个端口与目标 IP）。[`NetLogOutput`] 作为附加输出端注册到 klog，
+//! 把达到控制台级别的日志放入队列；网络接口就绪后由 [`netconsole_thread`] 把队列中的
+//! 日志逐条作为 UDP 报文发出。接口就绪前的日志留在队列中，超过上限时丢弃最旧的。
+//!
+//! 日志路径上只做入队（拿不到锁时直接丢弃），不会在中断上下文或持有网络锁时进入协议栈。
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::kernel::hrtimer::{Ktime, NSEC_PER_MSEC, ktime_get};
+use crate::kernel::{current_task, sleep_task_with_block, wake_task_at, yield_task};
+use crate::net::socket::{NET_IFACE, SOCKET_SET};
+use crate::net::{
+    IpAddress, IpEndpoint, Ipv4Address, SmoltcpSocketHandle, SocketHandle, create_udp_socket,
+    poll_network_interfaces, udp,
+};
+use crate::sync::SpinLock;
+use klog::LogOutput;
+
+/// 缺省的本地端口
+const DEFAULT_LOCAL_PORT: u16 = 6665;
+/// 缺省的目标端口
+const DEFAULT_REMOTE_PORT: u16 = 6666;
+/// 队列中最多保留的日志条数
+const NETCONSOLE_QUEUE_MAX: usize = 256;
+/// 发送间隔
+const NETCONSOLE_INTERVAL: Ktime = 50 * NSEC_PER_MSEC;
+
+/// netconsole 配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetconsoleConfig {
+    /// 本地 UDP 端口
+    pub local_port: u16,
+    /// 日志接收端
+    pub remote: IpEndpoint,
+}
+
+impl NetconsoleConfig {
+    /// 解析 `netconsole=` 的参数值，格式错误或缺少目标 IP 时返回 `None`
+    pub fn parse(arg: &str) -> Option<Self> {
+        // 开头的 `+`（扩展格式）与 `r`（附带版本号）标志不影响发送
+        let arg = arg.trim_start_matches(['+', 'r']);
+        let (local, remote) = arg.split_once(',')?;
+        let local_port = parse_port(local.split('@').next()?, DEFAULT_LOCAL_PORT)?;
+        let (remote_port, remote) = remote.split_once('@')?;
+        let remote_port = parse_port(remote_port, DEFAULT_REMOTE_PORT)?;
+        let ip = remote.split('/').next()?.parse::<Ipv4Address>().ok()?;
+        Some(Self {
+            local_port,
+            remote: IpEndpoint::new(IpAddress::Ipv4(ip), remote_port),
+        })
+    }
+}
+
+/// 解析端口，空串取缺省值
+fn parse_port(s: &str, default: u16) -> Option<u16> {
+    if s.is_empty() {
+        Some(default)
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// 去掉 ANSI 控制序列（`ESC [ ... 字母`）
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() {
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// netconsole 输出端：只把日志放入发送队列
+pub struct NetLogOutput {
+    queue: SpinLock<VecDeque<String>>,
+    /// 因队列已满或锁竞争丢弃的日志条数
+    dropped: AtomicUsize,
+}
+
+impl NetLogOutput {
+    const fn new() -> Self {
+        Self {
+            queue: SpinLock::new(VecDeque::new()),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl LogOutput for NetLogOutput {
+    fn write_str(&self, s: &str) {
+        let Some(mut queue) = self.queue.try_lock() else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        if queue.len() >= NETCONSOLE_QUEUE_MAX {
+            queue.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        queue.push_back(strip_ansi(s));
+    }
+}
+
+static NET_LOG_OUTPUT: NetLogOutput = NetLogOutput::new();
+static NETCONSOLE_CONFIG: SpinLock<Option<NetconsoleConfig>> = SpinLock::new(None);
+
+/// 按命令行启用 netconsole，返回是否启用
+///
+/// 在保存命令行之后尽早调用，以便缓存网络就绪前的日志。
+pub fn init(cmdline: &str) -> bool {
+    let Some(arg) = cmdline
+        .split_whitespace()
+        .find_map(|tok| tok.strip_prefix("netconsole="))
+    else {
+        return false;
+    };
+    let Some(config) = NetconsoleConfig::parse(arg) else {
+        crate::pr_warn!("netconsole: invalid argument '{}'", arg);
+        return false;
+    };
+    // Safety: NET_LOG_OUTPUT 是静态实例，且只在这里注册一次
+    if !unsafe { klog::register_log_sink(&NET_LOG_OUTPUT) } {
+        crate::pr_warn!("netconsole: no free log sink");
+        return false;
+    }
+    *NETCONSOLE_CONFIG.lock() = Some(config);
+    crate::pr_info!("netconsole: logging to {}", config.remote);
+    true
+}
+
+/// 是否已启用 netconsole
+pub fn enabled() -> bool {
+    NETCONSOLE_CONFIG.lock().is_some()
+}
+
+/// 发送队列中的日志，发送缓冲区满时留待下次
+fn send_pending(handle: SmoltcpSocketHandle, remote: IpEndpoint) {
+    let dropped = NET_LOG_OUTPUT.dropped.swap(0, Ordering::Relaxed);
+    if dropped > 0 {
+        NET_LOG_OUTPUT.queue.lock().push_front(alloc::format!(
+            "[netconsole: {} messages dropped]\n",
+            dropped
+        ));
+    }
+    loop {
+        let Some(line) = NET_LOG_OUTPUT.queue.lock().pop_front() else {
+            break;
+        };
+        let mut sockets = SOCKET_SET.lock();
+        let socket = sockets.get_mut::<udp::Socket>(handle);
+        if socket.send_slice(line.as_bytes(), remote).is_err() {
+            drop(sockets);
+            NET_LOG_OUTPUT.queue.lock().push_front(line);
+            break;
+        }
+    }
+}
+
+/// netconsole 发送线程（由 kthreadd 在启用 netconsole 时创建）
+pub fn netconsole_thread() {
+    let Some(config) = *NETCONSOLE_CONFIG.lock() else {
+        return;
+    };
+    let task = current_task();
+    let mut handle = None;
+    loop {
+        if handle.is_none() && NET_IFACE.lock().is_some() {
+            handle = match create_udp_socket() {
+                Ok(SocketHandle::Udp(h)) => {
+                    let bound = SOCKET_SET
+                        .lock()
+                        .get_mut::<udp::Socket>(h)
+                        .bind(config.local_port)
+                        .is_ok();
+                    bound.then_some(h)
+                }
+                _ => None,
+            };
+        }
+        if let Some(h) = handle {
+            send_pending(h, config.remote);
+            poll_network_interfaces();
+        }
+        sleep_task_with_block(task.clone(), true);
+        let timer = wake_task_at(task.clone(), ktime_get() + NETCONSOLE_INTERVAL);
+        yield_task();
+        timer.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_netconsole_parse() {
+        let config =
+            NetconsoleConfig::parse("4444@10.0.2.15/eth0,9353@10.0.2.2/12:34:56:78:9a:bc").unwrap();
+        assert_eq!(config.local_port, 4444);
+        assert_eq!(
+            config.remote,
+            IpEndpoint::new(IpAddress::Ipv4(Ipv4Address::new(10, 0, 2, 2)), 9353)
+        );
+
+        let config = NetconsoleConfig::parse("+@/,@192.168.1.1/").unwrap();
+        assert_eq!(config.local_port, DEFAULT_LOCAL_PORT);
+        assert_eq!(config.remote.port, DEFAULT_REMOTE_PORT);
+
+        assert_eq!(NetconsoleConfig::parse("@/eth0,6666@/"), None);
+        assert_eq!(NetconsoleConfig::parse("6665@10.0.2.15/eth0"), None);
+    }
+
+    #[test_case]
+    fn test_netconsole_strip_ansi() {
+        assert_eq!(
+            strip_ansi("\x1b[31m[ERR] disk failed\x1b[0m\n"),
+            "[ERR] disk failed\n"
+        );
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+}
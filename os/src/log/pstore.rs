@@ -0,0 +1,115 @@
+//! pstore 后端
+//!
+//! 默认使用紧跟内核镜像之后的一块保留物理内存（由 [`crate::mm::init`] 从帧分配器中扣除），
+//! 热重启后内容仍在。命令行给出 `pstore.blkdev=N` 时改用第 N 个块设备的开头，
+//! 该块设备必须专门留给 pstore。
+//!
+//! 上一次启动保存的崩溃日志由 sysfs 导出为 `/sys/fs/pstore/dmesg-ramoops-0`。
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+
+use klog::pstore::{PstoreBackend, RamPstore, register_backend};
+
+use crate::arch::mm::paddr_to_vaddr;
+use crate::config::PSTORE_SIZE;
+use crate::device::block::BlockDriver;
+use crate::device::{BLK_DRIVERS, CMDLINE};
+
+pub use klog::pstore::{dump, erase, read_record};
+
+/// 以 `[paddr, paddr + PSTORE_SIZE)` 作为 pstore 存储
+pub fn init_ram(paddr: usize) {
+    // Safety: 该区域已从帧分配器中扣除，只由 pstore 使用，且位于内核的物理内存直接映射中
+    let backend = unsafe { RamPstore::new(paddr_to_vaddr(paddr) as *mut u8, PSTORE_SIZE) };
+    // Safety: 启动阶段单核调用，不会与 panic 时的转储并发
+    unsafe { register_backend(Box::leak(Box::new(backend))) };
+}
+
+/// 按命令行的 `pstore.blkdev=N` 改用块设备作为 pstore 存储
+///
+/// 必须在块设备探测完成之后调用。
+pub fn init_blk() {
+    let index = CMDLINE
+        .read()
+        .split_whitespace()
+        .find_map(|tok| tok.strip_prefix("pstore.blkdev="))
+        .and_then(|n| n.parse::<usize>().ok());
+    let Some(index) = index else {
+        return;
+    };
+    let Some(driver) = BLK_DRIVERS.read().get(index).cloned() else {
+        crate::pr_warn!("pstore: block device {} not found", index);
+        return;
+    };
+    crate::pr_info!("pstore: using block device {}", driver.get_id());
+    let backend = BlockPstore { driver };
+    // Safety: 启动阶段调用，不会与 panic 时的转储并发
+    unsafe { register_backend(Box::leak(Box::new(backend))) };
+}
+
+/// 基于块设备的后端，使用设备开头的 `PSTORE_SIZE` 字节
+struct BlockPstore {
+    driver: Arc<dyn BlockDriver>,
+}
+
+impl BlockPstore {
+    /// 对 `[offset, offset + len)` 覆盖到的每个块调用 `f(块号, 块内范围, 在请求中的偏移)`
+    fn for_each_block(
+        &self,
+        offset: usize,
+        len: usize,
+        mut f: impl FnMut(usize, core::ops::Range<usize>, usize) -> bool,
+    ) -> bool {
+        let block_size = self.driver.block_size();
+        let mut pos = offset;
+        while pos < offset + len {
+            let in_block = pos % block_size;
+            let n = (block_size - in_block).min(offset + len - pos);
+            if !f(pos / block_size, in_block..in_block + n, pos - offset) {
+                return false;
+            }
+            pos += n;
+        }
+        true
+    }
+}
+
+impl PstoreBackend for BlockPstore {
+    fn size(&self) -> usize {
+        (self.driver.total_blocks() * self.driver.block_size()).min(PSTORE_SIZE)
+    }
+
+    fn read(&self, offset: usize, buf: &mut [u8]) -> bool {
+        if offset + buf.len() > self.size() {
+            return false;
+        }
+        let mut block = vec![0u8; self.driver.block_size()];
+        self.for_each_block(offset, buf.len(), |id, range, done| {
+            if !self.driver.read_block(id, &mut block) {
+                return false;
+            }
+            let n = range.len();
+            buf[done..done + n].copy_from_slice(&block[range]);
+            true
+        })
+    }
+
+    fn write(&self, offset: usize, data: &[u8]) -> bool {
+        if offset + data.len() > self.size() {
+            return false;
+        }
+        let block_size = self.driver.block_size();
+        let mut block = vec![0u8; block_size];
+        self.for_each_block(offset, data.len(), |id, range, done| {
+            // 不足一块时先读出原内容
+            if range.len() < block_size && !self.driver.read_block(id, &mut block) {
+                return false;
+            }
+            let n = range.len();
+            block[range].copy_from_slice(&data[done..done + n]);
+            self.driver.write_block(id, &block)
+        }) && self.driver.flush()
+    }
+}
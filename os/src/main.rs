@@ -140,6 +140,10 @@ pub extern "C" fn rust_main(_hartid: usize) -> ! {
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
+    // 同步输出刷写线程尚未输出的日志
+    crate::log::set_console_deferred(false);
+    // 保存崩溃日志，重启后从 /sys/fs/pstore 读取
+    crate::log::pstore::dump(format_args!("{}", info));
     if let Some(location) = info.location() {
         earlyprintln!(
             "Panicked at {}:{} {}",
@@ -0,0 +1,43 @@
+//! 内存规整接入
+//!
+//! [`mm::compaction`] 按反向映射记录的根页表找到页的属主后调用本模块迁移页。
+//! 规整可能发生在持有任务管理器、任务或地址空间锁的分配路径上，与 [`oom`](super::oom)
+//! 相同，这里只尝试加锁，拿不到锁的页本轮不迁移。
+
+use mm::address::{Ppn, Vpn};
+use mm::compaction::{self, MigrateOps};
+use mm::frame_allocator::FrameTracker;
+
+use crate::kernel::{TASK_MANAGER, TaskManagerTrait};
+
+/// 注册页迁移操作
+pub fn init() {
+    // Safety: 启动阶段单核调用，只调用一次
+    unsafe { compaction::register_migrate_ops(&TASK_MIGRATE_OPS) };
+}
+
+/// 在任务的地址空间中迁移页
+struct TaskMigrateOps;
+
+static TASK_MIGRATE_OPS: TaskMigrateOps = TaskMigrateOps;
+
+impl MigrateOps for TaskMigrateOps {
+    fn migrate_page(&self, owner: Ppn, vpn: Vpn, old: Ppn, new: FrameTracker) -> bool {
+        let tasks = match TASK_MANAGER.try_lock() {
+            Some(tm) => tm.get_all_tasks(),
+            None => return false,
+        };
+        for task in &tasks {
+            let Some(space) = task.try_lock().and_then(|t| t.memory_space.clone()) else {
+                continue;
+            };
+            let Some(mut space) = space.try_lock() else {
+                continue;
+            };
+            if space.root_ppn() == owner {
+                return space.migrate_page(vpn, old, new);
+            }
+        }
+        false
+    }
+}
@@ -7,11 +7,69 @@
 //! - 基于 **talc::Talck** 的全局堆分配器。
 //! - 由链接器符号定义的堆内存区域。
 //! - 用于设置堆的初始化函数。
+//!
+//! 分配器外层包裹 [`SlabAccounting`]，把已分配的字节数计入 `mm::vmstat`（`/proc/meminfo` 的 `Slab`）。
+//! 启用 `fault-injection` feature 时，再在最外层包裹
+//! `test_support::fault::FaultInjectingAlloc`，可由测试按策略让堆分配失败。
+
+use core::alloc::{GlobalAlloc, Layout};
 
 use crate::earlyprintln;
 use crate::sync::RawSpinLockWithoutGuard;
+use mm::vmstat::{self, NrItem};
 use talc::{Span, Talc, Talck};
 
+/// 统计已分配字节数的堆分配器包装
+pub struct SlabAccounting<A> {
+    inner: A,
+}
+
+impl<A> SlabAccounting<A> {
+    /// 包装 `inner`
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    /// 被包装的分配器
+    pub const fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for SlabAccounting<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.inner.alloc(layout) };
+        if !ptr.is_null() {
+            vmstat::mod_state(NrItem::SlabBytes, layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.inner.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            vmstat::mod_state(NrItem::SlabBytes, layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) };
+        vmstat::mod_state(NrItem::SlabBytes, -(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { self.inner.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            vmstat::mod_state(
+                NrItem::SlabBytes,
+                new_size as isize - layout.size() as isize,
+            );
+        }
+        new_ptr
+    }
+}
+
 /// 全局堆分配器实例
 ///
 /// 使用 talc 的基于锁的分配器 (**Talck**) 和我们自定义的 **`RawSpinLockWithoutGuard`**。
@@ -19,9 +77,19 @@ use talc::{Span, Talc, Talck};
 /// 以防止当中断处理程序尝试分配内存时发生死锁。
 ///
 /// 初始化时使用一个空范围 (**Span::empty()**)；实际内存将在 `init_heap()` 中声明。
+#[cfg(not(feature = "fault-injection"))]
 #[global_allocator]
-static ALLOCATOR: Talck<RawSpinLockWithoutGuard, talc::ClaimOnOom> =
-    Talc::new(unsafe { talc::ClaimOnOom::new(Span::empty()) }).lock();
+static ALLOCATOR: SlabAccounting<Talck<RawSpinLockWithoutGuard, talc::ClaimOnOom>> =
+    SlabAccounting::new(Talc::new(unsafe { talc::ClaimOnOom::new(Span::empty()) }).lock());
+
+/// 带故障注入的全局堆分配器（仅 `fault-injection` feature）
+#[cfg(feature = "fault-injection")]
+#[global_allocator]
+static FAULTY_ALLOCATOR: test_support::fault::FaultInjectingAlloc<
+    SlabAccounting<Talck<RawSpinLockWithoutGuard, talc::ClaimOnOom>>,
+> = test_support::fault::FaultInjectingAlloc::new(SlabAccounting::new(
+    Talc::new(unsafe { talc::ClaimOnOom::new(Span::empty()) }).lock(),
+));
 
 /// 使用链接器脚本中定义的堆内存区域初始化堆分配器
 ///
@@ -51,8 +119,13 @@ pub fn init_heap() {
         heap_size / 1024 / 1024
     );
 
+    #[cfg(not(feature = "fault-injection"))]
+    let talck = ALLOCATOR.inner();
+    #[cfg(feature = "fault-injection")]
+    let talck = FAULTY_ALLOCATOR.inner().inner();
+
     unsafe {
-        ALLOCATOR
+        talck
             .lock()
             .claim(Span::new(heap_start as *mut u8, heap_end as *mut u8))
             .expect("Failed to initialize heap allocator");
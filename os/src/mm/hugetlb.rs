@@ -0,0 +1,32 @@
+//! 大页池接入
+//!
+//! 大页池由 [`mm::hugetlb`] 实现，本模块在启动时按命令行的 `hugepages=N` 预留 N 个 2M 大页。
+//! 启动早期物理内存碎片最少，此时预留最容易拿到足够的连续帧。
+
+use mm::hugetlb;
+
+use crate::device::CMDLINE;
+
+/// 按命令行的 `hugepages=N` 预留大页池
+///
+/// 必须在设备树解析完成（命令行已读入）之后调用。
+pub fn init() {
+    let count = CMDLINE
+        .read()
+        .split_whitespace()
+        .find_map(|tok| tok.strip_prefix("hugepages="))
+        .and_then(|n| n.parse::<usize>().ok());
+    let Some(count) = count else {
+        return;
+    };
+    let reserved = hugetlb::set_nr_hugepages(count);
+    if reserved < count {
+        crate::pr_warn!(
+            "hugetlb: only reserved {} of {} huge pages",
+            reserved,
+            count
+        );
+    } else {
+        crate::pr_info!("hugetlb: reserved {} huge pages", reserved);
+    }
+}
@@ -0,0 +1,125 @@
+//! 内核地址空间布局随机化（KASLR）
+//!
+//! 启用 `kaslr` feature（`make KASLR=1`）时内核被链接为位置无关可执行文件，
+//! 启动汇编在进入 Rust 之前：
+//! 1. 用计时器与 RTC 的读数作为早期熵，调用 [`kaslr_choose_slide`] 选出偏移；
+//! 2. 把内核映射到链接地址加上偏移处并跳转过去；
+//! 3. 调用 [`kaslr_relocate`] 按 `.rela.dyn` 修正镜像中的绝对地址。
+//!
+//! 内核镜像位于线性映射之中（RISC-V 的高半区直接映射、LoongArch 的 DMW 窗口），
+//! 两者使用同一个偏移：`arch::mm` 的地址转换函数都加上了 [`kaslr_slide`]，
+//! 之后建立的内核页表、MMIO 映射与帧访问都随之移动。
+//!
+//! 未启用时偏移恒为 0，布局与链接地址一致。
+
+use crate::arch::mm::{KASLR_ALIGN, KASLR_SLOTS};
+
+unsafe extern "C" {
+    /// 启动时选定的偏移（在 entry.S 的 `.data` 中定义，不会被清零 BSS 覆盖）
+    static kaslr_slide: usize;
+}
+
+/// `.rela.dyn` 中的一项（`Elf64_Rela`）
+#[repr(C)]
+pub struct Elf64Rela {
+    offset: usize,
+    info: usize,
+    addend: usize,
+}
+
+/// 相对重定位类型：`R_RISCV_RELATIVE` 与 `R_LARCH_RELATIVE` 都是 3
+const R_RELATIVE: usize = 3;
+
+/// 内核虚拟地址相对链接地址的偏移
+#[inline]
+pub fn kaslr_slide() -> usize {
+    // SAFETY: 只在启动汇编中写入一次，之后只读
+    unsafe { core::ptr::read_volatile(&raw const kaslr_slide) }
+}
+
+/// 由熵选出偏移：`KASLR_SLOTS` 个候选位置之一，按 `KASLR_ALIGN` 对齐
+///
+/// 在启用分页与重定位之前调用，不能访问任何需要重定位的数据。
+pub const fn slide_from_entropy(entropy: usize, slots: usize, align: usize) -> usize {
+    // splitmix64 的混合步骤，让计时器低位的变化扩散到所有位
+    let mut x = (entropy as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+    (x % slots as u64) as usize * align
+}
+
+/// 启动汇编调用：选择本次启动的偏移
+///
+/// 此时分页可能尚未开启，仅做整数运算。
+#[unsafe(no_mangle)]
+pub extern "C" fn kaslr_choose_slide(entropy: usize) -> usize {
+    if cfg!(feature = "kaslr") {
+        slide_from_entropy(entropy, KASLR_SLOTS, KASLR_ALIGN)
+    } else {
+        0
+    }
+}
+
+/// 启动汇编调用：按 `[start, end)` 中的相对重定位项修正内核镜像
+///
+/// 每项的位置与值都是链接地址，修正后加上 `slide`。
+/// 未以位置无关方式链接时重定位表为空。
+///
+/// # Safety
+/// 只能在跳转到偏移后的地址、进入 Rust 主入口之前由主核调用一次；
+/// 调用前不能访问任何含绝对地址的数据。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kaslr_relocate(
+    slide: usize,
+    start: *const Elf64Rela,
+    end: *const Elf64Rela,
+) {
+    if slide == 0 {
+        return;
+    }
+    let mut rela = start;
+    while rela < end {
+        // SAFETY: [start, end) 是链接器生成的重定位表，目标位于已映射的内核镜像中
+        unsafe {
+            let entry = &*rela;
+            if entry.info & 0xffff_ffff == R_RELATIVE {
+                let target = entry.offset.wrapping_add(slide) as *mut usize;
+                target.write(entry.addend.wrapping_add(slide));
+            }
+            rela = rela.add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arch::mm::{paddr_to_vaddr, vaddr_to_paddr};
+
+    // 偏移总是对齐且落在候选范围内，不同的熵能选出不同的位置
+    #[test_case]
+    fn test_kaslr_slide_from_entropy() {
+        for entropy in 0..64 {
+            let slide = slide_from_entropy(entropy, KASLR_SLOTS, KASLR_ALIGN);
+            assert!(slide % KASLR_ALIGN == 0);
+            assert!(slide / KASLR_ALIGN < KASLR_SLOTS);
+        }
+        let first = slide_from_entropy(0, KASLR_SLOTS, KASLR_ALIGN);
+        assert!((1..64).any(|e| slide_from_entropy(e, KASLR_SLOTS, KASLR_ALIGN) != first));
+        if !cfg!(feature = "kaslr") {
+            assert!(kaslr_slide() == 0);
+        }
+    }
+
+    // 地址转换在偏移下仍然互逆，内核镜像位于线性映射之中
+    #[test_case]
+    fn test_kaslr_translation_round_trip() {
+        unsafe extern "C" {
+            fn stext();
+        }
+        let vaddr = stext as usize;
+        let paddr = unsafe { vaddr_to_paddr(vaddr) };
+        assert!(paddr_to_vaddr(paddr) == vaddr);
+    }
+}
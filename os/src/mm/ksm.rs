@@ -0,0 +1,73 @@
+//! KSM 扫描线程（ksmd）
+//!
+//! 合并算法由 [`mm::ksm`] 实现，本模块负责按 `/sys/kernel/mm/ksm` 的参数周期性地驱动扫描：
+//! 每批扫描 `pages_to_scan` 页后休眠 `sleep_millisecs` 毫秒，按根页表的物理页号依次
+//! 扫描各进程地址空间中的可合并区域，扫描完所有地址空间即为一轮完整扫描。
+//! `run` 为 0 时线程每秒检查一次是否被启动。
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use mm::address::{Ppn, UsizeConvert, Vpn};
+use mm::ksm;
+
+use super::MemorySpace;
+use crate::kernel::hrtimer::{NSEC_PER_MSEC, NSEC_PER_SEC, ktime_get};
+use crate::kernel::{
+    TASK_MANAGER, TaskManagerTrait, current_task, sleep_task_with_block, wake_task_at, yield_task,
+};
+use crate::sync::SpinLock;
+
+/// KSM 扫描线程主函数（由 kthreadd 通过 `kthread_spawn` 创建）
+pub fn ksmd() {
+    let task = current_task();
+    let mut cursor = None;
+    loop {
+        let interval = if ksm::run() {
+            scan_batch(&mut cursor);
+            ksm::sleep_millisecs() as u64 * NSEC_PER_MSEC
+        } else {
+            NSEC_PER_SEC
+        };
+        sleep_task_with_block(task.clone(), true);
+        let timer = wake_task_at(task.clone(), ktime_get() + interval);
+        yield_task();
+        timer.cancel();
+    }
+}
+
+/// 从 `cursor`（地址空间的根页表与其中的虚拟页）处继续扫描一批页
+///
+/// 所有地址空间都扫描完时结束一轮完整扫描，`cursor` 回到开头。
+fn scan_batch(cursor: &mut Option<(Ppn, Vpn)>) {
+    let mut budget = ksm::pages_to_scan();
+    for (root, space) in user_spaces() {
+        let from = match *cursor {
+            Some((owner, _)) if root < owner => continue,
+            Some((owner, vpn)) if root == owner => vpn,
+            _ => Vpn::from_usize(0),
+        };
+        if let Some(next) = space.lock().ksm_scan(from, &mut budget) {
+            *cursor = Some((root, next));
+            return;
+        }
+    }
+    *cursor = None;
+    ksm::end_full_scan();
+}
+
+/// 所有用户地址空间（同一进程的线程共享的地址空间只出现一次），按根页表的物理页号排序
+fn user_spaces() -> Vec<(Ppn, Arc<SpinLock<MemorySpace>>)> {
+    let tasks = TASK_MANAGER.lock().get_all_tasks();
+    let mut spaces: Vec<(Ppn, Arc<SpinLock<MemorySpace>>)> = tasks
+        .iter()
+        .filter_map(|task| task.lock().memory_space.clone())
+        .map(|space| {
+            let root = space.lock().root_ppn();
+            (root, space)
+        })
+        .collect();
+    spaces.sort_by_key(|(root, _)| *root);
+    spaces.dedup_by_key(|(root, _)| *root);
+    spaces
+}
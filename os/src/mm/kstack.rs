@@ -0,0 +1,277 @@
+//! 带保护页的内核栈
+//!
+//! 内核栈不从线性映射中分配连续帧，而是映射到内核栈区（从 [`KSTACK_AREA_START`] 开始）
+//! 的一个槽位中：每个槽位 [`KSTACK_SLOT_SIZE`] 字节，栈占据槽位的顶部，
+//! 下方的 [`KSTACK_GUARD_SIZE`] 字节不映射，作为保护页。
+//! 栈溢出时访问保护页产生缺页，陷阱处理程序据此报告 "kernel stack overflow in task N"，
+//! 而不是悄悄写坏相邻的物理帧。
+//!
+//! 内核栈区只占根页表中的一项，该项指向所有地址空间共享的下一级页表（见 [`link_kstack_area`]），
+//! 在内核页表中建立的映射对所有地址空间立即可见。vmalloc 区紧随其后，共用这一项（见 [`super::vmalloc`]）。
+//!
+//! 溢出后 `sp` 落在保护页中，陷阱入口检测到这种情况时改用 [`KSTACK_OVERFLOW_STACK`]，
+//! 否则陷阱处理程序自己的栈帧会再次缺页。
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use lazy_static::lazy_static;
+use mm::TlbBatchContextWrapper;
+use mm::address::{ConvertablePaddr, PageNum, UsizeConvert, Vaddr, Vpn};
+use mm::frame_allocator::{FrameTracker, alloc_frame, alloc_frames};
+use mm::page_table::{
+    PageSize, PageTableEntry as _, PageTableInner as PageTableInnerTrait, UniversalPTEFlag,
+};
+
+use super::with_kernel_space;
+pub use crate::arch::mm::KSTACK_AREA_START;
+use crate::arch::mm::{PageTableEntry, PageTableInner};
+use crate::config::PAGE_SIZE;
+use crate::sync::SpinLock;
+
+/// 内核栈的页数
+pub const KERNEL_STACK_PAGES: usize = 4;
+/// 内核栈的大小（字节）
+pub const KERNEL_STACK_SIZE: usize = KERNEL_STACK_PAGES * PAGE_SIZE;
+/// 每个槽位的大小，取 2 的幂以便陷阱入口用掩码判断 `sp` 是否落在保护页中
+pub const KSTACK_SLOT_SIZE: usize = 2 * KERNEL_STACK_SIZE;
+/// 每个栈下方保护页的大小
+pub const KSTACK_GUARD_SIZE: usize = KSTACK_SLOT_SIZE - KERNEL_STACK_SIZE;
+/// 槽位个数，即同时存在的内核栈上限
+pub const KSTACK_SLOTS: usize = 4096;
+/// 内核栈区的大小
+pub const KSTACK_AREA_SIZE: usize = KSTACK_SLOTS * KSTACK_SLOT_SIZE;
+
+// 内核栈区必须落在根页表的一项之内（RISC-V Sv39 为 1 GiB）
+const _: () = assert!(KSTACK_AREA_SIZE <= 1 << 30);
+const _: () = assert!(KSTACK_SLOT_SIZE.is_power_of_two());
+
+lazy_static! {
+    /// 内核栈区共享的下一级页表
+    static ref KSTACK_TABLE: FrameTracker =
+        alloc_frame().expect("kstack: failed to alloc shared page table");
+}
+
+/// 空闲槽位
+struct SlotAllocator {
+    /// 从未使用过的最小槽位
+    next: usize,
+    /// 释放后可重用的槽位
+    free: Vec<usize>,
+}
+
+static SLOTS: SpinLock<SlotAllocator> = SpinLock::new(SlotAllocator {
+    next: 0,
+    free: Vec::new(),
+});
+
+/// 每个槽位所属任务的 tid，0 表示尚未登记
+///
+/// 陷阱处理程序在栈溢出时读取，不能加锁。
+static KSTACK_OWNERS: [AtomicU32; KSTACK_SLOTS] = [const { AtomicU32::new(0) }; KSTACK_SLOTS];
+
+/// 释放时仍在其上运行、推迟到下一次分配时释放的栈
+static DEFERRED: SpinLock<Vec<(usize, Vec<FrameTracker>)>> = SpinLock::new(Vec::new());
+
+/// 栈溢出后陷阱处理使用的栈
+#[repr(C, align(16))]
+pub struct OverflowStack([u8; KERNEL_STACK_SIZE]);
+
+/// 陷阱入口在内核栈溢出时切换到的栈，同一时刻只供一个 CPU 使用
+pub static mut KSTACK_OVERFLOW_STACK: OverflowStack = OverflowStack([0; KERNEL_STACK_SIZE]);
+/// 溢出栈是否已被占用，陷阱入口用原子交换获取，之后不再释放
+pub static KSTACK_OVERFLOW_LOCK: AtomicU32 = AtomicU32::new(0);
+
+/// 让 `page_table` 中的内核栈区指向共享页表
+///
+/// 每个新建的地址空间都要调用一次，之后内核栈的映射与内核页表一致。
+pub fn link_kstack_area(page_table: &PageTableInner) {
+    let levels = <PageTableInner as PageTableInnerTrait<PageTableEntry>>::LEVELS;
+    let vpn = Vpn::from_addr_floor(Vaddr::from_usize(KSTACK_AREA_START));
+    let idx = (vpn.as_usize() >> (9 * (levels - 1))) & 0x1ff;
+    let root = page_table.root_ppn().start_addr().to_vaddr().as_usize() as *mut PageTableEntry;
+    // SAFETY: 根页表占一整页（512 项），由 page_table 持有
+    unsafe {
+        root.add(idx)
+            .write(PageTableEntry::new_table(KSTACK_TABLE.ppn()))
+    };
+}
+
+/// 带保护页的内核栈
+#[derive(Debug)]
+pub struct KernelStack {
+    slot: usize,
+    frames: Vec<FrameTracker>,
+}
+
+impl KernelStack {
+    /// 分配并映射一个内核栈，帧不足或槽位用尽时返回 `None`
+    pub fn new() -> Option<Self> {
+        // 先释放推迟的栈，此时已不在它们上面运行
+        let deferred = core::mem::take(&mut *DEFERRED.lock());
+        for (slot, frames) in deferred {
+            release(slot, frames);
+        }
+
+        let frames = alloc_frames(KERNEL_STACK_PAGES)?;
+        let slot = {
+            let mut slots = SLOTS.lock();
+            match slots.free.pop() {
+                Some(slot) => slot,
+                None if slots.next < KSTACK_SLOTS => {
+                    slots.next += 1;
+                    slots.next - 1
+                }
+                None => return None,
+            }
+        };
+        let stack = KernelStack { slot, frames };
+        let mapped = with_kernel_space(|space| {
+            let page_table = space.page_table_mut();
+            stack
+                .vpns()
+                .zip(&stack.frames)
+                .try_for_each(|(vpn, frame)| {
+                    page_table.map(
+                        vpn,
+                        frame.ppn(),
+                        PageSize::Size4K,
+                        UniversalPTEFlag::kernel_rw(),
+                    )
+                })
+        });
+        // 映射失败时 Drop 解除已建立的映射并归还槽位
+        mapped.ok().map(|_| stack)
+    }
+
+    /// 栈底（最低地址），其下方是保护页
+    pub fn bottom(&self) -> usize {
+        slot_base(self.slot) + KSTACK_GUARD_SIZE
+    }
+
+    /// 栈顶（最高地址，不含）
+    pub fn top(&self) -> usize {
+        slot_base(self.slot) + KSTACK_SLOT_SIZE
+    }
+
+    /// 登记栈所属任务，用于溢出时报告
+    pub fn set_owner(&self, tid: u32) {
+        KSTACK_OWNERS[self.slot].store(tid, Ordering::Relaxed);
+    }
+
+    fn vpns(&self) -> impl Iterator<Item = Vpn> {
+        let start = Vpn::from_addr_floor(Vaddr::from_usize(self.bottom()));
+        (0..KERNEL_STACK_PAGES).map(move |i| Vpn::from_usize(start.as_usize() + i))
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        let frames = core::mem::take(&mut self.frames);
+        // 任务退出时最后一个引用可能在它自己的栈上释放，解除映射要推迟
+        let marker = 0u8;
+        let sp = &marker as *const u8 as usize;
+        if (self.bottom()..self.top()).contains(&sp) {
+            DEFERRED.lock().push((self.slot, frames));
+        } else {
+            release(self.slot, frames);
+        }
+    }
+}
+
+/// 槽位的起始地址
+fn slot_base(slot: usize) -> usize {
+    KSTACK_AREA_START + slot * KSTACK_SLOT_SIZE
+}
+
+/// 解除槽位中栈的映射，归还物理帧与槽位
+fn release(slot: usize, frames: Vec<FrameTracker>) {
+    let start = Vpn::from_addr_floor(Vaddr::from_usize(slot_base(slot) + KSTACK_GUARD_SIZE));
+    with_kernel_space(|space| {
+        let page_table = space.page_table_mut();
+        let _ = TlbBatchContextWrapper::execute(|batch| {
+            for i in 0..KERNEL_STACK_PAGES {
+                // 映射失败的栈只建立了一部分映射
+                let _ = page_table
+                    .unmap_with_batch(Vpn::from_usize(start.as_usize() + i), Some(&mut *batch));
+            }
+            Ok(())
+        });
+    });
+    drop(frames);
+    KSTACK_OWNERS[slot].store(0, Ordering::Relaxed);
+    SLOTS.lock().free.push(slot);
+}
+
+/// `addr` 是否位于内核栈区（不属于线性映射）
+pub fn is_kstack_addr(addr: usize) -> bool {
+    addr.wrapping_sub(KSTACK_AREA_START) < KSTACK_AREA_SIZE
+}
+
+/// `addr` 落在某个内核栈的保护页中时返回其槽位
+fn guard_slot(addr: usize) -> Option<usize> {
+    let offset = addr.wrapping_sub(KSTACK_AREA_START);
+    if offset >= KSTACK_AREA_SIZE || offset % KSTACK_SLOT_SIZE >= KSTACK_GUARD_SIZE {
+        return None;
+    }
+    Some(offset / KSTACK_SLOT_SIZE)
+}
+
+/// `addr` 是否落在某个内核栈的保护页中
+pub fn is_kstack_guard(addr: usize) -> bool {
+    guard_slot(addr).is_some()
+}
+
+/// 报告访问 `addr` 处保护页引起的内核栈溢出并停机
+///
+/// 只在陷阱处理程序中调用，此时运行在溢出栈上，不能加锁。
+pub fn kstack_overflow(addr: usize, pc: usize) -> ! {
+    let tid = guard_slot(addr).map_or(0, |slot| KSTACK_OWNERS[slot].load(Ordering::Relaxed));
+    crate::earlyprintln!(
+        "[kstack] guard page hit: fault address {:#x}, pc {:#x}",
+        addr,
+        pc
+    );
+    panic!("kernel stack overflow in task {}", tid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 栈映射在槽位顶部，可读写；下方的保护页没有映射，并被识别为保护页
+    #[test_case]
+    fn test_kstack_guard_page() {
+        let stack = KernelStack::new().expect("failed to alloc kernel stack");
+        stack.set_owner(42);
+        assert!(stack.top() - stack.bottom() == KERNEL_STACK_SIZE);
+
+        // SAFETY: [bottom, top) 已映射且由 stack 独占
+        unsafe {
+            let bottom = stack.bottom() as *mut usize;
+            bottom.write_volatile(0x5a5a);
+            assert!(bottom.read_volatile() == 0x5a5a);
+        }
+        assert!(is_kstack_addr(stack.bottom()) && !is_kstack_addr(KSTACK_AREA_START - 1));
+        assert!(!is_kstack_guard(stack.bottom()));
+        assert!(!is_kstack_guard(stack.top() - 8));
+        assert!(is_kstack_guard(stack.bottom() - 8));
+        assert!(guard_slot(stack.bottom() - KSTACK_GUARD_SIZE) == Some(stack.slot));
+        assert!(KSTACK_OWNERS[stack.slot].load(Ordering::Relaxed) == 42);
+
+        let guard = Vpn::from_addr_floor(Vaddr::from_usize(stack.bottom() - 8));
+        with_kernel_space(|space| assert!(space.page_table().walk(guard).is_err()));
+
+        // 当前地址空间同样能访问新映射的栈
+        let current = crate::kernel::current_memory_space();
+        let top = Vpn::from_addr_floor(Vaddr::from_usize(stack.top() - 8));
+        assert!(current.lock().page_table().walk(top).is_ok());
+
+        // 释放后槽位被重用，所有者被清除
+        let slot = stack.slot;
+        drop(stack);
+        assert!(KSTACK_OWNERS[slot].load(Ordering::Relaxed) == 0);
+        let again = KernelStack::new().expect("failed to alloc kernel stack");
+        assert!(again.slot == slot);
+    }
+}
@@ -3,18 +3,21 @@ use core::cmp::Ordering;
 use crate::arch::mm::{paddr_to_vaddr, vaddr_to_paddr};
 use crate::config::{
     MAX_USER_HEAP_SIZE, MEMORY_END, PAGE_SIZE, USER_SIGRETURN_TRAMPOLINE, USER_STACK_SIZE,
-    USER_STACK_TOP,
+    USER_STACK_TOP, USER_VDSO_BASE, USER_VVAR_BASE,
 };
 use mm::address::{Paddr, PageNum, Ppn, UsizeConvert, Vaddr, Vpn, VpnRange};
 // 从 mm crate 导入类型
 use crate::arch::mm::PageTableInner as ActivePageTableInner;
+use crate::mm::userfaultfd::{UserfaultCtx, UserfaultRange};
 use crate::sync::SpinLock;
 use crate::{pr_err, pr_warn};
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
 use alloc::vec::Vec;
 use lazy_static::lazy_static;
+use mm::frame_allocator::FrameTracker;
 use mm::memory_space::{AreaType, MapType, MappingArea, MmapFile};
-use mm::page_table::{PageTableInner, PagingError, UniversalPTEFlag};
+use mm::page_table::{PageSize, PageTableInner, PagingError, UniversalPTEFlag};
+use uapi::mm::{MlockFlags, MlockallFlags, MremapFlags};
 
 // 内核链接器符号
 unsafe extern "C" {
@@ -73,15 +76,31 @@ pub struct MemorySpace {
     /// 堆的起始地址 (brk 系统调用使用，仅限用户空间)
     /// 注意：这是堆的固定起始位置，真正的堆顶（current brk）存储在 UserHeap 区域的 vpn_range.end 中
     heap_start: Option<Vpn>,
+
+    /// 登记到 userfaultfd 的范围，fork 时不继承
+    userfault: Vec<UserfaultRange>,
+
+    /// mlock 锁定的范围（互不重叠），其中的页不会被换出，fork 时不继承
+    mlocked: Vec<VpnRange>,
+
+    /// mlockall(MCL_FUTURE) 之后新建的映射按此标志锁定，fork 时不继承
+    mlock_future: Option<MlockFlags>,
 }
 
 impl MemorySpace {
     /// 创建一个新的空内存空间
+    ///
+    /// 内核栈区与所有地址空间共享，不属于任何映射区域。
     pub fn new() -> Self {
+        let page_table = ActivePageTableInner::new();
+        super::kstack::link_kstack_area(&page_table);
         MemorySpace {
-            page_table: ActivePageTableInner::new(),
+            page_table,
             areas: Vec::new(),
             heap_start: None,
+            userfault: Vec::new(),
+            mlocked: Vec::new(),
+            mlock_future: None,
         }
     }
 
@@ -153,6 +172,7 @@ impl MemorySpace {
 
         // Userspace rt_sigreturn trampoline (Linux ABI).
         space.map_user_sigreturn_trampoline()?;
+        space.map_user_vdso()?;
 
         Ok(space)
     }
@@ -195,6 +215,46 @@ impl MemorySpace {
         Ok(())
     }
 
+    /// 映射 vDSO 数据页与代码页
+    ///
+    /// 两者都是内核持有的物理页，所有进程共享同一份，fork 时不复制。
+    fn map_user_vdso(&mut self) -> Result<(), PagingError> {
+        let (image_ppn, image_pages) = crate::kernel::vdso::image_pages();
+        let vvar_start = Vpn::from_addr_floor(Vaddr::from_usize(USER_VVAR_BASE));
+        let vdso_start = Vpn::from_addr_floor(Vaddr::from_usize(USER_VDSO_BASE));
+        let vvar_range = VpnRange::new(vvar_start, vdso_start);
+        let vdso_range = VpnRange::new(
+            vdso_start,
+            Vpn::from_usize(vdso_start.as_usize() + image_pages),
+        );
+
+        // If already mapped (layout differences), don't fail hard.
+        if self
+            .areas
+            .iter()
+            .any(|a| a.vpn_range().overlaps(&vvar_range) || a.vpn_range().overlaps(&vdso_range))
+        {
+            return Ok(());
+        }
+
+        self.insert_area(MappingArea::new(
+            vvar_range,
+            AreaType::UserVvar,
+            MapType::Fixed(crate::kernel::vdso::data_ppn()),
+            UniversalPTEFlag::user_read(),
+            None,
+        ))?;
+        self.insert_area(MappingArea::new(
+            vdso_range,
+            AreaType::UserVdso,
+            MapType::Fixed(image_ppn),
+            UniversalPTEFlag::user_rx(),
+            None,
+        ))?;
+
+        Ok(())
+    }
+
     /// 从当前地址空间中向指定虚拟地址写入字节序列（跨页安全）。
     pub fn write_bytes_at(&mut self, va: usize, bytes: &[u8]) -> Result<(), PagingError> {
         if bytes.is_empty() {
@@ -204,6 +264,10 @@ impl MemorySpace {
         let mut written = 0usize;
         while written < bytes.len() {
             let cur_va = va.checked_add(written).ok_or(PagingError::InvalidAddress)?;
+            let vpn = Vpn::from_addr_floor(Vaddr::from_usize(cur_va));
+            self.fault_in_page(vpn)?;
+            // 直接写物理帧绕过了页表的写保护，写时复制共享的页须先变为独占
+            self.unshare_page(vpn)?;
             let paddr = self
                 .page_table
                 .translate(Vaddr::from_usize(cur_va))
@@ -223,8 +287,8 @@ impl MemorySpace {
         Ok(())
     }
 
-    /// 从指定虚拟地址读取字节序列（跨页安全）。
-    pub fn read_bytes_at(&self, va: usize, out: &mut [u8]) -> Result<(), PagingError> {
+    /// 从指定虚拟地址读取字节序列（跨页安全），换出或被丢弃的页会先重新建立映射。
+    pub fn read_bytes_at(&mut self, va: usize, out: &mut [u8]) -> Result<(), PagingError> {
         if out.is_empty() {
             return Ok(());
         }
@@ -232,6 +296,7 @@ impl MemorySpace {
         let mut read = 0usize;
         while read < out.len() {
             let cur_va = va.checked_add(read).ok_or(PagingError::InvalidAddress)?;
+            self.fault_in_page(Vpn::from_addr_floor(Vaddr::from_usize(cur_va)))?;
             let paddr = self
                 .page_table
                 .translate(Vaddr::from_usize(cur_va))
@@ -250,13 +315,13 @@ impl MemorySpace {
         Ok(())
     }
 
-    pub fn read_u64_at(&self, va: usize) -> Result<u64, PagingError> {
+    pub fn read_u64_at(&mut self, va: usize) -> Result<u64, PagingError> {
         let mut buf = [0u8; 8];
         self.read_bytes_at(va, &mut buf)?;
         Ok(u64::from_le_bytes(buf))
     }
 
-    pub fn read_i64_at(&self, va: usize) -> Result<i64, PagingError> {
+    pub fn read_i64_at(&mut self, va: usize) -> Result<i64, PagingError> {
         let mut buf = [0u8; 8];
         self.read_bytes_at(va, &mut buf)?;
         Ok(i64::from_le_bytes(buf))
@@ -276,7 +341,7 @@ impl MemorySpace {
     /// - `include_trampoline`: 是否包含带有内核权限 (U=0) 的跳板页映射
     ///
     /// # 映射内容
-    /// 所有映射都使用 **直接映射** (VA = PA + VADDR_START) 且设置 **U=0** 标志:
+    /// 所有映射都使用 **直接映射** (VA = PA + VADDR_START + KASLR 偏移) 且设置 **U=0** 标志:
     /// - 跳板页（可选）：R+X，直接映射
     /// - 内核 .text 段：R+X，直接映射
     /// - 内核 .rodata 段：R，直接映射
@@ -474,6 +539,27 @@ impl MemorySpace {
         Ok(())
     }
 
+    /// 插入一个从大页池取页的匿名映射区域（mmap MAP_HUGETLB），`vpn_range` 的两端必须按 2M 对齐
+    ///
+    /// 池中的空闲大页不足时返回 [`PagingError::FrameAllocFailed`]，已取得的大页放回池中。
+    pub fn insert_hugetlb_area(
+        &mut self,
+        vpn_range: VpnRange,
+        flags: UniversalPTEFlag,
+    ) -> Result<(), PagingError> {
+        if self.areas.iter().any(|a| a.vpn_range().overlaps(&vpn_range)) {
+            return Err(PagingError::AlreadyMapped);
+        }
+        let mut area = MappingArea::new_hugetlb(vpn_range, flags);
+        if let Err(e) = area.map(&mut self.page_table) {
+            // 先解除已建立的大页映射，区域丢弃时大页才能安全地回到池中
+            let _ = area.unmap(&mut self.page_table);
+            return Err(e);
+        }
+        self.areas.push(area);
+        Ok(())
+    }
+
     /// 插入一个“保留”区域（不建立页表映射）
     ///
     /// 用于 mmap(PROT_NONE) / guard page 场景：需要占位并参与重叠检查，
@@ -893,6 +979,7 @@ impl MemorySpace {
 
         // Userspace rt_sigreturn trampoline (Linux ABI).
         space.map_user_sigreturn_trampoline()?;
+        space.map_user_vdso()?;
 
         let entry_point = load_bias + elf.header.pt2.entry_point() as usize;
         let ph_off = elf.header.pt2.ph_offset() as usize;
@@ -979,7 +1066,11 @@ impl MemorySpace {
                     }
                 }
                 Ordering::Less => {
-                    // 收缩
+                    // 收缩，释放的页不再锁定
+                    self.munlock_range(VpnRange::new(
+                        core::cmp::max(new_end_vpn, heap_bottom),
+                        old_end,
+                    ));
                     if new_end_vpn <= heap_bottom {
                         // 收缩到起始位置或更低，删除整个堆区域
                         let mut area = self.areas.remove(idx);
@@ -1221,6 +1312,9 @@ impl MemorySpace {
         let start_vpn = Vpn::from_addr_floor(Vaddr::from_usize(start));
         let end_vpn = Vpn::from_addr_ceil(Vaddr::from_usize(start + len));
         let unmap_range = VpnRange::new(start_vpn, end_vpn);
+        self.check_hugetlb_aligned(unmap_range)?;
+        self.unregister_userfault(unmap_range);
+        self.munlock_range(unmap_range);
 
         // 收集需要处理的区域
         // 注意：不能在迭代时修改 self.areas，所以先收集索引
@@ -1245,8 +1339,8 @@ impl MemorySpace {
             // 移除原区域
             let area = self.areas.remove(idx);
 
-            // 只处理 Framed / Reserved，Direct 映射不应该被 munmap
-            if area.map_type() == MapType::Direct {
+            // 只处理 Framed / Reserved，Direct / Fixed 映射不应该被 munmap
+            if matches!(area.map_type(), MapType::Direct | MapType::Fixed(_)) {
                 // 重新插入原区域
                 self.areas.insert(idx, area);
                 continue;
@@ -1315,6 +1409,7 @@ impl MemorySpace {
         let start_vpn = Vpn::from_addr_floor(Vaddr::from_usize(start));
         let end_vpn = Vpn::from_addr_ceil(Vaddr::from_usize(start + len));
         let change_range = VpnRange::new(start_vpn, end_vpn);
+        self.check_hugetlb_aligned(change_range)?;
 
         // 收集需要处理的区域
         // 注意：不能在迭代时修改 self.areas，所以先收集索引
@@ -1325,7 +1420,9 @@ impl MemorySpace {
                 // 只处理 Framed / Reserved，Direct 映射不允许修改权限
                 match area.map_type() {
                     MapType::Framed | MapType::Reserved => affected_indices.push(idx),
-                    MapType::Direct => return Err(PagingError::UnsupportedMapType),
+                    MapType::Direct | MapType::Fixed(_) => {
+                        return Err(PagingError::UnsupportedMapType);
+                    }
                 }
             }
         }
@@ -1368,26 +1465,472 @@ impl MemorySpace {
         Ok(())
     }
 
+    /// 重新映射内存区域（mremap 系统调用）
+    ///
+    /// # 参数
+    /// - `old_addr`: 原映射的起始地址，必须页对齐
+    /// - `old_size`: 原映射的长度（字节）
+    /// - `new_size`: 新的长度（字节）
+    /// - `flags`: MREMAP_MAYMOVE / MREMAP_FIXED
+    /// - `new_addr`: MREMAP_FIXED 时的目标地址
+    ///
+    /// # 返回值
+    /// - 成功: 返回映射新的起始地址
+    /// - 失败: 返回 PagingError
+    ///
+    /// # 注意
+    /// - 原范围必须落在同一个 Framed/Reserved 区域内，只覆盖区域一部分时先把它拆分出来
+    /// - 缩小时解除尾部的映射；扩大时优先原地扩展，其后的地址已被占用且允许移动时整体移动
+    /// - 移动只搬移页表项，物理页中的数据不复制
+    /// - 原范围的 userfaultfd 登记与锁定随之取消，需要时由调用者重新锁定新范围
+    /// - 大页池的区域不能重新映射
+    pub fn mremap(
+        &mut self,
+        old_addr: usize,
+        old_size: usize,
+        new_size: usize,
+        flags: MremapFlags,
+        new_addr: usize,
+    ) -> Result<usize, PagingError> {
+        // 参数验证（old_size 为 0 时复制共享映射的用法不支持）
+        if old_addr % PAGE_SIZE != 0 || old_size == 0 || new_size == 0 {
+            return Err(PagingError::InvalidAddress);
+        }
+        if flags.contains(MremapFlags::DONTUNMAP) {
+            return Err(PagingError::UnsupportedMapType);
+        }
+        let fixed = flags.contains(MremapFlags::FIXED);
+        if fixed && (!flags.contains(MremapFlags::MAYMOVE) || new_addr % PAGE_SIZE != 0) {
+            return Err(PagingError::InvalidAddress);
+        }
+
+        let old_start = Vpn::from_addr_floor(Vaddr::from_usize(old_addr));
+        let old_range = VpnRange::new(
+            old_start,
+            Vpn::from_addr_ceil(Vaddr::from_usize(old_addr + old_size)),
+        );
+        let old_pages = old_range.len();
+        let new_pages = new_size.div_ceil(PAGE_SIZE);
+        if self
+            .areas
+            .iter()
+            .any(|a| a.is_hugetlb() && a.vpn_range().overlaps(&old_range))
+        {
+            return Err(PagingError::InvalidAddress);
+        }
+        self.unregister_userfault(old_range);
+        self.munlock_range(old_range);
+
+        if fixed {
+            let new_start = Vpn::from_addr_floor(Vaddr::from_usize(new_addr));
+            let new_range =
+                VpnRange::new(new_start, Vpn::from_usize(new_start.as_usize() + new_pages));
+            if new_range.overlaps(&old_range) {
+                return Err(PagingError::InvalidAddress);
+            }
+            // 目标范围原有的映射被替换
+            self.munmap(new_addr, new_pages * PAGE_SIZE)?;
+            let idx = self.isolate_area(old_range)?;
+            if new_pages < old_pages {
+                self.areas[idx].shrink(&mut self.page_table, old_pages - new_pages)?;
+            }
+            self.areas[idx].move_to(&mut self.page_table, new_start)?;
+            if new_pages > old_pages {
+                self.areas[idx].extend(&mut self.page_table, new_pages - old_pages)?;
+            }
+            return Ok(new_addr);
+        }
+
+        let idx = self.isolate_area(old_range)?;
+        if new_pages <= old_pages {
+            if new_pages < old_pages {
+                self.areas[idx].shrink(&mut self.page_table, old_pages - new_pages)?;
+            }
+            return Ok(old_addr);
+        }
+
+        // 扩大：其后的地址空闲时原地扩展
+        let grow_range = VpnRange::new(
+            old_range.end(),
+            Vpn::from_usize(old_start.as_usize() + new_pages),
+        );
+        if !self
+            .areas
+            .iter()
+            .any(|a| a.vpn_range().overlaps(&grow_range))
+        {
+            self.areas[idx].extend(&mut self.page_table, new_pages - old_pages)?;
+            return Ok(old_addr);
+        }
+        if !flags.contains(MremapFlags::MAYMOVE) {
+            return Err(PagingError::OutOfMemory);
+        }
+
+        // 移动到新的空闲地址（原区域仍在 areas 中，不会与之重叠）
+        let new_addr = self
+            .find_free_region(new_pages * PAGE_SIZE, PAGE_SIZE)
+            .ok_or(PagingError::OutOfMemory)?;
+        let new_start = Vpn::from_addr_floor(Vaddr::from_usize(new_addr));
+        self.areas[idx].move_to(&mut self.page_table, new_start)?;
+        self.areas[idx].extend(&mut self.page_table, new_pages - old_pages)?;
+        Ok(new_addr)
+    }
+
+    /// 把包含 `range` 的区域拆分，使 `range` 单独成为一个区域，返回它在 `areas` 中的下标
+    ///
+    /// `range` 必须落在同一个 Framed/Reserved 区域内。
+    fn isolate_area(&mut self, range: VpnRange) -> Result<usize, PagingError> {
+        let mut idx = self
+            .areas
+            .iter()
+            .position(|a| a.vpn_range().contains(range.start()))
+            .ok_or(PagingError::NotMapped)?;
+        let area_range = self.areas[idx].vpn_range();
+        if range.end() > area_range.end() {
+            return Err(PagingError::NotMapped);
+        }
+        if !matches!(
+            self.areas[idx].map_type(),
+            MapType::Framed | MapType::Reserved
+        ) {
+            return Err(PagingError::UnsupportedMapType);
+        }
+
+        let mut area = self.areas.remove(idx);
+        if range.start() > area_range.start() {
+            let (left, right) = area.split_at(&mut self.page_table, range.start())?;
+            self.areas.insert(idx, left);
+            idx += 1;
+            area = right;
+        }
+        if range.end() < area_range.end() {
+            let (left, right) = area.split_at(&mut self.page_table, range.end())?;
+            self.areas.insert(idx, right);
+            area = left;
+        }
+        self.areas.insert(idx, area);
+        Ok(idx)
+    }
+
+    /// 丢弃 `[start, start+len)` 内的页（madvise MADV_DONTNEED）
+    ///
+    /// 之后访问时匿名页重新填零，文件映射重新读入文件内容。
+    ///
+    /// # 注意
+    /// - 范围内有未映射的地址时返回 [`PagingError::NotMapped`]，不做任何修改
+    /// - 直接映射与固定映射不能丢弃
+    /// - 范围内有锁定的页、或两端落在大页池区域的大页内部时返回 [`PagingError::InvalidAddress`]
+    pub fn madvise_dontneed(&mut self, start: usize, len: usize) -> Result<(), PagingError> {
+        self.check_not_mlocked(start, len)?;
+        let end = start.checked_add(len).ok_or(PagingError::InvalidAddress)?;
+        self.check_hugetlb_aligned(VpnRange::new(
+            Vpn::from_addr_floor(Vaddr::from_usize(start)),
+            Vpn::from_addr_ceil(Vaddr::from_usize(end)),
+        ))?;
+        self.for_each_area_in(start, len, |area, page_table, start, end| {
+            area.discard_range(page_table, start, end)
+        })
+    }
+
+    /// 预先建立 `[start, start+len)` 内的映射（madvise MADV_WILLNEED）
+    ///
+    /// 换出的页换入，被丢弃的页重新填充或从文件读入。
+    pub fn madvise_willneed(&mut self, start: usize, len: usize) -> Result<(), PagingError> {
+        self.for_each_area_in(start, len, |area, page_table, start, end| {
+            area.populate_range(page_table, start, end)
+        })
+    }
+
+    /// 把 `[start, start+len)` 内的页标记为可丢弃（madvise MADV_FREE）
+    ///
+    /// # 返回值
+    /// 被标记的页，调用者把它们交给回收（见 [`lazyfree_add_pages`](crate::mm::swap::lazyfree_add_pages)）
+    ///
+    /// # 注意
+    /// - 只适用于私有匿名映射，范围内有其它映射时返回 [`PagingError::UnsupportedMapType`]
+    /// - 范围内有锁定的页时返回 [`PagingError::InvalidAddress`]
+    pub fn madvise_free(&mut self, start: usize, len: usize) -> Result<Vec<Vpn>, PagingError> {
+        self.check_not_mlocked(start, len)?;
+        let mut marked = Vec::new();
+        self.for_each_area_in(start, len, |area, page_table, start, end| {
+            marked.extend(area.lazy_free_range(page_table, start, end)?);
+            Ok(())
+        })?;
+        Ok(marked)
+    }
+
+    /// 设置 `[start, start+len)` 是否由 KSM 合并相同的页（madvise MADV_MERGEABLE / UNMERGEABLE）
+    ///
+    /// 部分覆盖的区域先拆分；取消标记时已合并的页重新变为独占，需要分配新帧。
+    ///
+    /// # 注意
+    /// - 范围内有未映射的地址时返回 [`PagingError::NotMapped`]，不做任何修改
+    /// - 直接映射与固定映射不能标记，返回 [`PagingError::UnsupportedMapType`]
+    pub fn madvise_mergeable(
+        &mut self,
+        start: usize,
+        len: usize,
+        mergeable: bool,
+    ) -> Result<(), PagingError> {
+        // 先检查范围被完全覆盖，再逐个拆出范围内的部分
+        let mut pieces = Vec::new();
+        self.for_each_area_in(start, len, |_, _, start, end| {
+            pieces.push(VpnRange::new(start, end));
+            Ok(())
+        })?;
+        for piece in pieces {
+            let idx = self.isolate_area(piece)?;
+            let area = &mut self.areas[idx];
+            if !mergeable {
+                area.ksm_unmerge_range(&mut self.page_table, piece.start(), piece.end())?;
+            }
+            area.set_mergeable(mergeable);
+        }
+        Ok(())
+    }
+
+    /// KSM 扫描从 `from` 起可合并区域中的页，最多扫描 `budget` 页
+    ///
+    /// 区域按地址顺序扫描，扫描的页数从 `budget` 中扣除。
+    ///
+    /// # 返回值
+    /// 预算用完时返回下次继续扫描的位置；本地址空间已扫描完时返回 `None`
+    pub fn ksm_scan(&mut self, from: Vpn, budget: &mut usize) -> Option<Vpn> {
+        let mut order: Vec<usize> = (0..self.areas.len())
+            .filter(|&i| self.areas[i].is_mergeable() && self.areas[i].vpn_range().end() > from)
+            .collect();
+        order.sort_by_key(|&i| self.areas[i].vpn_range().start());
+        for idx in order {
+            let area = &mut self.areas[idx];
+            let range = area.vpn_range();
+            let start = core::cmp::max(range.start(), from);
+            for vpn in VpnRange::new(start, range.end()) {
+                if *budget == 0 {
+                    return Some(vpn);
+                }
+                *budget -= 1;
+                area.ksm_scan_page(&mut self.page_table, vpn);
+            }
+        }
+        None
+    }
+
+    /// 检查 `range` 的两端没有落在大页池区域的某个 2M 大页内部（大页池的页不能拆分）
+    fn check_hugetlb_aligned(&self, range: VpnRange) -> Result<(), PagingError> {
+        let huge_pages = PageSize::Size2M.pages();
+        for area in self.areas.iter().filter(|a| a.is_hugetlb()) {
+            for vpn in [range.start(), range.end()] {
+                if area.vpn_range().contains(vpn) && vpn.as_usize() % huge_pages != 0 {
+                    return Err(PagingError::InvalidAddress);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 对 `[start, start+len)` 覆盖的每个区域调用 `f`，传入截取到该区域内的页号范围
+    ///
+    /// 范围必须完全被区域覆盖，否则返回 [`PagingError::NotMapped`]，不调用 `f`。
+    fn for_each_area_in<F>(&mut self, start: usize, len: usize, mut f: F) -> Result<(), PagingError>
+    where
+        F: FnMut(&mut MappingArea, &mut ActivePageTableInner, Vpn, Vpn) -> Result<(), PagingError>,
+    {
+        let end = start.checked_add(len).ok_or(PagingError::InvalidAddress)?;
+        let range = VpnRange::new(
+            Vpn::from_addr_floor(Vaddr::from_usize(start)),
+            Vpn::from_addr_ceil(Vaddr::from_usize(end)),
+        );
+
+        // 区域互不重叠，重叠部分的页数之和等于范围页数即完全覆盖
+        let clip = |area: &MappingArea| {
+            let area_range = area.vpn_range();
+            let start = core::cmp::max(area_range.start(), range.start());
+            let end = core::cmp::min(area_range.end(), range.end());
+            (start < end).then_some((start, end))
+        };
+        let covered: usize = self
+            .areas
+            .iter()
+            .filter_map(clip)
+            .map(|(start, end)| end.as_usize() - start.as_usize())
+            .sum();
+        if covered != range.len() {
+            return Err(PagingError::NotMapped);
+        }
+
+        for area in self.areas.iter_mut() {
+            if let Some((start, end)) = clip(area) {
+                f(area, &mut self.page_table, start, end)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 锁定 `[start, start+len)` 内的页（mlock），锁定的页不会被换出
+    ///
+    /// `populate` 为真时预先建立范围内的映射，否则页在第一次访问时建立（MLOCK_ONFAULT）。
+    ///
+    /// # 返回值
+    /// - `Err(PagingError::NotMapped)`: 范围内有未映射的地址，不做任何修改
+    pub fn mlock(&mut self, start: usize, len: usize, populate: bool) -> Result<(), PagingError> {
+        let range = Self::page_range(start, len)?;
+        self.for_each_area_in(start, len, |area, page_table, start, end| {
+            if populate {
+                area.populate_range(page_table, start, end)
+            } else {
+                Ok(())
+            }
+        })?;
+        self.munlock_range(range);
+        self.mlocked.push(range);
+        Ok(())
+    }
+
+    /// 解除 `[start, start+len)` 内的锁定（munlock）
+    ///
+    /// # 返回值
+    /// - `Err(PagingError::NotMapped)`: 范围内有未映射的地址，不做任何修改
+    pub fn munlock(&mut self, start: usize, len: usize) -> Result<(), PagingError> {
+        let range = Self::page_range(start, len)?;
+        self.for_each_area_in(start, len, |_, _, _, _| Ok(()))?;
+        self.munlock_range(range);
+        Ok(())
+    }
+
+    /// 锁定所有用户映射（mlockall）
+    ///
+    /// - `MCL_CURRENT`: 锁定当前的用户映射（vDSO 除外）
+    /// - `MCL_FUTURE`: 之后新建的映射由调用者按 [`mlock_future`](Self::mlock_future) 锁定
+    /// - `MCL_ONFAULT`: 不预先建立映射
+    pub fn mlockall(&mut self, flags: MlockallFlags) -> Result<(), PagingError> {
+        let onfault = flags.contains(MlockallFlags::ONFAULT);
+        if flags.contains(MlockallFlags::CURRENT) {
+            for range in self.lockable_ranges() {
+                let start = range.start().start_addr().as_usize();
+                self.mlock(start, range.len() * PAGE_SIZE, !onfault)?;
+            }
+        }
+        self.mlock_future = flags.contains(MlockallFlags::FUTURE).then(|| {
+            if onfault {
+                MlockFlags::ONFAULT
+            } else {
+                MlockFlags::empty()
+            }
+        });
+        Ok(())
+    }
+
+    /// 解除所有锁定并取消 `MCL_FUTURE`（munlockall），返回原来锁定的范围
+    pub fn munlockall(&mut self) -> Vec<VpnRange> {
+        self.mlock_future = None;
+        core::mem::take(&mut self.mlocked)
+    }
+
+    /// mlockall(MCL_FUTURE) 之后新建映射的锁定标志，未设置时为 `None`
+    pub fn mlock_future(&self) -> Option<MlockFlags> {
+        self.mlock_future
+    }
+
+    /// 锁定的总页数
+    pub fn locked_pages(&self) -> usize {
+        self.mlocked.iter().map(|r| r.len()).sum()
+    }
+
+    /// `range` 内已锁定的页数
+    pub fn locked_pages_in(&self, range: VpnRange) -> usize {
+        self.mlocked
+            .iter()
+            .map(|r| {
+                let start = core::cmp::max(r.start(), range.start());
+                let end = core::cmp::min(r.end(), range.end());
+                end.as_usize().saturating_sub(start.as_usize())
+            })
+            .sum()
+    }
+
+    /// `vpn` 处的页是否被锁定
+    pub fn is_mlocked(&self, vpn: Vpn) -> bool {
+        self.mlocked.iter().any(|r| r.contains(vpn))
+    }
+
+    /// 可被 mlockall(MCL_CURRENT) 锁定的用户映射范围
+    pub fn lockable_ranges(&self) -> Vec<VpnRange> {
+        self.areas
+            .iter()
+            .filter(|area| {
+                matches!(
+                    area.area_type(),
+                    AreaType::UserText
+                        | AreaType::UserRodata
+                        | AreaType::UserData
+                        | AreaType::UserBss
+                        | AreaType::UserStack
+                        | AreaType::UserHeap
+                        | AreaType::UserMmap
+                )
+            })
+            .map(|area| area.vpn_range())
+            .collect()
+    }
+
+    /// 取消 `range` 内的锁定，部分重叠的锁定范围被截短或拆成两段
+    fn munlock_range(&mut self, range: VpnRange) {
+        let mut kept = Vec::with_capacity(self.mlocked.len());
+        for r in self.mlocked.drain(..) {
+            if !r.overlaps(&range) {
+                kept.push(r);
+                continue;
+            }
+            if r.start() < range.start() {
+                kept.push(VpnRange::new(r.start(), range.start()));
+            }
+            if range.end() < r.end() {
+                kept.push(VpnRange::new(range.end(), r.end()));
+            }
+        }
+        self.mlocked = kept;
+    }
+
+    /// `[start, start+len)` 内有锁定的页时返回 [`PagingError::InvalidAddress`]
+    fn check_not_mlocked(&self, start: usize, len: usize) -> Result<(), PagingError> {
+        if self.locked_pages_in(Self::page_range(start, len)?) != 0 {
+            return Err(PagingError::InvalidAddress);
+        }
+        Ok(())
+    }
+
+    /// `[start, start+len)` 覆盖的页号范围
+    fn page_range(start: usize, len: usize) -> Result<VpnRange, PagingError> {
+        let end = start.checked_add(len).ok_or(PagingError::InvalidAddress)?;
+        Ok(VpnRange::new(
+            Vpn::from_addr_floor(Vaddr::from_usize(start)),
+            Vpn::from_addr_ceil(Vaddr::from_usize(end)),
+        ))
+    }
+
     /// 克隆内存空间（用于 fork 系统调用）
     ///
     /// # 注意
-    /// - 直接映射是共享的（不复制）
-    /// - 帧映射是深层复制的
-    pub fn clone_for_fork(&self) -> Result<Self, PagingError> {
+    /// - 直接映射与固定映射（vDSO）是共享的（不复制）
+    /// - 帧映射以写时复制方式共享物理帧，父子双方的私有可写页都变为只读，
+    ///   写缺页时由 [`handle_cow_fault`](Self::handle_cow_fault) 复制
+    /// - 子进程不继承 mlock 锁定与 mlockall(MCL_FUTURE)
+    pub fn clone_for_fork(&mut self) -> Result<Self, PagingError> {
         let mut new_space = MemorySpace::new();
         new_space.heap_start = self.heap_start;
 
-        for area in self.areas.iter() {
+        for area in self.areas.iter_mut() {
             match area.map_type() {
-                MapType::Direct => {
-                    // 直接映射：克隆元数据并重新映射到新的页表
+                MapType::Direct | MapType::Fixed(_) => {
+                    // 直接映射 / 固定映射：克隆元数据并重新映射到新的页表
                     let mut new_area = area.clone_metadata();
                     new_area.map(&mut new_space.page_table)?;
                     new_space.areas.push(new_area);
                 }
                 MapType::Framed => {
-                    // 帧映射：深层复制数据
-                    let new_area = area.clone_with_data(&mut new_space.page_table)?;
+                    // 帧映射：写时复制（共享文件映射仍深层复制）
+                    let new_area =
+                        area.clone_cow(&mut self.page_table, &mut new_space.page_table)?;
                     new_space.areas.push(new_area);
                 }
                 MapType::Reserved => {
@@ -1401,6 +1944,269 @@ impl MemorySpace {
         Ok(new_space)
     }
 
+    /// 为包含 `vpn` 的文件映射区域加载文件数据（共享映射可能直接映射页缓存）
+    pub fn load_area_from_file(&mut self, vpn: Vpn) -> Result<(), PagingError> {
+        match self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            Some(area) => area.load_from_file(&mut self.page_table),
+            None => Ok(()),
+        }
+    }
+
+    /// 处理 `vaddr` 处的写缺页
+    ///
+    /// # 返回值
+    /// 缺页由写时复制引起并已解决时返回 `true`，重新执行出错指令即可；
+    /// 否则是真正的访问违例，返回 `false`
+    pub fn handle_cow_fault(&mut self, vaddr: Vaddr) -> bool {
+        let vpn = Vpn::from_addr_floor(vaddr);
+        let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        else {
+            return false;
+        };
+        match area.handle_cow_fault(&mut self.page_table, vpn) {
+            Ok(handled) => handled,
+            Err(e) => {
+                pr_warn!("handle_cow_fault: {:#x}: {:?}", vaddr.as_usize(), e);
+                false
+            }
+        }
+    }
+
+    /// 处理访问换出页引起的缺页：把 `vaddr` 所在的页换入
+    ///
+    /// # 返回值
+    /// 该页在交换区中并已换入时返回 `true`，重新执行出错指令即可
+    pub fn handle_swap_fault(&mut self, vaddr: Vaddr) -> bool {
+        let vpn = Vpn::from_addr_floor(vaddr);
+        if self.page_table.swap_entry(vpn).is_none() {
+            return false;
+        }
+        let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        else {
+            return false;
+        };
+        match area.swap_in_page(&mut self.page_table, vpn) {
+            Ok(swapped) => swapped,
+            Err(e) => {
+                pr_warn!("handle_swap_fault: {:#x}: {:?}", vaddr.as_usize(), e);
+                false
+            }
+        }
+    }
+
+    /// 处理访问 `vaddr` 处没有页表项的页引起的缺页：重新填充被 madvise 丢弃或回收的页
+    ///
+    /// # 返回值
+    /// 缺页已解决时返回 `true`，重新执行出错指令即可
+    pub fn handle_demand_fault(&mut self, vaddr: Vaddr) -> bool {
+        let vpn = Vpn::from_addr_floor(vaddr);
+        let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        else {
+            return false;
+        };
+        match area.handle_demand_fault(&mut self.page_table, vpn) {
+            Ok(handled) => handled,
+            Err(e) => {
+                pr_warn!("handle_demand_fault: {:#x}: {:?}", vaddr.as_usize(), e);
+                false
+            }
+        }
+    }
+
+    /// 尝试把 `vpn` 处的页换出到交换区，锁定的页从 LRU 中移除
+    pub fn swap_out_page(&mut self, vpn: Vpn) -> mm::ReclaimResult {
+        if self.is_mlocked(vpn) {
+            return mm::ReclaimResult::Gone;
+        }
+        match self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            Some(area) => area.swap_out_page(&mut self.page_table, vpn),
+            None => mm::ReclaimResult::Gone,
+        }
+    }
+
+    /// 把 `vpn` 处的物理页 `old` 迁移到 `new`（内存规整使用）
+    ///
+    /// 锁定的页同样可以迁移：mlock 只保证页常驻，不保证物理地址不变。
+    ///
+    /// # 返回值
+    /// 迁移成功时返回 `true`；`vpn` 不在可换出的区域内或已不映射 `old` 时返回 `false`
+    pub fn migrate_page(&mut self, vpn: Vpn, old: Ppn, new: FrameTracker) -> bool {
+        match self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            Some(area) => area.migrate_page(&mut self.page_table, vpn, old, new),
+            None => false,
+        }
+    }
+
+    /// 把 `vpn` 处换出的页换入、被 madvise 丢弃的页重新填充，
+    /// `vpn` 不在任何区域内或已有映射时什么都不做
+    fn fault_in_page(&mut self, vpn: Vpn) -> Result<(), PagingError> {
+        if let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            if !area.swap_in_page(&mut self.page_table, vpn)? {
+                area.handle_demand_fault(&mut self.page_table, vpn)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 让 `vpn` 处的写时复制共享页变为独占，`vpn` 不在任何区域内时什么都不做
+    fn unshare_page(&mut self, vpn: Vpn) -> Result<(), PagingError> {
+        if let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+        {
+            area.unshare_page(&mut self.page_table, vpn)?;
+        }
+        Ok(())
+    }
+
+    /// 把 `range` 登记到 userfaultfd 上下文 `ctx`（UFFDIO_REGISTER 的 MISSING 模式）
+    ///
+    /// 范围必须完全被用户私有匿名映射覆盖。mmap 会立即为新映射分配并填零，
+    /// 所以登记时丢弃范围内内容全为零的页，之后第一次访问它们产生缺页事件。
+    /// 同一个上下文重复登记时覆盖原来的登记。
+    ///
+    /// # 返回值
+    /// - `Err(PagingError::NotMapped)`: 范围内有未映射的地址
+    /// - `Err(PagingError::UnsupportedMapType)`: 范围内有文件映射、共享映射或其它区域
+    /// - `Err(PagingError::AlreadyMapped)`: 范围已被另一个 userfaultfd 登记
+    pub fn register_userfault(
+        &mut self,
+        range: VpnRange,
+        ctx: &Arc<UserfaultCtx>,
+    ) -> Result<(), PagingError> {
+        let start = range.start().start_addr().as_usize();
+        let len = range.len() * PAGE_SIZE;
+        self.for_each_area_in(start, len, |area, _, _, _| {
+            let anonymous = area.map_type() == MapType::Framed
+                && area.area_type() == AreaType::UserMmap
+                && area.is_private()
+                && area.is_anonymous();
+            if anonymous {
+                Ok(())
+            } else {
+                Err(PagingError::UnsupportedMapType)
+            }
+        })?;
+
+        // 上下文关闭后留下的登记失效
+        self.userfault.retain(|r| r.ctx.strong_count() > 0);
+        let ctx_weak = Arc::downgrade(ctx);
+        if self
+            .userfault
+            .iter()
+            .any(|r| r.range.overlaps(&range) && !Weak::ptr_eq(&r.ctx, &ctx_weak))
+        {
+            return Err(PagingError::AlreadyMapped);
+        }
+        self.unregister_userfault(range);
+        self.userfault.push(UserfaultRange {
+            range,
+            ctx: ctx_weak,
+        });
+
+        for vpn in range {
+            if self.page_is_zero(vpn) {
+                let addr = vpn.start_addr().as_usize();
+                self.for_each_area_in(addr, PAGE_SIZE, |area, page_table, start, end| {
+                    area.discard_range(page_table, start, end)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 取消 `range` 内的 userfaultfd 登记，部分重叠的登记被截短或拆成两段
+    pub fn unregister_userfault(&mut self, range: VpnRange) {
+        let mut kept = Vec::with_capacity(self.userfault.len());
+        for r in self.userfault.drain(..) {
+            if !r.range.overlaps(&range) {
+                kept.push(r);
+                continue;
+            }
+            if r.range.start() < range.start() {
+                kept.push(UserfaultRange {
+                    range: VpnRange::new(r.range.start(), range.start()),
+                    ctx: r.ctx.clone(),
+                });
+            }
+            if range.end() < r.range.end() {
+                kept.push(UserfaultRange {
+                    range: VpnRange::new(range.end(), r.range.end()),
+                    ctx: r.ctx,
+                });
+            }
+        }
+        self.userfault = kept;
+    }
+
+    /// `vpn` 所在范围登记的 userfaultfd 上下文（上下文已关闭时为 `None`）
+    pub fn userfault_ctx(&self, vpn: Vpn) -> Option<Arc<UserfaultCtx>> {
+        self.userfault
+            .iter()
+            .find(|r| r.range.contains(vpn))
+            .and_then(|r| r.ctx.upgrade())
+    }
+
+    /// `vpn` 处是否缺页：没有页表项，也不在交换区中
+    pub fn page_is_missing(&self, vpn: Vpn) -> bool {
+        self.page_table.walk(vpn).is_err() && self.page_table.swap_entry(vpn).is_none()
+    }
+
+    /// 用 `data` 填充 `vpn` 处缺失的页，`data` 为 `None` 时填零（UFFDIO_COPY / UFFDIO_ZEROPAGE）
+    ///
+    /// # 返回值
+    /// - `Err(PagingError::AlreadyMapped)`: 该页已存在
+    /// - `Err(PagingError::NotMapped)`: `vpn` 不在任何区域内
+    pub fn userfault_fill(&mut self, vpn: Vpn, data: Option<&[u8]>) -> Result<(), PagingError> {
+        if !self.page_is_missing(vpn) {
+            return Err(PagingError::AlreadyMapped);
+        }
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range().contains(vpn))
+            .ok_or(PagingError::NotMapped)?;
+        area.fill_missing_page(&mut self.page_table, vpn, data)
+    }
+
+    /// `vpn` 处的页是否已映射且内容全为零
+    fn page_is_zero(&self, vpn: Vpn) -> bool {
+        let Some(paddr) = self.page_table.translate(vpn.start_addr()) else {
+            return false;
+        };
+        // SAFETY: 已映射页的物理帧在线性映射中，持有地址空间锁期间不会被释放
+        let page = unsafe {
+            core::slice::from_raw_parts(paddr_to_vaddr(paddr.as_usize()) as *const u8, PAGE_SIZE)
+        };
+        page.iter().all(|&b| b == 0)
+    }
+
     /// 进程手动映射MMIO区域
     pub fn map_mmio(&mut self, paddr: Paddr, size: usize) -> Result<Vaddr, PagingError> {
         // LoongArch uses DMW (direct mapping window) for MMIO (typically vseg=0x8). These
@@ -1529,6 +2335,7 @@ mod memory_space_tests {
     use crate::mm::address::{Vpn, VpnRange};
     use crate::mm::page_table::UniversalPTEFlag;
     use crate::println;
+    use mm::page_table::PageSize;
 
     // 1. 创建内存空间
     #[test_case]
@@ -2428,4 +3235,414 @@ mod memory_space_tests {
 
         println!("  mprotect partial single page test passed");
     }
+
+    // 28. 测试大的匿名映射使用 2M 大页，mprotect 部分修改时拆分大页
+    #[test_case]
+    fn test_huge_page_mmap_split() {
+        let mut ms = MemorySpace::new();
+
+        // 两个 2M 大页大小的区域，起始页按 2M 对齐
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x200000), Vpn::from_usize(0x200400));
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+
+        let (head_ppn, size, _) = ms.page_table().walk(Vpn::from_usize(0x200000)).unwrap();
+        assert!(size == PageSize::Size2M);
+        let (ppn, _, _) = ms.page_table().walk(Vpn::from_usize(0x200005)).unwrap();
+        assert!(ppn.as_usize() == head_ppn.as_usize() + 5);
+
+        // 修改第一个大页中的一页，只拆分该大页
+        let start = Vpn::from_usize(0x200005).start_addr().as_usize();
+        ms.mprotect(start, PAGE_SIZE, UniversalPTEFlag::user_read())
+            .unwrap();
+
+        let (split_ppn, size, flags) = ms.page_table().walk(Vpn::from_usize(0x200005)).unwrap();
+        assert!(size == PageSize::Size4K);
+        assert!(split_ppn == ppn);
+        assert!(!flags.contains(UniversalPTEFlag::WRITEABLE));
+        let (_, size, _) = ms.page_table().walk(Vpn::from_usize(0x200006)).unwrap();
+        assert!(size == PageSize::Size4K);
+        let (_, size, _) = ms.page_table().walk(Vpn::from_usize(0x200200)).unwrap();
+        assert!(size == PageSize::Size2M);
+
+        // 解除整个区域的映射
+        ms.munmap(vpn_range.start().start_addr().as_usize(), 0x400 * PAGE_SIZE)
+            .unwrap();
+        assert!(ms.page_table().walk(Vpn::from_usize(0x200200)).is_err());
+    }
+
+    // 29. 测试 mremap 原地扩大、移动和缩小
+    #[test_case]
+    fn test_mremap_grow_move_shrink() {
+        let mut ms = MemorySpace::new();
+        ms.set_heap_start(Vpn::from_usize(0x10000));
+
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x20000), Vpn::from_usize(0x20002));
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+        let addr = vpn_range.start().start_addr().as_usize();
+        ms.write_bytes_at(addr, b"mremap").unwrap();
+        let (ppn, _, _) = ms.page_table().walk(vpn_range.start()).unwrap();
+
+        // 其后空闲，原地扩大
+        let result = ms.mremap(addr, 2 * PAGE_SIZE, 4 * PAGE_SIZE, MremapFlags::empty(), 0);
+        assert!(result.unwrap() == addr);
+        assert!(ms.find_area(Vpn::from_usize(0x20003)).is_some());
+
+        // 其后被占用：不允许移动时失败，允许移动时搬移页表项
+        ms.insert_framed_area(
+            VpnRange::new(Vpn::from_usize(0x20005), Vpn::from_usize(0x20006)),
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(
+            ms.mremap(addr, 4 * PAGE_SIZE, 8 * PAGE_SIZE, MremapFlags::empty(), 0)
+                .is_err()
+        );
+        let new_addr = ms
+            .mremap(addr, 4 * PAGE_SIZE, 8 * PAGE_SIZE, MremapFlags::MAYMOVE, 0)
+            .unwrap();
+        assert!(new_addr != addr);
+        assert!(ms.page_table().walk(vpn_range.start()).is_err());
+        let new_vpn = Vpn::from_addr_floor(Vaddr::from_usize(new_addr));
+        let (moved_ppn, _, _) = ms.page_table().walk(new_vpn).unwrap();
+        assert!(moved_ppn == ppn);
+        let mut buf = [0u8; 6];
+        ms.read_bytes_at(new_addr, &mut buf).unwrap();
+        assert!(&buf == b"mremap");
+
+        // 缩小只解除尾部映射
+        let result = ms.mremap(new_addr, 8 * PAGE_SIZE, PAGE_SIZE, MremapFlags::empty(), 0);
+        assert!(result.unwrap() == new_addr);
+        assert!(ms.page_table().walk(new_vpn).is_ok());
+        let second = Vpn::from_usize(new_vpn.as_usize() + 1);
+        assert!(ms.page_table().walk(second).is_err());
+    }
+
+    // 30. 测试 madvise 丢弃、预填充和标记可丢弃
+    #[test_case]
+    fn test_madvise_dontneed_willneed_free() {
+        let mut ms = MemorySpace::new();
+
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x30000), Vpn::from_usize(0x30004));
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+        let addr = vpn_range.start().start_addr().as_usize();
+        ms.write_bytes_at(addr, b"madvise").unwrap();
+
+        // 范围内有未映射的地址时失败
+        assert!(ms.madvise_dontneed(addr, 8 * PAGE_SIZE).is_err());
+
+        // DONTNEED：解除映射，访问时重新填零
+        ms.madvise_dontneed(addr, 2 * PAGE_SIZE).unwrap();
+        assert!(ms.page_table().walk(vpn_range.start()).is_err());
+        assert!(ms.find_area(vpn_range.start()).unwrap().mapped_pages() == 2);
+        assert!(ms.handle_demand_fault(Vaddr::from_usize(addr)));
+        let mut buf = [0xffu8; 7];
+        ms.read_bytes_at(addr, &mut buf).unwrap();
+        assert!(buf == [0u8; 7]);
+
+        // WILLNEED：重新填充剩下被丢弃的页
+        let second = Vpn::from_usize(vpn_range.start().as_usize() + 1);
+        assert!(ms.page_table().walk(second).is_err());
+        ms.madvise_willneed(addr, 4 * PAGE_SIZE).unwrap();
+        assert!(ms.page_table().walk(second).is_ok());
+
+        // FREE：去掉写权限，写缺页后恢复
+        ms.write_bytes_at(addr, b"free").unwrap();
+        let marked = ms.madvise_free(addr, PAGE_SIZE).unwrap();
+        assert!(marked.len() == 1);
+        let (_, _, flags) = ms.page_table().walk(vpn_range.start()).unwrap();
+        assert!(!flags.contains(UniversalPTEFlag::WRITEABLE));
+        assert!(ms.handle_cow_fault(Vaddr::from_usize(addr)));
+        let (_, _, flags) = ms.page_table().walk(vpn_range.start()).unwrap();
+        assert!(flags.contains(UniversalPTEFlag::WRITEABLE));
+
+        // 回收可丢弃的页：直接释放，访问时填零
+        ms.madvise_free(addr, PAGE_SIZE).unwrap();
+        assert!(ms.swap_out_page(vpn_range.start()) == mm::ReclaimResult::Reclaimed);
+        assert!(ms.page_table().walk(vpn_range.start()).is_err());
+        ms.read_bytes_at(addr, &mut buf[..4]).unwrap();
+        assert!(buf[..4] == [0u8; 4]);
+    }
+
+    // 31. 测试反向映射：fork 后父子共享的页映射数为 2，写时复制后各自私有
+    #[test_case]
+    fn test_rmap_fork_cow() {
+        let mut ms = MemorySpace::new();
+
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x31000), Vpn::from_usize(0x31002));
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+        let vpn = vpn_range.start();
+        let addr = vpn.start_addr().as_usize();
+        ms.write_bytes_at(addr, b"rmap").unwrap();
+        let ppn = ms.find_area(vpn).unwrap().get_ppn(vpn).unwrap();
+        assert!(mm::rmap::page_mapcount(ppn) == 1);
+
+        let mut child = ms.clone_for_fork().expect("fork failed");
+        assert!(mm::rmap::page_mapcount(ppn) == 2);
+        let usage = ms.find_area(vpn).unwrap().usage(ms.page_table());
+        assert!(usage.rss == 2 * PAGE_SIZE && usage.pss == PAGE_SIZE);
+        assert!(usage.shared_clean + usage.shared_dirty == 2 * PAGE_SIZE);
+
+        // 子进程写入后得到自己的副本，父进程的页重新变为私有
+        assert!(child.handle_cow_fault(Vaddr::from_usize(addr)));
+        let child_ppn = child.find_area(vpn).unwrap().get_ppn(vpn).unwrap();
+        assert!(child_ppn != ppn);
+        assert!(mm::rmap::page_mapcount(ppn) == 1);
+        assert!(mm::rmap::page_mapcount(child_ppn) == 1);
+
+        drop(child);
+        assert!(mm::rmap::page_mapcount(child_ppn) == 0);
+    }
+
+    // 32. 测试 mlock：锁定的页不换出、不能丢弃，munmap 与 fork 不保留锁定
+    #[test_case]
+    fn test_mlock_munlock() {
+        let mut ms = MemorySpace::new();
+
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x34000), Vpn::from_usize(0x34004));
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+        let addr = vpn_range.start().start_addr().as_usize();
+        let second = Vpn::from_usize(vpn_range.start().as_usize() + 1);
+
+        // 范围内有未映射的地址时失败，不做任何修改
+        assert!(ms.mlock(addr, 8 * PAGE_SIZE, true).is_err());
+        assert!(ms.locked_pages() == 0);
+
+        // 锁定时重新填充被丢弃的页，重复锁定不重复计数
+        ms.madvise_dontneed(addr, 2 * PAGE_SIZE).unwrap();
+        ms.mlock(addr, 2 * PAGE_SIZE, true).unwrap();
+        ms.mlock(addr + PAGE_SIZE, PAGE_SIZE, true).unwrap();
+        assert!(ms.locked_pages() == 2);
+        assert!(ms.page_table().walk(second).is_ok());
+
+        // 锁定的页不换出，不能 MADV_DONTNEED / MADV_FREE
+        assert!(ms.swap_out_page(vpn_range.start()) == mm::ReclaimResult::Gone);
+        assert!(ms.page_table().walk(vpn_range.start()).is_ok());
+        assert!(ms.madvise_dontneed(addr, 4 * PAGE_SIZE).is_err());
+        assert!(ms.madvise_free(addr + PAGE_SIZE, PAGE_SIZE).is_err());
+
+        // fork 不继承锁定
+        let child = ms.clone_for_fork().unwrap();
+        assert!(child.locked_pages() == 0);
+        drop(child);
+
+        // munlock 与 munmap 解除部分锁定
+        ms.munlock(addr, PAGE_SIZE).unwrap();
+        assert!(!ms.is_mlocked(vpn_range.start()));
+        assert!(ms.is_mlocked(second));
+        ms.munmap(addr + PAGE_SIZE, PAGE_SIZE).unwrap();
+        assert!(ms.locked_pages() == 0);
+
+        // mlockall(MCL_CURRENT | MCL_FUTURE) 锁定剩下的用户映射，munlockall 全部解除
+        ms.mlockall(MlockallFlags::CURRENT | MlockallFlags::FUTURE).unwrap();
+        assert!(ms.locked_pages() == 3);
+        assert!(ms.mlock_future() == Some(MlockFlags::empty()));
+        assert!(ms.munlockall().len() == 2);
+        assert!(ms.locked_pages() == 0 && ms.mlock_future().is_none());
+    }
+
+    // 33. 测试 KSM：内容相同的页经三轮扫描合并为同一帧，取消标记后重新分开
+    #[test_case]
+    fn test_ksm_merge() {
+        let mut ms = MemorySpace::new();
+
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x35000), Vpn::from_usize(0x35003));
+        let data = alloc::vec![0x5a_u8; 2 * PAGE_SIZE];
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            Some(&data),
+            None,
+        )
+        .expect("Failed to insert area");
+        let addr = vpn_range.start().start_addr().as_usize();
+        let first = vpn_range.start();
+        let second = Vpn::from_usize(first.as_usize() + 1);
+        let ppn = |ms: &MemorySpace, vpn: Vpn| ms.page_table().walk(vpn).unwrap().0;
+
+        // 只标记前两页，区域随之拆分
+        let areas = ms.areas().len();
+        ms.madvise_mergeable(addr, 2 * PAGE_SIZE, true).unwrap();
+        assert!(ms.areas().len() == areas + 1);
+
+        // 第一轮记录哈希，第二轮提升第二页，第三轮第一页合并进来
+        for _ in 0..3 {
+            let mut budget = usize::MAX;
+            assert!(ms.ksm_scan(Vpn::from_usize(0), &mut budget).is_none());
+            mm::ksm::end_full_scan();
+        }
+        assert!(ppn(&ms, first) == ppn(&ms, second));
+        let (_, _, flags) = ms.page_table().walk(first).unwrap();
+        assert!(!flags.contains(UniversalPTEFlag::WRITEABLE));
+
+        // 取消标记后各自独占，内容不变
+        ms.madvise_mergeable(addr, 2 * PAGE_SIZE, false).unwrap();
+        assert!(ppn(&ms, first) != ppn(&ms, second));
+        let copy = unsafe {
+            core::slice::from_raw_parts(
+                paddr_to_vaddr(ppn(&ms, first).start_addr().as_usize()) as *const u8,
+                PAGE_SIZE,
+            )
+        };
+        assert!(copy.iter().all(|&b| b == 0x5a));
+    }
+
+    // 34. 测试 MAP_HUGETLB：从大页池取 2M 页映射，不能拆分，解除映射后大页回到池中
+    #[test_case]
+    fn test_hugetlb_area() {
+        let mut ms = MemorySpace::new();
+        if mm::hugetlb::set_nr_hugepages(1) < 1 {
+            println!("  no contiguous frames for a huge page, skipped");
+            return;
+        }
+
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x400000), Vpn::from_usize(0x400200));
+        ms.insert_hugetlb_area(vpn_range, UniversalPTEFlag::user_rw())
+            .expect("Failed to insert hugetlb area");
+        let (_, size, _) = ms.page_table().walk(vpn_range.start()).unwrap();
+        assert!(size == PageSize::Size2M);
+        assert!(mm::hugetlb::free_hugepages() == 0);
+
+        // 池已空，再插入一个大页池区域失败且不留下区域
+        let other = VpnRange::new(Vpn::from_usize(0x400400), Vpn::from_usize(0x400600));
+        assert!(matches!(
+            ms.insert_hugetlb_area(other, UniversalPTEFlag::user_rw()),
+            Err(PagingError::FrameAllocFailed)
+        ));
+        assert!(ms.find_area(other.start()).is_none());
+
+        // 落在大页内部的范围不能解除映射或修改权限
+        let addr = vpn_range.start().start_addr().as_usize();
+        assert!(matches!(
+            ms.munmap(addr + PAGE_SIZE, PAGE_SIZE),
+            Err(PagingError::InvalidAddress)
+        ));
+        assert!(matches!(
+            ms.mprotect(addr, PAGE_SIZE, UniversalPTEFlag::user_read()),
+            Err(PagingError::InvalidAddress)
+        ));
+
+        // 整体解除映射后大页回到池中
+        ms.munmap(addr, 0x200 * PAGE_SIZE).unwrap();
+        assert!(mm::hugetlb::free_hugepages() == 1);
+        assert!(mm::hugetlb::set_nr_hugepages(0) == 0);
+    }
+
+    // 35. 测试内核访问用户内存触发的写时复制：当前 CPU 已持有地址空间锁时判定为无法处理，
+    //     否则照常加锁解决缺页，不因锁被占用而放弃
+    #[test_case]
+    fn test_kernel_cow_fault_locking() {
+        use crate::kernel::current_cpu;
+        use crate::sync::PreemptGuard;
+
+        let mut ms = MemorySpace::new();
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x32000), Vpn::from_usize(0x32001));
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+        let vpn = vpn_range.start();
+        let addr = vpn.start_addr().as_usize();
+        ms.write_bytes_at(addr, b"cow").unwrap();
+        let ppn = ms.find_area(vpn).unwrap().get_ppn(vpn).unwrap();
+        let child = Arc::new(SpinLock::new(ms.clone_for_fork().expect("fork failed")));
+        let saved = {
+            let _guard = PreemptGuard::new();
+            current_cpu().current_memory_space.replace(child.clone())
+        };
+
+        // 当前 CPU 持有锁时再加锁会死锁
+        let held = child.lock();
+        assert!(crate::mm::lock_fault_space(&child, true).is_none());
+        assert!(!crate::mm::handle_cow_fault(addr, true));
+        drop(held);
+
+        // 锁未被当前 CPU 持有时照常加锁，子进程得到自己的副本
+        assert!(crate::mm::handle_cow_fault(addr, true));
+        let child_ppn = child.lock().find_area(vpn).unwrap().get_ppn(vpn).unwrap();
+        assert!(child_ppn != ppn);
+        assert!(mm::rmap::page_mapcount(ppn) == 1);
+
+        let _guard = PreemptGuard::new();
+        current_cpu().current_memory_space = saved;
+    }
+
+    // 36. 测试内核访问被丢弃的用户页：与写时复制相同，只有当前 CPU 已持有地址空间锁时才判定为无法处理
+    #[test_case]
+    fn test_kernel_demand_fault_locking() {
+        use crate::kernel::current_cpu;
+        use crate::sync::PreemptGuard;
+
+        let mut ms = MemorySpace::new();
+        let vpn_range = VpnRange::new(Vpn::from_usize(0x33000), Vpn::from_usize(0x33001));
+        ms.insert_framed_area(
+            vpn_range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+        let vpn = vpn_range.start();
+        let addr = vpn.start_addr().as_usize();
+        ms.madvise_dontneed(addr, PAGE_SIZE).unwrap();
+        assert!(ms.page_table().walk(vpn).is_err());
+        let space = Arc::new(SpinLock::new(ms));
+        let saved = {
+            let _guard = PreemptGuard::new();
+            current_cpu().current_memory_space.replace(space.clone())
+        };
+
+        let held = space.lock();
+        assert!(!crate::mm::handle_demand_fault(addr, true));
+        drop(held);
+
+        assert!(crate::mm::handle_demand_fault(addr, true));
+        assert!(space.lock().page_table().walk(vpn).is_ok());
+
+        let _guard = PreemptGuard::new();
+        current_cpu().current_memory_space = saved;
+    }
 }
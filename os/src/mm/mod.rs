@@ -13,8 +13,17 @@ pub use mm::page_table;
 pub use mm::frame_allocator::init_frame_allocator;
 
 // os-specific 的模块
+pub mod compaction;
 pub mod global_allocator;
+pub mod hugetlb;
+pub mod kaslr;
+pub mod ksm;
+pub mod kstack;
 pub mod memory_space;
+pub mod oom;
+pub mod swap;
+pub mod userfaultfd;
+pub mod vmalloc;
 
 // Re-export global_allocator 中的 init_heap
 pub use global_allocator::init_heap;
@@ -27,8 +36,10 @@ pub use memory_space::{
 pub use mm::memory_space::{AreaType, MapType, MappingArea, MmapFile};
 
 use crate::arch::mm::vaddr_to_paddr;
-use crate::config::{MEMORY_END, PAGE_SIZE};
+use crate::config::{MEMORY_END, PAGE_SIZE, PSTORE_SIZE};
 use crate::earlyprintln;
+use crate::sync::{SpinLock, SpinLockGuard};
+use alloc::sync::Arc;
 use mm::address::{Ppn, UsizeConvert};
 
 unsafe extern "C" {
@@ -42,6 +53,7 @@ unsafe extern "C" {
 /// 1. 初始化内核堆分配器。
 /// 2. 初始化物理帧分配器（需要堆分配来创建位图）。
 /// 3. 内核地址空间由 lazy_static KERNEL_SPACE 自动创建。
+/// 4. 注册 vmalloc 区操作。
 ///
 /// # 返回值
 /// 返回内核根页表的 PPN，调用者需要在合适时机激活它。
@@ -58,6 +70,10 @@ pub fn init() -> Ppn {
     // 分配器将管理 [start, end) 范围内的内存。
     let start = ekernel_paddr.div_ceil(PAGE_SIZE) * PAGE_SIZE; // 页对齐
 
+    // 紧跟内核镜像的一段留给 pstore：同一内核重启后地址不变，可以读回上一次的崩溃日志
+    crate::log::pstore::init_ram(start);
+    let start = start + PSTORE_SIZE;
+
     // 优先使用设备树中的内存信息，否则使用配置中的 MEMORY_END
     let mut end =
         if let Some((dram_start, dram_size)) = crate::device::device_tree::early_dram_info() {
@@ -92,6 +108,9 @@ pub fn init() -> Ppn {
         "[MM] Created kernel space, root PPN: 0x{:x}",
         root_ppn.as_usize()
     );
+
+    // 4. vmalloc 区映射到内核页表中
+    vmalloc::init();
     root_ppn
 }
 
@@ -108,3 +127,135 @@ pub fn activate(root_ppn: Ppn) {
     // 调用特定架构的页表激活函数，例如在 RISC-V 上设置 SATP 寄存器。
     crate::arch::mm::PageTableInner::activate(root_ppn);
 }
+
+/// 为处理缺页锁住地址空间
+///
+/// 内核经 [`SumGuard`](crate::arch::trap::SumGuard) 访问用户内存时同样会触发缺页。
+/// `in_kernel` 为真且当前 CPU 已持有该锁时再加锁会自旋死锁，返回 `None` 表示无法处理；
+/// 锁被其他 CPU 持有时照常加锁，等对方释放后处理缺页。
+pub(crate) fn lock_fault_space(
+    space: &Arc<SpinLock<MemorySpace>>,
+    in_kernel: bool,
+) -> Option<SpinLockGuard<'_, MemorySpace>> {
+    if in_kernel && space.is_held_by_current_cpu() {
+        return None;
+    }
+    Some(space.lock())
+}
+
+/// 处理当前任务地址空间中 `vaddr` 处的写缺页（写时复制）
+///
+/// 加锁规则见 [`lock_fault_space`]。
+///
+/// # 返回值
+/// 缺页已解决、可以重新执行出错指令时返回 `true`
+pub fn handle_cow_fault(vaddr: usize, in_kernel: bool) -> bool {
+    let space = {
+        let _guard = crate::sync::PreemptGuard::new();
+        crate::kernel::current_cpu().current_memory_space.clone()
+    };
+    let Some(space) = space else {
+        return false;
+    };
+    let Some(mut space) = lock_fault_space(&space, in_kernel) else {
+        return false;
+    };
+    let handled = space.handle_cow_fault(mm::address::Vaddr::from_usize(vaddr));
+    if handled {
+        mm::vmstat::count_event(mm::vmstat::VmEvent::PgFault);
+    }
+    handled
+}
+
+/// 处理当前任务地址空间中 `vaddr` 处访问没有页表项的页引起的缺页
+///
+/// 被 `madvise` 丢弃或回收的页在这里重新填充，加锁规则见 [`lock_fault_space`]。
+///
+/// # 返回值
+/// 缺页已解决、可以重新执行出错指令时返回 `true`
+pub fn handle_demand_fault(vaddr: usize, in_kernel: bool) -> bool {
+    use mm::address::{PageNum, Vaddr, Vpn, VpnRange};
+
+    let space = {
+        let _guard = crate::sync::PreemptGuard::new();
+        crate::kernel::current_cpu().current_memory_space.clone()
+    };
+    let Some(space) = space else {
+        return false;
+    };
+    let vpn = Vpn::from_addr_floor(Vaddr::from_usize(vaddr));
+    let (handled, swappable) = {
+        let Some(mut guard) = lock_fault_space(&space, in_kernel) else {
+            return false;
+        };
+        let handled = guard.handle_demand_fault(Vaddr::from_usize(vaddr));
+        let swappable = guard.find_area(vpn).is_some_and(|a| a.is_swappable());
+        (handled, swappable)
+    };
+    if handled {
+        mm::vmstat::count_event(mm::vmstat::VmEvent::PgFault);
+    }
+    if handled && swappable {
+        // 重新填充的页排到 LRU 队尾
+        swap::lru_add_range(
+            &space,
+            VpnRange::new(vpn, Vpn::from_usize(vpn.as_usize() + 1)),
+        );
+    }
+    handled
+}
+
+/// 启动时检查内核映射的 W^X 布局
+///
+/// 逐页遍历内核地址空间，核对区域权限与实际页表项：.text 不可写、.rodata 只读、
+/// 其余内核区域不可执行。发现违规时逐区域报告，返回违规页数。
+pub fn check_wx_mappings() -> usize {
+    use mm::page_table::PageTableInner as PageTableInnerTrait;
+    use mm::wx::kernel_wx_violation;
+
+    let mut violations = 0;
+    with_kernel_space(|space| {
+        for area in space.areas() {
+            if let Some(reason) = kernel_wx_violation(area.area_type(), area.permission()) {
+                crate::pr_err!(
+                    "[W^X] {:?} area {:#x}..{:#x}: {}",
+                    area.area_type(),
+                    area.vpn_range().start().start_addr().as_usize(),
+                    area.vpn_range().end().start_addr().as_usize(),
+                    reason
+                );
+            }
+            let mut bad_pages = 0;
+            for vpn in area.vpn_range() {
+                let Ok((_, _, flags)) = space.page_table().walk(vpn) else {
+                    continue;
+                };
+                if kernel_wx_violation(area.area_type(), flags).is_some() {
+                    bad_pages += 1;
+                }
+            }
+            if bad_pages != 0 {
+                crate::pr_err!(
+                    "[W^X] {:?} area {:#x}..{:#x}: {} page(s) violate W^X in the page table",
+                    area.area_type(),
+                    area.vpn_range().start().start_addr().as_usize(),
+                    area.vpn_range().end().start_addr().as_usize(),
+                    bad_pages
+                );
+            }
+            violations += bad_pages;
+        }
+    });
+
+    // LoongArch 内核镜像经 DMW 直映窗口执行，窗口本身没有读/写/执行权限位，
+    // 页表中的 W^X 布局只约束经页表访问的内核地址。
+    #[cfg(target_arch = "loongarch64")]
+    crate::pr_warn!("[W^X] kernel image runs through DMW1, which cannot enforce W^X");
+
+    if violations == 0 {
+        crate::pr_info!("[W^X] kernel mappings are W^X");
+    } else {
+        crate::pr_err!("[W^X] {} kernel page(s) violate W^X", violations);
+    }
+    violations
+}
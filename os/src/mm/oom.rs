@@ -0,0 +1,209 @@
+//! OOM killer 接入
+//!
+//! [`mm::oom`] 在帧分配回收失败时调用本模块：
+//! - 在用户进程中按 badness（常驻页数与 `oom_score_adj`）选出受害者并发送 SIGKILL；
+//! - 立即丢弃受害者地址空间中的私有页，不等它下次被调度时退出，
+//!   分配路径可能无法睡眠，必须马上得到空闲帧。
+//!
+//! 分配可能发生在持有任务管理器、任务或地址空间锁的位置，这里只尝试加锁，
+//! 拿不到锁的进程本轮不参与选择。
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
+
+use mm::address::{PageNum, UsizeConvert};
+use mm::oom::{self, OomOps};
+use uapi::signal::{NUM_SIGKILL, SignalFlags};
+
+use super::MemorySpace;
+use super::frame_allocator::{get_free_frames, get_total_frames};
+use crate::config::PAGE_SIZE;
+use crate::kernel::{SharedTask, TASK_MANAGER, TaskManagerTrait, TaskState, send_signal_process};
+use crate::sync::SpinLock;
+
+/// 注册 OOM 操作
+pub fn init() {
+    // Safety: 启动阶段单核调用，只调用一次
+    unsafe { oom::register_oom_ops(&TASK_OOM_OPS) };
+}
+
+/// 地址空间中已映射的页数（常驻内存）
+pub fn rss_pages(space: &MemorySpace) -> usize {
+    space.areas().iter().map(|area| area.mapped_pages()).sum()
+}
+
+/// 进程当前的 badness，不可被选中时返回 `None`
+///
+/// 只用于 procfs 等可以睡眠的上下文，OOM 路径使用 [`TaskOomOps`] 中只尝试加锁的版本。
+pub fn task_badness(task: &SharedTask) -> Option<usize> {
+    let (space, adj) = {
+        let t = task.lock();
+        (
+            t.memory_space.clone(),
+            t.oom_score_adj.load(Ordering::Relaxed),
+        )
+    };
+    let rss = space.map_or(0, |space| rss_pages(&space.lock()));
+    oom::oom_badness(rss, adj, get_total_frames())
+}
+
+/// OOM 候选进程
+struct Candidate {
+    task: SharedTask,
+    space: Arc<SpinLock<MemorySpace>>,
+    pid: u32,
+    rss: usize,
+    badness: usize,
+}
+
+/// 从任务列表中选出 badness 最高的用户进程
+fn select_victim(tasks: &[SharedTask], total: usize) -> Option<Candidate> {
+    let mut victim: Option<Candidate> = None;
+    for task in tasks {
+        let (space, pid, adj) = {
+            let Some(t) = task.try_lock() else {
+                continue;
+            };
+            // init 与内核线程不可被杀，线程由其所属进程代表
+            if !t.is_process() || t.pid == 1 || t.state == TaskState::Zombie {
+                continue;
+            }
+            let Some(space) = t.memory_space.clone() else {
+                continue;
+            };
+            // 已经被杀的进程正在释放内存，不再重复选中
+            let killed = t
+                .shared_pending
+                .try_lock()
+                .is_none_or(|p| p.signals.contains(SignalFlags::SIGKILL));
+            if killed {
+                continue;
+            }
+            (space, t.pid, t.oom_score_adj.load(Ordering::Relaxed))
+        };
+        let Some(rss) = space.try_lock().map(|s| rss_pages(&s)) else {
+            continue;
+        };
+        let Some(badness) = oom::oom_badness(rss, adj, total) else {
+            continue;
+        };
+        if victim.as_ref().is_none_or(|v| badness > v.badness) {
+            victim = Some(Candidate {
+                task: task.clone(),
+                space,
+                pid,
+                rss,
+                badness,
+            });
+        }
+    }
+    victim
+}
+
+/// 丢弃地址空间中所有私有页，返回是否尝试了释放
+fn reap_space(space: &SpinLock<MemorySpace>) -> bool {
+    let Some(mut space) = space.try_lock() else {
+        return false;
+    };
+    let ranges: Vec<(usize, usize)> = space
+        .areas()
+        .iter()
+        .filter(|area| area.is_swappable())
+        .map(|area| {
+            let start = area.vpn_range().start().start_addr().as_usize();
+            (start, area.vpn_range().len() * PAGE_SIZE)
+        })
+        .collect();
+    for (start, len) in ranges {
+        let _ = space.madvise_dontneed(start, len);
+    }
+    true
+}
+
+/// 按任务选择并杀死 OOM 受害者
+struct TaskOomOps;
+
+static TASK_OOM_OPS: TaskOomOps = TaskOomOps;
+
+impl OomOps for TaskOomOps {
+    fn kill_victim(&self) -> bool {
+        let tasks = match TASK_MANAGER.try_lock() {
+            Some(tm) => tm.get_all_tasks(),
+            None => return false,
+        };
+        let total = get_total_frames();
+        let Some(victim) = select_victim(&tasks, total) else {
+            crate::pr_err!("Out of memory and no killable processes");
+            return false;
+        };
+        drop(tasks);
+
+        crate::pr_err!(
+            "Out of memory: Killed process {} rss:{}kB oom_score:{}",
+            victim.pid,
+            victim.rss * PAGE_SIZE / 1024,
+            oom::oom_score(victim.badness, total)
+        );
+        send_signal_process(&victim.task, NUM_SIGKILL);
+
+        // 受害者可能正持有自己地址空间的锁，此时只能等它退出时释放
+        let free_before = get_free_frames();
+        if !reap_space(&victim.space) {
+            return false;
+        }
+        get_free_frames() > free_before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::task::TaskStruct;
+    use crate::mm::AreaType;
+    use mm::address::{Vpn, VpnRange};
+    use mm::page_table::UniversalPTEFlag;
+
+    /// 创建带有 `pages` 个已映射页的用户进程
+    fn new_user_task(tid: u32, pages: usize) -> SharedTask {
+        let mut ms = MemorySpace::new();
+        let range = VpnRange::new(Vpn::from_usize(0x40000), Vpn::from_usize(0x40000 + pages));
+        ms.insert_framed_area(
+            range,
+            AreaType::UserMmap,
+            UniversalPTEFlag::user_rw(),
+            None,
+            None,
+        )
+        .expect("Failed to insert area");
+        let mut task = TaskStruct::new_dummy_task(tid);
+        task.memory_space = Some(Arc::new(SpinLock::new(ms)));
+        task.into_shared()
+    }
+
+    // OOM 受害者选择：常驻页最多者优先，oom_score_adj 可以改变顺序，-1000 永不被选中
+    #[test_case]
+    fn test_oom_select_victim() {
+        let small = new_user_task(1001, 2);
+        let large = new_user_task(1002, 4);
+        let kthread = TaskStruct::new_dummy_task(1003).into_shared();
+        let tasks = [small.clone(), large.clone(), kthread];
+        let total = get_total_frames();
+
+        let victim = select_victim(&tasks, total).expect("no victim selected");
+        assert!(victim.pid == 1002 && victim.rss == 4);
+
+        small.lock().oom_score_adj.store(1000, Ordering::Relaxed);
+        assert!(select_victim(&tasks, total).unwrap().pid == 1001);
+
+        small.lock().oom_score_adj.store(-1000, Ordering::Relaxed);
+        large.lock().oom_score_adj.store(-1000, Ordering::Relaxed);
+        assert!(select_victim(&tasks, total).is_none());
+
+        // 回收后受害者的私有页全部释放
+        large.lock().oom_score_adj.store(0, Ordering::Relaxed);
+        let victim = select_victim(&tasks, total).unwrap();
+        assert!(reap_space(&victim.space));
+        assert!(rss_pages(&victim.space.lock()) == 0);
+    }
+}
@@ -0,0 +1,195 @@
+//! 交换区接入
+//!
+//! 交换区格式、槽位分配、匿名页 LRU 与回收由 [`mm::swap`] 实现，本模块负责与内核其余部分对接：
+//! - 命令行给出 `swap.blkdev=N` 时在第 N 个块设备上启用交换区（没有有效头部时先格式化），
+//!   该块设备必须专门留给交换区；
+//! - 为 [`mm::swap`] 提供按地址空间换出页的 [`SwapOps`]；
+//! - 把用户私有帧映射的页加入 LRU，把 `madvise(MADV_FREE)` 标记的页交给回收，
+//!   处理访问换出页引起的缺页。
+
+use alloc::sync::Arc;
+
+use mm::address::{PageNum, UsizeConvert, Vaddr, Vpn, VpnRange};
+use mm::swap::{self, ReclaimResult, SwapDevice, SwapError, SwapOps, SwapOwner};
+
+use super::MemorySpace;
+use crate::config::PAGE_SIZE;
+use crate::device::block::BlockDriver;
+use crate::device::{BLK_DRIVERS, CMDLINE};
+use crate::sync::SpinLock;
+
+/// 按命令行的 `swap.blkdev=N` 在块设备上启用交换区
+///
+/// 必须在块设备探测完成之后、启动从核之前调用。
+pub fn init() {
+    // Safety: 启动阶段单核调用，只调用一次
+    unsafe { swap::register_swap_ops(&SPACE_SWAP_OPS) };
+
+    let index = CMDLINE
+        .read()
+        .split_whitespace()
+        .find_map(|tok| tok.strip_prefix("swap.blkdev="))
+        .and_then(|n| n.parse::<usize>().ok());
+    let Some(index) = index else {
+        return;
+    };
+    let Some(driver) = BLK_DRIVERS.read().get(index).cloned() else {
+        crate::pr_warn!("swap: block device {} not found", index);
+        return;
+    };
+    let dev: Arc<dyn SwapDevice> = Arc::new(BlockSwapDevice { driver });
+    let mut result = swap::swapon(dev.clone());
+    if result == Err(SwapError::InvalidHeader) {
+        crate::pr_info!("swap: no swap header on block device {}, formatting", index);
+        result = swap::mkswap(dev.as_ref()).and_then(|_| swap::swapon(dev));
+    }
+    match (result, swap::swap_info()) {
+        (Ok(()), Some(info)) => crate::pr_info!(
+            "swap: enabled on block device {}, {} KiB",
+            index,
+            info.total * PAGE_SIZE / 1024
+        ),
+        (result, _) => crate::pr_warn!(
+            "swap: failed to enable block device {}: {:?}",
+            index,
+            result
+        ),
+    }
+}
+
+/// 把 `space` 中 `range` 内的页加入 LRU（调用者保证它们属于可换出的区域）
+pub fn lru_add_range(space: &Arc<SpinLock<MemorySpace>>, range: VpnRange) {
+    if swap::swap_info().is_none() {
+        return;
+    }
+    let owner: SwapOwner = space.clone();
+    for vpn in range {
+        swap::lru_add(&owner, vpn);
+    }
+}
+
+/// 把 `range` 内属于可换出区域的页加入 LRU（用于解除锁定之后）
+///
+/// `guard` 是调用者已持有的 `space` 的锁。
+pub fn lru_add_unlocked(space: &Arc<SpinLock<MemorySpace>>, guard: &MemorySpace, range: VpnRange) {
+    for area in guard.areas().iter().filter(|area| area.is_swappable()) {
+        let area_range = area.vpn_range();
+        let start = core::cmp::max(area_range.start(), range.start());
+        let end = core::cmp::min(area_range.end(), range.end());
+        if start < end {
+            lru_add_range(space, VpnRange::new(start, end));
+        }
+    }
+}
+
+/// 把 `space` 中经 `madvise(MADV_FREE)` 标记的页交给回收
+///
+/// 与 LRU 不同，没有启用交换区时也会加入：这些页回收时直接释放，不写交换区。
+pub fn lazyfree_add_pages(space: &Arc<SpinLock<MemorySpace>>, vpns: &[Vpn]) {
+    let owner: SwapOwner = space.clone();
+    for &vpn in vpns {
+        swap::lazyfree_add(&owner, vpn);
+    }
+}
+
+/// 把 `space` 中所有可换出区域的页加入 LRU（用于 fork、execve 得到的新地址空间）
+pub fn lru_add_space(space: &Arc<SpinLock<MemorySpace>>) {
+    if swap::swap_info().is_none() {
+        return;
+    }
+    let owner: SwapOwner = space.clone();
+    let space = space.lock();
+    for area in space.areas().iter().filter(|area| area.is_swappable()) {
+        for vpn in area.vpn_range() {
+            swap::lru_add(&owner, vpn);
+        }
+    }
+}
+
+/// 处理当前任务地址空间中 `vaddr` 处访问换出页引起的缺页
+///
+/// 加锁规则见 [`lock_fault_space`](super::lock_fault_space)。
+///
+/// # 返回值
+/// 该页已换入、可以重新执行出错指令时返回 `true`
+pub fn handle_swap_fault(vaddr: usize, in_kernel: bool) -> bool {
+    let space = {
+        let _guard = crate::sync::PreemptGuard::new();
+        crate::kernel::current_cpu().current_memory_space.clone()
+    };
+    let Some(space) = space else {
+        return false;
+    };
+    let swapped_in = {
+        let Some(mut guard) = super::lock_fault_space(&space, in_kernel) else {
+            return false;
+        };
+        guard.handle_swap_fault(Vaddr::from_usize(vaddr))
+    };
+    if swapped_in {
+        // 需要读交换区，计为主缺页
+        mm::vmstat::count_event(mm::vmstat::VmEvent::PgFault);
+        mm::vmstat::count_event(mm::vmstat::VmEvent::PgMajFault);
+        // 换入的页重新排到 LRU 队尾
+        let owner: SwapOwner = space;
+        swap::lru_add(&owner, Vpn::from_addr_floor(Vaddr::from_usize(vaddr)));
+    }
+    swapped_in
+}
+
+/// 按地址空间换出页
+struct SpaceSwapOps;
+
+static SPACE_SWAP_OPS: SpaceSwapOps = SpaceSwapOps;
+
+impl SwapOps for SpaceSwapOps {
+    fn try_swap_out(&self, owner: &SwapOwner, vpn: Vpn) -> ReclaimResult {
+        let Some(space) = owner.downcast_ref::<SpinLock<MemorySpace>>() else {
+            return ReclaimResult::Gone;
+        };
+        // 回收可能发生在持有该地址空间锁的分配路径上
+        match space.try_lock() {
+            Some(mut space) => space.swap_out_page(vpn),
+            None => ReclaimResult::Busy,
+        }
+    }
+}
+
+/// 以整个块设备作为交换区
+struct BlockSwapDevice {
+    driver: Arc<dyn BlockDriver>,
+}
+
+impl BlockSwapDevice {
+    fn blocks_per_page(&self) -> usize {
+        PAGE_SIZE / self.driver.block_size()
+    }
+}
+
+impl SwapDevice for BlockSwapDevice {
+    fn nr_pages(&self) -> usize {
+        self.driver.total_blocks() / self.blocks_per_page()
+    }
+
+    fn read_page(&self, index: usize, buf: &mut [u8]) -> Result<(), SwapError> {
+        let block_size = self.driver.block_size();
+        let first = index * self.blocks_per_page();
+        for (i, block) in buf.chunks_mut(block_size).enumerate() {
+            if !self.driver.read_block(first + i, block) {
+                return Err(SwapError::Io);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_page(&self, index: usize, buf: &[u8]) -> Result<(), SwapError> {
+        let block_size = self.driver.block_size();
+        let first = index * self.blocks_per_page();
+        for (i, block) in buf.chunks(block_size).enumerate() {
+            if !self.driver.write_block(first + i, block) {
+                return Err(SwapError::Io);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+//! vmalloc 区接入
+//!
+//! 分配与地址管理由 [`mm::vmalloc`] 实现，本模块提供 vmalloc 区的位置与内核页表操作。
+//! vmalloc 区紧跟在内核栈区之后，与它共用根页表中指向共享页表的那一项
+//! （见 [`super::kstack::link_kstack_area`]），在内核页表中建立的映射对所有地址空间立即可见。
+
+use mm::TlbBatchContextWrapper;
+use mm::address::{Ppn, UsizeConvert, Vpn};
+use mm::page_table::{PageSize, PageTableInner as _, PagingResult, UniversalPTEFlag};
+use mm::vmalloc::{self, VmallocOps};
+
+use super::kstack::{KSTACK_AREA_SIZE, KSTACK_AREA_START};
+use super::with_kernel_space;
+
+pub use mm::vmalloc::{is_vmalloc_addr, vfree, vmalloc, vmalloc_to_ppn, vmalloc_used_pages};
+
+/// vmalloc 区的起始地址
+pub const VMALLOC_START: usize = KSTACK_AREA_START + KSTACK_AREA_SIZE;
+/// vmalloc 区的大小
+pub const VMALLOC_SIZE: usize = 512 * 1024 * 1024;
+
+// 与内核栈区一起落在根页表的一项之内（RISC-V Sv39 为 1 GiB）
+const _: () = assert!(KSTACK_AREA_SIZE + VMALLOC_SIZE <= 1 << 30);
+
+/// 注册 vmalloc 区操作
+///
+/// 在内核地址空间建立之后调用。
+pub fn init() {
+    // Safety: 启动阶段单核调用，只调用一次
+    unsafe { vmalloc::register_vmalloc_ops(&KERNEL_VMALLOC_OPS) };
+}
+
+/// 在内核页表中映射 vmalloc 区
+struct KernelVmallocOps;
+
+static KERNEL_VMALLOC_OPS: KernelVmallocOps = KernelVmallocOps;
+
+impl VmallocOps for KernelVmallocOps {
+    fn region(&self) -> (usize, usize) {
+        (VMALLOC_START, VMALLOC_SIZE)
+    }
+
+    fn map_page(&self, vpn: Vpn, ppn: Ppn) -> PagingResult<()> {
+        with_kernel_space(|space| {
+            space
+                .page_table_mut()
+                .map(vpn, ppn, PageSize::Size4K, UniversalPTEFlag::kernel_rw())
+        })
+    }
+
+    fn unmap_pages(&self, start: Vpn, pages: usize) {
+        with_kernel_space(|space| {
+            let page_table = space.page_table_mut();
+            let _ = TlbBatchContextWrapper::execute(|batch| {
+                for i in 0..pages {
+                    let _ = page_table
+                        .unmap_with_batch(Vpn::from_usize(start.as_usize() + i), Some(&mut *batch));
+                }
+                Ok(())
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PAGE_SIZE;
+    use mm::address::{PageNum, Vaddr};
+
+    // 分配落在 vmalloc 区且可读写，页之间不必物理连续；释放后解除映射，地址可重用
+    #[test_case]
+    fn test_vmalloc_vfree() {
+        let used = vmalloc_used_pages();
+        let ptr = vmalloc(3 * PAGE_SIZE + 1).expect("vmalloc failed");
+        let addr = ptr.as_ptr() as usize;
+        assert!(is_vmalloc_addr(addr) && addr % PAGE_SIZE == 0);
+        assert!(vmalloc_used_pages() == used + 4);
+
+        // SAFETY: [addr, addr + 4 页) 由本次分配独占
+        unsafe {
+            let last = (addr + 4 * PAGE_SIZE - 8) as *mut usize;
+            assert!(last.read_volatile() == 0);
+            last.write_volatile(0xa5a5);
+            assert!(last.read_volatile() == 0xa5a5);
+        }
+        let ppn = vmalloc_to_ppn(addr + 3 * PAGE_SIZE).unwrap();
+        let current = crate::kernel::current_memory_space();
+        let vpn = Vpn::from_addr_floor(Vaddr::from_usize(addr + 3 * PAGE_SIZE));
+        assert!(current.lock().page_table().walk(vpn).unwrap().0 == ppn);
+
+        // 末尾的保护页没有映射
+        let guard = Vpn::from_usize(vpn.as_usize() + 1);
+        assert!(current.lock().page_table().walk(guard).is_err());
+
+        vfree(ptr);
+        assert!(vmalloc_used_pages() == used);
+        assert!(current.lock().page_table().walk(vpn).is_err());
+        let again = vmalloc(PAGE_SIZE).expect("vmalloc failed");
+        assert!(again.as_ptr() as usize == addr);
+        vfree(again);
+    }
+}
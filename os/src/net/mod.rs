@@ -14,10 +14,6 @@ impl net::NetOps for OsNetOps {
     fn get_time_ms(&self) -> u64 {
         crate::arch::timer::get_time_ms() as u64
     }
-
-    fn wake_poll_waiters(&self) {
-        crate::kernel::syscall::io::wake_poll_waiters();
-    }
 }
 
 static NET_OPS: OsNetOps = OsNetOps;
@@ -16,7 +16,8 @@ use crate::kernel::{
     Scheduler, TASK_MANAGER, TaskManagerTrait, TaskState, TaskStruct, current_cpu, current_task,
     pick_cpu, prepare_exec_image_from_path, scheduler_of, yield_task,
 };
-use crate::mm::frame_allocator::{alloc_contig_frames, alloc_frame};
+use crate::mm::frame_allocator::alloc_frame;
+use crate::mm::kstack::KernelStack;
 use crate::sync::SpinLock;
 use crate::vfs::{
     File, FileMode, FsError, InodeMetadata, InodeType, create_stdio_files, get_root_dentry,
@@ -79,6 +80,7 @@ fn ensure_top_level_dir(path: &str) -> Result<(), FsError> {
     }
     let root = get_root_dentry()?;
     root.inode.mkdir(name, mode)?;
+    crate::vfs::fsnotify_create(root.inode.as_ref(), name, true);
     Ok(())
 }
 
@@ -273,7 +275,7 @@ fn spawn_user_and_wait(
     let pid = tid;
     let ppid = { current_task().lock().pid };
 
-    let kstack_tracker = alloc_contig_frames(4).ok_or(())?;
+    let kstack = KernelStack::new().ok_or(())?;
     let trap_frame_tracker = alloc_frame().ok_or(())?;
 
     let task = TaskStruct::utask_create(
@@ -282,7 +284,7 @@ fn spawn_user_and_wait(
         ppid,
         pid, // pgid
         TaskStruct::empty_children(),
-        kstack_tracker,
+        kstack,
         trap_frame_tracker,
         space.clone(),
         alloc::sync::Arc::new(SpinLock::new(SignalHandlerTable::new())),
@@ -0,0 +1,318 @@
+//! 审计子系统
+//!
+//! 把安全相关事件记入专用环形缓冲区，经 `/proc/audit` 读出：
+//! - `SYSCALL`：命中审计规则的系统调用及其返回值；
+//! - `DENIED`：返回 EACCES / EPERM 的系统调用，总是记录；
+//! - `CRED`：set*id 等凭证修改，总是记录。
+//!
+//! 规则经 `/proc/audit_rules` 读写，每条规则按 uid 与系统调用号过滤，缺省字段匹配任意值：
+//!
+//! ```text
+//! add uid=1000 syscall=56
+//! del uid=1000 syscall=56
+//! clear
+//! ```
+//!
+//! 缓冲区满时丢弃最旧的记录并累计 `lost`。
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use uapi::errno::{EACCES, EINVAL, EPERM};
+
+use crate::kernel::current_task;
+use crate::kernel::hrtimer::{NSEC_PER_MSEC, NSEC_PER_SEC, ktime_get};
+use crate::sync::SpinLock;
+
+/// 缓冲区最多保留的记录数
+const AUDIT_BACKLOG_LIMIT: usize = 256;
+
+/// 规则数量上限
+const AUDIT_MAX_RULES: usize = 64;
+
+/// 审计事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEvent {
+    /// 命中规则的系统调用
+    Syscall {
+        /// 系统调用号
+        nr: usize,
+        /// 参数
+        args: [usize; 6],
+        /// 返回值
+        ret: isize,
+    },
+    /// 因权限不足失败的系统调用
+    Denied {
+        /// 系统调用号
+        nr: usize,
+        /// 返回值（-EACCES 或 -EPERM）
+        ret: isize,
+    },
+    /// 凭证修改
+    Cred {
+        /// 操作名（如 "setresuid"）
+        op: &'static str,
+        /// 请求的 ID（未用到的位置为 u32::MAX）
+        ids: [u32; 3],
+        /// 返回值
+        ret: isize,
+    },
+}
+
+/// 一条审计记录
+#[derive(Debug, Clone, Copy)]
+pub struct AuditRecord {
+    /// 序号，从 1 开始单调递增
+    pub seq: u64,
+    /// 记录时间（纳秒）
+    pub time: u64,
+    /// 进程 ID
+    pub pid: u32,
+    /// 线程 ID
+    pub tid: u32,
+    /// 真实用户 ID
+    pub uid: u32,
+    /// 事件内容
+    pub event: AuditEvent,
+}
+
+/// 审计规则：按 uid 与系统调用号过滤，`None` 匹配任意值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRule {
+    /// 匹配的真实用户 ID
+    pub uid: Option<u32>,
+    /// 匹配的系统调用号
+    pub syscall: Option<usize>,
+}
+
+impl AuditRule {
+    fn matches(&self, uid: u32, nr: usize) -> bool {
+        self.uid.is_none_or(|u| u == uid) && self.syscall.is_none_or(|s| s == nr)
+    }
+
+    /// 解析 `uid=N syscall=M` 形式的规则
+    fn parse<'a>(fields: impl Iterator<Item = &'a str>) -> Result<Self, i32> {
+        let mut rule = AuditRule {
+            uid: None,
+            syscall: None,
+        };
+        for field in fields {
+            let (key, value) = field.split_once('=').ok_or(EINVAL)?;
+            match key {
+                "uid" => rule.uid = Some(value.parse().map_err(|_| EINVAL)?),
+                "syscall" => rule.syscall = Some(value.parse().map_err(|_| EINVAL)?),
+                _ => return Err(EINVAL),
+            }
+        }
+        Ok(rule)
+    }
+}
+
+struct AuditLog {
+    records: VecDeque<AuditRecord>,
+    next_seq: u64,
+    lost: u64,
+    rules: Vec<AuditRule>,
+}
+
+static AUDIT: SpinLock<AuditLog> = SpinLock::new(AuditLog {
+    records: VecDeque::new(),
+    next_seq: 1,
+    lost: 0,
+    rules: Vec::new(),
+});
+
+/// 是否存在审计规则；没有规则时系统调用出口只需检查返回值
+static HAS_RULES: AtomicBool = AtomicBool::new(false);
+
+fn log_event(event: AuditEvent) {
+    let (pid, tid, uid) = {
+        let task = current_task();
+        let t = task.lock();
+        (t.pid, t.tid, t.credential.uid)
+    };
+    push(pid, tid, uid, event);
+}
+
+fn push(pid: u32, tid: u32, uid: u32, event: AuditEvent) {
+    let time = ktime_get();
+    let mut log = AUDIT.lock();
+    if log.records.len() >= AUDIT_BACKLOG_LIMIT {
+        log.records.pop_front();
+        log.lost += 1;
+    }
+    let seq = log.next_seq;
+    log.next_seq += 1;
+    log.records.push_back(AuditRecord {
+        seq,
+        time,
+        pid,
+        tid,
+        uid,
+        event,
+    });
+}
+
+/// 系统调用出口的审计钩子
+///
+/// 由各架构的 `dispatch_syscall` 在系统调用返回后调用。
+pub fn syscall_exit(nr: usize, args: [usize; 6], ret: isize) {
+    let denied = ret == -(EACCES as isize) || ret == -(EPERM as isize);
+    if !denied && !HAS_RULES.load(Ordering::Relaxed) {
+        return;
+    }
+    let (pid, tid, uid) = {
+        let task = current_task();
+        let t = task.lock();
+        (t.pid, t.tid, t.credential.uid)
+    };
+    let matched =
+        HAS_RULES.load(Ordering::Relaxed) && AUDIT.lock().rules.iter().any(|r| r.matches(uid, nr));
+    if matched {
+        push(pid, tid, uid, AuditEvent::Syscall { nr, args, ret });
+    }
+    if denied {
+        push(pid, tid, uid, AuditEvent::Denied { nr, ret });
+    }
+}
+
+/// 记录一次凭证修改
+pub fn cred_change(op: &'static str, ids: [u32; 3], ret: isize) {
+    log_event(AuditEvent::Cred { op, ids, ret });
+}
+
+fn format_record(out: &mut String, r: &AuditRecord) {
+    let _ = write!(
+        out,
+        "audit({}.{:03}:{}): ",
+        r.time / NSEC_PER_SEC,
+        r.time % NSEC_PER_SEC / NSEC_PER_MSEC,
+        r.seq
+    );
+    match r.event {
+        AuditEvent::Syscall { nr, args, ret } => {
+            let _ = write!(out, "type=SYSCALL syscall={} exit={}", nr, ret);
+            for (i, a) in args.iter().enumerate() {
+                let _ = write!(out, " a{}={:#x}", i, a);
+            }
+        }
+        AuditEvent::Denied { nr, ret } => {
+            let _ = write!(out, "type=DENIED syscall={} exit={}", nr, ret);
+        }
+        AuditEvent::Cred { op, ids, ret } => {
+            let _ = write!(out, "type=CRED op={}", op);
+            for id in ids.iter().filter(|&&id| id != u32::MAX) {
+                let _ = write!(out, " id={}", id);
+            }
+            let _ = write!(out, " res={}", if ret == 0 { "success" } else { "failed" });
+        }
+    }
+    let _ = writeln!(out, " pid={} tid={} uid={}", r.pid, r.tid, r.uid);
+}
+
+/// 格式化全部记录（`/proc/audit` 的内容）
+pub fn read_log() -> Vec<u8> {
+    let log = AUDIT.lock();
+    let mut out = String::new();
+    if log.lost != 0 {
+        let _ = writeln!(out, "lost={}", log.lost);
+    }
+    for r in log.records.iter() {
+        format_record(&mut out, r);
+    }
+    out.into_bytes()
+}
+
+/// 列出规则（`/proc/audit_rules` 的内容）
+pub fn read_rules() -> Vec<u8> {
+    let log = AUDIT.lock();
+    let mut out = String::new();
+    for rule in log.rules.iter() {
+        let uid = rule.uid.map_or(String::from("any"), |u| format!("{}", u));
+        let nr = rule
+            .syscall
+            .map_or(String::from("any"), |s| format!("{}", s));
+        let _ = writeln!(out, "uid={} syscall={}", uid, nr);
+    }
+    out.into_bytes()
+}
+
+/// 执行规则命令：`add <rule>`、`del <rule>` 或 `clear`，每行一条
+///
+/// 失败返回正的 errno。
+pub fn write_rules(cmds: &str) -> Result<(), i32> {
+    let mut log = AUDIT.lock();
+    let result = cmds.lines().try_for_each(|line| {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            None => {}
+            Some("clear") => log.rules.clear(),
+            Some("add") => {
+                let rule = AuditRule::parse(fields)?;
+                if log.rules.len() >= AUDIT_MAX_RULES {
+                    return Err(EINVAL);
+                }
+                if !log.rules.contains(&rule) {
+                    log.rules.push(rule);
+                }
+            }
+            Some("del") => {
+                let rule = AuditRule::parse(fields)?;
+                log.rules.retain(|r| *r != rule);
+            }
+            Some(_) => return Err(EINVAL),
+        }
+        Ok(())
+    });
+    HAS_RULES.store(!log.rules.is_empty(), Ordering::Relaxed);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_audit_rule_parse_and_match() {
+        let rule = AuditRule::parse("uid=1000 syscall=56".split_whitespace()).unwrap();
+        assert!(rule.matches(1000, 56));
+        assert!(!rule.matches(0, 56));
+        assert!(!rule.matches(1000, 57));
+
+        let any_uid = AuditRule::parse("syscall=56".split_whitespace()).unwrap();
+        assert!(any_uid.matches(0, 56));
+        assert!(any_uid.matches(1000, 56));
+
+        assert_eq!(AuditRule::parse("gid=0".split_whitespace()), Err(EINVAL));
+        assert_eq!(AuditRule::parse("uid=x".split_whitespace()), Err(EINVAL));
+    }
+
+    #[test_case]
+    fn test_audit_format_cred_record() {
+        let mut out = String::new();
+        format_record(
+            &mut out,
+            &AuditRecord {
+                seq: 7,
+                time: 3 * NSEC_PER_SEC + 250 * NSEC_PER_MSEC,
+                pid: 2,
+                tid: 2,
+                uid: 0,
+                event: AuditEvent::Cred {
+                    op: "setuid",
+                    ids: [1000, u32::MAX, u32::MAX],
+                    ret: -(EPERM as isize),
+                },
+            },
+        );
+        assert_eq!(
+            out,
+            "audit(3.250:7): type=CRED op=setuid id=1000 res=failed pid=2 tid=2 uid=0\n"
+        );
+    }
+}
@@ -2,10 +2,13 @@
 
 #![allow(dead_code)]
 
+use super::chacha20::{CHACHA20_BLOCK_SIZE, CHACHA20_KEY_WORDS, chacha20_block};
+
 /// 熵池的最小种子位数阈值，确保足够的初始熵以安全地生成随机数。
 pub const MIN_SEED_BITS: usize = 128;
 
 /// 定义熵池操作所需的错误类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EntropyError {
     /// 熵池未初始化或熵值不足。
     Unseeded,
@@ -59,38 +62,173 @@ pub trait EntropyPool {
     }
 }
 
-/// 一个简单的伪熵池实现。
-pub struct BiogasPoll {
-    biogas: usize,
+/// 输入池的字数（512 位）
+const POOL_WORDS: usize = 16;
+
+/// 输入池最多记录的熵位数
+pub const POOL_BITS: usize = POOL_WORDS * 32;
+
+/// 基于 ChaCha20 的熵池
+///
+/// 注入的原始熵先混合进输入池；熵估计达到 [`MIN_SEED_BITS`] 后重新播种，
+/// 把输入池与当前密钥一起经 ChaCha20 压缩为新的密钥。输出由 ChaCha20 密钥流生成，
+/// 每次输出后立即用后续密钥流替换密钥，泄露当前状态也无法恢复之前的输出。
+pub struct ChaChaPool {
+    /// 输入池
+    input: [u32; POOL_WORDS],
+    /// 下一个混合位置
+    input_pos: usize,
+    /// 输入池中累计的熵估计（位）
+    entropy_count: usize,
+    /// CSPRNG 密钥
+    key: [u32; CHACHA20_KEY_WORDS],
+    /// 密钥流块计数器
+    counter: u64,
+    /// 是否已经用足够的熵播种过
+    seeded: bool,
 }
 
-impl EntropyPool for BiogasPoll {
-    fn new() -> Self {
-        BiogasPoll {
-            biogas: 0x1145141919810,
+impl ChaChaPool {
+    /// 创建空的熵池，可用于静态初始化
+    pub const fn empty() -> Self {
+        Self {
+            input: [0; POOL_WORDS],
+            input_pos: 0,
+            entropy_count: 0,
+            key: [0; CHACHA20_KEY_WORDS],
+            counter: 0,
+            seeded: false,
+        }
+    }
+
+    /// 把一个字混合进输入池
+    fn mix_word(&mut self, word: u32) {
+        let pos = self.input_pos;
+        let next = self.input[(pos + 1) % POOL_WORDS];
+        self.input[pos] =
+            (self.input[pos].rotate_left(7) ^ word).wrapping_add(next.rotate_right(3));
+        self.input_pos = (pos + 1) % POOL_WORDS;
+    }
+
+    /// 用输入池重新生成密钥，清空熵估计
+    ///
+    /// 首次播种要求熵估计达到 [`MIN_SEED_BITS`]，否则只混合而不标记为已播种。
+    pub fn reseed(&mut self) {
+        let mut key = self.key;
+        for (k, i) in key.iter_mut().zip(self.input[..CHACHA20_KEY_WORDS].iter()) {
+            *k ^= *i;
+        }
+        let nonce = [
+            self.input[8] ^ self.input[10] ^ self.input[12] ^ self.input[14],
+            self.input[9] ^ self.input[11] ^ self.input[13] ^ self.input[15],
+        ];
+        let block = chacha20_block(&key, self.counter, &nonce);
+        self.key.copy_from_slice(&block[..CHACHA20_KEY_WORDS]);
+        // 输入池保留压缩结果的另一半，后续注入继续在其上混合
+        for (i, b) in self
+            .input
+            .iter_mut()
+            .zip(block[CHACHA20_KEY_WORDS..].iter().cycle())
+        {
+            *i ^= *b;
         }
+        self.counter = 0;
+        if self.entropy_count >= MIN_SEED_BITS {
+            self.seeded = true;
+        }
+        self.entropy_count = 0;
+    }
+
+    /// 生成随机字节，不检查是否已播种
+    ///
+    /// 未播种时输出不具备密码学强度，只用于不能等待的场合。
+    pub fn fill_insecure(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(CHACHA20_BLOCK_SIZE) {
+            let block = chacha20_block(&self.key, self.counter, &[0, 0]);
+            self.counter = self.counter.wrapping_add(1);
+            for (d, s) in chunk
+                .iter_mut()
+                .zip(block.iter().flat_map(|w| w.to_le_bytes()))
+            {
+                *d = s;
+            }
+        }
+        // 立即替换密钥，保证前向安全
+        let block = chacha20_block(&self.key, self.counter, &[0, 0]);
+        self.key.copy_from_slice(&block[..CHACHA20_KEY_WORDS]);
+        self.counter = 0;
+    }
+}
+
+impl EntropyPool for ChaChaPool {
+    fn new() -> Self {
+        Self::empty()
     }
 
     fn try_fill(&mut self, dest: &mut [u8]) -> Result<usize, EntropyError> {
-        for i in 0..dest.len() {
-            dest[i] = (self.biogas & 0xFF) as u8;
-            self.biogas = self
-                .biogas
-                .wrapping_mul(6364136223846793005)
-                .wrapping_add(1);
+        if !self.seeded {
+            return Err(EntropyError::Unseeded);
         }
+        self.fill_insecure(dest);
         Ok(dest.len())
     }
 
-    fn add_entropy(&mut self, _data: &[u8], _entropy_bits: usize) {
-        // 简单实现不做任何操作
+    fn add_entropy(&mut self, data: &[u8], entropy_bits: usize) {
+        for chunk in data.chunks(4) {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.mix_word(u32::from_le_bytes(word));
+        }
+        self.entropy_count = (self.entropy_count + entropy_bits).min(POOL_BITS);
     }
 
     fn get_entropy_count(&self) -> usize {
-        114514
+        self.entropy_count
     }
 
     fn is_seeded(&self) -> bool {
-        true
+        self.seeded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 熵不足时拒绝输出，播种后可以输出
+    #[test_case]
+    fn test_chacha_pool_seeding() {
+        let mut pool = ChaChaPool::new();
+        let mut buf = [0u8; 16];
+        assert!(matches!(
+            pool.try_fill(&mut buf),
+            Err(EntropyError::Unseeded)
+        ));
+        pool.add_entropy(&[1, 2, 3, 4], MIN_SEED_BITS / 2);
+        pool.reseed();
+        assert!(!pool.is_seeded());
+        pool.add_entropy(&[5, 6, 7, 8], MIN_SEED_BITS);
+        pool.reseed();
+        assert!(pool.is_seeded());
+        assert!(pool.get_entropy_count() == 0);
+        assert!(pool.try_fill(&mut buf) == Ok(16));
+    }
+
+    // 连续输出互不相同，不同输入得到不同输出
+    #[test_case]
+    fn test_chacha_pool_output_differs() {
+        let mut a = ChaChaPool::new();
+        let mut b = ChaChaPool::new();
+        a.add_entropy(b"seed-a", MIN_SEED_BITS);
+        b.add_entropy(b"seed-b", MIN_SEED_BITS);
+        a.reseed();
+        b.reseed();
+        let (mut x, mut y, mut z) = ([0u8; 100], [0u8; 100], [0u8; 100]);
+        a.fill_insecure(&mut x);
+        a.fill_insecure(&mut y);
+        b.fill_insecure(&mut z);
+        assert!(x != y);
+        assert!(x != z);
+        assert!(x.iter().any(|&v| v != 0));
     }
 }
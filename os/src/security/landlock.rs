@@ -0,0 +1,326 @@
+//! Landlock 风格的非特权文件系统沙箱
+//!
+//! 进程用 `landlock_create_ruleset` 创建规则集 fd，声明要限制的访问权限（handled），
+//! 再用 `landlock_add_rule` 为若干目录（或文件）授予其下的访问权限，
+//! 最后 `landlock_restrict_self` 把规则集的快照作为一层叠加到自己的域（[`LandlockDomain`]）上。
+//!
+//! 访问路径时逐层检查：对每一层，沿 dentry 向上（跨越挂载点）收集命中规则授予的权限，
+//! 该层 handled 的请求权限必须全部被授予，否则返回 EACCES。层只能增加不能移除，
+//! fork 时继承，execve 时保留。
+//!
+//! 检查点由文件系统系统调用在操作前调用（[`file_open`]、[`path_mknod`]、[`path_unlink`]、
+//! [`bprm_check`] 等），对应 Linux LSM 的同名钩子。
+
+use alloc::{sync::Arc, vec::Vec};
+
+use uapi::errno::{E2BIG, EBADF, EBADFD, EINVAL, ENOMSG, EPERM};
+use uapi::fcntl::{FdFlags, OpenFlags};
+use uapi::landlock::*;
+
+use crate::kernel::{Capabilities, current_task};
+use crate::sync::SpinLock;
+use crate::util::user_buffer::copy_from_user;
+use crate::vfs::{
+    DENTRY_CACHE, Dentry, File, FileMode, FsError, InodeMetadata, InodeType, current_mnt_ns,
+};
+
+/// 一个域最多叠加的层数（与 Linux 一致）
+const LANDLOCK_MAX_NUM_LAYERS: usize = 16;
+
+/// 一条规则：`dentry` 及其之下允许 `allowed` 中的访问
+#[derive(Clone)]
+struct Rule {
+    dentry: Arc<Dentry>,
+    allowed: u64,
+}
+
+/// 规则集的不可变快照，作为域中的一层
+struct Layer {
+    handled: u64,
+    rules: Vec<Rule>,
+}
+
+/// 任务的 Landlock 域：依次叠加的各层，fork 时共享
+pub struct LandlockDomain {
+    layers: Vec<Arc<Layer>>,
+}
+
+/// `landlock_create_ruleset` 返回的 fd 背后的文件
+struct RulesetFile {
+    handled: u64,
+    rules: SpinLock<Vec<Rule>>,
+}
+
+impl File for RulesetFile {
+    fn readable(&self) -> bool {
+        false
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::InvalidArgument)
+    }
+
+    fn metadata(&self) -> Result<InodeMetadata, FsError> {
+        Err(FsError::NotSupported)
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+/// 沿 dentry 向上走一步；到达被挂载文件系统的根时跳到被覆盖的挂载点目录
+fn parent_crossing_mounts(dentry: &Arc<Dentry>) -> Option<Arc<Dentry>> {
+    if let Some(parent) = dentry.parent() {
+        return Some(parent);
+    }
+    current_mnt_ns()
+        .list_all()
+        .into_values()
+        .find(|mp| Arc::ptr_eq(&mp.root, dentry) && mp.mount_path != "/")
+        .and_then(|mp| DENTRY_CACHE.lookup(&mp.mount_path))
+}
+
+impl Layer {
+    /// 本层是否允许对 `dentry` 的 `access` 访问
+    fn allows(&self, dentry: &Arc<Dentry>, access: u64) -> bool {
+        let need = access & self.handled;
+        if need == 0 {
+            return true;
+        }
+        let mut granted = 0;
+        let mut cur = Some(dentry.clone());
+        while let Some(d) = cur {
+            for rule in self.rules.iter().filter(|r| Arc::ptr_eq(&r.dentry, &d)) {
+                granted |= rule.allowed;
+            }
+            if granted & need == need {
+                return true;
+            }
+            cur = parent_crossing_mounts(&d);
+        }
+        false
+    }
+}
+
+/// 检查当前任务能否对 `dentry` 进行 `access`（LANDLOCK_ACCESS_FS_* 的组合）访问
+pub fn check_access(dentry: &Arc<Dentry>, access: u64) -> Result<(), FsError> {
+    let Some(domain) = current_task().lock().landlock.clone() else {
+        return Ok(());
+    };
+    if domain.layers.iter().all(|l| l.allows(dentry, access)) {
+        Ok(())
+    } else {
+        Err(FsError::PermissionDenied)
+    }
+}
+
+/// 在目录下创建某类型文件所需的访问权限
+pub fn make_access(inode_type: InodeType) -> u64 {
+    match inode_type {
+        InodeType::File => LANDLOCK_ACCESS_FS_MAKE_REG,
+        InodeType::Directory => LANDLOCK_ACCESS_FS_MAKE_DIR,
+        InodeType::Symlink => LANDLOCK_ACCESS_FS_MAKE_SYM,
+        InodeType::CharDevice => LANDLOCK_ACCESS_FS_MAKE_CHAR,
+        InodeType::BlockDevice => LANDLOCK_ACCESS_FS_MAKE_BLOCK,
+        InodeType::Fifo => LANDLOCK_ACCESS_FS_MAKE_FIFO,
+        InodeType::Socket => LANDLOCK_ACCESS_FS_MAKE_SOCK,
+    }
+}
+
+/// 从目录中删除某类型文件所需的访问权限
+pub fn remove_access(inode_type: InodeType) -> u64 {
+    match inode_type {
+        InodeType::Directory => LANDLOCK_ACCESS_FS_REMOVE_DIR,
+        _ => LANDLOCK_ACCESS_FS_REMOVE_FILE,
+    }
+}
+
+/// 打开文件：目录需要 READ_DIR，文件按读写方式需要 READ_FILE / WRITE_FILE
+pub fn file_open(dentry: &Arc<Dentry>, flags: OpenFlags) -> Result<(), FsError> {
+    let is_dir = dentry.inode.metadata()?.inode_type == InodeType::Directory;
+    let mut access = 0;
+    if is_dir {
+        access |= LANDLOCK_ACCESS_FS_READ_DIR;
+    } else {
+        if flags.readable() {
+            access |= LANDLOCK_ACCESS_FS_READ_FILE;
+        }
+        if flags.writable() {
+            access |= LANDLOCK_ACCESS_FS_WRITE_FILE;
+        }
+    }
+    check_access(dentry, access)
+}
+
+/// 在 `parent` 下创建 `mode` 类型的文件
+pub fn path_mknod(parent: &Arc<Dentry>, mode: FileMode) -> Result<(), FsError> {
+    let inode_type = match mode.bits() & FileMode::S_IFMT.bits() {
+        m if m == FileMode::S_IFDIR.bits() => InodeType::Directory,
+        m if m == FileMode::S_IFLNK.bits() => InodeType::Symlink,
+        m if m == FileMode::S_IFCHR.bits() => InodeType::CharDevice,
+        m if m == FileMode::S_IFBLK.bits() => InodeType::BlockDevice,
+        m if m == FileMode::S_IFIFO.bits() => InodeType::Fifo,
+        m if m == FileMode::S_IFSOCK.bits() => InodeType::Socket,
+        _ => InodeType::File,
+    };
+    check_access(parent, make_access(inode_type))
+}
+
+/// 从 `parent` 中删除目录或文件
+pub fn path_unlink(parent: &Arc<Dentry>, is_dir: bool) -> Result<(), FsError> {
+    let inode_type = if is_dir {
+        InodeType::Directory
+    } else {
+        InodeType::File
+    };
+    check_access(parent, remove_access(inode_type))
+}
+
+/// 执行文件
+pub fn bprm_check(dentry: &Arc<Dentry>) -> Result<(), FsError> {
+    check_access(dentry, LANDLOCK_ACCESS_FS_EXECUTE)
+}
+
+fn get_ruleset(fd: i32) -> Result<Arc<dyn File>, i32> {
+    let file = current_task()
+        .lock()
+        .fd_table
+        .get(fd as usize)
+        .map_err(|_| EBADF)?;
+    if file.as_any().downcast_ref::<RulesetFile>().is_none() {
+        return Err(EBADFD);
+    }
+    Ok(file)
+}
+
+fn create_ruleset(attr: *const LandlockRulesetAttr, size: usize, flags: u32) -> Result<isize, i32> {
+    if flags == LANDLOCK_CREATE_RULESET_VERSION {
+        if !attr.is_null() || size != 0 {
+            return Err(EINVAL);
+        }
+        return Ok(LANDLOCK_ABI_VERSION as isize);
+    }
+    if flags != 0 {
+        return Err(EINVAL);
+    }
+    if size < core::mem::size_of::<LandlockRulesetAttr>() {
+        return Err(EINVAL);
+    }
+    let attr = copy_from_user(attr)?;
+    if attr.handled_access_fs & !LANDLOCK_ACCESS_FS_ALL != 0 {
+        return Err(EINVAL);
+    }
+    if attr.handled_access_fs == 0 {
+        return Err(ENOMSG);
+    }
+
+    let file = Arc::new(RulesetFile {
+        handled: attr.handled_access_fs,
+        rules: SpinLock::new(Vec::new()),
+    });
+    current_task()
+        .lock()
+        .fd_table
+        .alloc_with_flags(file, FdFlags::CLOEXEC)
+        .map(|fd| fd as isize)
+        .map_err(|e| -e.to_errno() as i32)
+}
+
+fn add_rule(
+    ruleset_fd: i32,
+    rule_type: u32,
+    attr: *const LandlockPathBeneathAttr,
+    flags: u32,
+) -> Result<isize, i32> {
+    if flags != 0 || rule_type != LANDLOCK_RULE_PATH_BENEATH {
+        return Err(EINVAL);
+    }
+    let file = get_ruleset(ruleset_fd)?;
+    let ruleset = file.as_any().downcast_ref::<RulesetFile>().unwrap();
+    let attr = copy_from_user(attr)?;
+    let (allowed, parent_fd) = (attr.allowed_access, attr.parent_fd);
+    if allowed == 0 {
+        return Err(ENOMSG);
+    }
+    if allowed & !ruleset.handled != 0 {
+        return Err(EINVAL);
+    }
+
+    let parent = current_task()
+        .lock()
+        .fd_table
+        .get(parent_fd as usize)
+        .map_err(|_| EBADF)?;
+    let dentry = parent.dentry().map_err(|_| EBADFD)?;
+    let is_dir = dentry
+        .inode
+        .metadata()
+        .map_err(|e| -e.to_errno() as i32)?
+        .inode_type
+        == InodeType::Directory;
+    if !is_dir && allowed & !LANDLOCK_ACCESS_FS_FILE != 0 {
+        return Err(EINVAL);
+    }
+    ruleset.rules.lock().push(Rule { dentry, allowed });
+    Ok(0)
+}
+
+fn restrict_self(ruleset_fd: i32, flags: u32) -> Result<isize, i32> {
+    if flags != 0 {
+        return Err(EINVAL);
+    }
+    let task = current_task();
+    {
+        let t = task.lock();
+        if !t.no_new_privs && !t.credential.capabilities.has(Capabilities::SYS_ADMIN) {
+            return Err(EPERM);
+        }
+    }
+    let file = get_ruleset(ruleset_fd)?;
+    let ruleset = file.as_any().downcast_ref::<RulesetFile>().unwrap();
+    let layer = Arc::new(Layer {
+        handled: ruleset.handled,
+        rules: ruleset.rules.lock().clone(),
+    });
+
+    let mut t = task.lock();
+    let mut layers = t
+        .landlock
+        .as_ref()
+        .map_or_else(Vec::new, |d| d.layers.clone());
+    if layers.len() >= LANDLOCK_MAX_NUM_LAYERS {
+        return Err(E2BIG);
+    }
+    layers.push(layer);
+    t.landlock = Some(Arc::new(LandlockDomain { layers }));
+    Ok(0)
+}
+
+/// landlock_create_ruleset(2)
+pub fn do_create_ruleset(attr: *const LandlockRulesetAttr, size: usize, flags: u32) -> isize {
+    create_ruleset(attr, size, flags).unwrap_or_else(|e| -(e as isize))
+}
+
+/// landlock_add_rule(2)
+pub fn do_add_rule(
+    ruleset_fd: i32,
+    rule_type: u32,
+    attr: *const LandlockPathBeneathAttr,
+    flags: u32,
+) -> isize {
+    add_rule(ruleset_fd, rule_type, attr, flags).unwrap_or_else(|e| -(e as isize))
+}
+
+/// landlock_restrict_self(2)
+pub fn do_restrict_self(ruleset_fd: i32, flags: u32) -> isize {
+    restrict_self(ruleset_fd, flags).unwrap_or_else(|e| -(e as isize))
+}
@@ -1,5 +1,10 @@
 //! 安全相关模块
 
+pub mod audit;
+mod chacha20;
 mod entropy_pool;
+pub mod landlock;
+pub mod random;
+pub mod seccomp;
 
 pub use entropy_pool::*;
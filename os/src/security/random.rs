@@ -0,0 +1,190 @@
+//! 内核随机数生成器
+//!
+//! 全局的 [`ChaChaPool`] 从以下熵源收集熵：
+//! - 时钟节拍与外部中断到来时刻的计数器抖动，每次记 1 位；
+//! - 启动时以及等待播种时主动测量的计时抖动；
+//! - 硬件随机数发生器（virtio-rng），按输出长度全额记入；
+//! - RTC 时间、用户写入 /dev/random 的数据等，只混合不记熵。
+//!
+//! 输入池的熵估计达到 [`MIN_SEED_BITS`] 时重新播种。首次播种之后，
+//! 两次播种至少间隔 [`RESEED_INTERVAL`]，使每次播种都积累足够多的新熵。
+
+use crate::arch::timer::get_time;
+use crate::kernel::hrtimer::{Ktime, NSEC_PER_SEC, ktime_get};
+use crate::sync::SpinLock;
+use crate::{pr_info, pr_warn};
+
+use super::{ChaChaPool, EntropyPool, MIN_SEED_BITS};
+
+/// 两次重新播种的最小间隔
+const RESEED_INTERVAL: Ktime = 60 * NSEC_PER_SEC;
+
+/// 主动测量计时抖动时每轮的采样数
+const JITTER_SAMPLES: usize = 64;
+
+/// 启动时最多测量的计时抖动轮数
+const BOOT_JITTER_ROUNDS: usize = 64;
+
+/// 一次从硬件随机数发生器读取的字节数
+const HWRNG_CHUNK: usize = 32;
+
+struct Crng {
+    pool: ChaChaPool,
+    /// 上次重新播种的时间
+    last_reseed: Ktime,
+}
+
+static CRNG: SpinLock<Crng> = SpinLock::new(Crng {
+    pool: ChaChaPool::empty(),
+    last_reseed: 0,
+});
+
+/// 熵估计足够时重新播种
+fn try_reseed(crng: &mut Crng) {
+    if crng.pool.get_entropy_count() < MIN_SEED_BITS {
+        return;
+    }
+    let now = ktime_get();
+    if !crng.pool.is_seeded() || now.saturating_sub(crng.last_reseed) >= RESEED_INTERVAL {
+        crng.pool.reseed();
+        crng.last_reseed = now;
+    }
+}
+
+fn mix(data: &[u8], entropy_bits: usize) {
+    let mut crng = CRNG.lock();
+    crng.pool.add_entropy(data, entropy_bits);
+    try_reseed(&mut crng);
+}
+
+/// 混合设备相关的数据，不记熵
+pub fn add_device_randomness(data: &[u8]) {
+    mix(data, 0);
+}
+
+/// 混合时钟节拍到来时刻的计数器值，由时钟节拍调用
+pub fn add_timer_randomness() {
+    mix(&(get_time() as u64).to_le_bytes(), 1);
+}
+
+/// 混合外部中断到来时刻的计数器值与中断号，由中断派发调用
+pub fn add_interrupt_randomness(irq: usize) {
+    let sample = (get_time() as u64) ^ ((irq as u64) << 48);
+    mix(&sample.to_le_bytes(), 1);
+}
+
+/// 混合硬件随机数发生器的输出
+pub fn add_hwgenerator_randomness(data: &[u8], entropy_bits: usize) {
+    mix(data, entropy_bits);
+}
+
+/// 主动测量一轮计时抖动
+fn add_jitter_randomness() {
+    let mut samples = [0u8; JITTER_SAMPLES];
+    let mut last = get_time();
+    let mut acc = 0u64;
+    for sample in samples.iter_mut() {
+        // 长度依赖上一次读数的忙等，放大执行时间的抖动
+        for _ in 0..(last & 0xf) + 1 {
+            acc = acc.rotate_left(5) ^ get_time() as u64;
+            core::hint::spin_loop();
+        }
+        let now = get_time();
+        *sample = (now.wrapping_sub(last) as u8) ^ (acc as u8);
+        last = now;
+    }
+    // 计数器抖动的熵很有限，保守地每 8 个采样记 1 位
+    mix(&samples, JITTER_SAMPLES / 8);
+}
+
+/// 从硬件随机数发生器补充熵，没有可用设备时返回 false
+fn add_hwrng_randomness() -> bool {
+    let mut buf = [0u8; HWRNG_CHUNK];
+    let n = crate::device::rng::hwrng_read(&mut buf);
+    if n > 0 {
+        add_hwgenerator_randomness(&buf[..n], n * 8);
+    }
+    n > 0
+}
+
+/// 初始化随机数生成器
+///
+/// 混合启动时的设备信息，并从硬件随机数发生器与计时抖动收集初始熵。
+pub fn init() {
+    let now = crate::kernel::time::realtime_now();
+    add_device_randomness(&now.tv_sec.to_le_bytes());
+    add_device_randomness(&now.tv_nsec.to_le_bytes());
+    add_device_randomness(&(get_time() as u64).to_le_bytes());
+
+    let hwrng = add_hwrng_randomness();
+    for _ in 0..BOOT_JITTER_ROUNDS {
+        if rng_is_initialized() {
+            break;
+        }
+        add_jitter_randomness();
+    }
+
+    if rng_is_initialized() {
+        pr_info!("[Random] crng initialized (hwrng: {})", hwrng);
+    } else {
+        pr_warn!("[Random] crng not yet initialized, /dev/random will block");
+    }
+}
+
+/// 随机数生成器是否已经播种
+pub fn rng_is_initialized() -> bool {
+    CRNG.lock().pool.is_seeded()
+}
+
+/// 输入池当前的熵估计（位）
+pub fn entropy_count() -> usize {
+    CRNG.lock().pool.get_entropy_count()
+}
+
+/// 立即用输入池重新生成密钥
+pub fn force_reseed() {
+    let mut crng = CRNG.lock();
+    crng.pool.reseed();
+    crng.last_reseed = ktime_get();
+}
+
+/// 生成随机字节，从不阻塞
+///
+/// 播种完成前的输出不具备密码学强度。
+pub fn get_random_bytes(buf: &mut [u8]) {
+    // 分段持锁，避免长时间关中断
+    for chunk in buf.chunks_mut(256) {
+        CRNG.lock().pool.fill_insecure(chunk);
+    }
+}
+
+/// 等待随机数生成器完成播种
+///
+/// 等待期间主动收集熵；被信号打断时返回 `EINTR`。
+pub fn wait_for_random_bytes() -> Result<(), i32> {
+    while !rng_is_initialized() {
+        if !add_hwrng_randomness() {
+            add_jitter_randomness();
+        }
+        let task = crate::kernel::current_task();
+        if crate::ipc::signal_interrupts_syscall(&task) {
+            return Err(uapi::errno::EINTR);
+        }
+        crate::kernel::yield_task();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 连续两次输出不同
+    #[test_case]
+    fn test_random_bytes_differ() {
+        let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+        get_random_bytes(&mut a);
+        get_random_bytes(&mut b);
+        assert!(a != b);
+    }
+}
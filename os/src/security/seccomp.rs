@@ -0,0 +1,309 @@
+//! seccomp 系统调用过滤
+//!
+//! 每个任务持有一条过滤器链（[`Seccomp`]）：新安装的过滤器指向之前的链，
+//! fork/clone 时子任务共享父任务的链，execve 时保留。
+//! 系统调用分发前由 [`secure_computing`] 对链上的每个过滤器求值，
+//! 取优先级最高的动作执行（KILL_PROCESS > KILL_THREAD > TRAP > ERRNO > TRACE > LOG > ALLOW）。
+//!
+//! 过滤器由 [`bpf`] crate 校验与解释执行，输入为 `struct seccomp_data`。
+
+use alloc::{sync::Arc, vec::Vec};
+
+use uapi::errno::{EACCES, EFAULT, EINVAL, ENOMEM, ENOSYS, EOPNOTSUPP};
+use uapi::filter::{SockFilter, SockFprog};
+use uapi::seccomp::*;
+use uapi::signal::{NUM_SIGKILL, NUM_SIGSYS};
+
+use crate::arch::constant::AUDIT_ARCH;
+use crate::arch::syscall::{SYS_EXIT, SYS_READ, SYS_RT_SIGRETURN, SYS_WRITE};
+use crate::arch::trap::TrapFrame;
+use crate::kernel::{Capabilities, SharedTask, TASK_MANAGER, TaskManagerTrait, current_task};
+use crate::util::user_buffer::{UserBuffer, copy_from_user, validate_user_ptr};
+use crate::{pr_info, pr_warn};
+
+/// 一条过滤器链上所有过滤器的指令总数上限（每个过滤器额外计 4 条，与 Linux 一致）
+const MAX_INSNS_PER_PATH: usize = 1 << 15;
+
+/// ERRNO 动作能返回的最大错误码
+const MAX_ERRNO: u32 = 4095;
+
+/// 已安装的 seccomp 过滤器，通过 `prev` 串成链
+#[derive(Debug)]
+pub struct SeccompFilter {
+    prog: Vec<SockFilter>,
+    /// 是否记录除 ALLOW 以外的动作（SECCOMP_FILTER_FLAG_LOG）
+    log: bool,
+    prev: Option<Arc<SeccompFilter>>,
+}
+
+impl SeccompFilter {
+    /// 链上从本过滤器开始的指令总数（含每个过滤器的 4 条额外计数）
+    fn path_len(&self) -> usize {
+        let mut len = 0;
+        let mut cur = Some(self);
+        while let Some(f) = cur {
+            len += f.prog.len() + 4;
+            cur = f.prev.as_deref();
+        }
+        len
+    }
+}
+
+/// 任务的 seccomp 状态
+#[derive(Debug, Clone, Default)]
+pub struct Seccomp {
+    /// SECCOMP_MODE_*
+    pub mode: u32,
+    /// 过滤器链的头（最近安装的过滤器）
+    pub filter: Option<Arc<SeccompFilter>>,
+}
+
+impl Seccomp {
+    /// 本任务的过滤器链是否为 `chain` 的前缀（即 `chain` 由本链追加过滤器得到）
+    fn is_ancestor_of(&self, chain: &Option<Arc<SeccompFilter>>) -> bool {
+        let Some(filter) = &self.filter else {
+            return true;
+        };
+        let mut cur = chain.as_ref();
+        while let Some(f) = cur {
+            if Arc::ptr_eq(f, filter) {
+                return true;
+            }
+            cur = f.prev.as_ref();
+        }
+        false
+    }
+}
+
+/// 动作的优先级比较值：与 Linux 一致，按有符号数比较动作部分，越小优先级越高
+fn action_rank(ret: u32) -> i32 {
+    (ret & SECCOMP_RET_ACTION_FULL) as i32
+}
+
+/// 对过滤器链求值，返回优先级最高的结果以及给出该结果的过滤器是否要求记录
+fn run_filters(filter: &SeccompFilter, data: &SeccompData) -> (u32, bool) {
+    let mut ret = SECCOMP_RET_ALLOW;
+    let mut log = false;
+    let mut cur = Some(filter);
+    while let Some(f) = cur {
+        let cur_ret = bpf::run(&f.prog, data);
+        if action_rank(cur_ret) < action_rank(ret) {
+            ret = cur_ret;
+            log = f.log;
+        }
+        cur = f.prev.as_deref();
+    }
+    (ret, log)
+}
+
+/// 以 SIGSYS / SIGKILL 终止当前线程或整个进程
+fn kill_current(task: SharedTask, sig: usize, whole_process: bool) -> ! {
+    let is_process = task.lock().is_process();
+    if whole_process || is_process {
+        crate::kernel::terminate_task(128 + sig);
+    }
+    TASK_MANAGER.lock().exit_task(task, (128 + sig) as i32);
+    crate::kernel::schedule();
+    unreachable!("seccomp: killed thread should not be scheduled again");
+}
+
+/// 在系统调用分发前执行 seccomp 检查
+///
+/// 返回 `true` 表示继续执行该系统调用；返回 `false` 表示系统调用被跳过，
+/// 返回值已写入 `frame`。KILL 类动作不会返回。
+pub fn secure_computing(frame: &mut TrapFrame) -> bool {
+    let task = current_task();
+    let (mode, filter, tid) = {
+        let t = task.lock();
+        (t.seccomp.mode, t.seccomp.filter.clone(), t.tid)
+    };
+    let nr = frame.syscall_id();
+
+    match mode {
+        SECCOMP_MODE_DISABLED => true,
+        SECCOMP_MODE_STRICT => {
+            if matches!(nr, SYS_READ | SYS_WRITE | SYS_EXIT | SYS_RT_SIGRETURN) {
+                return true;
+            }
+            pr_warn!(
+                "[seccomp] tid {} killed in strict mode, syscall {}",
+                tid,
+                nr
+            );
+            kill_current(task, NUM_SIGKILL, false)
+        }
+        _ => {
+            let Some(filter) = filter else {
+                return true;
+            };
+            let data = SeccompData {
+                nr: nr as i32,
+                arch: AUDIT_ARCH,
+                instruction_pointer: frame.get_sepc() as u64,
+                args: frame.syscall_args().map(|a| a as u64),
+            };
+            let (ret, log) = run_filters(&filter, &data);
+            let action = ret & SECCOMP_RET_ACTION_FULL;
+            if (log && action != SECCOMP_RET_ALLOW) || action == SECCOMP_RET_LOG {
+                pr_info!("[seccomp] tid {} syscall {} action {:#x}", tid, nr, action);
+            }
+            match action {
+                SECCOMP_RET_ALLOW | SECCOMP_RET_LOG => true,
+                SECCOMP_RET_ERRNO => {
+                    let errno = (ret & SECCOMP_RET_DATA).min(MAX_ERRNO);
+                    frame.set_a0((-(errno as isize)) as usize);
+                    false
+                }
+                SECCOMP_RET_TRAP => {
+                    TASK_MANAGER.lock().send_signal(task, NUM_SIGSYS);
+                    frame.set_a0((-(ENOSYS as isize)) as usize);
+                    false
+                }
+                // 没有跟踪者 / 用户态监听者时，与 Linux 一致返回 ENOSYS
+                SECCOMP_RET_TRACE | SECCOMP_RET_USER_NOTIF => {
+                    frame.set_a0((-(ENOSYS as isize)) as usize);
+                    false
+                }
+                SECCOMP_RET_KILL_THREAD => kill_current(task, NUM_SIGSYS, false),
+                // KILL_PROCESS 以及未知动作
+                _ => kill_current(task, NUM_SIGSYS, true),
+            }
+        }
+    }
+}
+
+/// 从用户空间复制一个 `struct sock_fprog` 描述的 cBPF 程序
+///
+/// seccomp 与 SO_ATTACH_FILTER 共用。只复制，不校验；`len` 为 0 或超过 BPF_MAXINSNS 时返回 EINVAL。
+pub fn copy_fprog_from_user(fprog: *const SockFprog) -> Result<Vec<SockFilter>, i32> {
+    let fprog = copy_from_user(fprog)?;
+    let len = fprog.len as usize;
+    if len == 0 || len > uapi::filter::BPF_MAXINSNS {
+        return Err(EINVAL);
+    }
+    let size = len * core::mem::size_of::<SockFilter>();
+    if fprog.filter.is_null() || !validate_user_ptr(fprog.filter.wrapping_add(len - 1)) {
+        return Err(EFAULT);
+    }
+    // SAFETY: 已检查整个指令数组位于用户地址空间
+    let bytes = unsafe { UserBuffer::new(fprog.filter as *mut u8, size).copy_from_user() };
+    Ok(bytes
+        .chunks_exact(core::mem::size_of::<SockFilter>())
+        .map(|c| SockFilter {
+            code: u16::from_ne_bytes([c[0], c[1]]),
+            jt: c[2],
+            jf: c[3],
+            k: u32::from_ne_bytes([c[4], c[5], c[6], c[7]]),
+        })
+        .collect())
+}
+
+/// 把当前任务切换到严格模式
+fn set_mode_strict() -> Result<(), i32> {
+    let task = current_task();
+    let mut t = task.lock();
+    if t.seccomp.mode == SECCOMP_MODE_FILTER {
+        return Err(EINVAL);
+    }
+    t.seccomp.mode = SECCOMP_MODE_STRICT;
+    Ok(())
+}
+
+/// 为当前任务安装一个过滤器
+///
+/// 成功返回 0；带 SECCOMP_FILTER_FLAG_TSYNC 且某个线程无法同步时返回该线程的 tid。
+fn set_mode_filter(flags: u32, fprog: *const SockFprog) -> Result<isize, i32> {
+    const KNOWN_FLAGS: u32 =
+        SECCOMP_FILTER_FLAG_TSYNC | SECCOMP_FILTER_FLAG_LOG | SECCOMP_FILTER_FLAG_SPEC_ALLOW;
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(EINVAL);
+    }
+
+    let task = current_task();
+    {
+        let t = task.lock();
+        if !t.no_new_privs && !t.credential.capabilities.has(Capabilities::SYS_ADMIN) {
+            return Err(EACCES);
+        }
+        if t.seccomp.mode == SECCOMP_MODE_STRICT {
+            return Err(EINVAL);
+        }
+    }
+
+    let prog = copy_fprog_from_user(fprog)?;
+    bpf::check_seccomp(&prog).map_err(|_| EINVAL)?;
+
+    let mut t = task.lock();
+    let prev = t.seccomp.filter.clone();
+    let filter = Arc::new(SeccompFilter {
+        prog,
+        log: flags & SECCOMP_FILTER_FLAG_LOG != 0,
+        prev: prev.clone(),
+    });
+    if filter.path_len() > MAX_INSNS_PER_PATH {
+        return Err(ENOMEM);
+    }
+
+    if flags & SECCOMP_FILTER_FLAG_TSYNC != 0 {
+        let (pid, tid, no_new_privs) = (t.pid, t.tid, t.no_new_privs);
+        drop(t);
+        let threads = TASK_MANAGER.lock().get_task_cond(|th| th.lock().pid == pid);
+        // 其他线程的过滤器链必须是调用者链的前缀，才能被同步为新链
+        for th in threads.iter() {
+            let th = th.lock();
+            if th.tid != tid
+                && (th.seccomp.mode == SECCOMP_MODE_STRICT || !th.seccomp.is_ancestor_of(&prev))
+            {
+                return Ok(th.tid as isize);
+            }
+        }
+        for th in threads {
+            let mut th = th.lock();
+            th.seccomp = Seccomp {
+                mode: SECCOMP_MODE_FILTER,
+                filter: Some(filter.clone()),
+            };
+            th.no_new_privs |= no_new_privs;
+        }
+        return Ok(0);
+    }
+
+    t.seccomp = Seccomp {
+        mode: SECCOMP_MODE_FILTER,
+        filter: Some(filter),
+    };
+    Ok(0)
+}
+
+/// seccomp(2) / prctl(PR_SET_SECCOMP) 的公共实现
+pub fn do_seccomp(op: u32, flags: u32, args: usize) -> isize {
+    let ret = match op {
+        SECCOMP_SET_MODE_STRICT => {
+            if flags != 0 || args != 0 {
+                Err(EINVAL)
+            } else {
+                set_mode_strict().map(|_| 0)
+            }
+        }
+        SECCOMP_SET_MODE_FILTER => set_mode_filter(flags, args as *const SockFprog),
+        SECCOMP_GET_ACTION_AVAIL => {
+            let action = args as *const u32;
+            if flags != 0 {
+                Err(EINVAL)
+            } else {
+                match copy_from_user(action)? {
+                    SECCOMP_RET_KILL_PROCESS
+                    | SECCOMP_RET_KILL_THREAD
+                    | SECCOMP_RET_TRAP
+                    | SECCOMP_RET_ERRNO
+                    | SECCOMP_RET_TRACE
+                    | SECCOMP_RET_LOG
+                    | SECCOMP_RET_ALLOW => Ok(0),
+                    _ => Err(EOPNOTSUPP),
+                }
+            }
+        }
+        _ => Err(EINVAL),
+    };
+    ret.unwrap_or_else(|e| -(e as isize))
+}
@@ -0,0 +1,18 @@
+//! 锁顺序表中各层级的锁类
+//!
+//! 与 [`crate::sync`] 模块文档中的锁顺序表一一对应，层级编号相同。
+//! 开启 `lockdep` 特性后，持有高层级编号的锁时获取低层级编号的锁会通过 klog 报告。
+
+use sync::LockClass;
+
+/// 层级 1：全局任务管理器 `TASK_MANAGER`
+pub static TASK_MANAGER: LockClass = LockClass::with_level("task_manager", 1);
+
+/// 层级 2：等待队列内部锁 `WaitQueue.lock`
+pub static WAIT_QUEUE: LockClass = LockClass::with_level("wait_queue", 2);
+
+/// 层级 3：Per-CPU 调度器
+pub static SCHEDULER: LockClass = LockClass::with_level("scheduler", 3);
+
+/// 层级 4：单个任务实例 `SpinLock<TaskStruct>`
+pub static TASK: LockClass = LockClass::with_level("task", 4);
@@ -1,7 +1,7 @@
 //! 同步原语
 //!
 //! 向其它内核模块提供基本的锁和同步原语
-//! 包括自旋锁、读写锁、票号锁、中断保护、抢占控制、Per-CPU 等
+//! 包括自旋锁、读写锁、顺序锁、票号锁、中断保护、抢占控制、Per-CPU 等
 //!
 //! # 锁顺序（Lock Ordering）与死锁预防
 //!
@@ -22,18 +22,32 @@
 //! 注意：
 //! - CPU 本地状态访问依赖 `PreemptGuard`（用于防止任务迁移），它不是“锁”，但如果需要同时使用
 //!   `PreemptGuard` 与多把锁，通常建议先进入 `PreemptGuard`，再按上述顺序获取其它锁。
+//! - 会被中断处理程序访问的数据使用 `SpinLockIrq`，只在任务上下文访问的数据使用 `SpinLock`。
+//!   调试构建中，持有 `SpinLockIrq` 时调度或睡眠、在中断开启的中断上下文里获取它都会 panic。
 //! - 若不确定某个调用是否会隐式获取其它锁（例如 wait/schedule/wake 路径），优先把“取引用/取快照”
 //!   与“持锁操作”拆开，尽量缩短持锁时间。
+//!
+//! 开启 `lockdep` 特性后，上表中使用 [`lock_class`] 锁类创建的锁会在运行时检查获取顺序，
+//! 违反层级、与已观察到的顺序相反或递归获取时通过 klog 报告。
+//! 开启 `lock-stat` 特性后，这些锁类的获取与竞争次数可以在 `/proc/lock_stat` 查看。
 
+pub mod lock_class;
 mod mutex;
 mod per_cpu;
+mod sched_ops;
 
 pub use mutex::*;
 pub use per_cpu::PerCpu;
+pub use sched_ops::init_sync_sched_ops;
 
 // 从 sync crate re-export
 pub use sync::{
-    IntrGuard, PreemptGuard, RawSpinLock, RawSpinLockGuard, RawSpinLockWithoutGuard, RwLock,
-    RwLockReadGuard, RwLockWriteGuard, SpinLock, SpinLockGuard, TicketLock, TicketLockGuard,
-    preempt_disable, preempt_disabled, preempt_enable,
+    Completion, Condvar, CondvarGuard, IntrGuard, LockClass, PercpuRef, PreemptGuard, RawSpinLock,
+    RawSpinLockGuard, RawSpinLockWithoutGuard, Rcu, RcuReadGuard, RcuRef, RcuWriteGuard, RwLock,
+    RwLockFairness, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard, SeqLock,
+    SeqLockWriteGuard, SpinLock, SpinLockGuard, SpinLockIrq, SpinLockIrqGuard, TicketLock,
+    TicketLockGuard, WaitOptions, WaitQueue, WaitResult, call_rcu, in_interrupt, irq_enter,
+    irq_exit, irq_locks_held, might_sleep, preempt_disable, preempt_disabled, preempt_enable,
+    rcu_note_quiescent_state, rcu_process_callbacks, rcu_read_lock, rcu_read_unlock, rcu_tick,
+    synchronize_rcu,
 };
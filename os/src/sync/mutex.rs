@@ -3,7 +3,7 @@ use core::cell::UnsafeCell;
 use core::sync::atomic::{AtomicBool, Ordering};
 
 use crate::kernel::{WaitQueue, current_task, yield_task};
-use sync::{RawSpinLock, RawSpinLockGuard, SpinLock};
+use sync::{CondvarGuard, RawSpinLock, RawSpinLockGuard, SpinLock};
 
 /// 互斥锁
 pub struct Mutex<T> {
@@ -70,6 +70,19 @@ impl<T> core::ops::DerefMut for MutexGuard<'_, T> {
     }
 }
 
+impl<'a, T> CondvarGuard<'a> for MutexGuard<'a, T> {
+    type Lock = Mutex<T>;
+
+    fn unlock(self) -> &'a Mutex<T> {
+        // Safety: 保护器由 `&'a Mutex<T>` 创建
+        unsafe { &*self.mutex }
+    }
+
+    fn relock(lock: &'a Mutex<T>) -> Self {
+        lock.lock()
+    }
+}
+
 impl<T> Drop for MutexGuard<'_, T> {
     fn drop(&mut self) {
         // 仍持有 _spin，自然是互斥的
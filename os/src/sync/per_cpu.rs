@@ -1,6 +1,11 @@
 //! Per-CPU 变量机制
 //!
 //! 允许每个 CPU 维护独立的数据副本，避免锁竞争。
+//!
+//! 数据副本在创建时按 CPU 数量动态分配，而不是按 `MAX_CPU_COUNT` 静态分配，因此既可以用作全局
+//! `static`，也可以嵌入到运行时创建的对象中（例如每个对象一份的 per-CPU 计数器）。
+//! 分配了副本的 CPU 不一定在线：[`PerCpu::for_each_cpu`] 只访问在线 CPU，
+//! 离线 CPU 上残留的数据可以通过 [`PerCpu::get_mut_of`] 取走（例如归还 per-CPU 缓存的物理页帧）。
 
 use alloc::vec::Vec;
 use core::cell::UnsafeCell;
@@ -132,6 +137,54 @@ impl<T> PerCpu<T> {
         // SAFETY: cpu_id 已检查有效性
         unsafe { &*self.data[cpu_id].get() }
     }
+
+    /// 获取指定在线 CPU 的数据（只读），CPU 离线或没有分配副本时返回 `None`
+    #[inline]
+    pub fn get_of_online(&self, cpu_id: usize) -> Option<&T> {
+        if !crate::kernel::cpu_online(cpu_id) {
+            return None;
+        }
+        // SAFETY: 下标有效时元素来自 UnsafeCell，只读访问
+        self.data.get(cpu_id).map(|slot| unsafe { &*slot.get() })
+    }
+
+    /// 获取指定 CPU 的数据（可变）
+    ///
+    /// # Safety
+    ///
+    /// 调用者必须确保没有其他引用指向同一数据，通常意味着该 CPU 已离线（或尚未上线），
+    /// 且没有其它 CPU 同时访问它的数据。
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_mut_of(&self, cpu_id: usize) -> &mut T {
+        core::assert!(cpu_id < self.data.len(), "Invalid CPU ID");
+        // SAFETY: 调用者保证独占访问
+        unsafe { &mut *self.data[cpu_id].get() }
+    }
+
+    /// 分配了数据副本的 CPU 数量（包括离线 CPU）
+    #[inline]
+    pub fn possible_cpus(&self) -> usize {
+        self.data.len()
+    }
+
+    /// 按 CPU ID 升序访问每个在线 CPU 的数据
+    ///
+    /// 用于汇总 per-CPU 统计、遍历 per-CPU 队列等跨核只读访问。
+    pub fn for_each_cpu<F: FnMut(usize, &T)>(&self, mut f: F) {
+        for cpu_id in 0..self.data.len() {
+            if crate::kernel::cpu_online(cpu_id) {
+                f(cpu_id, self.get_of(cpu_id));
+            }
+        }
+    }
+
+    /// 按 CPU ID 升序访问每个分配了数据副本的 CPU（包括离线 CPU）的数据
+    pub fn for_each_possible_cpu<F: FnMut(usize, &T)>(&self, mut f: F) {
+        for cpu_id in 0..self.data.len() {
+            f(cpu_id, self.get_of(cpu_id));
+        }
+    }
 }
 
 // SAFETY: PerCpu<T> 可以在线程间传递，因为每个 CPU 访问不同的数据
@@ -172,4 +225,40 @@ mod tests {
         *value = 100;
         assert!(*per_cpu.get_mut() == 100);
     }
+
+    #[test_case]
+    fn test_per_cpu_for_each_cpu() {
+        let per_cpu = PerCpu::new_with_id(|cpu_id| cpu_id);
+        let mut visited = 0;
+        let mut last = None;
+        per_cpu.for_each_cpu(|cpu_id, value| {
+            assert!(*value == cpu_id);
+            assert!(crate::kernel::cpu_online(cpu_id));
+            assert!(last.is_none_or(|last| last < cpu_id));
+            last = Some(cpu_id);
+            visited += 1;
+        });
+        // 当前 CPU 一定在线
+        assert!(visited >= 1);
+
+        let mut possible = 0;
+        per_cpu.for_each_possible_cpu(|_, _| possible += 1);
+        assert!(possible == per_cpu.possible_cpus());
+        assert!(visited <= possible);
+    }
+
+    #[test_case]
+    fn test_per_cpu_offline_slot() {
+        let per_cpu = PerCpu::new_with_id_and_count(crate::config::MAX_CPU_COUNT, |_| 1usize);
+        // 超出已上线范围的 CPU 不可通过 get_of_online 访问，但数据仍可被取走
+        let offline = (0..per_cpu.possible_cpus()).find(|&id| !crate::kernel::cpu_online(id));
+        if let Some(cpu_id) = offline {
+            assert!(per_cpu.get_of_online(cpu_id).is_none());
+            let drained = core::mem::take(unsafe { per_cpu.get_mut_of(cpu_id) });
+            assert!(drained == 1);
+            assert!(*per_cpu.get_of(cpu_id) == 0);
+        }
+        assert!(per_cpu.get_of_online(per_cpu.possible_cpus()).is_none());
+        assert!(per_cpu.get_of_online(0) == Some(&1));
+    }
 }
@@ -0,0 +1,87 @@
+//! sync crate 的调度操作
+//!
+//! 为 [`sync::Condvar`]、[`sync::WaitQueue`] 与 [`sync::synchronize_rcu`] 提供睡眠、唤醒与让出 CPU：任务句柄是 `Arc::into_raw` 得到的 [`SharedTask`] 指针。
+
+use alloc::sync::Arc;
+
+use crate::kernel::hrtimer::ktime_get;
+use crate::kernel::{
+    SharedTask, TaskStruct, current_task, sleep_task_with_block, wake_task_at, wake_up_with_block,
+    yield_task,
+};
+use crate::sync::SpinLock;
+
+struct SyncSchedOps;
+
+/// 借用句柄对应的任务（不消耗句柄持有的引用）
+///
+/// # Safety
+/// `task` 必须是 `current_task` 返回且尚未释放的句柄
+unsafe fn task_of(task: usize) -> SharedTask {
+    let ptr = task as *const SpinLock<TaskStruct>;
+    unsafe {
+        Arc::increment_strong_count(ptr);
+        Arc::from_raw(ptr)
+    }
+}
+
+impl sync::SchedOps for SyncSchedOps {
+    fn current_task(&self) -> usize {
+        Arc::into_raw(current_task()) as usize
+    }
+
+    fn release_task(&self, task: usize) {
+        // Safety: 句柄由 current_task 创建，这里归还它持有的引用
+        drop(unsafe { Arc::from_raw(task as *const SpinLock<TaskStruct>) });
+    }
+
+    fn block(&self, task: usize) {
+        // Safety: 调用者持有句柄
+        sleep_task_with_block(unsafe { task_of(task) }, false);
+    }
+
+    fn block_interruptible(&self, task: usize) {
+        // Safety: 调用者持有句柄
+        sleep_task_with_block(unsafe { task_of(task) }, true);
+    }
+
+    fn signal_pending(&self, task: usize) -> bool {
+        // Safety: 调用者持有句柄
+        crate::ipc::signal_interrupts_syscall(&unsafe { task_of(task) })
+    }
+
+    fn wake(&self, task: usize) {
+        // Safety: 调用者持有句柄
+        wake_up_with_block(unsafe { task_of(task) });
+    }
+
+    fn schedule(&self, task: usize, deadline: Option<u64>) {
+        match deadline {
+            Some(deadline) => {
+                // Safety: 调用者持有句柄
+                let timer = wake_task_at(unsafe { task_of(task) }, deadline);
+                yield_task();
+                timer.cancel();
+            }
+            None => yield_task(),
+        }
+    }
+
+    fn yield_now(&self) {
+        yield_task();
+    }
+
+    fn now(&self) -> u64 {
+        ktime_get()
+    }
+}
+
+static SYNC_SCHED_OPS: SyncSchedOps = SyncSchedOps;
+
+/// 初始化 sync crate 的调度操作
+///
+/// # Safety
+/// 必须在单线程环境下调用，且只能调用一次
+pub unsafe fn init_sync_sched_ops() {
+    unsafe { sync::register_sched_ops(&SYNC_SCHED_OPS) };
+}
@@ -0,0 +1,133 @@
+//! 内核微基准（kbench）
+//!
+//! 仅在 `cfg(test)` 且启用 `kbench` feature 时编译。基准通过
+//! `test_support::bench_case!` 声明，与普通 `#[test_case]` 一起由 [`test_runner`](super::test_runner)
+//! 收集执行，结果以 `[kbench] name=... iters=... ...` 行输出到控制台，供脚本解析。
+//!
+//! 运行方式：`make test KBENCH=1`（即 `cargo test --features kbench`）。
+//!
+//! 跨核争用基准 `kbench_spinlock_contended` 需要争抢线程在另一个 CPU 上运行；测试入口目前在
+//! 启动从核之前执行，因此该基准在 `make test` 下总是以 `no-peer-cpu` 跳过，而不是给出单核数据。
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use test_support::bench::Bencher;
+use test_support::bench_case;
+
+use crate::arch::timer::{clock_freq, get_time};
+use crate::kernel::{cpu_online_mask, kthread_spawn};
+use crate::mm::frame_allocator::alloc_frame;
+use crate::sync::{RwLock, SpinLock, TicketLock};
+use crate::vfs::{File, PipeFile};
+
+fn spinlock_uncontended(b: &mut Bencher) {
+    let lock = SpinLock::new(0usize);
+    b.iter_batched(|| *lock.lock() += 1);
+}
+
+fn ticketlock_uncontended(b: &mut Bencher) {
+    let lock = TicketLock::new(0usize);
+    b.iter_batched(|| *lock.lock() += 1);
+}
+
+fn rwlock_read(b: &mut Bencher) {
+    let lock = RwLock::new(0usize);
+    b.iter_batched(|| *lock.read());
+}
+
+/// 锁已被持有时 try_lock 失败路径的开销（不涉及其它 CPU，不是真正的跨核争用）
+fn spinlock_try_lock_held(b: &mut Bencher) {
+    let lock = SpinLock::new(0usize);
+    let _guard = lock.lock();
+    b.iter_batched(|| lock.try_lock().is_none());
+}
+
+/// 跨核争用基准中双方争抢的锁
+static CONTENDED_LOCK: SpinLock<usize> = SpinLock::new(0);
+/// 争抢线程已开始运行
+static PEER_RUNNING: AtomicBool = AtomicBool::new(false);
+/// 通知争抢线程退出
+static PEER_STOP: AtomicBool = AtomicBool::new(false);
+
+fn contend_peer() {
+    PEER_RUNNING.store(true, Ordering::Release);
+    while !PEER_STOP.load(Ordering::Acquire) {
+        *CONTENDED_LOCK.lock() += 1;
+    }
+    PEER_RUNNING.store(false, Ordering::Release);
+}
+
+/// 另一个 CPU 上的内核线程不停加锁时，本核加锁的开销
+///
+/// 争抢线程由调度器选核；若 100ms 内未在其它 CPU 上跑起来（只有一个在线 CPU，
+/// 或被分到本核），则以 `no-peer-cpu` 跳过。
+fn spinlock_contended(b: &mut Bencher) {
+    if crate::kernel::try_current_task().is_none() {
+        b.skip("no-current-task");
+        return;
+    }
+    if cpu_online_mask().count_ones() < 2 {
+        b.skip("no-peer-cpu");
+        return;
+    }
+    PEER_STOP.store(false, Ordering::Release);
+    kthread_spawn(contend_peer);
+    let deadline = get_time() + clock_freq() / 10;
+    while !PEER_RUNNING.load(Ordering::Acquire) {
+        if get_time() >= deadline {
+            // 争抢线程被分到本核，本核让出之前它不会运行；让它一运行就退出
+            PEER_STOP.store(true, Ordering::Release);
+            b.skip("no-peer-cpu");
+            return;
+        }
+        core::hint::spin_loop();
+    }
+    b.iter_batched(|| *CONTENDED_LOCK.lock() += 1);
+    PEER_STOP.store(true, Ordering::Release);
+    while PEER_RUNNING.load(Ordering::Acquire) {
+        core::hint::spin_loop();
+    }
+}
+
+fn frame_alloc_free(b: &mut Bencher) {
+    b.iter(|| alloc_frame().expect("kbench: out of frames"));
+}
+
+fn pipe_throughput_4k(b: &mut Bencher) {
+    let (read_end, write_end) = PipeFile::create_pair();
+    let wbuf = [0x5Au8; 4096];
+    let mut rbuf = [0u8; 4096];
+    b.iter(|| {
+        let n = write_end.write(&wbuf).unwrap_or(0);
+        read_end.read(&mut rbuf[..n]).unwrap_or(0)
+    });
+}
+
+/// 测试环境下可能尚无当前任务，此时显式跳过
+fn syscall_getpid(b: &mut Bencher) {
+    if crate::kernel::try_current_task().is_none() {
+        b.skip("no-current-task");
+        return;
+    }
+    let mut frame = crate::arch::trap::TrapFrame::zero_init();
+    b.iter(|| crate::kernel::syscall::sys_getpid(&mut frame));
+}
+
+bench_case!(kbench_spinlock_uncontended, spinlock_uncontended);
+bench_case!(kbench_ticketlock_uncontended, ticketlock_uncontended);
+bench_case!(kbench_rwlock_read, rwlock_read);
+bench_case!(kbench_spinlock_try_lock_held, spinlock_try_lock_held);
+bench_case!(kbench_spinlock_contended, spinlock_contended);
+bench_case!(
+    kbench_frame_alloc_free,
+    iters = 1000,
+    warmup = 10,
+    frame_alloc_free
+);
+bench_case!(
+    kbench_pipe_throughput_4k,
+    iters = 1000,
+    warmup = 10,
+    pipe_throughput_4k
+);
+bench_case!(kbench_syscall_getpid, syscall_getpid);
@@ -1,5 +1,9 @@
 pub mod assert;
+#[cfg(all(test, feature = "kbench"))]
+mod bench;
 pub mod net_test;
+#[cfg(feature = "syscall-fuzz")]
+pub mod sysfuzz;
 use crate::arch::intr::{are_interrupts_enabled, disable_interrupts, enable_interrupts};
 
 /// 可运行的测试用例。
@@ -64,6 +68,19 @@ where
     }
 }
 
+/// 微基准：运行并输出一行 `[kbench]` 结果，不参与断言统计。
+#[cfg(feature = "kbench")]
+impl Testable for test_support::bench::BenchCase {
+    fn run(&self) -> bool {
+        crate::println!("\x1b[33mRunning bench: {}\x1b[0m", self.name());
+        match test_support::bench::BenchCase::run(self) {
+            Ok(result) => crate::println!("{}", result),
+            Err(skipped) => crate::println!("{}", skipped),
+        }
+        true
+    }
+}
+
 /// 测试运行器。它由测试框架自动调用，并传入一个包含所有测试的切片。
 #[cfg(test)]
 pub fn test_runner(tests: &[&dyn Testable]) {
@@ -96,6 +113,16 @@ pub fn test_runner(tests: &[&dyn Testable]) {
         }
     }
 
+    // 覆盖率报告（coverage feature）：逐行输出到串口，供 scripts/kcov_report.py 汇总
+    #[cfg(feature = "coverage")]
+    {
+        let mut report = alloc::string::String::new();
+        let _ = test_support::coverage::write_report(&mut report);
+        for line in report.lines() {
+            println!("{}", line);
+        }
+    }
+
     let failed_assertions = TEST_FAILED.load(Ordering::SeqCst);
     println!("\x1b[33m\n--- Test Summary ---\x1b[0m");
     println!(
@@ -0,0 +1,430 @@
+//! 系统调用模糊测试（sysfuzz）
+//!
+//! 仅在启用 `syscall-fuzz` feature 时编译。
+//! 按种子生成随机系统调用序列，直接经 `dispatch_syscall` 进入内核，
+//! 用于发现参数校验缺陷（越界长度、非法 fd、错误标志位等）。
+//!
+//! # 运行方式
+//!
+//! - `make run SYSFUZZ=1`：init 任务在文件系统就绪后调用 [`run_and_shutdown`]，
+//!   不进入用户态，结束后关机（有卡死时以失败状态退出）。此时中断已开启，看门狗生效。
+//! - `make test SYSFUZZ=1`：额外注册一个少量迭代的测试用例。测试模式下尚未开启中断，
+//!   看门狗不会触发，只用于快速回归。
+//!
+//! # 参数来源
+//!
+//! - fd：标准 fd、非法值，以及运行中 `openat`/`dup` 成功返回的 fd
+//! - 指针：空指针、暂存缓冲区（含非对齐偏移）、预置的路径字符串；
+//!   系统调用只接受用户地址，两者都放在映射到当前地址空间用户部分的暂存区中，结束时解除映射。
+//!   编译时设置 `SYSFUZZ_WILD=1` 会额外加入野指针（内核缺页即视为缺陷，会中止测试）
+//! - 长度 / 标志 / 整数：边界值与随机位组合
+//!
+//! # 复现
+//!
+//! 开始时打印 `[sysfuzz] seed=... iters=...`。用相同的种子重新运行即可得到相同的调用序列：
+//! `SYSFUZZ_SEED=0x1234 SYSFUZZ_ITERS=5000 make run SYSFUZZ=1`（两者均在编译时读取）。
+//!
+//! # 卡死检测
+//!
+//! 每次调用前启用 [`watchdog`]，超时后在时钟中断中打印种子、迭代序号与调用参数。
+//! 可能无限阻塞的系统调用（等待、睡眠、信号、进程控制等）不在调用表中。
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use mm::page_table::UniversalPTEFlag;
+
+use crate::arch::syscall::*;
+use crate::arch::trap::TrapFrame;
+use crate::config::PAGE_SIZE;
+use crate::kernel::{current_memory_space, watchdog};
+
+/// 默认种子
+const DEFAULT_SEED: u64 = 0x5EED_5A17_F022_0001;
+/// 默认迭代次数
+const DEFAULT_ITERS: u64 = 2000;
+/// 测试模式下的迭代次数
+#[cfg(test)]
+const TEST_ITERS: u64 = 200;
+/// 单次系统调用的卡死判定阈值（毫秒）
+const HANG_TIMEOUT_MS: usize = 5000;
+/// 动态 fd 语料上限
+const MAX_DYNAMIC_FDS: usize = 16;
+/// 暂存缓冲区大小
+const SCRATCH_SIZE: usize = 8192;
+/// 暂存区（缓冲区之后跟一页路径字符串）的大小
+const SCRATCH_MAP_SIZE: usize = SCRATCH_SIZE + PAGE_SIZE;
+/// 暂存区在用户地址空间中的建议位置，被占用时由 mmap 另选
+const SCRATCH_HINT: usize = 0x5000_0000;
+
+/// 参数类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arg {
+    /// 不使用的参数（置 0）
+    Unused,
+    /// 文件描述符
+    Fd,
+    /// 目录 fd（含 AT_FDCWD）
+    DirFd,
+    /// 缓冲区指针
+    Buf,
+    /// 路径字符串指针
+    Path,
+    /// 长度
+    Len,
+    /// 标志位
+    Flags,
+    /// 任意整数
+    Int,
+}
+
+/// 调用表项
+struct Spec {
+    nr: usize,
+    name: &'static str,
+    args: [Arg; 6],
+    /// 成功时返回值是否为新 fd
+    returns_fd: bool,
+}
+
+const fn spec(nr: usize, name: &'static str, args: &[Arg], returns_fd: bool) -> Spec {
+    let mut full = [Arg::Unused; 6];
+    let mut i = 0;
+    while i < args.len() {
+        full[i] = args[i];
+        i += 1;
+    }
+    Spec {
+        nr,
+        name,
+        args: full,
+        returns_fd,
+    }
+}
+
+use Arg::*;
+
+/// 可安全模糊的系统调用（不会无限阻塞或终止测试任务）
+static SPECS: &[Spec] = &[
+    spec(SYS_GETCWD, "getcwd", &[Buf, Len], false),
+    spec(SYS_DUP, "dup", &[Fd], true),
+    spec(SYS_DUP3, "dup3", &[Fd, Fd, Flags], true),
+    spec(SYS_FCNTL, "fcntl", &[Fd, Int, Int], false),
+    spec(SYS_IOCTL, "ioctl", &[Fd, Int, Buf], false),
+    spec(SYS_MKDIRAT, "mkdirat", &[DirFd, Path, Flags], false),
+    spec(SYS_UNLINKAT, "unlinkat", &[DirFd, Path, Flags], false),
+    spec(
+        SYS_FACCESSAT,
+        "faccessat",
+        &[DirFd, Path, Flags, Flags],
+        false,
+    ),
+    spec(SYS_OPENAT, "openat", &[DirFd, Path, Flags, Flags], true),
+    spec(SYS_CLOSE, "close", &[Fd], false),
+    spec(SYS_GETDENTS64, "getdents64", &[Fd, Buf, Len], false),
+    spec(SYS_LSEEK, "lseek", &[Fd, Int, Int], false),
+    spec(SYS_FTRUNCATE, "ftruncate", &[Fd, Len], false),
+    spec(SYS_READ, "read", &[Fd, Buf, Len], false),
+    spec(SYS_WRITE, "write", &[Fd, Buf, Len], false),
+    spec(SYS_PREAD64, "pread64", &[Fd, Buf, Len, Int], false),
+    spec(SYS_PWRITE64, "pwrite64", &[Fd, Buf, Len, Int], false),
+    spec(
+        SYS_READLINKAT,
+        "readlinkat",
+        &[DirFd, Path, Buf, Len],
+        false,
+    ),
+    spec(SYS_FSTATAT, "newfstatat", &[DirFd, Path, Buf, Flags], false),
+    spec(SYS_FSTAT, "fstat", &[Fd, Buf], false),
+    spec(SYS_STATX, "statx", &[DirFd, Path, Flags, Flags, Buf], false),
+    spec(SYS_FSYNC, "fsync", &[Fd], false),
+    spec(SYS_UNAME, "uname", &[Buf], false),
+    spec(SYS_GETPID, "getpid", &[], false),
+    spec(SYS_GETUID, "getuid", &[], false),
+    spec(SYS_UMASK, "umask", &[Flags], false),
+    spec(SYS_CLOCK_GETTIME, "clock_gettime", &[Int, Buf], false),
+    spec(SYS_GETTIMEOFDAY, "gettimeofday", &[Buf, Buf], false),
+    spec(SYS_GETRANDOM, "getrandom", &[Buf, Len, Flags], false),
+    spec(SYS_GETRLIMIT, "getrlimit", &[Int, Buf], false),
+    spec(SYS_SYSINFO, "sysinfo", &[Buf], false),
+    spec(SYS_TIMES, "times", &[Buf], false),
+    spec(
+        SYS_RT_SIGPROCMASK,
+        "rt_sigprocmask",
+        &[Int, Buf, Buf, Len],
+        false,
+    ),
+];
+
+/// 预置路径（NUL 结尾）
+static PATHS: &[&[u8]] = &[
+    b"/\0",
+    b".\0",
+    b"..\0",
+    b"/tmp\0",
+    b"/tmp/sysfuzz\0",
+    b"/dev/null\0",
+    b"/proc/self\0",
+    b"\0",
+    b"//////\0",
+    b"/nonexistent/a/b/c\0",
+];
+
+/// 当前调用的上下文，供看门狗报告
+static CUR_SEED: AtomicU64 = AtomicU64::new(0);
+static CUR_ITER: AtomicU64 = AtomicU64::new(0);
+static CUR_NR: AtomicUsize = AtomicUsize::new(0);
+static CUR_ARGS: [AtomicUsize; 6] = [const { AtomicUsize::new(0) }; 6];
+
+fn report_hang() {
+    crate::pr_err!(
+        "[sysfuzz] HANG seed={:#x} iter={} nr={} args=[{:#x}, {:#x}, {:#x}, {:#x}, {:#x}, {:#x}]",
+        CUR_SEED.load(Ordering::Relaxed),
+        CUR_ITER.load(Ordering::Relaxed),
+        CUR_NR.load(Ordering::Relaxed),
+        CUR_ARGS[0].load(Ordering::Relaxed),
+        CUR_ARGS[1].load(Ordering::Relaxed),
+        CUR_ARGS[2].load(Ordering::Relaxed),
+        CUR_ARGS[3].load(Ordering::Relaxed),
+        CUR_ARGS[4].load(Ordering::Relaxed),
+        CUR_ARGS[5].load(Ordering::Relaxed),
+    );
+}
+
+/// xorshift64* 伪随机数
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(if seed == 0 { DEFAULT_SEED } else { seed })
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+/// 模糊测试统计
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FuzzStats {
+    /// 成功（返回值 >= 0）次数
+    pub ok: u64,
+    /// 返回错误码次数
+    pub err: u64,
+    /// 卡死次数（看门狗触发）
+    pub hangs: u64,
+}
+
+struct Fuzzer {
+    rng: Rng,
+    fds: [isize; MAX_DYNAMIC_FDS],
+    nfds: usize,
+    wild: bool,
+    /// 暂存缓冲区的用户地址，作为"合法"用户缓冲区（内容无意义）
+    scratch: usize,
+    /// 复制到暂存区中的预置路径的用户地址
+    paths: Vec<usize>,
+}
+
+impl Fuzzer {
+    fn gen_fd(&mut self) -> usize {
+        const FIXED: [isize; 6] = [0, 1, 2, -1, 255, 1 << 20];
+        if self.nfds > 0 && self.rng.below(3) != 0 {
+            let fd = self.fds[self.rng.below(self.nfds)];
+            return fd as usize;
+        }
+        self.rng.pick(&FIXED) as usize
+    }
+
+    fn gen_arg(&mut self, kind: Arg) -> usize {
+        match kind {
+            Unused => 0,
+            Fd => self.gen_fd(),
+            DirFd => {
+                if self.rng.below(2) == 0 {
+                    uapi::fs::AT_FDCWD as isize as usize
+                } else {
+                    self.gen_fd()
+                }
+            }
+            Buf => match self.rng.below(if self.wild { 5 } else { 4 }) {
+                0 => 0,
+                1 => self.scratch,
+                2 => self.scratch + self.rng.below(SCRATCH_SIZE),
+                3 => self.scratch + SCRATCH_SIZE - 1,
+                _ => self.rng.pick(&[0xdead_0000usize, usize::MAX - 7, 0x10]),
+            },
+            Path => {
+                if self.rng.below(8) == 0 {
+                    self.gen_arg(Buf)
+                } else {
+                    self.rng.pick(&self.paths)
+                }
+            }
+            Len => match self.rng.below(5) {
+                0 => 0,
+                1 => 1,
+                2 => self.rng.below(SCRATCH_SIZE + 1),
+                3 => SCRATCH_SIZE * 4,
+                _ => self.rng.pick(&[usize::MAX, isize::MAX as usize, 1 << 31]),
+            },
+            Flags => match self.rng.below(3) {
+                0 => 0,
+                1 => 1 << self.rng.below(32),
+                _ => self.rng.next() as u32 as usize,
+            },
+            Int => match self.rng.below(4) {
+                0 => self.rng.below(16),
+                1 => usize::MAX,
+                2 => i32::MIN as usize,
+                _ => self.rng.next() as usize,
+            },
+        }
+    }
+
+    fn record_fd(&mut self, fd: isize) {
+        if fd < 0 {
+            return;
+        }
+        if self.nfds < MAX_DYNAMIC_FDS {
+            self.fds[self.nfds] = fd;
+            self.nfds += 1;
+        } else {
+            let slot = self.rng.below(MAX_DYNAMIC_FDS);
+            self.fds[slot] = fd;
+        }
+    }
+}
+
+/// 在当前地址空间映射暂存区并复制预置路径，返回暂存区起始地址与各路径的地址
+fn map_scratch() -> (usize, Vec<usize>) {
+    let space = current_memory_space();
+    let mut space = space.lock();
+    let base = space
+        .mmap(SCRATCH_HINT, SCRATCH_MAP_SIZE, UniversalPTEFlag::user_rw())
+        .expect("sysfuzz: failed to map scratch region");
+    let mut paths = Vec::with_capacity(PATHS.len());
+    let mut at = base + SCRATCH_SIZE;
+    for path in PATHS {
+        space
+            .write_bytes_at(at, path)
+            .expect("sysfuzz: failed to copy path");
+        paths.push(at);
+        at += path.len();
+    }
+    (base, paths)
+}
+
+/// 以给定种子运行 `iters` 次随机系统调用
+pub fn run(seed: u64, iters: u64) -> FuzzStats {
+    let (scratch, paths) = map_scratch();
+    let mut fuzzer = Fuzzer {
+        rng: Rng::new(seed),
+        fds: [0; MAX_DYNAMIC_FDS],
+        nfds: 0,
+        wild: option_env!("SYSFUZZ_WILD") == Some("1"),
+        scratch,
+        paths,
+    };
+    let mut stats = FuzzStats::default();
+    CUR_SEED.store(seed, Ordering::Relaxed);
+    crate::println!("[sysfuzz] seed={:#x} iters={}", seed, iters);
+
+    for iter in 0..iters {
+        let spec = &SPECS[fuzzer.rng.below(SPECS.len())];
+        let mut args = [0usize; 6];
+        for (slot, kind) in args.iter_mut().zip(spec.args) {
+            *slot = fuzzer.gen_arg(kind);
+        }
+
+        CUR_ITER.store(iter, Ordering::Relaxed);
+        CUR_NR.store(spec.nr, Ordering::Relaxed);
+        for (cur, arg) in CUR_ARGS.iter().zip(args) {
+            cur.store(arg, Ordering::Relaxed);
+        }
+        crate::pr_debug!("[sysfuzz] #{} {}{:x?}", iter, spec.name, args);
+
+        let mut frame = TrapFrame::zero_init();
+        frame.set_syscall(spec.nr, args);
+        watchdog::arm(HANG_TIMEOUT_MS, Some(report_hang));
+        crate::arch::syscall::dispatch_syscall(&mut frame);
+        watchdog::disarm();
+        if watchdog::fired() {
+            stats.hangs += 1;
+        }
+
+        let ret = frame.get_a0() as isize;
+        if ret >= 0 {
+            stats.ok += 1;
+            if spec.returns_fd {
+                fuzzer.record_fd(ret);
+            }
+        } else {
+            stats.err += 1;
+        }
+    }
+
+    // 关闭运行中打开的 fd，避免影响后续测试
+    for &fd in &fuzzer.fds[..fuzzer.nfds] {
+        if fd > 2 {
+            let mut frame = TrapFrame::zero_init();
+            frame.set_syscall(SYS_CLOSE, [fd as usize, 0, 0, 0, 0, 0]);
+            crate::arch::syscall::dispatch_syscall(&mut frame);
+        }
+    }
+    current_memory_space()
+        .lock()
+        .munmap(scratch, SCRATCH_MAP_SIZE)
+        .expect("sysfuzz: failed to unmap scratch region");
+
+    crate::println!(
+        "[sysfuzz] done seed={:#x} ok={} err={} hangs={}",
+        seed,
+        stats.ok,
+        stats.err,
+        stats.hangs
+    );
+    stats
+}
+
+fn parse_u64(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        _ => s.parse().ok(),
+    }
+}
+
+fn configured_seed() -> u64 {
+    option_env!("SYSFUZZ_SEED")
+        .and_then(parse_u64)
+        .unwrap_or(DEFAULT_SEED)
+}
+
+/// 以编译时配置的种子与迭代次数运行，然后关机（不会返回）
+pub fn run_and_shutdown() {
+    let iters = option_env!("SYSFUZZ_ITERS")
+        .and_then(parse_u64)
+        .unwrap_or(DEFAULT_ITERS);
+    let stats = run(configured_seed(), iters);
+    crate::arch::lib::sbi::shutdown(stats.hangs > 0);
+}
+
+#[cfg(test)]
+#[test_case]
+fn test_syscall_fuzz_smoke() {
+    let stats = run(configured_seed(), TEST_ITERS);
+    assert!(stats.ok + stats.err == TEST_ITERS);
+}
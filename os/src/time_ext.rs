@@ -5,8 +5,6 @@
 
 use uapi::time::TimeSpec;
 
-use crate::arch::timer::{clock_freq, get_time};
-
 /// 获取当前墙上时钟时间
 ///
 /// 返回自 Unix 纪元以来的时间（CLOCK_REALTIME）
@@ -19,6 +17,5 @@ pub fn timespec_now() -> TimeSpec {
 ///
 /// 返回自系统启动以来的时间（CLOCK_MONOTONIC）
 pub fn timespec_monotonic_now() -> TimeSpec {
-    let time = get_time();
-    TimeSpec::from_freq(time, clock_freq())
+    crate::kernel::hrtimer::ktime_to_timespec(crate::kernel::hrtimer::ktime_get())
 }
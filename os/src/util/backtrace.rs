@@ -0,0 +1,55 @@
+//! 基于帧指针的简易调用栈回溯
+//!
+//! 内核以 `-Cforce-frame-pointers=yes` 编译，RISC-V 与 LoongArch 的栈帧布局一致：
+//! `fp - 8` 处保存返回地址，`fp - 16` 处保存上一帧的帧指针。
+//! 回溯只打印返回地址，需配合 `addr2line` 离线符号化；
+//! 启用 KASLR 时打印的是减去偏移后的链接地址。
+
+use crate::arch::constant::SV39_TOP_HALF_BASE;
+use crate::earlyprintln;
+use crate::mm::kaslr::kaslr_slide;
+
+/// 最多打印的栈帧数
+const MAX_FRAMES: usize = 32;
+
+/// 读取当前帧指针
+#[inline(always)]
+fn current_fp() -> usize {
+    let fp: usize;
+    // SAFETY: 只读取帧指针寄存器
+    unsafe {
+        #[cfg(target_arch = "riscv64")]
+        core::arch::asm!("mv {}, s0", out(reg) fp);
+        #[cfg(target_arch = "loongarch64")]
+        core::arch::asm!("move {}, $fp", out(reg) fp);
+    }
+    fp
+}
+
+/// 打印当前调用栈
+#[inline(never)]
+pub fn print_backtrace() {
+    print_backtrace_from(current_fp());
+}
+
+/// 从帧指针 `fp` 开始打印调用栈（如陷阱帧中保存的 s0 / $fp）
+pub fn print_backtrace_from(mut fp: usize) {
+    earlyprintln!("Backtrace:");
+    for i in 0..MAX_FRAMES {
+        // 帧指针必须位于内核地址空间且 16 字节对齐
+        if fp < SV39_TOP_HALF_BASE + 16 || fp & 0xf != 0 {
+            break;
+        }
+        // SAFETY: fp 位于内核地址空间，按帧布局读取上一帧信息
+        let (ra, prev) = unsafe { (*((fp - 8) as *const usize), *((fp - 16) as *const usize)) };
+        if ra == 0 {
+            break;
+        }
+        earlyprintln!("  #{:<2} {:#018x}", i, ra.wrapping_sub(kaslr_slide()));
+        // 栈向低地址增长，上一帧必须在更高地址
+        if prev <= fp {
+            break;
+        }
+        fp = prev;
+    }
+}
@@ -1,6 +1,7 @@
 //! 工具函数模块
 #![allow(dead_code)]
 pub mod address;
+pub mod backtrace;
 pub mod ring_buffer;
 pub mod stdio;
 pub mod user_buffer;
@@ -4,38 +4,48 @@
 //! 例如，系统调用接口通常传入指向用户缓冲区的指针和长度
 //! 这个模块提供了对这类缓冲区的抽象和操作方法
 //!
+//! 所有用户内存访问都必须在 [`SumGuard`] 作用域内进行；系统调用读写单个用户结构体时
+//! 使用带地址检查的 [`copy_from_user`] / [`copy_to_user`]。
+//!
 //! XXX: 目前的实现直接依赖于 RISC-V 特权级的 SUM 位来允许内核访问用户空间
 //!      未来可能需要改进为通过页表映射等方式实现更通用的用户空间访问
 
 use alloc::vec::Vec;
 use core::ptr;
 
+use uapi::errno::EFAULT;
+
 use crate::arch::constant::{USER_BASE, USER_TOP};
 use crate::arch::trap::SumGuard;
 
-/// 向用户空间写入数据
-/// # 参数
-/// - `user_ptr`: 指向用户空间的指针
-/// - `value`: 要写入的数据
-/// # Safety
-/// 调用者必须确保 `user_ptr` 指向的内存是有效且可写的用户空间地址。
-pub unsafe fn write_to_user<T>(user_ptr: *mut T, value: T) {
-    let _guard = SumGuard::new();
-    unsafe {
-        ptr::write_volatile(user_ptr, value);
+/// 从用户空间读取一个值，先检查地址范围
+///
+/// 系统调用读取用户传入的结构体指针时应使用此函数，而不是直接解引用。
+/// 用户指针不要求按 `T` 对齐。
+/// # 返回值
+/// - 地址为空或越出用户空间时返回 `Err(EFAULT)`
+pub fn copy_from_user<T: Copy>(user_ptr: *const T) -> Result<T, i32> {
+    if !validate_user_ptr(user_ptr) {
+        return Err(EFAULT);
     }
+    let _guard = SumGuard::new();
+    // SAFETY: 地址范围已检查，未映射的页由缺页处理负责
+    Ok(unsafe { ptr::read_unaligned(user_ptr) })
 }
 
-/// 从用户空间读取数据
-/// # 参数
-/// - `user_ptr`: 指向用户空间的指针
+/// 向用户空间写入一个值，先检查地址范围
+///
+/// 与 [`copy_from_user`] 对应，用户指针不要求按 `T` 对齐。
 /// # 返回值
-/// - 读取到的数据
-/// # Safety
-/// 调用者必须确保 `user_ptr` 指向的内存是有效且可读的用户空间地址。
-pub unsafe fn read_from_user<T: Copy>(user_ptr: *const T) -> T {
+/// - 地址为空或越出用户空间时返回 `Err(EFAULT)`
+pub fn copy_to_user<T>(user_ptr: *mut T, value: T) -> Result<(), i32> {
+    if !validate_user_ptr_mut(user_ptr) {
+        return Err(EFAULT);
+    }
     let _guard = SumGuard::new();
-    unsafe { ptr::read_volatile(user_ptr) }
+    // SAFETY: 地址范围已检查，未映射的页由缺页处理负责
+    unsafe { ptr::write_unaligned(user_ptr, value) };
+    Ok(())
 }
 
 /// 用户缓冲区结构体
@@ -168,3 +178,18 @@ pub fn validate_user_ptr<T>(ptr: *const T) -> bool {
 pub fn validate_user_ptr_mut<T>(ptr: *mut T) -> bool {
     validate_user_ptr(ptr as *const T)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 空指针与内核地址必须在访问前被拒绝
+    #[test_case]
+    fn test_copy_user_rejects_bad_pointers() {
+        assert_eq!(copy_from_user(ptr::null::<u64>()), Err(EFAULT));
+        assert_eq!(copy_from_user((USER_TOP + 1) as *const u64), Err(EFAULT));
+        assert_eq!(copy_from_user((USER_TOP - 3) as *const u64), Err(EFAULT));
+        assert_eq!(copy_to_user(ptr::null_mut::<u32>(), 0), Err(EFAULT));
+        assert_eq!(copy_to_user((USER_TOP + 1) as *mut u32, 0), Err(EFAULT));
+    }
+}
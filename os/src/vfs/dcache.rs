@@ -0,0 +1,27 @@
+//! 目录项缓存接入内存回收
+//!
+//! 帧分配换出之后仍然失败时，[`mm::shrinker`] 要求全局 [`DENTRY_CACHE`] 回收未使用的目录项，
+//! 连带释放它们持有的 inode 与页缓存。
+
+use mm::shrinker::{Shrinker, register_shrinker};
+
+use super::DENTRY_CACHE;
+
+struct DentryShrinker;
+
+impl Shrinker for DentryShrinker {
+    fn count_objects(&self) -> usize {
+        DENTRY_CACHE.count_reclaimable()
+    }
+
+    fn scan_objects(&self, nr: usize) -> usize {
+        DENTRY_CACHE.shrink(nr)
+    }
+}
+
+static DENTRY_SHRINKER: DentryShrinker = DentryShrinker;
+
+/// 登记目录项缓存的收缩回调
+pub fn init_dentry_shrinker() {
+    register_shrinker(&DENTRY_SHRINKER);
+}
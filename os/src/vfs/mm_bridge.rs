@@ -3,8 +3,9 @@
 //! 为 os crate 的 Inode 和 File trait 实现 mm crate 的 MmInode 和 MmFile trait，
 //! 使得 VFS 文件可以用于内存映射。
 
+use crate::fs::ext4::Ext4Inode;
 use alloc::sync::Arc;
-use mm::{MmFile, MmInode};
+use mm::{MmFile, MmInode, PageCache};
 use vfs::{File, Inode};
 
 /// Inode 的包装类型，实现 MmInode trait
@@ -18,6 +19,19 @@ impl MmInode for InodeWrapper {
     fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize, isize> {
         self.0.write_at(offset, buf).map_err(|e| e.to_errno())
     }
+
+    fn page_cache(&self) -> Option<Arc<PageCache>> {
+        self.0
+            .downcast_ref::<Ext4Inode>()
+            .map(|inode| inode.page_cache())
+    }
+
+    fn writeback(&self) -> Result<(), isize> {
+        match self.0.downcast_ref::<Ext4Inode>() {
+            Some(inode) => inode.writeback().map_err(|e| e.to_errno()),
+            None => Ok(()),
+        }
+    }
 }
 
 /// File 的包装类型，实现 MmFile trait
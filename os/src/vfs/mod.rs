@@ -2,6 +2,7 @@
 //!
 //! 此模块重新导出 vfs crate 的所有公共接口，并提供 os crate 特定的实现。
 
+mod dcache;
 mod mm_bridge;
 mod ops_impl;
 
@@ -14,6 +15,8 @@ pub use mm_bridge::{FileWrapper, InodeWrapper};
 // Re-export ops_impl for initialization
 pub use ops_impl::init_vfs_ops;
 
+pub use dcache::init_dentry_shrinker;
+
 use alloc::{vec, vec::Vec};
 
 /// 从指定路径加载 ELF 文件内容
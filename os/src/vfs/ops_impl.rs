@@ -2,14 +2,42 @@
 //!
 //! 此模块为 vfs crate 的 VfsOps 和 DeviceOps trait 提供 os crate 的具体实现。
 
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use device::console::FrameConsole;
+use lazy_static::lazy_static;
 use uapi::time::TimeSpec;
-use vfs::{CharDriver, Dentry, DeviceOps, VfsOps, chrdev_major};
+use vfs::{
+    CharDriver, Dentry, DeviceOps, FsCred, FsError, MountNamespace, Tty, VfsOps, chrdev_major,
+    console_minor, mem_minor, misc_minor,
+};
 
+use crate::arch::trap::SumGuard;
 use crate::config::DEFAULT_MAX_FDS;
+use crate::device::console::frame_console::FRAME_CONSOLE;
+use crate::device::rtc::{RtcDriver, rtc_time_from_epoch, rtc_time_to_epoch};
 use crate::device::serial::SerialDriver;
-use crate::device::{BLK_DRIVERS, SERIAL_DRIVERS};
+use crate::device::{BLK_DRIVERS, RTC_DRIVERS, SERIAL_DRIVERS};
+use crate::security::random;
+use crate::sync::{WaitOptions, WaitQueue, WaitResult, lock_class};
 use crate::time_ext::timespec_now;
+use crate::util::user_buffer::{UserBuffer, copy_from_user, copy_to_user};
+
+/// 等待通道散列桶数量
+const WAIT_CHAN_BUCKETS: usize = 16;
+
+lazy_static! {
+    /// 等待通道散列到的等待队列，同一桶内的通道共享队列，被唤醒者自行重新检查条件
+    static ref WAIT_CHANS: Vec<WaitQueue> = (0..WAIT_CHAN_BUCKETS)
+        .map(|_| WaitQueue::with_class(&lock_class::WAIT_QUEUE))
+        .collect();
+}
+
+fn wait_chan_queue(chan: usize) -> &'static WaitQueue {
+    // 通道通常是对象地址，低位对齐部分无区分度
+    &WAIT_CHANS[(chan >> 4) % WAIT_CHAN_BUCKETS]
+}
 
 /// VFS 操作实现
 struct VfsOpsImpl;
@@ -23,6 +51,15 @@ impl VfsOps for VfsOpsImpl {
         crate::kernel::current_task().lock().fs.lock().root.clone()
     }
 
+    fn current_mnt_ns(&self) -> Option<Arc<MountNamespace>> {
+        // 启动早期尚无任务，此时使用初始命名空间
+        crate::kernel::try_current_task().map(|t| t.lock().mnt_ns.clone())
+    }
+
+    fn current_fs_cred(&self) -> Option<FsCred> {
+        crate::kernel::try_current_task().map(|_| crate::kernel::current_fs_cred())
+    }
+
     fn default_max_fds(&self) -> usize {
         DEFAULT_MAX_FDS
     }
@@ -31,26 +68,13 @@ impl VfsOps for VfsOpsImpl {
         timespec_now()
     }
 
-    fn enter_user_access(&self) {
-        #[cfg(target_arch = "riscv64")]
-        unsafe {
-            riscv::register::sstatus::set_sum();
-        }
-        #[cfg(target_arch = "loongarch64")]
-        {
-            // LoongArch 不需要特殊处理
-        }
+    fn enter_user_access(&self) -> bool {
+        SumGuard::new().into_raw()
     }
 
-    fn exit_user_access(&self) {
-        #[cfg(target_arch = "riscv64")]
-        unsafe {
-            riscv::register::sstatus::clear_sum();
-        }
-        #[cfg(target_arch = "loongarch64")]
-        {
-            // LoongArch 不需要特殊处理
-        }
+    fn exit_user_access(&self, was_enabled: bool) {
+        // SAFETY: was_enabled 来自配对的 enter_user_access
+        drop(unsafe { SumGuard::from_raw(was_enabled) });
     }
 
     fn console_getchar(&self) -> Option<u8> {
@@ -64,6 +88,82 @@ impl VfsOps for VfsOpsImpl {
     fn console_write_str(&self, s: &str) {
         crate::console::write_str(s);
     }
+
+    fn current_pgid(&self) -> u32 {
+        crate::kernel::try_current_task().map_or(0, |task| task.lock().pgid)
+    }
+
+    fn kill_pgrp(&self, pgid: u32, sig: usize) {
+        use crate::kernel::{TASK_MANAGER, TaskManagerTrait};
+
+        if pgid == 0 {
+            return;
+        }
+        let task_manager = TASK_MANAGER.lock();
+        let targets =
+            task_manager.get_task_cond(|t| t.lock().pgid == pgid && t.lock().is_process());
+        for task in targets {
+            task_manager.send_signal(task, sig);
+        }
+    }
+
+    fn current_pid(&self) -> u32 {
+        crate::kernel::try_current_task().map_or(0, |task| task.lock().pid)
+    }
+
+    fn current_sid(&self) -> u32 {
+        crate::kernel::try_current_task().map_or(0, |task| task.lock().sid)
+    }
+
+    fn pgrp_in_session(&self, pgid: u32, sid: u32) -> bool {
+        use crate::kernel::{TASK_MANAGER, TaskManagerTrait};
+
+        !TASK_MANAGER
+            .lock()
+            .get_task_cond(|t| {
+                let t = t.lock();
+                t.pgid == pgid && t.sid == sid && t.is_process()
+            })
+            .is_empty()
+    }
+
+    fn controlling_tty(&self) -> Option<Arc<Tty>> {
+        crate::kernel::try_current_task().and_then(|task| task.lock().ctty.clone())
+    }
+
+    fn set_controlling_tty(&self, tty: Option<Arc<Tty>>) {
+        if let Some(task) = crate::kernel::try_current_task() {
+            task.lock().ctty = tty;
+        }
+    }
+
+    fn wait_event(&self, chan: usize, deadline_ms: Option<i64>, cond: &dyn Fn() -> bool) -> bool {
+        use crate::kernel::hrtimer::ktime_get;
+
+        let task = crate::kernel::current_task();
+        if crate::ipc::signal_interrupts_syscall(&task) {
+            return false;
+        }
+
+        let mut options = WaitOptions::new().interruptible();
+        if let Some(deadline) = deadline_ms {
+            let now = timespec_now();
+            let remaining = deadline - (now.tv_sec * 1000 + now.tv_nsec / 1_000_000);
+            if remaining <= 0 {
+                return true;
+            }
+            options = options.deadline(ktime_get().saturating_add(remaining as u64 * 1_000_000));
+        }
+
+        // 条件检查与入队在等待队列的锁内原子完成，防止丢失唤醒
+        wait_chan_queue(chan).wait_event_with(options, cond) != WaitResult::Interrupted
+    }
+
+    fn wake_event(&self, chan: usize) {
+        wait_chan_queue(chan).wake_all();
+        // 终端、管道等的可读性变化同样需要通知 poll/select 的等待者
+        crate::kernel::syscall::io::wake_poll_waiters();
+    }
 }
 
 /// 设备操作实现
@@ -77,19 +177,35 @@ impl DeviceOps for DeviceOpsImpl {
         let min = minor(dev);
 
         match maj {
-            chrdev_major::TTY => {
-                // TTY 设备：ttyS0 的 minor 是 64，映射到 SERIAL_DRIVERS[0]
-                // 标准 Linux 中 ttyS* 的 minor 从 64 开始
-                let idx = if min >= 64 { min - 64 } else { min };
+            chrdev_major::TTY if min >= 64 => {
+                // ttyS<n>：minor 从 64 开始，对应 SERIAL_DRIVERS[n]
                 let drivers = SERIAL_DRIVERS.read();
-                let driver = drivers.get(idx as usize)?.clone();
+                let driver = drivers.get((min - 64) as usize)?.clone();
                 Some(Arc::new(SerialDriverWrapper(driver)))
             }
-            chrdev_major::CONSOLE => {
-                // console 设备：直接使用 SERIAL_DRIVERS[0]
-                let drivers = SERIAL_DRIVERS.read();
-                let driver = drivers.first()?.clone();
-                Some(Arc::new(SerialDriverWrapper(driver)))
+            chrdev_major::TTY => {
+                // tty<n>：帧缓冲控制台的虚拟终端，tty0 为前台虚拟终端
+                let console = FRAME_CONSOLE.read().clone()?;
+                if min as usize > console.count() {
+                    return None;
+                }
+                Some(Arc::new(VtWrapper {
+                    console,
+                    vt: (min as usize).checked_sub(1),
+                }))
+            }
+            chrdev_major::MISC if min == misc_minor::RTC => {
+                let rtc = RTC_DRIVERS.read().first()?.clone();
+                Some(Arc::new(RtcWrapper(rtc)))
+            }
+            chrdev_major::MEM if min == mem_minor::RANDOM || min == mem_minor::URANDOM => {
+                Some(Arc::new(RandomWrapper {
+                    blocking: min == mem_minor::RANDOM,
+                }))
+            }
+            chrdev_major::CONSOLE if min == console_minor::CONSOLE => {
+                // console 设备：启动参数 console= 选择的终端
+                self.get_chrdev_driver(self.console_device()?)
             }
             _ => None,
         }
@@ -100,8 +216,9 @@ impl DeviceOps for DeviceOpsImpl {
     }
 
     fn read_block(&self, idx: usize, block_id: usize, buf: &mut [u8]) -> bool {
-        let drivers = BLK_DRIVERS.read();
-        if let Some(driver) = drivers.get(idx) {
+        // 块 I/O 会睡眠，先取出驱动再离开 RCU 读临界区
+        let driver = BLK_DRIVERS.read().get(idx).cloned();
+        if let Some(driver) = driver {
             driver.read_block(block_id, buf)
         } else {
             false
@@ -109,8 +226,9 @@ impl DeviceOps for DeviceOpsImpl {
     }
 
     fn write_block(&self, idx: usize, block_id: usize, buf: &[u8]) -> bool {
-        let drivers = BLK_DRIVERS.read();
-        if let Some(driver) = drivers.get(idx) {
+        // 块 I/O 会睡眠，先取出驱动再离开 RCU 读临界区
+        let driver = BLK_DRIVERS.read().get(idx).cloned();
+        if let Some(driver) = driver {
             driver.write_block(block_id, buf)
         } else {
             false
@@ -125,6 +243,10 @@ impl DeviceOps for DeviceOpsImpl {
             0
         }
     }
+
+    fn console_device(&self) -> Option<u64> {
+        crate::device::console::console_device()
+    }
 }
 
 /// SerialDriver 到 CharDriver 的适配器
@@ -139,11 +261,153 @@ impl CharDriver for SerialDriverWrapper {
         self.0.write(data);
     }
 
+    fn pushes_input(&self) -> bool {
+        self.0.rx_irq_enabled()
+    }
+
     fn ioctl(&self, _request: u32, _arg: usize) -> Result<isize, i32> {
         Err(uapi::errno::ENOTTY)
     }
 }
 
+/// 帧缓冲虚拟终端到 CharDriver 的适配器
+struct VtWrapper {
+    console: Arc<FrameConsole>,
+    /// 虚拟终端编号，`None` 表示前台虚拟终端（tty0）
+    vt: Option<usize>,
+}
+
+impl VtWrapper {
+    fn vt(&self) -> usize {
+        self.vt.unwrap_or_else(|| self.console.active())
+    }
+}
+
+impl CharDriver for VtWrapper {
+    fn try_read(&self) -> Option<u8> {
+        self.console.read_byte(self.vt())
+    }
+
+    fn write(&self, data: &[u8]) {
+        self.console
+            .write_vt(self.vt(), &String::from_utf8_lossy(data));
+    }
+
+    fn ioctl(&self, _request: u32, _arg: usize) -> Result<isize, i32> {
+        Err(uapi::errno::ENOTTY)
+    }
+}
+
+/// RTC 到 CharDriver 的适配器（/dev/misc/rtc）
+struct RtcWrapper(Arc<dyn RtcDriver>);
+
+impl CharDriver for RtcWrapper {
+    fn try_read(&self) -> Option<u8> {
+        None
+    }
+
+    fn write(&self, _data: &[u8]) {}
+
+    fn ioctl(&self, request: u32, arg: usize) -> Result<isize, i32> {
+        use uapi::errno::{EACCES, EFAULT, EINVAL, EIO, ENOTTY};
+        use uapi::ioctl::{RTC_RD_TIME, RTC_SET_TIME, RtcTime};
+
+        if arg == 0 {
+            return Err(EFAULT);
+        }
+        match request {
+            RTC_RD_TIME => {
+                let tm = rtc_time_from_epoch(self.0.read_epoch());
+                copy_to_user(arg as *mut RtcTime, tm)?;
+                Ok(0)
+            }
+            RTC_SET_TIME => {
+                let privileged = crate::kernel::capable(crate::kernel::Capabilities::SYS_TIME);
+                if !privileged {
+                    return Err(EACCES);
+                }
+                let tm = copy_from_user(arg as *const RtcTime)?;
+                let epoch = rtc_time_to_epoch(&tm).ok_or(EINVAL)?;
+                if self.0.set_epoch(epoch) {
+                    Ok(0)
+                } else {
+                    Err(EIO)
+                }
+            }
+            _ => Err(ENOTTY),
+        }
+    }
+}
+
+/// 内核随机数生成器到 CharDriver 的适配器（/dev/random、/dev/urandom）
+struct RandomWrapper {
+    /// 为 true 时（/dev/random）在随机数生成器播种前阻塞读取
+    blocking: bool,
+}
+
+impl CharDriver for RandomWrapper {
+    fn try_read(&self) -> Option<u8> {
+        let mut byte = [0u8; 1];
+        random::get_random_bytes(&mut byte);
+        Some(byte[0])
+    }
+
+    fn read(&self, buf: &mut [u8], nonblock: bool) -> Result<usize, FsError> {
+        if self.blocking && !random::rng_is_initialized() {
+            if nonblock {
+                return Err(FsError::WouldBlock);
+            }
+            random::wait_for_random_bytes().map_err(|_| FsError::Interrupted)?;
+        }
+        random::get_random_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn write(&self, data: &[u8]) {
+        // 写入的数据只混合进输入池，不记熵
+        random::add_device_randomness(data);
+    }
+
+    fn ioctl(&self, request: u32, arg: usize) -> Result<isize, i32> {
+        use uapi::errno::{EFAULT, ENOTTY, EPERM};
+        use uapi::ioctl::{RNDADDENTROPY, RNDGETENTCNT, RNDRESEEDCRNG, RandPoolInfo};
+
+        let privileged = || crate::kernel::capable(crate::kernel::Capabilities::SYS_ADMIN);
+        match request {
+            RNDGETENTCNT => {
+                if arg == 0 {
+                    return Err(EFAULT);
+                }
+                let count = random::entropy_count() as core::ffi::c_int;
+                copy_to_user(arg as *mut core::ffi::c_int, count)?;
+                Ok(0)
+            }
+            RNDADDENTROPY => {
+                if !privileged() {
+                    return Err(EPERM);
+                }
+                if arg == 0 {
+                    return Err(EFAULT);
+                }
+                let info = copy_from_user(arg as *const RandPoolInfo)?;
+                let len = info.buf_size.max(0) as usize;
+                let data_ptr = (arg + core::mem::size_of::<RandPoolInfo>()) as *mut u8;
+                let data = unsafe { UserBuffer::new(data_ptr, len).copy_from_user() };
+                random::add_hwgenerator_randomness(&data, info.entropy_count.max(0) as usize);
+                Ok(0)
+            }
+            RNDRESEEDCRNG => {
+                if !privileged() {
+                    return Err(EPERM);
+                }
+                random::force_reseed();
+                Ok(0)
+            }
+            _ => Err(ENOTTY),
+        }
+    }
+}
+
 /// 全局 VFS 操作实例
 static VFS_OPS: VfsOpsImpl = VfsOpsImpl;
 
@@ -1,6 +1,7 @@
 use super::*;
 use crate::vfs::FileSystem;
 use alloc::string::String;
+use uapi::fcntl::ResolveFlags;
 
 // P1 重要功能测试
 
@@ -10,7 +11,7 @@ fn test_mount_fs() {
     let fs = create_test_fs();
 
     // 挂载到 /test
-    let result = MOUNT_TABLE.mount(
+    let result = current_mnt_ns().mount(
         fs.clone(),
         "/test",
         MountFlags::empty(),
@@ -19,7 +20,7 @@ fn test_mount_fs() {
     assert!(result.is_ok());
 
     // 查找挂载点
-    let mount = MOUNT_TABLE.find_mount("/test");
+    let mount = current_mnt_ns().find_mount("/test");
     assert!(mount.is_some());
 }
 
@@ -28,12 +29,12 @@ fn test_mount_list() {
     // 挂载文件系统
     let fs1 = create_test_fs();
 
-    MOUNT_TABLE
+    current_mnt_ns()
         .mount(fs1, "/mnt_test", MountFlags::empty(), None)
         .ok();
 
     // 列出挂载点
-    let mounts = MOUNT_TABLE.list_mounts();
+    let mounts = current_mnt_ns().list_mounts();
     // 至少应该有根文件系统
     assert!(mounts.len() >= 1);
 }
@@ -44,16 +45,16 @@ fn test_mount_list() {
 fn test_umount_fs() {
     // 创建文件系统并挂载
     let fs = create_test_fs();
-    MOUNT_TABLE
+    current_mnt_ns()
         .mount(fs, "/test_umount2", MountFlags::empty(), None)
         .ok();
 
     // 卸载
-    let result = MOUNT_TABLE.umount("/test_umount2");
+    let result = current_mnt_ns().umount("/test_umount2");
     assert!(result.is_ok());
 
     // 卸载后应该找不到原挂载点（可能会匹配到根挂载点，但不应该是 /test_umount2）
-    let mount = MOUNT_TABLE.find_mount("/test_umount2");
+    let mount = current_mnt_ns().find_mount("/test_umount2");
     if let Some(m) = mount {
         assert!(m.mount_path != "/test_umount2");
     }
@@ -70,28 +71,28 @@ fn test_overmount() {
     let fs2 = create_test_fs();
 
     // 在同一路径挂载两次
-    let result1 = MOUNT_TABLE.mount(fs1, "/overmount_test", MountFlags::empty(), None);
+    let result1 = current_mnt_ns().mount(fs1, "/overmount_test", MountFlags::empty(), None);
     assert!(result1.is_ok());
 
-    let result2 = MOUNT_TABLE.mount(fs2, "/overmount_test", MountFlags::empty(), None);
+    let result2 = current_mnt_ns().mount(fs2, "/overmount_test", MountFlags::empty(), None);
     assert!(result2.is_ok()); // 应该支持 overmount
 
     // 查找挂载点应该返回最新的
-    let mount = MOUNT_TABLE.find_mount("/overmount_test");
+    let mount = current_mnt_ns().find_mount("/overmount_test");
     assert!(mount.is_some());
 
     // 卸载一次，应该还能找到挂载点（下层的）
-    let umount_result = MOUNT_TABLE.umount("/overmount_test");
+    let umount_result = current_mnt_ns().umount("/overmount_test");
     assert!(umount_result.is_ok());
 
-    let mount_after = MOUNT_TABLE.find_mount("/overmount_test");
+    let mount_after = current_mnt_ns().find_mount("/overmount_test");
     assert!(mount_after.is_some()); // 应该还有下层挂载
 
     // 再卸载一次，这次应该彻底没有了
-    let umount_result2 = MOUNT_TABLE.umount("/overmount_test");
+    let umount_result2 = current_mnt_ns().umount("/overmount_test");
     assert!(umount_result2.is_ok());
 
-    let mount_final = MOUNT_TABLE.find_mount("/overmount_test");
+    let mount_final = current_mnt_ns().find_mount("/overmount_test");
     if let Some(m) = mount_final {
         assert!(m.mount_path != "/overmount_test");
     }
@@ -100,7 +101,7 @@ fn test_overmount() {
 #[test_case]
 fn test_umount_root_should_fail() {
     // 尝试卸载根文件系统应该失败
-    let result = MOUNT_TABLE.umount("/");
+    let result = current_mnt_ns().umount("/");
     assert!(result.is_err());
 }
 
@@ -109,7 +110,7 @@ fn test_umount_root_should_fail() {
 #[test_case]
 fn test_lookup_across_mount_point() {
     // 让测试可重复执行：如果该挂载点曾经残留，先尽力卸载干净。
-    while MOUNT_TABLE.umount("/mnt_lookup_test").is_ok() {}
+    while current_mnt_ns().umount("/mnt_lookup_test").is_ok() {}
 
     // 创建一个测试文件系统
     let fs = create_test_fs();
@@ -120,11 +121,11 @@ fn test_lookup_across_mount_point() {
     assert!(create_result.is_ok());
 
     // 挂载到 /mnt_lookup_test
-    let mount_result = MOUNT_TABLE.mount(fs.clone(), "/mnt_lookup_test", MountFlags::empty(), None);
+    let mount_result = current_mnt_ns().mount(fs.clone(), "/mnt_lookup_test", MountFlags::empty(), None);
     assert!(mount_result.is_ok());
 
     // 获取挂载点
-    let mount_point = MOUNT_TABLE.find_mount("/mnt_lookup_test");
+    let mount_point = current_mnt_ns().find_mount("/mnt_lookup_test");
     assert!(mount_point.is_some());
 
     if let Some(mp) = mount_point {
@@ -134,5 +135,135 @@ fn test_lookup_across_mount_point() {
     }
 
     // 清理
-    while MOUNT_TABLE.umount("/mnt_lookup_test").is_ok() {}
+    while current_mnt_ns().umount("/mnt_lookup_test").is_ok() {}
+}
+
+// P5 挂载命名空间测试
+
+#[test_case]
+fn test_copied_mnt_ns_is_isolated() {
+    let ns = current_mnt_ns().copy();
+    assert!(ns.find_mount("/").is_some());
+
+    // 在副本中挂载，当前命名空间不可见
+    ns.mount(create_test_fs(), "/mnt_ns_test", MountFlags::empty(), None)
+        .unwrap();
+    assert!(ns.find_mount("/mnt_ns_test").is_some());
+    assert!(
+        current_mnt_ns()
+            .list_mounts()
+            .iter()
+            .all(|(path, _)| path != "/mnt_ns_test")
+    );
+
+    // 丢弃副本不会卸载与当前命名空间共享的根文件系统
+    assert!(ns.umount("/mnt_ns_test").is_ok());
+    drop(ns);
+    assert!(current_mnt_ns().find_mount("/").is_some());
+    assert!(vfs_lookup("/").is_ok());
+}
+
+#[test_case]
+fn test_bind_mount_and_remount_read_only() {
+    while current_mnt_ns().umount("/mnt_bind_dst").is_ok() {}
+    while current_mnt_ns().umount("/mnt_bind_src").is_ok() {}
+
+    let fs = create_test_fs();
+    fs.root_inode()
+        .create("file", FileMode::from_bits_truncate(0o644))
+        .unwrap();
+    current_mnt_ns()
+        .mount(fs, "/mnt_bind_src", MountFlags::empty(), None)
+        .unwrap();
+    let dst = FileMode::S_IFDIR | FileMode::from_bits_truncate(0o755);
+    let _ = get_root_dentry().unwrap().inode.mkdir("mnt_bind_dst", dst);
+
+    // 绑定挂载后两条路径看到同一个 inode
+    let source = vfs_lookup("/mnt_bind_src").unwrap();
+    current_mnt_ns().bind(&source, "/mnt_bind_dst").unwrap();
+    let a = vfs_lookup("/mnt_bind_src/file").unwrap();
+    let b = vfs_lookup("/mnt_bind_dst/file").unwrap();
+    assert!(Arc::ptr_eq(&a.inode, &b.inode));
+
+    // 只把绑定挂载点改为只读
+    current_mnt_ns()
+        .remount("/mnt_bind_dst", MountFlags::READ_ONLY)
+        .unwrap();
+    assert!(matches!(mnt_want_write(&b), Err(FsError::ReadOnlyFs)));
+    assert!(mnt_want_write(&a).is_ok());
+
+    while current_mnt_ns().umount("/mnt_bind_dst").is_ok() {}
+    while current_mnt_ns().umount("/mnt_bind_src").is_ok() {}
+}
+
+// P6 受限路径解析测试 (openat2 RESOLVE_*)
+
+#[test_case]
+fn test_lookup_with_resolve_policy() {
+    while current_mnt_ns().umount("/mnt_resolve_test").is_ok() {}
+
+    // /mnt_resolve_test/{top, a/f, a/up -> ../top, a/abs -> /f}
+    let fs = create_test_fs();
+    let root = fs.root_inode();
+    root.create("top", FileMode::from_bits_truncate(0o644))
+        .unwrap();
+    let a = root
+        .mkdir("a", FileMode::S_IFDIR | FileMode::from_bits_truncate(0o755))
+        .unwrap();
+    a.create("f", FileMode::from_bits_truncate(0o644)).unwrap();
+    a.symlink("up", "../top").unwrap();
+    a.symlink("abs", "/f").unwrap();
+    current_mnt_ns()
+        .mount(fs, "/mnt_resolve_test", MountFlags::empty(), None)
+        .unwrap();
+    let base = vfs_lookup("/mnt_resolve_test/a").unwrap();
+    let f = vfs_lookup("/mnt_resolve_test/a/f").unwrap();
+    let lookup = |path: &str, flags: ResolveFlags| {
+        vfs_lookup_from_with(base.clone(), path, true, &ResolvePolicy::from_flags(flags))
+    };
+
+    // RESOLVE_BENEATH：任何逃出起始目录的解析都失败
+    assert!(lookup("f", ResolveFlags::BENEATH).is_ok());
+    assert!(matches!(
+        lookup("../top", ResolveFlags::BENEATH),
+        Err(FsError::CrossDevice)
+    ));
+    assert!(matches!(
+        lookup("/f", ResolveFlags::BENEATH),
+        Err(FsError::CrossDevice)
+    ));
+    assert!(matches!(
+        lookup("up", ResolveFlags::BENEATH),
+        Err(FsError::CrossDevice)
+    ));
+
+    // RESOLVE_IN_ROOT：起始目录视为根，".." 和绝对路径都被限制在其中
+    let g = lookup("/f", ResolveFlags::IN_ROOT).unwrap();
+    assert!(Arc::ptr_eq(&g.inode, &f.inode));
+    let g = lookup("../../f", ResolveFlags::IN_ROOT).unwrap();
+    assert!(Arc::ptr_eq(&g.inode, &f.inode));
+    let g = lookup("abs", ResolveFlags::IN_ROOT).unwrap();
+    assert!(Arc::ptr_eq(&g.inode, &f.inode));
+    assert!(matches!(
+        lookup("up", ResolveFlags::IN_ROOT),
+        Err(FsError::NotFound)
+    ));
+
+    // RESOLVE_NO_SYMLINKS：不跟随符号链接
+    assert!(matches!(
+        lookup("up", ResolveFlags::NO_SYMLINKS),
+        Err(FsError::TooManySymlinks)
+    ));
+    assert!(lookup("up", ResolveFlags::empty()).is_ok());
+
+    // RESOLVE_NO_XDEV：不能跨入其它挂载
+    let policy = ResolvePolicy::from_flags(ResolveFlags::NO_XDEV);
+    let rootd = get_root_dentry().unwrap();
+    assert!(matches!(
+        vfs_lookup_from_with(rootd, "mnt_resolve_test/top", true, &policy),
+        Err(FsError::CrossDevice)
+    ));
+    assert!(lookup("f", ResolveFlags::NO_XDEV).is_ok());
+
+    while current_mnt_ns().umount("/mnt_resolve_test").is_ok() {}
 }
@@ -0,0 +1,52 @@
+#!/usr/bin/env python3
+"""汇总内核测试输出中的 kcov 覆盖率报告。
+
+用法：
+    make test COVERAGE=1 2>&1 | tee /tmp/test.log
+    python3 scripts/kcov_report.py /tmp/test.log [--uncovered]
+"""
+from __future__ import annotations
+
+import argparse
+import re
+import sys
+from collections import defaultdict
+
+_LINE_RE = re.compile(r"\[kcov\] (\S+):(\d+) (\d+)")
+
+
+def main() -> int:
+    parser = argparse.ArgumentParser(description="Summarize [kcov] lines from a kernel test log")
+    parser.add_argument("log", help="serial log file ('-' for stdin)")
+    parser.add_argument("--uncovered", action="store_true", help="list points that were never hit")
+    args = parser.parse_args()
+
+    stream = sys.stdin if args.log == "-" else open(args.log, encoding="utf-8", errors="replace")
+    per_file: dict[str, list[tuple[int, int]]] = defaultdict(list)
+    with stream:
+        for raw in stream:
+            m = _LINE_RE.search(raw)
+            if m:
+                per_file[m.group(1)].append((int(m.group(2)), int(m.group(3))))
+
+    if not per_file:
+        print("no [kcov] lines found (was the kernel built with --features coverage?)")
+        return 1
+
+    total = hit = 0
+    for path in sorted(per_file):
+        points = sorted(per_file[path])
+        covered = sum(1 for _, n in points if n > 0)
+        total += len(points)
+        hit += covered
+        print(f"{covered:4d}/{len(points):<4d} {100.0 * covered / len(points):6.1f}%  {path}")
+        if args.uncovered:
+            for line, n in points:
+                if n == 0:
+                    print(f"            uncovered: {path}:{line}")
+    print(f"{hit:4d}/{total:<4d} {100.0 * hit / total:6.1f}%  TOTAL")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,53 @@
+#!/bin/sh
+# LTP 子集运行器（在内核中以 `ltp` feature 启动时由 init 执行）
+#
+# 对 /ltp/cases.txt 中的每个用例输出一行结构化结果：
+#   [ltp] case=<name> result=<PASS|FAIL|BROK|CONF|TIMEOUT> exit=<code> secs=<n>
+# 最后输出 `[ltp] done total=<n>` 并关机。结果由 test-support 的 ltp-check 解析。
+
+LTP_ROOT=/ltp
+CASE_TIMEOUT=${CASE_TIMEOUT:-60}
+
+export LTPROOT=$LTP_ROOT
+export TMPDIR=/tmp
+export PATH=$LTP_ROOT/bin:$PATH
+
+mkdir -p /tmp /proc /dev
+mount -t proc proc /proc 2>/dev/null
+mount -t tmpfs none /tmp 2>/dev/null
+mount -t tmpfs none /dev 2>/dev/null
+
+outcome() {
+    # LTP 退出码是位掩码：TFAIL=1 TBROK=2 TWARN=4 TCONF=32
+    code=$1
+    if [ "$code" -eq 0 ]; then echo PASS
+    elif [ "$code" -eq 124 ] || [ "$code" -eq 137 ]; then echo TIMEOUT
+    elif [ $((code & 2)) -ne 0 ]; then echo BROK
+    elif [ $((code & 1)) -ne 0 ]; then echo FAIL
+    elif [ $((code & 32)) -ne 0 ]; then echo CONF
+    else echo BROK
+    fi
+}
+
+total=0
+echo "[ltp] start"
+while read -r name _; do
+    case "$name" in ''|'#'*) continue ;; esac
+    total=$((total + 1))
+    if [ ! -x "$LTP_ROOT/bin/$name" ]; then
+        echo "[ltp] case=$name result=CONF exit=32 secs=0"
+        continue
+    fi
+    start=$(date +%s)
+    cd /tmp && timeout "$CASE_TIMEOUT" "$LTP_ROOT/bin/$name" > "/tmp/$name.log" 2>&1
+    code=$?
+    secs=$(( $(date +%s) - start ))
+    echo "[ltp] case=$name result=$(outcome $code) exit=$code secs=$secs"
+    if [ "$code" -ne 0 ]; then
+        sed "s/^/[ltp-log] $name: /" "/tmp/$name.log" | tail -n 20
+    fi
+done < "$LTP_ROOT/cases.txt"
+echo "[ltp] done total=$total"
+
+sync
+poweroff -f
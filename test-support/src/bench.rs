@@ -0,0 +1,467 @@
+//! 内核微基准框架（kbench）
+//!
+//! 以架构计时器的原始计数（RISC-V `time` / LoongArch `rdtime.d` / x86_64 TSC）为时基，
+//! 运行具名的微基准并输出机器可解析的结果行。
+//!
+//! # 使用方式
+//!
+//! 在启用了 `custom_test_frameworks` 的 crate 中用 [`bench_case!`](crate::bench_case)
+//! 声明基准，它会展开为一个带 `#[test_case]` 的 [`BenchCase`] 静态项，
+//! 由测试运行器收集；运行器对 `BenchCase` 调用 [`BenchCase::run`] 并打印结果：
+//!
+//! ```ignore
+//! test_support::bench_case!(spinlock_uncontended, |b| {
+//!     let lock = SpinLock::new(0usize);
+//!     b.iter(|| *lock.lock() += 1);
+//! });
+//! ```
+//!
+//! # 输出格式
+//!
+//! 每个基准输出一行，字段以空格分隔、`key=value` 形式出现，便于脚本提取：
+//!
+//! ```text
+//! [kbench] name=spinlock_uncontended iters=10000 total=123456 mean=12 min=10 max=97
+//! ```
+//!
+//! 所有时间单位均为计数器原始周期；换算为纳秒需要结合平台的计时器频率。
+//! 运行环境不满足条件的基准调用 [`Bencher::skip`]，输出 `skipped=<原因>` 而不是测量结果：
+//!
+//! ```text
+//! [kbench] name=syscall_getpid skipped=no-current-task
+//! ```
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// 结果行前缀
+pub const RESULT_PREFIX: &str = "[kbench]";
+
+/// 默认测量迭代次数
+pub const DEFAULT_ITERS: u64 = 10_000;
+
+/// 默认预热迭代次数
+pub const DEFAULT_WARMUP: u64 = 100;
+
+/// 自定义周期计数源（0 表示使用架构默认实现）
+static CYCLE_SOURCE: AtomicUsize = AtomicUsize::new(0);
+
+/// 注册自定义周期计数源
+///
+/// 内核可注册与调度器一致的时间源；未注册时使用 [`read_arch_cycles`]。
+pub fn set_cycle_source(source: fn() -> u64) {
+    CYCLE_SOURCE.store(source as usize, Ordering::Release);
+}
+
+/// 读取架构计时器的原始计数
+#[inline(always)]
+pub fn read_arch_cycles() -> u64 {
+    #[cfg(target_arch = "riscv64")]
+    {
+        let t: u64;
+        // SAFETY: 读取只读的 time CSR，无副作用
+        unsafe { core::arch::asm!("rdtime {}", out(reg) t, options(nomem, nostack)) };
+        t
+    }
+    #[cfg(target_arch = "loongarch64")]
+    {
+        let t: u64;
+        // SAFETY: 读取稳定计数器，无副作用
+        unsafe { core::arch::asm!("rdtime.d {}, $zero", out(reg) t, options(nomem, nostack)) };
+        t
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        #[allow(unused_unsafe)]
+        // SAFETY: rdtsc 在用户态与内核态均可执行
+        unsafe {
+            core::arch::x86_64::_rdtsc()
+        }
+    }
+    #[cfg(not(any(
+        target_arch = "riscv64",
+        target_arch = "loongarch64",
+        target_arch = "x86_64"
+    )))]
+    {
+        // 无可用计时器时退化为单调递增计数，仅保证结果格式正确
+        static FALLBACK: AtomicUsize = AtomicUsize::new(0);
+        FALLBACK.fetch_add(1, Ordering::Relaxed) as u64
+    }
+}
+
+/// 读取当前周期计数（优先使用注册的计数源）
+#[inline]
+pub fn cycles() -> u64 {
+    let source = CYCLE_SOURCE.load(Ordering::Acquire);
+    if source == 0 {
+        read_arch_cycles()
+    } else {
+        // SAFETY: 非零值只可能来自 set_cycle_source 存入的 fn() -> u64
+        let f: fn() -> u64 = unsafe { core::mem::transmute(source) };
+        f()
+    }
+}
+
+/// 单个基准的测量结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchResult<'a> {
+    /// 基准名称
+    pub name: &'a str,
+    /// 测量迭代次数
+    pub iters: u64,
+    /// 总周期数
+    pub total: u64,
+    /// 单次迭代最小周期数
+    pub min: u64,
+    /// 单次迭代最大周期数
+    pub max: u64,
+}
+
+impl BenchResult<'_> {
+    /// 单次迭代平均周期数
+    pub fn mean(&self) -> u64 {
+        self.total.checked_div(self.iters).unwrap_or(0)
+    }
+
+    /// 从结果行解析（与 `Display` 输出互逆）
+    ///
+    /// 非 kbench 结果行或字段缺失时返回 None。
+    pub fn parse(line: &str) -> Option<BenchResult<'_>> {
+        let rest = line.trim().strip_prefix(RESULT_PREFIX)?;
+        let (mut name, mut iters, mut total, mut min, mut max) = (None, None, None, None, None);
+        for field in rest.split_whitespace() {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "name" => name = Some(value),
+                "iters" => iters = value.parse().ok(),
+                "total" => total = value.parse().ok(),
+                "min" => min = value.parse().ok(),
+                "max" => max = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(BenchResult {
+            name: name?,
+            iters: iters?,
+            total: total?,
+            min: min?,
+            max: max?,
+        })
+    }
+}
+
+/// 被跳过的基准
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchSkipped<'a> {
+    /// 基准名称
+    pub name: &'a str,
+    /// 跳过原因（不含空白）
+    pub reason: &'a str,
+}
+
+impl BenchSkipped<'_> {
+    /// 从结果行解析（与 `Display` 输出互逆）
+    pub fn parse(line: &str) -> Option<BenchSkipped<'_>> {
+        let rest = line.trim().strip_prefix(RESULT_PREFIX)?;
+        let (mut name, mut reason) = (None, None);
+        for field in rest.split_whitespace() {
+            match field.split_once('=')? {
+                ("name", value) => name = Some(value),
+                ("skipped", value) => reason = Some(value),
+                _ => {}
+            }
+        }
+        Some(BenchSkipped {
+            name: name?,
+            reason: reason?,
+        })
+    }
+}
+
+impl fmt::Display for BenchSkipped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} name={} skipped={}",
+            RESULT_PREFIX, self.name, self.reason
+        )
+    }
+}
+
+impl fmt::Display for BenchResult<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} name={} iters={} total={} mean={} min={} max={}",
+            RESULT_PREFIX,
+            self.name,
+            self.iters,
+            self.total,
+            self.mean(),
+            self.min,
+            self.max
+        )
+    }
+}
+
+/// 基准执行器，由基准函数驱动测量
+pub struct Bencher {
+    iters: u64,
+    warmup: u64,
+    total: u64,
+    min: u64,
+    max: u64,
+    measured: u64,
+    skipped: Option<&'static str>,
+}
+
+impl Bencher {
+    /// 创建执行器
+    pub const fn new(iters: u64, warmup: u64) -> Self {
+        Self {
+            iters,
+            warmup,
+            total: 0,
+            min: u64::MAX,
+            max: 0,
+            measured: 0,
+            skipped: None,
+        }
+    }
+
+    /// 逐次测量闭包：先预热，再对每次迭代单独计时
+    ///
+    /// 适合单次耗时远大于计时开销的操作（如帧分配、系统调用）。
+    pub fn iter<R>(&mut self, mut f: impl FnMut() -> R) {
+        for _ in 0..self.warmup {
+            core::hint::black_box(f());
+        }
+        for _ in 0..self.iters {
+            let start = cycles();
+            core::hint::black_box(f());
+            let delta = cycles().wrapping_sub(start);
+            self.record(delta);
+        }
+    }
+
+    /// 批量测量：整体计时后均摊
+    ///
+    /// 适合单次耗时接近计时开销的操作（如无竞争加锁）；min/max 为均摊值。
+    pub fn iter_batched<R>(&mut self, mut f: impl FnMut() -> R) {
+        for _ in 0..self.warmup {
+            core::hint::black_box(f());
+        }
+        let start = cycles();
+        for _ in 0..self.iters {
+            core::hint::black_box(f());
+        }
+        let total = cycles().wrapping_sub(start);
+        let per_iter = total.checked_div(self.iters).unwrap_or(0);
+        self.total += total;
+        self.measured += self.iters;
+        self.min = self.min.min(per_iter);
+        self.max = self.max.max(per_iter);
+    }
+
+    /// 记录一次外部测得的耗时（用于需要自行计时的场景，如跨 CPU 的吞吐测试）
+    pub fn record(&mut self, delta: u64) {
+        self.total += delta;
+        self.measured += 1;
+        self.min = self.min.min(delta);
+        self.max = self.max.max(delta);
+    }
+
+    /// 标记基准因运行环境不满足条件而跳过，调用后基准函数应直接返回
+    ///
+    /// `reason` 写入结果行的 `skipped=` 字段，不能含空白。
+    pub fn skip(&mut self, reason: &'static str) {
+        self.skipped = Some(reason);
+    }
+
+    /// 设置的测量迭代次数
+    pub fn iters(&self) -> u64 {
+        self.iters
+    }
+
+    fn result<'a>(&self, name: &'a str) -> BenchResult<'a> {
+        BenchResult {
+            name,
+            iters: self.measured,
+            total: self.total,
+            min: if self.measured == 0 { 0 } else { self.min },
+            max: self.max,
+        }
+    }
+}
+
+/// 一个具名微基准
+pub struct BenchCase {
+    name: &'static str,
+    iters: u64,
+    warmup: u64,
+    func: fn(&mut Bencher),
+}
+
+impl BenchCase {
+    /// 以默认迭代次数创建基准
+    pub const fn new(name: &'static str, func: fn(&mut Bencher)) -> Self {
+        Self {
+            name,
+            iters: DEFAULT_ITERS,
+            warmup: DEFAULT_WARMUP,
+            func,
+        }
+    }
+
+    /// 指定迭代次数与预热次数
+    pub const fn with_iters(mut self, iters: u64, warmup: u64) -> Self {
+        self.iters = iters;
+        self.warmup = warmup;
+        self
+    }
+
+    /// 基准名称
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// 运行基准并返回结果，基准调用了 [`Bencher::skip`] 时返回跳过原因
+    pub fn run(&self) -> Result<BenchResult<'static>, BenchSkipped<'static>> {
+        let mut bencher = Bencher::new(self.iters, self.warmup);
+        (self.func)(&mut bencher);
+        match bencher.skipped {
+            Some(reason) => Err(BenchSkipped {
+                name: self.name,
+                reason,
+            }),
+            None => Ok(bencher.result(self.name)),
+        }
+    }
+}
+
+/// 依次运行一组基准，将结果行写入 `out`
+///
+/// # 返回值
+/// 实际测量（未被跳过）的基准数量；写出失败时返回错误
+pub fn run_benches(benches: &[&BenchCase], out: &mut dyn fmt::Write) -> Result<usize, fmt::Error> {
+    let mut measured = 0;
+    for bench in benches {
+        match bench.run() {
+            Ok(result) => {
+                writeln!(out, "{}", result)?;
+                measured += 1;
+            }
+            Err(skipped) => writeln!(out, "{}", skipped)?,
+        }
+    }
+    Ok(measured)
+}
+
+/// 声明一个微基准
+///
+/// 展开为 `#[test_case]` 静态项，需要调用方 crate 启用 `custom_test_frameworks`，
+/// 并让测试运行器能够运行 [`BenchCase`]。
+///
+/// ```ignore
+/// bench_case!(frame_alloc, |b| b.iter(|| alloc_frame()));
+/// bench_case!(pipe_throughput, iters = 64, warmup = 4, |b| { ... });
+/// ```
+#[macro_export]
+macro_rules! bench_case {
+    ($name:ident, $func:expr) => {
+        #[allow(non_upper_case_globals)]
+        #[test_case]
+        static $name: $crate::bench::BenchCase =
+            $crate::bench::BenchCase::new(stringify!($name), $func);
+    };
+    ($name:ident, iters = $iters:expr, warmup = $warmup:expr, $func:expr) => {
+        #[allow(non_upper_case_globals)]
+        #[test_case]
+        static $name: $crate::bench::BenchCase =
+            $crate::bench::BenchCase::new(stringify!($name), $func).with_iters($iters, $warmup);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Buf {
+        data: [u8; 256],
+        len: usize,
+    }
+
+    impl fmt::Write for Buf {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            if end > self.data.len() {
+                return Err(fmt::Error);
+            }
+            self.data[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    fn spin(b: &mut Bencher) {
+        let mut x = 0u64;
+        b.iter(|| {
+            x = x.wrapping_add(1);
+            x
+        });
+    }
+
+    #[test]
+    fn test_bench_case_counts_iterations() {
+        let case = BenchCase::new("spin", spin).with_iters(50, 5);
+        let r = case.run().unwrap();
+        assert_eq!(r.name, "spin");
+        assert_eq!(r.iters, 50);
+        assert!(r.min <= r.mean() && r.mean() <= r.max);
+    }
+
+    #[test]
+    fn test_result_line_roundtrip() {
+        let case = BenchCase::new("roundtrip", spin).with_iters(8, 0);
+        let mut buf = Buf {
+            data: [0; 256],
+            len: 0,
+        };
+        assert_eq!(run_benches(&[&case], &mut buf), Ok(1));
+        let line = core::str::from_utf8(&buf.data[..buf.len]).unwrap();
+        assert!(line.starts_with(RESULT_PREFIX));
+
+        let parsed = BenchResult::parse(line).unwrap();
+        assert_eq!(parsed.name, "roundtrip");
+        assert_eq!(parsed.iters, 8);
+        assert!(BenchResult::parse("Running test: foo").is_none());
+    }
+
+    #[test]
+    fn test_skipped_bench_reports_reason() {
+        let case = BenchCase::new("needs_task", |b| b.skip("no-current-task"));
+        let skipped = case.run().unwrap_err();
+        assert_eq!(skipped.reason, "no-current-task");
+
+        let mut buf = Buf {
+            data: [0; 256],
+            len: 0,
+        };
+        assert_eq!(run_benches(&[&case], &mut buf), Ok(0));
+        let line = core::str::from_utf8(&buf.data[..buf.len]).unwrap();
+        // 跳过的行不能被当作测量结果解析
+        assert!(BenchResult::parse(line).is_none());
+        assert_eq!(BenchSkipped::parse(line), Some(skipped));
+    }
+
+    #[test]
+    fn test_batched_and_recorded() {
+        let mut b = Bencher::new(10, 0);
+        b.iter_batched(|| 1);
+        b.record(5);
+        let r = b.result("mixed");
+        assert_eq!(r.iters, 11);
+        assert!(r.max >= 5);
+    }
+}
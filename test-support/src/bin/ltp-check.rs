@@ -0,0 +1,66 @@
+//! 比对 LTP 子集运行日志与期望结果
+//!
+//! ```text
+//! cargo run -p test-support --features std --bin ltp-check -- <serial.log> [expected.txt] [cases.txt]
+//! ```
+//!
+//! 有回归、缺失结果或运行未完成时以状态码 1 退出。
+
+use std::process::ExitCode;
+use std::{env, fs};
+
+use test_support::ltp::compare;
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn run() -> Result<bool, String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let log_path = args
+        .first()
+        .ok_or("usage: ltp-check <serial.log> [expected.txt] [cases.txt]")?;
+    let default_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/ltp");
+    let expected_path = args
+        .get(1)
+        .cloned()
+        .unwrap_or_else(|| format!("{}/expected.txt", default_dir));
+    let cases_path = args
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| format!("{}/cases.txt", default_dir));
+
+    let cmp = compare(
+        &read(log_path)?,
+        &read(&expected_path)?,
+        &read(&cases_path)?,
+    )?;
+
+    for (outcome, n) in &cmp.counts {
+        println!("{:8} {}", outcome.as_str(), n);
+    }
+    for (name, want, got) in &cmp.regressions {
+        println!("REGRESSION {}: expected {}, got {}", name, want, got);
+    }
+    for name in &cmp.fixed {
+        println!("FIXED      {}: now PASS, update expected.txt", name);
+    }
+    for name in &cmp.missing {
+        println!("MISSING    {}", name);
+    }
+    if !cmp.completed {
+        println!("INCOMPLETE: no `[ltp] done` line (kernel crashed or hung?)");
+    }
+    Ok(cmp.is_ok())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("ltp-check: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! 故障注入框架
+//!
+//! 为 mm / vfs / ext4 等子系统的错误处理路径提供可控的失败来源。
+//! 被测 crate 在关键位置（帧分配、堆分配、块设备读写）调用 [`should_fail`]，
+//! 测试用例则通过 [`FaultScope`] 为某个 [`FaultPoint`] 配置 [`FaultPolicy`]，
+//! 从而在不修改被测代码的前提下模拟"内存耗尽"或"磁盘 I/O 错误"。
+//!
+//! # 接入方式
+//!
+//! - 被测 crate 在 `cfg(test)` 或 `fault-injection` feature 下调用 [`should_fail`]；
+//!   未启用任何策略时只有一次原子读，开销可忽略。
+//! - 堆分配可用 [`FaultInjectingAlloc`] 包装实际的全局分配器。
+//!
+//! # 不变量
+//!
+//! - 故障配置是全局状态。宿主机上 `cargo test` 默认多线程并行，
+//!   因此配置只能经由 [`FaultScope`] 修改：它会串行化这些测试，
+//!   并在离开作用域时清除全部策略，避免影响后续用例。
+//! - 概率策略使用带种子的 xorshift 伪随机数，同一种子得到同一失败序列，便于复现。
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// 可注入故障的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(usize)]
+pub enum FaultPoint {
+    /// 物理帧分配（`mm::frame_allocator`）
+    FrameAlloc = 0,
+    /// 内核堆分配（全局分配器）
+    HeapAlloc = 1,
+    /// 块设备读
+    BlockRead = 2,
+    /// 块设备写
+    BlockWrite = 3,
+}
+
+impl FaultPoint {
+    /// 故障点数量
+    pub const COUNT: usize = 4;
+
+    /// 全部故障点
+    pub const ALL: [FaultPoint; Self::COUNT] = [
+        FaultPoint::FrameAlloc,
+        FaultPoint::HeapAlloc,
+        FaultPoint::BlockRead,
+        FaultPoint::BlockWrite,
+    ];
+
+    fn bit(self) -> usize {
+        1 << self as usize
+    }
+}
+
+/// 故障触发策略
+///
+/// 调用计数从 1 开始，只统计策略生效期间的调用。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultPolicy {
+    /// 不注入故障
+    Disabled,
+    /// 每次调用都失败
+    Always,
+    /// 仅第 n 次调用失败
+    Nth(u64),
+    /// 每第 n 次调用失败一次（n、2n、3n ...）
+    EveryNth(u64),
+    /// 前 n 次调用成功，此后全部失败（模拟资源逐渐耗尽）
+    AfterN(u64),
+    /// 以 `per_mille`/1000 的概率失败，`seed` 决定伪随机序列
+    Probability {
+        /// 失败概率（千分比，>= 1000 等价于 `Always`）
+        per_mille: u16,
+        /// 伪随机数种子（0 会被替换为固定的非零值）
+        seed: u64,
+    },
+    /// 脚本化失败：第 i 次调用（i 从 1 开始）在 bit (i-1) 为 1 时失败，超过 64 次后不再失败
+    Script(u64),
+}
+
+/// 单个故障点的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultStats {
+    /// 策略生效期间的调用次数
+    pub calls: u64,
+    /// 实际注入的失败次数
+    pub injected: u64,
+}
+
+#[derive(Clone, Copy)]
+struct PointState {
+    policy: FaultPolicy,
+    stats: FaultStats,
+    rng: u64,
+}
+
+impl PointState {
+    const fn new() -> Self {
+        Self {
+            policy: FaultPolicy::Disabled,
+            stats: FaultStats {
+                calls: 0,
+                injected: 0,
+            },
+            rng: 0,
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // xorshift64
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        x
+    }
+
+    fn decide(&mut self) -> bool {
+        self.stats.calls += 1;
+        let n = self.stats.calls;
+        let fail = match self.policy {
+            FaultPolicy::Disabled => false,
+            FaultPolicy::Always => true,
+            FaultPolicy::Nth(k) => n == k,
+            FaultPolicy::EveryNth(k) => n.checked_rem(k) == Some(0),
+            FaultPolicy::AfterN(k) => n > k,
+            FaultPolicy::Probability { per_mille, .. } => {
+                (self.next_random() % 1000) < per_mille as u64
+            }
+            FaultPolicy::Script(bits) => n <= 64 && (bits >> (n - 1)) & 1 == 1,
+        };
+        if fail {
+            self.stats.injected += 1;
+        }
+        fail
+    }
+}
+
+/// 全部故障点的状态，由 `STATE_LOCK` 保护
+struct FaultTable(UnsafeCell<[PointState; FaultPoint::COUNT]>);
+
+// SAFETY: 内部数据只在持有 STATE_LOCK 时访问
+unsafe impl Sync for FaultTable {}
+
+static TABLE: FaultTable = FaultTable(UnsafeCell::new([PointState::new(); FaultPoint::COUNT]));
+static STATE_LOCK: AtomicBool = AtomicBool::new(false);
+/// 启用了策略的故障点位掩码（快速路径）
+static ENABLED_MASK: AtomicUsize = AtomicUsize::new(0);
+/// 串行化使用故障注入的测试
+static SCOPE_LOCK: AtomicBool = AtomicBool::new(false);
+
+fn with_table<R>(f: impl FnOnce(&mut [PointState; FaultPoint::COUNT]) -> R) -> R {
+    while STATE_LOCK
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+    // SAFETY: 已持有 STATE_LOCK，独占访问 TABLE
+    let r = f(unsafe { &mut *TABLE.0.get() });
+    STATE_LOCK.store(false, Ordering::Release);
+    r
+}
+
+/// 为故障点设置策略，并清零其统计（只经由 [`FaultScope`] 调用）
+fn set_fault(point: FaultPoint, policy: FaultPolicy) {
+    with_table(|table| {
+        let state = &mut table[point as usize];
+        state.policy = policy;
+        state.stats = FaultStats::default();
+        state.rng = match policy {
+            FaultPolicy::Probability { seed: 0, .. } => 0x9E37_79B9_7F4A_7C15,
+            FaultPolicy::Probability { seed, .. } => seed,
+            _ => 0,
+        };
+    });
+    if policy == FaultPolicy::Disabled {
+        ENABLED_MASK.fetch_and(!point.bit(), Ordering::AcqRel);
+    } else {
+        ENABLED_MASK.fetch_or(point.bit(), Ordering::AcqRel);
+    }
+}
+
+/// 关闭某个故障点的注入
+fn clear_fault(point: FaultPoint) {
+    set_fault(point, FaultPolicy::Disabled);
+}
+
+/// 关闭全部故障点的注入
+fn clear_all_faults() {
+    for point in FaultPoint::ALL {
+        clear_fault(point);
+    }
+}
+
+/// 查询故障点在当前策略下的统计
+fn fault_stats(point: FaultPoint) -> FaultStats {
+    with_table(|table| table[point as usize].stats)
+}
+
+/// 被测代码的注入点：返回 true 表示本次操作应当模拟失败
+///
+/// 未对 `point` 设置策略时直接返回 false，且不计入调用次数。
+#[inline]
+pub fn should_fail(point: FaultPoint) -> bool {
+    if ENABLED_MASK.load(Ordering::Acquire) & point.bit() == 0 {
+        return false;
+    }
+    with_table(|table| table[point as usize].decide())
+}
+
+/// 故障注入作用域
+///
+/// 创建时获取全局作用域锁（串行化所有故障注入测试），
+/// 析构时清除全部策略并释放锁。
+///
+/// # 示例
+/// ```ignore
+/// let scope = FaultScope::new();
+/// scope.inject(FaultPoint::FrameAlloc, FaultPolicy::Nth(3));
+/// // ... 第 3 次帧分配返回 None ...
+/// assert_eq!(scope.stats(FaultPoint::FrameAlloc).injected, 1);
+/// ```
+pub struct FaultScope {
+    _private: (),
+}
+
+impl FaultScope {
+    /// 进入故障注入作用域（若其它测试持有作用域则自旋等待）
+    pub fn new() -> Self {
+        while SCOPE_LOCK
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        clear_all_faults();
+        Self { _private: () }
+    }
+
+    /// 为故障点设置策略
+    pub fn inject(&self, point: FaultPoint, policy: FaultPolicy) {
+        set_fault(point, policy);
+    }
+
+    /// 关闭某个故障点
+    pub fn clear(&self, point: FaultPoint) {
+        clear_fault(point);
+    }
+
+    /// 查询故障点统计
+    pub fn stats(&self, point: FaultPoint) -> FaultStats {
+        fault_stats(point)
+    }
+}
+
+impl Default for FaultScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for FaultScope {
+    fn drop(&mut self) {
+        clear_all_faults();
+        SCOPE_LOCK.store(false, Ordering::Release);
+    }
+}
+
+/// 带故障注入的全局分配器包装
+///
+/// 在 [`FaultPoint::HeapAlloc`] 触发时返回空指针，其余情况转发给内部分配器。
+/// 注意：只有使用 `try_reserve` 等可失败接口的代码路径能够观察到失败，
+/// 普通 `Vec::push` 等会走 `handle_alloc_error`。
+pub struct FaultInjectingAlloc<A> {
+    inner: A,
+}
+
+impl<A> FaultInjectingAlloc<A> {
+    /// 包装一个分配器
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    /// 获取内部分配器
+    pub const fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+// SAFETY: 仅在转发前决定是否返回空指针，不改变内部分配器的语义
+unsafe impl<A: GlobalAlloc> GlobalAlloc for FaultInjectingAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if should_fail(FaultPoint::HeapAlloc) {
+            return core::ptr::null_mut();
+        }
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if should_fail(FaultPoint::HeapAlloc) {
+            return core::ptr::null_mut();
+        }
+        unsafe { self.inner.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if should_fail(FaultPoint::HeapAlloc) {
+            return core::ptr::null_mut();
+        }
+        unsafe { self.inner.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(point: FaultPoint, n: usize) -> [bool; 16] {
+        let mut out = [false; 16];
+        for slot in out.iter_mut().take(n) {
+            *slot = should_fail(point);
+        }
+        out
+    }
+
+    #[test]
+    fn test_disabled_point_never_fails() {
+        let scope = FaultScope::new();
+        assert!(!should_fail(FaultPoint::FrameAlloc));
+        assert_eq!(scope.stats(FaultPoint::FrameAlloc), FaultStats::default());
+    }
+
+    #[test]
+    fn test_nth_and_every_nth() {
+        let scope = FaultScope::new();
+        scope.inject(FaultPoint::BlockRead, FaultPolicy::Nth(3));
+        let r = run(FaultPoint::BlockRead, 5);
+        assert_eq!(&r[..5], &[false, false, true, false, false]);
+
+        scope.inject(FaultPoint::BlockWrite, FaultPolicy::EveryNth(2));
+        let r = run(FaultPoint::BlockWrite, 6);
+        assert_eq!(&r[..6], &[false, true, false, true, false, true]);
+        assert_eq!(
+            scope.stats(FaultPoint::BlockWrite),
+            FaultStats {
+                calls: 6,
+                injected: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_after_n_and_script() {
+        let scope = FaultScope::new();
+        scope.inject(FaultPoint::FrameAlloc, FaultPolicy::AfterN(2));
+        let r = run(FaultPoint::FrameAlloc, 4);
+        assert_eq!(&r[..4], &[false, false, true, true]);
+
+        scope.inject(FaultPoint::FrameAlloc, FaultPolicy::Script(0b1010));
+        let r = run(FaultPoint::FrameAlloc, 5);
+        assert_eq!(&r[..5], &[false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_probability_is_reproducible() {
+        let scope = FaultScope::new();
+        let policy = FaultPolicy::Probability {
+            per_mille: 500,
+            seed: 42,
+        };
+        scope.inject(FaultPoint::HeapAlloc, policy);
+        let first = run(FaultPoint::HeapAlloc, 16);
+        scope.inject(FaultPoint::HeapAlloc, policy);
+        let second = run(FaultPoint::HeapAlloc, 16);
+        assert_eq!(first, second);
+        assert!(first.iter().any(|&f| f));
+        assert!(first.iter().any(|&f| !f));
+    }
+
+    #[test]
+    fn test_scope_drop_clears_policies() {
+        {
+            let scope = FaultScope::new();
+            scope.inject(FaultPoint::BlockRead, FaultPolicy::Always);
+            assert!(should_fail(FaultPoint::BlockRead));
+        }
+        let _scope = FaultScope::new();
+        assert!(!should_fail(FaultPoint::BlockRead));
+    }
+}
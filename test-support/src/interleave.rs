@@ -0,0 +1,703 @@
+//! 受控交错调度器（宿主机并发测试）
+//!
+//! 在宿主机上以真实线程运行被测代码，但任一时刻只允许一个线程执行：
+//! 线程只在调度点（[`yield_now`]、[`spin_loop`]、[`spawn`]、[`JoinHandle::join`]）让出，
+//! 由调度器决定接下来运行哪个线程。这样同一测试可以按确定的顺序反复执行，
+//! 用于验证无锁日志缓冲区、RCU、调度器唤醒等路径在各种交错下的正确性。
+//!
+//! 仅在 `std` feature（或本 crate 自身的测试）下可用。
+//!
+//! # 探索模式
+//!
+//! - [`Model::exhaustive`]：深度优先枚举所有调度选择，以抢占次数为界
+//!   （CHESS 风格的 preemption bounding），找到反例即可复现。
+//! - [`Model::random`]：按种子随机选择，适合状态空间过大的场景。
+//!
+//! # 使用方式
+//!
+//! ```ignore
+//! use test_support::interleave::{self, Model};
+//!
+//! Model::exhaustive(2).check(|| {
+//!     let v = Arc::new(AtomicUsize::new(0));
+//!     let v2 = v.clone();
+//!     let t = interleave::spawn(move || {
+//!         let x = v2.load(Ordering::SeqCst);
+//!         interleave::yield_now();
+//!         v2.store(x + 1, Ordering::SeqCst);
+//!     });
+//!     v.fetch_add(1, Ordering::SeqCst);
+//!     t.join();
+//! });
+//! ```
+//!
+//! 被测代码需要在共享内存访问之间调用 [`yield_now`] 才能产生交错；
+//! 在自旋等待中调用 [`spin_loop`]，调度器会优先切换到其它线程，避免活锁；
+//! 自旋中的线程在有线程经过非自旋的调度点（可能修改了它等待的状态）之前，
+//! 不会被另一个自旋线程选中，避免多个自旋者互相切换而饿死取得进展的线程。
+//! 两者在模型之外分别退化为空操作和 `core::hint::spin_loop`，可以直接留在被测代码中。
+//!
+//! # 失败报告
+//!
+//! 模型内任一线程 panic、出现死锁或单次执行超过步数上限时，`check` 会打印
+//! 执行序号与调度序列后重新抛出 panic；调度序列可交给 [`Model::replay`] 单独复现。
+
+use std::any::Any;
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::eprintln;
+use std::panic::{self, AssertUnwindSafe};
+use std::string::String;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::vec::Vec;
+
+/// 默认单次执行的调度步数上限
+pub const DEFAULT_MAX_STEPS: usize = 100_000;
+
+/// 探索模式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// 穷举所有调度，抢占次数不超过 `max_preemptions`
+    Exhaustive {
+        /// 抢占次数上限
+        max_preemptions: usize,
+    },
+    /// 随机调度 `iterations` 次
+    Random {
+        /// 伪随机数种子
+        seed: u64,
+        /// 执行次数
+        iterations: usize,
+    },
+    /// 按给定的调度序列执行一次
+    Replay(Vec<usize>),
+}
+
+/// 一次探索的统计结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    /// 实际执行次数
+    pub executions: usize,
+    /// 穷举模式下是否遍历完整个（受界的）调度空间
+    pub complete: bool,
+}
+
+/// 并发模型
+#[derive(Debug, Clone)]
+pub struct Model {
+    mode: Mode,
+    max_steps: usize,
+    max_executions: usize,
+}
+
+impl Model {
+    /// 穷举模式
+    pub fn exhaustive(max_preemptions: usize) -> Self {
+        Self::with_mode(Mode::Exhaustive { max_preemptions })
+    }
+
+    /// 随机模式
+    pub fn random(seed: u64, iterations: usize) -> Self {
+        Self::with_mode(Mode::Random { seed, iterations })
+    }
+
+    /// 按调度序列复现一次执行
+    pub fn replay(schedule: &[usize]) -> Self {
+        Self::with_mode(Mode::Replay(schedule.to_vec()))
+    }
+
+    fn with_mode(mode: Mode) -> Self {
+        Self {
+            mode,
+            max_steps: DEFAULT_MAX_STEPS,
+            max_executions: usize::MAX,
+        }
+    }
+
+    /// 设置单次执行的调度步数上限（超过视为活锁）
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// 设置执行次数上限（穷举模式下达到上限时 `complete` 为 false）
+    pub fn max_executions(mut self, max_executions: usize) -> Self {
+        self.max_executions = max_executions;
+        self
+    }
+
+    /// 在所有（受界的）交错下运行 `f`
+    ///
+    /// `f` 运行在模型的 0 号线程上；返回前会等待其派生的所有线程结束。
+    ///
+    /// # Panics
+    /// 任一执行中出现 panic、死锁或活锁时 panic。
+    pub fn check<F>(&self, f: F) -> Report
+    where
+        F: Fn(),
+    {
+        let mut executions = 0;
+        let mut replay = match &self.mode {
+            Mode::Replay(schedule) => schedule.clone(),
+            _ => Vec::new(),
+        };
+        loop {
+            if executions >= self.max_executions {
+                return Report {
+                    executions,
+                    complete: false,
+                };
+            }
+            let strategy = match &self.mode {
+                Mode::Exhaustive { max_preemptions } => Strategy::Dfs {
+                    replay: core::mem::take(&mut replay),
+                    max_preemptions: *max_preemptions,
+                },
+                Mode::Random { seed, .. } => Strategy::Random {
+                    state: mix(*seed, executions as u64),
+                },
+                Mode::Replay(_) => Strategy::Dfs {
+                    replay: replay.clone(),
+                    max_preemptions: usize::MAX,
+                },
+            };
+            let choices = run_once(&f, strategy, self.max_steps, executions);
+            executions += 1;
+
+            match &self.mode {
+                Mode::Exhaustive { .. } => match next_replay(&choices) {
+                    Some(next) => replay = next,
+                    None => {
+                        return Report {
+                            executions,
+                            complete: true,
+                        }
+                    }
+                },
+                Mode::Random { iterations, .. } => {
+                    if executions >= *iterations {
+                        return Report {
+                            executions,
+                            complete: false,
+                        };
+                    }
+                }
+                Mode::Replay(_) => {
+                    return Report {
+                        executions,
+                        complete: false,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 以默认参数（穷举，最多 2 次抢占）检查 `f`
+pub fn model<F>(f: F) -> Report
+where
+    F: Fn(),
+{
+    Model::exhaustive(2).check(f)
+}
+
+/// 当前线程是否运行在模型中
+pub fn in_model() -> bool {
+    CONTEXT.with(|c| c.borrow().is_some())
+}
+
+/// 调度点：允许调度器在此切换到其它线程
+///
+/// 模型之外为空操作。
+pub fn yield_now() {
+    if let Some((shared, me)) = current() {
+        shared.switch(me, Reason::Yield);
+    }
+}
+
+/// 自旋等待中的调度点：调度器会优先运行其它线程
+///
+/// 模型之外等价于 `core::hint::spin_loop`。
+pub fn spin_loop() {
+    match current() {
+        Some((shared, me)) => shared.switch(me, Reason::Spin),
+        None => core::hint::spin_loop(),
+    }
+}
+
+/// 在模型中派生线程
+///
+/// # Panics
+/// 在模型之外调用时 panic。
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (shared, me) = current().expect("interleave::spawn called outside of a model");
+    let result = Arc::new(Mutex::new(None));
+    let id = {
+        let mut exec = shared.lock();
+        exec.threads.push(Status::Runnable);
+        exec.spinning.push(false);
+        exec.threads.len() - 1
+    };
+
+    let thread_shared = shared.clone();
+    let thread_result = result.clone();
+    let os_thread = std::thread::spawn(move || {
+        CONTEXT.with(|c| *c.borrow_mut() = Some((thread_shared.clone(), id)));
+        if thread_shared.wait_turn(id) {
+            let ret = panic::catch_unwind(AssertUnwindSafe(f));
+            match ret {
+                Ok(value) => *thread_result.lock().unwrap() = Some(value),
+                Err(payload) => thread_shared.record_panic(payload),
+            }
+        }
+        thread_shared.finish(id);
+        CONTEXT.with(|c| *c.borrow_mut() = None);
+    });
+    shared.lock().os_threads.push(os_thread);
+
+    // 新线程可能先于父线程运行
+    shared.switch(me, Reason::Yield);
+    JoinHandle { id, result }
+}
+
+/// 模型线程句柄
+pub struct JoinHandle<T> {
+    id: usize,
+    result: Arc<Mutex<Option<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// 等待线程结束并取回返回值
+    pub fn join(self) -> T {
+        let (shared, me) = current().expect("JoinHandle::join called outside of a model");
+        loop {
+            if shared.lock().threads[self.id] == Status::Finished {
+                break;
+            }
+            shared.switch(me, Reason::Join(self.id));
+        }
+        self.result
+            .lock()
+            .unwrap()
+            .take()
+            .expect("interleave: joined thread panicked")
+    }
+}
+
+std::thread_local! {
+    static CONTEXT: RefCell<Option<(Arc<Shared>, usize)>> = const { RefCell::new(None) };
+}
+
+fn current() -> Option<(Arc<Shared>, usize)> {
+    CONTEXT.with(|c| c.borrow().clone())
+}
+
+/// 其它线程失败后用于展开等待线程的 panic 负载
+struct Aborted;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Runnable,
+    /// 等待指定线程结束
+    Joining(usize),
+    /// 0 号线程等待所有线程结束
+    JoiningAll,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reason {
+    Yield,
+    Spin,
+    Join(usize),
+    JoinAll,
+    Exit,
+}
+
+enum Strategy {
+    Dfs {
+        replay: Vec<usize>,
+        max_preemptions: usize,
+    },
+    Random {
+        state: u64,
+    },
+}
+
+struct Execution {
+    active: usize,
+    threads: Vec<Status>,
+    /// 上次让出是否是自旋，且此后没有线程经过非自旋的调度点
+    spinning: Vec<bool>,
+    strategy: Strategy,
+    /// (选择的下标, 可选数量)
+    choices: Vec<(usize, usize)>,
+    preemptions: usize,
+    steps: usize,
+    max_steps: usize,
+    aborted: bool,
+    panic: Option<Box<dyn Any + Send>>,
+    os_threads: Vec<std::thread::JoinHandle<()>>,
+}
+
+struct Shared {
+    exec: Mutex<Execution>,
+    cv: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Execution> {
+        self.exec.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 阻塞直到轮到 `me` 运行；执行被中止时返回 false
+    fn wait_turn(&self, me: usize) -> bool {
+        let mut exec = self.lock();
+        while exec.active != me && !exec.aborted {
+            exec = self.cv.wait(exec).unwrap_or_else(|e| e.into_inner());
+        }
+        !exec.aborted
+    }
+
+    fn switch(&self, me: usize, reason: Reason) {
+        let mut exec = self.lock();
+        if exec.aborted {
+            drop(exec);
+            panic::resume_unwind(Box::new(Aborted));
+        }
+        match reason {
+            Reason::Join(target) => exec.threads[me] = Status::Joining(target),
+            Reason::JoinAll => exec.threads[me] = Status::JoiningAll,
+            Reason::Exit => exec.threads[me] = Status::Finished,
+            Reason::Yield | Reason::Spin => {}
+        }
+        exec.steps += 1;
+        if exec.steps > exec.max_steps {
+            exec.fail("interleave: step limit exceeded (livelock?)");
+        } else if let Some(next) = exec.pick_next(me, reason) {
+            exec.active = next;
+        } else if exec.threads.iter().any(|s| *s != Status::Finished) {
+            exec.fail("interleave: deadlock, no runnable thread");
+        }
+        self.cv.notify_all();
+        if reason == Reason::Exit {
+            return;
+        }
+        while exec.active != me && !exec.aborted {
+            exec = self.cv.wait(exec).unwrap_or_else(|e| e.into_inner());
+        }
+        let aborted = exec.aborted;
+        drop(exec);
+        if aborted {
+            panic::resume_unwind(Box::new(Aborted));
+        }
+    }
+
+    fn finish(&self, me: usize) {
+        let mut exec = self.lock();
+        if exec.aborted {
+            exec.threads[me] = Status::Finished;
+            self.cv.notify_all();
+            return;
+        }
+        drop(exec);
+        self.switch(me, Reason::Exit);
+    }
+
+    fn record_panic(&self, payload: Box<dyn Any + Send>) {
+        let mut exec = self.lock();
+        if payload.downcast_ref::<Aborted>().is_none() && exec.panic.is_none() {
+            exec.panic = Some(payload);
+        }
+        exec.aborted = true;
+        self.cv.notify_all();
+    }
+}
+
+impl Execution {
+    fn fail(&mut self, msg: &'static str) {
+        if self.panic.is_none() {
+            self.panic = Some(Box::new(msg));
+        }
+        self.aborted = true;
+    }
+
+    fn runnable(&self, id: usize) -> bool {
+        match self.threads[id] {
+            Status::Runnable => true,
+            Status::Joining(target) => self.threads[target] == Status::Finished,
+            Status::JoiningAll => self.threads[1..].iter().all(|s| *s == Status::Finished),
+            Status::Finished => false,
+        }
+    }
+
+    fn pick_next(&mut self, me: usize, reason: Reason) -> Option<usize> {
+        let me_runnable = self.runnable(me);
+        let others: Vec<usize> = (0..self.threads.len())
+            .filter(|&t| t != me && self.runnable(t))
+            .collect();
+        if reason == Reason::Spin {
+            self.spinning[me] = true;
+        } else {
+            // 经过非自旋的调度点，共享状态可能已经改变，自旋者都值得再检查一次
+            self.spinning.fill(false);
+        }
+        let mut options: Vec<usize> = Vec::new();
+        match reason {
+            // 主动让出：当前线程排在首位，切走算一次抢占
+            Reason::Yield if me_runnable => {
+                options.push(me);
+                let bounded = match self.strategy {
+                    Strategy::Dfs {
+                        max_preemptions, ..
+                    } => self.preemptions >= max_preemptions,
+                    Strategy::Random { .. } => false,
+                };
+                if !bounded {
+                    options.extend(others);
+                }
+            }
+            // 自旋：只要有其它线程可运行就不再选择自己，并优先选择不在自旋的线程
+            Reason::Spin => {
+                options.extend(others.iter().copied().filter(|&t| !self.spinning[t]));
+                if options.is_empty() {
+                    options.extend(others);
+                }
+                if options.is_empty() && me_runnable {
+                    options.push(me);
+                }
+            }
+            _ => {
+                if me_runnable {
+                    options.push(me);
+                }
+                options.extend(others);
+            }
+        }
+        if options.is_empty() {
+            return None;
+        }
+
+        let idx = if options.len() == 1 {
+            0
+        } else {
+            let idx = match &mut self.strategy {
+                Strategy::Dfs { replay, .. } => replay
+                    .get(self.choices.len())
+                    .map_or(0, |&i| i.min(options.len() - 1)),
+                Strategy::Random { state } => (xorshift(state) % options.len() as u64) as usize,
+            };
+            self.choices.push((idx, options.len()));
+            idx
+        };
+        let next = options[idx];
+        if reason == Reason::Yield && me_runnable && next != me {
+            self.preemptions += 1;
+        }
+        Some(next)
+    }
+}
+
+/// 运行一次执行，返回本次的调度选择序列
+fn run_once<F>(f: &F, strategy: Strategy, max_steps: usize, execution: usize) -> Vec<(usize, usize)>
+where
+    F: Fn(),
+{
+    let shared = Arc::new(Shared {
+        exec: Mutex::new(Execution {
+            active: 0,
+            threads: std::vec![Status::Runnable],
+            spinning: std::vec![false],
+            strategy,
+            choices: Vec::new(),
+            preemptions: 0,
+            steps: 0,
+            max_steps,
+            aborted: false,
+            panic: None,
+            os_threads: Vec::new(),
+        }),
+        cv: Condvar::new(),
+    });
+
+    let previous = CONTEXT.with(|c| c.borrow_mut().replace((shared.clone(), 0)));
+    let ret = panic::catch_unwind(AssertUnwindSafe(|| {
+        f();
+        if shared.lock().threads.len() > 1 {
+            shared.switch(0, Reason::JoinAll);
+        }
+    }));
+    if let Err(payload) = ret {
+        shared.record_panic(payload);
+    }
+    CONTEXT.with(|c| *c.borrow_mut() = previous);
+
+    let os_threads = core::mem::take(&mut shared.lock().os_threads);
+    for t in os_threads {
+        let _ = t.join();
+    }
+
+    let mut exec = shared.lock();
+    let choices = core::mem::take(&mut exec.choices);
+    if let Some(payload) = exec.panic.take() {
+        drop(exec);
+        let schedule: Vec<usize> = choices.iter().map(|&(idx, _)| idx).collect();
+        eprintln!(
+            "interleave: failure in execution #{}, schedule = {:?}",
+            execution, schedule
+        );
+        if let Some(msg) = payload_message(payload.as_ref()) {
+            eprintln!("interleave: {}", msg);
+        }
+        panic::resume_unwind(payload);
+    }
+    choices
+}
+
+/// 深度优先回溯：把最后一个还有余量的选择加一，丢弃其后的选择
+fn next_replay(choices: &[(usize, usize)]) -> Option<Vec<usize>> {
+    let pos = choices.iter().rposition(|&(idx, len)| idx + 1 < len)?;
+    let mut replay: Vec<usize> = choices[..pos].iter().map(|&(idx, _)| idx).collect();
+    replay.push(choices[pos].0 + 1);
+    Some(replay)
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> Option<String> {
+    if let Some(s) = payload.downcast_ref::<&'static str>() {
+        Some(String::from(*s))
+    } else {
+        payload.downcast_ref::<String>().cloned()
+    }
+}
+
+fn mix(seed: u64, n: u64) -> u64 {
+    let x = seed ^ n.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    if x == 0 {
+        0x2545_F491_4F6C_DD1D
+    } else {
+        x
+    }
+}
+
+fn xorshift(state: &mut u64) -> u64 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *state = x;
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// 非原子的读-改-写：两个线程并发自增会丢失更新
+    fn racy_increment(v: &AtomicUsize) {
+        let x = v.load(Ordering::SeqCst);
+        yield_now();
+        v.store(x + 1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_exhaustive_finds_lost_update() {
+        let ret = panic::catch_unwind(|| {
+            Model::exhaustive(2).check(|| {
+                let v = Arc::new(AtomicUsize::new(0));
+                let v2 = v.clone();
+                let t = spawn(move || racy_increment(&v2));
+                racy_increment(&v);
+                t.join();
+                assert_eq!(v.load(Ordering::SeqCst), 2);
+            })
+        });
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_exhaustive_atomic_increment_passes() {
+        let report = model(|| {
+            let v = Arc::new(AtomicUsize::new(0));
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let v = v.clone();
+                    spawn(move || {
+                        v.fetch_add(1, Ordering::SeqCst);
+                        yield_now();
+                        v.fetch_add(1, Ordering::SeqCst);
+                    })
+                })
+                .collect();
+            for h in handles {
+                h.join();
+            }
+            assert_eq!(v.load(Ordering::SeqCst), 4);
+        });
+        assert!(report.complete);
+        assert!(report.executions > 1);
+    }
+
+    #[test]
+    fn test_spin_wait_makes_progress() {
+        let report = Model::random(7, 50).check(|| {
+            let flag = Arc::new(AtomicBool::new(false));
+            let f2 = flag.clone();
+            let t = spawn(move || {
+                yield_now();
+                f2.store(true, Ordering::Release);
+                42
+            });
+            while !flag.load(Ordering::Acquire) {
+                spin_loop();
+            }
+            assert_eq!(t.join(), 42);
+        });
+        assert_eq!(report.executions, 50);
+    }
+
+    #[test]
+    fn test_spinners_do_not_starve_progress() {
+        let report = Model::exhaustive(1).max_steps(1_000).check(|| {
+            let flag = Arc::new(AtomicBool::new(false));
+            let spinners: Vec<_> = (0..2)
+                .map(|_| {
+                    let flag = flag.clone();
+                    spawn(move || {
+                        while !flag.load(Ordering::Acquire) {
+                            spin_loop();
+                        }
+                    })
+                })
+                .collect();
+            yield_now();
+            flag.store(true, Ordering::Release);
+            for s in spinners {
+                s.join();
+            }
+        });
+        assert!(report.complete);
+    }
+
+    #[test]
+    fn test_deadlock_detected() {
+        let ret = panic::catch_unwind(|| {
+            Model::exhaustive(0).check(|| {
+                let (shared, me) = current().unwrap();
+                shared.switch(me, Reason::Join(me));
+            })
+        });
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_outside_model_is_noop() {
+        assert!(!in_model());
+        yield_now();
+        spin_loop();
+    }
+}
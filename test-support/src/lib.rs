@@ -1,10 +1,30 @@
 //! 测试支持 crate
 //!
 //! 提供测试运行器、Mock 实现和测试工具
+//!
+//! - [`mock`]：各子系统 ops trait 的 Mock 实现
+//! - [`coverage`]：gcov 风格的覆盖率计数点（kcov）
+//! - [`fault`]：帧分配 / 堆分配 / 块 I/O 的故障注入
+//! - [`bench`]：基于架构计时器的微基准框架（kbench）
+//! - [`packet`]：以太网 / ARP / IPv4 TCP / UDP 帧的构造与解析（配合 `mock::net::MockNetDevice`）
+//! - [`ltp`]：LTP 子集结果行解析与期望比对（宿主机工具 `ltp-check`）
+//! - `interleave`：宿主机上的受控交错调度器，用于并发原语测试（需要 `std` feature）
 
 #![no_std]
 
+extern crate alloc;
+
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+pub mod bench;
+pub mod coverage;
+pub mod fault;
+#[cfg(any(test, feature = "std"))]
+pub mod interleave;
+pub mod ltp;
 pub mod mock;
+pub mod packet;
 
 /// 测试运行器
 pub fn test_runner(tests: &[&dyn Fn()]) {
@@ -0,0 +1,282 @@
+//! LTP 子集结果解析与回归比对
+//!
+//! 内核以 `ltp` feature 启动时，`ltp/run_ltp.sh` 逐个运行 `ltp/cases.txt` 中的用例，
+//! 并在串口输出结构化结果行：
+//!
+//! ```text
+//! [ltp] case=open01 result=PASS exit=0 secs=1
+//! [ltp] done total=58
+//! ```
+//!
+//! 本模块解析这些结果行与期望文件（`ltp/expected.txt`），并给出回归 / 修复 / 缺失列表。
+//! 行解析在 `no_std` 下可用；比对需要 `std` feature（宿主机工具 `ltp-check` 使用）。
+//!
+//! # 期望文件格式
+//!
+//! 每行 `<用例名> <结果>`，`#` 开头为注释。未列出的用例默认期望 PASS。
+
+use core::fmt;
+
+/// 结果行前缀
+pub const RESULT_PREFIX: &str = "[ltp]";
+
+/// 用例结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    /// 通过
+    Pass,
+    /// 测试断言失败（TFAIL）
+    Fail,
+    /// 测试环境损坏（TBROK）
+    Broken,
+    /// 配置不支持，跳过（TCONF）
+    Conf,
+    /// 超时被终止
+    Timeout,
+}
+
+impl Outcome {
+    /// 从结果字符串解析
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "PASS" => Some(Self::Pass),
+            "FAIL" => Some(Self::Fail),
+            "BROK" => Some(Self::Broken),
+            "CONF" => Some(Self::Conf),
+            "TIMEOUT" => Some(Self::Timeout),
+            _ => None,
+        }
+    }
+
+    /// 结果字符串
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pass => "PASS",
+            Self::Fail => "FAIL",
+            Self::Broken => "BROK",
+            Self::Conf => "CONF",
+            Self::Timeout => "TIMEOUT",
+        }
+    }
+
+    /// 按 LTP 退出码位掩码换算（TFAIL=1 TBROK=2 TCONF=32；124/137 视为超时）
+    pub fn from_exit_code(code: i32) -> Self {
+        match code {
+            0 => Self::Pass,
+            124 | 137 => Self::Timeout,
+            c if c & 2 != 0 => Self::Broken,
+            c if c & 1 != 0 => Self::Fail,
+            c if c & 32 != 0 => Self::Conf,
+            _ => Self::Broken,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 单个用例的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaseResult<'a> {
+    /// 用例名
+    pub name: &'a str,
+    /// 结果
+    pub outcome: Outcome,
+    /// 退出码
+    pub exit: i32,
+    /// 耗时（秒）
+    pub secs: u32,
+}
+
+/// 结果流中的一行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Record<'a> {
+    /// 用例结果
+    Case(CaseResult<'a>),
+    /// 运行结束，附带运行器统计的用例数
+    Done {
+        /// 用例总数
+        total: u32,
+    },
+}
+
+/// 解析一行串口输出
+///
+/// 非 `[ltp]` 结果行（包括 `[ltp-log]`、`[ltp] start`）返回 None。
+/// 允许行首有其它内容（如日志时间戳），只要其中包含前缀。
+pub fn parse_line(line: &str) -> Option<Record<'_>> {
+    let pos = line.find(RESULT_PREFIX)?;
+    let rest = line[pos + RESULT_PREFIX.len()..].trim();
+    if let Some(total) = rest.strip_prefix("done") {
+        let total = field(total, "total")?.parse().ok()?;
+        return Some(Record::Done { total });
+    }
+    Some(Record::Case(CaseResult {
+        name: field(rest, "case")?,
+        outcome: Outcome::parse(field(rest, "result")?)?,
+        exit: field(rest, "exit")?.parse().ok()?,
+        secs: field(rest, "secs")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0),
+    }))
+}
+
+fn field<'a>(s: &'a str, key: &str) -> Option<&'a str> {
+    s.split_whitespace()
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+/// 解析期望文件，逐项返回 `(用例名, 期望结果)`；格式错误的行返回 Err(行号)
+pub fn parse_expectations(text: &str) -> impl Iterator<Item = Result<(&str, Outcome), usize>> {
+    text.lines().enumerate().filter_map(|(idx, line)| {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            return None;
+        }
+        let mut parts = line.split_whitespace();
+        let entry = match (
+            parts.next(),
+            parts.next().and_then(Outcome::parse),
+            parts.next(),
+        ) {
+            (Some(name), Some(outcome), None) => Ok((name, outcome)),
+            _ => Err(idx + 1),
+        };
+        Some(entry)
+    })
+}
+
+#[cfg(any(test, feature = "std"))]
+pub use self::compare::{compare, Comparison};
+
+#[cfg(any(test, feature = "std"))]
+mod compare {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::format;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    /// 结果与期望的比对
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    pub struct Comparison {
+        /// 各结果的用例数
+        pub counts: BTreeMap<Outcome, usize>,
+        /// 回归：期望 PASS 实际不是，或结果比期望更差 `(用例, 期望, 实际)`
+        pub regressions: Vec<(String, Outcome, Outcome)>,
+        /// 已修复：期望非 PASS 实际 PASS
+        pub fixed: Vec<String>,
+        /// 期望文件中有、但结果中没有的用例
+        pub missing: Vec<String>,
+        /// 是否看到 `done` 行（否则运行中途崩溃或卡死）
+        pub completed: bool,
+    }
+
+    impl Comparison {
+        /// 运行完整、没有回归且每个用例都有结果
+        pub fn is_ok(&self) -> bool {
+            self.completed && self.regressions.is_empty() && self.missing.is_empty()
+        }
+    }
+
+    /// 比对串口日志与期望文件
+    ///
+    /// `cases` 为精选用例列表（`cases.txt` 的内容），用于发现未产生结果的用例。
+    pub fn compare(log: &str, expected: &str, cases: &str) -> Result<Comparison, String> {
+        let mut expect = BTreeMap::new();
+        for entry in parse_expectations(expected) {
+            let (name, outcome) =
+                entry.map_err(|line| format!("malformed expectation at line {}", line))?;
+            expect.insert(name, outcome);
+        }
+
+        let mut cmp = Comparison::default();
+        let mut seen = BTreeMap::new();
+        for record in log.lines().filter_map(parse_line) {
+            match record {
+                Record::Case(r) => {
+                    *cmp.counts.entry(r.outcome).or_insert(0) += 1;
+                    seen.insert(r.name, r.outcome);
+                    let want = expect.get(r.name).copied().unwrap_or(Outcome::Pass);
+                    if r.outcome == want {
+                        continue;
+                    }
+                    if r.outcome == Outcome::Pass {
+                        cmp.fixed.push(r.name.to_string());
+                    } else {
+                        cmp.regressions.push((r.name.to_string(), want, r.outcome));
+                    }
+                }
+                Record::Done { .. } => cmp.completed = true,
+            }
+        }
+
+        let listed = cases
+            .lines()
+            .filter_map(|l| l.split('#').next()?.split_whitespace().next());
+        for name in listed.chain(expect.keys().copied()) {
+            if !seen.contains_key(name) && !cmp.missing.iter().any(|m| m == name) {
+                cmp.missing.push(name.to_string());
+            }
+        }
+        Ok(cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+[ 1.0] [ltp] start
+[ltp] case=open01 result=PASS exit=0 secs=1
+[ltp] case=dup201 result=PASS exit=0 secs=0
+[ltp-log] kill02: tst_test.c:1 TBROK
+[ltp] case=kill02 result=BROK exit=2 secs=3
+[ltp] case=read01 result=FAIL exit=1 secs=1
+[ltp] done total=5
+";
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("[ltp] case=open01 result=PASS exit=0 secs=1"),
+            Some(Record::Case(CaseResult {
+                name: "open01",
+                outcome: Outcome::Pass,
+                exit: 0,
+                secs: 1,
+            }))
+        );
+        assert_eq!(
+            parse_line("x [ltp] done total=3"),
+            Some(Record::Done { total: 3 })
+        );
+        assert_eq!(parse_line("[ltp] start"), None);
+        assert_eq!(parse_line("[ltp-log] open01: ok"), None);
+        assert_eq!(Outcome::from_exit_code(33), Outcome::Fail);
+        assert_eq!(Outcome::from_exit_code(32), Outcome::Conf);
+    }
+
+    #[test]
+    fn test_compare_against_expectations() {
+        let expected = "# comment\ndup201 FAIL\nkill02 BROK\numask01 CONF\n";
+        let cases = "open01\ndup201\nkill02\nread01\nwrite01 # new\n";
+        let cmp = compare(LOG, expected, cases).unwrap();
+
+        assert!(cmp.completed);
+        assert_eq!(cmp.fixed, ["dup201"]);
+        assert_eq!(cmp.regressions.len(), 1);
+        assert_eq!(cmp.regressions[0].0, "read01");
+        assert_eq!(cmp.missing, ["write01", "umask01"]);
+        assert_eq!(cmp.counts.get(&Outcome::Pass), Some(&2));
+        assert!(!cmp.is_ok());
+
+        assert!(compare(LOG, "open01 MAYBE\n", "").is_err());
+    }
+}
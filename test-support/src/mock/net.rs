@@ -3,6 +3,12 @@
 //! 注意：这里不直接依赖 `net` crate（避免循环依赖）。
 //! `net` crate 在 `cfg(test)` 下为这些类型实现其 trait（例如 `NetOps`）。
 
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
 /// Mock 的网络运行时操作
 pub struct MockNetOps;
 
@@ -14,3 +20,122 @@ impl MockNetOps {
 
 /// 全局 Mock 实例
 pub static MOCK_NET_OPS: MockNetOps = MockNetOps::new();
+
+/// Mock 网卡的默认 MAC 地址（本地管理地址）
+pub const MOCK_NET_MAC: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x02];
+
+/// 帧队列（自旋锁保护，宿主机与内核均可用）
+struct FrameQueue {
+    locked: AtomicBool,
+    frames: UnsafeCell<VecDeque<Vec<u8>>>,
+}
+
+// SAFETY: `frames` 只在持有 `locked` 时访问
+unsafe impl Sync for FrameQueue {}
+
+impl FrameQueue {
+    const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            frames: UnsafeCell::new(VecDeque::new()),
+        }
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut VecDeque<Vec<u8>>) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        // SAFETY: 已持有锁
+        let r = f(unsafe { &mut *self.frames.get() });
+        self.locked.store(false, Ordering::Release);
+        r
+    }
+}
+
+/// Mock 网卡：接收队列由测试注入，发送的帧被记录下来供测试检查
+///
+/// 配合 [`packet`](crate::packet) 构造帧，可以在宿主机上驱动协议栈完成握手、
+/// 收发数据等流程，无需 QEMU 或 virtio-net。
+/// `device` crate 在 `cfg(test)` 或 `mock` feature 下为其实现 `NetDevice`。
+pub struct MockNetDevice {
+    device_id: usize,
+    mac: [u8; 6],
+    mtu: usize,
+    loopback: AtomicBool,
+    rx: FrameQueue,
+    tx: FrameQueue,
+}
+
+impl MockNetDevice {
+    /// 设备名称
+    pub const NAME: &'static str = "mock-net";
+
+    /// 创建 Mock 网卡（MTU 1500）
+    pub fn new(device_id: usize, mac: [u8; 6]) -> Arc<Self> {
+        Arc::new(Self {
+            device_id,
+            mac,
+            mtu: 1500,
+            loopback: AtomicBool::new(false),
+            rx: FrameQueue::new(),
+            tx: FrameQueue::new(),
+        })
+    }
+
+    /// 回环模式：发送的帧同时进入接收队列
+    pub fn set_loopback(&self, enabled: bool) {
+        self.loopback.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 注入一帧到接收队列
+    pub fn inject(&self, frame: Vec<u8>) {
+        self.rx.with(|q| q.push_back(frame));
+    }
+
+    /// 取走所有已发送的帧（按发送顺序）
+    pub fn take_sent(&self) -> Vec<Vec<u8>> {
+        self.tx.with(|q| q.drain(..).collect())
+    }
+
+    /// 接收队列中尚未被读取的帧数
+    pub fn pending_rx(&self) -> usize {
+        self.rx.with(|q| q.len())
+    }
+
+    /// 发送一帧（`NetDevice::send` 的实现）
+    pub fn transmit(&self, frame: &[u8]) {
+        if self.loopback.load(Ordering::Relaxed) {
+            self.rx.with(|q| q.push_back(frame.to_vec()));
+        }
+        self.tx.with(|q| q.push_back(frame.to_vec()));
+    }
+
+    /// 从接收队列取一帧到 `buf`（`NetDevice::receive` 的实现）
+    ///
+    /// 队列为空返回 None；超出 `buf` 的部分被截断。
+    pub fn receive_into(&self, buf: &mut [u8]) -> Option<usize> {
+        let frame = self.rx.with(|q| q.pop_front())?;
+        let len = frame.len().min(buf.len());
+        buf[..len].copy_from_slice(&frame[..len]);
+        Some(len)
+    }
+
+    /// 设备标识符
+    pub fn device_id(&self) -> usize {
+        self.device_id
+    }
+
+    /// MTU（IP 层）
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// MAC 地址
+    pub fn mac_address(&self) -> [u8; 6] {
+        self.mac
+    }
+}
@@ -0,0 +1,581 @@
+//! 以太网帧构造与解析（网络栈测试用）
+//!
+//! 用链式调用构造 ARP / IPv4 TCP / IPv4 UDP 帧，校验和自动填充；
+//! 配合 [`MockNetDevice`](crate::mock::net::MockNetDevice) 注入协议栈，
+//! 再用 [`Frame::parse`] 检查协议栈发出的帧：
+//!
+//! ```ignore
+//! let syn = packet::ether(PEER_MAC, OUR_MAC)
+//!     .ipv4(PEER_IP, OUR_IP)
+//!     .tcp(40000, 80)
+//!     .syn()
+//!     .seq(1000)
+//!     .mss(1460)
+//!     .build();
+//! dev.inject(syn);
+//! ```
+//!
+//! 只覆盖测试需要的最小子集：IPv4 无分片、无 IP 选项，TCP 选项仅支持 MSS。
+
+use alloc::vec::Vec;
+use core::ops::BitOr;
+
+/// MAC 地址
+pub type Mac = [u8; 6];
+
+/// IPv4 地址
+pub type Ipv4 = [u8; 4];
+
+/// 广播 MAC 地址
+pub const BROADCAST_MAC: Mac = [0xff; 6];
+
+/// 以太网类型：IPv4
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+/// 以太网类型：ARP
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+
+const ETH_HDR_LEN: usize = 14;
+const IPV4_HDR_LEN: usize = 20;
+const TCP_HDR_LEN: usize = 20;
+const UDP_HDR_LEN: usize = 8;
+
+/// TCP 标志位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TcpFlags(pub u8);
+
+impl TcpFlags {
+    /// 无标志
+    pub const NONE: Self = Self(0);
+    /// FIN
+    pub const FIN: Self = Self(0x01);
+    /// SYN
+    pub const SYN: Self = Self(0x02);
+    /// RST
+    pub const RST: Self = Self(0x04);
+    /// PSH
+    pub const PSH: Self = Self(0x08);
+    /// ACK
+    pub const ACK: Self = Self(0x10);
+
+    /// 是否包含 `other` 中的全部标志
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for TcpFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// 开始构造一个以太网帧
+pub fn ether(src: Mac, dst: Mac) -> EtherBuilder {
+    EtherBuilder { src, dst }
+}
+
+/// 以太网层构造器
+#[derive(Debug, Clone, Copy)]
+pub struct EtherBuilder {
+    src: Mac,
+    dst: Mac,
+}
+
+impl EtherBuilder {
+    /// ARP 请求：`sender_ip` 询问 `target_ip` 的 MAC
+    pub fn arp_request(self, sender_ip: Ipv4, target_ip: Ipv4) -> Vec<u8> {
+        self.arp(1, sender_ip, [0; 6], target_ip)
+    }
+
+    /// ARP 应答：告知 `target` 自己（`sender_ip`）的 MAC 为帧源地址
+    pub fn arp_reply(self, sender_ip: Ipv4, target_mac: Mac, target_ip: Ipv4) -> Vec<u8> {
+        self.arp(2, sender_ip, target_mac, target_ip)
+    }
+
+    fn arp(self, op: u16, sender_ip: Ipv4, target_mac: Mac, target_ip: Ipv4) -> Vec<u8> {
+        let mut frame = self.header(ETHERTYPE_ARP);
+        frame.extend_from_slice(&1u16.to_be_bytes()); // 硬件类型：以太网
+        frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        frame.extend_from_slice(&[6, 4]);
+        frame.extend_from_slice(&op.to_be_bytes());
+        frame.extend_from_slice(&self.src);
+        frame.extend_from_slice(&sender_ip);
+        frame.extend_from_slice(&target_mac);
+        frame.extend_from_slice(&target_ip);
+        frame
+    }
+
+    /// 进入 IPv4 层
+    pub fn ipv4(self, src: Ipv4, dst: Ipv4) -> Ipv4Builder {
+        Ipv4Builder {
+            ether: self,
+            src,
+            dst,
+            ttl: 64,
+            ident: 0,
+        }
+    }
+
+    fn header(self, ethertype: u16) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(128);
+        frame.extend_from_slice(&self.dst);
+        frame.extend_from_slice(&self.src);
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame
+    }
+}
+
+/// IPv4 层构造器
+#[derive(Debug, Clone, Copy)]
+pub struct Ipv4Builder {
+    ether: EtherBuilder,
+    src: Ipv4,
+    dst: Ipv4,
+    ttl: u8,
+    ident: u16,
+}
+
+impl Ipv4Builder {
+    /// 设置 TTL（默认 64）
+    pub fn ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// 设置 IP 标识
+    pub fn ident(mut self, ident: u16) -> Self {
+        self.ident = ident;
+        self
+    }
+
+    /// 进入 TCP 层
+    pub fn tcp(self, src_port: u16, dst_port: u16) -> TcpBuilder {
+        TcpBuilder {
+            ip: self,
+            src_port,
+            dst_port,
+            seq: 0,
+            ack: 0,
+            flags: TcpFlags::NONE,
+            window: 64240,
+            mss: None,
+            payload: Vec::new(),
+        }
+    }
+
+    /// 进入 UDP 层
+    pub fn udp(self, src_port: u16, dst_port: u16) -> UdpBuilder {
+        UdpBuilder {
+            ip: self,
+            src_port,
+            dst_port,
+            payload: Vec::new(),
+        }
+    }
+
+    /// 以给定协议号与载荷（已含传输层头部）封装完整帧
+    fn wrap(self, protocol: u8, segment: &[u8]) -> Vec<u8> {
+        let mut frame = self.ether.header(ETHERTYPE_IPV4);
+        let ip_start = frame.len();
+        let total_len = (IPV4_HDR_LEN + segment.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0]);
+        frame.extend_from_slice(&total_len.to_be_bytes());
+        frame.extend_from_slice(&self.ident.to_be_bytes());
+        frame.extend_from_slice(&0x4000u16.to_be_bytes()); // DF，无分片
+        frame.extend_from_slice(&[self.ttl, protocol, 0, 0]);
+        frame.extend_from_slice(&self.src);
+        frame.extend_from_slice(&self.dst);
+        let csum = checksum(&frame[ip_start..], 0);
+        frame[ip_start + 10..ip_start + 12].copy_from_slice(&csum.to_be_bytes());
+        frame.extend_from_slice(segment);
+        frame
+    }
+
+    /// 传输层伪首部的部分和
+    fn pseudo_header_sum(&self, protocol: u8, len: usize) -> u32 {
+        let mut sum = 0u32;
+        for pair in self.src.chunks(2).chain(self.dst.chunks(2)) {
+            sum += u16::from_be_bytes([pair[0], pair[1]]) as u32;
+        }
+        sum + protocol as u32 + len as u32
+    }
+}
+
+/// TCP 层构造器
+#[derive(Debug, Clone)]
+pub struct TcpBuilder {
+    ip: Ipv4Builder,
+    src_port: u16,
+    dst_port: u16,
+    seq: u32,
+    ack: u32,
+    flags: TcpFlags,
+    window: u16,
+    mss: Option<u16>,
+    payload: Vec<u8>,
+}
+
+impl TcpBuilder {
+    /// 设置序号
+    pub fn seq(mut self, seq: u32) -> Self {
+        self.seq = seq;
+        self
+    }
+
+    /// 设置确认号并置 ACK 标志
+    pub fn ack(mut self, ack: u32) -> Self {
+        self.ack = ack;
+        self.flags = self.flags | TcpFlags::ACK;
+        self
+    }
+
+    /// 追加标志位
+    pub fn flags(mut self, flags: TcpFlags) -> Self {
+        self.flags = self.flags | flags;
+        self
+    }
+
+    /// 置 SYN 标志
+    pub fn syn(self) -> Self {
+        self.flags(TcpFlags::SYN)
+    }
+
+    /// 置 FIN 标志
+    pub fn fin(self) -> Self {
+        self.flags(TcpFlags::FIN)
+    }
+
+    /// 置 RST 标志
+    pub fn rst(self) -> Self {
+        self.flags(TcpFlags::RST)
+    }
+
+    /// 置 PSH 标志
+    pub fn psh(self) -> Self {
+        self.flags(TcpFlags::PSH)
+    }
+
+    /// 设置接收窗口（默认 64240）
+    pub fn window(mut self, window: u16) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// 添加 MSS 选项
+    pub fn mss(mut self, mss: u16) -> Self {
+        self.mss = Some(mss);
+        self
+    }
+
+    /// 设置载荷
+    pub fn payload(mut self, data: &[u8]) -> Self {
+        self.payload = data.to_vec();
+        self
+    }
+
+    /// 生成完整的以太网帧
+    pub fn build(self) -> Vec<u8> {
+        let options_len = if self.mss.is_some() { 4 } else { 0 };
+        let hdr_len = TCP_HDR_LEN + options_len;
+        let mut seg = Vec::with_capacity(hdr_len + self.payload.len());
+        seg.extend_from_slice(&self.src_port.to_be_bytes());
+        seg.extend_from_slice(&self.dst_port.to_be_bytes());
+        seg.extend_from_slice(&self.seq.to_be_bytes());
+        seg.extend_from_slice(&self.ack.to_be_bytes());
+        seg.extend_from_slice(&[((hdr_len / 4) as u8) << 4, self.flags.0]);
+        seg.extend_from_slice(&self.window.to_be_bytes());
+        seg.extend_from_slice(&[0, 0, 0, 0]); // 校验和、紧急指针
+        if let Some(mss) = self.mss {
+            seg.extend_from_slice(&[2, 4]);
+            seg.extend_from_slice(&mss.to_be_bytes());
+        }
+        seg.extend_from_slice(&self.payload);
+
+        let csum = checksum(&seg, self.ip.pseudo_header_sum(IPPROTO_TCP, seg.len()));
+        seg[16..18].copy_from_slice(&csum.to_be_bytes());
+        self.ip.wrap(IPPROTO_TCP, &seg)
+    }
+}
+
+/// UDP 层构造器
+#[derive(Debug, Clone)]
+pub struct UdpBuilder {
+    ip: Ipv4Builder,
+    src_port: u16,
+    dst_port: u16,
+    payload: Vec<u8>,
+}
+
+impl UdpBuilder {
+    /// 设置载荷
+    pub fn payload(mut self, data: &[u8]) -> Self {
+        self.payload = data.to_vec();
+        self
+    }
+
+    /// 生成完整的以太网帧
+    pub fn build(self) -> Vec<u8> {
+        let len = UDP_HDR_LEN + self.payload.len();
+        let mut seg = Vec::with_capacity(len);
+        seg.extend_from_slice(&self.src_port.to_be_bytes());
+        seg.extend_from_slice(&self.dst_port.to_be_bytes());
+        seg.extend_from_slice(&(len as u16).to_be_bytes());
+        seg.extend_from_slice(&[0, 0]);
+        seg.extend_from_slice(&self.payload);
+
+        let csum = match checksum(&seg, self.ip.pseudo_header_sum(IPPROTO_UDP, len)) {
+            // UDP 中校验和 0 表示"未计算"，按 RFC 768 发送全 1
+            0 => 0xffff,
+            c => c,
+        };
+        seg[6..8].copy_from_slice(&csum.to_be_bytes());
+        self.ip.wrap(IPPROTO_UDP, &seg)
+    }
+}
+
+/// Internet 校验和（RFC 1071），`initial` 为伪首部等额外部分和
+pub fn checksum(data: &[u8], initial: u32) -> u16 {
+    let mut sum = initial;
+    let mut chunks = data.chunks_exact(2);
+    for pair in &mut chunks {
+        sum += u16::from_be_bytes([pair[0], pair[1]]) as u32;
+    }
+    if let [last] = chunks.remainder() {
+        sum += (*last as u32) << 8;
+    }
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// 解析后的 TCP 段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpSegment<'a> {
+    /// 源 IP
+    pub src_ip: Ipv4,
+    /// 目的 IP
+    pub dst_ip: Ipv4,
+    /// 源端口
+    pub src_port: u16,
+    /// 目的端口
+    pub dst_port: u16,
+    /// 序号
+    pub seq: u32,
+    /// 确认号
+    pub ack: u32,
+    /// 标志位
+    pub flags: TcpFlags,
+    /// 接收窗口
+    pub window: u16,
+    /// MSS 选项（若存在）
+    pub mss: Option<u16>,
+    /// 载荷
+    pub payload: &'a [u8],
+}
+
+/// 解析后的 UDP 数据报
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UdpDatagram<'a> {
+    /// 源 IP
+    pub src_ip: Ipv4,
+    /// 目的 IP
+    pub dst_ip: Ipv4,
+    /// 源端口
+    pub src_port: u16,
+    /// 目的端口
+    pub dst_port: u16,
+    /// 载荷
+    pub payload: &'a [u8],
+}
+
+/// 解析后的帧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame<'a> {
+    /// ARP 报文
+    Arp {
+        /// 操作码（1 请求，2 应答）
+        op: u16,
+        /// 发送方 MAC
+        sender_mac: Mac,
+        /// 发送方 IP
+        sender_ip: Ipv4,
+        /// 目标 IP
+        target_ip: Ipv4,
+    },
+    /// IPv4 TCP
+    Tcp(TcpSegment<'a>),
+    /// IPv4 UDP
+    Udp(UdpDatagram<'a>),
+    /// 其它以太网类型或 IP 协议
+    Other {
+        /// 以太网类型
+        ethertype: u16,
+    },
+}
+
+impl<'a> Frame<'a> {
+    /// 解析以太网帧；长度或首部字段不合法时返回 None
+    ///
+    /// 不校验校验和（协议栈发出的帧可能依赖硬件卸载）。
+    pub fn parse(frame: &'a [u8]) -> Option<Self> {
+        let ethertype = be16(frame, 12)?;
+        let body = frame.get(ETH_HDR_LEN..)?;
+        match ethertype {
+            ETHERTYPE_ARP => Some(Frame::Arp {
+                op: be16(body, 6)?,
+                sender_mac: body.get(8..14)?.try_into().ok()?,
+                sender_ip: body.get(14..18)?.try_into().ok()?,
+                target_ip: body.get(24..28)?.try_into().ok()?,
+            }),
+            ETHERTYPE_IPV4 => Self::parse_ipv4(body),
+            _ => Some(Frame::Other { ethertype }),
+        }
+    }
+
+    fn parse_ipv4(ip: &'a [u8]) -> Option<Self> {
+        let ihl = (*ip.first()? & 0x0f) as usize * 4;
+        let total_len = be16(ip, 2)? as usize;
+        let src_ip: Ipv4 = ip.get(12..16)?.try_into().ok()?;
+        let dst_ip: Ipv4 = ip.get(16..20)?.try_into().ok()?;
+        let seg = ip.get(ihl..total_len)?;
+        match ip[9] {
+            IPPROTO_TCP => {
+                let hdr_len = (*seg.get(12)? >> 4) as usize * 4;
+                Some(Frame::Tcp(TcpSegment {
+                    src_ip,
+                    dst_ip,
+                    src_port: be16(seg, 0)?,
+                    dst_port: be16(seg, 2)?,
+                    seq: be32(seg, 4)?,
+                    ack: be32(seg, 8)?,
+                    flags: TcpFlags(seg[13] & 0x3f),
+                    window: be16(seg, 14)?,
+                    mss: find_mss(seg.get(TCP_HDR_LEN..hdr_len)?),
+                    payload: seg.get(hdr_len..)?,
+                }))
+            }
+            IPPROTO_UDP => Some(Frame::Udp(UdpDatagram {
+                src_ip,
+                dst_ip,
+                src_port: be16(seg, 0)?,
+                dst_port: be16(seg, 2)?,
+                payload: seg.get(UDP_HDR_LEN..be16(seg, 4)? as usize)?,
+            })),
+            _ => Some(Frame::Other {
+                ethertype: ETHERTYPE_IPV4,
+            }),
+        }
+    }
+
+    /// 若为 TCP 段则返回之
+    pub fn tcp(self) -> Option<TcpSegment<'a>> {
+        match self {
+            Frame::Tcp(seg) => Some(seg),
+            _ => None,
+        }
+    }
+
+    /// 若为 UDP 数据报则返回之
+    pub fn udp(self) -> Option<UdpDatagram<'a>> {
+        match self {
+            Frame::Udp(dgram) => Some(dgram),
+            _ => None,
+        }
+    }
+}
+
+fn find_mss(mut options: &[u8]) -> Option<u16> {
+    while let [kind, rest @ ..] = options {
+        match kind {
+            0 => return None,
+            1 => options = rest,
+            _ => {
+                let len = *rest.first()? as usize;
+                if *kind == 2 && len == 4 {
+                    return be16(options, 2);
+                }
+                options = options.get(len.max(2)..)?;
+            }
+        }
+    }
+    None
+}
+
+fn be16(buf: &[u8], off: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(buf.get(off..off + 2)?.try_into().ok()?))
+}
+
+fn be32(buf: &[u8], off: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(buf.get(off..off + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A_MAC: Mac = [0x02, 0, 0, 0, 0, 0xaa];
+    const B_MAC: Mac = [0x02, 0, 0, 0, 0, 0xbb];
+    const A_IP: Ipv4 = [10, 0, 0, 1];
+    const B_IP: Ipv4 = [10, 0, 0, 2];
+
+    #[test]
+    fn test_tcp_build_parse_roundtrip() {
+        let frame = ether(A_MAC, B_MAC)
+            .ipv4(A_IP, B_IP)
+            .tcp(40000, 80)
+            .syn()
+            .seq(1000)
+            .mss(1460)
+            .payload(b"hi")
+            .build();
+
+        // IPv4 首部与 TCP（含伪首部）的校验和都应验证为 0
+        let ip = &frame[ETH_HDR_LEN..];
+        assert_eq!(checksum(&ip[..IPV4_HDR_LEN], 0), 0);
+        let b = ether(A_MAC, B_MAC).ipv4(A_IP, B_IP);
+        let seg = &ip[IPV4_HDR_LEN..];
+        assert_eq!(
+            checksum(seg, b.pseudo_header_sum(IPPROTO_TCP, seg.len())),
+            0
+        );
+
+        let seg = Frame::parse(&frame).unwrap().tcp().unwrap();
+        assert_eq!((seg.src_ip, seg.dst_ip), (A_IP, B_IP));
+        assert_eq!((seg.src_port, seg.dst_port), (40000, 80));
+        assert_eq!(seg.seq, 1000);
+        assert!(seg.flags.contains(TcpFlags::SYN));
+        assert!(!seg.flags.contains(TcpFlags::ACK));
+        assert_eq!(seg.mss, Some(1460));
+        assert_eq!(seg.payload, b"hi");
+    }
+
+    #[test]
+    fn test_udp_and_arp() {
+        let frame = ether(A_MAC, B_MAC)
+            .ipv4(A_IP, B_IP)
+            .udp(5353, 53)
+            .payload(b"query")
+            .build();
+        let dgram = Frame::parse(&frame).unwrap().udp().unwrap();
+        assert_eq!((dgram.src_port, dgram.dst_port), (5353, 53));
+        assert_eq!(dgram.payload, b"query");
+
+        let arp = ether(A_MAC, BROADCAST_MAC).arp_request(A_IP, B_IP);
+        assert_eq!(
+            Frame::parse(&arp),
+            Some(Frame::Arp {
+                op: 1,
+                sender_mac: A_MAC,
+                sender_ip: A_IP,
+                target_ip: B_IP,
+            })
+        );
+        assert_eq!(Frame::parse(&arp[..10]), None);
+    }
+}